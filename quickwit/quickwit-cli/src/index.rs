@@ -203,6 +203,34 @@ pub fn build_index_command() -> Command {
                         .required(false),
                 ])
             )
+        .subcommand(
+            Command::new("snapshot")
+                .display_order(9)
+                .about("Snapshots and restores index metadata and split manifests.")
+                .subcommand(
+                    Command::new("create")
+                        .display_order(1)
+                        .about("Writes a snapshot of an index to its storage.")
+                        .long_about("Writes a consistent snapshot of the index metadata, source configs, checkpoints, and published split manifests to a single JSON document, `index-snapshot.json`, at the root of the index storage. The split files are not copied.")
+                        .args(&[
+                            arg!(--index <INDEX> "ID of the target index")
+                                .display_order(1)
+                                .required(true),
+                        ])
+                    )
+                .subcommand(
+                    Command::new("restore")
+                        .display_order(2)
+                        .about("Recreates an index from a snapshot.")
+                        .long_about("Recreates an index from a snapshot, for instance into a fresh metastore. The index must not exist. Splits whose file is missing from the index storage are marked for deletion instead of being published. The index and its splits are created atomically.")
+                        .args(&[
+                            arg!(--"snapshot-uri" <SNAPSHOT_URI> "Location of the snapshot file.")
+                                .display_order(1)
+                                .required(true),
+                        ])
+                    )
+                .arg_required_else_help(true)
+            )
         .arg_required_else_help(true)
 }
 
@@ -272,6 +300,18 @@ pub struct ListIndexesArgs {
     pub client_args: ClientArgs,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct CreateIndexSnapshotArgs {
+    pub client_args: ClientArgs,
+    pub index_id: IndexId,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RestoreIndexSnapshotArgs {
+    pub client_args: ClientArgs,
+    pub snapshot_uri: Uri,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum IndexCliCommand {
     Clear(ClearIndexArgs),
@@ -282,6 +322,8 @@ pub enum IndexCliCommand {
     Ingest(IngestDocsArgs),
     List(ListIndexesArgs),
     Search(SearchIndexArgs),
+    CreateSnapshot(CreateIndexSnapshotArgs),
+    RestoreSnapshot(RestoreIndexSnapshotArgs),
 }
 
 impl IndexCliCommand {
@@ -304,11 +346,41 @@ impl IndexCliCommand {
             "ingest" => Self::parse_ingest_args(submatches),
             "list" => Self::parse_list_args(submatches),
             "search" => Self::parse_search_args(submatches),
+            "snapshot" => Self::parse_snapshot_args(submatches),
             "update" => Self::parse_update_args(submatches),
             _ => bail!("unknown index subcommand `{subcommand}`"),
         }
     }
 
+    fn parse_snapshot_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let (subcommand, mut submatches) = matches
+            .remove_subcommand()
+            .context("failed to parse index snapshot subcommand")?;
+        let client_args = ClientArgs::parse(&mut submatches)?;
+        match subcommand.as_str() {
+            "create" => {
+                let index_id = submatches
+                    .remove_one::<String>("index")
+                    .expect("`index` should be a required arg.");
+                Ok(Self::CreateSnapshot(CreateIndexSnapshotArgs {
+                    client_args,
+                    index_id,
+                }))
+            }
+            "restore" => {
+                let snapshot_uri = submatches
+                    .remove_one::<String>("snapshot-uri")
+                    .map(|uri| Uri::from_str(&uri))
+                    .expect("`snapshot-uri` should be a required arg.")?;
+                Ok(Self::RestoreSnapshot(RestoreIndexSnapshotArgs {
+                    client_args,
+                    snapshot_uri,
+                }))
+            }
+            _ => bail!("unknown index snapshot subcommand `{subcommand}`"),
+        }
+    }
+
     fn parse_clear_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
         let client_args = ClientArgs::parse(&mut matches)?;
         let index_id = matches
@@ -485,6 +557,8 @@ impl IndexCliCommand {
             Self::List(args) => list_index_cli(args).await,
             Self::Search(args) => search_index_cli(args).await,
             Self::Update(args) => update_index_cli(args).await,
+            Self::CreateSnapshot(args) => create_index_snapshot_cli(args).await,
+            Self::RestoreSnapshot(args) => restore_index_snapshot_cli(args).await,
         }
     }
 }
@@ -565,6 +639,41 @@ pub async fn update_index_cli(args: UpdateIndexArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn create_index_snapshot_cli(args: CreateIndexSnapshotArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "create-index-snapshot");
+    println!("❯ Taking index snapshot...");
+    let qw_client = args.client_args.client();
+    let report = qw_client.indexes().snapshot(&args.index_id).await?;
+    println!(
+        "{} Snapshot of {} split(s) successfully written to `{}`.",
+        "✔".color(GREEN_COLOR),
+        report.num_splits,
+        report.snapshot_uri
+    );
+    Ok(())
+}
+
+pub async fn restore_index_snapshot_cli(args: RestoreIndexSnapshotArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "restore-index-snapshot");
+    println!("❯ Restoring index snapshot...");
+    let qw_client = args.client_args.client();
+    let report = qw_client.indexes().restore(&args.snapshot_uri).await?;
+
+    for split_id in &report.missing_split_ids {
+        println!(
+            "{} File of split `{split_id}` is missing, the split was marked for deletion.",
+            "⚠".yellow()
+        );
+    }
+    println!(
+        "{} Index `{}` successfully restored with {} split(s).",
+        "✔".color(GREEN_COLOR),
+        report.index_uid.index_id,
+        report.num_restored_splits
+    );
+    Ok(())
+}
+
 pub async fn list_index_cli(args: ListIndexesArgs) -> anyhow::Result<()> {
     debug!(args=?args, "list-index");
     let qw_client = args.client_args.client();
@@ -1080,6 +1189,7 @@ pub async fn search_index(args: SearchIndexArgs) -> anyhow::Result<SearchRespons
                 field_name: "_score".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             }],
         })
         .unwrap_or_default();
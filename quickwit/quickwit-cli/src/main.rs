@@ -135,8 +135,9 @@ mod tests {
     use bytesize::ByteSize;
     use quickwit_cli::cli::{build_cli, CliCommand};
     use quickwit_cli::index::{
-        ClearIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, IndexCliCommand,
-        IngestDocsArgs, SearchIndexArgs,
+        ClearIndexArgs, CreateIndexArgs, CreateIndexSnapshotArgs, DeleteIndexArgs,
+        DescribeIndexArgs, IndexCliCommand, IngestDocsArgs, RestoreIndexSnapshotArgs,
+        SearchIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, SplitCliCommand};
     use quickwit_cli::tool::{
@@ -648,6 +649,44 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_snapshot_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(["index", "snapshot", "create", "--index", "wikipedia"])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_cmd =
+            CliCommand::Index(IndexCliCommand::CreateSnapshot(CreateIndexSnapshotArgs {
+                client_args: ClientArgs::default(),
+                index_id: "wikipedia".to_string(),
+            }));
+        assert_eq!(command, expected_cmd);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from([
+                "index",
+                "snapshot",
+                "restore",
+                "--snapshot-uri",
+                "s3://quickwit-indexes/wikipedia/index-snapshot.json",
+                "--endpoint",
+                "http://127.0.0.1:8000",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_cmd =
+            CliCommand::Index(IndexCliCommand::RestoreSnapshot(RestoreIndexSnapshotArgs {
+                client_args: ClientArgs {
+                    cluster_endpoint: Url::from_str("http://127.0.0.1:8000").unwrap(),
+                    ..Default::default()
+                },
+                snapshot_uri: Uri::for_test("s3://quickwit-indexes/wikipedia/index-snapshot.json"),
+            }));
+        assert_eq!(command, expected_cmd);
+    }
+
     #[test]
     fn test_parse_split_describe_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
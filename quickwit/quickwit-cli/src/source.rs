@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::{bail, Context};
@@ -25,7 +26,7 @@ use colored::Colorize;
 use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_config::{validate_identifier, ConfigFormat, SourceConfig};
-use quickwit_metastore::checkpoint::SourceCheckpoint;
+use quickwit_metastore::checkpoint::{SerializableShardPositions, SourceCheckpoint};
 use quickwit_proto::types::{IndexId, SourceId};
 use quickwit_storage::{load_file, StorageResolver};
 use serde_json::Value as JsonValue;
@@ -137,6 +138,28 @@ pub fn build_source_command() -> Command {
                         .required(true),
                 ])
             )
+        .subcommand(
+            Command::new("export-positions")
+                .about("Exports the positions of the sources to a JSON document.")
+                .args(&[
+                    arg!(--indexes <INDEX_ID_PATTERNS> "Comma-separated list of index ID patterns whose source positions are exported. Defaults to all the indexes.")
+                        .display_order(1)
+                        .required(false)
+                        .value_delimiter(','),
+                    arg!(--output <OUTPUT_PATH> "Path of the JSON document to write. Defaults to stdout.")
+                        .display_order(2)
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("import-positions")
+                .about("Seeds the source checkpoints from a JSON document produced by `export-positions`.")
+                .args(&[
+                    arg!(--input <INPUT_PATH> "Path of the JSON document to import.")
+                        .display_order(1)
+                        .required(true),
+                ])
+            )
         .arg_required_else_help(true)
 }
 
@@ -184,6 +207,20 @@ pub struct ResetCheckpointArgs {
     pub assume_yes: bool,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct ExportPositionsArgs {
+    pub client_args: ClientArgs,
+    pub index_id_patterns: Vec<String>,
+    pub output_path_opt: Option<PathBuf>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ImportPositionsArgs {
+    pub client_args: ClientArgs,
+    pub input_path: PathBuf,
+    pub assume_yes: bool,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum SourceCliCommand {
     CreateSource(CreateSourceArgs),
@@ -192,6 +229,8 @@ pub enum SourceCliCommand {
     DescribeSource(DescribeSourceArgs),
     ListSources(ListSourcesArgs),
     ResetCheckpoint(ResetCheckpointArgs),
+    ExportPositions(ExportPositionsArgs),
+    ImportPositions(ImportPositionsArgs),
 }
 
 impl SourceCliCommand {
@@ -203,6 +242,8 @@ impl SourceCliCommand {
             Self::DescribeSource(args) => describe_source_cli(args).await,
             Self::ListSources(args) => list_sources_cli(args).await,
             Self::ResetCheckpoint(args) => reset_checkpoint_cli(args).await,
+            Self::ExportPositions(args) => export_positions_cli(args).await,
+            Self::ImportPositions(args) => import_positions_cli(args).await,
         }
     }
 
@@ -224,6 +265,12 @@ impl SourceCliCommand {
             "reset-checkpoint" => {
                 Self::parse_reset_checkpoint_args(submatches).map(Self::ResetCheckpoint)
             }
+            "export-positions" => {
+                Self::parse_export_positions_args(submatches).map(Self::ExportPositions)
+            }
+            "import-positions" => {
+                Self::parse_import_positions_args(submatches).map(Self::ImportPositions)
+            }
             _ => bail!("unknown source subcommand `{subcommand}`"),
         }
     }
@@ -323,6 +370,34 @@ impl SourceCliCommand {
             assume_yes,
         })
     }
+
+    fn parse_export_positions_args(mut matches: ArgMatches) -> anyhow::Result<ExportPositionsArgs> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id_patterns = matches
+            .remove_many::<String>("indexes")
+            .map(|values| values.collect())
+            .unwrap_or_default();
+        let output_path_opt = matches.remove_one::<String>("output").map(PathBuf::from);
+        Ok(ExportPositionsArgs {
+            client_args,
+            index_id_patterns,
+            output_path_opt,
+        })
+    }
+
+    fn parse_import_positions_args(mut matches: ArgMatches) -> anyhow::Result<ImportPositionsArgs> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let input_path = matches
+            .remove_one::<String>("input")
+            .map(PathBuf::from)
+            .expect("`input` should be a required arg.");
+        let assume_yes = matches.get_flag("yes");
+        Ok(ImportPositionsArgs {
+            client_args,
+            input_path,
+            assume_yes,
+        })
+    }
 }
 
 async fn create_source_cli(args: CreateSourceArgs) -> anyhow::Result<()> {
@@ -522,6 +597,70 @@ async fn reset_checkpoint_cli(args: ResetCheckpointArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn export_positions_cli(args: ExportPositionsArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "export-positions");
+    let qw_client = args.client_args.client();
+    let shard_positions = qw_client
+        .indexes()
+        .export_positions(&args.index_id_patterns)
+        .await
+        .context("failed to export positions")?;
+    let shard_positions_json = serde_json::to_string_pretty(&shard_positions)?;
+
+    if let Some(output_path) = &args.output_path_opt {
+        tokio::fs::write(output_path, shard_positions_json)
+            .await
+            .with_context(|| format!("failed to write positions to `{}`", output_path.display()))?;
+        println!(
+            "{} Positions of {} source(s) successfully exported to `{}`.",
+            "✔".color(GREEN_COLOR),
+            shard_positions.sources.len(),
+            output_path.display()
+        );
+    } else {
+        println!("{shard_positions_json}");
+    }
+    Ok(())
+}
+
+async fn import_positions_cli(args: ImportPositionsArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "import-positions");
+    println!("❯ Importing positions...");
+    let shard_positions_json = tokio::fs::read(&args.input_path).await.with_context(|| {
+        format!(
+            "failed to read positions from `{}`",
+            args.input_path.display()
+        )
+    })?;
+    let shard_positions: SerializableShardPositions =
+        serde_json::from_slice(&shard_positions_json).context("failed to parse positions")?;
+
+    if !args.assume_yes {
+        let prompt = "This operation will overwrite the source checkpoints. Do you want to \
+                      proceed?"
+            .to_string();
+        if !prompt_confirmation(&prompt, false) {
+            return Ok(());
+        }
+    }
+    let qw_client = args.client_args.client();
+    let report = qw_client
+        .indexes()
+        .import_positions(&shard_positions)
+        .await
+        .context("failed to import positions")?;
+
+    for warning in &report.warnings {
+        println!("{} {warning}", "⚠".yellow());
+    }
+    println!(
+        "{} {} position(s) successfully imported.",
+        "✔".color(GREEN_COLOR),
+        report.num_imported_positions
+    );
+    Ok(())
+}
+
 /// Recursively flattens a JSON object into a vector of `(path, value)` tuples where `path`
 /// represents the full path of each property in the original object. For instance, `{"root": true,
 /// "parent": {"child": 0}}` yields `[("root", true), ("parent.child", 0)]`. Arrays are not
@@ -554,7 +693,7 @@ mod tests {
     use std::num::NonZeroUsize;
     use std::str::FromStr;
 
-    use quickwit_config::{SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_metastore::checkpoint::PartitionId;
     use quickwit_proto::types::Position;
     use serde_json::json;
@@ -725,6 +864,61 @@ mod tests {
         assert_eq!(command, expected_command);
     }
 
+    #[test]
+    fn test_parse_export_import_positions_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec![
+                "source",
+                "export-positions",
+                "--indexes",
+                "hdfs-logs,wiki-*",
+                "--output",
+                "/tmp/positions.json",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command =
+            CliCommand::Source(SourceCliCommand::ExportPositions(ExportPositionsArgs {
+                client_args: ClientArgs::default(),
+                index_id_patterns: vec!["hdfs-logs".to_string(), "wiki-*".to_string()],
+                output_path_opt: Some(PathBuf::from("/tmp/positions.json")),
+            }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec!["source", "export-positions"])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command =
+            CliCommand::Source(SourceCliCommand::ExportPositions(ExportPositionsArgs {
+                client_args: ClientArgs::default(),
+                index_id_patterns: Vec::new(),
+                output_path_opt: None,
+            }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec![
+                "source",
+                "import-positions",
+                "--input",
+                "/tmp/positions.json",
+                "--yes",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(matches).unwrap();
+        let expected_command =
+            CliCommand::Source(SourceCliCommand::ImportPositions(ImportPositionsArgs {
+                client_args: ClientArgs::default(),
+                input_path: PathBuf::from("/tmp/positions.json"),
+                assume_yes: true,
+            }));
+        assert_eq!(command, expected_command);
+    }
+
     #[test]
     fn test_make_describe_source_tables() {
         assert!(make_describe_source_tables(
@@ -744,6 +938,10 @@ mod tests {
             source_id: "foo-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::file_from_str("path/to/file").unwrap(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -805,6 +1003,10 @@ mod tests {
                 source_id: "foo-source".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -813,6 +1015,10 @@ mod tests {
                 source_id: "bar-source".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -36,8 +36,8 @@ use quickwit_common::runtimes::RuntimesConfig;
 use quickwit_common::uri::Uri;
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
-    IndexerConfig, NodeConfig, SourceConfig, SourceInputFormat, SourceParams, TransformConfig,
-    VecSourceParams, CLI_SOURCE_ID,
+    IndexerConfig, NodeConfig, SourceConfig, SourceInputFormat, SourceParams, SourcePriority,
+    TransformConfig, VecSourceParams, CLI_SOURCE_ID,
 };
 use quickwit_index_management::{clear_cache_directory, IndexService};
 use quickwit_indexing::actors::{IndexingService, MergePipeline, MergeSchedulerService};
@@ -420,6 +420,10 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
         source_id: CLI_SOURCE_ID.to_string(),
         num_pipelines: NonZeroUsize::new(1).expect("1 is always non-zero."),
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_params,
         transform_config,
         input_format: args.input_format,
@@ -551,6 +555,15 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         sort_by,
         count_all: CountHits::CountAll,
         allow_failed_splits: false,
+        max_query_ast_depth: None,
+        max_query_ast_clause_count: None,
+        fields: None,
+        debug_fetch_docs_source: false,
+        pit_keep_alive_secs: None,
+        pit_id: None,
+        track_total_hits: None,
+        allow_disk_use: false,
+        sample_rate: None,
     };
     let search_request =
         search_request_from_api_request(vec![args.index_id], search_request_query_string)?;
@@ -606,6 +619,10 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
                 source_id: args.source_id,
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -31,7 +31,9 @@ use chitchat::{
     FailureDetectorConfig, KeyChangeEvent, ListenerHandle, NodeState,
 };
 use itertools::Itertools;
-use quickwit_proto::indexing::{IndexingPipelineId, IndexingTask, PipelineMetrics};
+use quickwit_proto::indexing::{
+    IndexingPipelineId, IndexingTask, IndexingTaskPriority, PipelineHealth, PipelineMetrics,
+};
 use quickwit_proto::types::{NodeId, NodeIdRef, PipelineUid, ShardId};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, watch, Mutex, RwLock};
@@ -44,8 +46,8 @@ use crate::change::{compute_cluster_change_events, ClusterChange, ClusterChangeS
 use crate::grpc_gossip::spawn_catchup_callback_task;
 use crate::member::{
     build_cluster_member, ClusterMember, NodeStateExt, ENABLED_SERVICES_KEY,
-    GRPC_ADVERTISE_ADDR_KEY, PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY,
-    READINESS_VALUE_READY,
+    GRPC_ADVERTISE_ADDR_KEY, INDEXING_DRAINING_KEY, PIPELINE_HEALTH_PREFIX,
+    PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY, READINESS_VALUE_READY,
 };
 use crate::metrics::spawn_metrics_task;
 use crate::{ClusterChangeStream, ClusterNode};
@@ -60,6 +62,15 @@ const MARKED_FOR_DELETION_GRACE_PERIOD: Duration = if cfg!(any(test, feature = "
 // `{INDEXING_TASK_PREFIX}{PIPELINE_ULID}`.
 const INDEXING_TASK_PREFIX: &str = "indexer.task:";
 
+// Standby indexing tasks are published under their own prefix so that nodes unaware of standby
+// pipelines ignore them.
+const INDEXING_STANDBY_TASK_PREFIX: &str = "indexer.standby_task:";
+
+// The priority of an indexing task is published under its own key, formatted as
+// `{INDEXING_TASK_PRIORITY_PREFIX}{PIPELINE_ULID}`, and only when it is not the default one, so
+// that the format of the task keys and values remains unchanged.
+const INDEXING_TASK_PRIORITY_PREFIX: &str = "indexer.task_priority:";
+
 #[derive(Clone)]
 pub struct Cluster {
     cluster_id: String,
@@ -270,6 +281,23 @@ impl Cluster {
             .await
     }
 
+    pub async fn is_self_node_draining(&self) -> bool {
+        self.chitchat()
+            .await
+            .lock()
+            .await
+            .node_state(&self.self_chitchat_id)
+            .expect("The self node should always be present in the set of live nodes.")
+            .is_draining()
+    }
+
+    /// Marks the self node as draining. The control plane then moves the node's indexing tasks
+    /// to other indexers and stops assigning new ones to it.
+    pub async fn set_self_node_draining(&self, draining: bool) {
+        self.set_self_key_value(INDEXING_DRAINING_KEY, draining)
+            .await
+    }
+
     /// Sets a key-value pair on the cluster node's state.
     pub async fn set_self_key_value(&self, key: impl Display, value: impl Display) {
         self.chitchat()
@@ -293,6 +321,21 @@ impl Cluster {
         chitchat_self_node.set_with_ttl(key.clone(), value);
     }
 
+    /// Sets several key-value pairs on the cluster node's state, acquiring the chitchat lock only
+    /// once. The keys are deleted after the TTL.
+    pub async fn set_self_key_values_delete_after_ttl(
+        &self,
+        key_values: impl IntoIterator<Item = (String, String)>,
+    ) {
+        let chitchat = self.chitchat().await;
+        let mut chitchat_lock = chitchat.lock().await;
+        let chitchat_self_node = chitchat_lock.self_node_state();
+
+        for (key, value) in key_values {
+            chitchat_self_node.set_with_ttl(key, value);
+        }
+    }
+
     pub async fn get_self_key_value(&self, key: &str) -> Option<String> {
         self.chitchat()
             .await
@@ -412,6 +455,34 @@ impl Cluster {
         }
     }
 
+    /// This exposes in chitchat the health of the indexing pipelines running on the node.
+    /// The health is exposed as follows:
+    /// Key:        pipeline_health:<pipeline_uid>
+    /// Value:      running
+    ///
+    /// The pipelines of a source share the same index UID and source ID, so they are keyed by
+    /// their pipeline UID.
+    pub async fn update_self_node_pipeline_health(
+        &self,
+        pipeline_health: &HashMap<&IndexingPipelineId, PipelineHealth>,
+    ) {
+        let chitchat = self.chitchat().await;
+        let mut chitchat_guard = chitchat.lock().await;
+        let node_state = chitchat_guard.self_node_state();
+        let mut current_health_keys: HashSet<String> = node_state
+            .iter_prefix(PIPELINE_HEALTH_PREFIX)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        for (pipeline_id, health) in pipeline_health {
+            let key = format!("{PIPELINE_HEALTH_PREFIX}{}", pipeline_id.pipeline_uid);
+            current_health_keys.remove(&key);
+            node_state.set(key, health.to_string());
+        }
+        for obsolete_health_key in current_health_keys {
+            node_state.delete(&obsolete_health_key);
+        }
+    }
+
     /// Updates indexing tasks in chitchat state.
     /// Tasks are grouped by (index_id, source_id), each group is stored in a key as follows:
     /// - key: `{INDEXING_TASK_PREFIX}{index_id}{INDEXING_TASK_SEPARATOR}{source_id}`
@@ -475,12 +546,27 @@ fn spawn_ready_members_task(
 
 /// Parses indexing tasks from the chitchat node state.
 pub fn parse_indexing_tasks(node_state: &NodeState) -> Vec<IndexingTask> {
+    let task_priorities: HashMap<&str, IndexingTaskPriority> = node_state
+        .iter_prefix(INDEXING_TASK_PRIORITY_PREFIX)
+        .flat_map(|(key, versioned_value)| {
+            let pipeline_uid_str = key.strip_prefix(INDEXING_TASK_PRIORITY_PREFIX)?;
+            let priority = versioned_value.value.parse().ok()?;
+            Some((pipeline_uid_str, priority))
+        })
+        .collect();
     node_state
         .iter_prefix(INDEXING_TASK_PREFIX)
+        .chain(node_state.iter_prefix(INDEXING_STANDBY_TASK_PREFIX))
         .map(|(key, versioned_value)| (key, versioned_value.value.as_str()))
         .flat_map(|(key, value)| {
-            let indexing_task_opt = chitchat_kv_to_indexing_task(key, value);
-            if indexing_task_opt.is_none() {
+            let mut indexing_task_opt = chitchat_kv_to_indexing_task(key, value);
+            if let Some(indexing_task) = &mut indexing_task_opt {
+                let pipeline_uid_str = indexing_task.pipeline_uid().to_string();
+
+                if let Some(priority) = task_priorities.get(pipeline_uid_str.as_str()) {
+                    indexing_task.set_priority(*priority);
+                }
+            } else {
                 warn!(key=%key, value=%value, "failed to parse indexing task from chitchat kv");
             }
             indexing_task_opt
@@ -498,12 +584,25 @@ pub(crate) fn set_indexing_tasks_in_node_state(
 ) {
     let mut current_indexing_tasks_keys: HashSet<String> = node_state
         .iter_prefix(INDEXING_TASK_PREFIX)
+        .chain(node_state.iter_prefix(INDEXING_STANDBY_TASK_PREFIX))
+        .chain(node_state.iter_prefix(INDEXING_TASK_PRIORITY_PREFIX))
         .map(|(key, _)| key.to_string())
         .collect();
     for indexing_task in indexing_tasks {
         let (key, value) = indexing_task_to_chitchat_kv(indexing_task);
         current_indexing_tasks_keys.remove(&key);
         node_state.set(key, value);
+
+        let priority = indexing_task.priority();
+
+        if priority != IndexingTaskPriority::Normal {
+            let priority_key = format!(
+                "{INDEXING_TASK_PRIORITY_PREFIX}{}",
+                indexing_task.pipeline_uid()
+            );
+            current_indexing_tasks_keys.remove(&priority_key);
+            node_state.set(priority_key, priority.as_str());
+        }
     }
     for obsolete_task_key in current_indexing_tasks_keys {
         node_state.delete(&obsolete_task_key);
@@ -517,9 +616,16 @@ fn indexing_task_to_chitchat_kv(indexing_task: &IndexingTask) -> (String, String
         shard_ids,
         pipeline_uid: _,
         params_fingerprint: _,
+        standby,
+        priority: _,
     } = indexing_task;
     let index_uid = indexing_task.index_uid();
-    let key = format!("{INDEXING_TASK_PREFIX}{}", indexing_task.pipeline_uid());
+    let prefix = if *standby {
+        INDEXING_STANDBY_TASK_PREFIX
+    } else {
+        INDEXING_TASK_PREFIX
+    };
+    let key = format!("{prefix}{}", indexing_task.pipeline_uid());
     let shard_ids_str = shard_ids.iter().sorted().join(",");
     let fingerprint = indexing_task.params_fingerprint;
     let value = format!("{index_uid}:{source_id}:{fingerprint}:{shard_ids_str}");
@@ -535,7 +641,12 @@ fn parse_shard_ids_str(shard_ids_str: &str) -> Vec<ShardId> {
 }
 
 fn chitchat_kv_to_indexing_task(key: &str, value: &str) -> Option<IndexingTask> {
-    let pipeline_uid_str = key.strip_prefix(INDEXING_TASK_PREFIX)?;
+    let (pipeline_uid_str, standby) =
+        if let Some(pipeline_uid_str) = key.strip_prefix(INDEXING_TASK_PREFIX) {
+            (pipeline_uid_str, false)
+        } else {
+            (key.strip_prefix(INDEXING_STANDBY_TASK_PREFIX)?, true)
+        };
     let pipeline_uid = PipelineUid::from_str(pipeline_uid_str).ok()?;
     let mut field_iterator = value.rsplitn(4, ':');
     let shards_str = field_iterator.next()?;
@@ -551,6 +662,8 @@ fn chitchat_kv_to_indexing_task(key: &str, value: &str) -> Option<IndexingTask>
         pipeline_uid: Some(pipeline_uid),
         shard_ids,
         params_fingerprint,
+        standby,
+        priority: IndexingTaskPriority::Normal as i32,
     })
 }
 
@@ -747,7 +860,7 @@ pub async fn create_cluster_for_test(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::net::SocketAddr;
     use std::time::Duration;
 
@@ -756,7 +869,7 @@ mod tests {
     use quickwit_common::test_utils::wait_until_predicate;
     use quickwit_config::service::QuickwitService;
     use quickwit_proto::indexing::IndexingTask;
-    use quickwit_proto::types::IndexUid;
+    use quickwit_proto::types::{IndexUid, PipelineUid};
     use rand::Rng;
 
     use super::*;
@@ -892,6 +1005,149 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_cluster_node_draining() {
+        let transport = ChannelTransport::default();
+        let node_1 = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let peer_seeds = vec![node_1.gossip_listen_addr.to_string()];
+        let node_2 = create_cluster_for_test(peer_seeds, &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let wait_secs = Duration::from_secs(5);
+        node_1
+            .wait_for_ready_members(|members| members.len() == 2, wait_secs)
+            .await
+            .unwrap();
+        assert!(!node_2.is_self_node_draining().await);
+
+        node_2.set_self_node_draining(true).await;
+        assert!(node_2.is_self_node_draining().await);
+
+        tokio::time::timeout(wait_secs, async {
+            loop {
+                let is_draining = node_1
+                    .ready_nodes()
+                    .await
+                    .iter()
+                    .any(|node| node.node_id() == node_2.self_node_id() && node.is_draining());
+                if is_draining {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cluster_node_indexing_affinity_labels() {
+        let transport = ChannelTransport::default();
+        let node_1 = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let peer_seeds = vec![node_1.gossip_listen_addr.to_string()];
+        let node_2 = create_cluster_for_test(peer_seeds, &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let wait_secs = Duration::from_secs(5);
+        node_1
+            .wait_for_ready_members(|members| members.len() == 2, wait_secs)
+            .await
+            .unwrap();
+
+        node_2
+            .set_self_key_value(
+                format!("{}zone", crate::INDEXING_AFFINITY_LABEL_PREFIX),
+                "us-east-1a",
+            )
+            .await;
+
+        let expected_labels = BTreeMap::from_iter([("zone".to_string(), "us-east-1a".to_string())]);
+
+        tokio::time::timeout(wait_secs, async {
+            loop {
+                let has_labels = node_1.ready_nodes().await.iter().any(|node| {
+                    node.node_id() == node_2.self_node_id()
+                        && node.indexing_affinity_labels() == &expected_labels
+                });
+                if has_labels {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cluster_node_pipeline_health() {
+        let transport = ChannelTransport::default();
+        let node_1 = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let peer_seeds = vec![node_1.gossip_listen_addr.to_string()];
+        let node_2 = create_cluster_for_test(peer_seeds, &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let wait_secs = Duration::from_secs(5);
+        node_1
+            .wait_for_ready_members(|members| members.len() == 2, wait_secs)
+            .await
+            .unwrap();
+
+        let pipeline_id = |pipeline_ord: u128| IndexingPipelineId {
+            node_id: node_2.self_node_id().to_owned(),
+            index_uid: IndexUid::for_test("test-index", 0),
+            source_id: "test-source".to_string(),
+            pipeline_uid: PipelineUid::for_test(pipeline_ord),
+        };
+        let (pipeline_id_1, pipeline_id_2, pipeline_id_3) =
+            (pipeline_id(1), pipeline_id(2), pipeline_id(3));
+        let pipeline_health = HashMap::from_iter([
+            (&pipeline_id_1, PipelineHealth::Running),
+            (&pipeline_id_2, PipelineHealth::Retrying),
+            (&pipeline_id_3, PipelineHealth::Failed),
+        ]);
+        node_2
+            .update_self_node_pipeline_health(&pipeline_health)
+            .await;
+
+        let num_unhealthy_pipelines = |expected: usize| {
+            let node_1 = &node_1;
+            let node_2_id = node_2.self_node_id();
+            async move {
+                loop {
+                    let num_unhealthy_pipelines = node_1
+                        .ready_nodes()
+                        .await
+                        .iter()
+                        .find(|node| node.node_id() == node_2_id)
+                        .map(|node| node.num_unhealthy_pipelines());
+                    if num_unhealthy_pipelines == Some(expected) {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            }
+        };
+        tokio::time::timeout(wait_secs, num_unhealthy_pipelines(2))
+            .await
+            .unwrap();
+
+        // Obsolete pipelines are removed from the node state.
+        let pipeline_health = HashMap::from_iter([(&pipeline_id_2, PipelineHealth::Running)]);
+        node_2
+            .update_self_node_pipeline_health(&pipeline_health)
+            .await;
+        tokio::time::timeout(wait_secs, num_unhealthy_pipelines(0))
+            .await
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_multi_node_cluster_readiness() {
         let transport = ChannelTransport::default();
@@ -954,6 +1210,8 @@ mod tests {
             source_id: "source-1".to_string(),
             shard_ids: Vec::new(),
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let indexing_task2 = IndexingTask {
             pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -961,6 +1219,8 @@ mod tests {
             source_id: "source-1".to_string(),
             shard_ids: Vec::new(),
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         cluster2
             .set_self_key_value(GRPC_ADVERTISE_ADDR_KEY, "127.0.0.1:1001")
@@ -1043,6 +1303,8 @@ mod tests {
                     source_id: format!("source-{source_id}"),
                     shard_ids: Vec::new(),
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 }
             })
             .collect_vec();
@@ -1271,6 +1533,8 @@ mod tests {
                 source_id: "my-source1".to_string(),
                 shard_ids: vec![ShardId::from(1), ShardId::from(2)],
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             }],
             &mut node_state,
         );
@@ -1282,6 +1546,8 @@ mod tests {
                 source_id: "my-source1".to_string(),
                 shard_ids: vec![ShardId::from(1), ShardId::from(2), ShardId::from(3)],
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             }],
             &mut node_state,
         );
@@ -1293,6 +1559,8 @@ mod tests {
                     source_id: "my-source1".to_string(),
                     shard_ids: vec![ShardId::from(1), ShardId::from(2)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 },
                 IndexingTask {
                     pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -1300,6 +1568,8 @@ mod tests {
                     source_id: "my-source1".to_string(),
                     shard_ids: vec![ShardId::from(3), ShardId::from(4)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 },
             ],
             &mut node_state,
@@ -1313,6 +1583,8 @@ mod tests {
                     source_id: "my-source1".to_string(),
                     shard_ids: vec![ShardId::from(1), ShardId::from(2)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 },
                 IndexingTask {
                     pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -1320,6 +1592,8 @@ mod tests {
                     source_id: "my-source1".to_string(),
                     shard_ids: vec![ShardId::from(3), ShardId::from(4)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 },
             ],
             &mut node_state,
@@ -1333,6 +1607,8 @@ mod tests {
                     source_id: "my-source1".to_string(),
                     shard_ids: vec![ShardId::from(1), ShardId::from(2)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
                 },
                 IndexingTask {
                     pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -1340,10 +1616,86 @@ mod tests {
                     source_id: "my-source2".to_string(),
                     shard_ids: vec![ShardId::from(3), ShardId::from(4)],
                     params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
+                },
+            ],
+            &mut node_state,
+        );
+        // standby task.
+        test_serialize_indexing_tasks_aux(
+            &[
+                IndexingTask {
+                    pipeline_uid: Some(PipelineUid::for_test(1u128)),
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "my-source1".to_string(),
+                    shard_ids: Vec::new(),
+                    params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::Normal as i32,
+                },
+                IndexingTask {
+                    pipeline_uid: Some(PipelineUid::for_test(3u128)),
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "my-source2".to_string(),
+                    shard_ids: Vec::new(),
+                    params_fingerprint: 0,
+                    standby: true,
+                    priority: IndexingTaskPriority::Normal as i32,
+                },
+            ],
+            &mut node_state,
+        );
+        // promoted standby task.
+        test_serialize_indexing_tasks_aux(
+            &[IndexingTask {
+                pipeline_uid: Some(PipelineUid::for_test(3u128)),
+                index_uid: Some(index_uid.clone()),
+                source_id: "my-source2".to_string(),
+                shard_ids: Vec::new(),
+                params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
+            }],
+            &mut node_state,
+        );
+        // tasks with a priority.
+        test_serialize_indexing_tasks_aux(
+            &[
+                IndexingTask {
+                    pipeline_uid: Some(PipelineUid::for_test(1u128)),
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "my-source1".to_string(),
+                    shard_ids: Vec::new(),
+                    params_fingerprint: 0,
+                    standby: false,
+                    priority: IndexingTaskPriority::High as i32,
+                },
+                IndexingTask {
+                    pipeline_uid: Some(PipelineUid::for_test(3u128)),
+                    index_uid: Some(index_uid.clone()),
+                    source_id: "my-source2".to_string(),
+                    shard_ids: Vec::new(),
+                    params_fingerprint: 0,
+                    standby: true,
+                    priority: IndexingTaskPriority::Background as i32,
                 },
             ],
             &mut node_state,
         );
+        // priority reset to normal.
+        test_serialize_indexing_tasks_aux(
+            &[IndexingTask {
+                pipeline_uid: Some(PipelineUid::for_test(1u128)),
+                index_uid: Some(index_uid.clone()),
+                source_id: "my-source1".to_string(),
+                shard_ids: Vec::new(),
+                params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
+            }],
+            &mut node_state,
+        );
     }
 
     #[test]
@@ -1382,5 +1734,14 @@ mod tests {
         );
         assert_eq!(&task.source_id, "my_source");
         assert_eq!(&task.shard_ids, &[ShardId::from(1), ShardId::from(3)]);
+        assert!(!task.standby);
+
+        let standby_task = super::chitchat_kv_to_indexing_task(
+            "indexer.standby_task:01BX5ZZKBKACTAV9WEVGEMMVS0",
+            "my_index:00000000000000000000000000:my_source:42:",
+        )
+        .unwrap();
+        assert!(standby_task.standby);
+        assert!(standby_task.shard_ids.is_empty());
     }
 }
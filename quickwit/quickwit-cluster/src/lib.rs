@@ -50,7 +50,10 @@ pub use crate::cluster::{
     create_cluster_for_test, create_cluster_for_test_with_id, grpc_addr_from_listen_addr_for_test,
 };
 pub use crate::cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
-pub use crate::member::{ClusterMember, INDEXING_CPU_CAPACITY_KEY};
+pub use crate::member::{
+    ClusterMember, INDEXING_AFFINITY_LABEL_PREFIX, INDEXING_CPU_CAPACITY_KEY,
+    INDEXING_PLAN_DELTAS_KEY,
+};
 pub use crate::node::ClusterNode;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -168,6 +171,18 @@ pub async fn start_cluster_service(node_config: &NodeConfig) -> anyhow::Result<C
         cluster
             .set_self_key_value(INDEXING_CPU_CAPACITY_KEY, indexing_cpu_capacity)
             .await;
+        cluster
+            .set_self_key_value(INDEXING_PLAN_DELTAS_KEY, true)
+            .await;
+
+        for (label_key, label_value) in &node_config.indexer_config.indexing_affinity_labels {
+            cluster
+                .set_self_key_value(
+                    format!("{INDEXING_AFFINITY_LABEL_PREFIX}{label_key}"),
+                    label_value,
+                )
+                .await;
+        }
     }
     Ok(cluster)
 }
@@ -17,14 +17,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::mem::size_of;
 use std::net::SocketAddr;
 use std::str::FromStr;
 
 use anyhow::Context;
 use chitchat::{ChitchatId, NodeState, Version};
-use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
+use quickwit_proto::indexing::{CpuCapacity, IndexingTask, PipelineHealth};
 use quickwit_proto::types::NodeId;
 use tracing::{error, warn};
 
@@ -35,6 +35,7 @@ use crate::{GenerationId, QuickwitService};
 pub(crate) const GRPC_ADVERTISE_ADDR_KEY: &str = "grpc_advertise_addr";
 pub(crate) const ENABLED_SERVICES_KEY: &str = "enabled_services";
 pub(crate) const PIPELINE_METRICS_PREFIX: &str = "pipeline_metrics:";
+pub(crate) const PIPELINE_HEALTH_PREFIX: &str = "pipeline_health:";
 
 // Readiness key and values used to store node's readiness in Chitchat state.
 pub(crate) const READINESS_KEY: &str = "readiness";
@@ -43,11 +44,29 @@ pub(crate) const READINESS_VALUE_NOT_READY: &str = "NOT_READY";
 
 pub const INDEXING_CPU_CAPACITY_KEY: &str = "indexing_cpu_capacity";
 
+// Draining key used to signal that an indexer is being drained before shutdown.
+pub(crate) const INDEXING_DRAINING_KEY: &str = "indexing_draining";
+
+// Key used by indexers to advertise that they accept indexing plans with delta-encoded shard lists.
+pub const INDEXING_PLAN_DELTAS_KEY: &str = "indexing_plan_deltas";
+
+// Prefix of the keys used by indexers to advertise their indexing affinity labels, matched by the
+// control plane against the placement selectors of the sources.
+pub const INDEXING_AFFINITY_LABEL_PREFIX: &str = "indexing_affinity_label:";
+
 pub(crate) trait NodeStateExt {
     fn grpc_advertise_addr(&self) -> anyhow::Result<SocketAddr>;
 
     fn is_ready(&self) -> bool;
 
+    fn is_draining(&self) -> bool;
+
+    fn supports_indexing_plan_deltas(&self) -> bool;
+
+    fn num_unhealthy_pipelines(&self) -> usize;
+
+    fn indexing_affinity_labels(&self) -> BTreeMap<String, String>;
+
     fn size_bytes(&self) -> usize;
 }
 
@@ -70,6 +89,38 @@ impl NodeStateExt for NodeState {
             .unwrap_or(false)
     }
 
+    fn is_draining(&self) -> bool {
+        self.get(INDEXING_DRAINING_KEY)
+            .map(|draining_value| draining_value == "true")
+            .unwrap_or(false)
+    }
+
+    fn supports_indexing_plan_deltas(&self) -> bool {
+        self.get(INDEXING_PLAN_DELTAS_KEY)
+            .map(|plan_deltas_value| plan_deltas_value == "true")
+            .unwrap_or(false)
+    }
+
+    fn num_unhealthy_pipelines(&self) -> usize {
+        self.iter_prefix(PIPELINE_HEALTH_PREFIX)
+            .filter(|(_, versioned_value)| {
+                versioned_value
+                    .value
+                    .parse::<PipelineHealth>()
+                    .is_ok_and(|health| !health.is_healthy())
+            })
+            .count()
+    }
+
+    fn indexing_affinity_labels(&self) -> BTreeMap<String, String> {
+        self.iter_prefix(INDEXING_AFFINITY_LABEL_PREFIX)
+            .filter_map(|(key, versioned_value)| {
+                let label_key = key.strip_prefix(INDEXING_AFFINITY_LABEL_PREFIX)?;
+                Some((label_key.to_string(), versioned_value.value.clone()))
+            })
+            .collect()
+    }
+
     // TODO: Expose more accurate size of the state in Chitchat.
     fn size_bytes(&self) -> usize {
         const SIZE_OF_VERSION: usize = size_of::<Version>();
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Debug;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -28,7 +28,7 @@ use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
 use quickwit_proto::types::NodeIdRef;
 use tonic::transport::Channel;
 
-use crate::member::build_cluster_member;
+use crate::member::{build_cluster_member, NodeStateExt};
 
 #[derive(Clone)]
 pub struct ClusterNode {
@@ -52,6 +52,10 @@ impl ClusterNode {
             indexing_tasks: member.indexing_tasks,
             indexing_capacity: member.indexing_cpu_capacity,
             is_ready: member.is_ready,
+            is_draining: node_state.is_draining(),
+            supports_indexing_plan_deltas: node_state.supports_indexing_plan_deltas(),
+            num_unhealthy_pipelines: node_state.num_unhealthy_pipelines(),
+            indexing_affinity_labels: node_state.indexing_affinity_labels(),
             is_self_node,
         };
         let node = ClusterNode {
@@ -134,6 +138,28 @@ impl ClusterNode {
         self.inner.is_ready
     }
 
+    /// Returns whether the node is an indexer being drained: its indexing tasks are being moved to
+    /// other indexers and no new task should be assigned to it.
+    pub fn is_draining(&self) -> bool {
+        self.inner.is_draining
+    }
+
+    /// Returns whether the node is an indexer accepting indexing plans with delta-encoded shard
+    /// lists.
+    pub fn supports_indexing_plan_deltas(&self) -> bool {
+        self.inner.supports_indexing_plan_deltas
+    }
+
+    /// Returns the number of indexing pipelines of the node that are failed or being respawned.
+    pub fn num_unhealthy_pipelines(&self) -> usize {
+        self.inner.num_unhealthy_pipelines
+    }
+
+    /// Returns the indexing affinity labels advertised by the node.
+    pub fn indexing_affinity_labels(&self) -> &BTreeMap<String, String> {
+        &self.inner.indexing_affinity_labels
+    }
+
     pub fn is_self_node(&self) -> bool {
         self.inner.is_self_node
     }
@@ -157,6 +183,10 @@ impl PartialEq for ClusterNode {
             && self.inner.grpc_advertise_addr == other.inner.grpc_advertise_addr
             && self.inner.indexing_tasks == other.inner.indexing_tasks
             && self.inner.is_ready == other.inner.is_ready
+            && self.inner.is_draining == other.inner.is_draining
+            && self.inner.supports_indexing_plan_deltas == other.inner.supports_indexing_plan_deltas
+            && self.inner.num_unhealthy_pipelines == other.inner.num_unhealthy_pipelines
+            && self.inner.indexing_affinity_labels == other.inner.indexing_affinity_labels
             && self.inner.is_self_node == other.inner.is_self_node
     }
 }
@@ -169,5 +199,9 @@ struct InnerNode {
     indexing_tasks: Vec<IndexingTask>,
     indexing_capacity: CpuCapacity,
     is_ready: bool,
+    is_draining: bool,
+    supports_indexing_plan_deltas: bool,
+    num_unhealthy_pipelines: usize,
+    indexing_affinity_labels: BTreeMap<String, String>,
     is_self_node: bool,
 }
@@ -0,0 +1,324 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const UNITS: [(&str, u64); 5] = [
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1_000),
+    ("ms", 1),
+];
+
+/// A duration expressed in a human-readable way, as a sequence of integer amounts followed by a
+/// unit: `ms`, `s`, `m`, `h`, or `d`. Compound forms such as `1h30m` are accepted and whitespace
+/// may separate the amounts from their units (`1h 30m`, `30 s`).
+///
+/// The precision of the duration is the millisecond. It is displayed in its canonical compound
+/// form (`5400s` is displayed as `1h30m`), which parses back to the same duration.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct HumanDuration(Duration);
+
+impl HumanDuration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(Duration::from_secs(secs))
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(Duration::from_millis(millis))
+    }
+
+    pub const fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Returns the number of whole seconds of the duration, or an error if the duration is not a
+    /// whole number of seconds.
+    pub fn as_whole_secs(&self) -> anyhow::Result<u64> {
+        if self.0.subsec_nanos() != 0 {
+            bail!("duration `{self}` must be a whole number of seconds");
+        }
+        Ok(self.0.as_secs())
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl From<HumanDuration> for Duration {
+    fn from(human_duration: HumanDuration) -> Self {
+        human_duration.0
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = anyhow::Error;
+
+    fn from_str(duration_str: &str) -> anyhow::Result<Self> {
+        parse_millis(duration_str)
+            .map(|millis| Self(Duration::from_millis(millis)))
+            .with_context(|| format!("failed to parse duration `{duration_str}`"))
+    }
+}
+
+fn parse_millis(duration_str: &str) -> anyhow::Result<u64> {
+    let mut remaining = duration_str.trim_start();
+
+    if remaining.is_empty() {
+        bail!("duration is empty");
+    }
+    let mut total_millis: u64 = 0;
+
+    while !remaining.is_empty() {
+        let amount_len = remaining
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(remaining.len());
+        if amount_len == 0 {
+            bail!("expected an amount, found `{remaining}`");
+        }
+        let amount: u64 = remaining[..amount_len]
+            .parse()
+            .context("amount is too large")?;
+        remaining = remaining[amount_len..].trim_start();
+
+        let unit_len = remaining
+            .find(|ch: char| !ch.is_ascii_alphabetic())
+            .unwrap_or(remaining.len());
+        let unit = &remaining[..unit_len];
+        let Some((_, unit_millis)) = UNITS.iter().find(|(unit_name, _)| *unit_name == unit) else {
+            if unit.is_empty() {
+                bail!("amount `{amount}` is missing a unit (`ms`, `s`, `m`, `h`, or `d`)");
+            }
+            bail!("unknown unit `{unit}`, expected `ms`, `s`, `m`, `h`, or `d`");
+        };
+        total_millis = amount
+            .checked_mul(*unit_millis)
+            .and_then(|millis| total_millis.checked_add(millis))
+            .context("duration is too large")?;
+        remaining = remaining[unit_len..].trim_start();
+    }
+    Ok(total_millis)
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut remaining_millis = self.0.as_millis();
+
+        if remaining_millis == 0 {
+            return write!(formatter, "0s");
+        }
+        for (unit, unit_millis) in UNITS {
+            let unit_millis = unit_millis as u128;
+            let amount = remaining_millis / unit_millis;
+
+            if amount > 0 {
+                write!(formatter, "{amount}{unit}")?;
+                remaining_millis %= unit_millis;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for HumanDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for HumanDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let duration_str: Cow<'de, str> = Deserialize::deserialize(deserializer)?;
+        HumanDuration::from_str(&duration_str)
+            .map_err(|error| D::Error::custom(format!("{error:#}")))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SecsOrHumanDuration {
+    Secs(u64),
+    HumanDuration(String),
+}
+
+/// Deserializes a number of seconds expressed either as an integer, the historical format of the
+/// `*_secs` fields, or as a [`HumanDuration`] that is a whole number of seconds.
+pub fn deserialize_secs<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where D: Deserializer<'de> {
+    match SecsOrHumanDuration::deserialize(deserializer)? {
+        SecsOrHumanDuration::Secs(secs) => Ok(secs),
+        SecsOrHumanDuration::HumanDuration(duration_str) => HumanDuration::from_str(&duration_str)
+            .and_then(|human_duration| human_duration.as_whole_secs())
+            .map_err(|error| D::Error::custom(format!("{error:#}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[track_caller]
+    fn test_parse_aux(duration_str: &str, expected_millis: u64) {
+        let human_duration = HumanDuration::from_str(duration_str).unwrap();
+        assert_eq!(
+            human_duration.as_duration().as_millis() as u64,
+            expected_millis
+        );
+    }
+
+    #[test]
+    fn test_human_duration_parse() {
+        test_parse_aux("0s", 0);
+        test_parse_aux("250ms", 250);
+        test_parse_aux("30s", 30_000);
+        test_parse_aux("5m", 300_000);
+        test_parse_aux("2h", 7_200_000);
+        test_parse_aux("7d", 604_800_000);
+        test_parse_aux("1h30m", 5_400_000);
+        test_parse_aux("1h 30m", 5_400_000);
+        test_parse_aux(" 30 s ", 30_000);
+        test_parse_aux("1m1m", 120_000);
+        test_parse_aux("1d2h3m4s5ms", 93_784_005);
+    }
+
+    #[test]
+    fn test_human_duration_parse_errors() {
+        for invalid_duration_str in [
+            "",
+            " ",
+            "30",
+            "s",
+            "1h30",
+            "-1s",
+            "1.5h",
+            "2 days",
+            "1w",
+            "1y",
+            "30S",
+            "1h-30m",
+            "99999999999999999999s",
+            "18446744073709551615d",
+        ] {
+            HumanDuration::from_str(invalid_duration_str).unwrap_err();
+        }
+        let error = HumanDuration::from_str("1h30").unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "failed to parse duration `1h30`: amount `30` is missing a unit (`ms`, `s`, `m`, `h`, \
+             or `d`)"
+        );
+    }
+
+    #[test]
+    fn test_human_duration_display() {
+        assert_eq!(HumanDuration::from_secs(0).to_string(), "0s");
+        assert_eq!(HumanDuration::from_millis(250).to_string(), "250ms");
+        assert_eq!(HumanDuration::from_secs(30).to_string(), "30s");
+        assert_eq!(HumanDuration::from_secs(5_400).to_string(), "1h30m");
+        assert_eq!(HumanDuration::from_secs(86_400).to_string(), "1d");
+        assert_eq!(
+            HumanDuration::from_millis(93_784_005).to_string(),
+            "1d2h3m4s5ms"
+        );
+    }
+
+    #[test]
+    fn test_human_duration_serde() {
+        let human_duration: HumanDuration = serde_json::from_str(r#""1h30m""#).unwrap();
+        assert_eq!(human_duration, HumanDuration::from_secs(5_400));
+        assert_eq!(
+            serde_json::to_string(&human_duration).unwrap(),
+            r#""1h30m""#
+        );
+
+        let error = serde_json::from_str::<HumanDuration>(r#""1 hour""#).unwrap_err();
+        assert!(error.to_string().contains("unknown unit `hour`"));
+    }
+
+    #[test]
+    fn test_deserialize_secs() {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "deserialize_secs")]
+            timeout_secs: u64,
+        }
+        let config: Config = serde_json::from_str(r#"{"timeout_secs": 42}"#).unwrap();
+        assert_eq!(config.timeout_secs, 42);
+
+        let config: Config = serde_json::from_str(r#"{"timeout_secs": "1m30s"}"#).unwrap();
+        assert_eq!(config.timeout_secs, 90);
+
+        let error = serde_json::from_str::<Config>(r#"{"timeout_secs": "1500ms"}"#)
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains("duration `1s500ms` must be a whole number of seconds"));
+
+        serde_json::from_str::<Config>(r#"{"timeout_secs": -1}"#)
+            .err()
+            .unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn test_human_duration_display_parse_roundtrip(millis in 0..u64::MAX / 1_000) {
+            let human_duration = HumanDuration::from_millis(millis);
+            let parsed_human_duration = HumanDuration::from_str(&human_duration.to_string()).unwrap();
+            prop_assert_eq!(parsed_human_duration, human_duration);
+        }
+
+        #[test]
+        fn test_human_duration_parse_does_not_panic(duration_str in "\\PC{0,16}") {
+            let _ = HumanDuration::from_str(&duration_str);
+        }
+
+        #[test]
+        fn test_human_duration_parse_compound(
+            components in proptest::collection::vec((0..1_000u64, 0..5usize), 1..6),
+            separator in "[ ]{0,2}",
+        ) {
+            let mut duration_str = String::new();
+            let mut expected_millis = 0;
+
+            for (amount, unit_ord) in components {
+                let (unit, unit_millis) = UNITS[unit_ord];
+                duration_str.push_str(&format!("{amount}{separator}{unit}{separator}"));
+                expected_millis += amount * unit_millis;
+            }
+            let human_duration = HumanDuration::from_str(&duration_str).unwrap();
+            prop_assert_eq!(human_duration, HumanDuration::from_millis(expected_millis));
+        }
+    }
+}
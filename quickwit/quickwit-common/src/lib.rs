@@ -23,6 +23,7 @@ mod coolid;
 
 pub mod binary_heap;
 pub mod fs;
+pub mod human_duration;
 pub mod io;
 mod kill_switch;
 pub mod metrics;
@@ -55,6 +56,7 @@ use std::ops::{Range, RangeInclusive};
 use std::str::FromStr;
 
 pub use coolid::new_coolid;
+pub use human_duration::HumanDuration;
 pub use kill_switch::KillSwitch;
 pub use path_hasher::PathHasher;
 pub use progress::{Progress, ProtectedZoneGuard};
@@ -22,11 +22,11 @@ use std::sync::OnceLock;
 
 use once_cell::sync::Lazy;
 pub use prometheus::{
-    exponential_buckets, linear_buckets, Histogram, HistogramTimer,
-    HistogramVec as PrometheusHistogramVec, IntCounter, IntCounterVec as PrometheusIntCounterVec,
-    IntGauge, IntGaugeVec as PrometheusIntGaugeVec,
+    exponential_buckets, linear_buckets, Counter, CounterVec as PrometheusCounterVec, Gauge,
+    Histogram, HistogramTimer, HistogramVec as PrometheusHistogramVec, IntCounter,
+    IntCounterVec as PrometheusIntCounterVec, IntGauge, IntGaugeVec as PrometheusIntGaugeVec,
 };
-use prometheus::{Gauge, HistogramOpts, Opts, TextEncoder};
+use prometheus::{HistogramOpts, Opts, TextEncoder};
 
 #[derive(Clone)]
 pub struct HistogramVec<const N: usize> {
@@ -50,6 +50,17 @@ impl<const N: usize> IntCounterVec<N> {
     }
 }
 
+#[derive(Clone)]
+pub struct CounterVec<const N: usize> {
+    underlying: PrometheusCounterVec,
+}
+
+impl<const N: usize> CounterVec<N> {
+    pub fn with_label_values(&self, label_values: [&str; N]) -> Counter {
+        self.underlying.with_label_values(&label_values)
+    }
+}
+
 #[derive(Clone)]
 pub struct IntGaugeVec<const N: usize> {
     underlying: PrometheusIntGaugeVec,
@@ -133,6 +144,30 @@ pub fn new_counter_vec<const N: usize>(
     IntCounterVec { underlying }
 }
 
+pub fn new_float_counter_vec<const N: usize>(
+    name: &str,
+    help: &str,
+    subsystem: &str,
+    const_labels: &[(&str, &str)],
+    label_names: [&str; N],
+) -> CounterVec<N> {
+    let owned_const_labels: HashMap<String, String> = const_labels
+        .iter()
+        .map(|(label_name, label_value)| (label_name.to_string(), label_value.to_string()))
+        .collect();
+    let counter_opts = Opts::new(name, help)
+        .namespace("quickwit")
+        .subsystem(subsystem)
+        .const_labels(owned_const_labels);
+    let underlying = PrometheusCounterVec::new(counter_opts, &label_names)
+        .expect("failed to create float counter vec");
+
+    let collector = Box::new(underlying.clone());
+    prometheus::register(collector).expect("failed to register float counter vec");
+
+    CounterVec { underlying }
+}
+
 pub fn new_float_gauge(
     name: &str,
     help: &str,
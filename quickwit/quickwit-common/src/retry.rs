@@ -55,7 +55,7 @@ impl<E> Retryable for Retry<E> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct RetryParams {
     pub base_delay: Duration,
     pub max_delay: Duration,
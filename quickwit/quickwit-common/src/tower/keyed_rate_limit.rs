@@ -0,0 +1,129 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::time::Duration;
+
+use super::RpcName;
+use crate::metrics::IntCounterVec;
+use crate::rate_limiter::{RateLimiter, RateLimiterSettings};
+
+/// Rate limits requests independently for each key, for instance the ID of the node sending the
+/// requests, using one token bucket per key.
+///
+/// Throttled requests are counted in the `throttled_total` counter, labeled with the name of the
+/// RPC.
+pub struct KeyedRateLimit<K> {
+    settings: RateLimiterSettings,
+    rate_limiters: HashMap<K, RateLimiter>,
+    throttled_total: IntCounterVec<1>,
+}
+
+impl<K> fmt::Debug for KeyedRateLimit<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedRateLimit")
+            .field("settings", &self.settings)
+            .field("num_keys", &self.rate_limiters.len())
+            .finish()
+    }
+}
+
+impl<K> KeyedRateLimit<K>
+where K: Hash + Eq
+{
+    /// Creates a new keyed rate limiter. Each request costs one permit.
+    pub fn new(settings: RateLimiterSettings, throttled_total: IntCounterVec<1>) -> Self {
+        Self {
+            settings,
+            rate_limiters: HashMap::new(),
+            throttled_total,
+        }
+    }
+
+    /// Acquires a permit for a request `R` sent by `key`. If the request is throttled, returns
+    /// the duration to wait before retrying.
+    pub fn acquire<R: RpcName>(&mut self, key: K) -> Result<(), Duration> {
+        let settings = self.settings;
+        let acquire_res = self
+            .rate_limiters
+            .entry(key)
+            .or_insert_with(|| RateLimiter::from_settings(settings))
+            .acquire_with_duration(1);
+
+        if acquire_res.is_err() {
+            self.throttled_total
+                .with_label_values([R::rpc_name()])
+                .inc();
+        }
+        acquire_res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::new_counter_vec;
+    use crate::tower::ConstantRate;
+
+    struct TestRequest;
+
+    impl RpcName for TestRequest {
+        fn rpc_name() -> &'static str {
+            "test_rpc"
+        }
+    }
+
+    #[test]
+    fn test_keyed_rate_limit() {
+        let settings = RateLimiterSettings {
+            burst_limit: 2,
+            rate_limit: ConstantRate::new(10, Duration::from_secs(1)),
+            refill_period: Duration::from_millis(100),
+        };
+        let throttled_total = new_counter_vec(
+            "keyed_rate_limit_throttled_total",
+            "Number of throttled requests.",
+            "test",
+            &[],
+            ["rpc"],
+        );
+        let mut keyed_rate_limit = KeyedRateLimit::new(settings, throttled_total.clone());
+
+        keyed_rate_limit.acquire::<TestRequest>("node-1").unwrap();
+        keyed_rate_limit.acquire::<TestRequest>("node-1").unwrap();
+
+        let wait = keyed_rate_limit
+            .acquire::<TestRequest>("node-1")
+            .unwrap_err();
+        assert!(wait <= Duration::from_millis(100));
+        assert_eq!(throttled_total.with_label_values(["test_rpc"]).get(), 1);
+        // Other keys have their own bucket.
+        keyed_rate_limit.acquire::<TestRequest>("node-2").unwrap();
+
+        std::thread::sleep(Duration::from_millis(100));
+
+        keyed_rate_limit.acquire::<TestRequest>("node-1").unwrap();
+        keyed_rate_limit
+            .acquire::<TestRequest>("node-1")
+            .unwrap_err();
+        assert_eq!(throttled_total.with_label_values(["test_rpc"]).get(), 2);
+    }
+}
@@ -25,6 +25,7 @@ mod circuit_breaker;
 mod delay;
 mod estimate_rate;
 mod event_listener;
+mod keyed_rate_limit;
 mod load_shed;
 mod metrics;
 mod one_task_per_call_layer;
@@ -33,6 +34,7 @@ mod rate;
 mod rate_estimator;
 mod rate_limit;
 mod retry;
+mod rpc_policy;
 mod timeout;
 mod transport;
 
@@ -48,6 +50,7 @@ pub use delay::{Delay, DelayLayer};
 pub use estimate_rate::{EstimateRate, EstimateRateLayer};
 pub use event_listener::{EventListener, EventListenerLayer};
 use futures::Future;
+pub use keyed_rate_limit::KeyedRateLimit;
 pub use load_shed::{LoadShed, LoadShedLayer, MakeLoadShedError};
 pub use metrics::{GrpcMetrics, GrpcMetricsLayer, RpcName};
 pub use one_task_per_call_layer::{OneTaskPerCallLayer, TaskCancelled};
@@ -56,6 +59,10 @@ pub use rate::{ConstantRate, Rate};
 pub use rate_estimator::{RateEstimator, SmaRateEstimator};
 pub use rate_limit::{RateLimit, RateLimitLayer};
 pub use retry::{RetryLayer, RetryPolicy};
+pub use rpc_policy::{
+    RpcPolicy, RpcPolicyLayer, RpcPolicyMetrics, RpcPolicyService, RpcPolicyTable,
+    RpcStreamTimeoutLayer, RpcStreamTimeoutService,
+};
 pub use timeout::{Timeout, TimeoutExceeded, TimeoutLayer};
 pub use transport::{make_channel, warmup_channel, BalanceChannel};
 
@@ -0,0 +1,535 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use tokio::time::Instant;
+use tower::{Layer, Service, ServiceExt};
+use tracing::debug;
+
+use super::{BoxFuture, RpcName, TimeoutExceeded};
+use crate::metrics::{new_counter_vec, IntCounterVec};
+use crate::retry::{RetryParams, Retryable};
+use crate::ServiceStream;
+
+/// Timeout and retry policy applied to the requests of an RPC.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RpcPolicy {
+    /// Maximum duration of each attempt. `None` disables the timeout.
+    pub timeout_opt: Option<Duration>,
+    /// Retry settings. A `max_attempts` of 1 disables the retries.
+    pub retry_params: RetryParams,
+}
+
+impl Default for RpcPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_opt: None,
+            retry_params: RetryParams::no_retries(),
+        }
+    }
+}
+
+/// Table of [`RpcPolicy`] keyed by the name of the RPC, as returned by [`RpcName::rpc_name`].
+/// The RPCs missing from the table fall back to the default policy.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RpcPolicyTable {
+    default_policy: RpcPolicy,
+    policies: HashMap<String, RpcPolicy>,
+}
+
+impl RpcPolicyTable {
+    pub fn new(default_policy: RpcPolicy) -> Self {
+        Self {
+            default_policy,
+            policies: HashMap::new(),
+        }
+    }
+
+    /// Overrides the policy of the RPC named `rpc_name`.
+    pub fn with_policy(mut self, rpc_name: impl Into<String>, policy: RpcPolicy) -> Self {
+        self.policies.insert(rpc_name.into(), policy);
+        self
+    }
+
+    pub fn get(&self, rpc_name: &str) -> &RpcPolicy {
+        self.policies.get(rpc_name).unwrap_or(&self.default_policy)
+    }
+}
+
+/// Counts the requests that timed out and the requests that were retried, labeled with the name
+/// of the RPC.
+#[derive(Clone)]
+pub struct RpcPolicyMetrics {
+    timeouts_total: IntCounterVec<1>,
+    retries_total: IntCounterVec<1>,
+}
+
+impl RpcPolicyMetrics {
+    pub fn new(subsystem: &'static str, kind: &'static str) -> Self {
+        Self {
+            timeouts_total: new_counter_vec(
+                "rpc_timeouts_total",
+                "Number of RPC attempts that exceeded their timeout.",
+                subsystem,
+                &[("kind", kind)],
+                ["rpc"],
+            ),
+            retries_total: new_counter_vec(
+                "rpc_retries_total",
+                "Number of RPC attempts that were retried.",
+                subsystem,
+                &[("kind", kind)],
+                ["rpc"],
+            ),
+        }
+    }
+}
+
+/// Applies the timeout and retry policy of the RPC to each request, looking it up in a
+/// [`RpcPolicyTable`] by the name of the RPC.
+///
+/// For the RPCs returning a stream, the timeout only bounds the time it takes to open the stream.
+/// Stack a [`RpcStreamTimeoutLayer`] below this layer to bound the consumption of the stream too.
+#[derive(Clone)]
+pub struct RpcPolicyLayer {
+    table: Arc<RpcPolicyTable>,
+    metrics: RpcPolicyMetrics,
+}
+
+impl RpcPolicyLayer {
+    pub fn new(table: RpcPolicyTable, metrics: RpcPolicyMetrics) -> Self {
+        Self {
+            table: Arc::new(table),
+            metrics,
+        }
+    }
+
+    /// Returns the layer bounding the consumption of the streamed responses with the timeouts of
+    /// the same policies.
+    pub fn stream_timeout_layer(&self) -> RpcStreamTimeoutLayer {
+        RpcStreamTimeoutLayer {
+            table: self.table.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+impl<S> Layer<S> for RpcPolicyLayer {
+    type Service = RpcPolicyService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcPolicyService {
+            inner,
+            table: self.table.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcPolicyService<S> {
+    inner: S,
+    table: Arc<RpcPolicyTable>,
+    metrics: RpcPolicyMetrics,
+}
+
+impl<S> fmt::Debug for RpcPolicyService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcPolicyService")
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+impl<S, R> Service<R> for RpcPolicyService<S>
+where
+    S: Service<R> + Clone + Send + 'static,
+    S::Response: Send + 'static,
+    S::Error: From<TimeoutExceeded> + Retryable + fmt::Debug + Send + 'static,
+    S::Future: Send + 'static,
+    R: RpcName + Clone + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<S::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let rpc_name = R::rpc_name();
+        let policy = *self.table.get(rpc_name);
+        let metrics = self.metrics.clone();
+        // The service driven to readiness by `poll_ready` serves the first attempt.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let mut num_attempts = 0;
+            loop {
+                num_attempts += 1;
+                let response_fut = inner.call(request.clone());
+
+                let result = if let Some(timeout) = policy.timeout_opt {
+                    match tokio::time::timeout(timeout, response_fut).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            metrics.timeouts_total.with_label_values([rpc_name]).inc();
+                            Err(TimeoutExceeded.into())
+                        }
+                    }
+                } else {
+                    response_fut.await
+                };
+                let error = match result {
+                    Ok(response) => return Ok(response),
+                    Err(error) => error,
+                };
+                if !error.is_retryable() || num_attempts >= policy.retry_params.max_attempts {
+                    return Err(error);
+                }
+                let delay = policy.retry_params.compute_delay(num_attempts);
+                debug!(
+                    num_attempts=%num_attempts,
+                    delay_millis=%delay.as_millis(),
+                    error=?error,
+                    "{rpc_name} request failed, retrying"
+                );
+                metrics.retries_total.with_label_values([rpc_name]).inc();
+                tokio::time::sleep(delay).await;
+                inner.ready().await?;
+            }
+        })
+    }
+}
+
+/// Bounds each attempt of an RPC returning a stream, from the request to the last item of the
+/// stream, by the timeout of the RPC policy. Once the timeout elapses, the stream yields a timeout
+/// error and ends.
+#[derive(Clone)]
+pub struct RpcStreamTimeoutLayer {
+    table: Arc<RpcPolicyTable>,
+    metrics: RpcPolicyMetrics,
+}
+
+impl<S> Layer<S> for RpcStreamTimeoutLayer {
+    type Service = RpcStreamTimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcStreamTimeoutService {
+            inner,
+            table: self.table.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcStreamTimeoutService<S> {
+    inner: S,
+    table: Arc<RpcPolicyTable>,
+    metrics: RpcPolicyMetrics,
+}
+
+impl<S> fmt::Debug for RpcStreamTimeoutService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcStreamTimeoutService")
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+impl<S, R, T, E> Service<R> for RpcStreamTimeoutService<S>
+where
+    S: Service<R, Response = ServiceStream<Result<T, E>>, Error = E>,
+    S::Future: Send + 'static,
+    T: Send + 'static,
+    E: From<TimeoutExceeded> + Send + 'static,
+    R: RpcName,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<S::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let rpc_name = R::rpc_name();
+        let deadline_opt = self
+            .table
+            .get(rpc_name)
+            .timeout_opt
+            .map(|timeout| Instant::now() + timeout);
+        let metrics = self.metrics.clone();
+        let response_fut = self.inner.call(request);
+
+        Box::pin(async move {
+            let response_stream = response_fut.await?;
+
+            let Some(deadline) = deadline_opt else {
+                return Ok(response_stream);
+            };
+            let bounded_stream =
+                stream::unfold(Some(response_stream), move |response_stream_opt| {
+                    let metrics = metrics.clone();
+                    async move {
+                        let mut response_stream = response_stream_opt?;
+
+                        match tokio::time::timeout_at(deadline, response_stream.next()).await {
+                            Ok(item_opt) => item_opt.map(|item| (item, Some(response_stream))),
+                            Err(_) => {
+                                metrics.timeouts_total.with_label_values([rpc_name]).inc();
+                                Some((Err(TimeoutExceeded.into()), None))
+                            }
+                        }
+                    }
+                });
+            Ok(ServiceStream::new(Box::pin(bounded_stream.boxed())))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, Eq, PartialEq)]
+    enum TestError {
+        Timeout,
+        Unavailable,
+    }
+
+    impl From<TimeoutExceeded> for TestError {
+        fn from(_: TimeoutExceeded) -> Self {
+            Self::Timeout
+        }
+    }
+
+    impl Retryable for TestError {
+        fn is_retryable(&self) -> bool {
+            true
+        }
+    }
+
+    /// A request that takes `delay` to complete and fails with `Unavailable` `num_failures`
+    /// times first.
+    #[derive(Clone)]
+    struct TestRequest<const SLOW: bool> {
+        delay: Duration,
+        num_failures: usize,
+        num_attempts: Arc<AtomicUsize>,
+    }
+
+    type ListSplitsRequest = TestRequest<true>;
+    type IndexMetadataRequest = TestRequest<false>;
+
+    impl RpcName for ListSplitsRequest {
+        fn rpc_name() -> &'static str {
+            "list_splits"
+        }
+    }
+
+    impl RpcName for IndexMetadataRequest {
+        fn rpc_name() -> &'static str {
+            "index_metadata"
+        }
+    }
+
+    impl<const SLOW: bool> TestRequest<SLOW> {
+        fn new(delay: Duration, num_failures: usize) -> Self {
+            Self {
+                delay,
+                num_failures,
+                num_attempts: Arc::default(),
+            }
+        }
+
+        fn num_attempts(&self) -> usize {
+            self.num_attempts.load(Ordering::Relaxed)
+        }
+    }
+
+    async fn serve<const SLOW: bool>(request: TestRequest<SLOW>) -> Result<(), TestError> {
+        let num_attempts = request.num_attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        tokio::time::sleep(request.delay).await;
+
+        if num_attempts <= request.num_failures {
+            return Err(TestError::Unavailable);
+        }
+        Ok(())
+    }
+
+    /// The metrics subsystem must differ between tests since the metrics are registered
+    /// globally.
+    fn test_layer(subsystem: &'static str) -> RpcPolicyLayer {
+        let list_splits_policy = RpcPolicy {
+            timeout_opt: Some(Duration::from_millis(50)),
+            retry_params: RetryParams {
+                max_attempts: 2,
+                ..RetryParams::for_test()
+            },
+        };
+        let table = RpcPolicyTable::default().with_policy("list_splits", list_splits_policy);
+        let metrics = RpcPolicyMetrics::new(subsystem, "client");
+        RpcPolicyLayer::new(table, metrics)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rpc_stream_timeout_layer() {
+        let layer = test_layer("rpc_stream_timeout_test");
+        let metrics = layer.metrics.clone();
+
+        // Each item of the stream takes 20ms to come: the stream times out after two items.
+        let serve_stream = |_request: ListSplitsRequest| async {
+            let response_stream = stream::iter(0..10).then(|item| async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<_, TestError>(item)
+            });
+            Ok::<_, TestError>(ServiceStream::new(Box::pin(response_stream.boxed())))
+        };
+        let mut list_splits_service = layer
+            .stream_timeout_layer()
+            .layer(tower::service_fn(serve_stream));
+
+        let request = ListSplitsRequest::new(Duration::ZERO, 0);
+        let response_stream = list_splits_service
+            .ready()
+            .await
+            .unwrap()
+            .call(request)
+            .await
+            .unwrap();
+        let items: Vec<Result<usize, TestError>> = response_stream.collect().await;
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0], Ok(0));
+        assert_eq!(items[1], Ok(1));
+        assert_eq!(items[2], Err(TestError::Timeout));
+        assert_eq!(
+            metrics
+                .timeouts_total
+                .with_label_values(["list_splits"])
+                .get(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rpc_policy_table() {
+        let policy = RpcPolicy {
+            timeout_opt: Some(Duration::from_secs(1)),
+            retry_params: RetryParams::standard(),
+        };
+        let table = RpcPolicyTable::default().with_policy("list_splits", policy);
+        assert_eq!(*table.get("list_splits"), policy);
+        assert_eq!(*table.get("index_metadata"), RpcPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_policy_layer() {
+        let layer = test_layer("rpc_policy_test");
+        let metrics = layer.metrics.clone();
+
+        let mut list_splits_service = layer.layer(tower::service_fn(serve::<true>));
+        let mut index_metadata_service = layer.layer(tower::service_fn(serve::<false>));
+
+        // The `list_splits` requests time out after 50ms and are retried once.
+        let request = ListSplitsRequest::new(Duration::from_millis(200), 0);
+        let error = list_splits_service
+            .ready()
+            .await
+            .unwrap()
+            .call(request.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error, TestError::Timeout);
+        assert_eq!(request.num_attempts(), 2);
+        assert_eq!(
+            metrics
+                .timeouts_total
+                .with_label_values(["list_splits"])
+                .get(),
+            2
+        );
+        assert_eq!(
+            metrics
+                .retries_total
+                .with_label_values(["list_splits"])
+                .get(),
+            1
+        );
+
+        let request = ListSplitsRequest::new(Duration::from_millis(1), 1);
+        list_splits_service
+            .ready()
+            .await
+            .unwrap()
+            .call(request.clone())
+            .await
+            .unwrap();
+        assert_eq!(request.num_attempts(), 2);
+
+        // The `index_metadata` requests are neither timed out nor retried.
+        let request = IndexMetadataRequest::new(Duration::from_millis(200), 0);
+        index_metadata_service
+            .ready()
+            .await
+            .unwrap()
+            .call(request.clone())
+            .await
+            .unwrap();
+        assert_eq!(request.num_attempts(), 1);
+
+        let request = IndexMetadataRequest::new(Duration::from_millis(1), 1);
+        let error = index_metadata_service
+            .ready()
+            .await
+            .unwrap()
+            .call(request.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error, TestError::Unavailable);
+        assert_eq!(request.num_attempts(), 1);
+
+        assert_eq!(
+            metrics
+                .timeouts_total
+                .with_label_values(["index_metadata"])
+                .get(),
+            0
+        );
+        assert_eq!(
+            metrics
+                .retries_total
+                .with_label_values(["index_metadata"])
+                .get(),
+            0
+        );
+    }
+}
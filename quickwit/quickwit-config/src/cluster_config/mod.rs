@@ -17,6 +17,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::num::NonZeroU32;
+
 use bytesize::ByteSize;
 use quickwit_common::uri::Uri;
 
@@ -29,6 +31,7 @@ pub struct ClusterConfig {
     pub default_index_root_uri: Uri,
     pub replication_factor: usize,
     pub shard_throughput_limit: ByteSize,
+    pub max_shards_per_pipeline: NonZeroU32,
 }
 
 impl ClusterConfig {
@@ -40,6 +43,7 @@ impl ClusterConfig {
             default_index_root_uri: Uri::for_test("ram:///indexes"),
             replication_factor: 1,
             shard_throughput_limit: quickwit_common::shared_consts::DEFAULT_SHARD_THROUGHPUT_LIMIT,
+            max_shards_per_pipeline: crate::IndexerConfig::default_max_shards_per_pipeline(),
         }
     }
 }
@@ -28,11 +28,17 @@ use anyhow::{ensure, Context};
 use bytesize::ByteSize;
 use chrono::Utc;
 use cron::Schedule;
-use humantime::parse_duration;
 use quickwit_common::uri::Uri;
-use quickwit_doc_mapper::{DefaultDocMapperBuilder, DocMapper, DocMapping};
+use quickwit_common::{human_duration, HumanDuration};
+use quickwit_doc_mapper::{
+    classify_doc_mapping_update, DefaultDocMapperBuilder, DocMapper, DocMapping,
+    DocMappingUpdateVerdict,
+};
 use quickwit_proto::types::IndexId;
-use serde::{Deserialize, Serialize};
+use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
+use quickwit_query::ElasticQueryDsl;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 pub use serialize::{load_index_config_from_user_config, load_index_config_update};
 use siphasher::sip::SipHasher;
 use tracing::warn;
@@ -102,7 +108,11 @@ impl Default for IndexingResources {
 #[serde(deny_unknown_fields)]
 pub struct IndexingSettings {
     #[schema(default = 60)]
-    #[serde(default = "IndexingSettings::default_commit_timeout_secs")]
+    /// Accepts a number of seconds or a human-readable duration (`60s`, `1m30s`).
+    #[serde(
+        default = "IndexingSettings::default_commit_timeout_secs",
+        deserialize_with = "deserialize_commit_timeout_secs"
+    )]
     pub commit_timeout_secs: usize,
     #[schema(default = 8)]
     #[serde(default = "IndexingSettings::default_docstore_compression_level")]
@@ -121,6 +131,10 @@ pub struct IndexingSettings {
     pub merge_policy: MergePolicyConfig,
     #[serde(default)]
     pub resources: IndexingResources,
+    /// When set, the commit timeout of the indexing pipelines is adjusted based on their observed
+    /// throughput to produce splits of a target size.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adaptive_commit: Option<AdaptiveCommitSettings>,
 }
 
 impl IndexingSettings {
@@ -162,22 +176,172 @@ impl Default for IndexingSettings {
             split_num_docs_target: Self::default_split_num_docs_target(),
             merge_policy: MergePolicyConfig::default(),
             resources: IndexingResources::default(),
+            adaptive_commit: None,
         }
     }
 }
 
+fn deserialize_commit_timeout_secs<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where D: Deserializer<'de> {
+    let commit_timeout_secs = human_duration::deserialize_secs(deserializer)?;
+    usize::try_from(commit_timeout_secs).map_err(D::Error::custom)
+}
+
+/// Adjusts the effective commit timeout of the indexing pipelines so that low-throughput sources
+/// do not produce tiny splits and high-throughput sources do not accumulate huge workbenches.
+///
+/// The effective commit timeout is derived from the observed throughput of the pipeline and the
+/// target split size, and is kept within `[min_commit_timeout_secs, max_commit_timeout_secs]`.
+/// If both a number of documents and a size are targeted, the shortest timeout wins.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveCommitSettings {
+    /// Number of documents targeted per split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_split_num_docs: Option<usize>,
+    /// Uncompressed size of the documents targeted per split.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_split_size: Option<ByteSize>,
+    #[schema(default = 10)]
+    #[serde(
+        default = "AdaptiveCommitSettings::default_min_commit_timeout_secs",
+        deserialize_with = "deserialize_commit_timeout_secs"
+    )]
+    pub min_commit_timeout_secs: usize,
+    #[schema(default = 1800)]
+    #[serde(
+        default = "AdaptiveCommitSettings::default_max_commit_timeout_secs",
+        deserialize_with = "deserialize_commit_timeout_secs"
+    )]
+    pub max_commit_timeout_secs: usize,
+}
+
+impl AdaptiveCommitSettings {
+    fn default_min_commit_timeout_secs() -> usize {
+        10
+    }
+
+    fn default_max_commit_timeout_secs() -> usize {
+        1800
+    }
+
+    pub fn min_commit_timeout(&self) -> Duration {
+        Duration::from_secs(self.min_commit_timeout_secs as u64)
+    }
+
+    pub fn max_commit_timeout(&self) -> Duration {
+        Duration::from_secs(self.max_commit_timeout_secs as u64)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.target_split_num_docs.is_some() || self.target_split_size.is_some(),
+            "adaptive commit requires `target_split_num_docs` or `target_split_size` to be set"
+        );
+        ensure!(
+            self.target_split_num_docs != Some(0),
+            "`target_split_num_docs` must be strictly positive"
+        );
+        ensure!(
+            self.target_split_size.map(|size| size.as_u64()) != Some(0),
+            "`target_split_size` must be strictly positive"
+        );
+        ensure!(
+            self.min_commit_timeout_secs > 0,
+            "`min_commit_timeout_secs` must be strictly positive"
+        );
+        ensure!(
+            self.min_commit_timeout_secs <= self.max_commit_timeout_secs,
+            "`min_commit_timeout_secs` ({}) must be lower than or equal to \
+             `max_commit_timeout_secs` ({})",
+            self.min_commit_timeout_secs,
+            self.max_commit_timeout_secs
+        );
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SearchSettings {
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    /// Whether the Elasticsearch-compatible API rewrites the `@timestamp` field, when the doc
+    /// mapping does not define it, to the timestamp field of the index. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub es_timestamp_alias: Option<bool>,
+    /// Maximum value of `start_offset + max_hits` accepted for search requests, including the ones
+    /// paginated with `search_after`. Defaults to 10,000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_result_window: Option<u64>,
+    /// Filter combined with every query targeting the index, regardless of the query sent by the
+    /// client. It is typically used to restrict the searchable documents to a single tenant.
+    #[schema(value_type = Option<Object>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_filter: Option<SearchFilter>,
+}
+
+impl SearchSettings {
+    pub const DEFAULT_MAX_RESULT_WINDOW: u64 = 10_000;
+
+    pub fn es_timestamp_alias_enabled(&self) -> bool {
+        self.es_timestamp_alias.unwrap_or(true)
+    }
+
+    pub fn max_result_window(&self) -> u64 {
+        self.max_result_window
+            .unwrap_or(Self::DEFAULT_MAX_RESULT_WINDOW)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_result_window != Some(0),
+            "search_settings.max_result_window must be strictly positive"
+        );
+        if let Some(SearchFilter::QueryString(query_string)) = &self.search_filter {
+            ensure!(
+                !query_string.trim().is_empty(),
+                "search_settings.search_filter must not be empty"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Query stored in the search settings of an index and applied as a filter to every query
+/// targeting it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SearchFilter {
+    /// Query expressed in the Quickwit query language, e.g. `tenant_id:acme`.
+    QueryString(String),
+    /// Query expressed in the Elasticsearch query DSL, e.g. `{"term": {"tenant_id": "acme"}}`.
+    ElasticQueryDsl(serde_json::Value),
+}
+
+impl SearchFilter {
+    /// Converts the filter into a query AST and resolves its user text queries against the doc
+    /// mapper.
+    pub fn resolve(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<QueryAst> {
+        let query_ast = match self {
+            Self::QueryString(query_string) => query_ast_from_user_text(query_string, None),
+            Self::ElasticQueryDsl(es_query_dsl_json) => {
+                let es_query_dsl: ElasticQueryDsl =
+                    serde_json::from_value(es_query_dsl_json.clone())
+                        .context("failed to parse Elasticsearch query DSL")?;
+                QueryAst::try_from(es_query_dsl)?
+            }
+        };
+        doc_mapper.parse_user_query(query_ast, &[])
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct RetentionPolicy {
     /// Duration of time for which the splits should be retained, expressed in a human-friendly way
-    /// (`1 hour`, `3 days`, `1 week`, ...).
+    /// (`1h`, `3d`, `1 week`, ...).
     #[serde(rename = "period")]
     pub retention_period: String,
 
@@ -194,8 +358,13 @@ impl RetentionPolicy {
         "hourly".to_string()
     }
 
+    /// Parses the retention period as a [`HumanDuration`], falling back to the longer units
+    /// historically accepted (`3 days`, `1 week`, `2 months`, ...).
     pub fn retention_period(&self) -> anyhow::Result<Duration> {
-        parse_duration(&self.retention_period).with_context(|| {
+        if let Ok(human_duration) = HumanDuration::from_str(&self.retention_period) {
+            return Ok(human_duration.as_duration());
+        }
+        humantime::parse_duration(&self.retention_period).with_context(|| {
             format!(
                 "failed to parse retention period `{}`",
                 self.retention_period
@@ -226,6 +395,17 @@ impl RetentionPolicy {
         Ok(duration)
     }
 
+    /// Returns the Unix timestamp (in seconds) of the next scheduled evaluation of the retention
+    /// policy.
+    pub fn next_evaluation_timestamp(&self) -> anyhow::Result<i64> {
+        let schedule = self.evaluation_schedule()?;
+        let next_evaluation_date = schedule
+            .upcoming(Utc)
+            .next()
+            .context("retention evaluation schedule has no upcoming date")?;
+        Ok(next_evaluation_date.timestamp())
+    }
+
     pub(super) fn validate(&self) -> anyhow::Result<()> {
         self.retention_period()?;
         self.evaluation_schedule()?;
@@ -269,6 +449,22 @@ impl IndexConfig {
         hasher.finish()
     }
 
+    /// Classifies the update of the parameters relevant for indexers from `self` to
+    /// `new_index_config`. The update is compatible, i.e. running indexing pipelines can apply it
+    /// without being restarted, if the indexing settings are unchanged and the doc mapping update
+    /// is compatible.
+    pub fn classify_indexing_params_update(
+        &self,
+        new_index_config: &IndexConfig,
+    ) -> DocMappingUpdateVerdict {
+        if self.indexing_settings != new_index_config.indexing_settings {
+            return DocMappingUpdateVerdict::Breaking {
+                reason: "`indexing_settings` changed".to_string(),
+            };
+        }
+        classify_doc_mapping_update(&self.doc_mapping, &new_index_config.doc_mapping)
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub fn for_test(index_id: &str, index_uri: &str) -> Self {
         let index_uri = Uri::from_str(index_uri).unwrap();
@@ -344,6 +540,7 @@ impl IndexConfig {
                 r#"attributes.server"#.to_string(),
                 r"attributes.server\.status".to_string(),
             ],
+            ..Default::default()
         };
         IndexConfig {
             index_id: index_id.to_string(),
@@ -419,7 +616,9 @@ impl crate::TestableForRegression for IndexConfig {
             ],
             timestamp_field: Some("timestamp".to_string()),
             tag_fields: BTreeSet::from_iter(["tenant_id".to_string(), "log_level".to_string()]),
+            term_presence_filters: Vec::new(),
             partition_key: Some("tenant_id".to_string()),
+            doc_id_field: None,
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             index_field_presence: true,
             store_document_size: false,
@@ -449,6 +648,7 @@ impl crate::TestableForRegression for IndexConfig {
         };
         let search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            ..Default::default()
         };
         IndexConfig {
             index_id: "my-index".to_string(),
@@ -492,11 +692,26 @@ pub(super) fn validate_index_config(
     // Note: this needs a deep refactoring to separate the doc mapping configuration,
     // and doc mapper implementations.
     // TODO see if we should store the byproducton the IndexConfig.
-    build_doc_mapper(doc_mapping, search_settings)?;
+    let doc_mapper = build_doc_mapper(doc_mapping, search_settings)?;
+
+    search_settings.validate()?;
+
+    if let Some(search_filter) = &search_settings.search_filter {
+        let search_filter_ast = search_filter
+            .resolve(&*doc_mapper)
+            .context("invalid search_settings.search_filter")?;
+        doc_mapper
+            .query(doc_mapper.schema(), &search_filter_ast, true)
+            .context("invalid search_settings.search_filter")?;
+    }
 
     indexing_settings.merge_policy.validate()?;
     indexing_settings.resources.validate()?;
 
+    if let Some(adaptive_commit) = &indexing_settings.adaptive_commit {
+        adaptive_commit.validate()?;
+    }
+
     if let Some(retention_policy) = retention_policy_opt {
         retention_policy.validate()?;
 
@@ -592,8 +807,113 @@ mod tests {
             index_config.search_settings,
             SearchSettings {
                 default_search_fields: vec!["severity_text".to_string(), "body".to_string()],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_search_settings_es_timestamp_alias() {
+        let search_settings: SearchSettings = serde_yaml::from_str("{}").unwrap();
+        assert!(search_settings.es_timestamp_alias_enabled());
+        assert_eq!(
+            serde_json::to_value(&search_settings).unwrap(),
+            serde_json::json!({"default_search_fields": []})
+        );
+        let search_settings: SearchSettings =
+            serde_yaml::from_str("es_timestamp_alias: false").unwrap();
+        assert!(!search_settings.es_timestamp_alias_enabled());
+    }
+
+    #[test]
+    fn test_search_settings_max_result_window() {
+        let search_settings: SearchSettings = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(search_settings.max_result_window(), 10_000);
+
+        let search_settings: SearchSettings =
+            serde_yaml::from_str("max_result_window: 50000").unwrap();
+        assert_eq!(search_settings.max_result_window(), 50_000);
+        search_settings.validate().unwrap();
+
+        let search_settings: SearchSettings = serde_yaml::from_str("max_result_window: 0").unwrap();
+        search_settings.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_indexing_settings_adaptive_commit() {
+        let indexing_settings_yaml = r#"
+            commit_timeout_secs: 30
+            adaptive_commit:
+              target_split_num_docs: 1000000
+              target_split_size: 500MB
+              max_commit_timeout_secs: 600
+        "#;
+        let indexing_settings: IndexingSettings =
+            serde_yaml::from_str(indexing_settings_yaml).unwrap();
+        let adaptive_commit = indexing_settings.adaptive_commit.unwrap();
+        assert_eq!(
+            adaptive_commit,
+            AdaptiveCommitSettings {
+                target_split_num_docs: Some(1_000_000),
+                target_split_size: Some(ByteSize::mb(500)),
+                min_commit_timeout_secs: 10,
+                max_commit_timeout_secs: 600,
             }
         );
+        adaptive_commit.validate().unwrap();
+
+        let no_target = AdaptiveCommitSettings {
+            target_split_num_docs: None,
+            target_split_size: None,
+            ..adaptive_commit.clone()
+        };
+        no_target.validate().unwrap_err();
+
+        let inverted_bounds = AdaptiveCommitSettings {
+            min_commit_timeout_secs: 601,
+            ..adaptive_commit
+        };
+        let error = inverted_bounds.validate().unwrap_err();
+        assert!(error.to_string().contains("min_commit_timeout_secs"));
+
+        assert!(IndexingSettings::default().adaptive_commit.is_none());
+    }
+
+    #[test]
+    fn test_indexing_settings_commit_timeout() {
+        // Historical format: a number of seconds.
+        let indexing_settings: IndexingSettings =
+            serde_yaml::from_str("commit_timeout_secs: 61").unwrap();
+        assert_eq!(indexing_settings.commit_timeout_secs, 61);
+
+        let indexing_settings_yaml = r#"
+            commit_timeout_secs: 1m30s
+            adaptive_commit:
+              target_split_num_docs: 1000000
+              min_commit_timeout_secs: 15s
+              max_commit_timeout_secs: 1h
+        "#;
+        let indexing_settings: IndexingSettings =
+            serde_yaml::from_str(indexing_settings_yaml).unwrap();
+        assert_eq!(indexing_settings.commit_timeout(), Duration::from_secs(90));
+        let adaptive_commit = indexing_settings.adaptive_commit.as_ref().unwrap();
+        assert_eq!(adaptive_commit.min_commit_timeout_secs, 15);
+        assert_eq!(adaptive_commit.max_commit_timeout_secs, 3_600);
+
+        // The settings are still serialized as a number of seconds.
+        let indexing_settings_json = serde_json::to_value(&indexing_settings).unwrap();
+        assert_eq!(indexing_settings_json["commit_timeout_secs"], 90);
+        assert_eq!(
+            indexing_settings_json["adaptive_commit"]["max_commit_timeout_secs"],
+            3_600
+        );
+
+        for invalid_commit_timeout in ["1500ms", "90 seconds", "-1"] {
+            serde_yaml::from_str::<IndexingSettings>(&format!(
+                "commit_timeout_secs: {invalid_commit_timeout}"
+            ))
+            .unwrap_err();
+        }
     }
 
     #[test]
@@ -634,6 +954,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -668,6 +989,7 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    ..Default::default()
                 }
             );
         }
@@ -776,6 +1098,24 @@ mod tests {
 
     #[test]
     fn test_parse_retention_policy_period() {
+        for (retention_period, expected_retention_period_secs) in [
+            ("90s", 90),
+            ("1h30m", 5_400),
+            ("3d", 259_200),
+            // Historical formats.
+            ("3 days", 259_200),
+            ("1 week", 604_800),
+            ("1h 30min", 5_400),
+        ] {
+            let retention_policy = RetentionPolicy {
+                retention_period: retention_period.to_string(),
+                evaluation_schedule: "hourly".to_string(),
+            };
+            assert_eq!(
+                retention_policy.retention_period().unwrap(),
+                Duration::from_secs(expected_retention_period_secs)
+            );
+        }
         {
             let retention_policy = RetentionPolicy {
                 retention_period: "1 hour".to_string(),
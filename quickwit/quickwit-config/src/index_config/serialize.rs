@@ -262,6 +262,7 @@ impl From<IndexConfig> for IndexConfigV0_8 {
 mod test {
     use super::*;
     use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
+    use crate::SearchFilter;
 
     fn minimal_index_config_for_serialization() -> IndexConfigForSerialization {
         serde_yaml::from_str(
@@ -503,6 +504,69 @@ mod test {
         assert_eq!(updated_config.doc_mapping.field_mappings.len(), 1);
     }
 
+    #[test]
+    fn test_update_search_filter() {
+        let original_config_yaml = r#"
+            version: 0.8
+            index_id: hdfs-logs
+            doc_mapping:
+                mode: strict
+                field_mappings:
+                    - name: tenant_id
+                      type: text
+                      tokenizer: raw
+        "#;
+        let original_config: IndexConfig = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            original_config_yaml.as_bytes(),
+            &Uri::for_test("s3://mybucket"),
+        )
+        .unwrap();
+        let update_with_search_filter = |search_filter_yaml: &str| {
+            let updated_config_yaml = format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                    mode: strict
+                    field_mappings:
+                        - name: tenant_id
+                          type: text
+                          tokenizer: raw
+                search_settings:
+                    search_filter: {search_filter_yaml}
+                "#
+            );
+            load_index_config_update(
+                ConfigFormat::Yaml,
+                updated_config_yaml.as_bytes(),
+                &original_config,
+            )
+        };
+        let updated_config = update_with_search_filter("tenant_id:acme").unwrap();
+        assert_eq!(
+            updated_config.search_settings.search_filter,
+            Some(SearchFilter::QueryString("tenant_id:acme".to_string()))
+        );
+        let updated_config =
+            update_with_search_filter(r#"{"term": {"tenant_id": "acme"}}"#).unwrap();
+        assert!(matches!(
+            updated_config.search_settings.search_filter,
+            Some(SearchFilter::ElasticQueryDsl(_))
+        ));
+        // Syntax error.
+        let error = update_with_search_filter("'tenant_id:(acme'").unwrap_err();
+        assert!(format!("{error:?}").contains("invalid search_settings.search_filter"));
+        // Unknown field.
+        let error = update_with_search_filter("unknown_field:acme").unwrap_err();
+        assert!(format!("{error:?}").contains("invalid search_settings.search_filter"));
+        // Unsupported Elasticsearch query.
+        let error = update_with_search_filter(r#"{"unknown_query": {}}"#).unwrap_err();
+        assert!(format!("{error:?}").contains("invalid search_settings.search_filter"));
+        // Empty filter.
+        update_with_search_filter("''").unwrap_err();
+    }
+
     #[test]
     fn test_update_doc_mappings_failing_cases() {
         let original_config_yaml = r#"
@@ -48,20 +48,22 @@ pub use cluster_config::ClusterConfig;
 // See #2048
 use index_config::serialize::{IndexConfigV0_8, VersionedIndexConfig};
 pub use index_config::{
-    build_doc_mapper, load_index_config_from_user_config, load_index_config_update, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    build_doc_mapper, load_index_config_from_user_config, load_index_config_update,
+    AdaptiveCommitSettings, IndexConfig, IndexingResources, IndexingSettings, RetentionPolicy,
+    SearchFilter, SearchSettings,
 };
-pub use quickwit_doc_mapper::DocMapping;
+pub use quickwit_doc_mapper::{DocMapping, DocMappingUpdateVerdict};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 use source_config::FileSourceParamsForSerde;
 pub use source_config::{
     load_source_config_from_user_config, FileSourceMessageType, FileSourceNotification,
-    FileSourceParams, FileSourceSqs, KafkaSourceParams, KinesisSourceParams, PubSubSourceParams,
+    FileSourceParams, FileSourcePolling, FileSourcePollingOrdering, FileSourceSqs,
+    KafkaSourceParams, KinesisSourceParams, PlacementSelector, PubSubSourceParams,
     PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint, SourceConfig, SourceInputFormat,
-    SourceParams, TransformConfig, VecSourceParams, VoidSourceParams, CLI_SOURCE_ID,
-    INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    SourceParams, SourcePriority, TransformConfig, VecSourceParams, VoidSourceParams,
+    CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
 };
 use tracing::warn;
 
@@ -74,8 +76,10 @@ pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
 };
 pub use crate::node_config::{
-    IndexerConfig, IngestApiConfig, JaegerConfig, NodeConfig, SearcherConfig, SplitCacheLimits,
-    DEFAULT_QW_CONFIG_PATH,
+    AggregationSpillConfig, IndexerConfig, IngestApiConfig, JaegerConfig, LeafSearchHedgingConfig,
+    LeafSearchResponseCompression, LeafSearchTiersConfig, LoadSheddingConfig,
+    MetastoreRpcPolicyConfig, NodeConfig, SearcherConfig, SplitCacheLimits, SuggestConfig,
+    TenantSearchQuotaConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use crate::source_config::serialize::{SourceConfigV0_7, SourceConfigV0_8, VersionedSourceConfig};
 pub use crate::storage_config::{
@@ -101,6 +105,7 @@ pub fn disable_ingest_v1() -> bool {
 #[openapi(components(schemas(
     IndexingResources,
     IndexingSettings,
+    AdaptiveCommitSettings,
     SearchSettings,
     RetentionPolicy,
     MergePolicyConfig,
@@ -113,10 +118,14 @@ pub fn disable_ingest_v1() -> bool {
     VersionedIndexTemplate,
     IndexTemplateV0_8,
     SourceInputFormat,
+    SourcePriority,
+    PlacementSelector,
     SourceParams,
     FileSourceMessageType,
     FileSourceNotification,
     FileSourceParamsForSerde,
+    FileSourcePolling,
+    FileSourcePollingOrdering,
     FileSourceSqs,
     PubSubSourceParams,
     KafkaSourceParams,
@@ -19,7 +19,7 @@
 
 mod serialize;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::net::SocketAddr;
 use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
@@ -29,18 +29,22 @@ use std::time::Duration;
 use anyhow::{bail, ensure};
 use bytesize::ByteSize;
 use http::HeaderMap;
+use quickwit_common::human_duration;
 use quickwit_common::net::HostAddr;
+use quickwit_common::retry::RetryParams;
 use quickwit_common::shared_consts::DEFAULT_SHARD_THROUGHPUT_LIMIT;
+use quickwit_common::tower::{RpcPolicy, RpcPolicyTable};
 use quickwit_common::uri::Uri;
 use quickwit_proto::indexing::CpuCapacity;
 use quickwit_proto::types::NodeId;
-use serde::{Deserialize, Serialize};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
 use tracing::{info, warn};
 
 use crate::node_config::serialize::load_node_config_with_env;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
-use crate::{ConfigFormat, MetastoreConfigs};
+use crate::{validate_identifier, validate_index_id_pattern, ConfigFormat, MetastoreConfigs};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 
@@ -92,6 +96,11 @@ pub struct IndexerConfig {
     pub split_store_max_num_splits: usize,
     #[serde(default = "IndexerConfig::default_max_concurrent_split_uploads")]
     pub max_concurrent_split_uploads: usize,
+    /// Maximum number of bytes of splits waiting to be uploaded or being uploaded, per indexing
+    /// pipeline. Once this limit is reached, the pipeline stops building new splits until some
+    /// uploads complete.
+    #[serde(default = "IndexerConfig::default_split_upload_queue_max_num_bytes")]
+    pub split_upload_queue_max_num_bytes: ByteSize,
     /// Limits the IO throughput of the `SplitDownloader` and the `MergeExecutor`.
     /// On hardware where IO is constrained, it makes sure that Merges (a batch operation)
     /// does not starve indexing itself (as it is a latency sensitive operation).
@@ -109,6 +118,55 @@ pub struct IndexerConfig {
     pub enable_cooperative_indexing: bool,
     #[serde(default = "IndexerConfig::default_cpu_capacity")]
     pub cpu_capacity: CpuCapacity,
+    /// Maximum number of indexing plans accepted per minute from a given control plane node, once
+    /// the burst is exhausted. Plans received above this rate are rejected.
+    #[serde(default = "IndexerConfig::default_max_apply_plan_requests_per_minute")]
+    pub max_apply_plan_requests_per_minute: NonZeroU32,
+    /// Maximum number of indexing plans accepted in a burst from a given control plane node.
+    #[serde(default = "IndexerConfig::default_apply_plan_burst_limit")]
+    pub apply_plan_burst_limit: NonZeroU32,
+    /// Time after which the position of a shard that reached EOF is forgotten, provided the
+    /// shard is closed or deleted in the metastore.
+    #[serde(default = "IndexerConfig::default_shard_positions_eof_retention_secs")]
+    shard_positions_eof_retention_secs: NonZeroU64,
+    /// Interval at which the positions of the shards indexed by the node are flushed into the
+    /// cluster state.
+    #[serde(default = "IndexerConfig::default_shard_positions_flush_interval_millis")]
+    shard_positions_flush_interval_millis: NonZeroU64,
+    /// Maximum number of shard positions written into the cluster state at once.
+    #[serde(default = "IndexerConfig::default_shard_positions_flush_batch_size")]
+    pub shard_positions_flush_batch_size: NonZeroUsize,
+    /// Base delay of the exponential backoff applied between the restarts of a failing indexing
+    /// pipeline.
+    #[serde(default = "IndexerConfig::default_pipeline_restart_base_delay_secs")]
+    pipeline_restart_base_delay_secs: NonZeroU64,
+    /// Maximum delay between two restarts of a failing indexing pipeline.
+    #[serde(default = "IndexerConfig::default_pipeline_restart_max_delay_secs")]
+    pipeline_restart_max_delay_secs: NonZeroU64,
+    /// Number of failures within 30 minutes after which an indexing pipeline is no longer
+    /// restarted until a new indexing plan is applied or the pipeline is explicitly resumed.
+    #[serde(default = "IndexerConfig::default_max_pipeline_failures")]
+    pub max_pipeline_failures: NonZeroUsize,
+    /// Maximum number of shards assigned to a single indexing pipeline. The control plane spreads
+    /// the shards of a source over more pipelines to stay below this limit. This is a cluster-wide
+    /// setting: only the value configured on the control plane node is taken into account.
+    #[serde(default = "IndexerConfig::default_max_shards_per_pipeline")]
+    pub max_shards_per_pipeline: NonZeroU32,
+    /// Enables the sampling of the documents rejected by the indexing pipelines, which can be
+    /// inspected via the REST API.
+    #[serde(default = "IndexerConfig::default_enable_doc_processing_error_samples")]
+    pub enable_doc_processing_error_samples: bool,
+    /// Number of rejected documents kept per indexing pipeline.
+    #[serde(default = "IndexerConfig::default_max_doc_processing_error_samples")]
+    pub max_doc_processing_error_samples: NonZeroUsize,
+    /// Maximum number of bytes of a rejected document kept in a sample. Longer documents are
+    /// truncated.
+    #[serde(default = "IndexerConfig::default_doc_processing_error_sample_max_num_bytes")]
+    pub doc_processing_error_sample_max_num_bytes: ByteSize,
+    /// Labels advertised to the control plane, against which the placement selectors of the
+    /// sources are matched.
+    #[serde(default)]
+    pub indexing_affinity_labels: HashMap<String, String>,
 }
 
 impl IndexerConfig {
@@ -131,6 +189,10 @@ impl IndexerConfig {
         12
     }
 
+    pub fn default_split_upload_queue_max_num_bytes() -> ByteSize {
+        ByteSize::gib(4)
+    }
+
     pub fn default_split_store_max_num_bytes() -> ByteSize {
         ByteSize::gib(100)
     }
@@ -144,6 +206,71 @@ impl IndexerConfig {
             .unwrap_or(NonZeroUsize::new(1).unwrap())
     }
 
+    pub fn default_max_apply_plan_requests_per_minute() -> NonZeroU32 {
+        NonZeroU32::new(60).unwrap()
+    }
+
+    pub fn default_apply_plan_burst_limit() -> NonZeroU32 {
+        NonZeroU32::new(10).unwrap()
+    }
+
+    fn default_shard_positions_eof_retention_secs() -> NonZeroU64 {
+        NonZeroU64::new(3_600).unwrap()
+    }
+
+    pub fn shard_positions_eof_retention(&self) -> Duration {
+        Duration::from_secs(self.shard_positions_eof_retention_secs.get())
+    }
+
+    fn default_shard_positions_flush_interval_millis() -> NonZeroU64 {
+        NonZeroU64::new(500).unwrap()
+    }
+
+    pub fn shard_positions_flush_interval(&self) -> Duration {
+        Duration::from_millis(self.shard_positions_flush_interval_millis.get())
+    }
+
+    fn default_shard_positions_flush_batch_size() -> NonZeroUsize {
+        NonZeroUsize::new(1_000).unwrap()
+    }
+
+    fn default_pipeline_restart_base_delay_secs() -> NonZeroU64 {
+        NonZeroU64::new(1).unwrap()
+    }
+
+    fn default_pipeline_restart_max_delay_secs() -> NonZeroU64 {
+        NonZeroU64::new(600).unwrap()
+    }
+
+    fn default_max_pipeline_failures() -> NonZeroUsize {
+        NonZeroUsize::new(5).unwrap()
+    }
+
+    pub fn default_max_shards_per_pipeline() -> NonZeroU32 {
+        NonZeroU32::new(1_000).unwrap()
+    }
+
+    fn default_enable_doc_processing_error_samples() -> bool {
+        true
+    }
+
+    fn default_max_doc_processing_error_samples() -> NonZeroUsize {
+        NonZeroUsize::new(20).unwrap()
+    }
+
+    fn default_doc_processing_error_sample_max_num_bytes() -> ByteSize {
+        ByteSize::kib(1)
+    }
+
+    /// Returns the backoff policy applied between the restarts of a failing indexing pipeline.
+    pub fn pipeline_restart_params(&self) -> RetryParams {
+        RetryParams {
+            base_delay: Duration::from_secs(self.pipeline_restart_base_delay_secs.get()),
+            max_delay: Duration::from_secs(self.pipeline_restart_max_delay_secs.get()),
+            max_attempts: self.max_pipeline_failures.get(),
+        }
+    }
+
     fn default_cpu_capacity() -> CpuCapacity {
         CpuCapacity::one_cpu_thread() * (quickwit_common::num_cpus() as u32)
     }
@@ -157,9 +284,26 @@ impl IndexerConfig {
             split_store_max_num_bytes: ByteSize::mb(1),
             split_store_max_num_splits: 3,
             max_concurrent_split_uploads: 4,
+            split_upload_queue_max_num_bytes: Self::default_split_upload_queue_max_num_bytes(),
             cpu_capacity: PIPELINE_FULL_CAPACITY * 4u32,
             max_merge_write_throughput: None,
             merge_concurrency: NonZeroUsize::new(3).unwrap(),
+            max_apply_plan_requests_per_minute: Self::default_max_apply_plan_requests_per_minute(),
+            apply_plan_burst_limit: Self::default_apply_plan_burst_limit(),
+            shard_positions_eof_retention_secs: Self::default_shard_positions_eof_retention_secs(),
+            shard_positions_flush_interval_millis:
+                Self::default_shard_positions_flush_interval_millis(),
+            shard_positions_flush_batch_size: Self::default_shard_positions_flush_batch_size(),
+            pipeline_restart_base_delay_secs: Self::default_pipeline_restart_base_delay_secs(),
+            pipeline_restart_max_delay_secs: Self::default_pipeline_restart_max_delay_secs(),
+            max_pipeline_failures: Self::default_max_pipeline_failures(),
+            max_shards_per_pipeline: Self::default_max_shards_per_pipeline(),
+            enable_doc_processing_error_samples: Self::default_enable_doc_processing_error_samples(
+            ),
+            max_doc_processing_error_samples: Self::default_max_doc_processing_error_samples(),
+            doc_processing_error_sample_max_num_bytes:
+                Self::default_doc_processing_error_sample_max_num_bytes(),
+            indexing_affinity_labels: HashMap::new(),
         };
         Ok(indexer_config)
     }
@@ -173,9 +317,26 @@ impl Default for IndexerConfig {
             split_store_max_num_bytes: Self::default_split_store_max_num_bytes(),
             split_store_max_num_splits: Self::default_split_store_max_num_splits(),
             max_concurrent_split_uploads: Self::default_max_concurrent_split_uploads(),
+            split_upload_queue_max_num_bytes: Self::default_split_upload_queue_max_num_bytes(),
             cpu_capacity: Self::default_cpu_capacity(),
             merge_concurrency: Self::default_merge_concurrency(),
             max_merge_write_throughput: None,
+            max_apply_plan_requests_per_minute: Self::default_max_apply_plan_requests_per_minute(),
+            apply_plan_burst_limit: Self::default_apply_plan_burst_limit(),
+            shard_positions_eof_retention_secs: Self::default_shard_positions_eof_retention_secs(),
+            shard_positions_flush_interval_millis:
+                Self::default_shard_positions_flush_interval_millis(),
+            shard_positions_flush_batch_size: Self::default_shard_positions_flush_batch_size(),
+            pipeline_restart_base_delay_secs: Self::default_pipeline_restart_base_delay_secs(),
+            pipeline_restart_max_delay_secs: Self::default_pipeline_restart_max_delay_secs(),
+            max_pipeline_failures: Self::default_max_pipeline_failures(),
+            max_shards_per_pipeline: Self::default_max_shards_per_pipeline(),
+            enable_doc_processing_error_samples: Self::default_enable_doc_processing_error_samples(
+            ),
+            max_doc_processing_error_samples: Self::default_max_doc_processing_error_samples(),
+            doc_processing_error_sample_max_num_bytes:
+                Self::default_doc_processing_error_sample_max_num_bytes(),
+            indexing_affinity_labels: HashMap::new(),
         }
     }
 }
@@ -215,14 +376,427 @@ pub struct SearcherConfig {
     pub split_footer_cache_capacity: ByteSize,
     pub partial_request_cache_capacity: ByteSize,
     pub max_num_concurrent_split_searches: usize,
+    /// Maximum share of the split search permits, in percent, that a single index may hold while
+    /// the split searches of other indexes are waiting for a permit.
+    pub max_split_searches_per_index_percent: u8,
     pub max_num_concurrent_split_streams: usize,
     // Strangely, if None, this will also have the effect of not forwarding
     // to searcher.
     // TODO document and fix if necessary.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub split_cache: Option<SplitCacheLimits>,
-    #[serde(default = "SearcherConfig::default_request_timeout_secs")]
+    /// Accepts a number of seconds or a human-readable duration (`30s`, `1m30s`).
+    #[serde(
+        default = "SearcherConfig::default_request_timeout_secs",
+        deserialize_with = "deserialize_request_timeout_secs"
+    )]
     request_timeout_secs: NonZeroU64,
+    /// Margin, in seconds, by which the time range of a search is widened when pruning splits,
+    /// so that a clock skew between the indexers and the searchers does not prune the splits of
+    /// recent documents. Accepts a number of seconds or a human-readable duration.
+    #[serde(deserialize_with = "human_duration::deserialize_secs")]
+    pub split_pruning_slack_secs: u64,
+    #[serde(default)]
+    pub leaf_search_response_compression: LeafSearchResponseCompression,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tenant_quotas: Vec<TenantSearchQuotaConfig>,
+    /// Maximum length of the query string of the search REST API requests.
+    pub max_query_length: ByteSize,
+    /// Maximum size of the body of the search REST API requests.
+    pub max_request_body_size: ByteSize,
+    /// Node-wide pool of memory the leaf search requests draw their memory budget from.
+    pub search_memory_pool_size: ByteSize,
+    /// Maximum amount of memory a single leaf search request may use for its aggregation and
+    /// top-hits results before it is aborted.
+    pub leaf_request_memory_budget: ByteSize,
+    /// When set, the root searcher sends a duplicate of the leaf search requests that are
+    /// slower than usual to another searcher and keeps the first response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_search_hedging: Option<LeafSearchHedgingConfig>,
+    /// When set, the searcher rejects new leaf search requests while it is overloaded so that
+    /// the root searcher can route them to other searchers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_shedding: Option<LoadSheddingConfig>,
+    /// When set, the search threads are shared between a tier of cheap leaf search requests and
+    /// a tier of expensive ones, so that expensive requests cannot starve the cheap ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_search_tiers: Option<LeafSearchTiersConfig>,
+    /// Settings of the field name and value suggestion API.
+    pub suggest: SuggestConfig,
+    /// Whether the root searcher marks for deletion in the metastore the splits whose file the
+    /// leaves found missing from the storage.
+    pub report_missing_splits: bool,
+    /// Settings of the spilling of the intermediate aggregation results of the leaf search
+    /// requests to disk, for the requests that allow it.
+    pub aggregation_spill: AggregationSpillConfig,
+    /// Timeout and retry policies of the metastore requests sent by the searcher, keyed by RPC
+    /// name (e.g. `list_splits`). The searcher neither times out nor retries the requests of the
+    /// other RPCs.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metastore_rpc_policies: BTreeMap<String, MetastoreRpcPolicyConfig>,
+    /// Number of documents a split search scans between two checks of the cancellation and the
+    /// deadline of its request.
+    pub collection_checkpoint_num_docs: NonZeroU32,
+    /// Whether the split searches let the search thread run the pending split searches at each
+    /// collection checkpoint.
+    pub yield_at_collection_checkpoints: bool,
+}
+
+fn deserialize_request_timeout_secs<'de, D>(deserializer: D) -> Result<NonZeroU64, D::Error>
+where D: Deserializer<'de> {
+    let request_timeout_secs = human_duration::deserialize_secs(deserializer)?;
+    NonZeroU64::new(request_timeout_secs)
+        .ok_or_else(|| D::Error::custom("`request_timeout_secs` must be strictly positive"))
+}
+
+/// Settings of the leaf search request hedging.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeafSearchHedgingConfig {
+    /// Percentile of the recent leaf search latencies of a searcher after which a hedged
+    /// request is sent to another searcher.
+    #[serde(default = "LeafSearchHedgingConfig::default_latency_percentile")]
+    pub latency_percentile: u8,
+    /// Lower bound of the delay after which a hedged request is sent.
+    #[serde(default = "LeafSearchHedgingConfig::default_min_delay_millis")]
+    pub min_delay_millis: u64,
+    /// Maximum share of the leaf search requests, in percent, that may be hedged.
+    #[serde(default = "LeafSearchHedgingConfig::default_max_hedged_requests_percent")]
+    pub max_hedged_requests_percent: u8,
+}
+
+impl LeafSearchHedgingConfig {
+    fn default_latency_percentile() -> u8 {
+        95
+    }
+
+    fn default_min_delay_millis() -> u64 {
+        50
+    }
+
+    fn default_max_hedged_requests_percent() -> u8 {
+        5
+    }
+
+    pub fn min_delay(&self) -> Duration {
+        Duration::from_millis(self.min_delay_millis)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            (1..=99).contains(&self.latency_percentile),
+            "leaf_search_hedging.latency_percentile must be between 1 and 99, got `{}`",
+            self.latency_percentile
+        );
+        ensure!(
+            (1..=100).contains(&self.max_hedged_requests_percent),
+            "leaf_search_hedging.max_hedged_requests_percent must be between 1 and 100, got `{}`",
+            self.max_hedged_requests_percent
+        );
+        Ok(())
+    }
+}
+
+impl Default for LeafSearchHedgingConfig {
+    fn default() -> Self {
+        Self {
+            latency_percentile: Self::default_latency_percentile(),
+            min_delay_millis: Self::default_min_delay_millis(),
+            max_hedged_requests_percent: Self::default_max_hedged_requests_percent(),
+        }
+    }
+}
+
+/// Settings of the leaf search load shedding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    /// Pressure score, in percent, from which new leaf search requests are rejected.
+    #[serde(default = "LoadSheddingConfig::default_pressure_threshold_percent")]
+    pub pressure_threshold_percent: u32,
+    /// Average split search permit wait time at which the wait time alone brings the pressure
+    /// score to 100%.
+    #[serde(default = "LoadSheddingConfig::default_max_permit_wait_millis")]
+    pub max_permit_wait_millis: u64,
+}
+
+impl LoadSheddingConfig {
+    fn default_pressure_threshold_percent() -> u32 {
+        100
+    }
+
+    fn default_max_permit_wait_millis() -> u64 {
+        1_000
+    }
+
+    pub fn max_permit_wait(&self) -> Duration {
+        Duration::from_millis(self.max_permit_wait_millis)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.pressure_threshold_percent > 0,
+            "load_shedding.pressure_threshold_percent must be strictly positive"
+        );
+        ensure!(
+            self.max_permit_wait_millis > 0,
+            "load_shedding.max_permit_wait_millis must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        Self {
+            pressure_threshold_percent: Self::default_pressure_threshold_percent(),
+            max_permit_wait_millis: Self::default_max_permit_wait_millis(),
+        }
+    }
+}
+
+/// Settings of the cheap and expensive leaf search tiers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeafSearchTiersConfig {
+    /// Share of the search threads, in percent, the expensive leaf search requests may use. The
+    /// remaining threads are reserved to the cheap requests.
+    #[serde(default = "LeafSearchTiersConfig::default_expensive_threads_percent")]
+    pub expensive_threads_percent: u8,
+    /// Maximum number of hits a cheap leaf search request may ask for.
+    #[serde(default = "LeafSearchTiersConfig::default_cheap_max_hits")]
+    pub cheap_max_hits: u64,
+    /// Maximum number of splits a cheap leaf search request may target.
+    #[serde(default = "LeafSearchTiersConfig::default_cheap_max_splits")]
+    pub cheap_max_splits: usize,
+}
+
+impl LeafSearchTiersConfig {
+    fn default_expensive_threads_percent() -> u8 {
+        70
+    }
+
+    fn default_cheap_max_hits() -> u64 {
+        100
+    }
+
+    fn default_cheap_max_splits() -> usize {
+        10
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            (1..=99).contains(&self.expensive_threads_percent),
+            "leaf_search_tiers.expensive_threads_percent must be between 1 and 99, got `{}`",
+            self.expensive_threads_percent
+        );
+        ensure!(
+            self.cheap_max_splits > 0,
+            "leaf_search_tiers.cheap_max_splits must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+impl Default for LeafSearchTiersConfig {
+    fn default() -> Self {
+        Self {
+            expensive_threads_percent: Self::default_expensive_threads_percent(),
+            cheap_max_hits: Self::default_cheap_max_hits(),
+            cheap_max_splits: Self::default_cheap_max_splits(),
+        }
+    }
+}
+
+/// Settings of the field name and value suggestion API.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuggestConfig {
+    /// Number of the most recent splits of the index whose fields and terms are suggested.
+    #[serde(default = "SuggestConfig::default_max_num_splits")]
+    pub max_num_splits: usize,
+    /// Maximum number of suggestion requests per second served by a searcher. Requests above the
+    /// limit are rejected with a `429 Too Many Requests` error.
+    #[serde(default = "SuggestConfig::default_max_requests_per_sec")]
+    pub max_requests_per_sec: u32,
+}
+
+impl SuggestConfig {
+    fn default_max_num_splits() -> usize {
+        3
+    }
+
+    fn default_max_requests_per_sec() -> u32 {
+        20
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.max_num_splits > 0,
+            "suggest.max_num_splits must be strictly positive"
+        );
+        ensure!(
+            self.max_requests_per_sec > 0,
+            "suggest.max_requests_per_sec must be strictly positive"
+        );
+        Ok(())
+    }
+}
+
+impl Default for SuggestConfig {
+    fn default() -> Self {
+        Self {
+            max_num_splits: Self::default_max_num_splits(),
+            max_requests_per_sec: Self::default_max_requests_per_sec(),
+        }
+    }
+}
+
+/// Settings of the spilling of the intermediate aggregation results of the leaf search requests
+/// that set `allow_disk_use`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AggregationSpillConfig {
+    /// Size of the intermediate aggregation results a leaf search request buffers in memory
+    /// before it spills them to disk.
+    #[serde(default = "AggregationSpillConfig::default_in_memory_threshold")]
+    pub in_memory_threshold: ByteSize,
+    /// Disk space the leaf search requests of the searcher may spill to, all together. Once it
+    /// is used up, the intermediate aggregation results are kept in memory.
+    #[serde(default = "AggregationSpillConfig::default_max_disk_usage")]
+    pub max_disk_usage: ByteSize,
+}
+
+impl AggregationSpillConfig {
+    fn default_in_memory_threshold() -> ByteSize {
+        ByteSize::mb(64)
+    }
+
+    fn default_max_disk_usage() -> ByteSize {
+        ByteSize::gb(10)
+    }
+}
+
+impl Default for AggregationSpillConfig {
+    fn default() -> Self {
+        Self {
+            in_memory_threshold: Self::default_in_memory_threshold(),
+            max_disk_usage: Self::default_max_disk_usage(),
+        }
+    }
+}
+
+/// Timeout and retry policy of the metastore requests of an RPC sent by the searcher.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetastoreRpcPolicyConfig {
+    /// Maximum duration of each attempt. The attempts are not timed out when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_millis: Option<u64>,
+    /// Maximum number of times a request is retried after a retryable error or a timeout.
+    #[serde(default)]
+    pub max_retries: usize,
+    /// Base delay of the exponential backoff between two attempts.
+    #[serde(default = "MetastoreRpcPolicyConfig::default_base_backoff_millis")]
+    pub base_backoff_millis: u64,
+    /// Maximum delay between two attempts.
+    #[serde(default = "MetastoreRpcPolicyConfig::default_max_backoff_millis")]
+    pub max_backoff_millis: u64,
+}
+
+impl MetastoreRpcPolicyConfig {
+    fn default_base_backoff_millis() -> u64 {
+        RetryParams::standard().base_delay.as_millis() as u64
+    }
+
+    fn default_max_backoff_millis() -> u64 {
+        RetryParams::standard().max_delay.as_millis() as u64
+    }
+
+    pub fn rpc_policy(&self) -> RpcPolicy {
+        RpcPolicy {
+            timeout_opt: self.timeout_millis.map(Duration::from_millis),
+            retry_params: RetryParams {
+                base_delay: Duration::from_millis(self.base_backoff_millis),
+                max_delay: Duration::from_millis(self.max_backoff_millis),
+                max_attempts: self.max_retries + 1,
+            },
+        }
+    }
+
+    fn validate(&self, rpc_name: &str) -> anyhow::Result<()> {
+        ensure!(
+            self.timeout_millis != Some(0),
+            "metastore_rpc_policies.{rpc_name}.timeout_millis must be strictly positive"
+        );
+        ensure!(
+            self.base_backoff_millis <= self.max_backoff_millis,
+            "metastore_rpc_policies.{rpc_name}.base_backoff_millis ({}) must be lower or equal to \
+             max_backoff_millis ({})",
+            self.base_backoff_millis,
+            self.max_backoff_millis
+        );
+        Ok(())
+    }
+}
+
+impl Default for MetastoreRpcPolicyConfig {
+    fn default() -> Self {
+        Self {
+            timeout_millis: None,
+            max_retries: 0,
+            base_backoff_millis: Self::default_base_backoff_millis(),
+            max_backoff_millis: Self::default_max_backoff_millis(),
+        }
+    }
+}
+
+/// Search quotas enforced by the root searcher for the searches targeting the indexes of a
+/// tenant. A search is attributed to every tenant owning at least one of the indexes it targets.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantSearchQuotaConfig {
+    pub tenant_id: String,
+    /// Glob-like patterns (e.g. `tenant-a-*`) matching the IDs of the indexes owned by the
+    /// tenant.
+    pub index_id_patterns: Vec<String>,
+    /// Maximum number of root searches running concurrently for the tenant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_searches: Option<NonZeroUsize>,
+    /// Maximum number of splits a single search of the tenant is allowed to target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_splits_per_search: Option<NonZeroUsize>,
+}
+
+impl TenantSearchQuotaConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        validate_identifier("tenant", &self.tenant_id)?;
+        ensure!(
+            !self.index_id_patterns.is_empty(),
+            "tenant `{}` must define at least one index ID pattern",
+            self.tenant_id
+        );
+        for index_id_pattern in &self.index_id_patterns {
+            validate_index_id_pattern(index_id_pattern, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Controls the compression of the leaf search responses exchanged over gRPC. Compression is
+/// negotiated: a root only asks for compressed responses when its own setting allows it, and a
+/// leaf only compresses its responses when both its setting and the root allow it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafSearchResponseCompression {
+    /// Leaf search responses are never compressed.
+    #[default]
+    Off,
+    /// Leaf search responses are compressed only when they are expected to be large, i.e. when
+    /// the request carries aggregations or asks for many hits.
+    Auto,
+    /// Leaf search responses are always compressed.
+    Forced,
 }
 
 impl Default for SearcherConfig {
@@ -233,10 +807,27 @@ impl Default for SearcherConfig {
             partial_request_cache_capacity: ByteSize::mb(64),
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
+            max_split_searches_per_index_percent: 80,
             aggregation_memory_limit: ByteSize::mb(500),
             aggregation_bucket_limit: 65000,
             split_cache: None,
             request_timeout_secs: Self::default_request_timeout_secs(),
+            split_pruning_slack_secs: 60,
+            leaf_search_response_compression: LeafSearchResponseCompression::default(),
+            tenant_quotas: Vec::new(),
+            max_query_length: ByteSize::kib(64),
+            max_request_body_size: ByteSize::mib(1),
+            search_memory_pool_size: ByteSize::gb(2),
+            leaf_request_memory_budget: ByteSize::mb(500),
+            leaf_search_hedging: None,
+            load_shedding: None,
+            leaf_search_tiers: None,
+            suggest: SuggestConfig::default(),
+            report_missing_splits: false,
+            aggregation_spill: AggregationSpillConfig::default(),
+            metastore_rpc_policies: BTreeMap::new(),
+            collection_checkpoint_num_docs: NonZeroU32::new(8_192).unwrap(),
+            yield_at_collection_checkpoints: false,
         }
     }
 }
@@ -249,7 +840,18 @@ impl SearcherConfig {
     fn default_request_timeout_secs() -> NonZeroU64 {
         NonZeroU64::new(30).unwrap()
     }
-    fn validate(&self) -> anyhow::Result<()> {
+
+    /// Returns the timeout and retry policies of the metastore requests sent by the searcher.
+    pub fn metastore_rpc_policy_table(&self) -> RpcPolicyTable {
+        self.metastore_rpc_policies.iter().fold(
+            RpcPolicyTable::default(),
+            |table, (rpc_name, policy_config)| {
+                table.with_policy(rpc_name.clone(), policy_config.rpc_policy())
+            },
+        )
+    }
+    /// Checks the consistency of the searcher limits.
+    pub fn validate(&self) -> anyhow::Result<()> {
         if let Some(split_cache_limits) = self.split_cache {
             if self.max_num_concurrent_split_searches
                 > split_cache_limits.max_file_descriptors.get() as usize
@@ -272,6 +874,54 @@ impl SearcherConfig {
                 );
             }
         }
+        ensure!(
+            (1..=100).contains(&self.max_split_searches_per_index_percent),
+            "max_split_searches_per_index_percent must be between 1 and 100, got `{}`",
+            self.max_split_searches_per_index_percent
+        );
+        ensure!(
+            self.max_query_length.as_u64() > 0,
+            "max_query_length must be strictly positive"
+        );
+        ensure!(
+            self.max_query_length <= self.max_request_body_size,
+            "max_query_length ({}) must be lower or equal to max_request_body_size ({})",
+            self.max_query_length,
+            self.max_request_body_size
+        );
+        ensure!(
+            self.leaf_request_memory_budget.as_u64() > 0,
+            "leaf_request_memory_budget must be strictly positive"
+        );
+        ensure!(
+            self.leaf_request_memory_budget <= self.search_memory_pool_size,
+            "leaf_request_memory_budget ({}) must be lower or equal to search_memory_pool_size \
+             ({})",
+            self.leaf_request_memory_budget,
+            self.search_memory_pool_size
+        );
+        if let Some(leaf_search_hedging) = &self.leaf_search_hedging {
+            leaf_search_hedging.validate()?;
+        }
+        if let Some(load_shedding) = &self.load_shedding {
+            load_shedding.validate()?;
+        }
+        if let Some(leaf_search_tiers) = &self.leaf_search_tiers {
+            leaf_search_tiers.validate()?;
+        }
+        self.suggest.validate()?;
+        for (rpc_name, policy_config) in &self.metastore_rpc_policies {
+            policy_config.validate(rpc_name)?;
+        }
+        let mut tenant_ids = HashSet::with_capacity(self.tenant_quotas.len());
+        for tenant_quota in &self.tenant_quotas {
+            tenant_quota.validate()?;
+            ensure!(
+                tenant_ids.insert(&tenant_quota.tenant_id),
+                "tenant `{}` is defined more than once in `searcher.tenant_quotas`",
+                tenant_quota.tenant_id
+            );
+        }
         Ok(())
     }
 }
@@ -284,6 +934,8 @@ pub struct IngestApiConfig {
     replication_factor: usize,
     pub content_length_limit: ByteSize,
     pub shard_throughput_limit: ByteSize,
+    /// Maximum number of documents accepted in a single Elasticsearch `_bulk` request.
+    pub bulk_max_num_docs: usize,
 }
 
 impl Default for IngestApiConfig {
@@ -294,6 +946,7 @@ impl Default for IngestApiConfig {
             replication_factor: 1,
             content_length_limit: ByteSize::mib(10),
             shard_throughput_limit: DEFAULT_SHARD_THROUGHPUT_LIMIT,
+            bulk_max_num_docs: 100_000,
         }
     }
 }
@@ -345,6 +998,10 @@ impl IngestApiConfig {
             "shard_throughput_limit ({:?}) must be within 1mb and 20mb",
             self.shard_throughput_limit
         );
+        ensure!(
+            self.bulk_max_num_docs > 0,
+            "bulk_max_num_docs must be strictly positive"
+        );
         Ok(())
     }
 }
@@ -650,4 +1307,302 @@ mod tests {
         };
         assert!(grpc_config.validate().is_err());
     }
+
+    #[test]
+    fn test_searcher_config_tenant_quotas() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                tenant_quotas:
+                  - tenant_id: tenant-a
+                    index_id_patterns:
+                      - tenant-a-*
+                    max_concurrent_searches: 2
+                    max_splits_per_search: 1000
+                  - tenant_id: tenant-b
+                    index_id_patterns:
+                      - tenant-b-logs
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.tenant_quotas,
+            [
+                TenantSearchQuotaConfig {
+                    tenant_id: "tenant-a".to_string(),
+                    index_id_patterns: vec!["tenant-a-*".to_string()],
+                    max_concurrent_searches: Some(NonZeroUsize::new(2).unwrap()),
+                    max_splits_per_search: Some(NonZeroUsize::new(1000).unwrap()),
+                },
+                TenantSearchQuotaConfig {
+                    tenant_id: "tenant-b".to_string(),
+                    index_id_patterns: vec!["tenant-b-logs".to_string()],
+                    max_concurrent_searches: None,
+                    max_splits_per_search: None,
+                },
+            ]
+        );
+        let mut duplicate_tenant_config = searcher_config.clone();
+        duplicate_tenant_config.tenant_quotas[1].tenant_id = "tenant-a".to_string();
+        assert!(duplicate_tenant_config.validate().is_err());
+
+        let mut invalid_pattern_config = searcher_config;
+        invalid_pattern_config.tenant_quotas[0].index_id_patterns = vec!["-tenant-a-*".to_string()];
+        assert!(invalid_pattern_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_searcher_config_request_limits() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                max_query_length: 16KiB
+                max_request_body_size: 2MiB
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(searcher_config.max_query_length, ByteSize::kib(16));
+        assert_eq!(searcher_config.max_request_body_size, ByteSize::mib(2));
+
+        let searcher_config = SearcherConfig {
+            max_query_length: ByteSize::mb(2),
+            max_request_body_size: ByteSize::mb(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "max_query_length (2.0 MB) must be lower or equal to max_request_body_size (1.0 MB)"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_request_timeout() {
+        // Historical format: a number of seconds.
+        let searcher_config: SearcherConfig =
+            serde_yaml::from_str("request_timeout_secs: 45").unwrap();
+        assert_eq!(searcher_config.request_timeout(), Duration::from_secs(45));
+
+        let searcher_config: SearcherConfig =
+            serde_yaml::from_str("request_timeout_secs: 1m30s").unwrap();
+        assert_eq!(searcher_config.request_timeout(), Duration::from_secs(90));
+
+        let searcher_config: SearcherConfig =
+            serde_json::from_str(r#"{"request_timeout_secs": "2m"}"#).unwrap();
+        assert_eq!(searcher_config.request_timeout(), Duration::from_secs(120));
+
+        for invalid_request_timeout in ["0", "0s", "1500ms", "30 seconds", "-1"] {
+            serde_yaml::from_str::<SearcherConfig>(&format!(
+                "request_timeout_secs: {invalid_request_timeout}"
+            ))
+            .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_searcher_config_memory_budget() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                search_memory_pool_size: 4GB
+                leaf_request_memory_budget: 1GB
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(searcher_config.search_memory_pool_size, ByteSize::gb(4));
+        assert_eq!(searcher_config.leaf_request_memory_budget, ByteSize::gb(1));
+
+        let searcher_config = SearcherConfig {
+            search_memory_pool_size: ByteSize::mb(1),
+            leaf_request_memory_budget: ByteSize::mb(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "leaf_request_memory_budget (2.0 MB) must be lower or equal to \
+             search_memory_pool_size (1.0 MB)"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_leaf_search_hedging() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                leaf_search_hedging:
+                    latency_percentile: 90
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.leaf_search_hedging,
+            Some(LeafSearchHedgingConfig {
+                latency_percentile: 90,
+                min_delay_millis: 50,
+                max_hedged_requests_percent: 5,
+            })
+        );
+        let searcher_config = SearcherConfig {
+            leaf_search_hedging: Some(LeafSearchHedgingConfig {
+                max_hedged_requests_percent: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "leaf_search_hedging.max_hedged_requests_percent must be between 1 and 100, got `0`"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_load_shedding() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                load_shedding:
+                    pressure_threshold_percent: 80
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.load_shedding,
+            Some(LoadSheddingConfig {
+                pressure_threshold_percent: 80,
+                max_permit_wait_millis: 1_000,
+            })
+        );
+        let searcher_config = SearcherConfig {
+            load_shedding: Some(LoadSheddingConfig {
+                max_permit_wait_millis: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "load_shedding.max_permit_wait_millis must be strictly positive"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_leaf_search_tiers() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                leaf_search_tiers:
+                    expensive_threads_percent: 50
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.leaf_search_tiers,
+            Some(LeafSearchTiersConfig {
+                expensive_threads_percent: 50,
+                cheap_max_hits: 100,
+                cheap_max_splits: 10,
+            })
+        );
+        let searcher_config = SearcherConfig {
+            leaf_search_tiers: Some(LeafSearchTiersConfig {
+                expensive_threads_percent: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "leaf_search_tiers.expensive_threads_percent must be between 1 and 99, got `100`"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_suggest() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                suggest:
+                    max_num_splits: 5
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.suggest,
+            SuggestConfig {
+                max_num_splits: 5,
+                max_requests_per_sec: 20,
+            }
+        );
+        let searcher_config = SearcherConfig {
+            suggest: SuggestConfig {
+                max_num_splits: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "suggest.max_num_splits must be strictly positive"
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_aggregation_spill() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                aggregation_spill:
+                    in_memory_threshold: 1MB
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+        assert_eq!(
+            searcher_config.aggregation_spill,
+            AggregationSpillConfig {
+                in_memory_threshold: ByteSize::mb(1),
+                max_disk_usage: ByteSize::gb(10),
+            }
+        );
+    }
+
+    #[test]
+    fn test_searcher_config_metastore_rpc_policies() {
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                metastore_rpc_policies:
+                    list_splits:
+                        timeout_millis: 500
+                        max_retries: 2
+            "#,
+        )
+        .unwrap();
+        searcher_config.validate().unwrap();
+
+        let rpc_policy_table = searcher_config.metastore_rpc_policy_table();
+        assert_eq!(
+            *rpc_policy_table.get("list_splits"),
+            RpcPolicy {
+                timeout_opt: Some(Duration::from_millis(500)),
+                retry_params: RetryParams {
+                    max_attempts: 3,
+                    ..RetryParams::standard()
+                },
+            }
+        );
+        assert_eq!(
+            *rpc_policy_table.get("index_metadata"),
+            RpcPolicy::default()
+        );
+        let searcher_config: SearcherConfig = serde_yaml::from_str(
+            r#"
+                metastore_rpc_policies:
+                    list_splits:
+                        timeout_millis: 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            searcher_config.validate().unwrap_err().to_string(),
+            "metastore_rpc_policies.list_splits.timeout_millis must be strictly positive"
+        );
+    }
 }
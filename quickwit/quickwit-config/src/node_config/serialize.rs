@@ -343,6 +343,14 @@ fn validate(node_config: &NodeConfig) -> anyhow::Result<()> {
     if node_config.peer_seeds.is_empty() {
         warn!("peer seeds are empty");
     }
+    if node_config
+        .indexer_config
+        .indexing_affinity_labels
+        .keys()
+        .any(|label_key| label_key.is_empty())
+    {
+        bail!("indexing affinity label keys must not be empty");
+    }
     Ok(())
 }
 
@@ -469,9 +477,10 @@ pub fn node_config_for_tests_from_ports(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::env;
     use std::net::Ipv4Addr;
-    use std::num::{NonZeroU64, NonZeroUsize};
+    use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
     use std::path::Path;
 
     use bytesize::ByteSize;
@@ -479,6 +488,7 @@ mod tests {
 
     use super::*;
     use crate::storage_config::StorageBackendFlavor;
+    use crate::{AggregationSpillConfig, LeafSearchResponseCompression, SuggestConfig};
 
     fn get_config_filepath(config_filename: &str) -> String {
         format!(
@@ -587,10 +597,27 @@ mod tests {
                 split_store_max_num_bytes: ByteSize::tb(1),
                 split_store_max_num_splits: 10_000,
                 max_concurrent_split_uploads: 8,
+                split_upload_queue_max_num_bytes: ByteSize::gb(2),
                 merge_concurrency: NonZeroUsize::new(2).unwrap(),
                 cpu_capacity: IndexerConfig::default_cpu_capacity(),
                 enable_cooperative_indexing: false,
                 max_merge_write_throughput: Some(ByteSize::mb(100)),
+                max_apply_plan_requests_per_minute: NonZeroU32::new(30).unwrap(),
+                apply_plan_burst_limit: NonZeroU32::new(5).unwrap(),
+                shard_positions_eof_retention_secs: NonZeroU64::new(1_800).unwrap(),
+                shard_positions_flush_interval_millis: NonZeroU64::new(250).unwrap(),
+                shard_positions_flush_batch_size: NonZeroUsize::new(2_000).unwrap(),
+                pipeline_restart_base_delay_secs: NonZeroU64::new(2).unwrap(),
+                pipeline_restart_max_delay_secs: NonZeroU64::new(300).unwrap(),
+                max_pipeline_failures: NonZeroUsize::new(10).unwrap(),
+                max_shards_per_pipeline: NonZeroU32::new(500).unwrap(),
+                enable_doc_processing_error_samples: true,
+                max_doc_processing_error_samples: NonZeroUsize::new(50).unwrap(),
+                doc_processing_error_sample_max_num_bytes: ByteSize::kb(2),
+                indexing_affinity_labels: HashMap::from_iter([(
+                    "zone".to_string(),
+                    "us-east-1a".to_string()
+                )]),
             }
         );
         assert_eq!(
@@ -609,9 +636,26 @@ mod tests {
                 split_footer_cache_capacity: ByteSize::gb(1),
                 partial_request_cache_capacity: ByteSize::mb(64),
                 max_num_concurrent_split_searches: 150,
+                max_split_searches_per_index_percent: 80,
                 max_num_concurrent_split_streams: 120,
                 split_cache: None,
                 request_timeout_secs: NonZeroU64::new(30).unwrap(),
+                split_pruning_slack_secs: 60,
+                leaf_search_response_compression: LeafSearchResponseCompression::Auto,
+                tenant_quotas: Vec::new(),
+                max_query_length: ByteSize::kib(32),
+                max_request_body_size: ByteSize::mib(1),
+                search_memory_pool_size: ByteSize::gb(2),
+                leaf_request_memory_budget: ByteSize::gb(1),
+                leaf_search_hedging: None,
+                load_shedding: None,
+                leaf_search_tiers: None,
+                suggest: SuggestConfig::default(),
+                report_missing_splits: false,
+                aggregation_spill: AggregationSpillConfig::default(),
+                metastore_rpc_policies: BTreeMap::new(),
+                collection_checkpoint_num_docs: NonZeroU32::new(8_192).unwrap(),
+                yield_at_collection_checkpoints: false,
             }
         );
         assert_eq!(
@@ -1242,6 +1286,13 @@ mod tests {
         let error_message = ingest_config.validate().unwrap_err().to_string();
         assert!(error_message.contains("either 1 or 2, got `3`"));
 
+        let ingest_config = IngestApiConfig {
+            bulk_max_num_docs: 0,
+            ..Default::default()
+        };
+        let error_message = ingest_config.validate().unwrap_err().to_string();
+        assert!(error_message.contains("bulk_max_num_docs"));
+
         let node_config_yaml = r#"
             version: 0.8
             ingest_api:
@@ -1257,4 +1308,23 @@ mod tests {
         .to_string();
         assert!(error_message.contains("replication factor"));
     }
+
+    #[tokio::test]
+    async fn test_node_config_validates_indexing_affinity_labels() {
+        let node_config_yaml = r#"
+            version: 0.8
+            indexer:
+              indexing_affinity_labels:
+                "": us-east-1a
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error_message.contains("label keys must not be empty"));
+    }
 }
@@ -20,12 +20,15 @@
 pub(crate) mod serialize;
 
 use std::borrow::Cow;
-use std::num::NonZeroUsize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::str::FromStr;
 
 use bytes::Bytes;
 use quickwit_common::is_false;
 use quickwit_common::uri::Uri;
+use quickwit_proto::indexing::IndexingTaskPriority;
 use quickwit_proto::metastore::SourceType;
 use quickwit_proto::types::SourceId;
 use regex::Regex;
@@ -63,6 +66,21 @@ pub struct SourceConfig {
     // Denotes if this source is enabled.
     pub enabled: bool,
 
+    /// Whether the control plane keeps a warm standby pipeline for each pipeline of the source,
+    /// promoted when the indexer running the pipeline leaves the cluster.
+    pub critical: bool,
+
+    /// Priority of the indexing pipelines of the source, used by the control plane to place them
+    /// and by indexers to share their indexing resources.
+    pub priority: SourcePriority,
+
+    /// Restricts the indexers allowed to run the indexing pipelines of the source.
+    pub placement_selector: Option<PlacementSelector>,
+
+    /// Maximum number of shards assigned to a single indexing pipeline of the source. It can only
+    /// lower the `max_shards_per_pipeline` limit of the indexer configuration.
+    pub max_shards_per_pipeline: Option<NonZeroU32>,
+
     pub source_params: SourceParams,
 
     pub transform_config: Option<TransformConfig>,
@@ -113,6 +131,10 @@ impl SourceConfig {
             source_id: CLI_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::IngestCli,
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -125,6 +147,10 @@ impl SourceConfig {
             source_id: INGEST_V2_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: enable_ingest_v2(),
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Ingest,
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -137,6 +163,10 @@ impl SourceConfig {
             source_id: INGEST_API_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: !disable_ingest_v1(),
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::IngestApi,
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -149,6 +179,10 @@ impl SourceConfig {
             source_id: source_id.to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params,
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -163,6 +197,10 @@ impl crate::TestableForRegression for SourceConfig {
             source_id: "kafka-source".to_string(),
             num_pipelines: NonZeroUsize::new(2).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Kafka(KafkaSourceParams {
                 topic: "kafka-topic".to_string(),
                 client_log_level: None,
@@ -202,6 +240,64 @@ pub enum SourceInputFormat {
     PlainText,
 }
 
+/// Priority of the indexing pipelines of a source. When an indexer is contended, pipelines with a
+/// higher priority are granted indexing resources first and background pipelines are throttled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SourcePriority {
+    High,
+    #[default]
+    Normal,
+    Background,
+}
+
+impl SourcePriority {
+    /// For use with the `skip_serializing_if` serde attribute.
+    pub fn is_normal(&self) -> bool {
+        *self == SourcePriority::Normal
+    }
+}
+
+/// Restricts the placement of the indexing pipelines of a source to the indexers whose
+/// `indexing_affinity_labels` match the selector.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PlacementSelector {
+    /// Labels an indexer must carry, with the same values, to run the pipelines of the source.
+    pub match_labels: BTreeMap<String, String>,
+}
+
+impl PlacementSelector {
+    /// Returns whether an indexer carrying the given labels matches the selector.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.match_labels
+            .iter()
+            .all(|(label_key, label_value)| labels.get(label_key) == Some(label_value))
+    }
+}
+
+impl fmt::Display for PlacementSelector {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (label_idx, (label_key, label_value)) in self.match_labels.iter().enumerate() {
+            if label_idx > 0 {
+                write!(formatter, ",")?;
+            }
+            write!(formatter, "{label_key}={label_value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<SourcePriority> for IndexingTaskPriority {
+    fn from(priority: SourcePriority) -> Self {
+        match priority {
+            SourcePriority::High => IndexingTaskPriority::High,
+            SourcePriority::Normal => IndexingTaskPriority::Normal,
+            SourcePriority::Background => IndexingTaskPriority::Background,
+        }
+    }
+}
+
 impl FromStr for SourceInputFormat {
     type Err = String;
 
@@ -291,6 +387,34 @@ pub enum FileSourceNotification {
     Sqs(FileSourceSqs),
 }
 
+/// Order in which the objects of a polled prefix are ingested.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileSourcePollingOrdering {
+    /// Objects are ingested in the lexicographic order of their keys.
+    #[default]
+    Lexicographic,
+    /// Objects are ingested in the order of their last modification time, ties being broken by
+    /// key.
+    LastModified,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FileSourcePolling {
+    /// URI of the prefix to list periodically (e.g `s3://bucket/logs/`).
+    #[schema(value_type = String)]
+    pub prefix: Uri,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u32,
+    #[serde(default)]
+    pub ordering: FileSourcePollingOrdering,
+}
+
+fn default_poll_interval_secs() -> u32 {
+    30
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub(super) struct FileSourceParamsForSerde {
@@ -298,6 +422,8 @@ pub(super) struct FileSourceParamsForSerde {
     notifications: Vec<FileSourceNotification>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     filepath: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    polling: Option<FileSourcePolling>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -308,6 +434,7 @@ pub(super) struct FileSourceParamsForSerde {
 pub enum FileSourceParams {
     Notifications(FileSourceNotification),
     Filepath(Uri),
+    Polling(FileSourcePolling),
 }
 
 impl TryFrom<FileSourceParamsForSerde> for FileSourceParams {
@@ -320,7 +447,20 @@ impl TryFrom<FileSourceParamsForSerde> for FileSourceParams {
                     .into(),
             );
         }
-        if let Some(filepath) = value.filepath {
+        if value.polling.is_some() && (value.filepath.is_some() || !value.notifications.is_empty())
+        {
+            return Err(
+                "File source parameter `polling` is mutually exclusive with `notifications` and \
+                 `filepath`"
+                    .into(),
+            );
+        }
+        if let Some(polling) = value.polling {
+            if polling.poll_interval_secs == 0 {
+                return Err("File source parameter `poll_interval_secs` must be positive".into());
+            }
+            Ok(FileSourceParams::Polling(polling))
+        } else if let Some(filepath) = value.filepath {
             let uri = Uri::from_str(&filepath).map_err(|err| err.to_string())?;
             Ok(FileSourceParams::Filepath(uri))
         } else if value.notifications.len() == 1 {
@@ -331,7 +471,8 @@ impl TryFrom<FileSourceParamsForSerde> for FileSourceParams {
             return Err("Only one notification can be specified for now".into());
         } else {
             return Err(
-                "Either `notifications` or `filepath` must be specified as file source parameters"
+                "One of `notifications`, `filepath`, or `polling` must be specified as file \
+                 source parameters"
                     .into(),
             );
         }
@@ -344,10 +485,17 @@ impl From<FileSourceParams> for FileSourceParamsForSerde {
             FileSourceParams::Filepath(uri) => Self {
                 filepath: Some(uri.to_string()),
                 notifications: vec![],
+                polling: None,
             },
             FileSourceParams::Notifications(notification) => Self {
                 filepath: None,
                 notifications: vec![notification],
+                polling: None,
+            },
+            FileSourceParams::Polling(polling) => Self {
+                filepath: None,
+                notifications: vec![],
+                polling: Some(polling),
             },
         }
     }
@@ -639,6 +787,10 @@ mod tests {
             source_id: "hdfs-logs-kafka-source".to_string(),
             num_pipelines: NonZeroUsize::new(2).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Kafka(KafkaSourceParams {
                 topic: "cloudera-cluster-logs".to_string(),
                 client_log_level: None,
@@ -735,6 +887,10 @@ mod tests {
             source_id: "hdfs-logs-kinesis-source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Kinesis(KinesisSourceParams {
                 stream_name: "emr-cluster-logs".to_string(),
                 region_or_endpoint: None,
@@ -815,6 +971,24 @@ mod tests {
                 .unwrap_err();
             assert!(error.to_string().contains("supports multiple pipelines"));
         }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-polling-source",
+                "num_pipelines": 2,
+                "source_type": "file",
+                "params": {
+                    "polling": {
+                        "prefix": "s3://my-bucket/logs/"
+                    }
+                }
+            }
+            "#;
+            let error = load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+            assert!(error.to_string().contains("support a single pipeline only"));
+        }
     }
 
     #[tokio::test]
@@ -880,6 +1054,261 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_load_critical_source_config() {
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "critical": true,
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert!(source_config.critical);
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert_eq!(source_config_json["critical"], serde_json::json!(true));
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert!(!source_config.critical);
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert!(source_config_json.get("critical").is_none());
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-void-source",
+                "critical": true,
+                "source_type": "void",
+                "params": {}
+            }
+            "#;
+            let error = load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("critical sources are only supported"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_source_config_with_priority() {
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "priority": "background",
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert_eq!(source_config.priority, SourcePriority::Background);
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert_eq!(
+                source_config_json["priority"],
+                serde_json::json!("background")
+            );
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert_eq!(source_config.priority, SourcePriority::Normal);
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert!(source_config_json.get("priority").is_none());
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "priority": "urgent",
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_source_config_with_placement_selector() {
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "placement_selector": {
+                    "match_labels": {
+                        "zone": "us-east-1a"
+                    }
+                },
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            let placement_selector = source_config.placement_selector.clone().unwrap();
+            assert_eq!(placement_selector.to_string(), "zone=us-east-1a");
+
+            let matching_labels = BTreeMap::from_iter([
+                ("tier".to_string(), "hot".to_string()),
+                ("zone".to_string(), "us-east-1a".to_string()),
+            ]);
+            assert!(placement_selector.matches(&matching_labels));
+
+            let mismatching_labels =
+                BTreeMap::from_iter([("zone".to_string(), "us-east-1b".to_string())]);
+            assert!(!placement_selector.matches(&mismatching_labels));
+            assert!(!placement_selector.matches(&BTreeMap::new()));
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert_eq!(
+                source_config_json["placement_selector"],
+                serde_json::json!({"match_labels": {"zone": "us-east-1a"}})
+            );
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert!(source_config.placement_selector.is_none());
+
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert!(source_config_json.get("placement_selector").is_none());
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "placement_selector": {
+                    "match_labels": {}
+                },
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let error = load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("placement selector must match at least one label"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_source_config_with_max_shards_per_pipeline() {
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "_ingest-source",
+                "source_type": "ingest",
+                "max_shards_per_pipeline": 100
+            }
+            "#;
+            let source_config =
+                load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                    .unwrap();
+            assert_eq!(
+                source_config.max_shards_per_pipeline,
+                Some(NonZeroU32::new(100).unwrap())
+            );
+            let source_config_json = serde_json::to_value(&source_config).unwrap();
+            assert_eq!(source_config_json["max_shards_per_pipeline"], 100);
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "_ingest-source",
+                "source_type": "ingest",
+                "max_shards_per_pipeline": 0
+            }
+            "#;
+            load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+        }
+        {
+            let content = r#"
+            {
+                "version": "0.8",
+                "source_id": "hdfs-logs-kafka-source",
+                "max_shards_per_pipeline": 100,
+                "source_type": "kafka",
+                "params": {
+                    "topic": "my-topic"
+                }
+            }
+            "#;
+            let error = load_source_config_from_user_config(ConfigFormat::Json, content.as_bytes())
+                .unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("`max_shards_per_pipeline` is only supported for ingest sources"));
+        }
+    }
+
     #[test]
     fn test_file_source_params_serde() {
         {
@@ -977,6 +1406,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_file_source_params_polling_serde() {
+        {
+            let yaml = r#"
+                polling:
+                  prefix: s3://my-bucket/logs/
+            "#;
+            let file_params_deserialized = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
+            assert_eq!(
+                file_params_deserialized,
+                FileSourceParams::Polling(FileSourcePolling {
+                    prefix: Uri::for_test("s3://my-bucket/logs/"),
+                    poll_interval_secs: default_poll_interval_secs(),
+                    ordering: FileSourcePollingOrdering::Lexicographic,
+                })
+            );
+            let file_params_reserialized = serde_json::to_value(&file_params_deserialized).unwrap();
+            assert_eq!(
+                file_params_reserialized,
+                json!({"polling": {
+                    "prefix": "s3://my-bucket/logs/",
+                    "poll_interval_secs": default_poll_interval_secs(),
+                    "ordering": "lexicographic",
+                }})
+            );
+        }
+        {
+            let yaml = r#"
+                polling:
+                  prefix: s3://my-bucket/logs/
+                  poll_interval_secs: 5
+                  ordering: last_modified
+            "#;
+            let file_params_deserialized = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
+            assert_eq!(
+                file_params_deserialized,
+                FileSourceParams::Polling(FileSourcePolling {
+                    prefix: Uri::for_test("s3://my-bucket/logs/"),
+                    poll_interval_secs: 5,
+                    ordering: FileSourcePollingOrdering::LastModified,
+                })
+            );
+        }
+        {
+            let yaml = r#"
+                filepath: source-path.json
+                polling:
+                  prefix: s3://my-bucket/logs/
+            "#;
+            let error = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("`polling` is mutually exclusive with `notifications` and `filepath`"));
+        }
+        {
+            let yaml = r#"
+                polling:
+                  prefix: s3://my-bucket/logs/
+                  poll_interval_secs: 0
+            "#;
+            let error = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "File source parameter `poll_interval_secs` must be positive"
+            );
+        }
+    }
+
     #[test]
     fn test_kinesis_source_params_serialization() {
         {
@@ -1243,6 +1740,10 @@ mod tests {
             source_id: INGEST_API_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::IngestApi,
             transform_config: Some(TransformConfig {
                 vrl_script: ".message = downcase(string!(.message))".to_string(),
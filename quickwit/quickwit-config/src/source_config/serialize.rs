@@ -17,16 +17,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU32, NonZeroUsize};
 
 use anyhow::bail;
+use quickwit_common::is_false;
 use quickwit_proto::types::SourceId;
 use serde::{Deserialize, Serialize};
 
 use super::{TransformConfig, RESERVED_SOURCE_IDS};
 use crate::{
-    validate_identifier, ConfigFormat, FileSourceParams, SourceConfig, SourceInputFormat,
-    SourceParams,
+    validate_identifier, ConfigFormat, FileSourceParams, PlacementSelector, SourceConfig,
+    SourceInputFormat, SourceParams, SourcePriority,
 };
 
 type SourceConfigForSerialization = SourceConfigV0_8;
@@ -103,12 +104,34 @@ impl SourceConfigForSerialization {
             SourceParams::PubSub(_)
             | SourceParams::Kafka(_)
             | SourceParams::File(FileSourceParams::Notifications(_)) => {}
+            SourceParams::File(FileSourceParams::Polling(_)) => {
+                // The polled prefix is tracked as a single partition, which only one pipeline can
+                // own.
+                if self.num_pipelines > 1 {
+                    bail!("file sources polling a prefix support a single pipeline only");
+                }
+            }
             _ => {
                 if self.num_pipelines > 1 {
                     bail!("Quickwit currently supports multiple pipelines only for GCP PubSub or Kafka sources. open an issue https://github.com/quickwit-oss/quickwit/issues if you need the feature for other source types");
                 }
             }
         }
+        if self.critical
+            && !matches!(
+                self.source_params,
+                SourceParams::Kafka(_)
+                    | SourceParams::Kinesis(_)
+                    | SourceParams::PubSub(_)
+                    | SourceParams::Pulsar(_)
+                    | SourceParams::File(FileSourceParams::Notifications(_))
+            )
+        {
+            bail!(
+                "critical sources are only supported for Kafka, Kinesis, GCP PubSub, Pulsar, and \
+                 file notification sources"
+            );
+        }
 
         if let Some(transform_config) = &self.transform {
             if matches!(
@@ -122,11 +145,25 @@ impl SourceConfigForSerialization {
             }
             transform_config.validate_vrl_script()?;
         }
+        if let Some(placement_selector) = &self.placement_selector {
+            if placement_selector.match_labels.is_empty() {
+                bail!("placement selector must match at least one label");
+            }
+        }
+        if self.max_shards_per_pipeline.is_some()
+            && !matches!(self.source_params, SourceParams::Ingest)
+        {
+            bail!("`max_shards_per_pipeline` is only supported for ingest sources");
+        }
 
         Ok(SourceConfig {
             source_id: self.source_id,
             num_pipelines,
             enabled: self.enabled,
+            critical: self.critical,
+            priority: self.priority,
+            placement_selector: self.placement_selector,
+            max_shards_per_pipeline: self.max_shards_per_pipeline,
             source_params: self.source_params,
             transform_config: self.transform,
             input_format: self.input_format,
@@ -140,6 +177,10 @@ impl From<SourceConfig> for SourceConfigV0_8 {
             source_id: source_config.source_id,
             num_pipelines: source_config.num_pipelines.get(),
             enabled: source_config.enabled,
+            critical: source_config.critical,
+            priority: source_config.priority,
+            placement_selector: source_config.placement_selector,
+            max_shards_per_pipeline: source_config.max_shards_per_pipeline,
             source_params: source_config.source_params,
             transform: source_config.transform_config,
             input_format: source_config.input_format,
@@ -217,6 +258,23 @@ pub struct SourceConfigV0_8 {
     #[serde(default = "default_source_enabled")]
     pub enabled: bool,
 
+    // Denotes if the control plane should keep warm standby pipelines for this source.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub critical: bool,
+
+    // Priority of the indexing pipelines of this source.
+    #[serde(default, skip_serializing_if = "SourcePriority::is_normal")]
+    pub priority: SourcePriority,
+
+    // Restricts the indexers allowed to run the pipelines of this source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement_selector: Option<PlacementSelector>,
+
+    // Maximum number of shards assigned to a single indexing pipeline of this source.
+    #[schema(value_type = Option<u32>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_shards_per_pipeline: Option<NonZeroU32>,
+
     #[serde(flatten)]
     pub source_params: SourceParams,
 
@@ -243,6 +301,10 @@ impl From<SourceConfigV0_7> for SourceConfigV0_8 {
             source_id,
             num_pipelines: desired_num_pipelines,
             enabled,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params,
             transform,
             input_format,
@@ -36,21 +36,24 @@ use quickwit_cluster::{
 };
 use quickwit_common::pubsub::EventSubscriber;
 use quickwit_common::uri::Uri;
-use quickwit_common::{shared_consts, Progress};
+use quickwit_common::{shared_consts, Progress, ServiceStream};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{ClusterConfig, IndexConfig, IndexTemplate, SourceConfig};
 use quickwit_ingest::{IngesterPool, LocalShardsUpdate};
 use quickwit_metastore::{CreateIndexRequestExt, CreateIndexResponseExt, IndexMetadataResponseExt};
 use quickwit_proto::control_plane::{
     AdviseResetShardsRequest, AdviseResetShardsResponse, ControlPlaneError, ControlPlaneResult,
-    GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSubrequest,
+    ControlPlaneServiceStream, GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse,
+    GetOrCreateOpenShardsSubrequest, WatchIndexingPlanRequest, WatchIndexingPlanResponse,
 };
 use quickwit_proto::indexing::ShardPositionsUpdate;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteIndexRequest,
-    DeleteShardsRequest, DeleteSourceRequest, EmptyResponse, FindIndexTemplateMatchesRequest,
-    IndexMetadataResponse, IndexTemplateMatch, MetastoreError, MetastoreResult, MetastoreService,
-    MetastoreServiceClient, PruneShardsRequest, ToggleSourceRequest, UpdateIndexRequest,
+    DeleteShardsRequest, DeleteSourceRequest, EmptyResponse, EntityKind,
+    FindIndexTemplateMatchesRequest, IndexMetadataRequest, IndexMetadataResponse,
+    IndexTemplateMatch, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
+    PruneShardsRequest, RestoreIndexRequest, ToggleIngestRequest, ToggleSourceRequest,
+    UpdateIndexRequest,
 };
 use quickwit_proto::types::{IndexId, IndexUid, NodeId, ShardId, SourceId, SourceUid};
 use serde::Serialize;
@@ -60,7 +63,10 @@ use tracing::{debug, error, info};
 
 use crate::cooldown_map::{CooldownMap, CooldownStatus};
 use crate::debouncer::Debouncer;
-use crate::indexing_scheduler::{IndexingScheduler, IndexingSchedulerState};
+use crate::indexing_scheduler::{
+    IndexingPlanDryRun, IndexingPlanOverrides, IndexingPlanWatcher, IndexingScheduler,
+    IndexingSchedulerState, UnscheduledSource,
+};
 use crate::ingest::ingest_controller::{IngestControllerStats, RebalanceShardsCallback};
 use crate::ingest::IngestController;
 use crate::model::ControlPlaneModel;
@@ -164,8 +170,12 @@ impl ControlPlane {
                 let shard_throughput_limit_mib: f32 = cluster_config.shard_throughput_limit.as_u64()
                     as f32
                     / shared_consts::MIB as f32;
-                let indexing_scheduler =
-                    IndexingScheduler::new(cluster_id, self_node_id.clone(), indexer_pool.clone());
+                let indexing_scheduler = IndexingScheduler::new(
+                    cluster_id,
+                    self_node_id.clone(),
+                    indexer_pool.clone(),
+                    cluster_config.max_shards_per_pipeline,
+                );
                 let ingest_controller = IngestController::new(
                     metastore.clone(),
                     ingester_pool.clone(),
@@ -298,9 +308,21 @@ impl ControlPlane {
         while let Some(create_index_response_result) =
             progress.protect_future(create_index_futures.next()).await
         {
-            // Same here.
-            let create_index_response = create_index_response_result?;
-            let index_metadata = create_index_response.deserialize_index_metadata()?;
+            let index_metadata = match create_index_response_result {
+                Ok(create_index_response) => create_index_response.deserialize_index_metadata()?,
+                // The index was created concurrently without going through the control plane, for
+                // instance with the CLI. We fetch it and ingest into it as if we had created it.
+                Err(MetastoreError::AlreadyExists(EntityKind::Index { index_id })) => {
+                    info!(index_id=%index_id, "index was created concurrently");
+                    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+                    progress
+                        .protect_future(self.metastore.index_metadata(index_metadata_request))
+                        .await?
+                        .deserialize_index_metadata()?
+                }
+                // Same here.
+                Err(metastore_error) => return Err(metastore_error),
+            };
             self.model.add_index(index_metadata);
         }
         Ok(())
@@ -569,6 +591,51 @@ impl DeferableReplyHandler<CreateIndexRequest> for ControlPlane {
     }
 }
 
+// This handler is a metastore call proxied through the control plane: we must first forward the
+// request to the metastore, and then act on the event.
+#[async_trait]
+impl DeferableReplyHandler<RestoreIndexRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<CreateIndexResponse>;
+
+    async fn handle_message(
+        &mut self,
+        request: RestoreIndexRequest,
+        reply: impl FnOnce(Self::Reply) + Send + Sync + 'static,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        let response = match ctx
+            .protect_future(self.metastore.restore_index(request))
+            .await
+        {
+            Ok(response) => response,
+            Err(metastore_error) => {
+                reply(convert_metastore_error(metastore_error)?);
+                return Ok(());
+            }
+        };
+        let index_metadata = match response.deserialize_index_metadata() {
+            Ok(index_metadata) => index_metadata,
+            Err(serde_error) => {
+                error!(error=?serde_error, "failed to deserialize index metadata");
+                return Err(ActorExitStatus::from(anyhow::anyhow!(serde_error)));
+            }
+        };
+        let should_rebuild_plan = !index_metadata.sources.is_empty();
+        self.model.add_index(index_metadata);
+
+        if should_rebuild_plan {
+            let rebuild_plan_notifier = self.rebuild_plan_debounced(ctx);
+            tokio::task::spawn(async move {
+                rebuild_plan_notifier.await;
+                reply(Ok(response));
+            });
+        } else {
+            reply(Ok(response));
+        }
+        Ok(())
+    }
+}
+
 // This handler is a metastore call proxied through the control plane: we must first forward the
 // request to the metastore, and then act on the event.
 #[async_trait]
@@ -610,6 +677,42 @@ impl Handler<UpdateIndexRequest> for ControlPlane {
     }
 }
 
+// This handler is a metastore call proxied through the control plane: we must first forward the
+// request to the metastore, and then act on the event.
+#[async_trait]
+impl Handler<ToggleIngestRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<IndexMetadataResponse>;
+
+    async fn handle(
+        &mut self,
+        request: ToggleIngestRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let index_uid: IndexUid = request.index_uid().clone();
+        let ingest_blocked = request.ingest_blocked;
+        debug!(%index_uid, ingest_blocked, "toggling ingest");
+
+        let response = match ctx
+            .protect_future(self.metastore.toggle_ingest(request))
+            .await
+        {
+            Ok(response) => response,
+            Err(metastore_error) => {
+                return convert_metastore_error(metastore_error);
+            }
+        };
+        if self.model.set_ingest_blocked(&index_uid, ingest_blocked)? {
+            if ingest_blocked {
+                self.ingest_controller
+                    .close_shards_for_index(&index_uid, &mut self.model);
+            }
+            let _rebuild_plan_notifier = self.rebuild_plan_debounced(ctx);
+        }
+        info!(%index_uid, ingest_blocked, "toggled ingest");
+        Ok(Ok(response))
+    }
+}
+
 // This handler is a metastore call proxied through the control plane: we must first forward the
 // request to the metastore, and then act on the event.
 #[async_trait]
@@ -858,6 +961,31 @@ impl Handler<AdviseResetShardsRequest> for ControlPlane {
     }
 }
 
+// This is neither a proxied call nor a metastore callback.
+#[async_trait]
+impl Handler<WatchIndexingPlanRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<ControlPlaneServiceStream<WatchIndexingPlanResponse>>;
+
+    async fn handle(
+        &mut self,
+        _request: WatchIndexingPlanRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let event_stream = self
+            .indexing_scheduler
+            .watch_indexing_plan()
+            .into_stream()
+            .map(|event| {
+                let event_json = serde_json::to_vec(&event)
+                    .expect("indexing plan event should be JSON serializable");
+                Ok(WatchIndexingPlanResponse {
+                    event_json: event_json.into(),
+                })
+            });
+        Ok(Ok(ServiceStream::new(Box::pin(event_stream))))
+    }
+}
+
 #[async_trait]
 impl Handler<LocalShardsUpdate> for ControlPlane {
     type Reply = ControlPlaneResult<()>;
@@ -895,6 +1023,59 @@ impl Handler<GetDebugInfo> for ControlPlane {
     }
 }
 
+/// Computes the physical indexing plan the control plane would build if the overrides were
+/// applied to the cluster, without applying it.
+#[derive(Debug)]
+pub struct DryRunIndexingPlan(pub IndexingPlanOverrides);
+
+#[async_trait]
+impl Handler<DryRunIndexingPlan> for ControlPlane {
+    type Reply = IndexingPlanDryRun;
+
+    async fn handle(
+        &mut self,
+        DryRunIndexingPlan(overrides): DryRunIndexingPlan,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.indexing_scheduler.dry_run(&self.model, &overrides))
+    }
+}
+
+/// Lists the sources left out of the indexing plan because no indexer matches their placement
+/// selector.
+#[derive(Debug)]
+pub struct GetUnscheduledSources;
+
+#[async_trait]
+impl Handler<GetUnscheduledSources> for ControlPlane {
+    type Reply = Vec<UnscheduledSource>;
+
+    async fn handle(
+        &mut self,
+        _: GetUnscheduledSources,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.indexing_scheduler.unscheduled_sources().to_vec())
+    }
+}
+
+/// Subscribes to the changes of the physical indexing plan.
+#[derive(Debug)]
+pub struct WatchIndexingPlan;
+
+#[async_trait]
+impl Handler<WatchIndexingPlan> for ControlPlane {
+    type Reply = IndexingPlanWatcher;
+
+    async fn handle(
+        &mut self,
+        _: WatchIndexingPlan,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self.indexing_scheduler.watch_indexing_plan())
+    }
+}
+
 #[derive(Clone)]
 pub struct ControlPlaneEventSubscriber(WeakMailbox<ControlPlane>);
 
@@ -1002,6 +1183,10 @@ impl Handler<IndexerLeft> for ControlPlane {
         {
             return convert_metastore_error::<()>(metastore_error).map(|_| ());
         }
+        // Standby pipelines are promoted before rebuilding the plan, so that the promoted tasks
+        // replace the tasks of the indexer that left.
+        self.indexing_scheduler
+            .promote_standby_pipelines(message.0.node_id());
         self.indexing_scheduler.rebuild_plan(&self.model);
         Ok(())
     }
@@ -1074,6 +1259,7 @@ async fn watcher_indexers(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::sync::Arc;
 
     use mockall::Sequence;
@@ -1083,6 +1269,7 @@ mod tests {
     use quickwit_indexing::IndexingService;
     use quickwit_metastore::{
         CreateIndexRequestExt, IndexMetadata, ListIndexesMetadataResponseExt,
+        RestoreIndexRequestExt,
     };
     use quickwit_proto::control_plane::{
         GetOrCreateOpenShardsFailureReason, GetOrCreateOpenShardsSubrequest,
@@ -1164,6 +1351,65 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_control_plane_restore_index() {
+        let universe = Universe::with_accelerated_time();
+        let self_node_id: NodeId = "test-node".into();
+        let indexer_pool = IndexerPool::default();
+        let ingester_pool = IngesterPool::default();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_uid: IndexUid = IndexUid::for_test("test-index", 1);
+        let index_uid_clone = index_uid.clone();
+        mock_metastore
+            .expect_restore_index()
+            .withf(|restore_index_request| {
+                let (index_metadata, splits) = restore_index_request
+                    .deserialize_index_metadata_and_splits()
+                    .unwrap();
+                assert_eq!(index_metadata.index_id(), "test-index");
+                assert!(splits.is_empty());
+                true
+            })
+            .returning(move |_| {
+                let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+                index_metadata.index_uid = index_uid_clone.clone();
+                let index_metadata_json = serde_json::to_string(&index_metadata).unwrap();
+                let response = CreateIndexResponse {
+                    index_uid: Some(index_uid_clone.clone()),
+                    index_metadata_json,
+                };
+                Ok(response)
+            });
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+        let cluster_config = ClusterConfig::for_test();
+        let cluster_change_stream_factory = ClusterChangeStreamFactoryForTest::default();
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            self_node_id,
+            cluster_change_stream_factory,
+            indexer_pool,
+            ingester_pool,
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let restore_index_request =
+            RestoreIndexRequest::try_from_index_metadata_and_splits(&index_metadata, &[]).unwrap();
+        let restore_index_response = control_plane_mailbox
+            .ask_for_res(restore_index_request)
+            .await
+            .unwrap();
+        assert_eq!(restore_index_response.index_uid(), &index_uid);
+
+        let observable_state = control_plane_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(observable_state.num_indexes, 1);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_control_plane_delete_index() {
         let universe = Universe::with_accelerated_time();
@@ -1458,6 +1704,96 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_control_plane_block_ingest() {
+        let universe = Universe::with_accelerated_time();
+        let self_node_id: NodeId = "test-node".into();
+        let indexer_pool = IndexerPool::default();
+        let ingester_pool = IngesterPool::default();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .add_source(SourceConfig::ingest_v2())
+            .unwrap();
+        let index_uid = index_metadata.index_uid.clone();
+
+        let index_metadata_clone = index_metadata.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(move |_| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_clone,
+                ]))
+            });
+        let index_uid_clone = index_uid.clone();
+        mock_metastore.expect_list_shards().return_once(move |_| {
+            let subresponses = vec![ListShardsSubresponse {
+                index_uid: Some(index_uid_clone.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shards: vec![Shard {
+                    index_uid: Some(index_uid_clone),
+                    source_id: INGEST_V2_SOURCE_ID.to_string(),
+                    shard_id: Some(ShardId::from(1)),
+                    leader_id: "test-ingester".to_string(),
+                    shard_state: ShardState::Open as i32,
+                    ..Default::default()
+                }],
+            }];
+            Ok(ListShardsResponse { subresponses })
+        });
+        mock_metastore
+            .expect_toggle_ingest()
+            .times(1)
+            .return_once(move |toggle_ingest_request| {
+                assert!(toggle_ingest_request.ingest_blocked);
+                index_metadata.ingest_blocked = true;
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+
+        let cluster_config = ClusterConfig::for_test();
+        let cluster_change_stream_factory = ClusterChangeStreamFactoryForTest::default();
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            self_node_id,
+            cluster_change_stream_factory,
+            indexer_pool,
+            ingester_pool,
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        let toggle_ingest_request = ToggleIngestRequest {
+            index_uid: Some(index_uid.clone()),
+            ingest_blocked: true,
+        };
+        control_plane_mailbox
+            .ask_for_res(toggle_ingest_request)
+            .await
+            .unwrap();
+
+        let get_open_shards_request = GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "test-index".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+        };
+        let get_open_shards_response = control_plane_mailbox
+            .ask_for_res(get_open_shards_request)
+            .await
+            .unwrap();
+        assert!(get_open_shards_response.successes.is_empty());
+        assert_eq!(get_open_shards_response.failures.len(), 1);
+        assert_eq!(
+            get_open_shards_response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::IngestBlocked
+        );
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_control_plane_supervision_reload_from_metastore() {
         let universe = Universe::default();
@@ -1608,6 +1944,10 @@ mod tests {
             client,
             indexing_tasks: Vec::new(),
             indexing_capacity: CpuCapacity::from_cpu_millis(4_000),
+            is_draining: false,
+            supports_plan_deltas: false,
+            num_unhealthy_pipelines: 0,
+            indexing_affinity_labels: BTreeMap::new(),
         };
         indexer_pool.insert(indexer_node_info.node_id.clone(), indexer_node_info);
         let ingester_pool = IngesterPool::default();
@@ -1760,6 +2100,10 @@ mod tests {
             client,
             indexing_tasks: Vec::new(),
             indexing_capacity: CpuCapacity::from_cpu_millis(4_000),
+            is_draining: false,
+            supports_plan_deltas: false,
+            num_unhealthy_pipelines: 0,
+            indexing_affinity_labels: BTreeMap::new(),
         };
         indexer_pool.insert(indexer_node_info.node_id.clone(), indexer_node_info);
         let ingester_pool = IngesterPool::default();
@@ -1838,6 +2182,10 @@ mod tests {
             client,
             indexing_tasks: Vec::new(),
             indexing_capacity: CpuCapacity::from_cpu_millis(4_000),
+            is_draining: false,
+            supports_plan_deltas: false,
+            num_unhealthy_pipelines: 0,
+            indexing_affinity_labels: BTreeMap::new(),
         };
         indexer_pool.insert(indexer_node_info.node_id.clone(), indexer_node_info);
         let ingester_pool = IngesterPool::default();
@@ -2226,6 +2574,155 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    fn test_index_foo_template_matches() -> FindIndexTemplateMatchesResponse {
+        let index_template =
+            IndexTemplate::for_test("test-template-foo", &["test-index-foo*"], 100);
+        let index_template_json = serde_json::to_string(&index_template).unwrap();
+
+        FindIndexTemplateMatchesResponse {
+            matches: vec![IndexTemplateMatch {
+                template_id: "test-template-foo".to_string(),
+                index_id: "test-index-foo".to_string(),
+                index_template_json,
+            }],
+        }
+    }
+
+    fn test_index_foo_metadata(index_config: IndexConfig) -> IndexMetadata {
+        let index_uid = IndexUid::for_test("test-index-foo", 0);
+        let mut index_metadata = IndexMetadata::new_with_index_uid(index_uid, index_config);
+        index_metadata
+            .add_source(SourceConfig::ingest_v2())
+            .unwrap();
+        index_metadata
+    }
+
+    fn test_index_foo_get_or_create_open_shards_request() -> GetOrCreateOpenShardsRequest {
+        GetOrCreateOpenShardsRequest {
+            subrequests: vec![GetOrCreateOpenShardsSubrequest {
+                subrequest_id: 0,
+                index_id: "test-index-foo".to_string(),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+            }],
+            closed_shards: Vec::new(),
+            unavailable_leaders: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_create_indexes_on_concurrent_get_or_create_open_shards_requests() {
+        let universe = Universe::default();
+
+        let mut cluster_config = ClusterConfig::for_test();
+        cluster_config.auto_create_indexes = true;
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+        // The second request finds the index created by the first one in the model.
+        mock_metastore
+            .expect_find_index_template_matches()
+            .times(1)
+            .returning(|_| Ok(test_index_foo_template_matches()));
+        mock_metastore
+            .expect_create_index()
+            .times(1)
+            .returning(|request| {
+                let index_config = request.deserialize_index_config().unwrap();
+                let index_metadata = test_index_foo_metadata(index_config);
+                let index_metadata_json = serde_json::to_string(&index_metadata).unwrap();
+                Ok(CreateIndexResponse {
+                    index_uid: index_metadata.index_uid.into(),
+                    index_metadata_json,
+                })
+            });
+
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            NodeId::from("test-node"),
+            ClusterChangeStreamFactoryForTest::default(),
+            IndexerPool::default(),
+            IngesterPool::default(),
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        let (response_0, response_1) = tokio::join!(
+            control_plane_mailbox.ask(test_index_foo_get_or_create_open_shards_request()),
+            control_plane_mailbox.ask(test_index_foo_get_or_create_open_shards_request()),
+        );
+        for response in [response_0, response_1] {
+            let response = response.unwrap().unwrap();
+            assert_eq!(response.failures.len(), 1);
+            assert!(matches!(
+                response.failures[0].reason(),
+                GetOrCreateOpenShardsFailureReason::NoIngestersAvailable
+            ));
+        }
+        let control_plane_state = control_plane_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(control_plane_state.num_indexes, 1);
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_auto_create_indexes_index_created_concurrently() {
+        let universe = Universe::default();
+
+        let mut cluster_config = ClusterConfig::for_test();
+        cluster_config.auto_create_indexes = true;
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(|_| Ok(ListIndexesMetadataResponse::for_test(Vec::new())));
+        mock_metastore
+            .expect_find_index_template_matches()
+            .return_once(|_| Ok(test_index_foo_template_matches()));
+        // The index was created, for instance with the CLI, after the control plane loaded its
+        // model.
+        mock_metastore.expect_create_index().return_once(|_| {
+            Err(MetastoreError::AlreadyExists(EntityKind::Index {
+                index_id: "test-index-foo".to_string(),
+            }))
+        });
+        mock_metastore
+            .expect_index_metadata()
+            .return_once(|request| {
+                assert_eq!(request.into_index_id().as_deref(), Some("test-index-foo"));
+                let index_config =
+                    IndexConfig::for_test("test-index-foo", "ram:///indexes/test-index-foo");
+                let index_metadata = test_index_foo_metadata(index_config);
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+
+        let (control_plane_mailbox, _control_plane_handle, _readiness_rx) = ControlPlane::spawn(
+            &universe,
+            cluster_config,
+            NodeId::from("test-node"),
+            ClusterChangeStreamFactoryForTest::default(),
+            IndexerPool::default(),
+            IngesterPool::default(),
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        let response = control_plane_mailbox
+            .ask(test_index_foo_get_or_create_open_shards_request())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.failures.len(), 1);
+        assert!(matches!(
+            response.failures[0].reason(),
+            GetOrCreateOpenShardsFailureReason::NoIngestersAvailable
+        ));
+
+        let control_plane_state = control_plane_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(control_plane_state.num_indexes, 1);
+        assert_eq!(control_plane_state.num_sources, 1);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_watch_indexers() {
         let universe = Universe::with_accelerated_time();
@@ -2473,7 +2970,7 @@ mod tests {
         let mut mock_indexer = MockIndexingService::new();
         mock_indexer
             .expect_apply_indexing_plan()
-            .return_once(|_| Ok(ApplyIndexingPlanResponse {}));
+            .return_once(|_| Ok(ApplyIndexingPlanResponse::default()));
         let indexer = IndexingServiceClient::from_mock(mock_indexer);
 
         let indexer_info = IndexerNodeInfo {
@@ -2482,6 +2979,10 @@ mod tests {
             client: indexer,
             indexing_tasks: Vec::new(),
             indexing_capacity: CpuCapacity::from_cpu_millis(1_000),
+            is_draining: false,
+            supports_plan_deltas: false,
+            num_unhealthy_pipelines: 0,
+            indexing_affinity_labels: BTreeMap::new(),
         };
         indexer_pool.insert(ingester_id.clone(), indexer_info);
 
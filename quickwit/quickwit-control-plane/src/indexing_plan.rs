@@ -68,6 +68,25 @@ impl PhysicalIndexingPlan {
             .map(Vec::as_slice)
     }
 
+    /// Returns a copy of the plan without its warm standby tasks.
+    pub fn without_standby_tasks(&self) -> PhysicalIndexingPlan {
+        let indexing_tasks_per_indexer_id = self
+            .indexing_tasks_per_indexer_id
+            .iter()
+            .map(|(indexer_id, indexing_tasks)| {
+                let primary_tasks = indexing_tasks
+                    .iter()
+                    .filter(|indexing_task| !indexing_task.standby)
+                    .cloned()
+                    .collect();
+                (indexer_id.clone(), primary_tasks)
+            })
+            .collect();
+        PhysicalIndexingPlan {
+            indexing_tasks_per_indexer_id,
+        }
+    }
+
     pub fn normalize(&mut self) {
         for tasks in self.indexing_tasks_per_indexer_id.values_mut() {
             for task in tasks.iter_mut() {
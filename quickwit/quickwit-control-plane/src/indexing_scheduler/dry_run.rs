@@ -0,0 +1,514 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::num::{NonZeroU32, NonZeroUsize};
+
+use fnv::{FnvHashMap, FnvHashSet};
+use quickwit_proto::indexing::{
+    CpuCapacity, IndexingTask, IndexingTaskPriority, PIPELINE_FULL_CAPACITY,
+};
+use quickwit_proto::types::{IndexUid, NodeId, ShardId, SourceId, SourceUid};
+use serde::{Deserialize, Serialize};
+
+use super::scheduling::{build_physical_indexing_plan, SourceToSchedule, SourceToScheduleType};
+use crate::indexing_plan::PhysicalIndexingPlan;
+use crate::model::ShardLocations;
+
+/// Hypothetical changes to the cluster used to evaluate how the physical indexing plan would
+/// change, without applying anything.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexingPlanOverrides {
+    /// Overrides the indexing CPU capacity of some indexers. Indexers absent from the cluster are
+    /// added, and a capacity of zero removes the indexer.
+    #[serde(default)]
+    pub node_capacities: BTreeMap<NodeId, CpuCapacity>,
+    /// Removes some indexers from the cluster.
+    #[serde(default)]
+    pub removed_node_ids: Vec<NodeId>,
+    /// Adds some sources, scheduled like a non-sharded source such as Kafka.
+    #[serde(default)]
+    pub added_sources: Vec<AddedSource>,
+    /// Removes or disables some sources.
+    #[serde(default)]
+    pub removed_sources: Vec<RemovedSource>,
+}
+
+/// A source added by an [`IndexingPlanOverrides`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddedSource {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub num_pipelines: NonZeroUsize,
+}
+
+/// A source removed by an [`IndexingPlanOverrides`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemovedSource {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+}
+
+impl IndexingPlanOverrides {
+    pub(super) fn apply_to_sources(&self, sources: &mut Vec<SourceToSchedule>) {
+        sources.retain(|source| {
+            !self.removed_sources.iter().any(|removed_source| {
+                removed_source.index_uid == source.source_uid.index_uid
+                    && removed_source.source_id == source.source_uid.source_id
+            })
+        });
+        for added_source in &self.added_sources {
+            let source_uid = SourceUid {
+                index_uid: added_source.index_uid.clone(),
+                source_id: added_source.source_id.clone(),
+            };
+            sources.retain(|source| source.source_uid != source_uid);
+            sources.push(SourceToSchedule {
+                source_uid,
+                source_type: SourceToScheduleType::NonSharded {
+                    num_pipelines: added_source.num_pipelines.get() as u32,
+                    load_per_pipeline: NonZeroU32::new(PIPELINE_FULL_CAPACITY.cpu_millis())
+                        .unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            });
+        }
+    }
+
+    pub(super) fn apply_to_cpu_capacities(
+        &self,
+        indexer_id_to_cpu_capacities: &mut FnvHashMap<String, CpuCapacity>,
+    ) {
+        for (node_id, cpu_capacity) in &self.node_capacities {
+            if cpu_capacity.cpu_millis() > 0 {
+                indexer_id_to_cpu_capacities.insert(node_id.to_string(), *cpu_capacity);
+            } else {
+                indexer_id_to_cpu_capacities.remove(node_id.as_str());
+            }
+        }
+        for node_id in &self.removed_node_ids {
+            indexer_id_to_cpu_capacities.remove(node_id.as_str());
+        }
+    }
+}
+
+/// Result of an indexing plan dry run.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexingPlanDryRun {
+    /// Last plan applied by the scheduler.
+    pub current_plan: PhysicalIndexingPlan,
+    /// Plan the scheduler would build if the overrides were applied to the cluster.
+    pub hypothetical_plan: PhysicalIndexingPlan,
+    /// Changes between the current and the hypothetical plans, per indexer.
+    pub diff: IndexingPlanDiff,
+}
+
+/// Changes between two physical indexing plans, per indexer.
+pub type IndexingPlanDiff = BTreeMap<String, NodeIndexingPlanDiff>;
+
+/// Changes between two physical indexing plans for a given indexer.
+///
+/// Tasks are identified by their index, source, and shards: a task with the same identity
+/// scheduled on another indexer in the other plan is reported as moved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NodeIndexingPlanDiff {
+    /// Tasks absent from the current plan.
+    pub added_tasks: Vec<IndexingTask>,
+    /// Tasks absent from the hypothetical plan.
+    pub removed_tasks: Vec<IndexingTask>,
+    /// Tasks moved to this indexer.
+    pub moved_in_tasks: Vec<MovedIndexingTask>,
+    /// Tasks moved away from this indexer.
+    pub moved_out_tasks: Vec<MovedIndexingTask>,
+    /// CPU load of the tasks of the current plan.
+    pub current_cpu_load: CpuCapacity,
+    /// CPU load of the tasks of the hypothetical plan.
+    pub hypothetical_cpu_load: CpuCapacity,
+    /// Difference between the hypothetical and the current CPU loads, in CPU millis.
+    pub cpu_load_delta_millis: i64,
+}
+
+impl NodeIndexingPlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_tasks.is_empty()
+            && self.removed_tasks.is_empty()
+            && self.moved_in_tasks.is_empty()
+            && self.moved_out_tasks.is_empty()
+    }
+}
+
+/// A task moved from or to another indexer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MovedIndexingTask {
+    /// The indexer the task is moved from or to.
+    pub node_id: String,
+    pub task: IndexingTask,
+}
+
+type TaskKey<'a> = (Option<&'a IndexUid>, &'a str, Vec<&'a ShardId>);
+
+fn task_key(task: &IndexingTask) -> TaskKey {
+    let mut shard_ids: Vec<&ShardId> = task.shard_ids.iter().collect();
+    shard_ids.sort_unstable();
+    (task.index_uid.as_ref(), task.source_id.as_str(), shard_ids)
+}
+
+fn group_tasks_by_key(
+    physical_plan: &PhysicalIndexingPlan,
+) -> FnvHashMap<TaskKey, Vec<(&str, &IndexingTask)>> {
+    let mut tasks_by_key: FnvHashMap<TaskKey, Vec<(&str, &IndexingTask)>> = FnvHashMap::default();
+    for (node_id, tasks) in physical_plan.indexing_tasks_per_indexer() {
+        for task in tasks {
+            tasks_by_key
+                .entry(task_key(task))
+                .or_default()
+                .push((node_id.as_str(), task));
+        }
+    }
+    tasks_by_key
+}
+
+fn compute_task_cpu_load(
+    sources_by_uid: &FnvHashMap<(&IndexUid, &str), &SourceToScheduleType>,
+    task: &IndexingTask,
+) -> CpuCapacity {
+    let Some(index_uid) = task.index_uid.as_ref() else {
+        return CpuCapacity::zero();
+    };
+    match sources_by_uid.get(&(index_uid, task.source_id.as_str())) {
+        Some(SourceToScheduleType::Sharded { load_per_shard, .. }) => {
+            CpuCapacity::from_cpu_millis(load_per_shard.get()) * task.shard_ids.len() as u32
+        }
+        Some(SourceToScheduleType::NonSharded {
+            load_per_pipeline, ..
+        }) => CpuCapacity::from_cpu_millis(load_per_pipeline.get()),
+        Some(SourceToScheduleType::IngestV1) | None => CpuCapacity::zero(),
+    }
+}
+
+fn compute_cpu_loads(
+    physical_plan: &PhysicalIndexingPlan,
+    sources: &[SourceToSchedule],
+) -> FnvHashMap<String, CpuCapacity> {
+    let sources_by_uid: FnvHashMap<(&IndexUid, &str), &SourceToScheduleType> = sources
+        .iter()
+        .map(|source| {
+            let source_key = (
+                &source.source_uid.index_uid,
+                source.source_uid.source_id.as_str(),
+            );
+            (source_key, &source.source_type)
+        })
+        .collect();
+    physical_plan
+        .indexing_tasks_per_indexer()
+        .iter()
+        .map(|(node_id, tasks)| {
+            let cpu_load = tasks
+                .iter()
+                .map(|task| compute_task_cpu_load(&sources_by_uid, task))
+                .fold(CpuCapacity::zero(), |left, right| left + right);
+            (node_id.clone(), cpu_load)
+        })
+        .collect()
+}
+
+/// Computes the changes between the `current_plan` built for the `current_sources` and the
+/// `hypothetical_plan` built for the `hypothetical_sources`.
+pub(super) fn diff_physical_indexing_plans(
+    current_plan: &PhysicalIndexingPlan,
+    current_sources: &[SourceToSchedule],
+    hypothetical_plan: &PhysicalIndexingPlan,
+    hypothetical_sources: &[SourceToSchedule],
+) -> IndexingPlanDiff {
+    let mut diff = IndexingPlanDiff::new();
+
+    for node_id in current_plan
+        .indexing_tasks_per_indexer()
+        .keys()
+        .chain(hypothetical_plan.indexing_tasks_per_indexer().keys())
+    {
+        diff.entry(node_id.clone()).or_default();
+    }
+    let mut current_tasks_by_key = group_tasks_by_key(current_plan);
+    let mut hypothetical_tasks_by_key = group_tasks_by_key(hypothetical_plan);
+
+    let task_keys: Vec<TaskKey> = current_tasks_by_key
+        .keys()
+        .chain(hypothetical_tasks_by_key.keys())
+        .cloned()
+        .collect();
+
+    for task_key in task_keys {
+        let mut current_tasks = current_tasks_by_key.remove(&task_key).unwrap_or_default();
+        let mut hypothetical_tasks = hypothetical_tasks_by_key
+            .remove(&task_key)
+            .unwrap_or_default();
+
+        // Tasks that stay on the same indexer are left out of the diff.
+        hypothetical_tasks.retain(|(hypothetical_node_id, _)| {
+            if let Some(position) = current_tasks
+                .iter()
+                .position(|(current_node_id, _)| current_node_id == hypothetical_node_id)
+            {
+                current_tasks.swap_remove(position);
+                false
+            } else {
+                true
+            }
+        });
+        let num_moved_tasks = current_tasks.len().min(hypothetical_tasks.len());
+
+        for ((from_node_id, from_task), (to_node_id, to_task)) in current_tasks
+            .drain(..num_moved_tasks)
+            .zip(hypothetical_tasks.drain(..num_moved_tasks))
+        {
+            diff.entry(from_node_id.to_string())
+                .or_default()
+                .moved_out_tasks
+                .push(MovedIndexingTask {
+                    node_id: to_node_id.to_string(),
+                    task: from_task.clone(),
+                });
+            diff.entry(to_node_id.to_string())
+                .or_default()
+                .moved_in_tasks
+                .push(MovedIndexingTask {
+                    node_id: from_node_id.to_string(),
+                    task: to_task.clone(),
+                });
+        }
+        for (node_id, task) in current_tasks {
+            diff.entry(node_id.to_string())
+                .or_default()
+                .removed_tasks
+                .push(task.clone());
+        }
+        for (node_id, task) in hypothetical_tasks {
+            diff.entry(node_id.to_string())
+                .or_default()
+                .added_tasks
+                .push(task.clone());
+        }
+    }
+    let current_cpu_loads = compute_cpu_loads(current_plan, current_sources);
+    let hypothetical_cpu_loads = compute_cpu_loads(hypothetical_plan, hypothetical_sources);
+
+    for (node_id, node_diff) in diff.iter_mut() {
+        node_diff.current_cpu_load = current_cpu_loads
+            .get(node_id)
+            .copied()
+            .unwrap_or_else(CpuCapacity::zero);
+        node_diff.hypothetical_cpu_load = hypothetical_cpu_loads
+            .get(node_id)
+            .copied()
+            .unwrap_or_else(CpuCapacity::zero);
+        node_diff.cpu_load_delta_millis = node_diff.hypothetical_cpu_load.cpu_millis() as i64
+            - node_diff.current_cpu_load.cpu_millis() as i64;
+    }
+    diff
+}
+
+/// Builds the plan the scheduler would produce for the `hypothetical_sources` and indexers, and
+/// compares it with the current plan. Nothing is applied.
+pub(super) fn dry_run_indexing_plan(
+    current_plan_opt: Option<&PhysicalIndexingPlan>,
+    current_sources: &[SourceToSchedule],
+    hypothetical_sources: &[SourceToSchedule],
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+    shard_locations: &ShardLocations,
+) -> IndexingPlanDryRun {
+    let current_plan = current_plan_opt
+        .cloned()
+        .unwrap_or_else(|| PhysicalIndexingPlan::with_indexer_ids(&[]));
+
+    let hypothetical_plan = if indexer_id_to_cpu_capacities.is_empty() {
+        PhysicalIndexingPlan::with_indexer_ids(&[])
+    } else {
+        build_physical_indexing_plan(
+            hypothetical_sources,
+            indexer_id_to_cpu_capacities,
+            current_plan_opt,
+            shard_locations,
+        )
+    };
+    let diff = diff_physical_indexing_plans(
+        &current_plan,
+        current_sources,
+        &hypothetical_plan,
+        hypothetical_sources,
+    );
+    IndexingPlanDryRun {
+        current_plan,
+        hypothetical_plan,
+        diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn non_sharded_source(source_id: &str, load_per_pipeline: u32) -> SourceToSchedule {
+        SourceToSchedule {
+            source_uid: SourceUid {
+                index_uid: IndexUid::for_test("index-1", 0),
+                source_id: source_id.to_string(),
+            },
+            source_type: SourceToScheduleType::NonSharded {
+                num_pipelines: 1,
+                load_per_pipeline: NonZeroU32::new(load_per_pipeline).unwrap(),
+            },
+            params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
+        }
+    }
+
+    #[test]
+    fn test_dry_run_indexing_plan_remove_node() {
+        let sources = vec![
+            non_sharded_source("source-1", 3_000),
+            non_sharded_source("source-2", 3_000),
+        ];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities
+            .insert("indexer-1".to_string(), CpuCapacity::from_cpu_millis(4_000));
+        indexer_id_to_cpu_capacities
+            .insert("indexer-2".to_string(), CpuCapacity::from_cpu_millis(4_000));
+        let shard_locations = ShardLocations::default();
+        let current_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        assert_eq!(current_plan.indexer("indexer-1").unwrap().len(), 1);
+        assert_eq!(current_plan.indexer("indexer-2").unwrap().len(), 1);
+
+        let overrides = IndexingPlanOverrides {
+            node_capacities: BTreeMap::from([(
+                NodeId::from("indexer-1"),
+                CpuCapacity::from_cpu_millis(8_000),
+            )]),
+            removed_node_ids: vec![NodeId::from("indexer-2")],
+            ..Default::default()
+        };
+        overrides.apply_to_cpu_capacities(&mut indexer_id_to_cpu_capacities);
+
+        let dry_run = dry_run_indexing_plan(
+            Some(&current_plan),
+            &sources,
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            &shard_locations,
+        );
+        assert_eq!(dry_run.current_plan, current_plan);
+        assert_eq!(
+            dry_run
+                .hypothetical_plan
+                .indexer("indexer-1")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(dry_run.hypothetical_plan.indexer("indexer-2").is_none());
+
+        let moved_task = current_plan.indexer("indexer-2").unwrap()[0].clone();
+
+        let indexer_1_diff = &dry_run.diff["indexer-1"];
+        assert!(indexer_1_diff.added_tasks.is_empty());
+        assert!(indexer_1_diff.removed_tasks.is_empty());
+        assert!(indexer_1_diff.moved_out_tasks.is_empty());
+        assert_eq!(indexer_1_diff.moved_in_tasks.len(), 1);
+        assert_eq!(indexer_1_diff.moved_in_tasks[0].node_id, "indexer-2");
+        assert_eq!(
+            indexer_1_diff.moved_in_tasks[0].task.source_id,
+            moved_task.source_id
+        );
+        assert_eq!(indexer_1_diff.current_cpu_load.cpu_millis(), 3_000);
+        assert_eq!(indexer_1_diff.hypothetical_cpu_load.cpu_millis(), 6_000);
+        assert_eq!(indexer_1_diff.cpu_load_delta_millis, 3_000);
+
+        let indexer_2_diff = &dry_run.diff["indexer-2"];
+        assert!(indexer_2_diff.added_tasks.is_empty());
+        assert!(indexer_2_diff.removed_tasks.is_empty());
+        assert!(indexer_2_diff.moved_in_tasks.is_empty());
+        assert_eq!(
+            indexer_2_diff.moved_out_tasks,
+            [MovedIndexingTask {
+                node_id: "indexer-1".to_string(),
+                task: moved_task,
+            }]
+        );
+        assert_eq!(indexer_2_diff.current_cpu_load.cpu_millis(), 3_000);
+        assert_eq!(indexer_2_diff.hypothetical_cpu_load.cpu_millis(), 0);
+        assert_eq!(indexer_2_diff.cpu_load_delta_millis, -3_000);
+    }
+
+    #[test]
+    fn test_dry_run_indexing_plan_remove_source() {
+        let sources = vec![
+            non_sharded_source("source-1", 1_000),
+            non_sharded_source("source-2", 1_000),
+        ];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities
+            .insert("indexer-1".to_string(), CpuCapacity::from_cpu_millis(4_000));
+        let shard_locations = ShardLocations::default();
+        let current_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        let overrides = IndexingPlanOverrides {
+            removed_sources: vec![RemovedSource {
+                index_uid: IndexUid::for_test("index-1", 0),
+                source_id: "source-2".to_string(),
+            }],
+            ..Default::default()
+        };
+        let mut hypothetical_sources = vec![
+            non_sharded_source("source-1", 1_000),
+            non_sharded_source("source-2", 1_000),
+        ];
+        overrides.apply_to_sources(&mut hypothetical_sources);
+
+        let dry_run = dry_run_indexing_plan(
+            Some(&current_plan),
+            &sources,
+            &hypothetical_sources,
+            &indexer_id_to_cpu_capacities,
+            &shard_locations,
+        );
+        let indexer_1_diff = &dry_run.diff["indexer-1"];
+        assert!(indexer_1_diff.added_tasks.is_empty());
+        assert_eq!(indexer_1_diff.removed_tasks.len(), 1);
+        assert_eq!(indexer_1_diff.removed_tasks[0].source_id, "source-2");
+        assert_eq!(indexer_1_diff.cpu_load_delta_millis, -1_000);
+    }
+}
@@ -18,30 +18,50 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod change_tracker;
+mod dry_run;
+mod plan_convergence;
+mod plan_watch;
 mod scheduling;
 
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use fnv::{FnvHashMap, FnvHashSet};
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
+use prost::Message;
 use quickwit_common::pretty::PrettySample;
-use quickwit_config::{FileSourceParams, SourceParams};
+use quickwit_config::{FileSourceParams, PlacementSelector, SourceParams};
 use quickwit_proto::indexing::{
-    ApplyIndexingPlanRequest, CpuCapacity, IndexingService, IndexingTask, PIPELINE_FULL_CAPACITY,
+    AppliedIndexingPlan, ApplyIndexingPlanRequest, ApplyIndexingPlanResponse, CpuCapacity,
+    IndexingError, IndexingResult, IndexingService, IndexingTask, IndexingTaskPriority,
+    IndexingTaskStatus, PromoteIndexingPipelineRequest, PIPELINE_FULL_CAPACITY,
     PIPELINE_THROUGHPUT,
 };
-use quickwit_proto::types::NodeId;
-use scheduling::{SourceToSchedule, SourceToScheduleType};
+use quickwit_proto::types::{
+    IndexUid, NodeId, NodeIdRef, PipelineUid, ShardId, SourceId, SourceUid,
+};
+use scheduling::{
+    remove_shards_exceeding_pipeline_capacity, SourceToSchedule, SourceToScheduleType,
+};
 use serde::Serialize;
 use tracing::{debug, info, warn};
 
+pub use self::dry_run::{
+    AddedSource, IndexingPlanDiff, IndexingPlanDryRun, IndexingPlanOverrides, MovedIndexingTask,
+    NodeIndexingPlanDiff, RemovedSource,
+};
+pub use self::plan_watch::{IndexingPlanEvent, IndexingPlanSnapshot, IndexingPlanWatcher};
 use crate::indexing_plan::PhysicalIndexingPlan;
 use crate::indexing_scheduler::change_tracker::{NotifyChangeOnDrop, RebuildNotifier};
+use crate::indexing_scheduler::plan_convergence::{
+    PlanConvergenceTracker, DIVERGENCE_GRACE_PERIOD,
+};
+use crate::indexing_scheduler::plan_watch::{IndexingPlanPublisher, INDEXING_PLAN_EVENTS_CAPACITY};
 use crate::indexing_scheduler::scheduling::build_physical_indexing_plan;
 use crate::metrics::ShardLocalityMetrics;
 use crate::model::{ControlPlaneModel, ShardEntry, ShardLocations};
@@ -61,6 +81,26 @@ pub struct IndexingSchedulerState {
     pub last_applied_physical_plan: Option<PhysicalIndexingPlan>,
     #[serde(skip)]
     pub last_applied_plan_timestamp: Option<Instant>,
+    pub unscheduled_sources: Vec<UnscheduledSource>,
+    pub unscheduled_shards: Vec<UnscheduledShards>,
+}
+
+/// Source left out of the indexing plan because no indexer matches its placement selector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnscheduledSource {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub placement_selector: PlacementSelector,
+}
+
+/// Shards of a source left out of the indexing plan because the indexers cannot accommodate the
+/// number of pipelines required to index them without exceeding the maximum number of shards per
+/// pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnscheduledShards {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub shard_ids: Vec<ShardId>,
 }
 
 /// The [`IndexingScheduler`] is responsible for listing indexing tasks and assigning them to
@@ -106,8 +146,18 @@ pub struct IndexingScheduler {
     cluster_id: String,
     self_node_id: NodeId,
     indexer_pool: IndexerPool,
+    // Default maximum number of shards per pipeline, which sources may lower.
+    max_shards_per_pipeline: NonZeroU32,
     state: IndexingSchedulerState,
     pub(crate) next_rebuild_tracker: RebuildNotifier,
+    // Indexers that reported all the tasks of an applied plan as already running, along with the
+    // sequence number (`num_applied_physical_indexing_plan`) of that plan.
+    already_running_confirmations: Arc<Mutex<FnvHashMap<NodeId, usize>>>,
+    // Last plan successfully applied by each indexer, against which the shard lists of the next
+    // plan are delta-encoded.
+    applied_plans: Arc<Mutex<FnvHashMap<NodeId, AppliedIndexingPlan>>>,
+    plan_convergence_tracker: PlanConvergenceTracker,
+    plan_publisher: IndexingPlanPublisher,
 }
 
 impl fmt::Debug for IndexingScheduler {
@@ -161,17 +211,231 @@ fn compute_load_per_shard(shard_entries: &[&ShardEntry]) -> NonZeroU32 {
     }
 }
 
-fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
+fn get_indexer_id_to_cpu_capacities(
+    indexers: &[IndexerNodeInfo],
+) -> FnvHashMap<String, CpuCapacity> {
+    indexers
+        .iter()
+        .filter_map(|indexer| {
+            // Draining indexers are given no capacity so that their tasks get moved away.
+            if !indexer.is_draining && indexer.indexing_capacity.cpu_millis() > 0 {
+                Some((indexer.node_id.to_string(), indexer.indexing_capacity))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Adds an empty list of tasks for each draining indexer to the plan, so that applying the plan
+/// shuts down the pipelines still running on these indexers.
+fn add_draining_indexers_to_plan(
+    physical_plan: &mut PhysicalIndexingPlan,
+    indexers: &[IndexerNodeInfo],
+) {
+    for indexer in indexers {
+        if indexer.is_draining {
+            physical_plan
+                .indexing_tasks_per_indexer_mut()
+                .entry(indexer.node_id.to_string())
+                .or_default();
+        }
+    }
+}
+
+/// Returns the enabled sources for which the scheduler keeps warm standby pipelines.
+fn get_critical_sources(model: &ControlPlaneModel) -> FnvHashSet<SourceUid> {
+    model
+        .source_configs()
+        .filter(|(source_uid, source_config)| {
+            source_config.enabled
+                && source_config.critical
+                && !model.is_ingest_blocked(&source_uid.index_uid)
+        })
+        .map(|(source_uid, _)| source_uid)
+        .collect()
+}
+
+fn task_source_uid(indexing_task: &IndexingTask) -> SourceUid {
+    SourceUid {
+        index_uid: indexing_task.index_uid().clone(),
+        source_id: indexing_task.source_id.clone(),
+    }
+}
+
+/// Adds a warm standby task for each task of the critical sources to the plan. Each standby task
+/// is placed on the least loaded indexer that does not run a task of the same source, so that
+/// losing an indexer never takes down a pipeline along with its standby. The standby tasks of the
+/// previous plan are kept as long as their indexer is still eligible. Standby tasks of sources
+/// restricted by a placement selector only go to the indexers matching it.
+fn add_standby_tasks_to_plan(
+    physical_plan: &mut PhysicalIndexingPlan,
+    critical_sources: &FnvHashSet<SourceUid>,
+    eligible_indexer_ids_per_source: &FnvHashMap<SourceUid, &FnvHashSet<String>>,
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+    previous_plan_opt: Option<&PhysicalIndexingPlan>,
+) {
+    if critical_sources.is_empty() {
+        return;
+    }
+    // Indexers running the tasks of each critical source, one entry per task, along with the
+    // params fingerprint and the priority of the source.
+    let mut primary_tasks_per_source: BTreeMap<
+        SourceUid,
+        (Vec<String>, u64, IndexingTaskPriority),
+    > = BTreeMap::new();
+    let mut num_tasks_per_indexer: FnvHashMap<&str, usize> = FnvHashMap::default();
+
+    for (indexer_id, indexing_tasks) in physical_plan.indexing_tasks_per_indexer() {
+        num_tasks_per_indexer.insert(indexer_id, indexing_tasks.len());
+
+        for indexing_task in indexing_tasks {
+            let source_uid = task_source_uid(indexing_task);
+
+            if !critical_sources.contains(&source_uid) {
+                continue;
+            }
+            let (indexer_ids, params_fingerprint, priority) =
+                primary_tasks_per_source.entry(source_uid).or_default();
+            indexer_ids.push(indexer_id.clone());
+            *params_fingerprint = indexing_task.params_fingerprint;
+            *priority = indexing_task.priority();
+        }
+    }
+    let mut previous_standby_tasks_per_source: FnvHashMap<SourceUid, Vec<(&str, &IndexingTask)>> =
+        FnvHashMap::default();
+
+    if let Some(previous_plan) = previous_plan_opt {
+        for (indexer_id, indexing_tasks) in previous_plan.indexing_tasks_per_indexer() {
+            for indexing_task in indexing_tasks {
+                if indexing_task.standby {
+                    previous_standby_tasks_per_source
+                        .entry(task_source_uid(indexing_task))
+                        .or_default()
+                        .push((indexer_id, indexing_task));
+                }
+            }
+        }
+    }
+    let mut standby_tasks: Vec<(String, IndexingTask)> = Vec::new();
+
+    for (source_uid, (primary_indexer_ids, params_fingerprint, priority)) in
+        primary_tasks_per_source
+    {
+        let eligible_indexer_ids_opt = eligible_indexer_ids_per_source.get(&source_uid);
+        let candidate_indexer_ids: Vec<&str> = indexer_id_to_cpu_capacities
+            .keys()
+            .map(String::as_str)
+            .filter(|indexer_id| !primary_indexer_ids.iter().any(|id| id == indexer_id))
+            .filter(|indexer_id| {
+                eligible_indexer_ids_opt
+                    .map(|eligible_indexer_ids| eligible_indexer_ids.contains(*indexer_id))
+                    .unwrap_or(true)
+            })
+            .sorted()
+            .collect();
+
+        if candidate_indexer_ids.is_empty() {
+            warn!(
+                index_uid=%source_uid.index_uid,
+                source_id=%source_uid.source_id,
+                "no indexer available to run the standby pipelines of critical source"
+            );
+            continue;
+        }
+        let num_standby_tasks = primary_indexer_ids.len();
+        let mut standby_placements: Vec<(&str, PipelineUid)> =
+            Vec::with_capacity(num_standby_tasks);
+
+        for (indexer_id, previous_standby_task) in previous_standby_tasks_per_source
+            .remove(&source_uid)
+            .unwrap_or_default()
+        {
+            if standby_placements.len() == num_standby_tasks {
+                break;
+            }
+            if previous_standby_task.params_fingerprint == params_fingerprint
+                && previous_standby_task.priority() == priority
+                && candidate_indexer_ids.contains(&indexer_id)
+            {
+                standby_placements.push((indexer_id, previous_standby_task.pipeline_uid()));
+                *num_tasks_per_indexer.entry(indexer_id).or_default() += 1;
+            }
+        }
+        while standby_placements.len() < num_standby_tasks {
+            let indexer_id = candidate_indexer_ids
+                .iter()
+                .copied()
+                .min_by_key(|indexer_id| {
+                    num_tasks_per_indexer
+                        .get(indexer_id)
+                        .copied()
+                        .unwrap_or_default()
+                })
+                .expect("candidate indexers should not be empty");
+            standby_placements.push((indexer_id, PipelineUid::random()));
+            *num_tasks_per_indexer.entry(indexer_id).or_default() += 1;
+        }
+        for (indexer_id, pipeline_uid) in standby_placements {
+            let standby_task = IndexingTask {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                pipeline_uid: Some(pipeline_uid),
+                shard_ids: Vec::new(),
+                params_fingerprint,
+                standby: true,
+                priority: priority as i32,
+            };
+            standby_tasks.push((indexer_id.to_string(), standby_task));
+        }
+    }
+    for (indexer_id, standby_task) in standby_tasks {
+        physical_plan.add_indexing_task(&indexer_id, standby_task);
+    }
+}
+
+fn update_draining_indexer_metrics(indexers: &[IndexerNodeInfo]) {
+    let mut num_draining_indexers = 0;
+    for indexer in indexers {
+        if indexer.is_draining {
+            num_draining_indexers += 1;
+            crate::metrics::CONTROL_PLANE_METRICS
+                .draining_indexer_remaining_pipelines
+                .with_label_values([indexer.node_id.as_str()])
+                .set(indexer.indexing_tasks.len() as i64);
+        }
+    }
+    crate::metrics::CONTROL_PLANE_METRICS
+        .draining_indexers
+        .set(num_draining_indexers);
+}
+
+/// Returns the number of indexing pipelines of the cluster that are failed or being respawned, as
+/// reported by the indexers.
+pub(crate) fn num_unhealthy_pipelines(indexers: &[IndexerNodeInfo]) -> usize {
+    indexers
+        .iter()
+        .map(|indexer| indexer.num_unhealthy_pipelines)
+        .sum()
+}
+
+fn get_sources_to_schedule(
+    model: &ControlPlaneModel,
+    max_shards_per_pipeline: NonZeroU32,
+) -> Vec<SourceToSchedule> {
     let mut sources = Vec::new();
 
     for (source_uid, source_config) in model.source_configs() {
-        if !source_config.enabled {
+        if !source_config.enabled || model.is_ingest_blocked(&source_uid.index_uid) {
             continue;
         }
         let params_fingerprint = model
             .index_metadata(&source_uid.index_uid)
             .map(|index_meta| index_meta.index_config.indexing_params_fingerprint())
             .unwrap_or_default();
+        let reloadable_params_fingerprints =
+            model.reloadable_params_fingerprints(&source_uid.index_uid);
+        let priority = IndexingTaskPriority::from(source_config.priority);
         match source_config.source_params {
             SourceParams::File(FileSourceParams::Filepath(_))
             | SourceParams::IngestCli
@@ -186,6 +450,9 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
                     source_uid,
                     source_type: SourceToScheduleType::IngestV1,
                     params_fingerprint,
+                    priority,
+                    eligible_indexer_ids: None,
+                    reloadable_params_fingerprints,
                 });
             }
             SourceParams::Ingest => {
@@ -205,13 +472,23 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
                     .map(|shard_entry| shard_entry.shard_id().clone())
                     .collect();
                 let load_per_shard = compute_load_per_shard(&shard_entries[..]);
+                let max_shards_per_pipeline = source_config.max_shards_per_pipeline.map_or(
+                    max_shards_per_pipeline,
+                    |source_max_shards_per_pipeline| {
+                        source_max_shards_per_pipeline.min(max_shards_per_pipeline)
+                    },
+                );
                 sources.push(SourceToSchedule {
                     source_uid,
                     source_type: SourceToScheduleType::Sharded {
                         shard_ids,
                         load_per_shard,
+                        max_shards_per_pipeline,
                     },
                     params_fingerprint,
+                    priority,
+                    eligible_indexer_ids: None,
+                    reloadable_params_fingerprints,
                 });
             }
             SourceParams::Kafka(_)
@@ -228,6 +505,26 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
                             .unwrap(),
                     },
                     params_fingerprint,
+                    priority,
+                    eligible_indexer_ids: None,
+                    reloadable_params_fingerprints,
+                });
+            }
+            SourceParams::File(FileSourceParams::Polling(_)) => {
+                // The polled prefix is tracked as a single partition: running several pipelines
+                // would ingest every object several times.
+                sources.push(SourceToSchedule {
+                    source_uid,
+                    source_type: SourceToScheduleType::NonSharded {
+                        num_pipelines: 1,
+                        // FIXME
+                        load_per_pipeline: NonZeroU32::new(PIPELINE_FULL_CAPACITY.cpu_millis())
+                            .unwrap(),
+                    },
+                    params_fingerprint,
+                    priority,
+                    eligible_indexer_ids: None,
+                    reloadable_params_fingerprints,
                 });
             }
         }
@@ -235,14 +532,102 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
     sources
 }
 
+/// Restricts each source with a placement selector to the indexers whose affinity labels match
+/// it. The sources that no indexer with some indexing capacity matches are removed and returned.
+fn apply_placement_selectors(
+    model: &ControlPlaneModel,
+    sources: &mut Vec<SourceToSchedule>,
+    indexers: &[IndexerNodeInfo],
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+) -> Vec<UnscheduledSource> {
+    let mut unscheduled_sources = Vec::new();
+
+    sources.retain_mut(|source| {
+        let Some(placement_selector) = model
+            .source_metadata(&source.source_uid)
+            .and_then(|source_config| source_config.placement_selector.as_ref())
+        else {
+            return true;
+        };
+        let eligible_indexer_ids: FnvHashSet<String> = indexers
+            .iter()
+            .filter(|indexer| {
+                indexer_id_to_cpu_capacities.contains_key(indexer.node_id.as_str())
+                    && placement_selector.matches(&indexer.indexing_affinity_labels)
+            })
+            .map(|indexer| indexer.node_id.to_string())
+            .collect();
+
+        if eligible_indexer_ids.is_empty() {
+            unscheduled_sources.push(UnscheduledSource {
+                index_uid: source.source_uid.index_uid.clone(),
+                source_id: source.source_uid.source_id.clone(),
+                placement_selector: placement_selector.clone(),
+            });
+            return false;
+        }
+        source.eligible_indexer_ids = Some(eligible_indexer_ids);
+        true
+    });
+    unscheduled_sources.sort_by(|left, right| {
+        (&left.index_uid, &left.source_id).cmp(&(&right.index_uid, &right.source_id))
+    });
+    unscheduled_sources
+}
+
+/// Leaves out of the sharded sources the shards that the indexers cannot index without exceeding
+/// the maximum number of shards per pipeline of the source, and returns them.
+fn remove_unschedulable_shards(
+    sources: &mut Vec<SourceToSchedule>,
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+) -> Vec<UnscheduledShards> {
+    let mut unscheduled_shards: Vec<UnscheduledShards> =
+        remove_shards_exceeding_pipeline_capacity(sources, indexer_id_to_cpu_capacities)
+            .into_iter()
+            .map(|(source_uid, shard_ids)| UnscheduledShards {
+                index_uid: source_uid.index_uid,
+                source_id: source_uid.source_id,
+                shard_ids,
+            })
+            .collect();
+    unscheduled_shards.sort_by(|left, right| {
+        (&left.index_uid, &left.source_id).cmp(&(&right.index_uid, &right.source_id))
+    });
+    unscheduled_shards
+}
+
+/// Returns the indexers eligible to run the tasks of each source restricted by a placement
+/// selector.
+fn get_eligible_indexer_ids_per_source(
+    sources: &[SourceToSchedule],
+) -> FnvHashMap<SourceUid, &FnvHashSet<String>> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let eligible_indexer_ids = source.eligible_indexer_ids.as_ref()?;
+            Some((source.source_uid.clone(), eligible_indexer_ids))
+        })
+        .collect()
+}
+
 impl IndexingScheduler {
-    pub fn new(cluster_id: String, self_node_id: NodeId, indexer_pool: IndexerPool) -> Self {
+    pub fn new(
+        cluster_id: String,
+        self_node_id: NodeId,
+        indexer_pool: IndexerPool,
+        max_shards_per_pipeline: NonZeroU32,
+    ) -> Self {
         IndexingScheduler {
             cluster_id,
             self_node_id,
             indexer_pool,
+            max_shards_per_pipeline,
             state: IndexingSchedulerState::default(),
             next_rebuild_tracker: RebuildNotifier::default(),
+            already_running_confirmations: Arc::default(),
+            applied_plans: Arc::default(),
+            plan_convergence_tracker: PlanConvergenceTracker::default(),
+            plan_publisher: IndexingPlanPublisher::new(INDEXING_PLAN_EVENTS_CAPACITY),
         }
     }
 
@@ -250,6 +635,15 @@ impl IndexingScheduler {
         self.state.clone()
     }
 
+    pub(crate) fn unscheduled_sources(&self) -> &[UnscheduledSource] {
+        &self.state.unscheduled_sources
+    }
+
+    /// Subscribes to the changes of the physical indexing plan.
+    pub(crate) fn watch_indexing_plan(&self) -> IndexingPlanWatcher {
+        self.plan_publisher.subscribe()
+    }
+
     // Should be called whenever a change in the list of index/shard
     // has happened.
     //
@@ -260,20 +654,11 @@ impl IndexingScheduler {
 
         let notify_on_drop = self.next_rebuild_tracker.start_rebuild();
 
-        let sources = get_sources_to_schedule(model);
+        let mut sources = get_sources_to_schedule(model, self.max_shards_per_pipeline);
 
         let indexers: Vec<IndexerNodeInfo> = self.get_indexers_from_indexer_pool();
 
-        let indexer_id_to_cpu_capacities: FnvHashMap<String, CpuCapacity> = indexers
-            .iter()
-            .filter_map(|indexer| {
-                if indexer.indexing_capacity.cpu_millis() > 0 {
-                    Some((indexer.node_id.to_string(), indexer.indexing_capacity))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
 
         if indexer_id_to_cpu_capacities.is_empty() {
             if !sources.is_empty() {
@@ -281,14 +666,67 @@ impl IndexingScheduler {
             }
             return;
         };
+        let unscheduled_sources = apply_placement_selectors(
+            model,
+            &mut sources,
+            &indexers,
+            &indexer_id_to_cpu_capacities,
+        );
+        if unscheduled_sources != self.state.unscheduled_sources {
+            for unscheduled_source in &unscheduled_sources {
+                warn!(
+                    index_uid=%unscheduled_source.index_uid,
+                    source_id=%unscheduled_source.source_id,
+                    placement_selector=%unscheduled_source.placement_selector,
+                    "no indexer matches the placement selector of source, leaving it unscheduled"
+                );
+            }
+            self.state.unscheduled_sources = unscheduled_sources;
+        }
+        let unscheduled_shards =
+            remove_unschedulable_shards(&mut sources, &indexer_id_to_cpu_capacities);
+        if unscheduled_shards != self.state.unscheduled_shards {
+            for unscheduled_source_shards in &unscheduled_shards {
+                warn!(
+                    index_uid=%unscheduled_source_shards.index_uid,
+                    source_id=%unscheduled_source_shards.source_id,
+                    num_shards=unscheduled_source_shards.shard_ids.len(),
+                    "indexers cannot accommodate enough pipelines to index all the shards of \
+                     source without exceeding the maximum number of shards per pipeline, leaving \
+                     shards unscheduled"
+                );
+            }
+            let num_unscheduled_shards: usize = unscheduled_shards
+                .iter()
+                .map(|unscheduled_source_shards| unscheduled_source_shards.shard_ids.len())
+                .sum();
+            crate::metrics::CONTROL_PLANE_METRICS
+                .unscheduled_shards
+                .set(num_unscheduled_shards as i64);
+            self.state.unscheduled_shards = unscheduled_shards;
+        }
 
         let shard_locations = model.shard_locations();
-        let new_physical_plan = build_physical_indexing_plan(
+        // Standby tasks are placed once the regular tasks are scheduled.
+        let previous_primary_plan_opt = self
+            .state
+            .last_applied_physical_plan
+            .as_ref()
+            .map(PhysicalIndexingPlan::without_standby_tasks);
+        let mut new_physical_plan = build_physical_indexing_plan(
             &sources,
             &indexer_id_to_cpu_capacities,
-            self.state.last_applied_physical_plan.as_ref(),
+            previous_primary_plan_opt.as_ref(),
             &shard_locations,
         );
+        add_standby_tasks_to_plan(
+            &mut new_physical_plan,
+            &get_critical_sources(model),
+            &get_eligible_indexer_ids_per_source(&sources),
+            &indexer_id_to_cpu_capacities,
+            self.state.last_applied_physical_plan.as_ref(),
+        );
+        add_draining_indexers_to_plan(&mut new_physical_plan, &indexers);
         let shard_locality_metrics =
             get_shard_locality_metrics(&new_physical_plan, &shard_locations);
         crate::metrics::CONTROL_PLANE_METRICS.set_shard_locality_metrics(shard_locality_metrics);
@@ -302,10 +740,150 @@ impl IndexingScheduler {
                 return;
             }
         }
+        self.plan_convergence_tracker
+            .record_new_plan(Instant::now());
         self.apply_physical_indexing_plan(&indexers, new_physical_plan, Some(notify_on_drop));
+        self.publish_last_applied_plan(Some(sources));
         self.state.num_schedule_indexing_plan += 1;
     }
 
+    /// Computes the physical indexing plan the scheduler would build if the `overrides` were
+    /// applied to the cluster, and compares it with the last applied plan.
+    ///
+    /// The scheduler state is left untouched.
+    pub(crate) fn dry_run(
+        &self,
+        model: &ControlPlaneModel,
+        overrides: &IndexingPlanOverrides,
+    ) -> IndexingPlanDryRun {
+        let mut current_sources = get_sources_to_schedule(model, self.max_shards_per_pipeline);
+        let mut hypothetical_sources = get_sources_to_schedule(model, self.max_shards_per_pipeline);
+        overrides.apply_to_sources(&mut hypothetical_sources);
+
+        let indexers: Vec<IndexerNodeInfo> = self.get_indexers_from_indexer_pool();
+        let current_indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
+        let mut indexer_id_to_cpu_capacities = current_indexer_id_to_cpu_capacities.clone();
+        overrides.apply_to_cpu_capacities(&mut indexer_id_to_cpu_capacities);
+
+        apply_placement_selectors(
+            model,
+            &mut current_sources,
+            &indexers,
+            &current_indexer_id_to_cpu_capacities,
+        );
+        apply_placement_selectors(
+            model,
+            &mut hypothetical_sources,
+            &indexers,
+            &indexer_id_to_cpu_capacities,
+        );
+        remove_unschedulable_shards(&mut current_sources, &current_indexer_id_to_cpu_capacities);
+        remove_unschedulable_shards(&mut hypothetical_sources, &indexer_id_to_cpu_capacities);
+
+        let previous_primary_plan_opt = self
+            .state
+            .last_applied_physical_plan
+            .as_ref()
+            .map(PhysicalIndexingPlan::without_standby_tasks);
+
+        dry_run::dry_run_indexing_plan(
+            previous_primary_plan_opt.as_ref(),
+            &current_sources,
+            &hypothetical_sources,
+            &indexer_id_to_cpu_capacities,
+            &model.shard_locations(),
+        )
+    }
+
+    /// Promotes the warm standby pipelines backing the tasks of an indexer that left the cluster:
+    /// for each task of the indexer, a standby task of the same source becomes a regular task in
+    /// the last applied plan, and the indexer running it is asked to promote its pipeline right
+    /// away rather than on the next indexing plan.
+    pub(crate) fn promote_standby_pipelines(&mut self, left_indexer_id: &NodeIdRef) {
+        let indexers: Vec<IndexerNodeInfo> = self.get_indexers_from_indexer_pool();
+
+        let Some(last_applied_plan) = &mut self.state.last_applied_physical_plan else {
+            return;
+        };
+        let Some(left_indexer_tasks) = last_applied_plan
+            .indexing_tasks_per_indexer_mut()
+            .remove(left_indexer_id.as_str())
+        else {
+            return;
+        };
+        let live_indexers: FnvHashMap<&str, &IndexerNodeInfo> = indexers
+            .iter()
+            .filter(|indexer| !indexer.is_draining && indexer.node_id != *left_indexer_id)
+            .map(|indexer| (indexer.node_id.as_str(), indexer))
+            .collect();
+        let mut promotions: Vec<(IndexerNodeInfo, PipelineUid)> = Vec::new();
+
+        for left_indexer_task in left_indexer_tasks
+            .iter()
+            .filter(|indexing_task| !indexing_task.standby)
+        {
+            let standby_task_opt = last_applied_plan
+                .indexing_tasks_per_indexer_mut()
+                .iter_mut()
+                .filter_map(|(indexer_id, indexing_tasks)| {
+                    let indexer = live_indexers.get(indexer_id.as_str())?;
+                    Some(indexing_tasks.iter_mut().map(move |task| (*indexer, task)))
+                })
+                .flatten()
+                .find(|(_, indexing_task)| {
+                    indexing_task.standby
+                        && indexing_task.index_uid == left_indexer_task.index_uid
+                        && indexing_task.source_id == left_indexer_task.source_id
+                });
+            let Some((indexer, standby_task)) = standby_task_opt else {
+                continue;
+            };
+            standby_task.standby = false;
+            promotions.push((indexer.clone(), standby_task.pipeline_uid()));
+        }
+        if !promotions.is_empty() {
+            self.plan_convergence_tracker
+                .record_new_plan(Instant::now());
+            self.publish_last_applied_plan(None);
+        }
+        for (indexer, pipeline_uid) in promotions {
+            info!(
+                node_id=%indexer.node_id,
+                pipeline_uid=%pipeline_uid,
+                left_node_id=%left_indexer_id,
+                "promoting standby indexing pipeline"
+            );
+            tokio::spawn(async move {
+                let promote_request = PromoteIndexingPipelineRequest {
+                    pipeline_uid: Some(pipeline_uid),
+                };
+                let outcome = match indexer
+                    .client
+                    .clone()
+                    .promote_indexing_pipeline(promote_request)
+                    .await
+                {
+                    Ok(promote_response) if promote_response.warm => "warm",
+                    Ok(_) => "cold",
+                    Err(error) => {
+                        // The indexer is sent the promoted task with the next indexing plan.
+                        warn!(
+                            error=%error,
+                            node_id=%indexer.node_id,
+                            pipeline_uid=%pipeline_uid,
+                            "failed to promote standby indexing pipeline"
+                        );
+                        "failed"
+                    }
+                };
+                crate::metrics::CONTROL_PLANE_METRICS
+                    .standby_pipeline_promotions_total
+                    .with_label_values([outcome])
+                    .inc();
+            });
+        }
+    }
+
     /// Checks if the last applied plan corresponds to the running indexing tasks present in the
     /// chitchat cluster state. If true, do nothing.
     /// - If node IDs differ, schedule a new indexing plan.
@@ -329,6 +907,23 @@ impl IndexingScheduler {
             }
         }
         let indexers: Vec<IndexerNodeInfo> = self.get_indexers_from_indexer_pool();
+        update_draining_indexer_metrics(&indexers);
+        crate::metrics::CONTROL_PLANE_METRICS
+            .unhealthy_indexing_pipelines
+            .set(num_unhealthy_pipelines(&indexers) as i64);
+
+        // Tasks still assigned to a draining indexer must be moved to other indexers.
+        let has_tasks_on_draining_indexers = indexers.iter().any(|indexer| {
+            indexer.is_draining
+                && last_applied_plan
+                    .indexer(indexer.node_id.as_str())
+                    .is_some_and(|indexing_tasks| !indexing_tasks.is_empty())
+        });
+        if has_tasks_on_draining_indexers {
+            info!("indexing tasks are assigned to draining indexers: schedule an indexing plan");
+            self.rebuild_plan(model);
+            return;
+        }
         let running_indexing_tasks_by_node_id: FnvHashMap<String, Vec<IndexingTask>> = indexers
             .iter()
             .map(|indexer| (indexer.node_id.to_string(), indexer.indexing_tasks.clone()))
@@ -338,16 +933,83 @@ impl IndexingScheduler {
             &running_indexing_tasks_by_node_id,
             last_applied_plan.indexing_tasks_per_indexer(),
         );
+        let now = Instant::now();
+        if let Some(convergence_duration) = self
+            .plan_convergence_tracker
+            .observe(&indexing_plans_diff, now)
+        {
+            info!(
+                convergence_duration_secs = convergence_duration.as_secs_f64(),
+                "running plan converged to the last applied plan"
+            );
+            crate::metrics::CONTROL_PLANE_METRICS
+                .indexing_plan_convergence_seconds
+                .observe(convergence_duration.as_secs_f64());
+        }
+        crate::metrics::CONTROL_PLANE_METRICS
+            .indexing_plan_divergent_nodes
+            .set(
+                self.plan_convergence_tracker
+                    .num_divergent_nodes(now, DIVERGENCE_GRACE_PERIOD) as i64,
+            );
+
         if !indexing_plans_diff.has_same_nodes() {
             info!(plans_diff=?indexing_plans_diff, "running plan and last applied plan node IDs differ: schedule an indexing plan");
             self.rebuild_plan(model);
         } else if !indexing_plans_diff.has_same_tasks() {
+            if self.consume_already_running_confirmations(&indexing_plans_diff) {
+                // The indexers received the plan and reported all their tasks as already running:
+                // the running plan is most likely lagging behind in chitchat, so we give it one
+                // more loop to converge before resending the plan.
+                debug!(plans_diff=?indexing_plans_diff, "running tasks and last applied tasks differ but indexers confirmed the plan: skip reapply");
+                return;
+            }
             // Some nodes may have not received their tasks, apply it again.
             info!(plans_diff=?indexing_plans_diff, "running tasks and last applied tasks differ: reapply last plan");
             self.apply_physical_indexing_plan(&indexers, last_applied_plan.clone(), None);
         }
     }
 
+    /// Returns true if all the indexers whose running tasks differ from the last applied plan
+    /// confirmed that all the tasks of that plan were already running. The confirmations are
+    /// consumed so that the plan is reapplied if the difference persists.
+    fn consume_already_running_confirmations(
+        &self,
+        indexing_plans_diff: &IndexingPlansDiff,
+    ) -> bool {
+        let mut already_running_confirmations = self
+            .already_running_confirmations
+            .lock()
+            .expect("lock should not be poisoned");
+        let current_plan_seq = self.state.num_applied_physical_indexing_plan;
+        let mut node_ids_with_diff = indexing_plans_diff
+            .missing_tasks_by_node_id
+            .iter()
+            .chain(&indexing_plans_diff.unplanned_tasks_by_node_id)
+            .filter(|(_, tasks)| !tasks.is_empty())
+            .map(|(node_id, _)| *node_id)
+            .peekable();
+
+        if node_ids_with_diff.peek().is_none() {
+            return false;
+        }
+        let node_ids_with_diff: FnvHashSet<&str> = node_ids_with_diff.collect();
+        let all_confirmed = node_ids_with_diff
+            .iter()
+            .all(|node_id| already_running_confirmations.get(*node_id) == Some(&current_plan_seq));
+        for node_id in node_ids_with_diff {
+            already_running_confirmations.remove(node_id);
+        }
+        all_confirmed
+    }
+
+    /// Publishes the last applied plan to the watchers of the indexing plan.
+    fn publish_last_applied_plan(&mut self, sources_opt: Option<Vec<SourceToSchedule>>) {
+        if let Some(last_applied_plan) = &self.state.last_applied_physical_plan {
+            self.plan_publisher.publish(last_applied_plan, sources_opt);
+        }
+    }
+
     fn get_indexers_from_indexer_pool(&self) -> Vec<IndexerNodeInfo> {
         self.indexer_pool.values()
     }
@@ -360,6 +1022,16 @@ impl IndexingScheduler {
     ) {
         debug!(new_physical_plan=?new_physical_plan, "apply physical indexing plan");
         crate::metrics::CONTROL_PLANE_METRICS.apply_total.inc();
+        let plan_seq = self.state.num_applied_physical_indexing_plan + 1;
+        self.already_running_confirmations
+            .lock()
+            .expect("lock should not be poisoned")
+            .clear();
+        self.applied_plans
+            .lock()
+            .expect("lock should not be poisoned")
+            .retain(|node_id, _| indexers.iter().any(|indexer| indexer.node_id == *node_id));
+
         for (node_id, indexing_tasks) in new_physical_plan.indexing_tasks_per_indexer() {
             // We don't want to block on a slow indexer so we apply this change asynchronously
             // TODO not blocking is cool, but we need to make sure there is not accumulation
@@ -371,20 +1043,69 @@ impl IndexingScheduler {
                     .find(|indexer| indexer.node_id == *node_id)
                     .expect("This should never happen as the plan was built from these indexers.")
                     .clone();
-                let indexing_tasks = indexing_tasks.clone();
+                let applied_plan = AppliedIndexingPlan {
+                    plan_generation: plan_seq as u64,
+                    indexing_tasks: indexing_tasks.clone(),
+                };
+                let apply_plan_request = {
+                    let applied_plans_guard = self
+                        .applied_plans
+                        .lock()
+                        .expect("lock should not be poisoned");
+                    let base_plan_opt = applied_plans_guard
+                        .get(node_id)
+                        .filter(|_| indexer.supports_plan_deltas);
+                    ApplyIndexingPlanRequest::new(
+                        self.self_node_id.to_string(),
+                        applied_plan.plan_generation,
+                        &applied_plan.indexing_tasks,
+                        base_plan_opt,
+                    )
+                };
+                let already_running_confirmations = self.already_running_confirmations.clone();
+                let applied_plans = self.applied_plans.clone();
                 async move {
-                    if let Err(error) = indexer
-                        .client
-                        .clone()
-                        .apply_indexing_plan(ApplyIndexingPlanRequest { indexing_tasks })
-                        .await
-                    {
-                        warn!(
-                            error=%error,
-                            node_id=%indexer.node_id,
-                            generation_id=indexer.generation_id,
-                            "failed to apply indexing plan to indexer"
-                        );
+                    let apply_plan_result = send_apply_indexing_plan_request(
+                        &indexer,
+                        apply_plan_request,
+                        &applied_plan,
+                    )
+                    .await;
+                    let mut applied_plans_guard =
+                        applied_plans.lock().expect("lock should not be poisoned");
+
+                    match apply_plan_result {
+                        Ok(apply_plan_response) => {
+                            // Requests are sent concurrently, so a plan may be acknowledged after
+                            // a more recent one.
+                            if applied_plans_guard
+                                .get(&indexer.node_id)
+                                .map(|base_plan| base_plan.plan_generation)
+                                < Some(applied_plan.plan_generation)
+                            {
+                                applied_plans_guard.insert(indexer.node_id.clone(), applied_plan);
+                            }
+                            drop(applied_plans_guard);
+                            record_task_outcomes(&indexer, &apply_plan_response);
+
+                            if apply_plan_response.all_tasks_already_running() {
+                                already_running_confirmations
+                                    .lock()
+                                    .expect("lock should not be poisoned")
+                                    .insert(indexer.node_id.clone(), plan_seq);
+                            }
+                        }
+                        Err(error) => {
+                            // The indexer discards its last applied plan on error.
+                            applied_plans_guard.remove(&indexer.node_id);
+                            drop(applied_plans_guard);
+                            warn!(
+                                error=%error,
+                                node_id=%indexer.node_id,
+                                generation_id=indexer.generation_id,
+                                "failed to apply indexing plan to indexer"
+                            );
+                        }
                     }
                     drop(notify_on_drop);
                 }
@@ -396,6 +1117,107 @@ impl IndexingScheduler {
     }
 }
 
+/// Sends an apply indexing plan request to an indexer. If the shard lists of the request are
+/// delta-encoded against a plan the indexer did not apply last, the full plan is sent instead.
+async fn send_apply_indexing_plan_request(
+    indexer: &IndexerNodeInfo,
+    apply_plan_request: ApplyIndexingPlanRequest,
+    applied_plan: &AppliedIndexingPlan,
+) -> IndexingResult<ApplyIndexingPlanResponse> {
+    let is_delta_encoded = apply_plan_request.is_delta_encoded();
+    let control_plane_node_id = apply_plan_request.control_plane_node_id.clone();
+    record_apply_plan_request_bytes(&apply_plan_request);
+
+    match indexer
+        .client
+        .clone()
+        .apply_indexing_plan(apply_plan_request)
+        .await
+    {
+        Err(IndexingError::PlanGenerationMismatch(message)) if is_delta_encoded => {
+            info!(
+                node_id=%indexer.node_id,
+                generation_id=indexer.generation_id,
+                "indexer cannot apply delta-encoded indexing plan, sending full plan: {message}"
+            );
+            let full_apply_plan_request = ApplyIndexingPlanRequest::new(
+                control_plane_node_id,
+                applied_plan.plan_generation,
+                &applied_plan.indexing_tasks,
+                None,
+            );
+            record_apply_plan_request_bytes(&full_apply_plan_request);
+
+            indexer
+                .client
+                .clone()
+                .apply_indexing_plan(full_apply_plan_request)
+                .await
+        }
+        apply_plan_result => apply_plan_result,
+    }
+}
+
+fn record_apply_plan_request_bytes(apply_plan_request: &ApplyIndexingPlanRequest) {
+    let encoding = if apply_plan_request.is_delta_encoded() {
+        "delta"
+    } else {
+        "full"
+    };
+    crate::metrics::CONTROL_PLANE_METRICS
+        .apply_plan_request_bytes
+        .with_label_values([encoding])
+        .observe(apply_plan_request.encoded_len() as f64);
+}
+
+fn task_status_label(status: IndexingTaskStatus) -> &'static str {
+    match status {
+        IndexingTaskStatus::Unspecified => "unspecified",
+        IndexingTaskStatus::Started => "started",
+        IndexingTaskStatus::AlreadyRunning => "already_running",
+        IndexingTaskStatus::ShardUpdateApplied => "shard_update_applied",
+        IndexingTaskStatus::Failed => "failed",
+        IndexingTaskStatus::Promoted => "promoted",
+    }
+}
+
+/// Logs and counts the task outcomes reported by an indexer. Indexers predating task outcomes
+/// return an empty response, which is ignored.
+fn record_task_outcomes(
+    indexer: &IndexerNodeInfo,
+    apply_plan_response: &ApplyIndexingPlanResponse,
+) {
+    let mut num_tasks_per_status: FnvHashMap<IndexingTaskStatus, usize> = FnvHashMap::default();
+
+    for task_outcome in &apply_plan_response.task_outcomes {
+        let status = task_outcome.status();
+        *num_tasks_per_status.entry(status).or_default() += 1;
+
+        if status == IndexingTaskStatus::Failed {
+            warn!(
+                node_id=%indexer.node_id,
+                pipeline_uid=?task_outcome.pipeline_uid,
+                error=task_outcome.error_message.as_deref().unwrap_or_default(),
+                "indexer failed to apply indexing task"
+            );
+        }
+    }
+    if num_tasks_per_status.is_empty() {
+        return;
+    }
+    for (status, num_tasks) in &num_tasks_per_status {
+        crate::metrics::CONTROL_PLANE_METRICS
+            .indexing_task_outcomes_total
+            .with_label_values([task_status_label(*status)])
+            .inc_by(*num_tasks as u64);
+    }
+    debug!(
+        node_id=%indexer.node_id,
+        num_tasks_per_status=?num_tasks_per_status,
+        "applied indexing plan to indexer"
+    );
+}
+
 struct IndexingPlansDiff<'a> {
     pub missing_node_ids: FnvHashSet<&'a str>,
     pub unplanned_node_ids: FnvHashSet<&'a str>,
@@ -425,6 +1247,22 @@ impl<'a> IndexingPlansDiff<'a> {
     pub fn is_empty(&self) -> bool {
         self.has_same_nodes() && self.has_same_tasks()
     }
+
+    /// Returns the IDs of the nodes missing from one of the plans or running different tasks.
+    pub fn divergent_node_ids(&self) -> FnvHashSet<&'a str> {
+        let nodes_with_task_diff = self
+            .missing_tasks_by_node_id
+            .iter()
+            .chain(&self.unplanned_tasks_by_node_id)
+            .filter(|(_, tasks)| !tasks.is_empty())
+            .map(|(node_id, _)| *node_id);
+        self.missing_node_ids
+            .iter()
+            .chain(&self.unplanned_node_ids)
+            .copied()
+            .chain(nodes_with_task_diff)
+            .collect()
+    }
 }
 
 fn get_shard_locality_metrics(
@@ -663,8 +1501,16 @@ mod tests {
     use std::str::FromStr;
 
     use proptest::{prop_compose, proptest};
-    use quickwit_config::{IndexConfig, KafkaSourceParams, SourceConfig, SourceParams};
+    use quickwit_common::uri::Uri;
+    use quickwit_config::{
+        FileSourcePolling, FileSourcePollingOrdering, IndexConfig, IndexerConfig,
+        KafkaSourceParams, SourceConfig, SourceParams, SourcePriority,
+    };
     use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::indexing::{
+        IndexingServiceClient, IndexingTaskOutcome, MockIndexingService,
+        PromoteIndexingPipelineResponse,
+    };
     use quickwit_proto::types::{IndexUid, PipelineUid, ShardId, SourceUid};
 
     use super::*;
@@ -688,6 +1534,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_1b = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(11u128)),
@@ -695,6 +1543,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_2 = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(20u128)),
@@ -702,6 +1552,8 @@ mod tests {
                 source_id: "source-2".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             running_plan.insert(
                 "indexer-1".to_string(),
@@ -723,6 +1575,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_2 = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -730,6 +1584,8 @@ mod tests {
                 source_id: "source-2".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             running_plan.insert("indexer-1".to_string(), vec![task_1.clone()]);
             desired_plan.insert("indexer-1".to_string(), vec![task_2.clone()]);
@@ -757,6 +1613,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_2 = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(2u128)),
@@ -764,6 +1622,8 @@ mod tests {
                 source_id: "source-2".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             running_plan.insert("indexer-2".to_string(), vec![task_2.clone()]);
             desired_plan.insert("indexer-1".to_string(), vec![task_1.clone()]);
@@ -799,6 +1659,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_1b = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(11u128)),
@@ -806,6 +1668,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             let task_1c = IndexingTask {
                 pipeline_uid: Some(PipelineUid::for_test(12u128)),
@@ -813,6 +1677,8 @@ mod tests {
                 source_id: "source-1".to_string(),
                 shard_ids: Vec::new(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             };
             running_plan.insert("indexer-1".to_string(), vec![task_1a.clone()]);
             desired_plan.insert(
@@ -831,6 +1697,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_consume_already_running_confirmations() {
+        let mut indexing_scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from("test-control-plane"),
+            IndexerPool::default(),
+            IndexerConfig::default_max_shards_per_pipeline(),
+        );
+        indexing_scheduler.state.num_applied_physical_indexing_plan = 2;
+
+        let index_uid = IndexUid::from_str("index-1:11111111111111111111111111").unwrap();
+        let task = IndexingTask {
+            pipeline_uid: Some(PipelineUid::for_test(1u128)),
+            index_uid: Some(index_uid),
+            source_id: "source-1".to_string(),
+            shard_ids: Vec::new(),
+            params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
+        };
+        let mut running_plan = FnvHashMap::default();
+        running_plan.insert("indexer-1".to_string(), Vec::new());
+        running_plan.insert("indexer-2".to_string(), Vec::new());
+        let mut desired_plan = FnvHashMap::default();
+        desired_plan.insert("indexer-1".to_string(), vec![task]);
+        desired_plan.insert("indexer-2".to_string(), Vec::new());
+        let indexing_plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        assert!(!indexing_plans_diff.has_same_tasks());
+
+        // No confirmation.
+        assert!(!indexing_scheduler.consume_already_running_confirmations(&indexing_plans_diff));
+
+        // Confirmation of a previous plan.
+        indexing_scheduler
+            .already_running_confirmations
+            .lock()
+            .unwrap()
+            .insert(NodeId::from("indexer-1"), 1);
+        assert!(!indexing_scheduler.consume_already_running_confirmations(&indexing_plans_diff));
+
+        // Confirmation of the current plan: the confirmation is consumed.
+        indexing_scheduler
+            .already_running_confirmations
+            .lock()
+            .unwrap()
+            .insert(NodeId::from("indexer-1"), 2);
+        assert!(indexing_scheduler.consume_already_running_confirmations(&indexing_plans_diff));
+        assert!(!indexing_scheduler.consume_already_running_confirmations(&indexing_plans_diff));
+    }
+
+    #[test]
+    fn test_record_task_outcomes() {
+        let indexer = indexer_for_test("indexer-1", false);
+        let task_outcomes_total =
+            &crate::metrics::CONTROL_PLANE_METRICS.indexing_task_outcomes_total;
+        let num_started_before = task_outcomes_total.with_label_values(["started"]).get();
+        let num_failed_before = task_outcomes_total.with_label_values(["failed"]).get();
+
+        // Indexers predating task outcomes return an empty response.
+        record_task_outcomes(&indexer, &ApplyIndexingPlanResponse::default());
+
+        let apply_plan_response = ApplyIndexingPlanResponse {
+            task_outcomes: vec![
+                IndexingTaskOutcome::new(PipelineUid::for_test(1u128), IndexingTaskStatus::Started),
+                IndexingTaskOutcome::new(PipelineUid::for_test(2u128), IndexingTaskStatus::Started),
+                IndexingTaskOutcome::failed(PipelineUid::for_test(3u128), "source not found"),
+            ],
+        };
+        record_task_outcomes(&indexer, &apply_plan_response);
+
+        assert!(task_outcomes_total.with_label_values(["started"]).get() >= num_started_before + 2);
+        assert!(task_outcomes_total.with_label_values(["failed"]).get() > num_failed_before);
+    }
+
     #[test]
     fn test_get_sources_to_schedule() {
         let mut model = ControlPlaneModel::default();
@@ -850,6 +1790,10 @@ mod tests {
                     source_id: "source_disabled".to_string(),
                     num_pipelines: NonZeroUsize::new(3).unwrap(),
                     enabled: false,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
                     input_format: Default::default(),
@@ -863,6 +1807,10 @@ mod tests {
                     source_id: "source_enabled".to_string(),
                     num_pipelines: NonZeroUsize::new(2).unwrap(),
                     enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
                     input_format: Default::default(),
@@ -876,6 +1824,10 @@ mod tests {
                     source_id: "ingest_v1".to_string(),
                     num_pipelines: NonZeroUsize::new(2).unwrap(),
                     enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     // ingest v1
                     source_params: SourceParams::IngestApi,
                     transform_config: None,
@@ -890,6 +1842,10 @@ mod tests {
                     source_id: "ingest_v2".to_string(),
                     num_pipelines: NonZeroUsize::new(2).unwrap(),
                     enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     // ingest v2
                     source_params: SourceParams::Ingest,
                     transform_config: None,
@@ -905,6 +1861,10 @@ mod tests {
                     source_id: "ingest_v2_without_shard".to_string(),
                     num_pipelines: NonZeroUsize::new(2).unwrap(),
                     enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     // ingest v2
                     source_params: SourceParams::Ingest,
                     transform_config: None,
@@ -919,6 +1879,10 @@ mod tests {
                     source_id: "ingest_cli".to_string(),
                     num_pipelines: NonZeroUsize::new(2).unwrap(),
                     enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
                     // ingest v1
                     source_params: SourceParams::IngestCli,
                     transform_config: None,
@@ -934,8 +1898,55 @@ mod tests {
             ..Default::default()
         };
         model.insert_shards(&index_uid, &"ingest_v2".to_string(), vec![shard]);
-        let shards: Vec<SourceToSchedule> = get_sources_to_schedule(&model);
+        let shards: Vec<SourceToSchedule> =
+            get_sources_to_schedule(&model, IndexerConfig::default_max_shards_per_pipeline());
         assert_eq!(shards.len(), 3);
+
+        model.set_ingest_blocked(&index_uid, true).unwrap();
+        let shards: Vec<SourceToSchedule> =
+            get_sources_to_schedule(&model, IndexerConfig::default_max_shards_per_pipeline());
+        assert!(shards.is_empty());
+    }
+
+    #[test]
+    fn test_get_sources_to_schedule_polling_file_source_runs_single_pipeline() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(
+                &index_uid,
+                SourceConfig {
+                    source_id: "polling-source".to_string(),
+                    num_pipelines: NonZeroUsize::new(3).unwrap(),
+                    enabled: true,
+                    critical: false,
+                    priority: SourcePriority::Normal,
+                    placement_selector: None,
+                    max_shards_per_pipeline: None,
+                    source_params: SourceParams::File(FileSourceParams::Polling(
+                        FileSourcePolling {
+                            prefix: Uri::for_test("s3://my-bucket/logs/"),
+                            poll_interval_secs: 30,
+                            ordering: FileSourcePollingOrdering::Lexicographic,
+                        },
+                    )),
+                    transform_config: None,
+                    input_format: Default::default(),
+                },
+            )
+            .unwrap();
+        let sources =
+            get_sources_to_schedule(&model, IndexerConfig::default_max_shards_per_pipeline());
+        assert_eq!(sources.len(), 1);
+        assert!(matches!(
+            sources[0].source_type,
+            SourceToScheduleType::NonSharded {
+                num_pipelines: 1,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -956,6 +1967,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             },
             SourceToSchedule {
                 source_uid: source_2.clone(),
@@ -964,6 +1978,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             },
         ];
         let mut indexer_max_loads = FnvHashMap::default();
@@ -979,6 +1996,521 @@ mod tests {
         assert_eq!(indexer_2_tasks.len(), 3);
     }
 
+    fn indexer_for_test(node_id: &str, is_draining: bool) -> IndexerNodeInfo {
+        IndexerNodeInfo {
+            node_id: NodeId::from(node_id),
+            generation_id: 0,
+            client: IndexingServiceClient::from_mock(MockIndexingService::new()),
+            indexing_tasks: Vec::new(),
+            indexing_capacity: mcpu(4_000),
+            is_draining,
+            supports_plan_deltas: false,
+            num_unhealthy_pipelines: 0,
+            indexing_affinity_labels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_physical_indexing_plan_with_draining_indexer() {
+        let source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source1".to_string(),
+        };
+        let sources = vec![SourceToSchedule {
+            source_uid,
+            source_type: SourceToScheduleType::NonSharded {
+                num_pipelines: 4,
+                load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+            },
+            params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
+        }];
+        let shard_locations = ShardLocations::default();
+
+        let indexers = vec![
+            indexer_for_test("indexer1", false),
+            indexer_for_test("indexer2", false),
+        ];
+        let indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
+        let previous_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        assert!(!previous_plan.indexer("indexer2").unwrap().is_empty());
+
+        // `indexer2` starts draining: its tasks must move to `indexer1`.
+        let indexers = vec![
+            indexer_for_test("indexer1", false),
+            indexer_for_test("indexer2", true),
+        ];
+        let indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
+        assert_eq!(indexer_id_to_cpu_capacities.len(), 1);
+
+        let mut new_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&previous_plan),
+            &shard_locations,
+        );
+        add_draining_indexers_to_plan(&mut new_plan, &indexers);
+
+        assert_eq!(new_plan.indexer("indexer1").unwrap().len(), 4);
+        // The draining indexer is part of the plan with no tasks, so that applying the plan
+        // shuts down its pipelines.
+        assert!(new_plan.indexer("indexer2").unwrap().is_empty());
+
+        let plans_diff = get_indexing_plans_diff(
+            previous_plan.indexing_tasks_per_indexer(),
+            new_plan.indexing_tasks_per_indexer(),
+        );
+        assert!(plans_diff.has_same_nodes());
+        assert!(!plans_diff.has_same_tasks());
+
+        // Rebuilding the plan while the indexer is still draining places nothing new on it.
+        let mut next_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&new_plan),
+            &shard_locations,
+        );
+        add_draining_indexers_to_plan(&mut next_plan, &indexers);
+        assert_eq!(next_plan.indexer("indexer1").unwrap().len(), 4);
+        assert!(next_plan.indexer("indexer2").unwrap().is_empty());
+    }
+
+    fn task_for_test(source_uid: &SourceUid, pipeline_ord: u128, standby: bool) -> IndexingTask {
+        IndexingTask {
+            index_uid: Some(source_uid.index_uid.clone()),
+            source_id: source_uid.source_id.clone(),
+            pipeline_uid: Some(PipelineUid::for_test(pipeline_ord)),
+            shard_ids: Vec::new(),
+            params_fingerprint: 0,
+            standby,
+            priority: IndexingTaskPriority::Normal as i32,
+        }
+    }
+
+    #[test]
+    fn test_add_standby_tasks_to_plan() {
+        let critical_source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source-1".to_string(),
+        };
+        let other_source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source-2".to_string(),
+        };
+        let critical_sources = FnvHashSet::from_iter([critical_source_uid.clone()]);
+        let indexers = vec![
+            indexer_for_test("indexer1", false),
+            indexer_for_test("indexer2", false),
+            indexer_for_test("indexer3", false),
+            indexer_for_test("indexer4", true),
+        ];
+        let indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
+
+        let mut physical_plan = PhysicalIndexingPlan::with_indexer_ids(&[
+            "indexer1".to_string(),
+            "indexer2".to_string(),
+            "indexer3".to_string(),
+        ]);
+        physical_plan.add_indexing_task("indexer1", task_for_test(&critical_source_uid, 1, false));
+        physical_plan.add_indexing_task("indexer1", task_for_test(&critical_source_uid, 2, false));
+        physical_plan.add_indexing_task("indexer2", task_for_test(&other_source_uid, 3, false));
+        let primary_plan = physical_plan.clone();
+
+        add_standby_tasks_to_plan(
+            &mut physical_plan,
+            &critical_sources,
+            &FnvHashMap::default(),
+            &indexer_id_to_cpu_capacities,
+            None,
+        );
+        // One standby per task of the critical source, on the least loaded indexers that do not
+        // run a task of the source. Draining indexers are not eligible.
+        assert_eq!(physical_plan.indexer("indexer1").unwrap().len(), 2);
+
+        let standby_tasks_2: Vec<&IndexingTask> = physical_plan
+            .indexer("indexer2")
+            .unwrap()
+            .iter()
+            .filter(|task| task.standby)
+            .collect();
+        assert_eq!(standby_tasks_2.len(), 1);
+        assert_eq!(standby_tasks_2[0].source_id, "source-1");
+
+        let standby_tasks_3 = physical_plan.indexer("indexer3").unwrap();
+        assert_eq!(standby_tasks_3.len(), 1);
+        assert!(standby_tasks_3[0].standby);
+        assert!(physical_plan.indexer("indexer4").is_none());
+
+        assert_eq!(physical_plan.without_standby_tasks(), primary_plan);
+
+        // Rebuilding the plan keeps the standby tasks in place.
+        let mut next_plan = primary_plan.clone();
+        add_standby_tasks_to_plan(
+            &mut next_plan,
+            &critical_sources,
+            &FnvHashMap::default(),
+            &indexer_id_to_cpu_capacities,
+            Some(&physical_plan),
+        );
+        physical_plan.normalize();
+        next_plan.normalize();
+        assert_eq!(next_plan, physical_plan);
+
+        // Without another indexer, no standby task can be placed.
+        let indexer_id_to_cpu_capacities =
+            get_indexer_id_to_cpu_capacities(&[indexer_for_test("indexer1", false)]);
+        let mut single_indexer_plan = PhysicalIndexingPlan::with_indexer_ids(&[]);
+        single_indexer_plan
+            .add_indexing_task("indexer1", task_for_test(&critical_source_uid, 1, false));
+        let expected_plan = single_indexer_plan.clone();
+        add_standby_tasks_to_plan(
+            &mut single_indexer_plan,
+            &critical_sources,
+            &FnvHashMap::default(),
+            &indexer_id_to_cpu_capacities,
+            None,
+        );
+        assert_eq!(single_indexer_plan, expected_plan);
+    }
+
+    fn labeled_indexer_for_test(node_id: &str, labels: &[(&str, &str)]) -> IndexerNodeInfo {
+        IndexerNodeInfo {
+            indexing_affinity_labels: labels
+                .iter()
+                .map(|(label_key, label_value)| (label_key.to_string(), label_value.to_string()))
+                .collect(),
+            ..indexer_for_test(node_id, false)
+        }
+    }
+
+    fn placement_selector_for_test(labels: &[(&str, &str)]) -> PlacementSelector {
+        PlacementSelector {
+            match_labels: labels
+                .iter()
+                .map(|(label_key, label_value)| (label_key.to_string(), label_value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_apply_placement_selectors() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let kafka_source_params = KafkaSourceParams {
+            topic: "kafka-topic".to_string(),
+            client_log_level: None,
+            client_params: serde_json::json!({}),
+            enable_backfill_mode: false,
+        };
+        let placement_selectors = [
+            ("source-free", None),
+            (
+                "source-us",
+                Some(placement_selector_for_test(&[("zone", "us-east-1a")])),
+            ),
+            (
+                "source-gpu",
+                Some(placement_selector_for_test(&[
+                    ("zone", "us-east-1a"),
+                    ("gpu", "true"),
+                ])),
+            ),
+            (
+                "source-eu",
+                Some(placement_selector_for_test(&[("zone", "eu-west-1a")])),
+            ),
+        ];
+        for (source_id, placement_selector) in placement_selectors {
+            model
+                .add_source(
+                    &index_uid,
+                    SourceConfig {
+                        source_id: source_id.to_string(),
+                        num_pipelines: NonZeroUsize::new(1).unwrap(),
+                        enabled: true,
+                        critical: false,
+                        priority: SourcePriority::Normal,
+                        placement_selector,
+                        max_shards_per_pipeline: None,
+                        source_params: SourceParams::Kafka(kafka_source_params.clone()),
+                        transform_config: None,
+                        input_format: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+        // Only some of the indexers are labeled, and the one carrying all the labels of
+        // `source-gpu` is draining.
+        let indexers = vec![
+            indexer_for_test("indexer-1", false),
+            labeled_indexer_for_test("indexer-2", &[("zone", "us-east-1a")]),
+            labeled_indexer_for_test("indexer-3", &[("zone", "us-east-1a"), ("rack", "r1")]),
+            IndexerNodeInfo {
+                is_draining: true,
+                ..labeled_indexer_for_test("indexer-4", &[("zone", "us-east-1a"), ("gpu", "true")])
+            },
+            labeled_indexer_for_test("indexer-5", &[("zone", "eu-west-1a")]),
+        ];
+        let indexer_id_to_cpu_capacities = get_indexer_id_to_cpu_capacities(&indexers);
+
+        let mut sources =
+            get_sources_to_schedule(&model, IndexerConfig::default_max_shards_per_pipeline());
+        let unscheduled_sources = apply_placement_selectors(
+            &model,
+            &mut sources,
+            &indexers,
+            &indexer_id_to_cpu_capacities,
+        );
+        assert_eq!(
+            unscheduled_sources,
+            [UnscheduledSource {
+                index_uid: index_uid.clone(),
+                source_id: "source-gpu".to_string(),
+                placement_selector: placement_selector_for_test(&[
+                    ("zone", "us-east-1a"),
+                    ("gpu", "true"),
+                ]),
+            }]
+        );
+        let eligible_indexer_ids: FnvHashMap<&str, Option<Vec<&str>>> = sources
+            .iter()
+            .map(|source| {
+                let eligible_indexer_ids_opt = source
+                    .eligible_indexer_ids
+                    .as_ref()
+                    .map(|indexer_ids| indexer_ids.iter().map(String::as_str).sorted().collect());
+                (
+                    source.source_uid.source_id.as_str(),
+                    eligible_indexer_ids_opt,
+                )
+            })
+            .collect();
+        assert_eq!(eligible_indexer_ids.len(), 3);
+        assert_eq!(eligible_indexer_ids["source-free"], None);
+        assert_eq!(
+            eligible_indexer_ids["source-us"],
+            Some(vec!["indexer-2", "indexer-3"])
+        );
+        assert_eq!(eligible_indexer_ids["source-eu"], Some(vec!["indexer-5"]));
+
+        // The indexers matching the selector of a critical source are the only candidates for its
+        // standby tasks.
+        let source_uid = SourceUid {
+            index_uid,
+            source_id: "source-us".to_string(),
+        };
+        let mut physical_plan = PhysicalIndexingPlan::with_indexer_ids(&[]);
+        physical_plan.add_indexing_task("indexer-2", task_for_test(&source_uid, 1, false));
+        add_standby_tasks_to_plan(
+            &mut physical_plan,
+            &FnvHashSet::from_iter([source_uid]),
+            &get_eligible_indexer_ids_per_source(&sources),
+            &indexer_id_to_cpu_capacities,
+            None,
+        );
+        let standby_tasks = physical_plan.indexer("indexer-3").unwrap();
+        assert_eq!(standby_tasks.len(), 1);
+        assert!(standby_tasks[0].standby);
+    }
+
+    #[tokio::test]
+    async fn test_promote_standby_pipelines() {
+        let source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source-1".to_string(),
+        };
+        let (promote_tx, mut promote_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut mock_indexer = MockIndexingService::new();
+        mock_indexer
+            .expect_promote_indexing_pipeline()
+            .once()
+            .returning(move |promote_request| {
+                promote_tx.send(promote_request.pipeline_uid()).unwrap();
+                Ok(PromoteIndexingPipelineResponse { warm: true })
+            });
+        let indexer_pool = IndexerPool::default();
+        let mut indexer2 = indexer_for_test("indexer2", false);
+        indexer2.client = IndexingServiceClient::from_mock(mock_indexer);
+        indexer_pool.insert(indexer2.node_id.clone(), indexer2);
+
+        let mut indexing_scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from("test-control-plane"),
+            indexer_pool,
+            IndexerConfig::default_max_shards_per_pipeline(),
+        );
+        let mut last_applied_plan = PhysicalIndexingPlan::with_indexer_ids(&[]);
+        last_applied_plan.add_indexing_task("indexer1", task_for_test(&source_uid, 1, false));
+        last_applied_plan.add_indexing_task("indexer2", task_for_test(&source_uid, 2, true));
+        indexing_scheduler.state.last_applied_physical_plan = Some(last_applied_plan);
+
+        indexing_scheduler.promote_standby_pipelines(NodeIdRef::from_str("indexer1"));
+
+        let last_applied_plan = indexing_scheduler
+            .state
+            .last_applied_physical_plan
+            .as_ref()
+            .unwrap();
+        assert!(last_applied_plan.indexer("indexer1").is_none());
+        assert_eq!(
+            last_applied_plan.indexer("indexer2").unwrap(),
+            [task_for_test(&source_uid, 2, false)]
+        );
+        let promoted_pipeline_uid = promote_rx.recv().await.unwrap();
+        assert_eq!(promoted_pipeline_uid, PipelineUid::for_test(2u128));
+
+        // Promoting again for an indexer absent from the plan is a no-op.
+        indexing_scheduler.promote_standby_pipelines(NodeIdRef::from_str("indexer1"));
+    }
+
+    #[tokio::test]
+    async fn test_watch_indexing_plan() {
+        let source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source-1".to_string(),
+        };
+        let mut mock_indexer = MockIndexingService::new();
+        mock_indexer
+            .expect_promote_indexing_pipeline()
+            .returning(|_| Ok(PromoteIndexingPipelineResponse { warm: true }));
+        let indexer_pool = IndexerPool::default();
+        let mut indexer2 = indexer_for_test("indexer2", false);
+        indexer2.client = IndexingServiceClient::from_mock(mock_indexer);
+        indexer_pool.insert(indexer2.node_id.clone(), indexer2);
+
+        let mut indexing_scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from("test-control-plane"),
+            indexer_pool,
+            IndexerConfig::default_max_shards_per_pipeline(),
+        );
+        let mut last_applied_plan = PhysicalIndexingPlan::with_indexer_ids(&[]);
+        last_applied_plan.add_indexing_task("indexer1", task_for_test(&source_uid, 1, false));
+        last_applied_plan.add_indexing_task("indexer2", task_for_test(&source_uid, 2, true));
+        indexing_scheduler.state.last_applied_physical_plan = Some(last_applied_plan.clone());
+        indexing_scheduler.publish_last_applied_plan(Some(Vec::new()));
+
+        let mut watcher = indexing_scheduler.watch_indexing_plan();
+        let IndexingPlanEvent::Snapshot(snapshot) = watcher.next_event().await.unwrap() else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(snapshot.plan_version, 1);
+        assert_eq!(snapshot.plan.unwrap(), last_applied_plan);
+
+        indexing_scheduler.promote_standby_pipelines(NodeIdRef::from_str("indexer1"));
+
+        let IndexingPlanEvent::Diff { plan_version, diff } = watcher.next_event().await.unwrap()
+        else {
+            panic!("expected diff");
+        };
+        assert_eq!(plan_version, 2);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(
+            diff["indexer1"].removed_tasks,
+            [task_for_test(&source_uid, 1, false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_physical_indexing_plan_with_shard_deltas() {
+        let source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "source-1".to_string(),
+        };
+        let (request_tx, mut request_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut mock_indexer = MockIndexingService::new();
+        mock_indexer
+            .expect_apply_indexing_plan()
+            .times(3)
+            .returning(move |apply_plan_request: ApplyIndexingPlanRequest| {
+                let is_delta_encoded = apply_plan_request.is_delta_encoded();
+                request_tx.send(apply_plan_request).unwrap();
+
+                if is_delta_encoded {
+                    // The indexer lost its last applied plan, say, after a restart.
+                    Err(IndexingError::PlanGenerationMismatch(
+                        "no plan applied yet".to_string(),
+                    ))
+                } else {
+                    Ok(ApplyIndexingPlanResponse::default())
+                }
+            });
+        let indexer_pool = IndexerPool::default();
+        let mut indexer = indexer_for_test("indexer1", false);
+        indexer.client = IndexingServiceClient::from_mock(mock_indexer);
+        indexer.supports_plan_deltas = true;
+        indexer_pool.insert(indexer.node_id.clone(), indexer.clone());
+
+        let mut indexing_scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from("test-control-plane"),
+            indexer_pool,
+            IndexerConfig::default_max_shards_per_pipeline(),
+        );
+        let physical_plan = |shard_ids: std::ops::Range<u64>| {
+            let mut indexing_task = task_for_test(&source_uid, 1, false);
+            indexing_task.shard_ids = shard_ids.map(ShardId::from).collect();
+            let mut physical_plan =
+                PhysicalIndexingPlan::with_indexer_ids(&["indexer1".to_string()]);
+            physical_plan.add_indexing_task("indexer1", indexing_task);
+            physical_plan
+        };
+        let indexers = [indexer];
+        indexing_scheduler.apply_physical_indexing_plan(&indexers, physical_plan(0..10), None);
+
+        let apply_plan_request = request_rx.recv().await.unwrap();
+        assert_eq!(apply_plan_request.plan_generation, 1);
+        assert!(!apply_plan_request.is_delta_encoded());
+        assert_eq!(apply_plan_request.indexing_tasks[0].shard_ids.len(), 10);
+
+        let applied_plans = indexing_scheduler.applied_plans.clone();
+        let applied_plan_generation = || {
+            applied_plans
+                .lock()
+                .unwrap()
+                .get(&indexers[0].node_id)
+                .map(|applied_plan| applied_plan.plan_generation)
+        };
+        while applied_plan_generation() != Some(1) {
+            tokio::task::yield_now().await;
+        }
+        indexing_scheduler.apply_physical_indexing_plan(&indexers, physical_plan(1..12), None);
+
+        let apply_plan_request = request_rx.recv().await.unwrap();
+        assert_eq!(apply_plan_request.plan_generation, 2);
+        assert_eq!(apply_plan_request.base_plan_generation, Some(1));
+        assert!(apply_plan_request.indexing_tasks[0].shard_ids.is_empty());
+
+        let shard_delta = &apply_plan_request.shard_deltas[0];
+        assert_eq!(
+            shard_delta.added_shard_ids,
+            [ShardId::from(10), ShardId::from(11)]
+        );
+        assert_eq!(shard_delta.removed_shard_ids, [ShardId::from(0)]);
+
+        // The indexer rejected the delta, so the full plan is sent.
+        let apply_plan_request = request_rx.recv().await.unwrap();
+        assert_eq!(apply_plan_request.plan_generation, 2);
+        assert!(!apply_plan_request.is_delta_encoded());
+        assert_eq!(
+            apply_plan_request.indexing_tasks[0].shard_ids,
+            (1..12).map(ShardId::from).collect::<Vec<_>>()
+        );
+        while applied_plan_generation() != Some(2) {
+            tokio::task::yield_now().await;
+        }
+    }
+
     #[test]
     fn test_debug_indexing_task_map() {
         let mut map = FnvHashMap::default();
@@ -988,6 +2520,8 @@ mod tests {
             pipeline_uid: Some(PipelineUid::random()),
             shard_ids: vec!["shard1".into()],
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let task2 = IndexingTask {
             index_uid: Some(IndexUid::for_test("index2", 123)),
@@ -995,6 +2529,8 @@ mod tests {
             pipeline_uid: Some(PipelineUid::random()),
             shard_ids: vec!["shard2".into(), "shard3".into()],
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let task3 = IndexingTask {
             index_uid: Some(IndexUid::for_test("index3", 123)),
@@ -1002,6 +2538,8 @@ mod tests {
             pipeline_uid: Some(PipelineUid::random()),
             shard_ids: vec!["shard6".into()],
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         // order made to map with the debug for lisibility
         map.insert("indexer5", vec![&task2]);
@@ -1040,7 +2578,7 @@ mod tests {
                 model.add_source(index_uid, source_config.clone()).unwrap();
             }
 
-            let sources: Vec<SourceToSchedule> = get_sources_to_schedule(&model);
+            let sources: Vec<SourceToSchedule> = get_sources_to_schedule(&model, IndexerConfig::default_max_shards_per_pipeline());
             let mut indexer_max_loads = FnvHashMap::default();
             for i in 0..num_indexers {
                 let indexer_id = format!("indexer-{i}");
@@ -1051,7 +2589,7 @@ mod tests {
         }
     }
 
-    use quickwit_config::SourceInputFormat;
+    use quickwit_config::{SourceInputFormat, SourcePriority};
     use quickwit_proto::indexing::mcpu;
     use quickwit_proto::ingest::{Shard, ShardState};
 
@@ -1075,6 +2613,10 @@ mod tests {
               source_id,
               num_pipelines: NonZeroUsize::new(num_pipelines).unwrap(),
               enabled: true,
+              critical: false,
+              priority: SourcePriority::Normal,
+              placement_selector: None,
+              max_shards_per_pipeline: None,
               source_params: kafka_source_params_for_test(),
               transform_config: None,
               input_format: SourceInputFormat::Json,
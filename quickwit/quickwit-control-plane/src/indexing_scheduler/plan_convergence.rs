@@ -0,0 +1,189 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use fnv::FnvHashMap;
+
+use super::IndexingPlansDiff;
+
+/// Nodes whose running tasks differ from the desired plan for less than this duration are not
+/// reported as divergent: indexers need some time to spawn their pipelines and to advertise them
+/// via chitchat.
+pub(crate) const DIVERGENCE_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Tracks how long the cluster takes to converge to the last desired indexing plan, and which
+/// nodes keep running tasks that differ from it.
+///
+/// The tracker is fed the diff between the running plan observed via chitchat and the last
+/// applied plan every time the scheduler controls the running plan, so the measured durations
+/// have the granularity of the control loop.
+#[derive(Debug, Default)]
+pub(crate) struct PlanConvergenceTracker {
+    // Instant at which the last desired plan was applied, reset once the cluster converges.
+    pending_plan_applied_at: Option<Instant>,
+    // Instant since which the running tasks of each divergent node differ from the plan.
+    divergent_since: FnvHashMap<String, Instant>,
+}
+
+impl PlanConvergenceTracker {
+    /// Records that a new desired plan was applied at `now`.
+    pub fn record_new_plan(&mut self, now: Instant) {
+        self.pending_plan_applied_at = Some(now);
+    }
+
+    /// Updates the divergent nodes with the diff between the running plan and the last applied
+    /// plan observed at `now`.
+    ///
+    /// Returns the time elapsed since the last desired plan was applied if the running plan just
+    /// converged to it.
+    pub fn observe(&mut self, plans_diff: &IndexingPlansDiff, now: Instant) -> Option<Duration> {
+        let divergent_node_ids = plans_diff.divergent_node_ids();
+        self.divergent_since
+            .retain(|node_id, _| divergent_node_ids.contains(node_id.as_str()));
+
+        for node_id in divergent_node_ids {
+            self.divergent_since
+                .entry(node_id.to_string())
+                .or_insert(now);
+        }
+        if !self.divergent_since.is_empty() {
+            return None;
+        }
+        let plan_applied_at = self.pending_plan_applied_at.take()?;
+        Some(now.saturating_duration_since(plan_applied_at))
+    }
+
+    /// Returns the number of nodes whose running tasks have differed from the desired plan for
+    /// more than `grace_period`.
+    pub fn num_divergent_nodes(&self, now: Instant, grace_period: Duration) -> usize {
+        self.divergent_since
+            .values()
+            .filter(|divergent_since| {
+                now.saturating_duration_since(**divergent_since) > grace_period
+            })
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fnv::FnvHashMap;
+    use quickwit_proto::indexing::IndexingTask;
+    use quickwit_proto::types::{IndexUid, PipelineUid};
+
+    use super::*;
+    use crate::indexing_scheduler::get_indexing_plans_diff;
+
+    fn indexing_task(source_id: &str, pipeline_ord: u128) -> IndexingTask {
+        IndexingTask {
+            index_uid: Some(IndexUid::for_test("test-index", 0)),
+            source_id: source_id.to_string(),
+            pipeline_uid: Some(PipelineUid::for_test(pipeline_ord)),
+            ..Default::default()
+        }
+    }
+
+    fn plan(tasks_per_node: &[(&str, &[IndexingTask])]) -> FnvHashMap<String, Vec<IndexingTask>> {
+        tasks_per_node
+            .iter()
+            .map(|(node_id, tasks)| (node_id.to_string(), tasks.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_convergence_tracker() {
+        // A fake clock, advanced by hand.
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        let task_a = indexing_task("source-a", 1);
+        let task_b = indexing_task("source-b", 2);
+        let desired_plan = plan(&[
+            ("indexer-1", &[task_a.clone()]),
+            ("indexer-2", &[task_b.clone()]),
+        ]);
+        let mut tracker = PlanConvergenceTracker::default();
+        tracker.record_new_plan(at(0));
+
+        // No indexer runs its tasks yet.
+        let running_plan = plan(&[("indexer-1", &[]), ("indexer-2", &[])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        assert_eq!(tracker.observe(&plans_diff, at(3)), None);
+        assert_eq!(
+            tracker.num_divergent_nodes(at(3), DIVERGENCE_GRACE_PERIOD),
+            0
+        );
+
+        // `indexer-1` converged, `indexer-2` is still lagging behind past the grace period.
+        let running_plan = plan(&[("indexer-1", &[task_a.clone()]), ("indexer-2", &[])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        assert_eq!(tracker.observe(&plans_diff, at(70)), None);
+        assert_eq!(
+            tracker.num_divergent_nodes(at(70), DIVERGENCE_GRACE_PERIOD),
+            1
+        );
+
+        // The whole cluster converged.
+        let running_plan = plan(&[("indexer-1", &[task_a]), ("indexer-2", &[task_b])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        assert_eq!(
+            tracker.observe(&plans_diff, at(75)),
+            Some(Duration::from_secs(75))
+        );
+        assert_eq!(
+            tracker.num_divergent_nodes(at(75), DIVERGENCE_GRACE_PERIOD),
+            0
+        );
+
+        // The convergence of a plan is only recorded once.
+        assert_eq!(tracker.observe(&plans_diff, at(80)), None);
+    }
+
+    #[test]
+    fn test_plan_convergence_tracker_divergence_restarts_after_convergence() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        let task_a = indexing_task("source-a", 1);
+        let desired_plan = plan(&[("indexer-1", &[task_a.clone()])]);
+        let mut tracker = PlanConvergenceTracker::default();
+
+        let running_plan = plan(&[("indexer-1", &[])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        tracker.observe(&plans_diff, at(0));
+
+        let running_plan = plan(&[("indexer-1", &[task_a])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        tracker.observe(&plans_diff, at(10));
+
+        // The node diverges again: its grace period starts over.
+        let running_plan = plan(&[("indexer-1", &[])]);
+        let plans_diff = get_indexing_plans_diff(&running_plan, &desired_plan);
+        tracker.observe(&plans_diff, at(20));
+        assert_eq!(
+            tracker.num_divergent_nodes(at(70), DIVERGENCE_GRACE_PERIOD),
+            0
+        );
+        assert_eq!(
+            tracker.num_divergent_nodes(at(81), DIVERGENCE_GRACE_PERIOD),
+            1
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch};
+use tracing::debug;
+
+use super::dry_run::{diff_physical_indexing_plans, IndexingPlanDiff};
+use super::scheduling::SourceToSchedule;
+use crate::indexing_plan::PhysicalIndexingPlan;
+
+/// Maximum number of plan changes buffered for a watcher. A watcher falling further behind is
+/// sent a snapshot of the plan instead of the changes it missed.
+pub(crate) const INDEXING_PLAN_EVENTS_CAPACITY: usize = 32;
+
+/// Physical indexing plan, sent to a watcher when it subscribes or falls behind.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexingPlanSnapshot {
+    /// Version of the plan, incremented every time the plan changes.
+    pub plan_version: u64,
+    /// `None` until the scheduler applies its first plan.
+    pub plan: Option<PhysicalIndexingPlan>,
+}
+
+/// Event emitted to the watchers of the physical indexing plan.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexingPlanEvent {
+    Snapshot(IndexingPlanSnapshot),
+    /// Changes between the plans of versions `plan_version - 1` and `plan_version`.
+    Diff {
+        plan_version: u64,
+        diff: IndexingPlanDiff,
+    },
+}
+
+impl IndexingPlanEvent {
+    pub fn plan_version(&self) -> u64 {
+        match self {
+            IndexingPlanEvent::Snapshot(snapshot) => snapshot.plan_version,
+            IndexingPlanEvent::Diff { plan_version, .. } => *plan_version,
+        }
+    }
+}
+
+/// Publishes the changes of the physical indexing plan to its watchers.
+///
+/// Publishing never waits for the watchers: the changes go through a bounded broadcast channel,
+/// and a watcher that falls behind by more than its capacity resyncs from the latest snapshot.
+pub(crate) struct IndexingPlanPublisher {
+    snapshot_tx: watch::Sender<IndexingPlanSnapshot>,
+    event_tx: broadcast::Sender<IndexingPlanEvent>,
+    // Sources the current plan was built for, used to compute the CPU loads of the diffs.
+    sources: Vec<SourceToSchedule>,
+}
+
+impl IndexingPlanPublisher {
+    pub fn new(capacity: usize) -> Self {
+        let snapshot = IndexingPlanSnapshot {
+            plan_version: 0,
+            plan: None,
+        };
+        let (snapshot_tx, _) = watch::channel(snapshot);
+        let (event_tx, _) = broadcast::channel(capacity);
+
+        Self {
+            snapshot_tx,
+            event_tx,
+            sources: Vec::new(),
+        }
+    }
+
+    /// Publishes a new plan, built for `new_sources_opt` or for the sources of the current plan
+    /// if `None`.
+    pub fn publish(
+        &mut self,
+        new_plan: &PhysicalIndexingPlan,
+        new_sources_opt: Option<Vec<SourceToSchedule>>,
+    ) {
+        let current_snapshot = self.snapshot_tx.borrow().clone();
+        let plan_version = current_snapshot.plan_version + 1;
+
+        // Diffs are only computed for the watchers.
+        let diff_opt = if self.event_tx.receiver_count() > 0 {
+            let current_plan = current_snapshot
+                .plan
+                .unwrap_or_else(|| PhysicalIndexingPlan::with_indexer_ids(&[]));
+            let new_sources = new_sources_opt.as_deref().unwrap_or(&self.sources);
+            let mut diff =
+                diff_physical_indexing_plans(&current_plan, &self.sources, new_plan, new_sources);
+            diff.retain(|_, node_diff| !node_diff.is_empty());
+            Some(diff)
+        } else {
+            None
+        };
+        if let Some(new_sources) = new_sources_opt {
+            self.sources = new_sources;
+        }
+        // The snapshot is updated before the diff is sent so that a watcher resyncing in between
+        // skips the diff.
+        self.snapshot_tx.send_replace(IndexingPlanSnapshot {
+            plan_version,
+            plan: Some(new_plan.clone()),
+        });
+        if let Some(diff) = diff_opt {
+            // Sending only fails when all the watchers are gone.
+            let _ = self
+                .event_tx
+                .send(IndexingPlanEvent::Diff { plan_version, diff });
+        }
+    }
+
+    pub fn subscribe(&self) -> IndexingPlanWatcher {
+        IndexingPlanWatcher {
+            snapshot_rx: self.snapshot_tx.subscribe(),
+            event_rx: self.event_tx.subscribe(),
+            plan_version_opt: None,
+        }
+    }
+}
+
+/// Watches the physical indexing plan: yields a snapshot of the plan first, then the diff of
+/// every subsequent plan.
+pub struct IndexingPlanWatcher {
+    snapshot_rx: watch::Receiver<IndexingPlanSnapshot>,
+    event_rx: broadcast::Receiver<IndexingPlanEvent>,
+    // Version of the last plan sent to the watcher, `None` if the watcher needs a snapshot.
+    plan_version_opt: Option<u64>,
+}
+
+impl IndexingPlanWatcher {
+    /// Returns the next event, or `None` once the scheduler is gone.
+    pub async fn next_event(&mut self) -> Option<IndexingPlanEvent> {
+        loop {
+            let Some(plan_version) = self.plan_version_opt else {
+                let snapshot = self.snapshot_rx.borrow_and_update().clone();
+                self.plan_version_opt = Some(snapshot.plan_version);
+                return Some(IndexingPlanEvent::Snapshot(snapshot));
+            };
+            match self.event_rx.recv().await {
+                Ok(event) => {
+                    // Diffs already reflected in the last snapshot are skipped.
+                    if event.plan_version() <= plan_version {
+                        continue;
+                    }
+                    self.plan_version_opt = Some(event.plan_version());
+                    return Some(event);
+                }
+                Err(RecvError::Lagged(num_missed_events)) => {
+                    debug!(
+                        num_missed_events,
+                        "indexing plan watcher fell behind, resyncing from a snapshot"
+                    );
+                    crate::metrics::CONTROL_PLANE_METRICS
+                        .indexing_plan_watch_resyncs_total
+                        .inc();
+                    self.plan_version_opt = None;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> BoxStream<'static, IndexingPlanEvent> {
+        futures::stream::unfold(self, |mut watcher| async move {
+            let event = watcher.next_event().await?;
+            Some((event, watcher))
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::indexing::IndexingTask;
+    use quickwit_proto::types::{IndexUid, PipelineUid};
+
+    use super::*;
+
+    fn task_for_test(source_id: &str, pipeline_ord: u128) -> IndexingTask {
+        IndexingTask {
+            index_uid: Some(IndexUid::for_test("test-index", 0)),
+            source_id: source_id.to_string(),
+            pipeline_uid: Some(PipelineUid::for_test(pipeline_ord)),
+            ..Default::default()
+        }
+    }
+
+    fn plan_for_test(tasks: &[(&str, &str)]) -> PhysicalIndexingPlan {
+        let mut plan = PhysicalIndexingPlan::with_indexer_ids(&[]);
+
+        for (pipeline_ord, (node_id, source_id)) in tasks.iter().enumerate() {
+            plan.add_indexing_task(node_id, task_for_test(source_id, pipeline_ord as u128));
+        }
+        plan
+    }
+
+    fn unwrap_snapshot(event: IndexingPlanEvent) -> IndexingPlanSnapshot {
+        let IndexingPlanEvent::Snapshot(snapshot) = event else {
+            panic!("expected snapshot, got {event:?}");
+        };
+        snapshot
+    }
+
+    fn unwrap_diff(event: IndexingPlanEvent) -> (u64, IndexingPlanDiff) {
+        let IndexingPlanEvent::Diff { plan_version, diff } = event else {
+            panic!("expected diff, got {event:?}");
+        };
+        (plan_version, diff)
+    }
+
+    #[tokio::test]
+    async fn test_indexing_plan_watcher() {
+        let mut publisher = IndexingPlanPublisher::new(4);
+
+        let plan_1 = plan_for_test(&[("indexer-1", "source-1")]);
+        publisher.publish(&plan_1, None);
+
+        let mut watcher = publisher.subscribe();
+        let snapshot = unwrap_snapshot(watcher.next_event().await.unwrap());
+        assert_eq!(snapshot.plan_version, 1);
+        assert_eq!(snapshot.plan.unwrap(), plan_1);
+
+        let plan_2 = plan_for_test(&[("indexer-2", "source-1"), ("indexer-2", "source-2")]);
+        publisher.publish(&plan_2, None);
+
+        let (plan_version, diff) = unwrap_diff(watcher.next_event().await.unwrap());
+        assert_eq!(plan_version, 2);
+        assert_eq!(diff.len(), 2);
+
+        let indexer_1_diff = &diff["indexer-1"];
+        assert_eq!(indexer_1_diff.moved_out_tasks.len(), 1);
+        assert_eq!(indexer_1_diff.moved_out_tasks[0].node_id, "indexer-2");
+        assert_eq!(indexer_1_diff.moved_out_tasks[0].task.source_id, "source-1");
+
+        let indexer_2_diff = &diff["indexer-2"];
+        assert_eq!(indexer_2_diff.moved_in_tasks.len(), 1);
+        assert_eq!(indexer_2_diff.moved_in_tasks[0].node_id, "indexer-1");
+        assert_eq!(indexer_2_diff.added_tasks.len(), 1);
+        assert_eq!(indexer_2_diff.added_tasks[0].source_id, "source-2");
+
+        let plan_3 = plan_for_test(&[("indexer-2", "source-1")]);
+        publisher.publish(&plan_3, None);
+
+        let (plan_version, diff) = unwrap_diff(watcher.next_event().await.unwrap());
+        assert_eq!(plan_version, 3);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff["indexer-2"].removed_tasks.len(), 1);
+        assert_eq!(diff["indexer-2"].removed_tasks[0].source_id, "source-2");
+
+        drop(publisher);
+        assert!(watcher.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_indexing_plan_watcher_resyncs_after_overflow() {
+        let mut publisher = IndexingPlanPublisher::new(2);
+
+        let mut slow_watcher = publisher.subscribe();
+        let snapshot = unwrap_snapshot(slow_watcher.next_event().await.unwrap());
+        assert_eq!(snapshot.plan_version, 0);
+        assert!(snapshot.plan.is_none());
+
+        let mut fast_watcher = publisher.subscribe();
+        unwrap_snapshot(fast_watcher.next_event().await.unwrap());
+
+        // The slow watcher misses some changes, the fast one does not.
+        for num_tasks in 1..=5 {
+            let tasks: Vec<(&str, &str)> =
+                (0..num_tasks).map(|_| ("indexer-1", "source-1")).collect();
+            publisher.publish(&plan_for_test(&tasks), None);
+
+            let (plan_version, diff) = unwrap_diff(fast_watcher.next_event().await.unwrap());
+            assert_eq!(plan_version, num_tasks as u64);
+            assert_eq!(diff["indexer-1"].added_tasks.len(), 1);
+        }
+        let snapshot = unwrap_snapshot(slow_watcher.next_event().await.unwrap());
+        assert_eq!(snapshot.plan_version, 5);
+        assert_eq!(
+            snapshot.plan.unwrap().indexer("indexer-1").unwrap().len(),
+            5
+        );
+
+        // Once resynced, the slow watcher receives the diffs again.
+        let plan_6 = plan_for_test(&[("indexer-1", "source-1")]);
+        publisher.publish(&plan_6, None);
+
+        let (plan_version, diff) = unwrap_diff(slow_watcher.next_event().await.unwrap());
+        assert_eq!(plan_version, 6);
+        assert_eq!(diff["indexer-1"].removed_tasks.len(), 4);
+
+        let (plan_version, _diff) = unwrap_diff(fast_watcher.next_event().await.unwrap());
+        assert_eq!(plan_version, 6);
+    }
+}
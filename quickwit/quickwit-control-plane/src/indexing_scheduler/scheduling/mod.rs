@@ -20,12 +20,12 @@
 pub mod scheduling_logic;
 pub mod scheduling_logic_model;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::num::NonZeroU32;
 
 use fnv::{FnvHashMap, FnvHashSet};
 use quickwit_common::rate_limited_debug;
-use quickwit_proto::indexing::{CpuCapacity, IndexingTask};
+use quickwit_proto::indexing::{CpuCapacity, IndexingTask, IndexingTaskPriority};
 use quickwit_proto::types::{PipelineUid, ShardId, SourceUid};
 use scheduling_logic_model::{IndexerOrd, SourceOrd};
 use tracing::{error, warn};
@@ -64,9 +64,11 @@ fn populate_problem(
         SourceToScheduleType::Sharded {
             shard_ids,
             load_per_shard,
+            ..
         } => {
             let num_shards = shard_ids.len() as u32;
             let source_ord = problem.add_source(num_shards, *load_per_shard);
+            problem.set_source_priority(source_ord, source.priority);
             Some(source_ord)
         }
         SourceToScheduleType::NonSharded {
@@ -74,6 +76,7 @@ fn populate_problem(
             load_per_pipeline,
         } => {
             let source_ord = problem.add_source(*num_pipelines, *load_per_pipeline);
+            problem.set_source_priority(source_ord, source.priority);
             Some(source_ord)
         }
     }
@@ -155,18 +158,40 @@ fn convert_physical_plan_to_solution(
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SourceToSchedule {
     pub source_uid: SourceUid,
     pub source_type: SourceToScheduleType,
     pub params_fingerprint: u64,
+    pub priority: IndexingTaskPriority,
+    /// Indexers allowed to run the source, resolved from its placement selector. `None` means
+    /// that any indexer is.
+    pub eligible_indexer_ids: Option<FnvHashSet<String>>,
+    /// Fingerprints of previous parameters of the source from which running pipelines can switch
+    /// to the current ones without being restarted.
+    pub reloadable_params_fingerprints: FnvHashSet<u64>,
 }
 
-#[derive(Debug)]
+impl SourceToSchedule {
+    /// Returns whether the pipeline of a task previously planned for this source must be respawned
+    /// because the parameters or the priority of the source have changed. A pipeline whose
+    /// parameters can be updated in place is kept.
+    fn requires_new_pipeline(&self, previous_task: &IndexingTask) -> bool {
+        (previous_task.params_fingerprint != self.params_fingerprint
+            && !self
+                .reloadable_params_fingerprints
+                .contains(&previous_task.params_fingerprint))
+            || previous_task.priority() != self.priority
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum SourceToScheduleType {
     Sharded {
         shard_ids: Vec<ShardId>,
         load_per_shard: NonZeroU32,
+        /// Maximum number of shards assigned to a single pipeline, regardless of their load.
+        max_shards_per_pipeline: NonZeroU32,
     },
     NonSharded {
         num_pipelines: u32,
@@ -178,22 +203,28 @@ pub enum SourceToScheduleType {
 
 fn compute_max_num_shards_per_pipeline(source_type: &SourceToScheduleType) -> NonZeroU32 {
     match &source_type {
-        SourceToScheduleType::Sharded { load_per_shard, .. } => {
-            NonZeroU32::new(MAX_LOAD_PER_PIPELINE.cpu_millis() / load_per_shard.get())
-                .unwrap_or_else(|| {
-                    // We throttle shard at ingestion to ensure that a shard does not
-                    // exceed 5MB/s.
-                    //
-                    // This value has been chosen to make sure that one full pipeline
-                    // should always be able to handle the load of one shard.
-                    //
-                    // However it is possible for the system to take more than this
-                    // when it is playing catch up.
-                    //
-                    // This is a transitory state, and not a problem per se.
-                    warn!("load per shard is higher than `MAX_LOAD_PER_PIPELINE`");
-                    NonZeroU32::MIN // also colloquially known as `1`
-                })
+        SourceToScheduleType::Sharded {
+            load_per_shard,
+            max_shards_per_pipeline,
+            ..
+        } => {
+            let max_num_shards_per_pipeline_for_load =
+                NonZeroU32::new(MAX_LOAD_PER_PIPELINE.cpu_millis() / load_per_shard.get())
+                    .unwrap_or_else(|| {
+                        // We throttle shard at ingestion to ensure that a shard does not
+                        // exceed 5MB/s.
+                        //
+                        // This value has been chosen to make sure that one full pipeline
+                        // should always be able to handle the load of one shard.
+                        //
+                        // However it is possible for the system to take more than this
+                        // when it is playing catch up.
+                        //
+                        // This is a transitory state, and not a problem per se.
+                        warn!("load per shard is higher than `MAX_LOAD_PER_PIPELINE`");
+                        NonZeroU32::MIN // also colloquially known as `1`
+                    });
+            max_num_shards_per_pipeline_for_load.min(*max_shards_per_pipeline)
         }
         SourceToScheduleType::IngestV1 | SourceToScheduleType::NonSharded { .. } => {
             NonZeroU32::new(1u32).unwrap()
@@ -216,17 +247,24 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
         SourceToScheduleType::Sharded {
             shard_ids,
             load_per_shard,
+            ..
         } => {
             if remaining_num_shards_to_schedule_on_node == 0 {
                 return Vec::new();
             }
-            // For the moment we do something voluntarily suboptimal.
+            let max_num_shards_per_pipeline: NonZeroU32 =
+                compute_max_num_shards_per_pipeline(&source.source_type);
+            // For the moment we do something voluntarily suboptimal. We still keep enough
+            // pipelines to hold all the shards without exceeding the number of shards per
+            // pipeline.
             let max_num_pipelines = quickwit_common::div_ceil_u32(
                 remaining_num_shards_to_schedule_on_node * load_per_shard.get(),
                 CPU_PER_PIPELINE_LOAD_LOWER_THRESHOLD.cpu_millis(),
-            );
-            let max_num_shards_per_pipeline: NonZeroU32 =
-                compute_max_num_shards_per_pipeline(&source.source_type);
+            )
+            .max(quickwit_common::div_ceil_u32(
+                remaining_num_shards_to_schedule_on_node,
+                max_num_shards_per_pipeline.get(),
+            ));
             let mut new_tasks = Vec::new();
             for previous_task in previous_tasks {
                 let max_shard_in_pipeline = max_num_shards_per_pipeline
@@ -241,11 +279,10 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
                     .cloned()
                     .collect();
                 remaining_num_shards_to_schedule_on_node -= shard_ids.len() as u32;
-                let pipeline_uid = if previous_task.params_fingerprint == source.params_fingerprint
-                {
-                    previous_task.pipeline_uid
-                } else {
+                let pipeline_uid = if source.requires_new_pipeline(previous_task) {
                     Some(PipelineUid::random())
+                } else {
+                    previous_task.pipeline_uid
                 };
                 let new_task = IndexingTask {
                     index_uid: previous_task.index_uid.clone(),
@@ -253,6 +290,8 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
                     pipeline_uid,
                     shard_ids,
                     params_fingerprint: source.params_fingerprint,
+                    standby: false,
+                    priority: source.priority as i32,
                 };
                 new_tasks.push(new_task);
                 if new_tasks.len() >= max_num_pipelines as usize {
@@ -272,10 +311,11 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 .map(|task| (*task).clone())
                 .collect();
             for indexing_task in &mut indexing_tasks {
-                if indexing_task.params_fingerprint != source.params_fingerprint {
-                    indexing_task.params_fingerprint = source.params_fingerprint;
+                if source.requires_new_pipeline(indexing_task) {
+                    indexing_task.set_priority(source.priority);
                     indexing_task.pipeline_uid = Some(PipelineUid::random());
                 }
+                indexing_task.params_fingerprint = source.params_fingerprint;
             }
             indexing_tasks.resize_with(remaining_num_shards_to_schedule_on_node as usize, || {
                 IndexingTask {
@@ -284,6 +324,8 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
                     pipeline_uid: Some(PipelineUid::random()),
                     shard_ids: Vec::new(),
                     params_fingerprint: source.params_fingerprint,
+                    standby: false,
+                    priority: source.priority as i32,
                 }
             });
             indexing_tasks
@@ -293,10 +335,11 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
             if let Some(indexing_task) = previous_tasks.first() {
                 // The pipeline already exists, let's reuse it.
                 let mut indexing_task = (*indexing_task).clone();
-                if indexing_task.params_fingerprint != source.params_fingerprint {
-                    indexing_task.params_fingerprint = source.params_fingerprint;
+                if source.requires_new_pipeline(&indexing_task) {
+                    indexing_task.set_priority(source.priority);
                     indexing_task.pipeline_uid = Some(PipelineUid::random());
                 }
+                indexing_task.params_fingerprint = source.params_fingerprint;
                 vec![indexing_task]
             } else {
                 // The source is new, we need to create a new task.
@@ -306,6 +349,8 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
                     pipeline_uid: Some(PipelineUid::random()),
                     shard_ids: Vec::new(),
                     params_fingerprint: source.params_fingerprint,
+                    standby: false,
+                    priority: source.priority as i32,
                 }]
             }
         }
@@ -449,6 +494,7 @@ fn convert_scheduling_solution_to_physical_plan(
                 max_shard_per_pipeline,
                 &mut new_physical_plan,
                 source.params_fingerprint,
+                source.priority,
             );
         }
     }
@@ -551,6 +597,7 @@ fn add_shard_to_indexer(
     max_shard_per_pipeline: NonZeroU32,
     new_physical_plan: &mut PhysicalIndexingPlan,
     params_fingerprint: u64,
+    priority: IndexingTaskPriority,
 ) {
     let indexer_tasks = new_physical_plan
         .indexing_tasks_per_indexer_mut()
@@ -577,6 +624,8 @@ fn add_shard_to_indexer(
             pipeline_uid: Some(PipelineUid::random()),
             shard_ids: vec![missing_shard],
             params_fingerprint,
+            standby: false,
+            priority: priority as i32,
         });
     }
 }
@@ -624,6 +673,26 @@ fn inflate_node_capacities_if_necessary(problem: &mut SchedulingProblem) {
         let ratio = inflated_total_load / total_node_capacities;
         problem.scale_node_capacities(ratio);
     }
+
+    // Sources restricted to a few indexers must also fit on these indexers alone.
+    let max_eligible_ratio_opt = problem
+        .sources()
+        .filter_map(|source| {
+            let eligible_indexers = source.eligible_indexers.as_ref()?;
+            let eligible_node_capacities: u32 = eligible_indexers
+                .iter()
+                .map(|&indexer_ord| problem.indexer_cpu_capacity(indexer_ord).cpu_millis())
+                .sum();
+            let inflated_source_load =
+                (source.num_shards * source.load_per_shard.get()) as f32 * 1.2f32;
+            Some(inflated_source_load / eligible_node_capacities as f32)
+        })
+        .max_by(f32::total_cmp);
+    if let Some(max_eligible_ratio) = max_eligible_ratio_opt {
+        if max_eligible_ratio > 1.0f32 {
+            problem.scale_node_capacities(max_eligible_ratio);
+        }
+    }
 }
 
 /// Creates a physical plan given the current situation of the cluster and the list of sources
@@ -642,7 +711,8 @@ fn inflate_node_capacities_if_necessary(problem: &mut SchedulingProblem) {
 /// 4) convert the new scheduling solution back to the real world by reallocating the shard ids.
 ///
 /// TODO cut into pipelines.
-/// Panics if any sources has no shards.
+/// Panics if any sources has no shards, or if none of the eligible indexers of a source is part of
+/// `indexer_id_to_cpu_capacities`.
 pub fn build_physical_indexing_plan(
     sources: &[SourceToSchedule],
     indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
@@ -687,6 +757,55 @@ pub fn build_physical_indexing_plan(
     new_physical_plan
 }
 
+/// Returns the number of pipelines of a given source an indexer can accommodate: one per
+/// `CPU_PER_PIPELINE_LOAD_LOWER_THRESHOLD` of CPU capacity, and at least one.
+fn num_pipeline_slots(cpu_capacity: CpuCapacity) -> u32 {
+    (cpu_capacity.cpu_millis() / CPU_PER_PIPELINE_LOAD_LOWER_THRESHOLD.cpu_millis()).max(1)
+}
+
+/// Removes from the sharded sources the shards that cannot be assigned to a pipeline without
+/// exceeding the maximum number of shards per pipeline of the source, given the number of
+/// pipelines of the source its eligible indexers can accommodate. The shards with the lowest IDs
+/// are kept. Sources left without any shard are removed.
+///
+/// Returns the removed shards, per source.
+pub fn remove_shards_exceeding_pipeline_capacity(
+    sources: &mut Vec<SourceToSchedule>,
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+) -> Vec<(SourceUid, Vec<ShardId>)> {
+    let mut removed_shards = Vec::new();
+
+    sources.retain_mut(|source| {
+        let eligible_indexer_ids_opt = source.eligible_indexer_ids.as_ref();
+        let SourceToScheduleType::Sharded {
+            shard_ids,
+            max_shards_per_pipeline,
+            ..
+        } = &mut source.source_type
+        else {
+            return true;
+        };
+        let num_pipeline_slots: u32 = indexer_id_to_cpu_capacities
+            .iter()
+            .filter(|(indexer_id, _)| {
+                eligible_indexer_ids_opt
+                    .map(|eligible_indexer_ids| eligible_indexer_ids.contains(*indexer_id))
+                    .unwrap_or(true)
+            })
+            .map(|(_, cpu_capacity)| num_pipeline_slots(*cpu_capacity))
+            .sum();
+        let max_num_shards = num_pipeline_slots as usize * max_shards_per_pipeline.get() as usize;
+
+        if shard_ids.len() > max_num_shards {
+            shard_ids.sort_unstable();
+            let source_removed_shards = shard_ids.split_off(max_num_shards);
+            removed_shards.push((source.source_uid.clone(), source_removed_shards));
+        }
+        !shard_ids.is_empty()
+    });
+    removed_shards
+}
+
 /// Makes any checks on the sources.
 /// Sharded sources are not allowed to have no shards.
 fn check_sources(sources: &[SourceToSchedule]) {
@@ -719,6 +838,13 @@ fn convert_to_simplified_problem<'a>(
     for source in sources {
         if let Some(source_ord) = populate_problem(source, &mut problem) {
             let registered_source_ord = id_to_ord_map.add_source(source);
+            if let Some(eligible_indexer_ids) = &source.eligible_indexer_ids {
+                let eligible_indexers: BTreeSet<IndexerOrd> = eligible_indexer_ids
+                    .iter()
+                    .filter_map(|indexer_id| id_to_ord_map.indexer_ord(indexer_id))
+                    .collect();
+                problem.set_source_eligible_indexers(source_ord, eligible_indexers);
+            }
             if let SourceToScheduleType::Sharded { shard_ids, .. } = &source.source_type {
                 for shard_id in shard_ids {
                     for &indexer in shard_locations.get_shard_locations(shard_id) {
@@ -748,16 +874,16 @@ mod tests {
     use std::str::FromStr;
     use std::sync::atomic::{AtomicUsize, Ordering};
 
-    use fnv::FnvHashMap;
+    use fnv::{FnvHashMap, FnvHashSet};
     use itertools::Itertools;
-    use quickwit_proto::indexing::{mcpu, CpuCapacity, IndexingTask};
+    use quickwit_proto::indexing::{mcpu, CpuCapacity, IndexingTask, IndexingTaskPriority};
     use quickwit_proto::types::{IndexUid, NodeId, PipelineUid, ShardId, SourceUid};
     use rand::seq::SliceRandom;
 
     use super::{
         build_physical_indexing_plan,
-        convert_scheduling_solution_to_physical_plan_single_node_single_source, SourceToSchedule,
-        SourceToScheduleType,
+        convert_scheduling_solution_to_physical_plan_single_node_single_source,
+        remove_shards_exceeding_pipeline_capacity, SourceToSchedule, SourceToScheduleType,
     };
     use crate::indexing_plan::PhysicalIndexingPlan;
     use crate::indexing_scheduler::get_shard_locality_metrics;
@@ -795,8 +921,12 @@ mod tests {
                     ShardId::from(7),
                 ],
                 load_per_shard: NonZeroU32::new(1_000).unwrap(),
+                max_shards_per_pipeline: NonZeroU32::MAX,
             },
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         };
         let source_1 = SourceToSchedule {
             source_uid: source_uid1.clone(),
@@ -805,11 +935,17 @@ mod tests {
                 load_per_pipeline: NonZeroU32::new(3_200).unwrap(),
             },
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         };
         let source_2 = SourceToSchedule {
             source_uid: source_uid2.clone(),
             source_type: SourceToScheduleType::IngestV1,
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         };
         let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
         indexer_id_to_cpu_capacities.insert(indexer1.clone(), mcpu(16_000));
@@ -869,8 +1005,12 @@ mod tests {
                 source_type: SourceToScheduleType::Sharded {
                     shard_ids: vec![shard_ids[i].clone()],
                     load_per_shard: NonZeroU32::new(250).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::MAX,
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             })
             .collect();
 
@@ -911,6 +1051,9 @@ mod tests {
                 load_per_pipeline: NonZeroU32::new(1000).unwrap(),
             },
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         };
         let sources = vec![source_1];
 
@@ -954,6 +1097,8 @@ mod tests {
                 pipeline_uid: Some(*pipeline_uid),
                 shard_ids: shard_ids.to_vec(),
                 params_fingerprint: 0,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             });
         }
         plan
@@ -986,8 +1131,12 @@ mod tests {
                     ShardId::from(5),
                 ],
                 load_per_shard: NonZeroU32::new(1_000).unwrap(),
+                max_shards_per_pipeline: NonZeroU32::MAX,
             },
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         }];
         let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
         indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(10_000));
@@ -1014,6 +1163,353 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_physical_indexing_plan_with_max_shards_per_pipeline() {
+        let source_uid = source_id();
+        let sources = vec![SourceToSchedule {
+            source_uid: source_uid.clone(),
+            source_type: SourceToScheduleType::Sharded {
+                shard_ids: (0..10).map(ShardId::from).collect(),
+                load_per_shard: NonZeroU32::new(50).unwrap(),
+                max_shards_per_pipeline: NonZeroU32::new(3).unwrap(),
+            },
+            params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
+        }];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(10_000));
+        let shard_locations = ShardLocations::default();
+        let plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        let indexing_tasks = plan.indexer("node1").unwrap();
+        assert_eq!(indexing_tasks.len(), 4);
+
+        let mut shard_ids: Vec<ShardId> = Vec::new();
+        for indexing_task in indexing_tasks {
+            assert!(indexing_task.shard_ids.len() <= 3);
+            shard_ids.extend(indexing_task.shard_ids.iter().cloned());
+        }
+        shard_ids.sort_unstable();
+        assert_eq!(shard_ids, (0..10).map(ShardId::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_remove_shards_exceeding_pipeline_capacity() {
+        let source_uid = source_id();
+        let mut sources = vec![
+            SourceToSchedule {
+                source_uid: source_uid.clone(),
+                source_type: SourceToScheduleType::Sharded {
+                    shard_ids: (0..5).rev().map(ShardId::from).collect(),
+                    load_per_shard: NonZeroU32::new(50).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::new(3).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+            SourceToSchedule {
+                source_uid: SourceUid {
+                    index_uid: source_uid.index_uid.clone(),
+                    source_id: "source-2".to_string(),
+                },
+                source_type: SourceToScheduleType::Sharded {
+                    shard_ids: vec![ShardId::from(0)],
+                    load_per_shard: NonZeroU32::new(50).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::new(3).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+        ];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(2_000));
+
+        let removed_shards =
+            remove_shards_exceeding_pipeline_capacity(&mut sources, &indexer_id_to_cpu_capacities);
+        assert_eq!(
+            removed_shards,
+            vec![(source_uid, vec![ShardId::from(3), ShardId::from(4)])]
+        );
+        assert_eq!(sources.len(), 2);
+
+        let SourceToScheduleType::Sharded { shard_ids, .. } = &sources[0].source_type else {
+            panic!("expected a sharded source");
+        };
+        assert_eq!(
+            shard_ids,
+            &[ShardId::from(0), ShardId::from(1), ShardId::from(2)]
+        );
+
+        // With two more CPUs, the indexer accommodates two pipelines of the source.
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(4_000));
+
+        let removed_shards =
+            remove_shards_exceeding_pipeline_capacity(&mut sources, &indexer_id_to_cpu_capacities);
+        assert!(removed_shards.is_empty());
+    }
+
+    #[test]
+    fn test_build_physical_indexing_plan_with_reloadable_params_fingerprints() {
+        let source_uid = source_id();
+        let previous_task = IndexingTask {
+            index_uid: Some(source_uid.index_uid.clone()),
+            source_id: source_uid.source_id.clone(),
+            pipeline_uid: Some(PipelineUid::for_test(1u128)),
+            shard_ids: Vec::new(),
+            params_fingerprint: 1,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
+        };
+        let mut indexing_plan = PhysicalIndexingPlan::with_indexer_ids(&["node1".to_string()]);
+        indexing_plan.add_indexing_task("node1", previous_task);
+
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(10_000));
+        let shard_locations = ShardLocations::default();
+
+        let mut sources = vec![SourceToSchedule {
+            source_uid: source_uid.clone(),
+            source_type: SourceToScheduleType::NonSharded {
+                num_pipelines: 1,
+                load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+            },
+            params_fingerprint: 2,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::from_iter([1]),
+        }];
+        // The pipeline can switch to the new parameters in place, so it is kept.
+        let new_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&indexing_plan),
+            &shard_locations,
+        );
+        let indexing_tasks = new_plan.indexer("node1").unwrap();
+        assert_eq!(indexing_tasks.len(), 1);
+        assert_eq!(
+            indexing_tasks[0].pipeline_uid(),
+            PipelineUid::for_test(1u128)
+        );
+        assert_eq!(indexing_tasks[0].params_fingerprint, 2);
+
+        // Otherwise, the pipeline is replaced.
+        sources[0].reloadable_params_fingerprints.clear();
+
+        let new_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&indexing_plan),
+            &shard_locations,
+        );
+        let indexing_tasks = new_plan.indexer("node1").unwrap();
+        assert_eq!(indexing_tasks.len(), 1);
+        assert_ne!(
+            indexing_tasks[0].pipeline_uid(),
+            PipelineUid::for_test(1u128)
+        );
+        assert_eq!(indexing_tasks[0].params_fingerprint, 2);
+    }
+
+    #[test]
+    fn test_build_physical_indexing_plan_with_priorities() {
+        let high_source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "high-source".to_string(),
+        };
+        let background_source_uid = SourceUid {
+            index_uid: IndexUid::for_test("index-1", 0),
+            source_id: "background-source".to_string(),
+        };
+        let mut sources = vec![
+            SourceToSchedule {
+                source_uid: background_source_uid,
+                source_type: SourceToScheduleType::NonSharded {
+                    num_pipelines: 1,
+                    load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Background,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+            SourceToSchedule {
+                source_uid: high_source_uid,
+                source_type: SourceToScheduleType::NonSharded {
+                    num_pipelines: 2,
+                    load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::High,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+        ];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(10_000));
+        let shard_locations = ShardLocations::default();
+        let plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        let indexing_tasks = plan.indexer("node1").unwrap();
+        assert_eq!(indexing_tasks.len(), 3);
+
+        for indexing_task in indexing_tasks {
+            if indexing_task.source_id == "high-source" {
+                assert_eq!(indexing_task.priority(), IndexingTaskPriority::High);
+            } else {
+                assert_eq!(indexing_task.priority(), IndexingTaskPriority::Background);
+            }
+        }
+        // Changing the priority of a source respawns its pipelines only.
+        sources[0].priority = IndexingTaskPriority::Normal;
+
+        let new_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&plan),
+            &shard_locations,
+        );
+        let new_indexing_tasks = new_plan.indexer("node1").unwrap();
+        assert_eq!(new_indexing_tasks.len(), 3);
+
+        for new_indexing_task in new_indexing_tasks {
+            let is_same_pipeline = indexing_tasks
+                .iter()
+                .any(|indexing_task| indexing_task.pipeline_uid == new_indexing_task.pipeline_uid);
+
+            if new_indexing_task.source_id == "high-source" {
+                assert_eq!(new_indexing_task.priority(), IndexingTaskPriority::High);
+                assert!(is_same_pipeline);
+            } else {
+                assert_eq!(new_indexing_task.priority(), IndexingTaskPriority::Normal);
+                assert!(!is_same_pipeline);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_physical_indexing_plan_with_eligible_indexers() {
+        let index_uid = IndexUid::for_test("index-1", 0);
+        let source_uid = |source_id: &str| SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.to_string(),
+        };
+        let eligible_indexer_ids = |indexer_ids: &[&str]| -> Option<FnvHashSet<String>> {
+            Some(
+                indexer_ids
+                    .iter()
+                    .map(|indexer_id| indexer_id.to_string())
+                    .collect(),
+            )
+        };
+        let shard_ids: Vec<ShardId> = (0..6).map(ShardId::from).collect();
+        // Only `node1` and `node2` match the placement selector of `sharded-source`, and only
+        // `node3` the one of `pinned-source`. `free-source` has no selector.
+        let mut sources = vec![
+            SourceToSchedule {
+                source_uid: source_uid("sharded-source"),
+                source_type: SourceToScheduleType::Sharded {
+                    shard_ids: shard_ids.clone(),
+                    load_per_shard: NonZeroU32::new(250).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::MAX,
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: eligible_indexer_ids(&["node1", "node2"]),
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+            SourceToSchedule {
+                source_uid: source_uid("pinned-source"),
+                source_type: SourceToScheduleType::NonSharded {
+                    num_pipelines: 2,
+                    load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: eligible_indexer_ids(&["node3"]),
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+            SourceToSchedule {
+                source_uid: source_uid("free-source"),
+                source_type: SourceToScheduleType::NonSharded {
+                    num_pipelines: 3,
+                    load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
+                },
+                params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
+            },
+        ];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("node1".to_string(), mcpu(4_000));
+        indexer_id_to_cpu_capacities.insert("node2".to_string(), mcpu(4_000));
+        indexer_id_to_cpu_capacities.insert("node3".to_string(), mcpu(4_000));
+
+        // The shards are hosted on `node3`, but the affinity does not override the selector.
+        let mut shard_locations = ShardLocations::default();
+        let node3 = NodeId::from("node3");
+        for shard_id in &shard_ids {
+            shard_locations.add_location(shard_id, &node3);
+        }
+        let num_tasks = |plan: &PhysicalIndexingPlan, indexer_id: &str, source_id: &str| {
+            plan.indexer(indexer_id)
+                .unwrap_or_default()
+                .iter()
+                .filter(|task| task.source_id == source_id)
+                .map(|task| task.shard_ids.len().max(1))
+                .sum::<usize>()
+        };
+        let plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            None,
+            &shard_locations,
+        );
+        assert_eq!(
+            num_tasks(&plan, "node1", "sharded-source")
+                + num_tasks(&plan, "node2", "sharded-source"),
+            6
+        );
+        assert_eq!(num_tasks(&plan, "node3", "sharded-source"), 0);
+        assert_eq!(num_tasks(&plan, "node3", "pinned-source"), 2);
+        assert_eq!(
+            ["node1", "node2", "node3"]
+                .iter()
+                .map(|indexer_id| num_tasks(&plan, indexer_id, "free-source"))
+                .sum::<usize>(),
+            3
+        );
+
+        // The labels of the cluster changed: only `node1` matches the selector of `pinned-source`
+        // now, so its pipelines move away from `node3`.
+        sources[1].eligible_indexer_ids = eligible_indexer_ids(&["node1"]);
+
+        let new_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&plan),
+            &shard_locations,
+        );
+        assert_eq!(num_tasks(&new_plan, "node1", "pinned-source"), 2);
+        assert_eq!(num_tasks(&new_plan, "node3", "pinned-source"), 0);
+        assert_eq!(num_tasks(&new_plan, "node3", "sharded-source"), 0);
+    }
+
     fn group_shards_into_pipelines_aux(
         source_uid: &SourceUid,
         shard_ids: &[u64],
@@ -1026,8 +1522,12 @@ mod tests {
             source_type: SourceToScheduleType::Sharded {
                 shard_ids: shard_ids.iter().copied().map(ShardId::from).collect(),
                 load_per_shard: NonZeroU32::new(load_per_shard.cpu_millis()).unwrap(),
+                max_shards_per_pipeline: NonZeroU32::MAX,
             },
             params_fingerprint: 0,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexer_ids: None,
+            reloadable_params_fingerprints: FnvHashSet::default(),
         }];
         const NODE: &str = "node1";
         let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
@@ -1207,6 +1707,9 @@ mod tests {
                 },
                 source_type: SourceToScheduleType::IngestV1,
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             },
             SourceToSchedule {
                 source_uid: SourceUid {
@@ -1219,8 +1722,12 @@ mod tests {
                 source_type: SourceToScheduleType::Sharded {
                     shard_ids: vec![ShardId::from(1)],
                     load_per_shard: NonZeroU32::new(250).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::MAX,
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             },
         ];
         let mut capacities = FnvHashMap::default();
@@ -1241,6 +1748,8 @@ mod tests {
             pipeline_uid: Some(PipelineUid::random()),
             shard_ids: vec![ShardId::from(1), ShardId::from(4), ShardId::from(5)],
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let previous_task2 = IndexingTask {
             index_uid: Some(source_uid.index_uid.clone()),
@@ -1254,6 +1763,8 @@ mod tests {
                 ShardId::from(10),
             ],
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         {
             let sharded_source = SourceToSchedule {
@@ -1266,8 +1777,12 @@ mod tests {
                         ShardId::from(6),
                     ],
                     load_per_shard: NonZeroU32::new(1_000).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::MAX,
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 4,
@@ -1292,8 +1807,12 @@ mod tests {
                         ShardId::from(6),
                     ],
                     load_per_shard: NonZeroU32::new(250).unwrap(),
+                    max_shards_per_pipeline: NonZeroU32::MAX,
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 4,
@@ -1319,6 +1838,8 @@ mod tests {
             pipeline_uid: Some(pipeline_uid1),
             shard_ids: Vec::new(),
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let pipeline_uid2 = PipelineUid::random();
         let previous_task2 = IndexingTask {
@@ -1327,6 +1848,8 @@ mod tests {
             pipeline_uid: Some(pipeline_uid2),
             shard_ids: Vec::new(),
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         {
             let sharded_source = SourceToSchedule {
@@ -1336,6 +1859,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(4000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 1,
@@ -1355,6 +1881,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 0,
@@ -1371,6 +1900,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 2,
@@ -1393,6 +1925,9 @@ mod tests {
                     load_per_pipeline: NonZeroU32::new(1_000).unwrap(),
                 },
                 params_fingerprint: 0,
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexer_ids: None,
+                reloadable_params_fingerprints: FnvHashSet::default(),
             };
             let tasks = convert_scheduling_solution_to_physical_plan_single_node_single_source(
                 2,
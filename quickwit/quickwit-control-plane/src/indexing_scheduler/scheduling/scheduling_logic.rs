@@ -58,6 +58,9 @@ pub fn solve(
     // too many shards in the current solution.
     // Let's first shave off the extraneous shards.
     remove_extraneous_shards(&problem, &mut solution);
+    // The indexers allowed to run a source can change with their labels or with the placement
+    // selector of the source. We remove the shards placed on indexers that are no longer eligible.
+    remove_ineligible_shards(&problem, &mut solution);
     // Because the load associated to shards can change, some indexers
     // may have too much work assigned to them.
     // Again, we shave off some shards to make sure they are
@@ -158,6 +161,17 @@ fn assert_remove_extraneous_shards_post_condition(
     }
 }
 
+// Remove shards in solution that are placed on indexers not eligible to run their source.
+
+fn remove_ineligible_shards(problem: &SchedulingProblem, solution: &mut SchedulingSolution) {
+    for indexer_assignment in &mut solution.indexer_assignments {
+        let indexer_ord = indexer_assignment.indexer_ord;
+        indexer_assignment
+            .num_shards_per_source
+            .retain(|&source_ord, _| problem.is_source_eligible(source_ord, indexer_ord));
+    }
+}
+
 // -------------------------------------------------------------------------
 // Phase 2
 // Relieve sources from the node that are exceeding their maximum load.
@@ -181,16 +195,24 @@ fn enforce_indexer_cpu_capacity(
     }
     let mut load_to_remove: CpuCapacity =
         CpuCapacity::from_cpu_millis(total_load) - indexer_cpu_capacity;
-    let mut source_cpu_capacities: Vec<(CpuCapacity, SourceOrd)> = indexer_assignment
-        .num_shards_per_source
-        .iter()
-        .map(|(&source_ord, num_shards)| {
-            let load_for_source = problem.source_load_per_shard(source_ord).get() * num_shards;
-            (CpuCapacity::from_cpu_millis(load_for_source), source_ord)
-        })
-        .collect();
+    // Sources are evicted from the lowest to the highest priority, and then from the smallest to
+    // the largest load.
+    let mut source_cpu_capacities: Vec<(Reverse<usize>, CpuCapacity, SourceOrd)> =
+        indexer_assignment
+            .num_shards_per_source
+            .iter()
+            .map(|(&source_ord, num_shards)| {
+                let load_for_source = problem.source_load_per_shard(source_ord).get() * num_shards;
+                let priority_rank = problem.source_priority(source_ord).rank();
+                (
+                    Reverse(priority_rank),
+                    CpuCapacity::from_cpu_millis(load_for_source),
+                    source_ord,
+                )
+            })
+            .collect();
     source_cpu_capacities.sort();
-    for (source_cpu_capacity, source_ord) in source_cpu_capacities {
+    for (_, source_cpu_capacity, source_ord) in source_cpu_capacities {
         indexer_assignment.num_shards_per_source.remove(&source_ord);
         load_to_remove = if load_to_remove <= source_cpu_capacity {
             break;
@@ -223,6 +245,7 @@ fn attempt_place_unassigned_shards(
     for source in unassigned_shards {
         let indexers_with_most_available_capacity =
             compute_indexer_available_capacity(problem, &solution)
+                .filter(|(indexer_ord, _)| source.is_eligible(*indexer_ord))
                 .sorted_by_key(|(indexer_ord, capacity)| Reverse((*capacity, *indexer_ord)));
         place_unassigned_shards_single_source(
             source,
@@ -241,7 +264,7 @@ fn place_unassigned_shards_with_affinity(
     let mut unassigned_shards: Vec<Source> = compute_unassigned_sources(problem, solution);
     unassigned_shards.sort_by_key(|source| {
         let load = source.num_shards * source.load_per_shard.get();
-        Reverse(load)
+        (source.priority.rank(), Reverse(load))
     });
     for source in &unassigned_shards {
         // List of indexer with a non-null affinity and some available capacity, sorted by
@@ -249,7 +272,9 @@ fn place_unassigned_shards_with_affinity(
         let indexers_with_affinity_and_available_capacity = source
             .affinities
             .iter()
-            .filter(|&(_, &affinity)| affinity != 0u32)
+            .filter(|&(&indexer_ord, &affinity)| {
+                affinity != 0u32 && source.is_eligible(indexer_ord)
+            })
             .map(|(&indexer_ord, affinity)| {
                 let available_capacity =
                     solution.indexer_assignments[indexer_ord].indexer_available_capacity(problem);
@@ -274,7 +299,7 @@ fn place_unassigned_shards_with_affinity(
 //
 // We use a greedy algorithm as a simple heuristic here.
 //
-// We go through the sources in decreasing order of their load,
+// We go through the sources in decreasing order of their priority, and then of their load,
 // in two passes.
 //
 // In the first pass, we have a look at
@@ -296,7 +321,7 @@ fn place_unassigned_shards_ignoring_affinity(
     let mut unassigned_shards: Vec<Source> = compute_unassigned_sources(&problem, partial_solution);
     unassigned_shards.sort_by_key(|source| {
         let load = source.num_shards * source.load_per_shard.get();
-        Reverse(load)
+        (source.priority.rank(), Reverse(load))
     });
 
     // Thanks to the call to `inflate_node_capacities_if_necessary`,
@@ -421,10 +446,11 @@ fn compute_indexer_available_capacity<'a>(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
     use std::num::NonZeroU32;
 
     use proptest::prelude::*;
-    use quickwit_proto::indexing::mcpu;
+    use quickwit_proto::indexing::{mcpu, IndexingTaskPriority};
 
     use super::*;
 
@@ -533,6 +559,24 @@ mod tests {
         assert_eq!(solution.indexer_assignments[4].num_shards(2), 2);
     }
 
+    #[test]
+    fn test_enforce_nodes_cpu_capacity_evicts_background_sources_first() {
+        let mut problem = SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(3_500)]);
+        problem.add_source(1, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_priority(0, IndexingTaskPriority::High);
+        problem.add_source(2, NonZeroU32::new(1_500).unwrap());
+        problem.set_source_priority(1, IndexingTaskPriority::Background);
+        let mut solution = problem.new_solution();
+        solution.indexer_assignments[0].add_shards(0, 1);
+        solution.indexer_assignments[0].add_shards(1, 2);
+
+        enforce_indexers_cpu_capacity(&problem, &mut solution);
+
+        // The background source is evicted even though its load is the largest.
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 1);
+        assert_eq!(solution.indexer_assignments[0].num_shards(1), 0);
+    }
+
     #[test]
     fn test_compute_unassigned_shards_simple() {
         let mut problem = SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(4_000)]);
@@ -547,6 +591,8 @@ mod tests {
                 load_per_shard: NonZeroU32::new(1_000).unwrap(),
                 num_shards: 4,
                 affinities: BTreeMap::default(),
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexers: None,
             }
         );
     }
@@ -571,6 +617,8 @@ mod tests {
                 load_per_shard: NonZeroU32::new(1_000).unwrap(),
                 num_shards: 5 - (1 + 2),
                 affinities: Default::default(),
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexers: None,
             }
         );
         assert_eq!(
@@ -580,6 +628,8 @@ mod tests {
                 load_per_shard: NonZeroU32::new(2_000).unwrap(),
                 num_shards: 15 - (3 + 3),
                 affinities: Default::default(),
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexers: None,
             }
         );
     }
@@ -607,6 +657,32 @@ mod tests {
         assert_eq!(solution.indexer_assignments[1].num_shards(0), 4);
     }
 
+    #[test]
+    fn test_place_unassigned_shards_high_priority_first() {
+        let mut problem =
+            SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(4_000), mcpu(4_000)]);
+        problem.add_source(3, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_priority(0, IndexingTaskPriority::Background);
+        problem.add_source(2, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_priority(1, IndexingTaskPriority::High);
+        // Both sources have an affinity with indexer 0, which cannot host all of their shards.
+        for _ in 0..3 {
+            problem.inc_affinity(0, 0);
+        }
+        for _ in 0..2 {
+            problem.inc_affinity(1, 0);
+        }
+        let mut solution = problem.new_solution();
+        place_unassigned_shards_with_affinity(&problem, &mut solution);
+        let solution = place_unassigned_shards_ignoring_affinity(problem, &solution);
+
+        // The high priority source is placed first, even though its load is the smallest, and the
+        // background source only gets the remaining capacity.
+        assert_eq!(solution.indexer_assignments[0].num_shards(1), 2);
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 2);
+        assert_eq!(solution.indexer_assignments[1].num_shards(0), 1);
+    }
+
     #[test]
     fn test_place_unassigned_shards_reach_capacity() {
         let mut problem =
@@ -630,6 +706,8 @@ mod tests {
                 load_per_shard: NonZeroU32::new(1_000).unwrap(),
                 num_shards: 5 - (1 + 2),
                 affinities: Default::default(),
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexers: None,
             }
         );
         assert_eq!(
@@ -639,10 +717,74 @@ mod tests {
                 load_per_shard: NonZeroU32::new(2_000).unwrap(),
                 num_shards: 15 - (3 + 3),
                 affinities: Default::default(),
+                priority: IndexingTaskPriority::Normal,
+                eligible_indexers: None,
             }
         );
     }
 
+    #[test]
+    fn test_remove_ineligible_shards() {
+        let mut problem =
+            SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(4_000), mcpu(4_000)]);
+        problem.add_source(2, NonZeroU32::new(1_000).unwrap());
+        problem.add_source(2, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_eligible_indexers(0, BTreeSet::from([1]));
+        let mut solution = problem.new_solution();
+        solution.indexer_assignments[0].add_shards(0, 1);
+        solution.indexer_assignments[0].add_shards(1, 1);
+        solution.indexer_assignments[1].add_shards(0, 1);
+        solution.indexer_assignments[1].add_shards(1, 1);
+        remove_ineligible_shards(&problem, &mut solution);
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 0);
+        assert_eq!(solution.indexer_assignments[0].num_shards(1), 1);
+        assert_eq!(solution.indexer_assignments[1].num_shards(0), 1);
+        assert_eq!(solution.indexer_assignments[1].num_shards(1), 1);
+    }
+
+    #[test]
+    fn test_place_unassigned_shards_on_eligible_indexers() {
+        let mut problem = SchedulingProblem::with_indexer_cpu_capacities(vec![
+            mcpu(4_000),
+            mcpu(4_000),
+            mcpu(4_000),
+        ]);
+        problem.add_source(4, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_eligible_indexers(0, BTreeSet::from([1, 2]));
+        // The affinity of the source with an indexer it is not eligible to is ignored.
+        for _ in 0..4 {
+            problem.inc_affinity(0, 0);
+        }
+        let mut solution = problem.new_solution();
+        place_unassigned_shards_with_affinity(&problem, &mut solution);
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 0);
+
+        let solution = place_unassigned_shards_ignoring_affinity(problem, &solution);
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 0);
+        assert_eq!(
+            solution.indexer_assignments[1].num_shards(0)
+                + solution.indexer_assignments[2].num_shards(0),
+            4
+        );
+    }
+
+    #[test]
+    fn test_solve_moves_shards_to_eligible_indexers() {
+        let mut problem =
+            SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(8_000), mcpu(8_000)]);
+        problem.add_source(2, NonZeroU32::new(1_000).unwrap());
+        problem.add_source(2, NonZeroU32::new(1_000).unwrap());
+        problem.set_source_eligible_indexers(1, BTreeSet::from([1]));
+        let mut previous_solution = problem.new_solution();
+        previous_solution.indexer_assignments[0].add_shards(0, 2);
+        previous_solution.indexer_assignments[0].add_shards(1, 2);
+
+        let solution = solve(problem, previous_solution);
+        assert_eq!(solution.indexer_assignments[0].num_shards(0), 2);
+        assert_eq!(solution.indexer_assignments[0].num_shards(1), 0);
+        assert_eq!(solution.indexer_assignments[1].num_shards(1), 2);
+    }
+
     #[test]
     fn test_solve() {
         let mut problem = SchedulingProblem::with_indexer_cpu_capacities(vec![mcpu(800)]);
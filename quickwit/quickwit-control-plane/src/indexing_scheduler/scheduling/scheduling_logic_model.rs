@@ -18,10 +18,10 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::num::NonZeroU32;
 
-use quickwit_proto::indexing::CpuCapacity;
+use quickwit_proto::indexing::{CpuCapacity, IndexingTaskPriority};
 
 pub type SourceOrd = u32;
 pub type IndexerOrd = usize;
@@ -41,9 +41,21 @@ pub struct Source {
     /// and `affinity(source, indexer) <= num shard of source on indexer`
     pub affinities: BTreeMap<IndexerOrd, u32>,
     pub num_shards: u32,
+    /// Sources with a higher priority are placed first and evicted last from overloaded
+    /// indexers.
+    pub priority: IndexingTaskPriority,
+    /// Indexers allowed to run the shards of the source. `None` means that any indexer is.
+    pub eligible_indexers: Option<BTreeSet<IndexerOrd>>,
 }
 
 impl Source {
+    pub fn is_eligible(&self, indexer_ord: IndexerOrd) -> bool {
+        match &self.eligible_indexers {
+            Some(eligible_indexers) => eligible_indexers.contains(&indexer_ord),
+            None => true,
+        }
+    }
+
     // Remove a given number of shards, located on the given indexer.
     // Returns `false` if and only if all of the shards have been removed.
     //
@@ -147,10 +159,36 @@ impl SchedulingProblem {
             num_shards,
             load_per_shard,
             affinities: Default::default(),
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexers: None,
         });
         source_ord
     }
 
+    pub fn set_source_priority(&mut self, source_ord: SourceOrd, priority: IndexingTaskPriority) {
+        self.sources[source_ord as usize].priority = priority;
+    }
+
+    pub fn source_priority(&self, source_ord: SourceOrd) -> IndexingTaskPriority {
+        self.sources[source_ord as usize].priority
+    }
+
+    /// Restricts the placement of the shards of the source to the given indexers.
+    ///
+    /// Panics if the set of indexers is empty, as the source could then never be placed.
+    pub fn set_source_eligible_indexers(
+        &mut self,
+        source_ord: SourceOrd,
+        eligible_indexers: BTreeSet<IndexerOrd>,
+    ) {
+        assert!(!eligible_indexers.is_empty());
+        self.sources[source_ord as usize].eligible_indexers = Some(eligible_indexers);
+    }
+
+    pub fn is_source_eligible(&self, source_ord: SourceOrd, indexer_ord: IndexerOrd) -> bool {
+        self.sources[source_ord as usize].is_eligible(indexer_ord)
+    }
+
     /// Increases the affinity source <-> indexer by 1.
     /// This is done to record that the indexer is hosting one shard of the source.
     pub fn inc_affinity(&mut self, source_ord: SourceOrd, indexer_ord: IndexerOrd) {
@@ -261,6 +299,8 @@ mod tests {
             load_per_shard: NonZeroU32::new(1000u32).unwrap(),
             affinities,
             num_shards: 2 + 3,
+            priority: IndexingTaskPriority::Normal,
+            eligible_indexers: None,
         }
     }
 
@@ -261,6 +261,9 @@ fn get_open_shard_from_model(
     let Some(index_uid) = model.index_uid(&get_open_shards_subrequest.index_id) else {
         return Err(GetOrCreateOpenShardsFailureReason::IndexNotFound);
     };
+    if model.is_ingest_blocked(index_uid) {
+        return Err(GetOrCreateOpenShardsFailureReason::IngestBlocked);
+    }
     let Some(open_shard_entries) = model.find_open_shards(
         index_uid,
         &get_open_shards_subrequest.source_id,
@@ -1126,6 +1129,63 @@ impl IngestController {
         Ok(Some(tokio::spawn(close_shards_and_send_callback_fut)))
     }
 
+    /// Closes the open shards of an index for which ingest has just been blocked so that the
+    /// routers stop persisting documents into them. The shards are marked as closed in the model
+    /// right away, the close requests to the ingesters are fire and forget.
+    pub(crate) fn close_shards_for_index(
+        &self,
+        index_uid: &IndexUid,
+        model: &mut ControlPlaneModel,
+    ) -> Vec<ShardPKey> {
+        let mut shards_to_close: Vec<(LeaderId, ShardPKey)> = Vec::new();
+        let mut shard_ids_per_source: HashMap<SourceUid, Vec<ShardId>> = HashMap::new();
+
+        for shard_entry in model.list_shards_for_index(index_uid) {
+            if !shard_entry.is_open() {
+                continue;
+            }
+            let leader_id = NodeId::from(shard_entry.leader_id.clone());
+            let shard_pkey = ShardPKey {
+                index_uid: shard_entry.index_uid.clone(),
+                source_id: shard_entry.source_id.clone(),
+                shard_id: shard_entry.shard_id.clone(),
+            };
+            shards_to_close.push((leader_id, shard_pkey));
+
+            let source_uid = SourceUid {
+                index_uid: index_uid.clone(),
+                source_id: shard_entry.source_id.clone(),
+            };
+            shard_ids_per_source
+                .entry(source_uid)
+                .or_default()
+                .push(shard_entry.shard_id().clone());
+        }
+        for (source_uid, shard_ids) in shard_ids_per_source {
+            model.close_shards(&source_uid, &shard_ids);
+        }
+        let shard_pkeys: Vec<ShardPKey> = shards_to_close
+            .iter()
+            .map(|(_, shard_pkey)| shard_pkey.clone())
+            .collect();
+
+        if !shards_to_close.is_empty() {
+            info!(
+                index_uid=%index_uid,
+                "closing {} shards of index with blocked ingest",
+                shards_to_close.len()
+            );
+            let close_shards_fut = self.close_shards(shards_to_close);
+            fire_and_forget(
+                async move {
+                    close_shards_fut.await;
+                },
+                "close shards of index with blocked ingest",
+            );
+        }
+        shard_pkeys
+    }
+
     fn close_shards(
         &self,
         shards_to_close: Vec<(LeaderId, ShardPKey)>,
@@ -3106,6 +3166,83 @@ mod tests {
         assert_eq!(closed_shard.shard_id(), ShardId::from(0));
     }
 
+    #[tokio::test]
+    async fn test_ingest_controller_close_shards_for_index() {
+        let metastore = MetastoreServiceClient::mocked();
+        let ingester_pool = IngesterPool::default();
+        let replication_factor = 1;
+        let controller = IngestController::new(
+            metastore,
+            ingester_pool.clone(),
+            replication_factor,
+            TEST_SHARD_THROUGHPUT_LIMIT_MIB,
+        );
+        let mut model = ControlPlaneModel::default();
+
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        let source_config = SourceConfig::ingest_v2();
+        let source_id: SourceId = source_config.source_id.clone();
+        model.add_source(&index_uid, source_config).unwrap();
+
+        let shards = vec![
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.clone(),
+                shard_id: Some(ShardId::from(0)),
+                leader_id: "test-ingester".to_string(),
+                shard_state: ShardState::Open as i32,
+                ..Default::default()
+            },
+            Shard {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.clone(),
+                shard_id: Some(ShardId::from(1)),
+                leader_id: "test-ingester".to_string(),
+                shard_state: ShardState::Closed as i32,
+                ..Default::default()
+            },
+        ];
+        model.insert_shards(&index_uid, &source_id, shards);
+
+        let (close_shards_request_tx, close_shards_request_rx) = tokio::sync::oneshot::channel();
+        let mut mock_ingester = MockIngesterService::new();
+        mock_ingester
+            .expect_close_shards()
+            .return_once(move |request| {
+                let response = CloseShardsResponse {
+                    successes: request.shard_pkeys.clone(),
+                };
+                close_shards_request_tx.send(request).unwrap();
+                Ok(response)
+            });
+        let ingester = IngesterServiceClient::from_mock(mock_ingester);
+        ingester_pool.insert(NodeId::from("test-ingester"), ingester);
+
+        let closed_shards = controller.close_shards_for_index(&index_uid, &mut model);
+        assert_eq!(closed_shards.len(), 1);
+        assert_eq!(closed_shards[0].shard_id(), ShardId::from(0));
+
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+        assert!(model
+            .get_shards_for_source(&source_uid)
+            .unwrap()
+            .values()
+            .all(|shard_entry| shard_entry.is_closed()));
+
+        let close_shards_request = close_shards_request_rx.await.unwrap();
+        assert_eq!(close_shards_request.shard_pkeys.len(), 1);
+        assert_eq!(
+            close_shards_request.shard_pkeys[0].shard_id(),
+            ShardId::from(0)
+        );
+    }
+
     #[tokio::test]
     async fn test_ingest_controller_rebalance_shards() {
         setup_logging_for_tests();
@@ -24,6 +24,8 @@ pub mod ingest;
 pub(crate) mod metrics;
 pub(crate) mod model;
 
+use std::collections::BTreeMap;
+
 use quickwit_common::tower::Pool;
 use quickwit_proto::indexing::{CpuCapacity, IndexingServiceClient, IndexingTask};
 use quickwit_proto::types::NodeId;
@@ -36,6 +38,15 @@ pub struct IndexerNodeInfo {
     pub client: IndexingServiceClient,
     pub indexing_tasks: Vec<IndexingTask>,
     pub indexing_capacity: CpuCapacity,
+    /// Draining indexers do not receive new indexing tasks and see their running tasks moved to
+    /// other indexers.
+    pub is_draining: bool,
+    /// Whether the indexer accepts indexing plans with delta-encoded shard lists.
+    pub supports_plan_deltas: bool,
+    /// Number of indexing pipelines of the indexer that are failed or being respawned.
+    pub num_unhealthy_pipelines: usize,
+    /// Labels advertised by the indexer, matched against the placement selectors of the sources.
+    pub indexing_affinity_labels: BTreeMap<String, String>,
 }
 
 pub type IndexerPool = Pool<NodeId, IndexerNodeInfo>;
@@ -19,7 +19,8 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter, new_gauge, new_gauge_vec, IntCounter, IntGauge, IntGaugeVec,
+    exponential_buckets, new_counter, new_counter_vec, new_gauge, new_gauge_vec, new_histogram,
+    new_histogram_vec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -33,11 +34,21 @@ pub struct ControlPlaneMetrics {
     pub restart_total: IntCounter,
     pub schedule_total: IntCounter,
     pub apply_total: IntCounter,
+    pub indexing_task_outcomes_total: IntCounterVec<1>,
     pub metastore_error_aborted: IntCounter,
     pub metastore_error_maybe_executed: IntCounter,
     pub open_shards_total: IntGaugeVec<1>,
     pub local_shards: IntGauge,
     pub remote_shards: IntGauge,
+    pub draining_indexers: IntGauge,
+    pub draining_indexer_remaining_pipelines: IntGaugeVec<1>,
+    pub unhealthy_indexing_pipelines: IntGauge,
+    pub unscheduled_shards: IntGauge,
+    pub standby_pipeline_promotions_total: IntCounterVec<1>,
+    pub indexing_plan_convergence_seconds: Histogram,
+    pub indexing_plan_divergent_nodes: IntGauge,
+    pub apply_plan_request_bytes: HistogramVec<1>,
+    pub indexing_plan_watch_resyncs_total: IntCounter,
 }
 
 impl ControlPlaneMetrics {
@@ -80,6 +91,14 @@ impl Default for ControlPlaneMetrics {
                 "control_plane",
                 &[],
             ),
+            indexing_task_outcomes_total: new_counter_vec(
+                "indexing_task_outcomes_total",
+                "Number of indexing tasks applied to indexers, by outcome reported by the \
+                 indexers.",
+                "control_plane",
+                &[],
+                ["status"],
+            ),
             metastore_error_aborted: new_counter(
                 "metastore_error_aborted",
                 "Number of aborted metastore transaction (= do not trigger a control plane \
@@ -103,6 +122,71 @@ impl Default for ControlPlaneMetrics {
             ),
             local_shards,
             remote_shards,
+            draining_indexers: new_gauge(
+                "draining_indexers",
+                "Number of indexers being drained.",
+                "control_plane",
+                &[],
+            ),
+            draining_indexer_remaining_pipelines: new_gauge_vec(
+                "draining_indexer_remaining_pipelines",
+                "Number of indexing pipelines still running on a draining indexer.",
+                "control_plane",
+                &[],
+                ["node_id"],
+            ),
+            unhealthy_indexing_pipelines: new_gauge(
+                "unhealthy_indexing_pipelines",
+                "Number of indexing pipelines of the cluster that are failed or being respawned.",
+                "control_plane",
+                &[],
+            ),
+            unscheduled_shards: new_gauge(
+                "unscheduled_shards",
+                "Number of shards left out of the indexing plan because the indexers cannot \
+                 accommodate enough pipelines to index them without exceeding the maximum number \
+                 of shards per pipeline.",
+                "control_plane",
+                &[],
+            ),
+            standby_pipeline_promotions_total: new_counter_vec(
+                "standby_pipeline_promotions_total",
+                "Number of promotions of warm standby indexing pipelines triggered by the \
+                 departure of an indexer, per outcome in [warm, cold, failed].",
+                "control_plane",
+                &[],
+                ["outcome"],
+            ),
+            indexing_plan_convergence_seconds: new_histogram(
+                "indexing_plan_convergence_seconds",
+                "Time elapsed between the application of a new indexing plan and the moment the \
+                 indexing tasks running on the indexers match it.",
+                "control_plane",
+                exponential_buckets(1.0, 2.0, 10).unwrap(),
+            ),
+            indexing_plan_divergent_nodes: new_gauge(
+                "indexing_plan_divergent_nodes",
+                "Number of indexers whose running indexing tasks have differed from the last \
+                 applied indexing plan for more than a grace period.",
+                "control_plane",
+                &[],
+            ),
+            apply_plan_request_bytes: new_histogram_vec(
+                "apply_plan_request_bytes",
+                "Size in bytes of the apply indexing plan requests sent to the indexers, by \
+                 encoding of their shard lists (`full` or `delta`).",
+                "control_plane",
+                &[],
+                ["encoding"],
+                exponential_buckets(1024.0, 4.0, 10).unwrap(),
+            ),
+            indexing_plan_watch_resyncs_total: new_counter(
+                "indexing_plan_watch_resyncs_total",
+                "Number of times a watcher of the indexing plan fell too far behind and was sent \
+                 a snapshot of the plan instead of the changes it missed.",
+                "control_plane",
+                &[],
+            ),
         }
     }
 }
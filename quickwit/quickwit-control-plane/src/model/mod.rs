@@ -29,7 +29,7 @@ use anyhow::bail;
 use fnv::{FnvHashMap, FnvHashSet};
 use quickwit_common::pretty::PrettyDisplay;
 use quickwit_common::Progress;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{DocMappingUpdateVerdict, IndexConfig, SourceConfig};
 use quickwit_ingest::ShardInfos;
 use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
 use quickwit_proto::control_plane::ControlPlaneResult;
@@ -56,6 +56,9 @@ pub(crate) struct ControlPlaneModel {
     index_uid_table: FnvHashMap<IndexId, IndexUid>,
     index_table: FnvHashMap<IndexUid, IndexMetadata>,
     shard_table: ShardTable,
+    // Fingerprints of the previous indexing parameters of each index from which the running
+    // pipelines can switch to the current ones without being restarted.
+    reloadable_params_fingerprints: FnvHashMap<IndexUid, FnvHashSet<u64>>,
 }
 
 impl ControlPlaneModel {
@@ -160,6 +163,15 @@ impl ControlPlaneModel {
         self.index_table.get(index_uid)
     }
 
+    /// Returns the fingerprints of the previous indexing parameters of the index from which the
+    /// running pipelines can switch to the current ones without being restarted.
+    pub fn reloadable_params_fingerprints(&self, index_uid: &IndexUid) -> FnvHashSet<u64> {
+        self.reloadable_params_fingerprints
+            .get(index_uid)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn source_metadata(&self, source_uid: &SourceUid) -> Option<&SourceConfig> {
         self.index_metadata(&source_uid.index_uid)?
             .sources
@@ -214,16 +226,54 @@ impl ControlPlaneModel {
         let Some(index_model) = self.index_table.get_mut(index_uid) else {
             bail!("index `{}` not found", index_uid.index_id);
         };
-        let fp_changed = index_model.index_config.indexing_params_fingerprint()
-            != index_config.indexing_params_fingerprint();
+        let previous_fingerprint = index_model.index_config.indexing_params_fingerprint();
+        let fp_changed = previous_fingerprint != index_config.indexing_params_fingerprint();
+
+        if fp_changed {
+            let verdict = index_model
+                .index_config
+                .classify_indexing_params_update(&index_config);
+
+            if let DocMappingUpdateVerdict::Breaking { reason } = verdict {
+                info!(%index_uid, %reason, "indexing pipelines must be restarted to apply update");
+                self.reloadable_params_fingerprints.remove(index_uid);
+            } else {
+                self.reloadable_params_fingerprints
+                    .entry(index_uid.clone())
+                    .or_default()
+                    .insert(previous_fingerprint);
+            }
+        }
         index_model.index_config = index_config;
         self.update_metrics();
         Ok(fp_changed)
     }
 
+    /// Returns whether ingest is blocked for the specified index.
+    pub fn is_ingest_blocked(&self, index_uid: &IndexUid) -> bool {
+        self.index_table
+            .get(index_uid)
+            .map(|index_metadata| index_metadata.ingest_blocked)
+            .unwrap_or(false)
+    }
+
+    /// Blocks or unblocks ingest for the specified index. Returns `true` if the status has
+    /// changed, `false` otherwise. Returns an error if the index could not be found.
+    pub(crate) fn set_ingest_blocked(
+        &mut self,
+        index_uid: &IndexUid,
+        ingest_blocked: bool,
+    ) -> anyhow::Result<bool> {
+        let Some(index_model) = self.index_table.get_mut(index_uid) else {
+            bail!("index `{}` not found", index_uid.index_id);
+        };
+        Ok(index_model.set_ingest_blocked(ingest_blocked))
+    }
+
     pub(crate) fn delete_index(&mut self, index_uid: &IndexUid) {
         self.index_table.remove(index_uid);
         self.index_uid_table.remove(&index_uid.index_id);
+        self.reloadable_params_fingerprints.remove(index_uid);
         self.shard_table.delete_index(&index_uid.index_id);
         self.update_metrics();
     }
@@ -402,6 +452,7 @@ mod tests {
     use quickwit_metastore::IndexMetadata;
     use quickwit_proto::ingest::{Shard, ShardState};
     use quickwit_proto::metastore::{ListIndexesMetadataResponse, MockMetastoreService};
+    use quickwit_proto::types::DocMappingUid;
 
     use super::*;
 
@@ -571,6 +622,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_control_plane_model_update_index_config_reloadable_params_fingerprints() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata.clone());
+
+        let mut index_config = index_metadata.index_config.clone();
+        let fingerprint_0 = index_config.indexing_params_fingerprint();
+
+        // Adding a field is compatible.
+        index_config.doc_mapping.doc_mapping_uid = DocMappingUid::for_test(1);
+        index_config
+            .doc_mapping
+            .field_mappings
+            .push(serde_json::from_str(r#"{"name": "severity", "type": "text"}"#).unwrap());
+        let fingerprint_1 = index_config.indexing_params_fingerprint();
+
+        let fp_changed = model
+            .update_index_config(&index_uid, index_config.clone())
+            .unwrap();
+        assert!(fp_changed);
+        assert_eq!(
+            model.reloadable_params_fingerprints(&index_uid),
+            FnvHashSet::from_iter([fingerprint_0])
+        );
+
+        index_config.doc_mapping.doc_mapping_uid = DocMappingUid::for_test(2);
+        index_config
+            .doc_mapping
+            .field_mappings
+            .push(serde_json::from_str(r#"{"name": "status", "type": "u64"}"#).unwrap());
+        model
+            .update_index_config(&index_uid, index_config.clone())
+            .unwrap();
+        assert_eq!(
+            model.reloadable_params_fingerprints(&index_uid),
+            FnvHashSet::from_iter([fingerprint_0, fingerprint_1])
+        );
+
+        // Changing the type of a field is breaking.
+        index_config.doc_mapping.doc_mapping_uid = DocMappingUid::for_test(3);
+        index_config.doc_mapping.field_mappings.pop();
+        index_config
+            .doc_mapping
+            .field_mappings
+            .push(serde_json::from_str(r#"{"name": "status", "type": "text"}"#).unwrap());
+        model.update_index_config(&index_uid, index_config).unwrap();
+        assert!(model.reloadable_params_fingerprints(&index_uid).is_empty());
+    }
+
     #[test]
     fn test_control_plane_model_delete_index() {
         let mut model = ControlPlaneModel::default();
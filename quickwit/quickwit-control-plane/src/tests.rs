@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::num::NonZeroUsize;
 use std::time::Duration;
 
@@ -28,7 +29,7 @@ use quickwit_common::test_utils::wait_until_predicate;
 use quickwit_common::tower::{Change, Pool};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
-    ClusterConfig, KafkaSourceParams, SourceConfig, SourceInputFormat, SourceParams,
+    ClusterConfig, KafkaSourceParams, SourceConfig, SourceInputFormat, SourceParams, SourcePriority,
 };
 use quickwit_indexing::IndexingService;
 use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
@@ -47,6 +48,10 @@ fn index_metadata_for_test(index_id: &str, source_id: &str, num_pipelines: usize
     let mut index_metadata = IndexMetadata::for_test(index_id, "ram://indexes/test-index");
     let source_config = SourceConfig {
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_id: source_id.to_string(),
         num_pipelines: NonZeroUsize::new(num_pipelines).unwrap(),
         source_params: SourceParams::Kafka(KafkaSourceParams {
@@ -90,6 +95,10 @@ pub fn test_indexer_change_stream(
                             client,
                             indexing_tasks,
                             indexing_capacity: CpuCapacity::from_cpu_millis(4_000),
+                            is_draining: false,
+                            supports_plan_deltas: false,
+                            num_unhealthy_pipelines: 0,
+                            indexing_affinity_labels: BTreeMap::new(),
                         },
                     );
                     Some(change)
@@ -0,0 +1,220 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+
+use tantivy::schema::DateTimePrecision;
+use time::{Date, Month, Time};
+
+use crate::TantivyDateTime;
+
+const NANOS_PER_MICRO: i64 = 1_000;
+const NANOS_PER_MILLI: i64 = 1_000_000;
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const NANOS_PER_MINUTE: i64 = 60 * NANOS_PER_SEC;
+const NANOS_PER_HOUR: i64 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: i64 = 24 * NANOS_PER_HOUR;
+
+/// Finest unit of time expressed by a datetime literal. For instance, `2023-05-25` has a
+/// granularity of a day whereas `2023-05-25T10:20:11.322Z` has a granularity of a millisecond.
+///
+/// Variants are ordered from the coarsest to the finest unit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DateTimeGranularity {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl DateTimeGranularity {
+    /// Returns the granularity of a literal with `num_digits` fractional second digits.
+    pub(crate) fn from_num_subsecond_digits(num_digits: usize) -> Self {
+        match num_digits {
+            0 => DateTimeGranularity::Second,
+            1..=3 => DateTimeGranularity::Millisecond,
+            4..=6 => DateTimeGranularity::Microsecond,
+            _ => DateTimeGranularity::Nanosecond,
+        }
+    }
+
+    /// Returns the coarsest subsecond granularity able to represent `subsecond_nanos`.
+    pub(crate) fn from_subsecond_nanos(subsecond_nanos: u32) -> Self {
+        if subsecond_nanos as i64 % NANOS_PER_MILLI == 0 {
+            DateTimeGranularity::Millisecond
+        } else if subsecond_nanos as i64 % NANOS_PER_MICRO == 0 {
+            DateTimeGranularity::Microsecond
+        } else {
+            DateTimeGranularity::Nanosecond
+        }
+    }
+
+    /// Returns true if this granularity is strictly coarser than the datetime `precision`.
+    pub fn is_coarser_than(&self, precision: DateTimePrecision) -> bool {
+        let precision_granularity = match precision {
+            DateTimePrecision::Seconds => DateTimeGranularity::Second,
+            DateTimePrecision::Milliseconds => DateTimeGranularity::Millisecond,
+            DateTimePrecision::Microseconds => DateTimeGranularity::Microsecond,
+            DateTimePrecision::Nanoseconds => DateTimeGranularity::Nanosecond,
+        };
+        *self < precision_granularity
+    }
+
+    /// Returns the half-open range of datetimes covered by the unit of this granularity that
+    /// contains `date_time`. For instance, the range covering `2023-05-25T10:20:11Z` at day
+    /// granularity is `[2023-05-25T00:00:00Z, 2023-05-26T00:00:00Z)`.
+    ///
+    /// Units of a day or coarser are aligned on UTC.
+    pub fn covering_range(&self, date_time: TantivyDateTime) -> Range<TantivyDateTime> {
+        let unit_nanos = match self {
+            DateTimeGranularity::Year | DateTimeGranularity::Month => {
+                return self.covering_calendar_range(date_time);
+            }
+            DateTimeGranularity::Day => NANOS_PER_DAY,
+            DateTimeGranularity::Hour => NANOS_PER_HOUR,
+            DateTimeGranularity::Minute => NANOS_PER_MINUTE,
+            DateTimeGranularity::Second => NANOS_PER_SEC,
+            DateTimeGranularity::Millisecond => NANOS_PER_MILLI,
+            DateTimeGranularity::Microsecond => NANOS_PER_MICRO,
+            DateTimeGranularity::Nanosecond => 1,
+        };
+        let timestamp_nanos = date_time.into_timestamp_nanos();
+        let start_nanos = timestamp_nanos - timestamp_nanos.rem_euclid(unit_nanos);
+        let end_nanos = start_nanos + unit_nanos;
+        TantivyDateTime::from_timestamp_nanos(start_nanos)
+            ..TantivyDateTime::from_timestamp_nanos(end_nanos)
+    }
+
+    fn covering_calendar_range(&self, date_time: TantivyDateTime) -> Range<TantivyDateTime> {
+        let date = date_time.into_utc().date();
+        let (start_year, start_month, end_year, end_month) = match self {
+            DateTimeGranularity::Year => {
+                (date.year(), Month::January, date.year() + 1, Month::January)
+            }
+            _ => {
+                let end_year = if date.month() == Month::December {
+                    date.year() + 1
+                } else {
+                    date.year()
+                };
+                (date.year(), date.month(), end_year, date.month().next())
+            }
+        };
+        let first_day_of = |year: i32, month: Month| {
+            let date = Date::from_calendar_date(year, month, 1)
+                .expect("the first day of the month should be a valid date");
+            TantivyDateTime::from_primitive(date.with_time(Time::MIDNIGHT))
+        };
+        first_day_of(start_year, start_month)..first_day_of(end_year, end_month)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn covering_range(
+        granularity: DateTimeGranularity,
+        date_time: TantivyDateTime,
+    ) -> (TantivyDateTime, TantivyDateTime) {
+        let range = granularity.covering_range(date_time);
+        (range.start, range.end)
+    }
+
+    #[test]
+    fn test_date_time_granularity_is_coarser_than() {
+        assert!(DateTimeGranularity::Day.is_coarser_than(DateTimePrecision::Seconds));
+        assert!(DateTimeGranularity::Second.is_coarser_than(DateTimePrecision::Milliseconds));
+        assert!(!DateTimeGranularity::Second.is_coarser_than(DateTimePrecision::Seconds));
+        assert!(!DateTimeGranularity::Nanosecond.is_coarser_than(DateTimePrecision::Seconds));
+    }
+
+    #[test]
+    fn test_date_time_granularity_covering_range() {
+        let date_time = TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:11.322 UTC));
+        assert_eq!(
+            covering_range(DateTimeGranularity::Year, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-01-01 00:00:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2024-01-01 00:00:00 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Month, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-05-01 00:00:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2023-06-01 00:00:00 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Day, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-05-25 00:00:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2023-05-26 00:00:00 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Minute, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:21:00 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Second, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:11 UTC)),
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:12 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Millisecond, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:11.322 UTC)),
+                TantivyDateTime::from_utc(datetime!(2023-05-25 10:20:11.323 UTC)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_date_time_granularity_covering_range_end_of_year() {
+        let date_time = TantivyDateTime::from_utc(datetime!(2023-12-31 23:59:59 UTC));
+        assert_eq!(
+            covering_range(DateTimeGranularity::Month, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-12-01 00:00:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2024-01-01 00:00:00 UTC)),
+            )
+        );
+        assert_eq!(
+            covering_range(DateTimeGranularity::Day, date_time),
+            (
+                TantivyDateTime::from_utc(datetime!(2023-12-31 00:00:00 UTC)),
+                TantivyDateTime::from_utc(datetime!(2024-01-01 00:00:00 UTC)),
+            )
+        );
+    }
+}
@@ -24,7 +24,7 @@ use time::format_description::well_known::{Iso8601, Rfc2822, Rfc3339};
 use time::OffsetDateTime;
 
 use super::date_time_format::DateTimeInputFormat;
-use crate::TantivyDateTime;
+use crate::{DateTimeGranularity, TantivyDateTime};
 
 // Minimum supported timestamp value in seconds (13 Apr 1972 23:59:55 GMT).
 const MIN_TIMESTAMP_SECONDS: i64 = 72_057_595;
@@ -36,22 +36,48 @@ pub fn parse_date_time_str(
     date_time_str: &str,
     date_time_formats: &[DateTimeInputFormat],
 ) -> Result<TantivyDateTime, String> {
+    parse_date_time_str_with_granularity(date_time_str, date_time_formats)
+        .map(|(date_time, _granularity)| date_time)
+}
+
+/// Parses a datetime string and returns the parsed datetime along with the granularity of the
+/// literal, i.e. the finest unit of time it expresses. For instance, `2023-05-25` parsed with the
+/// `%Y-%m-%d` format has a granularity of a day.
+pub fn parse_date_time_str_with_granularity(
+    date_time_str: &str,
+    date_time_formats: &[DateTimeInputFormat],
+) -> Result<(TantivyDateTime, DateTimeGranularity), String> {
     for date_time_format in date_time_formats {
         let date_time_opt = match date_time_format {
             DateTimeInputFormat::Iso8601 => parse_iso8601(date_time_str)
-                .map(TantivyDateTime::from_utc)
+                .map(|date_time| {
+                    (
+                        TantivyDateTime::from_utc(date_time),
+                        iso8601_granularity(date_time_str),
+                    )
+                })
                 .ok(),
             DateTimeInputFormat::Rfc2822 => parse_rfc2822(date_time_str)
-                .map(TantivyDateTime::from_utc)
+                .map(|date_time| {
+                    (
+                        TantivyDateTime::from_utc(date_time),
+                        rfc2822_granularity(date_time_str),
+                    )
+                })
                 .ok(),
             DateTimeInputFormat::Rfc3339 => parse_rfc3339(date_time_str)
-                .map(TantivyDateTime::from_utc)
+                .map(|date_time| {
+                    (
+                        TantivyDateTime::from_utc(date_time),
+                        subsecond_granularity(date_time_str),
+                    )
+                })
                 .ok(),
             DateTimeInputFormat::Strptime(parser) => parser
-                .parse_date_time(date_time_str)
-                .map(TantivyDateTime::from_utc)
+                .parse_date_time_with_granularity(date_time_str)
+                .map(|(date_time, granularity)| (TantivyDateTime::from_utc(date_time), granularity))
                 .ok(),
-            DateTimeInputFormat::Timestamp => parse_timestamp_str(date_time_str),
+            DateTimeInputFormat::Timestamp => parse_timestamp_str_with_granularity(date_time_str),
         };
         if let Some(date_time) = date_time_opt {
             return Ok(date_time);
@@ -102,12 +128,18 @@ pub fn parse_timestamp_int(
 }
 
 pub fn parse_timestamp_str(timestamp_str: &str) -> Option<TantivyDateTime> {
+    parse_timestamp_str_with_granularity(timestamp_str).map(|(date_time, _granularity)| date_time)
+}
+
+fn parse_timestamp_str_with_granularity(
+    timestamp_str: &str,
+) -> Option<(TantivyDateTime, DateTimeGranularity)> {
     if let Ok(timestamp) = timestamp_str.parse::<i64>() {
-        return parse_timestamp(timestamp).ok();
+        return parse_timestamp_with_granularity(timestamp).ok();
     }
     if let Some((timestamp_secs_str, subsecond_digits_str)) = timestamp_str.split_once('.') {
         if subsecond_digits_str.is_empty() {
-            return parse_timestamp_str(timestamp_secs_str);
+            return parse_timestamp_str_with_granularity(timestamp_secs_str);
         }
         if let Ok(timestamp_secs) = timestamp_secs_str.parse::<i64>() {
             if (MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS).contains(&timestamp_secs) {
@@ -118,7 +150,12 @@ pub fn parse_timestamp_str(timestamp_str: &str) -> Option<TantivyDateTime> {
                 {
                     let nanos = subsecond_digits * 10i64.pow(9 - num_subsecond_digits as u32);
                     let timestamp_nanos = timestamp_secs * 1_000_000_000 + nanos;
-                    return Some(TantivyDateTime::from_timestamp_nanos(timestamp_nanos));
+                    let granularity =
+                        DateTimeGranularity::from_num_subsecond_digits(num_subsecond_digits);
+                    return Some((
+                        TantivyDateTime::from_timestamp_nanos(timestamp_nanos),
+                        granularity,
+                    ));
                 }
             }
         }
@@ -126,6 +163,48 @@ pub fn parse_timestamp_str(timestamp_str: &str) -> Option<TantivyDateTime> {
     None
 }
 
+/// Returns the granularity of a datetime string that expresses at least seconds, based on the
+/// number of fractional second digits it contains.
+fn subsecond_granularity(date_time_str: &str) -> DateTimeGranularity {
+    let num_subsecond_digits = date_time_str
+        .split_once(['.', ','])
+        .map(|(_, subsecond_str)| {
+            subsecond_str
+                .bytes()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count()
+        })
+        .unwrap_or(0);
+    DateTimeGranularity::from_num_subsecond_digits(num_subsecond_digits)
+}
+
+/// Returns the granularity of a RFC2822 datetime string. Seconds are optional in RFC2822.
+fn rfc2822_granularity(date_time_str: &str) -> DateTimeGranularity {
+    if date_time_str.matches(':').count() >= 2 {
+        DateTimeGranularity::Second
+    } else {
+        DateTimeGranularity::Minute
+    }
+}
+
+/// Returns the granularity of a ISO8601 datetime string. ISO8601 allows reduced precision time
+/// representations in both the basic (`T1209`) and the extended (`T12:09`) formats.
+fn iso8601_granularity(date_time_str: &str) -> DateTimeGranularity {
+    let Some((_date_str, time_str)) = date_time_str.split_once('T') else {
+        return DateTimeGranularity::Day;
+    };
+    let num_time_digits = time_str
+        .bytes()
+        .take_while(|byte| byte.is_ascii_digit() || *byte == b':')
+        .filter(|byte| byte.is_ascii_digit())
+        .count();
+    match num_time_digits {
+        0..=2 => DateTimeGranularity::Hour,
+        3..=4 => DateTimeGranularity::Minute,
+        _ => subsecond_granularity(time_str),
+    }
+}
+
 /// Parses a ISO8601 date.
 fn parse_iso8601(value: &str) -> Result<OffsetDateTime, String> {
     OffsetDateTime::parse(value, &Iso8601::DEFAULT).map_err(|error| error.to_string())
@@ -148,6 +227,14 @@ fn parse_rfc3339(value: &str) -> Result<OffsetDateTime, String> {
 /// - from `13 Apr 1972 23:59:55`: smallest value that can be converted to all precisions.
 /// - to: `16 Mar 2242 12:56:31`: greatest value that can be converted to all precisions.
 pub fn parse_timestamp(timestamp: i64) -> Result<TantivyDateTime, String> {
+    parse_timestamp_with_granularity(timestamp).map(|(date_time, _granularity)| date_time)
+}
+
+/// Same as [`parse_timestamp`] but also returns the granularity inferred from the timestamp
+/// precision.
+pub fn parse_timestamp_with_granularity(
+    timestamp: i64,
+) -> Result<(TantivyDateTime, DateTimeGranularity), String> {
     const MIN_TIMESTAMP_MILLIS: i64 = MIN_TIMESTAMP_SECONDS * 1000;
     const MAX_TIMESTAMP_MILLIS: i64 = MAX_TIMESTAMP_SECONDS * 1000;
 
@@ -158,18 +245,22 @@ pub fn parse_timestamp(timestamp: i64) -> Result<TantivyDateTime, String> {
     const MAX_TIMESTAMP_NANOS: i64 = MAX_TIMESTAMP_SECONDS * 1_000_000_000;
 
     match timestamp {
-        MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS => {
-            Ok(TantivyDateTime::from_timestamp_secs(timestamp))
-        }
-        MIN_TIMESTAMP_MILLIS..=MAX_TIMESTAMP_MILLIS => {
-            Ok(TantivyDateTime::from_timestamp_millis(timestamp))
-        }
-        MIN_TIMESTAMP_MICROS..=MAX_TIMESTAMP_MICROS => {
-            Ok(TantivyDateTime::from_timestamp_micros(timestamp))
-        }
-        MIN_TIMESTAMP_NANOS..=MAX_TIMESTAMP_NANOS => {
-            Ok(TantivyDateTime::from_timestamp_nanos(timestamp))
-        }
+        MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS => Ok((
+            TantivyDateTime::from_timestamp_secs(timestamp),
+            DateTimeGranularity::Second,
+        )),
+        MIN_TIMESTAMP_MILLIS..=MAX_TIMESTAMP_MILLIS => Ok((
+            TantivyDateTime::from_timestamp_millis(timestamp),
+            DateTimeGranularity::Millisecond,
+        )),
+        MIN_TIMESTAMP_MICROS..=MAX_TIMESTAMP_MICROS => Ok((
+            TantivyDateTime::from_timestamp_micros(timestamp),
+            DateTimeGranularity::Microsecond,
+        )),
+        MIN_TIMESTAMP_NANOS..=MAX_TIMESTAMP_NANOS => Ok((
+            TantivyDateTime::from_timestamp_nanos(timestamp),
+            DateTimeGranularity::Nanosecond,
+        )),
         _ => Err(format!(
             "failed to parse unix timestamp `{timestamp}`. Quickwit only support timestamp values \
              ranging from `13 Apr 1972 23:59:55` to `16 Mar 2242 12:56:31`"
@@ -335,6 +426,93 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_date_time_str_with_granularity() {
+        let date_time_formats = [
+            DateTimeInputFormat::Rfc3339,
+            DateTimeInputFormat::Rfc2822,
+            DateTimeInputFormat::Timestamp,
+            DateTimeInputFormat::Strptime(StrptimeParser::from_strptime("%Y-%m-%dT%H:%M").unwrap()),
+            DateTimeInputFormat::Strptime(
+                StrptimeParser::from_strptime("%Y-%m-%d %H:%M:%S.%f").unwrap(),
+            ),
+            DateTimeInputFormat::Strptime(StrptimeParser::from_strptime("%Y-%m-%d").unwrap()),
+            DateTimeInputFormat::Strptime(StrptimeParser::from_strptime("%Y-%m").unwrap()),
+            DateTimeInputFormat::Iso8601,
+        ];
+        for (date_time_str, expected_date_time, expected_granularity) in [
+            (
+                "2012-05-21T12:09:14Z",
+                datetime!(2012-05-21 12:09:14 UTC),
+                DateTimeGranularity::Second,
+            ),
+            (
+                "2012-05-21T12:09:14.123Z",
+                datetime!(2012-05-21 12:09:14.123 UTC),
+                DateTimeGranularity::Millisecond,
+            ),
+            (
+                "2012-05-21T12:09:14.123456+00:00",
+                datetime!(2012-05-21 12:09:14.123456 UTC),
+                DateTimeGranularity::Microsecond,
+            ),
+            (
+                "Mon, 21 May 2012 12:09:14 GMT",
+                datetime!(2012-05-21 12:09:14 UTC),
+                DateTimeGranularity::Second,
+            ),
+            (
+                "1337602154",
+                datetime!(2012-05-21 12:09:14 UTC),
+                DateTimeGranularity::Second,
+            ),
+            (
+                "1337602154123",
+                datetime!(2012-05-21 12:09:14.123 UTC),
+                DateTimeGranularity::Millisecond,
+            ),
+            (
+                "1337602154.5",
+                datetime!(2012-05-21 12:09:14.5 UTC),
+                DateTimeGranularity::Millisecond,
+            ),
+            (
+                "2012-05-21T12:09",
+                datetime!(2012-05-21 12:09:00 UTC),
+                DateTimeGranularity::Minute,
+            ),
+            (
+                "2012-05-21 12:09:14.120",
+                datetime!(2012-05-21 12:09:14.12 UTC),
+                DateTimeGranularity::Millisecond,
+            ),
+            (
+                "2012-05-21",
+                datetime!(2012-05-21 00:00:00 UTC),
+                DateTimeGranularity::Day,
+            ),
+            (
+                "2012-05",
+                datetime!(2012-05-01 00:00:00 UTC),
+                DateTimeGranularity::Month,
+            ),
+            (
+                "20120521T1209Z",
+                datetime!(2012-05-21 12:09:00 UTC),
+                DateTimeGranularity::Minute,
+            ),
+        ] {
+            let (date_time, granularity) =
+                parse_date_time_str_with_granularity(date_time_str, &date_time_formats).unwrap();
+            assert_eq!(
+                date_time,
+                TantivyDateTime::from_utc(expected_date_time),
+                "{date_time_str}"
+            );
+            assert_eq!(granularity, expected_granularity, "{date_time_str}");
+        }
+    }
+
     #[test]
     fn test_parse_timestamp_float() {
         let unix_ts_secs = OffsetDateTime::now_utc().unix_timestamp();
@@ -31,7 +31,7 @@ use time::parsing::Parsed;
 use time::{Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 use time_fmt::parse::time_format_item::parse_to_format_item;
 
-use crate::date_time_format;
+use crate::{date_time_format, DateTimeGranularity};
 
 const JAVA_DATE_FORMAT_TOKENS: &[&str] = &[
     "yyyy",
@@ -349,6 +349,24 @@ impl StrptimeParser {
         date_time_str: &str,
         default_offset: UtcOffset,
     ) -> Result<OffsetDateTime, String> {
+        self.parse_date_time_with_default_timezone_and_granularity(date_time_str, default_offset)
+            .map(|(date_time, _granularity)| date_time)
+    }
+
+    /// Parse a date assuming UTC if unspecified, and returns the granularity of the datetime
+    /// string, i.e. the finest unit of time specified by the format and present in the string.
+    pub fn parse_date_time_with_granularity(
+        &self,
+        date_time_str: &str,
+    ) -> Result<(OffsetDateTime, DateTimeGranularity), String> {
+        self.parse_date_time_with_default_timezone_and_granularity(date_time_str, UtcOffset::UTC)
+    }
+
+    fn parse_date_time_with_default_timezone_and_granularity(
+        &self,
+        date_time_str: &str,
+        default_offset: UtcOffset,
+    ) -> Result<(OffsetDateTime, DateTimeGranularity), String> {
         let mut parsed = Parsed::new();
         if !parsed
             .parse_items(date_time_str.as_bytes(), &self.items)
@@ -361,6 +379,9 @@ impl StrptimeParser {
             ));
         }
 
+        // The granularity must be computed before the missing components are completed below.
+        let granularity = parsed_granularity(&parsed);
+
         // The parsed datetime contains a date but seems to be missing "time".
         // We complete it artificially with 00:00:00.
         if parsed.hour_24().is_none()
@@ -389,12 +410,15 @@ impl StrptimeParser {
             let offset_datetime: OffsetDateTime = parsed
                 .try_into()
                 .map_err(|err: TryFromParsed| err.to_string())?;
-            return Ok(offset_datetime);
+            return Ok((offset_datetime, granularity));
         }
         let primitive_date_time: PrimitiveDateTime = parsed
             .try_into()
             .map_err(|err: TryFromParsed| err.to_string())?;
-        Ok(primitive_date_time.assume_offset(default_offset))
+        Ok((
+            primitive_date_time.assume_offset(default_offset),
+            granularity,
+        ))
     }
 
     pub fn format_date_time(&self, date_time: &OffsetDateTime) -> Result<String, Format> {
@@ -427,6 +451,25 @@ impl StrptimeParser {
     }
 }
 
+/// Returns the finest unit of time present in a parsed datetime.
+fn parsed_granularity(parsed: &Parsed) -> DateTimeGranularity {
+    if let Some(subsecond_nanos) = parsed.subsecond() {
+        DateTimeGranularity::from_subsecond_nanos(subsecond_nanos)
+    } else if parsed.second().is_some() {
+        DateTimeGranularity::Second
+    } else if parsed.minute().is_some() {
+        DateTimeGranularity::Minute
+    } else if parsed.hour_24().is_some() || parsed.hour_12().is_some() {
+        DateTimeGranularity::Hour
+    } else if parsed.day().is_some() || parsed.ordinal().is_some() || parsed.weekday().is_some() {
+        DateTimeGranularity::Day
+    } else if parsed.month().is_some() || parsed.monday_week_number().is_some() {
+        DateTimeGranularity::Month
+    } else {
+        DateTimeGranularity::Year
+    }
+}
+
 impl PartialEq for StrptimeParser {
     fn eq(&self, other: &Self) -> bool {
         self.strptime_format == other.strptime_format
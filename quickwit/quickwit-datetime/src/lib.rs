@@ -18,12 +18,15 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod date_time_format;
+mod date_time_granularity;
 mod date_time_parsing;
 pub mod java_date_time_format;
 
 pub use date_time_format::{DateTimeInputFormat, DateTimeOutputFormat};
+pub use date_time_granularity::DateTimeGranularity;
 pub use date_time_parsing::{
-    parse_date_time_str, parse_timestamp, parse_timestamp_float, parse_timestamp_int,
+    parse_date_time_str, parse_date_time_str_with_granularity, parse_timestamp,
+    parse_timestamp_float, parse_timestamp_int, parse_timestamp_with_granularity,
 };
 pub use java_date_time_format::StrptimeParser;
 pub use tantivy::DateTime as TantivyDateTime;
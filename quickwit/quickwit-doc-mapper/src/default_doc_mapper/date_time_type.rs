@@ -167,6 +167,13 @@ impl Default for InputFormats {
     }
 }
 
+impl InputFormats {
+    /// Returns an iterator over the accepted input formats, in order of precedence.
+    pub fn iter(&self) -> impl Iterator<Item = &DateTimeInputFormat> {
+        self.0.iter()
+    }
+}
+
 impl<'de> Deserialize<'de> for InputFormats {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de> {
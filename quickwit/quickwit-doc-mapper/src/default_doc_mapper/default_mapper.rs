@@ -18,15 +18,20 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::convert::Infallible;
 use std::num::NonZeroU32;
+use std::ops::Bound;
 
 use anyhow::{bail, Context};
 use fnv::FnvHashSet;
 use quickwit_common::PathHasher;
 use quickwit_proto::types::DocMappingUid;
-use quickwit_query::create_default_quickwit_tokenizer_manager;
-use quickwit_query::query_ast::QueryAst;
+use quickwit_query::query_ast::{
+    FullTextQuery, QueryAst, QueryAstTransformer, QueryAstVisitor, RangeQuery, TermQuery,
+    TermSetQuery,
+};
 use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::{create_default_quickwit_tokenizer_manager, InvalidQuery, JsonLiteral};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use serde_json_borrow::Map as BorrowedJsonMap;
@@ -37,7 +42,7 @@ use tantivy::schema::{
 };
 use tantivy::TantivyDocument as Document;
 
-use super::field_mapping_entry::RAW_TOKENIZER_NAME;
+use super::field_mapping_entry::{NumericUnit, RAW_TOKENIZER_NAME};
 use super::DefaultDocMapperBuilder;
 use crate::default_doc_mapper::mapping_tree::{
     build_field_path_from_str, build_mapping_tree, map_primitive_json_to_tantivy,
@@ -47,10 +52,11 @@ use crate::default_doc_mapper::FieldMappingType;
 use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
 use crate::routing_expression::RoutingExpr;
+use crate::term_presence::TermPresenceFilterConfig;
 use crate::{
-    Cardinality, DocMapper, DocMapping, DocParsingError, Mode, ModeType, QueryParserError,
-    TokenizerEntry, WarmupInfo, DOCUMENT_SIZE_FIELD_NAME, DYNAMIC_FIELD_NAME,
-    FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME,
+    Cardinality, DocMapper, DocMapping, DocParsingError, FieldMappingEntry, Mode, ModeType,
+    QueryParserError, TokenizerEntry, WarmupInfo, DOCUMENT_SIZE_FIELD_NAME, DYNAMIC_FIELD_NAME,
+    FIELD_PRESENCE_FIELD_NAME, ID_PSEUDO_FIELD_NAME, SOURCE_FIELD_NAME,
 };
 
 const FIELD_PRESENCE_FIELD: Field = Field::from_field_id(0u32);
@@ -83,15 +89,22 @@ pub struct DefaultDocMapper {
     timestamp_field_name: Option<String>,
     /// Timestamp field path (name parsed)
     timestamp_field_path: Option<Vec<String>>,
+    /// Name of the field uniquely identifying the documents, queried through `_id`.
+    doc_id_field_name: Option<String>,
     /// Root node of the field mapping tree.
     /// See [`MappingNode`].
     field_mappings: MappingNode,
     /// Concat fields which needs to learn about any element put in dynamic_field
     concatenate_dynamic_fields: Vec<Field>,
+    /// Paths of the numeric fields holding numbers of bytes. Query literals targeting these
+    /// fields may carry a size suffix, e.g. `10MB`.
+    byte_size_field_paths: HashSet<Vec<String>>,
     /// Schema generated by the store source and field mappings parameters.
     schema: Schema,
     /// List of field names used for tagging.
     tag_field_names: BTreeSet<String>,
+    /// Fields for which a term presence filter is recorded in the splits metadata.
+    term_presence_filter_configs: Vec<TermPresenceFilterConfig>,
     /// The partition key is a DSL used to route documents
     /// into specific splits.
     partition_key: RoutingExpr,
@@ -133,6 +146,24 @@ fn validate_timestamp_field(
     Ok(())
 }
 
+fn validate_doc_id_field(doc_id_field_name: &str, schema: &Schema) -> anyhow::Result<()> {
+    let doc_id_field = schema
+        .get_field(doc_id_field_name)
+        .with_context(|| format!("could not find doc id field `{doc_id_field_name}`"))?;
+    let doc_id_field_entry = schema.get_field_entry(doc_id_field);
+
+    if !doc_id_field_entry.is_indexed() {
+        bail!("doc id field `{doc_id_field_name}` should be indexed");
+    }
+    if !matches!(
+        doc_id_field_entry.field_type(),
+        FieldType::Str(_) | FieldType::U64(_) | FieldType::I64(_)
+    ) {
+        bail!("doc id field `{doc_id_field_name}` should be a text, u64, or i64 field");
+    }
+    Ok(())
+}
+
 impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
     fn from(default_doc_mapper: DefaultDocMapper) -> Self {
         let partition_key_str = default_doc_mapper.partition_key.to_string();
@@ -147,7 +178,9 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             field_mappings: default_doc_mapper.field_mappings.into(),
             timestamp_field: default_doc_mapper.timestamp_field_name,
             tag_fields: default_doc_mapper.tag_field_names,
+            term_presence_filters: default_doc_mapper.term_presence_filter_configs,
             partition_key: partition_key_opt,
+            doc_id_field: default_doc_mapper.doc_id_field_name,
             max_num_partitions: default_doc_mapper.max_num_partitions,
             index_field_presence: default_doc_mapper.index_field_presence,
             store_document_size: default_doc_mapper.document_size_field.is_some(),
@@ -199,6 +232,12 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
         if !concatenate_dynamic_fields.is_empty() && dynamic_field.is_none() {
             bail!("concatenate field has `include_dynamic_fields` set, but index isn't dynamic");
         }
+        let mut byte_size_field_paths = HashSet::new();
+        collect_byte_size_field_paths(
+            &mut Vec::new(),
+            &doc_mapping.field_mappings,
+            &mut byte_size_field_paths,
+        );
         let timestamp_field_path = if let Some(timestamp_field_name) = &doc_mapping.timestamp_field
         {
             validate_timestamp_field(timestamp_field_name, &field_mappings)?;
@@ -208,6 +247,9 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
         };
         let schema = schema_builder.build();
 
+        if let Some(doc_id_field_name) = &doc_mapping.doc_id_field {
+            validate_doc_id_field(doc_id_field_name, &schema)?;
+        }
         let tokenizer_manager = create_default_quickwit_tokenizer_manager();
         let mut custom_tokenizer_names = HashSet::new();
         for tokenizer_config_entry in &doc_mapping.tokenizers {
@@ -272,6 +314,17 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             validate_tag(tag_field_name, &schema)?;
         }
 
+        // Resolve term presence fields
+        let mut term_presence_field_names = HashSet::new();
+        for term_presence_filter_config in &doc_mapping.term_presence_filters {
+            let field_name = &term_presence_filter_config.field;
+            if !term_presence_field_names.insert(field_name) {
+                bail!("duplicated term presence filter for field `{field_name}`");
+            }
+            validate_term_presence_field(field_name, &schema)?;
+            term_presence_filter_config.validate()?;
+        }
+
         let partition_key_expr: &str = doc_mapping.partition_key.as_deref().unwrap_or("");
         let partition_key = RoutingExpr::new(partition_key_expr).with_context(|| {
             format!("failed to interpret the partition key: `{partition_key_expr}`")
@@ -295,9 +348,12 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             default_search_field_names,
             timestamp_field_name: doc_mapping.timestamp_field,
             timestamp_field_path,
+            doc_id_field_name: doc_mapping.doc_id_field,
             field_mappings,
             concatenate_dynamic_fields,
+            byte_size_field_paths,
             tag_field_names,
+            term_presence_filter_configs: doc_mapping.term_presence_filters,
             partition_key,
             max_num_partitions: doc_mapping.max_num_partitions,
             mode: doc_mapping.mode,
@@ -307,6 +363,206 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
     }
 }
 
+fn collect_byte_size_field_paths(
+    field_path: &mut Vec<String>,
+    field_mappings: &[FieldMappingEntry],
+    byte_size_field_paths: &mut HashSet<Vec<String>>,
+) {
+    for field_mapping in field_mappings {
+        field_path.push(field_mapping.name.clone());
+
+        match &field_mapping.mapping_type {
+            FieldMappingType::U64(numeric_options, _)
+            | FieldMappingType::I64(numeric_options, _)
+                if numeric_options.unit == Some(NumericUnit::Bytes) =>
+            {
+                byte_size_field_paths.insert(field_path.clone());
+            }
+            FieldMappingType::Object(object_options) => {
+                collect_byte_size_field_paths(
+                    field_path,
+                    &object_options.field_mappings,
+                    byte_size_field_paths,
+                );
+            }
+            _ => {}
+        }
+        field_path.pop();
+    }
+}
+
+/// Converts the literals with a size suffix, such as `10MB`, targeting byte size fields into
+/// numbers of bytes.
+struct ByteSizeLiteralConverter<'a> {
+    byte_size_field_paths: &'a HashSet<Vec<String>>,
+}
+
+impl<'a> ByteSizeLiteralConverter<'a> {
+    fn is_byte_size_field(&self, field_name: &str) -> bool {
+        self.byte_size_field_paths
+            .contains(&build_field_path_from_str(field_name))
+    }
+
+    fn convert_str(&self, field_name: &str, text: String) -> Result<String, InvalidQuery> {
+        let converted_text = match JsonLiteral::String(text).interpret_byte_size(field_name)? {
+            JsonLiteral::Number(num_bytes) => num_bytes.to_string(),
+            JsonLiteral::String(text) => text,
+            JsonLiteral::Bool(_) | JsonLiteral::Array(_) => {
+                unreachable!("string literals should only become numbers")
+            }
+        };
+        Ok(converted_text)
+    }
+
+    fn convert_bound(
+        &self,
+        field_name: &str,
+        bound: Bound<JsonLiteral>,
+    ) -> Result<Bound<JsonLiteral>, InvalidQuery> {
+        let converted_bound = match bound {
+            Bound::Included(literal) => Bound::Included(literal.interpret_byte_size(field_name)?),
+            Bound::Excluded(literal) => Bound::Excluded(literal.interpret_byte_size(field_name)?),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        Ok(converted_bound)
+    }
+}
+
+impl<'a> QueryAstTransformer for ByteSizeLiteralConverter<'a> {
+    type Err = InvalidQuery;
+
+    fn transform_term(
+        &mut self,
+        mut term_query: TermQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        if self.is_byte_size_field(&term_query.field) {
+            term_query.value = self.convert_str(&term_query.field, term_query.value)?;
+        }
+        Ok(Some(term_query.into()))
+    }
+
+    fn transform_term_set(
+        &mut self,
+        mut term_set_query: TermSetQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        for (field_name, terms) in term_set_query.terms_per_field.iter_mut() {
+            if !self.is_byte_size_field(field_name) {
+                continue;
+            }
+            *terms = std::mem::take(terms)
+                .into_iter()
+                .map(|term| self.convert_str(field_name, term))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(Some(term_set_query.into()))
+    }
+
+    fn transform_full_text(
+        &mut self,
+        mut full_text_query: FullTextQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        if self.is_byte_size_field(&full_text_query.field) {
+            full_text_query.text =
+                self.convert_str(&full_text_query.field, full_text_query.text)?;
+        }
+        Ok(Some(full_text_query.into()))
+    }
+
+    fn transform_range(
+        &mut self,
+        mut range_query: RangeQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        if self.is_byte_size_field(&range_query.field) {
+            range_query.lower_bound =
+                self.convert_bound(&range_query.field, range_query.lower_bound)?;
+            range_query.upper_bound =
+                self.convert_bound(&range_query.field, range_query.upper_bound)?;
+        }
+        Ok(Some(range_query.into()))
+    }
+}
+
+/// Finds the clauses querying the `_id` pseudo-field.
+#[derive(Default)]
+struct IdPseudoFieldFinder {
+    found: bool,
+}
+
+impl<'a> QueryAstVisitor<'a> for IdPseudoFieldFinder {
+    type Err = Infallible;
+
+    fn visit_term(&mut self, term_query: &'a TermQuery) -> Result<(), Infallible> {
+        self.found |= term_query.field == ID_PSEUDO_FIELD_NAME;
+        Ok(())
+    }
+
+    fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> Result<(), Infallible> {
+        self.found |= term_set_query
+            .terms_per_field
+            .contains_key(ID_PSEUDO_FIELD_NAME);
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> Result<(), Infallible> {
+        self.found |= full_text_query.field == ID_PSEUDO_FIELD_NAME;
+        Ok(())
+    }
+}
+
+/// Rewrites the clauses querying the `_id` pseudo-field to the doc id field of the doc mapping.
+struct IdPseudoFieldResolver<'a> {
+    doc_id_field_name_opt: Option<&'a str>,
+}
+
+impl<'a> IdPseudoFieldResolver<'a> {
+    fn resolve(&self, field_name: &mut String) -> Result<(), InvalidQuery> {
+        if field_name != ID_PSEUDO_FIELD_NAME {
+            return Ok(());
+        }
+        let doc_id_field_name = self
+            .doc_id_field_name_opt
+            .ok_or(InvalidQuery::MissingDocIdField)?;
+        *field_name = doc_id_field_name.to_string();
+        Ok(())
+    }
+}
+
+impl<'a> QueryAstTransformer for IdPseudoFieldResolver<'a> {
+    type Err = InvalidQuery;
+
+    fn transform_term(
+        &mut self,
+        mut term_query: TermQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        self.resolve(&mut term_query.field)?;
+        Ok(Some(term_query.into()))
+    }
+
+    fn transform_term_set(
+        &mut self,
+        mut term_set_query: TermSetQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        if let Some(terms) = term_set_query.terms_per_field.remove(ID_PSEUDO_FIELD_NAME) {
+            let mut field_name = ID_PSEUDO_FIELD_NAME.to_string();
+            self.resolve(&mut field_name)?;
+            term_set_query
+                .terms_per_field
+                .entry(field_name)
+                .or_default()
+                .extend(terms);
+        }
+        Ok(Some(term_set_query.into()))
+    }
+
+    fn transform_full_text(
+        &mut self,
+        mut full_text_query: FullTextQuery,
+    ) -> Result<Option<QueryAst>, InvalidQuery> {
+        self.resolve(&mut full_text_query.field)?;
+        Ok(Some(full_text_query.into()))
+    }
+}
+
 /// Checks that a given field name is a valid candidate for a tag.
 ///
 /// The conditions are:
@@ -359,6 +615,26 @@ fn validate_tag(tag_field_name: &str, schema: &Schema) -> Result<(), anyhow::Err
     Ok(())
 }
 
+/// Checks that a given field name is a valid candidate for a term presence filter.
+///
+/// The field must be an indexed text field using the `raw` tokenizer, so that the values of the
+/// field recorded in the filter are exactly the terms searched for by term queries.
+fn validate_term_presence_field(field_name: &str, schema: &Schema) -> anyhow::Result<()> {
+    let field = schema
+        .get_field(field_name)
+        .with_context(|| format!("unknown term presence filter field: `{field_name}`"))?;
+    let FieldType::Str(options) = schema.get_field_entry(field).field_type() else {
+        bail!("term presence filter field `{field_name}` should be a text field");
+    };
+    let Some(text_field_indexing) = options.get_indexing_options() else {
+        bail!("term presence filter field `{field_name}` should be indexed");
+    };
+    if text_field_indexing.tokenizer() != RAW_TOKENIZER_NAME {
+        bail!("term presence filter field `{field_name}` should use the `raw` tokenizer");
+    }
+    Ok(())
+}
+
 /// Checks that a given text/json field name has a registered tokenizer.
 fn validate_fields_tokenizers(
     schema: &Schema,
@@ -452,35 +728,51 @@ fn tantivy_value_to_json(val: TantivyValue) -> JsonValue {
     }
 }
 
+/// Records the presence of the leaves of a JSON value if `record_leaves` is set, and of the JSON
+/// objects holding at least one leaf.
+///
+/// Returns true if the value holds at least one leaf.
 #[inline]
 fn populate_field_presence_for_json_value<'a>(
     json_value: impl Value<'a>,
     path_hasher: &PathHasher,
     is_expand_dots_enabled: bool,
+    record_leaves: bool,
     output: &mut FnvHashSet<u64>,
-) {
+) -> bool {
     match json_value.as_value() {
-        ReferenceValue::Leaf(ReferenceValueLeaf::Null) => {}
+        ReferenceValue::Leaf(ReferenceValueLeaf::Null) => false,
         ReferenceValue::Leaf(_) => {
-            output.insert(path_hasher.finish());
+            if record_leaves {
+                output.insert(path_hasher.finish());
+            }
+            true
         }
         ReferenceValue::Array(items) => {
+            let mut is_present = false;
             for item in items {
-                populate_field_presence_for_json_value(
+                is_present |= populate_field_presence_for_json_value(
                     item,
                     path_hasher,
                     is_expand_dots_enabled,
+                    record_leaves,
                     output,
                 );
             }
+            is_present
         }
         ReferenceValue::Object(json_obj) => {
-            populate_field_presence_for_json_obj(
+            let is_present = populate_field_presence_for_json_obj(
                 json_obj,
                 path_hasher.clone(),
                 is_expand_dots_enabled,
+                record_leaves,
                 output,
             );
+            if is_present {
+                output.insert(path_hasher.finish());
+            }
+            is_present
         }
     }
 }
@@ -489,23 +781,53 @@ fn populate_field_presence_for_json_obj<'a, Iter: Iterator<Item = (&'a str, impl
     json_obj: Iter,
     path_hasher: PathHasher,
     is_expand_dots_enabled: bool,
+    record_leaves: bool,
     output: &mut FnvHashSet<u64>,
-) {
+) -> bool {
+    let mut is_present = false;
+
     for (field_key, field_value) in json_obj {
         let mut child_path_hasher = path_hasher.clone();
+        // The dots of the key delimit objects nested in `json_obj`.
+        let mut dotted_object_path_hashes: Vec<u64> = Vec::new();
+
         if is_expand_dots_enabled {
-            for segment in field_key.split('.') {
+            for (segment_ord, segment) in field_key.split('.').enumerate() {
+                if segment_ord > 0 {
+                    dotted_object_path_hashes.push(child_path_hasher.finish());
+                }
                 child_path_hasher.append(segment.as_bytes());
             }
         } else {
             child_path_hasher.append(field_key.as_bytes());
         };
-        populate_field_presence_for_json_value(
+        if populate_field_presence_for_json_value(
             field_value,
             &child_path_hasher,
             is_expand_dots_enabled,
+            record_leaves,
             output,
-        );
+        ) {
+            output.extend(dotted_object_path_hashes);
+            is_present = true;
+        }
+    }
+    is_present
+}
+
+impl DefaultDocMapper {
+    fn queries_id_pseudo_field(&self, query_ast: &QueryAst) -> bool {
+        if self
+            .field_mappings
+            .find_field_mapping_type(ID_PSEUDO_FIELD_NAME)
+            .is_some()
+        {
+            return false;
+        }
+        let mut id_pseudo_field_finder = IdPseudoFieldFinder::default();
+        // This cannot fail. The error type is Infallible.
+        let _: Result<(), Infallible> = id_pseudo_field_finder.visit(query_ast);
+        id_pseudo_field_finder.found
     }
 }
 
@@ -614,25 +936,36 @@ impl DocMapper for DefaultDocMapper {
                 FnvHashSet::with_capacity_and_hasher(document.len(), Default::default());
             for (field, value) in document.field_values() {
                 let field_entry = self.schema.get_field_entry(field);
-                if !field_entry.is_indexed() || field_entry.is_fast() {
-                    // We are using an tantivy's ExistsQuery for fast fields.
+                let json_options_opt =
+                    if let FieldType::JsonObject(json_options) = field_entry.field_type() {
+                        Some(json_options)
+                    } else {
+                        None
+                    };
+                if !field_entry.is_indexed()
+                    || (field_entry.is_fast() && json_options_opt.is_none())
+                {
+                    // Exists queries on fast fields are served by the column index.
                     continue;
                 }
                 let mut path_hasher: PathHasher = PathHasher::default();
                 path_hasher.append(&field.field_id().to_le_bytes()[..]);
                 if let Some(json_obj) = value.as_object() {
-                    let is_expand_dots_enabled: bool =
-                        if let FieldType::JsonObject(json_options) = field_entry.field_type() {
-                            json_options.is_expand_dots_enabled()
-                        } else {
-                            false
-                        };
-                    populate_field_presence_for_json_obj(
+                    let is_expand_dots_enabled: bool = json_options_opt
+                        .map(|json_options| json_options.is_expand_dots_enabled())
+                        .unwrap_or(false);
+                    // The column index has no column for JSON objects, so their presence is
+                    // recorded even for fast JSON fields.
+                    let is_present = populate_field_presence_for_json_obj(
                         json_obj,
-                        path_hasher,
+                        path_hasher.clone(),
                         is_expand_dots_enabled,
+                        !field_entry.is_fast(),
                         &mut field_presence_hashes,
                     );
+                    if is_present {
+                        field_presence_hashes.insert(path_hasher.finish());
+                    }
                 } else {
                     field_presence_hashes.insert(path_hasher.finish());
                 }
@@ -709,11 +1042,37 @@ impl DocMapper for DefaultDocMapper {
         query_ast: &QueryAst,
         with_validation: bool,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
+        let converted_query_ast_opt = if self.byte_size_field_paths.is_empty() {
+            None
+        } else {
+            let mut byte_size_literal_converter = ByteSizeLiteralConverter {
+                byte_size_field_paths: &self.byte_size_field_paths,
+            };
+            let converted_query_ast = byte_size_literal_converter
+                .transform(query_ast.clone())?
+                .unwrap_or(QueryAst::MatchNone);
+            Some(converted_query_ast)
+        };
+        let query_ast = converted_query_ast_opt.as_ref().unwrap_or(query_ast);
+
+        // The `_id` pseudo-field is only resolved if the doc mapping does not define an actual
+        // `_id` field.
+        let resolved_query_ast_opt = if self.queries_id_pseudo_field(query_ast) {
+            let mut id_pseudo_field_resolver = IdPseudoFieldResolver {
+                doc_id_field_name_opt: self.doc_id_field_name.as_deref(),
+            };
+            let resolved_query_ast = id_pseudo_field_resolver
+                .transform(query_ast.clone())?
+                .unwrap_or(QueryAst::MatchNone);
+            Some(resolved_query_ast)
+        } else {
+            None
+        };
+        let query_ast = resolved_query_ast_opt.as_ref().unwrap_or(query_ast);
         build_query(
             query_ast,
             split_schema,
             self.tokenizer_manager(),
-            &self.default_search_field_names[..],
             with_validation,
         )
     }
@@ -730,10 +1089,29 @@ impl DocMapper for DefaultDocMapper {
         self.timestamp_field_name.as_deref()
     }
 
+    fn is_single_valued_field(&self, field_path: &str) -> bool {
+        let Some(field_mapping_type) = self.field_mappings.find_field_mapping_type(field_path)
+        else {
+            return false;
+        };
+        match field_mapping_type {
+            FieldMappingType::I64(_, cardinality)
+            | FieldMappingType::U64(_, cardinality)
+            | FieldMappingType::F64(_, cardinality)
+            | FieldMappingType::DateTime(_, cardinality)
+            | FieldMappingType::Bool(_, cardinality) => cardinality == Cardinality::SingleValued,
+            _ => false,
+        }
+    }
+
     fn tag_field_names(&self) -> BTreeSet<String> {
         self.tag_field_names.clone()
     }
 
+    fn term_presence_filter_configs(&self) -> &[TermPresenceFilterConfig] {
+        &self.term_presence_filter_configs
+    }
+
     fn max_num_partitions(&self) -> NonZeroU32 {
         self.max_num_partitions
     }
@@ -750,7 +1128,7 @@ mod tests {
 
     use itertools::Itertools;
     use quickwit_common::PathHasher;
-    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst, TermQuery};
     use serde_json::{self, json, Value as JsonValue};
     use tantivy::schema::{
         FieldType, IndexRecordOption, OwnedValue as TantivyValue, OwnedValue, Type, Value,
@@ -821,8 +1199,8 @@ mod tests {
         let schema = doc_mapper.schema();
         // 9 property entry + 1 field "_source" + 2 fields values for "tags" field
         // + 2 values inf "server.status" field + 2 values in "server.payload" field
-        // + 7 values for field presence
-        assert_eq!(document.len(), 23);
+        // + 8 values for field presence
+        assert_eq!(document.len(), 24);
         let expected_json_paths_and_values: HashMap<String, JsonValue> =
             serde_json::from_str(EXPECTED_JSON_PATHS_AND_VALUES).unwrap();
         let mut field_presences: HashSet<u64> = HashSet::new();
@@ -867,7 +1245,7 @@ mod tests {
                 }
             }
         }
-        assert_eq!(field_presences.len(), 7);
+        assert_eq!(field_presences.len(), 8);
         let timestamp_field = schema.get_field("timestamp").unwrap();
         let body_field = schema.get_field("body").unwrap();
         let attributes_field = schema.get_field("attributes.server").unwrap();
@@ -1358,6 +1736,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_field_presence_of_json_objects() {
+        let doc_mapper = r#"{
+            "index_field_presence": true,
+            "mode": "dynamic",
+            "field_mappings": [
+                {
+                    "name": "user",
+                    "type": "object",
+                    "field_mappings": [
+                        {
+                            "name": "name",
+                            "type": "text"
+                        }
+                    ]
+                }
+            ]
+        }"#;
+        let doc_mapper = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper)
+            .unwrap()
+            .try_build()
+            .unwrap();
+        let schema = doc_mapper.schema();
+        let (_, document) = doc_mapper
+            .doc_from_json_str(
+                r#"{
+                    "user": {"name": "Fred", "address": {"city": "Paris", "zip": null}},
+                    "tags": [{"color": "red"}],
+                    "k8s.pod.name": "quickwit",
+                    "empty": {"obj": {}, "null": null}
+                }"#,
+            )
+            .unwrap();
+        let field_presences: HashSet<u64> = document
+            .get_all(schema.get_field(FIELD_PRESENCE_FIELD_NAME).unwrap())
+            .map(|value| value.as_u64().unwrap())
+            .collect();
+
+        let user_name_field = schema.get_field("user.name").unwrap();
+        let dynamic_field = schema.get_field(DYNAMIC_FIELD_NAME).unwrap();
+        let dynamic_path_hash = |path: &[&[u8]]| {
+            let dynamic_field_id = dynamic_field.field_id().to_le_bytes();
+            let mut full_path: Vec<&[u8]> = vec![&dynamic_field_id[..]];
+            full_path.extend_from_slice(path);
+            PathHasher::hash_path(&full_path)
+        };
+        let expected_field_presences: HashSet<u64> = [
+            PathHasher::hash_path(&[&user_name_field.field_id().to_le_bytes()[..]]),
+            // The leaves of the dynamic field are served by its column index, but its objects
+            // have no column.
+            dynamic_path_hash(&[]),
+            dynamic_path_hash(&[b"user"]),
+            dynamic_path_hash(&[b"user", b"address"]),
+            dynamic_path_hash(&[b"tags"]),
+            dynamic_path_hash(&[b"k8s"]),
+            dynamic_path_hash(&[b"k8s", b"pod"]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(field_presences, expected_field_presences);
+    }
+
     #[test]
     fn test_partition_key_in_tags() {
         let doc_mapper = r#"{
@@ -1505,6 +1945,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_doc_mapper_with_term_presence_filters() {
+        let build_doc_mapper = |term_presence_filters: &str| {
+            let doc_mapper = format!(
+                r#"{{
+                    "default_search_fields": [],
+                    "term_presence_filters": {term_presence_filters},
+                    "field_mappings": [
+                        {{
+                            "name": "trace_id",
+                            "type": "text",
+                            "tokenizer": "raw"
+                        }},
+                        {{
+                            "name": "body",
+                            "type": "text"
+                        }},
+                        {{
+                            "name": "status",
+                            "type": "u64"
+                        }}
+                    ]
+                }}"#
+            );
+            serde_json::from_str::<DefaultDocMapperBuilder>(&doc_mapper)
+                .unwrap()
+                .try_build()
+        };
+        let doc_mapper =
+            build_doc_mapper(r#"[{"field": "trace_id", "false_positive_rate": 0.001}]"#).unwrap();
+        let term_presence_named_fields = doc_mapper.term_presence_named_fields().unwrap();
+        assert_eq!(term_presence_named_fields.len(), 1);
+        assert_eq!(term_presence_named_fields[0].0.name, "trace_id");
+        assert_eq!(term_presence_named_fields[0].1.false_positive_rate, 0.001);
+
+        let doc_mapping = DefaultDocMapperBuilder::from(doc_mapper).doc_mapping;
+        assert_eq!(doc_mapping.term_presence_filters.len(), 1);
+
+        assert_eq!(
+            build_doc_mapper(r#"[{"field": "body"}]"#)
+                .unwrap_err()
+                .to_string(),
+            "term presence filter field `body` should use the `raw` tokenizer"
+        );
+        assert_eq!(
+            build_doc_mapper(r#"[{"field": "status"}]"#)
+                .unwrap_err()
+                .to_string(),
+            "term presence filter field `status` should be a text field"
+        );
+        assert_eq!(
+            build_doc_mapper(r#"[{"field": "trace_id"}, {"field": "trace_id"}]"#)
+                .unwrap_err()
+                .to_string(),
+            "duplicated term presence filter for field `trace_id`"
+        );
+        build_doc_mapper(r#"[{"field": "trace_id", "false_positive_rate": 0}]"#).unwrap_err();
+    }
+
     // See #1132
     #[test]
     fn test_by_default_store_source_is_false_and_fields_are_stored_individually() {
@@ -2108,6 +2607,133 @@ mod tests {
         Ok(format!("{query:?}"))
     }
 
+    #[test]
+    fn test_doc_mapper_query_with_byte_size_literals() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "size", "type": "u64", "unit": "bytes", "fast": true},
+                {"name": "count", "type": "u64", "fast": true},
+                {
+                    "name": "response",
+                    "type": "object",
+                    "field_mappings": [
+                        {"name": "size", "type": "i64", "unit": "bytes", "fast": true}
+                    ]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:>10MB"),
+            default_doc_mapper_query_aux(&doc_mapper, "size:>10000000"),
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:>10MiB"),
+            default_doc_mapper_query_aux(&doc_mapper, "size:>10485760"),
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:[1kb TO 2KiB]"),
+            default_doc_mapper_query_aux(&doc_mapper, "size:[1000 TO 2048]"),
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:1GB"),
+            default_doc_mapper_query_aux(&doc_mapper, "size:1000000000"),
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "response.size:<=1.5kB"),
+            default_doc_mapper_query_aux(&doc_mapper, "response.size:<=1500"),
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:>10XB").unwrap_err(),
+            "invalid query: invalid byte size `10XB` for field `size`: unknown unit `XB`"
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "size:>20000PB").unwrap_err(),
+            "invalid query: invalid byte size `20000PB` for field `size`: `20000PB` overflows u64"
+        );
+        // Fields without the `bytes` unit are left untouched.
+        assert!(default_doc_mapper_query_aux(&doc_mapper, "count:>10MB").is_err());
+    }
+
+    #[test]
+    fn test_doc_mapper_query_with_id_pseudo_field() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [
+                {"name": "event_id", "type": "text", "tokenizer": "raw"},
+                {"name": "body", "type": "text"}
+            ],
+            "doc_id_field": "event_id"
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "_id:abc-123"),
+            default_doc_mapper_query_aux(&doc_mapper, "event_id:abc-123"),
+        );
+        let term_query_aux = |field: &str| {
+            let query_ast = QueryAst::Term(TermQuery {
+                field: field.to_string(),
+                value: "abc-123".to_string(),
+            });
+            let (query, _) = doc_mapper
+                .query(doc_mapper.schema(), &query_ast, true)
+                .unwrap();
+            format!("{query:?}")
+        };
+        assert_eq!(term_query_aux("_id"), term_query_aux("event_id"));
+
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [{"name": "body", "type": "text"}]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "_id:abc-123").unwrap_err(),
+            "invalid query: the index has no unique id field to query `_id` on: set \
+             `doc_id_field` in its doc mapping"
+        );
+
+        // An actual `_id` field is queried as is.
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "field_mappings": [{"name": "_id", "type": "text", "tokenizer": "raw"}]
+        }"#,
+        )
+        .unwrap();
+        assert!(default_doc_mapper_query_aux(&doc_mapper, "_id:abc-123").is_ok());
+    }
+
+    #[test]
+    fn test_fail_to_build_doc_mapper_with_invalid_doc_id_field() {
+        for (doc_id_field, expected_msg) in [
+            ("missing", "could not find doc id field `missing`"),
+            (
+                "not_indexed",
+                "doc id field `not_indexed` should be indexed",
+            ),
+            (
+                "ts",
+                "doc id field `ts` should be a text, u64, or i64 field",
+            ),
+        ] {
+            let doc_mapper = format!(
+                r#"{{
+                "doc_id_field": "{doc_id_field}",
+                "field_mappings": [
+                    {{"name": "not_indexed", "type": "u64", "indexed": false, "fast": true}},
+                    {{"name": "ts", "type": "datetime", "fast": true}}
+                ]
+            }}"#
+            );
+            let builder = serde_json::from_str::<DefaultDocMapperBuilder>(&doc_mapper).unwrap();
+            assert_eq!(builder.try_build().unwrap_err().to_string(), expected_msg);
+        }
+    }
+
     #[test]
     fn test_doc_mapper_sub_field_query_on_non_json_field_should_error() {
         let doc_mapper: DefaultDocMapper = serde_json::from_str(
@@ -2289,6 +2915,43 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_is_single_valued_field() {
+        let mapper = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "field_mappings": [
+                {
+                    "name": "price",
+                    "type": "f64",
+                    "fast": true
+                },
+                {
+                    "name": "prices",
+                    "type": "array<f64>",
+                    "fast": true
+                },
+                {
+                    "name": "some_obj",
+                    "type": "object",
+                    "field_mappings": [
+                        {
+                            "name": "timestamp",
+                            "type": "datetime",
+                            "fast": true
+                        }
+                    ]
+                }
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert!(mapper.is_single_valued_field("price"));
+        assert!(!mapper.is_single_valued_field("prices"));
+        assert!(mapper.is_single_valued_field("some_obj.timestamp"));
+        assert!(!mapper.is_single_valued_field("some_obj"));
+        assert!(!mapper.is_single_valued_field("unknown"));
+    }
+
     #[test]
     fn test_build_doc_mapper_with_custom_ngram_tokenizer() {
         let mapper = serde_json::from_str::<DefaultDocMapper>(
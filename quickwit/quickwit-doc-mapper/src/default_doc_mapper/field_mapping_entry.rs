@@ -22,6 +22,7 @@ use std::convert::TryFrom;
 
 use anyhow::bail;
 use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_query::IP_NORMALIZER_NAME;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tantivy::schema::{
@@ -99,6 +100,11 @@ pub struct QuickwitNumericOptions {
     pub coerce: bool,
     #[serde(default)]
     pub output_format: NumericOutputFormat,
+    /// Unit of the values of the field. Query literals expressed in this unit are converted
+    /// into numbers.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<NumericUnit>,
 }
 
 impl Default for QuickwitNumericOptions {
@@ -110,6 +116,7 @@ impl Default for QuickwitNumericOptions {
             fast: false,
             coerce: true,
             output_format: NumericOutputFormat::default(),
+            unit: None,
         }
     }
 }
@@ -243,6 +250,14 @@ pub enum NumericOutputFormat {
     String,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumericUnit {
+    /// Values are numbers of bytes. Query literals such as `10MB` or `1GiB` are converted into
+    /// numbers of bytes.
+    Bytes,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct QuickwitIpAddrOptions {
@@ -309,6 +324,16 @@ impl QuickwitTextNormalizer {
     }
 }
 
+/// Type the values of a text field are coerced into in its fast field, so that range queries
+/// compare them as values of that type rather than lexicographically.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuickwitTextCoercion {
+    /// IP addresses stored as strings. The fast field holds the canonical IPv6 textual form of
+    /// the addresses, and the values that are not valid IP addresses are left out of it.
+    Ip,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct TextIndexingOptions {
     pub tokenizer: QuickwitTextTokenizer,
@@ -455,6 +480,9 @@ pub struct QuickwitTextOptions {
     pub stored: bool,
     #[serde(default)]
     pub fast: FastFieldOptions,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coerce: Option<QuickwitTextCoercion>,
 }
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -520,6 +548,7 @@ impl Default for QuickwitTextOptions {
             indexing_options: Some(TextIndexingOptions::default()),
             stored: true,
             fast: FastFieldOptions::default(),
+            coerce: None,
         }
     }
 }
@@ -530,11 +559,14 @@ impl From<QuickwitTextOptions> for TextOptions {
         if quickwit_text_options.stored {
             text_options = text_options.set_stored();
         }
-        match &quickwit_text_options.fast {
-            FastFieldOptions::EnabledWithNormalizer { normalizer } => {
+        match (&quickwit_text_options.fast, quickwit_text_options.coerce) {
+            (FastFieldOptions::EnabledWithNormalizer { .. }, Some(QuickwitTextCoercion::Ip)) => {
+                text_options = text_options.set_fast(Some(IP_NORMALIZER_NAME));
+            }
+            (FastFieldOptions::EnabledWithNormalizer { normalizer }, None) => {
                 text_options = text_options.set_fast(Some(normalizer.get_name()));
             }
-            FastFieldOptions::Disabled => {}
+            (FastFieldOptions::Disabled, _) => {}
         }
         if let Some(indexing_options) = quickwit_text_options.indexing_options {
             let text_field_indexing = TextFieldIndexing::default()
@@ -748,6 +780,13 @@ fn deserialize_mapping_type(
     match typ {
         Type::Str => {
             let text_options: QuickwitTextOptions = serde_json::from_value(json)?;
+            let is_fast_raw = text_options.fast
+                == FastFieldOptions::EnabledWithNormalizer {
+                    normalizer: QuickwitTextNormalizer::Raw,
+                };
+            if text_options.coerce.is_some() && !is_fast_raw {
+                bail!("`coerce` is only supported by fast text fields with the `raw` normalizer");
+            }
             Ok(FieldMappingType::Text(text_options, cardinality))
         }
         Type::U64 => {
@@ -760,6 +799,9 @@ fn deserialize_mapping_type(
         }
         Type::F64 => {
             let numeric_options: QuickwitNumericOptions = serde_json::from_value(json)?;
+            if numeric_options.unit.is_some() {
+                bail!("`unit` is only supported by `u64` and `i64` fields");
+            }
             Ok(FieldMappingType::F64(numeric_options, cardinality))
         }
         Type::Bool => {
@@ -866,7 +908,7 @@ mod tests {
     use serde_json::json;
     use tantivy::schema::{IndexRecordOption, JsonObjectOptions, TextOptions};
 
-    use super::FieldMappingEntry;
+    use super::{FieldMappingEntry, NumericUnit, IP_NORMALIZER_NAME};
     use crate::default_doc_mapper::field_mapping_entry::{
         QuickwitJsonOptions, QuickwitTextOptions, TextIndexingOptions,
     };
@@ -1224,7 +1266,7 @@ mod tests {
         assert_eq!(
             error.to_string(),
             "error while parsing field `my_field_name`: unknown field `tokenizer`, expected one \
-             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`"
+             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`, `unit`"
         );
     }
 
@@ -1316,7 +1358,7 @@ mod tests {
             .unwrap_err()
             .to_string(),
             "error while parsing field `my_field_name`: unknown field `tokenizer`, expected one \
-             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`"
+             of `description`, `stored`, `indexed`, `fast`, `coerce`, `output_format`, `unit`"
         );
     }
 
@@ -1389,6 +1431,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_deserialize_u64_mapping_with_bytes_unit() {
+        let entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "size",
+                "type": "u64",
+                "unit": "bytes"
+            }
+            "#,
+        )
+        .unwrap();
+        let FieldMappingType::U64(options, _) = &entry.mapping_type else {
+            panic!("Wrong type");
+        };
+        assert_eq!(options.unit, Some(NumericUnit::Bytes));
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap()["unit"],
+            json!("bytes")
+        );
+
+        let error = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "ratio",
+                "type": "f64",
+                "unit": "bytes"
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "error while parsing field `ratio`: `unit` is only supported by `u64` and `i64` fields"
+        );
+    }
+
     #[test]
     fn test_parse_f64_mapping() {
         let entry = serde_json::from_str::<FieldMappingEntry>(
@@ -1520,6 +1599,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_text_coerce_ip() {
+        let entry = serde_json::from_str::<FieldMappingEntry>(
+            r#"
+            {
+                "name": "client_ip",
+                "type": "text",
+                "tokenizer": "raw",
+                "fast": true,
+                "coerce": "ip"
+            }
+            "#,
+        )
+        .unwrap();
+        let entry_deserser = serde_json::to_value(&entry).unwrap();
+        assert_eq!(
+            entry_deserser,
+            json!({
+                "name": "client_ip",
+                "type": "text",
+                "fast": {"normalizer": "raw"},
+                "coerce": "ip",
+                "stored": true,
+                "indexed": true,
+                "record": "basic",
+                "tokenizer": "raw",
+                "fieldnorms": false,
+            })
+        );
+        let FieldMappingType::Text(text_options, _) = entry.mapping_type else {
+            panic!("wrong property type");
+        };
+        let tantivy_text_options = TextOptions::from(text_options);
+        assert_eq!(
+            tantivy_text_options.get_fast_field_tokenizer_name(),
+            Some(IP_NORMALIZER_NAME)
+        );
+
+        for invalid_fast_options in [r#"false"#, r#"{"normalizer": "lowercase"}"#] {
+            let error = serde_json::from_str::<FieldMappingEntry>(&format!(
+                r#"
+                {{
+                    "name": "client_ip",
+                    "type": "text",
+                    "fast": {invalid_fast_options},
+                    "coerce": "ip"
+                }}
+                "#
+            ))
+            .unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("`coerce` is only supported by fast text fields"));
+        }
+    }
+
     #[test]
     fn test_parse_text_mapping_multivalued() {
         let entry = serde_json::from_str::<FieldMappingEntry>(
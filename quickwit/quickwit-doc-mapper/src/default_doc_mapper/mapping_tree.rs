@@ -36,7 +36,7 @@ use super::date_time_type::QuickwitDateTimeOptions;
 use super::field_mapping_entry::{NumericOutputFormat, QuickwitBoolOptions};
 use crate::default_doc_mapper::field_mapping_entry::{
     QuickwitBytesOptions, QuickwitIpAddrOptions, QuickwitNumericOptions, QuickwitObjectOptions,
-    QuickwitTextOptions,
+    QuickwitTextCoercion, QuickwitTextOptions,
 };
 use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
 use crate::{Cardinality, DocParsingError, FieldMappingEntry, ModeType};
@@ -218,8 +218,17 @@ impl LeafType {
 
     fn value_from_json(&self, json_val: JsonValue) -> Result<TantivyValue, String> {
         match self {
-            LeafType::Text(_) => {
+            LeafType::Text(text_options) => {
                 if let JsonValue::String(text) = json_val {
+                    if text_options.coerce == Some(QuickwitTextCoercion::Ip)
+                        && IpAddr::from_str(text.trim()).is_err()
+                    {
+                        quickwit_common::rate_limited_warn!(
+                            limit_per_min = 2,
+                            "failed to coerce `{text}` into an IP address, the value is left out \
+                             of the fast field"
+                        );
+                    }
                     Ok(TantivyValue::Str(text))
                 } else {
                     Err(format!("expected string, got `{json_val}`"))
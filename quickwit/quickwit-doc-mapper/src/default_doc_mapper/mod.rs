@@ -31,8 +31,8 @@ pub use default_mapper_builder::DefaultDocMapperBuilder;
 #[cfg(all(test, feature = "multilang"))]
 pub(crate) use field_mapping_entry::TextIndexingOptions;
 pub use field_mapping_entry::{
-    BinaryFormat, FastFieldOptions, FieldMappingEntry, QuickwitBytesOptions, QuickwitJsonOptions,
-    QuickwitTextNormalizer,
+    BinaryFormat, FastFieldOptions, FieldMappingEntry, NumericUnit, QuickwitBytesOptions,
+    QuickwitJsonOptions, QuickwitTextCoercion, QuickwitTextNormalizer,
 };
 pub(crate) use field_mapping_entry::{
     FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
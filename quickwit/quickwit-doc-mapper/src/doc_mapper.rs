@@ -38,6 +38,7 @@ pub type Partition = u64;
 /// An alias for serde_json's object type.
 pub type JsonObject = serde_json::Map<String, JsonValue>;
 
+use crate::term_presence::TermPresenceFilterConfig;
 use crate::{DocParsingError, QueryParserError};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
@@ -109,6 +110,22 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     /// over time. The schema returned here represents the most up-to-date schema of the index.
     fn schema(&self) -> Schema;
 
+    /// Turns the user input queries of `query_ast` into structured queries.
+    ///
+    /// The user input queries that do not specify the fields they search into search the
+    /// `default_search_fields_override` fields, unless the list is empty, in which case they
+    /// search the default search fields of this doc mapper. This is the only place the default
+    /// search fields are resolved: the returned query AST no longer depends on them.
+    fn parse_user_query(
+        &self,
+        query_ast: QueryAst,
+        default_search_fields_override: &[String],
+    ) -> anyhow::Result<QueryAst> {
+        query_ast
+            .resolve_user_input_fields(&self.schema(), default_search_fields_override)?
+            .parse_user_query(self.default_search_fields())
+    }
+
     /// Returns the query.
     ///
     /// Considering schema evolution, splits within an index can have different schema
@@ -125,6 +142,12 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         None
     }
 
+    /// Returns true if the field at `field_path` is a numeric, datetime or bool field explicitly
+    /// mapped as single-valued. Multivalued, dynamic and unknown fields return false.
+    fn is_single_valued_field(&self, _field_path: &str) -> bool {
+        false
+    }
+
     /// Returns the list of search fields to search into, when no field is specified.
     /// (See `UserInputQuery`).
     fn default_search_fields(&self) -> &[String];
@@ -153,6 +176,35 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// Returns the configurations of the term presence filters recorded in the splits metadata.
+    fn term_presence_filter_configs(&self) -> &[TermPresenceFilterConfig] {
+        &[]
+    }
+
+    /// Returns the term presence filter fields on the current schema along with their
+    /// configuration.
+    /// Returns an error if a term presence filter field is not found in this schema.
+    fn term_presence_named_fields(
+        &self,
+    ) -> anyhow::Result<Vec<(NamedField, TermPresenceFilterConfig)>> {
+        let index_schema = self.schema();
+        self.term_presence_filter_configs()
+            .iter()
+            .map(|config| {
+                let field_name = &config.field;
+                let field = index_schema
+                    .get_field(field_name)
+                    .context(format!("field `{field_name}` must exist in the schema"))?;
+                let named_field = NamedField {
+                    name: field_name.clone(),
+                    field,
+                    field_type: index_schema.get_field_entry(field).field_type().clone(),
+                };
+                Ok((named_field, config.clone()))
+            })
+            .collect()
+    }
+
     /// Returns the maximum number of partitions.
     fn max_num_partitions(&self) -> NonZeroU32;
 
@@ -253,7 +305,7 @@ mod tests {
     use std::collections::{HashMap, HashSet};
     use std::ops::Bound;
 
-    use quickwit_query::query_ast::{query_ast_from_user_text, UserInputQuery};
+    use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst, UserInputQuery};
     use quickwit_query::BooleanOperand;
     use tantivy::schema::{Field, FieldType, Term};
 
@@ -386,6 +438,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doc_mapper_parse_user_query_default_search_fields() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+                "field_mappings": [
+                    {"name": "title", "type": "text"},
+                    {"name": "body", "type": "text"}
+                ],
+                "default_search_fields": ["body"],
+                "mode": "strict"
+            }"#,
+        )
+        .unwrap();
+        let query_ast = query_ast_from_user_text("hello", None);
+        let QueryAst::FullText(full_text_query) =
+            doc_mapper.parse_user_query(query_ast.clone(), &[]).unwrap()
+        else {
+            panic!("expected a full text query");
+        };
+        assert_eq!(full_text_query.field, "body");
+
+        let QueryAst::FullText(full_text_query) = doc_mapper
+            .parse_user_query(query_ast, &["title".to_string()])
+            .unwrap()
+        else {
+            panic!("expected a full text query");
+        };
+        assert_eq!(full_text_query.field, "title");
+
+        // The fields of the query take precedence over the override.
+        let query_ast = query_ast_from_user_text("hello", Some(vec!["body".to_string()]));
+        let QueryAst::FullText(full_text_query) = doc_mapper
+            .parse_user_query(query_ast, &["title".to_string()])
+            .unwrap()
+        else {
+            panic!("expected a full text query");
+        };
+        assert_eq!(full_text_query.field, "body");
+
+        let query_ast = query_ast_from_user_text("hello", None);
+        let error = doc_mapper
+            .parse_user_query(query_ast, &["unknown".to_string()])
+            .unwrap_err();
+        assert!(error.to_string().contains("unknown"));
+    }
+
+    #[test]
+    fn test_doc_mapper_parse_user_query_no_default_search_fields() {
+        let doc_mapper = DefaultDocMapperBuilder::default().try_build().unwrap();
+        assert!(doc_mapper.default_search_fields().is_empty());
+        let query_ast = query_ast_from_user_text("hello", None);
+        let error = doc_mapper.parse_user_query(query_ast, &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "term `hello` does not target any field and no default search field was supplied: \
+             target a field explicitly (`field:hello`) or set the default search fields of the \
+             request or of the index search settings"
+        );
+    }
+
     #[test]
     fn test_doc_mapper_query_with_json_field_ambiguous_term() {
         let doc_mapper = DefaultDocMapperBuilder::default().try_build().unwrap();
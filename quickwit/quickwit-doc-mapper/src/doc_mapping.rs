@@ -23,7 +23,8 @@ use std::num::NonZeroU32;
 use quickwit_proto::types::DocMappingUid;
 use serde::{Deserialize, Serialize};
 
-use crate::{FieldMappingEntry, QuickwitJsonOptions, TokenizerEntry};
+use crate::term_presence::TermPresenceFilterConfig;
+use crate::{FieldMappingEntry, FieldMappingType, QuickwitJsonOptions, TokenizerEntry};
 
 /// Defines how unmapped fields should be handled.
 #[derive(Clone, Copy, Default, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -137,11 +138,23 @@ pub struct DocMapping {
     #[serde(default)]
     pub tag_fields: BTreeSet<String>,
 
+    /// Declares the high cardinality fields for which a term presence filter is recorded in the
+    /// splits metadata.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub term_presence_filters: Vec<TermPresenceFilterConfig>,
+
     /// Expresses via a "mini-DSL" how to route documents to split partitions.
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub partition_key: Option<String>,
 
+    /// Declares the field which uniquely identifies the documents. Queries on the `_id`
+    /// pseudo-field run on this field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_id_field: Option<String>,
+
     /// The maximum number of partitions that an indexer can generate.
     #[schema(value_type = u32)]
     #[serde(default = "DocMapping::default_max_num_partitions")]
@@ -187,6 +200,132 @@ impl DocMapping {
     }
 }
 
+/// Verdict on whether a running indexing pipeline can switch from one doc mapping to another
+/// without being restarted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DocMappingUpdateVerdict {
+    /// The update only adds new fields or tokenizers: a document is indexed the same way by both
+    /// doc mappings, except for the new fields.
+    Compatible,
+    /// The update changes how some documents are parsed, indexed, or routed.
+    Breaking {
+        /// Describes the first change that makes the update breaking.
+        reason: String,
+    },
+}
+
+impl DocMappingUpdateVerdict {
+    /// Returns whether the update can be applied in place.
+    pub fn is_compatible(&self) -> bool {
+        matches!(self, Self::Compatible)
+    }
+
+    fn breaking(reason: impl Into<String>) -> Self {
+        Self::Breaking {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Classifies the update of a doc mapping from `current` to `new`. The update is compatible if
+/// `new` only adds field mappings, including in existing objects, or tokenizers. Any other
+/// change, such as changing the type or the options of an existing field, is breaking.
+pub fn classify_doc_mapping_update(
+    current: &DocMapping,
+    new: &DocMapping,
+) -> DocMappingUpdateVerdict {
+    if current.mode != new.mode {
+        return DocMappingUpdateVerdict::breaking("`mode` changed");
+    }
+    if current.timestamp_field != new.timestamp_field {
+        return DocMappingUpdateVerdict::breaking("`timestamp_field` changed");
+    }
+    if current.tag_fields != new.tag_fields {
+        return DocMappingUpdateVerdict::breaking("`tag_fields` changed");
+    }
+    if current.term_presence_filters != new.term_presence_filters {
+        return DocMappingUpdateVerdict::breaking("`term_presence_filters` changed");
+    }
+    if current.partition_key != new.partition_key {
+        return DocMappingUpdateVerdict::breaking("`partition_key` changed");
+    }
+    if current.max_num_partitions != new.max_num_partitions {
+        return DocMappingUpdateVerdict::breaking("`max_num_partitions` changed");
+    }
+    if current.index_field_presence != new.index_field_presence {
+        return DocMappingUpdateVerdict::breaking("`index_field_presence` changed");
+    }
+    if current.store_document_size != new.store_document_size {
+        return DocMappingUpdateVerdict::breaking("`store_document_size` changed");
+    }
+    if current.store_source != new.store_source {
+        return DocMappingUpdateVerdict::breaking("`store_source` changed");
+    }
+    for current_tokenizer in &current.tokenizers {
+        let new_tokenizer_opt = new
+            .tokenizers
+            .iter()
+            .find(|new_tokenizer| new_tokenizer.name == current_tokenizer.name);
+        if new_tokenizer_opt != Some(current_tokenizer) {
+            return DocMappingUpdateVerdict::breaking(format!(
+                "tokenizer `{}` was modified or removed",
+                current_tokenizer.name
+            ));
+        }
+    }
+    classify_field_mappings_update("", &current.field_mappings, &new.field_mappings)
+}
+
+fn classify_field_mappings_update(
+    path: &str,
+    current_field_mappings: &[FieldMappingEntry],
+    new_field_mappings: &[FieldMappingEntry],
+) -> DocMappingUpdateVerdict {
+    for current_field_mapping in current_field_mappings {
+        let field_path = format!("{path}{}", current_field_mapping.name);
+
+        let Some(new_field_mapping) = new_field_mappings
+            .iter()
+            .find(|new_field_mapping| new_field_mapping.name == current_field_mapping.name)
+        else {
+            return DocMappingUpdateVerdict::breaking(format!("field `{field_path}` was removed"));
+        };
+        match (
+            &current_field_mapping.mapping_type,
+            &new_field_mapping.mapping_type,
+        ) {
+            (FieldMappingType::Object(current_options), FieldMappingType::Object(new_options)) => {
+                let verdict = classify_field_mappings_update(
+                    &format!("{field_path}."),
+                    &current_options.field_mappings,
+                    &new_options.field_mappings,
+                );
+                if !verdict.is_compatible() {
+                    return verdict;
+                }
+            }
+            (current_mapping_type, new_mapping_type) => {
+                let current_field_type = current_mapping_type.quickwit_field_type();
+                let new_field_type = new_mapping_type.quickwit_field_type();
+
+                if current_field_type != new_field_type {
+                    return DocMappingUpdateVerdict::breaking(format!(
+                        "type of field `{field_path}` changed from `{}` to `{}`",
+                        current_field_type.to_type_id(),
+                        new_field_type.to_type_id()
+                    ));
+                }
+                if current_mapping_type != new_mapping_type {
+                    return DocMappingUpdateVerdict::breaking(format!(
+                        "options of field `{field_path}` changed"
+                    ));
+                }
+            }
+        }
+    }
+    DocMappingUpdateVerdict::Compatible
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,7 +358,13 @@ mod tests {
             ],
             timestamp_field: Some("timestamp".to_string()),
             tag_fields: BTreeSet::from_iter(["level".to_string()]),
+            term_presence_filters: vec![TermPresenceFilterConfig {
+                field: "trace_id".to_string(),
+                false_positive_rate: 0.001,
+                max_num_bytes: 1024,
+            }],
             partition_key: Some("tenant_id".to_string()),
+            doc_id_field: Some("message".to_string()),
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             index_field_presence: true,
             store_document_size: true,
@@ -249,7 +394,9 @@ mod tests {
         assert!(doc_mapping.field_mappings.is_empty());
         assert_eq!(doc_mapping.timestamp_field, None);
         assert!(doc_mapping.tag_fields.is_empty());
+        assert!(doc_mapping.term_presence_filters.is_empty());
         assert_eq!(doc_mapping.partition_key, None);
+        assert_eq!(doc_mapping.doc_id_field, None);
         assert_eq!(
             doc_mapping.max_num_partitions,
             NonZeroU32::new(200).unwrap()
@@ -258,4 +405,132 @@ mod tests {
         assert_eq!(doc_mapping.store_document_size, false);
         assert_eq!(doc_mapping.store_source, false);
     }
+
+    #[test]
+    fn test_classify_doc_mapping_update() {
+        let current_doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "strict",
+                "field_mappings": [
+                    {"name": "timestamp", "type": "datetime", "fast": true},
+                    {"name": "body", "type": "text"},
+                    {
+                        "name": "attributes",
+                        "type": "object",
+                        "field_mappings": [{"name": "server", "type": "text"}]
+                    }
+                ],
+                "timestamp_field": "timestamp"
+            }"#,
+        )
+        .unwrap();
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &current_doc_mapping);
+        assert_eq!(verdict, DocMappingUpdateVerdict::Compatible);
+
+        let new_doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "strict",
+                "field_mappings": [
+                    {"name": "timestamp", "type": "datetime", "fast": true},
+                    {"name": "severity", "type": "text"},
+                    {"name": "body", "type": "text"},
+                    {
+                        "name": "attributes",
+                        "type": "object",
+                        "field_mappings": [
+                            {"name": "server", "type": "text"},
+                            {"name": "status", "type": "u64"}
+                        ]
+                    }
+                ],
+                "timestamp_field": "timestamp",
+                "tokenizers": [{"name": "simple_lowercase", "type": "simple", "filters": ["lower_caser"]}]
+            }"#,
+        )
+        .unwrap();
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &new_doc_mapping);
+        assert!(verdict.is_compatible());
+
+        let new_doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "strict",
+                "field_mappings": [
+                    {"name": "timestamp", "type": "datetime", "fast": true},
+                    {"name": "body", "type": "u64"},
+                    {
+                        "name": "attributes",
+                        "type": "object",
+                        "field_mappings": [{"name": "server", "type": "text"}]
+                    }
+                ],
+                "timestamp_field": "timestamp"
+            }"#,
+        )
+        .unwrap();
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &new_doc_mapping);
+        assert_eq!(
+            verdict,
+            DocMappingUpdateVerdict::Breaking {
+                reason: "type of field `body` changed from `text` to `u64`".to_string()
+            }
+        );
+
+        let new_doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "strict",
+                "field_mappings": [
+                    {"name": "timestamp", "type": "datetime", "fast": true},
+                    {"name": "body", "type": "text"},
+                    {
+                        "name": "attributes",
+                        "type": "object",
+                        "field_mappings": [{"name": "server", "type": "text", "fast": true}]
+                    }
+                ],
+                "timestamp_field": "timestamp"
+            }"#,
+        )
+        .unwrap();
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &new_doc_mapping);
+        assert_eq!(
+            verdict,
+            DocMappingUpdateVerdict::Breaking {
+                reason: "options of field `attributes.server` changed".to_string()
+            }
+        );
+
+        let new_doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "strict",
+                "field_mappings": [
+                    {"name": "timestamp", "type": "datetime", "fast": true},
+                    {
+                        "name": "attributes",
+                        "type": "object",
+                        "field_mappings": [{"name": "server", "type": "text"}]
+                    }
+                ],
+                "timestamp_field": "timestamp"
+            }"#,
+        )
+        .unwrap();
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &new_doc_mapping);
+        assert_eq!(
+            verdict,
+            DocMappingUpdateVerdict::Breaking {
+                reason: "field `body` was removed".to_string()
+            }
+        );
+
+        let mut new_doc_mapping = current_doc_mapping.clone();
+        new_doc_mapping.mode = Mode::Lenient;
+
+        let verdict = classify_doc_mapping_update(&current_doc_mapping, &new_doc_mapping);
+        assert_eq!(
+            verdict,
+            DocMappingUpdateVerdict::Breaking {
+                reason: "`mode` changed".to_string()
+            }
+        );
+    }
 }
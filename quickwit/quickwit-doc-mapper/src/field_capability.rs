@@ -0,0 +1,340 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use quickwit_proto::search::{ListFieldType, ListFieldsResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::default_doc_mapper::FastFieldOptions;
+use crate::{DocMapping, FieldMappingEntry, FieldMappingType, QW_RESERVED_FIELD_NAMES};
+
+/// Describes how a field can be queried, aggregated, and retrieved. Field capabilities are used by
+/// query builders to introspect the fields of an index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FieldCapability {
+    /// Full path of the field. The path segments of object fields are separated by dots.
+    pub name: String,
+    /// Type of the field, as expressed in the doc mapping (`text`, `u64`, `array<datetime>`,
+    /// ...).
+    #[serde(rename = "type")]
+    pub field_type: String,
+    /// Whether the field is indexed and can be searched.
+    pub searchable: bool,
+    /// Whether the field is a fast field and can be aggregated or sorted on.
+    pub aggregatable: bool,
+    /// Whether the field is stored in the doc store.
+    pub stored: bool,
+    /// Input formats accepted by a datetime field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_formats: Option<Vec<String>>,
+    /// Output format of a datetime field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<String>,
+    /// Whether the field is not declared in the doc mapping and was captured dynamically.
+    #[serde(default)]
+    pub dynamic: bool,
+}
+
+impl DocMapping {
+    /// Returns the capabilities of the fields declared in the doc mapping. Object fields are
+    /// flattened into their leaf fields.
+    ///
+    /// If provided, the fields listed from the splits of the index that are not declared in the
+    /// doc mapping, such as the fields captured in dynamic mode or the keys of JSON fields, are
+    /// appended as dynamic fields.
+    pub fn field_capabilities(
+        &self,
+        split_fields_opt: Option<ListFieldsResponse>,
+    ) -> Vec<FieldCapability> {
+        let mut field_capabilities = Vec::new();
+        append_field_capabilities("", &self.field_mappings, &mut field_capabilities);
+
+        if let Some(split_fields) = split_fields_opt {
+            append_dynamic_field_capabilities(split_fields, &mut field_capabilities);
+        }
+        field_capabilities
+    }
+}
+
+fn append_dynamic_field_capabilities(
+    split_fields: ListFieldsResponse,
+    field_capabilities: &mut Vec<FieldCapability>,
+) {
+    let mapped_field_names: HashSet<String> = field_capabilities
+        .iter()
+        .map(|field_capability| field_capability.name.clone())
+        .collect();
+
+    for split_field in split_fields.fields {
+        if QW_RESERVED_FIELD_NAMES.contains(&split_field.field_name.as_str())
+            || mapped_field_names.contains(&split_field.field_name)
+        {
+            continue;
+        }
+        let field_type = match split_field.field_type() {
+            ListFieldType::Str => "text",
+            ListFieldType::U64 => "u64",
+            ListFieldType::I64 => "i64",
+            ListFieldType::F64 => "f64",
+            ListFieldType::Bool => "bool",
+            ListFieldType::Date => "datetime",
+            ListFieldType::Bytes => "bytes",
+            ListFieldType::IpAddr => "ip",
+            ListFieldType::Json => "json",
+            ListFieldType::Facet => continue,
+        };
+        let field_capability = FieldCapability {
+            name: split_field.field_name,
+            field_type: field_type.to_string(),
+            searchable: split_field.searchable,
+            aggregatable: split_field.aggregatable,
+            // Unmapped fields end up in JSON fields, which are stored.
+            stored: true,
+            input_formats: None,
+            output_format: None,
+            dynamic: true,
+        };
+        field_capabilities.push(field_capability);
+    }
+}
+
+fn append_field_capabilities(
+    path_prefix: &str,
+    field_mappings: &[FieldMappingEntry],
+    field_capabilities: &mut Vec<FieldCapability>,
+) {
+    for field_mapping in field_mappings {
+        let name = format!("{path_prefix}{}", field_mapping.name);
+
+        if let FieldMappingType::Object(object_options) = &field_mapping.mapping_type {
+            let object_path_prefix = format!("{name}.");
+            append_field_capabilities(
+                &object_path_prefix,
+                &object_options.field_mappings,
+                field_capabilities,
+            );
+            continue;
+        }
+        let field_type = field_mapping
+            .mapping_type
+            .quickwit_field_type()
+            .to_type_id();
+        let mut field_capability = FieldCapability {
+            name,
+            field_type,
+            searchable: false,
+            aggregatable: false,
+            stored: false,
+            input_formats: None,
+            output_format: None,
+            dynamic: false,
+        };
+        match &field_mapping.mapping_type {
+            FieldMappingType::Text(options, _) => {
+                field_capability.searchable = options.indexing_options.is_some();
+                field_capability.aggregatable = options.fast != FastFieldOptions::Disabled;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::I64(options, _)
+            | FieldMappingType::U64(options, _)
+            | FieldMappingType::F64(options, _) => {
+                field_capability.searchable = options.indexed;
+                field_capability.aggregatable = options.fast;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::DateTime(options, _) => {
+                field_capability.searchable = options.indexed;
+                field_capability.aggregatable = options.fast;
+                field_capability.stored = options.stored;
+                field_capability.input_formats = Some(
+                    options
+                        .input_formats
+                        .iter()
+                        .map(|input_format| input_format.to_string())
+                        .collect(),
+                );
+                field_capability.output_format = Some(options.output_format.to_string());
+            }
+            FieldMappingType::Bool(options, _) => {
+                field_capability.searchable = options.indexed;
+                field_capability.aggregatable = options.fast;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::IpAddr(options, _) => {
+                field_capability.searchable = options.indexed;
+                field_capability.aggregatable = options.fast;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::Bytes(options, _) => {
+                field_capability.searchable = options.indexed;
+                field_capability.aggregatable = options.fast;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::Json(options, _) => {
+                field_capability.searchable = options.indexing_options.is_some();
+                field_capability.aggregatable = options.fast != FastFieldOptions::Disabled;
+                field_capability.stored = options.stored;
+            }
+            FieldMappingType::Concatenate(_) => {
+                field_capability.searchable = true;
+            }
+            FieldMappingType::Object(_) => unreachable!("object fields are flattened above"),
+        }
+        field_capabilities.push(field_capability);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::ListFieldsEntryResponse;
+
+    use super::*;
+
+    #[test]
+    fn test_doc_mapping_field_capabilities() {
+        let doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "dynamic",
+                "field_mappings": [
+                    {
+                        "name": "body",
+                        "type": "text",
+                        "stored": true
+                    },
+                    {
+                        "name": "status_code",
+                        "type": "u64",
+                        "indexed": false,
+                        "fast": true
+                    },
+                    {
+                        "name": "timestamp",
+                        "type": "datetime",
+                        "input_formats": ["rfc3339", "unix_timestamp"],
+                        "output_format": "unix_timestamp_secs",
+                        "stored": false,
+                        "fast": true
+                    },
+                    {
+                        "name": "server",
+                        "type": "object",
+                        "field_mappings": [
+                            {
+                                "name": "tags",
+                                "type": "array<text>",
+                                "tokenizer": "raw",
+                                "fast": true
+                            }
+                        ]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let split_fields = ListFieldsResponse {
+            fields: vec![
+                ListFieldsEntryResponse {
+                    field_name: "body".to_string(),
+                    field_type: ListFieldType::Str as i32,
+                    searchable: true,
+                    ..Default::default()
+                },
+                ListFieldsEntryResponse {
+                    field_name: "_field_presence".to_string(),
+                    field_type: ListFieldType::U64 as i32,
+                    searchable: true,
+                    ..Default::default()
+                },
+                ListFieldsEntryResponse {
+                    field_name: "user_agent".to_string(),
+                    field_type: ListFieldType::Str as i32,
+                    searchable: true,
+                    aggregatable: true,
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(doc_mapping.field_capabilities(None).len(), 4);
+
+        let field_capabilities = doc_mapping.field_capabilities(Some(split_fields));
+        assert_eq!(field_capabilities.len(), 5);
+
+        assert_eq!(
+            field_capabilities[0],
+            FieldCapability {
+                name: "body".to_string(),
+                field_type: "text".to_string(),
+                searchable: true,
+                aggregatable: false,
+                stored: true,
+                input_formats: None,
+                output_format: None,
+                dynamic: false,
+            }
+        );
+        assert_eq!(
+            field_capabilities[1],
+            FieldCapability {
+                name: "status_code".to_string(),
+                field_type: "u64".to_string(),
+                searchable: false,
+                aggregatable: true,
+                stored: true,
+                input_formats: None,
+                output_format: None,
+                dynamic: false,
+            }
+        );
+        assert_eq!(
+            field_capabilities[2],
+            FieldCapability {
+                name: "timestamp".to_string(),
+                field_type: "datetime".to_string(),
+                searchable: true,
+                aggregatable: true,
+                stored: false,
+                input_formats: Some(vec!["rfc3339".to_string(), "unix_timestamp".to_string()]),
+                output_format: Some("unix_timestamp_secs".to_string()),
+                dynamic: false,
+            }
+        );
+        assert_eq!(field_capabilities[3].name, "server.tags");
+        assert_eq!(field_capabilities[3].field_type, "array<text>");
+        assert!(field_capabilities[3].searchable);
+        assert!(field_capabilities[3].aggregatable);
+        assert!(!field_capabilities[3].dynamic);
+
+        assert_eq!(
+            field_capabilities[4],
+            FieldCapability {
+                name: "user_agent".to_string(),
+                field_type: "text".to_string(),
+                searchable: true,
+                aggregatable: true,
+                stored: true,
+                input_formats: None,
+                output_format: None,
+                dynamic: true,
+            }
+        );
+    }
+}
@@ -29,27 +29,35 @@ mod default_doc_mapper;
 mod doc_mapper;
 mod doc_mapping;
 mod error;
+mod field_capability;
 mod query_builder;
 mod routing_expression;
 
 /// Pruning tags manipulation.
 pub mod tag_pruning;
+/// Per-split term presence filters.
+pub mod term_presence;
 
 pub use default_doc_mapper::{
     analyze_text, BinaryFormat, DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry,
-    FieldMappingType, QuickwitBytesOptions, QuickwitJsonOptions, TokenizerConfig, TokenizerEntry,
+    FieldMappingType, NumericUnit, QuickwitBytesOptions, QuickwitJsonOptions, TokenizerConfig,
+    TokenizerEntry,
 };
 use default_doc_mapper::{
     FastFieldOptions, FieldMappingEntryForSerialization, IndexRecordOptionSchema,
-    NgramTokenizerOption, QuickwitTextNormalizer, QuickwitTextTokenizer, RegexTokenizerOption,
-    TokenFilterType, TokenizerType,
+    NgramTokenizerOption, QuickwitTextCoercion, QuickwitTextNormalizer, QuickwitTextTokenizer,
+    RegexTokenizerOption, TokenFilterType, TokenizerType,
 };
 pub use doc_mapper::{DocMapper, JsonObject, NamedField, TermRange, WarmupInfo};
-pub use doc_mapping::{DocMapping, Mode, ModeType};
+pub use doc_mapping::{
+    classify_doc_mapping_update, DocMapping, DocMappingUpdateVerdict, Mode, ModeType,
+};
 pub use error::{DocParsingError, QueryParserError};
+pub use field_capability::FieldCapability;
 use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
 use quickwit_proto::types::DocMappingUid;
 pub use routing_expression::RoutingExpr;
+use term_presence::TermPresenceFilterConfig;
 
 /// Field name reserved for storing the source document.
 pub const SOURCE_FIELD_NAME: &str = "_source";
@@ -60,6 +68,10 @@ pub const DYNAMIC_FIELD_NAME: &str = "_dynamic";
 /// Field name reserved for storing the length of source document.
 pub const DOCUMENT_SIZE_FIELD_NAME: &str = "_doc_length";
 
+/// Pseudo-field of the Elasticsearch API identifying a document, resolved to the doc id field of
+/// the doc mapping.
+pub const ID_PSEUDO_FIELD_NAME: &str = "_id";
+
 /// Quickwit reserved field names.
 const QW_RESERVED_FIELD_NAMES: &[&str] = &[
     DOCUMENT_SIZE_FIELD_NAME,
@@ -81,14 +93,17 @@ pub enum Cardinality {
 #[openapi(components(schemas(
     DocMappingUid,
     FastFieldOptions,
+    FieldCapability,
     FieldMappingEntryForSerialization,
     IndexRecordOptionSchema,
     ModeType,
     NgramTokenizerOption,
     QuickwitJsonOptions,
+    QuickwitTextCoercion,
     QuickwitTextNormalizer,
     QuickwitTextTokenizer,
     RegexTokenizerOption,
+    TermPresenceFilterConfig,
     TokenFilterType,
     TokenizerConfig,
     TokenizerEntry,
@@ -22,11 +22,11 @@ use std::convert::Infallible;
 use std::ops::Bound;
 
 use quickwit_query::query_ast::{
-    FieldPresenceQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery,
-    TermSetQuery, WildcardQuery,
+    BoolQuery, FieldPresenceQuery, FullTextQuery, GeoBoundingBoxQuery, PhrasePrefixQuery, QueryAst,
+    QueryAstVisitor, RangeQuery, RegexQuery, TermSetQuery, WildcardQuery,
 };
 use quickwit_query::tokenizers::TokenizerManager;
-use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
+use quickwit_query::{find_field_or_hit_dynamic, find_object_subfields, InvalidQuery};
 use tantivy::query::Query;
 use tantivy::schema::{Field, Schema};
 use tantivy::Term;
@@ -46,6 +46,19 @@ impl<'a> QueryAstVisitor<'a> for RangeQueryFields {
             .insert(range_query.field.to_string());
         Ok(())
     }
+
+    fn visit_geo_bounding_box(
+        &mut self,
+        geo_bounding_box_query: &'a GeoBoundingBoxQuery,
+    ) -> Result<(), Infallible> {
+        // Bounding boxes are translated into range queries over the latitude and longitude
+        // fields.
+        self.range_query_field_names
+            .insert(geo_bounding_box_query.lat_field());
+        self.range_query_field_names
+            .insert(geo_bounding_box_query.lon_field());
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -68,12 +81,38 @@ impl<'a> QueryAstVisitor<'a> for ExistsQueryFields {
     }
 }
 
+struct ColumnarExclusionFields<'a> {
+    schema: &'a Schema,
+    tokenizer_manager: &'a TokenizerManager,
+    fast_field_names: HashSet<String>,
+}
+
+impl<'a, 'b: 'a> QueryAstVisitor<'a> for ColumnarExclusionFields<'b> {
+    type Err = Infallible;
+
+    fn visit_bool(&mut self, bool_query: &'a BoolQuery) -> Result<(), Infallible> {
+        // Bool queries made of term exclusions only are evaluated on the fast fields.
+        self.fast_field_names.extend(
+            bool_query.columnar_exclusion_fast_field_names(self.schema, self.tokenizer_manager),
+        );
+        for ast in bool_query
+            .must
+            .iter()
+            .chain(bool_query.should.iter())
+            .chain(bool_query.must_not.iter())
+            .chain(bool_query.filter.iter())
+        {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+}
+
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     query_ast: &QueryAst,
     schema: Schema,
     tokenizer_manager: &TokenizerManager,
-    search_fields: &[String],
     with_validation: bool,
 ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
     let mut range_query_fields = RangeQueryFields::default();
@@ -84,21 +123,31 @@ pub(crate) fn build_query(
     // This cannot fail. The error type is Infallible.
     let _: Result<(), Infallible> = exists_query_fields.visit(query_ast);
 
+    let mut columnar_exclusion_fields = ColumnarExclusionFields {
+        schema: &schema,
+        tokenizer_manager,
+        fast_field_names: HashSet::new(),
+    };
+    // This cannot fail. The error type is Infallible.
+    let _: Result<(), Infallible> = columnar_exclusion_fields.visit(query_ast);
+
     let mut fast_field_names = HashSet::new();
     fast_field_names.extend(range_query_fields.range_query_field_names);
-    fast_field_names.extend(
-        exists_query_fields
-            .exists_query_field_names
-            .into_iter()
-            .filter(|field| is_fast_field(&schema, field)),
-    );
-
-    let query = query_ast.build_tantivy_query(
-        &schema,
-        tokenizer_manager,
-        search_fields,
-        with_validation,
-    )?;
+    fast_field_names.extend(columnar_exclusion_fields.fast_field_names);
+
+    for exists_query_field_name in exists_query_fields.exists_query_field_names {
+        // Exists queries on objects run on the subfields of the object.
+        for (_, subfield_entry) in find_object_subfields(&exists_query_field_name, &schema) {
+            if subfield_entry.is_fast() {
+                fast_field_names.insert(subfield_entry.name().to_string());
+            }
+        }
+        if is_fast_field(&schema, &exists_query_field_name) {
+            fast_field_names.insert(exists_query_field_name);
+        }
+    }
+
+    let query = query_ast.build_tantivy_query(&schema, tokenizer_manager, with_validation)?;
 
     let term_set_query_fields = extract_term_set_query_fields(query_ast, &schema)?;
     let term_ranges_grouped_by_field =
@@ -160,6 +209,33 @@ impl<'a, 'b> QueryAstVisitor<'a> for ExtractTermSetFields<'b> {
         }
         Ok(())
     }
+
+    fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> anyhow::Result<()> {
+        // Wildcard queries that are not prefix queries scan the whole term dictionary.
+        if wildcard_query.is_prefix_query() {
+            return Ok(());
+        }
+        if let Ok((field, _field_entry, _path)) =
+            find_field_or_hit_dynamic(&wildcard_query.field, self.schema)
+        {
+            self.term_dict_fields_to_warm_up.insert(field);
+        } else {
+            anyhow::bail!("field does not exist: {}", wildcard_query.field);
+        }
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> anyhow::Result<()> {
+        // Regex queries scan the whole term dictionary.
+        if let Ok((field, _field_entry, _path)) =
+            find_field_or_hit_dynamic(&regex_query.field, self.schema)
+        {
+            self.term_dict_fields_to_warm_up.insert(field);
+        } else {
+            anyhow::bail!("field does not exist: {}", regex_query.field);
+        }
+        Ok(())
+    }
 }
 
 fn extract_term_set_query_fields(
@@ -258,6 +334,9 @@ impl<'a, 'b: 'a> QueryAstVisitor<'a> for ExtractPrefixTermRanges<'b> {
     }
 
     fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
+        if !wildcard_query.is_prefix_query() {
+            return Ok(());
+        }
         let (_, term) = wildcard_query.extract_prefix_term(self.schema, self.tokenizer_manager)?;
         self.add_prefix_term(term, u32::MAX, false);
         Ok(())
@@ -276,6 +355,7 @@ fn extract_prefix_term_ranges(
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
     use std::ops::Bound;
 
     use quickwit_query::query_ast::{
@@ -286,7 +366,7 @@ mod test {
     use tantivy::Term;
 
     use super::{build_query, ExtractPrefixTermRanges};
-    use crate::{TermRange, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
+    use crate::{TermRange, DYNAMIC_FIELD_NAME, FIELD_PRESENCE_FIELD_NAME, SOURCE_FIELD_NAME};
 
     enum TestExpectation<'a> {
         Err(&'a str),
@@ -348,7 +428,6 @@ mod test {
             &query_ast,
             schema,
             &create_default_quickwit_tokenizer_manager(),
-            &[],
             true,
         );
         query_result
@@ -474,17 +553,23 @@ mod test {
         check_build_query_static_mode(
             "foo",
             Vec::new(),
-            TestExpectation::Err("query requires a default search field and none was supplied"),
+            TestExpectation::Err(
+                "does not target any field and no default search field was supplied",
+            ),
         );
         check_build_query_static_mode(
             "bar",
             Vec::new(),
-            TestExpectation::Err("query requires a default search field and none was supplied"),
+            TestExpectation::Err(
+                "does not target any field and no default search field was supplied",
+            ),
         );
         check_build_query_static_mode(
             "title:hello AND (Jane OR desc:world)",
             Vec::new(),
-            TestExpectation::Err("query requires a default search field and none was supplied"),
+            TestExpectation::Err(
+                "does not target any field and no default search field was supplied",
+            ),
         );
         check_build_query_static_mode(
             "server.running:true",
@@ -660,7 +745,6 @@ mod test {
             &query_with_set,
             make_schema(true),
             &create_default_quickwit_tokenizer_manager(),
-            &[],
             true,
         )
         .unwrap();
@@ -673,13 +757,69 @@ mod test {
             &query_without_set,
             make_schema(true),
             &create_default_quickwit_tokenizer_manager(),
-            &[],
             true,
         )
         .unwrap();
         assert!(warmup_info.term_dict_fields.is_empty());
     }
 
+    #[test]
+    fn test_build_query_warmup_info_exists_object() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("server.name", TEXT);
+        schema_builder.add_u64_field("server.mem", FAST);
+        schema_builder.add_bool_field("server.running", FAST | INDEXED);
+        schema_builder.add_u64_field("uptime", FAST);
+        schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED);
+        let schema = schema_builder.build();
+
+        let query_ast = query_ast_from_user_text("server:* AND uptime:*", None)
+            .parse_user_query(&[])
+            .unwrap();
+        let (_, warmup_info) = build_query(
+            &query_ast,
+            schema,
+            &create_default_quickwit_tokenizer_manager(),
+            true,
+        )
+        .unwrap();
+        let mut fast_field_names: Vec<&str> = warmup_info
+            .fast_field_names
+            .iter()
+            .map(String::as_str)
+            .collect();
+        fast_field_names.sort();
+        assert_eq!(fast_field_names, ["server.mem", "server.running", "uptime"]);
+    }
+
+    #[test]
+    fn test_build_query_warmup_info_columnar_exclusion() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_u64_field("status", FAST | INDEXED);
+        let schema = schema_builder.build();
+
+        let fast_field_names = |user_text: &str| {
+            let query_ast = query_ast_from_user_text(user_text, None)
+                .parse_user_query(&[])
+                .unwrap();
+            let (_, warmup_info) = build_query(
+                &query_ast,
+                schema.clone(),
+                &create_default_quickwit_tokenizer_manager(),
+                true,
+            )
+            .unwrap();
+            warmup_info.fast_field_names
+        };
+        assert_eq!(
+            fast_field_names("NOT status:200 AND NOT status:404"),
+            HashSet::from(["status".to_string()])
+        );
+        assert!(fast_field_names("body:hello AND NOT status:200").is_empty());
+        assert!(fast_field_names("NOT body:hello AND NOT status:200").is_empty());
+    }
+
     #[test]
     fn test_extract_phrase_prefix_position_required() {
         let schema = make_schema(false);
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Display;
 
 use quickwit_query::query_ast::QueryAst;
@@ -38,12 +38,25 @@ pub fn match_tag_field_name(field_name: &str, tag: &str) -> bool {
 /// associated with a split, we are guaranteed that no documents
 /// in the split matches the query.
 pub fn extract_tags_from_query(query_ast: QueryAst) -> Option<TagFilterAst> {
-    let unsimplified_tag_filter_ast = extract_unsimplified_tags_filter_ast(query_ast);
+    let unsimplified_tag_filter_ast = extract_unsimplified_tags_filter_ast(query_ast, false);
     let term_filters_ast = simplify_ast(unsimplified_tag_filter_ast)?;
     Some(expand_to_tag_ast(term_filters_ast))
 }
 
-fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagFilterAst {
+/// Same as [`extract_tags_from_query`], except that only the queries searching for the exact
+/// values of a field are taken into account: term and term set queries, and full-text queries
+/// that do not override the tokenizer of the field nor match all documents when no term is left
+/// after tokenization. This is only correct for fields using the `raw` tokenizer.
+pub fn extract_exact_terms_from_query(query_ast: QueryAst) -> Option<TagFilterAst> {
+    let unsimplified_tag_filter_ast = extract_unsimplified_tags_filter_ast(query_ast, true);
+    let term_filters_ast = simplify_ast(unsimplified_tag_filter_ast)?;
+    Some(expand_to_tag_ast(term_filters_ast))
+}
+
+fn extract_unsimplified_tags_filter_ast(
+    query_ast: QueryAst,
+    exact_terms_only: bool,
+) -> UnsimplifiedTagFilterAst {
     match query_ast {
         QueryAst::Bool(bool_query) => {
             let mut clause_with_resolved_occur: Vec<(Occur, UnsimplifiedTagFilterAst)> = Vec::new();
@@ -55,12 +68,19 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
             ] {
                 for child_ast in children {
                     let child_unsimplified_tag_ast =
-                        extract_unsimplified_tags_filter_ast(child_ast);
+                        extract_unsimplified_tags_filter_ast(child_ast, exact_terms_only);
                     clause_with_resolved_occur.push((occur, child_unsimplified_tag_ast));
                 }
             }
             collect_tag_filters_for_clause(clause_with_resolved_occur)
         }
+        QueryAst::DisMax(dis_max_query) => UnsimplifiedTagFilterAst::Or(
+            dis_max_query
+                .disjuncts
+                .into_iter()
+                .map(|disjunct| extract_unsimplified_tags_filter_ast(disjunct, exact_terms_only))
+                .collect(),
+        ),
         QueryAst::Term(term_query) => UnsimplifiedTagFilterAst::Tag {
             is_present: true,
             field: term_query.field,
@@ -88,6 +108,17 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
                 .collect();
             UnsimplifiedTagFilterAst::Or(children)
         }
+        // A full-text query overriding the tokenizer does not search for the exact value, and a
+        // full-text query matching all the documents when its value is not indexed, for instance
+        // because it is too long, matches documents regardless of the values of the field.
+        QueryAst::FullText(full_text_query)
+            if exact_terms_only
+                && (full_text_query.params.tokenizer.is_some()
+                    || !full_text_query.params.zero_terms_query.is_none()) =>
+        {
+            UnsimplifiedTagFilterAst::Uninformative
+        }
+        QueryAst::PhrasePrefix(_) if exact_terms_only => UnsimplifiedTagFilterAst::Uninformative,
         QueryAst::FullText(full_text_query) => {
             // TODO This is a bug in a sense.
             // A phrase is supposed to go through the tokenizer.
@@ -105,19 +136,17 @@ fn extract_unsimplified_tags_filter_ast(query_ast: QueryAst) -> UnsimplifiedTagF
                 value: phrase_prefix_query.phrase,
             }
         }
-        QueryAst::Wildcard(wildcard_query) => {
-            // TODO same as FullText above.
-            UnsimplifiedTagFilterAst::Tag {
-                is_present: true,
-                field: wildcard_query.field,
-                value: wildcard_query.value,
-            }
+        QueryAst::Boost { underlying, .. } => {
+            extract_unsimplified_tags_filter_ast(*underlying, exact_terms_only)
         }
-        QueryAst::Boost { underlying, .. } => extract_unsimplified_tags_filter_ast(*underlying),
         QueryAst::UserInput(_user_text_query) => {
             panic!("Extract unsimplified should only be called on AST without UserInputQuery.");
         }
-        QueryAst::FieldPresence(_) => UnsimplifiedTagFilterAst::Uninformative,
+        // Tags cannot be matched against a wildcard pattern or a regex.
+        QueryAst::FieldPresence(_)
+        | QueryAst::GeoBoundingBox(_)
+        | QueryAst::Wildcard(_)
+        | QueryAst::Regex(_) => UnsimplifiedTagFilterAst::Uninformative,
     }
 }
 
@@ -237,8 +266,15 @@ impl TagFilterAst {
 fn simplify_ast(ast: UnsimplifiedTagFilterAst) -> Option<TermFilterAst> {
     match ast {
         UnsimplifiedTagFilterAst::And(conditions) => {
-            let mut pruned_conditions: Vec<TermFilterAst> =
-                conditions.into_iter().filter_map(simplify_ast).collect();
+            let mut pruned_conditions: Vec<TermFilterAst> = Vec::new();
+            for condition in conditions.into_iter().filter_map(simplify_ast) {
+                // Nested conjunctions are flattened.
+                if let TermFilterAst::And(sub_conditions) = condition {
+                    pruned_conditions.extend(sub_conditions);
+                } else {
+                    pruned_conditions.push(condition);
+                }
+            }
             match pruned_conditions.len() {
                 0 => None,
                 1 => pruned_conditions.pop().unwrap().into(),
@@ -250,7 +286,14 @@ fn simplify_ast(ast: UnsimplifiedTagFilterAst) -> Option<TermFilterAst> {
             for condition in conditions {
                 // If we get None as part of the condition here, we return None
                 // directly. (Remember None means True).
-                pruned_conditions.push(simplify_ast(condition)?);
+                let condition = simplify_ast(condition)?;
+                // Nested disjunctions are flattened, so that the values of a field end up in a
+                // single disjunction, whether they come from a term set or from several terms.
+                if let TermFilterAst::Or(sub_conditions) = condition {
+                    pruned_conditions.extend(sub_conditions);
+                } else {
+                    pruned_conditions.push(condition);
+                }
             }
             match pruned_conditions.len() {
                 0 => None,
@@ -294,22 +337,44 @@ fn expand_to_tag_ast(terms_filter_ast: TermFilterAst) -> TagFilterAst {
             TagFilterAst::And(children.into_iter().map(expand_to_tag_ast).collect())
         }
         TermFilterAst::Or(children) => {
-            TagFilterAst::Or(children.into_iter().map(expand_to_tag_ast).collect())
-        }
-        TermFilterAst::Term { field, value } => {
-            let field_is_tag = TagFilterAst::Tag {
-                is_present: false,
-                tag: field_tag(&field),
-            };
-            let term_tag = TagFilterAst::Tag {
-                is_present: true,
-                tag: term_tag(&field, &value),
-            };
-            TagFilterAst::Or(vec![field_is_tag, term_tag])
+            // The terms of a disjunction are grouped by field, so that a disjunction of values of a
+            // field, for instance `tenant_id: IN [a b c]`, expands to
+            // `¬tenant_id! ∨ tenant_id:a ∨ tenant_id:b ∨ tenant_id:c`.
+            let mut tag_asts: Vec<TagFilterAst> = Vec::new();
+            // Position in `tag_asts` of the disjunction of the values of each field.
+            let mut field_tag_ast_ords: HashMap<String, usize> = HashMap::new();
+
+            for child in children {
+                let TermFilterAst::Term { field, value } = child else {
+                    tag_asts.push(expand_to_tag_ast(child));
+                    continue;
+                };
+                if let Some(&tag_ast_ord) = field_tag_ast_ords.get(&field) {
+                    if let TagFilterAst::Or(field_tag_asts) = &mut tag_asts[tag_ast_ord] {
+                        field_tag_asts.push(tag(term_tag(&field, &value)));
+                    }
+                } else {
+                    field_tag_ast_ords.insert(field.clone(), tag_asts.len());
+                    tag_asts.push(expand_field_values_to_tag_ast(&field, &[value]));
+                }
+            }
+            if tag_asts.len() == 1 {
+                return tag_asts.pop().unwrap();
+            }
+            TagFilterAst::Or(tag_asts)
         }
+        TermFilterAst::Term { field, value } => expand_field_values_to_tag_ast(&field, &[value]),
     }
 }
 
+/// Expands a disjunction of values of a field into a tag filter, which also accepts the splits
+/// for which the field is not a tag field.
+fn expand_field_values_to_tag_ast(field: &str, values: &[String]) -> TagFilterAst {
+    let field_is_tag = no_tag(field_tag(field));
+    let term_tags = values.iter().map(|value| tag(term_tag(field, value)));
+    TagFilterAst::Or(std::iter::once(field_is_tag).chain(term_tags).collect())
+}
+
 fn collect_tag_filters_for_clause(
     clause: Vec<(Occur, UnsimplifiedTagFilterAst)>,
 ) -> UnsimplifiedTagFilterAst {
@@ -329,17 +394,24 @@ fn collect_tag_filters_for_clause(
         // phase.
         return UnsimplifiedTagFilterAst::And(removed_should_clause);
     }
-    let converted_not_clause = clause
-        .into_iter()
-        .map(|(occur, ast)| match occur {
-            Occur::MustNot => negate_ast(ast),
-            Occur::Should => ast,
+    // Without must clauses, at least one should clause has to match, and none of the must not
+    // clauses.
+    let mut should_clauses: Vec<UnsimplifiedTagFilterAst> = Vec::new();
+    let mut converted_not_clauses: Vec<UnsimplifiedTagFilterAst> = Vec::new();
+
+    for (occur, ast) in clause {
+        match occur {
+            Occur::MustNot => converted_not_clauses.push(negate_ast(ast)),
+            Occur::Should => should_clauses.push(ast),
             Occur::Must => {
                 unreachable!("This should never happen due to check above.")
             }
-        })
-        .collect();
-    UnsimplifiedTagFilterAst::Or(converted_not_clause)
+        }
+    }
+    if !should_clauses.is_empty() {
+        converted_not_clauses.push(UnsimplifiedTagFilterAst::Or(should_clauses));
+    }
+    UnsimplifiedTagFilterAst::And(converted_not_clauses)
 }
 
 /// Negate the unsimplified ast, pushing the negation to the leaf
@@ -387,10 +459,14 @@ pub fn no_tag(tag: impl ToString) -> TagFilterAst {
 }
 #[cfg(test)]
 mod test {
-    use quickwit_query::query_ast::{QueryAst, UserInputQuery};
+    use std::collections::{BTreeSet, HashMap};
+    use std::ops::RangeInclusive;
+
+    use proptest::prelude::*;
+    use quickwit_query::query_ast::{BoolQuery, QueryAst, TermQuery, TermSetQuery, UserInputQuery};
     use quickwit_query::BooleanOperand;
 
-    use super::extract_tags_from_query;
+    use super::{append_to_tag_set, extract_tags_from_query, field_tag};
     use crate::tag_pruning::TagFilterAst;
 
     fn extract_tags_from_query_helper(user_query: &str) -> Option<TagFilterAst> {
@@ -469,8 +545,108 @@ mod test {
 
     #[test]
     fn test_disjunction_of_tag_disjunction_with_not_clause() {
-        // ORed negative tags make the result inconclusive. See simplify_ast() for details
-        assert!(extract_tags_from_query_helper("(user:bart -lang:fr)").is_none());
+        // Negative tags are inconclusive, but the should clause must still match.
+        assert_eq!(
+            &extract_tags_from_query_helper("(user:bart -lang:fr)")
+                .unwrap()
+                .to_string(),
+            "(¬user! ∨ user:bart)"
+        );
+        assert_eq!(
+            &extract_tags_from_query_helper("(user:bart lang:en -lang:fr)")
+                .unwrap()
+                .to_string(),
+            "((¬user! ∨ user:bart) ∨ (¬lang! ∨ lang:en))"
+        );
+    }
+
+    #[test]
+    fn test_negation_of_tag_is_uninformative() {
+        assert!(extract_tags_from_query_helper("-lang:fr").is_none());
+        assert!(extract_tags_from_query_helper("NOT lang:fr").is_none());
+        assert!(extract_tags_from_query_helper("NOT (lang:fr AND user:bart)").is_none());
+        assert!(extract_tags_from_query_helper("NOT lang: IN [fr en]").is_none());
+    }
+
+    #[test]
+    fn test_extract_tags_from_term_set_query() {
+        assert_eq!(
+            &extract_tags_from_query_helper("lang: IN [fr en]")
+                .unwrap()
+                .to_string(),
+            "(¬lang! ∨ lang:en ∨ lang:fr)"
+        );
+        assert_eq!(
+            &extract_tags_from_query_helper("user:bart AND lang: IN [fr en]")
+                .unwrap()
+                .to_string(),
+            "(¬user! ∨ user:bart) ∧ (¬lang! ∨ lang:en ∨ lang:fr)"
+        );
+        // The values of a field are grouped, whether they come from a term set or from several
+        // terms.
+        assert_eq!(
+            &extract_tags_from_query_helper("lang:de OR user:bart OR lang: IN [fr en]")
+                .unwrap()
+                .to_string(),
+            "((¬lang! ∨ lang:de ∨ lang:en ∨ lang:fr) ∨ (¬user! ∨ user:bart))"
+        );
+        // A document can have several values for a field: the value sets of a conjunction are not
+        // intersected.
+        assert_eq!(
+            &extract_tags_from_query_helper("lang: IN [fr en] AND lang: IN [en de]")
+                .unwrap()
+                .to_string(),
+            "(¬lang! ∨ lang:en ∨ lang:fr) ∧ (¬lang! ∨ lang:de ∨ lang:en)"
+        );
+    }
+
+    #[test]
+    fn test_extract_tags_from_terms_query() {
+        // The Elasticsearch `terms` query is a disjunction of term queries.
+        let query_ast: QueryAst = BoolQuery {
+            should: ["a", "b", "c"]
+                .into_iter()
+                .map(|tenant_id| {
+                    TermQuery {
+                        field: "tenant_id".to_string(),
+                        value: tenant_id.to_string(),
+                    }
+                    .into()
+                })
+                .collect(),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            &extract_tags_from_query(query_ast).unwrap().to_string(),
+            "(¬tenant_id! ∨ tenant_id:a ∨ tenant_id:b ∨ tenant_id:c)"
+        );
+    }
+
+    #[test]
+    fn test_extract_tags_from_nested_bool_filter() {
+        let term_query = |field: &str, value: &str| -> QueryAst {
+            TermQuery {
+                field: field.to_string(),
+                value: value.to_string(),
+            }
+            .into()
+        };
+        let query_ast: QueryAst = BoolQuery {
+            filter: vec![BoolQuery {
+                must: vec![term_query("user", "bart")],
+                filter: vec![term_query("lang", "fr")],
+                ..Default::default()
+            }
+            .into()],
+            must_not: vec![term_query("lang", "en")],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(
+            &extract_tags_from_query(query_ast).unwrap().to_string(),
+            "(¬user! ∨ user:bart) ∧ (¬lang! ∨ lang:fr)"
+        );
     }
 
     #[test]
@@ -494,6 +670,179 @@ mod test {
         );
     }
 
+    const FIELDS: [&str; 3] = ["tenant_id", "lang", "body"];
+    const TAG_FIELDS: [&str; 2] = ["tenant_id", "lang"];
+    const VALUES: [&str; 3] = ["a", "b", "c"];
+
+    /// Values of each field of a document.
+    type Doc = HashMap<String, BTreeSet<String>>;
+
+    fn field_strategy() -> impl Strategy<Value = String> {
+        proptest::sample::select(FIELDS.to_vec()).prop_map(str::to_string)
+    }
+
+    fn values_strategy(
+        num_values: RangeInclusive<usize>,
+    ) -> impl Strategy<Value = BTreeSet<String>> {
+        proptest::collection::btree_set(
+            proptest::sample::select(VALUES.to_vec()).prop_map(str::to_string),
+            num_values,
+        )
+    }
+
+    fn query_ast_strategy(with_must_not: bool) -> impl Strategy<Value = QueryAst> {
+        let leaf = prop_oneof![
+            Just(QueryAst::MatchAll),
+            (field_strategy(), values_strategy(1..=1)).prop_map(|(field, values)| {
+                TermQuery {
+                    field,
+                    value: values.into_iter().next().unwrap(),
+                }
+                .into()
+            }),
+            (field_strategy(), values_strategy(1..=3)).prop_map(|(field, values)| {
+                TermSetQuery {
+                    terms_per_field: HashMap::from([(field, values)]),
+                }
+                .into()
+            }),
+        ];
+        let max_num_must_not = if with_must_not { 3 } else { 1 };
+
+        leaf.prop_recursive(3, 32, 3, move |element| {
+            let clauses = || proptest::collection::vec(element.clone(), 0..3);
+            let must_not = proptest::collection::vec(element.clone(), 0..max_num_must_not);
+            (clauses(), clauses(), clauses(), must_not).prop_map(
+                |(must, filter, should, must_not)| {
+                    BoolQuery {
+                        must,
+                        filter,
+                        should,
+                        must_not,
+                    }
+                    .into()
+                },
+            )
+        })
+    }
+
+    fn split_docs_strategy() -> impl Strategy<Value = Vec<Doc>> {
+        let doc_strategy = proptest::collection::vec(values_strategy(0..=2), FIELDS.len())
+            .prop_map(|values_per_field| {
+                FIELDS
+                    .iter()
+                    .map(|field| field.to_string())
+                    .zip(values_per_field)
+                    .collect::<Doc>()
+            });
+        proptest::collection::vec(doc_strategy, 0..4)
+    }
+
+    fn split_tags(docs: &[Doc]) -> BTreeSet<String> {
+        let mut tag_set = BTreeSet::new();
+
+        for field in TAG_FIELDS {
+            let values: Vec<String> = docs
+                .iter()
+                .flat_map(|doc| doc[field].iter().cloned())
+                .collect();
+            append_to_tag_set(field, &values, &mut tag_set);
+        }
+        tag_set
+    }
+
+    /// Brute-force evaluation of the query, seen as an expression over the values of the tag
+    /// fields of a split. The values of the fields that are not tag fields are unknown.
+    fn evaluate_tag_expression(query_ast: &QueryAst, tag_set: &BTreeSet<String>) -> bool {
+        let has_value = |field: &str, value: &str| {
+            !tag_set.contains(&field_tag(field)) || tag_set.contains(&format!("{field}:{value}"))
+        };
+        match query_ast {
+            QueryAst::MatchAll => true,
+            QueryAst::Term(term_query) => has_value(&term_query.field, &term_query.value),
+            QueryAst::TermSet(term_set_query) => term_set_query
+                .terms_per_field
+                .iter()
+                .any(|(field, values)| values.iter().any(|value| has_value(field, value))),
+            QueryAst::Bool(bool_query) => {
+                assert!(bool_query.must_not.is_empty());
+                let mut must_clauses = bool_query.must.iter().chain(&bool_query.filter).peekable();
+
+                if must_clauses.peek().is_some() {
+                    return must_clauses.all(|clause| evaluate_tag_expression(clause, tag_set));
+                }
+                bool_query.should.is_empty()
+                    || bool_query
+                        .should
+                        .iter()
+                        .any(|clause| evaluate_tag_expression(clause, tag_set))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Brute-force evaluation of the query on a document.
+    fn matches_doc(query_ast: &QueryAst, doc: &Doc) -> bool {
+        match query_ast {
+            QueryAst::MatchAll => true,
+            QueryAst::Term(term_query) => doc[&term_query.field].contains(&term_query.value),
+            QueryAst::TermSet(term_set_query) => term_set_query
+                .terms_per_field
+                .iter()
+                .any(|(field, values)| !doc[field].is_disjoint(values)),
+            QueryAst::Bool(bool_query) => {
+                let must_clauses: Vec<&QueryAst> =
+                    bool_query.must.iter().chain(&bool_query.filter).collect();
+                must_clauses.iter().all(|clause| matches_doc(clause, doc))
+                    && !bool_query
+                        .must_not
+                        .iter()
+                        .any(|clause| matches_doc(clause, doc))
+                    && (!must_clauses.is_empty()
+                        || bool_query.should.is_empty()
+                        || bool_query
+                            .should
+                            .iter()
+                            .any(|clause| matches_doc(clause, doc)))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(ProptestConfig {
+          cases: 10000, .. ProptestConfig::default()
+        })]
+        #[test]
+        fn test_proptest_tag_filter_matches_tag_expression(
+            query_ast in query_ast_strategy(false),
+            docs in split_docs_strategy(),
+        ) {
+            // Without negations, the pruning decision is exactly the one of the tag expression.
+            let tag_set = split_tags(&docs);
+            let expected = evaluate_tag_expression(&query_ast, &tag_set);
+            let tag_filter_opt = extract_tags_from_query(query_ast);
+            let is_split_kept = tag_filter_opt
+                .map(|tag_filter| tag_filter.evaluate(&tag_set))
+                .unwrap_or(true);
+            assert_eq!(is_split_kept, expected);
+        }
+
+        #[test]
+        fn test_proptest_tag_filter_never_prunes_matching_splits(
+            query_ast in query_ast_strategy(true),
+            docs in split_docs_strategy(),
+        ) {
+            let tag_set = split_tags(&docs);
+            let is_split_matching = docs.iter().any(|doc| matches_doc(&query_ast, doc));
+            let tag_filter_opt = extract_tags_from_query(query_ast);
+            let is_split_kept = tag_filter_opt
+                .map(|tag_filter| tag_filter.evaluate(&tag_set))
+                .unwrap_or(true);
+            assert!(is_split_kept || !is_split_matching);
+        }
+    }
+
     #[test]
     fn test_match_tag_field_name() {
         assert!(super::match_tag_field_name("tagfield", "tagfield:val"));
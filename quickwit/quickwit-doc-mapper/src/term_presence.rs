@@ -0,0 +1,379 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::Hasher;
+
+use anyhow::bail;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use quickwit_query::query_ast::QueryAst;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use siphasher::sip128::{Hasher128, SipHasher};
+
+use crate::tag_pruning::{extract_exact_terms_from_query, TagFilterAst};
+
+/// Term presence filters of a split, keyed by field name.
+pub type TermPresenceFilters = BTreeMap<String, TermPresenceFilter>;
+
+/// Declares a high cardinality field for which a term presence filter is recorded in the
+/// metadata of each split, allowing searches for exact values of the field to skip the splits
+/// that do not contain them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TermPresenceFilterConfig {
+    /// Name of the field. It must be an indexed text field using the `raw` tokenizer.
+    pub field: String,
+
+    /// Target probability that the filter reports a value absent from the split as present.
+    #[serde(default = "TermPresenceFilterConfig::default_false_positive_rate")]
+    pub false_positive_rate: f64,
+
+    /// Maximum size of the filter of a split. No filter is recorded for the splits holding too
+    /// many distinct values to meet the false positive rate within this budget.
+    #[serde(default = "TermPresenceFilterConfig::default_max_num_bytes")]
+    pub max_num_bytes: usize,
+}
+
+impl TermPresenceFilterConfig {
+    /// Returns the default value of `false_positive_rate`.
+    pub fn default_false_positive_rate() -> f64 {
+        0.01
+    }
+
+    /// Returns the default value of `max_num_bytes`.
+    pub fn default_max_num_bytes() -> usize {
+        16 * 1024
+    }
+
+    /// Checks that the false positive rate and the size budget are valid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(self.false_positive_rate > 0.0 && self.false_positive_rate < 1.0) {
+            bail!(
+                "false positive rate of the term presence filter of field `{}` must be in the \
+                 range (0, 1), got `{}`",
+                self.field,
+                self.false_positive_rate
+            );
+        }
+        if self.max_num_bytes == 0 {
+            bail!(
+                "size budget of the term presence filter of field `{}` must be strictly positive",
+                self.field
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the maximum number of distinct values that a filter can hold within the size
+    /// budget while meeting the false positive rate.
+    pub fn max_num_values(&self) -> usize {
+        let num_bits = self.max_num_bytes as f64 * 8.0;
+        (num_bits / num_bits_per_value(self.false_positive_rate)) as usize
+    }
+}
+
+/// Number of bits per value of a Bloom filter of optimal size for the false positive rate.
+fn num_bits_per_value(false_positive_rate: f64) -> f64 {
+    -false_positive_rate.ln() / (std::f64::consts::LN_2 * std::f64::consts::LN_2)
+}
+
+/// Bloom filter over the values of a field in a split.
+///
+/// The filter answers whether a value may be present in the split: a negative answer is
+/// definitive, whereas a positive answer is wrong with a probability bounded by the false positive
+/// rate the filter was built with. The values are hashed with SipHash-2-4 and a fixed key, so the
+/// filters remain valid across versions and nodes.
+#[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TermPresenceFilter {
+    num_hashes: u32,
+    #[serde(
+        serialize_with = "serialize_base64",
+        deserialize_with = "deserialize_base64"
+    )]
+    bits: Vec<u8>,
+}
+
+impl fmt::Debug for TermPresenceFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TermPresenceFilter")
+            .field("num_hashes", &self.num_hashes)
+            .field("num_bytes", &self.bits.len())
+            .finish()
+    }
+}
+
+impl TermPresenceFilter {
+    /// Builds a filter holding `values` with the given false positive rate.
+    pub fn build<'a>(
+        values: impl ExactSizeIterator<Item = &'a str>,
+        false_positive_rate: f64,
+    ) -> Self {
+        let num_bits = (values.len() as f64 * num_bits_per_value(false_positive_rate)).ceil();
+        let num_bytes = (num_bits / 8.0).ceil() as usize;
+        let num_hashes = (-false_positive_rate.log2()).ceil().clamp(1.0, 32.0) as u32;
+
+        let mut filter = Self {
+            num_hashes,
+            bits: vec![0; num_bytes],
+        };
+        for value in values {
+            for bit_pos in filter.bit_positions(value) {
+                filter.bits[bit_pos / 8] |= 1 << (bit_pos % 8);
+            }
+        }
+        filter
+    }
+
+    /// Returns the size of the filter in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns false if `value` is definitely absent from the split.
+    pub fn may_contain(&self, value: &str) -> bool {
+        if self.bits.is_empty() {
+            return false;
+        }
+        self.bit_positions(value)
+            .all(|bit_pos| self.bits[bit_pos / 8] & (1 << (bit_pos % 8)) != 0)
+    }
+
+    fn bit_positions(&self, value: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = SipHasher::new();
+        hasher.write(value.as_bytes());
+        let hash = hasher.finish128();
+        let num_bits = self.bits.len() as u64 * 8;
+
+        (0..self.num_hashes as u64)
+            .map(move |i| (hash.h1.wrapping_add(i.wrapping_mul(hash.h2)) % num_bits) as usize)
+    }
+}
+
+fn serialize_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+}
+
+fn deserialize_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    BASE64_STANDARD
+        .decode(encoded)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Extracts the predicate that the term presence filters of a split can evaluate from a query.
+///
+/// Only term and term set queries, and full-text queries that do not override the tokenizer
+/// of the field nor match all documents when no term is left after tokenization, are taken into
+/// account: term presence fields use the `raw` tokenizer, so these queries search for the exact
+/// values of the field. Any other query bypasses the filters.
+///
+/// Returns `None` if the filters cannot prune any split for the query.
+pub fn extract_term_presence_filter_from_query(query_ast: QueryAst) -> Option<TagFilterAst> {
+    extract_exact_terms_from_query(query_ast)
+}
+
+/// Evaluates a predicate extracted with [`extract_term_presence_filter_from_query`] over the term
+/// presence filters of a split.
+///
+/// Returns false only if no document of the split can match the query. Fields without a term
+/// presence filter in the split never cause the split to be pruned.
+pub fn may_match_term_presence_filters(
+    filter_ast: &TagFilterAst,
+    term_presence_filters: &TermPresenceFilters,
+) -> bool {
+    match filter_ast {
+        TagFilterAst::And(children) => children
+            .iter()
+            .all(|child| may_match_term_presence_filters(child, term_presence_filters)),
+        TagFilterAst::Or(children) => children
+            .iter()
+            .any(|child| may_match_term_presence_filters(child, term_presence_filters)),
+        TagFilterAst::Tag { is_present, tag } => {
+            // The predicate is made of `{field}!` tags, checking whether the field has a filter,
+            // and of `{field}:{value}` tags, checking whether the value may be present.
+            let tag_is_present = if let Some(field) = tag.strip_suffix('!') {
+                term_presence_filters.contains_key(field)
+            } else if let Some((field, value)) = tag.split_once(':') {
+                term_presence_filters
+                    .get(field)
+                    .map_or(true, |filter| filter.may_contain(value))
+            } else {
+                return true;
+            };
+            tag_is_present == *is_present
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::{
+        query_ast_from_user_text, FullTextMode, FullTextParams, FullTextQuery, QueryAst,
+        TermSetQuery,
+    };
+
+    use super::*;
+
+    fn trace_ids(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("trace-{i:08x}")).collect()
+    }
+
+    fn build_filter(values: &[String], false_positive_rate: f64) -> TermPresenceFilter {
+        TermPresenceFilter::build(values.iter().map(String::as_str), false_positive_rate)
+    }
+
+    fn filters_for_test(values: &[String]) -> TermPresenceFilters {
+        BTreeMap::from([("trace_id".to_string(), build_filter(values, 0.01))])
+    }
+
+    fn may_match_user_query(user_query: &str, filters: &TermPresenceFilters) -> bool {
+        let query_ast = query_ast_from_user_text(user_query, None)
+            .parse_user_query(&[])
+            .unwrap();
+        extract_term_presence_filter_from_query(query_ast).map_or(true, |filter_ast| {
+            may_match_term_presence_filters(&filter_ast, filters)
+        })
+    }
+
+    #[test]
+    fn test_term_presence_filter_config_deserialization() {
+        let config: TermPresenceFilterConfig =
+            serde_json::from_str(r#"{"field": "trace_id"}"#).unwrap();
+        assert_eq!(config.false_positive_rate, 0.01);
+        assert_eq!(config.max_num_bytes, 16 * 1024);
+        config.validate().unwrap();
+        // About 9.6 bits per value at 1%.
+        assert_eq!(config.max_num_values(), 13_674);
+
+        let config: TermPresenceFilterConfig = serde_json::from_str(
+            r#"{"field": "trace_id", "false_positive_rate": 1.0, "max_num_bytes": 1024}"#,
+        )
+        .unwrap();
+        config.validate().unwrap_err();
+
+        let config: TermPresenceFilterConfig =
+            serde_json::from_str(r#"{"field": "trace_id", "max_num_bytes": 0}"#).unwrap();
+        config.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_term_presence_filter_has_no_false_negatives() {
+        let values = trace_ids(0..10_000);
+        let filter = build_filter(&values, 0.01);
+        assert_eq!(filter.num_bytes(), 11_982);
+        assert!(values.iter().all(|value| filter.may_contain(value)));
+    }
+
+    #[test]
+    fn test_term_presence_filter_false_positive_rate() {
+        for false_positive_rate in [0.1, 0.01, 0.001] {
+            let filter = build_filter(&trace_ids(0..10_000), false_positive_rate);
+            let num_false_positives = trace_ids(10_000..110_000)
+                .iter()
+                .filter(|value| filter.may_contain(value))
+                .count();
+            let observed_false_positive_rate = num_false_positives as f64 / 100_000.0;
+            assert!(observed_false_positive_rate < false_positive_rate * 2.0);
+        }
+    }
+
+    #[test]
+    fn test_term_presence_filter_empty() {
+        let filter = build_filter(&[], 0.01);
+        assert_eq!(filter.num_bytes(), 0);
+        assert!(!filter.may_contain("trace-00000000"));
+    }
+
+    #[test]
+    fn test_term_presence_filter_serialization() {
+        let filter = build_filter(&trace_ids(0..100), 0.01);
+        let filter_json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(filter_json["num_hashes"], 7);
+        assert!(filter_json["bits"].is_string());
+
+        let deserialized_filter: TermPresenceFilter = serde_json::from_value(filter_json).unwrap();
+        assert_eq!(deserialized_filter, filter);
+    }
+
+    #[test]
+    fn test_may_match_term_presence_filters_term() {
+        let filters = filters_for_test(&trace_ids(0..100));
+        // Miss.
+        assert!(!may_match_user_query("trace_id:unknown", &filters));
+        // Hit.
+        assert!(may_match_user_query("trace_id:trace-00000001", &filters));
+        // Fields without a filter do not prune.
+        assert!(may_match_user_query("span_id:unknown", &filters));
+        assert!(may_match_user_query(
+            "trace_id:unknown OR span_id:unknown",
+            &filters
+        ));
+        assert!(!may_match_user_query(
+            "trace_id:unknown AND span_id:unknown",
+            &filters
+        ));
+    }
+
+    #[test]
+    fn test_may_match_term_presence_filters_terms() {
+        let filters = filters_for_test(&trace_ids(0..100));
+        assert!(!may_match_user_query("trace_id: IN [foo bar]", &filters));
+        assert!(may_match_user_query(
+            "trace_id: IN [foo trace-00000002]",
+            &filters
+        ));
+
+        let query_ast: QueryAst = TermSetQuery {
+            terms_per_field: [(
+                "trace_id".to_string(),
+                ["foo".to_string(), "bar".to_string()].into_iter().collect(),
+            )]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        let filter_ast = extract_term_presence_filter_from_query(query_ast).unwrap();
+        assert!(!may_match_term_presence_filters(&filter_ast, &filters));
+    }
+
+    #[test]
+    fn test_may_match_term_presence_filters_bypass() {
+        let filters = filters_for_test(&trace_ids(0..100));
+        // Negations, prefixes, wildcards, and ranges cannot rely on the filters.
+        assert!(may_match_user_query("NOT trace_id:unknown", &filters));
+        assert!(may_match_user_query("trace_id:unknown*", &filters));
+        assert!(may_match_user_query("trace_id:unk*wn", &filters));
+        assert!(may_match_user_query("trace_id:[a TO b]", &filters));
+
+        // Full-text queries overriding the tokenizer of the field do not search for exact values.
+        let query_ast: QueryAst = FullTextQuery {
+            field: "trace_id".to_string(),
+            text: "UNKNOWN".to_string(),
+            params: FullTextParams {
+                tokenizer: Some("raw_lowercase".to_string()),
+                mode: FullTextMode::PhraseFallbackToIntersection,
+                zero_terms_query: Default::default(),
+            },
+            lenient: false,
+        }
+        .into();
+        assert!(extract_term_presence_filter_from_query(query_ast).is_none());
+    }
+}
@@ -18,37 +18,50 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use futures_util::StreamExt;
 use itertools::Itertools;
 use quickwit_common::fs::{empty_dir, get_cache_directory_path};
 use quickwit_common::pretty::PrettySample;
-use quickwit_common::rate_limited_error;
+use quickwit_common::uri::Uri;
+use quickwit_common::{rate_limited_error, split_file};
 use quickwit_config::{validate_identifier, IndexConfig, SourceConfig};
 use quickwit_indexing::check_source_connectivity;
+use quickwit_metastore::checkpoint::{
+    IndexCheckpointDelta, PartitionId, SerializableShardPositions, SerializableSourcePositions,
+    SourceCheckpoint, SourceCheckpointDelta,
+};
 use quickwit_metastore::{
-    AddSourceRequestExt, CreateIndexResponseExt, IndexMetadata, IndexMetadataResponseExt,
-    ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
-    MetastoreServiceStreamSplitsExt, SplitInfo, SplitMetadata, SplitState,
+    use_shard_api, AddSourceRequestExt, CreateIndexResponseExt, IndexMetadata,
+    IndexMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsQuery,
+    ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, RestoreIndexRequestExt,
+    SnapshotIndexResponseExt, Split, SplitInfo, SplitMetadata, SplitState,
 };
+use quickwit_proto::ingest::Shard;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, EntityKind,
-    IndexMetadataRequest, ListIndexesMetadataRequest, ListSplitsRequest,
-    MarkSplitsForDeletionRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
-    ResetSourceCheckpointRequest,
+    IndexMetadataRequest, ListIndexesMetadataRequest, ListShardsRequest, ListShardsSubrequest,
+    ListSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError, MetastoreService,
+    MetastoreServiceClient, PublishSplitsRequest, ResetSourceCheckpointRequest,
+    RestoreIndexRequest, SnapshotIndexRequest,
 };
-use quickwit_proto::types::{IndexUid, SplitId};
+use quickwit_proto::types::{IndexUid, ShardId, SourceId, SplitId};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
-use quickwit_storage::{StorageResolver, StorageResolverError};
+use quickwit_storage::{load_file, StorageResolver, StorageResolverError};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::{error, info};
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
 
 use crate::garbage_collection::{
     delete_splits_from_storage_and_metastore, run_garbage_collect, DeleteSplitsError,
     SplitRemovalInfo,
 };
+use crate::snapshot::{
+    IndexSnapshot, IndexSnapshotReport, RestoreIndexReport, INDEX_SNAPSHOT_FILE_NAME,
+};
 
 #[derive(Error, Debug)]
 pub enum IndexServiceError {
@@ -66,6 +79,8 @@ pub enum IndexServiceError {
     OperationNotAllowed(String),
     #[error("internal error: {0}")]
     Internal(String),
+    #[error("invalid index snapshot: {0:#}")]
+    InvalidSnapshot(anyhow::Error),
 }
 
 impl ServiceError for IndexServiceError {
@@ -77,6 +92,7 @@ impl ServiceError for IndexServiceError {
             }
             Self::InvalidConfig(_) => ServiceErrorCode::BadRequest,
             Self::InvalidIdentifier(_) => ServiceErrorCode::BadRequest,
+            Self::InvalidSnapshot(_) => ServiceErrorCode::BadRequest,
             Self::Metastore(error) => error.error_code(),
             Self::OperationNotAllowed(_) => ServiceErrorCode::Forbidden,
             Self::SplitDeletion(delete_splits_error) => {
@@ -504,6 +520,446 @@ impl IndexService {
 
         Ok(source_config)
     }
+
+    /// Exports the positions of the sources of the indexes matching `index_id_patterns`. The
+    /// positions of sources relying on the shard API are read from the shard table, which holds
+    /// the positions tracked by the shard positions service. The positions of the other sources
+    /// are read from the index checkpoints.
+    pub async fn export_positions(
+        &self,
+        index_id_patterns: Vec<String>,
+    ) -> Result<SerializableShardPositions, IndexServiceError> {
+        let list_indexes_metadata_request = ListIndexesMetadataRequest { index_id_patterns };
+        let indexes_metadata = self
+            .metastore
+            .list_indexes_metadata(list_indexes_metadata_request)
+            .await?
+            .deserialize_indexes_metadata()
+            .await?;
+
+        let mut sources = Vec::new();
+        let mut list_shards_subrequests = Vec::new();
+
+        for index_metadata in &indexes_metadata {
+            for (source_id, source_config) in &index_metadata.sources {
+                if use_shard_api(&source_config.source_params) {
+                    list_shards_subrequests.push(ListShardsSubrequest {
+                        index_uid: Some(index_metadata.index_uid.clone()),
+                        source_id: source_id.clone(),
+                        shard_state: None,
+                    });
+                    continue;
+                }
+                let Some(source_checkpoint) =
+                    index_metadata.checkpoint.source_checkpoint(source_id)
+                else {
+                    continue;
+                };
+                if source_checkpoint.is_empty() {
+                    continue;
+                }
+                sources.push(SerializableSourcePositions {
+                    index_uid: index_metadata.index_uid.clone(),
+                    source_id: source_id.clone(),
+                    positions: source_checkpoint.clone(),
+                    num_position_regressions: 0,
+                });
+            }
+        }
+        if !list_shards_subrequests.is_empty() {
+            let list_shards_request = ListShardsRequest {
+                subrequests: list_shards_subrequests,
+            };
+            let list_shards_response = self.metastore.list_shards(list_shards_request).await?;
+
+            for list_shards_subresponse in list_shards_response.subresponses {
+                let positions: SourceCheckpoint = list_shards_subresponse
+                    .shards
+                    .iter()
+                    .filter(|shard| !shard.publish_position_inclusive().is_beginning())
+                    .map(|shard| {
+                        (
+                            PartitionId::from(shard.shard_id().as_str()),
+                            shard.publish_position_inclusive(),
+                        )
+                    })
+                    .collect();
+                if positions.is_empty() {
+                    continue;
+                }
+                sources.push(SerializableSourcePositions {
+                    index_uid: list_shards_subresponse.index_uid().clone(),
+                    source_id: list_shards_subresponse.source_id,
+                    positions,
+                    num_position_regressions: 0,
+                });
+            }
+        }
+        Ok(SerializableShardPositions { sources })
+    }
+
+    /// Seeds the checkpoints of the sources with the positions of a previous export.
+    ///
+    /// Indexes are matched by ID so that the positions can be imported into a fresh cluster where
+    /// the indexes have been recreated. Unknown indexes, sources, and shards, as well as positions
+    /// that are not ahead of the current ones, are skipped and reported as warnings.
+    pub async fn import_positions(
+        &mut self,
+        shard_positions: SerializableShardPositions,
+    ) -> Result<ImportPositionsReport, IndexServiceError> {
+        let mut report = ImportPositionsReport::default();
+
+        for source_positions in shard_positions.sources {
+            let index_id = source_positions.index_uid.index_id.clone();
+            let source_id = source_positions.source_id.clone();
+            let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+
+            let index_metadata = match self.metastore.index_metadata(index_metadata_request).await {
+                Ok(index_metadata_response) => {
+                    index_metadata_response.deserialize_index_metadata()?
+                }
+                Err(MetastoreError::NotFound(_)) => {
+                    report.warn(format!(
+                        "index `{index_id}` does not exist, skipping positions of source \
+                         `{source_id}`"
+                    ));
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+            if index_metadata.index_uid != source_positions.index_uid {
+                report.warn(format!(
+                    "index `{index_id}` was exported with UID `{}` and now has UID `{}`, \
+                     importing positions of source `{source_id}` into the latter",
+                    source_positions.index_uid, index_metadata.index_uid
+                ));
+            }
+            let Some(source_config) = index_metadata.sources.get(&source_id) else {
+                report.warn(format!(
+                    "source `{source_id}` does not exist in index `{index_id}`, skipping its \
+                     positions"
+                ));
+                continue;
+            };
+            if use_shard_api(&source_config.source_params) {
+                self.import_shard_positions(
+                    index_metadata.index_uid.clone(),
+                    source_positions,
+                    &mut report,
+                )
+                .await?;
+            } else {
+                self.import_partition_positions(&index_metadata, source_positions, &mut report)
+                    .await?;
+            }
+        }
+        Ok(report)
+    }
+
+    async fn import_partition_positions(
+        &mut self,
+        index_metadata: &IndexMetadata,
+        source_positions: SerializableSourcePositions,
+        report: &mut ImportPositionsReport,
+    ) -> Result<(), IndexServiceError> {
+        let index_id = index_metadata.index_id();
+        let source_id = source_positions.source_id;
+        let current_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(&source_id)
+            .cloned()
+            .unwrap_or_default();
+        let mut source_delta = SourceCheckpointDelta::default();
+
+        for (partition_id, position) in source_positions.positions.iter() {
+            let current_position = current_checkpoint
+                .position_for_partition(&partition_id)
+                .cloned()
+                .unwrap_or_default();
+
+            if position <= current_position {
+                report.warn(format!(
+                    "position of partition `{partition_id}` of source `{source_id}` in index \
+                     `{index_id}` is not ahead of the current one, skipping it"
+                ));
+                continue;
+            }
+            source_delta
+                .record_partition_delta(partition_id, current_position, position)
+                .expect("partition delta should be valid");
+        }
+        if source_delta.is_empty() {
+            return Ok(());
+        }
+        let num_positions = source_delta.num_partitions();
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id,
+            source_delta,
+            source_incarnation_opt: None,
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_metadata.index_uid.clone()),
+            index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(
+                &index_checkpoint_delta,
+            )?),
+            ..Default::default()
+        };
+        self.metastore
+            .publish_splits(publish_splits_request)
+            .await?;
+        report.num_imported_positions += num_positions;
+        Ok(())
+    }
+
+    async fn import_shard_positions(
+        &mut self,
+        index_uid: IndexUid,
+        source_positions: SerializableSourcePositions,
+        report: &mut ImportPositionsReport,
+    ) -> Result<(), IndexServiceError> {
+        let index_id = index_uid.index_id.clone();
+        let source_id = source_positions.source_id;
+        let list_shards_request = ListShardsRequest {
+            subrequests: vec![ListShardsSubrequest {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.clone(),
+                shard_state: None,
+            }],
+        };
+        let shards: HashMap<ShardId, Shard> = self
+            .metastore
+            .list_shards(list_shards_request)
+            .await?
+            .subresponses
+            .into_iter()
+            .flat_map(|list_shards_subresponse| list_shards_subresponse.shards)
+            .map(|shard| (shard.shard_id().clone(), shard))
+            .collect();
+
+        for (partition_id, position) in source_positions.positions.iter() {
+            let shard_id = ShardId::from(partition_id.as_str());
+
+            let Some(shard) = shards.get(&shard_id) else {
+                report.warn(format!(
+                    "shard `{shard_id}` of source `{source_id}` does not exist in index \
+                     `{index_id}`, skipping its position"
+                ));
+                continue;
+            };
+            let current_position = shard.publish_position_inclusive();
+
+            if position <= current_position {
+                report.warn(format!(
+                    "position of shard `{shard_id}` of source `{source_id}` in index `{index_id}` \
+                     is not ahead of the current one, skipping it"
+                ));
+                continue;
+            }
+            let mut source_delta = SourceCheckpointDelta::default();
+            source_delta
+                .record_partition_delta(partition_id, current_position, position)
+                .expect("partition delta should be valid");
+            let index_checkpoint_delta = IndexCheckpointDelta {
+                source_id: source_id.clone(),
+                source_delta,
+                source_incarnation_opt: None,
+            };
+            // Shard positions can only be updated by the owner of the shard publish token.
+            let publish_splits_request = PublishSplitsRequest {
+                index_uid: Some(index_uid.clone()),
+                index_checkpoint_delta_json_opt: Some(serde_utils::to_json_str(
+                    &index_checkpoint_delta,
+                )?),
+                publish_token_opt: Some(shard.publish_token().to_string()),
+                ..Default::default()
+            };
+            self.metastore
+                .publish_splits(publish_splits_request)
+                .await?;
+            report.num_imported_positions += 1;
+        }
+        Ok(())
+    }
+
+    /// Takes a snapshot of the metadata, source configs, source checkpoints, and published split
+    /// manifests of an index and writes it as a single JSON document to the index storage.
+    pub async fn snapshot_index(
+        &self,
+        index_id: &str,
+    ) -> Result<IndexSnapshotReport, IndexServiceError> {
+        let snapshot = self.read_index_snapshot(index_id).await?;
+        let index_uri = snapshot.index_metadata.index_uri().clone();
+        let snapshot_uri = index_uri
+            .join(INDEX_SNAPSHOT_FILE_NAME)
+            .map_err(|error| IndexServiceError::Internal(error.to_string()))?;
+        let num_splits = snapshot.splits.len();
+        let snapshot_json = serde_utils::to_json_bytes_pretty(&snapshot)?;
+
+        let storage = self.storage_resolver.resolve(&index_uri).await?;
+        storage
+            .put(Path::new(INDEX_SNAPSHOT_FILE_NAME), Box::new(snapshot_json))
+            .await
+            .map_err(|error| {
+                IndexServiceError::Internal(format!(
+                    "failed to write index snapshot to `{snapshot_uri}`: {error}"
+                ))
+            })?;
+        info!(
+            index_id=%index_id,
+            snapshot_uri=%snapshot_uri,
+            num_splits=%num_splits,
+            "index snapshot successfully written"
+        );
+        Ok(IndexSnapshotReport {
+            snapshot_uri,
+            num_splits,
+        })
+    }
+
+    /// Reads the index metadata and the published splits of an index. The metastore reads both
+    /// consistently, so the splits contain exactly the documents up to the source checkpoints.
+    async fn read_index_snapshot(
+        &self,
+        index_id: &str,
+    ) -> Result<IndexSnapshot, IndexServiceError> {
+        let snapshot_index_request = SnapshotIndexRequest {
+            index_id: index_id.to_string(),
+        };
+        let snapshot_index_response = self
+            .metastore
+            .snapshot_index(snapshot_index_request)
+            .await?;
+        let index_metadata = snapshot_index_response.deserialize_index_metadata()?;
+        let splits: Vec<SplitMetadata> = snapshot_index_response
+            .deserialize_splits()?
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .collect();
+        Ok(IndexSnapshot {
+            index_metadata,
+            splits,
+            snapshot_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+        })
+    }
+
+    /// Recreates an index from a snapshot written by [`IndexService::snapshot_index`].
+    ///
+    /// The index must not exist in the metastore. The split files are expected to still be in the
+    /// index storage: splits whose file is missing are marked for deletion instead of being
+    /// published. The positions of sources relying on the shard API are not restored. The index,
+    /// its source checkpoints, and its splits are created in a single metastore transaction.
+    pub async fn restore_index(
+        &mut self,
+        snapshot_uri: &Uri,
+    ) -> Result<RestoreIndexReport, IndexServiceError> {
+        let snapshot_bytes = load_file(&self.storage_resolver, snapshot_uri)
+            .await
+            .map_err(IndexServiceError::InvalidSnapshot)?;
+        let snapshot: IndexSnapshot =
+            serde_utils::from_json_bytes(&snapshot_bytes).map_err(|error| {
+                IndexServiceError::InvalidSnapshot(anyhow::anyhow!(
+                    "failed to parse index snapshot `{snapshot_uri}`: {error}"
+                ))
+            })?;
+        let IndexSnapshot {
+            mut index_metadata,
+            splits,
+            ..
+        } = snapshot;
+        let index_id = index_metadata.index_id().to_string();
+        let index_uri = index_metadata.index_uri().clone();
+        let storage = self.storage_resolver.resolve(&index_uri).await?;
+
+        let shard_api_source_ids: Vec<SourceId> = index_metadata
+            .sources
+            .values()
+            .filter(|source_config| use_shard_api(&source_config.source_params))
+            .map(|source_config| source_config.source_id.clone())
+            .collect();
+
+        for source_id in &shard_api_source_ids {
+            index_metadata.checkpoint.remove_source(source_id);
+            index_metadata.checkpoint.add_source(source_id);
+        }
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let mut restored_splits = Vec::with_capacity(splits.len());
+        let mut num_restored_splits = 0;
+        let mut missing_split_ids = Vec::new();
+
+        for split_metadata in splits {
+            let split_path = PathBuf::from(split_file(&split_metadata.split_id));
+            let split_exists = storage.exists(&split_path).await.map_err(|error| {
+                IndexServiceError::Internal(format!(
+                    "failed to check existence of split `{}`: {error}",
+                    split_metadata.split_id
+                ))
+            })?;
+            let restored_split = if split_exists {
+                num_restored_splits += 1;
+
+                Split {
+                    split_state: SplitState::Published,
+                    update_timestamp: now_timestamp,
+                    publish_timestamp: Some(now_timestamp),
+                    split_metadata,
+                }
+            } else {
+                warn!(
+                    index_id=%index_id,
+                    split_id=%split_metadata.split_id,
+                    "split file is missing, marking split for deletion"
+                );
+                missing_split_ids.push(split_metadata.split_id.clone());
+
+                Split {
+                    split_state: SplitState::MarkedForDeletion,
+                    update_timestamp: now_timestamp,
+                    publish_timestamp: None,
+                    split_metadata,
+                }
+            };
+            restored_splits.push(restored_split);
+        }
+        let restore_index_request = RestoreIndexRequest::try_from_index_metadata_and_splits(
+            &index_metadata,
+            &restored_splits,
+        )?;
+        let index_uid = self
+            .metastore
+            .restore_index(restore_index_request)
+            .await?
+            .index_uid()
+            .clone();
+
+        info!(
+            index_id=%index_id,
+            snapshot_uri=%snapshot_uri,
+            num_restored_splits=%num_restored_splits,
+            num_missing_splits=%missing_split_ids.len(),
+            "index successfully restored"
+        );
+        Ok(RestoreIndexReport {
+            index_uid,
+            num_restored_splits,
+            missing_split_ids,
+        })
+    }
+}
+
+/// Outcome of a positions import.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ImportPositionsReport {
+    /// Number of shard or partition positions that were imported.
+    pub num_imported_positions: usize,
+    /// Positions that were skipped and why.
+    pub warnings: Vec<String>,
+}
+
+impl ImportPositionsReport {
+    fn warn(&mut self, warning: String) {
+        warn!("{warning}");
+        self.warnings.push(warning);
+    }
 }
 
 /// Clears the cache directory of a given source.
@@ -531,11 +987,17 @@ mod tests {
 
     use quickwit_common::uri::Uri;
     use quickwit_config::{IndexConfig, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID};
+    use quickwit_indexing::TestSandbox;
     use quickwit_metastore::{
         metastore_for_test, MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt,
     };
-    use quickwit_proto::metastore::StageSplitsRequest;
+    use quickwit_proto::metastore::{OpenShardSubrequest, OpenShardsRequest, StageSplitsRequest};
+    use quickwit_proto::search::SearchRequest;
+    use quickwit_proto::types::{DocMappingUid, Position};
+    use quickwit_query::query_ast::qast_json_helper;
+    use quickwit_search::single_node_search;
     use quickwit_storage::PutPayload;
+    use serde_json::json;
 
     use super::*;
 
@@ -644,4 +1106,375 @@ mod tests {
         assert!(splits.is_empty());
         assert!(!storage.exists(split_path).await.unwrap());
     }
+
+    async fn open_shard_for_test(
+        metastore: &MetastoreServiceClient,
+        index_uid: &IndexUid,
+        shard_id: u64,
+        publish_token_opt: Option<&str>,
+    ) {
+        let open_shards_request = OpenShardsRequest {
+            subrequests: vec![OpenShardSubrequest {
+                subrequest_id: 0,
+                index_uid: Some(index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_id: Some(ShardId::from(shard_id)),
+                leader_id: "test-ingester".to_string(),
+                follower_id: None,
+                doc_mapping_uid: Some(DocMappingUid::default()),
+                publish_token: publish_token_opt.map(ToString::to_string),
+            }],
+        };
+        metastore.open_shards(open_shards_request).await.unwrap();
+    }
+
+    async fn publish_position_for_test(
+        metastore: &MetastoreServiceClient,
+        index_uid: &IndexUid,
+        source_id: &str,
+        partition_id: PartitionId,
+        position: Position,
+        publish_token_opt: Option<&str>,
+    ) {
+        let mut source_delta = SourceCheckpointDelta::default();
+        source_delta
+            .record_partition_delta(partition_id, Position::Beginning, position)
+            .unwrap();
+        let index_checkpoint_delta = IndexCheckpointDelta {
+            source_id: source_id.to_string(),
+            source_delta,
+            source_incarnation_opt: None,
+        };
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid.clone()),
+            index_checkpoint_delta_json_opt: Some(
+                serde_json::to_string(&index_checkpoint_delta).unwrap(),
+            ),
+            publish_token_opt: publish_token_opt.map(ToString::to_string),
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_import_positions() {
+        let source_metastore = metastore_for_test();
+        let mut source_index_service =
+            IndexService::new(source_metastore.clone(), StorageResolver::for_test());
+
+        let index_config = IndexConfig::for_test("test-index", "ram://indexes/test-index");
+        let source_index_uid = source_index_service
+            .create_index(index_config.clone(), false)
+            .await
+            .unwrap()
+            .index_uid;
+        let other_index_config = IndexConfig::for_test("other-index", "ram://indexes/other-index");
+        let other_index_uid = source_index_service
+            .create_index(other_index_config, false)
+            .await
+            .unwrap()
+            .index_uid;
+
+        publish_position_for_test(
+            &source_metastore,
+            &source_index_uid,
+            INGEST_API_SOURCE_ID,
+            PartitionId::from(0u64),
+            Position::offset(42u64),
+            None,
+        )
+        .await;
+        publish_position_for_test(
+            &source_metastore,
+            &other_index_uid,
+            INGEST_API_SOURCE_ID,
+            PartitionId::from(0u64),
+            Position::offset(1u64),
+            None,
+        )
+        .await;
+
+        for shard_id in [1, 2] {
+            open_shard_for_test(
+                &source_metastore,
+                &source_index_uid,
+                shard_id,
+                Some("test-publish-token"),
+            )
+            .await;
+            publish_position_for_test(
+                &source_metastore,
+                &source_index_uid,
+                INGEST_V2_SOURCE_ID,
+                PartitionId::from(ShardId::from(shard_id).as_str()),
+                Position::offset(shard_id * 10),
+                Some("test-publish-token"),
+            )
+            .await;
+        }
+        let exported_positions = source_index_service
+            .export_positions(vec!["*".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(exported_positions.sources.len(), 3);
+
+        let exported_positions_json = serde_json::to_string(&exported_positions).unwrap();
+        let exported_positions: SerializableShardPositions =
+            serde_json::from_str(&exported_positions_json).unwrap();
+
+        // The fresh cluster only knows about `test-index` and its first shard.
+        let target_metastore = metastore_for_test();
+        let mut target_index_service =
+            IndexService::new(target_metastore.clone(), StorageResolver::for_test());
+        let target_index_uid = target_index_service
+            .create_index(index_config, false)
+            .await
+            .unwrap()
+            .index_uid;
+        open_shard_for_test(&target_metastore, &target_index_uid, 1, None).await;
+
+        let report = target_index_service
+            .import_positions(exported_positions.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.num_imported_positions, 2);
+        // Index UID mismatch (x2), unknown index, and unknown shard.
+        assert_eq!(report.warnings.len(), 4);
+        assert!(report
+            .warnings
+            .iter()
+            .any(|warning| warning.contains("index `other-index` does not exist")));
+        assert!(report
+            .warnings
+            .iter()
+            .any(|warning| warning.contains("shard `00000000000000000002`")));
+
+        // A pipeline restarting on the fresh cluster resumes from the imported positions.
+        let index_metadata = target_metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        let source_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(INGEST_API_SOURCE_ID)
+            .unwrap();
+        assert_eq!(
+            source_checkpoint.position_for_partition(&PartitionId::from(0u64)),
+            Some(&Position::offset(42u64))
+        );
+        let list_shards_request = ListShardsRequest {
+            subrequests: vec![ListShardsSubrequest {
+                index_uid: Some(target_index_uid.clone()),
+                source_id: INGEST_V2_SOURCE_ID.to_string(),
+                shard_state: None,
+            }],
+        };
+        let shards = target_metastore
+            .list_shards(list_shards_request)
+            .await
+            .unwrap()
+            .subresponses
+            .remove(0)
+            .shards;
+        assert_eq!(shards.len(), 1);
+        assert_eq!(
+            shards[0].publish_position_inclusive(),
+            Position::offset(10u64)
+        );
+
+        // Importing the same positions again is a no-op.
+        let report = target_index_service
+            .import_positions(exported_positions)
+            .await
+            .unwrap();
+        assert_eq!(report.num_imported_positions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore_index() {
+        let index_id = "test-snapshot-index";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        for split_ord in 0..3 {
+            let docs =
+                (0..10).map(move |doc_id| json!({"body": format!("doc {split_ord}-{doc_id}")}));
+            test_sandbox.add_documents(docs).await.unwrap();
+        }
+        let storage_resolver = test_sandbox.storage_resolver();
+        let source_index_service =
+            IndexService::new(test_sandbox.metastore(), storage_resolver.clone());
+        let snapshot_report = source_index_service.snapshot_index(index_id).await.unwrap();
+        assert!(snapshot_report.num_splits > 1);
+        assert!(snapshot_report
+            .snapshot_uri
+            .as_str()
+            .ends_with(INDEX_SNAPSHOT_FILE_NAME));
+
+        // Restore the index into a fresh metastore.
+        let target_metastore = metastore_for_test();
+        let mut target_index_service =
+            IndexService::new(target_metastore.clone(), storage_resolver.clone());
+        let restore_report = target_index_service
+            .restore_index(&snapshot_report.snapshot_uri)
+            .await
+            .unwrap();
+        assert_eq!(
+            restore_report.num_restored_splits,
+            snapshot_report.num_splits
+        );
+        assert!(restore_report.missing_split_ids.is_empty());
+
+        let index_metadata = target_metastore
+            .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(index_metadata.index_uid, restore_report.index_uid);
+        let source_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(INGEST_API_SOURCE_ID)
+            .unwrap();
+        assert_eq!(source_checkpoint.num_partitions(), 3);
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("doc", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response =
+            single_node_search(search_request, target_metastore, storage_resolver)
+                .await
+                .unwrap();
+        assert_eq!(search_response.num_hits, 30);
+
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_restore_index_marks_missing_splits_for_deletion() {
+        let mut source_metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut source_index_service =
+            IndexService::new(source_metastore.clone(), storage_resolver.clone());
+        let index_uri = "ram://indexes/test-index";
+        let index_config = IndexConfig::for_test("test-index", index_uri);
+        let index_uid = source_index_service
+            .create_index(index_config, false)
+            .await
+            .unwrap()
+            .index_uid;
+
+        let splits_metadata = ["split-1", "split-2"]
+            .into_iter()
+            .map(|split_id| SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let stage_splits_request =
+            StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)
+                .unwrap();
+        source_metastore
+            .stage_splits(stage_splits_request)
+            .await
+            .unwrap();
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid.clone()),
+            staged_split_ids: vec!["split-1".to_string(), "split-2".to_string()],
+            index_checkpoint_delta_json_opt: Some(
+                serde_json::to_string(&IndexCheckpointDelta::for_test(INGEST_API_SOURCE_ID, 0..42))
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        source_metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+
+        let storage = storage_resolver
+            .resolve(&Uri::for_test(index_uri))
+            .await
+            .unwrap();
+        let payload: Box<dyn PutPayload> = Box::new(vec![0]);
+        storage
+            .put(Path::new("split-1.split"), payload)
+            .await
+            .unwrap();
+
+        let snapshot_report = source_index_service
+            .snapshot_index("test-index")
+            .await
+            .unwrap();
+        assert_eq!(snapshot_report.num_splits, 2);
+
+        let target_metastore = metastore_for_test();
+        let mut target_index_service =
+            IndexService::new(target_metastore.clone(), storage_resolver);
+        let restore_report = target_index_service
+            .restore_index(&snapshot_report.snapshot_uri)
+            .await
+            .unwrap();
+        assert_eq!(restore_report.num_restored_splits, 1);
+        assert_eq!(restore_report.missing_split_ids, ["split-2"]);
+
+        let splits = target_metastore
+            .list_splits(
+                ListSplitsRequest::try_from_index_uid(restore_report.index_uid.clone()).unwrap(),
+            )
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        assert_eq!(splits.len(), 2);
+
+        for split in splits {
+            let expected_split_state = if split.split_id() == "split-1" {
+                SplitState::Published
+            } else {
+                SplitState::MarkedForDeletion
+            };
+            assert_eq!(split.split_state, expected_split_state);
+        }
+        let index_metadata = target_metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(index_metadata.sources.len(), 3);
+        let source_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(INGEST_API_SOURCE_ID)
+            .unwrap();
+        assert_eq!(
+            source_checkpoint.position_for_partition(&PartitionId::default()),
+            Some(&Position::offset(41u64))
+        );
+
+        // Restoring an index that already exists fails.
+        let error = target_index_service
+            .restore_index(&snapshot_report.snapshot_uri)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            IndexServiceError::Metastore(MetastoreError::AlreadyExists(_))
+        ));
+    }
 }
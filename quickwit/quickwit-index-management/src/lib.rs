@@ -19,6 +19,13 @@
 
 mod garbage_collection;
 mod index;
+mod snapshot;
 
 pub use garbage_collection::{run_garbage_collect, GcMetrics};
-pub use index::{clear_cache_directory, validate_storage_uri, IndexService, IndexServiceError};
+pub use index::{
+    clear_cache_directory, validate_storage_uri, ImportPositionsReport, IndexService,
+    IndexServiceError,
+};
+pub use snapshot::{
+    IndexSnapshot, IndexSnapshotReport, RestoreIndexReport, INDEX_SNAPSHOT_FILE_NAME,
+};
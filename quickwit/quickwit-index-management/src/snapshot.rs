@@ -0,0 +1,138 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_common::uri::Uri;
+use quickwit_metastore::{IndexMetadata, SplitMetadata};
+use quickwit_proto::types::{IndexUid, SplitId};
+use serde::{Deserialize, Serialize};
+
+/// Name of the file, written at the root of the index storage, that holds the snapshot of an
+/// index.
+pub const INDEX_SNAPSHOT_FILE_NAME: &str = "index-snapshot.json";
+
+/// Point-in-time copy of the metadata of an index and of the manifests of its published splits.
+///
+/// The source checkpoints carried by the index metadata are consistent with the published splits:
+/// the splits contain exactly the documents up to these checkpoints.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "VersionedIndexSnapshot")]
+#[serde(from = "VersionedIndexSnapshot")]
+pub struct IndexSnapshot {
+    /// Index config, source configs, and source checkpoints.
+    pub index_metadata: IndexMetadata,
+    /// Metadata of the published splits.
+    pub splits: Vec<SplitMetadata>,
+    /// Time at which the snapshot was taken.
+    pub snapshot_timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum VersionedIndexSnapshot {
+    #[serde(rename = "0.9")]
+    V0_9(IndexSnapshotV0_9),
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexSnapshotV0_9 {
+    index_metadata: IndexMetadata,
+    splits: Vec<SplitMetadata>,
+    snapshot_timestamp: i64,
+}
+
+impl From<IndexSnapshot> for VersionedIndexSnapshot {
+    fn from(snapshot: IndexSnapshot) -> Self {
+        VersionedIndexSnapshot::V0_9(IndexSnapshotV0_9 {
+            index_metadata: snapshot.index_metadata,
+            splits: snapshot.splits,
+            snapshot_timestamp: snapshot.snapshot_timestamp,
+        })
+    }
+}
+
+impl From<VersionedIndexSnapshot> for IndexSnapshot {
+    fn from(versioned_snapshot: VersionedIndexSnapshot) -> Self {
+        match versioned_snapshot {
+            VersionedIndexSnapshot::V0_9(v0_9) => IndexSnapshot {
+                index_metadata: v0_9.index_metadata,
+                splits: v0_9.splits,
+                snapshot_timestamp: v0_9.snapshot_timestamp,
+            },
+        }
+    }
+}
+
+/// Outcome of an index snapshot.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IndexSnapshotReport {
+    /// URI of the snapshot file.
+    pub snapshot_uri: Uri,
+    /// Number of published splits recorded in the snapshot.
+    pub num_splits: usize,
+}
+
+/// Outcome of an index restore.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RestoreIndexReport {
+    /// UID of the restored index.
+    pub index_uid: IndexUid,
+    /// Number of splits published.
+    pub num_restored_splits: usize,
+    /// Splits whose file is missing from the index storage. They are marked for deletion instead
+    /// of being published.
+    pub missing_split_ids: Vec<SplitId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{IndexConfig, SourceConfig};
+    use quickwit_metastore::checkpoint::IndexCheckpointDelta;
+
+    use super::*;
+
+    #[test]
+    fn test_index_snapshot_serde() {
+        let index_config = IndexConfig::for_test("test-index", "ram:///indexes/test-index");
+        let mut index_metadata = IndexMetadata::new(index_config);
+        index_metadata
+            .add_source(SourceConfig::ingest_api_default())
+            .unwrap();
+        index_metadata
+            .checkpoint
+            .try_apply_delta(IndexCheckpointDelta::for_test("_ingest-api-source", 0..42))
+            .unwrap();
+        let split_metadata = SplitMetadata {
+            split_id: "test-split".to_string(),
+            index_uid: index_metadata.index_uid.clone(),
+            num_docs: 42,
+            ..Default::default()
+        };
+        let snapshot = IndexSnapshot {
+            index_metadata,
+            splits: vec![split_metadata],
+            snapshot_timestamp: 1_700_000_000,
+        };
+        let snapshot_json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(snapshot_json["version"], "0.9");
+        assert_eq!(snapshot_json["snapshot_timestamp"], 1_700_000_000);
+
+        let deserialized_snapshot: IndexSnapshot = serde_json::from_value(snapshot_json).unwrap();
+        assert_eq!(deserialized_snapshot, snapshot);
+    }
+}
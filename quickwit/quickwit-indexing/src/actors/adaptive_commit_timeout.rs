@@ -0,0 +1,277 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_config::AdaptiveCommitSettings;
+
+/// Weight given to the throughput observed on the last workbench when updating the throughput
+/// estimate. The remaining weight is given to the previous estimate.
+const SMOOTHING_FACTOR: f64 = 0.5;
+
+/// Steers the commit timeout of an indexer toward the value that produces splits of the size
+/// targeted by the [`AdaptiveCommitSettings`].
+///
+/// The throughput of the pipeline is measured on the workbenches emitted by the indexer and
+/// smoothed with an exponential moving average. The effective commit timeout is then the time
+/// required, at that throughput, to accumulate the target number of documents (or bytes),
+/// clamped within the configured bounds.
+///
+/// Throughput is measured on the workbenches rather than taken from `PipelineMetrics` because the
+/// latter are only computed for cooperative indexers, for which the commit timeout also drives
+/// the indexing cycle and is therefore not adjusted.
+pub(crate) struct AdaptiveCommitTimeout {
+    settings: AdaptiveCommitSettings,
+    docs_per_sec_opt: Option<f64>,
+    bytes_per_sec_opt: Option<f64>,
+    effective_commit_timeout: Duration,
+}
+
+impl AdaptiveCommitTimeout {
+    pub fn new(settings: AdaptiveCommitSettings, initial_commit_timeout: Duration) -> Self {
+        let effective_commit_timeout = initial_commit_timeout
+            .clamp(settings.min_commit_timeout(), settings.max_commit_timeout());
+        Self {
+            settings,
+            docs_per_sec_opt: None,
+            bytes_per_sec_opt: None,
+            effective_commit_timeout,
+        }
+    }
+
+    /// Returns the commit timeout to apply to the next workbench.
+    pub fn effective_commit_timeout(&self) -> Duration {
+        self.effective_commit_timeout
+    }
+
+    /// Records the number of documents and bytes indexed in a workbench that was open for
+    /// `elapsed`, and returns the updated effective commit timeout.
+    pub fn record_workbench(
+        &mut self,
+        num_docs: u64,
+        num_bytes: u64,
+        elapsed: Duration,
+    ) -> Duration {
+        let elapsed_secs = elapsed.as_secs_f64();
+
+        // Workbenches committed right after their creation (force commit, tiny heap...) do not
+        // carry any meaningful information about the throughput.
+        if elapsed_secs < 1.0 {
+            return self.effective_commit_timeout;
+        }
+        update_ewma(&mut self.docs_per_sec_opt, num_docs as f64 / elapsed_secs);
+        update_ewma(&mut self.bytes_per_sec_opt, num_bytes as f64 / elapsed_secs);
+
+        let max_commit_timeout = self.settings.max_commit_timeout();
+        let time_to_target_num_docs_opt = self
+            .settings
+            .target_split_num_docs
+            .zip(self.docs_per_sec_opt)
+            .map(|(target, rate)| time_to_target(target as f64, rate, max_commit_timeout));
+        let time_to_target_size_opt = self
+            .settings
+            .target_split_size
+            .zip(self.bytes_per_sec_opt)
+            .map(|(target, rate)| time_to_target(target.as_u64() as f64, rate, max_commit_timeout));
+        let commit_timeout = match (time_to_target_num_docs_opt, time_to_target_size_opt) {
+            (Some(left), Some(right)) => left.min(right),
+            (Some(commit_timeout), None) | (None, Some(commit_timeout)) => commit_timeout,
+            (None, None) => max_commit_timeout,
+        };
+        self.effective_commit_timeout =
+            commit_timeout.clamp(self.settings.min_commit_timeout(), max_commit_timeout);
+        self.effective_commit_timeout
+    }
+}
+
+fn update_ewma(ewma_opt: &mut Option<f64>, value: f64) {
+    let ewma = match *ewma_opt {
+        Some(ewma) => SMOOTHING_FACTOR * value + (1.0 - SMOOTHING_FACTOR) * ewma,
+        None => value,
+    };
+    *ewma_opt = Some(ewma);
+}
+
+fn time_to_target(target: f64, rate: f64, max_commit_timeout: Duration) -> Duration {
+    if rate <= 0.0 {
+        return max_commit_timeout;
+    }
+    let secs = target / rate;
+
+    if secs >= max_commit_timeout.as_secs_f64() {
+        return max_commit_timeout;
+    }
+    Duration::from_secs_f64(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+
+    use super::*;
+
+    fn adaptive_commit_settings(target_split_num_docs: usize) -> AdaptiveCommitSettings {
+        AdaptiveCommitSettings {
+            target_split_num_docs: Some(target_split_num_docs),
+            target_split_size: None,
+            min_commit_timeout_secs: 10,
+            max_commit_timeout_secs: 1800,
+        }
+    }
+
+    /// Simulates a source emitting `docs_per_sec_fn(i)` docs per second during the i-th workbench,
+    /// each workbench being committed on timeout. Returns the number of docs of each split.
+    fn simulate(
+        adaptive_commit_timeout: &mut AdaptiveCommitTimeout,
+        num_workbenches: usize,
+        docs_per_sec_fn: impl Fn(usize) -> f64,
+    ) -> Vec<u64> {
+        let mut split_num_docs = Vec::with_capacity(num_workbenches);
+
+        for i in 0..num_workbenches {
+            let elapsed = adaptive_commit_timeout.effective_commit_timeout();
+            let num_docs = (docs_per_sec_fn(i) * elapsed.as_secs_f64()) as u64;
+            adaptive_commit_timeout.record_workbench(num_docs, num_docs * 100, elapsed);
+            split_num_docs.push(num_docs);
+        }
+        split_num_docs
+    }
+
+    fn assert_within(value: u64, target: u64, tolerance: f64) {
+        let deviation = (value as f64 - target as f64).abs() / target as f64;
+        assert!(
+            deviation <= tolerance,
+            "{value} is not within {tolerance} of {target}"
+        );
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_clamps_initial_timeout() {
+        let settings = adaptive_commit_settings(1_000);
+        let adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(settings.clone(), Duration::from_secs(1));
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(10)
+        );
+        let adaptive_commit_timeout = AdaptiveCommitTimeout::new(settings, Duration::from_secs(60));
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_low_throughput_source() {
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(adaptive_commit_settings(100_000), Duration::from_secs(60));
+        // 100 docs/s: the commit timeout grows from 60s to 1000s.
+        let split_num_docs = simulate(&mut adaptive_commit_timeout, 10, |_| 100.0);
+        assert_eq!(split_num_docs[0], 6_000);
+        assert_within(*split_num_docs.last().unwrap(), 100_000, 0.01);
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(1_000)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_high_throughput_source() {
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(adaptive_commit_settings(100_000), Duration::from_secs(60));
+        // 5k docs/s: the commit timeout shrinks from 60s to 20s.
+        let split_num_docs = simulate(&mut adaptive_commit_timeout, 10, |_| 5_000.0);
+        assert_eq!(split_num_docs[0], 300_000);
+        assert_within(*split_num_docs.last().unwrap(), 100_000, 0.01);
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_fluctuating_throughput_source() {
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(adaptive_commit_settings(100_000), Duration::from_secs(60));
+        // The throughput alternates between 800 and 1,200 docs/s.
+        let split_num_docs = simulate(&mut adaptive_commit_timeout, 50, |i| {
+            if i % 2 == 0 {
+                800.0
+            } else {
+                1_200.0
+            }
+        });
+        // The smoothing lags one workbench behind, so individual splits oscillate around the
+        // target...
+        for num_docs in &split_num_docs[10..] {
+            assert_within(*num_docs, 100_000, 0.3);
+        }
+        // ... but the average split size matches it.
+        let mean_num_docs =
+            split_num_docs[10..].iter().sum::<u64>() / (split_num_docs.len() as u64 - 10);
+        assert_within(mean_num_docs, 100_000, 0.05);
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_respects_bounds() {
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(adaptive_commit_settings(100_000), Duration::from_secs(60));
+        // 10 docs/s would require a 10,000s timeout.
+        simulate(&mut adaptive_commit_timeout, 5, |_| 10.0);
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(1800)
+        );
+        // 1M docs/s would require a 0.1s timeout.
+        simulate(&mut adaptive_commit_timeout, 5, |_| 1_000_000.0);
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(10)
+        );
+        // An idle source pushes the timeout back to the maximum.
+        simulate(&mut adaptive_commit_timeout, 20, |_| 0.0);
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(1800)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_uses_shortest_target() {
+        let settings = AdaptiveCommitSettings {
+            target_split_num_docs: Some(100_000),
+            target_split_size: Some(ByteSize::mb(1)),
+            min_commit_timeout_secs: 10,
+            max_commit_timeout_secs: 1800,
+        };
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(settings, Duration::from_secs(60));
+        // 1,000 docs/s of 100 bytes: 1MB is reached after 10s, way before 100k docs.
+        simulate(&mut adaptive_commit_timeout, 5, |_| 1_000.0);
+        assert_eq!(
+            adaptive_commit_timeout.effective_commit_timeout(),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_commit_timeout_ignores_short_workbenches() {
+        let mut adaptive_commit_timeout =
+            AdaptiveCommitTimeout::new(adaptive_commit_settings(100_000), Duration::from_secs(60));
+        let effective_commit_timeout =
+            adaptive_commit_timeout.record_workbench(1_000_000, 0, Duration::from_millis(10));
+        assert_eq!(effective_commit_timeout, Duration::from_secs(60));
+    }
+}
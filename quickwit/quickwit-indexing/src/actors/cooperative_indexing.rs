@@ -22,8 +22,10 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use once_cell::sync::Lazy;
-use quickwit_proto::indexing::{CpuCapacity, PipelineMetrics, PIPELINE_FULL_CAPACITY};
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use quickwit_proto::indexing::{
+    CpuCapacity, IndexingTaskPriority, PipelineMetrics, PIPELINE_FULL_CAPACITY,
+};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
 use tokio::time::Instant;
 
 /// We allow ourselves to adjust the sleep time by at most `NUDGE_TOLERANCE`
@@ -33,6 +35,99 @@ const NUDGE_TOLERANCE: Duration = Duration::from_secs(5);
 // Origin of time. It is used to compute the phase of the pipeline.
 static ORIGIN_OF_TIME: Lazy<Instant> = Lazy::new(Instant::now);
 
+const NUM_PRIORITIES: usize = IndexingTaskPriority::ALL.len();
+
+/// Indexing permits shared by the pipelines of an indexer, handed out by priority.
+///
+/// A pipeline only starts waiting for a permit once no pipeline with a higher priority is waiting
+/// for one, and hands the permit it was granted over if such a pipeline showed up in the meantime.
+/// As a result, when the indexer is contended, high priority pipelines get the permits first and
+/// background pipelines are throttled until the other pipelines catch up.
+///
+/// Each handle carries the priority of the pipeline it is given to. See
+/// [`IndexingPermits::with_priority`].
+#[derive(Clone)]
+pub struct IndexingPermits {
+    semaphore: Arc<Semaphore>,
+    // Number of pipelines waiting for a permit, per priority rank.
+    num_waiters_tx: Arc<watch::Sender<[usize; NUM_PRIORITIES]>>,
+    priority: IndexingTaskPriority,
+}
+
+impl IndexingPermits {
+    pub fn new(num_permits: usize) -> Self {
+        let (num_waiters_tx, _num_waiters_rx) = watch::channel([0; NUM_PRIORITIES]);
+        Self {
+            semaphore: Arc::new(Semaphore::new(num_permits)),
+            num_waiters_tx: Arc::new(num_waiters_tx),
+            priority: IndexingTaskPriority::Normal,
+        }
+    }
+
+    /// Returns a handle on the same permits for a pipeline with the given priority.
+    pub fn with_priority(&self, priority: IndexingTaskPriority) -> Self {
+        Self {
+            semaphore: self.semaphore.clone(),
+            num_waiters_tx: self.num_waiters_tx.clone(),
+            priority,
+        }
+    }
+
+    pub fn priority(&self) -> IndexingTaskPriority {
+        self.priority
+    }
+
+    async fn acquire(&self) -> OwnedSemaphorePermit {
+        let rank = self.priority.rank();
+        let no_higher_priority_waiters = |num_waiters: &[usize; NUM_PRIORITIES]| {
+            num_waiters[..rank]
+                .iter()
+                .all(|num_waiters| *num_waiters == 0)
+        };
+        let _waiter_guard = WaiterGuard::new(&self.num_waiters_tx, rank);
+        let mut num_waiters_rx = self.num_waiters_tx.subscribe();
+
+        loop {
+            // The sender is owned by `self`, so the channel cannot be closed.
+            let _ = num_waiters_rx.wait_for(no_higher_priority_waiters).await;
+
+            let permit = Semaphore::acquire_owned(self.semaphore.clone())
+                .await
+                .unwrap();
+
+            // A pipeline with a higher priority may have started waiting while we were queued for
+            // the permit: in that case, we release the permit so that it goes to that pipeline.
+            if no_higher_priority_waiters(&num_waiters_rx.borrow()) {
+                return permit;
+            }
+        }
+    }
+}
+
+/// Counts a pipeline as waiting for a permit until the guard is dropped, which also happens if the
+/// acquisition is cancelled.
+struct WaiterGuard<'a> {
+    num_waiters_tx: &'a watch::Sender<[usize; NUM_PRIORITIES]>,
+    rank: usize,
+}
+
+impl<'a> WaiterGuard<'a> {
+    fn new(num_waiters_tx: &'a watch::Sender<[usize; NUM_PRIORITIES]>, rank: usize) -> Self {
+        num_waiters_tx.send_modify(|num_waiters| num_waiters[rank] += 1);
+        Self {
+            num_waiters_tx,
+            rank,
+        }
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        self.num_waiters_tx
+            .send_modify(|num_waiters| num_waiters[self.rank] -= 1);
+    }
+}
+
 /// Cooperative indexing is a mechanism to deal with a large amount of pipelines.
 ///
 /// Instead of having all pipelines index concurrently, cooperative indexing:
@@ -44,7 +139,8 @@ static ORIGIN_OF_TIME: Lazy<Instant> = Lazy::new(Instant::now);
 ///   they don't all use the same resources (disk/cpu/network) at the same time.
 ///
 /// It works by:
-/// - a semaphore is used to restrict the number of pipelines indexing at the same time.
+/// - a semaphore is used to restrict the number of pipelines indexing at the same time. Its permits
+///   are handed out by priority (see [`IndexingPermits`]).
 /// - in the indexer when `on_drain` is called, the indexer will cut a split and "go to sleep" for a
 ///   given amount of time.
 ///
@@ -79,7 +175,7 @@ static ORIGIN_OF_TIME: Lazy<Instant> = Lazy::new(Instant::now);
 pub(crate) struct CooperativeIndexingCycle {
     target_phase: Duration,
     commit_timeout: Duration,
-    indexing_permits: Arc<Semaphore>,
+    indexing_permits: IndexingPermits,
 }
 
 impl CooperativeIndexingCycle {
@@ -88,7 +184,7 @@ impl CooperativeIndexingCycle {
     pub fn new(
         phase_id: &(impl Hash + ?Sized),
         commit_timeout: Duration,
-        indexing_permits: Arc<Semaphore>,
+        indexing_permits: IndexingPermits,
     ) -> CooperativeIndexingCycle {
         assert!(commit_timeout.as_millis() > 0);
         let mut hasher = DefaultHasher::new();
@@ -104,7 +200,7 @@ impl CooperativeIndexingCycle {
     fn new_with_phase(
         target_phase: Duration,
         commit_timeout: Duration,
-        indexing_permits: Arc<Semaphore>,
+        indexing_permits: IndexingPermits,
     ) -> CooperativeIndexingCycle {
         // Force the initial of the origin of time.
         let _t0 = *ORIGIN_OF_TIME;
@@ -133,9 +229,7 @@ impl CooperativeIndexingCycle {
 
     pub async fn cooperative_indexing_period(&self) -> CooperativeIndexingPeriod {
         let t_wake = Instant::now();
-        let permit = Semaphore::acquire_owned(self.indexing_permits.clone())
-            .await
-            .unwrap();
+        let permit = self.indexing_permits.acquire().await;
         let t_work_start = Instant::now();
         CooperativeIndexingPeriod {
             t_wake,
@@ -215,6 +309,10 @@ impl CooperativeIndexingPeriod {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::task::JoinHandle;
+
     use super::*;
 
     #[track_caller]
@@ -252,12 +350,12 @@ mod tests {
         for target_phase_secs in [0, 1, 2, 5, 10, 15, 20, 25, 29, 30, 1_000] {
             for start_time_secs in [0, 1, 2, 5, 10, 15, 20, 25, 29, 30] {
                 let target_phase = Duration::from_secs(target_phase_secs);
-                let semaphore = Arc::new(Semaphore::new(1));
+                let indexing_permits = IndexingPermits::new(1);
                 tokio::time::sleep(Duration::from_secs(start_time_secs)).await;
                 let cooperative_indexing = CooperativeIndexingCycle::new_with_phase(
                     target_phase,
                     Duration::from_secs(30),
-                    semaphore.clone(),
+                    indexing_permits.clone(),
                 );
                 let initial_sleep_duration: Duration =
                     cooperative_indexing.initial_sleep_duration();
@@ -275,9 +373,9 @@ mod tests {
     #[tokio::test]
     async fn test_cooperative_indexing_simple() {
         tokio::time::pause();
-        let semaphore = Arc::new(Semaphore::new(1));
+        let indexing_permits = IndexingPermits::new(1);
         let cooperative_indexing =
-            CooperativeIndexingCycle::new("id", Duration::from_secs(30), semaphore.clone());
+            CooperativeIndexingCycle::new("id", Duration::from_secs(30), indexing_permits.clone());
         let guard = cooperative_indexing.cooperative_indexing_period().await;
         tokio::time::advance(Duration::from_secs(10)).await;
         let (sleep_time, metrics) = guard.end_of_work(100_000_000);
@@ -299,10 +397,10 @@ mod tests {
     #[tokio::test]
     async fn test_cooperative_indexing_maximum_throughput() {
         tokio::time::pause();
-        let semaphore = Arc::new(Semaphore::new(1));
+        let indexing_permits = IndexingPermits::new(1);
         let cooperative_indexing =
-            CooperativeIndexingCycle::new("id", Duration::from_secs(30), semaphore.clone());
-        let semaphore_guard = Semaphore::acquire_owned(semaphore).await;
+            CooperativeIndexingCycle::new("id", Duration::from_secs(30), indexing_permits.clone());
+        let semaphore_guard = Semaphore::acquire_owned(indexing_permits.semaphore.clone()).await;
         drop_after(semaphore_guard, Duration::from_secs(30));
         let cycle_guard = cooperative_indexing.cooperative_indexing_period().await;
         tokio::time::advance(Duration::from_secs(15)).await;
@@ -318,10 +416,10 @@ mod tests {
     #[tokio::test]
     async fn test_cooperative_indexing_simple_contention() {
         tokio::time::pause();
-        let semaphore = Arc::new(Semaphore::new(1));
+        let indexing_permits = IndexingPermits::new(1);
         let cooperative_indexing =
-            CooperativeIndexingCycle::new("id", Duration::from_secs(30), semaphore.clone());
-        let semaphore_guard = Semaphore::acquire_owned(semaphore).await;
+            CooperativeIndexingCycle::new("id", Duration::from_secs(30), indexing_permits.clone());
+        let semaphore_guard = Semaphore::acquire_owned(indexing_permits.semaphore.clone()).await;
         drop_after(semaphore_guard, Duration::from_secs(10));
         let cycle_guard = cooperative_indexing.cooperative_indexing_period().await;
         tokio::time::advance(Duration::from_secs(10)).await;
@@ -340,7 +438,7 @@ mod tests {
         let num_threads = 10;
         let num_pipelines = 100;
         let num_steps = 15;
-        let semaphore = Arc::new(Semaphore::new(num_threads));
+        let indexing_permits = IndexingPermits::new(num_threads);
         let commit_timeout = Duration::from_secs(30);
         let t0 = Instant::now();
         let mut handles = Vec::new();
@@ -350,7 +448,7 @@ mod tests {
             let cooperative_indexing = CooperativeIndexingCycle::new_with_phase(
                 target_phase,
                 commit_timeout,
-                semaphore.clone(),
+                indexing_permits.clone(),
             );
             let join_handle = tokio::task::spawn(async move {
                 let mut last_phase = 0;
@@ -374,4 +472,96 @@ mod tests {
             assert!(phase.abs_diff(expected_phase_millis) < 3);
         }
     }
+
+    fn spawn_pipeline_for_test(
+        indexing_permits: IndexingPermits,
+        work_time: Duration,
+        num_periods: Arc<AtomicUsize>,
+    ) -> JoinHandle<()> {
+        let cooperative_indexing = CooperativeIndexingCycle::new_with_phase(
+            Duration::ZERO,
+            Duration::from_secs(30),
+            indexing_permits,
+        );
+        tokio::task::spawn(async move {
+            loop {
+                let cycle_guard = cooperative_indexing.cooperative_indexing_period().await;
+                tokio::time::sleep(work_time).await;
+                let (sleep_time, _) = cycle_guard.end_of_work(1_000_000);
+                num_periods.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(sleep_time).await;
+            }
+        })
+    }
+
+    // Runs two normal priority pipelines that need more than the single indexing permit along
+    // with a light pipeline with the given priority, and returns the number of indexing periods
+    // completed by the light pipeline.
+    async fn num_light_pipeline_periods_under_contention(
+        light_pipeline_priority: IndexingTaskPriority,
+    ) -> usize {
+        let indexing_permits = IndexingPermits::new(1);
+        let mut join_handles = Vec::new();
+
+        for _ in 0..2 {
+            let join_handle = spawn_pipeline_for_test(
+                indexing_permits.with_priority(IndexingTaskPriority::Normal),
+                Duration::from_secs(20),
+                Arc::default(),
+            );
+            join_handles.push(join_handle);
+        }
+        let num_light_pipeline_periods = Arc::new(AtomicUsize::new(0));
+        let join_handle = spawn_pipeline_for_test(
+            indexing_permits.with_priority(light_pipeline_priority),
+            Duration::from_secs(1),
+            num_light_pipeline_periods.clone(),
+        );
+        join_handles.push(join_handle);
+
+        tokio::time::sleep(Duration::from_secs(300)).await;
+
+        for join_handle in join_handles {
+            join_handle.abort();
+        }
+        num_light_pipeline_periods.load(Ordering::Relaxed)
+    }
+
+    #[tokio::test]
+    async fn test_cooperative_indexing_throttles_background_pipelines() {
+        tokio::time::pause();
+        let num_normal_periods =
+            num_light_pipeline_periods_under_contention(IndexingTaskPriority::Normal).await;
+        assert!(num_normal_periods >= 3, "{num_normal_periods}");
+
+        let num_background_periods =
+            num_light_pipeline_periods_under_contention(IndexingTaskPriority::Background).await;
+        assert!(num_background_periods <= 1, "{num_background_periods}");
+    }
+
+    #[tokio::test]
+    async fn test_indexing_permits_served_by_priority() {
+        let indexing_permits = IndexingPermits::new(1);
+        let permit = indexing_permits
+            .with_priority(IndexingTaskPriority::High)
+            .acquire()
+            .await;
+
+        let background_permits = indexing_permits.with_priority(IndexingTaskPriority::Background);
+        let background_join_handle =
+            tokio::task::spawn(async move { background_permits.acquire().await });
+        tokio::task::yield_now().await;
+
+        let high_permits = indexing_permits.with_priority(IndexingTaskPriority::High);
+        let high_join_handle = tokio::task::spawn(async move { high_permits.acquire().await });
+        tokio::task::yield_now().await;
+
+        drop(permit);
+
+        let high_permit = high_join_handle.await.unwrap();
+        assert!(!background_join_handle.is_finished());
+
+        drop(high_permit);
+        background_join_handle.await.unwrap();
+    }
 }
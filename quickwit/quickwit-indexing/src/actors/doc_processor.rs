@@ -17,9 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::VecDeque;
 use std::string::FromUtf8Error;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
@@ -40,13 +41,15 @@ use serde_json::Value as JsonValue;
 use tantivy::schema::{Field, Value};
 use tantivy::{DateTime, TantivyDocument};
 use thiserror::Error;
+use time::OffsetDateTime;
 use tokio::runtime::Handle;
 
 #[cfg(feature = "vrl")]
 use super::vrl_processing::*;
 use crate::actors::Indexer;
 use crate::models::{
-    NewPublishLock, NewPublishToken, ProcessedDoc, ProcessedDocBatch, PublishLock, RawDocBatch,
+    DocMapperUpdate, NewPublishLock, NewPublishToken, ProcessedDoc, ProcessedDocBatch, PublishLock,
+    RawDocBatch,
 };
 
 const PLAIN_TEXT: &str = "plain_text";
@@ -408,6 +411,75 @@ impl DocProcessorCounters {
     }
 }
 
+/// Controls the sampling of the documents rejected by the [`DocProcessor`].
+#[derive(Clone, Copy, Debug)]
+pub struct DocProcessingErrorSamplingSettings {
+    /// Number of samples kept. The oldest samples are evicted first.
+    pub max_num_samples: usize,
+    /// Maximum number of bytes of the raw document kept in a sample.
+    pub max_raw_doc_num_bytes: usize,
+}
+
+/// A document rejected by the [`DocProcessor`], along with the reason it was rejected.
+#[derive(Clone, Debug, Serialize, utoipa::ToSchema)]
+pub struct DocProcessingErrorSample {
+    /// Unix timestamp, in seconds, at which the document was rejected.
+    pub timestamp: i64,
+    pub reason: String,
+    /// Copy of the raw document, truncated to `max_raw_doc_num_bytes` and decoded lossily as
+    /// UTF-8.
+    pub raw_doc: String,
+    /// Size of the raw document before truncation.
+    pub raw_doc_num_bytes: usize,
+    pub is_truncated: bool,
+}
+
+/// Keeps the last documents rejected by a [`DocProcessor`]. The sampler is shared with the
+/// indexing pipeline, which serves the samples, and is replaced whenever the pipeline respawns.
+#[derive(Debug)]
+pub struct DocProcessingErrorSampler {
+    settings: DocProcessingErrorSamplingSettings,
+    samples: Mutex<VecDeque<DocProcessingErrorSample>>,
+}
+
+impl DocProcessingErrorSampler {
+    pub fn new(settings: DocProcessingErrorSamplingSettings) -> Self {
+        Self {
+            settings,
+            samples: Mutex::new(VecDeque::with_capacity(settings.max_num_samples)),
+        }
+    }
+
+    fn record(&self, error: &DocProcessorError, raw_doc: &[u8]) {
+        if self.settings.max_num_samples == 0 {
+            return;
+        }
+        let raw_doc_num_bytes = raw_doc.len();
+        let is_truncated = raw_doc_num_bytes > self.settings.max_raw_doc_num_bytes;
+        let truncated_raw_doc =
+            &raw_doc[..raw_doc_num_bytes.min(self.settings.max_raw_doc_num_bytes)];
+        let sample = DocProcessingErrorSample {
+            timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            reason: error.to_string(),
+            raw_doc: String::from_utf8_lossy(truncated_raw_doc).into_owned(),
+            raw_doc_num_bytes,
+            is_truncated,
+        };
+        let mut samples = self.samples.lock().expect("lock should not be poisoned");
+
+        if samples.len() >= self.settings.max_num_samples {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Returns the samples, from the oldest to the most recent.
+    pub fn samples(&self) -> Vec<DocProcessingErrorSample> {
+        let samples = self.samples.lock().expect("lock should not be poisoned");
+        samples.iter().cloned().collect()
+    }
+}
+
 pub struct DocProcessor {
     doc_mapper: Arc<dyn DocMapper>,
     indexer_mailbox: Mailbox<Indexer>,
@@ -417,6 +489,7 @@ pub struct DocProcessor {
     #[cfg(feature = "vrl")]
     transform_opt: Option<VrlProgram>,
     input_format: SourceInputFormat,
+    error_sampler_opt: Option<Arc<DocProcessingErrorSampler>>,
 }
 
 impl DocProcessor {
@@ -443,9 +516,16 @@ impl DocProcessor {
                 .map(VrlProgram::try_from_transform_config)
                 .transpose()?,
             input_format,
+            error_sampler_opt: None,
         })
     }
 
+    /// Records the documents rejected by the doc processor into the given sampler.
+    pub fn with_error_sampler(mut self, error_sampler: Arc<DocProcessingErrorSampler>) -> Self {
+        self.error_sampler_opt = Some(error_sampler);
+        self
+    }
+
     // Extract a timestamp from a tantivy document.
     //
     // If the timestamp is set up in the docmapper and the timestamp is missing,
@@ -474,6 +554,9 @@ impl DocProcessor {
         #[cfg(not(feature = "vrl"))]
         let transform_opt: Option<&mut VrlProgram> = None;
 
+        // Cloning `Bytes` only increments a reference count.
+        let raw_doc_for_sampling_opt = self.error_sampler_opt.as_ref().map(|_| raw_doc.clone());
+
         for json_doc_result in parse_raw_doc(self.input_format, raw_doc, num_bytes, transform_opt) {
             let processed_doc_result =
                 json_doc_result.and_then(|json_doc| self.process_json_doc(json_doc));
@@ -490,6 +573,11 @@ impl DocProcessor {
                         source_id = self.counters.source_id,
                         "{error}",
                     );
+                    if let (Some(error_sampler), Some(raw_doc)) =
+                        (&self.error_sampler_opt, &raw_doc_for_sampling_opt)
+                    {
+                        error_sampler.record(&error, raw_doc);
+                    }
                     self.counters.record_error(error, num_bytes as u64);
                 }
             }
@@ -611,6 +699,24 @@ impl Handler<NewPublishLock> for DocProcessor {
     }
 }
 
+#[async_trait]
+impl Handler<DocMapperUpdate> for DocProcessor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: DocMapperUpdate,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.timestamp_field_opt = extract_timestamp_field(&*message.doc_mapper)?;
+        self.doc_mapper = message.doc_mapper.clone();
+        // The batches processed with the previous doc mapper are already in the indexer's queue,
+        // so the indexer receives the update right after them.
+        ctx.send_message(&self.indexer_mailbox, message).await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<NewPublishToken> for DocProcessor {
     type Reply = ();
@@ -631,23 +737,33 @@ mod tests {
 
     use prost::Message;
     use quickwit_actors::Universe;
+    use quickwit_common::temp_dir::TempDirectory;
     use quickwit_common::uri::Uri;
-    use quickwit_config::{build_doc_mapper, SearchSettings};
-    use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
+    use quickwit_config::{build_doc_mapper, IndexingSettings, SearchSettings};
+    use quickwit_doc_mapper::{
+        classify_doc_mapping_update, default_doc_mapper_for_test, DefaultDocMapper, DocMapping,
+    };
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_opentelemetry::otlp::{OtlpGrpcLogsService, OtlpGrpcTracesService};
+    use quickwit_proto::indexing::IndexingPipelineId;
+    use quickwit_proto::metastore::{
+        LastDeleteOpstampResponse, MetastoreServiceClient, MockMetastoreService,
+    };
     use quickwit_proto::opentelemetry::proto::collector::logs::v1::ExportLogsServiceRequest;
     use quickwit_proto::opentelemetry::proto::collector::trace::v1::ExportTraceServiceRequest;
     use quickwit_proto::opentelemetry::proto::common::v1::any_value::Value as OtlpAnyValueValue;
     use quickwit_proto::opentelemetry::proto::common::v1::AnyValue as OtlpAnyValue;
     use quickwit_proto::opentelemetry::proto::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
     use quickwit_proto::opentelemetry::proto::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use quickwit_proto::types::{DocMappingUid, IndexUid, NodeId, PipelineUid};
     use serde_json::Value as JsonValue;
-    use tantivy::schema::NamedFieldDocument;
-    use tantivy::Document;
+    use tantivy::collector::Count;
+    use tantivy::query::TermQuery;
+    use tantivy::schema::{IndexRecordOption, NamedFieldDocument};
+    use tantivy::{Document, Term};
 
     use super::*;
-    use crate::models::{PublishLock, RawDocBatch};
+    use crate::models::{CommitTrigger, IndexedSplitBatchBuilder, PublishLock, RawDocBatch};
 
     #[tokio::test]
     async fn test_doc_processor_simple() {
@@ -727,6 +843,59 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_doc_processor_samples_errors() {
+        let universe = Universe::with_accelerated_time();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let (indexer_mailbox, _indexer_inbox) = universe.create_test_mailbox();
+        let error_sampler = Arc::new(DocProcessingErrorSampler::new(
+            DocProcessingErrorSamplingSettings {
+                max_num_samples: 2,
+                max_raw_doc_num_bytes: 8,
+            },
+        ));
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+        )
+        .unwrap()
+        .with_error_sampler(error_sampler.clone());
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    b"not json",                                      // evicted
+                    br#"{"body": "happy", "timestamp": 1628837062}"#, // ok
+                    b"{",                                             // invalid json
+                    br#"{"body": "missing timestamp"}"#,              // missing timestamp
+                ],
+                0..4,
+            ))
+            .await
+            .unwrap();
+        doc_processor_handle.process_pending_and_observe().await;
+
+        let samples = error_sampler.samples();
+        assert_eq!(samples.len(), 2);
+
+        assert!(samples[0].reason.starts_with("JSON parse error"));
+        assert_eq!(samples[0].raw_doc, "{");
+        assert_eq!(samples[0].raw_doc_num_bytes, 1);
+        assert!(!samples[0].is_truncated);
+
+        assert!(samples[1].reason.starts_with("doc mapper parse error"));
+        assert_eq!(samples[1].raw_doc, r#"{"body":"#);
+        assert_eq!(samples[1].raw_doc_num_bytes, 29);
+        assert!(samples[1].is_truncated);
+
+        universe.assert_quit().await;
+    }
+
     const DOCMAPPER_WITH_PARTITION_JSON: &str = r#"
         {
             "tag_fields": ["tenant"],
@@ -861,6 +1030,153 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_doc_processor_and_indexer_update_doc_mapper_in_place() {
+        let universe = Universe::with_accelerated_time();
+
+        let mut doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "mode": "lenient",
+                "field_mappings": [{"name": "body", "type": "text"}]
+            }"#,
+        )
+        .unwrap();
+        doc_mapping.doc_mapping_uid = DocMappingUid::for_test(1);
+        let doc_mapper = build_doc_mapper(&doc_mapping, &SearchSettings::default()).unwrap();
+
+        let mut new_doc_mapping = doc_mapping.clone();
+        new_doc_mapping.doc_mapping_uid = DocMappingUid::for_test(2);
+        new_doc_mapping
+            .field_mappings
+            .push(serde_json::from_str(r#"{"name": "severity", "type": "text"}"#).unwrap());
+        assert!(classify_doc_mapping_update(&doc_mapping, &new_doc_mapping).is_compatible());
+        let new_doc_mapper =
+            build_doc_mapper(&new_doc_mapping, &SearchSettings::default()).unwrap();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_last_delete_opstamp()
+            .returning(|_request| Ok(LastDeleteOpstampResponse::new(0)));
+        let pipeline_id = IndexingPipelineId {
+            index_uid: IndexUid::for_test("test-index", 0),
+            source_id: "test-source".to_string(),
+            node_id: NodeId::from("test-node"),
+            pipeline_uid: PipelineUid::for_test(0u128),
+        };
+        let (index_serializer_mailbox, index_serializer_inbox) = universe.create_test_mailbox();
+        let indexer = Indexer::new(
+            pipeline_id,
+            doc_mapper.clone(),
+            MetastoreServiceClient::from_mock(mock_metastore),
+            TempDirectory::for_test(),
+            IndexingSettings::for_test(),
+            None,
+            index_serializer_mailbox,
+        );
+        let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
+        let doc_processor = DocProcessor::try_new(
+            "test-index".to_string(),
+            "test-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+
+        // The documents straddle the update: the first one is indexed with the previous doc
+        // mapper, which ignores the `severity` field.
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[br#"{"body": "before", "severity": "info"}"#],
+                0..1,
+            ))
+            .await
+            .unwrap();
+        doc_processor_mailbox
+            .send_message(DocMapperUpdate {
+                doc_mapper: new_doc_mapper,
+                params_fingerprint: 1,
+            })
+            .await
+            .unwrap();
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    br#"{"body": "after", "severity": "info"}"#,
+                    br#"{"body": "after", "severity": "warn"}"#,
+                ],
+                1..3,
+            ))
+            .await
+            .unwrap();
+        universe
+            .send_exit_with_success(&doc_processor_mailbox)
+            .await
+            .unwrap();
+        let (doc_processor_exit_status, _) = doc_processor_handle.join().await;
+        assert!(doc_processor_exit_status.is_success());
+        let (indexer_exit_status, _) = indexer_handle.join().await;
+        assert!(indexer_exit_status.is_success());
+
+        let mut messages_iter = index_serializer_inbox.drain_for_test().into_iter();
+        assert_eq!(messages_iter.len(), 3);
+
+        let split_batch = *messages_iter
+            .next()
+            .unwrap()
+            .downcast::<IndexedSplitBatchBuilder>()
+            .unwrap();
+        assert_eq!(split_batch.commit_trigger, CommitTrigger::DocMapperUpdate);
+        assert_eq!(split_batch.splits.len(), 1);
+        let split = split_batch.splits.into_iter().next().unwrap();
+        assert_eq!(
+            split.split_attrs.doc_mapping_uid,
+            DocMappingUid::for_test(1)
+        );
+        assert_eq!(split.split_attrs.num_docs, 1);
+        let index = split.finalize().unwrap().index;
+        assert!(index.schema().get_field("severity").is_err());
+
+        // The packager receives the update right after the splits created with the previous doc
+        // mapper.
+        let doc_mapper_update = messages_iter
+            .next()
+            .unwrap()
+            .downcast::<DocMapperUpdate>()
+            .unwrap();
+        assert_eq!(
+            doc_mapper_update.doc_mapper.doc_mapping_uid(),
+            DocMappingUid::for_test(2)
+        );
+
+        let split_batch = *messages_iter
+            .next()
+            .unwrap()
+            .downcast::<IndexedSplitBatchBuilder>()
+            .unwrap();
+        assert_eq!(split_batch.commit_trigger, CommitTrigger::NoMoreDocs);
+        assert_eq!(split_batch.splits.len(), 1);
+        let split = split_batch.splits.into_iter().next().unwrap();
+        assert_eq!(
+            split.split_attrs.doc_mapping_uid,
+            DocMappingUid::for_test(2)
+        );
+        assert_eq!(split.split_attrs.num_docs, 2);
+        let index = split.finalize().unwrap().index;
+        let severity_field = index.schema().get_field("severity").unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(severity_field, "info"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&term_query, &Count).unwrap(), 1);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_doc_processor_otlp_logs_json() {
         let root_uri = Uri::for_test("ram:///indexes");
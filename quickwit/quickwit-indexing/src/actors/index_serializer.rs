@@ -25,7 +25,9 @@ use tokio::runtime::Handle;
 use tracing::instrument;
 
 use crate::actors::Packager;
-use crate::models::{EmptySplit, IndexedSplit, IndexedSplitBatch, IndexedSplitBatchBuilder};
+use crate::models::{
+    DocMapperUpdate, EmptySplit, IndexedSplit, IndexedSplitBatch, IndexedSplitBatchBuilder,
+};
 
 /// The index serializer takes a non-serialized split,
 /// and serializes it before passing it to the packager.
@@ -97,6 +99,7 @@ impl Handler<IndexedSplitBatchBuilder> for IndexSerializer {
             publish_token_opt: batch_builder.publish_token_opt,
             merge_task_opt: None,
             batch_parent_span: batch_builder.batch_parent_span,
+            batch_created_at_opt: Some(batch_builder.batch_created_at),
         };
         ctx.send_message(&self.packager_mailbox, indexed_split_batch)
             .await?;
@@ -123,3 +126,17 @@ impl Handler<EmptySplit> for IndexSerializer {
         Ok(())
     }
 }
+
+#[async_trait]
+impl Handler<DocMapperUpdate> for IndexSerializer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: DocMapperUpdate,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        ctx.send_message(&self.packager_mailbox, message).await?;
+        Ok(())
+    }
+}
@@ -21,6 +21,7 @@ use std::collections::hash_map::Entry;
 use std::num::NonZeroU32;
 use std::ops::RangeInclusive;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -49,16 +50,20 @@ use tantivy::schema::Schema;
 use tantivy::store::{Compressor, ZstdCompressor};
 use tantivy::tokenizer::TokenizerManager;
 use tantivy::{DateTime, IndexBuilder, IndexSettings};
+use time::OffsetDateTime;
 use tokio::runtime::Handle;
-use tokio::sync::Semaphore;
+use tokio::time::Instant;
 use tracing::{info, info_span, warn, Span};
 use ulid::Ulid;
 
-use crate::actors::cooperative_indexing::{CooperativeIndexingCycle, CooperativeIndexingPeriod};
+use crate::actors::adaptive_commit_timeout::AdaptiveCommitTimeout;
+use crate::actors::cooperative_indexing::{
+    CooperativeIndexingCycle, CooperativeIndexingPeriod, IndexingPermits,
+};
 use crate::actors::IndexSerializer;
 use crate::models::{
-    CommitTrigger, EmptySplit, IndexedSplitBatchBuilder, IndexedSplitBuilder, NewPublishLock,
-    NewPublishToken, ProcessedDoc, ProcessedDocBatch, PublishLock,
+    CommitTrigger, DocMapperUpdate, EmptySplit, IndexedSplitBatchBuilder, IndexedSplitBuilder,
+    NewPublishLock, NewPublishToken, ProcessedDoc, ProcessedDocBatch, PublishLock,
 };
 
 // Random partition ID used to gather partitions exceeding the maximum number of partitions.
@@ -88,6 +93,10 @@ pub struct IndexerCounters {
     /// Metrics describing the load and indexing performance of the
     /// pipeline. This is only updated for cooperative indexers.
     pub pipeline_metrics_opt: Option<PipelineMetrics>,
+
+    /// Commit timeout applied to the current workbench when the adaptive commit timeout is
+    /// enabled.
+    pub effective_commit_timeout_secs_opt: Option<u64>,
 }
 
 struct IndexerState {
@@ -97,15 +106,27 @@ struct IndexerState {
     indexing_settings: IndexingSettings,
     publish_lock: PublishLock,
     publish_token_opt: Option<PublishToken>,
+    // Incarnation of the source, stamped on the checkpoint deltas so that the metastore discards
+    // them if the source was deleted and re-created since the pipeline was spawned.
+    source_incarnation_opt: Option<Ulid>,
     schema: Schema,
     doc_mapping_uid: DocMappingUid,
     tokenizer_manager: TokenizerManager,
     max_num_partitions: NonZeroU32,
     index_settings: IndexSettings,
     cooperative_indexing_opt: Option<CooperativeIndexingCycle>,
+    adaptive_commit_timeout_opt: Option<AdaptiveCommitTimeout>,
 }
 
 impl IndexerState {
+    fn commit_timeout(&self) -> Duration {
+        if let Some(adaptive_commit_timeout) = &self.adaptive_commit_timeout_opt {
+            adaptive_commit_timeout.effective_commit_timeout()
+        } else {
+            self.indexing_settings.commit_timeout()
+        }
+    }
+
     fn create_indexed_split_builder(
         &self,
         partition_id: u64,
@@ -220,6 +241,7 @@ impl IndexerState {
         let checkpoint_delta = IndexCheckpointDelta {
             source_id: self.pipeline_id.source_id.clone(),
             source_delta: SourceCheckpointDelta::default(),
+            source_incarnation_opt: self.source_incarnation_opt,
         };
         let publish_lock = self.publish_lock.clone();
         let publish_token_opt = self.publish_token_opt.clone();
@@ -245,6 +267,7 @@ impl IndexerState {
             ),
             cooperative_indexing_period,
             split_builders_guard,
+            created_at: Instant::now(),
         };
         Ok(workbench)
     }
@@ -263,10 +286,7 @@ impl IndexerState {
             let commit_timeout_message = CommitTimeout {
                 workbench_id: indexing_workbench.workbench_id,
             };
-            ctx.schedule_self_msg(
-                self.indexing_settings.commit_timeout(),
-                commit_timeout_message,
-            );
+            ctx.schedule_self_msg(self.commit_timeout(), commit_timeout_message);
             *indexing_workbench_opt = Some(indexing_workbench);
         }
         let current_indexing_workbench = indexing_workbench_opt.as_mut().context(
@@ -366,6 +386,7 @@ struct IndexingWorkbench {
     memory_usage: GaugeGuard<'static>,
     split_builders_guard: GaugeGuard<'static>,
     cooperative_indexing_period: Option<CooperativeIndexingPeriod>,
+    created_at: Instant,
 }
 
 pub struct Indexer {
@@ -522,6 +543,44 @@ impl Handler<NewPublishLock> for Indexer {
     }
 }
 
+#[async_trait]
+impl Handler<DocMapperUpdate> for Indexer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: DocMapperUpdate,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        // The documents of the current workbench were mapped with the previous doc mapper, so we
+        // commit them before creating splits with the new schema.
+        self.send_to_serializer(CommitTrigger::DocMapperUpdate, ctx)
+            .await?;
+
+        let doc_mapper = &message.doc_mapper;
+        info!(
+            index=%self.indexer_state.pipeline_id.index_uid,
+            source=self.indexer_state.pipeline_id.source_id.as_str(),
+            pipeline_uid=%self.indexer_state.pipeline_id.pipeline_uid,
+            previous_doc_mapping_uid=%self.indexer_state.doc_mapping_uid,
+            doc_mapping_uid=%doc_mapper.doc_mapping_uid(),
+            "update-doc-mapper"
+        );
+        self.indexer_state.schema = doc_mapper.schema();
+        self.indexer_state.doc_mapping_uid = doc_mapper.doc_mapping_uid();
+        self.indexer_state.tokenizer_manager =
+            doc_mapper.tokenizer_manager().tantivy_manager().clone();
+        self.indexer_state.max_num_partitions = doc_mapper.max_num_partitions();
+        // The packager extracts the tags and builds the term presence filters of the splits using
+        // the field IDs of the schema, which may be shifted by the new fields. The splits created
+        // with the previous schema are already in the serializer's queue, so the packager receives
+        // the update right after them.
+        ctx.send_message(&self.index_serializer_mailbox, message)
+            .await?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<NewPublishToken> for Indexer {
     type Reply = ();
@@ -544,7 +603,7 @@ impl Indexer {
         metastore: MetastoreServiceClient,
         indexing_directory: TempDirectory,
         indexing_settings: IndexingSettings,
-        cooperative_indexing_permits_opt: Option<Arc<Semaphore>>,
+        cooperative_indexing_permits_opt: Option<IndexingPermits>,
         index_serializer_mailbox: Mailbox<IndexSerializer>,
     ) -> Self {
         let schema = doc_mapper.schema();
@@ -565,6 +624,30 @@ impl Indexer {
                     cooperative_indexing_permits,
                 )
             });
+        // The commit timeout of cooperative indexers sets the period of their indexing cycle, so
+        // it cannot be adjusted on the fly.
+        let adaptive_commit_timeout_opt: Option<AdaptiveCommitTimeout> =
+            if cooperative_indexing_opt.is_none() {
+                indexing_settings
+                    .adaptive_commit
+                    .clone()
+                    .map(|adaptive_commit_settings| {
+                        AdaptiveCommitTimeout::new(
+                            adaptive_commit_settings,
+                            indexing_settings.commit_timeout(),
+                        )
+                    })
+            } else {
+                None
+            };
+        let counters = IndexerCounters {
+            effective_commit_timeout_secs_opt: adaptive_commit_timeout_opt.as_ref().map(
+                |adaptive_commit_timeout| {
+                    adaptive_commit_timeout.effective_commit_timeout().as_secs()
+                },
+            ),
+            ..Default::default()
+        };
         Self {
             indexer_state: IndexerState {
                 pipeline_id,
@@ -573,19 +656,27 @@ impl Indexer {
                 indexing_settings,
                 publish_lock: PublishLock::default(),
                 publish_token_opt: None,
+                source_incarnation_opt: None,
                 schema,
                 doc_mapping_uid: doc_mapper.doc_mapping_uid(),
                 tokenizer_manager: tokenizer_manager.tantivy_manager().clone(),
                 index_settings,
                 max_num_partitions: doc_mapper.max_num_partitions(),
                 cooperative_indexing_opt,
+                adaptive_commit_timeout_opt,
             },
             index_serializer_mailbox,
             indexing_workbench_opt: None,
-            counters: IndexerCounters::default(),
+            counters,
         }
     }
 
+    /// Stamps the checkpoint deltas of the indexed splits with the given source incarnation.
+    pub fn with_source_incarnation(mut self, source_incarnation_opt: Option<Ulid>) -> Self {
+        self.indexer_state.source_incarnation_opt = source_incarnation_opt;
+        self
+    }
+
     fn memory_usage(&self) -> ByteSize {
         if let Some(workbench) = &self.indexing_workbench_opt {
             ByteSize(workbench.memory_usage.get() as u64)
@@ -643,6 +734,7 @@ impl Indexer {
             batch_parent_span,
             memory_usage,
             split_builders_guard,
+            created_at,
             ..
         }) = self.indexing_workbench_opt.take()
         else {
@@ -675,6 +767,31 @@ impl Indexer {
         }
         let num_splits = splits.len() as u64;
         let split_ids = splits.iter().map(|split| split.split_id()).join(",");
+
+        if let Some(adaptive_commit_timeout) = &mut self.indexer_state.adaptive_commit_timeout_opt {
+            let num_bytes: u64 = splits
+                .iter()
+                .map(|split| split.split_attrs.uncompressed_docs_size_in_bytes)
+                .sum();
+            let previous_commit_timeout = adaptive_commit_timeout.effective_commit_timeout();
+            let effective_commit_timeout = adaptive_commit_timeout.record_workbench(
+                self.counters.num_docs_in_workbench,
+                num_bytes,
+                created_at.elapsed(),
+            );
+            if effective_commit_timeout.as_secs() != previous_commit_timeout.as_secs() {
+                info!(
+                    index=%self.indexer_state.pipeline_id.index_uid,
+                    source=self.indexer_state.pipeline_id.source_id.as_str(),
+                    pipeline_uid=%self.indexer_state.pipeline_id.pipeline_uid,
+                    previous_commit_timeout_secs=previous_commit_timeout.as_secs(),
+                    effective_commit_timeout_secs=effective_commit_timeout.as_secs(),
+                    "adjust-commit-timeout"
+                );
+            }
+            self.counters.effective_commit_timeout_secs_opt =
+                Some(effective_commit_timeout.as_secs());
+        }
         info!(
             index=%self.indexer_state.pipeline_id.index_uid,
             source=self.indexer_state.pipeline_id.source_id.as_str(),
@@ -692,6 +809,7 @@ impl Indexer {
                 publish_token_opt,
                 commit_trigger,
                 batch_parent_span,
+                batch_created_at: OffsetDateTime::now_utc() - created_at.elapsed(),
                 memory_usage,
                 _split_builders_guard: split_builders_guard,
             },
@@ -712,6 +830,7 @@ mod tests {
     use std::time::Duration;
 
     use quickwit_actors::Universe;
+    use quickwit_config::AdaptiveCommitSettings;
     use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_proto::metastore::{
@@ -867,6 +986,7 @@ mod tests {
                 num_docs_in_workbench: 1, //< the num docs in split counter has been reset.
                 num_doc_batches_in_workbench: 1, //< the num docs in split counter has been reset.
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
         let messages: Vec<IndexedSplitBatchBuilder> = index_serializer_inbox.drain_for_test_typed();
@@ -1050,6 +1170,78 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_indexer_applies_adaptive_commit_timeout() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: IndexUid::new_with_random_ulid("test-index"),
+            source_id: "test-source".to_string(),
+            node_id: NodeId::from("test-node"),
+            pipeline_uid: PipelineUid::default(),
+        };
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let schema = doc_mapper.schema();
+        let body_field = schema.get_field("body").unwrap();
+        let indexing_directory = TempDirectory::for_test();
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.commit_timeout_secs = 60;
+        indexing_settings.adaptive_commit = Some(AdaptiveCommitSettings {
+            target_split_num_docs: Some(1_000_000),
+            target_split_size: None,
+            min_commit_timeout_secs: 120,
+            max_commit_timeout_secs: 600,
+        });
+        let (index_serializer_mailbox, index_serializer_inbox) = universe.create_test_mailbox();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_last_delete_opstamp()
+            .returning(move |_last_delete_opstamp_request| Ok(LastDeleteOpstampResponse::new(10)));
+        let indexer = Indexer::new(
+            pipeline_id,
+            doc_mapper,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            index_serializer_mailbox,
+        );
+        let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
+        indexer_mailbox
+            .send_message(ProcessedDocBatch::new(
+                vec![ProcessedDoc {
+                    doc: doc!(body_field=>"this is a test document"),
+                    timestamp_opt: None,
+                    partition: 1,
+                    num_bytes: 30,
+                }],
+                SourceCheckpointDelta::from_range(0..1),
+                false,
+            ))
+            .await?;
+        // The configured commit timeout is raised to the adaptive commit timeout lower bound.
+        universe.sleep(Duration::from_secs(62)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_splits_emitted, 0);
+        assert_eq!(
+            indexer_counters.effective_commit_timeout_secs_opt,
+            Some(120)
+        );
+
+        universe.sleep(Duration::from_secs(60)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_splits_emitted, 1);
+
+        let indexed_serializer_messages: Vec<IndexedSplitBatchBuilder> =
+            index_serializer_inbox.drain_for_test_typed();
+        assert_eq!(indexed_serializer_messages.len(), 1);
+        assert_eq!(
+            indexed_serializer_messages[0].commit_trigger,
+            CommitTrigger::Timeout
+        );
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_indexer_triggers_commit_on_drained_mailbox() -> anyhow::Result<()> {
         let universe = Universe::new();
@@ -1080,7 +1272,7 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             indexing_directory,
             indexing_settings,
-            Some(Arc::new(Semaphore::new(1))),
+            Some(IndexingPermits::new(1)),
             index_serializer_mailbox,
         );
         let (indexer_mailbox, indexer_handle) = universe.spawn_builder().spawn(indexer);
@@ -1124,6 +1316,7 @@ mod tests {
                 num_docs_in_workbench: 0,
                 num_doc_batches_in_workbench: 0,
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
         let indexed_split_batches: Vec<IndexedSplitBatchBuilder> =
@@ -1198,6 +1391,7 @@ mod tests {
                 num_docs_in_workbench: 0,
                 num_doc_batches_in_workbench: 0,
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
         let output_messages: Vec<IndexedSplitBatchBuilder> =
@@ -1291,6 +1485,7 @@ mod tests {
                 num_splits_emitted: 0,
                 num_split_batches_emitted: 0,
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
         universe.send_exit_with_success(&indexer_mailbox).await?;
@@ -1304,6 +1499,7 @@ mod tests {
                 num_splits_emitted: 2,
                 num_split_batches_emitted: 1,
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
         let split_batches: Vec<IndexedSplitBatchBuilder> =
@@ -1652,6 +1848,7 @@ mod tests {
                 num_docs_in_workbench: 0, //< the num docs in split counter has been reset.
                 num_doc_batches_in_workbench: 2, //< the num docs in split counter has been reset.
                 pipeline_metrics_opt: None,
+                effective_commit_timeout_secs_opt: None,
             }
         );
 
@@ -17,40 +17,51 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use bytesize::ByteSize;
 use quickwit_actors::{
-    Actor, ActorContext, ActorExitStatus, ActorHandle, Handler, Health, Mailbox, QueueCapacity,
-    Supervisable, HEARTBEAT,
+    Actor, ActorContext, ActorExitStatus, ActorHandle, Handler, Health, Inbox, Mailbox,
+    QueueCapacity, Supervisable, HEARTBEAT,
 };
+use quickwit_common::metrics::index_label;
 use quickwit_common::pubsub::EventBroker;
+use quickwit_common::retry::RetryParams;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_common::KillSwitch;
 use quickwit_config::{IndexingSettings, SourceConfig};
 use quickwit_doc_mapper::DocMapper;
 use quickwit_ingest::IngesterPool;
-use quickwit_proto::indexing::IndexingPipelineId;
+use quickwit_proto::indexing::{IndexingPipelineId, IndexingTaskPriority, PipelineHealth};
 use quickwit_proto::metastore::{MetastoreError, MetastoreServiceClient};
 use quickwit_proto::types::ShardId;
 use quickwit_storage::{Storage, StorageResolver};
 use tokio::sync::Semaphore;
 use tracing::{debug, error, info, instrument};
+use ulid::Ulid;
 
 use super::MergePlanner;
-use crate::actors::doc_processor::DocProcessor;
+use crate::actors::doc_processor::{
+    DocProcessingErrorSample, DocProcessingErrorSampler, DocProcessingErrorSamplingSettings,
+    DocProcessor,
+};
 use crate::actors::index_serializer::IndexSerializer;
 use crate::actors::publisher::PublisherType;
 use crate::actors::sequencer::Sequencer;
 use crate::actors::uploader::UploaderType;
-use crate::actors::{Indexer, Packager, Publisher, Uploader};
+use crate::actors::{Indexer, IndexingPermits, Packager, Publisher, Uploader};
 use crate::merge_policy::MergePolicy;
-use crate::models::IndexingStatistics;
+use crate::models::{
+    DocMapperReloaded, DocMapperUpdate, GetDocProcessingErrorSamples, IndexingStatistics,
+    PausePipeline, PromotePipeline, ResumePipeline,
+};
 use crate::source::{
-    quickwit_supported_sources, AssignShards, Assignment, SourceActor, SourceRuntime,
+    check_source_connectivity, quickwit_supported_sources, AssignShards, Assignment, SourceActor,
+    SourceRuntime,
 };
 use crate::split_store::IndexingSplitStore;
 use crate::SplitsUpdateMailbox;
@@ -59,6 +70,13 @@ const SUPERVISE_INTERVAL: Duration = Duration::from_secs(1);
 
 const MAX_RETRY_DELAY: Duration = Duration::from_secs(600); // 10 min.
 
+/// Upload backpressure ratio above which a running pipeline is reported as backpressured.
+const BACKPRESSURED_UPLOAD_RATIO: f32 = 0.5;
+
+/// A pipeline that failed `restart_params.max_attempts` times within this window is no longer
+/// restarted.
+const RECENT_FAILURES_WINDOW: Duration = Duration::from_secs(30 * 60);
+
 #[derive(Debug)]
 struct SuperviseLoop;
 
@@ -84,7 +102,11 @@ static SPAWN_PIPELINE_SEMAPHORE: Semaphore = Semaphore::const_new(10);
 
 struct IndexingPipelineHandles {
     source_mailbox: Mailbox<SourceActor>,
-    source_handle: ActorHandle<SourceActor>,
+    // `None` while the pipeline is a warm standby: the source actor is only spawned on promotion.
+    source_handle_opt: Option<ActorHandle<SourceActor>>,
+    // Inbox of the source actor and mailbox of the doc processor, set aside until the standby
+    // pipeline is promoted.
+    standby_source_opt: Option<(Inbox<SourceActor>, Mailbox<DocProcessor>)>,
     doc_processor: ActorHandle<DocProcessor>,
     indexer: ActorHandle<Indexer>,
     index_serializer: ActorHandle<IndexSerializer>,
@@ -113,6 +135,11 @@ pub struct Spawn {
     retry_count: usize,
 }
 
+/// Restarts a pipeline that failed too many times and is no longer restarted automatically. Does
+/// nothing if the pipeline is not in the failed state.
+#[derive(Clone, Copy, Debug)]
+pub struct RestartFailedPipeline;
+
 pub struct IndexingPipeline {
     params: IndexingPipelineParams,
     previous_generations_statistics: IndexingStatistics,
@@ -125,6 +152,26 @@ pub struct IndexingPipeline {
     // requiring a respawn of the pipeline.
     // We keep the list of shards here however, to reassign them after a respawn.
     shard_ids: BTreeSet<ShardId>,
+
+    // Whether the source of the pipeline should be paused. Like the set of shards, this survives
+    // respawns.
+    is_paused: bool,
+
+    // Time of the last update of the upload backpressure ratio and total upload queue blocked time
+    // at that moment.
+    upload_backpressure_checkpoint: (Instant, u64),
+    upload_backpressure_ratio: f32,
+
+    // Instants of the recent failures of the pipeline, whether the actors of a running
+    // generation failed or spawning a new generation failed.
+    recent_failures: VecDeque<Instant>,
+    // Set once the pipeline failed too many times. The pipeline is then no longer restarted until
+    // it receives a `RestartFailedPipeline` or a `ResumePipeline` message.
+    is_failed: bool,
+
+    // Documents rejected by the doc processor of the current generation. A new sampler is created
+    // on every respawn.
+    doc_processing_error_sampler_opt: Option<Arc<DocProcessingErrorSampler>>,
 }
 
 #[async_trait]
@@ -160,6 +207,8 @@ impl Actor for IndexingPipeline {
 impl IndexingPipeline {
     pub fn new(params: IndexingPipelineParams) -> Self {
         let params_fingerprint = params.params_fingerprint;
+        let is_standby = params.is_standby;
+        let priority = params.priority;
         IndexingPipeline {
             params,
             previous_generations_statistics: Default::default(),
@@ -167,16 +216,23 @@ impl IndexingPipeline {
             kill_switch: KillSwitch::default(),
             statistics: IndexingStatistics {
                 params_fingerprint,
+                is_standby,
+                priority,
                 ..Default::default()
             },
             shard_ids: Default::default(),
+            is_paused: false,
+            upload_backpressure_checkpoint: (Instant::now(), 0),
+            upload_backpressure_ratio: 0.0,
+            recent_failures: VecDeque::new(),
+            is_failed: false,
+            doc_processing_error_sampler_opt: None,
         }
     }
 
     fn supervisables(&self) -> Vec<&dyn Supervisable> {
         if let Some(handles) = &self.handles_opt {
-            let supervisables: Vec<&dyn Supervisable> = vec![
-                &handles.source_handle,
+            let mut supervisables: Vec<&dyn Supervisable> = vec![
                 &handles.doc_processor,
                 &handles.indexer,
                 &handles.index_serializer,
@@ -185,6 +241,9 @@ impl IndexingPipeline {
                 &handles.sequencer,
                 &handles.publisher,
             ];
+            if let Some(source_handle) = &handles.source_handle_opt {
+                supervisables.insert(0, source_handle);
+            }
             supervisables
         } else {
             Vec::new()
@@ -248,8 +307,89 @@ impl IndexingPipeline {
         self.statistics.generation
     }
 
+    /// Records a failure of the pipeline and returns the number of failures within
+    /// `RECENT_FAILURES_WINDOW`.
+    fn record_failure(&mut self) -> usize {
+        let now = Instant::now();
+        self.recent_failures.push_back(now);
+        while let Some(failure_instant) = self.recent_failures.front() {
+            if now.duration_since(*failure_instant) <= RECENT_FAILURES_WINDOW {
+                break;
+            }
+            self.recent_failures.pop_front();
+        }
+        self.recent_failures.len()
+    }
+
+    /// Schedules the respawn of the pipeline after a failure, with an exponential backoff. If the
+    /// pipeline failed too many times recently, it enters the failed state instead.
+    fn handle_failure(&mut self, retry_count: usize, ctx: &ActorContext<Self>) {
+        let num_recent_failures = self.record_failure();
+        let restart_params = self.params.restart_params;
+
+        if num_recent_failures >= restart_params.max_attempts {
+            error!(
+                num_recent_failures,
+                "indexing pipeline failed too many times, it will not be restarted until a new \
+                 indexing plan is applied or it is resumed"
+            );
+            self.is_failed = true;
+        } else {
+            let restart_delay = restart_params.compute_delay(num_recent_failures);
+            info!(num_recent_failures, restart_delay=?restart_delay, "restarting indexing pipeline after some time");
+            crate::metrics::INDEXER_METRICS
+                .pipeline_restarts_total
+                .with_label_values([
+                    index_label(&self.params.pipeline_id.index_uid.index_id),
+                    &self.params.pipeline_id.source_id,
+                ])
+                .inc();
+            ctx.schedule_self_msg(restart_delay, Spawn { retry_count });
+        }
+        self.perform_observe(ctx);
+    }
+
+    /// Takes the pipeline out of the failed state and respawns it.
+    async fn restart_failed_pipeline(
+        &mut self,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if !self.is_failed {
+            return Ok(());
+        }
+        info!("restarting failed indexing pipeline");
+        self.is_failed = false;
+        self.recent_failures.clear();
+        self.handle(Spawn::default(), ctx).await?;
+        self.perform_observe(ctx);
+        Ok(())
+    }
+
+    /// Computes the health of the pipeline from the state of its actors and its recent failures.
+    fn health(&self) -> PipelineHealth {
+        if self.is_failed {
+            return PipelineHealth::Failed;
+        }
+        if self.handles_opt.is_none() {
+            return PipelineHealth::Retrying;
+        }
+        if self.upload_backpressure_ratio >= BACKPRESSURED_UPLOAD_RATIO {
+            return PipelineHealth::Backpressured;
+        }
+        PipelineHealth::Running
+    }
+
     fn perform_observe(&mut self, ctx: &ActorContext<Self>) {
         let Some(handles) = &self.handles_opt else {
+            // The pipeline is not running: only its health, spawn attempts, and parameters can
+            // change.
+            self.statistics.num_spawn_attempts =
+                self.previous_generations_statistics.num_spawn_attempts;
+            self.statistics.params_fingerprint = self.params.params_fingerprint;
+            self.statistics.is_standby = self.params.is_standby;
+            self.statistics.priority = self.params.priority;
+            self.statistics.health = self.health();
+            ctx.observe(self);
             return;
         };
         handles.doc_processor.refresh_observe();
@@ -271,9 +411,30 @@ impl IndexingPipeline {
         self.statistics.pipeline_metrics_opt = pipeline_metrics_opt;
         self.statistics.params_fingerprint = self.params.params_fingerprint;
         self.statistics.shard_ids.clone_from(&self.shard_ids);
+        self.statistics.is_paused = self.is_paused;
+        self.statistics.is_standby = self.params.is_standby;
+        self.statistics.priority = self.params.priority;
+        self.update_upload_backpressure_ratio();
+        self.statistics.upload_backpressure_ratio = self.upload_backpressure_ratio;
+        self.statistics.health = self.health();
         ctx.observe(self);
     }
 
+    fn update_upload_backpressure_ratio(&mut self) {
+        let (checkpoint_instant, checkpoint_blocked_micros) = self.upload_backpressure_checkpoint;
+        let elapsed = checkpoint_instant.elapsed();
+        // Observations can happen in quick succession, for instance when the pipeline is paused.
+        // Computing the ratio over such short periods would be meaningless.
+        if elapsed < SUPERVISE_INTERVAL {
+            return;
+        }
+        let upload_queue_blocked_micros = self.statistics.upload_queue_blocked_micros;
+        let blocked_micros = upload_queue_blocked_micros.saturating_sub(checkpoint_blocked_micros);
+        self.upload_backpressure_ratio =
+            (blocked_micros as f32 / elapsed.as_micros() as f32).min(1.0);
+        self.upload_backpressure_checkpoint = (Instant::now(), upload_queue_blocked_micros);
+    }
+
     /// Checks if some actors have terminated.
     async fn perform_health_check(
         &mut self,
@@ -292,8 +453,7 @@ impl IndexingPipeline {
             Health::Healthy => {}
             Health::FailureOrUnhealthy => {
                 self.terminate().await;
-                let first_retry_delay = wait_duration_before_retry(0);
-                ctx.schedule_self_msg(first_retry_delay, Spawn { retry_count: 0 });
+                self.handle_failure(0, ctx);
             }
             Health::Success => {
                 return Err(ActorExitStatus::Success);
@@ -340,7 +500,9 @@ impl IndexingPipeline {
             self.params.metastore.clone(),
             Some(self.params.merge_planner_mailbox.clone()),
             Some(source_mailbox.clone()),
-        );
+        )
+        .with_pipeline_uid(self.params.pipeline_id.pipeline_uid)
+        .with_event_broker(self.params.event_broker.clone());
         let (publisher_mailbox, publisher_handle) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -370,6 +532,7 @@ impl IndexingPipeline {
             self.params.split_store.clone(),
             SplitsUpdateMailbox::Sequencer(sequencer_mailbox),
             self.params.max_concurrent_split_uploads_index,
+            Some(self.params.split_upload_queue_max_num_bytes),
             self.params.event_broker.clone(),
         );
         let (uploader_mailbox, uploader_handle) = ctx
@@ -384,7 +547,13 @@ impl IndexingPipeline {
 
         // Packager
         let tag_fields = self.params.doc_mapper.tag_named_fields()?;
-        let packager = Packager::new("Packager", tag_fields, uploader_mailbox);
+        let term_presence_fields = self.params.doc_mapper.term_presence_named_fields()?;
+        let packager = Packager::new(
+            "Packager",
+            tag_fields,
+            term_presence_fields,
+            uploader_mailbox,
+        );
         let (packager_mailbox, packager_handle) = ctx
             .spawn_actor()
             .set_kill_switch(self.kill_switch.clone())
@@ -404,9 +573,13 @@ impl IndexingPipeline {
             self.params.metastore.clone(),
             self.params.indexing_directory.clone(),
             self.params.indexing_settings.clone(),
-            self.params.cooperative_indexing_permits.clone(),
+            self.params
+                .cooperative_indexing_permits
+                .as_ref()
+                .map(|permits| permits.with_priority(self.params.priority)),
             index_serializer_mailbox,
-        );
+        )
+        .with_source_incarnation(self.params.source_incarnation_opt);
         let (indexer_mailbox, indexer_handle) = ctx
             .spawn_actor()
             .set_backpressure_micros_counter(
@@ -417,7 +590,7 @@ impl IndexingPipeline {
             .set_kill_switch(self.kill_switch.clone())
             .spawn(indexer);
 
-        let doc_processor = DocProcessor::try_new(
+        let mut doc_processor = DocProcessor::try_new(
             index_id.to_string(),
             source_id.to_string(),
             self.params.doc_mapper.clone(),
@@ -425,6 +598,14 @@ impl IndexingPipeline {
             self.params.source_config.transform_config.clone(),
             self.params.source_config.input_format,
         )?;
+        self.doc_processing_error_sampler_opt = self
+            .params
+            .doc_processing_error_sampling_opt
+            .map(|settings| Arc::new(DocProcessingErrorSampler::new(settings)));
+
+        if let Some(error_sampler) = &self.doc_processing_error_sampler_opt {
+            doc_processor = doc_processor.with_error_sampler(error_sampler.clone());
+        }
         let (doc_processor_mailbox, doc_processor_handle) = ctx
             .spawn_actor()
             .set_backpressure_micros_counter(
@@ -434,6 +615,57 @@ impl IndexingPipeline {
             )
             .set_kill_switch(self.kill_switch.clone())
             .spawn(doc_processor);
+        let (source_handle_opt, standby_source_opt) = if self.params.is_standby {
+            // A standby pipeline does not consume its source, so that it does not compete with the
+            // primary pipeline, e.g. by joining its Kafka consumer group. We only make sure that
+            // the source is reachable.
+            ctx.protect_future(check_source_connectivity(
+                &self.params.source_storage_resolver,
+                &self.params.source_config,
+            ))
+            .await?;
+            info!("indexing pipeline spawned as warm standby");
+            (None, Some((source_inbox, doc_processor_mailbox)))
+        } else {
+            let source_handle = self
+                .spawn_source(
+                    source_mailbox.clone(),
+                    source_inbox,
+                    doc_processor_mailbox,
+                    ctx,
+                )
+                .await?;
+            (Some(source_handle), None)
+        };
+
+        // Increment generation once we are sure there will be no spawning error.
+        self.previous_generations_statistics = self.statistics.clone();
+        self.statistics.generation += 1;
+        self.handles_opt = Some(IndexingPipelineHandles {
+            source_mailbox,
+            source_handle_opt,
+            standby_source_opt,
+            doc_processor: doc_processor_handle,
+            indexer: indexer_handle,
+            index_serializer: index_serializer_handle,
+            packager: packager_handle,
+            uploader: uploader_handle,
+            sequencer: sequencer_handle,
+            publisher: publisher_handle,
+            next_check_for_progress: Instant::now() + *HEARTBEAT,
+        });
+        Ok(())
+    }
+
+    /// Loads the source of the pipeline, starting from the latest published checkpoint, and spawns
+    /// its actor.
+    async fn spawn_source(
+        &self,
+        source_mailbox: Mailbox<SourceActor>,
+        source_inbox: Inbox<SourceActor>,
+        doc_processor_mailbox: Mailbox<DocProcessor>,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<ActorHandle<SourceActor>> {
         let source_runtime = SourceRuntime {
             pipeline_id: self.params.pipeline_id.clone(),
             source_config: self.params.source_config.clone(),
@@ -461,29 +693,22 @@ impl IndexingPipeline {
         });
         source_mailbox.send_message(assign_shards_message).await?;
 
-        // Increment generation once we are sure there will be no spawning error.
-        self.previous_generations_statistics = self.statistics.clone();
-        self.statistics.generation += 1;
-        self.handles_opt = Some(IndexingPipelineHandles {
-            source_mailbox,
-            source_handle,
-            doc_processor: doc_processor_handle,
-            indexer: indexer_handle,
-            index_serializer: index_serializer_handle,
-            packager: packager_handle,
-            uploader: uploader_handle,
-            sequencer: sequencer_handle,
-            publisher: publisher_handle,
-            next_check_for_progress: Instant::now() + *HEARTBEAT,
-        });
-        Ok(())
+        if self.is_paused {
+            source_handle.pause();
+        }
+        Ok(source_handle)
     }
 
     async fn terminate(&mut self) {
         self.kill_switch.kill();
         if let Some(handles) = self.handles_opt.take() {
+            let kill_source = async {
+                if let Some(source_handle) = handles.source_handle_opt {
+                    source_handle.kill().await;
+                }
+            };
             tokio::join!(
-                handles.source_handle.kill(),
+                kill_source,
                 handles.indexer.kill(),
                 handles.packager.kill(),
                 handles.uploader.kill(),
@@ -517,7 +742,7 @@ impl Handler<Spawn> for IndexingPipeline {
         spawn: Spawn,
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
-        if self.handles_opt.is_some() {
+        if self.handles_opt.is_some() || self.is_failed {
             return Ok(());
         }
         self.previous_generations_statistics.num_spawn_attempts = 1 + spawn.retry_count;
@@ -528,19 +753,26 @@ impl Handler<Spawn> for IndexingPipeline {
                 info!(error = ?spawn_error, "could not spawn pipeline, index might have been deleted");
                 return Err(ActorExitStatus::Success);
             }
-            let retry_delay = wait_duration_before_retry(spawn.retry_count + 1);
-            error!(error = ?spawn_error, retry_count = spawn.retry_count, retry_delay = ?retry_delay, "error while spawning indexing pipeline, retrying after some time");
-            ctx.schedule_self_msg(
-                retry_delay,
-                Spawn {
-                    retry_count: spawn.retry_count + 1,
-                },
-            );
+            error!(error = ?spawn_error, retry_count = spawn.retry_count, "error while spawning indexing pipeline");
+            self.handle_failure(spawn.retry_count + 1, ctx);
         }
         Ok(())
     }
 }
 
+#[async_trait]
+impl Handler<RestartFailedPipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: RestartFailedPipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.restart_failed_pipeline(ctx).await
+    }
+}
+
 #[async_trait]
 impl Handler<AssignShards> for IndexingPipeline {
     type Reply = ();
@@ -570,6 +802,172 @@ impl Handler<AssignShards> for IndexingPipeline {
     }
 }
 
+#[async_trait]
+impl Handler<PausePipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: PausePipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if !self.is_paused {
+            info!(pipeline_id=?self.params.pipeline_id, "pausing indexing pipeline");
+        }
+        self.is_paused = true;
+        // Pausing the source is enough: the downstream actors are left untouched so that the
+        // in-flight batches are indexed and published.
+        if let Some(source_handle) = self
+            .handles_opt
+            .as_ref()
+            .and_then(|handles| handles.source_handle_opt.as_ref())
+        {
+            source_handle.pause();
+        }
+        self.statistics.is_paused = true;
+        self.perform_observe(ctx);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<ResumePipeline> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: ResumePipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if self.is_paused {
+            info!(pipeline_id=?self.params.pipeline_id, "resuming indexing pipeline");
+        }
+        self.is_paused = false;
+        if let Some(source_handle) = self
+            .handles_opt
+            .as_ref()
+            .and_then(|handles| handles.source_handle_opt.as_ref())
+        {
+            source_handle.resume();
+        }
+        self.statistics.is_paused = false;
+        self.perform_observe(ctx);
+        // Resuming a pipeline is also the way to retry a pipeline that failed too many times.
+        self.restart_failed_pipeline(ctx).await
+    }
+}
+
+#[async_trait]
+impl Handler<PromotePipeline> for IndexingPipeline {
+    type Reply = bool;
+
+    async fn handle(
+        &mut self,
+        _message: PromotePipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<bool, ActorExitStatus> {
+        if self.params.is_standby {
+            info!(pipeline_id=?self.params.pipeline_id, "promoting standby indexing pipeline");
+        }
+        // From now on, respawns spawn a regular pipeline.
+        self.params.is_standby = false;
+
+        let Some(handles) = self.handles_opt.as_mut() else {
+            // The standby pipeline is not running: the next spawn is a cold start.
+            self.perform_observe(ctx);
+            return Ok(false);
+        };
+        let Some((source_inbox, doc_processor_mailbox)) = handles.standby_source_opt.take() else {
+            // The pipeline is already consuming its source.
+            return Ok(true);
+        };
+        let source_mailbox = handles.source_mailbox.clone();
+
+        // The downstream actors are already running, so we only need to spawn the source, which
+        // fetches the latest published checkpoint. The generation of the pipeline is unchanged.
+        let spawn_source_result = self
+            .spawn_source(source_mailbox, source_inbox, doc_processor_mailbox, ctx)
+            .await;
+        let warm = match spawn_source_result {
+            Ok(source_handle) => {
+                if let Some(handles) = self.handles_opt.as_mut() {
+                    handles.source_handle_opt = Some(source_handle);
+                }
+                true
+            }
+            Err(spawn_error) => {
+                error!(error=?spawn_error, "error while promoting standby indexing pipeline");
+                self.terminate().await;
+                self.handle_failure(0, ctx);
+                false
+            }
+        };
+        self.perform_observe(ctx);
+        Ok(warm)
+    }
+}
+
+#[async_trait]
+impl Handler<DocMapperUpdate> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: DocMapperUpdate,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if message.params_fingerprint == self.params.params_fingerprint {
+            return Ok(());
+        }
+        let previous_doc_mapping_uid = self.params.doc_mapper.doc_mapping_uid();
+        let doc_mapping_uid = message.doc_mapper.doc_mapping_uid();
+        info!(
+            pipeline_id=?self.params.pipeline_id,
+            %previous_doc_mapping_uid,
+            %doc_mapping_uid,
+            "updating doc mapper of indexing pipeline"
+        );
+        // From now on, respawns use the new doc mapper.
+        self.params.doc_mapper = message.doc_mapper.clone();
+        self.params.params_fingerprint = message.params_fingerprint;
+
+        if let Some(handles) = &self.handles_opt {
+            // The source keeps running: the doc processor switches to the new doc mapper between
+            // two batches. If the doc processor is gone, the pipeline is about to be respawned
+            // with the new doc mapper anyway.
+            let _ = ctx
+                .send_message(handles.doc_processor.mailbox(), message.clone())
+                .await;
+        }
+        self.params.event_broker.publish(DocMapperReloaded {
+            pipeline_id: self.params.pipeline_id.clone(),
+            previous_doc_mapping_uid,
+            doc_mapping_uid,
+            params_fingerprint: message.params_fingerprint,
+        });
+        self.perform_observe(ctx);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<GetDocProcessingErrorSamples> for IndexingPipeline {
+    type Reply = Vec<DocProcessingErrorSample>;
+
+    async fn handle(
+        &mut self,
+        _message: GetDocProcessingErrorSamples,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let samples = self
+            .doc_processing_error_sampler_opt
+            .as_ref()
+            .map(|error_sampler| error_sampler.samples())
+            .unwrap_or_default();
+        Ok(samples)
+    }
+}
+
 pub struct IndexingPipelineParams {
     pub pipeline_id: IndexingPipelineId,
     pub metastore: MetastoreServiceClient,
@@ -581,7 +979,8 @@ pub struct IndexingPipelineParams {
     pub indexing_settings: IndexingSettings,
     pub split_store: IndexingSplitStore,
     pub max_concurrent_split_uploads_index: usize,
-    pub cooperative_indexing_permits: Option<Arc<Semaphore>>,
+    pub split_upload_queue_max_num_bytes: ByteSize,
+    pub cooperative_indexing_permits: Option<IndexingPermits>,
 
     // Merge-related parameters
     pub merge_policy: Arc<dyn MergePolicy>,
@@ -590,23 +989,37 @@ pub struct IndexingPipelineParams {
 
     // Source-related parameters
     pub source_config: SourceConfig,
+    /// Incarnation of the source when the pipeline was spawned, if it has one.
+    pub source_incarnation_opt: Option<Ulid>,
     pub source_storage_resolver: StorageResolver,
     pub ingester_pool: IngesterPool,
     pub queues_dir_path: PathBuf,
     pub params_fingerprint: u64,
 
     pub event_broker: EventBroker,
+    /// Backoff policy applied between the restarts of the pipeline after a failure.
+    /// `max_attempts` is the number of failures within 30 minutes after which the pipeline is no
+    /// longer restarted.
+    pub restart_params: RetryParams,
+    /// Sampling of the documents rejected by the doc processor. `None` disables it.
+    pub doc_processing_error_sampling_opt: Option<DocProcessingErrorSamplingSettings>,
+    /// Whether the pipeline is spawned as a warm standby: all its actors but the source are
+    /// spawned, and the source is only consumed once the pipeline is promoted.
+    pub is_standby: bool,
+    /// Priority of the pipeline's indexer when competing for the cooperative indexing permits.
+    pub priority: IndexingTaskPriority,
 }
 
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroUsize;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
     use quickwit_actors::{Command, Universe};
     use quickwit_common::ServiceStream;
-    use quickwit_config::{IndexingSettings, SourceInputFormat, SourceParams};
+    use quickwit_config::{IndexingSettings, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
     use quickwit_metastore::checkpoint::IndexCheckpointDelta;
     use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt, PublishSplitsRequestExt};
@@ -647,6 +1060,10 @@ mod tests {
             source_id: "test-source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::file_from_str(test_file).unwrap(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -709,6 +1126,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
             source_config,
+            source_incarnation_opt: None,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
@@ -719,11 +1137,16 @@ mod tests {
             merge_policy: default_merge_policy(),
             queues_dir_path: PathBuf::from("./queues"),
             max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
             max_concurrent_split_uploads_merge: 5,
             cooperative_indexing_permits: None,
             merge_planner_mailbox,
             event_broker: EventBroker::default(),
             params_fingerprint: 42u64,
+            restart_params: RetryParams::for_test(),
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
         };
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (_pipeline_mailbox, pipeline_handle) = universe.spawn_builder().spawn(pipeline);
@@ -767,6 +1190,10 @@ mod tests {
             source_id: "test-source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::file_from_str(test_file).unwrap(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -822,6 +1249,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
             source_config,
+            source_incarnation_opt: None,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
@@ -832,11 +1260,16 @@ mod tests {
             split_store,
             merge_policy: default_merge_policy(),
             max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
             max_concurrent_split_uploads_merge: 5,
             cooperative_indexing_permits: None,
             merge_planner_mailbox,
             event_broker: Default::default(),
             params_fingerprint: 42u64,
+            restart_params: RetryParams::for_test(),
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
         };
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (_pipeline_mailbox, pipeline_handler) = universe.spawn_builder().spawn(pipeline);
@@ -859,6 +1292,115 @@ mod tests {
         indexing_pipeline_simple("data/test_corpus.json.gz").await
     }
 
+    #[tokio::test]
+    async fn test_standby_indexing_pipeline_promotion() {
+        let node_id = NodeId::from("test-node");
+        let index_uid: IndexUid = IndexUid::for_test("test-index-standby", 1);
+        let pipeline_id = IndexingPipelineId {
+            node_id,
+            index_uid: index_uid.clone(),
+            source_id: "test-source".to_string(),
+            pipeline_uid: PipelineUid::for_test(0u128),
+        };
+        let source_config = SourceConfig {
+            source_id: "test-source".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: true,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::file_from_str("data/test_corpus.json").unwrap(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let source_config_clone = source_config.clone();
+
+        let index_metadata_calls = Arc::new(AtomicUsize::new(0));
+        let index_metadata_calls_clone = index_metadata_calls.clone();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            index_metadata_calls_clone.fetch_add(1, Ordering::SeqCst);
+            let mut index_metadata =
+                IndexMetadata::for_test("test-index-standby", "ram:///indexes/test-index-standby");
+            index_metadata
+                .add_source(source_config_clone.clone())
+                .unwrap();
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        mock_metastore
+            .expect_last_delete_opstamp()
+            .returning(move |_| Ok(LastDeleteOpstampResponse::new(10)));
+        mock_metastore
+            .expect_stage_splits()
+            .returning(|_| Ok(EmptyResponse {}));
+        mock_metastore
+            .expect_publish_splits()
+            .times(1)
+            .returning(|_| Ok(EmptyResponse {}));
+
+        let universe = Universe::new();
+        let storage = Arc::new(RamStorage::default());
+        let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
+        let (merge_planner_mailbox, _) = universe.create_test_mailbox();
+        let pipeline_params = IndexingPipelineParams {
+            pipeline_id: pipeline_id.clone(),
+            doc_mapper: Arc::new(default_doc_mapper_for_test()),
+            source_config,
+            source_incarnation_opt: None,
+            source_storage_resolver: StorageResolver::for_test(),
+            indexing_directory: TempDirectory::for_test(),
+            indexing_settings: IndexingSettings::for_test(),
+            ingester_pool: IngesterPool::default(),
+            metastore: MetastoreServiceClient::from_mock(mock_metastore),
+            queues_dir_path: PathBuf::from("./queues"),
+            storage,
+            split_store,
+            merge_policy: default_merge_policy(),
+            max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
+            max_concurrent_split_uploads_merge: 5,
+            cooperative_indexing_permits: None,
+            merge_planner_mailbox,
+            event_broker: Default::default(),
+            params_fingerprint: 42u64,
+            restart_params: RetryParams::for_test(),
+            doc_processing_error_sampling_opt: None,
+            is_standby: true,
+            priority: IndexingTaskPriority::Normal,
+        };
+        let pipeline = IndexingPipeline::new(pipeline_params);
+        let (pipeline_mailbox, pipeline_handle) = universe.spawn_builder().spawn(pipeline);
+
+        // The standby pipeline is running, but does not consume its source.
+        let obs = pipeline_handle.process_pending_and_observe().await;
+        assert!(obs.is_standby);
+        assert_eq!(obs.health, PipelineHealth::Running);
+        assert_eq!(obs.generation, 1);
+        assert_eq!(obs.num_spawn_attempts, 1);
+        assert_eq!(obs.num_docs, 0);
+        assert_eq!(index_metadata_calls.load(Ordering::SeqCst), 0);
+
+        let warm = pipeline_mailbox
+            .ask(PromotePipeline {
+                pipeline_uid: pipeline_id.pipeline_uid,
+            })
+            .await
+            .unwrap();
+        assert!(warm);
+
+        // The promotion skips the cold start: the pipeline is not respawned.
+        let (pipeline_exit_status, pipeline_statistics) = pipeline_handle.join().await;
+        assert!(pipeline_exit_status.is_success());
+        assert!(!pipeline_statistics.is_standby);
+        assert_eq!(pipeline_statistics.generation, 1);
+        assert_eq!(pipeline_statistics.num_spawn_attempts, 1);
+        assert_eq!(pipeline_statistics.num_published_splits, 1);
+        // Only the source fetched the index metadata for its checkpoint.
+        assert_eq!(index_metadata_calls.load(Ordering::SeqCst), 1);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_merge_pipeline_does_not_stop_on_indexing_pipeline_failure() {
         let node_id = NodeId::from("test-node");
@@ -872,6 +1414,10 @@ mod tests {
             source_id: "test-source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -921,6 +1467,7 @@ mod tests {
             pipeline_id,
             doc_mapper,
             source_config,
+            source_incarnation_opt: None,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
@@ -931,11 +1478,16 @@ mod tests {
             split_store,
             merge_policy: default_merge_policy(),
             max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
             max_concurrent_split_uploads_merge: 5,
             cooperative_indexing_permits: None,
             merge_planner_mailbox: merge_planner_mailbox.clone(),
             event_broker: Default::default(),
             params_fingerprint: 42u64,
+            restart_params: RetryParams::for_test(),
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
         };
         let indexing_pipeline = IndexingPipeline::new(indexing_pipeline_params);
         let (_indexing_pipeline_mailbox, indexing_pipeline_handler) =
@@ -963,6 +1515,231 @@ mod tests {
         panic!("Pipeline was apparently not restarted.");
     }
 
+    #[tokio::test]
+    async fn test_indexing_pipeline_health_transitions_on_source_failure() {
+        let node_id = NodeId::from("test-node");
+        let pipeline_id = IndexingPipelineId {
+            node_id,
+            index_uid: IndexUid::for_test("test-index", 2),
+            source_id: "test-source".to_string(),
+            pipeline_uid: PipelineUid::for_test(0u128),
+        };
+        let source_config = SourceConfig {
+            source_id: "test-source".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let source_config_clone = source_config.clone();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            let mut index_metadata =
+                IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+            index_metadata
+                .add_source(source_config_clone.clone())
+                .unwrap();
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        let universe = Universe::with_accelerated_time();
+        let (merge_planner_mailbox, _) = universe.create_test_mailbox();
+        let storage = Arc::new(RamStorage::default());
+        let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
+        let indexing_pipeline_params = IndexingPipelineParams {
+            pipeline_id,
+            doc_mapper: Arc::new(default_doc_mapper_for_test()),
+            source_config,
+            source_incarnation_opt: None,
+            source_storage_resolver: StorageResolver::for_test(),
+            indexing_directory: TempDirectory::for_test(),
+            indexing_settings: IndexingSettings::for_test(),
+            ingester_pool: IngesterPool::default(),
+            metastore: MetastoreServiceClient::from_mock(mock_metastore),
+            queues_dir_path: PathBuf::from("./queues"),
+            storage,
+            split_store,
+            merge_policy: default_merge_policy(),
+            max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
+            max_concurrent_split_uploads_merge: 5,
+            cooperative_indexing_permits: None,
+            merge_planner_mailbox,
+            event_broker: Default::default(),
+            params_fingerprint: 42u64,
+            restart_params: RetryParams {
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(1),
+                max_attempts: 3,
+            },
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
+        };
+        let indexing_pipeline = IndexingPipeline::new(indexing_pipeline_params);
+        let (_indexing_pipeline_mailbox, indexing_pipeline_handle) =
+            universe.spawn_builder().spawn(indexing_pipeline);
+        let obs = indexing_pipeline_handle.process_pending_and_observe().await;
+        assert_eq!(obs.generation, 1);
+        let mut health_transitions = vec![obs.health];
+
+        // Shutting down the source makes the pipeline fail and respawn.
+        let source = universe.get::<SourceActor>().into_iter().next().unwrap();
+        let _ = source.ask(Command::Quit).await;
+
+        for _ in 0..100 {
+            universe.sleep(Duration::from_millis(100)).await;
+            let obs = indexing_pipeline_handle.process_pending_and_observe().await;
+            if health_transitions.last() != Some(&obs.health) {
+                health_transitions.push(obs.health);
+            }
+            if obs.generation == 2 && obs.health == PipelineHealth::Running {
+                break;
+            }
+        }
+        assert_eq!(
+            health_transitions,
+            [
+                PipelineHealth::Running,
+                PipelineHealth::Retrying,
+                PipelineHealth::Running
+            ]
+        );
+        universe.quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexing_pipeline_restart_backoff_and_circuit_breaker() {
+        let pipeline_id = IndexingPipelineId {
+            node_id: NodeId::from("test-node"),
+            index_uid: IndexUid::for_test("test-index-restart-backoff", 0),
+            source_id: "test-source".to_string(),
+            pipeline_uid: PipelineUid::for_test(0u128),
+        };
+        let source_config = SourceConfig {
+            source_id: "test-source".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        // The pipeline can never be spawned.
+        let num_spawn_attempts = Arc::new(AtomicUsize::new(0));
+        let num_spawn_attempts_clone = num_spawn_attempts.clone();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            num_spawn_attempts_clone.fetch_add(1, Ordering::Relaxed);
+            Err(MetastoreError::Timeout("timeout error".to_string()))
+        });
+        let universe = Universe::with_accelerated_time();
+        let (merge_planner_mailbox, _) = universe.create_test_mailbox();
+        let storage = Arc::new(RamStorage::default());
+        let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
+        let indexing_pipeline_params = IndexingPipelineParams {
+            pipeline_id: pipeline_id.clone(),
+            doc_mapper: Arc::new(default_doc_mapper_for_test()),
+            source_config,
+            source_incarnation_opt: None,
+            source_storage_resolver: StorageResolver::for_test(),
+            indexing_directory: TempDirectory::for_test(),
+            indexing_settings: IndexingSettings::for_test(),
+            ingester_pool: IngesterPool::default(),
+            metastore: MetastoreServiceClient::from_mock(mock_metastore),
+            queues_dir_path: PathBuf::from("./queues"),
+            storage,
+            split_store,
+            merge_policy: default_merge_policy(),
+            max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
+            max_concurrent_split_uploads_merge: 5,
+            cooperative_indexing_permits: None,
+            merge_planner_mailbox,
+            event_broker: Default::default(),
+            params_fingerprint: 42u64,
+            restart_params: RetryParams {
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(3),
+                max_attempts: 4,
+            },
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
+        };
+        let indexing_pipeline = IndexingPipeline::new(indexing_pipeline_params);
+        let (indexing_pipeline_mailbox, indexing_pipeline_handle) =
+            universe.spawn_builder().spawn(indexing_pipeline);
+
+        // Records the (simulated) time of each spawn attempt, with a 100ms resolution.
+        let poll_interval = Duration::from_millis(100);
+        let mut elapsed = Duration::ZERO;
+        let mut spawn_attempt_times = Vec::new();
+        let mut last_num_spawn_attempts = 0;
+
+        for _ in 0..200 {
+            let obs = indexing_pipeline_handle.process_pending_and_observe().await;
+            let current_num_spawn_attempts = num_spawn_attempts.load(Ordering::Relaxed);
+            for _ in last_num_spawn_attempts..current_num_spawn_attempts {
+                spawn_attempt_times.push(elapsed);
+            }
+            last_num_spawn_attempts = current_num_spawn_attempts;
+
+            if obs.health == PipelineHealth::Failed {
+                break;
+            }
+            assert_eq!(obs.health, PipelineHealth::Retrying);
+            universe.sleep(poll_interval).await;
+            elapsed += poll_interval;
+        }
+        assert_eq!(spawn_attempt_times.len(), 4);
+
+        // The delays are jittered between half and the full exponential delay, capped at 3s.
+        let expected_delay_ranges = [(500, 1_000), (1_000, 2_000), (1_500, 3_000)];
+        for (window, (min_delay_millis, max_delay_millis)) in
+            spawn_attempt_times.windows(2).zip(expected_delay_ranges)
+        {
+            let delay_millis = (window[1] - window[0]).as_millis() as u64;
+            assert!(
+                delay_millis + 100 >= min_delay_millis && delay_millis <= max_delay_millis + 100,
+                "restart delay {delay_millis}ms not in [{min_delay_millis}ms, \
+                 {max_delay_millis}ms]"
+            );
+        }
+        let num_restarts = crate::metrics::INDEXER_METRICS
+            .pipeline_restarts_total
+            .with_label_values(["test-index-restart-backoff", "test-source"])
+            .get();
+        assert_eq!(num_restarts, 3);
+
+        // A failed pipeline is no longer restarted.
+        universe.sleep(Duration::from_secs(60)).await;
+        let obs = indexing_pipeline_handle.process_pending_and_observe().await;
+        assert_eq!(obs.health, PipelineHealth::Failed);
+        assert_eq!(num_spawn_attempts.load(Ordering::Relaxed), 4);
+
+        // Until it is resumed.
+        indexing_pipeline_mailbox
+            .ask(ResumePipeline {
+                pipeline_uid: pipeline_id.pipeline_uid,
+            })
+            .await
+            .unwrap();
+        let obs = indexing_pipeline_handle.process_pending_and_observe().await;
+        assert_eq!(obs.health, PipelineHealth::Retrying);
+        assert_eq!(num_spawn_attempts.load(Ordering::Relaxed), 5);
+
+        universe.quit().await;
+    }
+
     async fn indexing_pipeline_all_failures_handling(test_file: &str) -> anyhow::Result<()> {
         let node_id = NodeId::from("test-node");
         let index_uid: IndexUid = IndexUid::for_test("test-index", 2);
@@ -976,6 +1753,10 @@ mod tests {
             source_id: "test-source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::file_from_str(test_file).unwrap(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1048,6 +1829,7 @@ mod tests {
             pipeline_id,
             doc_mapper: Arc::new(broken_mapper),
             source_config,
+            source_incarnation_opt: None,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
             indexing_settings: IndexingSettings::for_test(),
@@ -1058,11 +1840,16 @@ mod tests {
             split_store,
             merge_policy: default_merge_policy(),
             max_concurrent_split_uploads_index: 4,
+            split_upload_queue_max_num_bytes: ByteSize::gib(4),
             max_concurrent_split_uploads_merge: 5,
             cooperative_indexing_permits: None,
             merge_planner_mailbox,
             params_fingerprint: 42u64,
             event_broker: Default::default(),
+            restart_params: RetryParams::for_test(),
+            doc_processing_error_sampling_opt: None,
+            is_standby: false,
+            priority: IndexingTaskPriority::Normal,
         };
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (_pipeline_mailbox, pipeline_handler) = universe.spawn_builder().spawn(pipeline);
@@ -17,23 +17,28 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use async_trait::async_trait;
+use bytesize::ByteSize;
 use futures::TryStreamExt;
 use itertools::Itertools;
 use quickwit_actors::{
-    Actor, ActorContext, ActorExitStatus, ActorHandle, ActorState, Handler, Healthz, Mailbox,
-    Observation,
+    Actor, ActorContext, ActorExitStatus, ActorHandle, ActorState, DeferableReplyHandler, Handler,
+    Healthz, Mailbox, Observation,
 };
 use quickwit_cluster::Cluster;
 use quickwit_common::fs::get_cache_directory_path;
 use quickwit_common::io::Limiter;
 use quickwit_common::pubsub::EventBroker;
+use quickwit_common::rate_limiter::RateLimiterSettings;
+use quickwit_common::retry::RetryParams;
+use quickwit_common::tower::{ConstantRate, KeyedRateLimit};
 use quickwit_common::{io, temp_dir};
 use quickwit_config::{
     build_doc_mapper, IndexConfig, IndexerConfig, SourceConfig, INGEST_API_SOURCE_ID,
@@ -48,8 +53,10 @@ use quickwit_metastore::{
     SplitMetadata, SplitState,
 };
 use quickwit_proto::indexing::{
-    ApplyIndexingPlanRequest, ApplyIndexingPlanResponse, IndexingError, IndexingPipelineId,
-    IndexingTask, MergePipelineId, PipelineMetrics,
+    AppliedIndexingPlan, ApplyIndexingPlanRequest, ApplyIndexingPlanResponse, IndexingError,
+    IndexingPipelineId, IndexingTask, IndexingTaskOutcome, IndexingTaskPriority,
+    IndexingTaskStatus, MergePipelineId, PipelineHealth, PipelineMetrics,
+    PromoteIndexingPipelineRequest, PromoteIndexingPipelineResponse,
 };
 use quickwit_proto::metastore::{
     IndexMetadataRequest, IndexMetadataSubrequest, IndexesMetadataRequest,
@@ -60,12 +67,19 @@ use quickwit_proto::types::{IndexId, IndexUid, NodeId, PipelineUid, ShardId};
 use quickwit_storage::StorageResolver;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
-use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
+use ulid::Ulid;
 
 use super::merge_pipeline::{MergePipeline, MergePipelineParams};
 use super::{MergePlanner, MergeSchedulerService};
-use crate::models::{DetachIndexingPipeline, DetachMergePipeline, ObservePipeline, SpawnPipeline};
+use crate::actors::{
+    DocProcessingErrorSample, DocProcessingErrorSamplingSettings, IndexingPermits,
+    RestartFailedPipeline,
+};
+use crate::models::{
+    DetachIndexingPipeline, DetachMergePipeline, DocMapperUpdate, GetDocProcessingErrorSamples,
+    ObservePipeline, PausePipeline, PromotePipeline, ResumePipeline, SpawnPipeline,
+};
 use crate::source::{AssignShards, Assignment};
 use crate::split_store::{LocalSplitStore, SplitStoreQuota};
 use crate::{IndexingPipeline, IndexingPipelineParams, IndexingSplitStore, IndexingStatistics};
@@ -81,6 +95,18 @@ pub struct IndexingServiceCounters {
     pub num_running_merge_pipelines: usize,
     pub num_deleted_queues: usize,
     pub num_delete_queue_failures: usize,
+    /// UIDs of the paused indexing pipelines. Paused pipelines are also counted as running.
+    #[serde(default)]
+    pub paused_pipeline_uids: Vec<PipelineUid>,
+    /// Health of the indexing pipelines, per pipeline UID.
+    #[serde(default)]
+    pub pipeline_health: BTreeMap<PipelineUid, PipelineHealth>,
+    /// UIDs of the warm standby indexing pipelines. Standby pipelines are also counted as running.
+    #[serde(default)]
+    pub standby_pipeline_uids: Vec<PipelineUid>,
+    /// Priority of the indexing pipelines, per pipeline UID.
+    #[serde(default)]
+    pub pipeline_priorities: BTreeMap<PipelineUid, IndexingTaskPriority>,
 }
 
 struct MergePipelineHandle {
@@ -94,6 +120,13 @@ struct PipelineHandle {
     indexing_pipeline_id: IndexingPipelineId,
 }
 
+/// Last indexing plan applied by the indexing service, against which the control plane that sent
+/// it may delta-encode the shard lists of its next plan.
+struct LastAppliedPlan {
+    control_plane_node_id: String,
+    applied_plan: AppliedIndexingPlan,
+}
+
 /// The indexing service is (single) actor service running on indexer and in charge
 /// of executing the indexing plans received from the control plane.
 ///
@@ -114,9 +147,15 @@ pub struct IndexingService {
     counters: IndexingServiceCounters,
     local_split_store: Arc<LocalSplitStore>,
     max_concurrent_split_uploads: usize,
+    split_upload_queue_max_num_bytes: ByteSize,
     merge_pipeline_handles: HashMap<MergePipelineId, MergePipelineHandle>,
-    cooperative_indexing_permits: Option<Arc<Semaphore>>,
+    cooperative_indexing_permits: Option<IndexingPermits>,
     merge_io_throughput_limiter_opt: Option<Limiter>,
+    // Rate limits the indexing plans received from each control plane node.
+    apply_plan_rate_limit: KeyedRateLimit<String>,
+    last_applied_plan_opt: Option<LastAppliedPlan>,
+    pipeline_restart_params: RetryParams,
+    doc_processing_error_sampling_opt: Option<DocProcessingErrorSamplingSettings>,
     event_broker: EventBroker,
 }
 
@@ -159,10 +198,34 @@ impl IndexingService {
             temp_dir::create_or_purge_directory(&data_dir_path.join(INDEXING_DIR_NAME)).await?;
         let queue_dir_path = data_dir_path.join(QUEUES_DIR_NAME);
         let cooperative_indexing_permits = if indexer_config.enable_cooperative_indexing {
-            Some(Arc::new(Semaphore::new(num_blocking_threads)))
+            Some(IndexingPermits::new(num_blocking_threads))
         } else {
             None
         };
+        let apply_plan_refill_period =
+            Duration::from_secs(60) / indexer_config.max_apply_plan_requests_per_minute.get();
+        let apply_plan_rate_limiter_settings = RateLimiterSettings {
+            burst_limit: indexer_config.apply_plan_burst_limit.get() as u64,
+            rate_limit: ConstantRate::new(1, apply_plan_refill_period),
+            refill_period: apply_plan_refill_period,
+        };
+        let doc_processing_error_sampling_opt =
+            if indexer_config.enable_doc_processing_error_samples {
+                Some(DocProcessingErrorSamplingSettings {
+                    max_num_samples: indexer_config.max_doc_processing_error_samples.get(),
+                    max_raw_doc_num_bytes: indexer_config
+                        .doc_processing_error_sample_max_num_bytes
+                        .as_u64() as usize,
+                })
+            } else {
+                None
+            };
+        let apply_plan_rate_limit = KeyedRateLimit::new(
+            apply_plan_rate_limiter_settings,
+            crate::metrics::INDEXER_METRICS
+                .apply_plan_throttled_total
+                .clone(),
+        );
         Ok(IndexingService {
             node_id,
             indexing_root_directory,
@@ -177,9 +240,14 @@ impl IndexingService {
             indexing_pipelines: Default::default(),
             counters: Default::default(),
             max_concurrent_split_uploads: indexer_config.max_concurrent_split_uploads,
+            split_upload_queue_max_num_bytes: indexer_config.split_upload_queue_max_num_bytes,
             merge_pipeline_handles: HashMap::new(),
             merge_io_throughput_limiter_opt,
             cooperative_indexing_permits,
+            apply_plan_rate_limit,
+            last_applied_plan_opt: None,
+            pipeline_restart_params: indexer_config.pipeline_restart_params(),
+            doc_processing_error_sampling_opt,
             event_broker,
         })
     }
@@ -230,6 +298,97 @@ impl IndexingService {
         Ok(observation)
     }
 
+    /// Forwards a pause or resume message to the indexing pipeline with the given UID and waits
+    /// for the pipeline to process it.
+    async fn pause_or_resume_pipeline<M>(
+        &mut self,
+        pipeline_uid: &PipelineUid,
+        pause_or_resume_message: M,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), IndexingError>
+    where
+        IndexingPipeline: DeferableReplyHandler<M, Reply = ()>,
+        M: Debug + Send + 'static,
+    {
+        let pipeline_mailbox = &self
+            .indexing_pipelines
+            .get(pipeline_uid)
+            .ok_or(IndexingError::PipelineNotFound {
+                pipeline_uid: *pipeline_uid,
+            })?
+            .mailbox;
+        ctx.protect_future(pipeline_mailbox.ask(pause_or_resume_message))
+            .await
+            .map_err(|error| {
+                let message = format!(
+                    "failed to pause or resume indexing pipeline `{}`: {error}",
+                    pipeline_uid.short_id()
+                );
+                IndexingError::Internal(message)
+            })
+    }
+
+    async fn get_doc_processing_error_samples(
+        &mut self,
+        pipeline_uid: &PipelineUid,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Vec<DocProcessingErrorSample>, IndexingError> {
+        if self.doc_processing_error_sampling_opt.is_none() {
+            let message = "doc processing error sampling is disabled on this indexer".to_string();
+            return Err(IndexingError::Unavailable(message));
+        }
+        let pipeline_mailbox = &self
+            .indexing_pipelines
+            .get(pipeline_uid)
+            .ok_or(IndexingError::PipelineNotFound {
+                pipeline_uid: *pipeline_uid,
+            })?
+            .mailbox;
+        let get_samples_message = GetDocProcessingErrorSamples {
+            pipeline_uid: *pipeline_uid,
+        };
+        ctx.protect_future(pipeline_mailbox.ask(get_samples_message))
+            .await
+            .map_err(|error| {
+                let message = format!(
+                    "failed to get doc processing error samples of indexing pipeline `{}`: {error}",
+                    pipeline_uid.short_id()
+                );
+                IndexingError::Internal(message)
+            })
+    }
+
+    /// Promotes the warm standby indexing pipeline with the given UID and returns whether it was
+    /// promoted warm, i.e. without a cold start.
+    async fn promote_pipeline(
+        &self,
+        pipeline_uid: PipelineUid,
+        ctx: &ActorContext<Self>,
+    ) -> Result<bool, IndexingError> {
+        let pipeline_mailbox = &self
+            .indexing_pipelines
+            .get(&pipeline_uid)
+            .ok_or_else(|| {
+                let message = format!("could not find indexing pipeline `{pipeline_uid}`");
+                IndexingError::Internal(message)
+            })?
+            .mailbox;
+        let now = Instant::now();
+        let warm = ctx
+            .protect_future(pipeline_mailbox.ask(PromotePipeline { pipeline_uid }))
+            .await
+            .map_err(|error| {
+                let message =
+                    format!("failed to promote indexing pipeline `{pipeline_uid}`: {error}");
+                IndexingError::Internal(message)
+            })?;
+        crate::metrics::INDEXER_METRICS
+            .standby_pipeline_promotion_duration_secs
+            .observe(now.elapsed().as_secs_f64());
+        info!(pipeline_uid=%pipeline_uid, warm, "promoted standby indexing pipeline");
+        Ok(warm)
+    }
+
     async fn spawn_pipeline(
         &mut self,
         ctx: &ActorContext<Self>,
@@ -244,19 +403,36 @@ impl IndexingService {
             node_id: self.node_id.clone(),
             pipeline_uid,
         };
+        let source_incarnation_opt = index_metadata
+            .checkpoint
+            .source_incarnation(&source_config.source_id);
         let index_config = index_metadata.into_index_config();
-        self.spawn_pipeline_inner(ctx, pipeline_id.clone(), index_config, source_config, None)
-            .await?;
+        let priority = source_config.priority.into();
+        self.spawn_pipeline_inner(
+            ctx,
+            pipeline_id.clone(),
+            index_config,
+            source_config,
+            source_incarnation_opt,
+            None,
+            false,
+            priority,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn spawn_pipeline_inner(
         &mut self,
         ctx: &ActorContext<Self>,
         indexing_pipeline_id: IndexingPipelineId,
         index_config: IndexConfig,
         source_config: SourceConfig,
+        source_incarnation_opt: Option<Ulid>,
         immature_splits_opt: Option<Vec<SplitMetadata>>,
+        is_standby: bool,
+        priority: IndexingTaskPriority,
     ) -> Result<(), IndexingError> {
         if self
             .indexing_pipelines
@@ -324,6 +500,7 @@ impl IndexingService {
             indexing_settings: index_config.indexing_settings.clone(),
             split_store,
             max_concurrent_split_uploads_index,
+            split_upload_queue_max_num_bytes: self.split_upload_queue_max_num_bytes,
             cooperative_indexing_permits: self.cooperative_indexing_permits.clone(),
 
             // Merge-related parameters
@@ -333,12 +510,17 @@ impl IndexingService {
 
             // Source-related parameters
             source_config,
+            source_incarnation_opt,
             ingester_pool: self.ingester_pool.clone(),
             queues_dir_path: self.queue_dir_path.clone(),
             source_storage_resolver: self.storage_resolver.clone(),
             params_fingerprint,
 
             event_broker: self.event_broker.clone(),
+            restart_params: self.pipeline_restart_params,
+            doc_processing_error_sampling_opt: self.doc_processing_error_sampling_opt,
+            is_standby,
+            priority,
         };
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (pipeline_mailbox, pipeline_handle) = ctx.spawn_actor().spawn(pipeline);
@@ -534,6 +716,42 @@ impl IndexingService {
         self.cluster
             .update_self_node_pipeline_metrics(&pipeline_metrics)
             .await;
+
+        let pipeline_health: HashMap<&IndexingPipelineId, PipelineHealth> = self
+            .indexing_pipelines
+            .values()
+            .map(|pipeline_handle| {
+                let health = pipeline_handle.handle.last_observation().health;
+                (&pipeline_handle.indexing_pipeline_id, health)
+            })
+            .collect();
+        let num_standby_pipelines = self
+            .indexing_pipelines
+            .values()
+            .filter(|pipeline_handle| pipeline_handle.handle.last_observation().is_standby)
+            .count();
+        crate::metrics::INDEXER_METRICS
+            .standby_pipelines
+            .set(num_standby_pipelines as i64);
+
+        for health in PipelineHealth::ALL {
+            let num_pipelines = pipeline_health
+                .values()
+                .filter(|pipeline_health| **pipeline_health == health)
+                .count();
+            crate::metrics::INDEXER_METRICS
+                .indexing_pipelines_by_health
+                .with_label_values([health.as_str()])
+                .set(num_pipelines as i64);
+            if health == PipelineHealth::Failed {
+                crate::metrics::INDEXER_METRICS
+                    .pipelines_failed
+                    .set(num_pipelines as i64);
+            }
+        }
+        self.cluster
+            .update_self_node_pipeline_health(&pipeline_health)
+            .await;
         Ok(())
     }
 
@@ -569,7 +787,14 @@ impl IndexingService {
     /// or not.
     ///
     /// If a pipeline actor has failed, this function just logs an error.
-    async fn assign_shards_to_pipelines(&mut self, tasks: &[IndexingTask]) {
+    /// Assigns their shards to the pipelines of the tasks and returns the outcome of the
+    /// assignments that changed the shards of a pipeline or could not be delivered.
+    async fn assign_shards_to_pipelines(
+        &mut self,
+        tasks: &[IndexingTask],
+    ) -> HashMap<PipelineUid, Result<(), String>> {
+        let mut shard_updates = HashMap::new();
+
         for task in tasks {
             if task.shard_ids.is_empty() {
                 continue;
@@ -578,61 +803,166 @@ impl IndexingService {
             let Some(pipeline_handle) = self.indexing_pipelines.get(&pipeline_uid) else {
                 continue;
             };
-            let assignment = Assignment {
-                shard_ids: task.shard_ids.iter().cloned().collect(),
-            };
-            let message = AssignShards(assignment);
+            let shard_ids: BTreeSet<ShardId> = task.shard_ids.iter().cloned().collect();
+            let is_shard_update = pipeline_handle.handle.last_observation().shard_ids != shard_ids;
+            let message = AssignShards(Assignment { shard_ids });
 
             if let Err(error) = pipeline_handle.mailbox.send_message(message).await {
                 error!(%error, "failed to assign shards to indexing pipeline");
+                let error_message = format!("failed to assign shards to pipeline: {error}");
+                shard_updates.insert(pipeline_uid, Err(error_message));
+            } else if is_shard_update {
+                shard_updates.insert(pipeline_uid, Ok(()));
             }
         }
+        shard_updates
     }
 
     /// Applies the indexing plan by:
     /// - Stopping the running pipelines not present in the provided plan.
-    /// - Starting the pipelines that are not running.
+    /// - Starting the pipelines that are not running, as warm standbys for the standby tasks.
+    /// - Promoting the standby pipelines whose task is no longer a standby.
+    /// - Switching the running pipelines whose indexing parameters changed to the new doc mapper.
+    /// - Assigning their shards to the pipelines.
+    ///
+    /// Returns the outcome of each task of the plan.
     async fn apply_indexing_plan(
         &mut self,
         tasks: &[IndexingTask],
         ctx: &ActorContext<Self>,
-    ) -> Result<(), IndexingError> {
+    ) -> Result<Vec<IndexingTaskOutcome>, IndexingError> {
         let pipeline_diff = self.compute_pipeline_diff(tasks);
 
         if !pipeline_diff.pipelines_to_shutdown.is_empty() {
             self.shutdown_pipelines(&pipeline_diff.pipelines_to_shutdown)
                 .await;
         }
-        let mut spawn_pipeline_failures: Vec<IndexingPipelineId> = Vec::new();
+        let mut spawn_pipeline_failures: HashMap<PipelineUid, String> = HashMap::new();
 
         if !pipeline_diff.pipelines_to_spawn.is_empty() {
             spawn_pipeline_failures = self
-                .spawn_pipelines(&pipeline_diff.pipelines_to_spawn, ctx)
+                .spawn_pipelines(
+                    &pipeline_diff.pipelines_to_spawn,
+                    &pipeline_diff.standby_pipeline_uids,
+                    &pipeline_diff.pipeline_priorities,
+                    ctx,
+                )
                 .await?;
         }
-        self.assign_shards_to_pipelines(tasks).await;
+        let mut doc_mapper_update_failures: HashMap<PipelineUid, String> = HashMap::new();
+
+        if !pipeline_diff.pipelines_to_update.is_empty() {
+            doc_mapper_update_failures = self
+                .update_pipeline_doc_mappers(&pipeline_diff.pipelines_to_update, ctx)
+                .await;
+        }
+        let mut promotions: HashMap<PipelineUid, Result<(), String>> = HashMap::new();
+
+        for pipeline_uid in pipeline_diff.pipelines_to_promote {
+            let promotion = self
+                .promote_pipeline(pipeline_uid, ctx)
+                .await
+                .map(|_warm| ())
+                .map_err(|error| error.to_string());
+            promotions.insert(pipeline_uid, promotion);
+        }
+        let spawned_pipeline_uids: HashSet<PipelineUid> = pipeline_diff
+            .pipelines_to_spawn
+            .iter()
+            .map(|pipeline_id| pipeline_id.pipeline_uid)
+            .collect();
+        let mut shard_updates = self.assign_shards_to_pipelines(tasks).await;
+        self.restart_failed_pipelines().await;
         self.update_chitchat_running_plan().await;
 
-        if !spawn_pipeline_failures.is_empty() {
-            let message = format!(
-                "failed to spawn indexing pipelines: {:?}",
-                spawn_pipeline_failures
-            );
-            return Err(IndexingError::Internal(message));
+        let task_outcomes = tasks
+            .iter()
+            .map(|task| {
+                let pipeline_uid = task.pipeline_uid();
+
+                if let Some(error_message) = spawn_pipeline_failures.remove(&pipeline_uid) {
+                    return IndexingTaskOutcome::failed(pipeline_uid, error_message);
+                }
+                if spawned_pipeline_uids.contains(&pipeline_uid) {
+                    return IndexingTaskOutcome::new(pipeline_uid, IndexingTaskStatus::Started);
+                }
+                if let Some(error_message) = doc_mapper_update_failures.remove(&pipeline_uid) {
+                    return IndexingTaskOutcome::failed(pipeline_uid, error_message);
+                }
+                match promotions.remove(&pipeline_uid) {
+                    Some(Ok(())) => {
+                        return IndexingTaskOutcome::new(pipeline_uid, IndexingTaskStatus::Promoted)
+                    }
+                    Some(Err(error_message)) => {
+                        return IndexingTaskOutcome::failed(pipeline_uid, error_message)
+                    }
+                    None => {}
+                }
+                match shard_updates.remove(&pipeline_uid) {
+                    Some(Ok(())) => IndexingTaskOutcome::new(
+                        pipeline_uid,
+                        IndexingTaskStatus::ShardUpdateApplied,
+                    ),
+                    Some(Err(error_message)) => {
+                        IndexingTaskOutcome::failed(pipeline_uid, error_message)
+                    }
+                    None => {
+                        IndexingTaskOutcome::new(pipeline_uid, IndexingTaskStatus::AlreadyRunning)
+                    }
+                }
+            })
+            .collect();
+        Ok(task_outcomes)
+    }
+
+    /// Gives the pipelines that failed too many times and are no longer restarted a new chance,
+    /// since a new indexing plan was applied.
+    async fn restart_failed_pipelines(&self) {
+        for pipeline_handle in self.indexing_pipelines.values() {
+            if pipeline_handle.handle.last_observation().health != PipelineHealth::Failed {
+                continue;
+            }
+            if let Err(error) = pipeline_handle
+                .mailbox
+                .send_message(RestartFailedPipeline)
+                .await
+            {
+                warn!(pipeline_id=?pipeline_handle.indexing_pipeline_id, %error, "failed to restart failed indexing pipeline");
+            }
         }
-        Ok(())
     }
 
     /// Identifies the pipelines to spawn and shutdown by comparing the scheduled plan with the
     /// current running plan.
     fn compute_pipeline_diff(&self, tasks: &[IndexingTask]) -> IndexingPipelineDiff {
         let mut pipelines_to_spawn: Vec<IndexingPipelineId> = Vec::new();
+        let mut standby_pipeline_uids: HashSet<PipelineUid> = HashSet::new();
+        let mut pipeline_priorities: HashMap<PipelineUid, IndexingTaskPriority> = HashMap::new();
+        let mut pipelines_to_promote: Vec<PipelineUid> = Vec::new();
+        let mut pipelines_to_update: Vec<(PipelineUid, u64)> = Vec::new();
         let mut scheduled_pipeline_uids: HashSet<PipelineUid> = HashSet::with_capacity(tasks.len());
 
         for task in tasks {
             let pipeline_uid = task.pipeline_uid();
 
-            if !self.indexing_pipelines.contains_key(&pipeline_uid) {
+            if let Some(pipeline_handle) = self.indexing_pipelines.get(&pipeline_uid) {
+                // A standby pipeline is never demoted: a primary task is at worst rescheduled as a
+                // standby on a new pipeline.
+                if !task.standby && pipeline_handle.handle.last_observation().is_standby {
+                    pipelines_to_promote.push(pipeline_uid);
+                }
+                // The control plane keeps the pipelines whose indexing parameters can be updated
+                // in place, so a fingerprint mismatch calls for a doc mapper update.
+                if pipeline_handle.handle.last_observation().params_fingerprint
+                    != task.params_fingerprint
+                {
+                    pipelines_to_update.push((pipeline_uid, task.params_fingerprint));
+                }
+            } else {
+                if task.standby {
+                    standby_pipeline_uids.insert(pipeline_uid);
+                }
+                pipeline_priorities.insert(pipeline_uid, task.priority());
                 let pipeline_id = IndexingPipelineId {
                     node_id: self.node_id.clone(),
                     index_uid: task.index_uid().clone(),
@@ -653,15 +983,89 @@ impl IndexingService {
         IndexingPipelineDiff {
             pipelines_to_shutdown,
             pipelines_to_spawn,
+            standby_pipeline_uids,
+            pipeline_priorities,
+            pipelines_to_promote,
+            pipelines_to_update,
+        }
+    }
+
+    /// Switches the supplied running pipelines to the doc mapper of the current config of their
+    /// index, which must match the supplied indexing parameters fingerprint, and returns the error
+    /// messages of the pipelines that could not be updated.
+    async fn update_pipeline_doc_mappers(
+        &mut self,
+        pipelines_to_update: &[(PipelineUid, u64)],
+        ctx: &ActorContext<Self>,
+    ) -> HashMap<PipelineUid, String> {
+        let mut failures: HashMap<PipelineUid, String> = HashMap::new();
+        let mut index_configs: HashMap<IndexUid, Result<IndexConfig, String>> = HashMap::new();
+
+        for &(pipeline_uid, params_fingerprint) in pipelines_to_update {
+            let Some(pipeline_handle) = self.indexing_pipelines.get(&pipeline_uid) else {
+                continue;
+            };
+            let pipeline_mailbox = pipeline_handle.mailbox.clone();
+            let index_uid = pipeline_handle.indexing_pipeline_id.index_uid.clone();
+
+            if !index_configs.contains_key(&index_uid) {
+                let index_config_result = self
+                    .index_metadata(ctx, &index_uid.index_id)
+                    .await
+                    .map_err(|error| error.to_string())
+                    .and_then(|index_metadata| {
+                        if index_metadata.index_uid == index_uid {
+                            Ok(index_metadata.index_config)
+                        } else {
+                            Err(format!("index `{index_uid}` was deleted"))
+                        }
+                    });
+                index_configs.insert(index_uid.clone(), index_config_result);
+            }
+            let doc_mapper_update_result = match &index_configs[&index_uid] {
+                Ok(index_config)
+                    if index_config.indexing_params_fingerprint() == params_fingerprint =>
+                {
+                    build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+                        .map(|doc_mapper| DocMapperUpdate {
+                            doc_mapper,
+                            params_fingerprint,
+                        })
+                        .map_err(|error| error.to_string())
+                }
+                Ok(_) => Err(format!(
+                    "config of index `{index_uid}` changed since the indexing plan was built"
+                )),
+                Err(error_message) => Err(error_message.clone()),
+            };
+            let update_result = match doc_mapper_update_result {
+                Ok(doc_mapper_update) => ctx
+                    .protect_future(pipeline_mailbox.ask(doc_mapper_update))
+                    .await
+                    .map_err(|error| error.to_string()),
+                Err(error_message) => Err(error_message),
+            };
+            if let Err(error_message) = update_result {
+                let message = format!(
+                    "failed to update doc mapper of indexing pipeline `{pipeline_uid}`: \
+                     {error_message}"
+                );
+                warn!("{message}");
+                failures.insert(pipeline_uid, message);
+            }
         }
+        failures
     }
 
-    /// Spawns the pipelines with supplied ids and returns a list of failed pipelines.
+    /// Spawns the pipelines with supplied ids and returns the error messages of the pipelines
+    /// that failed to spawn.
     async fn spawn_pipelines(
         &mut self,
         pipelines_to_spawn: &[IndexingPipelineId],
+        standby_pipeline_uids: &HashSet<PipelineUid>,
+        pipeline_priorities: &HashMap<PipelineUid, IndexingTaskPriority>,
         ctx: &ActorContext<Self>,
-    ) -> Result<Vec<IndexingPipelineId>, IndexingError> {
+    ) -> Result<HashMap<PipelineUid, String>, IndexingError> {
         let indexes_metadata = self.indexes_metadata(ctx, pipelines_to_spawn).await?;
 
         let per_index_uid_indexes_metadata: HashMap<IndexUid, IndexMetadata> = indexes_metadata
@@ -673,7 +1077,7 @@ impl IndexingService {
             self.fetch_immature_splits_for_new_merge_pipelines(pipelines_to_spawn, ctx)
                 .await?;
 
-        let mut spawn_pipeline_failures: Vec<IndexingPipelineId> = Vec::new();
+        let mut spawn_pipeline_failures: HashMap<PipelineUid, String> = HashMap::new();
 
         for pipeline_to_spawn in pipelines_to_spawn {
             if let Some(index_metadata) =
@@ -692,23 +1096,46 @@ impl IndexingService {
                             pipeline_to_spawn.clone(),
                             index_metadata.index_config.clone(),
                             source_config.clone(),
+                            index_metadata
+                                .checkpoint
+                                .source_incarnation(&pipeline_to_spawn.source_id),
                             immature_splits_opt,
+                            standby_pipeline_uids.contains(&pipeline_to_spawn.pipeline_uid),
+                            pipeline_priorities
+                                .get(&pipeline_to_spawn.pipeline_uid)
+                                .copied()
+                                .unwrap_or_default(),
                         )
                         .await
                     {
                         error!(pipeline_id=?pipeline_to_spawn, %error, "failed to spawn pipeline");
-                        spawn_pipeline_failures.push(pipeline_to_spawn.clone());
+                        spawn_pipeline_failures.insert(
+                            pipeline_to_spawn.pipeline_uid,
+                            format!("failed to spawn pipeline: {error}"),
+                        );
                     }
                 } else {
                     error!(pipeline_id=?pipeline_to_spawn, "failed to spawn pipeline: source not found");
-                    spawn_pipeline_failures.push(pipeline_to_spawn.clone());
+                    spawn_pipeline_failures.insert(
+                        pipeline_to_spawn.pipeline_uid,
+                        format!(
+                            "failed to spawn pipeline: source `{}` not found",
+                            pipeline_to_spawn.source_id
+                        ),
+                    );
                 }
             } else {
                 error!(
                     "failed to spawn pipeline: index `{}` no longer exists",
                     pipeline_to_spawn.index_uid
                 );
-                spawn_pipeline_failures.push(pipeline_to_spawn.clone());
+                spawn_pipeline_failures.insert(
+                    pipeline_to_spawn.pipeline_uid,
+                    format!(
+                        "failed to spawn pipeline: index `{}` no longer exists",
+                        pipeline_to_spawn.index_uid
+                    ),
+                );
             }
         }
         Ok(spawn_pipeline_failures)
@@ -767,6 +1194,8 @@ impl IndexingService {
                     pipeline_uid: Some(pipeline_handle.indexing_pipeline_id.pipeline_uid),
                     shard_ids,
                     params_fingerprint: assignment.params_fingerprint,
+                    standby: assignment.is_standby,
+                    priority: assignment.priority as i32,
                 }
             })
             .collect();
@@ -881,6 +1310,53 @@ impl Handler<DetachMergePipeline> for IndexingService {
     }
 }
 
+#[async_trait]
+impl Handler<PausePipeline> for IndexingService {
+    type Reply = Result<(), IndexingError>;
+
+    async fn handle(
+        &mut self,
+        message: PausePipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_uid = message.pipeline_uid;
+        Ok(self
+            .pause_or_resume_pipeline(&pipeline_uid, message, ctx)
+            .await)
+    }
+}
+
+#[async_trait]
+impl Handler<ResumePipeline> for IndexingService {
+    type Reply = Result<(), IndexingError>;
+
+    async fn handle(
+        &mut self,
+        message: ResumePipeline,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_uid = message.pipeline_uid;
+        Ok(self
+            .pause_or_resume_pipeline(&pipeline_uid, message, ctx)
+            .await)
+    }
+}
+
+#[async_trait]
+impl Handler<GetDocProcessingErrorSamples> for IndexingService {
+    type Reply = Result<Vec<DocProcessingErrorSample>, IndexingError>;
+
+    async fn handle(
+        &mut self,
+        message: GetDocProcessingErrorSamples,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        Ok(self
+            .get_doc_processing_error_samples(&message.pipeline_uid, ctx)
+            .await)
+    }
+}
+
 #[derive(Debug)]
 struct SuperviseLoop;
 
@@ -904,7 +1380,42 @@ impl Actor for IndexingService {
     type ObservableState = IndexingServiceCounters;
 
     fn observable_state(&self) -> Self::ObservableState {
-        self.counters.clone()
+        let mut counters = self.counters.clone();
+        counters.paused_pipeline_uids = self
+            .indexing_pipelines
+            .iter()
+            .filter(|(_, pipeline_handle)| pipeline_handle.handle.last_observation().is_paused)
+            .map(|(pipeline_uid, _)| *pipeline_uid)
+            .sorted()
+            .collect();
+        counters.pipeline_health = self
+            .indexing_pipelines
+            .iter()
+            .map(|(pipeline_uid, pipeline_handle)| {
+                (
+                    *pipeline_uid,
+                    pipeline_handle.handle.last_observation().health,
+                )
+            })
+            .collect();
+        counters.standby_pipeline_uids = self
+            .indexing_pipelines
+            .iter()
+            .filter(|(_, pipeline_handle)| pipeline_handle.handle.last_observation().is_standby)
+            .map(|(pipeline_uid, _)| *pipeline_uid)
+            .sorted()
+            .collect();
+        counters.pipeline_priorities = self
+            .indexing_pipelines
+            .iter()
+            .map(|(pipeline_uid, pipeline_handle)| {
+                (
+                    *pipeline_uid,
+                    pipeline_handle.handle.last_observation().priority,
+                )
+            })
+            .collect();
+        counters
     }
 
     async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
@@ -938,13 +1449,75 @@ impl Handler<ApplyIndexingPlanRequest> for IndexingService {
 
     async fn handle(
         &mut self,
-        plan_request: ApplyIndexingPlanRequest,
+        mut plan_request: ApplyIndexingPlanRequest,
         ctx: &ActorContext<Self>,
     ) -> Result<Self::Reply, ActorExitStatus> {
-        Ok(self
+        if let Err(retry_after) = self
+            .apply_plan_rate_limit
+            .acquire::<ApplyIndexingPlanRequest>(plan_request.control_plane_node_id.clone())
+        {
+            warn!(
+                control_plane_node_id=%plan_request.control_plane_node_id,
+                "rejecting indexing plan: too many plans received from control plane"
+            );
+            let message = format!(
+                "too many indexing plans received from control plane node `{}`, retry in {}ms",
+                plan_request.control_plane_node_id,
+                retry_after.as_millis()
+            );
+            return Ok(Err(IndexingError::Unavailable(message)));
+        }
+        let base_plan_opt = self
+            .last_applied_plan_opt
+            .as_ref()
+            .filter(|last_applied_plan| {
+                last_applied_plan.control_plane_node_id == plan_request.control_plane_node_id
+            })
+            .map(|last_applied_plan| &last_applied_plan.applied_plan);
+
+        if let Err(error) = plan_request.resolve_shard_deltas(base_plan_opt) {
+            info!(
+                control_plane_node_id=%plan_request.control_plane_node_id,
+                "requesting full indexing plan: {error}"
+            );
+            return Ok(Err(error));
+        }
+        let apply_result = self
             .apply_indexing_plan(&plan_request.indexing_tasks, ctx)
-            .await
-            .map(|_| ApplyIndexingPlanResponse {}))
+            .await;
+
+        // The control plane discards the plan on error as well, so that the next plan is not
+        // delta-encoded.
+        self.last_applied_plan_opt = if apply_result.is_ok() {
+            Some(LastAppliedPlan {
+                control_plane_node_id: plan_request.control_plane_node_id,
+                applied_plan: AppliedIndexingPlan {
+                    plan_generation: plan_request.plan_generation,
+                    indexing_tasks: plan_request.indexing_tasks,
+                },
+            })
+        } else {
+            None
+        };
+        Ok(apply_result.map(|task_outcomes| ApplyIndexingPlanResponse { task_outcomes }))
+    }
+}
+
+#[async_trait]
+impl Handler<PromoteIndexingPipelineRequest> for IndexingService {
+    type Reply = Result<PromoteIndexingPipelineResponse, IndexingError>;
+
+    async fn handle(
+        &mut self,
+        promote_request: PromoteIndexingPipelineRequest,
+        ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let pipeline_uid = promote_request.pipeline_uid();
+        let promote_result = self.promote_pipeline(pipeline_uid, ctx).await;
+        // The control plane marks the task as a regular task in its next plan, but the running plan
+        // broadcast to the cluster should reflect the promotion right away.
+        self.update_chitchat_running_plan().await;
+        Ok(promote_result.map(|warm| PromoteIndexingPipelineResponse { warm }))
     }
 }
 
@@ -966,33 +1539,44 @@ impl Handler<Healthz> for IndexingService {
 struct IndexingPipelineDiff {
     pipelines_to_shutdown: Vec<PipelineUid>,
     pipelines_to_spawn: Vec<IndexingPipelineId>,
+    // Subset of the pipelines to spawn that are spawned as warm standbys.
+    standby_pipeline_uids: HashSet<PipelineUid>,
+    // Priority of the pipelines to spawn.
+    pipeline_priorities: HashMap<PipelineUid, IndexingTaskPriority>,
+    pipelines_to_promote: Vec<PipelineUid>,
+    // Running pipelines to switch to a new doc mapper, with their new parameters fingerprint.
+    pipelines_to_update: Vec<(PipelineUid, u64)>,
 }
 
 #[cfg(test)]
 mod tests {
-    use std::num::NonZeroUsize;
+    use std::num::{NonZeroU32, NonZeroUsize};
     use std::path::Path;
-    use std::time::Duration;
 
-    use quickwit_actors::{Health, ObservationType, Supervisable, Universe, HEARTBEAT};
+    use bytes::Bytes;
+    use quickwit_actors::{AskError, Health, ObservationType, Supervisable, Universe, HEARTBEAT};
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
     use quickwit_common::rand::append_random_suffix;
     use quickwit_common::ServiceStream;
     use quickwit_config::{
         IngestApiConfig, KafkaSourceParams, SourceConfig, SourceInputFormat, SourceParams,
-        VecSourceParams,
+        SourcePriority, VecSourceParams,
     };
     use quickwit_ingest::{init_ingest_api, CreateQueueIfNotExistsRequest};
+    use quickwit_metastore::checkpoint::{
+        IndexCheckpointDelta, PartitionId, SourceCheckpointDelta,
+    };
     use quickwit_metastore::{
         metastore_for_test, AddSourceRequestExt, CreateIndexRequestExt,
-        ListIndexesMetadataResponseExt, Split,
+        ListIndexesMetadataResponseExt, Split, StageSplitsRequestExt,
     };
     use quickwit_proto::indexing::IndexingTask;
     use quickwit_proto::metastore::{
-        AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, IndexMetadataResponse,
-        IndexesMetadataResponse, ListIndexesMetadataResponse, ListSplitsResponse,
-        MockMetastoreService,
+        AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, DeleteSourceRequest,
+        IndexMetadataResponse, IndexesMetadataResponse, ListIndexesMetadataResponse,
+        ListSplitsResponse, MockMetastoreService, PublishSplitsRequest, StageSplitsRequest,
     };
+    use quickwit_proto::types::Position;
 
     use super::*;
 
@@ -1071,6 +1655,10 @@ mod tests {
             source_id: "test-indexing-service--source-0".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1111,6 +1699,38 @@ mod tests {
         assert_eq!(observation.generation, 1);
         assert_eq!(observation.num_spawn_attempts, 1);
 
+        // Test pause and resume.
+        indexing_service
+            .ask_for_res(PausePipeline {
+                pipeline_uid: pipeline_id.pipeline_uid,
+            })
+            .await
+            .unwrap();
+        let observation = indexing_service_handle.observe().await;
+        assert_eq!(observation.num_running_pipelines, 1);
+        assert_eq!(
+            observation.paused_pipeline_uids,
+            vec![PipelineUid::for_test(1111u128)]
+        );
+        indexing_service
+            .ask_for_res(ResumePipeline {
+                pipeline_uid: pipeline_id.pipeline_uid,
+            })
+            .await
+            .unwrap();
+        let observation = indexing_service_handle.observe().await;
+        assert!(observation.paused_pipeline_uids.is_empty());
+        let error = indexing_service
+            .ask_for_res(PausePipeline {
+                pipeline_uid: PipelineUid::for_test(2222u128),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            AskError::ErrorReply(IndexingError::PipelineNotFound { .. })
+        ));
+
         // Test detach.
         let pipeline_handle = indexing_service
             .ask_for_res(DetachIndexingPipeline {
@@ -1148,6 +1768,10 @@ mod tests {
             source_id: "test-indexing-service--source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Vec(VecSourceParams {
                 docs: Vec::new(),
                 batch_num_docs: 10,
@@ -1189,9 +1813,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_indexing_service_apply_plan() {
-        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
-
+    async fn test_indexing_service_recreated_source_restarts_from_start_position() {
         quickwit_common::setup_logging_for_tests();
         let transport = ChannelTransport::default();
         let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
@@ -1199,32 +1821,232 @@ mod tests {
             .unwrap();
         let metastore = metastore_for_test();
 
-        let index_id = append_random_suffix("test-indexing-service");
+        let index_id = append_random_suffix("test-indexing-service-recreated-source");
         let index_uri = format!("ram:///indexes/{index_id}");
         let index_config = IndexConfig::for_test(&index_id, &index_uri);
 
-        let create_index_request =
-            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let docs = (0..4)
+            .map(|doc_idx| {
+                Bytes::from(format!(
+                    r#"{{"timestamp": 1700000000, "body": "doc-{doc_idx}"}}"#
+                ))
+            })
+            .collect();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::Vec(VecSourceParams {
+                docs,
+                batch_num_docs: 10,
+                partition: "0".to_string(),
+            }),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
         let index_uid: IndexUid = metastore
-            .create_index(create_index_request)
+            .create_index(
+                CreateIndexRequest::try_from_index_and_source_configs(
+                    &index_config,
+                    &[source_config.clone()],
+                )
+                .unwrap(),
+            )
             .await
             .unwrap()
             .index_uid()
             .clone();
-        let add_source_request = AddSourceRequest::try_from_source_config(
-            index_uid.clone(),
-            &SourceConfig::ingest_api_default(),
-        )
-        .unwrap();
-        metastore.add_source(add_source_request).await.unwrap();
-        let universe = Universe::new();
-        let temp_dir = tempfile::tempdir().unwrap();
-        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
-            temp_dir.path(),
-            &universe,
-            metastore.clone(),
-            cluster.clone(),
-        )
+
+        let fetch_index_metadata = || async {
+            metastore
+                .index_metadata(IndexMetadataRequest::for_index_id(index_id.clone()))
+                .await
+                .unwrap()
+                .deserialize_index_metadata()
+                .unwrap()
+        };
+        let partition_id = PartitionId::from("0");
+
+        // Runs the pipeline of the source on a fresh indexing service and returns the number of
+        // documents it indexed.
+        let run_pipeline_to_completion = |pipeline_uid: PipelineUid| {
+            let metastore = metastore.clone();
+            let cluster = cluster.clone();
+            let source_config = source_config.clone();
+            let index_id = index_id.clone();
+            async move {
+                let universe = Universe::with_accelerated_time();
+                let temp_dir = tempfile::tempdir().unwrap();
+                let (indexing_service, _indexing_service_handle) =
+                    spawn_indexing_service_for_test(temp_dir.path(), &universe, metastore, cluster)
+                        .await;
+                let pipeline_id = indexing_service
+                    .ask_for_res(SpawnPipeline {
+                        index_id,
+                        source_config,
+                        pipeline_uid,
+                    })
+                    .await
+                    .unwrap();
+                let pipeline_handle = indexing_service
+                    .ask_for_res(DetachIndexingPipeline { pipeline_id })
+                    .await
+                    .unwrap();
+                let (pipeline_exit_status, pipeline_statistics) = pipeline_handle.join().await;
+                assert!(pipeline_exit_status.is_success());
+                universe.quit().await;
+                pipeline_statistics.num_docs
+            }
+        };
+
+        // The first incarnation of the source indexes all the documents.
+        let num_docs = run_pipeline_to_completion(PipelineUid::for_test(1u128)).await;
+        assert_eq!(num_docs, 4);
+
+        let index_metadata = fetch_index_metadata().await;
+        let previous_incarnation = index_metadata
+            .checkpoint
+            .source_incarnation(&source_config.source_id)
+            .unwrap();
+        assert_eq!(
+            index_metadata
+                .checkpoint
+                .source_checkpoint(&source_config.source_id)
+                .unwrap()
+                .position_for_partition(&partition_id)
+                .unwrap(),
+            &Position::offset(3u64)
+        );
+
+        // The source is deleted and re-created with the same ID while the indexer is down.
+        metastore
+            .delete_source(DeleteSourceRequest {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_config.source_id.clone(),
+            })
+            .await
+            .unwrap();
+        metastore
+            .add_source(
+                AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // A split of the previous incarnation of the source is published after the source was
+        // re-created. Its checkpoint delta must be discarded.
+        let split_metadata = SplitMetadata {
+            split_id: "stale-split".to_string(),
+            index_uid: index_uid.clone(),
+            source_id: source_config.source_id.clone(),
+            ..Default::default()
+        };
+        metastore
+            .stage_splits(
+                StageSplitsRequest::try_from_split_metadata(index_uid.clone(), &split_metadata)
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let stale_checkpoint_delta = IndexCheckpointDelta {
+            source_id: source_config.source_id.clone(),
+            source_delta: SourceCheckpointDelta::from_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                Position::offset(3u64),
+            )
+            .unwrap(),
+            source_incarnation_opt: Some(previous_incarnation),
+        };
+        metastore
+            .publish_splits(PublishSplitsRequest {
+                index_uid: Some(index_uid.clone()),
+                staged_split_ids: vec![split_metadata.split_id.clone()],
+                index_checkpoint_delta_json_opt: Some(
+                    serde_json::to_string(&stale_checkpoint_delta).unwrap(),
+                ),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let index_metadata = fetch_index_metadata().await;
+        let current_incarnation = index_metadata
+            .checkpoint
+            .source_incarnation(&source_config.source_id)
+            .unwrap();
+        assert_ne!(current_incarnation, previous_incarnation);
+        assert!(index_metadata
+            .checkpoint
+            .source_checkpoint(&source_config.source_id)
+            .unwrap()
+            .is_empty());
+
+        // After the restart, the new incarnation of the source indexes the documents from the
+        // start position again instead of resuming from the stale checkpoint.
+        let num_docs = run_pipeline_to_completion(PipelineUid::for_test(2u128)).await;
+        assert_eq!(num_docs, 4);
+
+        let index_metadata = fetch_index_metadata().await;
+        assert_eq!(
+            index_metadata
+                .checkpoint
+                .source_incarnation(&source_config.source_id),
+            Some(current_incarnation)
+        );
+        assert_eq!(
+            index_metadata
+                .checkpoint
+                .source_checkpoint(&source_config.source_id)
+                .unwrap()
+                .position_for_partition(&partition_id)
+                .unwrap(),
+            &Position::offset(3u64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_apply_plan() {
+        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
+
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let add_source_request = AddSourceRequest::try_from_source_config(
+            index_uid.clone(),
+            &SourceConfig::ingest_api_default(),
+        )
+        .unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
+            temp_dir.path(),
+            &universe,
+            metastore.clone(),
+            cluster.clone(),
+        )
         .await;
 
         // Test `apply plan`.
@@ -1232,6 +2054,10 @@ mod tests {
             source_id: "test-indexing-service--source-1".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1252,6 +2078,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(0u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1259,12 +2087,25 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(1u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
         ];
-        indexing_service
-            .ask_for_res(ApplyIndexingPlanRequest { indexing_tasks })
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks,
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
             .await
             .unwrap();
+        assert_eq!(
+            apply_plan_response.task_outcomes,
+            [
+                IndexingTaskOutcome::new(PipelineUid::for_test(0u128), IndexingTaskStatus::Started),
+                IndexingTaskOutcome::new(PipelineUid::for_test(1u128), IndexingTaskStatus::Started),
+            ]
+        );
         assert_eq!(
             indexing_service_handle
                 .observe()
@@ -1283,6 +2124,10 @@ mod tests {
             source_id: "test-indexing-service--source-2".to_string(),
             num_pipelines: NonZeroUsize::new(2).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Kafka(kafka_params),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1298,6 +2143,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(3u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1305,6 +2152,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(1u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1312,6 +2161,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(2u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1319,14 +2170,33 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(4u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
         ];
-        indexing_service
+        let apply_plan_response = indexing_service
             .ask_for_res(ApplyIndexingPlanRequest {
                 indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
             })
             .await
             .unwrap();
+        let task_statuses: Vec<IndexingTaskStatus> = apply_plan_response
+            .task_outcomes
+            .iter()
+            .map(|task_outcome| task_outcome.status())
+            .collect();
+        assert_eq!(
+            task_statuses,
+            [
+                IndexingTaskStatus::Started,
+                IndexingTaskStatus::AlreadyRunning,
+                IndexingTaskStatus::Started,
+                IndexingTaskStatus::Started,
+            ]
+        );
+        assert!(!apply_plan_response.all_tasks_already_running());
         assert_eq!(
             indexing_service_handle
                 .observe()
@@ -1360,6 +2230,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(3u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1367,6 +2239,8 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(1u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
             IndexingTask {
                 index_uid: Some(metadata.index_uid.clone()),
@@ -1374,11 +2248,15 @@ mod tests {
                 shard_ids: Vec::new(),
                 pipeline_uid: Some(PipelineUid::for_test(4u128)),
                 params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
             },
         ];
         indexing_service
             .ask_for_res(ApplyIndexingPlanRequest {
                 indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -1418,6 +2296,8 @@ mod tests {
         indexing_service
             .ask_for_res(ApplyIndexingPlanRequest {
                 indexing_tasks: Vec::new(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -1429,6 +2309,523 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_indexing_service_apply_plan_reports_failed_tasks() {
+        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
+
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source-1".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let add_source_request =
+            AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config).unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
+            temp_dir.path(),
+            &universe,
+            metastore.clone(),
+            cluster.clone(),
+        )
+        .await;
+
+        let indexing_tasks = vec![
+            IndexingTask {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_config.source_id.clone(),
+                shard_ids: Vec::new(),
+                pipeline_uid: Some(PipelineUid::for_test(0u128)),
+                params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
+            },
+            IndexingTask {
+                index_uid: Some(index_uid.clone()),
+                source_id: "test-indexing-service--source-unknown".to_string(),
+                shard_ids: Vec::new(),
+                pipeline_uid: Some(PipelineUid::for_test(1u128)),
+                params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
+            },
+        ];
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(apply_plan_response.task_outcomes.len(), 2);
+
+        let started_outcome = &apply_plan_response.task_outcomes[0];
+        assert_eq!(started_outcome.status(), IndexingTaskStatus::Started);
+        assert!(started_outcome.error_message.is_none());
+
+        let failed_outcome = &apply_plan_response.task_outcomes[1];
+        assert_eq!(failed_outcome.pipeline_uid(), PipelineUid::for_test(1u128));
+        assert_eq!(failed_outcome.status(), IndexingTaskStatus::Failed);
+        assert!(failed_outcome
+            .error_message
+            .as_ref()
+            .unwrap()
+            .contains("test-indexing-service--source-unknown"));
+
+        // Reapplying the plan leaves the running pipeline untouched.
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks[..1].to_vec(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(apply_plan_response.all_tasks_already_running());
+
+        indexing_service_handle.quit().await;
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_apply_plan_with_shard_deltas() {
+        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
+
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source-1".to_string(),
+            num_pipelines: NonZeroUsize::MIN,
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let add_source_request =
+            AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config).unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
+            temp_dir.path(),
+            &universe,
+            metastore.clone(),
+            cluster.clone(),
+        )
+        .await;
+
+        let applied_plan = |plan_generation: u64, shard_ids: std::ops::Range<u64>| {
+            let indexing_task = IndexingTask {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_config.source_id.clone(),
+                shard_ids: shard_ids.map(ShardId::from).collect(),
+                pipeline_uid: Some(PipelineUid::for_test(0u128)),
+                params_fingerprint: PARAMS_FINGERPRINT,
+                standby: false,
+                priority: IndexingTaskPriority::Normal as i32,
+            };
+            AppliedIndexingPlan {
+                plan_generation,
+                indexing_tasks: vec![indexing_task],
+            }
+        };
+        let apply_plan_request =
+            |applied_plan: &AppliedIndexingPlan, base_plan_opt: Option<&AppliedIndexingPlan>| {
+                ApplyIndexingPlanRequest::new(
+                    "test-control-plane",
+                    applied_plan.plan_generation,
+                    &applied_plan.indexing_tasks,
+                    base_plan_opt,
+                )
+            };
+        let plan_1 = applied_plan(1, 0..10);
+        indexing_service
+            .ask_for_res(apply_plan_request(&plan_1, None))
+            .await
+            .unwrap();
+
+        // The shard list is delta-encoded against the last applied plan.
+        let plan_2 = applied_plan(2, 1..12);
+        let delta_request = apply_plan_request(&plan_2, Some(&plan_1));
+        assert!(delta_request.is_delta_encoded());
+
+        let apply_plan_response = indexing_service.ask_for_res(delta_request).await.unwrap();
+        assert_eq!(apply_plan_response.task_outcomes.len(), 1);
+        assert_ne!(
+            apply_plan_response.task_outcomes[0].status(),
+            IndexingTaskStatus::Failed
+        );
+        assert_eq!(
+            indexing_service_handle
+                .process_pending_and_observe()
+                .await
+                .num_running_pipelines,
+            1
+        );
+
+        // Plan 1 is no longer the last applied plan, so the indexer requests the full plan.
+        let plan_3 = applied_plan(3, 2..12);
+        let error = indexing_service
+            .ask_for_res(apply_plan_request(&plan_3, Some(&plan_1)))
+            .await
+            .unwrap_err();
+        let AskError::ErrorReply(IndexingError::PlanGenerationMismatch(_)) = error else {
+            panic!("expected a plan generation mismatch error, got `{error:?}`");
+        };
+        // Deltas from another control plane are not resolved against the plans of this one.
+        let mut other_control_plane_request = apply_plan_request(&plan_3, Some(&plan_2));
+        other_control_plane_request.control_plane_node_id = "other-control-plane".to_string();
+
+        let error = indexing_service
+            .ask_for_res(other_control_plane_request)
+            .await
+            .unwrap_err();
+        let AskError::ErrorReply(IndexingError::PlanGenerationMismatch(_)) = error else {
+            panic!("expected a plan generation mismatch error, got `{error:?}`");
+        };
+        // The rejected deltas left the last applied plan untouched.
+        indexing_service
+            .ask_for_res(apply_plan_request(&plan_3, Some(&plan_2)))
+            .await
+            .unwrap();
+
+        // After a full resync, deltas are resolved against the new plan.
+        let plan_4 = applied_plan(4, 5..15);
+        indexing_service
+            .ask_for_res(apply_plan_request(&plan_4, None))
+            .await
+            .unwrap();
+        let plan_5 = applied_plan(5, 6..16);
+        indexing_service
+            .ask_for_res(apply_plan_request(&plan_5, Some(&plan_4)))
+            .await
+            .unwrap();
+
+        indexing_service_handle.quit().await;
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_standby_pipelines() {
+        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
+
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            num_pipelines: NonZeroUsize::new(2).unwrap(),
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let add_source_request =
+            AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config).unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
+            temp_dir.path(),
+            &universe,
+            metastore.clone(),
+            cluster.clone(),
+        )
+        .await;
+
+        let mut indexing_tasks: Vec<IndexingTask> = (0..2)
+            .map(|pipeline_ord| IndexingTask {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_config.source_id.clone(),
+                shard_ids: Vec::new(),
+                pipeline_uid: Some(PipelineUid::for_test(pipeline_ord as u128)),
+                params_fingerprint: PARAMS_FINGERPRINT,
+                standby: true,
+                priority: IndexingTaskPriority::Normal as i32,
+            })
+            .collect();
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(apply_plan_response
+            .task_outcomes
+            .iter()
+            .all(|task_outcome| task_outcome.status() == IndexingTaskStatus::Started));
+
+        let indexing_service_obs = indexing_service_handle.observe().await;
+        assert_eq!(indexing_service_obs.num_running_pipelines, 2);
+        assert_eq!(
+            indexing_service_obs.standby_pipeline_uids,
+            [PipelineUid::for_test(0u128), PipelineUid::for_test(1u128)]
+        );
+        cluster
+            .wait_for_ready_members(
+                |members| {
+                    members.iter().any(|member| {
+                        member.indexing_tasks.len() == 2
+                            && member
+                                .indexing_tasks
+                                .iter()
+                                .all(|indexing_task| indexing_task.standby)
+                    })
+                },
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        // Promoting a pipeline directly.
+        let promote_response = indexing_service
+            .ask_for_res(PromoteIndexingPipelineRequest {
+                pipeline_uid: Some(PipelineUid::for_test(0u128)),
+            })
+            .await
+            .unwrap();
+        assert!(promote_response.warm);
+
+        let promote_error = indexing_service
+            .ask_for_res(PromoteIndexingPipelineRequest {
+                pipeline_uid: Some(PipelineUid::for_test(2u128)),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(promote_error, AskError::ErrorReply(_)));
+
+        // Promoting a pipeline through the indexing plan.
+        for indexing_task in &mut indexing_tasks {
+            indexing_task.standby = false;
+        }
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            apply_plan_response.task_outcomes[0].status(),
+            IndexingTaskStatus::AlreadyRunning
+        );
+        assert_eq!(
+            apply_plan_response.task_outcomes[1].status(),
+            IndexingTaskStatus::Promoted
+        );
+        let indexing_service_obs = indexing_service_handle.observe().await;
+        assert_eq!(indexing_service_obs.num_running_pipelines, 2);
+        assert!(indexing_service_obs.standby_pipeline_uids.is_empty());
+
+        cluster
+            .wait_for_ready_members(
+                |members| {
+                    members.iter().any(|member| {
+                        member.indexing_tasks.len() == 2
+                            && member
+                                .indexing_tasks
+                                .iter()
+                                .all(|indexing_task| !indexing_task.standby)
+                    })
+                },
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        indexing_service_handle.quit().await;
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_indexing_service_pipeline_priorities() {
+        const PARAMS_FINGERPRINT: u64 = 3865067856550546352u64;
+
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            num_pipelines: NonZeroUsize::new(2).unwrap(),
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let add_source_request =
+            AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config).unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+
+        let universe = Universe::new();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) = spawn_indexing_service_for_test(
+            temp_dir.path(),
+            &universe,
+            metastore.clone(),
+            cluster.clone(),
+        )
+        .await;
+
+        let indexing_tasks: Vec<IndexingTask> =
+            [IndexingTaskPriority::High, IndexingTaskPriority::Background]
+                .into_iter()
+                .enumerate()
+                .map(|(pipeline_ord, priority)| IndexingTask {
+                    index_uid: Some(index_uid.clone()),
+                    source_id: source_config.source_id.clone(),
+                    shard_ids: Vec::new(),
+                    pipeline_uid: Some(PipelineUid::for_test(pipeline_ord as u128)),
+                    params_fingerprint: PARAMS_FINGERPRINT,
+                    standby: false,
+                    priority: priority as i32,
+                })
+                .collect();
+        indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks: indexing_tasks.clone(),
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let indexing_service_obs = indexing_service_handle.observe().await;
+        assert_eq!(indexing_service_obs.num_running_pipelines, 2);
+        assert_eq!(
+            indexing_service_obs.pipeline_priorities,
+            BTreeMap::from_iter([
+                (PipelineUid::for_test(0u128), IndexingTaskPriority::High),
+                (
+                    PipelineUid::for_test(1u128),
+                    IndexingTaskPriority::Background
+                ),
+            ])
+        );
+        // The priorities are broadcast along with the running plan, so the plan is considered
+        // applied by the control plane.
+        cluster
+            .wait_for_ready_members(
+                |members| {
+                    members.iter().any(|member| {
+                        let mut member_indexing_tasks = member.indexing_tasks.clone();
+                        member_indexing_tasks.sort_unstable_by_key(|task| task.pipeline_uid);
+                        member_indexing_tasks == indexing_tasks
+                    })
+                },
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        let apply_plan_response = indexing_service
+            .ask_for_res(ApplyIndexingPlanRequest {
+                indexing_tasks,
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(apply_plan_response.all_tasks_already_running());
+
+        indexing_service_handle.quit().await;
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_indexing_service_shutdown_merge_pipeline_when_no_indexing_pipeline() {
         quickwit_common::setup_logging_for_tests();
@@ -1446,6 +2843,10 @@ mod tests {
             source_id: "test-indexing-service--source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1575,6 +2976,10 @@ mod tests {
             source_id: "test-indexing-service--source".to_string(),
             num_pipelines: NonZeroUsize::MIN,
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -1709,6 +3114,75 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_indexing_service_apply_plan_rate_limit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let universe = Universe::new();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let mut indexer_config = IndexerConfig::for_test().unwrap();
+        indexer_config.max_apply_plan_requests_per_minute = NonZeroU32::new(1).unwrap();
+        indexer_config.apply_plan_burst_limit = NonZeroU32::new(2).unwrap();
+
+        let merge_scheduler_mailbox: Mailbox<MergeSchedulerService> = universe.get_or_spawn_one();
+        let indexing_service = IndexingService::new(
+            NodeId::from("test-node"),
+            temp_dir.path().to_path_buf(),
+            indexer_config,
+            1,
+            cluster,
+            metastore_for_test(),
+            None,
+            merge_scheduler_mailbox,
+            IngesterPool::default(),
+            StorageResolver::unconfigured(),
+            EventBroker::default(),
+        )
+        .await
+        .unwrap();
+        let (indexing_service_mailbox, _indexing_service_handle) =
+            universe.spawn_builder().spawn(indexing_service);
+
+        let apply_plan_throttled_total = crate::metrics::INDEXER_METRICS
+            .apply_plan_throttled_total
+            .with_label_values(["apply_indexing_plan"]);
+        let num_throttled_before = apply_plan_throttled_total.get();
+
+        let apply_plan_request = |control_plane_node_id: &str| ApplyIndexingPlanRequest {
+            indexing_tasks: Vec::new(),
+            control_plane_node_id: control_plane_node_id.to_string(),
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            indexing_service_mailbox
+                .ask_for_res(apply_plan_request("control-plane-1"))
+                .await
+                .unwrap();
+        }
+        for _ in 0..3 {
+            let error = indexing_service_mailbox
+                .ask_for_res(apply_plan_request("control-plane-1"))
+                .await
+                .unwrap_err();
+            let AskError::ErrorReply(IndexingError::Unavailable(message)) = error else {
+                panic!("expected an unavailable error, got `{error:?}`");
+            };
+            assert!(message.contains("control-plane-1"));
+            assert!(message.contains("retry in"));
+        }
+        assert_eq!(apply_plan_throttled_total.get(), num_throttled_before + 3);
+
+        // Plans from other control plane nodes are rate limited independently.
+        indexing_service_mailbox
+            .ask_for_res(apply_plan_request("control-plane-2"))
+            .await
+            .unwrap();
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_indexing_service_apply_indexing_plan_batches_metastore_calls() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -1809,7 +3283,16 @@ mod tests {
                         source_id: "test-source".to_string(),
                         shard_ids: Vec::new(),
                         pipeline_uid: Some(PipelineUid::for_test(0)),
-                        params_fingerprint: 0,
+                        // Matches the fingerprint of the running pipeline, which must not be
+                        // updated.
+                        params_fingerprint: IndexMetadata::for_test(
+                            "test-index-0",
+                            "ram:///indexes/test-index-0",
+                        )
+                        .index_config
+                        .indexing_params_fingerprint(),
+                        standby: false,
+                        priority: IndexingTaskPriority::Normal as i32,
                     },
                     IndexingTask {
                         index_uid: Some(IndexUid::for_test("test-index-1", 0)),
@@ -1817,6 +3300,8 @@ mod tests {
                         shard_ids: Vec::new(),
                         pipeline_uid: Some(PipelineUid::for_test(1)),
                         params_fingerprint: 0,
+                        standby: false,
+                        priority: IndexingTaskPriority::Normal as i32,
                     },
                     IndexingTask {
                         index_uid: Some(IndexUid::for_test("test-index-2", 0)),
@@ -1824,8 +3309,12 @@ mod tests {
                         shard_ids: Vec::new(),
                         pipeline_uid: Some(PipelineUid::for_test(2)),
                         params_fingerprint: 0,
+                        standby: false,
+                        priority: IndexingTaskPriority::Normal as i32,
                     },
                 ],
+                control_plane_node_id: "test-control-plane".to_string(),
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -139,6 +139,7 @@ impl Handler<MergeScratch> for MergeExecutor {
                     publish_token_opt: None,
                     batch_parent_span: merge_task.merge_parent_span.clone(),
                     merge_task_opt: Some(merge_task),
+                    batch_created_at_opt: None,
                 },
             )
             .await?;
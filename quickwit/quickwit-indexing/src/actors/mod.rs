@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod adaptive_commit_timeout;
 mod cooperative_indexing;
 mod doc_processor;
 mod index_serializer;
@@ -35,10 +36,14 @@ mod uploader;
 #[cfg(feature = "vrl")]
 mod vrl_processing;
 
-pub use doc_processor::{DocProcessor, DocProcessorCounters};
+pub use cooperative_indexing::IndexingPermits;
+pub use doc_processor::{
+    DocProcessingErrorSample, DocProcessingErrorSampler, DocProcessingErrorSamplingSettings,
+    DocProcessor, DocProcessorCounters,
+};
 pub use index_serializer::IndexSerializer;
 pub use indexer::{Indexer, IndexerCounters};
-pub use indexing_pipeline::{IndexingPipeline, IndexingPipelineParams};
+pub use indexing_pipeline::{IndexingPipeline, IndexingPipelineParams, RestartFailedPipeline};
 pub use indexing_service::{IndexingService, IndexingServiceCounters, INDEXING_DIR_NAME};
 pub use merge_executor::{combine_partition_ids, merge_split_attrs, MergeExecutor};
 pub use merge_pipeline::MergePipeline;
@@ -31,7 +31,10 @@ use quickwit_common::runtimes::RuntimeType;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_directories::write_hotcache;
 use quickwit_doc_mapper::tag_pruning::append_to_tag_set;
-use quickwit_doc_mapper::NamedField;
+use quickwit_doc_mapper::term_presence::{
+    TermPresenceFilter, TermPresenceFilterConfig, TermPresenceFilters,
+};
+use quickwit_doc_mapper::{DocMapper, NamedField};
 use quickwit_proto::search::{
     serialize_split_fields, ListFieldType, ListFields, ListFieldsEntryResponse,
 };
@@ -50,7 +53,7 @@ const MAX_VALUES_PER_TAG_FIELD: usize = if cfg!(any(test, feature = "testsuite")
 
 use crate::actors::Uploader;
 use crate::models::{
-    EmptySplit, IndexedSplit, IndexedSplitBatch, PackagedSplit, PackagedSplitBatch,
+    DocMapperUpdate, EmptySplit, IndexedSplit, IndexedSplitBatch, PackagedSplit, PackagedSplitBatch,
 };
 
 /// The role of the packager is to get an index writer and
@@ -59,6 +62,7 @@ use crate::models::{
 /// This includes the following steps:
 /// - commit: this step is CPU heavy
 /// - identifying the list of tags for the splits, and labelling it accordingly
+/// - building the term presence filters of the splits
 /// - creating a bundle file
 /// - computing the hotcache
 /// - appending it to the split file.
@@ -70,21 +74,32 @@ pub struct Packager {
     uploader_mailbox: Mailbox<Uploader>,
     /// List of tag fields ([`Vec<NamedField>`]) defined in the index config.
     tag_fields: Vec<NamedField>,
+    /// List of term presence filter fields defined in the index config.
+    term_presence_fields: Vec<(NamedField, TermPresenceFilterConfig)>,
 }
 
 impl Packager {
     pub fn new(
         actor_name: &'static str,
         tag_fields: Vec<NamedField>,
+        term_presence_fields: Vec<(NamedField, TermPresenceFilterConfig)>,
         uploader_mailbox: Mailbox<Uploader>,
     ) -> Packager {
         Packager {
             actor_name,
             uploader_mailbox,
             tag_fields,
+            term_presence_fields,
         }
     }
 
+    /// Resolves the tag and term presence filter fields against the schema of the new doc mapper.
+    fn update_doc_mapper(&mut self, doc_mapper: &dyn DocMapper) -> anyhow::Result<()> {
+        self.tag_fields = doc_mapper.tag_named_fields()?;
+        self.term_presence_fields = doc_mapper.term_presence_named_fields()?;
+        Ok(())
+    }
+
     pub async fn process_indexed_split(
         &self,
         split: IndexedSplit,
@@ -92,8 +107,13 @@ impl Packager {
     ) -> anyhow::Result<PackagedSplit> {
         let segment_metas = split.index.searchable_segment_metas()?;
         assert_eq!(segment_metas.len(), 1);
-        let packaged_split =
-            create_packaged_split(&segment_metas[..], split, &self.tag_fields, ctx)?;
+        let packaged_split = create_packaged_split(
+            &segment_metas[..],
+            split,
+            &self.tag_fields,
+            &self.term_presence_fields,
+            ctx,
+        )?;
         Ok(packaged_split)
     }
 }
@@ -162,6 +182,7 @@ impl Handler<IndexedSplitBatch> for Packager {
                 batch.publish_token_opt,
                 batch.merge_task_opt,
                 batch.batch_parent_span,
+                batch.batch_created_at_opt,
             ),
         )
         .await?;
@@ -170,6 +191,20 @@ impl Handler<IndexedSplitBatch> for Packager {
     }
 }
 
+#[async_trait]
+impl Handler<DocMapperUpdate> for Packager {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        message: DocMapperUpdate,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.update_doc_mapper(&*message.doc_mapper)?;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<EmptySplit> for Packager {
     type Reply = ();
@@ -222,7 +257,7 @@ fn build_hotcache<W: io::Write>(split_path: &Path, out: &mut W) -> anyhow::Resul
 /// field term dictionary.
 ///
 /// returns None if:
-/// - the number of terms exceed MAX_VALUES_PER_TAG_FIELD
+/// - the number of terms exceed `max_terms`
 /// - some of the terms are not value utf8.
 /// - an error occurs.
 ///
@@ -239,7 +274,7 @@ fn try_extract_terms(
         .sum::<usize>();
     if num_terms > max_terms {
         bail!(
-            "number of unique terms for tag field {} > {}",
+            "number of unique terms for field {} > {}",
             named_field.name,
             max_terms
         );
@@ -277,6 +312,7 @@ fn create_packaged_split(
     segment_metas: &[SegmentMeta],
     split: IndexedSplit,
     tag_fields: &[NamedField],
+    term_presence_fields: &[(NamedField, TermPresenceFilterConfig)],
     ctx: &ActorContext<Packager>,
 ) -> anyhow::Result<PackagedSplit> {
     debug!(split_id = split.split_id(), "create-packaged-split");
@@ -312,6 +348,36 @@ fn create_packaged_split(
         }
     }
 
+    // Builds term presence filters only when a field cardinality allows the filter to meet its
+    // false positive rate within its size budget.
+    let mut term_presence_filters = TermPresenceFilters::default();
+    for (named_field, term_presence_filter_config) in term_presence_fields {
+        let inverted_indexes = index_reader
+            .searcher()
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.inverted_index(named_field.field))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match try_extract_terms(
+            named_field,
+            &inverted_indexes,
+            term_presence_filter_config.max_num_values(),
+        ) {
+            Ok(terms) => {
+                let term_presence_filter = TermPresenceFilter::build(
+                    terms.iter().map(String::as_str),
+                    term_presence_filter_config.false_positive_rate,
+                );
+                term_presence_filters.insert(named_field.name.clone(), term_presence_filter);
+            }
+            Err(term_extraction_error) => {
+                warn!(err=?term_extraction_error, "no term presence filter will be registered in the split metadata");
+            }
+        }
+        ctx.record_progress();
+    }
+
     ctx.record_progress();
 
     debug!(split_id = split.split_id(), "build-hotcache");
@@ -326,6 +392,7 @@ fn create_packaged_split(
         split_attrs: split.split_attrs,
         split_scratch_directory: split.split_scratch_directory,
         tags,
+        term_presence_filters,
         split_files,
         hotcache_bytes,
     };
@@ -386,6 +453,8 @@ mod tests {
     use std::ops::RangeInclusive;
 
     use quickwit_actors::{ObservationType, Universe};
+    use quickwit_config::{build_doc_mapper, SearchSettings};
+    use quickwit_doc_mapper::{classify_doc_mapping_update, DocMapping};
     use quickwit_metastore::checkpoint::IndexCheckpointDelta;
     use quickwit_proto::search::{deserialize_split_fields, ListFieldsEntryResponse};
     use quickwit_proto::types::{DocMappingUid, IndexUid, NodeId};
@@ -566,7 +635,25 @@ mod tests {
                 "tag_str", "tag_many", "tag_u64", "tag_i64", "tag_f64", "tag_bool",
             ],
         );
-        let packager = Packager::new("TestPackager", tag_fields, mailbox);
+        let term_presence_fields =
+            get_tag_fields(indexed_split.index.schema(), &["tag_many", "tag_str"])
+                .into_iter()
+                .map(|named_field| {
+                    let max_num_bytes = if named_field.name == "tag_str" {
+                        // Too small to hold a single value.
+                        1
+                    } else {
+                        TermPresenceFilterConfig::default_max_num_bytes()
+                    };
+                    let term_presence_filter_config = TermPresenceFilterConfig {
+                        field: named_field.name.clone(),
+                        false_positive_rate: 0.01,
+                        max_num_bytes,
+                    };
+                    (named_field, term_presence_filter_config)
+                })
+                .collect();
+        let packager = Packager::new("TestPackager", tag_fields, term_presence_fields, mailbox);
         let (packager_mailbox, packager_handle) = universe.spawn_builder().spawn(packager);
         packager_mailbox
             .send_message(IndexedSplitBatch {
@@ -576,6 +663,7 @@ mod tests {
                 publish_token_opt: None,
                 merge_task_opt: None,
                 batch_parent_span: Span::none(),
+                batch_created_at_opt: None,
             })
             .await?;
         assert_eq!(
@@ -603,6 +691,12 @@ mod tests {
                 "tag_u64:42"
             ]
         );
+        assert_eq!(split.term_presence_filters.len(), 1);
+        let term_presence_filter = &split.term_presence_filters["tag_many"];
+        for num in 1..10 {
+            assert!(term_presence_filter.may_contain(&format!("many-{num}")));
+        }
+        assert!(!term_presence_filter.may_contain("many-10"));
         assert_eq!(
             split.split_attrs.time_range,
             Some(
@@ -613,4 +707,51 @@ mod tests {
         universe.assert_quit().await;
         Ok(())
     }
+    #[tokio::test]
+    async fn test_packager_update_doc_mapper() {
+        let universe = Universe::with_accelerated_time();
+        let (uploader_mailbox, _uploader_inbox) = universe.create_test_mailbox();
+
+        let doc_mapping: DocMapping = serde_json::from_str(
+            r#"{
+                "field_mappings": [
+                    {"name": "body", "type": "text"},
+                    {"name": "tenant", "type": "text", "tokenizer": "raw"}
+                ],
+                "tag_fields": ["tenant"],
+                "term_presence_filters": [{"field": "tenant"}]
+            }"#,
+        )
+        .unwrap();
+        let doc_mapper = build_doc_mapper(&doc_mapping, &SearchSettings::default()).unwrap();
+
+        // Inserting a field before `tenant` is a compatible update that shifts its field ID.
+        let mut new_doc_mapping = doc_mapping.clone();
+        new_doc_mapping.field_mappings.insert(
+            1,
+            serde_json::from_str(r#"{"name": "severity", "type": "text"}"#).unwrap(),
+        );
+        assert!(classify_doc_mapping_update(&doc_mapping, &new_doc_mapping).is_compatible());
+        let new_doc_mapper =
+            build_doc_mapper(&new_doc_mapping, &SearchSettings::default()).unwrap();
+
+        let tenant_field = doc_mapper.schema().get_field("tenant").unwrap();
+        let new_tenant_field = new_doc_mapper.schema().get_field("tenant").unwrap();
+        assert_ne!(tenant_field, new_tenant_field);
+
+        let mut packager = Packager::new(
+            "TestPackager",
+            doc_mapper.tag_named_fields().unwrap(),
+            doc_mapper.term_presence_named_fields().unwrap(),
+            uploader_mailbox,
+        );
+        assert_eq!(packager.tag_fields[0].field, tenant_field);
+
+        packager.update_doc_mapper(&*new_doc_mapper).unwrap();
+        assert_eq!(packager.tag_fields.len(), 1);
+        assert_eq!(packager.tag_fields[0].field, new_tenant_field);
+        assert_eq!(packager.term_presence_fields.len(), 1);
+        assert_eq!(packager.term_presence_fields[0].0.field, new_tenant_field);
+        universe.assert_quit().await;
+    }
 }
@@ -21,12 +21,15 @@ use anyhow::Context;
 use async_trait::async_trait;
 use fail::fail_point;
 use quickwit_actors::{Actor, ActorContext, Handler, Mailbox, QueueCapacity};
+use quickwit_common::pubsub::EventBroker;
 use quickwit_proto::metastore::{MetastoreService, MetastoreServiceClient, PublishSplitsRequest};
+use quickwit_proto::types::PipelineUid;
 use serde::Serialize;
-use tracing::{info, instrument, warn};
+use time::OffsetDateTime;
+use tracing::{info, info_span, warn, Instrument, Span};
 
 use crate::actors::MergePlanner;
-use crate::models::{NewSplits, SplitsUpdate};
+use crate::models::{NewSplits, SplitsPublished, SplitsUpdate};
 use crate::source::{SourceActor, SuggestTruncate};
 
 #[derive(Clone, Debug, Default, Serialize)]
@@ -57,6 +60,8 @@ pub struct Publisher {
     metastore: MetastoreServiceClient,
     merge_planner_mailbox_opt: Option<Mailbox<MergePlanner>>,
     source_mailbox_opt: Option<Mailbox<SourceActor>>,
+    pipeline_uid_opt: Option<PipelineUid>,
+    event_broker_opt: Option<EventBroker>,
     counters: PublisherCounters,
 }
 
@@ -72,9 +77,53 @@ impl Publisher {
             metastore,
             merge_planner_mailbox_opt,
             source_mailbox_opt,
+            pipeline_uid_opt: None,
+            event_broker_opt: None,
             counters: PublisherCounters::default(),
         }
     }
+
+    /// Sets the UID of the indexing pipeline owning this publisher, so that it can be attached to
+    /// the publish spans.
+    pub fn with_pipeline_uid(mut self, pipeline_uid: PipelineUid) -> Self {
+        self.pipeline_uid_opt = Some(pipeline_uid);
+        self
+    }
+
+    /// Sets the event broker on which a [`SplitsPublished`] event is published for each batch of
+    /// new splits.
+    pub fn with_event_broker(mut self, event_broker: EventBroker) -> Self {
+        self.event_broker_opt = Some(event_broker);
+        self
+    }
+
+    /// Creates the span covering the publication of a batch of splits and the propagation of the
+    /// new checkpoint to the source (and, for ingest sources, to the shard positions).
+    fn publish_span(&self, split_update: &SplitsUpdate) -> Span {
+        let source_id: &str = split_update
+            .checkpoint_delta_opt
+            .as_ref()
+            .map(|checkpoint_delta| checkpoint_delta.source_id.as_str())
+            .unwrap_or_default();
+        let shard_ids: Vec<&str> = split_update
+            .checkpoint_delta_opt
+            .iter()
+            .flat_map(|checkpoint_delta| checkpoint_delta.source_delta.partitions())
+            .map(|partition_id| partition_id.as_str())
+            .collect();
+        let pipeline_uid: String = self
+            .pipeline_uid_opt
+            .map(|pipeline_uid| pipeline_uid.short_id())
+            .unwrap_or_default();
+        info_span!(
+            parent: split_update.parent_span.id(),
+            "publisher",
+            index_uid=%split_update.index_uid,
+            source_id=%source_id,
+            pipeline_uid=%pipeline_uid,
+            shard_ids=?shard_ids,
+        )
+    }
 }
 
 #[async_trait]
@@ -101,11 +150,23 @@ impl Actor for Publisher {
 impl Handler<SplitsUpdate> for Publisher {
     type Reply = ();
 
-    #[instrument(name="publisher", parent=split_update.parent_span.id(),  skip(self, ctx))]
     async fn handle(
         &mut self,
         split_update: SplitsUpdate,
         ctx: &ActorContext<Self>,
+    ) -> Result<(), quickwit_actors::ActorExitStatus> {
+        let publish_span = self.publish_span(&split_update);
+        self.publish(split_update, ctx)
+            .instrument(publish_span)
+            .await
+    }
+}
+
+impl Publisher {
+    async fn publish(
+        &mut self,
+        split_update: SplitsUpdate,
+        ctx: &ActorContext<Self>,
     ) -> Result<(), quickwit_actors::ActorExitStatus> {
         fail_point!("publisher:before");
 
@@ -116,6 +177,7 @@ impl Handler<SplitsUpdate> for Publisher {
             checkpoint_delta_opt,
             publish_lock,
             publish_token_opt,
+            batch_created_at_opt,
             ..
         } = split_update;
 
@@ -130,7 +192,7 @@ impl Handler<SplitsUpdate> for Publisher {
             .collect();
         if let Some(_guard) = publish_lock.acquire().await {
             let publish_splits_request = PublishSplitsRequest {
-                index_uid: Some(index_uid),
+                index_uid: Some(index_uid.clone()),
                 staged_split_ids: split_ids.clone(),
                 replaced_split_ids: replaced_split_ids.clone(),
                 index_checkpoint_delta_json_opt,
@@ -148,6 +210,19 @@ impl Handler<SplitsUpdate> for Publisher {
             return Ok(());
         }
         info!("publish-new-splits");
+        if let (Some(event_broker), Some(batch_created_at)) =
+            (&self.event_broker_opt, batch_created_at_opt)
+        {
+            if !new_splits.is_empty() {
+                let splits_published = SplitsPublished::new(
+                    index_uid,
+                    batch_created_at,
+                    OffsetDateTime::now_utc(),
+                    &new_splits,
+                );
+                event_broker.publish(splits_published);
+            }
+        }
         if let Some(source_mailbox) = self.source_mailbox_opt.as_ref() {
             if let Some(checkpoint) = checkpoint_delta_opt {
                 // We voluntarily do not log anything here.
@@ -159,7 +234,10 @@ impl Handler<SplitsUpdate> for Publisher {
                 let suggest_truncate_res = ctx
                     .send_message(
                         source_mailbox,
-                        SuggestTruncate(checkpoint.source_delta.get_source_checkpoint()),
+                        SuggestTruncate {
+                            checkpoint: checkpoint.source_delta.get_source_checkpoint(),
+                            parent_span: Span::current(),
+                        },
                     )
                     .await;
                 if let Err(send_truncate_err) = suggest_truncate_res {
@@ -194,17 +272,45 @@ impl Handler<SplitsUpdate> for Publisher {
 
 #[cfg(test)]
 mod tests {
-    use quickwit_actors::Universe;
+    use std::sync::{Arc, Mutex};
+
+    use quickwit_actors::{ActorContext, Universe};
     use quickwit_metastore::checkpoint::{
         IndexCheckpointDelta, PartitionId, SourceCheckpoint, SourceCheckpointDelta,
     };
     use quickwit_metastore::{PublishSplitsRequestExt, SplitMetadata};
     use quickwit_proto::metastore::{EmptyResponse, MockMetastoreService};
-    use quickwit_proto::types::{IndexUid, Position};
+    use quickwit_proto::types::{IndexUid, PipelineUid, Position};
+    use tokio::sync::watch;
     use tracing::Span;
 
     use super::*;
-    use crate::models::PublishLock;
+    use crate::models::{PublishLock, PublishedSplit};
+
+    /// Buffer collecting the logs emitted by a test subscriber.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl CapturedLogs {
+        fn lines(&self) -> Vec<String> {
+            let logs = self.0.lock().unwrap();
+            String::from_utf8_lossy(&logs)
+                .lines()
+                .map(|line| line.to_string())
+                .collect()
+        }
+    }
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
 
     #[tokio::test]
     async fn test_publisher_publish_operation() {
@@ -250,11 +356,13 @@ mod tests {
                 checkpoint_delta_opt: Some(IndexCheckpointDelta {
                     source_id: "source".to_string(),
                     source_delta: SourceCheckpointDelta::from_range(1..3),
+                    source_incarnation_opt: None,
                 }),
                 publish_lock: PublishLock::default(),
                 publish_token_opt: None,
                 merge_task: None,
                 parent_span: tracing::Span::none(),
+                batch_created_at_opt: None,
             })
             .await
             .is_ok());
@@ -265,7 +373,7 @@ mod tests {
         let suggest_truncate_checkpoints: Vec<SourceCheckpoint> = source_inbox
             .drain_for_test_typed::<SuggestTruncate>()
             .into_iter()
-            .map(|msg| msg.0)
+            .map(|suggest_truncate| suggest_truncate.checkpoint)
             .collect();
 
         assert_eq!(suggest_truncate_checkpoints.len(), 1);
@@ -282,6 +390,77 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_publisher_publishes_splits_published_event() {
+        let universe = Universe::with_accelerated_time();
+        let ref_index_uid: IndexUid = IndexUid::for_test("index", 1);
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_publish_splits()
+            .times(2)
+            .returning(|_| Ok(EmptyResponse {}));
+        let event_broker = EventBroker::default();
+        let (splits_published_tx, splits_published_rx) = flume::unbounded();
+        event_broker
+            .subscribe(move |splits_published: SplitsPublished| {
+                splits_published_tx.send(splits_published).unwrap();
+            })
+            .forever();
+        let publisher = Publisher::new(
+            PublisherType::MainPublisher,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            None,
+        )
+        .with_event_broker(event_broker);
+        let (publisher_mailbox, publisher_handle) = universe.spawn_builder().spawn(publisher);
+
+        let batch_created_at = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        for batch_created_at_opt in [Some(batch_created_at), None] {
+            publisher_mailbox
+                .send_message(SplitsUpdate {
+                    index_uid: ref_index_uid.clone(),
+                    new_splits: vec![SplitMetadata {
+                        split_id: "split".to_string(),
+                        time_range: Some(1_699_999_990..=1_699_999_999),
+                        ..Default::default()
+                    }],
+                    replaced_split_ids: Vec::new(),
+                    checkpoint_delta_opt: None,
+                    publish_lock: PublishLock::default(),
+                    publish_token_opt: None,
+                    merge_task: None,
+                    parent_span: Span::none(),
+                    batch_created_at_opt,
+                })
+                .await
+                .unwrap();
+        }
+        let publisher_observation = publisher_handle.process_pending_and_observe().await.state;
+        assert_eq!(publisher_observation.num_published_splits, 2);
+
+        // The update without batch creation time does not produce any event.
+        let splits_published = splits_published_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(splits_published.index_uid, ref_index_uid);
+        assert_eq!(splits_published.batch_created_at, batch_created_at);
+        assert!(splits_published.published_at > batch_created_at);
+        assert_eq!(
+            splits_published.splits,
+            [PublishedSplit {
+                split_id: "split".to_string(),
+                max_doc_timestamp_opt: Some(
+                    OffsetDateTime::from_unix_timestamp(1_699_999_999).unwrap()
+                ),
+            }]
+        );
+        assert!(splits_published_rx
+            .recv_timeout(std::time::Duration::from_millis(100))
+            .is_err());
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_publisher_publish_operation_with_empty_splits() {
         let universe = Universe::with_accelerated_time();
@@ -323,11 +502,13 @@ mod tests {
                 checkpoint_delta_opt: Some(IndexCheckpointDelta {
                     source_id: "source".to_string(),
                     source_delta: SourceCheckpointDelta::from_range(1..3),
+                    source_incarnation_opt: None,
                 }),
                 publish_lock: PublishLock::default(),
                 publish_token_opt: None,
                 merge_task: None,
                 parent_span: tracing::Span::none(),
+                batch_created_at_opt: None,
             })
             .await
             .is_ok());
@@ -340,7 +521,7 @@ mod tests {
         let suggest_truncate_checkpoints: Vec<SourceCheckpoint> = source_inbox
             .drain_for_test_typed::<SuggestTruncate>()
             .into_iter()
-            .map(|msg| msg.0)
+            .map(|suggest_truncate| suggest_truncate.checkpoint)
             .collect();
 
         assert_eq!(suggest_truncate_checkpoints.len(), 1);
@@ -394,6 +575,7 @@ mod tests {
             publish_token_opt: None,
             merge_task: None,
             parent_span: Span::none(),
+            batch_created_at_opt: None,
         };
         assert!(publisher_mailbox
             .send_message(publisher_message)
@@ -436,6 +618,7 @@ mod tests {
                 publish_token_opt: None,
                 merge_task: None,
                 parent_span: Span::none(),
+                batch_created_at_opt: None,
             })
             .await
             .unwrap();
@@ -447,4 +630,101 @@ mod tests {
         assert!(merger_messages.is_empty());
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_publisher_publish_span() {
+        let captured_logs = CapturedLogs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_max_level(tracing::Level::INFO)
+            .with_writer({
+                let captured_logs = captured_logs.clone();
+                move || captured_logs.clone()
+            })
+            .finish();
+        let _subscriber_guard = tracing::subscriber::set_default(subscriber);
+
+        let universe = Universe::with_accelerated_time();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_publish_splits()
+            .times(1)
+            .returning(|_| Ok(EmptyResponse {}));
+        let (source_mailbox, source_inbox) = universe.create_test_mailbox();
+        let pipeline_uid: PipelineUid = "01HAV29D4XY3D462FS3D8K5Q2H".parse().unwrap();
+        let mut publisher = Publisher::new(
+            PublisherType::MainPublisher,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+            Some(source_mailbox),
+        )
+        .with_pipeline_uid(pipeline_uid);
+        let (publisher_mailbox, _publisher_inbox) = universe.create_test_mailbox::<Publisher>();
+        let (observable_state_tx, _observable_state_rx) =
+            watch::channel(PublisherCounters::default());
+        let ctx = ActorContext::for_test(&universe, publisher_mailbox, observable_state_tx);
+
+        let mut source_delta = SourceCheckpointDelta::default();
+        for shard_id in ["shard-1", "shard-2"] {
+            source_delta
+                .record_partition_delta(
+                    PartitionId::from(shard_id),
+                    Position::Beginning,
+                    Position::offset(10u64),
+                )
+                .unwrap();
+        }
+        let index_uid: IndexUid = IndexUid::for_test("index", 1);
+        let split_update = SplitsUpdate {
+            index_uid: index_uid.clone(),
+            new_splits: vec![SplitMetadata {
+                split_id: "split".to_string(),
+                ..Default::default()
+            }],
+            replaced_split_ids: Vec::new(),
+            checkpoint_delta_opt: Some(IndexCheckpointDelta {
+                source_id: "source".to_string(),
+                source_delta,
+                source_incarnation_opt: None,
+            }),
+            publish_lock: PublishLock::default(),
+            publish_token_opt: None,
+            merge_task: None,
+            parent_span: Span::none(),
+            batch_created_at_opt: None,
+        };
+        publisher.handle(split_update, &ctx).await.unwrap();
+
+        let expected_span = format!(
+            "publisher{{index_uid={index_uid} source_id=source pipeline_uid=3D8K5Q2H \
+             shard_ids=[\"shard-1\", \"shard-2\"]}}"
+        );
+        let log_lines = captured_logs.lines();
+        let publish_log_line = log_lines
+            .iter()
+            .find(|line| line.contains("publish-new-splits"))
+            .unwrap();
+        assert!(
+            publish_log_line.contains(&expected_span),
+            "`{publish_log_line}` does not contain `{expected_span}`"
+        );
+
+        // The span is propagated to the source, which emits the shard positions update within it.
+        let suggest_truncates = source_inbox.drain_for_test_typed::<SuggestTruncate>();
+        assert_eq!(suggest_truncates.len(), 1);
+        suggest_truncates[0]
+            .parent_span
+            .in_scope(|| info!("truncate-shards"));
+
+        let log_lines = captured_logs.lines();
+        let truncate_log_line = log_lines
+            .iter()
+            .find(|line| line.contains("truncate-shards"))
+            .unwrap();
+        assert!(
+            truncate_log_line.contains(&expected_span),
+            "`{truncate_log_line}` does not contain `{expected_span}`"
+        );
+        universe.assert_quit().await;
+    }
 }
@@ -22,9 +22,11 @@ use std::iter::FromIterator;
 use std::mem;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
+use bytesize::ByteSize;
 use fail::fail_point;
 use itertools::Itertools;
 use once_cell::sync::OnceCell;
@@ -36,10 +38,10 @@ use quickwit_metastore::{SplitMetadata, StageSplitsRequestExt};
 use quickwit_proto::metastore::{MetastoreService, MetastoreServiceClient, StageSplitsRequest};
 use quickwit_proto::search::{ReportSplit, ReportSplitsRequest};
 use quickwit_proto::types::{IndexUid, PublishToken};
-use quickwit_storage::SplitPayloadBuilder;
+use quickwit_storage::{PutPayload, SplitPayloadBuilder};
 use serde::Serialize;
 use tokio::sync::oneshot::Sender;
-use tokio::sync::{oneshot, Semaphore, SemaphorePermit};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore, SemaphorePermit};
 use tracing::{debug, info, instrument, warn, Instrument, Span};
 
 use crate::actors::sequencer::{Sequencer, SequencerCommand};
@@ -60,6 +62,60 @@ use crate::split_store::IndexingSplitStore;
 static CONCURRENT_UPLOAD_PERMITS_INDEX: OnceCell<Semaphore> = OnceCell::new();
 static CONCURRENT_UPLOAD_PERMITS_MERGE: OnceCell<Semaphore> = OnceCell::new();
 
+/// Bounds the number of bytes of the splits accepted by an uploader that are not uploaded yet.
+///
+/// When the budget is exhausted, the uploader stops accepting new split batches. Its mailbox has
+/// no capacity, so the packager and, in turn, the indexer block until some uploads complete.
+#[derive(Clone)]
+struct UploadQueueBudget {
+    // One permit stands for one KiB so that budgets larger than 4GiB can be expressed.
+    semaphore: Arc<Semaphore>,
+    max_num_permits: u32,
+}
+
+impl UploadQueueBudget {
+    fn new(max_num_bytes: ByteSize) -> Self {
+        let max_num_permits = num_bytes_to_num_permits(max_num_bytes.as_u64()).max(1);
+        UploadQueueBudget {
+            semaphore: Arc::new(Semaphore::new(max_num_permits as usize)),
+            max_num_permits,
+        }
+    }
+
+    /// Waits until there is room for `num_bytes` in the queue. Batches larger than the whole
+    /// budget are accepted once the queue is empty.
+    async fn acquire(&self, num_bytes: u64) -> OwnedSemaphorePermit {
+        let num_permits = num_bytes_to_num_permits(num_bytes).min(self.max_num_permits);
+        self.semaphore
+            .clone()
+            .acquire_many_owned(num_permits)
+            .await
+            .expect("the upload queue semaphore should never be closed")
+    }
+}
+
+fn num_bytes_to_num_permits(num_bytes: u64) -> u32 {
+    num_bytes.div_ceil(1024).min(u32::MAX as u64) as u32
+}
+
+/// Accounts for a split batch in the upload queue until it is dropped.
+struct UploadQueueGuard {
+    num_bytes: u64,
+    counters: UploaderCounters,
+    _permit_opt: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for UploadQueueGuard {
+    fn drop(&mut self) {
+        self.counters
+            .num_upload_queue_bytes
+            .fetch_sub(self.num_bytes, Ordering::Relaxed);
+        INDEXER_METRICS
+            .split_upload_queue_bytes
+            .sub(self.num_bytes as i64);
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum UploaderType {
     IndexUploader,
@@ -168,6 +224,7 @@ pub struct Uploader {
     split_store: IndexingSplitStore,
     split_update_mailbox: SplitsUpdateMailbox,
     max_concurrent_split_uploads: usize,
+    upload_queue_budget_opt: Option<UploadQueueBudget>,
     counters: UploaderCounters,
     event_broker: EventBroker,
 }
@@ -180,6 +237,7 @@ impl Uploader {
         split_store: IndexingSplitStore,
         split_update_mailbox: SplitsUpdateMailbox,
         max_concurrent_split_uploads: usize,
+        upload_queue_max_num_bytes_opt: Option<ByteSize>,
         event_broker: EventBroker,
     ) -> Uploader {
         Uploader {
@@ -189,10 +247,39 @@ impl Uploader {
             split_store,
             split_update_mailbox,
             max_concurrent_split_uploads,
+            upload_queue_budget_opt: upload_queue_max_num_bytes_opt.map(UploadQueueBudget::new),
             counters: Default::default(),
             event_broker,
         }
     }
+
+    /// Adds a split batch of `num_bytes` to the upload queue, waiting for room in the queue if it
+    /// is bounded.
+    async fn enqueue_upload(&self, num_bytes: u64, ctx: &ActorContext<Self>) -> UploadQueueGuard {
+        let permit_opt = if let Some(upload_queue_budget) = &self.upload_queue_budget_opt {
+            let _guard = ctx.protect_zone();
+            let start = Instant::now();
+            let permit = upload_queue_budget.acquire(num_bytes).await;
+            self.counters
+                .upload_queue_blocked_micros
+                .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+            Some(permit)
+        } else {
+            None
+        };
+        self.counters
+            .num_upload_queue_bytes
+            .fetch_add(num_bytes, Ordering::Relaxed);
+        INDEXER_METRICS
+            .split_upload_queue_bytes
+            .add(num_bytes as i64);
+        UploadQueueGuard {
+            num_bytes,
+            counters: self.counters.clone(),
+            _permit_opt: permit_opt,
+        }
+    }
+
     async fn acquire_semaphore(
         &self,
         ctx: &ActorContext<Self>,
@@ -233,6 +320,10 @@ impl Uploader {
 pub struct UploaderCounters {
     pub num_staged_splits: Arc<AtomicU64>,
     pub num_uploaded_splits: Arc<AtomicU64>,
+    /// Number of bytes of the splits waiting to be uploaded or being uploaded.
+    pub num_upload_queue_bytes: Arc<AtomicU64>,
+    /// Time spent waiting for room in the upload queue.
+    pub upload_queue_blocked_micros: Arc<AtomicU64>,
 }
 
 #[async_trait]
@@ -273,6 +364,17 @@ impl Handler<PackagedSplitBatch> for Uploader {
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
         fail_point!("uploader:before");
+        let mut batch_num_bytes = 0;
+        for packaged_split in &batch.splits {
+            let split_payload = SplitPayloadBuilder::get_split_payload(
+                &packaged_split.split_files,
+                &packaged_split.serialized_split_fields,
+                &packaged_split.hotcache_bytes,
+            )?;
+            batch_num_bytes += split_payload.len();
+        }
+        let upload_queue_guard = self.enqueue_upload(batch_num_bytes, ctx).await;
+
         let split_update_sender = self
             .split_update_mailbox
             .get_split_update_sender(ctx)
@@ -325,6 +427,7 @@ impl Handler<PackagedSplitBatch> for Uploader {
                         &merge_policy,
                         &packaged_split.split_attrs,
                         packaged_split.tags.clone(),
+                        packaged_split.term_presence_filters.clone(),
                         split_streamer.footer_range.start..split_streamer.footer_range.end,
                     );
 
@@ -366,20 +469,24 @@ impl Handler<PackagedSplitBatch> for Uploader {
                     packaged_splits_and_metadata.push((packaged_split, metadata));
                 }
 
-                let splits_update = make_publish_operation(
-                    index_uid,
-                    packaged_splits_and_metadata,
-                    batch.checkpoint_delta_opt,
-                    batch.publish_lock,
-                    batch.publish_token_opt,
-                    batch.merge_task_opt,
-                    batch.batch_parent_span,
-                );
+                let splits_update = SplitsUpdate {
+                    batch_created_at_opt: batch.batch_created_at_opt,
+                    ..make_publish_operation(
+                        index_uid,
+                        packaged_splits_and_metadata,
+                        batch.checkpoint_delta_opt,
+                        batch.publish_lock,
+                        batch.publish_token_opt,
+                        batch.merge_task_opt,
+                        batch.batch_parent_span,
+                    )
+                };
 
                 split_update_sender.send(splits_update, &ctx_clone).await?;
-                // We explicitly drop it in order to force move the permit guard into the async
-                // task.
+                // We explicitly drop them in order to force move the permit guard and the upload
+                // queue guard into the async task.
                 mem::drop(permit_guard);
+                mem::drop(upload_queue_guard);
                 Result::<(), anyhow::Error>::Ok(())
             }
             .instrument(Span::current()),
@@ -417,6 +524,7 @@ impl Handler<EmptySplit> for Uploader {
             publish_token_opt: empty_split.publish_token_opt,
             merge_task: None,
             parent_span: empty_split.batch_parent_span,
+            batch_created_at_opt: None,
         };
 
         split_update_sender.send(splits_update, ctx).await?;
@@ -450,6 +558,7 @@ fn make_publish_operation(
         publish_token_opt,
         merge_task,
         parent_span,
+        batch_created_at_opt: None,
     }
 }
 
@@ -490,10 +599,11 @@ mod tests {
     use quickwit_actors::{ObservationType, Universe};
     use quickwit_common::pubsub::EventSubscriber;
     use quickwit_common::temp_dir::TempDirectory;
+    use quickwit_common::uri::Uri;
     use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
     use quickwit_proto::metastore::{EmptyResponse, MockMetastoreService};
     use quickwit_proto::types::{DocMappingUid, NodeId};
-    use quickwit_storage::RamStorage;
+    use quickwit_storage::{MockStorage, RamStorage};
     use tantivy::DateTime;
     use tokio::sync::oneshot;
 
@@ -537,6 +647,7 @@ mod tests {
             split_store,
             SplitsUpdateMailbox::Sequencer(sequencer_mailbox),
             4,
+            None,
             event_broker,
         );
         let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
@@ -544,6 +655,7 @@ mod tests {
         let checkpoint_delta_opt: Option<IndexCheckpointDelta> = Some(IndexCheckpointDelta {
             source_id: "test-source".to_string(),
             source_delta: SourceCheckpointDelta::from_range(3..15),
+            source_incarnation_opt: None,
         });
         uploader_mailbox
             .send_message(PackagedSplitBatch::new(
@@ -568,6 +680,7 @@ mod tests {
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_presence_filters: Default::default(),
                     hotcache_bytes: Vec::new(),
                     split_files: Vec::new(),
                 }],
@@ -576,6 +689,7 @@ mod tests {
                 None,
                 None,
                 Span::none(),
+                None,
             ))
             .await?;
         assert_eq!(
@@ -652,6 +766,7 @@ mod tests {
             split_store,
             SplitsUpdateMailbox::Sequencer(sequencer_mailbox),
             4,
+            None,
             EventBroker::default(),
         );
         let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
@@ -681,6 +796,7 @@ mod tests {
             serialized_split_fields: Vec::new(),
             split_scratch_directory: split_scratch_directory_1,
             tags: Default::default(),
+            term_presence_filters: Default::default(),
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
         };
@@ -708,6 +824,7 @@ mod tests {
             serialized_split_fields: Vec::new(),
             split_scratch_directory: split_scratch_directory_2,
             tags: Default::default(),
+            term_presence_filters: Default::default(),
             split_files: Vec::new(),
             hotcache_bytes: Vec::new(),
         };
@@ -719,6 +836,7 @@ mod tests {
                 None,
                 None,
                 Span::none(),
+                None,
             ))
             .await?;
         assert_eq!(
@@ -799,6 +917,7 @@ mod tests {
             split_store,
             SplitsUpdateMailbox::Publisher(publisher_mailbox),
             4,
+            None,
             EventBroker::default(),
         );
         let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
@@ -806,6 +925,7 @@ mod tests {
         let checkpoint_delta_opt: Option<IndexCheckpointDelta> = Some(IndexCheckpointDelta {
             source_id: "test-source".to_string(),
             source_delta: SourceCheckpointDelta::from_range(3..15),
+            source_incarnation_opt: None,
         });
         uploader_mailbox
             .send_message(PackagedSplitBatch::new(
@@ -827,6 +947,7 @@ mod tests {
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_presence_filters: Default::default(),
                     hotcache_bytes: Vec::new(),
                     split_files: Vec::new(),
                 }],
@@ -835,6 +956,7 @@ mod tests {
                 None,
                 None,
                 Span::none(),
+                None,
             ))
             .await?;
         assert_eq!(
@@ -872,12 +994,14 @@ mod tests {
             split_store,
             SplitsUpdateMailbox::Sequencer(sequencer_mailbox),
             4,
+            None,
             EventBroker::default(),
         );
         let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
         let checkpoint_delta = IndexCheckpointDelta {
             source_id: "test-source".to_string(),
             source_delta: SourceCheckpointDelta::from_range(3..15),
+            source_incarnation_opt: None,
         };
         uploader_mailbox
             .send_message(EmptySplit {
@@ -976,6 +1100,7 @@ mod tests {
             split_store,
             SplitsUpdateMailbox::Publisher(publisher_mailbox),
             4,
+            None,
             event_broker,
         );
         let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
@@ -983,6 +1108,7 @@ mod tests {
         let checkpoint_delta_opt: Option<IndexCheckpointDelta> = Some(IndexCheckpointDelta {
             source_id: "test-source".to_string(),
             source_delta: SourceCheckpointDelta::from_range(3..15),
+            source_incarnation_opt: None,
         });
         uploader_mailbox
             .send_message(PackagedSplitBatch::new(
@@ -1007,6 +1133,7 @@ mod tests {
                     serialized_split_fields: Vec::new(),
                     split_scratch_directory,
                     tags: Default::default(),
+                    term_presence_filters: Default::default(),
                     hotcache_bytes: Vec::new(),
                     split_files: Vec::new(),
                 }],
@@ -1015,6 +1142,7 @@ mod tests {
                 None,
                 None,
                 Span::none(),
+                None,
             ))
             .await?;
         assert_eq!(
@@ -1032,4 +1160,129 @@ mod tests {
         universe.assert_quit().await;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_upload_queue_budget() {
+        let upload_queue_budget = UploadQueueBudget::new(ByteSize::kib(10));
+        let first_permit = upload_queue_budget.acquire(6 * 1024).await;
+
+        let second_permit_fut = upload_queue_budget.acquire(6 * 1024);
+        tokio::pin!(second_permit_fut);
+        tokio::time::timeout(Duration::from_millis(50), &mut second_permit_fut)
+            .await
+            .unwrap_err();
+
+        mem::drop(first_permit);
+        let second_permit = second_permit_fut.await;
+        mem::drop(second_permit);
+
+        // Batches larger than the budget are accepted once the queue is empty.
+        let _permit = upload_queue_budget.acquire(20 * 1024).await;
+    }
+
+    #[tokio::test]
+    async fn test_uploader_upload_queue_backpressure() {
+        const PUT_DELAY: Duration = Duration::from_millis(100);
+
+        let universe = Universe::new();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_stage_splits()
+            .times(3)
+            .returning(|_| Ok(EmptyResponse {}));
+        let mut mock_storage = MockStorage::new();
+        mock_storage
+            .expect_uri()
+            .return_const(Uri::for_test("ram:///indexes/test-index"));
+        mock_storage.expect_put().times(3).returning(|_, _| {
+            // Simulates a slow object storage. The upload tasks do not run on the test runtime,
+            // so blocking here does not prevent the test from making progress.
+            std::thread::sleep(PUT_DELAY);
+            Ok(())
+        });
+        let split_store =
+            IndexingSplitStore::create_without_local_store_for_test(Arc::new(mock_storage));
+        let (publisher_mailbox, publisher_inbox) = universe.create_test_mailbox::<Publisher>();
+        // The budget only fits one split at a time.
+        let upload_queue_max_num_bytes = ByteSize::kib(4);
+        let uploader = Uploader::new(
+            UploaderType::IndexUploader,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            Arc::new(NopMergePolicy),
+            split_store,
+            SplitsUpdateMailbox::Publisher(publisher_mailbox),
+            4,
+            Some(upload_queue_max_num_bytes),
+            EventBroker::default(),
+        );
+        let (uploader_mailbox, uploader_handle) = universe.spawn_builder().spawn(uploader);
+
+        for split_ord in 0..3 {
+            let split_attrs = SplitAttrs {
+                node_id: NodeId::from("test-node"),
+                index_uid: IndexUid::for_test("test-index", 0),
+                source_id: "test-source".to_string(),
+                doc_mapping_uid: DocMappingUid::default(),
+                split_id: format!("test-split-{split_ord}"),
+                partition_id: 0,
+                num_docs: 10,
+                uncompressed_docs_size_in_bytes: 1_000,
+                time_range: None,
+                replaced_split_ids: Vec::new(),
+                delete_opstamp: 0,
+                num_merge_ops: 0,
+            };
+            let packaged_split = PackagedSplit {
+                split_attrs,
+                serialized_split_fields: Vec::new(),
+                split_scratch_directory: TempDirectory::for_test(),
+                tags: Default::default(),
+                term_presence_filters: Default::default(),
+                hotcache_bytes: vec![0u8; 3 * 1024],
+                split_files: Vec::new(),
+            };
+            uploader_mailbox
+                .send_message(PackagedSplitBatch::new(
+                    vec![packaged_split],
+                    None,
+                    PublishLock::default(),
+                    None,
+                    None,
+                    Span::none(),
+                    None,
+                ))
+                .await
+                .unwrap();
+            let num_upload_queue_bytes = uploader_handle
+                .observe()
+                .await
+                .num_upload_queue_bytes
+                .load(Ordering::Relaxed);
+            assert!(num_upload_queue_bytes <= upload_queue_max_num_bytes.as_u64());
+        }
+        universe.sleep(PUT_DELAY * 4).await;
+
+        let uploader_counters = uploader_handle.process_pending_and_observe().await.state;
+        assert_eq!(
+            uploader_counters
+                .num_uploaded_splits
+                .load(Ordering::Relaxed),
+            3
+        );
+        assert_eq!(
+            uploader_counters
+                .num_upload_queue_bytes
+                .load(Ordering::Relaxed),
+            0
+        );
+        // The second and third batches waited for the upload of the previous one.
+        let upload_queue_blocked_micros = uploader_counters
+            .upload_queue_blocked_micros
+            .load(Ordering::Relaxed);
+        assert!(upload_queue_blocked_micros >= PUT_DELAY.as_micros() as u64);
+
+        let splits_updates: Vec<SplitsUpdate> = publisher_inbox.drain_for_test_typed();
+        assert_eq!(splits_updates.len(), 3);
+        universe.assert_quit().await;
+    }
 }
@@ -29,7 +29,7 @@ use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_storage::StorageResolver;
 use tracing::info;
 
-use crate::actors::MergeSchedulerService;
+use crate::actors::{DocProcessingErrorSample, MergeSchedulerService};
 pub use crate::actors::{
     IndexingError, IndexingPipeline, IndexingPipelineParams, IndexingService, PublisherType,
     Sequencer, SplitsUpdateMailbox,
@@ -56,7 +56,12 @@ use self::merge_policy::MergePolicy;
 pub use self::source::check_source_connectivity;
 
 #[derive(utoipa::OpenApi)]
-#[openapi(components(schemas(IndexingStatistics, PipelineMetrics, CpuCapacity)))]
+#[openapi(components(schemas(
+    IndexingStatistics,
+    PipelineMetrics,
+    CpuCapacity,
+    DocProcessingErrorSample
+)))]
 /// Schema used for the OpenAPI generation which are apart of this crate.
 pub struct IndexingApiSchemas;
 
@@ -19,8 +19,8 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    new_counter, new_counter_vec, new_gauge, new_gauge_vec, IntCounter, IntCounterVec, IntGauge,
-    IntGaugeVec,
+    exponential_buckets, new_counter, new_counter_vec, new_gauge, new_gauge_vec, new_histogram,
+    new_histogram_vec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 pub struct IndexerMetrics {
@@ -28,10 +28,21 @@ pub struct IndexerMetrics {
     pub processed_bytes: IntCounterVec<2>,
     pub backpressure_micros: IntCounterVec<1>,
     pub available_concurrent_upload_permits: IntGaugeVec<1>,
+    pub split_upload_queue_bytes: IntGauge,
     pub split_builders: IntGauge,
     pub ongoing_merge_operations: IntGauge,
     pub pending_merge_operations: IntGauge,
     pub pending_merge_bytes: IntGauge,
+    pub apply_plan_throttled_total: IntCounterVec<1>,
+    pub shard_position_regressions_total: IntCounterVec<2>,
+    pub pending_shard_position_writes: IntGauge,
+    pub shard_positions_flush_duration_secs: Histogram,
+    pub indexing_pipelines_by_health: IntGaugeVec<1>,
+    pub pipeline_restarts_total: IntCounterVec<2>,
+    pub pipelines_failed: IntGauge,
+    pub standby_pipelines: IntGauge,
+    pub standby_pipeline_promotion_duration_secs: Histogram,
+    pub ingest_to_publish_latency_seconds: HistogramVec<2>,
     // We use a lazy counter, as most users do not use Kafka.
     #[cfg_attr(not(feature = "kafka"), allow(dead_code))]
     pub kafka_rebalance_total: Lazy<IntCounter>,
@@ -71,6 +82,12 @@ impl Default for IndexerMetrics {
                 &[],
                 ["component"],
             ),
+            split_upload_queue_bytes: new_gauge(
+                "split_upload_queue_bytes",
+                "Number of bytes of splits waiting to be uploaded or being uploaded.",
+                "indexing",
+                &[],
+            ),
             split_builders: new_gauge(
                 "split_builders",
                 "Number of existing index writer instances.",
@@ -95,6 +112,84 @@ impl Default for IndexerMetrics {
                 "indexing",
                 &[],
             ),
+            apply_plan_throttled_total: new_counter_vec(
+                "apply_plan_throttled_total",
+                "Number of indexing plan requests rejected because a control plane node sent them \
+                 above the configured rate.",
+                "indexing",
+                &[],
+                ["rpc"],
+            ),
+            shard_position_regressions_total: new_counter_vec(
+                "shard_position_regressions_total",
+                "Number of shard positions received from the cluster that were older than the \
+                 known position of the shard, and were therefore ignored.",
+                "indexing",
+                &[],
+                ["index", "source"],
+            ),
+            pending_shard_position_writes: new_gauge(
+                "pending_shard_position_writes",
+                "Number of positions of the local shards waiting to be written into the cluster \
+                 state.",
+                "indexing",
+                &[],
+            ),
+            shard_positions_flush_duration_secs: new_histogram(
+                "shard_positions_flush_duration_secs",
+                "Duration of the flushes of the positions of the local shards into the cluster \
+                 state, in seconds.",
+                "indexing",
+                exponential_buckets(0.0001, 2.0, 15).unwrap(),
+            ),
+            indexing_pipelines_by_health: new_gauge_vec(
+                "indexing_pipelines_by_health",
+                "Number of indexing pipelines running on the node, per health in [running, \
+                 backpressured, retrying, failed].",
+                "indexing",
+                &[],
+                ["health"],
+            ),
+            pipeline_restarts_total: new_counter_vec(
+                "pipeline_restarts_total",
+                "Number of restarts of indexing pipelines scheduled after a failure.",
+                "indexing",
+                &[],
+                ["index", "source"],
+            ),
+            pipelines_failed: new_gauge(
+                "pipelines_failed",
+                "Number of indexing pipelines that failed too many times and are no longer \
+                 restarted until a new indexing plan is applied or they are resumed.",
+                "indexing",
+                &[],
+            ),
+            standby_pipelines: new_gauge(
+                "standby_pipelines",
+                "Number of warm standby indexing pipelines running on the node.",
+                "indexing",
+                &[],
+            ),
+            standby_pipeline_promotion_duration_secs: new_histogram(
+                "standby_pipeline_promotion_duration_secs",
+                "Duration of the promotions of warm standby indexing pipelines, i.e. the time it \
+                 takes to start consuming their source, in seconds.",
+                "indexing",
+                exponential_buckets(0.001, 2.0, 15).unwrap(),
+            ),
+            ingest_to_publish_latency_seconds: new_histogram_vec(
+                "ingest_to_publish_latency_seconds",
+                "Time elapsed between the ingestion of documents and the publication of the split \
+                 containing them, in seconds, by index and reference in [max_doc_timestamp, \
+                 batch_creation]. `max_doc_timestamp` measures the freshness of the index from \
+                 the timestamp of the most recent document of the split, `batch_creation` the \
+                 latency of the indexing pipeline from the reception of the first documents of \
+                 the batch.",
+                "indexing",
+                &[],
+                ["index", "reference"],
+                exponential_buckets(0.1, 2.0, 16).unwrap(),
+            ),
             kafka_rebalance_total: Lazy::new(|| {
                 new_counter(
                     "kafka_rebalance_total",
@@ -0,0 +1,58 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+
+use quickwit_common::pubsub::Event;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::indexing::IndexingPipelineId;
+use quickwit_proto::types::DocMappingUid;
+
+/// Switches a running indexing pipeline to a new doc mapper, compatible with the current one,
+/// without restarting its source. The doc processor applies the new doc mapper from the next
+/// batch onwards, and the indexer commits its current workbench before indexing the documents
+/// mapped with the new doc mapper, so that a split never mixes both. The update then follows these
+/// splits down to the packager, which resolves the tag and term presence filter fields against the
+/// new schema.
+#[derive(Clone)]
+pub struct DocMapperUpdate {
+    pub doc_mapper: Arc<dyn DocMapper>,
+    pub params_fingerprint: u64,
+}
+
+impl fmt::Debug for DocMapperUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DocMapperUpdate")
+            .field("doc_mapping_uid", &self.doc_mapper.doc_mapping_uid())
+            .field("params_fingerprint", &self.params_fingerprint)
+            .finish()
+    }
+}
+
+/// Event published once an indexing pipeline switched to a new doc mapper in place.
+#[derive(Debug, Clone)]
+pub struct DocMapperReloaded {
+    pub pipeline_id: IndexingPipelineId,
+    pub previous_doc_mapping_uid: DocMappingUid,
+    pub doc_mapping_uid: DocMappingUid,
+    pub params_fingerprint: u64,
+}
+
+impl Event for DocMapperReloaded {}
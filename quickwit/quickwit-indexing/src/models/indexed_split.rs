@@ -28,6 +28,7 @@ use quickwit_proto::indexing::IndexingPipelineId;
 use quickwit_proto::types::{DocMappingUid, IndexUid, PublishToken};
 use tantivy::directory::MmapDirectory;
 use tantivy::IndexBuilder;
+use time::OffsetDateTime;
 use tracing::{instrument, Span};
 
 use crate::controlled_directory::ControlledDirectory;
@@ -167,10 +168,14 @@ pub struct IndexedSplitBatch {
     /// If `None`, the split batch was built in the `IndexingPipeline`.
     pub merge_task_opt: Option<MergeTask>,
     pub batch_parent_span: Span,
+    /// Wall-clock time at which the indexer received the first documents of the batch. `None` for
+    /// the splits produced by merges.
+    pub batch_created_at_opt: Option<OffsetDateTime>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CommitTrigger {
+    DocMapperUpdate,
     Drained,
     ForceCommit,
     MemoryLimit,
@@ -187,6 +192,8 @@ pub struct IndexedSplitBatchBuilder {
     pub publish_token_opt: Option<PublishToken>,
     pub commit_trigger: CommitTrigger,
     pub batch_parent_span: Span,
+    /// Wall-clock time at which the indexer received the first documents of the batch.
+    pub batch_created_at: OffsetDateTime,
     pub memory_usage: GaugeGuard<'static>,
     pub _split_builders_guard: GaugeGuard<'static>,
 }
@@ -48,3 +48,30 @@ pub struct DetachMergePipeline {
 pub struct ObservePipeline {
     pub pipeline_id: IndexingPipelineId,
 }
+
+/// Pauses an indexing pipeline: its source stops emitting new batches, but the batches already
+/// emitted are still indexed and published. The pipeline keeps its indexing tasks.
+#[derive(Clone, Copy, Debug)]
+pub struct PausePipeline {
+    pub pipeline_uid: PipelineUid,
+}
+
+/// Resumes a paused indexing pipeline: its source resumes emitting batches from where it stopped.
+#[derive(Clone, Copy, Debug)]
+pub struct ResumePipeline {
+    pub pipeline_uid: PipelineUid,
+}
+
+/// Promotes a warm standby indexing pipeline: its source actor is spawned and starts consuming the
+/// source from the latest published position. Replies whether the pipeline was promoted warm, i.e.
+/// with its actors already running.
+#[derive(Clone, Copy, Debug)]
+pub struct PromotePipeline {
+    pub pipeline_uid: PipelineUid,
+}
+
+/// Returns the most recent documents rejected by the doc processor of an indexing pipeline.
+#[derive(Clone, Copy, Debug)]
+pub struct GetDocProcessingErrorSamples {
+    pub pipeline_uid: PipelineUid,
+}
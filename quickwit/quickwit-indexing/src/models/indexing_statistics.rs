@@ -20,7 +20,7 @@
 use std::collections::BTreeSet;
 use std::sync::atomic::Ordering;
 
-use quickwit_proto::indexing::PipelineMetrics;
+use quickwit_proto::indexing::{IndexingTaskPriority, PipelineHealth, PipelineMetrics};
 use quickwit_proto::types::ShardId;
 use serde::Serialize;
 
@@ -47,16 +47,35 @@ pub struct IndexingStatistics {
     pub total_bytes_processed: u64,
     /// Size in bytes of resulting split
     pub total_size_splits: u64,
+    /// Number of bytes of the splits waiting to be uploaded or being uploaded
+    pub split_upload_queue_bytes: u64,
+    /// Time spent waiting for room in the upload queue
+    pub upload_queue_blocked_micros: u64,
+    /// Fraction of time spent waiting for room in the upload queue over the last supervision
+    /// period
+    pub upload_backpressure_ratio: f32,
     /// Pipeline generation.
     pub generation: usize,
     /// Number of successive pipeline spawn attempts.
     pub num_spawn_attempts: usize,
     // Pipeline metrics.
     pub pipeline_metrics_opt: Option<PipelineMetrics>,
+    /// Commit timeout currently applied by the indexer, when the adaptive commit timeout is
+    /// enabled.
+    pub effective_commit_timeout_secs_opt: Option<u64>,
     // List of shard ids.
     #[schema(value_type = Vec<u64>)]
     pub shard_ids: BTreeSet<ShardId>,
     pub params_fingerprint: u64,
+    /// Whether the pipeline is paused, i.e. its source does not emit new batches.
+    pub is_paused: bool,
+    /// Whether the pipeline is a warm standby, i.e. its source is not consumed until the pipeline
+    /// is promoted.
+    pub is_standby: bool,
+    /// Priority of the pipeline when competing for the cooperative indexing permits.
+    pub priority: IndexingTaskPriority,
+    /// Health of the pipeline, as assessed by its supervisor.
+    pub health: PipelineHealth,
 }
 
 impl IndexingStatistics {
@@ -70,6 +89,7 @@ impl IndexingStatistics {
         self.num_docs += doc_processor_counters.num_processed_docs();
         self.num_invalid_docs += doc_processor_counters.num_invalid_docs();
         self.num_local_splits += indexer_counters.num_splits_emitted;
+        self.effective_commit_timeout_secs_opt = indexer_counters.effective_commit_timeout_secs_opt;
         self.total_bytes_processed += doc_processor_counters
             .num_bytes_total
             .load(Ordering::Relaxed);
@@ -77,6 +97,12 @@ impl IndexingStatistics {
         self.num_uploaded_splits += uploader_counters
             .num_uploaded_splits
             .load(Ordering::Relaxed);
+        self.split_upload_queue_bytes = uploader_counters
+            .num_upload_queue_bytes
+            .load(Ordering::Relaxed);
+        self.upload_queue_blocked_micros += uploader_counters
+            .upload_queue_blocked_micros
+            .load(Ordering::Relaxed);
         self.num_published_splits += publisher_counters.num_published_splits;
         self.num_empty_splits += publisher_counters.num_empty_splits;
         self
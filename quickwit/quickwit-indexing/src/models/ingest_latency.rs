@@ -0,0 +1,223 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use async_trait::async_trait;
+use quickwit_common::metrics::index_label;
+use quickwit_common::pubsub::{Event, EventSubscriber};
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::types::{IndexUid, SplitId};
+use time::OffsetDateTime;
+
+use crate::metrics::INDEXER_METRICS;
+
+/// Event published by the main publisher of an indexing pipeline once a batch of new splits is
+/// published, stamped with the wall-clock times required to measure the freshness of the index.
+#[derive(Debug, Clone)]
+pub struct SplitsPublished {
+    pub index_uid: IndexUid,
+    /// Wall-clock time at which the indexer received the first documents of the batch.
+    pub batch_created_at: OffsetDateTime,
+    /// Wall-clock time at which the splits were published.
+    pub published_at: OffsetDateTime,
+    pub splits: Vec<PublishedSplit>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PublishedSplit {
+    pub split_id: SplitId,
+    /// Timestamp of the most recent document of the split. `None` if the index has no timestamp
+    /// field.
+    pub max_doc_timestamp_opt: Option<OffsetDateTime>,
+}
+
+impl SplitsPublished {
+    pub fn new(
+        index_uid: IndexUid,
+        batch_created_at: OffsetDateTime,
+        published_at: OffsetDateTime,
+        split_metadatas: &[SplitMetadata],
+    ) -> Self {
+        let splits = split_metadatas
+            .iter()
+            .map(|split_metadata| PublishedSplit {
+                split_id: split_metadata.split_id.clone(),
+                max_doc_timestamp_opt: split_metadata.time_range.as_ref().and_then(|time_range| {
+                    OffsetDateTime::from_unix_timestamp(*time_range.end()).ok()
+                }),
+            })
+            .collect();
+        Self {
+            index_uid,
+            batch_created_at,
+            published_at,
+            splits,
+        }
+    }
+}
+
+impl Event for SplitsPublished {}
+
+/// Latencies between the ingestion of the documents of a split and its publication, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct IngestLatencies {
+    /// Time elapsed since the timestamp of the most recent document of the split. `None` if the
+    /// index has no timestamp field or if the timestamp is in the future.
+    since_max_doc_timestamp_secs_opt: Option<f64>,
+    /// Time elapsed since the indexer received the first documents of the batch.
+    since_batch_creation_secs: f64,
+}
+
+fn compute_ingest_latencies(event: &SplitsPublished) -> Vec<IngestLatencies> {
+    let since_batch_creation_secs = (event.published_at - event.batch_created_at)
+        .as_seconds_f64()
+        .max(0.0);
+    event
+        .splits
+        .iter()
+        .map(|split| {
+            let since_max_doc_timestamp_secs_opt = split
+                .max_doc_timestamp_opt
+                .map(|max_doc_timestamp| (event.published_at - max_doc_timestamp).as_seconds_f64())
+                .filter(|latency_secs| *latency_secs >= 0.0);
+            IngestLatencies {
+                since_max_doc_timestamp_secs_opt,
+                since_batch_creation_secs,
+            }
+        })
+        .collect()
+}
+
+/// Records the `ingest_to_publish_latency_seconds` histograms from the [`SplitsPublished`]
+/// events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IngestLatencyRecorder;
+
+#[async_trait]
+impl EventSubscriber<SplitsPublished> for IngestLatencyRecorder {
+    async fn handle_event(&mut self, event: SplitsPublished) {
+        let index_label = index_label(&event.index_uid.index_id);
+        for ingest_latencies in compute_ingest_latencies(&event) {
+            if let Some(latency_secs) = ingest_latencies.since_max_doc_timestamp_secs_opt {
+                INDEXER_METRICS
+                    .ingest_to_publish_latency_seconds
+                    .with_label_values([index_label, "max_doc_timestamp"])
+                    .observe(latency_secs);
+            }
+            INDEXER_METRICS
+                .ingest_to_publish_latency_seconds
+                .with_label_values([index_label, "batch_creation"])
+                .observe(ingest_latencies.since_batch_creation_secs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_common::pubsub::EventBroker;
+    use time::ext::NumericalDuration;
+
+    use super::*;
+
+    fn split_metadata_for_test(
+        split_id: &str,
+        max_doc_timestamp_opt: Option<i64>,
+    ) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range: max_doc_timestamp_opt
+                .map(|max_doc_timestamp| max_doc_timestamp - 60..=max_doc_timestamp),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_ingest_latencies() {
+        let published_at = OffsetDateTime::from_unix_timestamp(1_700_000_100).unwrap();
+        let event = SplitsPublished::new(
+            IndexUid::for_test("test-index", 0),
+            published_at - 1500.milliseconds(),
+            published_at,
+            &[
+                split_metadata_for_test("split-1", Some(1_700_000_090)),
+                // The index has no timestamp field.
+                split_metadata_for_test("split-2", None),
+                // The documents are timestamped in the future.
+                split_metadata_for_test("split-3", Some(1_700_000_200)),
+            ],
+        );
+        assert_eq!(
+            compute_ingest_latencies(&event),
+            [
+                IngestLatencies {
+                    since_max_doc_timestamp_secs_opt: Some(10.0),
+                    since_batch_creation_secs: 1.5,
+                },
+                IngestLatencies {
+                    since_max_doc_timestamp_secs_opt: None,
+                    since_batch_creation_secs: 1.5,
+                },
+                IngestLatencies {
+                    since_max_doc_timestamp_secs_opt: None,
+                    since_batch_creation_secs: 1.5,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_latency_recorder() {
+        let event_broker = EventBroker::default();
+        event_broker.subscribe(IngestLatencyRecorder).forever();
+
+        let published_at = OffsetDateTime::now_utc();
+        let max_doc_timestamp = published_at.unix_timestamp() - 5;
+        event_broker.publish(SplitsPublished::new(
+            IndexUid::for_test("test-ingest-latency-recorder", 0),
+            published_at - 2.seconds(),
+            published_at,
+            &[
+                split_metadata_for_test("split-1", Some(max_doc_timestamp)),
+                split_metadata_for_test("split-2", None),
+            ],
+        ));
+        let since_max_doc_timestamp_histogram = INDEXER_METRICS
+            .ingest_to_publish_latency_seconds
+            .with_label_values(["test-ingest-latency-recorder", "max_doc_timestamp"]);
+        let since_batch_creation_histogram = INDEXER_METRICS
+            .ingest_to_publish_latency_seconds
+            .with_label_values(["test-ingest-latency-recorder", "batch_creation"]);
+
+        // The events are handled asynchronously.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while since_batch_creation_histogram.get_sample_count() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(since_max_doc_timestamp_histogram.get_sample_count(), 1);
+        let since_max_doc_timestamp_secs = since_max_doc_timestamp_histogram.get_sample_sum();
+        assert!((5.0..6.0).contains(&since_max_doc_timestamp_secs));
+        assert_eq!(since_batch_creation_histogram.get_sample_count(), 2);
+        assert_eq!(since_batch_creation_histogram.get_sample_sum(), 4.0);
+    }
+}
@@ -19,9 +19,11 @@
 
 #![allow(rustdoc::invalid_html_tags)]
 
+mod doc_mapper_update;
 mod indexed_split;
 mod indexing_service_message;
 mod indexing_statistics;
+mod ingest_latency;
 mod merge_planner_message;
 mod merge_scratch;
 mod merge_statistics;
@@ -33,14 +35,17 @@ mod raw_doc_batch;
 mod shard_positions;
 mod split_attrs;
 
+pub use doc_mapper_update::{DocMapperReloaded, DocMapperUpdate};
 pub use indexed_split::{
     CommitTrigger, EmptySplit, IndexedSplit, IndexedSplitBatch, IndexedSplitBatchBuilder,
     IndexedSplitBuilder,
 };
 pub use indexing_service_message::{
-    DetachIndexingPipeline, DetachMergePipeline, ObservePipeline, SpawnPipeline,
+    DetachIndexingPipeline, DetachMergePipeline, GetDocProcessingErrorSamples, ObservePipeline,
+    PausePipeline, PromotePipeline, ResumePipeline, SpawnPipeline,
 };
 pub use indexing_statistics::IndexingStatistics;
+pub use ingest_latency::{IngestLatencyRecorder, PublishedSplit, SplitsPublished};
 pub use merge_planner_message::NewSplits;
 pub use merge_scratch::MergeScratch;
 pub use merge_statistics::MergeStatistics;
@@ -51,7 +56,9 @@ pub use publisher_message::SplitsUpdate;
 use quickwit_proto::types::PublishToken;
 pub use raw_doc_batch::RawDocBatch;
 pub(crate) use shard_positions::LocalShardPositionsUpdate;
-pub use shard_positions::ShardPositionsService;
+pub use shard_positions::{
+    ShardPositionsFlushSettings, ShardPositionsService, ShardPositionsServiceState,
+};
 pub use split_attrs::{create_split_metadata, SplitAttrs};
 
 #[derive(Debug)]
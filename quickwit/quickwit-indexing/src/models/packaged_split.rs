@@ -23,8 +23,10 @@ use std::path::PathBuf;
 
 use itertools::Itertools;
 use quickwit_common::temp_dir::TempDirectory;
+use quickwit_doc_mapper::term_presence::TermPresenceFilters;
 use quickwit_metastore::checkpoint::IndexCheckpointDelta;
 use quickwit_proto::types::{IndexUid, PublishToken, SplitId};
+use time::OffsetDateTime;
 use tracing::Span;
 
 use crate::merge_policy::MergeTask;
@@ -35,6 +37,7 @@ pub struct PackagedSplit {
     pub split_attrs: SplitAttrs,
     pub split_scratch_directory: TempDirectory,
     pub tags: BTreeSet<String>,
+    pub term_presence_filters: TermPresenceFilters,
     pub split_files: Vec<PathBuf>,
     pub hotcache_bytes: Vec<u8>,
 }
@@ -55,6 +58,7 @@ impl fmt::Debug for PackagedSplit {
             .field("split_attrs", &self.split_attrs)
             .field("split_scratch_directory", &self.split_scratch_directory)
             .field("tags", &self.tags)
+            .field("term_presence_filters", &self.term_presence_filters)
             .field("split_files", &self.split_files)
             .finish()
     }
@@ -72,6 +76,9 @@ pub struct PackagedSplitBatch {
     /// If `None`, the split batch was built in the `IndexingPipeline`.
     pub merge_task_opt: Option<MergeTask>,
     pub batch_parent_span: Span,
+    /// Wall-clock time at which the indexer received the first documents of the batch. `None` for
+    /// the splits produced by merges.
+    pub batch_created_at_opt: Option<OffsetDateTime>,
 }
 
 impl PackagedSplitBatch {
@@ -86,6 +93,7 @@ impl PackagedSplitBatch {
         publish_token_opt: Option<PublishToken>,
         merge_task_opt: Option<MergeTask>,
         batch_parent_span: Span,
+        batch_created_at_opt: Option<OffsetDateTime>,
     ) -> Self {
         assert!(!splits.is_empty());
         assert!(
@@ -102,6 +110,7 @@ impl PackagedSplitBatch {
             publish_token_opt,
             merge_task_opt,
             batch_parent_span,
+            batch_created_at_opt,
         }
     }
 
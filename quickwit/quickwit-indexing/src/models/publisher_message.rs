@@ -23,6 +23,7 @@ use itertools::Itertools;
 use quickwit_metastore::checkpoint::IndexCheckpointDelta;
 use quickwit_metastore::SplitMetadata;
 use quickwit_proto::types::{IndexUid, PublishToken};
+use time::OffsetDateTime;
 use tracing::Span;
 
 use crate::merge_policy::MergeTask;
@@ -41,6 +42,9 @@ pub struct SplitsUpdate {
     /// If `None`, the split batch was built in the `IndexingPipeline`.
     pub merge_task: Option<MergeTask>,
     pub parent_span: Span,
+    /// Wall-clock time at which the indexer received the first documents of the batch. `None` for
+    /// the splits produced by merges and for empty batches.
+    pub batch_created_at_opt: Option<OffsetDateTime>,
 }
 
 impl fmt::Debug for SplitsUpdate {
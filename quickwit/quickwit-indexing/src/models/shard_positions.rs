@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Debug;
 use std::time::{Duration, Instant};
 
@@ -26,15 +26,48 @@ use async_trait::async_trait;
 use fnv::FnvHashMap;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, SpawnContext};
 use quickwit_cluster::{Cluster, ListenerHandle};
+use quickwit_common::metrics::index_label;
 use quickwit_common::pretty::PrettyDisplay;
 use quickwit_common::pubsub::{Event, EventBroker};
-use quickwit_proto::indexing::ShardPositionsUpdate;
-use quickwit_proto::types::{Position, ShardId, SourceUid};
-use tracing::{debug, error, info, warn};
+use quickwit_common::rate_limited_warn;
+use quickwit_proto::indexing::{ShardPositionsCleanup, ShardPositionsUpdate};
+use quickwit_proto::ingest::ShardState;
+use quickwit_proto::metastore::{
+    ListShardsRequest, ListShardsSubrequest, MetastoreError, MetastoreService,
+    MetastoreServiceClient,
+};
+use quickwit_proto::types::{NodeId, Position, ShardId, SourceUid};
+use serde::Serialize;
+use tracing::{debug, error, info, instrument, warn, Span};
+
+use crate::metrics::INDEXER_METRICS;
 
 /// Prefix used in chitchat to publish the shard positions.
 const SHARD_POSITIONS_PREFIX: &str = "indexer.shard_positions:";
 
+/// Interval at which the shards that reached EOF are considered for garbage collection.
+const GC_EOF_SHARDS_INTERVAL: Duration = if cfg!(test) {
+    Duration::from_millis(100)
+} else {
+    Duration::from_secs(60)
+};
+
+/// Maximum time a flush waits for the chitchat lock before the batch being written is queued
+/// again for the next flush.
+const CHITCHAT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Controls how the positions of the local shards are written into chitchat.
+///
+/// Instead of writing a chitchat key on every publish, the `ShardPositionsService` coalesces the
+/// position updates and flushes them every `interval`, in batches of at most `batch_size` keys.
+/// Each batch acquires the chitchat lock once, and the lock is released between batches so that
+/// the gossip loop is not starved.
+#[derive(Debug, Clone, Copy)]
+pub struct ShardPositionsFlushSettings {
+    pub interval: Duration,
+    pub batch_size: usize,
+}
+
 /// This event means that a pipeline running in the current node (hence "local")
 /// performed a publish on an ingest pipeline, and hence the position of a shard has been updated.
 ///
@@ -48,22 +81,35 @@ const SHARD_POSITIONS_PREFIX: &str = "indexer.shard_positions:";
 /// Consumers of such events should listen to the more `ShardPositionsUpdate` event instead.
 /// That event is broadcasted via the cluster event broker, and will include both local
 /// changes and changes from other nodes.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub(crate) struct LocalShardPositionsUpdate {
     source_uid: SourceUid,
     // This list can be partial: not all shards for the source need to be listed here.
     shard_positions: Vec<(ShardId, Position)>,
+    // Span of the publication that triggered this update.
+    parent_span: Span,
 }
 
 impl LocalShardPositionsUpdate {
+    /// Creates a new update. The current span is recorded as the parent span of the processing of
+    /// the update by the `ShardPositionsService`.
     pub fn new(source_uid: SourceUid, shard_positions: Vec<(ShardId, Position)>) -> Self {
         LocalShardPositionsUpdate {
             source_uid,
             shard_positions,
+            parent_span: Span::current(),
         }
     }
 }
 
+impl PartialEq for LocalShardPositionsUpdate {
+    fn eq(&self, other: &Self) -> bool {
+        self.source_uid == other.source_uid && self.shard_positions == other.shard_positions
+    }
+}
+
+impl Eq for LocalShardPositionsUpdate {}
+
 /// This event is an internal detail of the `ShardPositionsService`.
 ///
 /// When a shard position change in the cluster is detected, a `ClusterShardPositionUpdate`
@@ -73,18 +119,47 @@ struct ClusterShardPositionsUpdate {
     pub source_uid: SourceUid,
     pub shard_id: ShardId,
     pub position: Position,
+    // Node that published the position.
+    pub node_id: NodeId,
 }
 
 impl Event for LocalShardPositionsUpdate {}
 
+/// Self-scheduled message triggering the garbage collection of the shards that reached EOF.
+#[derive(Debug)]
+struct GcEofShards;
+
+/// Self-scheduled message triggering the flush of the pending shard position writes into
+/// chitchat.
+#[derive(Debug)]
+struct FlushShardPositions;
+
 /// The published shard positions is a model unique to the indexer service instance that
 /// keeps track of the latest (known) published position for the shards of all managed sources.
 ///
 /// It receives updates through the event broker, and only keeps the maximum published position
-/// for each shard.
+/// for each shard. Positions received from the cluster that are older than the known position of
+/// their shard, for instance published by a node that rejoined the cluster with a stale state,
+/// are ignored and counted as regressions.
+///
+/// Shards that have been at EOF for longer than `eof_retention` and that are closed or deleted in
+/// the metastore are removed from the model and from chitchat, and a `ShardPositionsCleanup`
+/// event is published.
+///
+/// The positions of the local shards are written into chitchat periodically, see
+/// [`ShardPositionsFlushSettings`].
 pub struct ShardPositionsService {
     shard_positions_per_source: FnvHashMap<SourceUid, BTreeMap<ShardId, Position>>,
+    // Time at which the shards were first seen at EOF.
+    eof_since_per_source: FnvHashMap<SourceUid, BTreeMap<ShardId, Instant>>,
+    num_position_regressions_per_source: FnvHashMap<SourceUid, u64>,
+    eof_retention: Duration,
+    // Positions of the local shards waiting to be written into chitchat, keyed by chitchat key.
+    pending_position_writes: BTreeMap<String, Position>,
+    flush_settings: ShardPositionsFlushSettings,
+    num_chitchat_writes: u64,
     cluster: Cluster,
+    metastore: MetastoreServiceClient,
     event_broker: EventBroker,
     cluster_listener_handle_opt: Option<ListenerHandle>,
 }
@@ -92,6 +167,7 @@ pub struct ShardPositionsService {
 fn parse_shard_positions_from_kv(
     key: &str,
     value: &str,
+    node_id: &str,
 ) -> anyhow::Result<ClusterShardPositionsUpdate> {
     let (source_uid_str, shard_id_str) = key.rsplit_once(':').context("invalid key")?;
     let shard_id = ShardId::from(shard_id_str);
@@ -106,6 +182,7 @@ fn parse_shard_positions_from_kv(
         source_uid,
         shard_id,
         position,
+        node_id: NodeId::from(node_id),
     })
 }
 
@@ -113,8 +190,9 @@ fn push_position_update(
     shard_positions_service_mailbox: &Mailbox<ShardPositionsService>,
     key: &str,
     value: &str,
+    node_id: &str,
 ) {
-    let shard_positions = match parse_shard_positions_from_kv(key, value) {
+    let shard_positions = match parse_shard_positions_from_kv(key, value, node_id) {
         Ok(shard_positions) => shard_positions,
         Err(error) => {
             error!(key=key, value=value, error=%error, "failed to parse shard positions from cluster kv");
@@ -129,10 +207,39 @@ fn push_position_update(
     }
 }
 
+/// Number of shards tracked by the `ShardPositionsService`, and number of position regressions
+/// received from the cluster per source.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ShardPositionsServiceState {
+    pub num_shards: usize,
+    pub num_eof_shards: usize,
+    pub num_position_regressions_per_source: BTreeMap<String, u64>,
+    pub num_pending_position_writes: usize,
+    // Number of batches of shard positions written into chitchat.
+    pub num_chitchat_writes: u64,
+}
+
 #[async_trait]
 impl Actor for ShardPositionsService {
-    type ObservableState = ();
-    fn observable_state(&self) {}
+    type ObservableState = ShardPositionsServiceState;
+
+    fn observable_state(&self) -> ShardPositionsServiceState {
+        ShardPositionsServiceState {
+            num_shards: self
+                .shard_positions_per_source
+                .values()
+                .map(BTreeMap::len)
+                .sum(),
+            num_eof_shards: self.eof_since_per_source.values().map(BTreeMap::len).sum(),
+            num_position_regressions_per_source: self
+                .num_position_regressions_per_source
+                .iter()
+                .map(|(source_uid, num_regressions)| (source_uid.to_string(), *num_regressions))
+                .collect(),
+            num_pending_position_writes: self.pending_position_writes.len(),
+            num_chitchat_writes: self.num_chitchat_writes,
+        }
+    }
 
     async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
         let mailbox = ctx.mailbox().clone();
@@ -140,7 +247,7 @@ impl Actor for ShardPositionsService {
         self.cluster_listener_handle_opt = Some(
             self.cluster
                 .subscribe(SHARD_POSITIONS_PREFIX, move |event| {
-                    push_position_update(&mailbox, event.key, event.value);
+                    push_position_update(&mailbox, event.key, event.value, &event.node.node_id);
                 })
                 .await,
         );
@@ -156,10 +263,15 @@ impl Actor for ShardPositionsService {
         let chitchat = self.cluster.chitchat().await;
         let chitchat_lock = chitchat.lock().await;
         let mut num_keys = 0;
-        for node_state in chitchat_lock.node_states().values() {
+        for (chitchat_id, node_state) in chitchat_lock.node_states() {
             for (key, versioned_value) in node_state.iter_prefix(SHARD_POSITIONS_PREFIX) {
                 let key_stripped = key.strip_prefix(SHARD_POSITIONS_PREFIX).unwrap();
-                push_position_update(ctx.mailbox(), key_stripped, &versioned_value.value);
+                push_position_update(
+                    ctx.mailbox(),
+                    key_stripped,
+                    &versioned_value.value,
+                    &chitchat_id.node_id,
+                );
                 num_keys += 1;
             }
             // It is tempting to yield here, but we are holding the chitchat lock.
@@ -177,32 +289,68 @@ impl Actor for ShardPositionsService {
                 elapsed.pretty_display(),
             );
         }
+        ctx.schedule_self_msg(GC_EOF_SHARDS_INTERVAL, GcEofShards);
+        ctx.schedule_self_msg(self.flush_settings.interval, FlushShardPositions);
+        Ok(())
+    }
+
+    async fn finalize(
+        &mut self,
+        _exit_status: &ActorExitStatus,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<()> {
+        self.flush_position_writes(ctx).await;
         Ok(())
     }
 }
 
 impl ShardPositionsService {
-    pub fn spawn(spawn_ctx: &SpawnContext, event_broker: EventBroker, cluster: Cluster) {
-        let shard_positions_service = ShardPositionsService::new(event_broker.clone(), cluster);
+    pub fn spawn(
+        spawn_ctx: &SpawnContext,
+        event_broker: EventBroker,
+        cluster: Cluster,
+        metastore: MetastoreServiceClient,
+        eof_retention: Duration,
+        flush_settings: ShardPositionsFlushSettings,
+    ) -> Mailbox<ShardPositionsService> {
+        let shard_positions_service = ShardPositionsService::new(
+            event_broker.clone(),
+            cluster,
+            metastore,
+            eof_retention,
+            flush_settings,
+        );
         let (shard_positions_service_mailbox, _) =
             spawn_ctx.spawn_builder().spawn(shard_positions_service);
         // This subscription is in charge of updating the shard positions model.
+        let mailbox_clone = shard_positions_service_mailbox.clone();
         event_broker
             .subscribe_without_timeout::<LocalShardPositionsUpdate>(move |update| {
-                if shard_positions_service_mailbox
-                    .try_send_message(update)
-                    .is_err()
-                {
+                if mailbox_clone.try_send_message(update).is_err() {
                     error!("failed to send update to shard positions service");
                 }
             })
             .forever();
+        shard_positions_service_mailbox
     }
 
-    fn new(event_broker: EventBroker, cluster: Cluster) -> ShardPositionsService {
+    fn new(
+        event_broker: EventBroker,
+        cluster: Cluster,
+        metastore: MetastoreServiceClient,
+        eof_retention: Duration,
+        flush_settings: ShardPositionsFlushSettings,
+    ) -> ShardPositionsService {
         ShardPositionsService {
             shard_positions_per_source: Default::default(),
+            eof_since_per_source: Default::default(),
+            num_position_regressions_per_source: Default::default(),
+            eof_retention,
+            pending_position_writes: Default::default(),
+            flush_settings,
+            num_chitchat_writes: 0,
             cluster,
+            metastore,
             event_broker,
             cluster_listener_handle_opt: None,
         }
@@ -222,7 +370,38 @@ impl Handler<ClusterShardPositionsUpdate> for ShardPositionsService {
             source_uid,
             shard_id,
             position,
+            node_id,
         } = update;
+        if let Some(known_position) = self
+            .shard_positions_per_source
+            .get(&source_uid)
+            .and_then(|shard_positions| shard_positions.get(&shard_id))
+        {
+            if position < *known_position {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    index_uid=%source_uid.index_uid,
+                    source_id=%source_uid.source_id,
+                    shard_id=%shard_id,
+                    node_id=%node_id,
+                    received_position=?position,
+                    known_position=?known_position,
+                    "ignoring shard position regression received from the cluster"
+                );
+                INDEXER_METRICS
+                    .shard_position_regressions_total
+                    .with_label_values([
+                        index_label(&source_uid.index_uid.index_id),
+                        &source_uid.source_id,
+                    ])
+                    .inc();
+                *self
+                    .num_position_regressions_per_source
+                    .entry(source_uid)
+                    .or_default() += 1;
+                return Ok(());
+            }
+        }
         let updated_shard_positions = self.apply_update(&source_uid, vec![(shard_id, position)]);
         debug!(updated_shard_positions=?updated_shard_positions, "cluster position update");
         if !updated_shard_positions.is_empty() {
@@ -236,6 +415,7 @@ impl Handler<ClusterShardPositionsUpdate> for ShardPositionsService {
 impl Handler<LocalShardPositionsUpdate> for ShardPositionsService {
     type Reply = ();
 
+    #[instrument(name = "shard-positions-update", parent = update.parent_span.id(), skip_all)]
     async fn handle(
         &mut self,
         update: LocalShardPositionsUpdate,
@@ -244,23 +424,186 @@ impl Handler<LocalShardPositionsUpdate> for ShardPositionsService {
         let LocalShardPositionsUpdate {
             source_uid,
             shard_positions,
+            ..
         } = update;
         let updated_shard_positions: Vec<(ShardId, Position)> =
             self.apply_update(&source_uid, shard_positions);
         if updated_shard_positions.is_empty() {
             return Ok(());
         }
-        self.publish_positions_into_chitchat(&source_uid, &updated_shard_positions)
-            .await;
+        self.queue_position_writes(&source_uid, &updated_shard_positions);
         self.publish_shard_updates_to_event_broker(source_uid, updated_shard_positions);
         Ok(())
     }
 }
 
+#[async_trait]
+impl Handler<FlushShardPositions> for ShardPositionsService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: FlushShardPositions,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.flush_position_writes(ctx).await;
+        ctx.schedule_self_msg(self.flush_settings.interval, FlushShardPositions);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Handler<GcEofShards> for ShardPositionsService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: GcEofShards,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        self.gc_eof_shards(Instant::now(), ctx).await;
+        ctx.schedule_self_msg(GC_EOF_SHARDS_INTERVAL, GcEofShards);
+        Ok(())
+    }
+}
+
 impl ShardPositionsService {
-    async fn publish_positions_into_chitchat(
+    /// Removes the shards that have been at EOF for longer than the retention period and that are
+    /// closed or deleted in the metastore.
+    async fn gc_eof_shards(&mut self, now: Instant, ctx: &ActorContext<Self>) {
+        let candidates_per_source: Vec<(SourceUid, Vec<ShardId>)> = self
+            .eof_since_per_source
+            .iter()
+            .filter_map(|(source_uid, eof_since_per_shard)| {
+                let candidates: Vec<ShardId> = eof_since_per_shard
+                    .iter()
+                    .filter(|(_, eof_since)| now.duration_since(**eof_since) >= self.eof_retention)
+                    .map(|(shard_id, _)| shard_id.clone())
+                    .collect();
+                if candidates.is_empty() {
+                    None
+                } else {
+                    Some((source_uid.clone(), candidates))
+                }
+            })
+            .collect();
+
+        for (source_uid, candidates) in candidates_per_source {
+            let Some(removable_shard_ids) = self
+                .find_closed_or_deleted_shards(&source_uid, candidates, ctx)
+                .await
+            else {
+                continue;
+            };
+            if removable_shard_ids.is_empty() {
+                continue;
+            }
+            self.remove_shards(&source_uid, &removable_shard_ids);
+            self.remove_positions_from_chitchat(&source_uid, &removable_shard_ids)
+                .await;
+            info!(
+                index_uid=%source_uid.index_uid,
+                source_id=%source_uid.source_id,
+                num_shards=removable_shard_ids.len(),
+                "removed positions of shards that reached EOF"
+            );
+            self.event_broker.publish(ShardPositionsCleanup {
+                source_uid,
+                removed_shard_ids: removable_shard_ids,
+            });
+        }
+    }
+
+    /// Returns the subset of `shard_ids` that are closed or deleted in the metastore, or `None` if
+    /// the metastore could not be reached.
+    async fn find_closed_or_deleted_shards(
         &self,
         source_uid: &SourceUid,
+        shard_ids: Vec<ShardId>,
+        ctx: &ActorContext<Self>,
+    ) -> Option<Vec<ShardId>> {
+        let list_shards_request = ListShardsRequest {
+            subrequests: vec![ListShardsSubrequest {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                shard_state: None,
+            }],
+        };
+        let list_shards_result = ctx
+            .protect_future(self.metastore.list_shards(list_shards_request))
+            .await;
+        let list_shards_response = match list_shards_result {
+            Ok(list_shards_response) => list_shards_response,
+            // The index or the source was deleted, and its shards along with it.
+            Err(MetastoreError::NotFound(_)) => return Some(shard_ids),
+            Err(error) => {
+                warn!(%error, "failed to list shards from the metastore");
+                return None;
+            }
+        };
+        let open_shard_ids: HashSet<ShardId> = list_shards_response
+            .subresponses
+            .into_iter()
+            .flat_map(|subresponse| subresponse.shards)
+            .filter(|shard| shard.shard_state() != ShardState::Closed)
+            .filter_map(|shard| shard.shard_id)
+            .collect();
+        let closed_or_deleted_shard_ids = shard_ids
+            .into_iter()
+            .filter(|shard_id| !open_shard_ids.contains(shard_id))
+            .collect();
+        Some(closed_or_deleted_shard_ids)
+    }
+
+    fn remove_shards(&mut self, source_uid: &SourceUid, shard_ids: &[ShardId]) {
+        if let Some(shard_positions) = self.shard_positions_per_source.get_mut(source_uid) {
+            for shard_id in shard_ids {
+                shard_positions.remove(shard_id);
+            }
+            if shard_positions.is_empty() {
+                self.shard_positions_per_source.remove(source_uid);
+            }
+        }
+        if let Some(eof_since_per_shard) = self.eof_since_per_source.get_mut(source_uid) {
+            for shard_id in shard_ids {
+                eof_since_per_shard.remove(shard_id);
+            }
+            if eof_since_per_shard.is_empty() {
+                self.eof_since_per_source.remove(source_uid);
+            }
+        }
+    }
+
+    /// Deletes the chitchat keys of the given shards published by this node, along with their
+    /// pending writes. Keys published by other nodes are deleted by their own shard positions
+    /// service.
+    async fn remove_positions_from_chitchat(
+        &mut self,
+        source_uid: &SourceUid,
+        shard_ids: &[ShardId],
+    ) {
+        let SourceUid {
+            index_uid,
+            source_id,
+        } = &source_uid;
+        for shard_id in shard_ids {
+            let key = format!("{SHARD_POSITIONS_PREFIX}{index_uid}:{source_id}:{shard_id}");
+            self.pending_position_writes.remove(&key);
+
+            if self.cluster.get_self_key_value(&key).await.is_some() {
+                self.cluster.remove_self_key(&key).await;
+            }
+        }
+        INDEXER_METRICS
+            .pending_shard_position_writes
+            .set(self.pending_position_writes.len() as i64);
+    }
+
+    /// Queues the given positions for the next flush into chitchat. A position still pending for
+    /// a shard is superseded.
+    fn queue_position_writes(
+        &mut self,
+        source_uid: &SourceUid,
         shard_positions: &[(ShardId, Position)],
     ) {
         let SourceUid {
@@ -269,10 +612,62 @@ impl ShardPositionsService {
         } = &source_uid;
         for (shard_id, position) in shard_positions {
             let key = format!("{SHARD_POSITIONS_PREFIX}{index_uid}:{source_id}:{shard_id}");
-            self.cluster
-                .set_self_key_value_delete_after_ttl(key, position)
+            self.pending_position_writes.insert(key, position.clone());
+        }
+        INDEXER_METRICS
+            .pending_shard_position_writes
+            .set(self.pending_position_writes.len() as i64);
+    }
+
+    /// Writes the pending shard positions into chitchat, in batches of at most `batch_size` keys.
+    /// If a batch cannot be written in time, the batch and the following ones are queued again
+    /// for the next flush.
+    async fn flush_position_writes(&mut self, ctx: &ActorContext<Self>) {
+        if self.pending_position_writes.is_empty() {
+            return;
+        }
+        let _flush_timer = INDEXER_METRICS
+            .shard_positions_flush_duration_secs
+            .start_timer();
+        let batch_size = self.flush_settings.batch_size.max(1);
+        let mut pending_position_writes =
+            std::mem::take(&mut self.pending_position_writes).into_iter();
+
+        loop {
+            let batch: Vec<(String, Position)> =
+                pending_position_writes.by_ref().take(batch_size).collect();
+
+            if batch.is_empty() {
+                break;
+            }
+            let key_values = batch
+                .iter()
+                .map(|(key, position)| (key.clone(), position.to_string()));
+            let write_future = self
+                .cluster
+                .set_self_key_values_delete_after_ttl(key_values);
+            let write_result = ctx
+                .protect_future(tokio::time::timeout(CHITCHAT_WRITE_TIMEOUT, write_future))
                 .await;
+            self.num_chitchat_writes += 1;
+
+            if write_result.is_err() {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    num_positions = batch.len(),
+                    "failed to write shard positions into chitchat: retrying at next flush"
+                );
+                self.pending_position_writes.extend(batch);
+                self.pending_position_writes
+                    .extend(pending_position_writes.by_ref());
+                break;
+            }
+            // Let the gossip loop acquire the chitchat lock between two batches.
+            tokio::task::yield_now().await;
         }
+        INDEXER_METRICS
+            .pending_shard_position_writes
+            .set(self.pending_position_writes.len() as i64);
     }
 
     fn publish_shard_updates_to_event_broker(
@@ -315,6 +710,14 @@ impl ShardPositionsService {
 
         for (shard, position) in updated_positions_per_shard.iter() {
             current_shard_positions.insert(shard.clone(), position.clone());
+
+            if position.is_eof() {
+                self.eof_since_per_source
+                    .entry(source_uid.clone())
+                    .or_default()
+                    .entry(shard.clone())
+                    .or_insert_with(Instant::now);
+            }
         }
 
         updated_positions_per_shard
@@ -325,13 +728,22 @@ impl ShardPositionsService {
 mod tests {
     use std::time::Duration;
 
-    use quickwit_actors::Universe;
+    use quickwit_actors::{Observe, Universe};
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
     use quickwit_common::pubsub::EventBroker;
+    use quickwit_proto::ingest::Shard;
+    use quickwit_proto::metastore::{
+        ListShardsResponse, ListShardsSubresponse, MockMetastoreService,
+    };
     use quickwit_proto::types::IndexUid;
 
     use super::*;
 
+    const TEST_FLUSH_SETTINGS: ShardPositionsFlushSettings = ShardPositionsFlushSettings {
+        interval: Duration::from_millis(10),
+        batch_size: 1_000,
+    };
+
     #[tokio::test]
     async fn test_shard_positions_from_cluster() {
         quickwit_common::setup_logging_for_tests();
@@ -374,6 +786,9 @@ mod tests {
             universe1.spawn_ctx(),
             event_broker1.clone(),
             cluster1.clone(),
+            MetastoreServiceClient::mocked(),
+            Duration::from_secs(3_600),
+            TEST_FLUSH_SETTINGS,
         );
 
         // One of the event is published before cluster formation.
@@ -404,6 +819,9 @@ mod tests {
             universe2.spawn_ctx(),
             event_broker2.clone(),
             cluster2.clone(),
+            MetastoreServiceClient::mocked(),
+            Duration::from_secs(3_600),
+            TEST_FLUSH_SETTINGS,
         );
 
         // ----------------------
@@ -485,7 +903,14 @@ mod tests {
             .unwrap();
         let event_broker = EventBroker::default();
 
-        ShardPositionsService::spawn(universe.spawn_ctx(), event_broker.clone(), cluster.clone());
+        ShardPositionsService::spawn(
+            universe.spawn_ctx(),
+            event_broker.clone(),
+            cluster.clone(),
+            MetastoreServiceClient::mocked(),
+            Duration::from_secs(3_600),
+            TEST_FLUSH_SETTINGS,
+        );
 
         let index_uid = IndexUid::new_with_random_ulid("index-test");
         let source_id = "test-source".to_string();
@@ -557,4 +982,288 @@ mod tests {
         }
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_shard_positions_gc_eof_shards() {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::with_accelerated_time();
+        let transport = ChannelTransport::default();
+
+        let cluster: Cluster = create_cluster_for_test(Vec::new(), &[], &transport, true)
+            .await
+            .unwrap();
+        let event_broker = EventBroker::default();
+
+        let (cleanup_tx, mut cleanup_rx) =
+            tokio::sync::mpsc::unbounded_channel::<ShardPositionsCleanup>();
+        event_broker
+            .subscribe(move |cleanup: ShardPositionsCleanup| {
+                cleanup_tx.send(cleanup).unwrap();
+            })
+            .forever();
+
+        let index_uid = IndexUid::new_with_random_ulid("index-test");
+        let source_id = "test-source".to_string();
+        let key_prefix = format!("{SHARD_POSITIONS_PREFIX}{index_uid}:{source_id}");
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: source_id.clone(),
+        };
+
+        // Shard 1 is closed, shard 2 is still open (e.g. not yet closed by the control plane),
+        // shard 3 was deleted, and shard 4 has not reached EOF.
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_shards()
+            .returning(move |request| {
+                assert_eq!(request.subrequests.len(), 1);
+                let subrequest = &request.subrequests[0];
+                assert_eq!(subrequest.source_id, "test-source");
+
+                let shards = vec![
+                    Shard {
+                        index_uid: subrequest.index_uid.clone(),
+                        source_id: subrequest.source_id.clone(),
+                        shard_id: Some(ShardId::from(1)),
+                        shard_state: ShardState::Closed as i32,
+                        ..Default::default()
+                    },
+                    Shard {
+                        index_uid: subrequest.index_uid.clone(),
+                        source_id: subrequest.source_id.clone(),
+                        shard_id: Some(ShardId::from(2)),
+                        shard_state: ShardState::Open as i32,
+                        ..Default::default()
+                    },
+                    Shard {
+                        index_uid: subrequest.index_uid.clone(),
+                        source_id: subrequest.source_id.clone(),
+                        shard_id: Some(ShardId::from(4)),
+                        shard_state: ShardState::Open as i32,
+                        ..Default::default()
+                    },
+                ];
+                let response = ListShardsResponse {
+                    subresponses: vec![ListShardsSubresponse {
+                        index_uid: subrequest.index_uid.clone(),
+                        source_id: subrequest.source_id.clone(),
+                        shards,
+                    }],
+                };
+                Ok(response)
+            });
+        let eof_retention = Duration::from_millis(500);
+        let shard_positions_service_mailbox = ShardPositionsService::spawn(
+            universe.spawn_ctx(),
+            event_broker.clone(),
+            cluster.clone(),
+            MetastoreServiceClient::from_mock(mock_metastore),
+            eof_retention,
+            TEST_FLUSH_SETTINGS,
+        );
+        event_broker.publish(LocalShardPositionsUpdate::new(
+            source_uid.clone(),
+            vec![
+                (ShardId::from(1), Position::eof(10u64)),
+                (ShardId::from(2), Position::eof(20u64)),
+                (ShardId::from(3), Position::eof(30u64)),
+                (ShardId::from(4), Position::offset(40u64)),
+            ],
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(state.num_shards, 4);
+        assert_eq!(state.num_eof_shards, 3);
+        assert_eq!(state.num_pending_position_writes, 0);
+        for shard_id in 1..=4 {
+            let key = format!("{key_prefix}:{shard_id}");
+            assert!(cluster.get_self_key_value(&key).await.is_some());
+        }
+
+        // The EOF shards age out.
+        let cleanup = tokio::time::timeout(Duration::from_secs(10), cleanup_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cleanup,
+            ShardPositionsCleanup {
+                source_uid: source_uid.clone(),
+                removed_shard_ids: vec![ShardId::from(1), ShardId::from(3)],
+            }
+        );
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(state.num_shards, 2);
+        assert_eq!(state.num_eof_shards, 1);
+        for (shard_id, is_removed) in [(1, true), (2, false), (3, true), (4, false)] {
+            let key = format!("{key_prefix}:{shard_id}");
+            assert_eq!(cluster.get_self_key_value(&key).await.is_none(), is_removed);
+        }
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_shard_positions_ignores_regressions_from_cluster() {
+        let universe = Universe::with_accelerated_time();
+        let transport = ChannelTransport::default();
+        let cluster: Cluster = create_cluster_for_test(Vec::new(), &[], &transport, true)
+            .await
+            .unwrap();
+        let event_broker = EventBroker::default();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ShardPositionsUpdate>();
+        event_broker
+            .subscribe(move |update: ShardPositionsUpdate| {
+                tx.send(update).unwrap();
+            })
+            .forever();
+
+        let index_uid = IndexUid::new_with_random_ulid("index-regression-test");
+        let source_uid = SourceUid {
+            index_uid: index_uid.clone(),
+            source_id: "test-source".to_string(),
+        };
+        let shard_positions_service_mailbox = ShardPositionsService::spawn(
+            universe.spawn_ctx(),
+            event_broker.clone(),
+            cluster.clone(),
+            MetastoreServiceClient::mocked(),
+            Duration::from_secs(3_600),
+            TEST_FLUSH_SETTINGS,
+        );
+        let num_regressions_before = INDEXER_METRICS
+            .shard_position_regressions_total
+            .with_label_values([index_uid.index_id.as_str(), "test-source"])
+            .get();
+
+        shard_positions_service_mailbox
+            .ask(ClusterShardPositionsUpdate {
+                source_uid: source_uid.clone(),
+                shard_id: ShardId::from(1),
+                position: Position::offset(20u64),
+                node_id: NodeId::from("test-node-1"),
+            })
+            .await
+            .unwrap();
+        let update = rx.recv().await.unwrap();
+        assert_eq!(
+            update.updated_shard_positions,
+            [(ShardId::from(1), Position::offset(20u64))]
+        );
+
+        // A node that rejoined the cluster with a stale state publishes an older position.
+        shard_positions_service_mailbox
+            .ask(ClusterShardPositionsUpdate {
+                source_uid: source_uid.clone(),
+                shard_id: ShardId::from(1),
+                position: Position::offset(10u64),
+                node_id: NodeId::from("test-node-2"),
+            })
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(
+            state,
+            ShardPositionsServiceState {
+                num_shards: 1,
+                num_eof_shards: 0,
+                num_position_regressions_per_source: BTreeMap::from_iter([(
+                    source_uid.to_string(),
+                    1
+                )]),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            INDEXER_METRICS
+                .shard_position_regressions_total
+                .with_label_values([index_uid.index_id.as_str(), "test-source"])
+                .get(),
+            num_regressions_before + 1
+        );
+
+        // Replaying the known position is not a regression.
+        shard_positions_service_mailbox
+            .ask(ClusterShardPositionsUpdate {
+                source_uid: source_uid.clone(),
+                shard_id: ShardId::from(1),
+                position: Position::offset(20u64),
+                node_id: NodeId::from("test-node-1"),
+            })
+            .await
+            .unwrap();
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(
+            state.num_position_regressions_per_source[&source_uid.to_string()],
+            1
+        );
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_shard_positions_batches_chitchat_writes() {
+        let universe = Universe::new();
+        let transport = ChannelTransport::default();
+        let cluster: Cluster = create_cluster_for_test(Vec::new(), &[], &transport, true)
+            .await
+            .unwrap();
+        let event_broker = EventBroker::default();
+
+        // The periodic flush never fires during the test: flushes are triggered explicitly.
+        let flush_settings = ShardPositionsFlushSettings {
+            interval: Duration::from_secs(3_600),
+            batch_size: 100,
+        };
+        let shard_positions_service_mailbox = ShardPositionsService::spawn(
+            universe.spawn_ctx(),
+            event_broker.clone(),
+            cluster.clone(),
+            MetastoreServiceClient::mocked(),
+            Duration::from_secs(3_600),
+            flush_settings,
+        );
+        let index_uid = IndexUid::new_with_random_ulid("index-test");
+        let source_id = "test-source".to_string();
+        let key_prefix = format!("{SHARD_POSITIONS_PREFIX}{index_uid}:{source_id}");
+        let source_uid = SourceUid {
+            index_uid,
+            source_id,
+        };
+        let num_shards = 1_000u64;
+
+        // 5,000 updates: every shard moves forward five times before the flush.
+        for offset in 1..=5u64 {
+            for shard_id in 0..num_shards {
+                shard_positions_service_mailbox
+                    .ask(LocalShardPositionsUpdate::new(
+                        source_uid.clone(),
+                        vec![(ShardId::from(shard_id), Position::offset(offset))],
+                    ))
+                    .await
+                    .unwrap();
+            }
+        }
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(state.num_pending_position_writes, num_shards as usize);
+        assert_eq!(state.num_chitchat_writes, 0);
+
+        shard_positions_service_mailbox
+            .ask(FlushShardPositions)
+            .await
+            .unwrap();
+
+        let state = shard_positions_service_mailbox.ask(Observe).await.unwrap();
+        assert_eq!(state.num_pending_position_writes, 0);
+        assert_eq!(state.num_chitchat_writes, 10);
+
+        for shard_id in 0..num_shards {
+            let key = format!("{key_prefix}:{}", ShardId::from(shard_id));
+            let value = cluster.get_self_key_value(&key).await.unwrap();
+            assert_eq!(value, Position::offset(5u64).to_string());
+        }
+        universe.assert_quit().await;
+    }
 }
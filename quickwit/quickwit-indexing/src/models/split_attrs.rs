@@ -22,6 +22,7 @@ use std::fmt;
 use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 
+use quickwit_doc_mapper::term_presence::TermPresenceFilters;
 use quickwit_metastore::SplitMetadata;
 use quickwit_proto::types::{DocMappingUid, IndexUid, NodeId, SourceId, SplitId};
 use tantivy::DateTime;
@@ -94,6 +95,7 @@ pub fn create_split_metadata(
     merge_policy: &Arc<dyn MergePolicy>,
     split_attrs: &SplitAttrs,
     tags: BTreeSet<String>,
+    term_presence_filters: TermPresenceFilters,
     footer_offsets: Range<u64>,
 ) -> SplitMetadata {
     let create_timestamp = OffsetDateTime::now_utc().unix_timestamp();
@@ -115,6 +117,7 @@ pub fn create_split_metadata(
         create_timestamp,
         maturity,
         tags,
+        term_presence_filters,
         footer_offsets,
         delete_opstamp: split_attrs.delete_opstamp,
         num_merge_ops: split_attrs.num_merge_ops,
@@ -28,6 +28,7 @@ use quickwit_proto::metastore::SourceType;
 use quickwit_proto::types::SourceId;
 
 use super::doc_file_reader::ObjectUriBatchReader;
+use super::prefix_poller::PrefixPoller;
 #[cfg(feature = "queue-sources")]
 use super::queue_sources::coordinator::QueueCoordinator;
 use crate::actors::DocProcessor;
@@ -41,6 +42,7 @@ enum FileSourceState {
         num_bytes_processed: u64,
         num_lines_processed: u64,
     },
+    Polling(Box<PrefixPoller>),
 }
 
 pub struct FileSource {
@@ -68,7 +70,7 @@ impl Source for FileSource {
             FileSourceState::Notification(coordinator) => {
                 coordinator.initialize(doc_processor_mailbox, ctx).await
             }
-            FileSourceState::Filepath { .. } => Ok(()),
+            FileSourceState::Filepath { .. } | FileSourceState::Polling(_) => Ok(()),
         }
     }
 
@@ -101,6 +103,9 @@ impl Source for FileSource {
                     return Err(ActorExitStatus::Success);
                 }
             }
+            FileSourceState::Polling(poller) => {
+                return poller.emit_batches(doc_processor_mailbox, ctx).await;
+            }
         }
         Ok(Duration::ZERO)
     }
@@ -120,7 +125,7 @@ impl Source for FileSource {
             FileSourceState::Notification(coordinator) => {
                 coordinator.suggest_truncate(checkpoint, ctx).await
             }
-            FileSourceState::Filepath { .. } => Ok(()),
+            FileSourceState::Filepath { .. } | FileSourceState::Polling(_) => Ok(()),
         }
     }
 
@@ -140,6 +145,9 @@ impl Source for FileSource {
                     "num_lines_processed": num_lines_processed,
                 })
             }
+            FileSourceState::Polling(poller) => {
+                serde_json::to_value(poller.observable_state()).unwrap()
+            }
         }
     }
 }
@@ -179,6 +187,10 @@ impl TypedSourceFactory for FileSourceFactory {
                     num_lines_processed: 0,
                 }
             }
+            FileSourceParams::Polling(polling) => {
+                let poller = PrefixPoller::try_new(polling, source_runtime).await?;
+                FileSourceState::Polling(Box::new(poller))
+            }
             #[cfg(feature = "sqs")]
             FileSourceParams::Notifications(quickwit_config::FileSourceNotification::Sqs(
                 sqs_config,
@@ -209,7 +221,7 @@ mod tests {
     use bytes::Bytes;
     use quickwit_actors::{Command, Universe};
     use quickwit_common::uri::Uri;
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpointDelta};
     use quickwit_proto::types::{IndexUid, Position};
 
@@ -239,6 +251,10 @@ mod tests {
             source_id: "test-file-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -291,6 +307,10 @@ mod tests {
             source_id: "test-file-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -355,6 +375,10 @@ mod tests {
             source_id: "test-file-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
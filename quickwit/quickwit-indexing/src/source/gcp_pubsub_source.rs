@@ -292,7 +292,7 @@ mod gcp_pubsub_emulator_tests {
     use google_cloud_pubsub::publisher::Publisher;
     use google_cloud_pubsub::subscription::SubscriptionConfig;
     use quickwit_actors::Universe;
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_proto::types::{IndexId, IndexUid};
     use serde_json::json;
 
@@ -313,6 +313,10 @@ mod gcp_pubsub_emulator_tests {
             source_id,
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::PubSub(PubSubSourceParams {
                 project_id: Some(GCP_TEST_PROJECT.to_string()),
                 enable_backfill_mode: true,
@@ -259,7 +259,8 @@ mod tests {
     use quickwit_actors::Universe;
     use quickwit_common::rand::append_random_suffix;
     use quickwit_config::{
-        IngestApiConfig, SourceConfig, SourceInputFormat, SourceParams, INGEST_API_SOURCE_ID,
+        IngestApiConfig, SourceConfig, SourceInputFormat, SourceParams, SourcePriority,
+        INGEST_API_SOURCE_ID,
     };
     use quickwit_ingest::{init_ingest_api, CommitType, DocBatchBuilder, IngestRequest};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
@@ -300,6 +301,10 @@ mod tests {
             source_id: INGEST_API_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::IngestApi,
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -356,6 +361,57 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_source_pause_resume() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let index_id = append_random_suffix("test-ingest-api-source");
+        let index_uid = IndexUid::new_with_random_ulid(&index_id);
+        let temp_dir = tempfile::tempdir()?;
+        let queues_dir_path = temp_dir.path();
+
+        let ingest_api_service =
+            init_ingest_api(&universe, queues_dir_path, &IngestApiConfig::default()).await?;
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_config = make_source_config();
+        let source_runtime = SourceRuntimeBuilder::new(index_uid, source_config)
+            .with_queues_dir(queues_dir_path)
+            .build();
+        let ingest_api_source = IngestApiSource::try_new(source_runtime).await?;
+        let ingest_api_source_actor = SourceActor {
+            source: Box::new(ingest_api_source),
+            doc_processor_mailbox,
+        };
+        let (_ingest_api_source_mailbox, ingest_api_source_handle) =
+            universe.spawn_builder().spawn(ingest_api_source_actor);
+        ingest_api_source_handle.pause();
+
+        let ingest_req = make_ingest_request(index_id.clone(), 1, 1_000, CommitType::Auto);
+        ingest_api_service
+            .ask_for_res(ingest_req)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        universe.sleep(Duration::from_secs(2)).await;
+        let counters = ingest_api_source_handle.observe().await.state;
+        assert_eq!(counters["num_docs_processed"], 0);
+        assert!(doc_processor_inbox
+            .drain_for_test_typed::<RawDocBatch>()
+            .is_empty());
+
+        ingest_api_source_handle.resume();
+        universe.sleep(Duration::from_secs(2)).await;
+        let counters = ingest_api_source_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters["num_docs_processed"], 1_000);
+        let doc_batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(doc_batches.len(), 1);
+        assert!(&doc_batches[0].docs[0].starts_with(b"000000"));
+        ingest_api_source_handle.quit().await;
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     /// See #2310
     #[tokio::test]
     async fn test_ingest_api_source_partition_id_changes() -> anyhow::Result<()> {
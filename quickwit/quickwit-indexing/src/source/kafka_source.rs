@@ -766,7 +766,7 @@ mod kafka_broker_tests {
 
     use quickwit_actors::{ActorContext, Universe};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::types::IndexUid;
@@ -882,6 +882,10 @@ mod kafka_broker_tests {
             source_id: source_id.clone(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Kafka(KafkaSourceParams {
                 topic: topic.to_string(),
                 client_log_level: None,
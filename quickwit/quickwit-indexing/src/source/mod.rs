@@ -67,6 +67,7 @@ mod ingest_api_source;
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+mod prefix_poller;
 #[cfg(feature = "pulsar")]
 mod pulsar_source;
 #[cfg(feature = "queue-sources")]
@@ -77,7 +78,7 @@ mod vec_source;
 mod void_source;
 
 use std::collections::BTreeSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -95,7 +96,7 @@ use once_cell::sync::{Lazy, OnceCell};
 pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
 #[cfg(feature = "sqs")]
 pub use queue_sources::sqs_queue;
-use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
+use quickwit_actors::{Actor, ActorContext, ActorExitStatus, ActorState, Handler, Mailbox};
 use quickwit_common::metrics::{GaugeGuard, MEMORY_METRICS};
 use quickwit_common::pubsub::EventBroker;
 use quickwit_common::runtimes::RuntimeType;
@@ -115,7 +116,7 @@ use quickwit_storage::StorageResolver;
 use serde_json::Value as JsonValue;
 pub use source_factory::{SourceFactory, SourceLoader, TypedSourceFactory};
 use tokio::runtime::Handle;
-use tracing::error;
+use tracing::{error, info_span, Instrument, Span};
 pub use vec_source::{VecSource, VecSourceFactory};
 pub use void_source::{VoidSource, VoidSourceFactory};
 
@@ -374,6 +375,13 @@ impl Handler<Loop> for SourceActor {
     type Reply = ();
 
     async fn handle(&mut self, _message: Loop, ctx: &SourceContext) -> Result<(), ActorExitStatus> {
+        if ctx.state() == ActorState::Paused {
+            // Scheduled messages are processed even when the actor is paused. We push the loop
+            // message back to the low priority queue, which is only consumed once the actor is
+            // resumed.
+            ctx.send_self_message(Loop).await?;
+            return Ok(());
+        }
         let wait_for = self
             .source
             .emit_batches(&self.doc_processor_mailbox, ctx)
@@ -437,6 +445,11 @@ pub async fn check_source_connectivity(
             storage.file_num_bytes(file_name).await?;
             Ok(())
         }
+        SourceParams::File(FileSourceParams::Polling(polling)) => {
+            let storage = storage_resolver.resolve(&polling.prefix).await?;
+            storage.list_files_recursive(Path::new(""), None).await?;
+            Ok(())
+        }
         #[allow(unused_variables)]
         SourceParams::File(FileSourceParams::Notifications(FileSourceNotification::Sqs(
             sqs_config,
@@ -487,8 +500,14 @@ pub async fn check_source_connectivity(
     }
 }
 
+/// Sent by the publisher to the source once a checkpoint has been published.
 #[derive(Debug)]
-pub struct SuggestTruncate(pub SourceCheckpoint);
+pub struct SuggestTruncate {
+    pub checkpoint: SourceCheckpoint,
+    /// Span of the publication that emitted this message. The truncation (and, for ingest
+    /// sources, the resulting shard positions update) is recorded within this span.
+    pub parent_span: Span,
+}
 
 #[async_trait]
 impl Handler<SuggestTruncate> for SourceActor {
@@ -499,9 +518,18 @@ impl Handler<SuggestTruncate> for SourceActor {
         suggest_truncate: SuggestTruncate,
         ctx: &SourceContext,
     ) -> Result<(), ActorExitStatus> {
-        let SuggestTruncate(checkpoint) = suggest_truncate;
+        let SuggestTruncate {
+            checkpoint,
+            parent_span,
+        } = suggest_truncate;
+        let suggest_truncate_span = info_span!(parent: parent_span.id(), "suggest-truncate");
 
-        if let Err(error) = self.source.suggest_truncate(checkpoint, ctx).await {
+        if let Err(error) = self
+            .source
+            .suggest_truncate(checkpoint, ctx)
+            .instrument(suggest_truncate_span)
+            .await
+        {
             // Failing to process suggest truncate does not
             // kill the source nor the indexing pipeline, but we log the error.
             error!(%error, "failed to process suggest truncate");
@@ -575,7 +603,7 @@ mod tests {
 
     use std::num::NonZeroUsize;
 
-    use quickwit_config::{SourceInputFormat, VecSourceParams};
+    use quickwit_config::{SourceInputFormat, SourcePriority, VecSourceParams};
     use quickwit_metastore::checkpoint::IndexCheckpointDelta;
     use quickwit_metastore::IndexMetadata;
     use quickwit_proto::metastore::{IndexMetadataResponse, MockMetastoreService};
@@ -672,6 +700,7 @@ mod tests {
                         let delta = IndexCheckpointDelta {
                             source_id,
                             source_delta,
+                            source_incarnation_opt: None,
                         };
                         index_metadata.checkpoint.try_apply_delta(delta).unwrap();
                     }
@@ -690,6 +719,10 @@ mod tests {
                 source_id: "void".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::void(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -701,6 +734,10 @@ mod tests {
                 source_id: "vec".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -712,6 +749,10 @@ mod tests {
                 source_id: "file".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::file_from_str("file-does-not-exist.json").unwrap(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -727,6 +768,10 @@ mod tests {
                 source_id: "file".to_string(),
                 num_pipelines: NonZeroUsize::new(1).unwrap(),
                 enabled: true,
+                critical: false,
+                priority: SourcePriority::Normal,
+                placement_selector: None,
+                max_shards_per_pipeline: None,
                 source_params: SourceParams::file_from_str("data/test_corpus.json").unwrap(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
@@ -795,6 +840,7 @@ mod test_setup_helper {
         let checkpoint_delta = IndexCheckpointDelta {
             source_id: source_config.source_id.to_string(),
             source_delta,
+            source_incarnation_opt: None,
         };
         let checkpoint_delta_json = serde_json::to_string(&checkpoint_delta).unwrap();
         let publish_splits_request = PublishSplitsRequest {
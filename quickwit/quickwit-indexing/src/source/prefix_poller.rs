@@ -0,0 +1,882 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::uri::Uri;
+use quickwit_config::{FileSourcePolling, FileSourcePollingOrdering};
+use quickwit_metastore::checkpoint::PartitionId;
+use quickwit_proto::metastore::SourceType;
+use quickwit_proto::types::Position;
+use quickwit_storage::{FileEntry, Storage, StorageError, StorageErrorKind, StorageResolver};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use super::doc_file_reader::DocFileReader;
+use super::{BatchBuilder, BATCH_NUM_BYTES_LIMIT};
+use crate::actors::DocProcessor;
+use crate::source::{SourceContext, SourceRuntime};
+
+/// Marks an object that has been entirely ingested in place of its offset. It sorts after the
+/// zero-padded offsets of the object.
+const COMPLETED_OBJECT_MARKER: &str = "~";
+
+/// With the lexicographic ordering, the prefix is listed from the last ingested key onwards.
+/// Objects listed out of order can only be detected by listing the whole prefix, which is done
+/// once every `FULL_LISTING_PERIOD` polls.
+const FULL_LISTING_PERIOD: usize = 10;
+
+#[derive(Default, Serialize)]
+pub struct PrefixPollerObservableState {
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of lines processed by the source.
+    pub num_lines_processed: u64,
+    /// Number of objects entirely processed by the source.
+    pub num_objects_processed: u64,
+    /// Number of objects skipped because they were listed out of order or deleted before they
+    /// could be read.
+    pub num_objects_skipped: u64,
+}
+
+fn millis_since_epoch(system_time: SystemTime) -> u64 {
+    system_time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// An object listed under the polled prefix along with the key used to order it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct PolledObject {
+    key: String,
+    /// Encodes the position of the object in the ingestion order. Positions within the object
+    /// are formed by appending the offset, or the completion marker, to it.
+    cursor_prefix: String,
+    last_modified_millis: u64,
+    start_offset: u64,
+}
+
+impl PolledObject {
+    fn new(file_entry: &FileEntry, ordering: FileSourcePollingOrdering) -> Self {
+        let key = file_entry.path.to_string_lossy().to_string();
+        let last_modified_millis = millis_since_epoch(file_entry.last_modified);
+        let sort_key = match ordering {
+            FileSourcePollingOrdering::Lexicographic => 0,
+            FileSourcePollingOrdering::LastModified => last_modified_millis,
+        };
+        // The tab separator sorts before any printable character, so a key always sorts before
+        // the keys it is a prefix of.
+        let cursor_prefix = format!("{sort_key:020}\t{key}\t");
+        Self {
+            key,
+            cursor_prefix,
+            last_modified_millis,
+            start_offset: 0,
+        }
+    }
+
+    /// Positions end with the start time of the last full listing of the prefix. It does not
+    /// affect their order since offsets are zero-padded.
+    fn position(&self, offset: u64, full_listing_millis: u64) -> Position {
+        Position::offset(
+            format!(
+                "{}{offset:020}\t{full_listing_millis:020}",
+                self.cursor_prefix
+            )
+            .as_str(),
+        )
+    }
+
+    fn completed_position(&self, full_listing_millis: u64) -> Position {
+        Position::offset(
+            format!(
+                "{}{COMPLETED_OBJECT_MARKER}\t{full_listing_millis:020}",
+                self.cursor_prefix
+            )
+            .as_str(),
+        )
+    }
+}
+
+/// Position of the source within the ingestion order of the objects.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Cursor {
+    cursor_prefix: String,
+    /// `None` if the object has been entirely ingested.
+    offset_opt: Option<u64>,
+    /// Start time of the last full listing of the prefix before the position was reached.
+    full_listing_millis: u64,
+}
+
+impl Cursor {
+    fn parse(position: &Position) -> anyhow::Result<Option<Self>> {
+        let position_str = match position {
+            Position::Beginning => return Ok(None),
+            Position::Offset(offset) => offset.as_str(),
+            Position::Eof(_) => bail!("polled prefix positions should never reach EOF"),
+        };
+        let invalid_position = || format!("invalid polled prefix position `{position_str}`");
+        let (head, full_listing_millis_str) = position_str
+            .rsplit_once('\t')
+            .with_context(invalid_position)?;
+        let full_listing_millis = full_listing_millis_str
+            .parse::<u64>()
+            .with_context(invalid_position)?;
+        let (cursor_prefix, offset_str) = head.rsplit_once('\t').with_context(invalid_position)?;
+        let offset_opt = if offset_str == COMPLETED_OBJECT_MARKER {
+            None
+        } else {
+            let offset = offset_str.parse::<u64>().with_context(invalid_position)?;
+            Some(offset)
+        };
+        Ok(Some(Self {
+            cursor_prefix: format!("{cursor_prefix}\t"),
+            offset_opt,
+            full_listing_millis,
+        }))
+    }
+
+    /// Returns the key of the object the cursor points to.
+    fn key(&self) -> &str {
+        let (_sort_key, key_and_tab) = self
+            .cursor_prefix
+            .split_once('\t')
+            .expect("cursor prefix should contain a sort key");
+        key_and_tab.strip_suffix('\t').unwrap_or(key_and_tab)
+    }
+}
+
+struct ObjectInProgress {
+    object: PolledObject,
+    reader: DocFileReader,
+    current_offset: u64,
+}
+
+/// The `PrefixPoller` periodically lists the objects stored under a prefix and ingests the
+/// objects it has not seen yet, one at a time, in the configured order.
+///
+/// The whole prefix is tracked as a single partition. Its position encodes the object being read
+/// and the offset within that object, so that the lexicographic order of the positions matches
+/// the ingestion order.
+///
+/// With the lexicographic ordering, the prefix is listed from the last ingested key onwards, and
+/// entirely once in a while to report the objects listed out of order. The last-modified
+/// ordering does not map to the order of the keys, so every poll lists the whole prefix.
+pub struct PrefixPoller {
+    storage_resolver: StorageResolver,
+    storage: Arc<dyn Storage>,
+    prefix: Uri,
+    partition_id: PartitionId,
+    source_type: SourceType,
+    poll_interval: Duration,
+    ordering: FileSourcePollingOrdering,
+    current_position: Position,
+    /// Keys listed since the last full listing, used to tell apart new objects that were listed
+    /// out of order from objects that have already been ingested. `None` until the first listing.
+    listed_keys_opt: Option<HashSet<String>>,
+    /// Start time of the last full listing, recorded in the positions. After a restart, an object
+    /// that sorts before the checkpoint and was modified after that time has been listed out of
+    /// order.
+    full_listing_millis: u64,
+    num_incremental_listings: usize,
+    pending_objects: VecDeque<PolledObject>,
+    object_in_progress_opt: Option<ObjectInProgress>,
+    observable_state: PrefixPollerObservableState,
+}
+
+impl fmt::Debug for PrefixPoller {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("PrefixPoller")
+            .field("prefix", &self.prefix)
+            .field("ordering", &self.ordering)
+            .finish()
+    }
+}
+
+impl PrefixPoller {
+    pub async fn try_new(
+        polling: FileSourcePolling,
+        source_runtime: SourceRuntime,
+    ) -> anyhow::Result<Self> {
+        let partition_id = PartitionId::from(polling.prefix.as_str());
+        let current_position = source_runtime
+            .fetch_checkpoint()
+            .await?
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or_default();
+        // Fail early if the position was not written by this source.
+        let full_listing_millis = Cursor::parse(&current_position)?
+            .map(|cursor| cursor.full_listing_millis)
+            .unwrap_or(0);
+        let storage = source_runtime
+            .storage_resolver
+            .resolve(&polling.prefix)
+            .await?;
+        Ok(Self {
+            storage_resolver: source_runtime.storage_resolver,
+            storage,
+            prefix: polling.prefix,
+            partition_id,
+            source_type: source_runtime.source_config.source_type(),
+            poll_interval: Duration::from_secs(polling.poll_interval_secs as u64),
+            ordering: polling.ordering,
+            current_position,
+            listed_keys_opt: None,
+            full_listing_millis,
+            num_incremental_listings: 0,
+            pending_objects: VecDeque::new(),
+            object_in_progress_opt: None,
+            observable_state: PrefixPollerObservableState::default(),
+        })
+    }
+
+    /// Returns the key to list the prefix from, or `None` if the whole prefix must be listed.
+    fn start_after(&self, cursor_opt: Option<&Cursor>) -> Option<PathBuf> {
+        let cursor = cursor_opt?;
+
+        if self.ordering != FileSourcePollingOrdering::Lexicographic
+            || self.listed_keys_opt.is_none()
+            || self.num_incremental_listings + 1 >= FULL_LISTING_PERIOD
+            // The object being read must be listed again to resume it.
+            || cursor.offset_opt.is_some()
+        {
+            return None;
+        }
+        Some(PathBuf::from(cursor.key()))
+    }
+
+    /// Lists the objects under the prefix and queues the ones located after the current position.
+    async fn poll_objects(&mut self, ctx: &SourceContext) -> anyhow::Result<()> {
+        let cursor_opt = Cursor::parse(&self.current_position)?;
+        let start_after_opt = self.start_after(cursor_opt.as_ref());
+        let listing_millis = millis_since_epoch(SystemTime::now());
+
+        let file_entries = ctx
+            .protect_future(
+                self.storage
+                    .list_files_recursive(Path::new(""), start_after_opt.as_deref()),
+            )
+            .await?;
+        let mut objects: Vec<PolledObject> = file_entries
+            .iter()
+            .map(|file_entry| PolledObject::new(file_entry, self.ordering))
+            .collect();
+        objects.sort_by(|left, right| left.cursor_prefix.cmp(&right.cursor_prefix));
+
+        let (mut listed_keys, last_listed_keys_opt) = if start_after_opt.is_some() {
+            // An incremental listing only returns objects located after the current position.
+            (self.listed_keys_opt.take().unwrap_or_default(), None)
+        } else {
+            (
+                HashSet::with_capacity(objects.len()),
+                self.listed_keys_opt.take(),
+            )
+        };
+        for mut object in objects {
+            listed_keys.insert(object.key.clone());
+
+            let Some(cursor) = &cursor_opt else {
+                self.pending_objects.push_back(object);
+                continue;
+            };
+            if object.cursor_prefix > cursor.cursor_prefix {
+                self.pending_objects.push_back(object);
+                continue;
+            }
+            if object.cursor_prefix == cursor.cursor_prefix {
+                if let Some(offset) = cursor.offset_opt {
+                    object.start_offset = offset;
+                    self.pending_objects.push_back(object);
+                }
+                continue;
+            }
+            let is_listed_out_of_order = match &last_listed_keys_opt {
+                Some(last_listed_keys) => !last_listed_keys.contains(&object.key),
+                // After a restart, the objects modified after the last full listing could not be
+                // part of it.
+                None => object.last_modified_millis > cursor.full_listing_millis,
+            };
+            if is_listed_out_of_order {
+                self.observable_state.num_objects_skipped += 1;
+                warn!(
+                    prefix=%self.prefix,
+                    key=%object.key,
+                    "skipping object listed out of order: it sorts before the last ingested object"
+                );
+            }
+        }
+        if start_after_opt.is_some() {
+            self.num_incremental_listings += 1;
+        } else {
+            self.full_listing_millis = listing_millis;
+            self.num_incremental_listings = 0;
+        }
+        self.listed_keys_opt = Some(listed_keys);
+        Ok(())
+    }
+
+    /// Opens the next pending object. If the object no longer exists, it is skipped and recorded
+    /// as completed.
+    async fn open_next_object(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<(), ActorExitStatus> {
+        let Some(object) = self.pending_objects.pop_front() else {
+            return Ok(());
+        };
+        let object_uri = self.prefix.join(&object.key)?;
+        let reader_res = ctx
+            .protect_future(DocFileReader::from_uri(
+                &self.storage_resolver,
+                &object_uri,
+                object.start_offset as usize,
+            ))
+            .await;
+        match reader_res {
+            Ok(reader) => {
+                info!(uri=%object_uri, offset=object.start_offset, "reading polled object");
+                self.object_in_progress_opt = Some(ObjectInProgress {
+                    current_offset: object.start_offset,
+                    object,
+                    reader,
+                });
+            }
+            Err(error) if is_not_found(&error) => {
+                self.observable_state.num_objects_skipped += 1;
+                warn!(uri=%object_uri, "skipping object deleted before it could be read");
+
+                let mut batch_builder = BatchBuilder::new(self.source_type);
+                let to_position = object.completed_position(self.full_listing_millis);
+                batch_builder
+                    .checkpoint_delta
+                    .record_partition_delta(
+                        self.partition_id.clone(),
+                        self.current_position.clone(),
+                        to_position.clone(),
+                    )
+                    .context("failed to record partition delta")?;
+                self.current_position = to_position;
+                doc_processor_mailbox
+                    .send_message(batch_builder.build())
+                    .await?;
+            }
+            Err(error) => return Err(error.into()),
+        }
+        Ok(())
+    }
+
+    async fn read_batch(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<(), ActorExitStatus> {
+        let Some(object_in_progress) = &mut self.object_in_progress_opt else {
+            return Ok(());
+        };
+        let mut batch_builder = BatchBuilder::new(self.source_type);
+        let limit_num_bytes = object_in_progress.current_offset + BATCH_NUM_BYTES_LIMIT;
+        let mut new_offset = object_in_progress.current_offset;
+        let mut is_eof = false;
+
+        while new_offset < limit_num_bytes {
+            let Some(record) = ctx
+                .protect_future(object_in_progress.reader.next_record())
+                .await?
+            else {
+                is_eof = true;
+                break;
+            };
+            new_offset = record.next_offset;
+            batch_builder.add_doc(record.doc);
+
+            if record.is_last {
+                is_eof = true;
+                break;
+            }
+        }
+        let to_position = if is_eof {
+            object_in_progress
+                .object
+                .completed_position(self.full_listing_millis)
+        } else {
+            object_in_progress
+                .object
+                .position(new_offset, self.full_listing_millis)
+        };
+        batch_builder
+            .checkpoint_delta
+            .record_partition_delta(
+                self.partition_id.clone(),
+                self.current_position.clone(),
+                to_position.clone(),
+            )
+            .context("failed to record partition delta")?;
+        self.current_position = to_position;
+        object_in_progress.current_offset = new_offset;
+
+        self.observable_state.num_lines_processed += batch_builder.docs.len() as u64;
+        self.observable_state.num_bytes_processed += batch_builder.num_bytes;
+
+        if is_eof {
+            self.observable_state.num_objects_processed += 1;
+            self.object_in_progress_opt = None;
+        }
+        doc_processor_mailbox
+            .send_message(batch_builder.build())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        if self.object_in_progress_opt.is_none() {
+            if self.pending_objects.is_empty() {
+                self.poll_objects(ctx).await?;
+            }
+            if self.pending_objects.is_empty() {
+                // Nothing new under the prefix: the source actor idles in its mailbox until the
+                // next poll, which does not count against its health.
+                return Ok(self.poll_interval);
+            }
+            self.open_next_object(doc_processor_mailbox, ctx).await?;
+        }
+        self.read_batch(doc_processor_mailbox, ctx).await?;
+        Ok(Duration::ZERO)
+    }
+
+    pub fn observable_state(&self) -> &PrefixPollerObservableState {
+        &self.observable_state
+    }
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<StorageError>()
+        .map(|storage_error| storage_error.kind() == StorageErrorKind::NotFound)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use bytes::Bytes;
+    use quickwit_actors::{ActorContext, Inbox, Universe};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_proto::types::IndexUid;
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::tests::SourceRuntimeBuilder;
+    use crate::source::SourceActor;
+
+    const PREFIX: &str = "ram:///polled-prefix";
+
+    fn polling_params(ordering: FileSourcePollingOrdering) -> FileSourcePolling {
+        FileSourcePolling {
+            prefix: Uri::for_test(PREFIX),
+            poll_interval_secs: 10,
+            ordering,
+        }
+    }
+
+    async fn setup_poller(
+        ordering: FileSourcePollingOrdering,
+        checkpoint_delta_opt: Option<SourceCheckpointDelta>,
+    ) -> (PrefixPoller, Arc<dyn Storage>) {
+        let polling = polling_params(ordering);
+        let source_config = SourceConfig {
+            source_id: "test-prefix-poller".to_string(),
+            num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
+            source_params: SourceParams::File(quickwit_config::FileSourceParams::Polling(
+                polling.clone(),
+            )),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+        };
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let source_runtime = SourceRuntimeBuilder::new(index_uid, source_config)
+            .with_mock_metastore(checkpoint_delta_opt)
+            .build();
+        let storage = source_runtime
+            .storage_resolver
+            .resolve(&polling.prefix)
+            .await
+            .unwrap();
+        let poller = PrefixPoller::try_new(polling, source_runtime)
+            .await
+            .unwrap();
+        (poller, storage)
+    }
+
+    async fn put_object(storage: &Arc<dyn Storage>, key: &str, lines: &[&str]) {
+        let payload: Vec<u8> = lines
+            .iter()
+            .flat_map(|line| format!("{line}\n").into_bytes())
+            .collect();
+        storage
+            .put(Path::new(key), Box::new(payload))
+            .await
+            .unwrap();
+    }
+
+    struct PollerHarness {
+        universe: Universe,
+        doc_processor_mailbox: Mailbox<DocProcessor>,
+        doc_processor_inbox: Inbox<DocProcessor>,
+        ctx: SourceContext,
+    }
+
+    impl PollerHarness {
+        fn new() -> Self {
+            let universe = Universe::with_accelerated_time();
+            let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+            let (doc_processor_mailbox, doc_processor_inbox) =
+                universe.create_test_mailbox::<DocProcessor>();
+            let (observable_state_tx, _observable_state_rx) =
+                watch::channel(serde_json::Value::Null);
+            let ctx: SourceContext =
+                ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+            Self {
+                universe,
+                doc_processor_mailbox,
+                doc_processor_inbox,
+                ctx,
+            }
+        }
+
+        /// Calls `emit_batches` until the poller goes idle and returns the emitted batches.
+        async fn emit_until_idle(&self, poller: &mut PrefixPoller) -> Vec<RawDocBatch> {
+            loop {
+                let wait_for = poller
+                    .emit_batches(&self.doc_processor_mailbox, &self.ctx)
+                    .await
+                    .unwrap();
+                if !wait_for.is_zero() {
+                    assert_eq!(wait_for, poller.poll_interval);
+                    break;
+                }
+            }
+            self.doc_processor_inbox.drain_for_test_typed()
+        }
+    }
+
+    fn docs(batches: &[RawDocBatch]) -> Vec<Bytes> {
+        batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter().cloned())
+            .collect()
+    }
+
+    /// Formats the delta of the polled prefix without the full listing times of its positions,
+    /// which depend on the clock.
+    fn format_delta(checkpoint_delta: &SourceCheckpointDelta) -> String {
+        let (_partition_id, partition_delta) = checkpoint_delta.iter().next().unwrap();
+        let format_position = |position: &Position| {
+            let Some(cursor) = Cursor::parse(position).unwrap() else {
+                return String::new();
+            };
+            match cursor.offset_opt {
+                Some(offset) => format!("{}{offset:020}", cursor.cursor_prefix),
+                None => format!("{}{COMPLETED_OBJECT_MARKER}", cursor.cursor_prefix),
+            }
+        };
+        format!(
+            "({}..{}]",
+            format_position(&partition_delta.from),
+            format_position(&partition_delta.to)
+        )
+    }
+
+    #[test]
+    fn test_cursor_parse() {
+        assert_eq!(Cursor::parse(&Position::Beginning).unwrap(), None);
+
+        let object = PolledObject {
+            key: "logs/a.json".to_string(),
+            cursor_prefix: "00000000000000000000\tlogs/a.json\t".to_string(),
+            last_modified_millis: 0,
+            start_offset: 0,
+        };
+        let cursor = Cursor::parse(&object.position(42, 7)).unwrap().unwrap();
+        assert_eq!(cursor.cursor_prefix, object.cursor_prefix);
+        assert_eq!(cursor.offset_opt, Some(42));
+        assert_eq!(cursor.full_listing_millis, 7);
+        assert_eq!(cursor.key(), "logs/a.json");
+
+        let cursor = Cursor::parse(&object.completed_position(7))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cursor.cursor_prefix, object.cursor_prefix);
+        assert_eq!(cursor.offset_opt, None);
+        assert_eq!(cursor.full_listing_millis, 7);
+
+        assert!(object.position(41, u64::MAX) < object.position(42, 0));
+        assert!(object.position(u64::MAX, u64::MAX) < object.completed_position(0));
+        Cursor::parse(&Position::offset(42u64)).unwrap_err();
+        Cursor::parse(&Position::eof(42u64)).unwrap_err();
+    }
+
+    #[test]
+    fn test_polled_object_positions_order() {
+        let file_entry = |path: &str, last_modified_secs: u64| FileEntry {
+            path: path.into(),
+            num_bytes: 0,
+            last_modified: UNIX_EPOCH + Duration::from_secs(last_modified_secs),
+        };
+        let ordering = FileSourcePollingOrdering::Lexicographic;
+        let object_a = PolledObject::new(&file_entry("a", 2), ordering);
+        let object_ab = PolledObject::new(&file_entry("a/b", 1), ordering);
+        assert!(object_a.completed_position(0) < object_ab.position(0, 0));
+
+        let ordering = FileSourcePollingOrdering::LastModified;
+        let object_a = PolledObject::new(&file_entry("a", 2), ordering);
+        let object_ab = PolledObject::new(&file_entry("a/b", 1), ordering);
+        assert!(object_ab.completed_position(0) < object_a.position(0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_prefix_poller_picks_up_new_objects() {
+        let (mut poller, storage) =
+            setup_poller(FileSourcePollingOrdering::Lexicographic, None).await;
+        let harness = PollerHarness::new();
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert!(batches.is_empty());
+
+        put_object(&storage, "2024/01/b.json", &["b0", "b1"]).await;
+        put_object(&storage, "2024/01/a.json", &["a0"]).await;
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["a0\n", "b0\n", "b1\n"]);
+
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        for batch in &batches {
+            checkpoint_delta
+                .extend(batch.checkpoint_delta.clone())
+                .unwrap();
+        }
+        assert_eq!(
+            format_delta(&checkpoint_delta),
+            "(..00000000000000000000\t2024/01/b.json\t~]"
+        );
+        put_object(&storage, "2024/02/c.json", &["c0"]).await;
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["c0\n"]);
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert!(batches.is_empty());
+
+        let observable_state = poller.observable_state();
+        assert_eq!(observable_state.num_objects_processed, 3);
+        assert_eq!(observable_state.num_lines_processed, 4);
+        assert_eq!(observable_state.num_objects_skipped, 0);
+
+        harness.universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_prefix_poller_resumes_from_checkpoint() {
+        let ordering = FileSourcePollingOrdering::Lexicographic;
+        let object_a = PolledObject::new(
+            &FileEntry {
+                path: "a.json".into(),
+                num_bytes: 0,
+                last_modified: UNIX_EPOCH,
+            },
+            ordering,
+        );
+        let object_b = PolledObject::new(
+            &FileEntry {
+                path: "b.json".into(),
+                num_bytes: 0,
+                last_modified: UNIX_EPOCH,
+            },
+            ordering,
+        );
+        // The last full listing took place after the objects below were written.
+        let full_listing_millis = millis_since_epoch(SystemTime::now()) + 60_000;
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            PartitionId::from(PREFIX),
+            Position::Beginning,
+            // The first line of `b.json` has already been ingested.
+            object_b.position(3, full_listing_millis),
+        )
+        .unwrap();
+        let (mut poller, storage) = setup_poller(ordering, Some(checkpoint_delta)).await;
+        let harness = PollerHarness::new();
+
+        assert!(object_a.completed_position(0) < object_b.position(0, 0));
+        put_object(&storage, "a.json", &["a0"]).await;
+        put_object(&storage, "b.json", &["b0", "b1"]).await;
+        put_object(&storage, "c.json", &["c0"]).await;
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["b1\n", "c0\n"]);
+
+        assert_eq!(
+            format_delta(&batches[0].checkpoint_delta),
+            "(00000000000000000000\tb.json\t00000000000000000003..00000000000000000000\tb.json\t~]"
+        );
+        assert_eq!(poller.observable_state().num_objects_skipped, 0);
+
+        harness.universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_prefix_poller_skips_deleted_and_out_of_order_objects() {
+        let (mut poller, storage) =
+            setup_poller(FileSourcePollingOrdering::Lexicographic, None).await;
+        let harness = PollerHarness::new();
+
+        put_object(&storage, "a.json", &["a0"]).await;
+        put_object(&storage, "b.json", &["b0"]).await;
+        put_object(&storage, "c.json", &["c0"]).await;
+
+        // The first call lists the prefix and reads `a.json`.
+        poller
+            .emit_batches(&harness.doc_processor_mailbox, &harness.ctx)
+            .await
+            .unwrap();
+        storage.delete(Path::new("b.json")).await.unwrap();
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["a0\n", "c0\n"]);
+        assert_eq!(batches.len(), 3);
+        assert!(batches[1].docs.is_empty());
+        assert_eq!(
+            format_delta(&batches[1].checkpoint_delta),
+            "(00000000000000000000\ta.json\t~..00000000000000000000\tb.json\t~]"
+        );
+        assert_eq!(poller.observable_state().num_objects_skipped, 1);
+
+        // `aa.json` sorts before `c.json`, which has already been ingested.
+        put_object(&storage, "aa.json", &["aa0"]).await;
+        put_object(&storage, "d.json", &["d0"]).await;
+
+        // The prefix is listed from `c.json` onwards, so `aa.json` is not listed yet.
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["d0\n"]);
+        assert_eq!(poller.observable_state().num_objects_skipped, 1);
+
+        // The next full listing reports it, once.
+        for _ in 0..2 * FULL_LISTING_PERIOD {
+            let batches = harness.emit_until_idle(&mut poller).await;
+            assert!(batches.is_empty());
+        }
+        let observable_state = poller.observable_state();
+        assert_eq!(observable_state.num_objects_processed, 3);
+        assert_eq!(observable_state.num_objects_skipped, 2);
+
+        harness.universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_prefix_poller_reports_objects_listed_out_of_order_after_restart() {
+        let ordering = FileSourcePollingOrdering::Lexicographic;
+        let object_b = PolledObject::new(
+            &FileEntry {
+                path: "b.json".into(),
+                num_bytes: 0,
+                last_modified: UNIX_EPOCH,
+            },
+            ordering,
+        );
+        {
+            // `a.json` was written after the last full listing before the restart.
+            let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(PREFIX),
+                Position::Beginning,
+                object_b.completed_position(1),
+            )
+            .unwrap();
+            let (mut poller, storage) = setup_poller(ordering, Some(checkpoint_delta)).await;
+            let harness = PollerHarness::new();
+
+            put_object(&storage, "a.json", &["a0"]).await;
+            put_object(&storage, "b.json", &["b0"]).await;
+            put_object(&storage, "c.json", &["c0"]).await;
+
+            let batches = harness.emit_until_idle(&mut poller).await;
+            assert_eq!(docs(&batches), ["c0\n"]);
+            assert_eq!(poller.observable_state().num_objects_skipped, 1);
+
+            harness.universe.assert_quit().await;
+        }
+        {
+            // `a.json` was part of the last full listing before the restart.
+            let full_listing_millis = millis_since_epoch(SystemTime::now()) + 60_000;
+            let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(PREFIX),
+                Position::Beginning,
+                object_b.completed_position(full_listing_millis),
+            )
+            .unwrap();
+            let (mut poller, storage) = setup_poller(ordering, Some(checkpoint_delta)).await;
+            let harness = PollerHarness::new();
+
+            put_object(&storage, "a.json", &["a0"]).await;
+            put_object(&storage, "b.json", &["b0"]).await;
+            put_object(&storage, "c.json", &["c0"]).await;
+
+            let batches = harness.emit_until_idle(&mut poller).await;
+            assert_eq!(docs(&batches), ["c0\n"]);
+            assert_eq!(poller.observable_state().num_objects_skipped, 0);
+
+            harness.universe.assert_quit().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_poller_last_modified_ordering() {
+        let (mut poller, storage) =
+            setup_poller(FileSourcePollingOrdering::LastModified, None).await;
+        let harness = PollerHarness::new();
+
+        put_object(&storage, "b.json", &["b0"]).await;
+        std::thread::sleep(Duration::from_millis(5));
+        put_object(&storage, "a.json", &["a0"]).await;
+
+        let batches = harness.emit_until_idle(&mut poller).await;
+        assert_eq!(docs(&batches), ["b0\n", "a0\n"]);
+
+        harness.universe.assert_quit().await;
+    }
+}
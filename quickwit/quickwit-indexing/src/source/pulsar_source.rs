@@ -445,7 +445,7 @@ mod pulsar_broker_tests {
     use futures::future::join_all;
     use quickwit_actors::{ActorHandle, Inbox, Universe, HEARTBEAT};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpointDelta};
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::metastore::MetastoreServiceClient;
@@ -493,6 +493,10 @@ mod pulsar_broker_tests {
             source_id: source_id.clone(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Pulsar(PulsarSourceParams {
                 topics: topics.into_iter().map(|v| v.as_ref().to_string()).collect(),
                 address: PULSAR_URI.to_string(),
@@ -609,7 +613,10 @@ mod pulsar_broker_tests {
         checkpoint
             .try_apply_delta(checkpoints!(partition => truncate_to))
             .expect("Create checkpoint");
-        let truncate = SuggestTruncate(checkpoint);
+        let truncate = SuggestTruncate {
+            checkpoint,
+            parent_span: tracing::Span::none(),
+        };
         source_handle
             .mailbox()
             .send_message(truncate)
@@ -116,7 +116,7 @@ mod tests {
 
     use std::num::NonZeroUsize;
 
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_proto::types::IndexUid;
 
     use crate::source::quickwit_supported_sources;
@@ -130,6 +130,10 @@ mod tests {
             source_id: "test-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -71,7 +71,7 @@ mod tests {
     use std::num::NonZeroUsize;
 
     use quickwit_actors::{Health, Supervisable, Universe};
-    use quickwit_config::{SourceInputFormat, SourceParams};
+    use quickwit_config::{SourceInputFormat, SourceParams, SourcePriority};
     use quickwit_proto::types::IndexUid;
     use serde_json::json;
 
@@ -86,6 +86,10 @@ mod tests {
             source_id: "test-void-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -106,6 +110,10 @@ mod tests {
             source_id: "test-void-source".to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
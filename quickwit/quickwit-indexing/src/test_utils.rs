@@ -30,7 +30,8 @@ use quickwit_common::rand::append_random_suffix;
 use quickwit_common::uri::Uri;
 use quickwit_config::{
     build_doc_mapper, ConfigFormat, IndexConfig, IndexerConfig, IngestApiConfig, MetastoreConfigs,
-    SourceConfig, SourceInputFormat, SourceParams, VecSourceParams, INGEST_API_SOURCE_ID,
+    SourceConfig, SourceInputFormat, SourceParams, SourcePriority, VecSourceParams,
+    INGEST_API_SOURCE_ID,
 };
 use quickwit_doc_mapper::DocMapper;
 use quickwit_ingest::{init_ingest_api, IngesterPool, QUEUES_DIR_NAME};
@@ -168,6 +169,10 @@ impl TestSandbox {
             source_id: INGEST_API_SOURCE_ID.to_string(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::Vec(VecSourceParams {
                 docs,
                 batch_num_docs: 10,
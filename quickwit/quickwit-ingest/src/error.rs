@@ -38,6 +38,8 @@ pub enum IngestServiceError {
     IndexAlreadyExists { index_id: IndexId },
     #[error("index `{index_id}` not found")]
     IndexNotFound { index_id: IndexId },
+    #[error("ingest is blocked for index `{index_id}`")]
+    IngestBlocked { index_id: IndexId },
     #[error("an internal error occurred: {0}")]
     Internal(String),
     #[error("invalid position: {0}")]
@@ -134,6 +136,9 @@ impl From<IngestFailure> for IngestServiceError {
             IngestFailureReason::CircuitBreaker => {
                 IngestServiceError::RateLimited(RateLimitingCause::CircuitBreaker)
             }
+            IngestFailureReason::IngestBlocked => IngestServiceError::IngestBlocked {
+                index_id: ingest_failure.index_id,
+            },
         }
     }
 }
@@ -150,6 +155,7 @@ impl ServiceError for IngestServiceError {
             }
             Self::IndexAlreadyExists { .. } => ServiceErrorCode::AlreadyExists,
             Self::IndexNotFound { .. } => ServiceErrorCode::NotFound,
+            Self::IngestBlocked { .. } => ServiceErrorCode::Forbidden,
             Self::Internal(err_msg) => {
                 rate_limited_error!(limit_per_min = 6, "ingest internal error: {err_msg}");
                 ServiceErrorCode::Internal
@@ -199,6 +205,7 @@ impl From<IngestServiceError> for tonic::Status {
             IngestServiceError::Corruption { .. } => tonic::Code::DataLoss,
             IngestServiceError::IndexAlreadyExists { .. } => tonic::Code::AlreadyExists,
             IngestServiceError::IndexNotFound { .. } => tonic::Code::NotFound,
+            IngestServiceError::IngestBlocked { .. } => tonic::Code::PermissionDenied,
             IngestServiceError::Internal(_) => tonic::Code::Internal,
             IngestServiceError::InvalidPosition(_) => tonic::Code::InvalidArgument,
             IngestServiceError::IoError { .. } => tonic::Code::Internal,
@@ -33,6 +33,7 @@ pub(crate) struct IngestResultMetrics {
     pub circuit_breaker: IntCounter,
     pub unspecified: IntCounter,
     pub index_not_found: IntCounter,
+    pub ingest_blocked: IntCounter,
     pub source_not_found: IntCounter,
     pub internal: IntCounter,
     pub no_shards_available: IntCounter,
@@ -60,6 +61,7 @@ impl Default for IngestResultMetrics {
             circuit_breaker: ingest_result_total_vec.with_label_values(["circuit_breaker"]),
             unspecified: ingest_result_total_vec.with_label_values(["unspecified"]),
             index_not_found: ingest_result_total_vec.with_label_values(["index_not_found"]),
+            ingest_blocked: ingest_result_total_vec.with_label_values(["ingest_blocked"]),
             source_not_found: ingest_result_total_vec.with_label_values(["source_not_found"]),
             internal: ingest_result_total_vec.with_label_values(["internal"]),
             no_shards_available: ingest_result_total_vec.with_label_values(["no_shards_available"]),
@@ -529,6 +529,9 @@ fn update_ingest_metrics(ingest_result: &IngestV2Result<IngestResponseV2>, num_s
                     IngestFailureReason::SourceNotFound => {
                         ingest_results_metrics.source_not_found.inc()
                     }
+                    IngestFailureReason::IngestBlocked => {
+                        ingest_results_metrics.ingest_blocked.inc()
+                    }
                     IngestFailureReason::Internal => ingest_results_metrics.internal.inc(),
                     IngestFailureReason::NoShardsAvailable => {
                         ingest_results_metrics.no_shards_available.inc()
@@ -155,6 +155,7 @@ impl IngestWorkbench {
             GetOrCreateOpenShardsFailureReason::NoIngestersAvailable => {
                 SubworkbenchFailure::NoShardsAvailable
             }
+            GetOrCreateOpenShardsFailureReason::IngestBlocked => SubworkbenchFailure::IngestBlocked,
             GetOrCreateOpenShardsFailureReason::Unspecified => {
                 warn!(
                     "failure reason for subrequest `{}` is unspecified",
@@ -328,6 +329,8 @@ pub(super) enum SubworkbenchFailure {
     IndexNotFound,
     // There is no entry in the routing table for this source.
     SourceNotFound,
+    // Ingest is blocked for this index: the index is in read-only mode.
+    IngestBlocked,
     // The routing table entry for this source is empty, shards are all closed, or their leaders
     // are unavailable.
     NoShardsAvailable,
@@ -347,6 +350,7 @@ impl SubworkbenchFailure {
         match self {
             Self::IndexNotFound => IngestFailureReason::IndexNotFound,
             Self::SourceNotFound => IngestFailureReason::SourceNotFound,
+            Self::IngestBlocked => IngestFailureReason::IngestBlocked,
             Self::Internal => IngestFailureReason::Internal,
             Self::NoShardsAvailable => IngestFailureReason::NoShardsAvailable,
             // In our last attempt, we did not manage to reach the ingester.
@@ -389,11 +393,13 @@ impl IngestSubworkbench {
     /// Returns `false` if and only if the last attempt suggests retrying (on any node) will fail.
     /// e.g.:
     /// - the index does not exist
-    /// - the source does not exist.
+    /// - the source does not exist
+    /// - ingest is blocked for the index.
     fn last_failure_is_transient(&self) -> bool {
         match self.last_failure_opt {
             Some(SubworkbenchFailure::IndexNotFound) => false,
             Some(SubworkbenchFailure::SourceNotFound) => false,
+            Some(SubworkbenchFailure::IngestBlocked) => false,
             Some(SubworkbenchFailure::Internal) => true,
             Some(SubworkbenchFailure::NoShardsAvailable) => true,
             Some(SubworkbenchFailure::Persist(_)) => true,
@@ -742,6 +748,24 @@ mod tests {
             Some(SubworkbenchFailure::SourceNotFound)
         ));
         assert_eq!(subworkbench.num_attempts, 1);
+
+        let get_or_create_open_shards_failure = GetOrCreateOpenShardsFailure {
+            subrequest_id: 0,
+            reason: GetOrCreateOpenShardsFailureReason::IngestBlocked as i32,
+            ..Default::default()
+        };
+        workbench.record_get_or_create_open_shards_failure(get_or_create_open_shards_failure);
+
+        let subworkbench = workbench.subworkbenches.get(&0).unwrap();
+        assert!(matches!(
+            subworkbench.last_failure_opt,
+            Some(SubworkbenchFailure::IngestBlocked)
+        ));
+        assert!(!subworkbench.is_pending());
+        assert_eq!(
+            subworkbench.last_failure_opt.as_ref().unwrap().reason(),
+            IngestFailureReason::IngestBlocked
+        );
     }
 
     #[test]
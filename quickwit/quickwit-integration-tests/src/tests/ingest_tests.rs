@@ -342,6 +342,108 @@ async fn test_ingest_v2_happy_path() {
     sandbox.shutdown().await.unwrap();
 }
 
+#[tokio::test]
+async fn test_ingest_v2_block_ingest() {
+    initialize_tests();
+    let mut sandbox = ClusterSandboxBuilder::build_and_start_standalone().await;
+    sandbox.enable_ingest_v2();
+    let index_id = "test_block_ingest";
+    let index_config = format!(
+        r#"
+        version: 0.8
+        index_id: {index_id}
+        doc_mapping:
+            field_mappings:
+            - name: body
+              type: text
+        indexing_settings:
+            commit_timeout_secs: 1
+        "#
+    );
+    sandbox
+        .indexer_rest_client
+        .indexes()
+        .create(index_config, ConfigFormat::Yaml, false)
+        .await
+        .unwrap();
+
+    ingest(
+        &sandbox.indexer_rest_client,
+        index_id,
+        ingest_json!({"body": "doc1"}),
+        CommitType::Auto,
+    )
+    .await
+    .unwrap();
+
+    sandbox
+        .wait_for_splits(index_id, Some(vec![SplitState::Published]), 1)
+        .await
+        .unwrap();
+
+    let index_metadata = sandbox
+        .indexer_rest_client
+        .indexes()
+        .block_ingest(index_id)
+        .await
+        .unwrap();
+    assert!(index_metadata.ingest_blocked);
+
+    // The shards of the index are closed asynchronously, so a few documents may still be accepted
+    // (and never indexed) before the routers learn about it.
+    wait_until_predicate(
+        || async {
+            let ingest_result = sandbox
+                .indexer_rest_client
+                .ingest(
+                    index_id,
+                    ingest_json!({"body": "doc2"}),
+                    None,
+                    None,
+                    CommitType::Auto,
+                )
+                .await;
+            matches!(ingest_result, Err(Error::Api(ApiError { code: 403, .. })))
+        },
+        Duration::from_secs(10),
+        Duration::from_millis(200),
+    )
+    .await
+    .unwrap();
+
+    // The index remains searchable.
+    sandbox.assert_hit_count(index_id, "*", 1).await;
+
+    let index_metadata = sandbox
+        .indexer_rest_client
+        .indexes()
+        .unblock_ingest(index_id)
+        .await
+        .unwrap();
+    assert!(!index_metadata.ingest_blocked);
+
+    ingest(
+        &sandbox.indexer_rest_client,
+        index_id,
+        ingest_json!({"body": "doc3"}),
+        CommitType::Force,
+    )
+    .await
+    .unwrap();
+
+    sandbox.assert_hit_count(index_id, "body:doc3", 1).await;
+
+    // Delete the index to avoid potential hanging on shutdown #5068
+    sandbox
+        .indexer_rest_client
+        .indexes()
+        .delete(index_id, false)
+        .await
+        .unwrap();
+
+    sandbox.shutdown().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_commit_force() {
     initialize_tests();
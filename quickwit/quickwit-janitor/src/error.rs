@@ -33,6 +33,8 @@ pub enum JanitorError {
     InvalidDeleteQuery(String),
     #[error("metastore error: `{0}`")]
     Metastore(#[from] MetastoreError),
+    #[error("index `{0}` has no retention policy")]
+    MissingRetentionPolicy(String),
 }
 
 impl ServiceError for JanitorError {
@@ -44,6 +46,7 @@ impl ServiceError for JanitorError {
             }
             Self::InvalidDeleteQuery(_) => ServiceErrorCode::BadRequest,
             Self::Metastore(metastore_error) => metastore_error.error_code(),
+            Self::MissingRetentionPolicy(_) => ServiceErrorCode::BadRequest,
         }
     }
 }
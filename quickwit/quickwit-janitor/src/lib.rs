@@ -36,11 +36,15 @@ mod metrics;
 mod retention_policy_execution;
 
 pub use janitor_service::JanitorService;
+pub use retention_policy_execution::{
+    evaluate_retention_policy, preview_retention_policy, ExpiredSplitPreview,
+    RetentionPolicyEvaluation, RetentionPolicyPreview,
+};
 
 use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(components(schemas(SplitInfo)))]
+#[openapi(components(schemas(SplitInfo, RetentionPolicyPreview, ExpiredSplitPreview)))]
 /// Schema used for the OpenAPI generation which are apart of this crate.
 pub struct JanitorApiSchemas;
 
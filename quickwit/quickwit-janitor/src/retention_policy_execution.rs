@@ -28,10 +28,69 @@ use quickwit_proto::metastore::{
     ListSplitsRequest, MarkSplitsForDeletionRequest, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
+use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use tracing::{info, warn};
 
 use crate::actors::RetentionPolicyExecutor;
+use crate::error::JanitorError;
+
+/// Outcome of the evaluation of a retention policy over the published splits of an index.
+#[derive(Debug, Default)]
+pub struct RetentionPolicyEvaluation {
+    /// Splits whose time range ends before the retention period.
+    pub expired_splits: Vec<SplitMetadata>,
+    /// Splits the retention policy cannot be applied to because they lack a time range.
+    pub ignored_splits: Vec<SplitMetadata>,
+}
+
+/// Evaluates a retention policy over published splits at `evaluation_timestamp`, without side
+/// effects.
+///
+/// A split is expired if all its documents are older than the retention period.
+pub fn evaluate_retention_policy(
+    published_splits: impl IntoIterator<Item = SplitMetadata>,
+    retention_policy: &RetentionPolicy,
+    evaluation_timestamp: i64,
+) -> anyhow::Result<RetentionPolicyEvaluation> {
+    let max_retention_timestamp = max_retention_timestamp(retention_policy, evaluation_timestamp)?;
+    let mut evaluation = RetentionPolicyEvaluation::default();
+
+    for split_metadata in published_splits {
+        match &split_metadata.time_range {
+            Some(time_range) if *time_range.end() <= max_retention_timestamp => {
+                evaluation.expired_splits.push(split_metadata)
+            }
+            Some(_) => {}
+            None => evaluation.ignored_splits.push(split_metadata),
+        }
+    }
+    Ok(evaluation)
+}
+
+fn max_retention_timestamp(
+    retention_policy: &RetentionPolicy,
+    evaluation_timestamp: i64,
+) -> anyhow::Result<i64> {
+    let retention_period = retention_policy.retention_period()?;
+    Ok(evaluation_timestamp - retention_period.as_secs() as i64)
+}
+
+/// Builds the request listing the published splits that may be expired at
+/// `evaluation_timestamp`. Splits without a time range match the time range filter on every
+/// metastore backend, so all of them are listed and reported as ignored by the evaluation.
+fn list_expired_split_candidates_request(
+    index_uid: IndexUid,
+    retention_policy: &RetentionPolicy,
+    evaluation_timestamp: i64,
+) -> anyhow::Result<ListSplitsRequest> {
+    let max_retention_timestamp = max_retention_timestamp(retention_policy, evaluation_timestamp)?;
+    let query = ListSplitsQuery::for_index(index_uid)
+        .with_split_state(SplitState::Published)
+        .with_time_range_end_lte(max_retention_timestamp);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    Ok(list_splits_request)
+}
 
 /// Detect all expired splits based a retention policy and
 /// only mark them as `MarkedForDeletion`. Actual split deletion
@@ -48,21 +107,21 @@ pub async fn run_execute_retention_policy(
     ctx: &ActorContext<RetentionPolicyExecutor>,
 ) -> anyhow::Result<Vec<SplitMetadata>> {
     // Select splits that are published and older than the retention period.
-    let retention_period = retention_policy.retention_period()?;
     let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
-    let max_retention_timestamp = current_timestamp - retention_period.as_secs() as i64;
-    let query = ListSplitsQuery::for_index(index_uid.clone())
-        .with_split_state(SplitState::Published)
-        .with_time_range_end_lte(max_retention_timestamp);
-
-    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
-    let (expired_splits, ignored_splits): (Vec<SplitMetadata>, Vec<SplitMetadata>) = ctx
+    let list_splits_request = list_expired_split_candidates_request(
+        index_uid.clone(),
+        retention_policy,
+        current_timestamp,
+    )?;
+    let candidate_splits = ctx
         .protect_future(metastore.list_splits(list_splits_request))
         .await?
         .collect_splits_metadata()
-        .await?
-        .into_iter()
-        .partition(|split_metadata| split_metadata.time_range.is_some());
+        .await?;
+    let RetentionPolicyEvaluation {
+        expired_splits,
+        ignored_splits,
+    } = evaluate_retention_policy(candidate_splits, retention_policy, current_timestamp)?;
 
     if !ignored_splits.is_empty() {
         let ignored_split_ids: Vec<String> = ignored_splits
@@ -96,3 +155,116 @@ pub async fn run_execute_retention_policy(
         .await?;
     Ok(expired_splits)
 }
+
+/// A split the retention policy would mark for deletion.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ExpiredSplitPreview {
+    /// The split ID.
+    pub split_id: SplitId,
+    /// Timestamp of the oldest document of the split, in seconds.
+    pub time_range_start: i64,
+    /// Timestamp of the most recent document of the split, in seconds.
+    pub time_range_end: i64,
+    /// The number of documents in the split.
+    pub num_docs: usize,
+    /// The size of the split file in bytes.
+    pub size_bytes: u64,
+}
+
+/// Splits the retention policy of an index would mark for deletion if it were evaluated now.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RetentionPolicyPreview {
+    /// Time at which the retention policy was evaluated, in seconds.
+    pub evaluation_timestamp: i64,
+    /// Time of the next scheduled evaluation of the retention policy, in seconds.
+    pub next_evaluation_timestamp: i64,
+    /// The splits that would be marked for deletion.
+    pub expired_splits: Vec<ExpiredSplitPreview>,
+    /// Number of published splits the retention policy ignores because they lack a time range.
+    /// All of them are counted, whatever their age: such splits are never deleted by the
+    /// retention policy.
+    pub num_ignored_splits: usize,
+}
+
+/// Evaluates the retention policy of an index now without marking any split for deletion.
+pub async fn preview_retention_policy(
+    index_uid: IndexUid,
+    metastore: MetastoreServiceClient,
+    retention_policy: &RetentionPolicy,
+) -> Result<RetentionPolicyPreview, JanitorError> {
+    let evaluation_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let next_evaluation_timestamp = retention_policy
+        .next_evaluation_timestamp()
+        .map_err(|error| JanitorError::Internal(error.to_string()))?;
+    let list_splits_request =
+        list_expired_split_candidates_request(index_uid, retention_policy, evaluation_timestamp)
+            .map_err(|error| JanitorError::Internal(error.to_string()))?;
+    let candidate_splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?;
+    let evaluation =
+        evaluate_retention_policy(candidate_splits, retention_policy, evaluation_timestamp)
+            .map_err(|error| JanitorError::Internal(error.to_string()))?;
+    let expired_splits = evaluation
+        .expired_splits
+        .into_iter()
+        .filter_map(|split_metadata| {
+            let time_range = split_metadata.time_range?;
+            Some(ExpiredSplitPreview {
+                split_id: split_metadata.split_id,
+                time_range_start: *time_range.start(),
+                time_range_end: *time_range.end(),
+                num_docs: split_metadata.num_docs,
+                size_bytes: split_metadata.footer_offsets.end,
+            })
+        })
+        .collect();
+    Ok(RetentionPolicyPreview {
+        evaluation_timestamp,
+        next_evaluation_timestamp,
+        expired_splits,
+        num_ignored_splits: evaluation.ignored_splits.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use super::*;
+
+    fn split_metadata(split_id: &str, time_range: Option<RangeInclusive<i64>>) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_retention_policy() {
+        let retention_policy = RetentionPolicy {
+            retention_period: "1 hour".to_string(),
+            evaluation_schedule: "hourly".to_string(),
+        };
+        let evaluation_timestamp = 10_000;
+        let splits = vec![
+            split_metadata("expired", Some(0..=5_000)),
+            split_metadata("expired-at-boundary", Some(5_000..=6_400)),
+            split_metadata("retained", Some(5_000..=6_401)),
+            split_metadata("no-time-range", None),
+        ];
+        let evaluation =
+            evaluate_retention_policy(splits, &retention_policy, evaluation_timestamp).unwrap();
+        let expired_split_ids: Vec<&str> = evaluation
+            .expired_splits
+            .iter()
+            .map(|split_metadata| split_metadata.split_id())
+            .collect();
+        assert_eq!(expired_split_ids, ["expired", "expired-at-boundary"]);
+        assert_eq!(evaluation.ignored_splits.len(), 1);
+        assert_eq!(evaluation.ignored_splits[0].split_id(), "no-time-range");
+    }
+}
@@ -33,7 +33,7 @@ use quickwit_config::merge_policy_config::MergePolicyConfig;
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
     load_index_config_from_user_config, ConfigFormat, IndexConfig, NodeConfig, SourceConfig,
-    SourceInputFormat, SourceParams, TransformConfig,
+    SourceInputFormat, SourceParams, SourcePriority, TransformConfig,
 };
 use quickwit_indexing::actors::{IndexingService, MergePipeline, MergeSchedulerService};
 use quickwit_indexing::models::{DetachIndexingPipeline, DetachMergePipeline, SpawnPipeline};
@@ -147,6 +147,10 @@ pub(super) async fn configure_source(
         source_id: LAMBDA_SOURCE_ID.to_owned(),
         num_pipelines: NonZeroUsize::new(1).expect("1 is always non-zero."),
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_params,
         transform_config,
         input_format,
@@ -66,8 +66,10 @@ async fn create_local_search_service(
 
 fn native_api(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_get_handler(search_service.clone()).or(search_post_handler(search_service))
+    search_get_handler(search_service.clone(), search_request_limits)
+        .or(search_post_handler(search_service, search_request_limits))
 }
 
 fn es_compat_api(
@@ -98,10 +100,11 @@ fn index_api(
 fn v1_searcher_api(
     search_service: Arc<dyn SearchService>,
     metastore: MetastoreServiceClient,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("api" / "v1" / ..)
         .and(
-            native_api(search_service.clone())
+            native_api(search_service.clone(), search_request_limits)
                 .or(es_compat_api(search_service, metastore.clone()))
                 .or(index_api(metastore)),
         )
@@ -123,6 +126,7 @@ pub async fn setup_searcher_api(
     );
     let _telemetry_handle_opt = quickwit_telemetry::start_telemetry_loop(telemetry_info);
 
+    let search_request_limits = SearchRequestLimits::from(&node_config.searcher_config);
     let search_service = create_local_search_service(
         node_config.searcher_config,
         metastore.clone(),
@@ -149,7 +153,11 @@ pub async fn setup_searcher_api(
 
     let api = warp::any()
         .and(before_hook)
-        .and(v1_searcher_api(search_service, metastore))
+        .and(v1_searcher_api(
+            search_service,
+            metastore,
+            search_request_limits,
+        ))
         .with(after_hook);
 
     Ok(api)
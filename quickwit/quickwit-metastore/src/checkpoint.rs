@@ -25,12 +25,13 @@ use std::iter::FromIterator;
 use std::ops::Range;
 use std::sync::Arc;
 
-use quickwit_proto::types::{Position, SourceId};
+use quickwit_proto::types::{IndexUid, Position, SourceId};
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
 /// Updates running indexing tasks in chitchat cluster state.
 use thiserror::Error;
 use tracing::{debug, warn};
+use ulid::Ulid;
 
 /// A `PartitionId` uniquely identifies a partition for a given source.
 #[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash)]
@@ -95,6 +96,14 @@ pub struct PartitionDelta {
 pub struct IndexCheckpoint {
     #[serde(flatten)]
     per_source: BTreeMap<SourceId, SourceCheckpoint>,
+    // Incarnation of the sources, assigned when a source is added. A source deleted and re-created
+    // with the same ID gets a new incarnation, so that the checkpoint deltas emitted by the
+    // pipelines of the previous incarnation are not applied to its checkpoint.
+    //
+    // It is serialized alongside the index metadata. Sources created before incarnations were
+    // introduced do not have one.
+    #[serde(skip)]
+    source_incarnations: BTreeMap<SourceId, Ulid>,
 }
 
 impl fmt::Debug for IndexCheckpoint {
@@ -107,7 +116,10 @@ impl fmt::Debug for IndexCheckpoint {
 
 impl From<BTreeMap<SourceId, SourceCheckpoint>> for IndexCheckpoint {
     fn from(per_source: BTreeMap<SourceId, SourceCheckpoint>) -> Self {
-        Self { per_source }
+        Self {
+            per_source,
+            source_incarnations: BTreeMap::new(),
+        }
     }
 }
 
@@ -118,6 +130,10 @@ impl IndexCheckpoint {
     /// current checkpoint, an error is returned, and the
     /// checkpoint remains unchanged.
     ///
+    /// A delta emitted for a previous incarnation of the source, i.e. before the source was
+    /// deleted and re-created with the same ID, is discarded with a warning. Deltas and sources
+    /// without an incarnation are assumed to belong to the current incarnation.
+    ///
     /// See [`SourceCheckpoint::try_apply_delta`] for more details.
     pub fn try_apply_delta(
         &mut self,
@@ -126,6 +142,20 @@ impl IndexCheckpoint {
         if delta.is_empty() {
             return Ok(false);
         }
+        if let (Some(delta_incarnation), Some(source_incarnation)) = (
+            delta.source_incarnation_opt,
+            self.source_incarnations.get(&delta.source_id),
+        ) {
+            if delta_incarnation != *source_incarnation {
+                warn!(
+                    source_id=%delta.source_id,
+                    delta_incarnation=%delta_incarnation,
+                    source_incarnation=%source_incarnation,
+                    "discarding checkpoint delta of a previous incarnation of the source"
+                );
+                return Ok(false);
+            }
+        }
         self.per_source
             .entry(delta.source_id)
             .or_default()
@@ -149,16 +179,37 @@ impl IndexCheckpoint {
         self.per_source.get(source_id)
     }
 
-    /// Adds a new source. If the source was already here, this
+    /// Returns the incarnation of a given source, if it has one.
+    pub fn source_incarnation(&self, source_id: &str) -> Option<Ulid> {
+        self.source_incarnations.get(source_id).copied()
+    }
+
+    pub(crate) fn source_incarnations(&self) -> &BTreeMap<SourceId, Ulid> {
+        &self.source_incarnations
+    }
+
+    pub(crate) fn set_source_incarnations(
+        &mut self,
+        source_incarnations: BTreeMap<SourceId, Ulid>,
+    ) {
+        self.source_incarnations = source_incarnations;
+    }
+
+    /// Adds a new source with a new incarnation. If the source was already here, this
     /// method returns successfully and does not override the existing checkpoint.
     pub fn add_source(&mut self, source_id: &str) {
-        self.per_source.entry(source_id.to_string()).or_default();
+        if let Entry::Vacant(entry) = self.per_source.entry(source_id.to_string()) {
+            entry.insert(SourceCheckpoint::default());
+            self.source_incarnations
+                .insert(source_id.to_string(), Ulid::new());
+        }
     }
 
-    /// Removes a source.
+    /// Removes a source and its incarnation.
     /// Returns successfully regardless of whether the source was present or not.
     pub fn remove_source(&mut self, source_id: &str) {
         self.per_source.remove(source_id);
+        self.source_incarnations.remove(source_id);
     }
 
     /// Returns [`true`] if the checkpoint is empty.
@@ -238,6 +289,31 @@ impl<'de> Deserialize<'de> for SourceCheckpoint {
     }
 }
 
+/// Serializable snapshot of the positions reached by a set of sources. It is used to export the
+/// positions of a cluster and to seed the checkpoints of another one, for instance when
+/// recovering from a disaster.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SerializableShardPositions {
+    pub sources: Vec<SerializableSourcePositions>,
+}
+
+/// Last published position of every shard (ingest V2 and file notification sources) or partition
+/// (other sources) of a source.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SerializableSourcePositions {
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    pub positions: SourceCheckpoint,
+    /// Number of stale positions received from other nodes for this source since the node serving
+    /// the export started. Ignored on import.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub num_position_regressions: u64,
+}
+
+fn is_zero(count: &u64) -> bool {
+    *count == 0
+}
+
 /// Error returned when trying to apply a checkpoint delta to a checkpoint that is not
 /// compatible. ie: the checkpoint delta starts from a point anterior to
 /// the checkpoint.
@@ -373,6 +449,11 @@ impl fmt::Debug for SourceCheckpoint {
 pub struct IndexCheckpointDelta {
     pub source_id: SourceId,
     pub source_delta: SourceCheckpointDelta,
+    /// Incarnation of the source the delta was emitted for. See
+    /// [`IndexCheckpoint::try_apply_delta`].
+    #[serde(default, rename = "source_incarnation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_incarnation_opt: Option<Ulid>,
 }
 
 impl IndexCheckpointDelta {
@@ -385,6 +466,7 @@ impl IndexCheckpointDelta {
         Self {
             source_id: source_id.to_string(),
             source_delta: SourceCheckpointDelta::from_range(pos_range),
+            source_incarnation_opt: None,
         }
     }
 }
@@ -801,6 +883,58 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_index_checkpoint_discards_delta_of_previous_source_incarnation() {
+        let mut index_checkpoint = IndexCheckpoint::default();
+        index_checkpoint.add_source("test-source");
+        let previous_incarnation = index_checkpoint.source_incarnation("test-source").unwrap();
+
+        index_checkpoint.remove_source("test-source");
+        assert!(index_checkpoint.source_incarnation("test-source").is_none());
+
+        index_checkpoint.add_source("test-source");
+        let current_incarnation = index_checkpoint.source_incarnation("test-source").unwrap();
+        assert_ne!(current_incarnation, previous_incarnation);
+
+        let mut stale_delta = IndexCheckpointDelta::for_test("test-source", 0..10);
+        stale_delta.source_incarnation_opt = Some(previous_incarnation);
+        assert!(!index_checkpoint.try_apply_delta(stale_delta).unwrap());
+        assert!(index_checkpoint
+            .source_checkpoint("test-source")
+            .unwrap()
+            .is_empty());
+
+        let mut delta = IndexCheckpointDelta::for_test("test-source", 0..3);
+        delta.source_incarnation_opt = Some(current_incarnation);
+        assert!(index_checkpoint.try_apply_delta(delta).unwrap());
+
+        // Deltas emitted by indexers unaware of incarnations are applied.
+        let delta = IndexCheckpointDelta::for_test("test-source", 3..5);
+        assert!(index_checkpoint.try_apply_delta(delta).unwrap());
+        assert_eq!(
+            index_checkpoint
+                .source_checkpoint("test-source")
+                .unwrap()
+                .position_for_partition(&PartitionId::default())
+                .unwrap(),
+            &Position::offset(4u64)
+        );
+    }
+
+    #[test]
+    fn test_index_checkpoint_without_source_incarnation_accepts_any_delta() {
+        // Sources created before incarnations were introduced do not have one.
+        let mut index_checkpoint = IndexCheckpoint::from(BTreeMap::from_iter([(
+            "test-source".to_string(),
+            SourceCheckpoint::default(),
+        )]));
+        assert!(index_checkpoint.source_incarnation("test-source").is_none());
+
+        let mut delta = IndexCheckpointDelta::for_test("test-source", 0..3);
+        delta.source_incarnation_opt = Some(Ulid::new());
+        assert!(index_checkpoint.try_apply_delta(delta).unwrap());
+    }
+
     #[test]
     fn test_get_source_checkpoint() {
         let partition = PartitionId::from("a");
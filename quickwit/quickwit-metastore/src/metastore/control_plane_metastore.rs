@@ -35,7 +35,8 @@ use quickwit_proto::metastore::{
     ListShardsRequest, ListShardsResponse, ListSplitsRequest, ListSplitsResponse,
     ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreResult, MetastoreService,
     MetastoreServiceClient, MetastoreServiceStream, OpenShardsRequest, OpenShardsResponse,
-    PruneShardsRequest, PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
+    PruneShardsRequest, PublishSplitsRequest, ResetSourceCheckpointRequest, RestoreIndexRequest,
+    SnapshotIndexRequest, SnapshotIndexResponse, StageSplitsRequest, ToggleIngestRequest,
     ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
     UpdateSplitsDeleteOpstampResponse,
 };
@@ -100,6 +101,22 @@ impl MetastoreService for ControlPlaneMetastore {
         Ok(response)
     }
 
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let response = self.control_plane.toggle_ingest(request).await?;
+        Ok(response)
+    }
+
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> MetastoreResult<CreateIndexResponse> {
+        let response = self.control_plane.restore_index(request).await?;
+        Ok(response)
+    }
+
     async fn delete_index(&self, request: DeleteIndexRequest) -> MetastoreResult<EmptyResponse> {
         let response = self.control_plane.delete_index(request).await?;
         Ok(response)
@@ -135,6 +152,13 @@ impl MetastoreService for ControlPlaneMetastore {
         self.metastore.index_metadata(request).await
     }
 
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> MetastoreResult<SnapshotIndexResponse> {
+        self.metastore.snapshot_index(request).await
+    }
+
     async fn indexes_metadata(
         &self,
         request: IndexesMetadataRequest,
@@ -239,6 +239,11 @@ impl FileBackedIndex {
         self.metadata.set_doc_mapping(doc_mapping)
     }
 
+    /// Blocks or unblocks ingest for the index, returning whether a mutation occurred.
+    pub fn set_ingest_blocked(&mut self, ingest_blocked: bool) -> bool {
+        self.metadata.set_ingest_blocked(ingest_blocked)
+    }
+
     /// Stages a single split.
     ///
     /// If a split already exists and is in the [SplitState::Staged] state,
@@ -274,6 +279,14 @@ impl FileBackedIndex {
         Ok(())
     }
 
+    /// Inserts splits as they are, whatever their state. Only used to restore an index from a
+    /// snapshot.
+    pub(crate) fn insert_restored_splits(&mut self, splits: Vec<Split>) {
+        for split in splits {
+            self.splits.insert(split.split_id().to_string(), split);
+        }
+    }
+
     /// Marks the splits for deletion. Returns whether a mutation occurred.
     pub(crate) fn mark_splits_for_deletion(
         &mut self,
@@ -22,10 +22,12 @@ use quickwit_proto::metastore::{MetastoreError, MetastoreResult};
 use regex::RegexSet;
 use regex_syntax::escape_into;
 
-pub(super) type IndexIdPattern = String;
+pub type IndexIdPattern = String;
 
+/// Matches index IDs against a set of index ID patterns. Patterns prefixed with `-` exclude the
+/// index IDs they match.
 #[derive(Debug)]
-pub(super) struct IndexIdMatcher {
+pub struct IndexIdMatcher {
     positive_matcher: RegexSet,
     negative_matcher: RegexSet,
 }
@@ -64,6 +66,7 @@ impl IndexIdMatcher {
         Ok(matcher)
     }
 
+    /// Returns whether the index ID matches at least one positive pattern and no negative pattern.
     pub fn is_match(&self, index_id: &str) -> bool {
         self.positive_matcher.is_match(index_id) && !self.negative_matcher.is_match(index_id)
     }
@@ -59,7 +59,8 @@ use quickwit_proto::metastore::{
     ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError, MetastoreResult,
     MetastoreService, MetastoreServiceStream, OpenShardSubrequest, OpenShardsRequest,
     OpenShardsResponse, PruneShardsRequest, PublishSplitsRequest, ResetSourceCheckpointRequest,
-    StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
+    RestoreIndexRequest, SnapshotIndexRequest, SnapshotIndexResponse, StageSplitsRequest,
+    ToggleIngestRequest, ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
     UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::types::{IndexId, IndexUid};
@@ -69,7 +70,7 @@ use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
 
 use self::file_backed_index::FileBackedIndex;
 pub use self::file_backed_metastore_factory::FileBackedMetastoreFactory;
-use self::index_id_matcher::IndexIdMatcher;
+pub use self::index_id_matcher::IndexIdMatcher;
 use self::lazy_file_backed_index::LazyFileBackedIndex;
 use self::manifest::{load_or_create_manifest, save_manifest, MANIFEST_FILE_NAME};
 use self::state::MetastoreState;
@@ -77,7 +78,8 @@ use self::store_operations::{delete_index, index_exists, load_index, put_index};
 use super::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadataResponseExt,
     IndexesMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsRequestExt,
-    ListSplitsResponseExt, PublishSplitsRequestExt, StageSplitsRequestExt, UpdateIndexRequestExt,
+    ListSplitsResponseExt, PublishSplitsRequestExt, RestoreIndexRequestExt,
+    SnapshotIndexResponseExt, StageSplitsRequestExt, UpdateIndexRequestExt,
     STREAM_SPLITS_CHUNK_SIZE,
 };
 use crate::checkpoint::IndexCheckpointDelta;
@@ -376,6 +378,68 @@ impl FileBackedMetastore {
         Ok(index_metadata)
     }
 
+    /// Writes a new index to the storage and registers it in the manifest. Fails if the index
+    /// already exists.
+    async fn insert_new_index(&self, index: FileBackedIndex) -> MetastoreResult<()> {
+        let index_id = index.index_id().to_string();
+
+        let mut state_wlock_guard = self.state.write().await;
+
+        // Checking if index already exists is a bit tedious:
+        // - first we check the index state: if it's `Active`, return `IndexAlreadyExists` error,
+        //   and if it's `Creating` or `Deleting`, it's ok to override them as these are
+        //   transitioning states.
+        // - if the index is not in the index states map, we still need to check the storage as we
+        //   don't want to override an existing metadata file.
+        if let Some(index_status) = state_wlock_guard.indexes.get(&index_id) {
+            if let LazyIndexStatus::Active(_) = index_status {
+                return Err(MetastoreError::AlreadyExists(EntityKind::Index {
+                    index_id,
+                }));
+            }
+        } else if index_exists(&*self.storage, &index_id).await? {
+            return Err(MetastoreError::Internal {
+                message: format!("index {index_id} cannot be created"),
+                cause: format!(
+                    "index {index_id} is not present in the manifest file but its file \
+                     `{index_id}/metastore.json` is on the storage"
+                ),
+            });
+        }
+        // Set state to `Creating` and rollback on metastore error.
+        state_wlock_guard
+            .indexes
+            .insert(index_id.clone(), LazyIndexStatus::Creating);
+
+        let manifest = state_wlock_guard.as_manifest();
+
+        if let Err(error) = save_manifest(&*self.storage, &manifest).await {
+            state_wlock_guard.indexes.remove(&index_id);
+            return Err(error);
+        }
+        put_index(&*self.storage, &index).await?;
+
+        state_wlock_guard.indexes.insert(
+            index_id.clone(),
+            LazyIndexStatus::Active(LazyFileBackedIndex::new(
+                self.storage.clone(),
+                index_id.clone(),
+                self.polling_interval_opt,
+                Some(index),
+            )),
+        );
+        // Set state to `Active` and rollback on metastore error.
+        let manifest = state_wlock_guard.as_manifest();
+
+        if let Err(error) = save_manifest(&*self.storage, &manifest).await {
+            state_wlock_guard
+                .indexes
+                .insert(index_id, LazyIndexStatus::Creating);
+            return Err(error);
+        }
+        Ok(())
+    }
+
     /// Returns the list of splits for the given request.
     /// No error is returned if any of the requested `index_uid` does not exist.
     async fn list_splits_inner(&self, request: ListSplitsRequest) -> MetastoreResult<Vec<Split>> {
@@ -441,65 +505,9 @@ impl MetastoreService for FileBackedMetastore {
             index_metadata.add_source(source_config)?;
         }
         let index_uid = index_metadata.index_uid.clone();
-        let index_id = &index_uid.index_id;
-
         let index_metadata_json = serde_utils::to_json_str(&index_metadata)?;
         let index = FileBackedIndex::from(index_metadata);
-
-        let mut state_wlock_guard = self.state.write().await;
-
-        // Checking if index already exists is a bit tedious:
-        // - first we check the index state: if it's `Active`, return `IndexAlreadyExists` error,
-        //   and if it's `Creating` or `Deleting`, it's ok to override them as these are
-        //   transitioning states.
-        // - if the index is not in the index states map, we still need to check the storage as we
-        //   don't want to override an existing metadata file.
-        if let Some(index_status) = state_wlock_guard.indexes.get(index_id) {
-            if let LazyIndexStatus::Active(_) = index_status {
-                return Err(MetastoreError::AlreadyExists(EntityKind::Index {
-                    index_id: index_id.to_string(),
-                }));
-            }
-        } else if index_exists(&*self.storage, index_id).await? {
-            return Err(MetastoreError::Internal {
-                message: format!("index {index_id} cannot be created"),
-                cause: format!(
-                    "index {index_id} is not present in the manifest file but its file \
-                     `{index_id}/metastore.json` is on the storage"
-                ),
-            });
-        }
-        // Set state to `Creating` and rollback on metastore error.
-        state_wlock_guard
-            .indexes
-            .insert(index_id.clone(), LazyIndexStatus::Creating);
-
-        let manifest = state_wlock_guard.as_manifest();
-
-        if let Err(error) = save_manifest(&*self.storage, &manifest).await {
-            state_wlock_guard.indexes.remove(index_id);
-            return Err(error);
-        }
-        put_index(&*self.storage, &index).await?;
-
-        state_wlock_guard.indexes.insert(
-            index_id.clone(),
-            LazyIndexStatus::Active(LazyFileBackedIndex::new(
-                self.storage.clone(),
-                index_id.clone(),
-                self.polling_interval_opt,
-                Some(index),
-            )),
-        );
-        // Set state to `Active` and rollback on metastore error.
-        let manifest = state_wlock_guard.as_manifest();
-
-        if let Err(error) = save_manifest(&*self.storage, &manifest).await {
-            state_wlock_guard
-                .indexes
-                .insert(index_id.clone(), LazyIndexStatus::Creating);
-            return Err(error);
-        }
+        self.insert_new_index(index).await?;
 
         let response = CreateIndexResponse {
             index_uid: index_uid.into(),
@@ -537,6 +545,65 @@ impl MetastoreService for FileBackedMetastore {
         IndexMetadataResponse::try_from_index_metadata(&index_metadata)
     }
 
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let ingest_blocked = request.ingest_blocked;
+        let index_uid = request.index_uid();
+
+        let index_metadata = self
+            .mutate(index_uid, |index| {
+                let mutation_occurred = index.set_ingest_blocked(ingest_blocked);
+                let index_metadata = index.metadata().clone();
+
+                if mutation_occurred {
+                    Ok(MutationOccurred::Yes(index_metadata))
+                } else {
+                    Ok(MutationOccurred::No(index_metadata))
+                }
+            })
+            .await?;
+        IndexMetadataResponse::try_from_index_metadata(&index_metadata)
+    }
+
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> MetastoreResult<SnapshotIndexResponse> {
+        // The index metadata and the splits are read while holding the index lock, so the splits
+        // are consistent with the source checkpoints.
+        let (index_metadata, splits) = self
+            .read_any(&request.index_id, |index| {
+                let query = ListSplitsQuery::for_index(index.index_uid().clone())
+                    .with_split_state(SplitState::Published);
+                let splits = index.list_splits(&query)?;
+                Ok((index.metadata().clone(), splits))
+            })
+            .await?;
+        SnapshotIndexResponse::try_from_index_metadata_and_splits(&index_metadata, &splits)
+    }
+
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> MetastoreResult<CreateIndexResponse> {
+        let (index_metadata, splits) = request.deserialize_index_metadata_and_splits()?;
+        let index_uid = index_metadata.index_uid.clone();
+        let index_metadata_json = serde_utils::to_json_str(&index_metadata)?;
+
+        // The index is written to the storage in a single file, along with its splits.
+        let mut index = FileBackedIndex::from(index_metadata);
+        index.insert_restored_splits(splits);
+        self.insert_new_index(index).await?;
+
+        let response = CreateIndexResponse {
+            index_uid: index_uid.into(),
+            index_metadata_json,
+        };
+        Ok(response)
+    }
+
     async fn delete_index(&self, request: DeleteIndexRequest) -> MetastoreResult<EmptyResponse> {
         // We pick the outer lock here, so that we enter a critical section.
         let mut state_wlock_guard = self.state.write().await;
@@ -596,6 +663,7 @@ impl MetastoreService for FileBackedMetastore {
         let splits_metadata = request.deserialize_splits_metadata()?;
 
         self.mutate(&index_uid, |index| {
+            index.metadata().check_ingest_not_blocked()?;
             let mut failed_split_ids = Vec::new();
 
             for split_metadata in splits_metadata {
@@ -632,6 +700,7 @@ impl MetastoreService for FileBackedMetastore {
             request.deserialize_index_checkpoint()?;
         let index_uid = request.index_uid().clone();
         self.mutate(&index_uid, |index| {
+            index.metadata().check_ingest_not_blocked()?;
             index.publish_splits(
                 request.staged_split_ids,
                 request.replaced_split_ids,
@@ -49,6 +49,9 @@ pub struct IndexMetadata {
     pub create_timestamp: i64,
     /// Sources
     pub sources: HashMap<SourceId, SourceConfig>,
+    /// Whether the index is in read-only mode: new documents are rejected and no new splits can
+    /// be staged or published, while the index remains searchable.
+    pub ingest_blocked: bool,
 }
 
 impl IndexMetadata {
@@ -66,6 +69,7 @@ impl IndexMetadata {
             checkpoint: Default::default(),
             create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             sources: HashMap::default(),
+            ingest_blocked: false,
         }
     }
 
@@ -140,6 +144,29 @@ impl IndexMetadata {
         }
     }
 
+    /// Blocks or unblocks ingest for the index, returning whether a mutation occurred.
+    pub fn set_ingest_blocked(&mut self, ingest_blocked: bool) -> bool {
+        if self.ingest_blocked != ingest_blocked {
+            self.ingest_blocked = ingest_blocked;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns an error if ingest is blocked for the index.
+    pub(crate) fn check_ingest_not_blocked(&self) -> MetastoreResult<()> {
+        if self.ingest_blocked {
+            return Err(MetastoreError::FailedPrecondition {
+                entity: EntityKind::Index {
+                    index_id: self.index_id().to_string(),
+                },
+                message: "ingest is blocked for this index".to_string(),
+            });
+        }
+        Ok(())
+    }
+
     /// Adds a source to the index. Returns an error if the source already exists.
     pub fn add_source(&mut self, source_config: SourceConfig) -> MetastoreResult<()> {
         match self.sources.entry(source_config.source_id.clone()) {
@@ -210,10 +237,18 @@ impl quickwit_config::TestableForRegression for IndexMetadata {
             checkpoint,
             create_timestamp: 1789,
             sources: Default::default(),
+            ingest_blocked: false,
         };
         index_metadata
             .add_source(SourceConfig::sample_for_regression())
             .unwrap();
+        let source_incarnation = ulid::Ulid::from_string("01HVSN5BHZ9R0K5QMKA7X2V0GW").unwrap();
+        index_metadata
+            .checkpoint
+            .set_source_incarnations(BTreeMap::from_iter([(
+                "kafka-source".to_string(),
+                source_incarnation,
+            )]));
         index_metadata
     }
 
@@ -222,5 +257,6 @@ impl quickwit_config::TestableForRegression for IndexMetadata {
         assert_eq!(self.checkpoint, other.checkpoint);
         assert_eq!(self.create_timestamp, other.create_timestamp);
         assert_eq!(self.sources, other.sources);
+        assert_eq!(self.ingest_blocked, other.ingest_blocked);
     }
 }
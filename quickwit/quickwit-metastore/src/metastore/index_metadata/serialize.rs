@@ -17,11 +17,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
+use quickwit_common::is_false;
 use quickwit_config::{IndexConfig, SourceConfig};
-use quickwit_proto::types::IndexUid;
+use quickwit_proto::types::{IndexUid, SourceId};
 use serde::{self, Deserialize, Serialize};
+use ulid::Ulid;
 
 use crate::checkpoint::IndexCheckpoint;
 use crate::split_metadata::utc_now_timestamp;
@@ -58,12 +60,15 @@ impl TryFrom<VersionedIndexMetadata> for IndexMetadata {
 impl From<IndexMetadata> for IndexMetadataV0_8 {
     fn from(index_metadata: IndexMetadata) -> Self {
         let sources: Vec<SourceConfig> = index_metadata.sources.values().cloned().collect();
+        let source_incarnations = index_metadata.checkpoint.source_incarnations().clone();
         Self {
             index_uid: index_metadata.index_uid,
             index_config: index_metadata.index_config,
             checkpoint: index_metadata.checkpoint,
+            source_incarnations,
             create_timestamp: index_metadata.create_timestamp,
             sources,
+            ingest_blocked: index_metadata.ingest_blocked,
         }
     }
 }
@@ -76,10 +81,18 @@ pub(crate) struct IndexMetadataV0_8 {
     pub index_config: IndexConfig,
     #[schema(value_type = Object)]
     pub checkpoint: IndexCheckpoint,
+    // Indexes created before source incarnations were introduced do not have any.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub source_incarnations: BTreeMap<SourceId, Ulid>,
     #[serde(default = "utc_now_timestamp")]
     pub create_timestamp: i64,
     #[schema(value_type = Vec<VersionedSourceConfig>)]
     pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub ingest_blocked: bool,
 }
 
 impl TryFrom<IndexMetadataV0_8> for IndexMetadata {
@@ -93,12 +106,15 @@ impl TryFrom<IndexMetadataV0_8> for IndexMetadata {
             }
             sources.insert(source.source_id.clone(), source);
         }
+        let mut checkpoint = v0_8.checkpoint;
+        checkpoint.set_source_incarnations(v0_8.source_incarnations);
         Ok(Self {
             index_uid: v0_8.index_uid,
             index_config: v0_8.index_config,
-            checkpoint: v0_8.checkpoint,
+            checkpoint,
             create_timestamp: v0_8.create_timestamp,
             sources,
+            ingest_blocked: v0_8.ingest_blocked,
         })
     }
 }
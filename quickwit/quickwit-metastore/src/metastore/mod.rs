@@ -43,7 +43,8 @@ use quickwit_proto::metastore::{
     IndexMetadataFailure, IndexMetadataRequest, IndexMetadataResponse, IndexesMetadataResponse,
     ListIndexesMetadataResponse, ListSplitsRequest, ListSplitsResponse, MetastoreError,
     MetastoreResult, MetastoreService, MetastoreServiceClient, MetastoreServiceStream,
-    PublishSplitsRequest, StageSplitsRequest, UpdateIndexRequest,
+    PublishSplitsRequest, RestoreIndexRequest, SnapshotIndexResponse, StageSplitsRequest,
+    UpdateIndexRequest,
 };
 use quickwit_proto::types::{IndexUid, NodeId, SplitId};
 use time::OffsetDateTime;
@@ -285,6 +286,104 @@ impl IndexMetadataResponseExt for IndexMetadataResponse {
     }
 }
 
+/// Helper trait to build a [`SnapshotIndexResponse`] and deserialize its payload.
+pub trait SnapshotIndexResponseExt {
+    /// Creates a new [`SnapshotIndexResponse`] from an [`IndexMetadata`] and its published splits.
+    fn try_from_index_metadata_and_splits(
+        index_metadata: &IndexMetadata,
+        splits: &[Split],
+    ) -> MetastoreResult<SnapshotIndexResponse>;
+
+    /// Deserializes the `index_metadata_serialized_json` field of a [`SnapshotIndexResponse`] into
+    /// an [`IndexMetadata`].
+    fn deserialize_index_metadata(&self) -> MetastoreResult<IndexMetadata>;
+
+    /// Deserializes the `splits_serialized_json` field of a [`SnapshotIndexResponse`] into a list
+    /// of [`Split`].
+    fn deserialize_splits(&self) -> MetastoreResult<Vec<Split>>;
+}
+
+impl SnapshotIndexResponseExt for SnapshotIndexResponse {
+    fn try_from_index_metadata_and_splits(
+        index_metadata: &IndexMetadata,
+        splits: &[Split],
+    ) -> MetastoreResult<Self> {
+        let index_metadata_serialized_json = serde_utils::to_json_str(index_metadata)?;
+        let splits_serialized_json = serde_utils::to_json_str(splits)?;
+        let response = Self {
+            index_metadata_serialized_json,
+            splits_serialized_json,
+        };
+        Ok(response)
+    }
+
+    fn deserialize_index_metadata(&self) -> MetastoreResult<IndexMetadata> {
+        serde_utils::from_json_str(&self.index_metadata_serialized_json)
+    }
+
+    fn deserialize_splits(&self) -> MetastoreResult<Vec<Split>> {
+        serde_utils::from_json_str(&self.splits_serialized_json)
+    }
+}
+
+/// Helper trait to build a [`RestoreIndexRequest`] and deserialize its payload.
+pub trait RestoreIndexRequestExt {
+    /// Creates a new [`RestoreIndexRequest`] from an [`IndexMetadata`] and its splits.
+    fn try_from_index_metadata_and_splits(
+        index_metadata: &IndexMetadata,
+        splits: &[Split],
+    ) -> MetastoreResult<RestoreIndexRequest>;
+
+    /// Deserializes the payload of a [`RestoreIndexRequest`] into the [`IndexMetadata`] and the
+    /// splits to restore. The index is given a new incarnation, and the splits are validated:
+    /// only published splits and splits marked for deletion can be restored.
+    fn deserialize_index_metadata_and_splits(&self)
+        -> MetastoreResult<(IndexMetadata, Vec<Split>)>;
+}
+
+impl RestoreIndexRequestExt for RestoreIndexRequest {
+    fn try_from_index_metadata_and_splits(
+        index_metadata: &IndexMetadata,
+        splits: &[Split],
+    ) -> MetastoreResult<Self> {
+        let index_metadata_serialized_json = serde_utils::to_json_str(index_metadata)?;
+        let splits_serialized_json = serde_utils::to_json_str(splits)?;
+        let request = Self {
+            index_metadata_serialized_json,
+            splits_serialized_json,
+        };
+        Ok(request)
+    }
+
+    fn deserialize_index_metadata_and_splits(
+        &self,
+    ) -> MetastoreResult<(IndexMetadata, Vec<Split>)> {
+        let mut index_metadata: IndexMetadata =
+            serde_utils::from_json_str(&self.index_metadata_serialized_json)?;
+        let mut splits: Vec<Split> = serde_utils::from_json_str(&self.splits_serialized_json)?;
+
+        let index_uid = IndexUid::new_with_random_ulid(index_metadata.index_id());
+        index_metadata.index_uid = index_uid.clone();
+
+        for split in &mut splits {
+            if !matches!(
+                split.split_state,
+                SplitState::Published | SplitState::MarkedForDeletion
+            ) {
+                let message = format!(
+                    "split `{}` is {:?}: only published splits and splits marked for deletion can \
+                     be restored",
+                    split.split_id(),
+                    split.split_state
+                );
+                return Err(MetastoreError::InvalidArgument { message });
+            }
+            split.split_metadata.index_uid = index_uid.clone();
+        }
+        Ok((index_metadata, splits))
+    }
+}
+
 /// Helper trait to build a [`IndexesMetadataResponse`] and deserialize its payload.
 #[async_trait]
 pub trait IndexesMetadataResponseExt {
@@ -615,7 +714,7 @@ pub struct ListSplitsQuery {
     /// A specific set of tag(s) to filter by.
     pub tags: Option<TagFilterAst>,
 
-    /// The time range to filter by.
+    /// The time range to filter by. Splits without a time range always match.
     pub time_range: FilterRange<i64>,
 
     /// The delete opstamp range to filter by.
@@ -969,10 +1068,11 @@ impl<T> Default for FilterRange<T> {
 
 /// Maps the given source params to whether checkpoints should be stored in the index metadata
 /// (false) or the shard table (true)
-fn use_shard_api(params: &SourceParams) -> bool {
+pub fn use_shard_api(params: &SourceParams) -> bool {
     match params {
         SourceParams::File(FileSourceParams::Filepath(_)) => false,
         SourceParams::File(FileSourceParams::Notifications(_)) => true,
+        SourceParams::File(FileSourceParams::Polling(_)) => false,
         SourceParams::Ingest => true,
         SourceParams::IngestApi => false,
         SourceParams::IngestCli => false,
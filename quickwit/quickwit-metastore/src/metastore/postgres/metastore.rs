@@ -46,7 +46,8 @@ use quickwit_proto::metastore::{
     ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError,
     MetastoreResult, MetastoreService, MetastoreServiceStream, OpenShardSubrequest,
     OpenShardSubresponse, OpenShardsRequest, OpenShardsResponse, PruneShardsRequest,
-    PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest,
+    PublishSplitsRequest, ResetSourceCheckpointRequest, RestoreIndexRequest, SnapshotIndexRequest,
+    SnapshotIndexResponse, StageSplitsRequest, ToggleIngestRequest, ToggleSourceRequest,
     UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::types::{IndexId, IndexUid, Position, PublishToken, ShardId, SourceId};
@@ -78,7 +79,8 @@ use crate::metastore::{
 use crate::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
     ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
-    MetastoreServiceExt, Split, SplitState, StageSplitsRequestExt, UpdateIndexRequestExt,
+    MetastoreServiceExt, RestoreIndexRequestExt, SnapshotIndexResponseExt, Split, SplitState,
+    StageSplitsRequestExt, UpdateIndexRequestExt,
 };
 
 /// PostgreSQL metastore implementation.
@@ -440,6 +442,22 @@ impl MetastoreService for PostgresqlMetastore {
         IndexMetadataResponse::try_from_index_metadata(&updated_index_metadata)
     }
 
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let ingest_blocked = request.ingest_blocked;
+        let index_uid: IndexUid = request.index_uid().clone();
+        let updated_index_metadata = run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata::<MetastoreError, _>(tx, index_uid, |index_metadata| {
+                let mutation_occurred = index_metadata.set_ingest_blocked(ingest_blocked);
+                Ok(MutationOccurred::from(mutation_occurred))
+            })
+            .await
+        })?;
+        IndexMetadataResponse::try_from_index_metadata(&updated_index_metadata)
+    }
+
     #[instrument(skip(self))]
     async fn index_metadata(
         &self,
@@ -565,6 +583,145 @@ impl MetastoreService for PostgresqlMetastore {
         Ok(response)
     }
 
+    #[instrument(skip(self))]
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> MetastoreResult<SnapshotIndexResponse> {
+        let index_id = request.index_id;
+        let (index_metadata, pg_splits) = run_with_tx!(self.connection_pool, tx, {
+            // Publishing splits locks the index row, so locking it here guarantees that the splits
+            // are consistent with the source checkpoints.
+            let index_metadata = index_metadata(tx, &index_id, true).await?;
+            let pg_splits: Vec<PgSplit> = sqlx::query_as::<_, PgSplit>(
+                r#"
+                SELECT *
+                FROM splits
+                WHERE index_uid = $1 AND split_state = $2
+                "#,
+            )
+            .bind(&index_metadata.index_uid)
+            .bind(SplitState::Published.as_str())
+            .fetch_all(tx.as_mut())
+            .await
+            .map_err(|sqlx_error| convert_sqlx_err(&index_id, sqlx_error))?;
+            Ok((index_metadata, pg_splits))
+        })?;
+        let splits: Vec<Split> = pg_splits
+            .into_iter()
+            .map(|pg_split| pg_split.try_into())
+            .collect::<MetastoreResult<_>>()?;
+        SnapshotIndexResponse::try_from_index_metadata_and_splits(&index_metadata, &splits)
+    }
+
+    #[instrument(skip_all)]
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> MetastoreResult<CreateIndexResponse> {
+        let (index_metadata, splits) = request.deserialize_index_metadata_and_splits()?;
+        let index_uid = index_metadata.index_uid.clone();
+        let response = CreateIndexResponse {
+            index_uid: index_uid.clone().into(),
+            index_metadata_json: serde_utils::to_json_str(&index_metadata)?,
+        };
+
+        let mut split_ids = Vec::with_capacity(splits.len());
+        let mut split_states = Vec::with_capacity(splits.len());
+        let mut time_range_start_list = Vec::with_capacity(splits.len());
+        let mut time_range_end_list = Vec::with_capacity(splits.len());
+        let mut tags_list = Vec::with_capacity(splits.len());
+        let mut splits_metadata_json = Vec::with_capacity(splits.len());
+        let mut delete_opstamps = Vec::with_capacity(splits.len());
+        let mut create_timestamps = Vec::with_capacity(splits.len());
+        let mut update_timestamps = Vec::with_capacity(splits.len());
+        let mut publish_timestamps = Vec::with_capacity(splits.len());
+        let mut maturity_timestamps = Vec::with_capacity(splits.len());
+        let mut node_ids = Vec::with_capacity(splits.len());
+
+        for split in splits {
+            let split_metadata = split.split_metadata;
+            splits_metadata_json.push(serde_utils::to_json_str(&split_metadata)?);
+            split_states.push(split.split_state.as_str());
+            update_timestamps.push(split.update_timestamp);
+            publish_timestamps.push(split.publish_timestamp);
+
+            let time_range_start = split_metadata
+                .time_range
+                .as_ref()
+                .map(|range| *range.start());
+            time_range_start_list.push(time_range_start);
+            maturity_timestamps.push(split_maturity_timestamp(&split_metadata));
+
+            let time_range_end = split_metadata.time_range.map(|range| *range.end());
+            time_range_end_list.push(time_range_end);
+
+            let tags: Vec<String> = split_metadata.tags.into_iter().collect();
+            tags_list.push(sqlx::types::Json(tags));
+            split_ids.push(split_metadata.split_id);
+            delete_opstamps.push(split_metadata.delete_opstamp as i64);
+            create_timestamps.push(split_metadata.create_timestamp);
+            node_ids.push(split_metadata.node_id);
+        }
+        run_with_tx!(self.connection_pool, tx, {
+            sqlx::query(
+                "INSERT INTO indexes (index_uid, index_id, index_metadata_json) VALUES ($1, $2, \
+                 $3)",
+            )
+            .bind(index_uid.to_string())
+            .bind(&index_uid.index_id)
+            .bind(&response.index_metadata_json)
+            .execute(tx.as_mut())
+            .await
+            .map_err(|sqlx_error| convert_sqlx_err(&index_uid.index_id, sqlx_error))?;
+
+            if !split_ids.is_empty() {
+                sqlx::query(r#"
+                    INSERT INTO splits
+                        (split_id, split_state, time_range_start, time_range_end, tags, split_metadata_json, delete_opstamp, create_timestamp, update_timestamp, publish_timestamp, maturity_timestamp, index_uid, node_id)
+                    SELECT
+                        split_id,
+                        split_state,
+                        time_range_start,
+                        time_range_end,
+                        ARRAY(SELECT json_array_elements_text(tags_json::json)) as tags,
+                        split_metadata_json,
+                        delete_opstamp,
+                        to_timestamp(create_timestamp),
+                        to_timestamp(update_timestamp),
+                        to_timestamp(publish_timestamp),
+                        to_timestamp(maturity_timestamp),
+                        $13 as index_uid,
+                        node_id
+                    FROM
+                        UNNEST($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                        AS restored_splits (split_id, split_state, time_range_start, time_range_end, tags_json, split_metadata_json, delete_opstamp, create_timestamp, update_timestamp, publish_timestamp, maturity_timestamp, node_id)
+                    "#)
+                    .bind(&split_ids)
+                    .bind(split_states)
+                    .bind(time_range_start_list)
+                    .bind(time_range_end_list)
+                    .bind(tags_list)
+                    .bind(splits_metadata_json)
+                    .bind(delete_opstamps)
+                    .bind(create_timestamps)
+                    .bind(update_timestamps)
+                    .bind(publish_timestamps)
+                    .bind(maturity_timestamps)
+                    .bind(&node_ids)
+                    .bind(&index_uid)
+                    .execute(tx.as_mut())
+                    .await
+                    .map_err(|sqlx_error| convert_sqlx_err(&index_uid.index_id, sqlx_error))?;
+            }
+            info!(
+                %index_uid,
+                "restored index with `{}` splits successfully", split_ids.len()
+            );
+            Ok(response)
+        })
+    }
+
     #[instrument(skip_all, fields(index_id=%request.index_uid()))]
     async fn delete_index(&self, request: DeleteIndexRequest) -> MetastoreResult<EmptyResponse> {
         let index_uid: IndexUid = request.index_uid().clone();
@@ -623,6 +780,11 @@ impl MetastoreService for PostgresqlMetastore {
 
         // TODO: Remove transaction.
         run_with_tx!(self.connection_pool, tx, {
+            // A missing index is reported by the insert below.
+            if let Some(pg_index) = index_opt_for_uid(tx.as_mut(), index_uid.clone(), false).await?
+            {
+                pg_index.index_metadata()?.check_ingest_not_blocked()?;
+            }
             let upserted_split_ids: Vec<String> = sqlx::query_scalar(r#"
                 INSERT INTO splits
                     (split_id, time_range_start, time_range_end, tags, split_metadata_json, delete_opstamp, maturity_timestamp, split_state, index_uid, node_id)
@@ -705,6 +867,8 @@ impl MetastoreService for PostgresqlMetastore {
                     index_id: index_uid.index_id,
                 }));
             }
+            index_metadata.check_ingest_not_blocked()?;
+
             if let Some(checkpoint_delta) = checkpoint_delta_opt {
                 let source_id = checkpoint_delta.source_id.clone();
                 let source = index_metadata.sources.get(&source_id).ok_or_else(|| {
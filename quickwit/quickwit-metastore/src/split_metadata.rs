@@ -25,6 +25,7 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use bytesize::ByteSize;
+use quickwit_doc_mapper::term_presence::TermPresenceFilters;
 use quickwit_proto::types::{DocMappingUid, IndexUid, SourceId, SplitId};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DurationMilliSeconds};
@@ -120,6 +121,13 @@ pub struct SplitMetadata {
     /// [`MAX_VALUES_PER_TAG_FIELD`]: https://github.com/quickwit-oss/quickwit/blob/main/quickwit-indexing/src/actors/packager.rs#L36
     pub tags: BTreeSet<String>,
 
+    /// Term presence filters of the fields registered in the
+    /// [`DocMapping`](quickwit_config::DocMapping) `term_presence_filters` attribute, keyed by
+    /// field name. A field has no filter when the split holds too many distinct values for the
+    /// size budget of the filter.
+    #[schema(value_type = Object)]
+    pub term_presence_filters: TermPresenceFilters,
+
     /// Contains the range of bytes of the footer that needs to be downloaded
     /// in order to open a split.
     ///
@@ -178,6 +186,9 @@ impl fmt::Debug for SplitMetadata {
             tags_str.push('}');
             debug_struct.field("tags", &tags_str);
         }
+        if !self.term_presence_filters.is_empty() {
+            debug_struct.field("term_presence_filters", &self.term_presence_filters);
+        }
         debug_struct.field("footer_offsets", &self.footer_offsets);
         debug_struct.field("delete_opstamp", &self.delete_opstamp);
         debug_struct.field("num_merge_ops", &self.num_merge_ops);
@@ -283,6 +294,7 @@ impl quickwit_config::TestableForRegression for SplitMetadata {
                 maturation_period: Duration::from_secs(4),
             },
             tags: ["234".to_string(), "aaa".to_string()].into_iter().collect(),
+            term_presence_filters: Default::default(),
             footer_offsets: 1000..2000,
             num_merge_ops: 3,
             doc_mapping_uid: DocMappingUid::default(),
@@ -422,6 +434,7 @@ mod tests {
                 tags.insert("😿".to_string());
                 tags
             },
+            term_presence_filters: Default::default(),
             footer_offsets: 0..1024,
             delete_opstamp: 0,
             num_merge_ops: 0,
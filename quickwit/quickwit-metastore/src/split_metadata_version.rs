@@ -17,9 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::{Range, RangeInclusive};
 
+use quickwit_doc_mapper::term_presence::TermPresenceFilters;
 use quickwit_proto::types::{DocMappingUid, IndexUid, SplitId};
 use serde::{Deserialize, Serialize};
 
@@ -78,6 +79,11 @@ pub(crate) struct SplitMetadataV0_8 {
     /// A set of tags for categorizing and searching group of splits.
     pub tags: BTreeSet<String>,
 
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    /// Term presence filters of the high cardinality fields, keyed by field name.
+    pub term_presence_filters: TermPresenceFilters,
+
     #[schema(value_type = Object)]
     /// Contains the range of bytes of the footer that needs to be downloaded
     /// in order to open a split.
@@ -130,6 +136,7 @@ impl From<SplitMetadataV0_8> for SplitMetadata {
             create_timestamp: v8.create_timestamp,
             maturity: v8.maturity,
             tags: v8.tags,
+            term_presence_filters: v8.term_presence_filters,
             footer_offsets: v8.footer_offsets,
             num_merge_ops: v8.num_merge_ops,
             doc_mapping_uid: v8.doc_mapping_uid,
@@ -152,6 +159,7 @@ impl From<SplitMetadata> for SplitMetadataV0_8 {
             create_timestamp: split.create_timestamp,
             maturity: split.maturity,
             tags: split.tags,
+            term_presence_filters: split.term_presence_filters,
             footer_offsets: split.footer_offsets,
             num_merge_ops: split.num_merge_ops,
             doc_mapping_uid: split.doc_mapping_uid,
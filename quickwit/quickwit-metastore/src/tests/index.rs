@@ -23,29 +23,34 @@
 //  - index_exists
 //  - index_metadata
 //  - list_indexes
+//  - snapshot_index
+//  - restore_index
 //  - delete_index
 
 use quickwit_common::rand::append_random_suffix;
 use quickwit_config::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
 use quickwit_config::{
-    IndexConfig, IndexingSettings, RetentionPolicy, SearchSettings, SourceConfig, CLI_SOURCE_ID,
-    INGEST_V2_SOURCE_ID,
+    IndexConfig, IndexingSettings, RetentionPolicy, SearchSettings, SourceConfig, SourceParams,
+    CLI_SOURCE_ID, INGEST_V2_SOURCE_ID,
 };
 use quickwit_doc_mapper::{Cardinality, FieldMappingEntry, FieldMappingType, QuickwitJsonOptions};
 use quickwit_proto::metastore::{
     CreateIndexRequest, DeleteIndexRequest, EntityKind, IndexMetadataFailure,
     IndexMetadataFailureReason, IndexMetadataRequest, IndexMetadataSubrequest,
-    IndexesMetadataRequest, ListIndexesMetadataRequest, MetastoreError, MetastoreService,
-    StageSplitsRequest, UpdateIndexRequest,
+    IndexesMetadataRequest, ListIndexesMetadataRequest, ListSplitsRequest, MetastoreError,
+    MetastoreService, PublishSplitsRequest, RestoreIndexRequest, SnapshotIndexRequest,
+    StageSplitsRequest, ToggleIngestRequest, UpdateIndexRequest,
 };
-use quickwit_proto::types::{DocMappingUid, IndexUid};
+use quickwit_proto::types::{DocMappingUid, IndexUid, Position};
 
 use super::DefaultForTest;
+use crate::checkpoint::{IndexCheckpointDelta, PartitionId};
 use crate::tests::cleanup_index;
 use crate::{
     CreateIndexRequestExt, IndexMetadataResponseExt, IndexesMetadataResponseExt,
-    ListIndexesMetadataResponseExt, MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt,
-    UpdateIndexRequestExt,
+    ListIndexesMetadataResponseExt, ListSplitsRequestExt, MetastoreServiceExt,
+    MetastoreServiceStreamSplitsExt, RestoreIndexRequestExt, SnapshotIndexResponseExt,
+    SplitMetadata, SplitState, StageSplitsRequestExt, UpdateIndexRequestExt,
 };
 
 pub async fn test_metastore_create_index<
@@ -171,6 +176,7 @@ pub async fn test_metastore_update_search_settings<
             index_uid.clone(),
             &SearchSettings {
                 default_search_fields: loop_search_settings.clone(),
+                ..Default::default()
             },
             &index_config.retention_policy_opt,
             &index_config.indexing_settings,
@@ -260,6 +266,277 @@ pub async fn test_metastore_update_indexing_settings<
     cleanup_index(&mut metastore, index_uid).await;
 }
 
+pub async fn test_metastore_toggle_ingest<
+    MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let (mut metastore, index_uid, index_config) =
+        setup_metastore_for_update::<MetastoreToTest>().await;
+
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_uid(index_uid.clone()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(!index_metadata.ingest_blocked);
+
+    let split_metadata = SplitMetadata {
+        split_id: "test-split".to_string(),
+        index_uid: index_uid.clone(),
+        ..Default::default()
+    };
+    let stage_splits_request =
+        StageSplitsRequest::try_from_split_metadata(index_uid.clone(), &split_metadata).unwrap();
+    metastore
+        .stage_splits(stage_splits_request.clone())
+        .await
+        .unwrap();
+
+    let toggle_ingest_request = ToggleIngestRequest {
+        index_uid: Some(index_uid.clone()),
+        ingest_blocked: true,
+    };
+    let resp_metadata = metastore
+        .toggle_ingest(toggle_ingest_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(resp_metadata.ingest_blocked);
+
+    // Updating the index config does not unblock ingest.
+    let index_update = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.search_settings,
+        &index_config.retention_policy_opt,
+        &index_config.indexing_settings,
+        &index_config.doc_mapping,
+    )
+    .unwrap();
+    metastore.update_index(index_update).await.unwrap();
+
+    let updated_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_uid(index_uid.clone()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(updated_metadata.ingest_blocked);
+
+    let error = metastore
+        .stage_splits(stage_splits_request.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::FailedPrecondition {
+            entity: EntityKind::Index { .. },
+            ..
+        }
+    ));
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec!["test-split".to_string()],
+        ..Default::default()
+    };
+    let error = metastore
+        .publish_splits(publish_splits_request.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::FailedPrecondition {
+            entity: EntityKind::Index { .. },
+            ..
+        }
+    ));
+
+    let toggle_ingest_request = ToggleIngestRequest {
+        index_uid: Some(index_uid.clone()),
+        ingest_blocked: false,
+    };
+    let resp_metadata = metastore
+        .toggle_ingest(toggle_ingest_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(!resp_metadata.ingest_blocked);
+
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    cleanup_index(&mut metastore, index_uid).await;
+}
+
+pub async fn test_metastore_snapshot_and_restore_index<
+    MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-snapshot-restore-index");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+    let source_id = format!("{index_id}--source");
+    let source_config = SourceConfig::for_test(&source_id, SourceParams::void());
+
+    let create_index_request =
+        CreateIndexRequest::try_from_index_and_source_configs(&index_config, &[source_config])
+            .unwrap();
+    let index_uid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    let split_metadata_1 = SplitMetadata {
+        split_id: "test-split-1".to_string(),
+        index_uid: index_uid.clone(),
+        ..Default::default()
+    };
+    let split_metadata_2 = SplitMetadata {
+        split_id: "test-split-2".to_string(),
+        index_uid: index_uid.clone(),
+        ..Default::default()
+    };
+    let stage_splits_request = StageSplitsRequest::try_from_splits_metadata(
+        index_uid.clone(),
+        [split_metadata_1, split_metadata_2],
+    )
+    .unwrap();
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec!["test-split-1".to_string()],
+        index_checkpoint_delta_json_opt: Some({
+            let checkpoint_delta = IndexCheckpointDelta::for_test(&source_id, 0..100);
+            serde_json::to_string(&checkpoint_delta).unwrap()
+        }),
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    // The snapshot only contains the published splits.
+    let snapshot_index_response = metastore
+        .snapshot_index(SnapshotIndexRequest {
+            index_id: index_id.clone(),
+        })
+        .await
+        .unwrap();
+    let index_metadata = snapshot_index_response
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(index_metadata.index_uid, index_uid);
+
+    let source_checkpoint = index_metadata
+        .checkpoint
+        .source_checkpoint(&source_id)
+        .unwrap();
+    assert_eq!(
+        source_checkpoint
+            .position_for_partition(&PartitionId::default())
+            .unwrap(),
+        &Position::offset(99u64)
+    );
+    let mut splits = snapshot_index_response.deserialize_splits().unwrap();
+    assert_eq!(splits.len(), 1);
+    assert_eq!(splits[0].split_id(), "test-split-1");
+    assert_eq!(splits[0].split_state, SplitState::Published);
+
+    let error = metastore
+        .snapshot_index(SnapshotIndexRequest {
+            index_id: "index-not-found".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::NotFound(EntityKind::Index { .. })
+    ));
+
+    let mut split_marked_for_deletion = splits[0].clone();
+    split_marked_for_deletion.split_metadata.split_id = "test-split-3".to_string();
+    split_marked_for_deletion.split_state = SplitState::MarkedForDeletion;
+    split_marked_for_deletion.publish_timestamp = None;
+    splits.push(split_marked_for_deletion);
+
+    // An existing index cannot be overwritten.
+    let restore_index_request =
+        RestoreIndexRequest::try_from_index_metadata_and_splits(&index_metadata, &splits).unwrap();
+    let error = metastore
+        .restore_index(restore_index_request.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::AlreadyExists(EntityKind::Index { .. })
+    ));
+
+    cleanup_index(&mut metastore, index_uid.clone()).await;
+
+    // Staged splits cannot be restored.
+    let mut staged_split = splits[0].clone();
+    staged_split.split_metadata.split_id = "test-split-4".to_string();
+    staged_split.split_state = SplitState::Staged;
+    let invalid_restore_index_request =
+        RestoreIndexRequest::try_from_index_metadata_and_splits(&index_metadata, &[staged_split])
+            .unwrap();
+    let error = metastore
+        .restore_index(invalid_restore_index_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+    assert!(!metastore.index_exists(&index_id).await.unwrap());
+
+    let restored_index_uid = metastore
+        .restore_index(restore_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+    assert_eq!(restored_index_uid.index_id, index_id);
+    assert_ne!(restored_index_uid, index_uid);
+
+    let restored_index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.clone()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(restored_index_metadata.index_uid, restored_index_uid);
+    assert_eq!(
+        restored_index_metadata.checkpoint,
+        index_metadata.checkpoint
+    );
+    assert_eq!(restored_index_metadata.sources, index_metadata.sources);
+
+    let mut restored_splits = metastore
+        .list_splits(ListSplitsRequest::try_from_index_uid(restored_index_uid.clone()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap();
+    restored_splits.sort_by(|left, right| left.split_id().cmp(right.split_id()));
+    assert_eq!(restored_splits.len(), 2);
+
+    for (restored_split, split) in restored_splits.iter().zip(&splits) {
+        assert_eq!(restored_split.split_id(), split.split_id());
+        assert_eq!(restored_split.split_state, split.split_state);
+        assert_eq!(restored_split.publish_timestamp, split.publish_timestamp);
+        assert_eq!(restored_split.split_metadata.index_uid, restored_index_uid);
+    }
+    cleanup_index(&mut metastore, restored_index_uid).await;
+}
+
 pub async fn test_metastore_update_doc_mapping<
     MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
 >() {
@@ -420,6 +420,33 @@ pub async fn test_metastore_list_splits<MetastoreToTest: MetastoreServiceExt + D
         let split_ids = collect_split_ids(&splits);
         assert_eq!(split_ids, &[&split_id_1, &split_id_2, &split_id_5]);
 
+        // Splits without a time range match any time range filter.
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_split_state(SplitState::Staged)
+            .with_time_range_end_lte(99);
+        let splits = metastore
+            .list_splits(ListSplitsRequest::try_from_list_splits_query(&query).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        let split_ids = collect_split_ids(&splits);
+        assert_eq!(split_ids, &[&split_id_1, &split_id_5]);
+
+        let query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_split_state(SplitState::Staged)
+            .with_time_range_end_lte(-1);
+        let splits = metastore
+            .list_splits(ListSplitsRequest::try_from_list_splits_query(&query).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        let split_ids = collect_split_ids(&splits);
+        assert_eq!(split_ids, &[&split_id_5]);
+
         let query = ListSplitsQuery::for_index(index_uid.clone())
             .with_split_state(SplitState::Staged)
             .with_time_range_start_gte(0)
@@ -224,6 +224,20 @@ macro_rules! metastore_test_suite {
                 $crate::tests::index::test_metastore_update_indexing_settings::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            #[serial_test::file_serial]
+            async fn test_metastore_toggle_ingest() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::index::test_metastore_toggle_ingest::<$metastore_type>().await;
+            }
+
+            #[tokio::test]
+            #[serial_test::file_serial]
+            async fn test_metastore_snapshot_and_restore_index() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::index::test_metastore_snapshot_and_restore_index::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             #[serial_test::file_serial]
             async fn test_metastore_create_index_enforces_index_id_maximum_length() {
@@ -389,6 +403,13 @@ macro_rules! metastore_test_suite {
                 $crate::tests::source::test_metastore_delete_source::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            #[serial_test::file_serial]
+            async fn test_metastore_discard_delta_of_previous_source_incarnation() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::source::test_metastore_discard_delta_of_previous_source_incarnation::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             #[serial_test::file_serial]
             async fn test_metastore_reset_checkpoint() {
@@ -756,6 +756,7 @@ pub async fn test_metastore_apply_checkpoint_delta_v2_single_shard<
     let index_checkpoint_delta = IndexCheckpointDelta {
         source_id: test_index.source_id.clone(),
         source_delta,
+        source_incarnation_opt: None,
     };
     let index_checkpoint_delta_json = serde_json::to_string(&index_checkpoint_delta).unwrap();
     let publish_splits_request = PublishSplitsRequest {
@@ -860,6 +861,7 @@ pub async fn test_metastore_apply_checkpoint_delta_v2_single_shard<
     let index_checkpoint_delta = IndexCheckpointDelta {
         source_id: test_index.source_id.clone(),
         source_delta,
+        source_incarnation_opt: None,
     };
     let index_checkpoint_delta_json = serde_json::to_string(&index_checkpoint_delta).unwrap();
     let publish_splits_request = PublishSplitsRequest {
@@ -971,6 +973,7 @@ pub async fn test_metastore_apply_checkpoint_delta_v2_multi_shards<
     let index_checkpoint_delta = IndexCheckpointDelta {
         source_id: test_index.source_id.clone(),
         source_delta,
+        source_incarnation_opt: None,
     };
     let index_checkpoint_delta_json = serde_json::to_string(&index_checkpoint_delta).unwrap();
     let publish_splits_request = PublishSplitsRequest {
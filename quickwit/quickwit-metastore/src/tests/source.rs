@@ -20,7 +20,7 @@
 use std::num::NonZeroUsize;
 
 use quickwit_common::rand::append_random_suffix;
-use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams};
+use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams, SourcePriority};
 use quickwit_proto::metastore::{
     AddSourceRequest, CreateIndexRequest, DeleteSourceRequest, EntityKind, IndexMetadataRequest,
     MetastoreError, PublishSplitsRequest, ResetSourceCheckpointRequest, SourceType,
@@ -29,7 +29,7 @@ use quickwit_proto::metastore::{
 use quickwit_proto::types::IndexUid;
 
 use super::DefaultForTest;
-use crate::checkpoint::SourceCheckpoint;
+use crate::checkpoint::{IndexCheckpointDelta, SourceCheckpoint};
 use crate::tests::cleanup_index;
 use crate::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadataResponseExt, MetastoreServiceExt,
@@ -57,6 +57,10 @@ pub async fn test_metastore_add_source<MetastoreToTest: MetastoreServiceExt + De
         source_id: source_id.to_string(),
         num_pipelines: NonZeroUsize::new(1).unwrap(),
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
@@ -156,6 +160,10 @@ pub async fn test_metastore_toggle_source<MetastoreToTest: MetastoreServiceExt +
         source_id: source_id.to_string(),
         num_pipelines: NonZeroUsize::new(1).unwrap(),
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
@@ -222,6 +230,10 @@ pub async fn test_metastore_delete_source<MetastoreToTest: MetastoreServiceExt +
         source_id: source_id.to_string(),
         num_pipelines: NonZeroUsize::new(1).unwrap(),
         enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
@@ -318,6 +330,123 @@ pub async fn test_metastore_delete_source<MetastoreToTest: MetastoreServiceExt +
     cleanup_index(&mut metastore, index_uid).await;
 }
 
+pub async fn test_metastore_discard_delta_of_previous_source_incarnation<
+    MetastoreToTest: MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-previous-source-incarnation");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+    let source_id = format!("{index_id}--source");
+
+    let source = SourceConfig {
+        source_id: source_id.clone(),
+        num_pipelines: NonZeroUsize::new(1).unwrap(),
+        enabled: true,
+        critical: false,
+        priority: SourcePriority::Normal,
+        placement_selector: None,
+        max_shards_per_pipeline: None,
+        source_params: SourceParams::void(),
+        transform_config: None,
+        input_format: SourceInputFormat::Json,
+    };
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid: IndexUid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+    metastore
+        .add_source(AddSourceRequest::try_from_source_config(index_uid.clone(), &source).unwrap())
+        .await
+        .unwrap();
+
+    let previous_incarnation = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap()
+        .checkpoint
+        .source_incarnation(&source_id)
+        .unwrap();
+
+    metastore
+        .delete_source(DeleteSourceRequest {
+            index_uid: index_uid.clone().into(),
+            source_id: source_id.clone(),
+        })
+        .await
+        .unwrap();
+    metastore
+        .add_source(AddSourceRequest::try_from_source_config(index_uid.clone(), &source).unwrap())
+        .await
+        .unwrap();
+
+    let current_incarnation = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap()
+        .checkpoint
+        .source_incarnation(&source_id)
+        .unwrap();
+    assert_ne!(current_incarnation, previous_incarnation);
+
+    for (split_idx, incarnation) in [previous_incarnation, current_incarnation]
+        .into_iter()
+        .enumerate()
+    {
+        let split_id = format!("{index_id}--split-{split_idx}");
+        let split_metadata = SplitMetadata {
+            split_id: split_id.clone(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        };
+        let stage_splits_request =
+            StageSplitsRequest::try_from_split_metadata(index_uid.clone(), &split_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+
+        let mut checkpoint_delta = IndexCheckpointDelta::for_test(&source_id, 0..10);
+        checkpoint_delta.source_incarnation_opt = Some(incarnation);
+
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid.clone()),
+            staged_split_ids: vec![split_id],
+            index_checkpoint_delta_json_opt: Some(
+                serde_json::to_string(&checkpoint_delta).unwrap(),
+            ),
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+
+        let source_checkpoint_opt = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap()
+            .checkpoint
+            .source_checkpoint(&source_id)
+            .cloned();
+
+        if incarnation == previous_incarnation {
+            assert_eq!(source_checkpoint_opt, Some(SourceCheckpoint::default()));
+        } else {
+            assert_eq!(source_checkpoint_opt.unwrap().num_partitions(), 1);
+        }
+    }
+    cleanup_index(&mut metastore, index_uid).await;
+}
+
 pub async fn test_metastore_reset_checkpoint<
     MetastoreToTest: MetastoreServiceExt + DefaultForTest,
 >() {
@@ -343,6 +472,10 @@ pub async fn test_metastore_reset_checkpoint<
             source_id: source_id.clone(),
             num_pipelines: NonZeroUsize::new(1).unwrap(),
             enabled: true,
+            critical: false,
+            priority: SourcePriority::Normal,
+            placement_selector: None,
+            max_shards_per_pipeline: None,
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
@@ -740,6 +740,7 @@ pub async fn test_metastore_publish_splits_concurrency<
                 let checkpoint_delta = IndexCheckpointDelta {
                     source_id,
                     source_delta,
+                    source_incarnation_opt: None,
                 };
                 let publish_splits_request = PublishSplitsRequest {
                     index_uid: Some(index_uid.clone()),
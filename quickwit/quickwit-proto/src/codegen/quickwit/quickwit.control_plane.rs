@@ -76,6 +76,18 @@ pub struct AdviseResetShardsResponse {
     pub shards_to_truncate: ::prost::alloc::vec::Vec<super::ingest::ShardIdPositions>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchIndexingPlanRequest {}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchIndexingPlanResponse {
+    /// JSON-serialized indexing plan event: either a snapshot of the plan or the changes of the plan.
+    #[prost(bytes = "bytes", tag = "1")]
+    pub event_json: ::prost::bytes::Bytes,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -84,6 +96,7 @@ pub enum GetOrCreateOpenShardsFailureReason {
     IndexNotFound = 1,
     SourceNotFound = 2,
     NoIngestersAvailable = 3,
+    IngestBlocked = 4,
 }
 impl GetOrCreateOpenShardsFailureReason {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -104,6 +117,9 @@ impl GetOrCreateOpenShardsFailureReason {
             GetOrCreateOpenShardsFailureReason::NoIngestersAvailable => {
                 "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_NO_INGESTERS_AVAILABLE"
             }
+            GetOrCreateOpenShardsFailureReason::IngestBlocked => {
+                "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_INGEST_BLOCKED"
+            }
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -121,6 +137,9 @@ impl GetOrCreateOpenShardsFailureReason {
             "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_NO_INGESTERS_AVAILABLE" => {
                 Some(Self::NoIngestersAvailable)
             }
+            "GET_OR_CREATE_OPEN_SHARDS_FAILURE_REASON_INGEST_BLOCKED" => {
+                Some(Self::IngestBlocked)
+            }
             _ => None,
         }
     }
@@ -129,6 +148,9 @@ impl GetOrCreateOpenShardsFailureReason {
 #[allow(unused_imports)]
 use std::str::FromStr;
 use tower::{Layer, Service, ServiceExt};
+pub type ControlPlaneServiceStream<T> = quickwit_common::ServiceStream<
+    crate::control_plane::ControlPlaneResult<T>,
+>;
 #[cfg_attr(any(test, feature = "testsuite"), mockall::automock)]
 #[async_trait::async_trait]
 pub trait ControlPlaneService: std::fmt::Debug + Send + Sync + 'static {
@@ -144,6 +166,20 @@ pub trait ControlPlaneService: std::fmt::Debug + Send + Sync + 'static {
     ) -> crate::control_plane::ControlPlaneResult<
         super::metastore::IndexMetadataResponse,
     >;
+    /// Blocks or unblocks ingest for an index.
+    async fn toggle_ingest(
+        &self,
+        request: super::metastore::ToggleIngestRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    >;
+    /// Recreates an index from a snapshot.
+    async fn restore_index(
+        &self,
+        request: super::metastore::RestoreIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::CreateIndexResponse,
+    >;
     /// Deletes an index.
     async fn delete_index(
         &self,
@@ -180,6 +216,14 @@ pub trait ControlPlaneService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: super::metastore::PruneShardsRequest,
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse>;
+    /// Streams a snapshot of the physical indexing plan, followed by the changes of the plan as they are applied.
+    /// A client falling too far behind gets a new snapshot instead of the changes it missed.
+    async fn watch_indexing_plan(
+        &self,
+        request: WatchIndexingPlanRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    >;
 }
 #[derive(Debug, Clone)]
 pub struct ControlPlaneServiceClient {
@@ -288,6 +332,22 @@ impl ControlPlaneService for ControlPlaneServiceClient {
     > {
         self.inner.0.update_index(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: super::metastore::ToggleIngestRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.inner.0.toggle_ingest(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: super::metastore::RestoreIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::CreateIndexResponse,
+    > {
+        self.inner.0.restore_index(request).await
+    }
     async fn delete_index(
         &self,
         request: super::metastore::DeleteIndexRequest,
@@ -330,6 +390,14 @@ impl ControlPlaneService for ControlPlaneServiceClient {
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.inner.0.prune_shards(request).await
     }
+    async fn watch_indexing_plan(
+        &self,
+        request: WatchIndexingPlanRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    > {
+        self.inner.0.watch_indexing_plan(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_control_plane_service {
@@ -356,6 +424,22 @@ pub mod mock_control_plane_service {
         > {
             self.inner.lock().await.update_index(request).await
         }
+        async fn toggle_ingest(
+            &self,
+            request: super::super::metastore::ToggleIngestRequest,
+        ) -> crate::control_plane::ControlPlaneResult<
+            super::super::metastore::IndexMetadataResponse,
+        > {
+            self.inner.lock().await.toggle_ingest(request).await
+        }
+        async fn restore_index(
+            &self,
+            request: super::super::metastore::RestoreIndexRequest,
+        ) -> crate::control_plane::ControlPlaneResult<
+            super::super::metastore::CreateIndexResponse,
+        > {
+            self.inner.lock().await.restore_index(request).await
+        }
         async fn delete_index(
             &self,
             request: super::super::metastore::DeleteIndexRequest,
@@ -410,6 +494,14 @@ pub mod mock_control_plane_service {
         > {
             self.inner.lock().await.prune_shards(request).await
         }
+        async fn watch_indexing_plan(
+            &self,
+            request: super::WatchIndexingPlanRequest,
+        ) -> crate::control_plane::ControlPlaneResult<
+            ControlPlaneServiceStream<super::WatchIndexingPlanResponse>,
+        > {
+            self.inner.lock().await.watch_indexing_plan(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -449,6 +541,40 @@ for InnerControlPlaneServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<super::metastore::ToggleIngestRequest>
+for InnerControlPlaneServiceClient {
+    type Response = super::metastore::IndexMetadataResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: super::metastore::ToggleIngestRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.toggle_ingest(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<super::metastore::RestoreIndexRequest>
+for InnerControlPlaneServiceClient {
+    type Response = super::metastore::CreateIndexResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: super::metastore::RestoreIndexRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.restore_index(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<super::metastore::DeleteIndexRequest>
 for InnerControlPlaneServiceClient {
     type Response = super::metastore::EmptyResponse;
@@ -566,6 +692,22 @@ for InnerControlPlaneServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<WatchIndexingPlanRequest> for InnerControlPlaneServiceClient {
+    type Response = ControlPlaneServiceStream<WatchIndexingPlanResponse>;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: WatchIndexingPlanRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.watch_indexing_plan(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct ControlPlaneServiceTowerServiceStack {
@@ -581,6 +723,16 @@ struct ControlPlaneServiceTowerServiceStack {
         super::metastore::IndexMetadataResponse,
         crate::control_plane::ControlPlaneError,
     >,
+    toggle_ingest_svc: quickwit_common::tower::BoxService<
+        super::metastore::ToggleIngestRequest,
+        super::metastore::IndexMetadataResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    restore_index_svc: quickwit_common::tower::BoxService<
+        super::metastore::RestoreIndexRequest,
+        super::metastore::CreateIndexResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
     delete_index_svc: quickwit_common::tower::BoxService<
         super::metastore::DeleteIndexRequest,
         super::metastore::EmptyResponse,
@@ -616,6 +768,11 @@ struct ControlPlaneServiceTowerServiceStack {
         super::metastore::EmptyResponse,
         crate::control_plane::ControlPlaneError,
     >,
+    watch_indexing_plan_svc: quickwit_common::tower::BoxService<
+        WatchIndexingPlanRequest,
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+        crate::control_plane::ControlPlaneError,
+    >,
 }
 #[async_trait::async_trait]
 impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
@@ -635,6 +792,22 @@ impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
     > {
         self.update_index_svc.clone().ready().await?.call(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: super::metastore::ToggleIngestRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.toggle_ingest_svc.clone().ready().await?.call(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: super::metastore::RestoreIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::CreateIndexResponse,
+    > {
+        self.restore_index_svc.clone().ready().await?.call(request).await
+    }
     async fn delete_index(
         &self,
         request: super::metastore::DeleteIndexRequest,
@@ -677,6 +850,14 @@ impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.prune_shards_svc.clone().ready().await?.call(request).await
     }
+    async fn watch_indexing_plan(
+        &self,
+        request: WatchIndexingPlanRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    > {
+        self.watch_indexing_plan_svc.clone().ready().await?.call(request).await
+    }
 }
 type CreateIndexLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -698,6 +879,26 @@ type UpdateIndexLayer = quickwit_common::tower::BoxLayer<
     super::metastore::IndexMetadataResponse,
     crate::control_plane::ControlPlaneError,
 >;
+type ToggleIngestLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        super::metastore::ToggleIngestRequest,
+        super::metastore::IndexMetadataResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    super::metastore::ToggleIngestRequest,
+    super::metastore::IndexMetadataResponse,
+    crate::control_plane::ControlPlaneError,
+>;
+type RestoreIndexLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        super::metastore::RestoreIndexRequest,
+        super::metastore::CreateIndexResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    super::metastore::RestoreIndexRequest,
+    super::metastore::CreateIndexResponse,
+    crate::control_plane::ControlPlaneError,
+>;
 type DeleteIndexLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         super::metastore::DeleteIndexRequest,
@@ -768,10 +969,22 @@ type PruneShardsLayer = quickwit_common::tower::BoxLayer<
     super::metastore::EmptyResponse,
     crate::control_plane::ControlPlaneError,
 >;
+type WatchIndexingPlanLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        WatchIndexingPlanRequest,
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+        crate::control_plane::ControlPlaneError,
+    >,
+    WatchIndexingPlanRequest,
+    ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    crate::control_plane::ControlPlaneError,
+>;
 #[derive(Debug, Default)]
 pub struct ControlPlaneServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
     update_index_layers: Vec<UpdateIndexLayer>,
+    toggle_ingest_layers: Vec<ToggleIngestLayer>,
+    restore_index_layers: Vec<RestoreIndexLayer>,
     delete_index_layers: Vec<DeleteIndexLayer>,
     add_source_layers: Vec<AddSourceLayer>,
     toggle_source_layers: Vec<ToggleSourceLayer>,
@@ -779,6 +992,7 @@ pub struct ControlPlaneServiceTowerLayerStack {
     get_or_create_open_shards_layers: Vec<GetOrCreateOpenShardsLayer>,
     advise_reset_shards_layers: Vec<AdviseResetShardsLayer>,
     prune_shards_layers: Vec<PruneShardsLayer>,
+    watch_indexing_plan_layers: Vec<WatchIndexingPlanLayer>,
 }
 impl ControlPlaneServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -837,6 +1051,60 @@ impl ControlPlaneServiceTowerLayerStack {
         >>::Service as tower::Service<
             super::metastore::UpdateIndexRequest,
         >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::ToggleIngestRequest,
+                    super::metastore::IndexMetadataResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                super::metastore::ToggleIngestRequest,
+                super::metastore::IndexMetadataResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                super::metastore::ToggleIngestRequest,
+                Response = super::metastore::IndexMetadataResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                super::metastore::ToggleIngestRequest,
+                super::metastore::IndexMetadataResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<
+            super::metastore::ToggleIngestRequest,
+        >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::RestoreIndexRequest,
+                    super::metastore::CreateIndexResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                super::metastore::RestoreIndexRequest,
+                super::metastore::CreateIndexResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                super::metastore::RestoreIndexRequest,
+                Response = super::metastore::CreateIndexResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                super::metastore::RestoreIndexRequest,
+                super::metastore::CreateIndexResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<
+            super::metastore::RestoreIndexRequest,
+        >>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     super::metastore::DeleteIndexRequest,
@@ -1024,11 +1292,40 @@ impl ControlPlaneServiceTowerLayerStack {
         >>::Service as tower::Service<
             super::metastore::PruneShardsRequest,
         >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    WatchIndexingPlanRequest,
+                    ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                WatchIndexingPlanRequest,
+                ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                WatchIndexingPlanRequest,
+                Response = ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                WatchIndexingPlanRequest,
+                ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<WatchIndexingPlanRequest>>::Future: Send + 'static,
     {
         self.create_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.update_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.toggle_ingest_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.restore_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.delete_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.add_source_layers
@@ -1043,6 +1340,8 @@ impl ControlPlaneServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.prune_shards_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.watch_indexing_plan_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_create_index_layer<L>(mut self, layer: L) -> Self
@@ -1087,6 +1386,48 @@ impl ControlPlaneServiceTowerLayerStack {
         self.update_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_toggle_ingest_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::ToggleIngestRequest,
+                    super::metastore::IndexMetadataResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                super::metastore::ToggleIngestRequest,
+                Response = super::metastore::IndexMetadataResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            super::metastore::ToggleIngestRequest,
+        >>::Future: Send + 'static,
+    {
+        self.toggle_ingest_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_restore_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    super::metastore::RestoreIndexRequest,
+                    super::metastore::CreateIndexResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                super::metastore::RestoreIndexRequest,
+                Response = super::metastore::CreateIndexResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            super::metastore::RestoreIndexRequest,
+        >>::Future: Send + 'static,
+    {
+        self.restore_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_delete_index_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -1234,6 +1575,26 @@ impl ControlPlaneServiceTowerLayerStack {
         self.prune_shards_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_watch_indexing_plan_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    WatchIndexingPlanRequest,
+                    ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                WatchIndexingPlanRequest,
+                Response = ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<WatchIndexingPlanRequest>>::Future: Send + 'static,
+    {
+        self.watch_indexing_plan_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> ControlPlaneServiceClient
     where
         T: ControlPlaneService,
@@ -1309,6 +1670,22 @@ impl ControlPlaneServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let toggle_ingest_svc = self
+            .toggle_ingest_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let restore_index_svc = self
+            .restore_index_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let delete_index_svc = self
             .delete_index_layers
             .into_iter()
@@ -1365,10 +1742,20 @@ impl ControlPlaneServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let watch_indexing_plan_svc = self
+            .watch_indexing_plan_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = ControlPlaneServiceTowerServiceStack {
             inner: inner_client,
             create_index_svc,
             update_index_svc,
+            toggle_ingest_svc,
+            restore_index_svc,
             delete_index_svc,
             add_source_svc,
             toggle_source_svc,
@@ -1376,6 +1763,7 @@ impl ControlPlaneServiceTowerLayerStack {
             get_or_create_open_shards_svc,
             advise_reset_shards_svc,
             prune_shards_svc,
+            watch_indexing_plan_svc,
         };
         ControlPlaneServiceClient::new(tower_svc_stack)
     }
@@ -1470,6 +1858,24 @@ where
                 crate::control_plane::ControlPlaneError,
             >,
         >
+        + tower::Service<
+            super::metastore::ToggleIngestRequest,
+            Response = super::metastore::IndexMetadataResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                super::metastore::IndexMetadataResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
+        + tower::Service<
+            super::metastore::RestoreIndexRequest,
+            Response = super::metastore::CreateIndexResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                super::metastore::CreateIndexResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >
         + tower::Service<
             super::metastore::DeleteIndexRequest,
             Response = super::metastore::EmptyResponse,
@@ -1532,6 +1938,15 @@ where
                 super::metastore::EmptyResponse,
                 crate::control_plane::ControlPlaneError,
             >,
+        >
+        + tower::Service<
+            WatchIndexingPlanRequest,
+            Response = ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+                crate::control_plane::ControlPlaneError,
+            >,
         >,
 {
     async fn create_index(
@@ -1550,6 +1965,22 @@ where
     > {
         self.clone().call(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: super::metastore::ToggleIngestRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.clone().call(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: super::metastore::RestoreIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::CreateIndexResponse,
+    > {
+        self.clone().call(request).await
+    }
     async fn delete_index(
         &self,
         request: super::metastore::DeleteIndexRequest,
@@ -1592,6 +2023,14 @@ where
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.clone().call(request).await
     }
+    async fn watch_indexing_plan(
+        &self,
+        request: WatchIndexingPlanRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    > {
+        self.clone().call(request).await
+    }
 }
 #[derive(Debug, Clone)]
 pub struct ControlPlaneServiceGrpcClientAdapter<T> {
@@ -1659,6 +2098,38 @@ where
                 super::metastore::UpdateIndexRequest::rpc_name(),
             ))
     }
+    async fn toggle_ingest(
+        &self,
+        request: super::metastore::ToggleIngestRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::IndexMetadataResponse,
+    > {
+        self.inner
+            .clone()
+            .toggle_ingest(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                super::metastore::ToggleIngestRequest::rpc_name(),
+            ))
+    }
+    async fn restore_index(
+        &self,
+        request: super::metastore::RestoreIndexRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        super::metastore::CreateIndexResponse,
+    > {
+        self.inner
+            .clone()
+            .restore_index(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                super::metastore::RestoreIndexRequest::rpc_name(),
+            ))
+    }
     async fn delete_index(
         &self,
         request: super::metastore::DeleteIndexRequest,
@@ -1757,6 +2228,30 @@ where
                 super::metastore::PruneShardsRequest::rpc_name(),
             ))
     }
+    async fn watch_indexing_plan(
+        &self,
+        request: WatchIndexingPlanRequest,
+    ) -> crate::control_plane::ControlPlaneResult<
+        ControlPlaneServiceStream<WatchIndexingPlanResponse>,
+    > {
+        self.inner
+            .clone()
+            .watch_indexing_plan(request)
+            .await
+            .map(|response| {
+                let streaming: tonic::Streaming<_> = response.into_inner();
+                let stream = quickwit_common::ServiceStream::from(streaming);
+                stream
+                    .map_err(|status| crate::error::grpc_status_to_service_error(
+                        status,
+                        WatchIndexingPlanRequest::rpc_name(),
+                    ))
+            })
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                WatchIndexingPlanRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct ControlPlaneServiceGrpcServerAdapter {
@@ -1800,6 +2295,34 @@ for ControlPlaneServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn toggle_ingest(
+        &self,
+        request: tonic::Request<super::metastore::ToggleIngestRequest>,
+    ) -> Result<
+        tonic::Response<super::metastore::IndexMetadataResponse>,
+        tonic::Status,
+    > {
+        self.inner
+            .0
+            .toggle_ingest(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn restore_index(
+        &self,
+        request: tonic::Request<super::metastore::RestoreIndexRequest>,
+    ) -> Result<
+        tonic::Response<super::metastore::CreateIndexResponse>,
+        tonic::Status,
+    > {
+        self.inner
+            .0
+            .restore_index(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
     async fn delete_index(
         &self,
         request: tonic::Request<super::metastore::DeleteIndexRequest>,
@@ -1877,6 +2400,22 @@ for ControlPlaneServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    type WatchIndexingPlanStream = quickwit_common::ServiceStream<
+        tonic::Result<WatchIndexingPlanResponse>,
+    >;
+    async fn watch_indexing_plan(
+        &self,
+        request: tonic::Request<WatchIndexingPlanRequest>,
+    ) -> Result<tonic::Response<Self::WatchIndexingPlanStream>, tonic::Status> {
+        self.inner
+            .0
+            .watch_indexing_plan(request.into_inner())
+            .await
+            .map(|stream| tonic::Response::new(
+                stream.map_err(crate::error::grpc_error_to_grpc_status),
+            ))
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
 }
 /// Generated client implementations.
 pub mod control_plane_service_grpc_client {
@@ -2027,6 +2566,68 @@ pub mod control_plane_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Blocks or unblocks ingest for an index.
+        pub async fn toggle_ingest(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::metastore::ToggleIngestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::IndexMetadataResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/ToggleIngest",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "ToggleIngest",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Recreates an index from a snapshot.
+        pub async fn restore_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::super::metastore::RestoreIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::CreateIndexResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/RestoreIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "RestoreIndex",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Deletes an index.
         pub async fn delete_index(
             &mut self,
@@ -2249,6 +2850,38 @@ pub mod control_plane_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Streams a snapshot of the physical indexing plan, followed by the changes of the plan as they are applied.
+        /// A client falling too far behind gets a new snapshot instead of the changes it missed.
+        pub async fn watch_indexing_plan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchIndexingPlanRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::WatchIndexingPlanResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/WatchIndexingPlan",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "WatchIndexingPlan",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -2274,6 +2907,22 @@ pub mod control_plane_service_grpc_server {
             tonic::Response<super::super::metastore::IndexMetadataResponse>,
             tonic::Status,
         >;
+        /// Blocks or unblocks ingest for an index.
+        async fn toggle_ingest(
+            &self,
+            request: tonic::Request<super::super::metastore::ToggleIngestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::IndexMetadataResponse>,
+            tonic::Status,
+        >;
+        /// Recreates an index from a snapshot.
+        async fn restore_index(
+            &self,
+            request: tonic::Request<super::super::metastore::RestoreIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::super::metastore::CreateIndexResponse>,
+            tonic::Status,
+        >;
         /// Deletes an index.
         async fn delete_index(
             &self,
@@ -2331,6 +2980,24 @@ pub mod control_plane_service_grpc_server {
             tonic::Response<super::super::metastore::EmptyResponse>,
             tonic::Status,
         >;
+        /// Server streaming response type for the WatchIndexingPlan method.
+        type WatchIndexingPlanStream: futures_core::Stream<
+                Item = std::result::Result<
+                    super::WatchIndexingPlanResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        /// Streams a snapshot of the physical indexing plan, followed by the changes of the plan as they are applied.
+        /// A client falling too far behind gets a new snapshot instead of the changes it missed.
+        async fn watch_indexing_plan(
+            &self,
+            request: tonic::Request<super::WatchIndexingPlanRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::WatchIndexingPlanStream>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct ControlPlaneServiceGrpcServer<T: ControlPlaneServiceGrpc> {
@@ -2510,6 +3177,104 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.control_plane.ControlPlaneService/ToggleIngest" => {
+                    #[allow(non_camel_case_types)]
+                    struct ToggleIngestSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::ToggleIngestRequest,
+                    > for ToggleIngestSvc<T> {
+                        type Response = super::super::metastore::IndexMetadataResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::ToggleIngestRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).toggle_ingest(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ToggleIngestSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.control_plane.ControlPlaneService/RestoreIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::super::metastore::RestoreIndexRequest,
+                    > for RestoreIndexSvc<T> {
+                        type Response = super::super::metastore::CreateIndexResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::super::metastore::RestoreIndexRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).restore_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.control_plane.ControlPlaneService/DeleteIndex" => {
                     #[allow(non_camel_case_types)]
                     struct DeleteIndexSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
@@ -2847,6 +3612,54 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.control_plane.ControlPlaneService/WatchIndexingPlan" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchIndexingPlanSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::ServerStreamingService<
+                        super::WatchIndexingPlanRequest,
+                    > for WatchIndexingPlanSvc<T> {
+                        type Response = super::WatchIndexingPlanResponse;
+                        type ResponseStream = T::WatchIndexingPlanStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchIndexingPlanRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).watch_indexing_plan(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchIndexingPlanSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -4,6 +4,34 @@
 pub struct ApplyIndexingPlanRequest {
     #[prost(message, repeated, tag = "1")]
     pub indexing_tasks: ::prost::alloc::vec::Vec<IndexingTask>,
+    /// The node ID of the control plane sending the plan. Indexers rate limit plan updates per
+    /// control plane node.
+    #[prost(string, tag = "2")]
+    pub control_plane_node_id: ::prost::alloc::string::String,
+    /// Generation of the plan. The control plane increments it every time it applies a plan.
+    #[prost(uint64, tag = "3")]
+    pub plan_generation: u64,
+    /// When set, the shard lists of the tasks listed in `shard_deltas` are encoded as deltas against
+    /// the tasks of the plan of this generation, last applied by the indexer. The control plane only
+    /// sends delta-encoded plans to the indexers advertising support for them.
+    #[prost(uint64, optional, tag = "4")]
+    pub base_plan_generation: ::core::option::Option<u64>,
+    /// The shard list deltas of the delta-encoded tasks, whose `shard_ids` are left empty.
+    #[prost(message, repeated, tag = "5")]
+    pub shard_deltas: ::prost::alloc::vec::Vec<IndexingTaskShardDelta>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IndexingTaskShardDelta {
+    #[prost(message, optional, tag = "1")]
+    pub pipeline_uid: ::core::option::Option<crate::types::PipelineUid>,
+    /// The shards assigned to the task since the base plan.
+    #[prost(message, repeated, tag = "2")]
+    pub added_shard_ids: ::prost::alloc::vec::Vec<crate::types::ShardId>,
+    /// The shards unassigned from the task since the base plan.
+    #[prost(message, repeated, tag = "3")]
+    pub removed_shard_ids: ::prost::alloc::vec::Vec<crate::types::ShardId>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -25,11 +53,131 @@ pub struct IndexingTask {
     /// as updating indexing settings or doc mapping) should influence this value.
     #[prost(uint64, tag = "6")]
     pub params_fingerprint: u64,
+    /// Whether the pipeline of the task is a warm standby: its actors are spawned but it does not
+    /// consume its source until it is promoted.
+    #[prost(bool, tag = "7")]
+    pub standby: bool,
+    /// The priority of the task. Indexers grant indexing resources to higher priority pipelines
+    /// first when they are contended.
+    #[prost(enumeration = "IndexingTaskPriority", tag = "8")]
+    pub priority: i32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ApplyIndexingPlanResponse {
+    /// The outcome of each task of the plan. Empty when returned by nodes predating this field.
+    #[prost(message, repeated, tag = "1")]
+    pub task_outcomes: ::prost::alloc::vec::Vec<IndexingTaskOutcome>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IndexingTaskOutcome {
+    #[prost(message, optional, tag = "1")]
+    pub pipeline_uid: ::core::option::Option<crate::types::PipelineUid>,
+    #[prost(enumeration = "IndexingTaskStatus", tag = "2")]
+    pub status: i32,
+    /// Why the task could not be applied, set for failed tasks only.
+    #[prost(string, optional, tag = "3")]
+    pub error_message: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PromoteIndexingPipelineRequest {
+    #[prost(message, optional, tag = "1")]
+    pub pipeline_uid: ::core::option::Option<crate::types::PipelineUid>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ApplyIndexingPlanResponse {}
+pub struct PromoteIndexingPipelineResponse {
+    /// Whether the pipeline was promoted from its warm state. False when the pipeline was not a
+    /// standby anymore or had to be respawned from scratch.
+    #[prost(bool, tag = "1")]
+    pub warm: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum IndexingTaskStatus {
+    Unspecified = 0,
+    /// The pipeline of the task was spawned.
+    Started = 1,
+    /// The pipeline of the task was already running with the same shards.
+    AlreadyRunning = 2,
+    /// The pipeline of the task was already running and its shards were updated.
+    ShardUpdateApplied = 3,
+    /// The task could not be applied.
+    Failed = 4,
+    /// The pipeline of the task was a warm standby and was promoted.
+    Promoted = 5,
+}
+impl IndexingTaskStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            IndexingTaskStatus::Unspecified => "INDEXING_TASK_STATUS_UNSPECIFIED",
+            IndexingTaskStatus::Started => "INDEXING_TASK_STATUS_STARTED",
+            IndexingTaskStatus::AlreadyRunning => "INDEXING_TASK_STATUS_ALREADY_RUNNING",
+            IndexingTaskStatus::ShardUpdateApplied => {
+                "INDEXING_TASK_STATUS_SHARD_UPDATE_APPLIED"
+            }
+            IndexingTaskStatus::Failed => "INDEXING_TASK_STATUS_FAILED",
+            IndexingTaskStatus::Promoted => "INDEXING_TASK_STATUS_PROMOTED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INDEXING_TASK_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "INDEXING_TASK_STATUS_STARTED" => Some(Self::Started),
+            "INDEXING_TASK_STATUS_ALREADY_RUNNING" => Some(Self::AlreadyRunning),
+            "INDEXING_TASK_STATUS_SHARD_UPDATE_APPLIED" => Some(Self::ShardUpdateApplied),
+            "INDEXING_TASK_STATUS_FAILED" => Some(Self::Failed),
+            "INDEXING_TASK_STATUS_PROMOTED" => Some(Self::Promoted),
+            _ => None,
+        }
+    }
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum IndexingTaskPriority {
+    Normal = 0,
+    /// Tasks indexing fresh data that should keep up with their source at the expense of others.
+    High = 1,
+    /// Tasks such as backfills that are throttled when other tasks need the resources.
+    Background = 2,
+}
+impl IndexingTaskPriority {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            IndexingTaskPriority::Normal => "INDEXING_TASK_PRIORITY_NORMAL",
+            IndexingTaskPriority::High => "INDEXING_TASK_PRIORITY_HIGH",
+            IndexingTaskPriority::Background => "INDEXING_TASK_PRIORITY_BACKGROUND",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INDEXING_TASK_PRIORITY_NORMAL" => Some(Self::Normal),
+            "INDEXING_TASK_PRIORITY_HIGH" => Some(Self::High),
+            "INDEXING_TASK_PRIORITY_BACKGROUND" => Some(Self::Background),
+            _ => None,
+        }
+    }
+}
 /// BEGIN quickwit-codegen
 #[allow(unused_imports)]
 use std::str::FromStr;
@@ -42,6 +190,12 @@ pub trait IndexingService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: ApplyIndexingPlanRequest,
     ) -> crate::indexing::IndexingResult<ApplyIndexingPlanResponse>;
+    /// Promotes a warm standby pipeline: the pipeline starts consuming its source from the latest
+    /// published position.
+    async fn promote_indexing_pipeline(
+        &self,
+        request: PromoteIndexingPipelineRequest,
+    ) -> crate::indexing::IndexingResult<PromoteIndexingPipelineResponse>;
 }
 #[derive(Debug, Clone)]
 pub struct IndexingServiceClient {
@@ -140,6 +294,12 @@ impl IndexingService for IndexingServiceClient {
     ) -> crate::indexing::IndexingResult<ApplyIndexingPlanResponse> {
         self.inner.0.apply_indexing_plan(request).await
     }
+    async fn promote_indexing_pipeline(
+        &self,
+        request: PromoteIndexingPipelineRequest,
+    ) -> crate::indexing::IndexingResult<PromoteIndexingPipelineResponse> {
+        self.inner.0.promote_indexing_pipeline(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_indexing_service {
@@ -156,6 +316,12 @@ pub mod mock_indexing_service {
         ) -> crate::indexing::IndexingResult<super::ApplyIndexingPlanResponse> {
             self.inner.lock().await.apply_indexing_plan(request).await
         }
+        async fn promote_indexing_pipeline(
+            &self,
+            request: super::PromoteIndexingPipelineRequest,
+        ) -> crate::indexing::IndexingResult<super::PromoteIndexingPipelineResponse> {
+            self.inner.lock().await.promote_indexing_pipeline(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -177,6 +343,22 @@ impl tower::Service<ApplyIndexingPlanRequest> for InnerIndexingServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<PromoteIndexingPipelineRequest> for InnerIndexingServiceClient {
+    type Response = PromoteIndexingPipelineResponse;
+    type Error = crate::indexing::IndexingError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: PromoteIndexingPipelineRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.promote_indexing_pipeline(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct IndexingServiceTowerServiceStack {
@@ -187,6 +369,11 @@ struct IndexingServiceTowerServiceStack {
         ApplyIndexingPlanResponse,
         crate::indexing::IndexingError,
     >,
+    promote_indexing_pipeline_svc: quickwit_common::tower::BoxService<
+        PromoteIndexingPipelineRequest,
+        PromoteIndexingPipelineResponse,
+        crate::indexing::IndexingError,
+    >,
 }
 #[async_trait::async_trait]
 impl IndexingService for IndexingServiceTowerServiceStack {
@@ -196,6 +383,12 @@ impl IndexingService for IndexingServiceTowerServiceStack {
     ) -> crate::indexing::IndexingResult<ApplyIndexingPlanResponse> {
         self.apply_indexing_plan_svc.clone().ready().await?.call(request).await
     }
+    async fn promote_indexing_pipeline(
+        &self,
+        request: PromoteIndexingPipelineRequest,
+    ) -> crate::indexing::IndexingResult<PromoteIndexingPipelineResponse> {
+        self.promote_indexing_pipeline_svc.clone().ready().await?.call(request).await
+    }
 }
 type ApplyIndexingPlanLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -207,9 +400,20 @@ type ApplyIndexingPlanLayer = quickwit_common::tower::BoxLayer<
     ApplyIndexingPlanResponse,
     crate::indexing::IndexingError,
 >;
+type PromoteIndexingPipelineLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        PromoteIndexingPipelineRequest,
+        PromoteIndexingPipelineResponse,
+        crate::indexing::IndexingError,
+    >,
+    PromoteIndexingPipelineRequest,
+    PromoteIndexingPipelineResponse,
+    crate::indexing::IndexingError,
+>;
 #[derive(Debug, Default)]
 pub struct IndexingServiceTowerLayerStack {
     apply_indexing_plan_layers: Vec<ApplyIndexingPlanLayer>,
+    promote_indexing_pipeline_layers: Vec<PromoteIndexingPipelineLayer>,
 }
 impl IndexingServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -239,9 +443,38 @@ impl IndexingServiceTowerLayerStack {
                 crate::indexing::IndexingError,
             >,
         >>::Service as tower::Service<ApplyIndexingPlanRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PromoteIndexingPipelineRequest,
+                    PromoteIndexingPipelineResponse,
+                    crate::indexing::IndexingError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PromoteIndexingPipelineRequest,
+                PromoteIndexingPipelineResponse,
+                crate::indexing::IndexingError,
+            >,
+        >>::Service: tower::Service<
+                PromoteIndexingPipelineRequest,
+                Response = PromoteIndexingPipelineResponse,
+                Error = crate::indexing::IndexingError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                PromoteIndexingPipelineRequest,
+                PromoteIndexingPipelineResponse,
+                crate::indexing::IndexingError,
+            >,
+        >>::Service as tower::Service<
+            PromoteIndexingPipelineRequest,
+        >>::Future: Send + 'static,
     {
         self.apply_indexing_plan_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.promote_indexing_pipeline_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_apply_indexing_plan_layer<L>(mut self, layer: L) -> Self
@@ -264,6 +497,28 @@ impl IndexingServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_promote_indexing_pipeline_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    PromoteIndexingPipelineRequest,
+                    PromoteIndexingPipelineResponse,
+                    crate::indexing::IndexingError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                PromoteIndexingPipelineRequest,
+                Response = PromoteIndexingPipelineResponse,
+                Error = crate::indexing::IndexingError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            PromoteIndexingPipelineRequest,
+        >>::Future: Send + 'static,
+    {
+        self.promote_indexing_pipeline_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> IndexingServiceClient
     where
         T: IndexingService,
@@ -328,9 +583,18 @@ impl IndexingServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let promote_indexing_pipeline_svc = self
+            .promote_indexing_pipeline_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = IndexingServiceTowerServiceStack {
             inner: inner_client,
             apply_indexing_plan_svc,
+            promote_indexing_pipeline_svc,
         };
         IndexingServiceClient::new(tower_svc_stack)
     }
@@ -412,7 +676,16 @@ where
         Response = ApplyIndexingPlanResponse,
         Error = crate::indexing::IndexingError,
         Future = BoxFuture<ApplyIndexingPlanResponse, crate::indexing::IndexingError>,
-    >,
+    >
+        + tower::Service<
+            PromoteIndexingPipelineRequest,
+            Response = PromoteIndexingPipelineResponse,
+            Error = crate::indexing::IndexingError,
+            Future = BoxFuture<
+                PromoteIndexingPipelineResponse,
+                crate::indexing::IndexingError,
+            >,
+        >,
 {
     async fn apply_indexing_plan(
         &self,
@@ -420,6 +693,12 @@ where
     ) -> crate::indexing::IndexingResult<ApplyIndexingPlanResponse> {
         self.clone().call(request).await
     }
+    async fn promote_indexing_pipeline(
+        &self,
+        request: PromoteIndexingPipelineRequest,
+    ) -> crate::indexing::IndexingResult<PromoteIndexingPipelineResponse> {
+        self.clone().call(request).await
+    }
 }
 #[derive(Debug, Clone)]
 pub struct IndexingServiceGrpcClientAdapter<T> {
@@ -469,6 +748,20 @@ where
                 ApplyIndexingPlanRequest::rpc_name(),
             ))
     }
+    async fn promote_indexing_pipeline(
+        &self,
+        request: PromoteIndexingPipelineRequest,
+    ) -> crate::indexing::IndexingResult<PromoteIndexingPipelineResponse> {
+        self.inner
+            .clone()
+            .promote_indexing_pipeline(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                PromoteIndexingPipelineRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct IndexingServiceGrpcServerAdapter {
@@ -498,6 +791,17 @@ for IndexingServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn promote_indexing_pipeline(
+        &self,
+        request: tonic::Request<PromoteIndexingPipelineRequest>,
+    ) -> Result<tonic::Response<PromoteIndexingPipelineResponse>, tonic::Status> {
+        self.inner
+            .0
+            .promote_indexing_pipeline(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
 }
 /// Generated client implementations.
 pub mod indexing_service_grpc_client {
@@ -615,6 +919,38 @@ pub mod indexing_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Promotes a warm standby pipeline: the pipeline starts consuming its source from the latest
+        /// published position.
+        pub async fn promote_indexing_pipeline(
+            &mut self,
+            request: impl tonic::IntoRequest<super::PromoteIndexingPipelineRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PromoteIndexingPipelineResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.indexing.IndexingService/PromoteIndexingPipeline",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.indexing.IndexingService",
+                        "PromoteIndexingPipeline",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -632,6 +968,15 @@ pub mod indexing_service_grpc_server {
             tonic::Response<super::ApplyIndexingPlanResponse>,
             tonic::Status,
         >;
+        /// Promotes a warm standby pipeline: the pipeline starts consuming its source from the latest
+        /// published position.
+        async fn promote_indexing_pipeline(
+            &self,
+            request: tonic::Request<super::PromoteIndexingPipelineRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PromoteIndexingPipelineResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct IndexingServiceGrpcServer<T: IndexingServiceGrpc> {
@@ -758,6 +1103,52 @@ pub mod indexing_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.indexing.IndexingService/PromoteIndexingPipeline" => {
+                    #[allow(non_camel_case_types)]
+                    struct PromoteIndexingPipelineSvc<T: IndexingServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: IndexingServiceGrpc,
+                    > tonic::server::UnaryService<super::PromoteIndexingPipelineRequest>
+                    for PromoteIndexingPipelineSvc<T> {
+                        type Response = super::PromoteIndexingPipelineResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PromoteIndexingPipelineRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).promote_indexing_pipeline(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PromoteIndexingPipelineSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
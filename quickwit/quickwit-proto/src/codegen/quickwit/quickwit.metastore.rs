@@ -38,6 +38,43 @@ pub struct UpdateIndexRequest {
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ToggleIngestRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(bool, tag = "2")]
+    pub ingest_blocked: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotIndexRequest {
+    #[prost(string, tag = "1")]
+    pub index_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotIndexResponse {
+    #[prost(string, tag = "1")]
+    pub index_metadata_serialized_json: ::prost::alloc::string::String,
+    /// Published splits of the index, consistent with the source checkpoints of the index metadata.
+    #[prost(string, tag = "2")]
+    pub splits_serialized_json: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RestoreIndexRequest {
+    /// Index config, source configs, and source checkpoints of the index to recreate.
+    #[prost(string, tag = "1")]
+    pub index_metadata_serialized_json: ::prost::alloc::string::String,
+    /// Splits of the index, either published or marked for deletion.
+    #[prost(string, tag = "2")]
+    pub splits_serialized_json: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListIndexesMetadataRequest {
     /// List of patterns an index should match or not match to get considered
     /// An index must match at least one positive pattern (a pattern not starting
@@ -645,6 +682,21 @@ impl RpcName for UpdateIndexRequest {
         "update_index"
     }
 }
+impl RpcName for ToggleIngestRequest {
+    fn rpc_name() -> &'static str {
+        "toggle_ingest"
+    }
+}
+impl RpcName for SnapshotIndexRequest {
+    fn rpc_name() -> &'static str {
+        "snapshot_index"
+    }
+}
+impl RpcName for RestoreIndexRequest {
+    fn rpc_name() -> &'static str {
+        "restore_index"
+    }
+}
 impl RpcName for IndexMetadataRequest {
     fn rpc_name() -> &'static str {
         "index_metadata"
@@ -804,6 +856,21 @@ pub trait MetastoreService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: UpdateIndexRequest,
     ) -> crate::metastore::MetastoreResult<IndexMetadataResponse>;
+    /// Blocks or unblocks ingest for an index.
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse>;
+    /// Returns the metadata of an index along with its published splits, read consistently.
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> crate::metastore::MetastoreResult<SnapshotIndexResponse>;
+    /// Recreates an index along with its sources, source checkpoints, and splits in a single transaction.
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> crate::metastore::MetastoreResult<CreateIndexResponse>;
     /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
     async fn index_metadata(
         &self,
@@ -1063,6 +1130,24 @@ impl MetastoreService for MetastoreServiceClient {
     ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
         self.inner.0.update_index(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.inner.0.toggle_ingest(request).await
+    }
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> crate::metastore::MetastoreResult<SnapshotIndexResponse> {
+        self.inner.0.snapshot_index(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
+        self.inner.0.restore_index(request).await
+    }
     async fn index_metadata(
         &self,
         request: IndexMetadataRequest,
@@ -1259,6 +1344,24 @@ pub mod mock_metastore_service {
         ) -> crate::metastore::MetastoreResult<super::IndexMetadataResponse> {
             self.inner.lock().await.update_index(request).await
         }
+        async fn toggle_ingest(
+            &self,
+            request: super::ToggleIngestRequest,
+        ) -> crate::metastore::MetastoreResult<super::IndexMetadataResponse> {
+            self.inner.lock().await.toggle_ingest(request).await
+        }
+        async fn snapshot_index(
+            &self,
+            request: super::SnapshotIndexRequest,
+        ) -> crate::metastore::MetastoreResult<super::SnapshotIndexResponse> {
+            self.inner.lock().await.snapshot_index(request).await
+        }
+        async fn restore_index(
+            &self,
+            request: super::RestoreIndexRequest,
+        ) -> crate::metastore::MetastoreResult<super::CreateIndexResponse> {
+            self.inner.lock().await.restore_index(request).await
+        }
         async fn index_metadata(
             &self,
             request: super::IndexMetadataRequest,
@@ -1474,6 +1577,54 @@ impl tower::Service<UpdateIndexRequest> for InnerMetastoreServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<ToggleIngestRequest> for InnerMetastoreServiceClient {
+    type Response = IndexMetadataResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ToggleIngestRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.toggle_ingest(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<SnapshotIndexRequest> for InnerMetastoreServiceClient {
+    type Response = SnapshotIndexResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: SnapshotIndexRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.snapshot_index(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<RestoreIndexRequest> for InnerMetastoreServiceClient {
+    type Response = CreateIndexResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: RestoreIndexRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.restore_index(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<IndexMetadataRequest> for InnerMetastoreServiceClient {
     type Response = IndexMetadataResponse;
     type Error = crate::metastore::MetastoreError;
@@ -1937,6 +2088,21 @@ struct MetastoreServiceTowerServiceStack {
         IndexMetadataResponse,
         crate::metastore::MetastoreError,
     >,
+    toggle_ingest_svc: quickwit_common::tower::BoxService<
+        ToggleIngestRequest,
+        IndexMetadataResponse,
+        crate::metastore::MetastoreError,
+    >,
+    snapshot_index_svc: quickwit_common::tower::BoxService<
+        SnapshotIndexRequest,
+        SnapshotIndexResponse,
+        crate::metastore::MetastoreError,
+    >,
+    restore_index_svc: quickwit_common::tower::BoxService<
+        RestoreIndexRequest,
+        CreateIndexResponse,
+        crate::metastore::MetastoreError,
+    >,
     index_metadata_svc: quickwit_common::tower::BoxService<
         IndexMetadataRequest,
         IndexMetadataResponse,
@@ -2092,6 +2258,24 @@ impl MetastoreService for MetastoreServiceTowerServiceStack {
     ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
         self.update_index_svc.clone().ready().await?.call(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.toggle_ingest_svc.clone().ready().await?.call(request).await
+    }
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> crate::metastore::MetastoreResult<SnapshotIndexResponse> {
+        self.snapshot_index_svc.clone().ready().await?.call(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
+        self.restore_index_svc.clone().ready().await?.call(request).await
+    }
     async fn index_metadata(
         &self,
         request: IndexMetadataRequest,
@@ -2287,6 +2471,36 @@ type UpdateIndexLayer = quickwit_common::tower::BoxLayer<
     IndexMetadataResponse,
     crate::metastore::MetastoreError,
 >;
+type ToggleIngestLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ToggleIngestRequest,
+        IndexMetadataResponse,
+        crate::metastore::MetastoreError,
+    >,
+    ToggleIngestRequest,
+    IndexMetadataResponse,
+    crate::metastore::MetastoreError,
+>;
+type SnapshotIndexLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        SnapshotIndexRequest,
+        SnapshotIndexResponse,
+        crate::metastore::MetastoreError,
+    >,
+    SnapshotIndexRequest,
+    SnapshotIndexResponse,
+    crate::metastore::MetastoreError,
+>;
+type RestoreIndexLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        RestoreIndexRequest,
+        CreateIndexResponse,
+        crate::metastore::MetastoreError,
+    >,
+    RestoreIndexRequest,
+    CreateIndexResponse,
+    crate::metastore::MetastoreError,
+>;
 type IndexMetadataLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         IndexMetadataRequest,
@@ -2571,6 +2785,9 @@ type DeleteIndexTemplatesLayer = quickwit_common::tower::BoxLayer<
 pub struct MetastoreServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
     update_index_layers: Vec<UpdateIndexLayer>,
+    toggle_ingest_layers: Vec<ToggleIngestLayer>,
+    snapshot_index_layers: Vec<SnapshotIndexLayer>,
+    restore_index_layers: Vec<RestoreIndexLayer>,
     index_metadata_layers: Vec<IndexMetadataLayer>,
     indexes_metadata_layers: Vec<IndexesMetadataLayer>,
     list_indexes_metadata_layers: Vec<ListIndexesMetadataLayer>,
@@ -2653,6 +2870,81 @@ impl MetastoreServiceTowerLayerStack {
                 crate::metastore::MetastoreError,
             >,
         >>::Service as tower::Service<UpdateIndexRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ToggleIngestRequest,
+                    IndexMetadataResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ToggleIngestRequest,
+                IndexMetadataResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                ToggleIngestRequest,
+                Response = IndexMetadataResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ToggleIngestRequest,
+                IndexMetadataResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<ToggleIngestRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    SnapshotIndexRequest,
+                    SnapshotIndexResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                SnapshotIndexRequest,
+                SnapshotIndexResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                SnapshotIndexRequest,
+                Response = SnapshotIndexResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                SnapshotIndexRequest,
+                SnapshotIndexResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<SnapshotIndexRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RestoreIndexRequest,
+                    CreateIndexResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RestoreIndexRequest,
+                CreateIndexResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                RestoreIndexRequest,
+                Response = CreateIndexResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RestoreIndexRequest,
+                CreateIndexResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<RestoreIndexRequest>>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     IndexMetadataRequest,
@@ -3374,6 +3666,12 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.update_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.toggle_ingest_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.snapshot_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.restore_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.index_metadata_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.indexes_metadata_layers
@@ -3470,6 +3768,63 @@ impl MetastoreServiceTowerLayerStack {
         self.update_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_toggle_ingest_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ToggleIngestRequest,
+                    IndexMetadataResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ToggleIngestRequest,
+                Response = IndexMetadataResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ToggleIngestRequest>>::Future: Send + 'static,
+    {
+        self.toggle_ingest_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_snapshot_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    SnapshotIndexRequest,
+                    SnapshotIndexResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                SnapshotIndexRequest,
+                Response = SnapshotIndexResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<SnapshotIndexRequest>>::Future: Send + 'static,
+    {
+        self.snapshot_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_restore_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RestoreIndexRequest,
+                    CreateIndexResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                RestoreIndexRequest,
+                Response = CreateIndexResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<RestoreIndexRequest>>::Future: Send + 'static,
+    {
+        self.restore_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_index_metadata_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -4101,6 +4456,30 @@ impl MetastoreServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let toggle_ingest_svc = self
+            .toggle_ingest_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let snapshot_index_svc = self
+            .snapshot_index_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let restore_index_svc = self
+            .restore_index_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let index_metadata_svc = self
             .index_metadata_layers
             .into_iter()
@@ -4329,6 +4708,9 @@ impl MetastoreServiceTowerLayerStack {
             inner: inner_client,
             create_index_svc,
             update_index_svc,
+            toggle_ingest_svc,
+            snapshot_index_svc,
+            restore_index_svc,
             index_metadata_svc,
             indexes_metadata_svc,
             list_indexes_metadata_svc,
@@ -4446,20 +4828,38 @@ where
             Future = BoxFuture<IndexMetadataResponse, crate::metastore::MetastoreError>,
         >
         + tower::Service<
-            IndexMetadataRequest,
+            ToggleIngestRequest,
             Response = IndexMetadataResponse,
             Error = crate::metastore::MetastoreError,
             Future = BoxFuture<IndexMetadataResponse, crate::metastore::MetastoreError>,
         >
         + tower::Service<
-            IndexesMetadataRequest,
-            Response = IndexesMetadataResponse,
+            SnapshotIndexRequest,
+            Response = SnapshotIndexResponse,
             Error = crate::metastore::MetastoreError,
-            Future = BoxFuture<IndexesMetadataResponse, crate::metastore::MetastoreError>,
+            Future = BoxFuture<SnapshotIndexResponse, crate::metastore::MetastoreError>,
         >
         + tower::Service<
-            ListIndexesMetadataRequest,
-            Response = ListIndexesMetadataResponse,
+            RestoreIndexRequest,
+            Response = CreateIndexResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<CreateIndexResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            IndexMetadataRequest,
+            Response = IndexMetadataResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<IndexMetadataResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            IndexesMetadataRequest,
+            Response = IndexesMetadataResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<IndexesMetadataResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            ListIndexesMetadataRequest,
+            Response = ListIndexesMetadataResponse,
             Error = crate::metastore::MetastoreError,
             Future = BoxFuture<
                 ListIndexesMetadataResponse,
@@ -4647,6 +5047,24 @@ where
     ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
         self.clone().call(request).await
     }
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.clone().call(request).await
+    }
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> crate::metastore::MetastoreResult<SnapshotIndexResponse> {
+        self.clone().call(request).await
+    }
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
+        self.clone().call(request).await
+    }
     async fn index_metadata(
         &self,
         request: IndexMetadataRequest,
@@ -4890,6 +5308,48 @@ where
                 UpdateIndexRequest::rpc_name(),
             ))
     }
+    async fn toggle_ingest(
+        &self,
+        request: ToggleIngestRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.inner
+            .clone()
+            .toggle_ingest(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ToggleIngestRequest::rpc_name(),
+            ))
+    }
+    async fn snapshot_index(
+        &self,
+        request: SnapshotIndexRequest,
+    ) -> crate::metastore::MetastoreResult<SnapshotIndexResponse> {
+        self.inner
+            .clone()
+            .snapshot_index(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                SnapshotIndexRequest::rpc_name(),
+            ))
+    }
+    async fn restore_index(
+        &self,
+        request: RestoreIndexRequest,
+    ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
+        self.inner
+            .clone()
+            .restore_index(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                RestoreIndexRequest::rpc_name(),
+            ))
+    }
     async fn index_metadata(
         &self,
         request: IndexMetadataRequest,
@@ -5345,6 +5805,39 @@ for MetastoreServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(crate::error::grpc_error_to_grpc_status)
     }
+    async fn toggle_ingest(
+        &self,
+        request: tonic::Request<ToggleIngestRequest>,
+    ) -> Result<tonic::Response<IndexMetadataResponse>, tonic::Status> {
+        self.inner
+            .0
+            .toggle_ingest(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn snapshot_index(
+        &self,
+        request: tonic::Request<SnapshotIndexRequest>,
+    ) -> Result<tonic::Response<SnapshotIndexResponse>, tonic::Status> {
+        self.inner
+            .0
+            .snapshot_index(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
+    async fn restore_index(
+        &self,
+        request: tonic::Request<RestoreIndexRequest>,
+    ) -> Result<tonic::Response<CreateIndexResponse>, tonic::Status> {
+        self.inner
+            .0
+            .restore_index(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(crate::error::grpc_error_to_grpc_status)
+    }
     async fn index_metadata(
         &self,
         request: tonic::Request<IndexMetadataRequest>,
@@ -5849,6 +6342,90 @@ pub mod metastore_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Blocks or unblocks ingest for an index.
+        pub async fn toggle_ingest(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ToggleIngestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::IndexMetadataResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/ToggleIngest",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.metastore.MetastoreService", "ToggleIngest"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the metadata of an index along with its published splits, read consistently.
+        pub async fn snapshot_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SnapshotIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SnapshotIndexResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/SnapshotIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.metastore.MetastoreService", "SnapshotIndex"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Recreates an index along with its sources, source checkpoints, and splits in a single transaction.
+        pub async fn restore_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RestoreIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateIndexResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/RestoreIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.metastore.MetastoreService", "RestoreIndex"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
         pub async fn index_metadata(
             &mut self,
@@ -6698,6 +7275,30 @@ pub mod metastore_service_grpc_server {
             tonic::Response<super::IndexMetadataResponse>,
             tonic::Status,
         >;
+        /// Blocks or unblocks ingest for an index.
+        async fn toggle_ingest(
+            &self,
+            request: tonic::Request<super::ToggleIngestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::IndexMetadataResponse>,
+            tonic::Status,
+        >;
+        /// Returns the metadata of an index along with its published splits, read consistently.
+        async fn snapshot_index(
+            &self,
+            request: tonic::Request<super::SnapshotIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SnapshotIndexResponse>,
+            tonic::Status,
+        >;
+        /// Recreates an index along with its sources, source checkpoints, and splits in a single transaction.
+        async fn restore_index(
+            &self,
+            request: tonic::Request<super::RestoreIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CreateIndexResponse>,
+            tonic::Status,
+        >;
         /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
         async fn index_metadata(
             &self,
@@ -7118,6 +7719,144 @@ pub mod metastore_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.metastore.MetastoreService/ToggleIngest" => {
+                    #[allow(non_camel_case_types)]
+                    struct ToggleIngestSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::ToggleIngestRequest>
+                    for ToggleIngestSvc<T> {
+                        type Response = super::IndexMetadataResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ToggleIngestRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).toggle_ingest(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ToggleIngestSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.metastore.MetastoreService/SnapshotIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct SnapshotIndexSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::SnapshotIndexRequest>
+                    for SnapshotIndexSvc<T> {
+                        type Response = super::SnapshotIndexResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SnapshotIndexRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).snapshot_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SnapshotIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.metastore.MetastoreService/RestoreIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct RestoreIndexSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::RestoreIndexRequest>
+                    for RestoreIndexSvc<T> {
+                        type Response = super::CreateIndexResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RestoreIndexRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).restore_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RestoreIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.metastore.MetastoreService/IndexMetadata" => {
                     #[allow(non_camel_case_types)]
                     struct IndexMetadataSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
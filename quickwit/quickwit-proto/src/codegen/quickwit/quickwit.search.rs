@@ -191,6 +191,59 @@ pub struct SearchRequest {
     pub search_after: ::core::option::Option<PartialHit>,
     #[prost(enumeration = "CountHits", tag = "17")]
     pub count_hits: i32,
+    /// Lowers the maximum depth of the query AST for this request.
+    /// Values above the searcher limit are ignored.
+    #[prost(uint32, optional, tag = "18")]
+    pub max_query_ast_depth: ::core::option::Option<u32>,
+    /// Lowers the maximum number of leaf clauses of the query AST for this request.
+    /// Values above the searcher limit are ignored.
+    #[prost(uint32, optional, tag = "19")]
+    pub max_query_ast_clause_count: ::core::option::Option<u32>,
+    /// Fields to return in the hits. If empty, the whole documents are returned.
+    /// When all of them are stored fast fields, the hits are built from the fast
+    /// fields instead of the doc store.
+    #[prost(string, repeated, tag = "20")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If set, the search response reports where the hits were fetched from.
+    #[prost(bool, tag = "21")]
+    pub debug_fetch_docs_source: bool,
+    /// Whether index ID patterns matching no index yield an empty response (the default)
+    /// or an `IndexesNotFound` error.
+    #[prost(bool, optional, tag = "22")]
+    pub allow_no_indices: ::core::option::Option<bool>,
+    /// If set, the `@timestamp` field is rewritten to the timestamp field of the targeted
+    /// indexes when they do not define it. Only set by the Elasticsearch-compatible API.
+    #[prost(bool, tag = "23")]
+    pub es_timestamp_alias: bool,
+    /// Overrides the default search fields of the targeted indexes for the user input
+    /// queries that do not specify the fields they search. Ignored if empty.
+    #[prost(string, repeated, tag = "24")]
+    pub default_search_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If set, the splits targeted by the search are recorded as a point in time kept alive
+    /// for this duration, and the search response includes its ID. When `pit_id` is also set,
+    /// the point in time is kept alive for this duration instead.
+    #[prost(uint32, optional, tag = "25")]
+    pub pit_keep_alive_secs: ::core::option::Option<u32>,
+    /// If set, the search targets the exact set of splits recorded by this point in time
+    /// instead of the splits currently published, so that the pages of a paginated search
+    /// are consistent with each other.
+    #[prost(string, optional, tag = "26")]
+    pub pit_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, hits are only counted accurately up to this value. Once more hits matched, the
+    /// splits that cannot contribute to the top hits are no longer searched, and the response
+    /// reports this value as a lower bound of the number of hits.
+    #[prost(uint64, optional, tag = "27")]
+    pub track_total_hits: ::core::option::Option<u64>,
+    /// If set, the leaves may spill the intermediate aggregation results to disk once they exceed
+    /// the in-memory threshold of the searcher, instead of buffering them all in memory.
+    #[prost(bool, tag = "28")]
+    pub allow_disk_use: bool,
+    /// If set, the aggregations are computed on a deterministic sample of the matching documents,
+    /// and their count-like results are scaled back up to estimate the exact values. The rate is
+    /// expressed in parts per million, within `\]0, 1_000_000\]`. The hits and `num_hits` are not
+    /// affected.
+    #[prost(uint32, optional, tag = "29")]
+    pub aggregation_sample_rate_ppm: ::core::option::Option<u32>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
@@ -206,6 +259,11 @@ pub struct SortField {
     /// unix_timestamp_nanos.
     #[prost(enumeration = "SortDatetimeFormat", optional, tag = "3")]
     pub sort_datetime_format: ::core::option::Option<i32>,
+    /// Optional value picked for multivalued fast fields.
+    /// If none, the minimum value is used for ascending sorts and the
+    /// maximum value for descending sorts.
+    #[prost(enumeration = "SortMode", optional, tag = "4")]
+    pub sort_mode: ::core::option::Option<i32>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -241,6 +299,32 @@ pub struct SearchResponse {
     /// Total number of successful splits searched.
     #[prost(uint64, tag = "8")]
     pub num_successful_splits: u64,
+    /// Where the hits were fetched from. Only set if `debug_fetch_docs_source`
+    /// was set in the request.
+    #[prost(enumeration = "FetchDocsSource", optional, tag = "9")]
+    pub fetch_docs_source: ::core::option::Option<i32>,
+    /// Set when the response may be missing some results, for instance because
+    /// the splits were listed from a stale cache after the metastore timed out.
+    #[prost(bool, tag = "10")]
+    pub partial: bool,
+    /// Non-fatal notices about how the request was interpreted, for instance when a field
+    /// was rewritten.
+    #[prost(string, repeated, tag = "11")]
+    pub warnings: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Point in time ID (only set if `pit_keep_alive_secs` or `pit_id` was set in the request)
+    #[prost(string, optional, tag = "12")]
+    pub pit_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// CPU time spent by the leaves searching the splits, expressed in microseconds.
+    #[prost(uint64, tag = "13")]
+    pub cpu_time_micros: u64,
+    /// Whether `num_hits` is exact or a lower bound, because counting stopped at
+    /// `track_total_hits`.
+    #[prost(enumeration = "NumHitsRelation", tag = "14")]
+    pub num_hits_relation: i32,
+    /// Rate at which the matching documents were sampled to compute the aggregations. Only set
+    /// if the aggregations were computed on a sample.
+    #[prost(double, optional, tag = "15")]
+    pub aggregation_sample_rate: ::core::option::Option<f64>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -262,6 +346,9 @@ pub struct SplitSearchError {
     /// Flag to indicate if the error can be considered a retryable error
     #[prost(bool, tag = "3")]
     pub retryable_error: bool,
+    /// Cause of the failure, when it is known.
+    #[prost(enumeration = "SplitSearchErrorCode", tag = "4")]
+    pub error_code: i32,
 }
 /// / A LeafSearchRequest can span multiple indices.
 /// /
@@ -325,6 +412,10 @@ pub struct SplitIdAndFooterOffsets {
     /// The number of docs in the split
     #[prost(uint64, tag = "6")]
     pub num_docs: u64,
+    /// The delete opstamp of the split. Per-split cache entries are keyed by it so that they are
+    /// invalidated once a delete task has been applied to the split.
+    #[prost(uint64, tag = "7")]
+    pub delete_opstamp: u64,
 }
 /// Hits returned by a FetchDocRequest.
 ///
@@ -457,6 +548,24 @@ pub struct LeafSearchResponse {
     pub intermediate_aggregation_result: ::core::option::Option<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// Number of matching documents lacking the field of the histogram aggregations having a
+    /// `missing` parameter.
+    #[prost(message, repeated, tag = "8")]
+    pub histogram_missing_doc_counts: ::prost::alloc::vec::Vec<HistogramMissingDocCount>,
+    /// CPU time spent searching the splits, expressed in microseconds.
+    #[prost(uint64, tag = "9")]
+    pub cpu_time_micros: u64,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HistogramMissingDocCount {
+    /// Name of the top-level histogram aggregation.
+    #[prost(string, tag = "1")]
+    pub aggregation_name: ::prost::alloc::string::String,
+    /// Number of matching documents lacking the histogram field.
+    #[prost(uint64, tag = "2")]
+    pub num_docs: u64,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -488,6 +597,9 @@ pub struct FetchDocsRequest {
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
+    /// Fields to return in the hits. If empty, the whole documents are returned.
+    #[prost(string, repeated, tag = "8")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -554,6 +666,13 @@ pub struct LeafListTermsRequest {
     /// split files.
     #[prost(string, tag = "3")]
     pub index_uri: ::prost::alloc::string::String,
+    /// Serialized doc mapper of the index. Only set along with `search_filter_ast`.
+    #[prost(string, optional, tag = "4")]
+    pub doc_mapper: ::core::option::Option<::prost::alloc::string::String>,
+    /// Serialized query AST of the search filter of the index. When set, only the terms of the
+    /// documents matching the filter are listed.
+    #[prost(string, optional, tag = "5")]
+    pub search_filter_ast: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -718,6 +837,66 @@ impl CountHits {
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum NumHitsRelation {
+    /// The number of hits is exact.
+    EqualTo = 0,
+    /// The number of hits is a lower bound of the actual number of hits.
+    GreaterThanOrEqualTo = 1,
+}
+impl NumHitsRelation {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            NumHitsRelation::EqualTo => "EQUAL_TO",
+            NumHitsRelation::GreaterThanOrEqualTo => "GREATER_THAN_OR_EQUAL_TO",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EQUAL_TO" => Some(Self::EqualTo),
+            "GREATER_THAN_OR_EQUAL_TO" => Some(Self::GreaterThanOrEqualTo),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FetchDocsSource {
+    /// The hits are read from the doc store.
+    DocStore = 0,
+    /// The hits are built from the fast fields.
+    FastFields = 1,
+}
+impl FetchDocsSource {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FetchDocsSource::DocStore => "DOC_STORE",
+            FetchDocsSource::FastFields => "FAST_FIELDS",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "DOC_STORE" => Some(Self::DocStore),
+            "FAST_FIELDS" => Some(Self::FastFields),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum SortOrder {
     /// Ascending order.
     Asc = 0,
@@ -746,6 +925,37 @@ impl SortOrder {
         }
     }
 }
+/// Value of a multivalued fast field used to sort a document.
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SortMode {
+    /// Smallest value of the document.
+    Min = 0,
+    /// Largest value of the document.
+    Max = 1,
+}
+impl SortMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SortMode::Min => "MIN",
+            SortMode::Max => "MAX",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "MIN" => Some(Self::Min),
+            "MAX" => Some(Self::Max),
+            _ => None,
+        }
+    }
+}
 /// Sort value format for datetime field.
 /// We keep an enum with only one format
 /// for future extension.
@@ -781,6 +991,45 @@ impl SortDatetimeFormat {
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum SplitSearchErrorCode {
+    /// The cause of the failure is only described by the error message.
+    Unspecified = 0,
+    /// The leaf request exceeded its memory budget, or the searcher ran out of search memory.
+    MemoryLimitExceeded = 1,
+    /// The split file is missing from the storage, for instance because it was garbage collected
+    /// after the split was listed.
+    SplitNotFound = 2,
+}
+impl SplitSearchErrorCode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SplitSearchErrorCode::Unspecified => "SPLIT_SEARCH_ERROR_CODE_UNSPECIFIED",
+            SplitSearchErrorCode::MemoryLimitExceeded => {
+                "SPLIT_SEARCH_ERROR_CODE_MEMORY_LIMIT_EXCEEDED"
+            }
+            SplitSearchErrorCode::SplitNotFound => "SPLIT_SEARCH_ERROR_CODE_SPLIT_NOT_FOUND",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SPLIT_SEARCH_ERROR_CODE_UNSPECIFIED" => Some(Self::Unspecified),
+            "SPLIT_SEARCH_ERROR_CODE_MEMORY_LIMIT_EXCEEDED" => {
+                Some(Self::MemoryLimitExceeded)
+            }
+            "SPLIT_SEARCH_ERROR_CODE_SPLIT_NOT_FOUND" => Some(Self::SplitNotFound),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum OutputFormat {
     /// Comma Separated Values format (<https://datatracker.ietf.org/doc/html/rfc4180>).
     /// The delimiter is `,`.
@@ -139,6 +139,12 @@ impl RpcName for AdviseResetShardsRequest {
     }
 }
 
+impl RpcName for WatchIndexingPlanRequest {
+    fn rpc_name() -> &'static str {
+        "watch_indexing_plan"
+    }
+}
+
 impl GetOrCreateOpenShardsFailureReason {
     pub fn create_failure(
         &self,
@@ -138,6 +138,7 @@ generate_getters! {
     PublishSplitsRequest,
     ResetSourceCheckpointRequest,
     StageSplitsRequest,
+    ToggleIngestRequest,
     ToggleSourceRequest,
     UpdateIndexRequest,
     UpdateSplitsDeleteOpstampRequest
@@ -147,7 +148,10 @@ generate_getters! {
 generate_copy_getters! {
     impl fn pipeline_uid() -> PipelineUid {} for
 
-    IndexingTask
+    IndexingTask,
+    IndexingTaskOutcome,
+    IndexingTaskShardDelta,
+    PromoteIndexingPipelineRequest
 }
 
 // [`Position`] getters. We use `clone` because `Position` is an `Arc` under the hood.
@@ -0,0 +1,273 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on the CPU capacity accepted when parsing or converting a CPU capacity coming from
+/// another node, a config file, or an API call: 10 million millis, i.e. 10,000 CPU threads. Larger
+/// values are most likely the result of a unit mistake and are rejected.
+pub const MAX_CPU_CAPACITY_MILLIS: u32 = 10_000_000;
+
+/// The CpuCapacity represents an amount of CPU resource available.
+///
+/// It is usually expressed in CPU millis (For instance, one full CPU thread is
+/// displayed as `1000m`).
+///
+/// All the conversions from external representations (strings and floats) go through this module
+/// and reject values above [`MAX_CPU_CAPACITY_MILLIS`] instead of silently truncating them.
+#[derive(
+    Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Ord, PartialOrd, utoipa::ToSchema,
+)]
+#[serde(
+    into = "CpuCapacityForSerialization",
+    try_from = "CpuCapacityForSerialization"
+)]
+pub struct CpuCapacity(u32);
+
+/// Short helper function to build `CpuCapacity`.
+#[inline(always)]
+pub const fn mcpu(milli_cpus: u32) -> CpuCapacity {
+    CpuCapacity::from_cpu_millis(milli_cpus)
+}
+
+impl CpuCapacity {
+    #[inline(always)]
+    pub const fn from_cpu_millis(cpu_millis: u32) -> CpuCapacity {
+        CpuCapacity(cpu_millis)
+    }
+
+    #[inline(always)]
+    pub fn cpu_millis(self) -> u32 {
+        self.0
+    }
+
+    #[inline(always)]
+    pub fn zero() -> CpuCapacity {
+        CpuCapacity::from_cpu_millis(0u32)
+    }
+
+    #[inline(always)]
+    pub fn one_cpu_thread() -> CpuCapacity {
+        CpuCapacity::from_cpu_millis(1_000u32)
+    }
+
+    fn try_from_cpu_millis(cpu_millis: u64) -> Result<CpuCapacity, String> {
+        if cpu_millis > MAX_CPU_CAPACITY_MILLIS as u64 {
+            return Err(format!(
+                "invalid cpu capacity: `{cpu_millis}m` exceeds the maximum of \
+                 `{MAX_CPU_CAPACITY_MILLIS}m`."
+            ));
+        }
+        Ok(CpuCapacity(cpu_millis as u32))
+    }
+
+    fn try_from_cpus(cpus: f32) -> Result<CpuCapacity, String> {
+        let cpu_millis = (cpus * 1000.0f32).round();
+
+        if !cpu_millis.is_finite() || cpu_millis < 0.0 {
+            return Err(format!("invalid cpu capacity: `{cpus}`."));
+        }
+        if cpu_millis > MAX_CPU_CAPACITY_MILLIS as f32 {
+            return Err(format!(
+                "invalid cpu capacity: `{cpus}` exceeds the maximum of \
+                 `{MAX_CPU_CAPACITY_MILLIS}m`."
+            ));
+        }
+        Ok(CpuCapacity(cpu_millis as u32))
+    }
+}
+
+impl Sub<CpuCapacity> for CpuCapacity {
+    type Output = CpuCapacity;
+
+    #[inline(always)]
+    fn sub(self, rhs: CpuCapacity) -> Self::Output {
+        CpuCapacity::from_cpu_millis(self.0 - rhs.0)
+    }
+}
+
+impl Add<CpuCapacity> for CpuCapacity {
+    type Output = CpuCapacity;
+
+    #[inline(always)]
+    fn add(self, rhs: CpuCapacity) -> Self::Output {
+        CpuCapacity::from_cpu_millis(self.0 + rhs.0)
+    }
+}
+
+impl Mul<u32> for CpuCapacity {
+    type Output = CpuCapacity;
+
+    #[inline(always)]
+    fn mul(self, rhs: u32) -> CpuCapacity {
+        CpuCapacity::from_cpu_millis(self.0 * rhs)
+    }
+}
+
+impl Mul<f32> for CpuCapacity {
+    type Output = CpuCapacity;
+
+    #[inline(always)]
+    fn mul(self, scale: f32) -> CpuCapacity {
+        CpuCapacity::from_cpu_millis((self.0 as f32 * scale) as u32)
+    }
+}
+
+impl Display for CpuCapacity {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}m", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CpuCapacityForSerialization {
+    Float(f32),
+    MilliCpuWithUnit(String),
+}
+
+impl TryFrom<CpuCapacityForSerialization> for CpuCapacity {
+    type Error = String;
+
+    fn try_from(
+        cpu_capacity_for_serialization: CpuCapacityForSerialization,
+    ) -> Result<CpuCapacity, Self::Error> {
+        match cpu_capacity_for_serialization {
+            CpuCapacityForSerialization::Float(cpu_capacity) => {
+                CpuCapacity::try_from_cpus(cpu_capacity)
+            }
+            CpuCapacityForSerialization::MilliCpuWithUnit(cpu_capacity_str) => {
+                Self::from_str(&cpu_capacity_str)
+            }
+        }
+    }
+}
+
+impl FromStr for CpuCapacity {
+    type Err = String;
+
+    fn from_str(cpu_capacity_str: &str) -> Result<Self, Self::Err> {
+        let Some(milli_cpus_without_unit_str) = cpu_capacity_str.strip_suffix('m') else {
+            return Err(format!(
+                "invalid cpu capacity: `{cpu_capacity_str}`. String format expects a trailing 'm'."
+            ));
+        };
+        let milli_cpus: u64 = milli_cpus_without_unit_str
+            .parse::<u64>()
+            .map_err(|_err| format!("invalid cpu capacity: `{cpu_capacity_str}`."))?;
+        CpuCapacity::try_from_cpu_millis(milli_cpus)
+    }
+}
+
+impl From<CpuCapacity> for CpuCapacityForSerialization {
+    fn from(cpu_capacity: CpuCapacity) -> CpuCapacityForSerialization {
+        CpuCapacityForSerialization::MilliCpuWithUnit(format!("{}m", cpu_capacity.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_cpu_capacity_serialization() {
+        assert_eq!(CpuCapacity::from_str("2000m").unwrap(), mcpu(2000));
+        assert_eq!(CpuCapacity::from_cpu_millis(2500), mcpu(2500));
+        assert_eq!(
+            CpuCapacity::from_str("2.5").unwrap_err(),
+            "invalid cpu capacity: `2.5`. String format expects a trailing 'm'."
+        );
+        assert_eq!(
+            serde_json::from_value::<CpuCapacity>(serde_json::Value::String("1200m".to_string()))
+                .unwrap(),
+            mcpu(1200)
+        );
+        assert_eq!(
+            serde_json::from_value::<CpuCapacity>(serde_json::Value::Number(
+                serde_json::Number::from_f64(1.2f64).unwrap()
+            ))
+            .unwrap(),
+            mcpu(1200)
+        );
+        assert_eq!(
+            serde_json::from_value::<CpuCapacity>(serde_json::Value::Number(
+                serde_json::Number::from(1u32)
+            ))
+            .unwrap(),
+            mcpu(1000)
+        );
+        assert_eq!(CpuCapacity::from_cpu_millis(2500).to_string(), "2500m");
+        assert_eq!(serde_json::to_string(&mcpu(2500)).unwrap(), "\"2500m\"");
+    }
+
+    #[test]
+    fn test_cpu_capacity_rejects_out_of_range_values() {
+        assert_eq!(
+            CpuCapacity::from_str("10000000m").unwrap(),
+            mcpu(MAX_CPU_CAPACITY_MILLIS)
+        );
+        assert_eq!(
+            CpuCapacity::from_str("10000001m").unwrap_err(),
+            "invalid cpu capacity: `10000001m` exceeds the maximum of `10000000m`."
+        );
+        // Used to wrap around when parsed as a `u32`.
+        CpuCapacity::from_str("4294968296m").unwrap_err();
+        CpuCapacity::from_str("-1000m").unwrap_err();
+
+        for invalid_cpus in [-1.0f64, 1e8f64] {
+            serde_json::from_value::<CpuCapacity>(serde_json::Value::Number(
+                serde_json::Number::from_f64(invalid_cpus).unwrap(),
+            ))
+            .unwrap_err();
+        }
+        // Used to be silently truncated to `u32::MAX` millis.
+        serde_json::from_str::<CpuCapacity>("1e12").unwrap_err();
+    }
+
+    proptest! {
+        #[test]
+        fn test_proptest_cpu_capacity_str_round_trip(cpu_millis in any::<u64>()) {
+            let cpu_capacity_str = format!("{cpu_millis}m");
+            match CpuCapacity::from_str(&cpu_capacity_str) {
+                Ok(cpu_capacity) => {
+                    prop_assert_eq!(cpu_capacity.cpu_millis() as u64, cpu_millis);
+                    prop_assert_eq!(cpu_capacity.to_string(), cpu_capacity_str);
+                }
+                Err(_) => prop_assert!(cpu_millis > MAX_CPU_CAPACITY_MILLIS as u64),
+            }
+        }
+
+        #[test]
+        fn test_proptest_cpu_capacity_serde_round_trip(
+            cpu_millis in 0..=MAX_CPU_CAPACITY_MILLIS
+        ) {
+            let cpu_capacity = mcpu(cpu_millis);
+            let cpu_capacity_json = serde_json::to_string(&cpu_capacity).unwrap();
+            let deserialized_cpu_capacity: CpuCapacity =
+                serde_json::from_str(&cpu_capacity_json).unwrap();
+            prop_assert_eq!(deserialized_cpu_capacity, cpu_capacity);
+        }
+    }
+}
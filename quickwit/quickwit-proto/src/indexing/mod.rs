@@ -17,10 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
-use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
 
 use bytesize::ByteSize;
 use quickwit_actors::AskError;
@@ -34,6 +35,10 @@ use crate::metastore::MetastoreError;
 use crate::types::{IndexUid, NodeId, PipelineUid, Position, ShardId, SourceId, SourceUid};
 use crate::{GrpcServiceError, ServiceError, ServiceErrorCode};
 
+mod cpu_capacity;
+
+pub use cpu_capacity::{mcpu, CpuCapacity, MAX_CPU_CAPACITY_MILLIS};
+
 include!("../codegen/quickwit/quickwit.indexing.rs");
 
 pub type IndexingResult<T> = std::result::Result<T, IndexingError>;
@@ -47,6 +52,10 @@ pub enum IndexingError {
     Metastore(#[from] MetastoreError),
     #[error("request timed out: {0}")]
     Timeout(String),
+    #[error("indexing plan generation mismatch: {0}")]
+    PlanGenerationMismatch(String),
+    #[error("indexing pipeline `{}` not found", pipeline_uid.short_id())]
+    PipelineNotFound { pipeline_uid: PipelineUid },
     #[error("too many requests")]
     TooManyRequests,
     #[error("service unavailable: {0}")]
@@ -66,6 +75,8 @@ impl ServiceError for IndexingError {
                 ServiceErrorCode::Internal
             }
             Self::Metastore(metastore_error) => metastore_error.error_code(),
+            Self::PlanGenerationMismatch(_) => ServiceErrorCode::BadRequest,
+            Self::PipelineNotFound { .. } => ServiceErrorCode::NotFound,
             Self::Timeout(_) => ServiceErrorCode::Timeout,
             Self::TooManyRequests => ServiceErrorCode::TooManyRequests,
             Self::Unavailable(_) => ServiceErrorCode::Unavailable,
@@ -170,6 +181,50 @@ impl Hash for IndexingTask {
         self.source_id.hash(state);
     }
 }
+impl IndexingTaskPriority {
+    /// Priorities sorted from the most to the least urgent.
+    pub const ALL: [IndexingTaskPriority; 3] = [
+        IndexingTaskPriority::High,
+        IndexingTaskPriority::Normal,
+        IndexingTaskPriority::Background,
+    ];
+
+    /// Returns the rank of the priority: tasks with a lower rank are scheduled and granted
+    /// indexing resources first.
+    pub fn rank(&self) -> usize {
+        match self {
+            IndexingTaskPriority::High => 0,
+            IndexingTaskPriority::Normal => 1,
+            IndexingTaskPriority::Background => 2,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IndexingTaskPriority::High => "high",
+            IndexingTaskPriority::Normal => "normal",
+            IndexingTaskPriority::Background => "background",
+        }
+    }
+}
+
+impl Display for IndexingTaskPriority {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IndexingTaskPriority {
+    type Err = String;
+
+    fn from_str(priority_str: &str) -> Result<Self, Self::Err> {
+        IndexingTaskPriority::ALL
+            .into_iter()
+            .find(|priority| priority.as_str() == priority_str)
+            .ok_or_else(|| format!("unknown indexing task priority `{priority_str}`"))
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
 pub struct PipelineMetrics {
     pub cpu_load: CpuCapacity,
@@ -185,197 +240,416 @@ impl Display for PipelineMetrics {
     }
 }
 
-/// One full pipeline (including merging) is assumed to consume 4 CPU threads.
-/// The actual number somewhere between 3 and 4. Quickwit is not super sensitive to this number.
-///
-/// It simply impacts the point where we prefer to work on balancing the load over the different
-/// indexers and the point where we prefer improving other feature of the system (shard locality,
-/// grouping pipelines associated to a given index on the same node, etc.).
-pub const PIPELINE_FULL_CAPACITY: CpuCapacity = CpuCapacity::from_cpu_millis(4_000u32);
-
-/// One full pipeline (including merging) is supposed to have the capacity to index at least 20mb/s.
-/// This is a defensive value: In reality, this is typically above 30mb/s.
-pub const PIPELINE_THROUGHPUT: ByteSize = ByteSize::mb(20);
-
-/// The CpuCapacity represents an amount of CPU resource available.
-///
-/// It is usually expressed in CPU millis (For instance, one full CPU thread is
-/// displayed as `1000m`).
+/// Health of an indexing pipeline, as assessed by its supervisor.
 #[derive(
-    Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, Ord, PartialOrd, utoipa::ToSchema,
-)]
-#[serde(
-    into = "CpuCapacityForSerialization",
-    try_from = "CpuCapacityForSerialization"
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
 )]
-pub struct CpuCapacity(u32);
-
-/// Short helper function to build `CpuCapacity`.
-#[inline(always)]
-pub const fn mcpu(milli_cpus: u32) -> CpuCapacity {
-    CpuCapacity::from_cpu_millis(milli_cpus)
+#[serde(rename_all = "snake_case")]
+pub enum PipelineHealth {
+    /// All the actors of the pipeline are running.
+    #[default]
+    Running,
+    /// The pipeline is running, but spends most of its time waiting for room in the upload
+    /// queue.
+    Backpressured,
+    /// The pipeline failed and is being respawned.
+    Retrying,
+    /// The pipeline failed repeatedly and is waiting for its next restart attempt.
+    Failed,
 }
 
-impl CpuCapacity {
-    #[inline(always)]
-    pub const fn from_cpu_millis(cpu_millis: u32) -> CpuCapacity {
-        CpuCapacity(cpu_millis)
-    }
+impl PipelineHealth {
+    pub const ALL: [PipelineHealth; 4] = [
+        PipelineHealth::Running,
+        PipelineHealth::Backpressured,
+        PipelineHealth::Retrying,
+        PipelineHealth::Failed,
+    ];
 
-    #[inline(always)]
-    pub fn cpu_millis(self) -> u32 {
-        self.0
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineHealth::Running => "running",
+            PipelineHealth::Backpressured => "backpressured",
+            PipelineHealth::Retrying => "retrying",
+            PipelineHealth::Failed => "failed",
+        }
     }
 
-    #[inline(always)]
-    pub fn zero() -> CpuCapacity {
-        CpuCapacity::from_cpu_millis(0u32)
+    /// Returns whether the pipeline is running, possibly backpressured.
+    pub fn is_healthy(&self) -> bool {
+        matches!(
+            self,
+            PipelineHealth::Running | PipelineHealth::Backpressured
+        )
     }
+}
 
-    #[inline(always)]
-    pub fn one_cpu_thread() -> CpuCapacity {
-        CpuCapacity::from_cpu_millis(1_000u32)
+impl Display for PipelineHealth {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
-impl Sub<CpuCapacity> for CpuCapacity {
-    type Output = CpuCapacity;
+impl FromStr for PipelineHealth {
+    type Err = String;
 
-    #[inline(always)]
-    fn sub(self, rhs: CpuCapacity) -> Self::Output {
-        CpuCapacity::from_cpu_millis(self.0 - rhs.0)
+    fn from_str(health_str: &str) -> Result<Self, Self::Err> {
+        PipelineHealth::ALL
+            .into_iter()
+            .find(|health| health.as_str() == health_str)
+            .ok_or_else(|| format!("unknown pipeline health `{health_str}`"))
     }
 }
 
-impl Add<CpuCapacity> for CpuCapacity {
-    type Output = CpuCapacity;
+/// One full pipeline (including merging) is assumed to consume 4 CPU threads.
+/// The actual number somewhere between 3 and 4. Quickwit is not super sensitive to this number.
+///
+/// It simply impacts the point where we prefer to work on balancing the load over the different
+/// indexers and the point where we prefer improving other feature of the system (shard locality,
+/// grouping pipelines associated to a given index on the same node, etc.).
+pub const PIPELINE_FULL_CAPACITY: CpuCapacity = CpuCapacity::from_cpu_millis(4_000u32);
+
+/// One full pipeline (including merging) is supposed to have the capacity to index at least 20mb/s.
+/// This is a defensive value: In reality, this is typically above 30mb/s.
+pub const PIPELINE_THROUGHPUT: ByteSize = ByteSize::mb(20);
 
-    #[inline(always)]
-    fn add(self, rhs: CpuCapacity) -> Self::Output {
-        CpuCapacity::from_cpu_millis(self.0 + rhs.0)
-    }
+/// Whenever a shard position update is detected (whether it is emit by an indexing pipeline local
+/// to the cluster or received via chitchat), the shard positions service publishes a
+/// `ShardPositionsUpdate` event through the cluster's `EventBroker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardPositionsUpdate {
+    pub source_uid: SourceUid,
+    // Only shards that received an update are listed here.
+    pub updated_shard_positions: Vec<(ShardId, Position)>,
 }
 
-impl Mul<u32> for CpuCapacity {
-    type Output = CpuCapacity;
+impl Event for ShardPositionsUpdate {}
 
-    #[inline(always)]
-    fn mul(self, rhs: u32) -> CpuCapacity {
-        CpuCapacity::from_cpu_millis(self.0 * rhs)
-    }
+/// When the shard positions service garbage collects shards that reached EOF a while ago and that
+/// are closed or deleted in the metastore, it publishes a `ShardPositionsCleanup` event through the
+/// cluster's `EventBroker`. No `ShardPositionsUpdate` event is published for these shards
+/// afterwards, so consumers should drop any state they keep for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardPositionsCleanup {
+    pub source_uid: SourceUid,
+    pub removed_shard_ids: Vec<ShardId>,
 }
 
-impl Mul<f32> for CpuCapacity {
-    type Output = CpuCapacity;
+impl Event for ShardPositionsCleanup {}
 
-    #[inline(always)]
-    fn mul(self, scale: f32) -> CpuCapacity {
-        CpuCapacity::from_cpu_millis((self.0 as f32 * scale) as u32)
+impl RpcName for ApplyIndexingPlanRequest {
+    fn rpc_name() -> &'static str {
+        "apply_indexing_plan"
     }
 }
 
-impl Display for CpuCapacity {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        write!(f, "{}m", self.0)
+impl RpcName for PromoteIndexingPipelineRequest {
+    fn rpc_name() -> &'static str {
+        "promote_indexing_pipeline"
     }
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(untagged)]
-enum CpuCapacityForSerialization {
-    Float(f32),
-    MilliCpuWithUnit(String),
+/// An indexing plan applied to an indexer, against which the shard lists of the next plan can be
+/// delta-encoded.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AppliedIndexingPlan {
+    pub plan_generation: u64,
+    pub indexing_tasks: Vec<IndexingTask>,
 }
 
-impl TryFrom<CpuCapacityForSerialization> for CpuCapacity {
-    type Error = String;
-
-    fn try_from(
-        cpu_capacity_for_serialization: CpuCapacityForSerialization,
-    ) -> Result<CpuCapacity, Self::Error> {
-        match cpu_capacity_for_serialization {
-            CpuCapacityForSerialization::Float(cpu_capacity) => {
-                Ok(CpuCapacity((cpu_capacity * 1000.0f32) as u32))
-            }
-            CpuCapacityForSerialization::MilliCpuWithUnit(cpu_capacity_str) => {
-                Self::from_str(&cpu_capacity_str)
+impl ApplyIndexingPlanRequest {
+    /// Creates a request applying the tasks of the plan of generation `plan_generation`.
+    ///
+    /// When `base_plan_opt` is set, the shard lists of the tasks that also belong to the base plan
+    /// are delta-encoded against it, as long as the delta is shorter than the full list.
+    pub fn new(
+        control_plane_node_id: impl Into<String>,
+        plan_generation: u64,
+        indexing_tasks: &[IndexingTask],
+        base_plan_opt: Option<&AppliedIndexingPlan>,
+    ) -> Self {
+        let mut request = Self {
+            indexing_tasks: indexing_tasks.to_vec(),
+            control_plane_node_id: control_plane_node_id.into(),
+            plan_generation,
+            base_plan_generation: None,
+            shard_deltas: Vec::new(),
+        };
+        let Some(base_plan) = base_plan_opt else {
+            return request;
+        };
+        for indexing_task in &mut request.indexing_tasks {
+            let Some(base_task) = base_plan
+                .indexing_tasks
+                .iter()
+                .find(|base_task| base_task.pipeline_uid == indexing_task.pipeline_uid)
+            else {
+                continue;
+            };
+            let base_shard_ids: BTreeSet<&ShardId> = base_task.shard_ids.iter().collect();
+            let shard_ids: BTreeSet<&ShardId> = indexing_task.shard_ids.iter().collect();
+
+            let added_shard_ids: Vec<ShardId> = shard_ids
+                .difference(&base_shard_ids)
+                .map(|shard_id| (*shard_id).clone())
+                .collect();
+            let removed_shard_ids: Vec<ShardId> = base_shard_ids
+                .difference(&shard_ids)
+                .map(|shard_id| (*shard_id).clone())
+                .collect();
+
+            if added_shard_ids.len() + removed_shard_ids.len() >= indexing_task.shard_ids.len() {
+                continue;
             }
+            request.shard_deltas.push(IndexingTaskShardDelta {
+                pipeline_uid: indexing_task.pipeline_uid,
+                added_shard_ids,
+                removed_shard_ids,
+            });
+            indexing_task.shard_ids.clear();
+        }
+        if !request.shard_deltas.is_empty() {
+            request.base_plan_generation = Some(base_plan.plan_generation);
         }
+        request
     }
-}
 
-impl FromStr for CpuCapacity {
-    type Err = String;
+    /// Returns true if the shard lists of some tasks are delta-encoded.
+    pub fn is_delta_encoded(&self) -> bool {
+        self.base_plan_generation.is_some()
+    }
 
-    fn from_str(cpu_capacity_str: &str) -> Result<Self, Self::Err> {
-        let Some(milli_cpus_without_unit_str) = cpu_capacity_str.strip_suffix('m') else {
-            return Err(format!(
-                "invalid cpu capacity: `{cpu_capacity_str}`. String format expects a trailing 'm'."
-            ));
+    /// Replaces the shard deltas of the request with the full shard lists of its tasks, resolved
+    /// against `base_plan_opt`, the plan last applied by the indexer.
+    ///
+    /// Fails with [`IndexingError::PlanGenerationMismatch`] if the request is encoded against
+    /// another plan. The control plane is then expected to send the full plan.
+    pub fn resolve_shard_deltas(
+        &mut self,
+        base_plan_opt: Option<&AppliedIndexingPlan>,
+    ) -> IndexingResult<()> {
+        let Some(base_plan_generation) = self.base_plan_generation else {
+            return Ok(());
+        };
+        let Some(base_plan) =
+            base_plan_opt.filter(|base_plan| base_plan.plan_generation == base_plan_generation)
+        else {
+            let message = format!(
+                "plan is encoded against generation {base_plan_generation}, last applied \
+                 generation is {:?}",
+                base_plan_opt.map(|base_plan| base_plan.plan_generation)
+            );
+            return Err(IndexingError::PlanGenerationMismatch(message));
         };
-        let milli_cpus: u32 = milli_cpus_without_unit_str
-            .parse::<u32>()
-            .map_err(|_err| format!("invalid cpu capacity: `{cpu_capacity_str}`."))?;
-        Ok(CpuCapacity(milli_cpus))
+        for shard_delta in std::mem::take(&mut self.shard_deltas) {
+            let pipeline_uid_opt = shard_delta.pipeline_uid;
+
+            let (Some(indexing_task), Some(base_task)) = (
+                self.indexing_tasks
+                    .iter_mut()
+                    .find(|indexing_task| indexing_task.pipeline_uid == pipeline_uid_opt),
+                base_plan
+                    .indexing_tasks
+                    .iter()
+                    .find(|base_task| base_task.pipeline_uid == pipeline_uid_opt),
+            ) else {
+                let message = format!(
+                    "shard delta of pipeline `{}` does not match any task of generations \
+                     {base_plan_generation} and {}",
+                    shard_delta.pipeline_uid(),
+                    self.plan_generation
+                );
+                return Err(IndexingError::PlanGenerationMismatch(message));
+            };
+            let removed_shard_ids: BTreeSet<&ShardId> =
+                shard_delta.removed_shard_ids.iter().collect();
+            let mut shard_ids: BTreeSet<ShardId> = base_task
+                .shard_ids
+                .iter()
+                .filter(|shard_id| !removed_shard_ids.contains(shard_id))
+                .cloned()
+                .collect();
+            shard_ids.extend(shard_delta.added_shard_ids);
+            indexing_task.shard_ids = shard_ids.into_iter().collect();
+        }
+        self.base_plan_generation = None;
+        Ok(())
     }
 }
 
-impl From<CpuCapacity> for CpuCapacityForSerialization {
-    fn from(cpu_capacity: CpuCapacity) -> CpuCapacityForSerialization {
-        CpuCapacityForSerialization::MilliCpuWithUnit(format!("{}m", cpu_capacity.0))
+impl ApplyIndexingPlanResponse {
+    /// Returns true if the indexer reported that all the tasks of the plan were already running.
+    /// Always false for indexers predating task outcomes, which return an empty response.
+    pub fn all_tasks_already_running(&self) -> bool {
+        !self.task_outcomes.is_empty()
+            && self
+                .task_outcomes
+                .iter()
+                .all(|task_outcome| task_outcome.status() == IndexingTaskStatus::AlreadyRunning)
     }
 }
 
-/// Whenever a shard position update is detected (whether it is emit by an indexing pipeline local
-/// to the cluster or received via chitchat), the shard positions service publishes a
-/// `ShardPositionsUpdate` event through the cluster's `EventBroker`.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ShardPositionsUpdate {
-    pub source_uid: SourceUid,
-    // Only shards that received an update are listed here.
-    pub updated_shard_positions: Vec<(ShardId, Position)>,
-}
-
-impl Event for ShardPositionsUpdate {}
+impl IndexingTaskOutcome {
+    pub fn new(pipeline_uid: PipelineUid, status: IndexingTaskStatus) -> Self {
+        Self {
+            pipeline_uid: Some(pipeline_uid),
+            status: status as i32,
+            error_message: None,
+        }
+    }
 
-impl RpcName for ApplyIndexingPlanRequest {
-    fn rpc_name() -> &'static str {
-        "apply_indexing_plan"
+    pub fn failed(pipeline_uid: PipelineUid, error_message: impl Into<String>) -> Self {
+        Self {
+            pipeline_uid: Some(pipeline_uid),
+            status: IndexingTaskStatus::Failed as i32,
+            error_message: Some(error_message.into()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use prost::Message;
+
     use super::*;
 
+    fn indexing_task(pipeline_ord: u128, shard_ids: impl Iterator<Item = u64>) -> IndexingTask {
+        IndexingTask {
+            index_uid: Some(IndexUid::for_test("test-index", 0)),
+            source_id: "test-source".to_string(),
+            pipeline_uid: Some(PipelineUid::for_test(pipeline_ord)),
+            shard_ids: shard_ids.map(ShardId::from).collect(),
+            ..Default::default()
+        }
+    }
+
     #[test]
-    fn test_cpu_capacity_serialization() {
-        assert_eq!(CpuCapacity::from_str("2000m").unwrap(), mcpu(2000));
-        assert_eq!(CpuCapacity::from_cpu_millis(2500), mcpu(2500));
-        assert_eq!(
-            CpuCapacity::from_str("2.5").unwrap_err(),
-            "invalid cpu capacity: `2.5`. String format expects a trailing 'm'."
-        );
-        assert_eq!(
-            serde_json::from_value::<CpuCapacity>(serde_json::Value::String("1200m".to_string()))
-                .unwrap(),
-            mcpu(1200)
-        );
-        assert_eq!(
-            serde_json::from_value::<CpuCapacity>(serde_json::Value::Number(
-                serde_json::Number::from_f64(1.2f64).unwrap()
-            ))
-            .unwrap(),
-            mcpu(1200)
-        );
+    fn test_apply_indexing_plan_request_shard_deltas() {
+        let base_plan = AppliedIndexingPlan {
+            plan_generation: 1,
+            indexing_tasks: vec![indexing_task(0, 0..10), indexing_task(1, 10..12)],
+        };
+        let indexing_tasks = vec![
+            // One shard removed, two added.
+            indexing_task(0, (1..10).chain(20..22)),
+            // The delta would be longer than the full list.
+            indexing_task(1, 30..32),
+            // A task missing from the base plan.
+            indexing_task(2, 40..45),
+        ];
+        let mut request =
+            ApplyIndexingPlanRequest::new("control-plane", 2, &indexing_tasks, Some(&base_plan));
+        assert!(request.is_delta_encoded());
+        assert_eq!(request.base_plan_generation, Some(1));
+        assert_eq!(request.shard_deltas.len(), 1);
+
+        let shard_delta = &request.shard_deltas[0];
+        assert_eq!(shard_delta.pipeline_uid(), PipelineUid::for_test(0));
         assert_eq!(
-            serde_json::from_value::<CpuCapacity>(serde_json::Value::Number(
-                serde_json::Number::from(1u32)
-            ))
-            .unwrap(),
-            mcpu(1000)
+            shard_delta.added_shard_ids,
+            [ShardId::from(20), ShardId::from(21)]
         );
-        assert_eq!(CpuCapacity::from_cpu_millis(2500).to_string(), "2500m");
-        assert_eq!(serde_json::to_string(&mcpu(2500)).unwrap(), "\"2500m\"");
+        assert_eq!(shard_delta.removed_shard_ids, [ShardId::from(0)]);
+        assert!(request.indexing_tasks[0].shard_ids.is_empty());
+        assert_eq!(request.indexing_tasks[1], indexing_tasks[1]);
+        assert_eq!(request.indexing_tasks[2], indexing_tasks[2]);
+
+        request.resolve_shard_deltas(Some(&base_plan)).unwrap();
+        assert!(!request.is_delta_encoded());
+        assert!(request.shard_deltas.is_empty());
+        assert_eq!(request.indexing_tasks, indexing_tasks);
+
+        let request = ApplyIndexingPlanRequest::new("control-plane", 2, &indexing_tasks, None);
+        assert!(!request.is_delta_encoded());
+        assert_eq!(request.indexing_tasks, indexing_tasks);
+    }
+
+    #[test]
+    fn test_apply_indexing_plan_request_resolve_shard_deltas_mismatch() {
+        let base_plan = AppliedIndexingPlan {
+            plan_generation: 1,
+            indexing_tasks: vec![indexing_task(0, 0..10)],
+        };
+        let indexing_tasks = vec![indexing_task(0, 1..10)];
+        let request =
+            ApplyIndexingPlanRequest::new("control-plane", 2, &indexing_tasks, Some(&base_plan));
+
+        let error = request.clone().resolve_shard_deltas(None).unwrap_err();
+        assert!(matches!(error, IndexingError::PlanGenerationMismatch(_)));
+        assert_eq!(error.error_code(), ServiceErrorCode::BadRequest);
+
+        let other_base_plan = AppliedIndexingPlan {
+            plan_generation: 0,
+            indexing_tasks: base_plan.indexing_tasks.clone(),
+        };
+        let error = request
+            .clone()
+            .resolve_shard_deltas(Some(&other_base_plan))
+            .unwrap_err();
+        assert!(matches!(error, IndexingError::PlanGenerationMismatch(_)));
+
+        let base_plan_without_task = AppliedIndexingPlan {
+            plan_generation: 1,
+            indexing_tasks: Vec::new(),
+        };
+        let error = request
+            .clone()
+            .resolve_shard_deltas(Some(&base_plan_without_task))
+            .unwrap_err();
+        assert!(matches!(error, IndexingError::PlanGenerationMismatch(_)));
+    }
+
+    #[test]
+    fn test_apply_indexing_plan_request_size_with_shard_deltas() {
+        // 50k shards spread over 50 pipelines, 1% of them moved between two plans.
+        let num_pipelines: u64 = 50;
+        let num_shards_per_pipeline: u64 = 1_000;
+
+        let base_tasks: Vec<IndexingTask> = (0..num_pipelines)
+            .map(|pipeline_ord| {
+                let first_shard_id = pipeline_ord * num_shards_per_pipeline;
+                indexing_task(
+                    pipeline_ord as u128,
+                    first_shard_id..first_shard_id + num_shards_per_pipeline,
+                )
+            })
+            .collect();
+        let base_plan = AppliedIndexingPlan {
+            plan_generation: 1,
+            indexing_tasks: base_tasks,
+        };
+        let indexing_tasks: Vec<IndexingTask> = (0..num_pipelines)
+            .map(|pipeline_ord| {
+                let first_shard_id = pipeline_ord * num_shards_per_pipeline + 10;
+                indexing_task(
+                    pipeline_ord as u128,
+                    first_shard_id..first_shard_id + num_shards_per_pipeline,
+                )
+            })
+            .collect();
+        let full_request = ApplyIndexingPlanRequest::new("control-plane", 2, &indexing_tasks, None);
+        let mut delta_request =
+            ApplyIndexingPlanRequest::new("control-plane", 2, &indexing_tasks, Some(&base_plan));
+
+        let full_request_len = full_request.encoded_len();
+        let delta_request_len = delta_request.encoded_len();
+        assert!(full_request_len > 1_000_000);
+        assert!(delta_request_len * 20 < full_request_len);
+
+        delta_request
+            .resolve_shard_deltas(Some(&base_plan))
+            .unwrap();
+        assert_eq!(delta_request, full_request);
     }
 }
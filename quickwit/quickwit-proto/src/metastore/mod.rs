@@ -21,7 +21,7 @@ use std::fmt;
 
 use quickwit_common::rate_limited_error;
 use quickwit_common::retry::Retryable;
-use quickwit_common::tower::MakeLoadShedError;
+use quickwit_common::tower::{MakeLoadShedError, TimeoutExceeded};
 use serde::{Deserialize, Serialize};
 
 use crate::types::{IndexId, IndexUid, QueueId, SourceId, SplitId};
@@ -189,6 +189,12 @@ impl From<sqlx::Error> for MetastoreError {
     }
 }
 
+impl From<TimeoutExceeded> for MetastoreError {
+    fn from(_timeout_exceeded: TimeoutExceeded) -> Self {
+        Self::Timeout("tower layer timeout".to_string())
+    }
+}
+
 impl ServiceError for MetastoreError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
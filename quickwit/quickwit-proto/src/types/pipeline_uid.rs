@@ -21,33 +21,140 @@ use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
 
-use super::ULID_SIZE;
+use super::{NodeIdRef, ULID_SIZE};
+
+// Layout of the pipeline UIDs generated by `PipelineUid::random`, from the most significant bits:
+// - 48 bits: creation timestamp in milliseconds since the Unix epoch;
+// - 8 bits: layout tag, which tells these UIDs apart from the legacy fully random ones;
+// - 16 bits: hint of the node that generated the UID;
+// - 56 bits: random bits, incremented for UIDs generated within the same millisecond.
+const LAYOUT_TAG: u8 = 0x51;
+const TIMESTAMP_SHIFT: u32 = 80;
+const LAYOUT_TAG_SHIFT: u32 = 72;
+const NODE_HINT_SHIFT: u32 = 56;
+const TIMESTAMP_MASK: u64 = (1 << 48) - 1;
+const RANDOM_MASK: u64 = (1 << 56) - 1;
+
+/// Hint of the node generating pipeline UIDs, set once on startup.
+static NODE_HINT: AtomicU16 = AtomicU16::new(0);
+
+/// Last (timestamp, random) pair generated by this process, used to keep UIDs monotonic.
+static LAST_GENERATED: Mutex<(u64, u64)> = Mutex::new((0, 0));
 
 /// A pipeline UID identifies an indexing pipeline and an indexing task.
+///
+/// Pipeline UIDs are ULIDs that embed their creation time and a hint of the node that created
+/// them. UIDs created by older versions are fully random: they remain valid, but
+/// [`PipelineUid::created_at`] and [`PipelineUid::node_hint`] return `None` for them.
 #[derive(Clone, Copy, Default, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct PipelineUid(Ulid);
 
 impl fmt::Debug for PipelineUid {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Pipeline({})", self.0)
+        match (self.created_at_millis(), self.node_hint()) {
+            (Some(created_at_millis), Some(node_hint)) => write!(
+                f,
+                "Pipeline({}, created_at_ms={created_at_millis}, node_hint={node_hint:04x})",
+                self.0
+            ),
+            _ => write!(f, "Pipeline({})", self.0),
+        }
     }
 }
 
+/// Formats the pipeline UID as a ULID. The alternate form (`{:#}`) is a short form intended for
+/// logs: the node hint, if any, followed by the [short ID](PipelineUid::short_id).
 impl Display for PipelineUid {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        self.0.fmt(f)
+        if !f.alternate() {
+            return self.0.fmt(f);
+        }
+        if let Some(node_hint) = self.node_hint() {
+            write!(f, "{node_hint:04x}-{}", self.short_id())
+        } else {
+            write!(f, "{}", self.short_id())
+        }
     }
 }
 
 impl PipelineUid {
-    /// Creates a new random pipeline UID.
+    /// Creates a new pipeline UID embedding the current time and the node hint.
+    ///
+    /// The UIDs created by a process are strictly increasing.
     pub fn random() -> Self {
-        Self(Ulid::new())
+        let now_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default()
+            & TIMESTAMP_MASK;
+        let random_bits = Ulid::new().random() as u64 & RANDOM_MASK;
+
+        let mut last_generated = LAST_GENERATED.lock().expect("lock should not be poisoned");
+        let (last_timestamp_millis, last_random_bits) = *last_generated;
+
+        let (timestamp_millis, random_bits) = if now_millis > last_timestamp_millis {
+            (now_millis, random_bits)
+        } else if last_random_bits < RANDOM_MASK {
+            (last_timestamp_millis, last_random_bits + 1)
+        } else {
+            // The random bits overflowed: we borrow the next millisecond.
+            (last_timestamp_millis + 1, random_bits)
+        };
+        *last_generated = (timestamp_millis, random_bits);
+        drop(last_generated);
+
+        let node_hint = NODE_HINT.load(Ordering::Relaxed);
+        Self::from_parts(timestamp_millis, node_hint, random_bits)
+    }
+
+    /// Sets the hint of the node embedded in the pipeline UIDs created by this process from
+    /// now on. The hint is a 16-bit hash of the node ID.
+    pub fn set_node_hint(node_id: &NodeIdRef) {
+        NODE_HINT.store(compute_node_hint(node_id), Ordering::Relaxed);
+    }
+
+    fn from_parts(timestamp_millis: u64, node_hint: u16, random_bits: u64) -> Self {
+        let ulid_u128 = ((timestamp_millis & TIMESTAMP_MASK) as u128) << TIMESTAMP_SHIFT
+            | (LAYOUT_TAG as u128) << LAYOUT_TAG_SHIFT
+            | (node_hint as u128) << NODE_HINT_SHIFT
+            | (random_bits & RANDOM_MASK) as u128;
+        Self(Ulid::from(ulid_u128))
+    }
+
+    fn has_embedded_metadata(&self) -> bool {
+        let ulid_u128 = u128::from(self.0);
+        (ulid_u128 >> LAYOUT_TAG_SHIFT) as u8 == LAYOUT_TAG && self.0.timestamp_ms() != 0
+    }
+
+    fn created_at_millis(&self) -> Option<u64> {
+        self.has_embedded_metadata().then(|| self.0.timestamp_ms())
+    }
+
+    /// Returns the time at which the pipeline UID was created, or `None` for legacy UIDs.
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at_millis()
+            .map(|created_at_millis| UNIX_EPOCH + Duration::from_millis(created_at_millis))
+    }
+
+    /// Returns the hint of the node that created the pipeline UID, or `None` for legacy UIDs.
+    pub fn node_hint(&self) -> Option<u16> {
+        self.has_embedded_metadata()
+            .then(|| (u128::from(self.0) >> NODE_HINT_SHIFT) as u16)
+    }
+
+    /// Returns the last 8 characters of the pipeline UID. These characters are drawn from the
+    /// random part of the ULID, so they are enough to tell the pipelines of a node apart in logs.
+    pub fn short_id(&self) -> String {
+        let pipeline_uid_str = self.0.to_string();
+        pipeline_uid_str[pipeline_uid_str.len() - 8..].to_string()
     }
 
     #[cfg(any(test, feature = "testsuite"))]
@@ -56,12 +163,23 @@ impl PipelineUid {
     }
 }
 
+/// Folds a node ID into 16 bits with the FNV-1a hash function, which is stable across releases
+/// and platforms.
+fn compute_node_hint(node_id: &NodeIdRef) -> u16 {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in node_id.as_str().bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    ((hash >> 16) ^ (hash & 0xffff)) as u16
+}
+
 impl FromStr for PipelineUid {
     type Err = &'static str;
 
     fn from_str(pipeline_uid_str: &str) -> Result<PipelineUid, Self::Err> {
-        let pipeline_ulid =
-            Ulid::from_string(pipeline_uid_str).map_err(|_| "invalid pipeline UID")?;
+        let pipeline_ulid = Ulid::from_string(pipeline_uid_str.trim())
+            .map_err(|_| "invalid pipeline UID, expected a 26-character ULID")?;
         Ok(PipelineUid(pipeline_ulid))
     }
 }
@@ -74,9 +192,8 @@ impl Serialize for PipelineUid {
 
 impl<'de> Deserialize<'de> for PipelineUid {
     fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let ulid_str: Cow<'de, str> = Cow::deserialize(deserializer)?;
-        let ulid = Ulid::from_string(&ulid_str).map_err(D::Error::custom)?;
-        Ok(Self(ulid))
+        let pipeline_uid_str: Cow<'de, str> = Cow::deserialize(deserializer)?;
+        PipelineUid::from_str(&pipeline_uid_str).map_err(D::Error::custom)
     }
 }
 
@@ -152,6 +269,92 @@ mod tests {
         assert_eq!(deserialized, pipeline_uid);
     }
 
+    #[test]
+    fn test_pipeline_uid_short_id() {
+        let pipeline_uid: PipelineUid = "01HAV29D4XY3D462FS3D8K5Q2H".parse().unwrap();
+        assert_eq!(pipeline_uid.short_id(), "3D8K5Q2H");
+        assert_eq!(PipelineUid::default().short_id(), "00000000");
+    }
+
+    #[test]
+    fn test_pipeline_uid_random_is_monotonic() {
+        let mut previous_pipeline_uid = PipelineUid::random();
+
+        for _ in 0..10_000 {
+            let pipeline_uid = PipelineUid::random();
+            assert!(pipeline_uid > previous_pipeline_uid);
+            previous_pipeline_uid = pipeline_uid;
+        }
+    }
+
+    #[test]
+    fn test_pipeline_uid_created_at() {
+        let before = SystemTime::now() - Duration::from_millis(1);
+        let pipeline_uid = PipelineUid::random();
+        let created_at = pipeline_uid.created_at().unwrap();
+        assert!(created_at >= before);
+        assert!(pipeline_uid.node_hint().is_some());
+
+        let pipeline_uid = PipelineUid::from_parts(1_700_000_000_000, 0xab12, 42);
+        assert_eq!(
+            pipeline_uid.created_at().unwrap(),
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_000)
+        );
+        assert_eq!(pipeline_uid.node_hint(), Some(0xab12));
+        assert_eq!(
+            format!("{pipeline_uid:?}"),
+            format!("Pipeline({pipeline_uid}, created_at_ms=1700000000000, node_hint=ab12)")
+        );
+        assert_eq!(
+            format!("{pipeline_uid:#}"),
+            format!("ab12-{}", pipeline_uid.short_id())
+        );
+    }
+
+    #[test]
+    fn test_pipeline_uid_legacy() {
+        let pipeline_uid: PipelineUid = "01HAV29D4XY3D462FS3D8K5Q2H".parse().unwrap();
+        assert!(pipeline_uid.created_at().is_none());
+        assert!(pipeline_uid.node_hint().is_none());
+        assert_eq!(
+            format!("{pipeline_uid:?}"),
+            "Pipeline(01HAV29D4XY3D462FS3D8K5Q2H)"
+        );
+        assert_eq!(format!("{pipeline_uid:#}"), "3D8K5Q2H");
+        assert_eq!(pipeline_uid.to_string(), "01HAV29D4XY3D462FS3D8K5Q2H");
+
+        assert!(PipelineUid::default().created_at().is_none());
+    }
+
+    #[test]
+    fn test_pipeline_uid_str_roundtrip() {
+        let pipeline_uid = PipelineUid::random();
+        let pipeline_uid_str = pipeline_uid.to_string();
+        assert_eq!(pipeline_uid_str.len(), 26);
+
+        let parsed_pipeline_uid: PipelineUid = pipeline_uid_str.parse().unwrap();
+        assert_eq!(parsed_pipeline_uid, pipeline_uid);
+        assert_eq!(parsed_pipeline_uid.created_at(), pipeline_uid.created_at());
+
+        let parsed_pipeline_uid: PipelineUid = pipeline_uid_str.to_lowercase().parse().unwrap();
+        assert_eq!(parsed_pipeline_uid, pipeline_uid);
+
+        "3D8K5Q2H".parse::<PipelineUid>().unwrap_err();
+    }
+
+    #[test]
+    fn test_compute_node_hint() {
+        let node_hint = compute_node_hint(NodeIdRef::from_str("test-node"));
+        assert_eq!(
+            compute_node_hint(NodeIdRef::from_str("test-node")),
+            node_hint
+        );
+        assert_ne!(
+            compute_node_hint(NodeIdRef::from_str("other-node")),
+            node_hint
+        );
+    }
+
     #[test]
     fn test_pipeline_uid_prost_serde_roundtrip() {
         let pipeline_uid = PipelineUid::random();
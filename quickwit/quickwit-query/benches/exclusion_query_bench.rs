@@ -0,0 +1,112 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use quickwit_query::create_default_quickwit_tokenizer_manager;
+use quickwit_query::query_ast::query_ast_from_user_text;
+use tantivy::collector::Count;
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED};
+use tantivy::{doc, Index, IndexWriter, Searcher, Term};
+
+const NUM_DOCS: u64 = 1_000_000;
+
+fn build_searcher() -> Searcher {
+    let mut schema_builder = Schema::builder();
+    let status_field = schema_builder.add_u64_field("status", INDEXED | FAST);
+    let level_options = TextOptions::default()
+        .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"))
+        .set_fast(Some("raw"));
+    let level_field = schema_builder.add_text_field("level", level_options);
+    let schema = schema_builder.build();
+
+    let index = Index::create_in_ram(schema);
+    let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 100_000_000).unwrap();
+    // 90% of the documents are excluded by `NOT status:200` and `NOT level:INFO`.
+    for i in 0..NUM_DOCS {
+        let (status, level) = if i % 10 == 0 {
+            (500u64, "ERROR")
+        } else {
+            (200u64, "INFO")
+        };
+        index_writer
+            .add_document(doc!(status_field => status, level_field => level))
+            .unwrap();
+    }
+    index_writer.commit().unwrap();
+    index.reader().unwrap().searcher()
+}
+
+fn naive_exclusion_query(term: Term) -> BooleanQuery {
+    BooleanQuery::new(vec![
+        (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+        (
+            Occur::MustNot,
+            Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)),
+        ),
+    ])
+}
+
+fn exclusion_query(searcher: &Searcher, user_text: &str) -> Box<dyn Query> {
+    query_ast_from_user_text(user_text, None)
+        .parse_user_query(&[])
+        .unwrap()
+        .build_tantivy_query(
+            searcher.schema(),
+            &create_default_quickwit_tokenizer_manager(),
+            true,
+        )
+        .unwrap()
+}
+
+pub fn exclusion_query_benchmark(c: &mut Criterion) {
+    let searcher = build_searcher();
+    let schema = searcher.schema().clone();
+    let status_field = schema.get_field("status").unwrap();
+    let level_field = schema.get_field("level").unwrap();
+
+    let mut group = c.benchmark_group("exclusion_query");
+    for (field_name, user_text, naive_query) in [
+        (
+            "u64",
+            "NOT status:200",
+            naive_exclusion_query(Term::from_field_u64(status_field, 200)),
+        ),
+        (
+            "str",
+            "NOT level:INFO",
+            naive_exclusion_query(Term::from_field_text(level_field, "INFO")),
+        ),
+    ] {
+        let query = exclusion_query(&searcher, user_text);
+        assert_eq!(
+            searcher.search(&*query, &Count).unwrap(),
+            searcher.search(&naive_query, &Count).unwrap()
+        );
+        group.bench_function(format!("naive-{field_name}"), |b| {
+            b.iter(|| searcher.search(black_box(&naive_query), &Count).unwrap());
+        });
+        group.bench_function(format!("columnar-{field_name}"), |b| {
+            b.iter(|| searcher.search(black_box(&*query), &Count).unwrap());
+        });
+    }
+}
+
+criterion_group!(exclusion_query_benches, exclusion_query_benchmark);
+criterion_main!(exclusion_query_benches);
@@ -0,0 +1,161 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tantivy::query::{AutomatonWeight, EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::schema::Field;
+use tantivy::{DocId, Score, SegmentReader, TantivyError};
+use tantivy_fst::Regex;
+
+/// A tantivy query matching the documents containing a term accepted by a regular expression,
+/// that fails if the regular expression matches more than `max_expansions` terms in a segment.
+///
+/// Unlike `tantivy::query::RegexQuery`, this query bounds the number of terms whose postings are
+/// read, so that a pattern matching a large fraction of a high-cardinality field returns an error
+/// instead of loading all the postings of the field.
+///
+/// The query can also be given a timeout, measured from the creation of its weight, i.e. for the
+/// search of a split. Going over it fails the search of the split.
+#[derive(Clone)]
+pub(crate) struct MaxExpansionsRegexQuery {
+    field: Field,
+    field_name: String,
+    // Either `wildcard` or `regex`. Only used in error messages.
+    query_type: &'static str,
+    pattern: String,
+    regex: Arc<Regex>,
+    max_expansions: u32,
+    timeout_opt: Option<Duration>,
+}
+
+impl fmt::Debug for MaxExpansionsRegexQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MaxExpansionsRegexQuery")
+            .field("field", &self.field_name)
+            .field("query_type", &self.query_type)
+            .field("pattern", &self.pattern)
+            .field("max_expansions", &self.max_expansions)
+            .field("timeout", &self.timeout_opt)
+            .finish()
+    }
+}
+
+impl MaxExpansionsRegexQuery {
+    /// `pattern` is the user facing pattern the regex was derived from. It is only used in error
+    /// messages.
+    pub fn new(
+        field: Field,
+        field_name: String,
+        query_type: &'static str,
+        pattern: String,
+        regex: Regex,
+        max_expansions: u32,
+    ) -> Self {
+        Self {
+            field,
+            field_name,
+            query_type,
+            pattern,
+            regex: Arc::new(regex),
+            max_expansions,
+            timeout_opt: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_opt = Some(timeout);
+        self
+    }
+}
+
+impl Query for MaxExpansionsRegexQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let automaton_weight = AutomatonWeight::new(self.field, self.regex.clone());
+        let deadline_opt = self.timeout_opt.map(|timeout| Instant::now() + timeout);
+        Ok(Box::new(MaxExpansionsRegexWeight {
+            query: self.clone(),
+            automaton_weight,
+            deadline_opt,
+        }))
+    }
+}
+
+struct MaxExpansionsRegexWeight {
+    query: MaxExpansionsRegexQuery,
+    automaton_weight: AutomatonWeight<Regex>,
+    deadline_opt: Option<Instant>,
+}
+
+impl MaxExpansionsRegexWeight {
+    fn check_deadline(&self) -> tantivy::Result<()> {
+        let (Some(deadline), Some(timeout)) = (self.deadline_opt, self.query.timeout_opt) else {
+            return Ok(());
+        };
+        if Instant::now() > deadline {
+            return Err(TantivyError::InvalidArgument(format!(
+                "{} query `{}` on field `{}` exceeded its evaluation timeout of {}ms on this split",
+                self.query.query_type,
+                self.query.pattern,
+                self.query.field_name,
+                timeout.as_millis()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_num_expansions(&self, reader: &SegmentReader) -> tantivy::Result<()> {
+        let inverted_index = reader.inverted_index(self.query.field)?;
+        let term_dict = inverted_index.terms();
+        let mut term_stream = term_dict.search(self.query.regex.as_ref()).into_stream()?;
+        let mut num_expansions: u32 = 0;
+
+        while term_stream.advance() {
+            num_expansions += 1;
+
+            if num_expansions > self.query.max_expansions {
+                return Err(TantivyError::InvalidArgument(format!(
+                    "{} query `{}` on field `{}` matches more than {} terms. use a more selective \
+                     pattern or query a field with fewer distinct values",
+                    self.query.query_type,
+                    self.query.pattern,
+                    self.query.field_name,
+                    self.query.max_expansions
+                )));
+            }
+            self.check_deadline()?;
+        }
+        self.check_deadline()
+    }
+}
+
+impl Weight for MaxExpansionsRegexWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        self.check_num_expansions(reader)?;
+        let scorer = self.automaton_weight.scorer(reader, boost)?;
+        self.check_deadline()?;
+        Ok(scorer)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.automaton_weight.explain(reader, doc)
+    }
+}
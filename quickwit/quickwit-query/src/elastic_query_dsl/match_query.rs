@@ -17,12 +17,12 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 use crate::elastic_query_dsl::{
     ConvertibleToQueryAst, ElasticQueryDslInner, StringOrStructForSerialization,
 };
-use crate::query_ast::{FullTextParams, FullTextQuery, QueryAst};
+use crate::query_ast::{FullTextMode, FullTextParams, FullTextQuery, MinimumShouldMatch, QueryAst};
 use crate::{BooleanOperand, MatchAllOrNone, OneFieldMap};
 
 /// `MatchQuery` as defined in
@@ -42,6 +42,8 @@ pub(crate) struct MatchQueryParams {
     pub(crate) operator: BooleanOperand,
     #[serde(default)]
     pub(crate) zero_terms_query: MatchAllOrNone,
+    #[serde(default, deserialize_with = "deserialize_minimum_should_match")]
+    pub(crate) minimum_should_match: Option<MinimumShouldMatch>,
     // Quickwit and Elastic have different notions of lenient. For us, it means it's okay to
     // disregard part of the query where which uses non-existing collumn (which Elastic does by
     // default). For Elastic, it covers type errors (searching text in an integer field).
@@ -49,11 +51,40 @@ pub(crate) struct MatchQueryParams {
     pub(crate) lenient: bool,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MinimumShouldMatchForDeserialization {
+    Int(i32),
+    Str(String),
+}
+
+// Elasticsearch accepts integers (`2`, `-1`) as well as strings (`"2"`, `"75%"`, `"-25%"`).
+// Combinations such as `"3<90%"` are not supported.
+pub(crate) fn deserialize_minimum_should_match<'de, D>(
+    deserializer: D,
+) -> Result<Option<MinimumShouldMatch>, D::Error>
+where D: Deserializer<'de> {
+    match MinimumShouldMatchForDeserialization::deserialize(deserializer)? {
+        MinimumShouldMatchForDeserialization::Int(count) => {
+            Ok(Some(MinimumShouldMatch::Count(count)))
+        }
+        MinimumShouldMatchForDeserialization::Str(minimum_should_match_str) => {
+            let minimum_should_match = minimum_should_match_str
+                .parse()
+                .map_err(serde::de::Error::custom)?;
+            Ok(Some(minimum_should_match))
+        }
+    }
+}
+
 impl ConvertibleToQueryAst for MatchQuery {
     fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
         let full_text_params = FullTextParams {
             tokenizer: None,
-            mode: self.params.operator.into(),
+            mode: FullTextMode::Bool {
+                operator: self.params.operator,
+                minimum_should_match: self.params.minimum_should_match,
+            },
             zero_terms_query: self.params.zero_terms_query,
         };
         Ok(QueryAst::FullText(FullTextQuery {
@@ -89,6 +120,7 @@ impl From<String> for MatchQueryParams {
             query,
             zero_terms_query: Default::default(),
             operator: Default::default(),
+            minimum_should_match: None,
             lenient: false,
         }
     }
@@ -138,6 +170,7 @@ mod tests {
                 query: "hello".to_string(),
                 operator: BooleanOperand::And,
                 zero_terms_query: crate::MatchAllOrNone::MatchAll,
+                minimum_should_match: None,
                 lenient: false,
             },
         };
@@ -156,9 +189,37 @@ mod tests {
         assert_eq!(
             params.mode,
             FullTextMode::Bool {
-                operator: BooleanOperand::And
+                operator: BooleanOperand::And,
+                minimum_should_match: None,
             }
         );
         assert_eq!(params.zero_terms_query, MatchAllOrNone::MatchAll);
     }
+
+    #[test]
+    fn test_deserialize_match_query_minimum_should_match() {
+        let match_query: MatchQuery = serde_json::from_str(
+            r#"{"my_field": {"query": "my query", "minimum_should_match": 2}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            match_query.params.minimum_should_match,
+            Some(MinimumShouldMatch::Count(2))
+        );
+        let match_query: MatchQuery = serde_json::from_str(
+            r#"{"my_field": {"query": "my query", "minimum_should_match": "-25%"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            match_query.params.minimum_should_match,
+            Some(MinimumShouldMatch::Percentage(-25))
+        );
+        let deser_error = serde_json::from_str::<MatchQuery>(
+            r#"{"my_field": {"query": "my query", "minimum_should_match": "3<90%"}}"#,
+        )
+        .unwrap_err();
+        assert!(deser_error
+            .to_string()
+            .contains("invalid `minimum_should_match` value `3<90%`"));
+    }
 }
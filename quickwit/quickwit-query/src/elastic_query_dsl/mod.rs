@@ -29,6 +29,7 @@ mod one_field_map;
 mod phrase_prefix_query;
 mod query_string_query;
 mod range_query;
+mod regexp_query;
 mod string_or_struct;
 mod term_query;
 mod terms_query;
@@ -38,6 +39,7 @@ pub use one_field_map::OneFieldMap;
 use phrase_prefix_query::MatchPhrasePrefixQuery;
 pub(crate) use query_string_query::QueryStringQuery;
 use range_query::RangeQuery;
+use regexp_query::RegexpQuery;
 pub(crate) use string_or_struct::StringOrStructForSerialization;
 use term_query::TermQuery;
 
@@ -48,7 +50,7 @@ use crate::elastic_query_dsl::match_query::MatchQuery;
 use crate::elastic_query_dsl::multi_match::MultiMatchQuery;
 use crate::elastic_query_dsl::terms_query::TermsQuery;
 use crate::not_nan_f32::NotNaNf32;
-use crate::query_ast::QueryAst;
+use crate::query_ast::{QueryAst, QueryAstLimits};
 
 fn default_max_expansions() -> u32 {
     50
@@ -78,6 +80,7 @@ pub(crate) enum ElasticQueryDslInner {
     MatchPhrasePrefix(MatchPhrasePrefixQuery),
     MultiMatch(MultiMatchQuery),
     Range(RangeQuery),
+    Regexp(RegexpQuery),
     Exists(ExistsQuery),
 }
 
@@ -89,7 +92,9 @@ impl TryFrom<ElasticQueryDsl> for QueryAst {
     type Error = anyhow::Error;
 
     fn try_from(es_dsl: ElasticQueryDsl) -> anyhow::Result<Self> {
-        es_dsl.0.convert_to_query_ast()
+        let query_ast = es_dsl.0.convert_to_query_ast()?;
+        QueryAstLimits::default().check(&query_ast)?;
+        Ok(query_ast)
     }
 }
 
@@ -123,6 +128,7 @@ impl ConvertibleToQueryAst for ElasticQueryDslInner {
                 match_phrase_prefix.convert_to_query_ast()
             }
             Self::Range(range_query) => range_query.convert_to_query_ast(),
+            Self::Regexp(regexp_query) => regexp_query.convert_to_query_ast(),
             Self::Match(match_query) => match_query.convert_to_query_ast(),
             Self::Exists(exists_query) => exists_query.convert_to_query_ast(),
             Self::MultiMatch(multi_match_query) => multi_match_query.convert_to_query_ast(),
@@ -151,4 +157,18 @@ mod tests {
             &term_query_from_field_value("product_id", "61809")
         );
     }
+
+    #[test]
+    fn test_query_dsl_clause_count_limit() {
+        let values: Vec<String> = (0..=crate::query_ast::DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT)
+            .map(|i| format!("\"{i}\""))
+            .collect();
+        let terms_query_json = format!(r#"{{"terms": {{"user.id": [{}]}}}}"#, values.join(","));
+        let query_dsl: ElasticQueryDsl = serde_json::from_str(&terms_query_json).unwrap();
+        let error = QueryAst::try_from(query_dsl).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "query exceeds the `max_query_ast_clause_count` limit of 1024"
+        );
+    }
 }
@@ -21,20 +21,29 @@ use serde::Deserialize;
 use serde_with::formats::PreferMany;
 use serde_with::{serde_as, OneOrMany};
 
-use crate::elastic_query_dsl::bool_query::BoolQuery;
 use crate::elastic_query_dsl::match_bool_prefix::MatchBoolPrefixQuery;
 use crate::elastic_query_dsl::match_phrase_query::{MatchPhraseQuery, MatchPhraseQueryParams};
 use crate::elastic_query_dsl::match_query::{MatchQuery, MatchQueryParams};
-use crate::elastic_query_dsl::phrase_prefix_query::{
-    MatchPhrasePrefixQuery, MatchPhrasePrefixQueryParams,
-};
 use crate::elastic_query_dsl::{ConvertibleToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{BoolQuery, DisMaxQuery, QueryAst};
 
-/// Multi match queries are a bit odd. They end up being expanded into another type of query.
+/// Multi match queries are a bit odd. They end up being expanded into one query per field.
 /// In Quickwit, we operate this expansion in generic way at the time of deserialization.
+///
+/// The per-field queries are then combined according to the type of the multi match query:
+/// - `best_fields` combines them in a dis max query, so that the score of a document is the score
+///   of its best matching field, plus `tie_breaker` times the scores of the other matching fields.
+/// - `most_fields` combines them in a boolean union, so that the scores of the matching fields add
+///   up.
 #[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(try_from = "MultiMatchQueryForDeserialization")]
-pub struct MultiMatchQuery(Box<ElasticQueryDslInner>);
+pub struct MultiMatchQuery {
+    match_type: MatchType,
+    // One query per field, along with the boost of the field.
+    field_queries: Vec<(ElasticQueryDslInner, Option<NotNaNf32>)>,
+    tie_breaker: Option<NotNaNf32>,
+}
 
 #[serde_as]
 #[derive(Deserialize, Debug, Eq, PartialEq, Clone)]
@@ -48,6 +57,8 @@ struct MultiMatchQueryForDeserialization {
     #[serde_as(deserialize_as = "OneOrMany<_, PreferMany>")]
     #[serde(default)]
     fields: Vec<String>,
+    #[serde(default)]
+    tie_breaker: Option<NotNaNf32>,
     // Quickwit and Elastic have different notions of lenient. For us, it means it's okay to
     // disregard part of the query where which uses non-existing collumn (which Elastic does by
     // default). For Elastic, it covers type errors (searching text in an integer field).
@@ -70,15 +81,9 @@ fn deserialize_match_query_for_one_field(
             };
             Ok(ElasticQueryDslInner::MatchPhrase(phrase_query))
         }
-        MatchType::PhrasePrefix => {
-            let phrase_prefix_params: MatchPhrasePrefixQueryParams =
-                serde_json::from_value(json_val)?;
-            let phrase_prefix = MatchPhrasePrefixQuery {
-                field: field.to_string(),
-                value: phrase_prefix_params,
-            };
-            Ok(ElasticQueryDslInner::MatchPhrasePrefix(phrase_prefix))
-        }
+        MatchType::PhrasePrefix => Err(serde::de::Error::custom(
+            "Quickwit does not support multi match queries of type `phrase_prefix`",
+        )),
         MatchType::BoolPrefix => {
             let bool_prefix_params: MatchQueryParams = serde_json::from_value(json_val)?;
             let bool_prefix = MatchBoolPrefixQuery {
@@ -98,13 +103,22 @@ fn deserialize_match_query_for_one_field(
     }
 }
 
+/// Splits a field of the `fields` array into its name and its boost (`title^3`).
+fn parse_field_and_boost(field_with_boost: &str) -> Result<(&str, Option<NotNaNf32>), String> {
+    let Some((field_name, boost_str)) = field_with_boost.split_once('^') else {
+        return Ok((field_with_boost, None));
+    };
+    let boost = boost_str
+        .parse::<f32>()
+        .ok()
+        .and_then(|boost| NotNaNf32::try_from(boost).ok())
+        .ok_or_else(|| {
+            format!("invalid boost in the multi match query fields (got `{field_with_boost}`)")
+        })?;
+    Ok((field_name, Some(boost)))
+}
+
 fn validate_field_name(field_name: &str) -> Result<(), String> {
-    if field_name.contains('^') {
-        return Err(format!(
-            "Quickwit does not support field boosting in the multi match query fields (got `{}`)",
-            field_name
-        ));
-    }
     if field_name.contains('*') {
         return Err(format!(
             "Quickwit does not support wildcards in the multi match query fields (got `{}`)",
@@ -125,22 +139,24 @@ impl TryFrom<MultiMatchQueryForDeserialization> for MultiMatchQuery {
                  must have at least one field.",
             ));
         }
-        for field in &multi_match_query.fields {
+        let mut field_queries = Vec::with_capacity(multi_match_query.fields.len());
+
+        for field_with_boost in &multi_match_query.fields {
+            let (field, boost_opt) =
+                parse_field_and_boost(field_with_boost).map_err(serde::de::Error::custom)?;
             validate_field_name(field).map_err(serde::de::Error::custom)?;
-        }
-        let mut children = Vec::new();
-        for field in multi_match_query.fields {
-            let child = deserialize_match_query_for_one_field(
+            let field_query = deserialize_match_query_for_one_field(
                 multi_match_query.match_type,
-                &field,
+                field,
                 multi_match_query.other_parameters.clone(),
             )?;
-            children.push(child);
+            field_queries.push((field_query, boost_opt));
         }
-        let bool_query = BoolQuery::union(children);
-        Ok(MultiMatchQuery(Box::new(ElasticQueryDslInner::Bool(
-            bool_query,
-        ))))
+        Ok(MultiMatchQuery {
+            match_type: multi_match_query.match_type,
+            field_queries,
+            tie_breaker: multi_match_query.tie_breaker,
+        })
     }
 }
 
@@ -149,30 +165,67 @@ impl TryFrom<MultiMatchQueryForDeserialization> for MultiMatchQuery {
 pub enum MatchType {
     #[default]
     MostFields,
-    BestFields,  // Not implemented will be converted to MostFields
+    BestFields,
     CrossFields, // Not implemented will be converted to MostFields
     Phrase,
-    PhrasePrefix,
+    PhrasePrefix, // Not supported
     BoolPrefix,
 }
 
 impl ConvertibleToQueryAst for MultiMatchQuery {
-    fn convert_to_query_ast(self) -> anyhow::Result<crate::query_ast::QueryAst> {
-        self.0.convert_to_query_ast()
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let mut field_query_asts = Vec::with_capacity(self.field_queries.len());
+
+        for (field_query, boost_opt) in self.field_queries {
+            let field_query_ast = field_query.convert_to_query_ast()?.boost(boost_opt);
+            field_query_asts.push(field_query_ast);
+        }
+        if self.match_type == MatchType::BestFields {
+            let dis_max_query = DisMaxQuery {
+                disjuncts: field_query_asts,
+                tie_breaker: self.tie_breaker.unwrap_or(NotNaNf32::ZERO),
+            };
+            return Ok(dis_max_query.into());
+        }
+        let bool_query = BoolQuery {
+            should: field_query_asts,
+            ..Default::default()
+        };
+        Ok(bool_query.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use tantivy::collector::TopDocs;
+    use tantivy::schema::{Schema as TantivySchema, TEXT};
+    use tantivy::{doc, DocAddress, Index, Score};
+
     use super::*;
-    use crate::elastic_query_dsl::default_max_expansions;
+    use crate::create_default_quickwit_tokenizer_manager;
+    use crate::elastic_query_dsl::ElasticQueryDsl;
+
+    fn match_query(field: &str, query: &str) -> ElasticQueryDslInner {
+        MatchQuery {
+            field: field.to_string(),
+            params: MatchQueryParams {
+                query: query.to_string(),
+                operator: crate::BooleanOperand::Or,
+                zero_terms_query: Default::default(),
+                minimum_should_match: None,
+                lenient: false,
+            },
+        }
+        .into()
+    }
 
     #[track_caller]
-    fn test_multimatch_query_ok_aux<T: Into<ElasticQueryDslInner>>(json: &str, expected: T) {
-        let expected: ElasticQueryDslInner = expected.into();
+    fn test_multimatch_query_ok_aux(
+        json: &str,
+        expected_field_queries: Vec<(ElasticQueryDslInner, Option<NotNaNf32>)>,
+    ) {
         let multi_match_query: MultiMatchQuery = serde_json::from_str(json).unwrap();
-        let es_query = &*multi_match_query.0;
-        assert_eq!(es_query, &expected);
+        assert_eq!(multi_match_query.field_queries, expected_field_queries);
     }
 
     #[track_caller]
@@ -185,186 +238,275 @@ mod tests {
 
     #[test]
     fn test_multimatch_query_deserialization() {
+        for match_type in ["most_fields", "best_fields", "cross_fields"] {
+            test_multimatch_query_ok_aux(
+                &format!(
+                    r#"{{
+                        "query": "quick brown fox",
+                        "type": "{match_type}",
+                        "fields": ["title", "body"]
+                    }}"#
+                ),
+                vec![
+                    (match_query("title", "quick brown fox"), None),
+                    (match_query("body", "quick brown fox"), None),
+                ],
+            );
+        }
         test_multimatch_query_ok_aux(
             r#"{
                 "query": "quick brown fox",
-                "type": "most_fields",
+                "type": "phrase",
                 "fields": ["title", "body"]
             }"#,
-            BoolQuery::union(vec![
-                MatchQuery {
-                    field: "title".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-                MatchQuery {
-                    field: "body".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-            ]),
+            ["title", "body"]
+                .into_iter()
+                .map(|field| {
+                    let match_phrase_query: ElasticQueryDslInner = MatchPhraseQuery {
+                        field: field.to_string(),
+                        params: MatchPhraseQueryParams {
+                            query: "quick brown fox".to_string(),
+                            zero_terms_query: Default::default(),
+                            analyzer: None,
+                            slop: Default::default(),
+                        },
+                    }
+                    .into();
+                    (match_phrase_query, None)
+                })
+                .collect(),
         );
-
         test_multimatch_query_ok_aux(
             r#"{
-            "query": "quick brown fox",
-            "type": "best_fields",
-            "fields": ["title", "body"]
-        }"#,
-            BoolQuery::union(vec![
-                MatchQuery {
-                    field: "title".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-                MatchQuery {
-                    field: "body".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-            ]),
+                "query": "quick brown",
+                "type": "bool_prefix",
+                "fields": ["title", "body"]
+            }"#,
+            ["title", "body"]
+                .into_iter()
+                .map(|field| {
+                    let match_bool_prefix_query: ElasticQueryDslInner = MatchBoolPrefixQuery {
+                        field: field.to_string(),
+                        params: MatchQueryParams {
+                            query: "quick brown".to_string(),
+                            operator: crate::BooleanOperand::Or,
+                            zero_terms_query: Default::default(),
+                            minimum_should_match: None,
+                            lenient: false,
+                        },
+                    }
+                    .into();
+                    (match_bool_prefix_query, None)
+                })
+                .collect(),
         );
+    }
 
-        test_multimatch_query_ok_aux(
+    #[test]
+    fn test_multimatch_query_deserialization_with_params() {
+        let multi_match_query: MultiMatchQuery = serde_json::from_str(
             r#"{
-            "query": "quick brown fox",
-            "type": "cross_fields",
-            "fields": ["title", "body"]
-        }"#,
-            BoolQuery::union(vec![
-                MatchQuery {
-                    field: "title".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-                MatchQuery {
-                    field: "body".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown fox".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-            ]),
+                "query": "quick brown fox",
+                "type": "best_fields",
+                "fields": ["title^3", "body^0.5", "summary"],
+                "operator": "AND",
+                "minimum_should_match": "75%",
+                "tie_breaker": 0.3
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(multi_match_query.match_type, MatchType::BestFields);
+        assert_eq!(
+            multi_match_query.tie_breaker,
+            Some(NotNaNf32::try_from(0.3).unwrap())
         );
+        let field_boosts: Vec<Option<NotNaNf32>> = multi_match_query
+            .field_queries
+            .iter()
+            .map(|(_, boost_opt)| *boost_opt)
+            .collect();
+        assert_eq!(
+            field_boosts,
+            [
+                Some(NotNaNf32::try_from(3.0).unwrap()),
+                Some(NotNaNf32::try_from(0.5).unwrap()),
+                None
+            ]
+        );
+        let ElasticQueryDslInner::Match(match_query) = &multi_match_query.field_queries[0].0 else {
+            panic!("expected a match query");
+        };
+        assert_eq!(match_query.field, "title");
+        assert_eq!(match_query.params.operator, crate::BooleanOperand::And);
+        assert_eq!(
+            match_query.params.minimum_should_match,
+            Some(crate::query_ast::MinimumShouldMatch::Percentage(75))
+        );
+    }
 
-        test_multimatch_query_ok_aux(
+    #[test]
+    fn test_multimatch_query_convert_to_query_ast() {
+        let best_fields_query: MultiMatchQuery = serde_json::from_str(
             r#"{
-            "query": "quick brown fox",
-            "type": "phrase",
-            "fields": ["title", "body"]
-        }"#,
-            BoolQuery::union(vec![
-                MatchPhraseQuery {
-                    field: "title".to_string(),
-                    params: MatchPhraseQueryParams {
-                        query: "quick brown fox".to_string(),
-                        zero_terms_query: Default::default(),
-                        analyzer: None,
-                        slop: Default::default(),
-                    },
-                }
-                .into(),
-                MatchPhraseQuery {
-                    field: "body".to_string(),
-                    params: MatchPhraseQueryParams {
-                        query: "quick brown fox".to_string(),
-                        zero_terms_query: Default::default(),
-                        analyzer: None,
-                        slop: Default::default(),
-                    },
-                }
-                .into(),
-            ]),
-        );
+                "query": "quick",
+                "type": "best_fields",
+                "fields": ["title^2", "body"],
+                "tie_breaker": 0.5
+            }"#,
+        )
+        .unwrap();
+        let QueryAst::DisMax(dis_max_query) = best_fields_query.convert_to_query_ast().unwrap()
+        else {
+            panic!("expected a dis max query");
+        };
+        assert_eq!(dis_max_query.tie_breaker, NotNaNf32::try_from(0.5).unwrap());
+        assert_eq!(dis_max_query.disjuncts.len(), 2);
+        assert!(matches!(
+            &dis_max_query.disjuncts[0],
+            QueryAst::Boost { underlying, boost }
+                if matches!(**underlying, QueryAst::FullText(_))
+                    && *boost == NotNaNf32::try_from(2.0).unwrap()
+        ));
+        assert!(matches!(&dis_max_query.disjuncts[1], QueryAst::FullText(_)));
 
-        test_multimatch_query_ok_aux(
+        let most_fields_query: MultiMatchQuery = serde_json::from_str(
             r#"{
-            "query": "quick brown fox",
-            "type": "phrase_prefix",
-            "fields": ["title", "body"]
-        }"#,
-            BoolQuery::union(vec![
-                MatchPhrasePrefixQuery {
-                    field: "title".to_string(),
-                    value: MatchPhrasePrefixQueryParams {
-                        query: "quick brown fox".to_string(),
-                        analyzer: Default::default(),
-                        max_expansions: default_max_expansions(),
-                        slop: Default::default(),
-                        zero_terms_query: Default::default(),
-                    },
-                }
-                .into(),
-                MatchPhrasePrefixQuery {
-                    field: "body".to_string(),
-                    value: MatchPhrasePrefixQueryParams {
-                        query: "quick brown fox".to_string(),
-                        analyzer: Default::default(),
-                        max_expansions: default_max_expansions(),
-                        slop: Default::default(),
-                        zero_terms_query: Default::default(),
-                    },
-                }
-                .into(),
-            ]),
+                "query": "quick",
+                "type": "most_fields",
+                "fields": ["title", "body"]
+            }"#,
+        )
+        .unwrap();
+        let QueryAst::Bool(bool_query) = most_fields_query.convert_to_query_ast().unwrap() else {
+            panic!("expected a bool query");
+        };
+        assert_eq!(bool_query.should.len(), 2);
+        assert!(bool_query.must.is_empty());
+        assert!(bool_query.must_not.is_empty());
+        assert!(bool_query.filter.is_empty());
+    }
+
+    #[test]
+    fn test_multimatch_query_best_fields_and_most_fields_scores() {
+        let mut schema_builder = TantivySchema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        // Matches `fox` in both fields.
+        index_writer
+            .add_document(doc!(title_field => "quick fox", body_field => "the fox jumps"))
+            .unwrap();
+        // Matches `fox` in the title only.
+        index_writer
+            .add_document(doc!(title_field => "brown fox", body_field => "the dog sleeps"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title_field => "lazy dog", body_field => "the dog barks"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let search = |es_query_json: &str| -> Vec<(Score, DocAddress)> {
+            let es_query: ElasticQueryDsl = serde_json::from_str(es_query_json).unwrap();
+            let query_ast = QueryAst::try_from(es_query).unwrap();
+            let query = query_ast
+                .build_tantivy_query(&schema, &tokenizer_manager, true)
+                .unwrap();
+            let mut top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+            top_docs.sort_by_key(|(_, doc_address)| *doc_address);
+            top_docs
+        };
+        let title_scores = search(r#"{"match": {"title": "fox"}}"#);
+        let body_scores = search(r#"{"match": {"body": "fox"}}"#);
+        assert_eq!(title_scores.len(), 2);
+        assert_eq!(body_scores.len(), 1);
+        let (title_score_0, title_score_1) = (title_scores[0].0, title_scores[1].0);
+        let body_score_0 = body_scores[0].0;
+
+        let best_fields_scores = search(
+            r#"{"multi_match": {"query": "fox", "type": "best_fields", "fields": ["title", "body"]}}"#,
         );
+        assert_eq!(best_fields_scores.len(), 2);
+        assert!((best_fields_scores[0].0 - title_score_0.max(body_score_0)).abs() < 1e-5);
+        assert!((best_fields_scores[1].0 - title_score_1).abs() < 1e-5);
 
-        test_multimatch_query_ok_aux(
-            r#"{
-            "query": "quick brown",
-            "type": "bool_prefix",
-            "fields": ["title", "body"]
-        }"#,
-            BoolQuery::union(vec![
-                MatchBoolPrefixQuery {
-                    field: "title".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-                MatchBoolPrefixQuery {
-                    field: "body".to_string(),
-                    params: MatchQueryParams {
-                        query: "quick brown".to_string(),
-                        operator: crate::BooleanOperand::Or,
-                        zero_terms_query: Default::default(),
-                        lenient: false,
-                    },
-                }
-                .into(),
-            ]),
+        let best_fields_with_tie_breaker_scores = search(
+            r#"{"multi_match": {"query": "fox", "type": "best_fields", "fields": ["title", "body"], "tie_breaker": 0.5}}"#,
+        );
+        let expected_score_0 =
+            title_score_0.max(body_score_0) + 0.5 * title_score_0.min(body_score_0);
+        assert!((best_fields_with_tie_breaker_scores[0].0 - expected_score_0).abs() < 1e-5);
+
+        let most_fields_scores = search(
+            r#"{"multi_match": {"query": "fox", "type": "most_fields", "fields": ["title", "body"]}}"#,
+        );
+        assert_eq!(most_fields_scores.len(), 2);
+        assert!((most_fields_scores[0].0 - (title_score_0 + body_score_0)).abs() < 1e-5);
+        assert!((most_fields_scores[1].0 - title_score_1).abs() < 1e-5);
+
+        // Matching several fields only pays off with `most_fields`.
+        assert!(most_fields_scores[0].0 > best_fields_scores[0].0);
+
+        let boosted_most_fields_scores = search(
+            r#"{"multi_match": {"query": "fox", "type": "most_fields", "fields": ["title^2", "body"]}}"#,
+        );
+        assert!(
+            (boosted_most_fields_scores[0].0 - (2.0 * title_score_0 + body_score_0)).abs() < 1e-5
+        );
+    }
+
+    #[test]
+    fn test_multimatch_query_minimum_should_match() {
+        let mut schema_builder = TantivySchema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(title_field => "quick brown fox", body_field => ""))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title_field => "quick dog", body_field => "brown"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let count = |es_query_json: &str| -> usize {
+            let es_query: ElasticQueryDsl = serde_json::from_str(es_query_json).unwrap();
+            let query_ast = QueryAst::try_from(es_query).unwrap();
+            let query = query_ast
+                .build_tantivy_query(&schema, &tokenizer_manager, true)
+                .unwrap();
+            searcher.count(&query).unwrap()
+        };
+        let multi_match_json = |extra_params: &str| {
+            format!(
+                r#"{{"multi_match": {{"query": "quick brown fox", "type": "best_fields", "fields": ["title", "body"]{extra_params}}}}}"#
+            )
+        };
+        assert_eq!(count(&multi_match_json("")), 2);
+        // The constraints apply to each field separately.
+        assert_eq!(
+            count(&multi_match_json(r#", "minimum_should_match": 2"#)),
+            1
+        );
+        assert_eq!(
+            count(&multi_match_json(r#", "minimum_should_match": "-1""#)),
+            1
+        );
+        assert_eq!(count(&multi_match_json(r#", "operator": "AND""#)), 1);
+        assert_eq!(
+            count(&multi_match_json(r#", "minimum_should_match": "10%""#)),
+            2
         );
     }
 
@@ -382,9 +524,34 @@ mod tests {
             r#"{
                 "query": "quick brown fox",
                 "type": "most_fields",
-                "fields": ["body", "title^3"]
+                "fields": ["body", "title^high"]
+            }"#,
+            "invalid boost in the multi match query fields (got `title^high`)",
+        );
+        test_multimatch_query_err_aux(
+            r#"{
+                "query": "quick brown fox",
+                "type": "phrase_prefix",
+                "fields": ["title", "body"]
+            }"#,
+            "Quickwit does not support multi match queries of type `phrase_prefix`",
+        );
+        test_multimatch_query_err_aux(
+            r#"{
+                "query": "quick brown fox",
+                "type": "most_relevant_fields",
+                "fields": ["title", "body"]
+            }"#,
+            "unknown variant `most_relevant_fields`",
+        );
+        test_multimatch_query_err_aux(
+            r#"{
+                "query": "quick brown fox",
+                "type": "best_fields",
+                "fields": ["title", "body"],
+                "minimum_should_match": "3<90%"
             }"#,
-            "Quickwit does not support field boosting",
+            "invalid `minimum_should_match` value `3<90%`",
         );
     }
 }
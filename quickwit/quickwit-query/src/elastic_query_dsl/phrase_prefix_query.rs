@@ -23,7 +23,9 @@ use crate::elastic_query_dsl::one_field_map::OneFieldMap;
 use crate::elastic_query_dsl::{
     default_max_expansions, ConvertibleToQueryAst, ElasticQueryDslInner,
 };
-use crate::query_ast::{self, FullTextMode, FullTextParams, QueryAst};
+use crate::query_ast::{
+    self, FullTextMode, FullTextParams, QueryAst, MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS,
+};
 use crate::MatchAllOrNone;
 
 pub(crate) type MatchPhrasePrefixQuery = OneFieldMap<MatchPhrasePrefixQueryParams>;
@@ -57,6 +59,12 @@ impl ConvertibleToQueryAst for MatchPhrasePrefixQuery {
             slop,
             zero_terms_query,
         } = self.value;
+        if max_expansions > MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS {
+            anyhow::bail!(
+                "`max_expansions` of `match_phrase_prefix` query must be at most \
+                 {MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS}, got {max_expansions}"
+            );
+        }
         let analyzer = FullTextParams {
             tokenizer: analyzer,
             mode: FullTextMode::Phrase { slop },
@@ -75,6 +83,8 @@ impl ConvertibleToQueryAst for MatchPhrasePrefixQuery {
 #[cfg(test)]
 mod tests {
     use super::{MatchAllOrNone, MatchPhrasePrefixQuery, MatchPhrasePrefixQueryParams};
+    use crate::elastic_query_dsl::ConvertibleToQueryAst;
+    use crate::query_ast::{FullTextMode, QueryAst};
 
     #[test]
     fn test_term_query_simple() {
@@ -94,4 +104,35 @@ mod tests {
 
         assert_eq!(&phrase_prefix, &expected);
     }
+
+    #[test]
+    fn test_phrase_prefix_query_slop_and_max_expansions() {
+        let phrase_prefix_json =
+            r#"{ "message": { "query": "quick f", "slop": 1, "max_expansions": 10 } }"#;
+        let phrase_prefix: MatchPhrasePrefixQuery =
+            serde_json::from_str(phrase_prefix_json).unwrap();
+        let QueryAst::PhrasePrefix(phrase_prefix_ast) =
+            phrase_prefix.convert_to_query_ast().unwrap()
+        else {
+            panic!("expected a phrase prefix query");
+        };
+        assert_eq!(phrase_prefix_ast.max_expansions, 10);
+        assert_eq!(
+            phrase_prefix_ast.params.mode,
+            FullTextMode::Phrase { slop: 1 }
+        );
+    }
+
+    #[test]
+    fn test_phrase_prefix_query_invalid_params() {
+        let phrase_prefix_json = r#"{ "message": { "query": "quick f", "slop": -1 } }"#;
+        serde_json::from_str::<MatchPhrasePrefixQuery>(phrase_prefix_json).unwrap_err();
+
+        let phrase_prefix_json =
+            r#"{ "message": { "query": "quick f", "max_expansions": 1000000 } }"#;
+        let phrase_prefix: MatchPhrasePrefixQuery =
+            serde_json::from_str(phrase_prefix_json).unwrap();
+        let error = phrase_prefix.convert_to_query_ast().unwrap_err();
+        assert!(error.to_string().contains("must be at most 10000"));
+    }
 }
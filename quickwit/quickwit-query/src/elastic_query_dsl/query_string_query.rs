@@ -28,8 +28,8 @@ use crate::BooleanOperand;
 #[serde(deny_unknown_fields)]
 pub(crate) struct QueryStringQuery {
     query: String,
-    /// Limitation. We do not support * at the moment.
-    /// We do not support JSON field either.
+    /// Fields can be boosted with a `^` suffix (`title^3`) and can be patterns (`user.*`). Both
+    /// are resolved when the query is validated against the doc mapping.
     ///
     /// Note that following elastic, we do not support "string" and require an array here.
     #[serde(default)]
@@ -207,4 +207,29 @@ mod tests {
             && default_operator == BooleanOperand::Or
             && default_fields == Some(vec!["text".to_string()])));
     }
+
+    #[test]
+    fn test_build_query_string_query_with_boosted_fields() {
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello", "fields": ["title^3", "body"] }"#).unwrap();
+        let query_ast = query_string_query
+            .convert_to_query_ast()
+            .unwrap()
+            .parse_user_query(&[])
+            .unwrap();
+        let QueryAst::Bool(bool_query) = query_ast else {
+            panic!();
+        };
+        assert_eq!(bool_query.should.len(), 2);
+        assert!(matches!(
+            &bool_query.should[0],
+            QueryAst::Boost { underlying, boost }
+                if f32::from(*boost) == 3.0
+                && matches!(underlying.as_ref(), QueryAst::FullText(full_text_query) if full_text_query.field == "title")
+        ));
+        assert!(matches!(
+            &bool_query.should[1],
+            QueryAst::FullText(full_text_query) if full_text_query.field == "body"
+        ));
+    }
 }
@@ -0,0 +1,148 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use super::StringOrStructForSerialization;
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertibleToQueryAst, ElasticQueryDslInner};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// The elasticsearch `regexp` query. The regular expression syntax is the one of the Rust `regex`
+/// crate, which covers the default syntax of Lucene regular expressions, but not its optional
+/// operators enabled with `flags`.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>")]
+pub(crate) struct RegexpQuery {
+    pub field: String,
+    pub params: RegexpQueryParams,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegexpQueryParams {
+    pub value: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    // Accepted for compatibility: the size of the automaton is bounded by quickwit instead.
+    #[serde(default)]
+    pub max_determinized_states: Option<u32>,
+    #[serde(default)]
+    pub boost: Option<NotNaNf32>,
+}
+
+impl From<String> for RegexpQueryParams {
+    fn from(value: String) -> RegexpQueryParams {
+        RegexpQueryParams {
+            value,
+            case_insensitive: false,
+            max_determinized_states: None,
+            boost: None,
+        }
+    }
+}
+
+impl From<OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>> for RegexpQuery {
+    fn from(one_field_map: OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>) -> Self {
+        RegexpQuery {
+            field: one_field_map.field,
+            params: one_field_map.value.inner,
+        }
+    }
+}
+
+impl From<RegexpQuery> for ElasticQueryDslInner {
+    fn from(regexp_query: RegexpQuery) -> Self {
+        Self::Regexp(regexp_query)
+    }
+}
+
+impl ConvertibleToQueryAst for RegexpQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let RegexpQueryParams {
+            value,
+            case_insensitive,
+            boost,
+            ..
+        } = self.params;
+        let regex = if case_insensitive {
+            format!("(?i){value}")
+        } else {
+            value
+        };
+        let regex_ast: QueryAst = query_ast::RegexQuery::from_field_value(self.field, regex).into();
+        Ok(regex_ast.boost(boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elastic_query_dsl::ElasticQueryDsl;
+
+    #[test]
+    fn test_regexp_query_short_form() {
+        let regexp_query_json = r#"{ "status": "error [0-9]{3}" }"#;
+        let regexp_query: RegexpQuery = serde_json::from_str(regexp_query_json).unwrap();
+        assert_eq!(regexp_query.field, "status");
+        assert_eq!(regexp_query.params.value, "error [0-9]{3}");
+
+        let query_ast = regexp_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Regex(query_ast::RegexQuery::from_field_value(
+                "status",
+                "error [0-9]{3}"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_regexp_query_with_params() {
+        let es_query_json = r#"{
+            "regexp": {
+                "user.id": {
+                    "value": "k.*y",
+                    "case_insensitive": true,
+                    "max_determinized_states": 10000,
+                    "boost": 2.0
+                }
+            }
+        }"#;
+        let es_query: ElasticQueryDsl = serde_json::from_str(es_query_json).unwrap();
+        let query_ast = QueryAst::try_from(es_query).unwrap();
+        let QueryAst::Boost { underlying, boost } = query_ast else {
+            panic!()
+        };
+        assert_eq!(boost, NotNaNf32::try_from(2.0).unwrap());
+        assert_eq!(
+            *underlying,
+            QueryAst::Regex(query_ast::RegexQuery::from_field_value(
+                "user.id", "(?i)k.*y"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_regexp_query_rejects_flags() {
+        let regexp_query_json = r#"{ "status": { "value": "a.*", "flags": "ALL" } }"#;
+        serde_json::from_str::<RegexpQuery>(regexp_query_json).unwrap_err();
+    }
+}
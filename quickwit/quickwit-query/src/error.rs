@@ -36,6 +36,12 @@ pub enum InvalidQuery {
         field_name: String,
         value: String,
     },
+    #[error("invalid byte size `{value}` for field `{field_name}`: {reason}")]
+    InvalidByteSize {
+        field_name: String,
+        value: String,
+        reason: String,
+    },
     #[error("range query on `{value_type}` field (`{field_name}`) forbidden")]
     RangeQueryNotSupportedForField {
         value_type: &'static str,
@@ -43,10 +49,22 @@ pub enum InvalidQuery {
     },
     #[error("field does not exist: `{full_path}`")]
     FieldDoesNotExist { full_path: String },
+    #[error(
+        "field `{field_name}` is stored or fast but not indexed: set `indexed: true` in its \
+         mapping to run this query, or use a query that does not need the field to be indexed, \
+         such as a range query on a fast field"
+    )]
+    FieldNotIndexed { field_name: String },
     #[error("Json field root is not a valid search field: `{full_path}`")]
     JsonFieldRootNotSearchable { full_path: String },
+    #[error("query exceeds the `{limit}` limit of {max}")]
+    QueryAstLimitExceeded { limit: &'static str, max: usize },
     #[error("user query should have been parsed")]
     UserQueryNotParsed,
+    #[error(
+        "the index has no unique id field to query `_id` on: set `doc_id_field` in its doc mapping"
+    )]
+    MissingDocIdField,
     #[error("{0}")]
     Other(#[from] anyhow::Error),
 }
@@ -0,0 +1,652 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use tantivy::columnar::{Column, StrColumn};
+use tantivy::query::{
+    AllScorer, ConstScorer, EnableScoring, Explanation, Query, Scorer, TermQuery, TermSetQuery,
+    Weight,
+};
+use tantivy::schema::{FieldEntry, FieldType, IndexRecordOption, Schema as TantivySchema, Type};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+use tracing::debug;
+
+use crate::query_ast::TantivyQueryAst;
+
+/// Strategy used to evaluate the exclusion clauses of a bool query on a segment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ExclusionStrategy {
+    /// The excluded terms are looked up in the inverted index, without computing any score.
+    NonScoringPostings,
+    /// None of the excluded values is present in the fast fields of the segment.
+    NothingToExclude,
+    /// The fast field columns are scanned document by document.
+    ColumnScan,
+}
+
+/// A tantivy query matching the documents containing any of the given terms, used for the
+/// `must_not` clauses of bool queries.
+///
+/// Excluded documents are subtracted from the positive clauses and never scored, so this query
+/// builds its weight with scoring disabled: the postings are opened without term frequencies and
+/// no BM25 statistics are computed.
+#[derive(Clone)]
+pub(crate) struct TermExclusionQuery {
+    terms: Vec<Term>,
+}
+
+impl std::fmt::Debug for TermExclusionQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("TermExclusionQuery")
+            .field("terms", &self.terms)
+            .field("strategy", &ExclusionStrategy::NonScoringPostings)
+            .finish()
+    }
+}
+
+impl TermExclusionQuery {
+    /// Wraps a `must_not` clause into a `TermExclusionQuery` if it is a term or a term set query.
+    /// Other clauses are returned unchanged.
+    pub fn wrap_must_not_clause(must_not_ast: TantivyQueryAst) -> TantivyQueryAst {
+        let Some(leaf) = must_not_ast.as_leaf() else {
+            return must_not_ast;
+        };
+        if leaf.downcast_ref::<TermQuery>().is_none()
+            && leaf.downcast_ref::<TermSetQuery>().is_none()
+        {
+            return must_not_ast;
+        }
+        let mut terms = Vec::new();
+        leaf.query_terms(&mut |term, _| terms.push(term.clone()));
+        if terms.is_empty() {
+            return must_not_ast;
+        }
+        TermExclusionQuery { terms }.into()
+    }
+}
+
+impl Query for TermExclusionQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let scoring_disabled = match enable_scoring.searcher() {
+            Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+            None => EnableScoring::disabled_from_schema(enable_scoring.schema()),
+        };
+        if let [term] = &self.terms[..] {
+            TermQuery::new(term.clone(), IndexRecordOption::Basic).weight(scoring_disabled)
+        } else {
+            TermSetQuery::new(self.terms.iter().cloned()).weight(scoring_disabled)
+        }
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for term in &self.terms {
+            visitor(term, false);
+        }
+    }
+}
+
+/// A tantivy query matching all the documents except those having one of the excluded values in
+/// a fast field.
+///
+/// It evaluates bool queries made of term exclusions only (`NOT status:200 AND NOT
+/// level:DEBUG`). When the excluded values match a large share of the documents, scanning the
+/// fast field columns is much cheaper than subtracting the postings of the excluded terms from
+/// the full set of documents.
+///
+/// It is only used when the fast field columns hold exactly the indexed terms, so that it
+/// matches the same documents as the equivalent bool query.
+#[derive(Clone)]
+pub(crate) struct ColumnarExclusionQuery {
+    excluded_terms_per_field: BTreeMap<String, Vec<Term>>,
+}
+
+impl std::fmt::Debug for ColumnarExclusionQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ColumnarExclusionQuery")
+            .field("excluded_terms", &self.excluded_terms_per_field)
+            .field("strategy", &ExclusionStrategy::ColumnScan)
+            .finish()
+    }
+}
+
+impl ColumnarExclusionQuery {
+    /// Returns a columnar exclusion query if all the `must_not` clauses are term exclusions on
+    /// fast fields compatible with the columnar evaluation.
+    pub fn try_from_must_not_clauses(
+        must_not_asts: &[TantivyQueryAst],
+        schema: &TantivySchema,
+    ) -> Option<Self> {
+        let mut excluded_terms_per_field: BTreeMap<String, Vec<Term>> = BTreeMap::new();
+        for must_not_ast in must_not_asts {
+            let term_exclusion_query = must_not_ast
+                .as_leaf()?
+                .downcast_ref::<TermExclusionQuery>()?;
+            for term in &term_exclusion_query.terms {
+                let field_entry = schema.get_field_entry(term.field());
+                if !is_columnar_compatible(field_entry, term) {
+                    return None;
+                }
+                excluded_terms_per_field
+                    .entry(field_entry.name().to_string())
+                    .or_default()
+                    .push(term.clone());
+            }
+        }
+        if excluded_terms_per_field.is_empty() {
+            return None;
+        }
+        Some(Self {
+            excluded_terms_per_field,
+        })
+    }
+
+    /// Names of the fast fields read by the query.
+    pub fn fast_field_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.excluded_terms_per_field.keys().map(String::as_str)
+    }
+}
+
+/// Returns true if the documents containing `term` in the inverted index are exactly the
+/// documents having the value of `term` in the fast field column.
+fn is_columnar_compatible(field_entry: &FieldEntry, term: &Term) -> bool {
+    if !field_entry.is_indexed()
+        || !field_entry.is_fast()
+        || term.typ() != field_entry.field_type().value_type()
+    {
+        return false;
+    }
+    match field_entry.field_type() {
+        FieldType::U64(_) | FieldType::I64(_) | FieldType::F64(_) | FieldType::Bool(_) => true,
+        // Text fast fields store the values normalized by the fast field tokenizer, which match
+        // the indexed terms only if neither tokenizer alters the text.
+        FieldType::Str(text_options) => {
+            let indexing_tokenizer_opt = text_options
+                .get_indexing_options()
+                .map(|indexing_options| indexing_options.tokenizer());
+            indexing_tokenizer_opt == Some("raw")
+                && text_options.get_fast_field_tokenizer_name() == Some("raw")
+        }
+        // Dates are truncated to the fast field precision, and JSON fields hold several columns
+        // per path.
+        _ => false,
+    }
+}
+
+impl Query for ColumnarExclusionQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(ColumnarExclusionWeight {
+            excluded_terms_per_field: self.excluded_terms_per_field.clone(),
+        }))
+    }
+}
+
+struct ColumnarExclusionWeight {
+    excluded_terms_per_field: BTreeMap<String, Vec<Term>>,
+}
+
+impl ColumnarExclusionWeight {
+    fn scorer_and_strategy(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tantivy::Result<(Box<dyn Scorer>, ExclusionStrategy)> {
+        let mut excluded_columns = Vec::new();
+        for (field_name, terms) in &self.excluded_terms_per_field {
+            if let Some(excluded_column) = ExcludedColumn::open(reader, field_name, terms)? {
+                excluded_columns.push(excluded_column);
+            }
+        }
+        if excluded_columns.is_empty() {
+            let all_scorer = AllScorer::new(reader.max_doc());
+            return Ok((
+                Box::new(ConstScorer::new(all_scorer, boost)),
+                ExclusionStrategy::NothingToExclude,
+            ));
+        }
+        let doc_set = ColumnarExclusionDocSet::new(excluded_columns, reader.max_doc());
+        Ok((
+            Box::new(ConstScorer::new(doc_set, boost)),
+            ExclusionStrategy::ColumnScan,
+        ))
+    }
+}
+
+impl Weight for ColumnarExclusionWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let (scorer, strategy) = self.scorer_and_strategy(reader, boost)?;
+        debug!(fields=?self.excluded_terms_per_field.keys(), strategy=?strategy, "exclusion query");
+        Ok(scorer)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let (mut scorer, strategy) = self.scorer_and_strategy(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new(
+            format!("ColumnarExclusionQuery ({strategy:?})"),
+            1.0,
+        ))
+    }
+}
+
+/// The column of a fast field and the values to exclude, in the representation of the column.
+enum ExcludedColumn {
+    U64(Column<u64>, Vec<u64>),
+    I64(Column<i64>, Vec<i64>),
+    F64(Column<f64>, Vec<f64>),
+    Bool(Column<bool>, Vec<bool>),
+    Str(StrColumn, Vec<u64>),
+}
+
+impl ExcludedColumn {
+    /// Returns `None` if the segment has no column for the field or if none of the excluded
+    /// values can be present in the column.
+    fn open(
+        reader: &SegmentReader,
+        field_name: &str,
+        terms: &[Term],
+    ) -> tantivy::Result<Option<Self>> {
+        let fast_fields = reader.fast_fields();
+        let Some(first_term) = terms.first() else {
+            return Ok(None);
+        };
+        let excluded_column_opt = match first_term.typ() {
+            Type::U64 => fast_fields.column_opt(field_name)?.map(|column| {
+                let values = terms.iter().filter_map(|term| term.value().as_u64());
+                ExcludedColumn::U64(column, values.collect())
+            }),
+            Type::I64 => fast_fields.column_opt(field_name)?.map(|column| {
+                let values = terms.iter().filter_map(|term| term.value().as_i64());
+                ExcludedColumn::I64(column, values.collect())
+            }),
+            Type::F64 => fast_fields.column_opt(field_name)?.map(|column| {
+                let values = terms.iter().filter_map(|term| term.value().as_f64());
+                ExcludedColumn::F64(column, values.collect())
+            }),
+            Type::Bool => fast_fields.column_opt(field_name)?.map(|column| {
+                let values = terms.iter().filter_map(|term| term.value().as_bool());
+                ExcludedColumn::Bool(column, values.collect())
+            }),
+            Type::Str => {
+                let Some(str_column) = fast_fields.str(field_name)? else {
+                    return Ok(None);
+                };
+                let mut term_ords = Vec::new();
+                for term in terms {
+                    let Some(value) = term.value().as_str() else {
+                        continue;
+                    };
+                    if let Some(term_ord) = str_column.dictionary().term_ord(value)? {
+                        term_ords.push(term_ord);
+                    }
+                }
+                if term_ords.is_empty() {
+                    return Ok(None);
+                }
+                Some(ExcludedColumn::Str(str_column, term_ords))
+            }
+            _ => None,
+        };
+        Ok(excluded_column_opt)
+    }
+
+    fn excludes(&self, doc: DocId) -> bool {
+        match self {
+            ExcludedColumn::U64(column, values) => column
+                .values_for_doc(doc)
+                .any(|value| values.contains(&value)),
+            ExcludedColumn::I64(column, values) => column
+                .values_for_doc(doc)
+                .any(|value| values.contains(&value)),
+            // Terms compare the bit representation of floats, so `-0.0` and `0.0` differ.
+            ExcludedColumn::F64(column, values) => column.values_for_doc(doc).any(|value| {
+                values
+                    .iter()
+                    .any(|excluded_value| excluded_value.to_bits() == value.to_bits())
+            }),
+            ExcludedColumn::Bool(column, values) => column
+                .values_for_doc(doc)
+                .any(|value| values.contains(&value)),
+            ExcludedColumn::Str(column, term_ords) => column
+                .term_ords(doc)
+                .any(|term_ord| term_ords.contains(&term_ord)),
+        }
+    }
+}
+
+/// A doc set iterating over the documents of a segment that have none of the excluded values.
+struct ColumnarExclusionDocSet {
+    excluded_columns: Vec<ExcludedColumn>,
+    doc: DocId,
+    max_doc: DocId,
+}
+
+impl ColumnarExclusionDocSet {
+    fn new(excluded_columns: Vec<ExcludedColumn>, max_doc: DocId) -> Self {
+        let mut doc_set = Self {
+            excluded_columns,
+            doc: 0,
+            max_doc,
+        };
+        doc_set.skip_excluded_docs();
+        doc_set
+    }
+
+    fn skip_excluded_docs(&mut self) -> DocId {
+        while self.doc < self.max_doc
+            && self
+                .excluded_columns
+                .iter()
+                .any(|excluded_column| excluded_column.excludes(self.doc))
+        {
+            self.doc += 1;
+        }
+        if self.doc >= self.max_doc {
+            self.doc = TERMINATED;
+        }
+        self.doc
+    }
+}
+
+impl DocSet for ColumnarExclusionDocSet {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        self.doc += 1;
+        self.skip_excluded_docs()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        self.doc = target;
+        self.skip_excluded_docs()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.max_doc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::{Count, DocSetCollector};
+    use tantivy::query::{AllQuery, BooleanQuery, Occur};
+    use tantivy::schema::{Field, Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, TEXT};
+    use tantivy::{Index, IndexWriter, TantivyDocument};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+    use crate::query_ast::query_ast_from_user_text;
+
+    struct TestIndex {
+        index: Index,
+        status: Field,
+        delta: Field,
+        ratio: Field,
+        is_error: Field,
+        level: Field,
+        tags: Field,
+        body: Field,
+    }
+
+    fn raw_fast_text_options() -> TextOptions {
+        TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"))
+            .set_fast(Some("raw"))
+    }
+
+    fn build_test_index() -> TestIndex {
+        let mut schema_builder = Schema::builder();
+        let status = schema_builder.add_u64_field("status", INDEXED | FAST);
+        let delta = schema_builder.add_i64_field("delta", INDEXED | FAST);
+        let ratio = schema_builder.add_f64_field("ratio", INDEXED | FAST);
+        let is_error = schema_builder.add_bool_field("is_error", INDEXED | FAST);
+        let level = schema_builder.add_text_field("level", raw_fast_text_options());
+        let tags = schema_builder.add_text_field("tags", raw_fast_text_options());
+        let body = schema_builder.add_text_field("body", TEXT | FAST);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema);
+        let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        // The documents of the second segment have no level.
+        for with_level in [true, false] {
+            for i in 0..1_000u64 {
+                let mut document = TantivyDocument::default();
+                if i % 13 != 0 {
+                    let status_value = if i % 10 == 0 {
+                        500
+                    } else if i % 4 == 0 {
+                        404
+                    } else {
+                        200
+                    };
+                    document.add_u64(status, status_value);
+                }
+                document.add_i64(delta, (i % 7) as i64 - 3);
+                document.add_f64(ratio, (i % 4) as f64 / 2.0);
+                document.add_bool(is_error, i % 10 == 0);
+                if with_level {
+                    document.add_text(level, ["INFO", "DEBUG", "WARN", "ERROR"][i as usize % 4]);
+                }
+                document.add_text(tags, "red");
+                if i % 3 == 0 {
+                    document.add_text(tags, "blue");
+                }
+                document.add_text(body, if i % 2 == 0 { "hello world" } else { "goodbye" });
+                index_writer.add_document(document).unwrap();
+            }
+            index_writer.commit().unwrap();
+        }
+        TestIndex {
+            index,
+            status,
+            delta,
+            ratio,
+            is_error,
+            level,
+            tags,
+            body,
+        }
+    }
+
+    fn build_query(index: &Index, user_text: &str) -> Box<dyn Query> {
+        query_ast_from_user_text(user_text, None)
+            .parse_user_query(&[])
+            .unwrap()
+            .build_tantivy_query(
+                &index.schema(),
+                &create_default_quickwit_tokenizer_manager(),
+                true,
+            )
+            .unwrap()
+    }
+
+    fn naive_exclusion_query(
+        positive_query: Box<dyn Query>,
+        excluded_terms: Vec<Term>,
+    ) -> BooleanQuery {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, positive_query)];
+        for term in excluded_terms {
+            let term_query = TermQuery::new(term, IndexRecordOption::WithFreqs);
+            clauses.push((Occur::MustNot, Box::new(term_query)));
+        }
+        BooleanQuery::new(clauses)
+    }
+
+    fn assert_same_matches(
+        index: &Index,
+        query: &dyn Query,
+        naive_query: &dyn Query,
+        expected_num_matches: usize,
+    ) {
+        let searcher = index.reader().unwrap().searcher();
+        let matches = searcher.search(query, &DocSetCollector).unwrap();
+        let naive_matches = searcher.search(naive_query, &DocSetCollector).unwrap();
+        assert_eq!(matches.len(), expected_num_matches);
+        assert_eq!(matches, naive_matches);
+        let num_matches = searcher.search(query, &Count).unwrap();
+        assert_eq!(num_matches, expected_num_matches);
+    }
+
+    #[test]
+    fn test_columnar_exclusion_query_matches_naive_exclusion() {
+        let test_index = build_test_index();
+        let test_cases = [
+            (
+                "NOT status:200",
+                vec![Term::from_field_u64(test_index.status, 200)],
+                706,
+            ),
+            (
+                "NOT status: IN [404 500]",
+                vec![
+                    Term::from_field_u64(test_index.status, 404),
+                    Term::from_field_u64(test_index.status, 500),
+                ],
+                1_448,
+            ),
+            (
+                "NOT delta:2",
+                vec![Term::from_field_i64(test_index.delta, 2)],
+                1_714,
+            ),
+            (
+                "NOT ratio:0.5",
+                vec![Term::from_field_f64(test_index.ratio, 0.5)],
+                1_500,
+            ),
+            (
+                "NOT is_error:true",
+                vec![Term::from_field_bool(test_index.is_error, true)],
+                1_800,
+            ),
+            (
+                "NOT level:INFO AND NOT level:DEBUG",
+                vec![
+                    Term::from_field_text(test_index.level, "INFO"),
+                    Term::from_field_text(test_index.level, "DEBUG"),
+                ],
+                1_500,
+            ),
+            (
+                "NOT level:TRACE",
+                vec![Term::from_field_text(test_index.level, "TRACE")],
+                2_000,
+            ),
+            (
+                "NOT tags:blue",
+                vec![Term::from_field_text(test_index.tags, "blue")],
+                1_332,
+            ),
+        ];
+        for (user_text, excluded_terms, expected_num_matches) in test_cases {
+            let query = build_query(&test_index.index, user_text);
+            assert!(
+                format!("{query:?}").contains("ColumnarExclusionQuery"),
+                "`{user_text}` should be evaluated on the fast fields"
+            );
+            let naive_query = naive_exclusion_query(Box::new(AllQuery), excluded_terms);
+            assert_same_matches(
+                &test_index.index,
+                &*query,
+                &naive_query,
+                expected_num_matches,
+            );
+        }
+    }
+
+    #[test]
+    fn test_term_exclusion_query_matches_naive_exclusion() {
+        let test_index = build_test_index();
+        let hello_term = Term::from_field_text(test_index.body, "hello");
+
+        let query = build_query(&test_index.index, "body:hello AND NOT status:200");
+        let query_debug = format!("{query:?}");
+        assert!(query_debug.contains("TermExclusionQuery"));
+        assert!(!query_debug.contains("ColumnarExclusionQuery"));
+        let positive_query = TermQuery::new(hello_term.clone(), IndexRecordOption::WithFreqs);
+        let naive_query = naive_exclusion_query(
+            Box::new(positive_query),
+            vec![Term::from_field_u64(test_index.status, 200)],
+        );
+        assert_same_matches(&test_index.index, &*query, &naive_query, 630);
+
+        // `body` is tokenized, so its fast field column does not hold the indexed terms.
+        let query = build_query(&test_index.index, "NOT body:hello");
+        let query_debug = format!("{query:?}");
+        assert!(query_debug.contains("TermExclusionQuery"));
+        assert!(!query_debug.contains("ColumnarExclusionQuery"));
+        let naive_query = naive_exclusion_query(Box::new(AllQuery), vec![hello_term]);
+        assert_same_matches(&test_index.index, &*query, &naive_query, 1_000);
+
+        let query = build_query(&test_index.index, "NOT level:INFO AND NOT body:hello");
+        assert!(!format!("{query:?}").contains("ColumnarExclusionQuery"));
+    }
+
+    #[test]
+    fn test_columnar_exclusion_query_scores_like_match_all() {
+        let test_index = build_test_index();
+        let searcher = test_index.index.reader().unwrap().searcher();
+        let query = build_query(&test_index.index, "NOT status:200");
+        let weight = query
+            .weight(EnableScoring::enabled_from_searcher(&searcher))
+            .unwrap();
+        let mut scorer = weight.scorer(searcher.segment_reader(0), 2.0).unwrap();
+        assert_eq!(scorer.doc(), 0);
+        assert_eq!(scorer.score(), 2.0);
+        assert_eq!(scorer.advance(), 4);
+    }
+
+    #[test]
+    fn test_columnar_exclusion_doc_set() {
+        let test_index = build_test_index();
+        let searcher = test_index.index.reader().unwrap().searcher();
+        let terms = [Term::from_field_text(test_index.level, "INFO")];
+        let mut num_segments_without_level = 0;
+        for segment_reader in searcher.segment_readers() {
+            let Some(excluded_column) =
+                ExcludedColumn::open(segment_reader, "level", &terms).unwrap()
+            else {
+                num_segments_without_level += 1;
+                continue;
+            };
+            // Documents 0, 4, 8, ... are excluded.
+            let mut doc_set = ColumnarExclusionDocSet::new(vec![excluded_column], 10);
+            assert_eq!(doc_set.doc(), 1);
+            assert_eq!(doc_set.advance(), 2);
+            assert_eq!(doc_set.seek(4), 5);
+            assert_eq!(doc_set.seek(5), 5);
+            assert_eq!(doc_set.seek(8), 9);
+            assert_eq!(doc_set.advance(), TERMINATED);
+            assert_eq!(doc_set.seek(12), TERMINATED);
+        }
+        assert_eq!(num_segments_without_level, 1);
+    }
+}
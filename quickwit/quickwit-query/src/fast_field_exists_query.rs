@@ -0,0 +1,249 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::columnar::ColumnIndex;
+use tantivy::query::{
+    AllScorer, ConstScorer, EmptyScorer, EnableScoring, ExistsQuery, Explanation, Query, Scorer,
+    Weight,
+};
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED};
+use tracing::debug;
+
+/// Strategy used by a [`FastFieldExistsQuery`] to find the documents of a segment having a value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ExistsStrategy {
+    /// No column holds a value for the field in the segment.
+    Empty,
+    /// The column holds a value for every document of the segment.
+    Full,
+    /// The non-null rows are read directly from the null bitmap of the column index.
+    NullBitmap,
+    /// The columns are scanned document by document (multivalued or multi-typed fields).
+    ColumnScan,
+}
+
+/// A tantivy query matching the documents having a value for a fast field.
+///
+/// Unlike `tantivy::query::ExistsQuery`, which checks every document of the segment against the
+/// column index, this query reads the non-null rows of optional columns straight from their null
+/// bitmap, which is much faster for sparse fields. Multivalued and multi-typed fields fall back to
+/// `tantivy::query::ExistsQuery`.
+///
+/// The strategy is reported in the `Debug` representation of the query (and thus in the search
+/// plan) and logged at the debug level for every segment.
+#[derive(Clone)]
+pub(crate) struct FastFieldExistsQuery {
+    field_name: String,
+}
+
+impl std::fmt::Debug for FastFieldExistsQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FastFieldExistsQuery")
+            .field("field", &self.field_name)
+            .field("strategy", &"null_bitmap_with_column_scan_fallback")
+            .finish()
+    }
+}
+
+impl FastFieldExistsQuery {
+    /// `field_name` is the full path of the fast field, including the JSON path if any.
+    pub fn new(field_name: String) -> Self {
+        Self { field_name }
+    }
+}
+
+impl Query for FastFieldExistsQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let column_scan_weight =
+            ExistsQuery::new_exists_query(self.field_name.clone()).weight(enable_scoring)?;
+        Ok(Box::new(FastFieldExistsWeight {
+            field_name: self.field_name.clone(),
+            column_scan_weight,
+        }))
+    }
+}
+
+struct FastFieldExistsWeight {
+    field_name: String,
+    column_scan_weight: Box<dyn Weight>,
+}
+
+impl FastFieldExistsWeight {
+    fn scorer_and_strategy(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tantivy::Result<(Box<dyn Scorer>, ExistsStrategy)> {
+        let mut column_indexes = Vec::new();
+        for column_handle in reader
+            .fast_fields()
+            .dynamic_column_handles(&self.field_name)?
+        {
+            let column_index = column_handle.open()?.column_index().clone();
+            if !matches!(column_index, ColumnIndex::Empty { .. }) {
+                column_indexes.push(column_index);
+            }
+        }
+        match &column_indexes[..] {
+            [] => Ok((Box::new(EmptyScorer), ExistsStrategy::Empty)),
+            [ColumnIndex::Full] => {
+                let all_scorer = AllScorer::new(reader.max_doc());
+                Ok((
+                    Box::new(ConstScorer::new(all_scorer, boost)),
+                    ExistsStrategy::Full,
+                ))
+            }
+            [ColumnIndex::Optional(optional_index)] => {
+                let doc_ids: Vec<DocId> = optional_index.iter_rows().collect();
+                let doc_set = SortedDocIdsDocSet::new(doc_ids);
+                Ok((
+                    Box::new(ConstScorer::new(doc_set, boost)),
+                    ExistsStrategy::NullBitmap,
+                ))
+            }
+            _ => {
+                let scorer = self.column_scan_weight.scorer(reader, boost)?;
+                Ok((scorer, ExistsStrategy::ColumnScan))
+            }
+        }
+    }
+}
+
+impl Weight for FastFieldExistsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let (scorer, strategy) = self.scorer_and_strategy(reader, boost)?;
+        debug!(field=%self.field_name, strategy=?strategy, "exists query");
+        Ok(scorer)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let (mut scorer, strategy) = self.scorer_and_strategy(reader, 1.0)?;
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new(
+            format!("FastFieldExistsQuery ({strategy:?})"),
+            1.0,
+        ))
+    }
+}
+
+/// A doc set iterating over a sorted list of doc IDs.
+struct SortedDocIdsDocSet {
+    doc_ids: Vec<DocId>,
+    cursor: usize,
+}
+
+impl SortedDocIdsDocSet {
+    fn new(doc_ids: Vec<DocId>) -> Self {
+        debug_assert!(doc_ids.windows(2).all(|window| window[0] < window[1]));
+        Self { doc_ids, cursor: 0 }
+    }
+}
+
+impl DocSet for SortedDocIdsDocSet {
+    fn advance(&mut self) -> DocId {
+        self.cursor += 1;
+        self.doc()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.cursor += self.doc_ids[self.cursor.min(self.doc_ids.len())..]
+            .partition_point(|doc_id| *doc_id < target);
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc_ids.get(self.cursor).copied().unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.doc_ids.len() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tantivy::collector::{Count, DocSetCollector};
+    use tantivy::schema::{OwnedValue, Schema, FAST, INDEXED, STORED};
+    use tantivy::{doc, Index, IndexWriter};
+
+    use super::*;
+
+    fn assert_same_matches(index: &Index, field_name: &str, expected_num_matches: usize) {
+        let searcher = index.reader().unwrap().searcher();
+        let fast_field_exists_query = FastFieldExistsQuery::new(field_name.to_string());
+        let column_scan_query = ExistsQuery::new_exists_query(field_name.to_string());
+        let fast_field_matches = searcher
+            .search(&fast_field_exists_query, &DocSetCollector)
+            .unwrap();
+        let column_scan_matches = searcher
+            .search(&column_scan_query, &DocSetCollector)
+            .unwrap();
+        assert_eq!(fast_field_matches.len(), expected_num_matches);
+        assert_eq!(fast_field_matches, column_scan_matches);
+        let num_matches = searcher.search(&fast_field_exists_query, &Count).unwrap();
+        assert_eq!(num_matches, expected_num_matches);
+    }
+
+    #[test]
+    fn test_fast_field_exists_query_sparse_field() {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let sparse_field = schema_builder.add_u64_field("sparse", FAST);
+        let dense_field = schema_builder.add_u64_field("dense", FAST);
+        let json_field = schema_builder.add_json_field("attributes", FAST);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema);
+        let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        // 1% of the documents have a value for the sparse field.
+        for id in 0..10_000u64 {
+            let mut document = doc!(id_field => id, dense_field => id);
+            if id % 100 == 7 {
+                document.add_u64(sparse_field, id);
+                let attributes =
+                    BTreeMap::from([("color".to_string(), OwnedValue::Str("red".to_string()))]);
+                document.add_object(json_field, attributes);
+            }
+            index_writer.add_document(document).unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        assert_same_matches(&index, "sparse", 100);
+        assert_same_matches(&index, "dense", 10_000);
+        assert_same_matches(&index, "attributes.color", 100);
+        assert_same_matches(&index, "attributes.size", 0);
+    }
+
+    #[test]
+    fn test_sorted_doc_ids_doc_set() {
+        let mut doc_set = SortedDocIdsDocSet::new(vec![3, 8, 13, 21]);
+        assert_eq!(doc_set.doc(), 3);
+        assert_eq!(doc_set.seek(3), 3);
+        assert_eq!(doc_set.seek(9), 13);
+        assert_eq!(doc_set.advance(), 21);
+        assert_eq!(doc_set.seek(22), TERMINATED);
+        assert_eq!(doc_set.advance(), TERMINATED);
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{InterpretUserInput, JsonLiteral};
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// A point on the globe, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    /// Creates a new geo point, returning `None` if the coordinates are out of bounds.
+    pub fn new(lat: f64, lon: f64) -> Option<GeoPoint> {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return None;
+        }
+        Some(GeoPoint { lat, lon })
+    }
+
+    /// Decodes a geohash into the center of the cell it describes.
+    pub fn from_geohash(geohash: &str) -> Option<GeoPoint> {
+        if geohash.is_empty() {
+            return None;
+        }
+        let mut lat_interval = (-90.0, 90.0);
+        let mut lon_interval = (-180.0, 180.0);
+        // Bits alternate between longitude and latitude, starting with longitude.
+        let mut is_lon_bit = true;
+
+        for geohash_char in geohash.bytes() {
+            let geohash_char = geohash_char.to_ascii_lowercase();
+            let chunk = GEOHASH_ALPHABET
+                .iter()
+                .position(|alphabet_char| *alphabet_char == geohash_char)?;
+
+            for bit_shift in (0..5).rev() {
+                let interval: &mut (f64, f64) = if is_lon_bit {
+                    &mut lon_interval
+                } else {
+                    &mut lat_interval
+                };
+                let mid = (interval.0 + interval.1) / 2.0;
+
+                if (chunk >> bit_shift) & 1 == 1 {
+                    interval.0 = mid;
+                } else {
+                    interval.1 = mid;
+                }
+                is_lon_bit = !is_lon_bit;
+            }
+        }
+        let lat = (lat_interval.0 + lat_interval.1) / 2.0;
+        let lon = (lon_interval.0 + lon_interval.1) / 2.0;
+        GeoPoint::new(lat, lon)
+    }
+}
+
+/// Geo points can be expressed as:
+/// - a `"lat,lon"` string, e.g. `"48.85,2.35"`;
+/// - a GeoJSON-style `[lon, lat]` array, e.g. `[2.35, 48.85]`;
+/// - a geohash string, e.g. `"u09tvw"`, interpreted as the center of its cell.
+impl<'a> InterpretUserInput<'a> for GeoPoint {
+    fn interpret_str(text: &'a str) -> Option<Self> {
+        if let Some((lat_str, lon_str)) = text.split_once(',') {
+            let lat = lat_str.trim().parse::<f64>().ok()?;
+            let lon = lon_str.trim().parse::<f64>().ok()?;
+            return GeoPoint::new(lat, lon);
+        }
+        GeoPoint::from_geohash(text.trim())
+    }
+
+    fn interpret_array(values: &'a [JsonLiteral]) -> Option<Self> {
+        let [lon_literal, lat_literal] = values else {
+            return None;
+        };
+        let lon = f64::interpret_json(lon_literal)?;
+        let lat = f64::interpret_json(lat_literal)?;
+        GeoPoint::new(lat, lon)
+    }
+
+    fn name() -> &'static str {
+        "geo_point"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeoPoint;
+    use crate::{InterpretUserInput, JsonLiteral};
+
+    fn assert_geo_point_approx_eq(geo_point: GeoPoint, lat: f64, lon: f64) {
+        assert!(
+            (geo_point.lat - lat).abs() < 1e-3 && (geo_point.lon - lon).abs() < 1e-3,
+            "expected ({lat}, {lon}), got {geo_point:?}"
+        );
+    }
+
+    #[test]
+    fn test_interpret_geo_point_lat_lon_str() {
+        let geo_point =
+            GeoPoint::interpret_json(&JsonLiteral::String("48.85,2.35".to_string())).unwrap();
+        assert_eq!(geo_point, GeoPoint::new(48.85, 2.35).unwrap());
+
+        let geo_point =
+            GeoPoint::interpret_json(&JsonLiteral::String(" -33.87 , 151.21 ".to_string()))
+                .unwrap();
+        assert_eq!(geo_point, GeoPoint::new(-33.87, 151.21).unwrap());
+
+        assert!(GeoPoint::interpret_json(&JsonLiteral::String("91,0".to_string())).is_none());
+        assert!(GeoPoint::interpret_json(&JsonLiteral::String("0,181".to_string())).is_none());
+        assert!(GeoPoint::interpret_json(&JsonLiteral::String("48.85,".to_string())).is_none());
+    }
+
+    #[test]
+    fn test_interpret_geo_point_array() {
+        let lon_lat = JsonLiteral::Array(vec![
+            JsonLiteral::Number(serde_json::Number::from_f64(2.35).unwrap()),
+            JsonLiteral::Number(serde_json::Number::from_f64(48.85).unwrap()),
+        ]);
+        let geo_point = GeoPoint::interpret_json(&lon_lat).unwrap();
+        assert_eq!(geo_point, GeoPoint::new(48.85, 2.35).unwrap());
+
+        let too_short = JsonLiteral::Array(vec![JsonLiteral::from(2u64)]);
+        assert!(GeoPoint::interpret_json(&too_short).is_none());
+
+        // Latitude is out of bounds, which is a hint that the order is wrong.
+        let lat_lon = JsonLiteral::Array(vec![JsonLiteral::from(2u64), JsonLiteral::from(91u64)]);
+        assert!(GeoPoint::interpret_json(&lat_lon).is_none());
+    }
+
+    #[test]
+    fn test_interpret_geo_point_geohash() {
+        let geo_point =
+            GeoPoint::interpret_json(&JsonLiteral::String("u09tvw".to_string())).unwrap();
+        assert_geo_point_approx_eq(geo_point, 48.859, 2.357);
+
+        let geo_point =
+            GeoPoint::interpret_json(&JsonLiteral::String("U09TVW".to_string())).unwrap();
+        assert_geo_point_approx_eq(geo_point, 48.859, 2.357);
+
+        let geo_point = GeoPoint::interpret_json(&JsonLiteral::String("s".to_string())).unwrap();
+        assert_eq!(geo_point, GeoPoint::new(22.5, 22.5).unwrap());
+
+        // `a` is not part of the geohash alphabet.
+        assert!(GeoPoint::interpret_json(&JsonLiteral::String("u09a".to_string())).is_none());
+        assert!(GeoPoint::interpret_json(&JsonLiteral::String("".to_string())).is_none());
+        assert!(GeoPoint::interpret_json(&JsonLiteral::Bool(true)).is_none());
+    }
+}
@@ -22,10 +22,15 @@ use std::str::FromStr;
 
 use base64::Engine;
 use once_cell::sync::OnceCell;
-use quickwit_datetime::{parse_date_time_str, parse_timestamp, DateTimeInputFormat};
+use quickwit_datetime::{
+    parse_date_time_str_with_granularity, parse_timestamp_with_granularity, DateTimeGranularity,
+    DateTimeInputFormat,
+};
 use serde::{Deserialize, Serialize};
 use tantivy::schema::IntoIpv6Addr;
 
+use crate::InvalidQuery;
+
 fn get_default_date_time_format() -> &'static [DateTimeInputFormat] {
     static DEFAULT_DATE_TIME_FORMATS: OnceCell<Vec<DateTimeInputFormat>> = OnceCell::new();
     DEFAULT_DATE_TIME_FORMATS
@@ -37,6 +42,8 @@ fn get_default_date_time_format() -> &'static [DateTimeInputFormat] {
                 DateTimeInputFormat::from_str("%Y-%m-%dT%H:%M:%S").unwrap(),
                 DateTimeInputFormat::from_str("%Y-%m-%d %H:%M:%S.%f").unwrap(),
                 DateTimeInputFormat::from_str("%Y-%m-%d %H:%M:%S").unwrap(),
+                DateTimeInputFormat::from_str("%Y-%m-%dT%H:%M").unwrap(),
+                DateTimeInputFormat::from_str("%Y-%m-%d %H:%M").unwrap(),
                 DateTimeInputFormat::from_str("%Y-%m-%d").unwrap(),
                 DateTimeInputFormat::from_str("%Y/%m/%d").unwrap(),
             ]
@@ -57,6 +64,7 @@ pub enum JsonLiteral {
     // We have decided to not make a difference at the moment.
     String(String),
     Bool(bool),
+    Array(Vec<JsonLiteral>),
 }
 
 pub trait InterpretUserInput<'a>: Sized {
@@ -65,6 +73,7 @@ pub trait InterpretUserInput<'a>: Sized {
             JsonLiteral::Number(number) => Self::interpret_number(number),
             JsonLiteral::String(str_val) => Self::interpret_str(str_val),
             JsonLiteral::Bool(bool_val) => Self::interpret_bool(*bool_val),
+            JsonLiteral::Array(values) => Self::interpret_array(values),
         }
     }
 
@@ -79,6 +88,10 @@ pub trait InterpretUserInput<'a>: Sized {
         None
     }
 
+    fn interpret_array(_values: &'a [JsonLiteral]) -> Option<Self> {
+        None
+    }
+
     fn name() -> &'static str {
         std::any::type_name::<Self>()
     }
@@ -147,20 +160,42 @@ impl<'a> InterpretUserInput<'a> for Ipv6Addr {
 }
 
 impl<'a> InterpretUserInput<'a> for tantivy::DateTime {
+    fn interpret_str(text: &str) -> Option<Self> {
+        let (date_time, _granularity) =
+            <(tantivy::DateTime, DateTimeGranularity)>::interpret_str(text)?;
+        Some(date_time)
+    }
+
+    fn interpret_number(number: &serde_json::Number) -> Option<Self> {
+        let (date_time, _granularity) =
+            <(tantivy::DateTime, DateTimeGranularity)>::interpret_number(number)?;
+        Some(date_time)
+    }
+}
+
+/// Interprets a datetime literal along with its granularity, i.e. the finest unit of time it
+/// expresses. This is used to give equality on datetimes the semantics of "within the same unit".
+impl<'a> InterpretUserInput<'a> for (tantivy::DateTime, DateTimeGranularity) {
     fn interpret_str(text: &str) -> Option<Self> {
         let date_time_formats = get_default_date_time_format();
-        if let Ok(datetime) = parse_date_time_str(text, date_time_formats) {
-            return Some(datetime);
+        if let Ok(datetime_and_granularity) =
+            parse_date_time_str_with_granularity(text, date_time_formats)
+        {
+            return Some(datetime_and_granularity);
         }
         // Parsing the normal string formats failed.
         // Maybe it is actually a timestamp as a string?
         let possible_timestamp = text.parse::<i64>().ok()?;
-        parse_timestamp(possible_timestamp).ok()
+        parse_timestamp_with_granularity(possible_timestamp).ok()
     }
 
     fn interpret_number(number: &serde_json::Number) -> Option<Self> {
         let possible_timestamp = number.as_i64()?;
-        parse_timestamp(possible_timestamp).ok()
+        parse_timestamp_with_granularity(possible_timestamp).ok()
+    }
+
+    fn name() -> &'static str {
+        tantivy::DateTime::name()
     }
 }
 
@@ -192,6 +227,71 @@ impl<'a> InterpretUserInput<'a> for Vec<u8> {
     }
 }
 
+/// Parses a byte size literal such as `10MB` or `1.5GiB` into a number of bytes. Suffixes are
+/// case-insensitive: `kB`, `MB`, `GB`, `TB`, and `PB` are powers of 1000 whereas `KiB`, `MiB`,
+/// `GiB`, `TiB`, and `PiB` are powers of 1024. A literal without a suffix, or with the `B`
+/// suffix, is a number of bytes.
+pub fn parse_byte_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let suffix_start = text
+        .find(|chr: char| !chr.is_ascii_digit() && chr != '.')
+        .unwrap_or(text.len());
+    let (number_str, suffix) = text.split_at(suffix_start);
+
+    if number_str.is_empty() {
+        return Err("expected a number".to_string());
+    }
+    let multiplier: u64 = match suffix.trim_start().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "pb" => 1_000_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        "pib" => 1 << 50,
+        _ => return Err(format!("unknown unit `{}`", suffix.trim_start())),
+    };
+    let overflow_error = || format!("`{text}` overflows u64");
+
+    if let Ok(number) = number_str.parse::<u64>() {
+        return number.checked_mul(multiplier).ok_or_else(overflow_error);
+    }
+    let number: f64 = number_str
+        .parse()
+        .map_err(|_| format!("`{number_str}` is not a valid number"))?;
+    let num_bytes = (number * multiplier as f64).round();
+
+    if num_bytes >= u64::MAX as f64 {
+        return Err(overflow_error());
+    }
+    Ok(num_bytes as u64)
+}
+
+impl JsonLiteral {
+    /// Interprets a literal targeting a field holding a number of bytes. String literals with a
+    /// size suffix are converted into a number of bytes. Other literals are returned as is.
+    pub fn interpret_byte_size(self, field_name: &str) -> Result<JsonLiteral, InvalidQuery> {
+        let JsonLiteral::String(text) = self else {
+            return Ok(self);
+        };
+        if text.parse::<i64>().is_ok() {
+            return Ok(JsonLiteral::String(text));
+        }
+        match parse_byte_size(&text) {
+            Ok(num_bytes) => Ok(JsonLiteral::from(num_bytes)),
+            Err(reason) => Err(InvalidQuery::InvalidByteSize {
+                field_name: field_name.to_string(),
+                value: text,
+                reason,
+            }),
+        }
+    }
+}
+
 impl From<bool> for JsonLiteral {
     fn from(b: bool) -> JsonLiteral {
         JsonLiteral::Bool(b)
@@ -218,11 +318,12 @@ impl From<i64> for JsonLiteral {
 
 #[cfg(test)]
 mod tests {
+    use quickwit_datetime::DateTimeGranularity;
     use tantivy::DateTime;
     use time::macros::datetime;
 
-    use crate::json_literal::InterpretUserInput;
-    use crate::JsonLiteral;
+    use crate::json_literal::{parse_byte_size, InterpretUserInput};
+    use crate::{InvalidQuery, JsonLiteral};
 
     #[test]
     fn test_interpret_str_u64() {
@@ -267,6 +368,75 @@ mod tests {
         assert_eq!(dt_opt, Some(DateTime::from_utc(expected_datetime)));
     }
 
+    #[test]
+    fn test_interpret_datetime_with_granularity() {
+        let (dt, granularity) =
+            <(DateTime, DateTimeGranularity)>::interpret_str("2023-05-25").unwrap();
+        assert_eq!(dt, DateTime::from_utc(datetime!(2023-05-25 00:00 UTC)));
+        assert_eq!(granularity, DateTimeGranularity::Day);
+
+        let (dt, granularity) =
+            <(DateTime, DateTimeGranularity)>::interpret_str("2023-05-25T18:20").unwrap();
+        assert_eq!(dt, DateTime::from_utc(datetime!(2023-05-25 18:20 UTC)));
+        assert_eq!(granularity, DateTimeGranularity::Minute);
+
+        let (dt, granularity) =
+            <(DateTime, DateTimeGranularity)>::interpret_str("2023-05-25T18:20:11.322Z").unwrap();
+        assert_eq!(
+            dt,
+            DateTime::from_utc(datetime!(2023-05-25 18:20:11.322 UTC))
+        );
+        assert_eq!(granularity, DateTimeGranularity::Millisecond);
+
+        let (dt, granularity) =
+            <(DateTime, DateTimeGranularity)>::interpret_number(&1685086013.into()).unwrap();
+        assert_eq!(dt, DateTime::from_utc(datetime!(2023-05-26 07:26:53 UTC)));
+        assert_eq!(granularity, DateTimeGranularity::Second);
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(parse_byte_size("123"), Ok(123));
+        assert_eq!(parse_byte_size("123B"), Ok(123));
+        assert_eq!(parse_byte_size("10MB"), Ok(10_000_000));
+        assert_eq!(parse_byte_size("10mb"), Ok(10_000_000));
+        assert_eq!(parse_byte_size("10 kB"), Ok(10_000));
+        assert_eq!(parse_byte_size("10MiB"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_byte_size("1gib"), Ok(1 << 30));
+        assert_eq!(parse_byte_size("1.5KiB"), Ok(1536));
+        assert_eq!(
+            parse_byte_size("10XB").unwrap_err(),
+            "unknown unit `XB`".to_string()
+        );
+        assert_eq!(
+            parse_byte_size("20000PB").unwrap_err(),
+            "`20000PB` overflows u64".to_string()
+        );
+        assert!(parse_byte_size("MB").is_err());
+    }
+
+    #[test]
+    fn test_interpret_byte_size() {
+        let literal = JsonLiteral::String("10MB".to_string())
+            .interpret_byte_size("size")
+            .unwrap();
+        assert_eq!(literal, JsonLiteral::from(10_000_000u64));
+
+        let literal = JsonLiteral::from(42u64)
+            .interpret_byte_size("size")
+            .unwrap();
+        assert_eq!(literal, JsonLiteral::from(42u64));
+
+        let error = JsonLiteral::String("10 parsecs".to_string())
+            .interpret_byte_size("size")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            InvalidQuery::InvalidByteSize { field_name, value, .. }
+            if field_name == "size" && value == "10 parsecs"
+        ));
+    }
+
     #[test]
     fn test_interpret_bytes_base16_lowercase() {
         let bytes_opt = Vec::<u8>::interpret_str("deadbeef");
@@ -28,25 +28,32 @@
 // For the individual detailed API documentation however, you should refer to elastic
 // documentation.
 
+mod automaton_query;
 mod elastic_query_dsl;
 mod error;
+mod exclusion_query;
+mod fast_field_exists_query;
+mod geo_point;
 mod json_literal;
 mod not_nan_f32;
 pub mod query_ast;
+mod sloppy_phrase_prefix_query;
 pub mod tokenizers;
 
 pub use elastic_query_dsl::{ElasticQueryDsl, OneFieldMap};
 pub use error::InvalidQuery;
-pub use json_literal::{InterpretUserInput, JsonLiteral};
+pub use geo_point::GeoPoint;
+pub use json_literal::{parse_byte_size, InterpretUserInput, JsonLiteral};
 pub(crate) use not_nan_f32::NotNaNf32;
-pub use query_ast::utils::find_field_or_hit_dynamic;
+pub use query_ast::utils::{find_field_or_hit_dynamic, find_object_subfields};
 use serde::{Deserialize, Serialize};
 pub use tantivy::query::Query as TantivyQuery;
 #[cfg(feature = "multilang")]
 pub use tokenizers::MultiLangTokenizer;
 pub use tokenizers::{
-    create_default_quickwit_tokenizer_manager, get_quickwit_fastfield_normalizer_manager,
-    CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
+    canonical_ip_text, create_default_quickwit_tokenizer_manager,
+    get_quickwit_fastfield_normalizer_manager, CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
+    IP_NORMALIZER_NAME,
 };
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
@@ -21,6 +21,7 @@ use serde::{Deserialize, Serialize};
 use tantivy::schema::Schema as TantivySchema;
 
 use super::{BuildTantivyAst, TantivyQueryAst};
+use crate::exclusion_query::{ColumnarExclusionQuery, TermExclusionQuery};
 use crate::query_ast::QueryAst;
 use crate::tokenizers::TokenizerManager;
 use crate::InvalidQuery;
@@ -56,49 +57,91 @@ impl From<BoolQuery> for QueryAst {
     }
 }
 
+impl BoolQuery {
+    /// Returns true if the query matches all the documents except those matching the `must_not`
+    /// clauses.
+    fn is_pure_exclusion(&self) -> bool {
+        !self.must_not.is_empty()
+            && self.should.is_empty()
+            && self.filter.is_empty()
+            && self
+                .must
+                .iter()
+                .all(|must| matches!(must, QueryAst::MatchAll))
+    }
+
+    fn build_must_not_clauses(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        with_validation: bool,
+    ) -> Result<Vec<TantivyQueryAst>, InvalidQuery> {
+        let mut must_not_leaves = Vec::with_capacity(self.must_not.len());
+        for must_not in &self.must_not {
+            let must_not_leaf =
+                must_not.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
+            must_not_leaves.push(TermExclusionQuery::wrap_must_not_clause(must_not_leaf));
+        }
+        Ok(must_not_leaves)
+    }
+
+    /// Returns the fast fields read to evaluate the query on the fast field columns, which
+    /// happens when the query only excludes terms from the full set of documents.
+    ///
+    /// These fast fields must be warmed up before running the query on a split.
+    pub fn columnar_exclusion_fast_field_names(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+    ) -> Vec<String> {
+        if !self.is_pure_exclusion() {
+            return Vec::new();
+        }
+        let Ok(must_not_leaves) = self.build_must_not_clauses(schema, tokenizer_manager, false)
+        else {
+            return Vec::new();
+        };
+        ColumnarExclusionQuery::try_from_must_not_clauses(&must_not_leaves, schema)
+            .map(|columnar_exclusion_query| {
+                columnar_exclusion_query
+                    .fast_field_names()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl BuildTantivyAst for BoolQuery {
     fn build_tantivy_ast_impl(
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        search_fields: &[String],
         with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let mut boolean_query = super::tantivy_query_ast::TantivyBoolQuery::default();
         for must in &self.must {
-            let must_leaf = must.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            )?;
+            let must_leaf =
+                must.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
             boolean_query.must.push(must_leaf);
         }
-        for must_not in &self.must_not {
-            let must_not_leaf = must_not.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            )?;
-            boolean_query.must_not.push(must_not_leaf);
+        boolean_query.must_not =
+            self.build_must_not_clauses(schema, tokenizer_manager, with_validation)?;
+        if self.is_pure_exclusion() {
+            if let Some(columnar_exclusion_query) =
+                ColumnarExclusionQuery::try_from_must_not_clauses(&boolean_query.must_not, schema)
+            {
+                return Ok(columnar_exclusion_query.into());
+            }
         }
         for should in &self.should {
-            let should_leaf = should.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            )?;
+            let should_leaf =
+                should.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
             boolean_query.should.push(should_leaf);
         }
         for filter in &self.filter {
-            let filter_leaf = filter.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            )?;
+            let filter_leaf =
+                filter.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
             boolean_query.filter.push(filter_leaf);
         }
         Ok(TantivyQueryAst::Bool(boolean_query))
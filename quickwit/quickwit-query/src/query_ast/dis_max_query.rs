@@ -0,0 +1,185 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::DisjunctionMaxQuery as TantivyDisjunctionMaxQuery;
+use tantivy::schema::Schema as TantivySchema;
+
+use super::{BuildTantivyAst, TantivyQueryAst};
+use crate::query_ast::QueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::{InvalidQuery, NotNaNf32, TantivyQuery};
+
+/// Matches the documents matching at least one of the disjuncts.
+///
+/// The score of a document is the score of its best matching disjunct, plus `tie_breaker` times
+/// the sum of the scores of the other matching disjuncts.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DisMaxQuery {
+    pub disjuncts: Vec<QueryAst>,
+    #[serde(default = "default_tie_breaker", skip_serializing_if = "is_zero")]
+    pub tie_breaker: NotNaNf32,
+}
+
+fn default_tie_breaker() -> NotNaNf32 {
+    NotNaNf32::ZERO
+}
+
+fn is_zero(tie_breaker: &NotNaNf32) -> bool {
+    *tie_breaker == NotNaNf32::ZERO
+}
+
+impl From<DisMaxQuery> for QueryAst {
+    fn from(dis_max_query: DisMaxQuery) -> Self {
+        QueryAst::DisMax(dis_max_query)
+    }
+}
+
+impl BuildTantivyAst for DisMaxQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let mut disjuncts: Vec<TantivyQueryAst> = Vec::with_capacity(self.disjuncts.len());
+
+        for disjunct in &self.disjuncts {
+            let disjunct_ast =
+                disjunct.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
+            disjuncts.push(disjunct_ast.simplify());
+        }
+        if disjuncts.len() <= 1 {
+            // The score of a single disjunct is left unchanged.
+            return Ok(disjuncts.pop().unwrap_or_else(TantivyQueryAst::match_none));
+        }
+        let disjuncts: Vec<Box<dyn TantivyQuery>> = disjuncts
+            .into_iter()
+            .map(Box::<dyn TantivyQuery>::from)
+            .collect();
+        let dis_max_query =
+            TantivyDisjunctionMaxQuery::with_tie_breaker(disjuncts, self.tie_breaker.into());
+        Ok(dis_max_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::TopDocs;
+    use tantivy::schema::TEXT;
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::query_ast::FullTextQuery;
+    use crate::{create_default_quickwit_tokenizer_manager, BooleanOperand};
+
+    fn full_text(field: &str, text: &str) -> QueryAst {
+        FullTextQuery {
+            field: field.to_string(),
+            text: text.to_string(),
+            params: crate::query_ast::FullTextParams {
+                tokenizer: None,
+                mode: BooleanOperand::Or.into(),
+                zero_terms_query: Default::default(),
+            },
+            lenient: false,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_dis_max_query_serde() {
+        let dis_max_query: QueryAst = DisMaxQuery {
+            disjuncts: vec![QueryAst::MatchAll],
+            tie_breaker: NotNaNf32::ZERO,
+        }
+        .into();
+        let dis_max_query_json = serde_json::to_string(&dis_max_query).unwrap();
+        assert_eq!(
+            dis_max_query_json,
+            r#"{"type":"dis_max","disjuncts":[{"type":"match_all"}]}"#
+        );
+        let dis_max_query_deser: QueryAst = serde_json::from_str(&dis_max_query_json).unwrap();
+        assert_eq!(dis_max_query_deser, dis_max_query);
+    }
+
+    #[test]
+    fn test_dis_max_query_empty_matches_none() {
+        let dis_max_query = DisMaxQuery {
+            disjuncts: Vec::new(),
+            tie_breaker: NotNaNf32::ZERO,
+        };
+        let schema = TantivySchema::builder().build();
+        let tantivy_ast = dis_max_query
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+        assert_eq!(tantivy_ast, TantivyQueryAst::match_none());
+    }
+
+    #[test]
+    fn test_dis_max_query_scores_best_disjunct() {
+        let mut schema_builder = TantivySchema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(title_field => "quick fox", body_field => "quick fox"))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title_field => "lazy dog", body_field => "lazy dog"))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let score = |query_ast: QueryAst| -> f32 {
+            let query = query_ast
+                .build_tantivy_query(&schema, &tokenizer_manager, true)
+                .unwrap();
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(1)).unwrap();
+            assert_eq!(top_docs.len(), 1);
+            top_docs[0].0
+        };
+        let title_score = score(full_text("title", "fox"));
+        let body_score = score(full_text("body", "fox"));
+
+        let dis_max_score = score(
+            DisMaxQuery {
+                disjuncts: vec![full_text("title", "fox"), full_text("body", "fox")],
+                tie_breaker: NotNaNf32::ZERO,
+            }
+            .into(),
+        );
+        assert!((dis_max_score - title_score.max(body_score)).abs() < 1e-6);
+
+        let dis_max_with_tie_breaker_score = score(
+            DisMaxQuery {
+                disjuncts: vec![full_text("title", "fox"), full_text("body", "fox")],
+                tie_breaker: NotNaNf32::try_from(0.5).unwrap(),
+            }
+            .into(),
+        );
+        let expected_score = title_score.max(body_score) + 0.5 * title_score.min(body_score);
+        assert!((dis_max_with_tie_breaker_score - expected_score).abs() < 1e-6);
+    }
+}
@@ -20,13 +20,18 @@
 use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
 use quickwit_common::PathHasher;
 use serde::{Deserialize, Serialize};
-use tantivy::schema::{Field, IndexRecordOption, Schema as TantivySchema};
+use tantivy::schema::{Field, FieldEntry, IndexRecordOption, Schema as TantivySchema, Type};
 use tantivy::Term;
 
-use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::fast_field_exists_query::FastFieldExistsQuery;
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+use crate::query_ast::utils::{
+    find_field_for_query, find_object_subfields, FieldQueryKind, DYNAMIC_FIELD_NAME,
+};
+use crate::query_ast::wildcard_query::DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS;
 use crate::query_ast::{BuildTantivyAst, QueryAst};
 use crate::tokenizers::TokenizerManager;
-use crate::{find_field_or_hit_dynamic, InvalidQuery};
+use crate::{BooleanOperand, InvalidQuery};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct FieldPresenceQuery {
@@ -69,40 +74,114 @@ fn compute_field_presence_hash(field: Field, field_path: &str) -> u64 {
     path_hasher.finish()
 }
 
-impl BuildTantivyAst for FieldPresenceQuery {
-    fn build_tantivy_ast_impl(
+/// Builds the query matching the documents having a value for `field` at `path`.
+fn build_field_presence_ast(
+    field_presence_field: Field,
+    field: Field,
+    field_entry: &FieldEntry,
+    path: &str,
+) -> TantivyQueryAst {
+    let field_presence_term_query = || {
+        let field_presence_hash = compute_field_presence_hash(field, path);
+        let field_presence_term: Term =
+            Term::from_field_u64(field_presence_field, field_presence_hash);
+        let field_presence_term_query =
+            tantivy::query::TermQuery::new(field_presence_term, IndexRecordOption::Basic);
+        TantivyQueryAst::from(field_presence_term_query)
+    };
+    if !field_entry.is_fast() {
+        // fallback to the presence field
+        return field_presence_term_query();
+    }
+    let full_path = if path.is_empty() {
+        field_entry.name().to_string()
+    } else {
+        format!("{}.{}", field_entry.name(), path)
+    };
+    let exists_query = TantivyQueryAst::from(FastFieldExistsQuery::new(full_path));
+
+    if field_entry.field_type().value_type() != Type::Json {
+        return exists_query;
+    }
+    // The path may refer to an object of the JSON field, which has no column: the presence of
+    // JSON objects is recorded in the presence field.
+    TantivyBoolQuery::build_clause(
+        BooleanOperand::Or,
+        vec![exists_query, field_presence_term_query()],
+    )
+    .into()
+}
+
+impl FieldPresenceQuery {
+    fn build_tantivy_ast_with_max_expansions(
         &self,
         schema: &TantivySchema,
-        _tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
-        _with_validation: bool,
+        max_expansions: u32,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let field_presence_field = schema.get_field(FIELD_PRESENCE_FIELD_NAME).map_err(|_| {
             InvalidQuery::SchemaError("field presence is not available for this split".to_string())
         })?;
-        let (field, field_entry, path) = find_field_or_hit_dynamic(&self.field, schema)?;
-        if field_entry.is_fast() {
-            let full_path = if path.is_empty() {
-                field_entry.name().to_string()
-            } else {
-                format!("{}.{}", field_entry.name(), path)
-            };
-            let exists_query = tantivy::query::ExistsQuery::new_exists_query(full_path);
-            Ok(TantivyQueryAst::from(exists_query))
-        } else {
-            // fallback to the presence field
-            let field_presence_hash = compute_field_presence_hash(field, path);
-            let field_presence_term: Term =
-                Term::from_field_u64(field_presence_field, field_presence_hash);
-            let field_presence_term_query =
-                tantivy::query::TermQuery::new(field_presence_term, IndexRecordOption::Basic);
-            Ok(TantivyQueryAst::from(field_presence_term_query))
+        let object_subfields: Vec<(Field, &FieldEntry)> =
+            find_object_subfields(&self.field, schema)
+                .into_iter()
+                .filter(|(_, field_entry)| field_entry.is_indexed() || field_entry.is_fast())
+                .collect();
+
+        if object_subfields.is_empty() {
+            let (field, field_entry, path) =
+                find_field_for_query(&self.field, schema, FieldQueryKind::Exists)?;
+            return Ok(build_field_presence_ast(
+                field_presence_field,
+                field,
+                field_entry,
+                path,
+            ));
+        }
+        // The field is an object of the doc mapping: it is present if any of its subfields is.
+        // In dynamic mode, the subfields missing from the doc mapping are nested in the dynamic
+        // field at the path of the object.
+        let dynamic_field_opt = schema.get_field(DYNAMIC_FIELD_NAME).ok();
+        let num_expansions = object_subfields.len() + dynamic_field_opt.is_some() as usize;
+
+        if num_expansions > max_expansions as usize {
+            return Err(InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_expansions",
+                max: max_expansions as usize,
+            });
         }
+        let mut subfield_presence_asts: Vec<TantivyQueryAst> = object_subfields
+            .into_iter()
+            .map(|(field, field_entry)| {
+                build_field_presence_ast(field_presence_field, field, field_entry, "")
+            })
+            .collect();
+        if let Some(dynamic_field) = dynamic_field_opt {
+            let dynamic_field_entry = schema.get_field_entry(dynamic_field);
+            subfield_presence_asts.push(build_field_presence_ast(
+                field_presence_field,
+                dynamic_field,
+                dynamic_field_entry,
+                &self.field,
+            ));
+        }
+        Ok(TantivyBoolQuery::build_clause(BooleanOperand::Or, subfield_presence_asts).into())
+    }
+}
+
+impl BuildTantivyAst for FieldPresenceQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _tokenizer_manager: &TokenizerManager,
+        _with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        self.build_tantivy_ast_with_max_expansions(schema, DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use tantivy::schema::{FAST, INDEXED, STORED, TEXT};
 
     use super::*;
 
@@ -135,4 +214,110 @@ mod tests {
             PathHasher::hash_path(&[&17u32.to_le_bytes()[..], b"attributes.color", b"hello"])
         );
     }
+
+    fn make_schema(dynamic_mode: bool) -> TantivySchema {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_text_field("user.name", TEXT);
+        schema_builder.add_text_field("user.address.city", TEXT);
+        schema_builder.add_u64_field("user.address.zip", FAST);
+        schema_builder.add_text_field("user.comment", STORED);
+        schema_builder.add_text_field("username", TEXT);
+        schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED);
+        if dynamic_mode {
+            schema_builder.add_json_field(DYNAMIC_FIELD_NAME, TEXT | FAST);
+        }
+        schema_builder.build()
+    }
+
+    fn field_presence_query(field: &str) -> FieldPresenceQuery {
+        FieldPresenceQuery {
+            field: field.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_field_presence_object_field() {
+        let schema = make_schema(false);
+        let tantivy_ast = field_presence_query("user")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap();
+        // `user.comment` is neither indexed nor fast, and `username` is not nested in `user`.
+        let bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(bool_query.should.len(), 3);
+        assert!(bool_query.must.is_empty());
+
+        let tantivy_ast_debug = format!("{tantivy_ast:?}");
+        assert!(tantivy_ast_debug.contains(r#"FastFieldExistsQuery { field: "user.address.zip""#));
+
+        let tantivy_ast = field_presence_query("user.address")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap();
+        let bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(bool_query.should.len(), 2);
+
+        let tantivy_ast = field_presence_query("user.name")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap();
+        assert!(tantivy_ast.as_leaf().is_some());
+
+        let error = field_presence_query("customer")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::FieldDoesNotExist { .. }));
+    }
+
+    #[test]
+    fn test_field_presence_object_field_max_expansions() {
+        let schema = make_schema(false);
+        field_presence_query("user")
+            .build_tantivy_ast_with_max_expansions(&schema, 3)
+            .unwrap();
+        let error = field_presence_query("user")
+            .build_tantivy_ast_with_max_expansions(&schema, 2)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_expansions",
+                max: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn test_field_presence_object_field_dynamic_mode() {
+        let schema = make_schema(true);
+        let dynamic_field = schema.get_field(DYNAMIC_FIELD_NAME).unwrap();
+
+        // The subfields of `user` missing from the doc mapping are looked up in the dynamic field.
+        let tantivy_ast = field_presence_query("user")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap();
+        let bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(bool_query.should.len(), 4);
+
+        let error = field_presence_query("user")
+            .build_tantivy_ast_with_max_expansions(&schema, 3)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::QueryAstLimitExceeded { .. }));
+
+        // Objects only known from the indexed documents are found in the column index if the
+        // dynamic field is fast, and in the presence field otherwise.
+        let tantivy_ast = field_presence_query("customer.address")
+            .build_tantivy_ast_with_max_expansions(&schema, 10)
+            .unwrap();
+        let bool_query = tantivy_ast.as_bool_query().unwrap();
+        assert_eq!(bool_query.should.len(), 2);
+
+        let tantivy_ast_debug = format!("{tantivy_ast:?}");
+        assert!(tantivy_ast_debug
+            .contains(r#"FastFieldExistsQuery { field: "_dynamic.customer.address""#));
+
+        let field_presence_hash = PathHasher::hash_path(&[
+            &dynamic_field.field_id().to_le_bytes()[..],
+            b"customer",
+            b"address",
+        ]);
+        assert!(tantivy_ast_debug.contains(&format!("{field_presence_hash}")));
+    }
 }
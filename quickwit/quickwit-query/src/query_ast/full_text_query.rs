@@ -20,9 +20,10 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use tantivy::query::{
-    PhrasePrefixQuery as TantivyPhrasePrefixQuery, PhraseQuery as TantivyPhraseQuery,
-    TermQuery as TantivyTermQuery,
+    BooleanQuery as TantivyBooleanQuery, PhrasePrefixQuery as TantivyPhrasePrefixQuery,
+    PhraseQuery as TantivyPhraseQuery, Query as TantivyQuery, TermQuery as TantivyTermQuery,
 };
+use tantivy::query_grammar::Occur;
 use tantivy::schema::{
     Field, FieldType, IndexRecordOption, JsonObjectOptions, Schema as TantivySchema,
     TextFieldIndexing,
@@ -118,7 +119,27 @@ impl FullTextParams {
             return Ok(TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into());
         }
         match self.mode {
-            FullTextMode::Bool { operator } => {
+            FullTextMode::Bool {
+                operator: BooleanOperand::Or,
+                minimum_should_match: Some(minimum_should_match),
+            } => {
+                let num_terms = terms.len();
+                let clauses: Vec<(Occur, Box<dyn TantivyQuery>)> = terms
+                    .into_iter()
+                    .map(|(_, term)| {
+                        let term_query: Box<dyn TantivyQuery> =
+                            Box::new(TantivyTermQuery::new(term, index_record_option));
+                        (Occur::Should, term_query)
+                    })
+                    .collect();
+                let minimum_required_clauses = minimum_should_match.resolve(num_terms);
+                Ok(TantivyBooleanQuery::with_minimum_required_clauses(
+                    clauses,
+                    minimum_required_clauses,
+                )
+                .into())
+            }
+            FullTextMode::Bool { operator, .. } => {
                 let leaf_queries: Vec<TantivyQueryAst> = terms
                     .into_iter()
                     .map(|(_, term)| TantivyTermQuery::new(term, index_record_option).into())
@@ -180,6 +201,9 @@ pub enum FullTextMode {
     // create a boolean clause (conjunction or disjunction based on the operator).
     Bool {
         operator: BooleanOperand,
+        // Only used with the `Or` operator.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        minimum_should_match: Option<MinimumShouldMatch>,
     },
     BoolPrefix {
         operator: BooleanOperand,
@@ -203,7 +227,58 @@ pub enum FullTextMode {
 
 impl From<BooleanOperand> for FullTextMode {
     fn from(operator: BooleanOperand) -> Self {
-        FullTextMode::Bool { operator }
+        FullTextMode::Bool {
+            operator,
+            minimum_should_match: None,
+        }
+    }
+}
+
+/// Minimum number of terms of a disjunction a document has to match, expressed like the
+/// Elasticsearch `minimum_should_match` parameter.
+///
+/// Negative values express the number of terms that may be missing. Whatever the value, the
+/// resolved number of terms is at least 1 and at most the number of terms.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MinimumShouldMatch {
+    Count(i32),
+    // Percentage of the number of terms, rounded down.
+    Percentage(i32),
+}
+
+impl MinimumShouldMatch {
+    pub fn resolve(&self, num_terms: usize) -> usize {
+        let num_terms_i64 = num_terms as i64;
+        let minimum_should_match = match *self {
+            MinimumShouldMatch::Count(count) if count < 0 => num_terms_i64 + count as i64,
+            MinimumShouldMatch::Count(count) => count as i64,
+            MinimumShouldMatch::Percentage(percentage) if percentage < 0 => {
+                num_terms_i64 - num_terms_i64 * (-percentage as i64) / 100
+            }
+            MinimumShouldMatch::Percentage(percentage) => num_terms_i64 * percentage as i64 / 100,
+        };
+        minimum_should_match.clamp(1, num_terms_i64.max(1)) as usize
+    }
+}
+
+impl std::str::FromStr for MinimumShouldMatch {
+    type Err = String;
+
+    fn from_str(minimum_should_match_str: &str) -> Result<Self, Self::Err> {
+        let trimmed = minimum_should_match_str.trim();
+        let parse_error = || {
+            format!(
+                "invalid `minimum_should_match` value `{minimum_should_match_str}`: expected an \
+                 integer or a percentage"
+            )
+        };
+        if let Some(percentage_str) = trimmed.strip_suffix('%') {
+            let percentage: i32 = percentage_str.parse().map_err(|_| parse_error())?;
+            return Ok(MinimumShouldMatch::Percentage(percentage));
+        }
+        let count: i32 = trimmed.parse().map_err(|_| parse_error())?;
+        Ok(MinimumShouldMatch::Count(count))
     }
 }
 
@@ -241,7 +316,6 @@ impl BuildTantivyAst for FullTextQuery {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         full_text_query(
@@ -331,12 +405,7 @@ mod tests {
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         assert_eq!(ast.const_predicate(), Some(crate::MatchAllOrNone::MatchAll));
     }
@@ -357,12 +426,7 @@ mod tests {
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
         assert_eq!(
@@ -388,12 +452,7 @@ mod tests {
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
         assert_eq!(
@@ -418,14 +477,35 @@ mod tests {
         schema_builder.add_text_field("body", TEXT);
         let schema = schema_builder.build();
         let ast: TantivyQueryAst = full_text_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let bool_query = ast.as_bool_query().unwrap();
         assert_eq!(bool_query.must.len(), 2);
     }
+
+    #[test]
+    fn test_minimum_should_match_resolve() {
+        use super::MinimumShouldMatch;
+
+        assert_eq!(MinimumShouldMatch::Count(2).resolve(3), 2);
+        assert_eq!(MinimumShouldMatch::Count(5).resolve(3), 3);
+        assert_eq!(MinimumShouldMatch::Count(0).resolve(3), 1);
+        assert_eq!(MinimumShouldMatch::Count(-1).resolve(3), 2);
+        assert_eq!(MinimumShouldMatch::Count(-5).resolve(3), 1);
+        assert_eq!(MinimumShouldMatch::Percentage(75).resolve(4), 3);
+        assert_eq!(MinimumShouldMatch::Percentage(75).resolve(3), 2);
+        assert_eq!(MinimumShouldMatch::Percentage(-25).resolve(3), 3);
+        assert_eq!(MinimumShouldMatch::Percentage(-25).resolve(4), 3);
+        assert_eq!(MinimumShouldMatch::Percentage(200).resolve(4), 4);
+
+        assert_eq!(
+            "75%".parse::<MinimumShouldMatch>().unwrap(),
+            MinimumShouldMatch::Percentage(75)
+        );
+        assert_eq!(
+            " -2 ".parse::<MinimumShouldMatch>().unwrap(),
+            MinimumShouldMatch::Count(-2)
+        );
+        assert!("3<90%".parse::<MinimumShouldMatch>().is_err());
+    }
 }
@@ -0,0 +1,253 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Bound;
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::{BoolQuery, BuildTantivyAst, QueryAst, RangeQuery};
+use crate::tokenizers::TokenizerManager;
+use crate::{GeoPoint, InterpretUserInput, InvalidQuery, JsonLiteral};
+
+/// Matches documents with a geo point within a bounding box. Bounds are inclusive.
+///
+/// The geo point is expected to be stored as an object with `lat` and `lon` fast fields,
+/// e.g. `{"location": {"lat": 48.85, "lon": 2.35}}`. The query is translated into range
+/// queries over `{field}.lat` and `{field}.lon`.
+///
+/// The corners can be expressed in any form accepted by [`GeoPoint`]. If the longitude of
+/// the top left corner is greater than the one of the bottom right corner, the box is
+/// considered to cross the antimeridian.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GeoBoundingBoxQuery {
+    pub field: String,
+    pub top_left: JsonLiteral,
+    pub bottom_right: JsonLiteral,
+}
+
+impl From<GeoBoundingBoxQuery> for QueryAst {
+    fn from(geo_bounding_box_query: GeoBoundingBoxQuery) -> Self {
+        QueryAst::GeoBoundingBox(geo_bounding_box_query)
+    }
+}
+
+fn coordinate_literal(coordinate: f64) -> JsonLiteral {
+    let number =
+        serde_json::Number::from_f64(coordinate).expect("geo point coordinates should be finite");
+    JsonLiteral::Number(number)
+}
+
+fn coordinate_range_query(field: String, lower_bound: f64, upper_bound: f64) -> QueryAst {
+    RangeQuery {
+        field,
+        lower_bound: Bound::Included(coordinate_literal(lower_bound)),
+        upper_bound: Bound::Included(coordinate_literal(upper_bound)),
+    }
+    .into()
+}
+
+impl GeoBoundingBoxQuery {
+    pub fn lat_field(&self) -> String {
+        format!("{}.lat", self.field)
+    }
+
+    pub fn lon_field(&self) -> String {
+        format!("{}.lon", self.field)
+    }
+
+    fn interpret_corner(&self, corner: &JsonLiteral) -> Result<GeoPoint, InvalidQuery> {
+        GeoPoint::interpret_json(corner).ok_or_else(|| InvalidQuery::InvalidSearchTerm {
+            expected_value_type: GeoPoint::name(),
+            field_name: self.field.clone(),
+            value: serde_json::to_string(corner).unwrap_or_default(),
+        })
+    }
+
+    /// Translates the bounding box into range queries over the latitude and longitude fields.
+    fn to_range_queries(&self) -> Result<QueryAst, InvalidQuery> {
+        let top_left = self.interpret_corner(&self.top_left)?;
+        let bottom_right = self.interpret_corner(&self.bottom_right)?;
+
+        if top_left.lat < bottom_right.lat {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "top left latitude `{}` is below bottom right latitude `{}` for field `{}`",
+                top_left.lat,
+                bottom_right.lat,
+                self.field
+            )));
+        }
+        let lat_query = coordinate_range_query(self.lat_field(), bottom_right.lat, top_left.lat);
+        let lon_query = if top_left.lon <= bottom_right.lon {
+            coordinate_range_query(self.lon_field(), top_left.lon, bottom_right.lon)
+        } else {
+            BoolQuery {
+                should: vec![
+                    coordinate_range_query(self.lon_field(), top_left.lon, 180.0),
+                    coordinate_range_query(self.lon_field(), -180.0, bottom_right.lon),
+                ],
+                ..Default::default()
+            }
+            .into()
+        };
+        let bool_query = BoolQuery {
+            filter: vec![lat_query, lon_query],
+            ..Default::default()
+        };
+        Ok(bool_query.into())
+    }
+}
+
+impl BuildTantivyAst for GeoBoundingBoxQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        self.to_range_queries()?
+            .build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::Count;
+    use tantivy::schema::{Schema, FAST};
+    use tantivy::{doc, Index};
+
+    use super::GeoBoundingBoxQuery;
+    use crate::query_ast::QueryAst;
+    use crate::{create_default_quickwit_tokenizer_manager, InvalidQuery, JsonLiteral};
+
+    /// Returns the number of points matched by a bounding box query.
+    fn count_matching_points(
+        points: &[(f64, f64)],
+        top_left: JsonLiteral,
+        bottom_right: JsonLiteral,
+    ) -> usize {
+        let mut schema_builder = Schema::builder();
+        let lat_field = schema_builder.add_f64_field("location.lat", FAST);
+        let lon_field = schema_builder.add_f64_field("location.lon", FAST);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for (lat, lon) in points {
+            index_writer
+                .add_document(doc!(lat_field => *lat, lon_field => *lon))
+                .unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let query_ast: QueryAst = GeoBoundingBoxQuery {
+            field: "location".to_string(),
+            top_left,
+            bottom_right,
+        }
+        .into();
+        let query = query_ast
+            .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        searcher.search(&query, &Count).unwrap()
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query_edges() {
+        let top_left = JsonLiteral::String("49,2".to_string());
+        let bottom_right = JsonLiteral::String("48,3".to_string());
+        // Points on the edges are included.
+        let points_inside = [
+            (49.0, 2.0),
+            (48.0, 3.0),
+            (48.5, 2.5),
+            (48.0, 2.0),
+            (49.0, 3.0),
+        ];
+        assert_eq!(
+            count_matching_points(&points_inside, top_left.clone(), bottom_right.clone()),
+            points_inside.len()
+        );
+        // Points right outside of the edges are excluded.
+        let points_outside = [
+            (49.000001, 2.5),
+            (47.999999, 2.5),
+            (48.5, 1.999999),
+            (48.5, 3.000001),
+            (-48.5, 2.5),
+            (48.5, -2.5),
+        ];
+        assert_eq!(
+            count_matching_points(&points_outside, top_left, bottom_right),
+            0
+        );
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query_literal_forms() {
+        let points = [(48.7, 2.5), (48.0, 2.5)];
+        // Same box expressed as GeoJSON-style arrays.
+        let top_left = JsonLiteral::Array(vec![JsonLiteral::from(2u64), JsonLiteral::from(49u64)]);
+        let bottom_right =
+            JsonLiteral::Array(vec![JsonLiteral::from(3u64), JsonLiteral::from(48u64)]);
+        assert_eq!(count_matching_points(&points, top_left, bottom_right), 2);
+        // A geohash corner is interpreted as the center of its cell.
+        let top_left = JsonLiteral::String("u09tvw".to_string());
+        let bottom_right = JsonLiteral::String("48.5,3".to_string());
+        assert_eq!(count_matching_points(&points, top_left, bottom_right), 1);
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query_crossing_antimeridian() {
+        let top_left = JsonLiteral::String("10,170".to_string());
+        let bottom_right = JsonLiteral::String("-10,-170".to_string());
+        let points = [(0.0, 175.0), (0.0, -175.0), (0.0, 180.0), (0.0, 0.0)];
+        assert_eq!(count_matching_points(&points, top_left, bottom_right), 3);
+    }
+
+    #[test]
+    fn test_geo_bounding_box_query_invalid() {
+        let schema = Schema::builder().build();
+        let build_error = |top_left: &str, bottom_right: &str| {
+            let query_ast: QueryAst = GeoBoundingBoxQuery {
+                field: "location".to_string(),
+                top_left: JsonLiteral::String(top_left.to_string()),
+                bottom_right: JsonLiteral::String(bottom_right.to_string()),
+            }
+            .into();
+            query_ast
+                .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+                .unwrap_err()
+        };
+        assert!(matches!(
+            build_error("not a point", "48,3"),
+            InvalidQuery::InvalidSearchTerm {
+                expected_value_type: "geo_point",
+                ..
+            }
+        ));
+        assert!(matches!(
+            build_error("48,2", "49,3"),
+            InvalidQuery::Other(_)
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::query_ast::{BoolQuery, DisMaxQuery, QueryAst, QueryAstVisitor, TermSetQuery};
+use crate::{InvalidQuery, NotNaNf32};
+
+/// Default maximum depth of a query AST.
+pub const DEFAULT_MAX_QUERY_AST_DEPTH: usize = 64;
+
+/// Default maximum number of leaf clauses in a query AST.
+pub const DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT: usize = 1_024;
+
+/// Limits on the shape of a query AST, protecting searchers from pathological queries.
+///
+/// The depth counts the nodes on the longest path from the root to a leaf, so a single term
+/// query has a depth of 1. The clause count is the total number of leaf clauses, where a term
+/// set query counts as many clauses as it has terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryAstLimits {
+    pub max_depth: usize,
+    pub max_clause_count: usize,
+}
+
+impl Default for QueryAstLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_QUERY_AST_DEPTH,
+            max_clause_count: DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT,
+        }
+    }
+}
+
+impl QueryAstLimits {
+    /// Applies per-request overrides. Overrides can only lower the limits: values above the
+    /// current limits are ignored.
+    pub fn with_overrides(
+        self,
+        max_depth_opt: Option<usize>,
+        max_clause_count_opt: Option<usize>,
+    ) -> Self {
+        Self {
+            max_depth: max_depth_opt
+                .map_or(self.max_depth, |max_depth| max_depth.min(self.max_depth)),
+            max_clause_count: max_clause_count_opt
+                .map_or(self.max_clause_count, |max_clause_count| {
+                    max_clause_count.min(self.max_clause_count)
+                }),
+        }
+    }
+
+    /// Returns an error if the query AST exceeds one of the limits.
+    pub fn check(&self, query_ast: &QueryAst) -> Result<(), InvalidQuery> {
+        let mut limits_checker = LimitsChecker {
+            limits: self,
+            depth: 0,
+            clause_count: 0,
+        };
+        limits_checker.visit(query_ast)
+    }
+}
+
+struct LimitsChecker<'a> {
+    limits: &'a QueryAstLimits,
+    depth: usize,
+    clause_count: usize,
+}
+
+impl LimitsChecker<'_> {
+    fn enter_node(&mut self) -> Result<(), InvalidQuery> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_query_ast_depth",
+                max: self.limits.max_depth,
+            });
+        }
+        Ok(())
+    }
+
+    fn add_clauses(&mut self, num_clauses: usize) -> Result<(), InvalidQuery> {
+        self.clause_count += num_clauses;
+        if self.clause_count > self.limits.max_clause_count {
+            return Err(InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_query_ast_clause_count",
+                max: self.limits.max_clause_count,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for LimitsChecker<'_> {
+    type Err = InvalidQuery;
+
+    fn visit(&mut self, query_ast: &'a QueryAst) -> Result<(), InvalidQuery> {
+        self.enter_node()?;
+        match query_ast {
+            QueryAst::Bool(bool_query) => self.visit_bool(bool_query)?,
+            QueryAst::DisMax(dis_max_query) => self.visit_dis_max(dis_max_query)?,
+            QueryAst::Boost { underlying, boost } => self.visit_boost(underlying, *boost)?,
+            QueryAst::TermSet(term_set_query) => self.visit_term_set(term_set_query)?,
+            _ => self.add_clauses(1)?,
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, bool_query: &'a BoolQuery) -> Result<(), InvalidQuery> {
+        for ast in bool_query
+            .must
+            .iter()
+            .chain(bool_query.should.iter())
+            .chain(bool_query.must_not.iter())
+            .chain(bool_query.filter.iter())
+        {
+            self.visit(ast)?;
+        }
+        Ok(())
+    }
+
+    fn visit_boost(
+        &mut self,
+        underlying: &'a QueryAst,
+        _boost: NotNaNf32,
+    ) -> Result<(), InvalidQuery> {
+        self.visit(underlying)
+    }
+
+    fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> Result<(), InvalidQuery> {
+        let num_terms: usize = term_set_query
+            .terms_per_field
+            .values()
+            .map(|terms| terms.len())
+            .sum();
+        self.add_clauses(num_terms.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+    use crate::query_ast::TermQuery;
+
+    fn term_query(value: &str) -> QueryAst {
+        TermQuery {
+            field: "body".to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    fn nested_bool_query(depth: usize) -> QueryAst {
+        let mut query_ast = term_query("leaf");
+        for _ in 1..depth {
+            query_ast = BoolQuery {
+                must: vec![query_ast],
+                ..Default::default()
+            }
+            .into();
+        }
+        query_ast
+    }
+
+    #[test]
+    fn test_query_ast_limits_with_overrides_only_lower_limits() {
+        let limits = QueryAstLimits::default().with_overrides(Some(10), Some(10_000));
+        assert_eq!(limits.max_depth, 10);
+        assert_eq!(limits.max_clause_count, DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT);
+
+        let limits = QueryAstLimits::default().with_overrides(None, None);
+        assert_eq!(limits, QueryAstLimits::default());
+    }
+
+    #[test]
+    fn test_query_ast_limits_depth() {
+        let limits = QueryAstLimits::default();
+        limits
+            .check(&nested_bool_query(DEFAULT_MAX_QUERY_AST_DEPTH))
+            .unwrap();
+        let error = limits
+            .check(&nested_bool_query(DEFAULT_MAX_QUERY_AST_DEPTH + 1))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_query_ast_depth",
+                max: DEFAULT_MAX_QUERY_AST_DEPTH,
+            }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "query exceeds the `max_query_ast_depth` limit of 64"
+        );
+        let limits = QueryAstLimits::default().with_overrides(Some(3), None);
+        limits.check(&nested_bool_query(3)).unwrap();
+        limits.check(&nested_bool_query(4)).unwrap_err();
+    }
+
+    #[test]
+    fn test_query_ast_limits_clause_count() {
+        let limits = QueryAstLimits::default();
+        let wide_bool_query: QueryAst = BoolQuery {
+            should: (0..DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT + 1)
+                .map(|i| term_query(&i.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+        .into();
+        let error = limits.check(&wide_bool_query).unwrap_err();
+        assert!(matches!(
+            error,
+            InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_query_ast_clause_count",
+                max: DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT,
+            }
+        ));
+
+        let terms: BTreeSet<String> = (0..DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT)
+            .map(|i| i.to_string())
+            .collect();
+        let term_set_query: QueryAst = TermSetQuery {
+            terms_per_field: HashMap::from([("body".to_string(), terms)]),
+        }
+        .into();
+        limits.check(&term_set_query).unwrap();
+        QueryAstLimits::default()
+            .with_overrides(None, Some(100))
+            .check(&term_set_query)
+            .unwrap_err();
+    }
+}
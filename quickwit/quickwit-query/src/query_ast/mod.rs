@@ -24,10 +24,15 @@ use tantivy::schema::Schema as TantivySchema;
 use crate::tokenizers::TokenizerManager;
 
 mod bool_query;
+mod dis_max_query;
 mod field_presence;
 mod full_text_query;
+mod geo_bounding_box_query;
+mod limits;
 mod phrase_prefix_query;
 mod range_query;
+mod regex_query;
+mod saved_query_ast;
 mod tantivy_query_ast;
 mod term_query;
 mod term_set_query;
@@ -37,13 +42,18 @@ mod visitor;
 mod wildcard_query;
 
 pub use bool_query::BoolQuery;
+pub use dis_max_query::DisMaxQuery;
 pub use field_presence::FieldPresenceQuery;
-pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
-pub use phrase_prefix_query::PhrasePrefixQuery;
+pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery, MinimumShouldMatch};
+pub use geo_bounding_box_query::GeoBoundingBoxQuery;
+pub use limits::{QueryAstLimits, DEFAULT_MAX_QUERY_AST_CLAUSE_COUNT, DEFAULT_MAX_QUERY_AST_DEPTH};
+pub use phrase_prefix_query::{PhrasePrefixQuery, MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS};
 pub use range_query::RangeQuery;
+pub use regex_query::RegexQuery;
 use tantivy_query_ast::TantivyQueryAst;
 pub use term_query::TermQuery;
 pub use term_set_query::TermSetQuery;
+use user_input_query::UserInputFieldsResolver;
 pub use user_input_query::UserInputQuery;
 pub use visitor::{QueryAstTransformer, QueryAstVisitor};
 pub use wildcard_query::WildcardQuery;
@@ -55,6 +65,7 @@ use crate::{BooleanOperand, InvalidQuery, NotNaNf32};
 #[serde(rename_all = "snake_case")]
 pub enum QueryAst {
     Bool(BoolQuery),
+    DisMax(DisMaxQuery),
     Term(TermQuery),
     TermSet(TermSetQuery),
     FieldPresence(FieldPresenceQuery),
@@ -63,6 +74,8 @@ pub enum QueryAst {
     Range(RangeQuery),
     UserInput(UserInputQuery),
     Wildcard(WildcardQuery),
+    Regex(RegexQuery),
+    GeoBoundingBox(GeoBoundingBoxQuery),
     MatchAll,
     MatchNone,
     Boost {
@@ -95,6 +108,17 @@ impl QueryAst {
                 }
                 .into())
             }
+            QueryAst::DisMax(DisMaxQuery {
+                disjuncts,
+                tie_breaker,
+            }) => {
+                let disjuncts = parse_user_query_in_asts(disjuncts, default_search_fields)?;
+                Ok(DisMaxQuery {
+                    disjuncts,
+                    tie_breaker,
+                }
+                .into())
+            }
             ast @ QueryAst::Term(_)
             | ast @ QueryAst::TermSet(_)
             | ast @ QueryAst::FullText(_)
@@ -103,7 +127,9 @@ impl QueryAst {
             | ast @ QueryAst::MatchNone
             | ast @ QueryAst::FieldPresence(_)
             | ast @ QueryAst::Range(_)
-            | ast @ QueryAst::Wildcard(_) => Ok(ast),
+            | ast @ QueryAst::Wildcard(_)
+            | ast @ QueryAst::Regex(_)
+            | ast @ QueryAst::GeoBoundingBox(_) => Ok(ast),
             QueryAst::UserInput(user_text_query) => {
                 user_text_query.parse_user_query(default_search_fields)
             }
@@ -117,6 +143,27 @@ impl QueryAst {
         }
     }
 
+    /// Resolves the default fields of the `UserInputQuery` nodes against the schema, expanding
+    /// field patterns such as `user.*` and checking that every other field exists.
+    ///
+    /// The nodes that do not define their own default fields are given
+    /// `default_fields_override`, unless it is empty, in which case they keep relying on the
+    /// default search fields passed to [`QueryAst::parse_user_query`].
+    pub fn resolve_user_input_fields(
+        self,
+        schema: &TantivySchema,
+        default_fields_override: &[String],
+    ) -> Result<QueryAst, InvalidQuery> {
+        let mut user_input_fields_resolver = UserInputFieldsResolver {
+            schema,
+            default_fields_override,
+        };
+        let query_ast = user_input_fields_resolver
+            .transform(self)?
+            .unwrap_or(QueryAst::MatchNone);
+        Ok(query_ast)
+    }
+
     pub fn boost(self, scale_boost_opt: Option<NotNaNf32>) -> Self {
         let Some(scale_boost) = scale_boost_opt else {
             return self;
@@ -152,7 +199,6 @@ trait BuildTantivyAst {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        search_fields: &[String],
         with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery>;
 
@@ -161,11 +207,10 @@ trait BuildTantivyAst {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        search_fields: &[String],
         with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let tantivy_ast_res =
-            self.build_tantivy_ast_impl(schema, tokenizer_manager, search_fields, with_validation);
+            self.build_tantivy_ast_impl(schema, tokenizer_manager, with_validation);
         if !with_validation && tantivy_ast_res.is_err() {
             return match tantivy_ast_res {
                 res @ Ok(_) | res @ Err(InvalidQuery::UserQueryNotParsed) => res,
@@ -181,72 +226,55 @@ impl BuildTantivyAst for QueryAst {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        search_fields: &[String],
         with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         match self {
-            QueryAst::Bool(bool_query) => bool_query.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
-            QueryAst::Term(term_query) => term_query.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
-            QueryAst::Range(range_query) => range_query.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
+            QueryAst::Bool(bool_query) => {
+                bool_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::DisMax(dis_max_query) => {
+                dis_max_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::Term(term_query) => {
+                term_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::Range(range_query) => {
+                range_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
             QueryAst::MatchAll => Ok(TantivyQueryAst::match_all()),
             QueryAst::MatchNone => Ok(TantivyQueryAst::match_none()),
             QueryAst::Boost { boost, underlying } => {
                 let underlying = underlying.build_tantivy_ast_call(
                     schema,
                     tokenizer_manager,
-                    search_fields,
                     with_validation,
                 )?;
                 let boost_query = TantivyBoostQuery::new(underlying.into(), (*boost).into());
                 Ok(boost_query.into())
             }
-            QueryAst::TermSet(term_set) => term_set.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
-            QueryAst::FullText(full_text_query) => full_text_query.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
+            QueryAst::TermSet(term_set) => {
+                term_set.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::FullText(full_text_query) => {
+                full_text_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
             QueryAst::PhrasePrefix(phrase_prefix_query) => phrase_prefix_query
-                .build_tantivy_ast_call(schema, tokenizer_manager, search_fields, with_validation),
-            QueryAst::UserInput(user_text_query) => user_text_query.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
-            QueryAst::FieldPresence(field_presence) => field_presence.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
-            QueryAst::Wildcard(wildcard) => wildcard.build_tantivy_ast_call(
-                schema,
-                tokenizer_manager,
-                search_fields,
-                with_validation,
-            ),
+                .build_tantivy_ast_call(schema, tokenizer_manager, with_validation),
+            QueryAst::UserInput(user_text_query) => {
+                user_text_query.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::FieldPresence(field_presence) => {
+                field_presence.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::Wildcard(wildcard) => {
+                wildcard.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::Regex(regex) => {
+                regex.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
+            QueryAst::GeoBoundingBox(geo_bounding_box) => {
+                geo_bounding_box.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)
+            }
         }
     }
 }
@@ -256,11 +284,10 @@ impl QueryAst {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        search_fields: &[String],
         with_validation: bool,
     ) -> Result<Box<dyn crate::TantivyQuery>, InvalidQuery> {
         let tantivy_query_ast =
-            self.build_tantivy_ast_call(schema, tokenizer_manager, search_fields, with_validation)?;
+            self.build_tantivy_ast_call(schema, tokenizer_manager, with_validation)?;
         Ok(tantivy_query_ast.simplify().into())
     }
 }
@@ -323,11 +350,19 @@ pub fn query_ast_from_user_text(user_text: &str, default_fields: Option<Vec<Stri
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
+    use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
+    use tantivy::schema::{Schema, FAST, INDEXED, STORED};
+
     use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
     use crate::query_ast::{
-        query_ast_from_user_text, BoolQuery, BuildTantivyAst, QueryAst, UserInputQuery,
+        query_ast_from_user_text, BoolQuery, BuildTantivyAst, FieldPresenceQuery, QueryAst,
+        RangeQuery, TermQuery, UserInputQuery,
+    };
+    use crate::{
+        create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery, JsonLiteral,
     };
-    use crate::{create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery};
 
     #[test]
     fn test_user_query_not_parsed() {
@@ -340,12 +375,7 @@ mod tests {
         .into();
         let schema = tantivy::schema::Schema::builder().build();
         let build_tantivy_ast_err: InvalidQuery = query_ast
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap_err();
         assert!(matches!(
             build_tantivy_ast_err,
@@ -365,12 +395,7 @@ mod tests {
         let query_ast_with_parsed_user_query: QueryAst = query_ast.parse_user_query(&[]).unwrap();
         let schema = tantivy::schema::Schema::builder().build();
         let tantivy_query_ast = query_ast_with_parsed_user_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         assert_eq!(&tantivy_query_ast, &TantivyQueryAst::match_all(),);
     }
@@ -393,12 +418,7 @@ mod tests {
             bool_query_ast.parse_user_query(&[]).unwrap();
         let schema = tantivy::schema::Schema::builder().build();
         let tantivy_query_ast = query_ast_with_parsed_user_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let tantivy_query_ast_simplified = tantivy_query_ast.simplify();
         // This does not get more simplified than this, because we need the boost 0 score.
@@ -450,4 +470,50 @@ mod tests {
         };
         assert_eq!(input_query.default_operator, BooleanOperand::And);
     }
+
+    #[test]
+    fn test_query_on_field_not_indexed() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("fast_only", FAST | STORED);
+        schema_builder.add_text_field("stored_only", STORED);
+        schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        for field_name in ["fast_only", "stored_only"] {
+            let term_query: QueryAst = TermQuery {
+                field: field_name.to_string(),
+                value: "5".to_string(),
+            }
+            .into();
+            let invalid_query = term_query
+                .build_tantivy_ast_call(&schema, &tokenizer_manager, true)
+                .unwrap_err();
+            assert!(matches!(
+                &invalid_query,
+                InvalidQuery::FieldNotIndexed { field_name: not_indexed_field_name }
+                    if not_indexed_field_name == field_name
+            ));
+            let error_message = invalid_query.to_string();
+            assert!(error_message.contains("not indexed"));
+            assert!(error_message.contains("range query on a fast field"));
+        }
+        let range_query: QueryAst = RangeQuery {
+            field: "fast_only".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::Number(5u64.into())),
+            upper_bound: Bound::Unbounded,
+        }
+        .into();
+        range_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, true)
+            .unwrap();
+
+        let exists_query: QueryAst = FieldPresenceQuery {
+            field: "fast_only".to_string(),
+        }
+        .into();
+        exists_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, true)
+            .unwrap();
+    }
 }
@@ -23,9 +23,14 @@ use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
 use tantivy::Term;
 
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
-use crate::query_ast::{BuildTantivyAst, FullTextParams, QueryAst};
+use crate::query_ast::utils::{find_field_for_query, FieldQueryKind};
+use crate::query_ast::{BuildTantivyAst, FullTextMode, FullTextParams, QueryAst};
+use crate::sloppy_phrase_prefix_query::SloppyPhrasePrefixQuery;
 use crate::tokenizers::TokenizerManager;
-use crate::{find_field_or_hit_dynamic, InvalidQuery};
+use crate::InvalidQuery;
+
+/// Maximum number of terms the prefix of a phrase prefix query can be expanded to.
+pub const MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS: u32 = 10_000;
 
 /// The PhraseQuery node is meant to be tokenized and searched.
 ///
@@ -46,7 +51,8 @@ impl PhrasePrefixQuery {
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
     ) -> Result<(Field, Vec<(usize, Term)>), InvalidQuery> {
-        let (field, field_entry, json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
+        let (field, field_entry, json_path) =
+            find_field_for_query(&self.field, schema, FieldQueryKind::Term)?;
         let field_type = field_entry.field_type();
 
         match field_type {
@@ -114,17 +120,30 @@ impl BuildTantivyAst for PhrasePrefixQuery {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if self.max_expansions > MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS {
+            return Err(InvalidQuery::QueryAstLimitExceeded {
+                limit: "max_expansions",
+                max: MAX_PHRASE_PREFIX_QUERY_MAX_EXPANSIONS as usize,
+            });
+        }
         let (_, terms) = self.get_terms(schema, tokenizer_manager)?;
-
+        let slop = if let FullTextMode::Phrase { slop } = self.params.mode {
+            slop
+        } else {
+            0
+        };
         if terms.is_empty() {
             if self.params.zero_terms_query.is_none() {
                 Ok(TantivyQueryAst::match_none())
             } else {
                 Ok(TantivyQueryAst::match_all())
             }
+        } else if slop > 0 && terms.len() > 1 {
+            let sloppy_phrase_prefix_query =
+                SloppyPhrasePrefixQuery::new(terms, slop, self.max_expansions);
+            Ok(sloppy_phrase_prefix_query.into())
         } else {
             let mut phrase_prefix_query = TantivyPhrasePrefixQuery::new_with_offset(terms);
             phrase_prefix_query.set_max_expansions(self.max_expansions);
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::net::Ipv6Addr;
 use std::ops::Bound;
 
 use serde::{Deserialize, Serialize};
@@ -30,8 +31,9 @@ use super::tantivy_query_ast::TantivyBoolQuery;
 use super::QueryAst;
 use crate::json_literal::InterpretUserInput;
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::utils::{find_field_for_query, FieldQueryKind};
 use crate::query_ast::BuildTantivyAst;
-use crate::tokenizers::TokenizerManager;
+use crate::tokenizers::{canonical_ip_text, TokenizerManager, IP_NORMALIZER_NAME};
 use crate::{InvalidQuery, JsonLiteral};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -117,18 +119,26 @@ impl BuildTantivyAst for RangeQuery {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let (field, field_entry, json_path) =
-            super::utils::find_field_or_hit_dynamic(&self.field, schema)?;
-        if !field_entry.is_fast() {
-            return Err(InvalidQuery::SchemaError(format!(
-                "range queries are only supported for fast fields. (`{}` is not a fast field)",
-                field_entry.name()
-            )));
-        }
+            find_field_for_query(&self.field, schema, FieldQueryKind::Range)?;
         Ok(match field_entry.field_type() {
+            tantivy::schema::FieldType::Str(options)
+                if options.get_fast_field_tokenizer_name() == Some(IP_NORMALIZER_NAME) =>
+            {
+                // The field holds IP addresses stored as strings: its fast field values are the
+                // canonical forms of the addresses, which sort like the addresses themselves.
+                let (lower_bound, upper_bound): (Bound<Ipv6Addr>, Bound<Ipv6Addr>) =
+                    convert_bounds(&self.lower_bound, &self.upper_bound, field_entry.name())?;
+                FastFieldRangeQuery::new(
+                    lower_bound
+                        .map(|ip_addr| Term::from_field_text(field, &canonical_ip_text(ip_addr))),
+                    upper_bound
+                        .map(|ip_addr| Term::from_field_text(field, &canonical_ip_text(ip_addr))),
+                )
+                .into()
+            }
             tantivy::schema::FieldType::Str(options) => {
                 let mut normalizer = options
                     .get_fast_field_tokenizer_name()
@@ -286,12 +296,17 @@ fn map_bound<TFrom, TTo>(bound: &Bound<TFrom>, transform: impl Fn(&TFrom) -> TTo
 mod tests {
     use std::ops::Bound;
 
-    use tantivy::schema::{DateOptions, DateTimePrecision, Schema, FAST, STORED, TEXT};
+    use tantivy::collector::Count;
+    use tantivy::schema::{
+        DateOptions, DateTimePrecision, Schema, TextOptions, FAST, STORED, TEXT,
+    };
+    use tantivy::{doc, Index};
 
     use super::RangeQuery;
-    use crate::query_ast::BuildTantivyAst;
+    use crate::query_ast::{BuildTantivyAst, QueryAst};
     use crate::{
-        create_default_quickwit_tokenizer_manager, InvalidQuery, JsonLiteral, MatchAllOrNone,
+        create_default_quickwit_tokenizer_manager, get_quickwit_fastfield_normalizer_manager,
+        InvalidQuery, JsonLiteral, MatchAllOrNone, IP_NORMALIZER_NAME,
     };
 
     fn make_schema(dynamic_mode: bool) -> Schema {
@@ -324,12 +339,7 @@ mod tests {
             upper_bound: Bound::Included(upper_value),
         };
         let tantivy_ast = range_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap()
             .simplify();
         let leaf = tantivy_ast.as_leaf().unwrap();
@@ -372,12 +382,7 @@ mod tests {
         };
         // with validation
         let invalid_query: InvalidQuery = range_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap_err();
         assert!(
             matches!(invalid_query, InvalidQuery::FieldDoesNotExist { full_path } if full_path == "missing_field.toto")
@@ -388,7 +393,6 @@ mod tests {
                 .build_tantivy_ast_call(
                     &schema,
                     &create_default_quickwit_tokenizer_manager(),
-                    &[],
                     false
                 )
                 .unwrap()
@@ -406,12 +410,7 @@ mod tests {
         };
         let schema = make_schema(true);
         let tantivy_ast = range_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         assert_eq!(
             format!("{:?}", tantivy_ast),
@@ -434,13 +433,83 @@ mod tests {
         };
         let schema = make_schema(false);
         let err = range_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap_err();
         assert!(matches!(err, InvalidQuery::SchemaError { .. }));
     }
+
+    /// Returns the number of IP addresses stored as strings matched by a range query on a field
+    /// coerced into IP addresses.
+    fn count_matching_ips(ips: &[&str], lower_bound: &str, upper_bound: &str) -> usize {
+        let mut schema_builder = Schema::builder();
+        let ip_options = TextOptions::default().set_fast(Some(IP_NORMALIZER_NAME));
+        let ip_field = schema_builder.add_text_field("ip", ip_options);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        index.set_fast_field_tokenizers(
+            get_quickwit_fastfield_normalizer_manager()
+                .tantivy_manager()
+                .clone(),
+        );
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for ip in ips {
+            index_writer.add_document(doc!(ip_field => *ip)).unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let query_ast: QueryAst = RangeQuery {
+            field: "ip".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String(lower_bound.to_string())),
+            upper_bound: Bound::Included(JsonLiteral::String(upper_bound.to_string())),
+        }
+        .into();
+        let query = query_ast
+            .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        searcher.search(&query, &Count).unwrap()
+    }
+
+    #[test]
+    fn test_range_query_ip_coerced_str_field() {
+        // Lexicographically, `192.168.0.5` is greater than `192.168.0.10`.
+        assert_eq!(
+            count_matching_ips(&["192.168.0.5"], "192.168.0.1", "192.168.0.10"),
+            1
+        );
+        let ips = [
+            "192.168.0.1",
+            "192.168.0.10",
+            "192.168.0.100",
+            "::ffff:192.168.0.2",
+            "10.0.0.1",
+            "not-an-ip",
+        ];
+        assert_eq!(count_matching_ips(&ips, "192.168.0.1", "192.168.0.10"), 3);
+        assert_eq!(
+            count_matching_ips(&ips, "192.168.0.0", "192.168.255.255"),
+            4
+        );
+        assert_eq!(count_matching_ips(&ips, "0.0.0.0", "255.255.255.255"), 5);
+    }
+
+    #[test]
+    fn test_range_query_ip_coerced_str_field_invalid_bound() {
+        let mut schema_builder = Schema::builder();
+        let ip_options = TextOptions::default().set_fast(Some(IP_NORMALIZER_NAME));
+        schema_builder.add_text_field("ip", ip_options);
+        let schema = schema_builder.build();
+
+        let range_query = RangeQuery {
+            field: "ip".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("192.168.0.1".to_string())),
+            upper_bound: Bound::Included(JsonLiteral::String("not-an-ip".to_string())),
+        };
+        let err = range_query
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap_err();
+        assert!(matches!(err, InvalidQuery::InvalidBoundary { .. }));
+    }
 }
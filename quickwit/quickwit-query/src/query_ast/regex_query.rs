@@ -0,0 +1,200 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+use tantivy_fst::Regex;
+
+use super::wildcard_query::{find_text_field_and_term_prefix, push_escaped};
+use super::{BuildTantivyAst, QueryAst};
+use crate::automaton_query::MaxExpansionsRegexQuery;
+use crate::query_ast::wildcard_query::DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS;
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// Maximum size, in bytes, of the compiled program of a regex query. Pathological patterns, such
+/// as nested counted repetitions, are rejected before any split is searched.
+const REGEX_QUERY_MAX_COMPILED_SIZE: usize = 1 << 20;
+
+/// Maximum time spent evaluating a regex query against the term dictionary of a split. Splits
+/// going over it are reported as failed splits.
+const REGEX_QUERY_SPLIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A regex query matches the terms of a field against a regular expression, e.g. `error [0-9]{3}`.
+///
+/// The regular expression must match the whole term, so it is best suited to fields indexed with
+/// the `raw` tokenizer. Like the wildcard query, it fails if the regular expression matches more
+/// than `max_expansions` terms in a split.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct RegexQuery {
+    pub field: String,
+    pub regex: String,
+    #[serde(default = "default_max_expansions")]
+    pub max_expansions: u32,
+}
+
+fn default_max_expansions() -> u32 {
+    DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS
+}
+
+impl From<RegexQuery> for QueryAst {
+    fn from(regex_query: RegexQuery) -> Self {
+        Self::Regex(regex_query)
+    }
+}
+
+impl RegexQuery {
+    pub fn from_field_value(field: impl ToString, regex: impl ToString) -> Self {
+        Self {
+            field: field.to_string(),
+            regex: regex.to_string(),
+            max_expansions: DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS,
+        }
+    }
+}
+
+/// Checks that the regular expression is valid and that its compiled program does not exceed
+/// [`REGEX_QUERY_MAX_COMPILED_SIZE`].
+fn validate_regex(regex: &str) -> anyhow::Result<()> {
+    regex::RegexBuilder::new(regex)
+        .size_limit(REGEX_QUERY_MAX_COMPILED_SIZE)
+        .build()
+        .map_err(|error| match error {
+            regex::Error::CompiledTooBig(size_limit) => anyhow!(
+                "regex `{regex}` exceeds the size limit of {size_limit} bytes once compiled. use \
+                 a simpler pattern"
+            ),
+            _ => anyhow!("invalid regex `{regex}`: {error}"),
+        })?;
+    Ok(())
+}
+
+impl BuildTantivyAst for RegexQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _tokenizer_manager: &TokenizerManager,
+        _with_validation: bool,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        validate_regex(&self.regex)?;
+
+        let (field, _tokenizer_name, term_prefix) =
+            find_text_field_and_term_prefix(&self.field, schema, "Regex")?;
+        let mut regex_str = String::new();
+        push_escaped(&mut regex_str, &term_prefix);
+        regex_str.push_str("(?:");
+        regex_str.push_str(&self.regex);
+        regex_str.push(')');
+
+        let regex = Regex::new(&regex_str)
+            .map_err(|error| anyhow!("failed to compile regex `{}`: {error}", self.regex))?;
+        let regex_query = MaxExpansionsRegexQuery::new(
+            field,
+            self.field.clone(),
+            "regex",
+            self.regex.clone(),
+            regex,
+            self.max_expansions,
+        )
+        .with_timeout(REGEX_QUERY_SPLIT_TIMEOUT);
+        Ok(regex_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::Count;
+    use tantivy::schema::{TextFieldIndexing, TextOptions, INDEXED};
+    use tantivy::{doc, Index};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    fn search_raw_field(values: &[&str], regex: &str) -> Result<usize, InvalidQuery> {
+        let mut schema_builder = TantivySchema::builder();
+        let text_options = TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"));
+        let field = schema_builder.add_text_field("my_field", text_options);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for value in values {
+            index_writer.add_document(doc!(field => *value)).unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let query_ast: QueryAst = RegexQuery::from_field_value("my_field", regex).into();
+        let query = query_ast.build_tantivy_query(
+            &schema,
+            &create_default_quickwit_tokenizer_manager(),
+            true,
+        )?;
+        let searcher = index.reader().unwrap().searcher();
+        Ok(searcher.search(&query, &Count).unwrap())
+    }
+
+    #[test]
+    fn test_regex_query_matches_whole_terms() {
+        let values = [
+            "error 404",
+            "error 500",
+            "error 5000",
+            "warn 404",
+            "Error 404",
+        ];
+        assert_eq!(search_raw_field(&values, "error [0-9]{3}").unwrap(), 2);
+        assert_eq!(search_raw_field(&values, "(error|warn) 404").unwrap(), 2);
+        assert_eq!(search_raw_field(&values, "(?i)error 404").unwrap(), 2);
+        assert_eq!(search_raw_field(&values, "error").unwrap(), 0);
+        assert_eq!(search_raw_field(&values, ".*404").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_regex_query_invalid_regex() {
+        let error = search_raw_field(&["error"], "error [0-9").unwrap_err();
+        assert!(error.to_string().contains("invalid regex `error [0-9`"));
+    }
+
+    #[test]
+    fn test_regex_query_rejects_pathological_regex() {
+        let error = search_raw_field(&["aaa"], "((a|b){1000}){1000}").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("regex `((a|b){1000}){1000}` exceeds the size limit"));
+    }
+
+    #[test]
+    fn test_regex_query_on_non_text_field() {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_u64_field("my_field", INDEXED);
+        let schema = schema_builder.build();
+
+        let query_ast: QueryAst = RegexQuery::from_field_value("my_field", "1.*").into();
+        let error = query_ast
+            .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+}
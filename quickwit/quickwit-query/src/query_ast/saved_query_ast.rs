@@ -0,0 +1,875 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use super::{
+    BoolQuery, DisMaxQuery, FieldPresenceQuery, FullTextMode, FullTextParams, FullTextQuery,
+    GeoBoundingBoxQuery, MinimumShouldMatch, PhrasePrefixQuery, QueryAst, RangeQuery, RegexQuery,
+    TermQuery, TermSetQuery, UserInputQuery, WildcardQuery,
+};
+use crate::{BooleanOperand, JsonLiteral, MatchAllOrNone, NotNaNf32};
+
+/// The saved query AST format is decoupled from the serde representation of the query AST, which
+/// is free to change between releases.
+///
+/// When changing the saved format:
+/// - add a new version and make it the one written by [`QueryAst::to_saved_json`];
+/// - upgrade the documents of the previous version on load;
+/// - check in a golden file for the new version.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "version")]
+enum VersionedSavedQueryAst {
+    // Version 1 is the serde representation of the query AST, as accepted by the `query_ast`
+    // parameter of the search API.
+    #[serde(rename = "1")]
+    V1 { query: JsonValue },
+    #[serde(rename = "2")]
+    V2 { query: SavedQueryAstV2 },
+}
+
+impl QueryAst {
+    /// Serializes the query AST into the latest version of the saved query JSON format.
+    ///
+    /// Unlike the serde representation of the query AST, this format is versioned, and documents
+    /// written by a release can be loaded by the following ones.
+    pub fn to_saved_json(&self) -> String {
+        let versioned_saved_query_ast = VersionedSavedQueryAst::V2 {
+            query: self.clone().into(),
+        };
+        serde_json::to_string(&versioned_saved_query_ast)
+            .expect("saved query AST should be JSON serializable")
+    }
+
+    /// Deserializes a query AST saved with [`QueryAst::to_saved_json`], upgrading documents
+    /// written in a previous version of the format.
+    pub fn from_saved_json(saved_json: &str) -> anyhow::Result<QueryAst> {
+        let versioned_saved_query_ast: VersionedSavedQueryAst =
+            serde_json::from_str(saved_json).context("failed to parse saved query")?;
+        let saved_query_ast_v2: SavedQueryAstV2 = match versioned_saved_query_ast {
+            VersionedSavedQueryAst::V1 { query } => {
+                let query_v2 = upgrade_v1_node(query)?;
+                serde_json::from_value(query_v2).context("failed to upgrade saved query")?
+            }
+            VersionedSavedQueryAst::V2 { query } => query,
+        };
+        Ok(saved_query_ast_v2.into())
+    }
+}
+
+// Upgrade from version 1.
+//
+// Version 2 differs from version 1 as follows:
+// - `boost` nodes name the node they wrap `query` instead of `underlying`;
+// - range bounds are serialized as `{"included": literal}`, `{"excluded": literal}`, or
+//   `"unbounded"`;
+// - literals are tagged with their type: `{"type": "number", "value": 42}`.
+
+fn upgrade_v1_node(node: JsonValue) -> anyhow::Result<JsonValue> {
+    let JsonValue::Object(mut node_obj) = node else {
+        bail!("saved query node should be a JSON object");
+    };
+    let node_type = node_obj
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .context("saved query node should have a `type`")?
+        .to_string();
+    match node_type.as_str() {
+        "bool" => {
+            for clause_key in ["must", "must_not", "should", "filter"] {
+                upgrade_v1_node_list(&mut node_obj, clause_key)?;
+            }
+        }
+        "dis_max" => {
+            upgrade_v1_node_list(&mut node_obj, "disjuncts")?;
+        }
+        "boost" => {
+            let underlying = node_obj
+                .remove("underlying")
+                .context("`boost` node should have an `underlying` node")?;
+            node_obj.insert("query".to_string(), upgrade_v1_node(underlying)?);
+        }
+        "range" => {
+            for bound_key in ["lower_bound", "upper_bound"] {
+                if let Some(bound) = node_obj.remove(bound_key) {
+                    node_obj.insert(bound_key.to_string(), upgrade_v1_bound(bound)?);
+                }
+            }
+        }
+        "geo_bounding_box" => {
+            for corner_key in ["top_left", "bottom_right"] {
+                if let Some(corner) = node_obj.remove(corner_key) {
+                    node_obj.insert(corner_key.to_string(), upgrade_v1_literal(corner)?);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(JsonValue::Object(node_obj))
+}
+
+fn upgrade_v1_node_list(
+    node_obj: &mut JsonMap<String, JsonValue>,
+    key: &str,
+) -> anyhow::Result<()> {
+    let Some(nodes) = node_obj.remove(key) else {
+        return Ok(());
+    };
+    let JsonValue::Array(nodes) = nodes else {
+        bail!("`{key}` should be an array of saved query nodes");
+    };
+    let upgraded_nodes = nodes
+        .into_iter()
+        .map(upgrade_v1_node)
+        .collect::<anyhow::Result<Vec<JsonValue>>>()?;
+    node_obj.insert(key.to_string(), JsonValue::Array(upgraded_nodes));
+    Ok(())
+}
+
+fn upgrade_v1_bound(bound: JsonValue) -> anyhow::Result<JsonValue> {
+    match bound {
+        JsonValue::String(bound_str) if bound_str == "Unbounded" => {
+            Ok(JsonValue::String("unbounded".to_string()))
+        }
+        JsonValue::Object(bound_obj) if bound_obj.len() == 1 => {
+            let (bound_kind, literal) = bound_obj.into_iter().next().unwrap();
+            let upgraded_bound_kind = match bound_kind.as_str() {
+                "Included" => "included",
+                "Excluded" => "excluded",
+                _ => bail!("unknown range bound `{bound_kind}`"),
+            };
+            let mut upgraded_bound_obj = JsonMap::new();
+            upgraded_bound_obj.insert(
+                upgraded_bound_kind.to_string(),
+                upgrade_v1_literal(literal)?,
+            );
+            Ok(JsonValue::Object(upgraded_bound_obj))
+        }
+        _ => bail!("invalid range bound `{bound}`"),
+    }
+}
+
+fn upgrade_v1_literal(literal: JsonValue) -> anyhow::Result<JsonValue> {
+    let (literal_type, value) = match literal {
+        JsonValue::Number(_) => ("number", literal),
+        JsonValue::String(_) => ("string", literal),
+        JsonValue::Bool(_) => ("bool", literal),
+        JsonValue::Array(values) => {
+            let upgraded_values = values
+                .into_iter()
+                .map(upgrade_v1_literal)
+                .collect::<anyhow::Result<Vec<JsonValue>>>()?;
+            ("array", JsonValue::Array(upgraded_values))
+        }
+        JsonValue::Null | JsonValue::Object(_) => bail!("invalid literal `{literal}`"),
+    };
+    let mut upgraded_literal_obj = JsonMap::new();
+    upgraded_literal_obj.insert(
+        "type".to_string(),
+        JsonValue::String(literal_type.to_string()),
+    );
+    upgraded_literal_obj.insert("value".to_string(), value);
+    Ok(JsonValue::Object(upgraded_literal_obj))
+}
+
+// Version 2.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SavedQueryAstV2 {
+    Bool {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        must: Vec<SavedQueryAstV2>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        must_not: Vec<SavedQueryAstV2>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        should: Vec<SavedQueryAstV2>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        filter: Vec<SavedQueryAstV2>,
+    },
+    DisMax {
+        disjuncts: Vec<SavedQueryAstV2>,
+        #[serde(default = "default_tie_breaker")]
+        tie_breaker: NotNaNf32,
+    },
+    Term {
+        field: String,
+        value: String,
+    },
+    TermSet {
+        // Sorted, so that the format is deterministic.
+        terms_per_field: BTreeMap<String, BTreeSet<String>>,
+    },
+    FieldPresence {
+        field: String,
+    },
+    FullText {
+        field: String,
+        text: String,
+        params: SavedFullTextParamsV2,
+        lenient: bool,
+    },
+    PhrasePrefix {
+        field: String,
+        phrase: String,
+        max_expansions: u32,
+        params: SavedFullTextParamsV2,
+    },
+    Range {
+        field: String,
+        lower_bound: SavedBoundV2,
+        upper_bound: SavedBoundV2,
+    },
+    UserInput {
+        user_text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default_fields: Option<Vec<String>>,
+        default_operator: SavedBooleanOperandV2,
+        lenient: bool,
+    },
+    Wildcard {
+        field: String,
+        value: String,
+        max_expansions: u32,
+    },
+    Regex {
+        field: String,
+        regex: String,
+        max_expansions: u32,
+    },
+    GeoBoundingBox {
+        field: String,
+        top_left: SavedLiteralV2,
+        bottom_right: SavedLiteralV2,
+    },
+    MatchAll,
+    MatchNone,
+    Boost {
+        query: Box<SavedQueryAstV2>,
+        boost: NotNaNf32,
+    },
+}
+
+fn default_tie_breaker() -> NotNaNf32 {
+    NotNaNf32::ZERO
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct SavedFullTextParamsV2 {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tokenizer: Option<String>,
+    mode: SavedFullTextModeV2,
+    #[serde(default)]
+    zero_terms_query: SavedZeroTermsQueryV2,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SavedFullTextModeV2 {
+    Bool {
+        operator: SavedBooleanOperandV2,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        minimum_should_match: Option<SavedMinimumShouldMatchV2>,
+    },
+    BoolPrefix {
+        operator: SavedBooleanOperandV2,
+        max_expansions: u32,
+    },
+    PhraseFallbackToIntersection,
+    Phrase {
+        #[serde(default)]
+        slop: u32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SavedMinimumShouldMatchV2 {
+    Count(i32),
+    Percentage(i32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum SavedBooleanOperandV2 {
+    And,
+    Or,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SavedZeroTermsQueryV2 {
+    #[default]
+    #[serde(rename = "none")]
+    MatchNone,
+    #[serde(rename = "all")]
+    MatchAll,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SavedBoundV2 {
+    Included(SavedLiteralV2),
+    Excluded(SavedLiteralV2),
+    Unbounded,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum SavedLiteralV2 {
+    Number(serde_json::Number),
+    String(String),
+    Bool(bool),
+    Array(Vec<SavedLiteralV2>),
+}
+
+fn into_saved_query_asts(query_asts: Vec<QueryAst>) -> Vec<SavedQueryAstV2> {
+    query_asts.into_iter().map(SavedQueryAstV2::from).collect()
+}
+
+fn from_saved_query_asts(saved_query_asts: Vec<SavedQueryAstV2>) -> Vec<QueryAst> {
+    saved_query_asts.into_iter().map(QueryAst::from).collect()
+}
+
+impl From<QueryAst> for SavedQueryAstV2 {
+    fn from(query_ast: QueryAst) -> Self {
+        match query_ast {
+            QueryAst::Bool(BoolQuery {
+                must,
+                must_not,
+                should,
+                filter,
+            }) => SavedQueryAstV2::Bool {
+                must: into_saved_query_asts(must),
+                must_not: into_saved_query_asts(must_not),
+                should: into_saved_query_asts(should),
+                filter: into_saved_query_asts(filter),
+            },
+            QueryAst::DisMax(DisMaxQuery {
+                disjuncts,
+                tie_breaker,
+            }) => SavedQueryAstV2::DisMax {
+                disjuncts: into_saved_query_asts(disjuncts),
+                tie_breaker,
+            },
+            QueryAst::Term(TermQuery { field, value }) => SavedQueryAstV2::Term { field, value },
+            QueryAst::TermSet(TermSetQuery { terms_per_field }) => SavedQueryAstV2::TermSet {
+                terms_per_field: terms_per_field.into_iter().collect(),
+            },
+            QueryAst::FieldPresence(FieldPresenceQuery { field }) => {
+                SavedQueryAstV2::FieldPresence { field }
+            }
+            QueryAst::FullText(FullTextQuery {
+                field,
+                text,
+                params,
+                lenient,
+            }) => SavedQueryAstV2::FullText {
+                field,
+                text,
+                params: params.into(),
+                lenient,
+            },
+            QueryAst::PhrasePrefix(PhrasePrefixQuery {
+                field,
+                phrase,
+                max_expansions,
+                params,
+            }) => SavedQueryAstV2::PhrasePrefix {
+                field,
+                phrase,
+                max_expansions,
+                params: params.into(),
+            },
+            QueryAst::Range(RangeQuery {
+                field,
+                lower_bound,
+                upper_bound,
+            }) => SavedQueryAstV2::Range {
+                field,
+                lower_bound: lower_bound.into(),
+                upper_bound: upper_bound.into(),
+            },
+            QueryAst::UserInput(UserInputQuery {
+                user_text,
+                default_fields,
+                default_operator,
+                lenient,
+            }) => SavedQueryAstV2::UserInput {
+                user_text,
+                default_fields,
+                default_operator: default_operator.into(),
+                lenient,
+            },
+            QueryAst::Wildcard(WildcardQuery {
+                field,
+                value,
+                max_expansions,
+            }) => SavedQueryAstV2::Wildcard {
+                field,
+                value,
+                max_expansions,
+            },
+            QueryAst::Regex(RegexQuery {
+                field,
+                regex,
+                max_expansions,
+            }) => SavedQueryAstV2::Regex {
+                field,
+                regex,
+                max_expansions,
+            },
+            QueryAst::GeoBoundingBox(GeoBoundingBoxQuery {
+                field,
+                top_left,
+                bottom_right,
+            }) => SavedQueryAstV2::GeoBoundingBox {
+                field,
+                top_left: top_left.into(),
+                bottom_right: bottom_right.into(),
+            },
+            QueryAst::MatchAll => SavedQueryAstV2::MatchAll,
+            QueryAst::MatchNone => SavedQueryAstV2::MatchNone,
+            QueryAst::Boost { underlying, boost } => SavedQueryAstV2::Boost {
+                query: Box::new((*underlying).into()),
+                boost,
+            },
+        }
+    }
+}
+
+impl From<SavedQueryAstV2> for QueryAst {
+    fn from(saved_query_ast: SavedQueryAstV2) -> Self {
+        match saved_query_ast {
+            SavedQueryAstV2::Bool {
+                must,
+                must_not,
+                should,
+                filter,
+            } => QueryAst::Bool(BoolQuery {
+                must: from_saved_query_asts(must),
+                must_not: from_saved_query_asts(must_not),
+                should: from_saved_query_asts(should),
+                filter: from_saved_query_asts(filter),
+            }),
+            SavedQueryAstV2::DisMax {
+                disjuncts,
+                tie_breaker,
+            } => QueryAst::DisMax(DisMaxQuery {
+                disjuncts: from_saved_query_asts(disjuncts),
+                tie_breaker,
+            }),
+            SavedQueryAstV2::Term { field, value } => QueryAst::Term(TermQuery { field, value }),
+            SavedQueryAstV2::TermSet { terms_per_field } => QueryAst::TermSet(TermSetQuery {
+                terms_per_field: terms_per_field.into_iter().collect(),
+            }),
+            SavedQueryAstV2::FieldPresence { field } => {
+                QueryAst::FieldPresence(FieldPresenceQuery { field })
+            }
+            SavedQueryAstV2::FullText {
+                field,
+                text,
+                params,
+                lenient,
+            } => QueryAst::FullText(FullTextQuery {
+                field,
+                text,
+                params: params.into(),
+                lenient,
+            }),
+            SavedQueryAstV2::PhrasePrefix {
+                field,
+                phrase,
+                max_expansions,
+                params,
+            } => QueryAst::PhrasePrefix(PhrasePrefixQuery {
+                field,
+                phrase,
+                max_expansions,
+                params: params.into(),
+            }),
+            SavedQueryAstV2::Range {
+                field,
+                lower_bound,
+                upper_bound,
+            } => QueryAst::Range(RangeQuery {
+                field,
+                lower_bound: lower_bound.into(),
+                upper_bound: upper_bound.into(),
+            }),
+            SavedQueryAstV2::UserInput {
+                user_text,
+                default_fields,
+                default_operator,
+                lenient,
+            } => QueryAst::UserInput(UserInputQuery {
+                user_text,
+                default_fields,
+                default_operator: default_operator.into(),
+                lenient,
+            }),
+            SavedQueryAstV2::Wildcard {
+                field,
+                value,
+                max_expansions,
+            } => QueryAst::Wildcard(WildcardQuery {
+                field,
+                value,
+                max_expansions,
+            }),
+            SavedQueryAstV2::Regex {
+                field,
+                regex,
+                max_expansions,
+            } => QueryAst::Regex(RegexQuery {
+                field,
+                regex,
+                max_expansions,
+            }),
+            SavedQueryAstV2::GeoBoundingBox {
+                field,
+                top_left,
+                bottom_right,
+            } => QueryAst::GeoBoundingBox(GeoBoundingBoxQuery {
+                field,
+                top_left: top_left.into(),
+                bottom_right: bottom_right.into(),
+            }),
+            SavedQueryAstV2::MatchAll => QueryAst::MatchAll,
+            SavedQueryAstV2::MatchNone => QueryAst::MatchNone,
+            SavedQueryAstV2::Boost { query, boost } => QueryAst::Boost {
+                underlying: Box::new((*query).into()),
+                boost,
+            },
+        }
+    }
+}
+
+impl From<FullTextParams> for SavedFullTextParamsV2 {
+    fn from(params: FullTextParams) -> Self {
+        let mode = match params.mode {
+            FullTextMode::Bool {
+                operator,
+                minimum_should_match,
+            } => SavedFullTextModeV2::Bool {
+                operator: operator.into(),
+                minimum_should_match: minimum_should_match.map(|minimum_should_match| {
+                    match minimum_should_match {
+                        MinimumShouldMatch::Count(count) => SavedMinimumShouldMatchV2::Count(count),
+                        MinimumShouldMatch::Percentage(percentage) => {
+                            SavedMinimumShouldMatchV2::Percentage(percentage)
+                        }
+                    }
+                }),
+            },
+            FullTextMode::BoolPrefix {
+                operator,
+                max_expansions,
+            } => SavedFullTextModeV2::BoolPrefix {
+                operator: operator.into(),
+                max_expansions,
+            },
+            FullTextMode::PhraseFallbackToIntersection => {
+                SavedFullTextModeV2::PhraseFallbackToIntersection
+            }
+            FullTextMode::Phrase { slop } => SavedFullTextModeV2::Phrase { slop },
+        };
+        let zero_terms_query = match params.zero_terms_query {
+            MatchAllOrNone::MatchNone => SavedZeroTermsQueryV2::MatchNone,
+            MatchAllOrNone::MatchAll => SavedZeroTermsQueryV2::MatchAll,
+        };
+        SavedFullTextParamsV2 {
+            tokenizer: params.tokenizer,
+            mode,
+            zero_terms_query,
+        }
+    }
+}
+
+impl From<SavedFullTextParamsV2> for FullTextParams {
+    fn from(params: SavedFullTextParamsV2) -> Self {
+        let mode = match params.mode {
+            SavedFullTextModeV2::Bool {
+                operator,
+                minimum_should_match,
+            } => FullTextMode::Bool {
+                operator: operator.into(),
+                minimum_should_match: minimum_should_match.map(|minimum_should_match| {
+                    match minimum_should_match {
+                        SavedMinimumShouldMatchV2::Count(count) => MinimumShouldMatch::Count(count),
+                        SavedMinimumShouldMatchV2::Percentage(percentage) => {
+                            MinimumShouldMatch::Percentage(percentage)
+                        }
+                    }
+                }),
+            },
+            SavedFullTextModeV2::BoolPrefix {
+                operator,
+                max_expansions,
+            } => FullTextMode::BoolPrefix {
+                operator: operator.into(),
+                max_expansions,
+            },
+            SavedFullTextModeV2::PhraseFallbackToIntersection => {
+                FullTextMode::PhraseFallbackToIntersection
+            }
+            SavedFullTextModeV2::Phrase { slop } => FullTextMode::Phrase { slop },
+        };
+        let zero_terms_query = match params.zero_terms_query {
+            SavedZeroTermsQueryV2::MatchNone => MatchAllOrNone::MatchNone,
+            SavedZeroTermsQueryV2::MatchAll => MatchAllOrNone::MatchAll,
+        };
+        FullTextParams {
+            tokenizer: params.tokenizer,
+            mode,
+            zero_terms_query,
+        }
+    }
+}
+
+impl From<BooleanOperand> for SavedBooleanOperandV2 {
+    fn from(operator: BooleanOperand) -> Self {
+        match operator {
+            BooleanOperand::And => SavedBooleanOperandV2::And,
+            BooleanOperand::Or => SavedBooleanOperandV2::Or,
+        }
+    }
+}
+
+impl From<SavedBooleanOperandV2> for BooleanOperand {
+    fn from(operator: SavedBooleanOperandV2) -> Self {
+        match operator {
+            SavedBooleanOperandV2::And => BooleanOperand::And,
+            SavedBooleanOperandV2::Or => BooleanOperand::Or,
+        }
+    }
+}
+
+impl From<Bound<JsonLiteral>> for SavedBoundV2 {
+    fn from(bound: Bound<JsonLiteral>) -> Self {
+        match bound {
+            Bound::Included(literal) => SavedBoundV2::Included(literal.into()),
+            Bound::Excluded(literal) => SavedBoundV2::Excluded(literal.into()),
+            Bound::Unbounded => SavedBoundV2::Unbounded,
+        }
+    }
+}
+
+impl From<SavedBoundV2> for Bound<JsonLiteral> {
+    fn from(bound: SavedBoundV2) -> Self {
+        match bound {
+            SavedBoundV2::Included(literal) => Bound::Included(literal.into()),
+            SavedBoundV2::Excluded(literal) => Bound::Excluded(literal.into()),
+            SavedBoundV2::Unbounded => Bound::Unbounded,
+        }
+    }
+}
+
+impl From<JsonLiteral> for SavedLiteralV2 {
+    fn from(literal: JsonLiteral) -> Self {
+        match literal {
+            JsonLiteral::Number(number) => SavedLiteralV2::Number(number),
+            JsonLiteral::String(text) => SavedLiteralV2::String(text),
+            JsonLiteral::Bool(bool_value) => SavedLiteralV2::Bool(bool_value),
+            JsonLiteral::Array(literals) => {
+                SavedLiteralV2::Array(literals.into_iter().map(SavedLiteralV2::from).collect())
+            }
+        }
+    }
+}
+
+impl From<SavedLiteralV2> for JsonLiteral {
+    fn from(literal: SavedLiteralV2) -> Self {
+        match literal {
+            SavedLiteralV2::Number(number) => JsonLiteral::Number(number),
+            SavedLiteralV2::String(text) => JsonLiteral::String(text),
+            SavedLiteralV2::Bool(bool_value) => JsonLiteral::Bool(bool_value),
+            SavedLiteralV2::Array(literals) => {
+                JsonLiteral::Array(literals.into_iter().map(JsonLiteral::from).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::ops::Bound;
+
+    use serde_json::Value as JsonValue;
+
+    use crate::query_ast::{
+        BoolQuery, DisMaxQuery, FieldPresenceQuery, FullTextMode, FullTextParams, FullTextQuery,
+        GeoBoundingBoxQuery, PhrasePrefixQuery, QueryAst, RangeQuery, RegexQuery, TermQuery,
+        TermSetQuery, UserInputQuery, WildcardQuery,
+    };
+    use crate::{BooleanOperand, JsonLiteral, MatchAllOrNone};
+
+    // Golden files of the saved query format. If the test comparing the serialized sample to
+    // the golden file of the current version fails, the saved format changed: add a new version
+    // rather than updating the golden file.
+    const SAVED_QUERY_AST_V1_JSON: &str = include_str!("../../test-data/saved-query-ast/v1.json");
+    const SAVED_QUERY_AST_V2_JSON: &str = include_str!("../../test-data/saved-query-ast/v2.json");
+
+    /// Query AST using every node type, serialized in the golden files.
+    fn sample_query_ast() -> QueryAst {
+        let full_text_query = QueryAst::FullText(FullTextQuery {
+            field: "body".to_string(),
+            text: "quick brown fox".to_string(),
+            params: FullTextParams {
+                tokenizer: Some("default".to_string()),
+                mode: FullTextMode::Bool {
+                    operator: BooleanOperand::And,
+                    minimum_should_match: None,
+                },
+                zero_terms_query: MatchAllOrNone::MatchAll,
+            },
+            lenient: false,
+        });
+        let boost_query = QueryAst::Boost {
+            underlying: Box::new(QueryAst::Term(TermQuery {
+                field: "severity".to_string(),
+                value: "error".to_string(),
+            })),
+            boost: 2.0f32.try_into().unwrap(),
+        };
+        let term_set_query = QueryAst::TermSet(TermSetQuery {
+            terms_per_field: HashMap::from_iter([(
+                "host".to_string(),
+                ["host-1".to_string(), "host-2".to_string()]
+                    .into_iter()
+                    .collect(),
+            )]),
+        });
+        let field_presence_query = QueryAst::FieldPresence(FieldPresenceQuery {
+            field: "deleted_at".to_string(),
+        });
+        let dis_max_query = QueryAst::DisMax(DisMaxQuery {
+            disjuncts: vec![
+                QueryAst::PhrasePrefix(PhrasePrefixQuery {
+                    field: "title".to_string(),
+                    phrase: "quickwit sea".to_string(),
+                    max_expansions: 50,
+                    params: FullTextParams {
+                        tokenizer: None,
+                        mode: FullTextMode::Phrase { slop: 1 },
+                        zero_terms_query: MatchAllOrNone::MatchNone,
+                    },
+                }),
+                QueryAst::Wildcard(WildcardQuery {
+                    field: "title".to_string(),
+                    value: "quick*".to_string(),
+                    max_expansions: 100,
+                }),
+                QueryAst::Regex(RegexQuery {
+                    field: "title".to_string(),
+                    regex: "qu.*k".to_string(),
+                    max_expansions: 100,
+                }),
+            ],
+            tie_breaker: 0.5f32.try_into().unwrap(),
+        });
+        let user_input_query = QueryAst::UserInput(UserInputQuery {
+            user_text: "service:api".to_string(),
+            default_fields: Some(vec!["body".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: true,
+        });
+        let timestamp_range_query = QueryAst::Range(RangeQuery {
+            field: "timestamp".to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("2024-01-01T00:00:00Z".to_string())),
+            upper_bound: Bound::Excluded(JsonLiteral::Number(1_735_689_600u64.into())),
+        });
+        let retries_range_query = QueryAst::Range(RangeQuery {
+            field: "retries".to_string(),
+            lower_bound: Bound::Unbounded,
+            upper_bound: Bound::Included(JsonLiteral::Number(3u64.into())),
+        });
+        let geo_bounding_box_query = QueryAst::GeoBoundingBox(GeoBoundingBoxQuery {
+            field: "location".to_string(),
+            top_left: JsonLiteral::String("40.73,-74.1".to_string()),
+            bottom_right: JsonLiteral::String("40.01,-71.12".to_string()),
+        });
+        QueryAst::Bool(BoolQuery {
+            must: vec![full_text_query, boost_query],
+            must_not: vec![term_set_query, field_presence_query],
+            should: vec![
+                dis_max_query,
+                user_input_query,
+                QueryAst::MatchAll,
+                QueryAst::MatchNone,
+            ],
+            filter: vec![
+                timestamp_range_query,
+                retries_range_query,
+                geo_bounding_box_query,
+            ],
+        })
+    }
+
+    #[test]
+    fn test_saved_query_ast_format_is_stable() {
+        let saved_json = sample_query_ast().to_saved_json();
+        let saved_json_value: JsonValue = serde_json::from_str(&saved_json).unwrap();
+        let expected_json_value: JsonValue = serde_json::from_str(SAVED_QUERY_AST_V2_JSON).unwrap();
+        assert_eq!(
+            saved_json_value, expected_json_value,
+            "the saved query format changed: add a new version of the format instead of updating \
+             the golden file"
+        );
+    }
+
+    #[test]
+    fn test_saved_query_ast_load_current_version() {
+        let query_ast = QueryAst::from_saved_json(SAVED_QUERY_AST_V2_JSON).unwrap();
+        assert_eq!(query_ast, sample_query_ast());
+
+        let query_ast = QueryAst::from_saved_json(&query_ast.to_saved_json()).unwrap();
+        assert_eq!(query_ast, sample_query_ast());
+    }
+
+    #[test]
+    fn test_saved_query_ast_upgrade_v1() {
+        let query_ast = QueryAst::from_saved_json(SAVED_QUERY_AST_V1_JSON).unwrap();
+        assert_eq!(query_ast, sample_query_ast());
+    }
+
+    #[test]
+    fn test_saved_query_ast_invalid() {
+        let error = QueryAst::from_saved_json(r#"{"query": {"type": "match_all"}}"#).unwrap_err();
+        assert_eq!(error.to_string(), "failed to parse saved query");
+
+        let error =
+            QueryAst::from_saved_json(r#"{"version": "3", "query": {"type": "match_all"}}"#)
+                .unwrap_err();
+        assert_eq!(error.to_string(), "failed to parse saved query");
+
+        let error = QueryAst::from_saved_json(
+            r#"{"version": "1", "query": {"type": "boost", "boost": 2.0}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`boost` node should have an `underlying` node"
+        );
+    }
+}
@@ -80,7 +80,6 @@ impl TantivyQueryAst {
         }
     }
 
-    #[cfg(test)]
     pub(crate) fn as_leaf(&self) -> Option<&dyn TantivyQuery> {
         match self {
             TantivyQueryAst::Leaf(tantivy_query) => Some(&**tantivy_query),
@@ -56,7 +56,6 @@ impl BuildTantivyAst for TermQuery {
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let full_text_params = FullTextParams {
@@ -126,11 +125,89 @@ impl From<TermQuery> for HashMap<String, TermQueryValue> {
 
 #[cfg(test)]
 mod tests {
-    use tantivy::schema::{Schema, INDEXED};
+    use std::ops::Bound;
+
+    use tantivy::query::{FastFieldRangeQuery, TermQuery as TantivyTermQuery};
+    use tantivy::schema::{DateOptions, DateTimePrecision, IndexRecordOption, Schema, INDEXED};
+    use tantivy::{DateTime, Term};
+    use time::macros::datetime;
 
     use crate::create_default_quickwit_tokenizer_manager;
     use crate::query_ast::{BuildTantivyAst, TermQuery};
 
+    fn build_date_term_query_leaf_str(value: &str) -> String {
+        let term_query = TermQuery {
+            field: "timestamp".to_string(),
+            value: value.to_string(),
+        };
+        let mut schema_builder = Schema::builder();
+        let date_options = DateOptions::default()
+            .set_indexed()
+            .set_fast()
+            .set_precision(DateTimePrecision::Milliseconds);
+        schema_builder.add_date_field("timestamp", date_options);
+        let schema = schema_builder.build();
+        let tantivy_query_ast = term_query
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+        let leaf = tantivy_query_ast.as_leaf().unwrap();
+        format!("{leaf:?}")
+    }
+
+    fn date_range_query_str(start: DateTime, end: DateTime) -> String {
+        let field = Schema::builder().add_date_field("timestamp", INDEXED);
+        let range_query = FastFieldRangeQuery::new(
+            Bound::Included(Term::from_field_date(field, start)),
+            Bound::Excluded(Term::from_field_date(field, end)),
+        );
+        format!("{range_query:?}")
+    }
+
+    #[test]
+    fn test_term_query_with_date_only_matches_the_whole_day() {
+        assert_eq!(
+            build_date_term_query_leaf_str("2023-05-25"),
+            date_range_query_str(
+                DateTime::from_utc(datetime!(2023-05-25 00:00 UTC)),
+                DateTime::from_utc(datetime!(2023-05-26 00:00 UTC)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_term_query_with_minute_precision_date_matches_the_whole_minute() {
+        assert_eq!(
+            build_date_term_query_leaf_str("2023-05-25T10:20"),
+            date_range_query_str(
+                DateTime::from_utc(datetime!(2023-05-25 10:20 UTC)),
+                DateTime::from_utc(datetime!(2023-05-25 10:21 UTC)),
+            )
+        );
+        assert_eq!(
+            build_date_term_query_leaf_str("2023-05-25T10:20:11Z"),
+            date_range_query_str(
+                DateTime::from_utc(datetime!(2023-05-25 10:20:11 UTC)),
+                DateTime::from_utc(datetime!(2023-05-25 10:20:12 UTC)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_term_query_with_full_precision_date() {
+        let field = Schema::builder().add_date_field("timestamp", INDEXED);
+        let expected_term_query = TantivyTermQuery::new(
+            Term::from_field_date(
+                field,
+                DateTime::from_utc(datetime!(2023-05-25 10:20:11.322 UTC)),
+            ),
+            IndexRecordOption::WithFreqs,
+        );
+        assert_eq!(
+            build_date_term_query_leaf_str("2023-05-25T10:20:11.322Z"),
+            format!("{expected_term_query:?}")
+        );
+    }
+
     #[test]
     fn test_term_query_with_ipaddr_ipv4() {
         let term_query = TermQuery {
@@ -141,12 +218,7 @@ mod tests {
         schema_builder.add_ip_addr_field("ip", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -165,12 +237,7 @@ mod tests {
         schema_builder.add_ip_addr_field("ip", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -189,12 +256,7 @@ mod tests {
         schema_builder.add_bytes_field("bytes", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -213,12 +275,7 @@ mod tests {
         schema_builder.add_bytes_field("bytes", INDEXED);
         let schema = schema_builder.build();
         let tantivy_query_ast = term_query
-            .build_tantivy_ast_call(
-                &schema,
-                &create_default_quickwit_tokenizer_manager(),
-                &[],
-                true,
-            )
+            .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
         assert_eq!(
@@ -17,7 +17,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::borrow::Cow;
 use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
 use std::ops::Bound;
 
 use anyhow::bail;
@@ -29,8 +31,11 @@ use tantivy::schema::Schema as TantivySchema;
 
 use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
+use crate::query_ast::utils::{find_field_for_query, FieldQueryKind};
+use crate::query_ast::wildcard_query::DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS;
 use crate::query_ast::{
     self, BuildTantivyAst, FieldPresenceQuery, FullTextMode, FullTextParams, QueryAst,
+    QueryAstTransformer,
 };
 use crate::tokenizers::TokenizerManager;
 use crate::{BooleanOperand, InvalidQuery, JsonLiteral};
@@ -68,21 +73,306 @@ impl UserInputQuery {
             .as_ref()
             .map(|search_fields| &search_fields[..])
             .unwrap_or(default_search_fields);
-        let user_input_ast = tantivy::query_grammar::parse_query(&self.user_text)
+        let (user_text, regexes) = extract_regexes(&self.user_text)?;
+        validate_boosts(&user_text)?;
+        let user_input_ast = tantivy::query_grammar::parse_query(&user_text)
             .map_err(|_| anyhow::anyhow!("failed to parse query: `{}`", &self.user_text))?;
         let default_occur = match self.default_operator {
             BooleanOperand::And => Occur::Must,
             BooleanOperand::Or => Occur::Should,
         };
-        convert_user_input_ast_to_query_ast(
+        let query_ast = convert_user_input_ast_to_query_ast(
             user_input_ast,
             default_occur,
             search_fields,
             self.lenient,
-        )
+        )?;
+        if regexes.is_empty() {
+            return Ok(query_ast);
+        }
+        let query_ast = RegexPlaceholderResolver { regexes }
+            .transform(query_ast)?
+            .unwrap_or(QueryAst::MatchNone);
+        Ok(query_ast)
+    }
+}
+
+impl UserInputQuery {
+    /// Resolves the `default_fields` of the query against the schema.
+    ///
+    /// Field patterns such as `user.*` are expanded into the matching indexed fields, and every
+    /// other field must exist in the schema. Boosts (`title^3`) are carried over to the expanded
+    /// fields.
+    pub fn resolve_default_fields(&mut self, schema: &TantivySchema) -> Result<(), InvalidQuery> {
+        let Some(default_fields) = self.default_fields.as_ref() else {
+            return Ok(());
+        };
+        let mut resolved_default_fields: Vec<String> = Vec::with_capacity(default_fields.len());
+
+        for default_field in default_fields {
+            let (field_name, boost_opt) = split_field_boost(default_field)?;
+
+            if !field_name.contains('*') {
+                find_field_for_query(field_name, schema, FieldQueryKind::Term)?;
+                resolved_default_fields.push(default_field.clone());
+                continue;
+            }
+            let num_resolved_default_fields = resolved_default_fields.len();
+
+            for (_field, field_entry) in schema.fields() {
+                // Fields starting with an underscore are internal.
+                if field_entry.name().starts_with('_')
+                    || !field_entry.is_indexed()
+                    || !matches_field_pattern(field_name, field_entry.name())
+                {
+                    continue;
+                }
+                let resolved_default_field = if let Some(boost) = boost_opt {
+                    format!("{}^{}", field_entry.name(), f32::from(boost))
+                } else {
+                    field_entry.name().to_string()
+                };
+                resolved_default_fields.push(resolved_default_field);
+            }
+            if resolved_default_fields.len() == num_resolved_default_fields {
+                return Err(InvalidQuery::FieldDoesNotExist {
+                    full_path: field_name.to_string(),
+                });
+            }
+        }
+        self.default_fields = Some(resolved_default_fields);
+        Ok(())
+    }
+}
+
+/// Resolves the default fields of the [`UserInputQuery`] nodes of a query AST.
+///
+/// The nodes that do not define their own default fields are given
+/// `default_fields_override`, unless it is empty.
+pub(super) struct UserInputFieldsResolver<'a> {
+    pub schema: &'a TantivySchema,
+    pub default_fields_override: &'a [String],
+}
+
+impl QueryAstTransformer for UserInputFieldsResolver<'_> {
+    type Err = InvalidQuery;
+
+    fn transform_user_text(
+        &mut self,
+        mut user_text_query: UserInputQuery,
+    ) -> Result<Option<QueryAst>, Self::Err> {
+        if user_text_query.default_fields.is_none() && !self.default_fields_override.is_empty() {
+            user_text_query.default_fields = Some(self.default_fields_override.to_vec());
+        }
+        user_text_query.resolve_default_fields(self.schema)?;
+        Ok(Some(user_text_query.into()))
+    }
+}
+
+/// Splits an elasticsearch-style boosted field, such as `title^3`, into the field name and its
+/// boost.
+fn split_field_boost(field: &str) -> anyhow::Result<(&str, Option<NotNaNf32>)> {
+    let Some((field_name, boost_str)) = field.rsplit_once('^') else {
+        return Ok((field, None));
+    };
+    let boost: f32 = boost_str
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid boost `{boost_str}` for field `{field_name}`"))?;
+    let boost = NotNaNf32::try_from(boost).map_err(|err_msg: &str| anyhow::anyhow!(err_msg))?;
+    Ok((field_name, Some(boost)))
+}
+
+/// Checks that every `^` suffix of the user query carries a finite and positive boost.
+///
+/// The tantivy grammar rejects malformed boosts with a generic parse error: this check runs first
+/// so that the error points at the offending offset.
+fn validate_boosts(user_text: &str) -> anyhow::Result<()> {
+    let mut delimiter_opt: Option<char> = None;
+    let mut escaped = false;
+    for (offset, c) in user_text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, delimiter_opt) {
+            ('\\', _) => escaped = true,
+            ('"' | '\'', None) => delimiter_opt = Some(c),
+            (_, Some(delimiter)) if c == delimiter => delimiter_opt = None,
+            ('^', None) => {
+                let boost_str: &str = user_text[offset + 1..]
+                    .split(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+                    .next()
+                    .unwrap_or_default();
+                if boost_str.is_empty() {
+                    bail!("missing boost value after `^` at offset {offset}");
+                }
+                let boost: f32 = boost_str.parse().map_err(|_| {
+                    anyhow::anyhow!("invalid boost `{boost_str}` at offset {offset}")
+                })?;
+                if !boost.is_finite() || boost <= 0.0 {
+                    bail!(
+                        "invalid boost `{boost_str}` at offset {offset}: boosts must be finite \
+                         and positive"
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Prefix of the placeholder terms substituted to the regexes of a user query, which the tantivy
+/// query grammar does not support.
+const REGEX_PLACEHOLDER_PREFIX: &str = "__quickwit_regex_";
+
+/// Replaces the slash-delimited regexes of the user query, such as `field:/error [0-9]{3}/`, with
+/// placeholder terms and returns the rewritten query along with the extracted regexes.
+///
+/// A regex starts with a `/` at the beginning of a term, and ends with the next unescaped `/`
+/// followed by a whitespace, a `)`, a boost, or the end of the query. `\/` matches a literal `/`.
+/// A `/` that does not start a regex, as in `path:/var/log`, is left untouched.
+fn extract_regexes(user_text: &str) -> anyhow::Result<(Cow<str>, Vec<String>)> {
+    let mut rewritten_user_text = String::new();
+    let mut regexes: Vec<String> = Vec::new();
+    let mut delimiter_opt: Option<char> = None;
+    let mut escaped = false;
+    let mut prev_char_opt: Option<char> = None;
+    let mut copied_up_to = 0;
+    let mut offset = 0;
+
+    while let Some(c) = user_text[offset..].chars().next() {
+        let is_term_start = prev_char_opt
+            .map(|prev_char| {
+                prev_char.is_whitespace() || matches!(prev_char, ':' | '(' | '+' | '-')
+            })
+            .unwrap_or(true);
+        prev_char_opt = Some(c);
+        let char_offset = offset;
+        offset += c.len_utf8();
+
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (c, delimiter_opt) {
+            ('\\', _) => escaped = true,
+            ('"' | '\'', None) => delimiter_opt = Some(c),
+            (_, Some(delimiter)) if c == delimiter => delimiter_opt = None,
+            ('/', None) if is_term_start => {
+                let Some((regex, regex_len)) = parse_regex(&user_text[offset..]) else {
+                    continue;
+                };
+                if regex.is_empty() {
+                    bail!("empty regex at offset {char_offset}");
+                }
+                rewritten_user_text.push_str(&user_text[copied_up_to..char_offset]);
+                write!(
+                    &mut rewritten_user_text,
+                    "{REGEX_PLACEHOLDER_PREFIX}{}",
+                    regexes.len()
+                )
+                .expect("writing to a string should not fail");
+                regexes.push(regex);
+
+                // Skips the regex and its closing `/`.
+                offset += regex_len + 1;
+                copied_up_to = offset;
+            }
+            _ => {}
+        }
+    }
+    if regexes.is_empty() {
+        return Ok((Cow::Borrowed(user_text), regexes));
+    }
+    rewritten_user_text.push_str(&user_text[copied_up_to..]);
+    Ok((Cow::Owned(rewritten_user_text), regexes))
+}
+
+/// Parses the regex starting right after an opening `/`. Returns the unescaped regex and the
+/// length of its escaped form, without the closing `/`, or `None` if the regex is not closed.
+fn parse_regex(text: &str) -> Option<(String, usize)> {
+    let mut regex = String::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (_, escaped_char) = chars.next()?;
+                if escaped_char != '/' {
+                    regex.push('\\');
+                }
+                regex.push(escaped_char);
+            }
+            '/' => {
+                let is_regex_end = chars
+                    .peek()
+                    .map(|(_, next_char)| {
+                        next_char.is_whitespace() || matches!(next_char, ')' | '^')
+                    })
+                    .unwrap_or(true);
+                if is_regex_end {
+                    return Some((regex, offset));
+                }
+                regex.push(c);
+            }
+            _ => regex.push(c),
+        }
+    }
+    None
+}
+
+/// Substitutes the regexes extracted by [`extract_regexes`] back into the query AST.
+struct RegexPlaceholderResolver {
+    regexes: Vec<String>,
+}
+
+impl QueryAstTransformer for RegexPlaceholderResolver {
+    type Err = anyhow::Error;
+
+    fn transform_full_text(
+        &mut self,
+        full_text_query: query_ast::FullTextQuery,
+    ) -> Result<Option<QueryAst>, Self::Err> {
+        let Some(regex) = full_text_query
+            .text
+            .strip_prefix(REGEX_PLACEHOLDER_PREFIX)
+            .and_then(|regex_ord_str| regex_ord_str.parse::<usize>().ok())
+            .and_then(|regex_ord| self.regexes.get(regex_ord))
+        else {
+            return Ok(Some(full_text_query.into()));
+        };
+        let regex_query = query_ast::RegexQuery {
+            field: full_text_query.field,
+            regex: regex.clone(),
+            max_expansions: DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS,
+        };
+        Ok(Some(regex_query.into()))
     }
 }
 
+/// Returns true if the field name matches the pattern, in which `*` matches any sequence of
+/// characters.
+fn matches_field_pattern(pattern: &str, field_name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part.
+    let first_part = parts.next().unwrap_or_default();
+    let Some(mut remaining) = field_name.strip_prefix(first_part) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last_part) = parts.pop() else {
+        return remaining.is_empty();
+    };
+    for part in parts {
+        let Some(position) = remaining.find(part) else {
+            return false;
+        };
+        remaining = &remaining[position + part.len()..];
+    }
+    remaining.ends_with(last_part)
+}
+
 impl From<UserInputQuery> for QueryAst {
     fn from(user_text_query: UserInputQuery) -> Self {
         QueryAst::UserInput(user_text_query)
@@ -94,7 +384,6 @@ impl BuildTantivyAst for UserInputQuery {
         &self,
         _schema: &TantivySchema,
         _tokenizer_manager: &TokenizerManager,
-        _default_search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, crate::InvalidQuery> {
         Err(InvalidQuery::UserQueryNotParsed)
@@ -142,7 +431,7 @@ fn convert_user_input_ast_to_query_ast(
                 let field = if let Some(field) = field {
                     field
                 } else if default_search_fields.len() == 1 {
-                    default_search_fields[0].clone()
+                    split_field_boost(&default_search_fields[0])?.0.to_string()
                 } else if default_search_fields.is_empty() {
                     bail!("range query without field is not supported");
                 } else {
@@ -168,7 +457,13 @@ fn convert_user_input_ast_to_query_ast(
                 let field_names: Vec<String> = if let Some(field) = field.as_ref() {
                     vec![field.to_string()]
                 } else {
-                    default_search_fields.to_vec()
+                    default_search_fields
+                        .iter()
+                        .map(|default_search_field| {
+                            split_field_boost(default_search_field)
+                                .map(|(field_name, _)| field_name.to_string())
+                        })
+                        .collect::<anyhow::Result<_>>()?
                 };
                 if field_names.is_empty() {
                     anyhow::bail!("set query need to target a specific field");
@@ -190,7 +485,11 @@ fn convert_user_input_ast_to_query_ast(
                 default_search_fields,
                 lenient,
             )?;
-            let boost: NotNaNf32 = (boost as f32)
+            let boost_f32 = boost as f32;
+            if !boost_f32.is_finite() || boost_f32 <= 0.0 {
+                bail!("invalid boost `{boost}`: boosts must be finite and positive");
+            }
+            let boost: NotNaNf32 = boost_f32
                 .try_into()
                 .map_err(|err_msg: &str| anyhow::anyhow!(err_msg))?;
             Ok(QueryAst::Boost {
@@ -240,21 +539,29 @@ fn convert_user_input_literal(
         delimiter,
         slop,
     } = user_input_literal;
-    let field_names: Vec<String> = if let Some(field_name) = field_name {
-        vec![field_name]
+    let field_names: Vec<(String, Option<NotNaNf32>)> = if let Some(field_name) = field_name {
+        vec![(field_name, None)]
     } else {
         default_search_fields
             .iter()
-            .map(|field_name| field_name.to_string())
-            .collect()
+            .map(|default_search_field| {
+                split_field_boost(default_search_field)
+                    .map(|(field_name, boost_opt)| (field_name.to_string(), boost_opt))
+            })
+            .collect::<anyhow::Result<_>>()?
     };
     if field_names.is_empty() {
-        anyhow::bail!("query requires a default search field and none was supplied");
+        anyhow::bail!(
+            "term `{phrase}` does not target any field and no default search field was supplied: \
+             target a field explicitly (`field:{phrase}`) or set the default search fields of the \
+             request or of the index search settings"
+        );
     }
     let mode = match delimiter {
         Delimiter::None => FullTextMode::PhraseFallbackToIntersection,
         Delimiter::SingleQuotes => FullTextMode::Bool {
             operator: BooleanOperand::And,
+            minimum_should_match: None,
         },
         Delimiter::DoubleQuotes => FullTextMode::Phrase { slop },
     };
@@ -266,8 +573,8 @@ fn convert_user_input_literal(
     let wildcard = delimiter == Delimiter::None && is_wildcard(&phrase);
     let mut phrase_queries: Vec<QueryAst> = field_names
         .into_iter()
-        .map(|field_name| {
-            if prefix {
+        .map(|(field_name, boost_opt)| {
+            let query_ast: QueryAst = if prefix {
                 query_ast::PhrasePrefixQuery {
                     field: field_name,
                     phrase: phrase.clone(),
@@ -279,6 +586,7 @@ fn convert_user_input_literal(
                 query_ast::WildcardQuery {
                     field: field_name,
                     value: phrase.clone(),
+                    max_expansions: DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS,
                 }
                 .into()
             } else {
@@ -289,7 +597,8 @@ fn convert_user_input_literal(
                     lenient,
                 }
                 .into()
-            }
+            };
+            query_ast.boost(boost_opt)
         })
         .collect();
     if phrase_queries.is_empty() {
@@ -307,8 +616,15 @@ fn convert_user_input_literal(
 
 #[cfg(test)]
 mod tests {
+    use quickwit_common::shared_consts::FIELD_PRESENCE_FIELD_NAME;
+    use tantivy::collector::TopDocs;
+    use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::{extract_regexes, matches_field_pattern};
     use crate::query_ast::{
-        BoolQuery, BuildTantivyAst, FullTextMode, FullTextQuery, QueryAst, UserInputQuery,
+        BoolQuery, BuildTantivyAst, FullTextMode, FullTextQuery, QueryAst, RegexQuery,
+        UserInputQuery,
     };
     use crate::{create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery};
 
@@ -323,12 +639,7 @@ mod tests {
         let schema = tantivy::schema::Schema::builder().build();
         {
             let invalid_query = user_input_query
-                .build_tantivy_ast_call(
-                    &schema,
-                    &create_default_quickwit_tokenizer_manager(),
-                    &[],
-                    true,
-                )
+                .build_tantivy_ast_call(&schema, &create_default_quickwit_tokenizer_manager(), true)
                 .unwrap_err();
             assert!(matches!(invalid_query, InvalidQuery::UserQueryNotParsed));
         }
@@ -337,7 +648,6 @@ mod tests {
                 .build_tantivy_ast_call(
                     &schema,
                     &create_default_quickwit_tokenizer_manager(),
-                    &[],
                     false,
                 )
                 .unwrap_err();
@@ -358,7 +668,9 @@ mod tests {
             .unwrap_err();
             assert_eq!(
                 &invalid_err.to_string(),
-                "query requires a default search field and none was supplied"
+                "term `hello` does not target any field and no default search field was supplied: \
+                 target a field explicitly (`field:hello`) or set the default search fields of \
+                 the request or of the index search settings"
             );
         }
         {
@@ -372,7 +684,9 @@ mod tests {
             .unwrap_err();
             assert_eq!(
                 &invalid_err.to_string(),
-                "query requires a default search field and none was supplied"
+                "term `hello` does not target any field and no default search field was supplied: \
+                 target a field explicitly (`field:hello`) or set the default search fields of \
+                 the request or of the index search settings"
             );
         }
     }
@@ -457,6 +771,157 @@ mod tests {
         assert_eq!(should.len(), 2);
     }
 
+    #[test]
+    fn test_user_input_query_boosted_default_fields() {
+        let ast = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["title^3".to_string(), "body".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { should, .. }) = ast else {
+            panic!()
+        };
+        assert_eq!(should.len(), 2);
+        let QueryAst::Boost { underlying, boost } = &should[0] else {
+            panic!()
+        };
+        assert_eq!(f32::from(*boost), 3.0);
+        let QueryAst::FullText(full_text_query) = underlying.as_ref() else {
+            panic!()
+        };
+        assert_eq!(&full_text_query.field, "title");
+        let QueryAst::FullText(full_text_query) = &should[1] else {
+            panic!()
+        };
+        assert_eq!(&full_text_query.field, "body");
+
+        let error = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["title^high".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        }
+        .parse_user_query(&[])
+        .unwrap_err();
+        assert_eq!(error.to_string(), "invalid boost `high` for field `title`");
+    }
+
+    #[test]
+    fn test_user_input_query_resolve_default_fields() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_text_field("user.name", TEXT);
+        schema_builder.add_text_field("user.email", TEXT);
+        schema_builder.add_text_field("user.password", STORED);
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+
+        let mut user_input_query = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["title^2".to_string(), "user.*^1.5".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        };
+        user_input_query.resolve_default_fields(&schema).unwrap();
+        assert_eq!(
+            user_input_query.default_fields.unwrap(),
+            ["title^2", "user.name^1.5", "user.email^1.5"]
+        );
+
+        let mut user_input_query = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["title".to_string(), "unknown^2".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        };
+        let error = user_input_query
+            .resolve_default_fields(&schema)
+            .unwrap_err();
+        assert!(
+            matches!(error, InvalidQuery::FieldDoesNotExist { full_path } if full_path == "unknown")
+        );
+
+        let mut user_input_query = UserInputQuery {
+            user_text: "hello".to_string(),
+            default_fields: Some(vec!["unknown.*".to_string()]),
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        };
+        let error = user_input_query
+            .resolve_default_fields(&schema)
+            .unwrap_err();
+        assert!(
+            matches!(error, InvalidQuery::FieldDoesNotExist { full_path } if full_path == "unknown.*")
+        );
+    }
+
+    #[test]
+    fn test_matches_field_pattern() {
+        assert!(matches_field_pattern("user.*", "user.name"));
+        assert!(matches_field_pattern("user.*", "user."));
+        assert!(!matches_field_pattern("user.*", "username"));
+        assert!(matches_field_pattern("*.name", "user.name"));
+        assert!(matches_field_pattern("u*r.*e", "user.name"));
+        assert!(!matches_field_pattern("u*r.*e", "user.email"));
+        assert!(matches_field_pattern("*", "title"));
+        assert!(matches_field_pattern("title", "title"));
+        assert!(!matches_field_pattern("title", "titles"));
+    }
+
+    #[test]
+    fn test_extract_regexes() {
+        let (user_text, regexes) = extract_regexes("status:ok").unwrap();
+        assert_eq!(user_text, "status:ok");
+        assert!(regexes.is_empty());
+
+        let (user_text, regexes) =
+            extract_regexes("msg:/error [0-9]{3}/ AND -path:/a\\/b.*/^2").unwrap();
+        assert_eq!(
+            user_text,
+            "msg:__quickwit_regex_0 AND -path:__quickwit_regex_1^2"
+        );
+        assert_eq!(regexes, ["error [0-9]{3}", "a/b.*"]);
+
+        let (user_text, regexes) = extract_regexes("(/x\\d+/ OR y)").unwrap();
+        assert_eq!(user_text, "(__quickwit_regex_0 OR y)");
+        assert_eq!(regexes, ["x\\d+"]);
+
+        // Slashes in paths, quoted phrases, and unclosed regexes are left untouched.
+        for user_text in ["path:/var/log", "msg:\"/a b/\"", "path:/var/log x"] {
+            let (rewritten_user_text, _) = extract_regexes(user_text).unwrap();
+            assert_eq!(rewritten_user_text, user_text);
+        }
+        extract_regexes("msg:// x").unwrap_err();
+    }
+
+    #[test]
+    fn test_user_input_query_regex() {
+        let ast = UserInputQuery {
+            user_text: "status:/error [0-9]{3}/ AND level:warn".to_string(),
+            default_fields: None,
+            default_operator: BooleanOperand::Or,
+            lenient: false,
+        }
+        .parse_user_query(&[])
+        .unwrap();
+        let QueryAst::Bool(BoolQuery { must, .. }) = ast else {
+            panic!()
+        };
+        assert_eq!(must.len(), 2);
+        assert_eq!(
+            must[0],
+            QueryAst::Regex(RegexQuery::from_field_value("status", "error [0-9]{3}"))
+        );
+        let QueryAst::FullText(full_text_query) = &must[1] else {
+            panic!()
+        };
+        assert_eq!(full_text_query.field, "level");
+        assert_eq!(full_text_query.text, "warn");
+    }
+
     #[test]
     fn test_user_input_query_field_specified_in_user_input() {
         let ast = UserInputQuery {
@@ -522,7 +987,8 @@ mod tests {
             assert_eq!(
                 double_quote_query.params.mode,
                 FullTextMode::Bool {
-                    operator: BooleanOperand::And
+                    operator: BooleanOperand::And,
+                    minimum_should_match: None,
                 }
             );
         }
@@ -537,4 +1003,130 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_user_input_query_boosted_clauses() {
+        for user_text in [
+            "title:hello^2",
+            "title:\"hello world\"^2",
+            "count:[1 TO 10]^2",
+            "count:*^2",
+        ] {
+            let ast = UserInputQuery {
+                user_text: user_text.to_string(),
+                default_fields: None,
+                default_operator: BooleanOperand::Or,
+                lenient: false,
+            }
+            .parse_user_query(&[])
+            .unwrap();
+            let QueryAst::Boost { boost, .. } = ast else {
+                panic!("expected a boost query for `{user_text}`, got `{ast:?}`");
+            };
+            assert_eq!(f32::from(boost), 2.0);
+        }
+    }
+
+    #[test]
+    fn test_user_input_query_invalid_boosts() {
+        let parse_error = |user_text: &str| {
+            UserInputQuery {
+                user_text: user_text.to_string(),
+                default_fields: None,
+                default_operator: BooleanOperand::Or,
+                lenient: false,
+            }
+            .parse_user_query(&[])
+            .unwrap_err()
+            .to_string()
+        };
+        assert_eq!(
+            parse_error("title:hello^"),
+            "missing boost value after `^` at offset 11"
+        );
+        assert_eq!(
+            parse_error("title:hello AND count:[1 TO 10]^abc"),
+            "missing boost value after `^` at offset 31"
+        );
+        assert_eq!(
+            parse_error("title:hello^-2"),
+            "invalid boost `-2` at offset 11: boosts must be finite and positive"
+        );
+        assert_eq!(
+            parse_error("title:hello^0"),
+            "invalid boost `0` at offset 11: boosts must be finite and positive"
+        );
+        assert_eq!(
+            parse_error("title:hello^1.2.3"),
+            "invalid boost `1.2.3` at offset 11"
+        );
+        let overflowing_boost = "9".repeat(50);
+        assert_eq!(
+            parse_error(&format!("title:hello^{overflowing_boost}")),
+            format!(
+                "invalid boost `{overflowing_boost}` at offset 11: boosts must be finite and \
+                 positive"
+            )
+        );
+        // Carets within quotes or escaped are not boosts.
+        for user_text in [r#"title:"a^b""#, r"title:a\^b"] {
+            UserInputQuery {
+                user_text: user_text.to_string(),
+                default_fields: None,
+                default_operator: BooleanOperand::Or,
+                lenient: false,
+            }
+            .parse_user_query(&[])
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_user_input_query_boosts_affect_scoring() {
+        let mut schema_builder = Schema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let count_field = schema_builder.add_u64_field("count", INDEXED | FAST);
+        schema_builder.add_u64_field(FIELD_PRESENCE_FIELD_NAME, INDEXED);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        index_writer
+            .add_document(doc!(title_field => "hello world", count_field => 5u64))
+            .unwrap();
+        index_writer
+            .add_document(doc!(title_field => "hello there", count_field => 20u64))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let top_score = |user_text: &str| -> f32 {
+            let query = UserInputQuery {
+                user_text: user_text.to_string(),
+                default_fields: None,
+                default_operator: BooleanOperand::Or,
+                lenient: false,
+            }
+            .parse_user_query(&[])
+            .unwrap()
+            .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(1)).unwrap();
+            top_docs[0].0
+        };
+        for user_text in [
+            "title:hello",
+            "title:\"hello world\"",
+            "count:[1 TO 10]",
+            "count:*",
+        ] {
+            let score = top_score(user_text);
+            let boosted_score = top_score(&format!("{user_text}^2"));
+            assert!(
+                (boosted_score - 2.0 * score).abs() < 1e-5,
+                "boost ignored for `{user_text}`: {score} vs {boosted_score}"
+            );
+        }
+    }
 }
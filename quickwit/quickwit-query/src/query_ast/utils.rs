@@ -17,13 +17,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::ops::Bound;
+
+use quickwit_datetime::DateTimeGranularity;
 use tantivy::json_utils::convert_to_fast_value_and_append_to_json_term;
-use tantivy::query::TermQuery as TantivyTermQuery;
+use tantivy::query::{FastFieldRangeQuery, TermQuery as TantivyTermQuery};
 use tantivy::schema::{
     Field, FieldEntry, FieldType, IndexRecordOption, JsonObjectOptions, Schema as TantivySchema,
     Type,
 };
-use tantivy::Term;
+use tantivy::{DateTime, Term};
 
 use crate::json_literal::InterpretUserInput;
 use crate::query_ast::full_text_query::FullTextParams;
@@ -32,7 +35,7 @@ use crate::tokenizers::TokenizerManager;
 use crate::InvalidQuery;
 use crate::MatchAllOrNone::MatchNone as TantivyEmptyQuery;
 
-const DYNAMIC_FIELD_NAME: &str = "_dynamic";
+pub(crate) const DYNAMIC_FIELD_NAME: &str = "_dynamic";
 
 fn make_term_query(term: Term) -> TantivyQueryAst {
     TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into()
@@ -63,6 +66,72 @@ pub fn find_field_or_hit_dynamic<'a>(
     Ok((field, field_entry, path))
 }
 
+/// Returns the fields nested in the object at `full_path`, if `full_path` refers to an object of
+/// the doc mapping rather than to a field.
+///
+/// Objects do not exist in the schema: their subfields are flattened into fields named after
+/// their full path, such as `user.address.city`.
+pub fn find_object_subfields<'a>(
+    full_path: &str,
+    schema: &'a TantivySchema,
+) -> Vec<(Field, &'a FieldEntry)> {
+    if full_path.is_empty() {
+        return Vec::new();
+    }
+    let subfield_prefix = format!("{full_path}.");
+    schema
+        .fields()
+        .filter(|(_, field_entry)| field_entry.name().starts_with(&subfield_prefix))
+        .collect()
+}
+
+/// The kind of query a field is resolved for.
+///
+/// Unlike term lookups, some queries can run on the fast field of a field that is not indexed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FieldQueryKind {
+    /// Queries looking up terms in the inverted index: term, full-text, phrase, wildcard...
+    Term,
+    /// Range queries, which run on fast fields.
+    Range,
+    /// Field presence queries, which run on fast fields or on the field presence field.
+    Exists,
+}
+
+/// Same as [`find_field_or_hit_dynamic`], but also checks that the field supports the given
+/// kind of query.
+pub fn find_field_for_query<'a>(
+    full_path: &'a str,
+    schema: &'a TantivySchema,
+    query_kind: FieldQueryKind,
+) -> Result<(Field, &'a FieldEntry, &'a str), InvalidQuery> {
+    let (field, field_entry, path) = find_field_or_hit_dynamic(full_path, schema)?;
+
+    match query_kind {
+        FieldQueryKind::Term => {
+            // Term queries on coarse datetime literals run as a range on the fast field.
+            let is_fast_date =
+                matches!(field_entry.field_type(), FieldType::Date(_)) && field_entry.is_fast();
+
+            if !field_entry.is_indexed() && !is_fast_date {
+                return Err(InvalidQuery::FieldNotIndexed {
+                    field_name: full_path.to_string(),
+                });
+            }
+        }
+        FieldQueryKind::Range => {
+            if !field_entry.is_fast() {
+                return Err(InvalidQuery::SchemaError(format!(
+                    "range queries are only supported for fast fields. (`{}` is not a fast field)",
+                    field_entry.name()
+                )));
+            }
+        }
+        FieldQueryKind::Exists => {}
+    }
+    Ok((field, field_entry, path))
+}
+
 /// Creates a full text query.
 ///
 /// If tokenize is set to true, the text will be tokenized.
@@ -74,13 +143,14 @@ pub(crate) fn full_text_query(
     tokenizer_manager: &TokenizerManager,
     lenient: bool,
 ) -> Result<TantivyQueryAst, InvalidQuery> {
-    let (field, field_entry, path) = match find_field_or_hit_dynamic(full_path, schema) {
-        Ok(res) => res,
-        Err(InvalidQuery::FieldDoesNotExist { .. }) if lenient => {
-            return Ok(TantivyEmptyQuery.into())
-        }
-        Err(e) => return Err(e),
-    };
+    let (field, field_entry, path) =
+        match find_field_for_query(full_path, schema, FieldQueryKind::Term) {
+            Ok(res) => res,
+            Err(InvalidQuery::FieldDoesNotExist { .. }) if lenient => {
+                return Ok(TantivyEmptyQuery.into())
+            }
+            Err(e) => return Err(e),
+        };
     compute_query_with_field(
         field,
         field_entry,
@@ -135,9 +205,25 @@ fn compute_query_with_field(
             let term = Term::from_field_bool(field, bool_val);
             Ok(make_term_query(term))
         }
-        FieldType::Date(_) => {
-            let dt = parse_value_from_user_text(value, field_entry.name())?;
-            let term = Term::from_field_date(field, dt);
+        FieldType::Date(date_options) => {
+            let (date_time, granularity): (DateTime, DateTimeGranularity) =
+                parse_value_from_user_text(value, field_entry.name())?;
+            // Following Elasticsearch, a datetime literal coarser than the field precision
+            // matches the whole unit it expresses: `2023-05-25` matches the entire day.
+            if date_options.is_fast() && granularity.is_coarser_than(date_options.get_precision()) {
+                let covering_range = granularity.covering_range(date_time);
+                let range_query = FastFieldRangeQuery::new(
+                    Bound::Included(Term::from_field_date(field, covering_range.start)),
+                    Bound::Excluded(Term::from_field_date(field, covering_range.end)),
+                );
+                return Ok(range_query.into());
+            }
+            if !field_entry.is_indexed() {
+                return Err(InvalidQuery::FieldNotIndexed {
+                    field_name: field_entry.name().to_string(),
+                });
+            }
+            let term = Term::from_field_date(field, date_time);
             Ok(make_term_query(term))
         }
         FieldType::Str(text_options) => {
@@ -17,23 +17,45 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::fmt::Write;
+
 use anyhow::{anyhow, bail, Context};
+use quickwit_common::rate_limited_warn;
 use serde::{Deserialize, Serialize};
 use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
+use tantivy::tokenizer::TextAnalyzer;
 use tantivy::Term;
+use tantivy_fst::Regex;
 
 use super::{BuildTantivyAst, QueryAst};
+use crate::automaton_query::MaxExpansionsRegexQuery;
+use crate::query_ast::utils::{find_field_for_query, FieldQueryKind};
 use crate::query_ast::TantivyQueryAst;
 use crate::tokenizers::TokenizerManager;
-use crate::{find_field_or_hit_dynamic, InvalidQuery};
+use crate::InvalidQuery;
+
+/// Default maximum number of terms a wildcard pattern can match in a split.
+pub(crate) const DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS: u32 = 10_000;
 
 /// A Wildcard query allows to match 'bond' with a query like 'b*d'.
 ///
-/// At the moment, only wildcard at end of term is supported.
+/// `*` matches any sequence of characters and `?` matches exactly one character. These
+/// characters can be matched literally by escaping them with `\`. The pattern is matched against
+/// the terms of the field, so it is best suited to fields indexed with the `raw` tokenizer.
+///
+/// Patterns with a single trailing `*` are run as prefix queries. Other patterns are compiled into
+/// a regular expression run against the term dictionary of the field, and fail if they match more
+/// than `max_expansions` terms.
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct WildcardQuery {
     pub field: String,
     pub value: String,
+    #[serde(default = "default_max_expansions")]
+    pub max_expansions: u32,
+}
+
+fn default_max_expansions() -> u32 {
+    DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS
 }
 
 impl From<WildcardQuery> for QueryAst {
@@ -48,10 +70,108 @@ impl WildcardQuery {
         Self {
             field: field.to_string(),
             value: value.to_string(),
+            max_expansions: DEFAULT_WILDCARD_QUERY_MAX_EXPANSIONS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum WildcardToken {
+    Literal(String),
+    // `?`
+    AnyChar,
+    // `*`
+    AnyChars,
+}
+
+fn parse_wildcard_pattern(pattern: &str) -> anyhow::Result<Vec<WildcardToken>> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        let wildcard_token = match c {
+            '\\' => {
+                let escaped_char = chars
+                    .next()
+                    .context("wildcard query ends with an incomplete escape sequence")?;
+                literal.push(escaped_char);
+                continue;
+            }
+            '?' => WildcardToken::AnyChar,
+            '*' => WildcardToken::AnyChars,
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            tokens.push(WildcardToken::Literal(std::mem::take(&mut literal)));
+        }
+        tokens.push(wildcard_token);
+    }
+    if !literal.is_empty() {
+        tokens.push(WildcardToken::Literal(literal));
+    }
+    Ok(tokens)
+}
+
+/// Returns true if the pattern only contains a trailing `*` and can hence run as a prefix query.
+fn is_prefix_pattern(tokens: &[WildcardToken]) -> bool {
+    matches!(
+        tokens,
+        [WildcardToken::AnyChars] | [WildcardToken::Literal(_), WildcardToken::AnyChars]
+    )
+}
+
+/// Appends `text` to `regex`, escaping everything but ASCII alphanumeric characters.
+pub(super) fn push_escaped(regex: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            regex.push(c);
+        } else {
+            write!(regex, "\\x{{{:x}}}", c as u32).expect("writing to a string should not fail");
         }
     }
 }
 
+fn normalize_literal(normalizer: &mut TextAnalyzer, literal: &str) -> anyhow::Result<String> {
+    let mut token_stream = normalizer.token_stream(literal);
+    let mut normalized_literals = Vec::new();
+    token_stream.process(&mut |token| normalized_literals.push(token.text.clone()));
+    let normalized_literal = normalized_literals
+        .pop()
+        .context("wildcard query generated no term")?;
+    if !normalized_literals.is_empty() {
+        bail!("wildcard query generated more than one term");
+    }
+    Ok(normalized_literal)
+}
+
+/// Builds a regular expression matching the serialized value of the terms matched by the pattern.
+/// `term_prefix` is the serialized prefix shared by all the terms of the field, i.e. the JSON path
+/// and type code for JSON fields.
+fn wildcard_tokens_to_regex(
+    tokens: &[WildcardToken],
+    term_prefix: &str,
+    normalizer: &mut TextAnalyzer,
+) -> anyhow::Result<String> {
+    let mut regex = String::from("(?s)");
+    push_escaped(&mut regex, term_prefix);
+
+    for token in tokens {
+        match token {
+            WildcardToken::Literal(literal) => {
+                let normalized_literal = normalize_literal(normalizer, literal)?;
+                push_escaped(&mut regex, &normalized_literal);
+            }
+            WildcardToken::AnyChar => regex.push('.'),
+            WildcardToken::AnyChars => regex.push_str(".*"),
+        }
+    }
+    Ok(regex)
+}
+
 fn extract_unique_token(mut tokens: Vec<Term>) -> anyhow::Result<Term> {
     let term = tokens
         .pop()
@@ -118,7 +238,8 @@ impl WildcardQuery {
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
     ) -> Result<(Field, Term), InvalidQuery> {
-        let (field, field_entry, json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
+        let (field, field_entry, json_path) =
+            find_field_for_query(&self.field, schema, FieldQueryKind::Term)?;
         let field_type = field_entry.field_type();
 
         let prefix = unescape_with_final_wildcard(&self.value)?;
@@ -182,14 +303,113 @@ impl WildcardQuery {
     }
 }
 
+impl WildcardQuery {
+    /// Returns true if the pattern only contains a trailing `*`, in which case the query runs as
+    /// a prefix query, see [`WildcardQuery::extract_prefix_term`]. Otherwise, the query runs
+    /// against the whole term dictionary of the field.
+    pub fn is_prefix_query(&self) -> bool {
+        parse_wildcard_pattern(&self.value)
+            .map(|tokens| is_prefix_pattern(&tokens))
+            .unwrap_or(false)
+    }
+
+    fn build_regex_query(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        tokens: &[WildcardToken],
+    ) -> Result<MaxExpansionsRegexQuery, InvalidQuery> {
+        let (field, tokenizer_name, term_prefix) =
+            find_text_field_and_term_prefix(&self.field, schema, "Wildcard")?;
+        let mut normalizer = tokenizer_manager
+            .get_normalizer(tokenizer_name)
+            .with_context(|| format!("no tokenizer named `{}` is registered", tokenizer_name))?;
+        let regex_str = wildcard_tokens_to_regex(tokens, &term_prefix, &mut normalizer)?;
+        let regex = Regex::new(&regex_str).map_err(|error| {
+            anyhow!(
+                "failed to compile wildcard query `{}` into a regex: {error}",
+                self.value
+            )
+        })?;
+        Ok(MaxExpansionsRegexQuery::new(
+            field,
+            self.field.clone(),
+            "wildcard",
+            self.value.clone(),
+            regex,
+            self.max_expansions,
+        ))
+    }
+}
+
+/// Resolves the text field targeted by a query run against the term dictionary, and returns its
+/// tokenizer along with the prefix shared by the terms of the field, which is only non-empty for
+/// JSON fields.
+pub(super) fn find_text_field_and_term_prefix<'a>(
+    field_name: &'a str,
+    schema: &'a TantivySchema,
+    query_type: &str,
+) -> Result<(Field, &'a str, String), InvalidQuery> {
+    let (field, field_entry, json_path) =
+        find_field_for_query(field_name, schema, FieldQueryKind::Term)?;
+
+    match field_entry.field_type() {
+        FieldType::Str(ref text_options) => {
+            let text_field_indexing = text_options.get_indexing_options().ok_or_else(|| {
+                InvalidQuery::SchemaError(format!(
+                    "field {} is not full-text searchable",
+                    field_entry.name()
+                ))
+            })?;
+            Ok((field, text_field_indexing.tokenizer(), String::new()))
+        }
+        FieldType::JsonObject(json_options) => {
+            let text_field_indexing =
+                json_options.get_text_indexing_options().ok_or_else(|| {
+                    InvalidQuery::SchemaError(format!(
+                        "field {} is not full-text searchable",
+                        field_entry.name()
+                    ))
+                })?;
+            let mut term =
+                Term::from_field_json_path(field, json_path, json_options.is_expand_dots_enabled());
+            term.append_type_and_str("");
+            let term_prefix = std::str::from_utf8(term.serialized_value_bytes())
+                .context("JSON path is not valid UTF-8")?
+                .to_string();
+            Ok((field, text_field_indexing.tokenizer(), term_prefix))
+        }
+        _ => Err(InvalidQuery::SchemaError(format!(
+            "trying to run a {query_type} query on a non-text field"
+        ))),
+    }
+}
+
 impl BuildTantivyAst for WildcardQuery {
     fn build_tantivy_ast_impl(
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
-        _search_fields: &[String],
         _with_validation: bool,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let tokens = parse_wildcard_pattern(&self.value)?;
+
+        if !is_prefix_pattern(&tokens) {
+            if matches!(
+                tokens.first(),
+                Some(WildcardToken::AnyChar | WildcardToken::AnyChars)
+            ) {
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    field = self.field,
+                    pattern = self.value,
+                    "wildcard query starts with a wildcard and requires scanning the whole term \
+                     dictionary of the field"
+                );
+            }
+            let regex_query = self.build_regex_query(schema, tokenizer_manager, &tokens)?;
+            return Ok(regex_query.into());
+        }
         let (_, term) = self.extract_prefix_term(schema, tokenizer_manager)?;
 
         let mut phrase_prefix_query =
@@ -201,17 +421,148 @@ impl BuildTantivyAst for WildcardQuery {
 
 #[cfg(test)]
 mod tests {
+    use tantivy::collector::Count;
     use tantivy::schema::{TextFieldIndexing, TextOptions};
+    use tantivy::{doc, Index};
 
     use super::*;
     use crate::create_default_quickwit_tokenizer_manager;
 
+    fn search_raw_field(
+        values: &[&str],
+        pattern: &str,
+        max_expansions: u32,
+    ) -> tantivy::Result<usize> {
+        let mut schema_builder = TantivySchema::builder();
+        let text_options = TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"));
+        let field = schema_builder.add_text_field("my_field", text_options);
+        let schema = schema_builder.build();
+
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for value in values {
+            index_writer.add_document(doc!(field => *value)).unwrap();
+        }
+        index_writer.commit().unwrap();
+
+        let query_ast: QueryAst = WildcardQuery {
+            field: "my_field".to_string(),
+            value: pattern.to_string(),
+            max_expansions,
+        }
+        .into();
+        let query = query_ast
+            .build_tantivy_query(&schema, &create_default_quickwit_tokenizer_manager(), true)
+            .unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        searcher.search(&query, &Count)
+    }
+
+    #[test]
+    fn test_parse_wildcard_pattern() {
+        assert_eq!(
+            parse_wildcard_pattern("error-*-prod").unwrap(),
+            vec![
+                WildcardToken::Literal("error-".to_string()),
+                WildcardToken::AnyChars,
+                WildcardToken::Literal("-prod".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_wildcard_pattern("*time?out*").unwrap(),
+            vec![
+                WildcardToken::AnyChars,
+                WildcardToken::Literal("time".to_string()),
+                WildcardToken::AnyChar,
+                WildcardToken::Literal("out".to_string()),
+                WildcardToken::AnyChars,
+            ]
+        );
+        assert_eq!(
+            parse_wildcard_pattern(r"a\*b\?c\\*").unwrap(),
+            vec![
+                WildcardToken::Literal(r"a*b?c\".to_string()),
+                WildcardToken::AnyChars,
+            ]
+        );
+        parse_wildcard_pattern(r"abc\").unwrap_err();
+    }
+
+    #[test]
+    fn test_wildcard_query_is_prefix_query() {
+        assert!(WildcardQuery::from_field_value("my_field", "err*").is_prefix_query());
+        assert!(WildcardQuery::from_field_value("my_field", r"e\*r*").is_prefix_query());
+        assert!(!WildcardQuery::from_field_value("my_field", "e*r*").is_prefix_query());
+        assert!(!WildcardQuery::from_field_value("my_field", "*err").is_prefix_query());
+        assert!(!WildcardQuery::from_field_value("my_field", "er?*").is_prefix_query());
+        assert!(!WildcardQuery::from_field_value("my_field", r"err\*").is_prefix_query());
+    }
+
+    #[test]
+    fn test_wildcard_tokens_to_regex() {
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+        let mut normalizer = tokenizer_manager.get_normalizer("raw_lowercase").unwrap();
+        let tokens = parse_wildcard_pattern("Error-*-P?od").unwrap();
+        let regex = wildcard_tokens_to_regex(&tokens, "", &mut normalizer).unwrap();
+        assert_eq!(regex, r"(?s)error\x{2d}.*\x{2d}p.od");
+        Regex::new(&regex).unwrap();
+    }
+
+    #[test]
+    fn test_wildcard_query_middle_wildcard() {
+        let values = [
+            "error-db-prod",
+            "error-api-prod",
+            "error-db-dev",
+            "warn-db-prod",
+            "error--prod",
+        ];
+        assert_eq!(search_raw_field(&values, "error-*-prod", 100).unwrap(), 3);
+        assert_eq!(search_raw_field(&values, "error-??-prod", 100).unwrap(), 1);
+        assert_eq!(search_raw_field(&values, "error-?*-prod", 100).unwrap(), 2);
+        assert_eq!(search_raw_field(&values, "*-db-*", 100).unwrap(), 3);
+        assert_eq!(search_raw_field(&values, "*prod", 100).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_wildcard_query_leading_and_trailing_wildcards() {
+        let values = ["timeout", "read timeout exceeded", "time out", "timeouts"];
+        assert_eq!(search_raw_field(&values, "*timeout*", 100).unwrap(), 3);
+        assert_eq!(search_raw_field(&values, "*timeout", 100).unwrap(), 1);
+        // Prefix patterns are run as prefix queries.
+        assert_eq!(search_raw_field(&values, "time*", 100).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_wildcard_query_escaped_wildcards() {
+        let values = ["a*b", "axb", "a?b", "a*bc", r"a\b"];
+        assert_eq!(search_raw_field(&values, r"a\*b", 100).unwrap(), 1);
+        assert_eq!(search_raw_field(&values, r"a\?b", 100).unwrap(), 1);
+        assert_eq!(search_raw_field(&values, r"a\**", 100).unwrap(), 2);
+        assert_eq!(search_raw_field(&values, r"a\\?", 100).unwrap(), 1);
+        assert_eq!(search_raw_field(&values, "a?b", 100).unwrap(), 4);
+        // Characters with a special meaning in regular expressions are matched literally.
+        assert_eq!(search_raw_field(&["a.b", "axb"], "?.b", 100).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_wildcard_query_expansion_limit() {
+        let values = ["error-db-prod", "error-api-prod", "error-db-dev"];
+        assert_eq!(search_raw_field(&values, "error-*-*", 3).unwrap(), 3);
+        let error = search_raw_field(&values, "error-*-*", 2).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("wildcard query `error-*-*` on field `my_field` matches more than 2 terms"));
+    }
+
     #[test]
     fn test_extract_term_for_wildcard() {
-        let query = WildcardQuery {
-            field: "my_field".to_string(),
-            value: "MyString Wh1ch a nOrMal Tokenizer would cut*".to_string(),
-        };
+        let query = WildcardQuery::from_field_value(
+            "my_field",
+            "MyString Wh1ch a nOrMal Tokenizer would cut*",
+        );
         let tokenizer_manager = create_default_quickwit_tokenizer_manager();
         for tokenizer in ["raw", "whitespace"] {
             let mut schema_builder = TantivySchema::builder();
@@ -0,0 +1,201 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use tantivy::query::{
+    BooleanQuery, EmptyScorer, EnableScoring, Explanation, PhraseQuery, Query, Scorer, Weight,
+};
+use tantivy::schema::Field;
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term};
+
+/// A tantivy phrase prefix query allowing up to `slop` position moves between the terms of the
+/// phrase, which `tantivy::query::PhrasePrefixQuery` does not support.
+///
+/// In each segment, the last term of the phrase is expanded to the first `max_expansions` terms
+/// of the dictionary it prefixes, and the query matches the documents matching any of the
+/// resulting sloppy phrases. Matching documents all get the same score.
+#[derive(Clone, Debug)]
+pub(crate) struct SloppyPhrasePrefixQuery {
+    field: Field,
+    // The phrase terms with their offsets. The last one is the prefix.
+    terms: Vec<(usize, Term)>,
+    slop: u32,
+    max_expansions: u32,
+}
+
+impl SloppyPhrasePrefixQuery {
+    /// Creates a new query. `terms` must contain at least two terms, the last one being the
+    /// prefix.
+    pub fn new(terms: Vec<(usize, Term)>, slop: u32, max_expansions: u32) -> Self {
+        assert!(
+            terms.len() > 1,
+            "a sloppy phrase prefix query must have at least two terms"
+        );
+        let field = terms[0].1.field();
+        Self {
+            field,
+            terms,
+            slop,
+            max_expansions,
+        }
+    }
+}
+
+impl Query for SloppyPhrasePrefixQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(SloppyPhrasePrefixWeight {
+            query: self.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for (_, term) in &self.terms[..self.terms.len() - 1] {
+            visitor(term, true);
+        }
+    }
+}
+
+struct SloppyPhrasePrefixWeight {
+    query: SloppyPhrasePrefixQuery,
+}
+
+impl SloppyPhrasePrefixWeight {
+    /// Returns the terms of the segment dictionary starting with the prefix, up to
+    /// `max_expansions` of them.
+    fn expand_prefix(&self, reader: &SegmentReader) -> tantivy::Result<Vec<Term>> {
+        let (_, prefix_term) = self.query.terms.last().expect("terms should not be empty");
+        let prefix_bytes = prefix_term.serialized_value_bytes();
+
+        let inverted_index = reader.inverted_index(self.query.field)?;
+        let mut term_stream_builder = inverted_index.terms().range().ge(prefix_bytes);
+
+        if let Some(prefix_end) = prefix_end(prefix_bytes) {
+            term_stream_builder = term_stream_builder.lt(prefix_end);
+        }
+        let mut term_stream = term_stream_builder.into_stream()?;
+        let mut expanded_terms = Vec::new();
+
+        while expanded_terms.len() < self.query.max_expansions as usize && term_stream.advance() {
+            let mut expanded_term = prefix_term.clone();
+            expanded_term.set_bytes(term_stream.key());
+            expanded_terms.push(expanded_term);
+        }
+        Ok(expanded_terms)
+    }
+}
+
+impl Weight for SloppyPhrasePrefixWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let expanded_terms = self.expand_prefix(reader)?;
+
+        if expanded_terms.is_empty() {
+            return Ok(Box::new(EmptyScorer));
+        }
+        let (prefix_offset, _) = self.query.terms.last().expect("terms should not be empty");
+        let phrase_terms = &self.query.terms[..self.query.terms.len() - 1];
+
+        let phrase_queries: Vec<Box<dyn Query>> = expanded_terms
+            .into_iter()
+            .map(|expanded_term| {
+                let mut terms = phrase_terms.to_vec();
+                terms.push((*prefix_offset, expanded_term));
+                let phrase_query = PhraseQuery::new_with_offset_and_slop(terms, self.query.slop);
+                Box::new(phrase_query) as Box<dyn Query>
+            })
+            .collect();
+        BooleanQuery::union(phrase_queries)
+            .weight(EnableScoring::disabled_from_schema(reader.schema()))?
+            .scorer(reader, boost)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+
+        if scorer.doc() > doc || scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("SloppyPhrasePrefixQuery", scorer.score()))
+    }
+}
+
+/// Returns the smallest byte string greater than all the byte strings starting with `prefix`,
+/// or `None` if there is no such string.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut prefix_end = prefix.to_vec();
+
+    while let Some(last_byte) = prefix_end.pop() {
+        if last_byte != u8::MAX {
+            prefix_end.push(last_byte + 1);
+            return Some(prefix_end);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::collector::Count;
+    use tantivy::schema::{Schema, TEXT};
+    use tantivy::{doc, Index};
+
+    use super::*;
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(prefix_end(b"abc"), Some(b"abd".to_vec()));
+        assert_eq!(prefix_end(b"ab\xff"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_end(b"\xff\xff"), None);
+        assert_eq!(prefix_end(b""), None);
+    }
+
+    #[test]
+    fn test_sloppy_phrase_prefix_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for body in ["quick fable", "quick brown fox", "quick red brown fox"] {
+            index_writer.add_document(doc!(body_field => body)).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let count = |slop: u32, max_expansions: u32| {
+            let query = SloppyPhrasePrefixQuery::new(
+                vec![
+                    (0, Term::from_field_text(body_field, "quick")),
+                    (1, Term::from_field_text(body_field, "f")),
+                ],
+                slop,
+                max_expansions,
+            );
+            searcher.search(&query, &Count).unwrap()
+        };
+        assert_eq!(count(0, 50), 1);
+        // The slop allows a one-token gap...
+        assert_eq!(count(1, 50), 2);
+        // ... or a two-token gap.
+        assert_eq!(count(2, 50), 3);
+        // `f` is only expanded to `fable`, the first term of the dictionary starting with it.
+        assert_eq!(count(2, 1), 1);
+        assert_eq!(count(2, 0), 0);
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv6Addr};
+
+use tantivy::schema::IntoIpv6Addr;
+use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
+
+/// Name of the fast field normalizer of the string fields coerced into IP addresses.
+pub const IP_NORMALIZER_NAME: &str = "ip";
+
+/// Formats an IP address into its canonical textual form: the fully expanded IPv6 notation, with
+/// each of the eight groups zero-padded to four lowercase hexadecimal digits. IPv4 addresses are
+/// mapped to IPv6 first.
+///
+/// Unlike the usual notations, the canonical form has a fixed width, so comparing two canonical
+/// forms lexicographically compares the IP addresses.
+pub fn canonical_ip_text(ip_addr: Ipv6Addr) -> String {
+    let mut ip_text = String::with_capacity(39);
+
+    for (segment_idx, segment) in ip_addr.segments().iter().enumerate() {
+        if segment_idx > 0 {
+            ip_text.push(':');
+        }
+        write!(ip_text, "{segment:04x}").expect("writing to a string should never fail");
+    }
+    ip_text
+}
+
+/// Normalizer emitting the canonical textual form of an IP address, see [`canonical_ip_text`].
+///
+/// Texts that are not valid IP addresses produce no token, so they are left out of the fast
+/// field.
+#[derive(Clone, Default)]
+pub(crate) struct IpNormalizer {
+    token: Token,
+}
+
+impl Tokenizer for IpNormalizer {
+    type TokenStream<'a> = IpNormalizerTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        self.token.reset();
+
+        let Ok(ip_addr) = text.trim().parse::<IpAddr>() else {
+            return IpNormalizerTokenStream {
+                token: &mut self.token,
+                has_token: false,
+            };
+        };
+        self.token.text = canonical_ip_text(ip_addr.into_ipv6_addr());
+        self.token.offset_to = text.len();
+        IpNormalizerTokenStream {
+            token: &mut self.token,
+            has_token: true,
+        }
+    }
+}
+
+pub(crate) struct IpNormalizerTokenStream<'a> {
+    token: &'a mut Token,
+    has_token: bool,
+}
+
+impl TokenStream for IpNormalizerTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        let has_token = self.has_token;
+        self.has_token = false;
+        has_token
+    }
+
+    fn token(&self) -> &Token {
+        self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(text: &str) -> Vec<String> {
+        let mut normalizer = IpNormalizer::default();
+        let mut token_stream = normalizer.token_stream(text);
+        let mut tokens = Vec::new();
+        token_stream.process(&mut |token| tokens.push(token.text.clone()));
+        tokens
+    }
+
+    #[test]
+    fn test_canonical_ip_text() {
+        let ip_addr: IpAddr = "192.168.0.5".parse().unwrap();
+        assert_eq!(
+            canonical_ip_text(ip_addr.into_ipv6_addr()),
+            "0000:0000:0000:0000:0000:ffff:c0a8:0005"
+        );
+        let ip_addr: Ipv6Addr = "2001:DB8::1".parse().unwrap();
+        assert_eq!(
+            canonical_ip_text(ip_addr),
+            "2001:0db8:0000:0000:0000:0000:0000:0001"
+        );
+        // Lexicographic comparison of the canonical forms matches the IP addresses order, which
+        // does not hold for their usual notation.
+        let ip_texts = [
+            "192.168.0.1",
+            "192.168.0.5",
+            "192.168.0.10",
+            "::1",
+            "2001:db8::1",
+        ];
+        let mut ip_addrs: Vec<Ipv6Addr> = ip_texts
+            .iter()
+            .map(|ip_text| ip_text.parse::<IpAddr>().unwrap().into_ipv6_addr())
+            .collect();
+        let mut canonical_ip_texts: Vec<String> =
+            ip_addrs.iter().copied().map(canonical_ip_text).collect();
+        ip_addrs.sort();
+        canonical_ip_texts.sort();
+        let expected_canonical_ip_texts: Vec<String> =
+            ip_addrs.into_iter().map(canonical_ip_text).collect();
+        assert_eq!(canonical_ip_texts, expected_canonical_ip_texts);
+    }
+
+    #[test]
+    fn test_ip_normalizer() {
+        assert_eq!(
+            normalize("192.168.0.10"),
+            ["0000:0000:0000:0000:0000:ffff:c0a8:000a"]
+        );
+        assert_eq!(
+            normalize(" ::ffff:192.168.0.10 "),
+            ["0000:0000:0000:0000:0000:ffff:c0a8:000a"]
+        );
+        assert!(normalize("not-an-ip").is_empty());
+        assert!(normalize("").is_empty());
+    }
+}
@@ -19,6 +19,7 @@
 
 mod chinese_compatible;
 mod code_tokenizer;
+mod ip_normalizer;
 #[cfg(feature = "multilang")]
 mod multilang;
 mod tokenizer_manager;
@@ -31,6 +32,8 @@ use tantivy::tokenizer::{
 
 use self::chinese_compatible::ChineseTokenizer;
 pub use self::code_tokenizer::CodeTokenizer;
+use self::ip_normalizer::IpNormalizer;
+pub use self::ip_normalizer::{canonical_ip_text, IP_NORMALIZER_NAME};
 #[cfg(feature = "multilang")]
 pub use self::multilang::MultiLangTokenizer;
 pub use self::tokenizer_manager::TokenizerManager;
@@ -119,6 +122,7 @@ fn create_quickwit_fastfield_normalizer_manager() -> TokenizerManager {
     let tokenizer_manager = TokenizerManager::new();
     tokenizer_manager.register("raw", raw_tokenizer, false);
     tokenizer_manager.register("lowercase", lower_case_tokenizer, true);
+    tokenizer_manager.register(IP_NORMALIZER_NAME, IpNormalizer::default(), false);
     tokenizer_manager
 }
 
@@ -73,7 +73,24 @@ impl std::fmt::Display for ApiError {
     }
 }
 
+/// Body of the error responses. Older servers return a bare `{"message": "..."}` object.
 #[derive(Deserialize)]
-pub(crate) struct ErrorResponsePayload {
+#[serde(untagged)]
+pub(crate) enum ErrorResponsePayload {
+    Error { error: ErrorBody },
+    Legacy { message: String },
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ErrorBody {
     pub message: String,
 }
+
+impl ErrorResponsePayload {
+    pub fn into_message(self) -> String {
+        match self {
+            Self::Error { error } => error.message,
+            Self::Legacy { message } => message,
+        }
+    }
+}
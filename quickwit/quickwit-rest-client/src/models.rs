@@ -51,7 +51,7 @@ impl ApiResponse {
         let error_body_bytes = self.inner.bytes().await.ok()?;
         let error_body_text = std::str::from_utf8(&error_body_bytes).ok()?;
         if let Ok(error_payload) = serde_json::from_str::<ErrorResponsePayload>(error_body_text) {
-            Some(error_payload.message)
+            Some(error_payload.into_message())
         } else {
             Some(error_body_text.to_string())
         }
@@ -21,13 +21,18 @@ use std::time::Duration;
 
 use bytes::Bytes;
 use quickwit_cluster::ClusterSnapshot;
+use quickwit_common::uri::Uri;
 use quickwit_config::{ConfigFormat, SourceConfig};
+use quickwit_index_management::{ImportPositionsReport, IndexSnapshotReport, RestoreIndexReport};
 use quickwit_indexing::actors::IndexingServiceCounters;
 pub use quickwit_ingest::CommitType;
+use quickwit_metastore::checkpoint::SerializableShardPositions;
 use quickwit_metastore::{IndexMetadata, Split, SplitInfo};
 use quickwit_proto::ingest::Shard;
 use quickwit_search::SearchResponseRest;
-use quickwit_serve::{ListSplitsQueryParams, ListSplitsResponse, SearchRequestQueryString};
+use quickwit_serve::{
+    ListSplitsQueryParams, ListSplitsResponse, RestoreIndexRequest, SearchRequestQueryString,
+};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use reqwest::{Client, ClientBuilder, Method, StatusCode, Url};
 use serde::Serialize;
@@ -396,6 +401,26 @@ impl<'a> IndexClient<'a> {
         Ok(index_metadata)
     }
 
+    pub async fn block_ingest(&self, index_id: &str) -> Result<IndexMetadata, Error> {
+        let path = format!("indexes/{index_id}/block-ingest");
+        let response = self
+            .transport
+            .send::<()>(Method::PUT, &path, None, None, None, self.timeout)
+            .await?;
+        let index_metadata = response.deserialize().await?;
+        Ok(index_metadata)
+    }
+
+    pub async fn unblock_ingest(&self, index_id: &str) -> Result<IndexMetadata, Error> {
+        let path = format!("indexes/{index_id}/unblock-ingest");
+        let response = self
+            .transport
+            .send::<()>(Method::PUT, &path, None, None, None, self.timeout)
+            .await?;
+        let index_metadata = response.deserialize().await?;
+        Ok(index_metadata)
+    }
+
     pub async fn clear(&self, index_id: &str) -> Result<(), Error> {
         let path = format!("indexes/{index_id}/clear");
         let response = self
@@ -422,6 +447,81 @@ impl<'a> IndexClient<'a> {
         let file_entries = response.deserialize().await?;
         Ok(file_entries)
     }
+
+    pub async fn export_positions(
+        &self,
+        index_id_patterns: &[String],
+    ) -> Result<SerializableShardPositions, Error> {
+        let query_params = if index_id_patterns.is_empty() {
+            None
+        } else {
+            Some([("index_id_patterns", index_id_patterns.join(","))])
+        };
+        let response = self
+            .transport
+            .send(
+                Method::GET,
+                "positions",
+                None,
+                query_params.as_ref(),
+                None,
+                self.timeout,
+            )
+            .await?;
+        let shard_positions = response.deserialize().await?;
+        Ok(shard_positions)
+    }
+
+    pub async fn import_positions(
+        &self,
+        shard_positions: &SerializableShardPositions,
+    ) -> Result<ImportPositionsReport, Error> {
+        let body = serde_json::to_vec(shard_positions).expect("Serialization should never fail.");
+        let response = self
+            .transport
+            .send::<()>(
+                Method::PUT,
+                "positions",
+                None,
+                None,
+                Some(Bytes::from(body)),
+                self.timeout,
+            )
+            .await?;
+        let report = response.deserialize().await?;
+        Ok(report)
+    }
+
+    pub async fn snapshot(&self, index_id: &str) -> Result<IndexSnapshotReport, Error> {
+        let path = format!("indexes/{index_id}/snapshot");
+        let response = self
+            .transport
+            .send::<()>(Method::POST, &path, None, None, None, self.timeout)
+            .await?;
+        let report = response.deserialize().await?;
+        Ok(report)
+    }
+
+    pub async fn restore(&self, snapshot_uri: &Uri) -> Result<RestoreIndexReport, Error> {
+        let restore_index_request = RestoreIndexRequest {
+            snapshot_uri: snapshot_uri.clone(),
+        };
+        let body =
+            serde_json::to_vec(&restore_index_request).expect("Serialization should never fail.");
+        let response = self
+            .transport
+            .send::<()>(
+                Method::POST,
+                "indexes/restore",
+                None,
+                None,
+                Some(Bytes::from(body)),
+                self.timeout,
+            )
+            .await?;
+        let report = response.deserialize().await?;
+        Ok(report)
+    }
 }
 
 /// Client for splits APIs.
@@ -694,7 +794,8 @@ mod test {
     use quickwit_indexing::mock_split;
     use quickwit_ingest::CommitType;
     use quickwit_metastore::IndexMetadata;
-    use quickwit_search::SearchResponseRest;
+    use quickwit_proto::search::NumHitsRelation;
+    use quickwit_search::{SearchResponseRest, SearchResponseStats};
     use quickwit_serve::{ListSplitsQueryParams, ListSplitsResponse, SearchRequestQueryString};
     use reqwest::header::CONTENT_TYPE;
     use reqwest::{StatusCode, Url};
@@ -737,6 +838,13 @@ mod test {
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            fetch_docs_source: None,
+            partial: false,
+            pit_id: None,
+            stats: SearchResponseStats::default(),
+            num_hits_relation: NumHitsRelation::EqualTo,
+            sampled: false,
+            sample_rate: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
@@ -872,9 +980,13 @@ mod test {
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/ingest"))
             .and(body_bytes(buffer.clone()))
-            .respond_with(
-                ResponseTemplate::new(405).set_body_json(json!({"message": "internal error"})),
-            )
+            .respond_with(ResponseTemplate::new(405).set_body_json(json!({
+                "error": {
+                    "code": "method_not_allowed",
+                    "message": "internal error",
+                    "details": {},
+                }
+            })))
             .up_to_n_times(1)
             .mount(&mock_server)
             .await;
@@ -890,7 +1002,7 @@ mod test {
             .await
             .unwrap_err();
         assert!(matches!(error, Error::Api(_)));
-        assert!(error.to_string().contains("internal error"));
+        assert!(error.to_string().ends_with("message=internal error)"));
     }
 
     #[tokio::test]
@@ -999,6 +1111,80 @@ mod test {
             .delete("my-index", true)
             .await
             .unwrap_err();
+
+        // GET positions
+        Mock::given(method("GET"))
+            .and(path("/api/v1/positions"))
+            .and(query_param("index_id_patterns", "my-index,other-*"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "sources": [{
+                    "index_uid": "my-index:00000000000000000000000000",
+                    "source_id": "my-source",
+                    "positions": {"0": "00000000000000000042"},
+                }]
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let shard_positions = qw_client
+            .indexes()
+            .export_positions(&["my-index".to_string(), "other-*".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(shard_positions.sources.len(), 1);
+
+        // PUT positions
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/positions"))
+            .and(body_json(&shard_positions))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "num_imported_positions": 1,
+                "warnings": [],
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let report = qw_client
+            .indexes()
+            .import_positions(&shard_positions)
+            .await
+            .unwrap();
+        assert_eq!(report.num_imported_positions, 1);
+
+        // POST snapshot
+        Mock::given(method("POST"))
+            .and(path("/api/v1/indexes/my-index/snapshot"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "snapshot_uri": "s3://my-bucket/my-index/index-snapshot.json",
+                "num_splits": 3,
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let snapshot_report = qw_client.indexes().snapshot("my-index").await.unwrap();
+        assert_eq!(snapshot_report.num_splits, 3);
+
+        // POST restore
+        Mock::given(method("POST"))
+            .and(path("/api/v1/indexes/restore"))
+            .and(body_json(json!({
+                "snapshot_uri": "s3://my-bucket/my-index/index-snapshot.json",
+            })))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!({
+                "index_uid": "my-index:00000000000000000000000000",
+                "num_restored_splits": 2,
+                "missing_split_ids": ["split-3"],
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let restore_report = qw_client
+            .indexes()
+            .restore(&snapshot_report.snapshot_uri)
+            .await
+            .unwrap();
+        assert_eq!(restore_report.num_restored_splits, 2);
+        assert_eq!(restore_report.missing_split_ids, ["split-3"]);
     }
 
     #[tokio::test]
@@ -0,0 +1,325 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::search::SearchRequest;
+use serde_json::{Map, Value};
+use tantivy::{DocId, SegmentOrdinal};
+
+use crate::SearchError;
+
+/// The sample rate of a request is expressed in parts per million.
+const SAMPLE_RATE_PPM_SCALE: u32 = 1_000_000;
+
+/// Converts a sample rate within `]0, 1]` into the parts per million of
+/// [`SearchRequest::aggregation_sample_rate_ppm`].
+pub fn sample_rate_to_ppm(sample_rate: f32) -> crate::Result<u32> {
+    if !(sample_rate > 0.0 && sample_rate <= 1.0) {
+        return Err(SearchError::InvalidArgument(format!(
+            "sample rate must be within ]0, 1], got `{sample_rate}`"
+        )));
+    }
+    let sample_rate_ppm = (sample_rate as f64 * SAMPLE_RATE_PPM_SCALE as f64).round() as u32;
+    Ok(sample_rate_ppm.max(1))
+}
+
+pub(crate) fn validate_aggregation_sample_rate(
+    search_request: &SearchRequest,
+) -> crate::Result<()> {
+    let Some(sample_rate_ppm) = search_request.aggregation_sample_rate_ppm else {
+        return Ok(());
+    };
+    if sample_rate_ppm == 0 || sample_rate_ppm > SAMPLE_RATE_PPM_SCALE {
+        return Err(SearchError::InvalidArgument(format!(
+            "aggregation sample rate must be within ]0, {SAMPLE_RATE_PPM_SCALE}] parts per \
+             million, got `{sample_rate_ppm}`"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the rate at which the documents feeding the aggregations of the request are sampled,
+/// or `None` if all the documents are aggregated.
+pub(crate) fn aggregation_sample_rate(search_request: &SearchRequest) -> Option<f64> {
+    search_request
+        .aggregation_sample_rate_ppm
+        .and_then(|sample_rate_ppm| {
+            if sample_rate_ppm >= SAMPLE_RATE_PPM_SCALE {
+                None
+            } else {
+                Some(sample_rate_ppm as f64 / SAMPLE_RATE_PPM_SCALE as f64)
+            }
+        })
+}
+
+/// Deterministically selects the documents of a segment that feed the aggregations.
+///
+/// A document is part of the sample if the hash of its address falls below the sample rate, so
+/// the same documents are sampled every time the same split is searched.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DocSampler {
+    seed: u64,
+    threshold: u64,
+}
+
+impl DocSampler {
+    pub fn new(sample_rate: f64, split_id: &str, segment_ord: SegmentOrdinal) -> Self {
+        // FNV-1a, which, unlike the standard library hashers, is stable across releases.
+        let mut seed: u64 = 0xcbf29ce484222325;
+        for byte in split_id.bytes() {
+            seed ^= byte as u64;
+            seed = seed.wrapping_mul(0x100000001b3);
+        }
+        seed = mix64(seed ^ ((segment_ord as u64) << 32));
+        let threshold = (sample_rate.clamp(0.0, 1.0) * u64::MAX as f64) as u64;
+        Self { seed, threshold }
+    }
+
+    #[inline]
+    pub fn is_sampled(&self, doc_id: DocId) -> bool {
+        mix64(self.seed ^ doc_id as u64) < self.threshold
+    }
+
+    /// Appends the sampled documents of the block to `sampled_docs`.
+    #[inline]
+    pub fn sample_block(&self, docs: &[DocId], sampled_docs: &mut Vec<DocId>) {
+        sampled_docs.extend(
+            docs.iter()
+                .copied()
+                .filter(|doc_id| self.is_sampled(*doc_id)),
+        );
+    }
+}
+
+/// Finalizer of SplitMix64, which spreads consecutive doc IDs uniformly over the `u64` range.
+#[inline]
+fn mix64(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/// Returns the warning added to the responses whose aggregations were computed on a sample.
+pub(crate) fn sampled_aggregations_warning(sample_rate: f64) -> String {
+    format!(
+        "aggregations were computed on a {:.2}% sample of the matching documents: document \
+         counts, `value_count` and `sum` values, and the `count` and `sum` of stats are scaled up \
+         by {:.2} to estimate the exact values, while averages, minimums, maximums, percentiles, \
+         cardinalities, and top hits are computed on the sample as is",
+        sample_rate * 100.0,
+        1.0 / sample_rate
+    )
+}
+
+/// Scales the count-like values of the aggregation results computed on a sample of the documents
+/// back up, so that they estimate the values that would have been computed on all the documents.
+///
+/// The aggregation request tells the kind of each aggregation: the document counts of the
+/// buckets, `value_count` and `sum` metrics, and the `count` and `sum` of `stats` are scaled,
+/// whereas the other metrics are estimated from the sample as is.
+pub(crate) fn scale_sampled_aggregation_results(
+    aggregation_results: &mut Value,
+    aggregation_request: &Value,
+    sample_rate: f64,
+) {
+    let (Some(aggregation_results), Some(aggregation_request)) = (
+        aggregation_results.as_object_mut(),
+        aggregation_request.as_object(),
+    ) else {
+        return;
+    };
+    scale_aggregations(aggregation_results, aggregation_request, 1.0 / sample_rate);
+}
+
+fn scale_aggregations(
+    aggregation_results: &mut Map<String, Value>,
+    aggregation_request: &Map<String, Value>,
+    scale: f64,
+) {
+    for (aggregation_name, aggregation) in aggregation_request {
+        let Some(Value::Object(aggregation_result)) = aggregation_results.get_mut(aggregation_name)
+        else {
+            continue;
+        };
+        let Some(aggregation) = aggregation.as_object() else {
+            continue;
+        };
+        let sub_aggregation_request = aggregation
+            .get("aggs")
+            .or_else(|| aggregation.get("aggregations"))
+            .and_then(Value::as_object);
+        let aggregation_kind_opt = aggregation
+            .keys()
+            .find(|key| !matches!(key.as_str(), "aggs" | "aggregations" | "meta"));
+
+        match aggregation_kind_opt.map(String::as_str) {
+            Some("value_count" | "sum") => {
+                scale_value(aggregation_result, "value", scale);
+            }
+            Some("stats" | "extended_stats") => {
+                scale_value(aggregation_result, "count", scale);
+                scale_value(aggregation_result, "sum", scale);
+                scale_value(aggregation_result, "sum_of_squares", scale);
+            }
+            _ => {}
+        }
+        scale_value(aggregation_result, "sum_other_doc_count", scale);
+        scale_value(aggregation_result, "doc_count_error_upper_bound", scale);
+
+        let buckets: Box<dyn Iterator<Item = &mut Value>> =
+            match aggregation_result.get_mut("buckets") {
+                Some(Value::Array(buckets)) => Box::new(buckets.iter_mut()),
+                Some(Value::Object(buckets)) => Box::new(buckets.values_mut()),
+                _ => continue,
+            };
+        for bucket in buckets {
+            let Some(bucket) = bucket.as_object_mut() else {
+                continue;
+            };
+            scale_value(bucket, "doc_count", scale);
+
+            if let Some(sub_aggregation_request) = sub_aggregation_request {
+                scale_aggregations(bucket, sub_aggregation_request, scale);
+            }
+        }
+    }
+}
+
+/// Scales a numeric value, keeping integers integral.
+fn scale_value(object: &mut Map<String, Value>, key: &str, scale: f64) {
+    let Some(value) = object.get_mut(key) else {
+        return;
+    };
+    if let Some(integer) = value.as_u64() {
+        *value = Value::from((integer as f64 * scale).round() as u64);
+    } else if let Some(float) = value.as_f64() {
+        *value = Value::from(float * scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_sample_rate_to_ppm() {
+        assert_eq!(sample_rate_to_ppm(1.0).unwrap(), 1_000_000);
+        assert_eq!(sample_rate_to_ppm(0.1).unwrap(), 100_000);
+        assert_eq!(sample_rate_to_ppm(1e-9).unwrap(), 1);
+        sample_rate_to_ppm(0.0).unwrap_err();
+        sample_rate_to_ppm(1.5).unwrap_err();
+        sample_rate_to_ppm(f32::NAN).unwrap_err();
+    }
+
+    #[test]
+    fn test_aggregation_sample_rate() {
+        let mut search_request = SearchRequest::default();
+        assert_eq!(aggregation_sample_rate(&search_request), None);
+        validate_aggregation_sample_rate(&search_request).unwrap();
+
+        search_request.aggregation_sample_rate_ppm = Some(1_000_000);
+        assert_eq!(aggregation_sample_rate(&search_request), None);
+
+        search_request.aggregation_sample_rate_ppm = Some(250_000);
+        assert_eq!(aggregation_sample_rate(&search_request), Some(0.25));
+
+        search_request.aggregation_sample_rate_ppm = Some(0);
+        validate_aggregation_sample_rate(&search_request).unwrap_err();
+
+        search_request.aggregation_sample_rate_ppm = Some(1_000_001);
+        validate_aggregation_sample_rate(&search_request).unwrap_err();
+    }
+
+    #[test]
+    fn test_doc_sampler() {
+        let doc_sampler = DocSampler::new(0.1, "split-1", 0);
+        let num_sampled_docs = (0..100_000)
+            .filter(|doc_id| doc_sampler.is_sampled(*doc_id))
+            .count();
+        assert!((9_500..=10_500).contains(&num_sampled_docs));
+
+        // The sample is deterministic, and differs from one segment to another.
+        let same_doc_sampler = DocSampler::new(0.1, "split-1", 0);
+        let other_doc_sampler = DocSampler::new(0.1, "split-1", 1);
+        let doc_ids: Vec<DocId> = (0..1_000).collect();
+        let mut sampled_docs = Vec::new();
+        let mut same_sampled_docs = Vec::new();
+        let mut other_sampled_docs = Vec::new();
+        doc_sampler.sample_block(&doc_ids, &mut sampled_docs);
+        same_doc_sampler.sample_block(&doc_ids, &mut same_sampled_docs);
+        other_doc_sampler.sample_block(&doc_ids, &mut other_sampled_docs);
+        assert_eq!(sampled_docs, same_sampled_docs);
+        assert_ne!(sampled_docs, other_sampled_docs);
+
+        let doc_sampler = DocSampler::new(1.0, "split-1", 0);
+        assert!((0..1_000).all(|doc_id| doc_sampler.is_sampled(doc_id)));
+    }
+
+    #[test]
+    fn test_scale_sampled_aggregation_results() {
+        let aggregation_request = json!({
+            "colors": {
+                "terms": {"field": "color"},
+                "aggs": {
+                    "num_prices": {"value_count": {"field": "price"}},
+                    "avg_price": {"avg": {"field": "price"}}
+                }
+            },
+            "price_stats": {"stats": {"field": "price"}},
+            "total_price": {"sum": {"field": "price"}},
+            "prices": {"range": {"field": "price", "ranges": [{"to": 10}], "keyed": true}}
+        });
+        let mut aggregation_results = json!({
+            "colors": {
+                "doc_count_error_upper_bound": 1,
+                "sum_other_doc_count": 2,
+                "buckets": [{
+                    "key": "red",
+                    "doc_count": 10,
+                    "num_prices": {"value": 8.0},
+                    "avg_price": {"value": 4.5}
+                }]
+            },
+            "price_stats": {"count": 8, "sum": 36.0, "min": 1.0, "max": 8.0, "avg": 4.5},
+            "total_price": {"value": 36.0},
+            "prices": {"buckets": {"*-10": {"to": 10.0, "doc_count": 8}}}
+        });
+        scale_sampled_aggregation_results(&mut aggregation_results, &aggregation_request, 0.25);
+
+        assert_eq!(
+            aggregation_results,
+            json!({
+                "colors": {
+                    "doc_count_error_upper_bound": 4,
+                    "sum_other_doc_count": 8,
+                    "buckets": [{
+                        "key": "red",
+                        "doc_count": 40,
+                        "num_prices": {"value": 32.0},
+                        "avg_price": {"value": 4.5}
+                    }]
+                },
+                "price_stats": {"count": 32, "sum": 144.0, "min": 1.0, "max": 8.0, "avg": 4.5},
+                "total_price": {"value": 144.0},
+                "prices": {"buckets": {"*-10": {"to": 10.0, "doc_count": 32}}}
+            })
+        );
+    }
+}
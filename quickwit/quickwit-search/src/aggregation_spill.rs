@@ -0,0 +1,232 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bytesize::ByteSize;
+
+use crate::metrics::SEARCH_METRICS;
+
+/// Node-wide disk budget for the intermediate aggregation results spilled by the leaf search
+/// requests running on a searcher.
+#[derive(Clone)]
+pub struct AggregationSpillDisk {
+    inner: Arc<AggregationSpillDiskInner>,
+}
+
+struct AggregationSpillDiskInner {
+    spill_dir: PathBuf,
+    capacity: u64,
+    num_bytes_used: AtomicU64,
+}
+
+impl AggregationSpillDisk {
+    pub fn new(spill_dir: PathBuf, capacity: ByteSize) -> Self {
+        Self {
+            inner: Arc::new(AggregationSpillDiskInner {
+                spill_dir,
+                capacity: capacity.as_u64(),
+                num_bytes_used: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes currently spilled to disk.
+    pub fn num_bytes_used(&self) -> u64 {
+        self.inner.num_bytes_used.load(Ordering::Acquire)
+    }
+
+    fn try_reserve(&self, num_bytes: u64) -> bool {
+        let capacity = self.inner.capacity;
+        let reserved = self
+            .inner
+            .num_bytes_used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num_bytes_used| {
+                num_bytes_used
+                    .checked_add(num_bytes)
+                    .filter(|new_num_bytes_used| *new_num_bytes_used <= capacity)
+            })
+            .is_ok();
+        if reserved {
+            SEARCH_METRICS
+                .aggregation_spill_disk_in_use_bytes
+                .add(num_bytes as i64);
+        }
+        reserved
+    }
+
+    fn release(&self, num_bytes: u64) {
+        self.inner
+            .num_bytes_used
+            .fetch_sub(num_bytes, Ordering::AcqRel);
+        SEARCH_METRICS
+            .aggregation_spill_disk_in_use_bytes
+            .sub(num_bytes as i64);
+    }
+
+    fn create_spill_file(&self) -> io::Result<SpillFile> {
+        std::fs::create_dir_all(&self.inner.spill_dir)?;
+        // The file is unlinked right away, so it is removed even if the searcher crashes.
+        let file = tempfile::tempfile_in(&self.inner.spill_dir)?;
+        Ok(SpillFile {
+            file,
+            num_bytes: 0,
+            spill_disk: self.clone(),
+        })
+    }
+}
+
+/// Append-only file holding the runs spilled by a leaf request. The bytes are returned to the
+/// disk budget when the file is dropped.
+struct SpillFile {
+    file: File,
+    num_bytes: u64,
+    spill_disk: AggregationSpillDisk,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        self.spill_disk.release(self.num_bytes);
+    }
+}
+
+/// Spills the intermediate aggregation results buffered by a leaf request once they exceed
+/// the in-memory threshold.
+///
+/// Each spilled run is a serialized intermediate aggregation result, read back one at a time
+/// when the leaf request merges its results. The spill performs blocking IO, so it must not be
+/// used from an async context.
+#[derive(Clone)]
+pub(crate) struct AggregationSpill {
+    spill_disk: AggregationSpillDisk,
+    in_memory_threshold: u64,
+    spilled_runs: Arc<Mutex<SpilledRuns>>,
+}
+
+#[derive(Default)]
+struct SpilledRuns {
+    spill_file_opt: Option<SpillFile>,
+    // Offset and length of the runs in the spill file.
+    runs: Vec<(u64, u64)>,
+}
+
+impl AggregationSpill {
+    pub fn new(spill_disk: AggregationSpillDisk, in_memory_threshold: ByteSize) -> Self {
+        Self {
+            spill_disk,
+            in_memory_threshold: in_memory_threshold.as_u64(),
+            spilled_runs: Arc::default(),
+        }
+    }
+
+    /// Number of bytes of intermediate results that can be buffered in memory before spilling.
+    pub fn in_memory_threshold(&self) -> u64 {
+        self.in_memory_threshold
+    }
+
+    /// Writes a run to the spill file. Returns `false` if the disk budget is exhausted, in which
+    /// case the caller keeps the run in memory.
+    pub fn spill(&self, run: &[u8]) -> io::Result<bool> {
+        let num_bytes = run.len() as u64;
+        if !self.spill_disk.try_reserve(num_bytes) {
+            return Ok(false);
+        }
+        let mut spilled_runs_guard = self.spilled_runs.lock().unwrap();
+        let SpilledRuns {
+            spill_file_opt,
+            runs,
+        } = &mut *spilled_runs_guard;
+
+        let spill_file = match spill_file_opt {
+            Some(spill_file) => spill_file,
+            None => match self.spill_disk.create_spill_file() {
+                Ok(spill_file) => spill_file_opt.insert(spill_file),
+                Err(io_error) => {
+                    self.spill_disk.release(num_bytes);
+                    return Err(io_error);
+                }
+            },
+        };
+        // The bytes are accounted to the file before writing, so that a partial write is released
+        // with the file.
+        spill_file.num_bytes += num_bytes;
+        let offset = spill_file.file.seek(SeekFrom::End(0))?;
+        spill_file.file.write_all(run)?;
+        runs.push((offset, num_bytes));
+
+        SEARCH_METRICS.leaf_aggregation_spills_total.inc();
+        SEARCH_METRICS
+            .leaf_aggregation_spilled_bytes_total
+            .inc_by(num_bytes);
+        Ok(true)
+    }
+
+    /// Number of runs spilled to disk so far.
+    pub fn num_spilled_runs(&self) -> usize {
+        self.spilled_runs.lock().unwrap().runs.len()
+    }
+
+    /// Reads back the spilled run of the given ordinal.
+    pub fn read_spilled_run(&self, run_ord: usize) -> io::Result<Vec<u8>> {
+        let mut spilled_runs_guard = self.spilled_runs.lock().unwrap();
+        let SpilledRuns {
+            spill_file_opt,
+            runs,
+        } = &mut *spilled_runs_guard;
+        let (offset, num_bytes) = runs[run_ord];
+        let spill_file = spill_file_opt
+            .as_mut()
+            .expect("spill file should exist if a run was spilled");
+        spill_file.file.seek(SeekFrom::Start(offset))?;
+        let mut run = vec![0u8; num_bytes as usize];
+        spill_file.file.read_exact(&mut run)?;
+        Ok(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregation_spill() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let spill_disk = AggregationSpillDisk::new(temp_dir.path().join("spill"), ByteSize::b(10));
+        let spill = AggregationSpill::new(spill_disk.clone(), ByteSize::b(1));
+
+        assert!(spill.spill(b"abcd").unwrap());
+        assert!(spill.spill(b"efgh").unwrap());
+        assert_eq!(spill_disk.num_bytes_used(), 8);
+
+        // The disk budget is exhausted.
+        assert!(!spill.spill(b"ijkl").unwrap());
+        assert_eq!(spill_disk.num_bytes_used(), 8);
+
+        assert_eq!(spill.num_spilled_runs(), 2);
+        assert_eq!(spill.read_spilled_run(1).unwrap(), b"efgh");
+        assert_eq!(spill.read_spilled_run(0).unwrap(), b"abcd");
+
+        drop(spill);
+        assert_eq!(spill_disk.num_bytes_used(), 0);
+    }
+}
@@ -25,10 +25,11 @@ use std::time::{Duration, Instant};
 use bytesize::ByteSize;
 use futures::{StreamExt, TryStreamExt};
 use http::Uri;
+use quickwit_config::LeafSearchResponseCompression;
 use quickwit_proto::search::{
-    GetKvRequest, LeafSearchStreamResponse, PutKvRequest, ReportSplitsRequest,
+    GetKvRequest, LeafSearchRequest, LeafSearchStreamResponse, PutKvRequest, ReportSplitsRequest,
 };
-use quickwit_proto::tonic::codegen::InterceptedService;
+use quickwit_proto::tonic::codegen::{CompressionEncoding, InterceptedService};
 use quickwit_proto::tonic::transport::{Channel, Endpoint};
 use quickwit_proto::tonic::Request;
 use quickwit_proto::{tonic, SpanContextInterceptor};
@@ -58,6 +59,7 @@ enum SearchServiceClientImpl {
 pub struct SearchServiceClient {
     client_impl: SearchServiceClientImpl,
     grpc_addr: SocketAddr,
+    leaf_search_response_compression: LeafSearchResponseCompression,
 }
 
 impl fmt::Debug for SearchServiceClient {
@@ -84,6 +86,7 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Grpc(client),
             grpc_addr,
+            leaf_search_response_compression: LeafSearchResponseCompression::default(),
         }
     }
 
@@ -92,9 +95,20 @@ impl SearchServiceClient {
         SearchServiceClient {
             client_impl: SearchServiceClientImpl::Local(service),
             grpc_addr,
+            leaf_search_response_compression: LeafSearchResponseCompression::default(),
         }
     }
 
+    /// Sets whether the client asks the leaf nodes for compressed leaf search responses. It has
+    /// no effect on local clients.
+    pub fn with_leaf_search_response_compression(
+        mut self,
+        leaf_search_response_compression: LeafSearchResponseCompression,
+    ) -> Self {
+        self.leaf_search_response_compression = leaf_search_response_compression;
+        self
+    }
+
     /// Return the grpc_addr the underlying client connects to.
     pub fn grpc_addr(&self) -> SocketAddr {
         self.grpc_addr
@@ -144,11 +158,23 @@ impl SearchServiceClient {
     ) -> crate::Result<quickwit_proto::search::LeafSearchResponse> {
         let start = Instant::now();
         let response_result = match &mut self.client_impl {
-            SearchServiceClientImpl::Grpc(grpc_client) => grpc_client
-                .leaf_search(request)
-                .await
-                .map(|tonic_response| tonic_response.into_inner())
-                .map_err(|tonic_error| parse_grpc_error(&tonic_error)),
+            SearchServiceClientImpl::Grpc(grpc_client) => {
+                let tonic_result = if accept_compressed_leaf_search_response(
+                    self.leaf_search_response_compression,
+                    &request,
+                ) {
+                    grpc_client
+                        .clone()
+                        .accept_compressed(CompressionEncoding::Gzip)
+                        .leaf_search(request)
+                        .await
+                } else {
+                    grpc_client.leaf_search(request).await
+                };
+                tonic_result
+                    .map(|tonic_response| tonic_response.into_inner())
+                    .map_err(|tonic_error| parse_grpc_error(&tonic_error))
+            }
             SearchServiceClientImpl::Local(service) => service.leaf_search(request).await,
         };
         let elapsed = start.elapsed().as_secs_f64();
@@ -338,6 +364,29 @@ impl SearchServiceClient {
     }
 }
 
+/// Minimum number of hits requested for a leaf search response to be compressed in `auto` mode.
+const AUTO_COMPRESSION_MIN_NUM_HITS: u64 = 100;
+
+/// Returns whether the leaf search response should be compressed, which is only a hint: the leaf
+/// node decides in the end whether to compress it or not.
+fn accept_compressed_leaf_search_response(
+    leaf_search_response_compression: LeafSearchResponseCompression,
+    request: &LeafSearchRequest,
+) -> bool {
+    match leaf_search_response_compression {
+        LeafSearchResponseCompression::Off => false,
+        LeafSearchResponseCompression::Forced => true,
+        LeafSearchResponseCompression::Auto => {
+            let Some(search_request) = &request.search_request else {
+                return false;
+            };
+            search_request.aggregation_request.is_some()
+                || search_request.start_offset + search_request.max_hits
+                    >= AUTO_COMPRESSION_MIN_NUM_HITS
+        }
+    }
+}
+
 /// Creates a [`SearchServiceClient`] from a socket address.
 /// The underlying channel connects lazily and is set up to time out after 5 seconds. It reconnects
 /// automatically should the connection be dropped.
@@ -371,3 +420,49 @@ pub fn create_search_client_from_channel(
         .max_encoding_message_size(max_message_size.0 as usize);
     SearchServiceClient::from_grpc_client(client, grpc_addr)
 }
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::SearchRequest;
+
+    use super::*;
+
+    #[test]
+    fn test_accept_compressed_leaf_search_response() {
+        let leaf_search_request =
+            |max_hits: u64, aggregation_request: Option<&str>| LeafSearchRequest {
+                search_request: Some(SearchRequest {
+                    max_hits,
+                    aggregation_request: aggregation_request.map(ToString::to_string),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+        let small_request = leaf_search_request(10, None);
+        let many_hits_request = leaf_search_request(1_000, None);
+        let aggregation_request = leaf_search_request(0, Some(r#"{"avg": {}}"#));
+
+        for request in [&small_request, &many_hits_request, &aggregation_request] {
+            assert!(!accept_compressed_leaf_search_response(
+                LeafSearchResponseCompression::Off,
+                request
+            ));
+            assert!(accept_compressed_leaf_search_response(
+                LeafSearchResponseCompression::Forced,
+                request
+            ));
+        }
+        assert!(!accept_compressed_leaf_search_response(
+            LeafSearchResponseCompression::Auto,
+            &small_request
+        ));
+        assert!(accept_compressed_leaf_search_response(
+            LeafSearchResponseCompression::Auto,
+            &many_hits_request
+        ));
+        assert!(accept_compressed_leaf_search_response(
+            LeafSearchResponseCompression::Auto,
+            &aggregation_request
+        ));
+    }
+}
@@ -17,11 +17,13 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
 use std::time::Duration;
 
 use base64::Engine;
 use futures::future::ready;
 use futures::{Future, StreamExt};
+use quickwit_config::LeafSearchHedgingConfig;
 use quickwit_proto::search::{
     FetchDocsRequest, FetchDocsResponse, GetKvRequest, LeafListFieldsRequest, LeafListTermsRequest,
     LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest,
@@ -33,6 +35,7 @@ use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, warn};
 
+use crate::leaf_search_hedging::LeafSearchHedger;
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfulSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
@@ -53,12 +56,23 @@ const TARGET_NUM_REPLICATION: usize = 2;
 #[derive(Clone)]
 pub struct ClusterClient {
     pub(crate) search_job_placer: SearchJobPlacer,
+    leaf_search_hedger_opt: Option<Arc<LeafSearchHedger>>,
 }
 
 impl ClusterClient {
     /// Instantiates [`ClusterClient`].
     pub fn new(search_job_placer: SearchJobPlacer) -> Self {
-        Self { search_job_placer }
+        Self {
+            search_job_placer,
+            leaf_search_hedger_opt: None,
+        }
+    }
+
+    /// Hedges the leaf search requests that are slower than usual by sending them to another
+    /// searcher.
+    pub fn with_leaf_search_hedging(mut self, hedging_config: &LeafSearchHedgingConfig) -> Self {
+        self.leaf_search_hedger_opt = Some(Arc::new(LeafSearchHedger::new(hedging_config)));
+        self
     }
 
     /// Fetches docs with retry on another node client.
@@ -92,7 +106,13 @@ impl ClusterClient {
         request: LeafSearchRequest,
         mut client: SearchServiceClient,
     ) -> crate::Result<LeafSearchResponse> {
-        let mut response_res = client.leaf_search(request.clone()).await;
+        let mut response_res = if let Some(leaf_search_hedger) = &self.leaf_search_hedger_opt {
+            self.hedged_leaf_search(leaf_search_hedger, &request, &mut client)
+                .await
+        } else {
+            client.leaf_search(request.clone()).await
+        };
+        self.record_if_shedding(&response_res, &client);
         let retry_policy = LeafSearchRetryPolicy {};
         // We retry only once.
         let Some(retry_request) = retry_policy.retry_request(request, &response_res) else {
@@ -123,10 +143,61 @@ impl ClusterClient {
             response_res, retry_request, client
         );
         let retry_result = client.leaf_search(retry_request).await;
+        self.record_if_shedding(&retry_result, &client);
         response_res = merge_original_with_retry_leaf_search_results(response_res, retry_result);
         response_res
     }
 
+    /// Deprioritizes the searcher in the job placement if it rejected the request because it was
+    /// overloaded.
+    fn record_if_shedding<T>(&self, response_res: &crate::Result<T>, client: &SearchServiceClient) {
+        if let Err(SearchError::Unavailable(error_msg)) = response_res {
+            debug!(
+                "Searcher {} is unavailable: `{error_msg}`. Deprioritizing it",
+                client.grpc_addr()
+            );
+            self.search_job_placer
+                .record_shedding_node(client.grpc_addr());
+        }
+    }
+
+    async fn hedged_leaf_search(
+        &self,
+        leaf_search_hedger: &LeafSearchHedger,
+        request: &LeafSearchRequest,
+        client: &mut SearchServiceClient,
+    ) -> crate::Result<LeafSearchResponse> {
+        let primary_addr = client.grpc_addr();
+        let select_hedge = || async move {
+            let first_split = request
+                .leaf_requests
+                .iter()
+                .flat_map(|leaf_req| leaf_req.split_offsets.iter())
+                .next()?;
+            let mut hedge_client =
+                retry_client(&self.search_job_placer, primary_addr, &first_split.split_id)
+                    .await
+                    .ok()?;
+            // With a single searcher, the job placer hands back the same searcher.
+            if hedge_client.grpc_addr() == primary_addr {
+                return None;
+            }
+            debug!(
+                "Leaf search on {} is slower than usual. Hedging request with {:?}",
+                primary_addr, hedge_client
+            );
+            let hedge_request = request.clone();
+            Some(async move { hedge_client.leaf_search(hedge_request).await })
+        };
+        leaf_search_hedger
+            .run(
+                primary_addr,
+                client.leaf_search(request.clone()),
+                select_hedge,
+            )
+            .await
+    }
+
     /// Leaf search with retry on another node client.
     pub async fn leaf_list_fields(
         &self,
@@ -326,6 +397,8 @@ fn merge_original_with_retry_leaf_search_response(
         partial_hits: original_response.partial_hits,
         num_successful_splits: original_response.num_successful_splits
             + retry_response.num_successful_splits,
+        histogram_missing_doc_counts: Vec::new(),
+        cpu_time_micros: original_response.cpu_time_micros + retry_response.cpu_time_micros,
     })
 }
 
@@ -406,6 +479,7 @@ mod tests {
                 timestamp_start: None,
                 timestamp_end: None,
                 num_docs: 0,
+                delete_opstamp: 0,
             }],
             ..Default::default()
         }
@@ -433,6 +507,7 @@ mod tests {
                         timestamp_start: None,
                         timestamp_end: None,
                         num_docs: 0,
+                        delete_opstamp: 0,
                     },
                     SplitIdAndFooterOffsets {
                         split_id: "split_2".to_string(),
@@ -441,6 +516,7 @@ mod tests {
                         timestamp_start: None,
                         timestamp_end: None,
                         num_docs: 0,
+                        delete_opstamp: 0,
                     },
                 ],
             }],
@@ -470,6 +546,7 @@ mod tests {
                     timestamp_start: None,
                     timestamp_end: None,
                     num_docs: 0,
+                    delete_opstamp: 0,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
@@ -478,6 +555,7 @@ mod tests {
                     timestamp_start: None,
                     timestamp_end: None,
                     num_docs: 0,
+                    delete_opstamp: 0,
                 },
             ],
         }
@@ -597,6 +675,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_2".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -613,6 +692,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_3".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -630,12 +710,60 @@ mod tests {
         assert_eq!(result.unwrap().num_hits, 2);
     }
 
+    #[tokio::test]
+    async fn test_cluster_client_leaf_search_reroutes_shed_requests() {
+        let request = mock_leaf_search_request();
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1
+            .expect_leaf_search()
+            .return_once(|_: LeafSearchRequest| {
+                Err(SearchError::Unavailable(
+                    "searcher is overloaded".to_string(),
+                ))
+            });
+        let mut mock_search_service_2 = MockSearchService::new();
+        mock_search_service_2
+            .expect_leaf_search()
+            .return_once(|_: LeafSearchRequest| {
+                Ok(LeafSearchResponse {
+                    num_attempted_splits: 2,
+                    ..Default::default()
+                })
+            });
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", mock_search_service_1),
+            ("127.0.0.1:1002", mock_search_service_2),
+        ]);
+        let first_client_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let first_client = searcher_pool.get(&first_client_addr).unwrap();
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let leaf_search_response = cluster_client
+            .leaf_search(request, first_client)
+            .await
+            .unwrap();
+        assert_eq!(leaf_search_response.num_attempted_splits, 2);
+
+        // The overloaded searcher is no longer assigned jobs while the other one is not loaded.
+        let jobs: Vec<SearchJob> = (0..10)
+            .map(|id| SearchJob::for_test(&format!("split_{id}"), 1))
+            .collect();
+        let assigned_addrs: Vec<SocketAddr> = search_job_placer
+            .assign_jobs(jobs, &HashSet::new())
+            .await
+            .unwrap()
+            .map(|(client, _)| client.grpc_addr())
+            .collect();
+        assert_eq!(assigned_addrs, vec!["127.0.0.1:1002".parse().unwrap()]);
+    }
+
     #[test]
     fn test_merge_leaf_search_retry_on_partial_success() -> anyhow::Result<()> {
         let split_error = SplitSearchError {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            ..Default::default()
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
@@ -669,6 +797,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            ..Default::default()
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
@@ -25,22 +25,26 @@ use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::WarmupInfo;
 use quickwit_proto::search::{
-    LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder, SortValue,
-    SplitSearchError,
+    HistogramMissingDocCount, LeafSearchResponse, PartialHit, SearchRequest, SortByValue,
+    SortField, SortMode, SortOrder, SortValue, SplitSearchError,
 };
 use quickwit_proto::types::SplitId;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::aggregation::{AggregationLimitsGuard, AggregationSegmentCollector};
 use tantivy::collector::{Collector, SegmentCollector};
-use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::columnar::{Cardinality, ColumnType, MonotonicallyMappableToU64};
 use tantivy::fastfield::Column;
 use tantivy::{DateTime, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
+use crate::aggregation_sampling::{aggregation_sample_rate, DocSampler};
+use crate::aggregation_spill::AggregationSpill;
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector, Span};
+use crate::histogram_missing::{merge_histogram_missing_doc_counts, strip_histogram_missing};
+use crate::search_memory::{MemoryLimitExceeded, SearchMemoryBudget, SplitSearchMemoryBudget};
 use crate::top_k_collector::{specialized_top_k_segment_collector, QuickwitSegmentTopKCollector};
-use crate::GlobalDocAddress;
+use crate::{GlobalDocAddress, SearchError};
 
 #[derive(Clone, Debug)]
 pub(crate) enum SortByComponent {
@@ -50,6 +54,7 @@ pub(crate) enum SortByComponent {
     FastField {
         field_name: String,
         order: SortOrder,
+        mode: SortMode,
     },
     Score {
         order: SortOrder,
@@ -86,7 +91,9 @@ impl SortByComponent {
     ) -> tantivy::Result<SortingFieldExtractorComponent> {
         match self {
             SortByComponent::DocId { .. } => Ok(SortingFieldExtractorComponent::DocId),
-            SortByComponent::FastField { field_name, .. } => {
+            SortByComponent::FastField {
+                field_name, mode, ..
+            } => {
                 let sort_column_opt: Option<(Column<u64>, ColumnType)> =
                     segment_reader.fast_fields().u64_lenient(field_name)?;
                 let (sort_column, column_type) = sort_column_opt.unwrap_or_else(|| {
@@ -96,9 +103,12 @@ impl SortByComponent {
                     )
                 });
                 let sort_field_type = SortFieldType::try_from(column_type)?;
+                let sort_column_is_multivalued =
+                    sort_column.get_cardinality() == Cardinality::Multivalued;
                 Ok(SortingFieldExtractorComponent::FastField {
                     sort_column,
                     sort_field_type,
+                    sort_mode: sort_column_is_multivalued.then_some(*mode),
                 })
             }
             SortByComponent::Score { .. } => Ok(SortingFieldExtractorComponent::Score),
@@ -112,11 +122,7 @@ impl SortByComponent {
         }
     }
     pub fn add_fast_field(&self, set: &mut HashSet<String>) {
-        if let SortByComponent::FastField {
-            field_name,
-            order: _,
-        } = self
-        {
+        if let SortByComponent::FastField { field_name, .. } = self {
             set.insert(field_name.clone());
         }
     }
@@ -146,6 +152,9 @@ pub(crate) enum SortingFieldExtractorComponent {
     FastField {
         sort_column: Column<u64>,
         sort_field_type: SortFieldType,
+        /// Set if the column is multivalued in this segment: the value of a document is then
+        /// the min or the max of its values.
+        sort_mode: Option<SortMode>,
     },
     Score,
 }
@@ -162,9 +171,20 @@ impl SortingFieldExtractorComponent {
     #[inline]
     pub fn extract_typed_sort_values_block(&self, doc_ids: &[DocId], values: &mut [Option<u64>]) {
         // In the collect block case we don't have scores to extract
-        if let SortingFieldExtractorComponent::FastField { sort_column, .. } = self {
+        if let SortingFieldExtractorComponent::FastField {
+            sort_column,
+            sort_mode,
+            ..
+        } = self
+        {
             let values = &mut values[..doc_ids.len()];
-            sort_column.first_vals(doc_ids, values);
+            if let Some(sort_mode) = sort_mode {
+                for (doc_id, value) in doc_ids.iter().zip(values.iter_mut()) {
+                    *value = multivalued_sort_value(sort_column, *doc_id, *sort_mode);
+                }
+            } else {
+                sort_column.first_vals(doc_ids, values);
+            }
         }
     }
 
@@ -178,9 +198,16 @@ impl SortingFieldExtractorComponent {
         match self {
             // Tie breaks are not handled here, but in SegmentPartialHit
             SortingFieldExtractorComponent::DocId => None,
-            SortingFieldExtractorComponent::FastField { sort_column, .. } => {
-                sort_column.first(doc_id)
-            }
+            SortingFieldExtractorComponent::FastField {
+                sort_column,
+                sort_mode: None,
+                ..
+            } => sort_column.first(doc_id),
+            SortingFieldExtractorComponent::FastField {
+                sort_column,
+                sort_mode: Some(sort_mode),
+                ..
+            } => multivalued_sort_value(sort_column, doc_id, *sort_mode),
             SortingFieldExtractorComponent::Score { .. } => Some((score as f64).to_u64()),
         }
     }
@@ -368,14 +395,35 @@ impl SortingFieldExtractorComponent {
                 };
                 Some(val)
             }
+            // Scores are serialized as JSON numbers, so a search_after cursor holding an
+            // integral score is deserialized as an integer.
             SortingFieldExtractorComponent::Score => match sort_value {
                 SortValue::F64(val) => Some(val.to_u64()),
-                _ => panic!("Internal error: Got non-F64 sort value for Score."),
+                SortValue::U64(val) => Some((val as f64).to_u64()),
+                SortValue::I64(val) => Some((val as f64).to_u64()),
+                SortValue::Boolean(val) => Some((val as u8 as f64).to_u64()),
             },
         }
     }
 }
 
+/// Returns the min or the max of the values of a document in a multivalued column.
+///
+/// The u64 representation of fast field values preserves the ordering of the original values, so
+/// they can be compared directly.
+#[inline]
+fn multivalued_sort_value(
+    sort_column: &Column<u64>,
+    doc_id: DocId,
+    sort_mode: SortMode,
+) -> Option<u64> {
+    let values = sort_column.values_for_doc(doc_id);
+    match sort_mode {
+        SortMode::Min => values.min(),
+        SortMode::Max => values.max(),
+    }
+}
+
 impl From<SortingFieldExtractorComponent> for SortingFieldExtractorPair {
     fn from(value: SortingFieldExtractorComponent) -> Self {
         Self {
@@ -480,7 +528,12 @@ enum AggregationSegmentCollectors {
 pub struct QuickwitSegmentCollector {
     segment_top_k_collector: Option<Box<dyn QuickwitSegmentTopKCollector>>,
     aggregation: Option<AggregationSegmentCollectors>,
+    /// Selects the documents feeding the tantivy aggregations when they are sampled.
+    doc_sampler_opt: Option<DocSampler>,
+    sampled_docs: Vec<DocId>,
     num_hits: u64,
+    memory_budget: SplitSearchMemoryBudget,
+    top_k_num_bytes: u64,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -542,7 +595,13 @@ impl SegmentCollector for QuickwitSegmentCollector {
                 collector.collect_block(filtered_docs)
             }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
-                collector.collect_block(filtered_docs)
+                if let Some(doc_sampler) = &self.doc_sampler_opt {
+                    self.sampled_docs.clear();
+                    doc_sampler.sample_block(filtered_docs, &mut self.sampled_docs);
+                    collector.collect_block(&self.sampled_docs)
+                } else {
+                    collector.collect_block(filtered_docs)
+                }
             }
             None => (),
         }
@@ -560,7 +619,13 @@ impl SegmentCollector for QuickwitSegmentCollector {
                 collector.collect(doc_id, score)
             }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
-                collector.collect(doc_id, score)
+                let is_sampled = self
+                    .doc_sampler_opt
+                    .map(|doc_sampler| doc_sampler.is_sampled(doc_id))
+                    .unwrap_or(true);
+                if is_sampled {
+                    collector.collect(doc_id, score)
+                }
             }
             None => (),
         }
@@ -571,18 +636,16 @@ impl SegmentCollector for QuickwitSegmentCollector {
         if let Some(segment_top_k_collector) = self.segment_top_k_collector {
             partial_hits = segment_top_k_collector.get_top_k();
         }
+        self.memory_budget.release(self.top_k_num_bytes);
 
         let intermediate_aggregation_result = match self.aggregation {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
                 let fruit: Vec<Span> = collector.harvest();
-                let serialized =
-                    postcard::to_allocvec(&fruit).expect("Collector fruit should be serializable.");
-                Some(serialized)
+                Some(serialize_charged_fruit(&fruit, &self.memory_budget)?)
             }
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
-                let serialized = postcard::to_allocvec(&collector.harvest()?)
-                    .expect("Collector fruit should be serializable.");
-                Some(serialized)
+                let fruit = collector.harvest()?;
+                Some(serialize_charged_fruit(&fruit, &self.memory_budget)?)
             }
             None => None,
         };
@@ -593,6 +656,8 @@ impl SegmentCollector for QuickwitSegmentCollector {
             failed_splits: Vec::new(),
             num_attempted_splits: 1,
             num_successful_splits: 1,
+            histogram_missing_doc_counts: Vec::new(),
+            cpu_time_micros: 0,
         })
     }
 }
@@ -627,7 +692,11 @@ impl QuickwitAggregations {
                 QuickwitIncrementalAggregations::FindTraceIdsAggregation(aggreg.clone(), Vec::new())
             }
             QuickwitAggregations::TantivyAggregations(aggreg) => {
-                QuickwitIncrementalAggregations::TantivyAggregations(aggreg.clone(), Vec::new())
+                QuickwitIncrementalAggregations::TantivyAggregations(
+                    aggreg.clone(),
+                    Vec::new(),
+                    None,
+                )
             }
         }
     }
@@ -636,7 +705,7 @@ impl QuickwitAggregations {
 #[derive(Clone)]
 enum QuickwitIncrementalAggregations {
     FindTraceIdsAggregation(FindTraceIdsCollector, Vec<Vec<Span>>),
-    TantivyAggregations(Aggregations, Vec<Vec<u8>>),
+    TantivyAggregations(Aggregations, Vec<Vec<u8>>, Option<AggregationSpill>),
     NoAggregation,
 }
 
@@ -652,7 +721,7 @@ impl QuickwitIncrementalAggregations {
                     state.push(new_state);
                 }
             }
-            QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
+            QuickwitIncrementalAggregations::TantivyAggregations(_, state, _) => {
                 state.push(intermediate_result);
             }
             QuickwitIncrementalAggregations::NoAggregation => (),
@@ -660,6 +729,34 @@ impl QuickwitIncrementalAggregations {
         Ok(())
     }
 
+    /// Merges the buffered intermediate aggregation results into a single run once they exceed
+    /// the in-memory threshold of the spill, and returns it along with the spill to write it to.
+    fn take_run_to_spill(&mut self) -> tantivy::Result<Option<(AggregationSpill, Vec<u8>)>> {
+        let QuickwitIncrementalAggregations::TantivyAggregations(_, state, Some(spill)) = self
+        else {
+            return Ok(None);
+        };
+        let num_buffered_bytes: usize = state.iter().map(Vec::len).sum();
+        if num_buffered_bytes as u64 <= spill.in_memory_threshold() {
+            return Ok(None);
+        }
+        // The buffered results are only dropped once they are merged.
+        let Some(merged_run) =
+            merge_tantivy_intermediate_aggregation_results(state.iter().map(Ok))?
+        else {
+            return Ok(None);
+        };
+        state.clear();
+        Ok(Some((spill.clone(), merged_run)))
+    }
+
+    /// Buffers back in memory a run that could not be spilled.
+    fn restore_unspilled_run(&mut self, run: Vec<u8>) {
+        if let QuickwitIncrementalAggregations::TantivyAggregations(_, state, _) = self {
+            state.push(run);
+        }
+    }
+
     fn virtual_worst_hit(&self) -> Option<PartialHit> {
         match self {
             QuickwitIncrementalAggregations::FindTraceIdsAggregation(collector, state) => {
@@ -681,7 +778,7 @@ impl QuickwitIncrementalAggregations {
                 }
                 None
             }
-            QuickwitIncrementalAggregations::TantivyAggregations(_, _) => None,
+            QuickwitIncrementalAggregations::TantivyAggregations(..) => None,
             QuickwitIncrementalAggregations::NoAggregation => None,
         }
     }
@@ -697,12 +794,20 @@ impl QuickwitIncrementalAggregations {
                 let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
                 Ok(Some(serialized))
             }
-            QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state) => {
+            QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state, None) => {
                 merge_intermediate_aggregation_result(
                     &Some(QuickwitAggregations::TantivyAggregations(aggregation)),
                     state.iter().map(|vec| vec.as_slice()),
                 )
             }
+            QuickwitIncrementalAggregations::TantivyAggregations(_, state, Some(spill)) => {
+                // The spilled runs are read back one at a time.
+                let spilled_runs = (0..spill.num_spilled_runs())
+                    .map(|run_ord| spill.read_spilled_run(run_ord).map_err(TantivyError::from));
+                merge_tantivy_intermediate_aggregation_results(
+                    spilled_runs.chain(state.into_iter().map(Ok)),
+                )
+            }
             QuickwitIncrementalAggregations::NoAggregation => Ok(None),
         }
     }
@@ -720,6 +825,9 @@ pub(crate) struct QuickwitCollector {
     pub sort_by: SortByPair,
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimitsGuard,
+    /// Rate at which the documents feeding the tantivy aggregations are sampled, if any.
+    pub aggregation_sample_rate: Option<f64>,
+    pub memory_budget: SplitSearchMemoryBudget,
     search_after: Option<PartialHit>,
 }
 
@@ -727,14 +835,22 @@ impl QuickwitCollector {
     pub fn is_count_only(&self) -> bool {
         self.max_hits == 0 && self.aggregation.is_none()
     }
+
+    /// Returns true if the collector only computes aggregations, in which case no hits are
+    /// collected.
+    pub fn is_aggregation_only(&self) -> bool {
+        self.max_hits + self.start_offset == 0 && self.aggregation.is_some()
+    }
+
     /// Updates search parameters affecting the returned documents.
     /// Does not update aggregations.
-    pub fn update_search_param(&mut self, search_request: &SearchRequest) {
-        let sort_by = sort_by_from_request(search_request);
+    pub fn update_search_param(&mut self, search_request: &SearchRequest) -> crate::Result<()> {
+        let sort_by = sort_by_from_request(search_request)?;
         self.sort_by = sort_by;
         self.max_hits = search_request.max_hits as usize;
         self.start_offset = search_request.start_offset as usize;
         self.search_after.clone_from(&search_request.search_after);
+        Ok(())
     }
     pub fn fast_field_names(&self) -> HashSet<String> {
         let mut fast_field_names = HashSet::default();
@@ -788,12 +904,21 @@ impl Collector for QuickwitCollector {
             ),
             None => None,
         };
-        let score_extractor = get_score_extractor(&self.sort_by, segment_reader)?;
-        let (order1, order2) = self.sort_by.sort_orders();
-
+        let doc_sampler_opt = self
+            .aggregation_sample_rate
+            .map(|sample_rate| DocSampler::new(sample_rate, &self.split_id, segment_ord));
+        // When no hit is requested, we skip the top-K collector altogether, including opening
+        // the sort columns.
+        let mut top_k_num_bytes = 0;
         let segment_top_k_collector = if leaf_max_hits == 0 {
             None
         } else {
+            top_k_num_bytes = top_k_segment_collector_num_bytes(leaf_max_hits);
+            self.memory_budget
+                .try_charge(top_k_num_bytes)
+                .map_err(memory_limit_exceeded_error)?;
+            let score_extractor = get_score_extractor(&self.sort_by, segment_reader)?;
+            let (order1, order2) = self.sort_by.sort_orders();
             let coll: Box<dyn QuickwitSegmentTopKCollector> = specialized_top_k_segment_collector(
                 self.split_id.clone(),
                 score_extractor,
@@ -810,6 +935,10 @@ impl Collector for QuickwitCollector {
             num_hits: 0,
             segment_top_k_collector,
             aggregation,
+            doc_sampler_opt,
+            sampled_docs: Vec::new(),
+            memory_budget: self.memory_budget.clone(),
+            top_k_num_bytes,
         })
     }
 
@@ -861,6 +990,46 @@ fn map_error(err: postcard::Error) -> TantivyError {
     TantivyError::InternalError(format!("merge result Postcard error: {err}"))
 }
 
+/// Collectors can only abort the search with a tantivy error. The leaf recovers the cause of the
+/// failure from the memory budget of the split search.
+fn memory_limit_exceeded_error(limit_exceeded: MemoryLimitExceeded) -> TantivyError {
+    TantivyError::InternalError(limit_exceeded.to_string())
+}
+
+/// Counts the bytes of a serialization without storing them.
+#[derive(Default)]
+struct ByteCounter(u64);
+
+impl Extend<u8> for ByteCounter {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, bytes: I) {
+        self.0 += bytes.into_iter().count() as u64;
+    }
+}
+
+/// Serializes the fruit of an aggregation segment collector. The intermediate aggregation results
+/// of the splits are kept around until the leaf request merges them, so the serialized fruit is
+/// charged to the memory budget for the rest of the request, before its buffer is allocated.
+fn serialize_charged_fruit<T: Serialize>(
+    fruit: &T,
+    memory_budget: &SplitSearchMemoryBudget,
+) -> tantivy::Result<Vec<u8>> {
+    let num_bytes = postcard::to_extend(fruit, ByteCounter::default())
+        .expect("Collector fruit should be serializable.")
+        .0;
+    memory_budget
+        .try_charge(num_bytes)
+        .map_err(memory_limit_exceeded_error)?;
+    let serialized = postcard::to_extend(fruit, Vec::with_capacity(num_bytes as usize))
+        .expect("Collector fruit should be serializable.");
+    Ok(serialized)
+}
+
+/// Upper bound of the memory allocated by the top-K segment collector, whose buffer holds up to
+/// ten times the number of hits to collect.
+fn top_k_segment_collector_num_bytes(leaf_max_hits: usize) -> u64 {
+    (leaf_max_hits.max(1) * 10 * std::mem::size_of::<SegmentPartialHit>()) as u64
+}
+
 /// Merges a set of Leaf Results.
 fn merge_intermediate_aggregation_result<'a>(
     aggregations_opt: &Option<QuickwitAggregations>,
@@ -905,6 +1074,25 @@ fn merge_intermediate_aggregation_result<'a>(
     Ok(merged_intermediate_aggregation_result)
 }
 
+/// Merges serialized tantivy intermediate aggregation results, deserializing them one at a time
+/// so that at most one of them is held in memory next to the merged result.
+fn merge_tantivy_intermediate_aggregation_results(
+    intermediate_aggregation_results: impl Iterator<Item = tantivy::Result<impl AsRef<[u8]>>>,
+) -> tantivy::Result<Option<Vec<u8>>> {
+    let mut merged_fruit_opt: Option<IntermediateAggregationResults> = None;
+    for intermediate_aggregation_result in intermediate_aggregation_results {
+        let fruit: IntermediateAggregationResults =
+            postcard::from_bytes(intermediate_aggregation_result?.as_ref()).map_err(map_error)?;
+        match &mut merged_fruit_opt {
+            Some(merged_fruit) => merged_fruit.merge_fruits(fruit)?,
+            None => merged_fruit_opt = Some(fruit),
+        }
+    }
+    merged_fruit_opt
+        .map(|merged_fruit| postcard::to_allocvec(&merged_fruit).map_err(map_error))
+        .transpose()
+}
+
 /// Merges a set of Leaf Results.
 fn merge_leaf_responses(
     aggregations_opt: &Option<QuickwitAggregations>,
@@ -937,6 +1125,15 @@ fn merge_leaf_responses(
         .iter()
         .map(|leaf_response| leaf_response.num_hits)
         .sum();
+    let histogram_missing_doc_counts = merge_histogram_missing_doc_counts(
+        leaf_responses
+            .iter()
+            .flat_map(|leaf_response| &leaf_response.histogram_missing_doc_counts),
+    );
+    let cpu_time_micros: u64 = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.cpu_time_micros)
+        .sum();
     let failed_splits = leaf_responses
         .iter()
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
@@ -959,6 +1156,8 @@ fn merge_leaf_responses(
         failed_splits,
         num_attempted_splits,
         num_successful_splits,
+        histogram_missing_doc_counts,
+        cpu_time_micros,
     })
 }
 
@@ -980,22 +1179,33 @@ fn top_k_partial_hits(
     top_k_hits.finalize()
 }
 
-pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair {
-    let to_sort_by_component = |field_name: &str, order| {
+pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> crate::Result<SortByPair> {
+    let to_sort_by_component = |sort_field: &SortField, order| {
+        let field_name = sort_field.field_name.as_str();
         if field_name == "_score" {
             SortByComponent::Score { order }
         } else if field_name == "_shard_doc" || field_name == "_doc" {
             SortByComponent::DocId { order }
         } else {
+            // Like Elasticsearch, multivalued fields are sorted by their smallest value in
+            // ascending order and by their largest value in descending order by default.
+            let mode = sort_field
+                .sort_mode
+                .and_then(SortMode::from_i32)
+                .unwrap_or(match order {
+                    SortOrder::Asc => SortMode::Min,
+                    SortOrder::Desc => SortMode::Max,
+                });
             SortByComponent::FastField {
                 field_name: field_name.to_string(),
                 order,
+                mode,
             }
         }
     };
 
     let num_sort_fields = search_request.sort_fields.len();
-    if num_sort_fields == 0 {
+    let sort_by = if num_sort_fields == 0 {
         SortByComponent::DocId {
             order: SortOrder::Desc,
         }
@@ -1003,19 +1213,22 @@ pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair
     } else if num_sort_fields == 1 {
         let sort_field = &search_request.sort_fields[0];
         let order = SortOrder::from_i32(sort_field.sort_order).unwrap_or(SortOrder::Desc);
-        to_sort_by_component(&sort_field.field_name, order).into()
+        to_sort_by_component(sort_field, order).into()
     } else if num_sort_fields == 2 {
         let sort_field1 = &search_request.sort_fields[0];
         let order1 = SortOrder::from_i32(sort_field1.sort_order).unwrap_or(SortOrder::Desc);
         let sort_field2 = &search_request.sort_fields[1];
         let order2 = SortOrder::from_i32(sort_field2.sort_order).unwrap_or(SortOrder::Desc);
         SortByPair {
-            first: to_sort_by_component(&sort_field1.field_name, order1),
-            second: Some(to_sort_by_component(&sort_field2.field_name, order2)),
+            first: to_sort_by_component(sort_field1, order1),
+            second: Some(to_sort_by_component(sort_field2, order2)),
         }
     } else {
-        panic!("Sort by more than 2 fields is not supported yet.")
-    }
+        return Err(SearchError::InvalidArgument(format!(
+            "sort by more than 2 fields is not supported, got {num_sort_fields}"
+        )));
+    };
+    Ok(sort_by)
 }
 
 /// Builds the QuickwitCollector, in function of the information that was requested by the user.
@@ -1023,12 +1236,13 @@ pub(crate) fn make_collector_for_split(
     split_id: SplitId,
     search_request: &SearchRequest,
     aggregation_limits: AggregationLimitsGuard,
+    memory_budget: SearchMemoryBudget,
 ) -> crate::Result<QuickwitCollector> {
     let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
+        Some(aggregation) => Some(serde_json::from_str(&strip_histogram_missing(aggregation))?),
         None => None,
     };
-    let sort_by = sort_by_from_request(search_request);
+    let sort_by = sort_by_from_request(search_request)?;
     Ok(QuickwitCollector {
         split_id,
         start_offset: search_request.start_offset as usize,
@@ -1036,6 +1250,8 @@ pub(crate) fn make_collector_for_split(
         sort_by,
         aggregation,
         aggregation_limits,
+        aggregation_sample_rate: aggregation_sample_rate(search_request),
+        memory_budget: memory_budget.for_split_search(),
         search_after: search_request.search_after.clone(),
     })
 }
@@ -1046,10 +1262,10 @@ pub(crate) fn make_merge_collector(
     aggregation_limits: &AggregationLimitsGuard,
 ) -> crate::Result<QuickwitCollector> {
     let aggregation = match &search_request.aggregation_request {
-        Some(aggregation) => Some(serde_json::from_str(aggregation)?),
+        Some(aggregation) => Some(serde_json::from_str(&strip_histogram_missing(aggregation))?),
         None => None,
     };
-    let sort_by = sort_by_from_request(search_request);
+    let sort_by = sort_by_from_request(search_request)?;
     Ok(QuickwitCollector {
         split_id: SplitId::default(),
         start_offset: search_request.start_offset as usize,
@@ -1057,6 +1273,8 @@ pub(crate) fn make_merge_collector(
         sort_by,
         aggregation,
         aggregation_limits: aggregation_limits.clone(),
+        aggregation_sample_rate: None,
+        memory_budget: SplitSearchMemoryBudget::default(),
         search_after: search_request.search_after.clone(),
     })
 }
@@ -1099,6 +1317,13 @@ impl PartialOrd for SegmentPartialHitSortingKey {
     }
 }
 
+/// Sorting key used to merge partial hits coming from different splits.
+///
+/// Hits are ordered by their sort values first. Ties are broken by the global address of the
+/// document, i.e. `(split_id, segment_ord, doc_id)`, using the order of the first sort field.
+/// This makes the merge independent of the order in which leaf responses are received, and it is
+/// the same tie-breaker as the one applied to `search_after` in the segment collectors, so
+/// paginating with the last hit of a page as cursor never skips or duplicates documents.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct PartialHitSortingKey {
     sort_value: Option<SortValue>,
@@ -1181,6 +1406,8 @@ pub(crate) struct IncrementalCollector {
     failed_splits: Vec<SplitSearchError>,
     num_attempted_splits: u64,
     num_successful_splits: u64,
+    histogram_missing_doc_counts: Vec<HistogramMissingDocCount>,
+    cpu_time_micros: u64,
     start_offset: usize,
 }
 
@@ -1202,6 +1429,8 @@ impl IncrementalCollector {
             failed_splits: Vec::new(),
             num_attempted_splits: 0,
             num_successful_splits: 0,
+            histogram_missing_doc_counts: Vec::new(),
+            cpu_time_micros: 0,
         }
     }
 
@@ -1214,6 +1443,8 @@ impl IncrementalCollector {
             num_attempted_splits,
             intermediate_aggregation_result,
             num_successful_splits,
+            histogram_missing_doc_counts,
+            cpu_time_micros,
         } = leaf_response;
 
         self.num_hits += num_hits;
@@ -1221,6 +1452,14 @@ impl IncrementalCollector {
         self.failed_splits.extend(failed_splits);
         self.num_attempted_splits += num_attempted_splits;
         self.num_successful_splits += num_successful_splits;
+        self.cpu_time_micros += cpu_time_micros;
+        if !histogram_missing_doc_counts.is_empty() {
+            self.histogram_missing_doc_counts = merge_histogram_missing_doc_counts(
+                self.histogram_missing_doc_counts
+                    .iter()
+                    .chain(&histogram_missing_doc_counts),
+            );
+        }
         if let Some(intermediate_aggregation_result) = intermediate_aggregation_result {
             self.incremental_aggregation
                 .add(intermediate_aggregation_result)?;
@@ -1228,6 +1467,37 @@ impl IncrementalCollector {
         Ok(())
     }
 
+    /// Spills the intermediate aggregation results to disk once they exceed the in-memory
+    /// threshold of the spill. Only tantivy aggregations can be spilled.
+    pub(crate) fn with_aggregation_spill(mut self, aggregation_spill: AggregationSpill) -> Self {
+        if let QuickwitIncrementalAggregations::TantivyAggregations(_, _, spill_opt) =
+            &mut self.incremental_aggregation
+        {
+            *spill_opt = Some(aggregation_spill);
+        }
+        self
+    }
+
+    /// Once the buffered intermediate aggregation results exceed the in-memory threshold of the
+    /// spill, merges them into a single run and takes it out of the collector, so that it can be
+    /// written to disk without holding the collector. The run must be handed back with
+    /// [`Self::restore_unspilled_aggregation_run`] if it could not be spilled.
+    pub(crate) fn take_aggregation_run_to_spill(
+        &mut self,
+    ) -> tantivy::Result<Option<(AggregationSpill, Vec<u8>)>> {
+        self.incremental_aggregation.take_run_to_spill()
+    }
+
+    /// Buffers back in memory an aggregation run that could not be spilled.
+    pub(crate) fn restore_unspilled_aggregation_run(&mut self, run: Vec<u8>) {
+        self.incremental_aggregation.restore_unspilled_run(run)
+    }
+
+    /// Number of hits recorded so far.
+    pub(crate) fn num_hits(&self) -> u64 {
+        self.num_hits
+    }
+
     /// Add a failed split to the state
     pub(crate) fn add_failed_split(&mut self, split_error: SplitSearchError) {
         self.failed_splits.push(split_error)
@@ -1265,6 +1535,8 @@ impl IncrementalCollector {
             num_attempted_splits: self.num_attempted_splits,
             num_successful_splits: self.num_successful_splits,
             intermediate_aggregation_result,
+            histogram_missing_doc_counts: self.histogram_missing_doc_counts,
+            cpu_time_micros: self.cpu_time_micros,
         })
     }
 }
@@ -1273,11 +1545,13 @@ impl IncrementalCollector {
 mod tests {
     use std::cmp::Ordering;
 
+    use proptest::prelude::*;
     use quickwit_proto::search::{
-        LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField, SortOrder,
+        LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField, SortMode, SortOrder,
         SortValue, SplitSearchError,
     };
     use quickwit_proto::types::DocMappingUid;
+    use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
     use tantivy::collector::Collector;
     use tantivy::TantivyDocument;
 
@@ -1435,12 +1709,14 @@ mod tests {
                             field_name: field.to_string(),
                             sort_order: SortOrder::Asc.into(),
                             sort_datetime_format: None,
+                            sort_mode: None,
                         }
                     } else {
                         SortField {
                             field_name: field.to_string(),
                             sort_order: SortOrder::Desc.into(),
                             sort_datetime_format: None,
+                            sort_mode: None,
                         }
                     }
                 })
@@ -1608,6 +1884,7 @@ mod tests {
                     "fake_split_id".to_string(),
                     &make_request(slice_len as u64, sort_str),
                     Default::default(),
+                    Default::default(),
                 )
                 .unwrap();
                 let res = searcher
@@ -1647,6 +1924,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multivalued_sort_modes() {
+        use tantivy::schema::{NumericOptions, Schema};
+        use tantivy::Index;
+
+        let mut schema_builder = Schema::builder();
+        let values_field =
+            schema_builder.add_u64_field("values", NumericOptions::default().set_fast());
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 20_000_000).unwrap();
+        for values in [&[1, 10][..], &[5], &[3, 7], &[]] {
+            let mut doc = TantivyDocument::new();
+            for value in values {
+                doc.add_u64(values_field, *value);
+            }
+            index_writer.add_document(doc).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let sort_doc_ids = |sort_order: SortOrder, sort_mode: Option<SortMode>| {
+            let search_request = SearchRequest {
+                max_hits: 10,
+                sort_fields: vec![SortField {
+                    field_name: "values".to_string(),
+                    sort_order: sort_order as i32,
+                    sort_datetime_format: None,
+                    sort_mode: sort_mode.map(|sort_mode| sort_mode as i32),
+                }],
+                ..SearchRequest::default()
+            };
+            let collector = super::make_collector_for_split(
+                "fake_split_id".to_string(),
+                &search_request,
+                Default::default(),
+                Default::default(),
+            )
+            .unwrap();
+            searcher
+                .search(&tantivy::query::AllQuery, &collector)
+                .unwrap()
+                .partial_hits
+                .into_iter()
+                .map(|partial_hit| {
+                    let sort_value = partial_hit.sort_value.and_then(|value| value.sort_value);
+                    (partial_hit.doc_id, sort_value)
+                })
+                .collect::<Vec<_>>()
+        };
+        let u64_value = |value: u64| Some(SortValue::U64(value));
+
+        // Ascending sorts use the min value by default.
+        let expected_min_asc = vec![
+            (0, u64_value(1)),
+            (2, u64_value(3)),
+            (1, u64_value(5)),
+            (3, None),
+        ];
+        assert_eq!(sort_doc_ids(SortOrder::Asc, None), expected_min_asc);
+        assert_eq!(
+            sort_doc_ids(SortOrder::Asc, Some(SortMode::Min)),
+            expected_min_asc
+        );
+        assert_eq!(
+            sort_doc_ids(SortOrder::Asc, Some(SortMode::Max)),
+            vec![
+                (1, u64_value(5)),
+                (2, u64_value(7)),
+                (0, u64_value(10)),
+                (3, None),
+            ]
+        );
+        // Descending sorts use the max value by default.
+        let expected_max_desc = vec![
+            (0, u64_value(10)),
+            (2, u64_value(7)),
+            (1, u64_value(5)),
+            (3, None),
+        ];
+        assert_eq!(sort_doc_ids(SortOrder::Desc, None), expected_max_desc);
+        assert_eq!(
+            sort_doc_ids(SortOrder::Desc, Some(SortMode::Max)),
+            expected_max_desc
+        );
+        assert_eq!(
+            sort_doc_ids(SortOrder::Desc, Some(SortMode::Min)),
+            vec![
+                (1, u64_value(5)),
+                (2, u64_value(3)),
+                (0, u64_value(1)),
+                (3, None),
+            ]
+        );
+    }
+
     #[test]
     fn test_search_after() {
         let index = make_index();
@@ -1690,11 +2063,13 @@ mod tests {
                         field_name: "sort1".to_string(),
                         sort_order: SortOrder::Desc.into(),
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                     SortField {
                         field_name: "sort2".to_string(),
                         sort_order: SortOrder::Asc.into(),
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                 ],
                 search_after: Some(search_after),
@@ -1704,6 +2079,7 @@ mod tests {
                 "fake_split_id".to_string(),
                 &request,
                 Default::default(),
+                Default::default(),
             )
             .unwrap();
             let res = searcher
@@ -1733,6 +2109,7 @@ mod tests {
                     field_name: "_shard_doc".to_string(),
                     sort_order: SortOrder::Desc.into(),
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
                 search_after: Some(search_after),
                 ..SearchRequest::default()
@@ -1742,6 +2119,7 @@ mod tests {
                 "fake_split_id1".to_string(),
                 &request,
                 Default::default(),
+                Default::default(),
             )
             .unwrap();
             let res = searcher
@@ -1756,6 +2134,7 @@ mod tests {
                 "fake_split_id2".to_string(),
                 &request,
                 Default::default(),
+                Default::default(),
             )
             .unwrap();
             let res = searcher
@@ -1769,6 +2148,7 @@ mod tests {
                 "fake_split_id3".to_string(),
                 &request,
                 Default::default(),
+                Default::default(),
             )
             .unwrap();
             let res = searcher
@@ -1781,6 +2161,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sort_by_more_than_two_fields_is_rejected() {
+        let sort_field = |field_name: &str| SortField {
+            field_name: field_name.to_string(),
+            sort_order: SortOrder::Desc.into(),
+            sort_datetime_format: None,
+            sort_mode: None,
+        };
+        let request = SearchRequest {
+            max_hits: 10,
+            sort_fields: vec![
+                sort_field("_score"),
+                sort_field("sort1"),
+                sort_field("sort2"),
+            ],
+            ..SearchRequest::default()
+        };
+        let error = super::make_collector_for_split(
+            "fake_split_id".to_string(),
+            &request,
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: sort by more than 2 fields is not supported, got 3"
+        );
+    }
+
+    #[test]
+    fn test_score_search_after_accepts_integral_scores() {
+        let extractor = super::SortingFieldExtractorComponent::Score;
+        assert_eq!(
+            extractor.convert_to_u64_ff_val(SortValue::U64(2), SortOrder::Desc),
+            extractor.convert_to_u64_ff_val(SortValue::F64(2.0), SortOrder::Desc),
+        );
+        assert_eq!(
+            extractor.convert_to_u64_ff_val(SortValue::I64(-3), SortOrder::Desc),
+            extractor.convert_to_u64_ff_val(SortValue::F64(-3.0), SortOrder::Desc),
+        );
+    }
+
     fn merge_collector_equal_results(
         request: &SearchRequest,
         results: Vec<LeafSearchResponse>,
@@ -1811,6 +2234,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1828,6 +2252,8 @@ mod tests {
                 num_attempted_splits: 3,
                 num_successful_splits: 3,
                 intermediate_aggregation_result: None,
+                histogram_missing_doc_counts: Vec::new(),
+                cpu_time_micros: 0,
             }],
         );
 
@@ -1845,7 +2271,9 @@ mod tests {
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
                 num_successful_splits: 3,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                histogram_missing_doc_counts: Vec::new(),
+                cpu_time_micros: 0,
             }
         );
 
@@ -1857,6 +2285,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1884,6 +2313,8 @@ mod tests {
                     num_attempted_splits: 3,
                     num_successful_splits: 3,
                     intermediate_aggregation_result: None,
+                    histogram_missing_doc_counts: Vec::new(),
+                    cpu_time_micros: 0,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1898,10 +2329,13 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 2,
                     num_successful_splits: 1,
                     intermediate_aggregation_result: None,
+                    histogram_missing_doc_counts: Vec::new(),
+                    cpu_time_micros: 0,
                 },
             ],
         );
@@ -1930,10 +2364,13 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    ..Default::default()
                 }],
                 num_attempted_splits: 5,
                 num_successful_splits: 4,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                histogram_missing_doc_counts: Vec::new(),
+                cpu_time_micros: 0,
             }
         );
 
@@ -1946,6 +2383,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1973,6 +2411,8 @@ mod tests {
                     num_attempted_splits: 3,
                     num_successful_splits: 3,
                     intermediate_aggregation_result: None,
+                    histogram_missing_doc_counts: Vec::new(),
+                    cpu_time_micros: 0,
                 },
                 LeafSearchResponse {
                     num_hits: 10,
@@ -1987,10 +2427,13 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 2,
                     num_successful_splits: 1,
                     intermediate_aggregation_result: None,
+                    histogram_missing_doc_counts: Vec::new(),
+                    cpu_time_micros: 0,
                 },
             ],
         );
@@ -2019,12 +2462,201 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    ..Default::default()
                 }],
                 num_attempted_splits: 5,
                 num_successful_splits: 4,
-                intermediate_aggregation_result: None
+                intermediate_aggregation_result: None,
+                histogram_missing_doc_counts: Vec::new(),
+                cpu_time_micros: 0,
             }
         );
         // TODO would be nice to test aggregation too.
     }
+
+    fn make_timestamp_split_index(timestamps: &[u64]) -> tantivy::Index {
+        use tantivy::schema::{NumericOptions, Schema};
+
+        let mut schema_builder = Schema::builder();
+        let timestamp_field =
+            schema_builder.add_u64_field("timestamp", NumericOptions::default().set_fast());
+        let index = tantivy::Index::create_in_ram(schema_builder.build());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for &timestamp in timestamps {
+            let mut doc = TantivyDocument::new();
+            doc.add_u64(timestamp_field, timestamp);
+            index_writer.add_document(doc).unwrap();
+        }
+        index_writer.commit().unwrap();
+        index
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+        #[test]
+        fn test_proptest_paginate_with_timestamp_collisions(
+            // Timestamps are drawn from a tiny range to get a lot of collisions within and across
+            // splits.
+            splits in proptest::collection::vec(proptest::collection::vec(0u64..3, 1..16), 1..5),
+            page_size in 1u64..5,
+            arrival_orders in proptest::collection::vec(
+                Just((0..4).collect::<Vec<usize>>()).prop_shuffle(),
+                1..8,
+            ),
+            sort_order in prop_oneof![Just(SortOrder::Asc), Just(SortOrder::Desc)],
+        ) {
+            let searchers: Vec<(String, tantivy::Searcher)> = splits
+                .iter()
+                .enumerate()
+                .map(|(split_ord, timestamps)| {
+                    let index = make_timestamp_split_index(timestamps);
+                    (format!("split_{split_ord}"), index.reader().unwrap().searcher())
+                })
+                .collect();
+            let num_docs: usize = splits.iter().map(Vec::len).sum();
+
+            let mut search_after: Option<PartialHit> = None;
+            let mut paginated_hits: Vec<PartialHit> = Vec::new();
+
+            for page in 0.. {
+                prop_assert!(page <= num_docs, "pagination did not terminate");
+                let request = SearchRequest {
+                    max_hits: page_size,
+                    sort_fields: vec![SortField {
+                        field_name: "timestamp".to_string(),
+                        sort_order: sort_order.into(),
+                        sort_datetime_format: None,
+                        sort_mode: None,
+                    }],
+                    search_after: search_after.clone(),
+                    ..SearchRequest::default()
+                };
+                // Leaf responses are merged in a different order on every page.
+                let leaf_responses: Vec<LeafSearchResponse> = arrival_orders
+                    [page % arrival_orders.len()]
+                    .iter()
+                    .filter_map(|split_ord| searchers.get(*split_ord))
+                    .map(|(split_id, searcher)| {
+                        let collector = super::make_collector_for_split(
+                            split_id.clone(),
+                            &request,
+                            Default::default(),
+                            Default::default(),
+                        )
+                        .unwrap();
+                        searcher
+                            .search(&tantivy::query::AllQuery, &collector)
+                            .unwrap()
+                    })
+                    .collect();
+                let page_hits = merge_collector_equal_results(&request, leaf_responses).partial_hits;
+                let Some(last_hit) = page_hits.last() else {
+                    break;
+                };
+                search_after = Some(last_hit.clone());
+                paginated_hits.extend(page_hits);
+            }
+
+            // Every document appears exactly once, ordered by timestamp, then split ID, then
+            // doc ID.
+            let mut expected_docs: Vec<(u64, String, u32)> = splits
+                .iter()
+                .enumerate()
+                .flat_map(|(split_ord, timestamps)| {
+                    timestamps.iter().enumerate().map(move |(doc_id, timestamp)| {
+                        (*timestamp, format!("split_{split_ord}"), doc_id as u32)
+                    })
+                })
+                .collect();
+            expected_docs.sort();
+            if sort_order == SortOrder::Desc {
+                expected_docs.reverse();
+            }
+            let paginated_docs: Vec<(u64, String, u32)> = paginated_hits
+                .into_iter()
+                .map(|hit| {
+                    let Some(SortValue::U64(timestamp)) =
+                        hit.sort_value.and_then(|sort_value| sort_value.sort_value)
+                    else {
+                        panic!("expected a u64 sort value");
+                    };
+                    (timestamp, hit.split_id, hit.doc_id)
+                })
+                .collect();
+            prop_assert_eq!(paginated_docs, expected_docs);
+        }
+
+        #[test]
+        fn test_proptest_aggregation_only_matches_hits_collection(
+            timestamps in proptest::collection::vec(0u64..1_000, 1..128),
+            max_hits in 1u64..10,
+            sort_order in prop_oneof![Just(SortOrder::Asc), Just(SortOrder::Desc)],
+        ) {
+            let index = make_timestamp_split_index(&timestamps);
+            let searcher = index.reader().unwrap().searcher();
+            let aggregation_request = r#"{
+                "timestamp_histogram": {
+                    "histogram": {"field": "timestamp", "interval": 100},
+                    "aggs": {"timestamp_avg": {"avg": {"field": "timestamp"}}}
+                }
+            }"#;
+            let search = |max_hits: u64| {
+                let request = SearchRequest {
+                    max_hits,
+                    sort_fields: vec![SortField {
+                        field_name: "timestamp".to_string(),
+                        sort_order: sort_order.into(),
+                        sort_datetime_format: None,
+                        sort_mode: None,
+                    }],
+                    aggregation_request: Some(aggregation_request.to_string()),
+                    ..SearchRequest::default()
+                };
+                let collector = super::make_collector_for_split(
+                    "split".to_string(),
+                    &request,
+                    Default::default(),
+                    Default::default(),
+                )
+                .unwrap();
+                let is_aggregation_only = collector.is_aggregation_only();
+                let leaf_response = searcher
+                    .search(&tantivy::query::AllQuery, &collector)
+                    .unwrap();
+                let intermediate_aggregation_results: IntermediateAggregationResults =
+                    postcard::from_bytes(&leaf_response.intermediate_aggregation_result.unwrap())
+                        .unwrap();
+                let aggregation_results = intermediate_aggregation_results
+                    .into_final_result(
+                        serde_json::from_str(aggregation_request).unwrap(),
+                        Default::default(),
+                    )
+                    .unwrap();
+                (
+                    is_aggregation_only,
+                    leaf_response.num_hits,
+                    leaf_response.partial_hits.len(),
+                    serde_json::to_value(aggregation_results).unwrap(),
+                )
+            };
+            let (is_aggregation_only, num_hits, num_partial_hits, aggregation_results) = search(0);
+            prop_assert!(is_aggregation_only);
+            prop_assert_eq!(num_partial_hits, 0);
+
+            let (
+                expected_is_aggregation_only,
+                expected_num_hits,
+                expected_num_partial_hits,
+                expected_aggregation_results,
+            ) = search(max_hits);
+            prop_assert!(!expected_is_aggregation_only);
+            prop_assert_eq!(
+                expected_num_partial_hits,
+                timestamps.len().min(max_hits as usize)
+            );
+            prop_assert_eq!(num_hits, expected_num_hits);
+            prop_assert_eq!(aggregation_results, expected_aggregation_results);
+        }
+    }
 }
@@ -22,14 +22,17 @@ use quickwit_common::rate_limited_error;
 use quickwit_doc_mapper::QueryParserError;
 use quickwit_proto::error::grpc_error_to_grpc_status;
 use quickwit_proto::metastore::{EntityKind, MetastoreError};
-use quickwit_proto::search::SplitSearchError;
+use quickwit_proto::search::{SplitSearchError, SplitSearchErrorCode};
 use quickwit_proto::{tonic, GrpcServiceError, ServiceError, ServiceErrorCode};
 use quickwit_storage::StorageResolverError;
 use serde::{Deserialize, Serialize};
+use tantivy::aggregation::AggregationError;
 use tantivy::TantivyError;
 use thiserror::Error;
 use tokio::task::JoinError;
 
+use crate::search_memory::MemoryLimitExceeded;
+
 /// Possible SearchError
 #[allow(missing_docs)]
 #[derive(Error, Debug, Serialize, Deserialize, Clone)]
@@ -45,6 +48,21 @@ pub enum SearchError {
     InvalidArgument(String),
     #[error("{0}")]
     InvalidQuery(String),
+    #[error("memory limit exceeded: {0}")]
+    MemoryLimitExceeded(String),
+    #[error("point in time `{pit_id}` not found: it is unknown or expired")]
+    PointInTimeNotFound { pit_id: String },
+    #[error(
+        "result window is too large, `start_offset + max_hits` must be less than or equal to \
+         {max_result_window} but was {result_window}. use `search_after` to paginate through \
+         large result sets, or raise the `search_settings.max_result_window` index setting"
+    )]
+    ResultWindowTooLarge {
+        result_window: u64,
+        max_result_window: u64,
+    },
+    #[error("split `{split_id}` not found in the storage")]
+    SplitNotFound { split_id: String },
     #[error("storage not found: `{0}`)")]
     StorageResolver(#[from] StorageResolverError),
     #[error("request timed out: {0}")]
@@ -56,13 +74,25 @@ pub enum SearchError {
 }
 
 impl SearchError {
-    /// Creates an internal `SearchError` from a list of split search errors.
+    /// Creates a `SearchError` from a list of split search errors. The error is internal unless
+    /// one of the splits ran out of memory, in which case that cause is reported instead.
     pub fn from_split_errors(failed_splits: &[SplitSearchError]) -> Option<SearchError> {
         let first_failing_split = failed_splits.first()?;
+        let out_of_memory_split_opt = failed_splits.iter().find(|failed_split| {
+            failed_split.error_code() == SplitSearchErrorCode::MemoryLimitExceeded
+        });
         let failed_splits = failed_splits
             .iter()
             .map(|failed_split| &failed_split.split_id)
             .join(", ");
+        if let Some(out_of_memory_split) = out_of_memory_split_opt {
+            let error_msg = format!(
+                "search failed for the following splits: {failed_splits:}. Split {} ran out of \
+                 memory: {}",
+                out_of_memory_split.split_id, out_of_memory_split.error,
+            );
+            return Some(SearchError::MemoryLimitExceeded(error_msg));
+        }
         let error_msg = format!(
             "search failed for the following splits: {failed_splits:}. For instance, split {} \
              failed with the following error message: {}",
@@ -83,6 +113,10 @@ impl ServiceError for SearchError {
             Self::InvalidAggregationRequest(_) => ServiceErrorCode::BadRequest,
             Self::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             Self::InvalidQuery(_) => ServiceErrorCode::BadRequest,
+            Self::MemoryLimitExceeded(_) => ServiceErrorCode::TooManyRequests,
+            Self::PointInTimeNotFound { .. } => ServiceErrorCode::NotFound,
+            Self::ResultWindowTooLarge { .. } => ServiceErrorCode::BadRequest,
+            Self::SplitNotFound { .. } => ServiceErrorCode::NotFound,
             Self::StorageResolver(storage_err) => {
                 rate_limited_error!(
                     limit_per_min = 6,
@@ -130,7 +164,18 @@ pub fn parse_grpc_error(grpc_error: &tonic::Status) -> SearchError {
 
 impl From<TantivyError> for SearchError {
     fn from(tantivy_error: TantivyError) -> Self {
-        SearchError::Internal(format!("tantivy error: {tantivy_error}"))
+        match tantivy_error {
+            TantivyError::AggregationError(AggregationError::MemoryExceeded { .. }) => {
+                SearchError::MemoryLimitExceeded(tantivy_error.to_string())
+            }
+            _ => SearchError::Internal(format!("tantivy error: {tantivy_error}")),
+        }
+    }
+}
+
+impl From<MemoryLimitExceeded> for SearchError {
+    fn from(limit_exceeded: MemoryLimitExceeded) -> Self {
+        SearchError::MemoryLimitExceeded(limit_exceeded.to_string())
     }
 }
 
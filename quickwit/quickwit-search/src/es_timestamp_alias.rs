@@ -0,0 +1,281 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::search::SearchRequest;
+use quickwit_query::query_ast::{FieldPresenceQuery, QueryAst, QueryAstTransformer, RangeQuery};
+use tracing::info;
+
+use crate::SearchError;
+
+/// Name of the timestamp field Elasticsearch clients (Kibana, Grafana, ...) query by default.
+const ES_TIMESTAMP_FIELD_NAME: &str = "@timestamp";
+
+/// Returns the timestamp field `@timestamp` should be rewritten to, provided all the indexes
+/// enable the alias, none of them define an `@timestamp` field, and they share the same timestamp
+/// field.
+fn alias_timestamp_field(indexes_metadata: &[IndexMetadata]) -> Option<&str> {
+    let mut timestamp_field_opt: Option<&str> = None;
+
+    for index_metadata in indexes_metadata {
+        let index_config = &index_metadata.index_config;
+
+        if !index_config.search_settings.es_timestamp_alias_enabled() {
+            return None;
+        }
+        let doc_mapping = &index_config.doc_mapping;
+
+        if doc_mapping
+            .field_mappings
+            .iter()
+            .any(|field_mapping| field_mapping.name == ES_TIMESTAMP_FIELD_NAME)
+        {
+            return None;
+        }
+        let timestamp_field = doc_mapping.timestamp_field.as_deref()?;
+
+        match timestamp_field_opt {
+            Some(other_timestamp_field) if other_timestamp_field != timestamp_field => {
+                return None;
+            }
+            _ => timestamp_field_opt = Some(timestamp_field),
+        }
+    }
+    timestamp_field_opt
+}
+
+struct TimestampFieldRewriter<'a> {
+    timestamp_field: &'a str,
+    num_rewrites: usize,
+}
+
+impl TimestampFieldRewriter<'_> {
+    fn rewrite(&mut self, field: &mut String) {
+        if field == ES_TIMESTAMP_FIELD_NAME {
+            *field = self.timestamp_field.to_string();
+            self.num_rewrites += 1;
+        }
+    }
+}
+
+impl QueryAstTransformer for TimestampFieldRewriter<'_> {
+    type Err = std::convert::Infallible;
+
+    fn transform_range(
+        &mut self,
+        mut range_query: RangeQuery,
+    ) -> Result<Option<QueryAst>, Self::Err> {
+        self.rewrite(&mut range_query.field);
+        Ok(Some(QueryAst::Range(range_query)))
+    }
+
+    fn transform_exists(
+        &mut self,
+        mut exists_query: FieldPresenceQuery,
+    ) -> Result<Option<QueryAst>, Self::Err> {
+        self.rewrite(&mut exists_query.field);
+        Ok(Some(QueryAst::FieldPresence(exists_query)))
+    }
+}
+
+/// Rewrites the `@timestamp` field of the range and exists clauses and of the sort fields of a
+/// request emitted by the Elasticsearch-compatible API to the timestamp field of the targeted
+/// indexes, when they do not define `@timestamp` themselves.
+///
+/// Returns a warning describing the rewrite, if any.
+pub(crate) fn rewrite_es_timestamp_alias(
+    indexes_metadata: &[IndexMetadata],
+    search_request: &mut SearchRequest,
+) -> crate::Result<Option<String>> {
+    if !search_request.es_timestamp_alias {
+        return Ok(None);
+    }
+    let Some(timestamp_field) = alias_timestamp_field(indexes_metadata) else {
+        return Ok(None);
+    };
+    let mut rewriter = TimestampFieldRewriter {
+        timestamp_field,
+        num_rewrites: 0,
+    };
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast = rewriter
+        .transform(query_ast)
+        .expect("can't fail unwrapping Infallible")
+        .unwrap_or(QueryAst::MatchAll);
+
+    for sort_field in &mut search_request.sort_fields {
+        rewriter.rewrite(&mut sort_field.field_name);
+    }
+    if rewriter.num_rewrites == 0 {
+        return Ok(None);
+    }
+    search_request.query_ast = serde_json::to_string(&query_ast)?;
+
+    let warning = format!(
+        "field `{ES_TIMESTAMP_FIELD_NAME}` is not defined in the doc mapping: it was rewritten to \
+         the timestamp field `{timestamp_field}`"
+    );
+    info!(num_rewrites = rewriter.num_rewrites, "{warning}");
+    Ok(Some(warning))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use quickwit_config::IndexConfig;
+    use quickwit_proto::search::{SortField, SortOrder};
+    use quickwit_query::query_ast::BoolQuery;
+    use quickwit_query::JsonLiteral;
+
+    use super::*;
+
+    fn index_metadata_for_test(index_id: &str, field_names: &[&str]) -> IndexMetadata {
+        let field_mappings: Vec<serde_json::Value> = field_names
+            .iter()
+            .map(|field_name| {
+                serde_json::json!({
+                    "name": field_name,
+                    "type": "datetime",
+                    "fast": true
+                })
+            })
+            .collect();
+        let doc_mapping_json = serde_json::json!({
+            "field_mappings": field_mappings,
+            "timestamp_field": field_names[0],
+        });
+        let mut index_config =
+            IndexConfig::for_test(index_id, &format!("ram:///indexes/{index_id}"));
+        index_config.doc_mapping = serde_json::from_value(doc_mapping_json).unwrap();
+        IndexMetadata::new(index_config)
+    }
+
+    fn timestamp_range(field: &str) -> QueryAst {
+        RangeQuery {
+            field: field.to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("2024-01-01T00:00:00Z".to_string())),
+            upper_bound: Bound::Unbounded,
+        }
+        .into()
+    }
+
+    fn es_search_request(query_ast: QueryAst, sort_field_name: &str) -> SearchRequest {
+        SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: serde_json::to_string(&query_ast).unwrap(),
+            sort_fields: vec![SortField {
+                field_name: sort_field_name.to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                sort_mode: None,
+            }],
+            es_timestamp_alias: true,
+            ..Default::default()
+        }
+    }
+
+    fn bool_filter(filter: Vec<QueryAst>) -> QueryAst {
+        QueryAst::Bool(BoolQuery {
+            filter,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_rewrite_es_timestamp_alias_range_exists_and_sort() {
+        let indexes_metadata = vec![
+            index_metadata_for_test("test-index-1", &["ts"]),
+            index_metadata_for_test("test-index-2", &["ts"]),
+        ];
+        let query_ast = bool_filter(vec![
+            timestamp_range("@timestamp"),
+            FieldPresenceQuery {
+                field: "@timestamp".to_string(),
+            }
+            .into(),
+        ]);
+        let mut search_request = es_search_request(query_ast, "@timestamp");
+
+        let warning = rewrite_es_timestamp_alias(&indexes_metadata, &mut search_request)
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("`ts`"));
+
+        let expected_query_ast = bool_filter(vec![
+            timestamp_range("ts"),
+            FieldPresenceQuery {
+                field: "ts".to_string(),
+            }
+            .into(),
+        ]);
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+        assert_eq!(query_ast, expected_query_ast);
+        assert_eq!(search_request.sort_fields[0].field_name, "ts");
+    }
+
+    #[test]
+    fn test_rewrite_es_timestamp_alias_untouched() {
+        let query_ast = bool_filter(vec![timestamp_range("@timestamp")]);
+        {
+            // The index defines an `@timestamp` field.
+            let indexes_metadata =
+                vec![index_metadata_for_test("test-index", &["@timestamp", "ts"])];
+            let mut search_request = es_search_request(query_ast.clone(), "@timestamp");
+            let expected_search_request = search_request.clone();
+            let warning_opt =
+                rewrite_es_timestamp_alias(&indexes_metadata, &mut search_request).unwrap();
+            assert!(warning_opt.is_none());
+            assert_eq!(search_request, expected_search_request);
+        }
+        {
+            // One of the indexes defines an `@timestamp` field.
+            let indexes_metadata = vec![
+                index_metadata_for_test("test-index-1", &["ts"]),
+                index_metadata_for_test("test-index-2", &["@timestamp"]),
+            ];
+            let mut search_request = es_search_request(query_ast.clone(), "@timestamp");
+            let expected_search_request = search_request.clone();
+            rewrite_es_timestamp_alias(&indexes_metadata, &mut search_request).unwrap();
+            assert_eq!(search_request, expected_search_request);
+        }
+        {
+            // The index disables the alias.
+            let mut index_metadata = index_metadata_for_test("test-index", &["ts"]);
+            index_metadata
+                .index_config
+                .search_settings
+                .es_timestamp_alias = Some(false);
+            let mut search_request = es_search_request(query_ast.clone(), "@timestamp");
+            let expected_search_request = search_request.clone();
+            rewrite_es_timestamp_alias(&[index_metadata], &mut search_request).unwrap();
+            assert_eq!(search_request, expected_search_request);
+        }
+        {
+            // The request does not come from the Elasticsearch-compatible API.
+            let indexes_metadata = vec![index_metadata_for_test("test-index", &["ts"])];
+            let mut search_request = es_search_request(query_ast, "@timestamp");
+            search_request.es_timestamp_alias = false;
+            let expected_search_request = search_request.clone();
+            rewrite_es_timestamp_alias(&indexes_metadata, &mut search_request).unwrap();
+            assert_eq!(search_request, expected_search_request);
+        }
+    }
+}
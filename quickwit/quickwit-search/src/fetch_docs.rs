@@ -17,25 +17,33 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::Ipv6Addr;
 use std::sync::Arc;
 
 use anyhow::{Context, Ok};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
-use quickwit_doc_mapper::DocMapper;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::search::{
-    FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
+    FetchDocsResponse, FetchDocsSource, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
 };
 use quickwit_storage::Storage;
+use serde_json::Value as JsonValue;
+use tantivy::columnar::{BytesColumn, Column, StrColumn};
+use tantivy::fastfield::FastFieldReaders;
 use tantivy::query::Query;
 use tantivy::schema::document::CompactDocValue;
-use tantivy::schema::{Document as DocumentTrait, Field, TantivyDocument, Value};
+use tantivy::schema::{
+    Document as DocumentTrait, Field, FieldType, NamedFieldDocument, OwnedValue, Schema,
+    TantivyDocument, Value,
+};
 use tantivy::snippet::SnippetGenerator;
-use tantivy::{ReloadPolicy, Score, Searcher, Term};
+use tantivy::{DateTime, DateTimePrecision, DocId, ReloadPolicy, Score, Searcher, Term};
 use tracing::{error, Instrument};
 
-use crate::leaf::open_index_with_caches;
+use crate::leaf::{open_index_with_caches, warmup};
 use crate::service::SearcherContext;
 use crate::{convert_document_to_json_string, GlobalDocAddress};
 
@@ -50,6 +58,8 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    fields: &[String],
+    fetch_docs_source: FetchDocsSource,
 ) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
     let mut split_fetch_docs_futures = Vec::new();
 
@@ -77,6 +87,8 @@ async fn fetch_docs_to_map(
             split_and_offset,
             doc_mapper.clone(),
             snippet_request_opt,
+            fields,
+            fetch_docs_source,
         ));
     }
 
@@ -110,6 +122,9 @@ async fn fetch_docs_to_map(
 /// This function takes a list of partial hits (possibly from different splits)
 /// and the storage associated to an index, fetches the document from
 /// the split document stores, and returns the full hits.
+///
+/// If `fields` is not empty, only these fields are returned. When all of them can be read
+/// from the fast fields (see [`select_fetch_docs_source`]), the doc store is not accessed.
 pub async fn fetch_docs(
     searcher_context: Arc<SearcherContext>,
     partial_hits: Vec<PartialHit>,
@@ -117,6 +132,33 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    fields: &[String],
+) -> anyhow::Result<FetchDocsResponse> {
+    let fetch_docs_source =
+        select_fetch_docs_source(&doc_mapper.schema(), fields, snippet_request_opt.is_some());
+    fetch_docs_from_source(
+        searcher_context,
+        partial_hits,
+        index_storage,
+        splits,
+        doc_mapper,
+        snippet_request_opt,
+        fields,
+        fetch_docs_source,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn fetch_docs_from_source(
+    searcher_context: Arc<SearcherContext>,
+    partial_hits: Vec<PartialHit>,
+    index_storage: Arc<dyn Storage>,
+    splits: &[SplitIdAndFooterOffsets],
+    doc_mapper: Arc<dyn DocMapper>,
+    snippet_request_opt: Option<&SnippetRequest>,
+    fields: &[String],
+    fetch_docs_source: FetchDocsSource,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
@@ -130,6 +172,8 @@ pub async fn fetch_docs(
         splits,
         doc_mapper,
         snippet_request_opt,
+        fields,
+        fetch_docs_source,
     )
     .await?;
 
@@ -155,6 +199,272 @@ pub async fn fetch_docs(
 // number of concurrent fetch allowed for a single split.
 const NUM_CONCURRENT_REQUESTS: usize = 30;
 
+/// Returns where the hits of a request returning the given `fields` are fetched from.
+///
+/// The fast fields are used only if each requested field is a stored fast field whose fast
+/// values are exactly the stored values, so that both sources return the same hits. Snippets
+/// are generated from the stored documents and therefore require the doc store.
+pub(crate) fn select_fetch_docs_source(
+    schema: &Schema,
+    fields: &[String],
+    has_snippets: bool,
+) -> FetchDocsSource {
+    if fields.is_empty() || has_snippets {
+        return FetchDocsSource::DocStore;
+    }
+    let all_fields_are_lossless_fast_fields = fields.iter().all(|field_name| {
+        let Ok(field) = schema.get_field(field_name) else {
+            return false;
+        };
+        let field_entry = schema.get_field_entry(field);
+        if !field_entry.is_stored() || !field_entry.is_fast() {
+            return false;
+        }
+        match field_entry.field_type() {
+            FieldType::U64(_)
+            | FieldType::I64(_)
+            | FieldType::F64(_)
+            | FieldType::Bool(_)
+            | FieldType::IpAddr(_)
+            | FieldType::Bytes(_) => true,
+            // Fast datetime values are truncated to the fast field precision.
+            FieldType::Date(date_options) => {
+                date_options.get_precision() == DateTimePrecision::Nanoseconds
+            }
+            // Fast text values go through the fast field normalizer.
+            FieldType::Str(text_options) => {
+                text_options.get_fast_field_tokenizer_name() == Some("raw")
+            }
+            FieldType::Facet(_) | FieldType::JsonObject(_) => false,
+        }
+    });
+    if all_fields_are_lossless_fast_fields {
+        FetchDocsSource::FastFields
+    } else {
+        FetchDocsSource::DocStore
+    }
+}
+
+/// A fast field column opened for a given segment, used to rebuild documents.
+enum FastFieldColumn {
+    U64(Column<u64>),
+    I64(Column<i64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+    Date(Column<DateTime>),
+    IpAddr(Column<Ipv6Addr>),
+    Str(StrColumn),
+    Bytes(BytesColumn),
+}
+
+impl FastFieldColumn {
+    fn open(
+        fast_field_readers: &FastFieldReaders,
+        field_name: &str,
+        field_type: &FieldType,
+    ) -> anyhow::Result<Option<Self>> {
+        let column_opt = match field_type {
+            FieldType::U64(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::U64),
+            FieldType::I64(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::I64),
+            FieldType::F64(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::F64),
+            FieldType::Bool(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::Bool),
+            FieldType::Date(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::Date),
+            FieldType::IpAddr(_) => fast_field_readers
+                .column_opt(field_name)?
+                .map(FastFieldColumn::IpAddr),
+            FieldType::Str(_) => fast_field_readers
+                .str(field_name)?
+                .map(FastFieldColumn::Str),
+            FieldType::Bytes(_) => fast_field_readers
+                .bytes(field_name)?
+                .map(FastFieldColumn::Bytes),
+            FieldType::Facet(_) | FieldType::JsonObject(_) => {
+                anyhow::bail!("field `{field_name}` cannot be read from the fast fields")
+            }
+        };
+        Ok(column_opt)
+    }
+
+    fn values(&self, doc_id: DocId) -> anyhow::Result<Vec<OwnedValue>> {
+        let values = match self {
+            FastFieldColumn::U64(column) => {
+                column.values_for_doc(doc_id).map(OwnedValue::U64).collect()
+            }
+            FastFieldColumn::I64(column) => {
+                column.values_for_doc(doc_id).map(OwnedValue::I64).collect()
+            }
+            FastFieldColumn::F64(column) => {
+                column.values_for_doc(doc_id).map(OwnedValue::F64).collect()
+            }
+            FastFieldColumn::Bool(column) => column
+                .values_for_doc(doc_id)
+                .map(OwnedValue::Bool)
+                .collect(),
+            FastFieldColumn::Date(column) => column
+                .values_for_doc(doc_id)
+                .map(OwnedValue::Date)
+                .collect(),
+            FastFieldColumn::IpAddr(column) => column
+                .values_for_doc(doc_id)
+                .map(OwnedValue::IpAddr)
+                .collect(),
+            FastFieldColumn::Str(column) => {
+                let mut values = Vec::new();
+                for term_ord in column.term_ords(doc_id) {
+                    let mut value = String::new();
+                    column.ord_to_str(term_ord, &mut value)?;
+                    values.push(OwnedValue::Str(value));
+                }
+                values
+            }
+            FastFieldColumn::Bytes(column) => {
+                let mut values = Vec::new();
+                for term_ord in column.term_ords(doc_id) {
+                    let mut value = Vec::new();
+                    column.ord_to_bytes(term_ord, &mut value)?;
+                    values.push(OwnedValue::Bytes(value));
+                }
+                values
+            }
+        };
+        Ok(values)
+    }
+}
+
+/// Rebuilds the documents restricted to `fields` from the fast fields of a split.
+async fn fetch_docs_from_fast_fields(
+    searcher: &Searcher,
+    global_doc_addrs: Vec<GlobalDocAddress>,
+    doc_mapper: &dyn DocMapper,
+    fields: &[String],
+) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
+    let warmup_info = WarmupInfo {
+        fast_field_names: fields.iter().cloned().collect(),
+        ..Default::default()
+    };
+    warmup(searcher, &warmup_info).await?;
+
+    let schema = searcher.schema();
+    let mut columns_per_segment: HashMap<u32, Vec<(&str, Option<FastFieldColumn>)>> =
+        HashMap::new();
+    let mut documents = Vec::with_capacity(global_doc_addrs.len());
+
+    for global_doc_addr in global_doc_addrs {
+        let doc_addr = global_doc_addr.doc_addr;
+        let columns = match columns_per_segment.entry(doc_addr.segment_ord) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let fast_field_readers =
+                    searcher.segment_reader(doc_addr.segment_ord).fast_fields();
+                let mut columns = Vec::with_capacity(fields.len());
+                for field_name in fields {
+                    let field = schema.get_field(field_name)?;
+                    let field_type = schema.get_field_entry(field).field_type();
+                    let column_opt =
+                        FastFieldColumn::open(fast_field_readers, field_name, field_type)?;
+                    columns.push((field_name.as_str(), column_opt));
+                }
+                entry.insert(columns)
+            }
+        };
+        let mut named_doc: BTreeMap<String, Vec<OwnedValue>> = BTreeMap::new();
+        for (field_name, column_opt) in columns.iter() {
+            let Some(column) = column_opt else {
+                continue;
+            };
+            let values = column.values(doc_addr.doc_id)?;
+            if !values.is_empty() {
+                named_doc.insert(field_name.to_string(), values);
+            }
+        }
+        let content_json = convert_document_to_projected_json_string(
+            NamedFieldDocument(named_doc),
+            doc_mapper,
+            fields,
+        )?;
+        documents.push((
+            global_doc_addr,
+            Document {
+                content_json,
+                snippet_json: None,
+            },
+        ));
+    }
+    Ok(documents)
+}
+
+/// Converts a document to JSON, keeping only the requested `fields` if any.
+fn convert_document_to_projected_json_string(
+    named_field_doc: NamedFieldDocument,
+    doc_mapper: &dyn DocMapper,
+    fields: &[String],
+) -> anyhow::Result<String> {
+    if fields.is_empty() {
+        return convert_document_to_json_string(named_field_doc, doc_mapper);
+    }
+    let NamedFieldDocument(named_field_doc_map) = named_field_doc;
+    let doc_json_map = doc_mapper.doc_to_json(named_field_doc_map)?;
+    let projected_doc_json_map = project_json_fields(&doc_json_map, fields);
+    let content_json = serde_json::to_string(&projected_doc_json_map)
+        .expect("Json serialization should never fail.");
+    Ok(content_json)
+}
+
+/// Keeps only the values at the given dot-separated paths.
+fn project_json_fields(
+    doc_json_map: &serde_json::Map<String, JsonValue>,
+    fields: &[String],
+) -> serde_json::Map<String, JsonValue> {
+    let mut projected_doc_json_map = serde_json::Map::new();
+    let unique_fields: HashSet<&str> = fields.iter().map(String::as_str).collect();
+
+    'fields: for field in unique_fields {
+        let mut path = field.split('.');
+        let Some(mut value) = path.next().and_then(|key| doc_json_map.get(key)) else {
+            continue;
+        };
+        let mut found = true;
+        for key in path {
+            match value.get(key) {
+                Some(sub_value) => value = sub_value,
+                None => {
+                    found = false;
+                    break;
+                }
+            }
+        }
+        if !found {
+            continue;
+        }
+        let mut keys: Vec<&str> = field.split('.').collect();
+        let last_key = keys.pop().expect("split should yield at least one key");
+        let mut target = &mut projected_doc_json_map;
+        for key in keys {
+            let Some(sub_target) = target
+                .entry(key)
+                .or_insert_with(|| JsonValue::Object(serde_json::Map::new()))
+                .as_object_mut()
+            else {
+                // A parent field was also requested: it already holds the whole value.
+                continue 'fields;
+            };
+            target = sub_target;
+        }
+        target.insert(last_key.to_string(), value.clone());
+    }
+    projected_doc_json_map
+}
+
 /// A struct for holding a fetched document's content and snippet.
 #[derive(Debug)]
 struct Document {
@@ -170,6 +480,8 @@ async fn fetch_docs_in_split(
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    fields: &[String],
+    fetch_docs_source: FetchDocsSource,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
     global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
@@ -196,16 +508,23 @@ async fn fetch_docs_in_split(
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = Arc::new(index_reader.searcher());
+
+    if fetch_docs_source == FetchDocsSource::FastFields {
+        return fetch_docs_from_fast_fields(&searcher, global_doc_addrs, &*doc_mapper, fields)
+            .await;
+    }
     let fields_snippet_generator_opt = if let Some(snippet_request) = snippet_request_opt {
         Some(create_fields_snippet_generator(&searcher, doc_mapper.clone(), snippet_request).await?)
     } else {
         None
     };
 
+    let fields: Arc<[String]> = fields.into();
     let doc_futures = global_doc_addrs.into_iter().map(|global_doc_addr| {
         let moved_searcher = searcher.clone();
         let moved_doc_mapper = doc_mapper.clone();
         let fields_snippet_generator_opt_clone = fields_snippet_generator_opt.clone();
+        let fields = fields.clone();
         async move {
             let doc: TantivyDocument = moved_searcher
                 .doc_async(global_doc_addr.doc_addr)
@@ -213,8 +532,11 @@ async fn fetch_docs_in_split(
                 .context("searcher-doc-async")?;
 
             let named_field_doc = doc.to_named_doc(moved_searcher.schema());
-            let content_json =
-                convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?;
+            let content_json = convert_document_to_projected_json_string(
+                named_field_doc,
+                &*moved_doc_mapper,
+                &fields,
+            )?;
             if fields_snippet_generator_opt_clone.is_none() {
                 return Ok((
                     global_doc_addr,
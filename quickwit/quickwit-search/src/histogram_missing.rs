@@ -0,0 +1,386 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use quickwit_proto::search::HistogramMissingDocCount;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tantivy::aggregation::agg_result::{
+    AggregationResult, AggregationResults, BucketEntries, BucketEntry, BucketResult,
+};
+use tantivy::aggregation::Key;
+
+use crate::SearchError;
+
+/// The `missing` parameter of a top-level `histogram` aggregation.
+///
+/// Tantivy cannot substitute a value to the documents lacking the histogram field, so the
+/// parameter is removed from the request handed to tantivy. Instead, the leaves count the
+/// matching documents lacking the field, and the root adds them to the bucket of the missing
+/// value when finalizing the aggregation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HistogramMissing {
+    pub aggregation_name: String,
+    pub field: String,
+    pub missing: f64,
+    interval: f64,
+    offset: f64,
+    hard_bounds_opt: Option<(f64, f64)>,
+}
+
+#[derive(Deserialize)]
+struct HistogramParams {
+    field: String,
+    interval: f64,
+    #[serde(default)]
+    offset: Option<f64>,
+    #[serde(default)]
+    hard_bounds: Option<HistogramBounds>,
+    missing: f64,
+}
+
+#[derive(Deserialize)]
+struct HistogramBounds {
+    min: f64,
+    max: f64,
+}
+
+impl HistogramMissing {
+    /// Returns the key of the bucket the documents lacking the field fall into, or `None` if the
+    /// missing value is clipped by the hard bounds of the histogram.
+    fn bucket_key(&self) -> Option<f64> {
+        if let Some((min, max)) = self.hard_bounds_opt {
+            if self.missing < min || self.missing > max {
+                return None;
+            }
+        }
+        let bucket_pos = ((self.missing - self.offset) / self.interval).floor();
+        Some(bucket_pos * self.interval + self.offset)
+    }
+}
+
+fn sub_aggregations(aggregation: &Map<String, Value>) -> Option<&Map<String, Value>> {
+    aggregation
+        .get("aggs")
+        .or_else(|| aggregation.get("aggregations"))
+        .and_then(Value::as_object)
+}
+
+fn has_histogram_missing(aggregations: &Map<String, Value>) -> bool {
+    aggregations
+        .values()
+        .filter_map(Value::as_object)
+        .any(|aggregation| {
+            let is_histogram_missing = aggregation
+                .get("histogram")
+                .and_then(Value::as_object)
+                .map_or(false, |histogram| histogram.contains_key("missing"));
+            is_histogram_missing
+                || sub_aggregations(aggregation).map_or(false, has_histogram_missing)
+        })
+}
+
+/// Extracts the `missing` parameters of the histogram aggregations of an aggregation request.
+///
+/// Only top-level histogram aggregations without sub-aggregations support the parameter: the
+/// documents lacking the field are only counted, so they can neither be attributed to a parent
+/// bucket nor feed sub-aggregations.
+pub(crate) fn extract_histogram_missing(
+    aggregation_json: &str,
+) -> crate::Result<Vec<HistogramMissing>> {
+    if !aggregation_json.contains("\"missing\"") {
+        return Ok(Vec::new());
+    }
+    let Ok(Value::Object(aggregations)) = serde_json::from_str::<Value>(aggregation_json) else {
+        return Ok(Vec::new());
+    };
+    let mut histogram_missings = Vec::new();
+
+    for (aggregation_name, aggregation) in &aggregations {
+        let Some(aggregation) = aggregation.as_object() else {
+            continue;
+        };
+        if sub_aggregations(aggregation).map_or(false, has_histogram_missing) {
+            return Err(SearchError::InvalidAggregationRequest(
+                "the `missing` parameter is only supported by top-level histogram aggregations"
+                    .to_string(),
+            ));
+        }
+        let Some(histogram) = aggregation.get("histogram") else {
+            continue;
+        };
+        if histogram.get("missing").is_none() {
+            continue;
+        }
+        if sub_aggregations(aggregation)
+            .map_or(false, |sub_aggregations| !sub_aggregations.is_empty())
+        {
+            return Err(SearchError::InvalidAggregationRequest(format!(
+                "histogram aggregation `{aggregation_name}` cannot have both a `missing` \
+                 parameter and sub-aggregations"
+            )));
+        }
+        let histogram_params: HistogramParams =
+            serde_json::from_value(histogram.clone()).map_err(|error| {
+                SearchError::InvalidAggregationRequest(format!(
+                    "invalid histogram aggregation `{aggregation_name}`: {error}"
+                ))
+            })?;
+        histogram_missings.push(HistogramMissing {
+            aggregation_name: aggregation_name.clone(),
+            field: histogram_params.field,
+            missing: histogram_params.missing,
+            interval: histogram_params.interval,
+            offset: histogram_params.offset.unwrap_or(0.0),
+            hard_bounds_opt: histogram_params
+                .hard_bounds
+                .map(|hard_bounds| (hard_bounds.min, hard_bounds.max)),
+        });
+    }
+    Ok(histogram_missings)
+}
+
+fn remove_histogram_missing(aggregations: &mut Map<String, Value>) -> bool {
+    let mut removed_something = false;
+
+    for aggregation in aggregations.values_mut() {
+        let Some(aggregation) = aggregation.as_object_mut() else {
+            continue;
+        };
+        if let Some(histogram) = aggregation
+            .get_mut("histogram")
+            .and_then(Value::as_object_mut)
+        {
+            removed_something |= histogram.remove("missing").is_some();
+        }
+        for sub_aggregations_key in ["aggs", "aggregations"] {
+            if let Some(sub_aggregations) = aggregation
+                .get_mut(sub_aggregations_key)
+                .and_then(Value::as_object_mut)
+            {
+                removed_something |= remove_histogram_missing(sub_aggregations);
+            }
+        }
+    }
+    removed_something
+}
+
+/// Removes the `missing` parameters of the histogram aggregations, which are applied by
+/// quickwit, from an aggregation request before it is handed over to tantivy.
+pub(crate) fn strip_histogram_missing(aggregation_json: &str) -> Cow<str> {
+    if !aggregation_json.contains("\"missing\"") {
+        return Cow::Borrowed(aggregation_json);
+    }
+    let Ok(Value::Object(mut aggregations)) = serde_json::from_str::<Value>(aggregation_json)
+    else {
+        return Cow::Borrowed(aggregation_json);
+    };
+    if !remove_histogram_missing(&mut aggregations) {
+        return Cow::Borrowed(aggregation_json);
+    }
+    Cow::Owned(Value::Object(aggregations).to_string())
+}
+
+/// Sums the missing document counts of several leaf responses.
+pub(crate) fn merge_histogram_missing_doc_counts<'a>(
+    histogram_missing_doc_counts: impl IntoIterator<Item = &'a HistogramMissingDocCount>,
+) -> Vec<HistogramMissingDocCount> {
+    let mut num_docs_per_aggregation: BTreeMap<&str, u64> = BTreeMap::new();
+
+    for histogram_missing_doc_count in histogram_missing_doc_counts {
+        *num_docs_per_aggregation
+            .entry(&histogram_missing_doc_count.aggregation_name)
+            .or_default() += histogram_missing_doc_count.num_docs;
+    }
+    num_docs_per_aggregation
+        .into_iter()
+        .map(|(aggregation_name, num_docs)| HistogramMissingDocCount {
+            aggregation_name: aggregation_name.to_string(),
+            num_docs,
+        })
+        .collect()
+}
+
+/// Adds the documents lacking the histogram field to the bucket of the missing value.
+pub(crate) fn apply_histogram_missing_doc_counts(
+    aggregation_results: &mut AggregationResults,
+    histogram_missings: &[HistogramMissing],
+    histogram_missing_doc_counts: &[HistogramMissingDocCount],
+) {
+    for histogram_missing_doc_count in histogram_missing_doc_counts {
+        if histogram_missing_doc_count.num_docs == 0 {
+            continue;
+        }
+        let Some(histogram_missing) = histogram_missings.iter().find(|histogram_missing| {
+            histogram_missing.aggregation_name == histogram_missing_doc_count.aggregation_name
+        }) else {
+            continue;
+        };
+        let Some(bucket_key) = histogram_missing.bucket_key() else {
+            continue;
+        };
+        let Some(AggregationResult::BucketResult(BucketResult::Histogram { buckets })) =
+            aggregation_results
+                .0
+                .get_mut(&histogram_missing.aggregation_name)
+        else {
+            continue;
+        };
+        let num_docs = histogram_missing_doc_count.num_docs;
+
+        match buckets {
+            BucketEntries::Vec(bucket_entries) => {
+                let bucket_pos = bucket_entries
+                    .partition_point(|bucket_entry| bucket_entry_key(bucket_entry) < bucket_key);
+                match bucket_entries.get_mut(bucket_pos) {
+                    Some(bucket_entry) if bucket_entry_key(bucket_entry) == bucket_key => {
+                        bucket_entry.doc_count += num_docs;
+                    }
+                    _ => {
+                        bucket_entries.insert(bucket_pos, new_bucket_entry(bucket_key, num_docs));
+                    }
+                }
+            }
+            BucketEntries::HashMap(bucket_entries) => {
+                bucket_entries
+                    .entry(bucket_key.to_string())
+                    .and_modify(|bucket_entry| bucket_entry.doc_count += num_docs)
+                    .or_insert_with(|| new_bucket_entry(bucket_key, num_docs));
+            }
+        }
+    }
+}
+
+fn bucket_entry_key(bucket_entry: &BucketEntry) -> f64 {
+    match bucket_entry.key {
+        Key::F64(key) => key,
+        _ => f64::NAN,
+    }
+}
+
+fn new_bucket_entry(bucket_key: f64, num_docs: u64) -> BucketEntry {
+    BucketEntry {
+        key_as_string: None,
+        key: Key::F64(bucket_key),
+        doc_count: num_docs,
+        sub_aggregation: AggregationResults::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_histogram_missing() {
+        assert!(extract_histogram_missing(
+            r#"{"h": {"histogram": {"field": "price", "interval": 10}}}"#
+        )
+        .unwrap()
+        .is_empty());
+
+        let histogram_missings = extract_histogram_missing(
+            r#"{
+                "h": {"histogram": {"field": "price", "interval": 10, "offset": 5, "missing": 42, "hard_bounds": {"min": 0, "max": 100}}},
+                "t": {"terms": {"field": "color", "missing": "none"}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(histogram_missings.len(), 1);
+        assert_eq!(histogram_missings[0].aggregation_name, "h");
+        assert_eq!(histogram_missings[0].field, "price");
+        assert_eq!(histogram_missings[0].bucket_key(), Some(35.0));
+
+        let error = extract_histogram_missing(
+            r#"{"t": {"terms": {"field": "color"}, "aggs": {"h": {"histogram": {"field": "price", "interval": 10, "missing": 0}}}}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidAggregationRequest(_)));
+
+        let error = extract_histogram_missing(
+            r#"{"h": {"histogram": {"field": "price", "interval": 10, "missing": 0}, "aggs": {"m": {"max": {"field": "price"}}}}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidAggregationRequest(_)));
+
+        let error = extract_histogram_missing(
+            r#"{"h": {"histogram": {"field": "price", "interval": 10, "missing": "zero"}}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidAggregationRequest(_)));
+    }
+
+    #[test]
+    fn test_histogram_missing_bucket_key() {
+        let histogram_missing =
+            |missing: f64, hard_bounds_opt: Option<(f64, f64)>| HistogramMissing {
+                aggregation_name: "h".to_string(),
+                field: "price".to_string(),
+                missing,
+                interval: 10.0,
+                offset: 0.0,
+                hard_bounds_opt,
+            };
+        assert_eq!(histogram_missing(0.0, None).bucket_key(), Some(0.0));
+        assert_eq!(histogram_missing(19.9, None).bucket_key(), Some(10.0));
+        assert_eq!(histogram_missing(-1.0, None).bucket_key(), Some(-10.0));
+        assert_eq!(
+            histogram_missing(20.0, Some((0.0, 20.0))).bucket_key(),
+            Some(20.0)
+        );
+        assert_eq!(
+            histogram_missing(25.0, Some((0.0, 20.0))).bucket_key(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_strip_histogram_missing() {
+        let aggregation_json = r#"{"t": {"terms": {"field": "color", "missing": "none"}}}"#;
+        assert!(matches!(
+            strip_histogram_missing(aggregation_json),
+            Cow::Borrowed(_)
+        ));
+
+        let aggregation_json = r#"{"h": {"histogram": {"field": "price", "interval": 10, "missing": 0}}, "t": {"terms": {"field": "color", "missing": "none"}}}"#;
+        let stripped: Value =
+            serde_json::from_str(&strip_histogram_missing(aggregation_json)).unwrap();
+        let expected: Value = serde_json::from_str(
+            r#"{"h": {"histogram": {"field": "price", "interval": 10}}, "t": {"terms": {"field": "color", "missing": "none"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(stripped, expected);
+    }
+
+    #[test]
+    fn test_merge_histogram_missing_doc_counts() {
+        let doc_count = |aggregation_name: &str, num_docs: u64| HistogramMissingDocCount {
+            aggregation_name: aggregation_name.to_string(),
+            num_docs,
+        };
+        let merged = merge_histogram_missing_doc_counts(&[
+            doc_count("h2", 1),
+            doc_count("h1", 2),
+            doc_count("h2", 3),
+        ]);
+        assert_eq!(merged, [doc_count("h1", 2), doc_count("h2", 4)]);
+    }
+}
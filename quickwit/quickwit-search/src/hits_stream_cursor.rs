@@ -0,0 +1,243 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use prost::Message;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::search::{PartialHit, SearchRequest};
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher;
+
+use crate::SearchError;
+
+const CHECKSUM_NUM_BYTES: usize = 8;
+
+/// Position of a hits stream, from which any searcher can resume it.
+///
+/// The cursor holds the whole state of the stream, so no state is kept on the searcher that
+/// served it. It is tied to the request and to the set of splits the stream scans: resuming it
+/// with another request or after the splits changed would produce duplicate or missing
+/// documents, so it is rejected.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct HitsStreamCursor {
+    pub request_fingerprint: u64,
+    pub split_set_fingerprint: u64,
+    // Ordinal of the split being scanned, in the scan order of the split set.
+    pub split_ord: usize,
+    // Last document streamed from that split. The documents of a split are streamed in the order
+    // of their address, so the scan of the split resumes right after it.
+    pub search_after_opt: Option<PartialHit>,
+    // Number of documents streamed so far, counted against the cap of the request.
+    pub num_streamed_docs: u64,
+}
+
+impl HitsStreamCursor {
+    /// Checks that the cursor was issued for the same request and the same set of splits.
+    pub fn validate(
+        &self,
+        request_fingerprint: u64,
+        split_metadatas: &[SplitMetadata],
+    ) -> crate::Result<()> {
+        if self.request_fingerprint != request_fingerprint {
+            return Err(SearchError::InvalidArgument(
+                "hits stream cursor was issued for another request".to_string(),
+            ));
+        }
+        if self.split_set_fingerprint != split_set_fingerprint(split_metadatas) {
+            return Err(SearchError::InvalidArgument(
+                "the splits targeted by the request changed since the hits stream cursor was \
+                 issued, use a point in time to stream a fixed set of splits"
+                    .to_string(),
+            ));
+        }
+        let Some(split_metadata) = split_metadatas.get(self.split_ord) else {
+            if self.split_ord == split_metadatas.len() && self.search_after_opt.is_none() {
+                // The stream is exhausted.
+                return Ok(());
+            }
+            return Err(SearchError::InvalidArgument(
+                "hits stream cursor is malformed".to_string(),
+            ));
+        };
+        if let Some(search_after) = &self.search_after_opt {
+            if search_after.split_id != split_metadata.split_id {
+                return Err(SearchError::InvalidArgument(
+                    "hits stream cursor is malformed".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HitsStreamCursor {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut payload =
+            serde_json::to_vec(self).expect("serializing a hits stream cursor should never fail");
+        let checksum = checksum(&payload);
+        payload.extend_from_slice(&checksum.to_le_bytes());
+        let b64_payload = BASE64_STANDARD.encode(payload);
+        write!(formatter, "{b64_payload}")
+    }
+}
+
+impl FromStr for HitsStreamCursor {
+    type Err = &'static str;
+
+    fn from_str(cursor_str: &str) -> Result<Self, Self::Err> {
+        let base64_decoded: Vec<u8> = BASE64_STANDARD
+            .decode(cursor_str)
+            .map_err(|_| "hits stream cursor is invalid base64")?;
+        if base64_decoded.len() <= CHECKSUM_NUM_BYTES {
+            return Err("hits stream cursor is truncated");
+        }
+        let (payload, checksum_bytes) =
+            base64_decoded.split_at(base64_decoded.len() - CHECKSUM_NUM_BYTES);
+        let expected_checksum = u64::from_le_bytes(checksum_bytes.try_into().unwrap());
+
+        if checksum(payload) != expected_checksum {
+            return Err("hits stream cursor is corrupted");
+        }
+        serde_json::from_slice(payload).map_err(|_| "hits stream cursor is malformed")
+    }
+}
+
+/// Fingerprints a hits stream request, as received from the client.
+pub(crate) fn request_fingerprint(search_request: &SearchRequest) -> u64 {
+    let mut hasher = SipHasher::new();
+    hasher.write(&search_request.encode_to_vec());
+    hasher.finish()
+}
+
+/// Fingerprints a set of splits, in scan order.
+pub(crate) fn split_set_fingerprint(split_metadatas: &[SplitMetadata]) -> u64 {
+    let mut hasher = SipHasher::new();
+
+    for split_metadata in split_metadatas {
+        split_metadata.split_id.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn checksum(payload: &[u8]) -> u64 {
+    let mut hasher = SipHasher::new_with_keys(0, 1);
+    hasher.write(payload);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_metadatas(split_ids: &[&str]) -> Vec<SplitMetadata> {
+        split_ids
+            .iter()
+            .map(|split_id| SplitMetadata {
+                split_id: split_id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn cursor_for_test(split_metadatas: &[SplitMetadata]) -> HitsStreamCursor {
+        HitsStreamCursor {
+            request_fingerprint: 42,
+            split_set_fingerprint: split_set_fingerprint(split_metadatas),
+            split_ord: 1,
+            search_after_opt: Some(PartialHit {
+                split_id: "split-2".to_string(),
+                segment_ord: 1,
+                doc_id: 2,
+                ..Default::default()
+            }),
+            num_streamed_docs: 10,
+        }
+    }
+
+    #[test]
+    fn test_hits_stream_cursor_serde() {
+        let cursor = cursor_for_test(&split_metadatas(&["split-1", "split-2"]));
+        let cursor_str = cursor.to_string();
+        assert_eq!(cursor_str.parse::<HitsStreamCursor>().unwrap(), cursor);
+
+        assert_eq!(
+            "not-base64!".parse::<HitsStreamCursor>().unwrap_err(),
+            "hits stream cursor is invalid base64"
+        );
+        assert_eq!(
+            BASE64_STANDARD
+                .encode(b"short")
+                .parse::<HitsStreamCursor>()
+                .unwrap_err(),
+            "hits stream cursor is truncated"
+        );
+        let mut payload = BASE64_STANDARD.decode(&cursor_str).unwrap();
+        payload[2] ^= 1;
+        assert_eq!(
+            BASE64_STANDARD
+                .encode(&payload)
+                .parse::<HitsStreamCursor>()
+                .unwrap_err(),
+            "hits stream cursor is corrupted"
+        );
+    }
+
+    #[test]
+    fn test_hits_stream_cursor_validate() {
+        let split_metadatas = split_metadatas(&["split-1", "split-2"]);
+        let cursor = cursor_for_test(&split_metadatas);
+        cursor.validate(42, &split_metadatas).unwrap();
+
+        let error = cursor.validate(43, &split_metadatas).unwrap_err();
+        assert!(error.to_string().contains("another request"));
+
+        let error = cursor.validate(42, &split_metadatas[..1]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("splits targeted by the request changed"));
+
+        let exhausted_cursor = HitsStreamCursor {
+            split_ord: 2,
+            search_after_opt: None,
+            ..cursor.clone()
+        };
+        exhausted_cursor.validate(42, &split_metadatas).unwrap();
+
+        let out_of_bounds_cursor = HitsStreamCursor {
+            split_ord: 3,
+            ..exhausted_cursor
+        };
+        out_of_bounds_cursor
+            .validate(42, &split_metadatas)
+            .unwrap_err();
+
+        let mismatching_split_cursor = HitsStreamCursor {
+            split_ord: 0,
+            ..cursor
+        };
+        mismatching_split_cursor
+            .validate(42, &split_metadatas)
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::search::{Hit, SearchRequest, SortByValue, SortOrder};
+use quickwit_query::query_ast::{
+    FieldPresenceQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery,
+    RegexQuery, TermQuery, TermSetQuery, WildcardQuery,
+};
+
+use crate::SearchError;
+
+/// Pseudo-field holding the ID of the index of a document. It is never sent to the leaves: the
+/// clauses on `_index` are evaluated by the root when it plans the search, and the hits tied on
+/// the other sort fields are sorted on `_index` by the root once they are fetched.
+const INDEX_PSEUDO_FIELD_NAME: &str = "_index";
+
+/// Indexes allowed by the `_index` clauses of a query.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct IndexIdFilter {
+    included_index_ids_opt: Option<BTreeSet<String>>,
+    excluded_index_ids: BTreeSet<String>,
+}
+
+impl IndexIdFilter {
+    fn include(&mut self, index_ids: BTreeSet<String>) {
+        let included_index_ids = match self.included_index_ids_opt.take() {
+            Some(included_index_ids) => included_index_ids
+                .intersection(&index_ids)
+                .cloned()
+                .collect(),
+            None => index_ids,
+        };
+        self.included_index_ids_opt = Some(included_index_ids);
+    }
+
+    fn exclude(&mut self, index_ids: BTreeSet<String>) {
+        self.excluded_index_ids.extend(index_ids);
+    }
+
+    fn is_noop(&self) -> bool {
+        self.included_index_ids_opt.is_none() && self.excluded_index_ids.is_empty()
+    }
+
+    fn matches(&self, index_id: &str) -> bool {
+        if self.excluded_index_ids.contains(index_id) {
+            return false;
+        }
+        match &self.included_index_ids_opt {
+            Some(included_index_ids) => included_index_ids.contains(index_id),
+            None => true,
+        }
+    }
+}
+
+/// Returns the index IDs matched by a clause only made of `_index` terms.
+fn index_pseudo_field_terms(query_ast: &QueryAst) -> Option<BTreeSet<String>> {
+    match query_ast {
+        QueryAst::Term(term_query) if term_query.field == INDEX_PSEUDO_FIELD_NAME => {
+            Some(BTreeSet::from([term_query.value.clone()]))
+        }
+        QueryAst::TermSet(term_set_query) if term_set_query.terms_per_field.len() == 1 => {
+            term_set_query
+                .terms_per_field
+                .get(INDEX_PSEUDO_FIELD_NAME)
+                .cloned()
+        }
+        QueryAst::Boost { underlying, .. } => index_pseudo_field_terms(underlying),
+        // The `terms` queries of the Elasticsearch API are converted into a union of term
+        // queries.
+        QueryAst::Bool(bool_query)
+            if bool_query.must.is_empty()
+                && bool_query.must_not.is_empty()
+                && bool_query.filter.is_empty()
+                && !bool_query.should.is_empty() =>
+        {
+            let mut index_ids = BTreeSet::new();
+            for should_query_ast in &bool_query.should {
+                index_ids.extend(index_pseudo_field_terms(should_query_ast)?);
+            }
+            Some(index_ids)
+        }
+        _ => None,
+    }
+}
+
+/// Rejects the clauses querying `_index` that cannot be evaluated when planning the search.
+struct UnsupportedIndexPseudoFieldClauses;
+
+impl UnsupportedIndexPseudoFieldClauses {
+    fn check(&self, field_name: &str) -> crate::Result<()> {
+        if field_name == INDEX_PSEUDO_FIELD_NAME {
+            return Err(SearchError::InvalidQuery(format!(
+                "field `{INDEX_PSEUDO_FIELD_NAME}` can only be queried with term or terms clauses \
+                 that must or must not match"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> QueryAstVisitor<'a> for UnsupportedIndexPseudoFieldClauses {
+    type Err = SearchError;
+
+    fn visit_term(&mut self, term_query: &'a TermQuery) -> crate::Result<()> {
+        self.check(&term_query.field)
+    }
+
+    fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> crate::Result<()> {
+        for field_name in term_set_query.terms_per_field.keys() {
+            self.check(field_name)?;
+        }
+        Ok(())
+    }
+
+    fn visit_full_text(&mut self, full_text_query: &'a FullTextQuery) -> crate::Result<()> {
+        self.check(&full_text_query.field)
+    }
+
+    fn visit_phrase_prefix(
+        &mut self,
+        phrase_prefix_query: &'a PhrasePrefixQuery,
+    ) -> crate::Result<()> {
+        self.check(&phrase_prefix_query.field)
+    }
+
+    fn visit_range(&mut self, range_query: &'a RangeQuery) -> crate::Result<()> {
+        self.check(&range_query.field)
+    }
+
+    fn visit_exists(&mut self, exists_query: &'a FieldPresenceQuery) -> crate::Result<()> {
+        self.check(&exists_query.field)
+    }
+
+    fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> crate::Result<()> {
+        self.check(&wildcard_query.field)
+    }
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> crate::Result<()> {
+        self.check(&regex_query.field)
+    }
+}
+
+/// Moves the `_index` clauses that must (not) match into the index ID filter. They are replaced
+/// with clauses matching all (no) documents, so that the meaning of the other clauses is left
+/// untouched.
+fn extract_index_id_filter(
+    query_ast: QueryAst,
+    index_id_filter: &mut IndexIdFilter,
+) -> crate::Result<QueryAst> {
+    if let Some(index_ids) = index_pseudo_field_terms(&query_ast) {
+        index_id_filter.include(index_ids);
+        return Ok(QueryAst::MatchAll);
+    }
+    match query_ast {
+        QueryAst::Bool(mut bool_query) => {
+            bool_query.must = bool_query
+                .must
+                .into_iter()
+                .map(|query_ast| extract_index_id_filter(query_ast, index_id_filter))
+                .collect::<crate::Result<_>>()?;
+            bool_query.filter = bool_query
+                .filter
+                .into_iter()
+                .map(|query_ast| extract_index_id_filter(query_ast, index_id_filter))
+                .collect::<crate::Result<_>>()?;
+            for must_not_query_ast in &mut bool_query.must_not {
+                if let Some(index_ids) = index_pseudo_field_terms(must_not_query_ast) {
+                    index_id_filter.exclude(index_ids);
+                    *must_not_query_ast = QueryAst::MatchNone;
+                } else {
+                    UnsupportedIndexPseudoFieldClauses.visit(must_not_query_ast)?;
+                }
+            }
+            for should_query_ast in &bool_query.should {
+                UnsupportedIndexPseudoFieldClauses.visit(should_query_ast)?;
+            }
+            Ok(QueryAst::Bool(bool_query))
+        }
+        QueryAst::Boost { underlying, boost } => {
+            let underlying = extract_index_id_filter(*underlying, index_id_filter)?;
+            Ok(QueryAst::Boost {
+                underlying: Box::new(underlying),
+                boost,
+            })
+        }
+        query_ast => {
+            UnsupportedIndexPseudoFieldClauses.visit(&query_ast)?;
+            Ok(query_ast)
+        }
+    }
+}
+
+/// Evaluates the `_index` clauses of the query of a search request: the indexes they rule out
+/// are removed from the search, and the clauses are removed from the query sent to the leaves.
+pub(crate) fn filter_indexes_on_index_pseudo_field(
+    mut indexes_metadata: Vec<IndexMetadata>,
+    search_request: &mut SearchRequest,
+) -> crate::Result<Vec<IndexMetadata>> {
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let mut index_id_filter = IndexIdFilter::default();
+    let query_ast = extract_index_id_filter(query_ast, &mut index_id_filter)?;
+
+    if index_id_filter.is_noop() {
+        return Ok(indexes_metadata);
+    }
+    search_request.query_ast = serde_json::to_string(&query_ast)?;
+    indexes_metadata.retain(|index_metadata| index_id_filter.matches(index_metadata.index_id()));
+    Ok(indexes_metadata)
+}
+
+/// Removes the `_index` sort field from a search request, and returns its sort order. The leaves
+/// sort the hits on the other sort fields.
+///
+/// The leaves cannot sort on `_index`, so it is only accepted as a tie-breaker: as the primary
+/// sort field, the top hits would be picked regardless of their index, and the order of the hits
+/// and the pagination would be wrong across splits.
+pub(crate) fn take_index_pseudo_field_sort(
+    search_request: &mut SearchRequest,
+) -> crate::Result<Option<SortOrder>> {
+    let Some(position) = search_request
+        .sort_fields
+        .iter()
+        .position(|sort_field| sort_field.field_name == INDEX_PSEUDO_FIELD_NAME)
+    else {
+        return Ok(None);
+    };
+    if position == 0 {
+        return Err(SearchError::InvalidArgument(format!(
+            "`{INDEX_PSEUDO_FIELD_NAME}` can only be used as a secondary sort field"
+        )));
+    }
+    if search_request.search_after.is_some() {
+        return Err(SearchError::InvalidArgument(format!(
+            "`search_after` is not supported when sorting on `{INDEX_PSEUDO_FIELD_NAME}`"
+        )));
+    }
+    let sort_field = search_request.sort_fields.remove(position);
+    Ok(Some(sort_field.sort_order()))
+}
+
+/// Sorts the hits tied on the other sort fields on the ID of their index. The hits come sorted on
+/// the other sort fields, so the ties are runs of consecutive hits.
+pub(crate) fn sort_hits_on_index_pseudo_field(hits: &mut [Hit], sort_order: SortOrder) {
+    for tied_hits in
+        hits.chunk_by_mut(|left_hit, right_hit| sort_values(left_hit) == sort_values(right_hit))
+    {
+        tied_hits.sort_by(|left_hit, right_hit| {
+            let ordering: Ordering = left_hit.index_id.cmp(&right_hit.index_id);
+            match sort_order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+    }
+}
+
+fn sort_values(hit: &Hit) -> (Option<&SortByValue>, Option<&SortByValue>) {
+    match &hit.partial_hit {
+        Some(partial_hit) => (
+            partial_hit.sort_value.as_ref(),
+            partial_hit.sort_value2.as_ref(),
+        ),
+        None => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::{PartialHit, SortField, SortValue};
+    use quickwit_query::query_ast::BoolQuery;
+
+    use super::*;
+
+    fn term(field: &str, value: &str) -> QueryAst {
+        TermQuery {
+            field: field.to_string(),
+            value: value.to_string(),
+        }
+        .into()
+    }
+
+    fn extract(query_ast: QueryAst) -> crate::Result<(QueryAst, IndexIdFilter)> {
+        let mut index_id_filter = IndexIdFilter::default();
+        let query_ast = extract_index_id_filter(query_ast, &mut index_id_filter)?;
+        Ok((query_ast, index_id_filter))
+    }
+
+    #[test]
+    fn test_extract_index_id_filter() {
+        {
+            let (query_ast, index_id_filter) = extract(term("body", "test")).unwrap();
+            assert_eq!(query_ast, term("body", "test"));
+            assert!(index_id_filter.is_noop());
+        }
+        {
+            let (query_ast, index_id_filter) = extract(term("_index", "index-1")).unwrap();
+            assert_eq!(query_ast, QueryAst::MatchAll);
+            assert!(index_id_filter.matches("index-1"));
+            assert!(!index_id_filter.matches("index-2"));
+        }
+        {
+            // A `terms` query of the Elasticsearch API within a filter.
+            let query_ast = QueryAst::Bool(BoolQuery {
+                must: vec![term("body", "test")],
+                filter: vec![QueryAst::Bool(BoolQuery {
+                    should: vec![term("_index", "index-1"), term("_index", "index-2")],
+                    ..Default::default()
+                })],
+                must_not: vec![term("_index", "index-2")],
+                ..Default::default()
+            });
+            let (query_ast, index_id_filter) = extract(query_ast).unwrap();
+            let expected_query_ast = QueryAst::Bool(BoolQuery {
+                must: vec![term("body", "test")],
+                filter: vec![QueryAst::MatchAll],
+                must_not: vec![QueryAst::MatchNone],
+                ..Default::default()
+            });
+            assert_eq!(query_ast, expected_query_ast);
+            assert!(index_id_filter.matches("index-1"));
+            assert!(!index_id_filter.matches("index-2"));
+            assert!(!index_id_filter.matches("index-3"));
+        }
+        {
+            // An `_index` clause that may not match cannot be evaluated when planning.
+            let query_ast = QueryAst::Bool(BoolQuery {
+                should: vec![term("body", "test"), term("_index", "index-1")],
+                ..Default::default()
+            });
+            let error = extract(query_ast).unwrap_err();
+            assert!(matches!(error, SearchError::InvalidQuery(_)));
+        }
+    }
+
+    #[test]
+    fn test_sort_on_index_pseudo_field() {
+        let index_sort_field = SortField {
+            field_name: "_index".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            ..Default::default()
+        };
+        let timestamp_sort_field = SortField {
+            field_name: "timestamp".to_string(),
+            sort_order: SortOrder::Asc as i32,
+            ..Default::default()
+        };
+        let mut search_request = SearchRequest {
+            sort_fields: vec![index_sort_field.clone(), timestamp_sort_field.clone()],
+            ..Default::default()
+        };
+        let error = take_index_pseudo_field_sort(&mut search_request).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let mut search_request = SearchRequest {
+            sort_fields: vec![timestamp_sort_field, index_sort_field],
+            ..Default::default()
+        };
+        let sort_order = take_index_pseudo_field_sort(&mut search_request)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sort_order, SortOrder::Desc);
+        assert_eq!(search_request.sort_fields.len(), 1);
+        assert_eq!(search_request.sort_fields[0].field_name, "timestamp");
+
+        let mut hits: Vec<Hit> = [
+            ("index-1", 1),
+            ("index-2", 1),
+            ("index-3", 2),
+            ("index-2", 3),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(ord, (index_id, timestamp))| Hit {
+            json: ord.to_string(),
+            partial_hit: Some(PartialHit {
+                sort_value: Some(SortValue::I64(timestamp).into()),
+                ..Default::default()
+            }),
+            index_id: index_id.to_string(),
+            ..Default::default()
+        })
+        .collect();
+        sort_hits_on_index_pseudo_field(&mut hits, sort_order);
+        let hits: Vec<(&str, &str)> = hits
+            .iter()
+            .map(|hit| (hit.index_id.as_str(), hit.json.as_str()))
+            .collect();
+        assert_eq!(
+            hits,
+            [
+                ("index-2", "1"),
+                ("index-1", "0"),
+                ("index-3", "2"),
+                ("index-2", "3")
+            ]
+        );
+    }
+}
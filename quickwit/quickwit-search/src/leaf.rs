@@ -17,93 +17,140 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::ops::Bound;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 
 use anyhow::Context;
 use futures::future::try_join_all;
+use quickwit_common::metrics::index_label;
 use quickwit_common::pretty::PrettySample;
+use quickwit_common::uri::Uri;
 use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
 use quickwit_doc_mapper::{DocMapper, TermRange, WarmupInfo};
 use quickwit_proto::search::{
-    CountHits, LeafSearchRequest, LeafSearchResponse, PartialHit, SearchRequest, SortOrder,
-    SortValue, SplitIdAndFooterOffsets, SplitSearchError,
+    CountHits, HistogramMissingDocCount, LeafSearchRequest, LeafSearchResponse, PartialHit,
+    SearchRequest, SortOrder, SortValue, SplitIdAndFooterOffsets, SplitSearchError,
+    SplitSearchErrorCode,
+};
+use quickwit_query::query_ast::{
+    BoolQuery, FieldPresenceQuery, QueryAst, QueryAstTransformer, RangeQuery, TermQuery,
 };
-use quickwit_query::query_ast::{BoolQuery, QueryAst, QueryAstTransformer, RangeQuery, TermQuery};
 use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::JsonLiteral;
 use quickwit_storage::{
-    wrap_storage_with_cache, BundleStorage, MemorySizedCache, OwnedBytes, SplitCache, Storage,
-    StorageResolver,
+    wrap_storage_with_cache, AsyncDebouncer, BundleStorage, MemorySizedCache, OwnedBytes,
+    SplitCache, Storage, StorageError, StorageErrorKind, StorageResolver, StorageResult,
 };
 use tantivy::aggregation::agg_req::{AggregationVariants, Aggregations};
 use tantivy::aggregation::AggregationLimitsGuard;
+use tantivy::columnar::Cardinality;
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
-use tantivy::schema::Field;
+use tantivy::schema::{Field, FieldType};
 use tantivy::{DateTime, Index, ReloadPolicy, Searcher, Term};
 use tokio::task::JoinError;
 use tracing::*;
 
+use crate::aggregation_spill::AggregationSpill;
 use crate::collector::{make_collector_for_split, make_merge_collector, IncrementalCollector};
+use crate::histogram_missing::{extract_histogram_missing, strip_histogram_missing};
+use crate::leaf_search_tiers::LeafSearchTierPermit;
 use crate::metrics::SEARCH_METRICS;
 use crate::root::is_metadata_count_request_with_ast;
+use crate::search_interrupt::{InterruptibleQuery, SearchInterrupt};
+use crate::search_memory::SearchMemoryBudget;
+use crate::search_permit_provider::SearchPermit;
 use crate::service::{deserialize_doc_mapper, SearcherContext};
 use crate::{QuickwitAggregations, SearchError};
 
+/// Returns whether opening a split failed because the split file is missing from the storage.
+fn is_split_not_found_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<StorageError>()
+            .is_some_and(|storage_error| storage_error.kind() == StorageErrorKind::NotFound)
+    })
+}
+
+/// Returns the footer of the split from the split footer cache, or fetches it from the storage.
+///
+/// Concurrent fetches of the footer of the same split share a single storage request. The
+/// returned flag is set if the footer was found in the cache.
 #[instrument(skip_all)]
 async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     footer_cache: &MemorySizedCache<String>,
-) -> anyhow::Result<OwnedBytes> {
+    footer_debouncer: &AsyncDebouncer<String, StorageResult<OwnedBytes>>,
+) -> anyhow::Result<(OwnedBytes, bool)> {
     {
         let possible_val = footer_cache.get(&split_and_footer_offsets.split_id);
         if let Some(footer_data) = possible_val {
-            return Ok(footer_data);
+            return Ok((footer_data, true));
         }
     }
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
-    let footer_data_opt = index_storage
-        .get_slice(
-            &split_file,
-            split_and_footer_offsets.split_footer_start as usize
-                ..split_and_footer_offsets.split_footer_end as usize,
+    let footer_range = split_and_footer_offsets.split_footer_start as usize
+        ..split_and_footer_offsets.split_footer_end as usize;
+    let mut is_fetch_initiator = false;
+    let index_storage_clone = index_storage.clone();
+    let footer_data_opt = footer_debouncer
+        .get_or_create(split_and_footer_offsets.split_id.clone(), || {
+            is_fetch_initiator = true;
+            async move {
+                index_storage_clone
+                    .get_slice(&split_file, footer_range)
+                    .await
+            }
+        })
+        .await;
+    if !is_fetch_initiator {
+        SEARCH_METRICS.split_footer_fetch_dedup_total.inc();
+    }
+    let footer_data_opt = footer_data_opt.with_context(|| {
+        format!(
+            "failed to fetch hotcache and footer from {} for split `{}`",
+            index_storage.uri(),
+            split_and_footer_offsets.split_id
         )
-        .await
-        .with_context(|| {
-            format!(
-                "failed to fetch hotcache and footer from {} for split `{}`",
-                index_storage.uri(),
-                split_and_footer_offsets.split_id
-            )
-        })?;
+    })?;
 
     footer_cache.put(
         split_and_footer_offsets.split_id.to_owned(),
         footer_data_opt.clone(),
     );
-
-    Ok(footer_data_opt)
+    Ok((footer_data_opt, false))
 }
 
 /// Returns hotcache_bytes and the split directory (`BundleStorage`) with cache layer:
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
-#[instrument(skip_all, fields(split_footer_start=split_and_footer_offsets.split_footer_start, split_footer_end=split_and_footer_offsets.split_footer_end))]
+///
+/// The span is tagged with `footer_cache_hit`, telling whether the split was opened from the split
+/// footer cache or had to be fetched from the storage.
+#[instrument(skip_all, fields(split_footer_start=split_and_footer_offsets.split_footer_start, split_footer_end=split_and_footer_offsets.split_footer_end, footer_cache_hit=field::Empty))]
 pub(crate) async fn open_split_bundle(
     searcher_context: &SearcherContext,
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
 ) -> anyhow::Result<(FileSlice, BundleStorage)> {
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
-    let footer_data = get_split_footer_from_cache_or_fetch(
+    let (footer_data, footer_cache_hit) = get_split_footer_from_cache_or_fetch(
         index_storage.clone(),
         split_and_footer_offsets,
         &searcher_context.split_footer_cache,
+        &searcher_context.split_footer_debouncer,
     )
     .await?;
+    Span::current().record("footer_cache_hit", footer_cache_hit);
+    debug!(
+        split_id = split_and_footer_offsets.split_id,
+        footer_cache_hit, "opened split bundle"
+    );
 
     // We wrap the top-level storage with the split cache.
     // This is before the bundle storage: at this point, this storage is reading `.split` files.
@@ -333,6 +380,150 @@ async fn warm_up_fieldnorms(searcher: &Searcher, requires_scoring: bool) -> anyh
     Ok(())
 }
 
+/// Shortcut taken by the leaf search of a split, recorded in the `tantivy_search` span and in
+/// the `leaf_search_fast_path_total` metric.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LeafSearchFastPath {
+    /// The count is read from the split metadata.
+    MetadataCount,
+    /// The count is computed from the timestamp fast field, without running the query.
+    TimestampRangeCount,
+    /// The count is computed by the query, without collecting hits.
+    QueryCount,
+    /// Only the aggregations are collected, no hits.
+    AggregationsOnly,
+    /// Hits are collected.
+    Hits,
+}
+
+impl LeafSearchFastPath {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LeafSearchFastPath::MetadataCount => "metadata_count",
+            LeafSearchFastPath::TimestampRangeCount => "timestamp_range_count",
+            LeafSearchFastPath::QueryCount => "query_count",
+            LeafSearchFastPath::AggregationsOnly => "aggregations_only",
+            LeafSearchFastPath::Hits => "hits",
+        }
+    }
+
+    fn record(&self, span: &Span) {
+        span.record("fast_path", self.as_str());
+        SEARCH_METRICS
+            .leaf_search_fast_path_total
+            .with_label_values([self.as_str()])
+            .inc();
+    }
+}
+
+/// Returns the bounds, expressed in nanoseconds, of a query matching all the documents within a
+/// time range, as rewritten by [`remove_redundant_timestamp_range`].
+fn extract_match_all_timestamp_range(
+    query_ast: &QueryAst,
+    timestamp_field: &str,
+) -> Option<(Bound<i64>, Bound<i64>)> {
+    let QueryAst::Bool(bool_query) = query_ast else {
+        return None;
+    };
+    if !bool_query.must_not.is_empty() || !bool_query.should.is_empty() {
+        return None;
+    }
+    if !bool_query
+        .must
+        .iter()
+        .all(|must_query| *must_query == QueryAst::MatchAll)
+    {
+        return None;
+    }
+    let mut filters = bool_query
+        .filter
+        .iter()
+        .filter(|filter_query| **filter_query != QueryAst::MatchAll);
+    let (Some(QueryAst::Range(range_query)), None) = (filters.next(), filters.next()) else {
+        return None;
+    };
+    if range_query.field != timestamp_field {
+        return None;
+    }
+    let convert_bound = |bound: &Bound<JsonLiteral>| -> Option<Bound<i64>> {
+        match bound {
+            Bound::Included(JsonLiteral::Number(number)) => number.as_i64().map(Bound::Included),
+            Bound::Excluded(JsonLiteral::Number(number)) => number.as_i64().map(Bound::Excluded),
+            Bound::Unbounded => Some(Bound::Unbounded),
+            _ => None,
+        }
+    };
+    let lower_bound = convert_bound(&range_query.lower_bound)?;
+    let upper_bound = convert_bound(&range_query.upper_bound)?;
+    Some((lower_bound, upper_bound))
+}
+
+/// Counts the documents whose timestamp falls within the given bounds by scanning the timestamp
+/// fast field, without scoring.
+///
+/// Returns `None` if the fast field cannot be used, in which case the query must be run.
+fn count_timestamp_range_with_fast_field(
+    searcher: &Searcher,
+    timestamp_field: &str,
+    (lower_bound, upper_bound): (Bound<i64>, Bound<i64>),
+) -> crate::Result<Option<u64>> {
+    let schema = searcher.schema();
+    let Ok(field) = schema.get_field(timestamp_field) else {
+        return Ok(None);
+    };
+    let FieldType::Date(date_options) = schema.get_field_entry(field).field_type() else {
+        return Ok(None);
+    };
+    // Mirrors the range query, which truncates its bounds to the precision of the fast field.
+    let precision = date_options.get_precision();
+    let truncate = |timestamp_nanos: i64| {
+        DateTime::from_timestamp_nanos(timestamp_nanos)
+            .truncate(precision)
+            .into_timestamp_nanos()
+    };
+    let lower_bound_nanos = match lower_bound {
+        Bound::Included(timestamp_nanos) => truncate(timestamp_nanos),
+        Bound::Excluded(timestamp_nanos) => truncate(timestamp_nanos).saturating_add(1),
+        Bound::Unbounded => i64::MIN,
+    };
+    let upper_bound_nanos = match upper_bound {
+        Bound::Included(timestamp_nanos) => truncate(timestamp_nanos),
+        Bound::Excluded(timestamp_nanos) => truncate(timestamp_nanos).saturating_sub(1),
+        Bound::Unbounded => i64::MAX,
+    };
+    if lower_bound_nanos > upper_bound_nanos {
+        return Ok(Some(0));
+    }
+    let timestamp_range = DateTime::from_timestamp_nanos(lower_bound_nanos)
+        ..=DateTime::from_timestamp_nanos(upper_bound_nanos);
+
+    let mut count = 0;
+    let mut doc_ids = Vec::new();
+
+    for segment_reader in searcher.segment_readers() {
+        if segment_reader.alive_bitset().is_some() {
+            return Ok(None);
+        }
+        let Some(column) = segment_reader
+            .fast_fields()
+            .column_opt::<DateTime>(timestamp_field)?
+        else {
+            continue;
+        };
+        if column.get_cardinality() == Cardinality::Multivalued {
+            return Ok(None);
+        }
+        doc_ids.clear();
+        column.get_docids_for_value_range(
+            timestamp_range.clone(),
+            0..segment_reader.max_doc(),
+            &mut doc_ids,
+        );
+        count += doc_ids.len() as u64;
+    }
+    Ok(Some(count))
+}
+
 fn get_leaf_resp_from_count(count: u64) -> LeafSearchResponse {
     LeafSearchResponse {
         num_hits: count,
@@ -341,10 +532,13 @@ fn get_leaf_resp_from_count(count: u64) -> LeafSearchResponse {
         num_attempted_splits: 1,
         num_successful_splits: 1,
         intermediate_aggregation_result: None,
+        histogram_missing_doc_counts: Vec::new(),
+        cpu_time_micros: 0,
     }
 }
 
 /// Apply a leaf search on a single split.
+#[allow(clippy::too_many_arguments)]
 async fn leaf_search_single_split(
     searcher_context: &SearcherContext,
     mut search_request: SearchRequest,
@@ -353,7 +547,11 @@ async fn leaf_search_single_split(
     doc_mapper: Arc<dyn DocMapper>,
     split_filter: Arc<RwLock<CanSplitDoBetter>>,
     aggregations_limits: AggregationLimitsGuard,
+    memory_budget: SearchMemoryBudget,
+    search_interrupt: SearchInterrupt,
+    leaf_search_tier_permit: LeafSearchTierPermit,
 ) -> crate::Result<LeafSearchResponse> {
+    search_interrupt.check()?;
     rewrite_request(
         &mut search_request,
         &split,
@@ -374,10 +572,12 @@ async fn leaf_search_single_split(
     // split can't have better results.
     //
     if is_metadata_count_request_with_ast(&query_ast, &search_request) {
+        LeafSearchFastPath::MetadataCount.record(&Span::current());
         return Ok(get_leaf_resp_from_count(split.num_docs));
     }
 
     let split_id = split.split_id.to_string();
+    let index_uri = storage.uri().as_str().to_string();
     let index = open_index_with_caches(
         searcher_context,
         storage,
@@ -385,7 +585,16 @@ async fn leaf_search_single_split(
         Some(doc_mapper.tokenizer_manager()),
         true,
     )
-    .await?;
+    .await
+    .map_err(|error| {
+        if is_split_not_found_error(&error) {
+            SearchError::SplitNotFound {
+                split_id: split_id.clone(),
+            }
+        } else {
+            SearchError::from(error)
+        }
+    })?;
     let split_schema = index.schema();
 
     let reader = index
@@ -393,43 +602,139 @@ async fn leaf_search_single_split(
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = reader.searcher();
+    let num_segments = searcher.segment_readers().len();
+    Span::current().record("num_segments", num_segments);
+    SEARCH_METRICS
+        .leaf_search_split_num_segments
+        .observe(num_segments as f64);
 
-    let mut collector =
-        make_collector_for_split(split_id.clone(), &search_request, aggregations_limits)?;
+    let mut collector = make_collector_for_split(
+        split_id.clone(),
+        &search_request,
+        aggregations_limits,
+        memory_budget,
+    )?;
 
     let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
 
+    // The matching documents lacking the field of a histogram with a `missing` parameter are
+    // counted separately, and added to the bucket of the missing value by the root.
+    let mut histogram_missing_queries = Vec::new();
+    if let Some(aggregation_json) = &search_request.aggregation_request {
+        for histogram_missing in extract_histogram_missing(aggregation_json)? {
+            let missing_query_ast: QueryAst = BoolQuery {
+                must: vec![query_ast.clone()],
+                must_not: vec![FieldPresenceQuery {
+                    field: histogram_missing.field,
+                }
+                .into()],
+                ..Default::default()
+            }
+            .into();
+            let (missing_query, missing_warmup_info) =
+                doc_mapper.query(split_schema.clone(), &missing_query_ast, false)?;
+            warmup_info.merge(missing_warmup_info);
+            histogram_missing_queries.push((histogram_missing.aggregation_name, missing_query));
+        }
+    }
+
     let collector_warmup_info = collector.warmup_info();
     warmup_info.merge(collector_warmup_info);
     warmup_info.simplify();
 
     warmup(&searcher, &warmup_info).await?;
-    let span = info_span!("tantivy_search");
+
+    let timestamp_range_opt = doc_mapper
+        .timestamp_field_name()
+        .and_then(|timestamp_field| {
+            extract_match_all_timestamp_range(&query_ast, timestamp_field)
+                .map(|timestamp_range| (timestamp_field.to_string(), timestamp_range))
+        });
+    let span = info_span!(
+        "tantivy_search",
+        fast_path = field::Empty,
+        num_docs_scanned = field::Empty
+    );
+    let searcher_config = searcher_context.searcher_config();
+    let query = InterruptibleQuery::new(
+        query,
+        search_interrupt.clone(),
+        searcher_config.collection_checkpoint_num_docs,
+        searcher_config.yield_at_collection_checkpoints,
+    );
 
     let (search_request, leaf_search_response) = {
         let split = split.clone();
 
-        crate::search_thread_pool()
-            .run_cpu_intensive(move || {
-                let _span_guard = span.enter();
-                // Our search execution has been scheduled, let's check if we can improve the
-                // request based on the results of the preceding searches
-                check_optimize_search_request(&mut search_request, &split, &split_filter);
-                collector.update_search_param(&search_request);
-                if is_metadata_count_request_with_ast(&query_ast, &search_request) {
-                    return Ok((
-                        search_request,
-                        get_leaf_resp_from_count(searcher.num_docs() as u64),
-                    ));
-                }
-                if collector.is_count_only() {
-                    let count = query.count(&searcher)? as u64;
-                    Ok((search_request, get_leaf_resp_from_count(count)))
-                } else {
-                    searcher
-                        .search(&query, &collector)
-                        .map(|resp| (search_request, resp))
-                }
+        searcher_context
+            .leaf_search_tiers
+            .run_cpu_intensive(leaf_search_tier_permit, move || {
+                // The split was warmed up beforehand, so the search does not wait on any IO and the
+                // time spent on the search thread approximates the CPU time of the search.
+                let cpu_time_start = Instant::now();
+                let search_split = move || {
+                    let _span_guard = span.enter();
+                    // The request may have been cancelled while the split search was queued.
+                    search_interrupt.check()?;
+                    // Our search execution has been scheduled, let's check if we can improve the
+                    // request based on the results of the preceding searches
+                    check_optimize_search_request(&mut search_request, &split, &split_filter);
+                    collector.update_search_param(&search_request)?;
+                    if is_metadata_count_request_with_ast(&query_ast, &search_request) {
+                        LeafSearchFastPath::MetadataCount.record(&span);
+                        return Ok((
+                            search_request,
+                            get_leaf_resp_from_count(searcher.num_docs() as u64),
+                        ));
+                    }
+                    if collector.is_count_only() {
+                        if let Some((timestamp_field, timestamp_range)) = timestamp_range_opt {
+                            if let Some(count) = count_timestamp_range_with_fast_field(
+                                &searcher,
+                                &timestamp_field,
+                                timestamp_range,
+                            )? {
+                                LeafSearchFastPath::TimestampRangeCount.record(&span);
+                                return Ok((search_request, get_leaf_resp_from_count(count)));
+                            }
+                        }
+                        LeafSearchFastPath::QueryCount.record(&span);
+                        let count = query.count(&searcher)? as u64;
+                        search_interrupt.check()?;
+                        return Ok((search_request, get_leaf_resp_from_count(count)));
+                    }
+                    if collector.is_aggregation_only() {
+                        LeafSearchFastPath::AggregationsOnly.record(&span);
+                    } else {
+                        LeafSearchFastPath::Hits.record(&span);
+                    }
+                    // The collectors abort the search with an opaque error when the request runs
+                    // out of memory.
+                    let search_res = searcher.search(&query, &collector);
+                    span.record("num_docs_scanned", query.num_docs_scanned());
+                    // The results of an interrupted search are partial.
+                    search_interrupt.check()?;
+                    let mut leaf_search_response = match search_res {
+                        Ok(leaf_search_response) => leaf_search_response,
+                        Err(tantivy_error) => match collector.memory_budget.limit_exceeded() {
+                            Some(limit_exceeded) => return Err(limit_exceeded.into()),
+                            None => return Err(tantivy_error.into()),
+                        },
+                    };
+                    for (aggregation_name, missing_query) in histogram_missing_queries {
+                        let num_docs = missing_query.count(&searcher)? as u64;
+                        leaf_search_response.histogram_missing_doc_counts.push(
+                            HistogramMissingDocCount {
+                                aggregation_name,
+                                num_docs,
+                            },
+                        );
+                    }
+                    crate::Result::Ok((search_request, leaf_search_response))
+                };
+                let (search_request, mut leaf_search_response) = search_split()?;
+                leaf_search_response.cpu_time_micros = cpu_time_start.elapsed().as_micros() as u64;
+                crate::Result::Ok((search_request, leaf_search_response))
             })
             .await
             .map_err(|_| {
@@ -437,9 +742,18 @@ async fn leaf_search_single_split(
             })??
     };
 
+    SEARCH_METRICS
+        .search_cpu_seconds_total
+        .with_label_values([index_label(&index_uri)])
+        .inc_by(leaf_search_response.cpu_time_micros as f64 / 1_000_000.0);
+    // Serving the response from the cache does not cost the CPU time of the search.
+    let cached_leaf_search_response = LeafSearchResponse {
+        cpu_time_micros: 0,
+        ..leaf_search_response.clone()
+    };
     searcher_context
         .leaf_search_cache
-        .put(split, search_request, leaf_search_response.clone());
+        .put(split, search_request, cached_leaf_search_response);
     Ok(leaf_search_response)
 }
 
@@ -468,6 +782,11 @@ fn rewrite_request(
 /// mostly `extended_bounds`.
 fn rewrite_aggregation(search_request: &mut SearchRequest) {
     if let Some(aggregation) = &search_request.aggregation_request {
+        // Re-serializing the aggregations would drop the `missing` parameter of histograms,
+        // which tantivy does not know about.
+        if matches!(strip_histogram_missing(aggregation), Cow::Owned(_)) {
+            return;
+        }
         let Ok(QuickwitAggregations::TantivyAggregations(mut aggregations)) =
             serde_json::from_str(aggregation)
         else {
@@ -583,22 +902,7 @@ fn remove_redundant_timestamp_range(
         return;
     };
 
-    let start_timestamp = search_request
-        .start_timestamp
-        .map(DateTime::from_timestamp_secs)
-        .map(Bound::Included)
-        .unwrap_or(Bound::Unbounded);
-    let end_timestamp = search_request
-        .end_timestamp
-        .map(DateTime::from_timestamp_secs)
-        .map(Bound::Excluded)
-        .unwrap_or(Bound::Unbounded);
-
-    let mut visitor = RemoveTimestampRange {
-        timestamp_field,
-        start_timestamp,
-        end_timestamp,
-    };
+    let mut visitor = RemoveTimestampRange::new(search_request, timestamp_field);
     let mut new_ast = visitor
         .transform(query_ast)
         .expect("can't fail unwrapping Infallible")
@@ -690,13 +994,33 @@ fn remove_redundant_timestamp_range(
 
 /// Remove all `must` and `filter timestamp ranges, and summarize them
 #[derive(Debug, Clone)]
-struct RemoveTimestampRange<'a> {
+pub(crate) struct RemoveTimestampRange<'a> {
     timestamp_field: &'a str,
-    start_timestamp: Bound<DateTime>,
-    end_timestamp: Bound<DateTime>,
+    pub(crate) start_timestamp: Bound<DateTime>,
+    pub(crate) end_timestamp: Bound<DateTime>,
 }
 
 impl<'a> RemoveTimestampRange<'a> {
+    /// Creates a visitor whose time range is initialized with the `start_timestamp` and
+    /// `end_timestamp` of the search request.
+    pub(crate) fn new(search_request: &SearchRequest, timestamp_field: &'a str) -> Self {
+        let start_timestamp = search_request
+            .start_timestamp
+            .map(DateTime::from_timestamp_secs)
+            .map(Bound::Included)
+            .unwrap_or(Bound::Unbounded);
+        let end_timestamp = search_request
+            .end_timestamp
+            .map(DateTime::from_timestamp_secs)
+            .map(Bound::Excluded)
+            .unwrap_or(Bound::Unbounded);
+        Self {
+            timestamp_field,
+            start_timestamp,
+            end_timestamp,
+        }
+    }
+
     fn update_start_timestamp(
         &mut self,
         lower_bound: &quickwit_query::JsonLiteral,
@@ -1075,6 +1399,11 @@ pub async fn multi_leaf_search(
         .collect::<crate::Result<_>>()?;
     // Creates a collector which merges responses into one
     let aggregation_limits = searcher_context.get_aggregation_limits();
+    let memory_budget = searcher_context.new_leaf_request_memory_budget();
+    // The split searches stop at their next collection checkpoint once the request times out or
+    // this future is dropped, for instance because the root searcher gave up on the request.
+    let search_interrupt = searcher_context.new_leaf_request_interrupt();
+    let _cancel_on_drop = search_interrupt.cancel_on_drop();
     // TODO: to avoid lockstep, we should pull up the future creation over the list of split ids
     // and have the semaphore on this level.
     // This will lower resource consumption due to less in-flight futures and avoid contention.
@@ -1115,6 +1444,8 @@ pub async fn multi_leaf_search(
                 leaf_search_request_ref.split_offsets,
                 doc_mapper,
                 aggregation_limits.clone(),
+                memory_budget.clone(),
+                search_interrupt.clone(),
             )
             .in_current_span(),
         );
@@ -1122,7 +1453,7 @@ pub async fn multi_leaf_search(
     }
 
     let leaf_responses: Vec<crate::Result<LeafSearchResponse>> = tokio::time::timeout(
-        searcher_context.searcher_config.request_timeout(),
+        searcher_context.searcher_config().request_timeout(),
         try_join_all(leaf_request_tasks),
     )
     .await??;
@@ -1138,6 +1469,7 @@ pub async fn multi_leaf_search(
                     split_id: "unknown".to_string(),
                     error: format!("{}", err),
                     retryable_error: true,
+                    ..Default::default()
                 });
             }
         }
@@ -1160,6 +1492,8 @@ async fn resolve_storage_and_leaf_search(
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
     aggregations_limits: AggregationLimitsGuard,
+    memory_budget: SearchMemoryBudget,
+    search_interrupt: SearchInterrupt,
 ) -> crate::Result<LeafSearchResponse> {
     let storage = storage_resolver.resolve(&index_uri).await?;
 
@@ -1170,6 +1504,8 @@ async fn resolve_storage_and_leaf_search(
         splits,
         doc_mapper,
         aggregations_limits,
+        memory_budget,
+        search_interrupt,
     )
     .await
 }
@@ -1188,6 +1524,18 @@ fn check_optimize_search_request(
     can_be_better
 }
 
+/// Returns true if the splits searched so far matched more hits than the `track_total_hits`
+/// threshold of the request.
+fn is_track_total_hits_reached(
+    search_request: &SearchRequest,
+    incremental_merge_collector: &Mutex<IncrementalCollector>,
+) -> bool {
+    let Some(track_total_hits) = search_request.track_total_hits else {
+        return false;
+    };
+    incremental_merge_collector.lock().unwrap().num_hits() > track_total_hits
+}
+
 /// Alter the search request so it does not return any docs.
 ///
 /// This is usually done since it cannot provide better hits results than existing fetched results.
@@ -1211,6 +1559,8 @@ pub async fn leaf_search(
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
     aggregations_limits: AggregationLimitsGuard,
+    memory_budget: SearchMemoryBudget,
+    search_interrupt: SearchInterrupt,
 ) -> Result<LeafSearchResponse, SearchError> {
     let num_docs: u64 = splits.iter().map(|split| split.num_docs).sum();
     let num_splits = splits.len();
@@ -1225,27 +1575,57 @@ pub async fn leaf_search(
 
     // if client wants full count, or we are doing an aggregation, we want to run every splits.
     // However if the aggregation is the tracing aggregation, we don't actually need all splits.
-    let run_all_splits = request.count_hits() == CountHits::CountAll
-        || (request.aggregation_request.is_some()
-            && !matches!(split_filter, CanSplitDoBetter::FindTraceIdsAggregation(_)));
+    let count_all_hits = request.count_hits() == CountHits::CountAll;
+    let run_all_splits_for_aggregation = request.aggregation_request.is_some()
+        && !matches!(split_filter, CanSplitDoBetter::FindTraceIdsAggregation(_));
 
     let split_filter = Arc::new(RwLock::new(split_filter));
+    let leaf_search_tier = searcher_context
+        .leaf_search_tiers
+        .classify(&request, num_splits);
 
     let mut leaf_search_single_split_join_handles: Vec<(String, tokio::task::JoinHandle<()>)> =
         Vec::with_capacity(split_with_req.len());
 
     let merge_collector = make_merge_collector(&request, &aggregations_limits)?;
-    let incremental_merge_collector = IncrementalCollector::new(merge_collector);
+    let mut incremental_merge_collector = IncrementalCollector::new(merge_collector);
+    if request.allow_disk_use {
+        if let Some(aggregation_spill_disk) = &searcher_context.aggregation_spill_disk_opt {
+            let in_memory_threshold = searcher_context
+                .searcher_config()
+                .aggregation_spill
+                .in_memory_threshold;
+            let aggregation_spill =
+                AggregationSpill::new(aggregation_spill_disk.clone(), in_memory_threshold);
+            incremental_merge_collector =
+                incremental_merge_collector.with_aggregation_spill(aggregation_spill);
+        }
+    }
     let incremental_merge_collector = Arc::new(Mutex::new(incremental_merge_collector));
 
     for (split, mut request) in split_with_req {
-        let leaf_split_search_permit = searcher_context.leaf_search_split_semaphore
-            .clone()
-            .acquire_owned()
-            .instrument(info_span!("waiting_for_leaf_search_split_semaphore"))
-            .await
-            .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
-
+        // The tier is picked first, so that a saturated tier does not hold split search permits
+        // the other tier could use.
+        let leaf_search_tier_permit = searcher_context
+            .leaf_search_tiers
+            .acquire(leaf_search_tier)
+            .instrument(info_span!("waiting_for_leaf_search_tier_permit"))
+            .await;
+        let permit_wait_start = Instant::now();
+        let leaf_split_search_permit = searcher_context
+            .search_permit_provider
+            .get_permit(index_storage.uri().as_str())
+            .instrument(info_span!("waiting_for_leaf_search_split_permit"))
+            .await;
+        searcher_context
+            .load_shedder
+            .record_permit_wait(permit_wait_start.elapsed());
+
+        // Once more hits than `track_total_hits` were counted, the remaining splits only need to
+        // be searched if they can improve the top hits.
+        let run_all_splits = run_all_splits_for_aggregation
+            || (count_all_hits
+                && !is_track_total_hits_reached(&request, &incremental_merge_collector));
         let can_be_better = check_optimize_search_request(&mut request, &split, &split_filter);
         if !can_be_better && !run_all_splits {
             continue;
@@ -1264,6 +1644,9 @@ pub async fn leaf_search(
                     incremental_merge_collector.clone(),
                     leaf_split_search_permit,
                     aggregations_limits.clone(),
+                    memory_budget.clone(),
+                    search_interrupt.clone(),
+                    leaf_search_tier_permit,
                 )
                 .in_current_span(),
             ),
@@ -1302,6 +1685,7 @@ pub async fn leaf_search(
             split_id,
             error: SearchError::from(split_search_join_error).to_string(),
             retryable_error: true,
+            ..Default::default()
         });
     }
 
@@ -1325,7 +1709,7 @@ pub async fn leaf_search(
 }
 
 #[allow(clippy::too_many_arguments)]
-#[instrument(skip_all, fields(split_id = split.split_id, num_docs = split.num_docs))]
+#[instrument(skip_all, fields(split_id = split.split_id, num_docs = split.num_docs, num_segments = field::Empty))]
 async fn leaf_search_single_split_wrapper(
     request: SearchRequest,
     searcher_context: Arc<SearcherContext>,
@@ -1334,13 +1718,23 @@ async fn leaf_search_single_split_wrapper(
     split: SplitIdAndFooterOffsets,
     split_filter: Arc<RwLock<CanSplitDoBetter>>,
     incremental_merge_collector: Arc<Mutex<IncrementalCollector>>,
-    leaf_split_search_permit: tokio::sync::OwnedSemaphorePermit,
+    leaf_split_search_permit: SearchPermit,
     aggregations_limits: AggregationLimitsGuard,
+    memory_budget: SearchMemoryBudget,
+    search_interrupt: SearchInterrupt,
+    leaf_search_tier_permit: LeafSearchTierPermit,
 ) {
     crate::SEARCH_METRICS.leaf_searches_splits_total.inc();
     let timer = crate::SEARCH_METRICS
         .leaf_search_split_duration_secs
         .start_timer();
+    let index_uri = index_storage.uri().clone();
+    // The split is pinned in the split cache for the duration of the search, so that its file does
+    // not get evicted while we are reading it.
+    let _split_pin_opt = searcher_context
+        .split_cache_opt
+        .as_ref()
+        .and_then(|split_cache| SplitCache::pin_split(split_cache.clone(), &split.split_id));
     let leaf_search_single_split_res = leaf_search_single_split(
         &searcher_context,
         request,
@@ -1349,17 +1743,22 @@ async fn leaf_search_single_split_wrapper(
         doc_mapper,
         split_filter.clone(),
         aggregations_limits,
+        memory_budget,
+        search_interrupt,
+        leaf_search_tier_permit,
     )
     .await;
 
-    // We explicitly drop it, to highlight it to the reader
-    std::mem::drop(leaf_split_search_permit);
-
     if leaf_search_single_split_res.is_ok() {
         timer.observe_duration();
     }
 
+    if let Err(SearchError::SplitNotFound { .. }) = &leaf_search_single_split_res {
+        report_missing_split(&index_uri, &split.split_id);
+    }
+
     let mut locked_incremental_merge_collector = incremental_merge_collector.lock().unwrap();
+    let mut aggregation_run_to_spill_opt = None;
     match leaf_search_single_split_res {
         Ok(split_search_res) => {
             if let Err(err) = locked_incremental_merge_collector.add_result(split_search_res) {
@@ -1367,14 +1766,34 @@ async fn leaf_search_single_split_wrapper(
                     split_id: split.split_id.clone(),
                     error: format!("Error parsing aggregation result: {err}"),
                     retryable_error: true,
+                    ..Default::default()
                 });
             }
+            match locked_incremental_merge_collector.take_aggregation_run_to_spill() {
+                Ok(aggregation_run_to_spill) => {
+                    aggregation_run_to_spill_opt = aggregation_run_to_spill;
+                }
+                Err(error) => {
+                    // The buffered results are left untouched, they are merged in memory.
+                    warn!(%error, "failed to merge intermediate aggregation results to spill");
+                }
+            }
+        }
+        Err(err) => {
+            let error_code = match &err {
+                SearchError::MemoryLimitExceeded(_) => SplitSearchErrorCode::MemoryLimitExceeded,
+                SearchError::SplitNotFound { .. } => SplitSearchErrorCode::SplitNotFound,
+                _ => SplitSearchErrorCode::Unspecified,
+            };
+            // Searching a missing split again on another searcher would fail the same way.
+            let retryable_error = error_code != SplitSearchErrorCode::SplitNotFound;
+            locked_incremental_merge_collector.add_failed_split(SplitSearchError {
+                split_id: split.split_id.clone(),
+                error: format!("{err}"),
+                retryable_error,
+                error_code: error_code as i32,
+            })
         }
-        Err(err) => locked_incremental_merge_collector.add_failed_split(SplitSearchError {
-            split_id: split.split_id.clone(),
-            error: format!("{err}"),
-            retryable_error: true,
-        }),
     }
     if let Some(last_hit) = locked_incremental_merge_collector.peek_worst_hit() {
         // TODO: we could use the RWLock instead and read the value instead of updating it
@@ -1384,14 +1803,161 @@ async fn leaf_search_single_split_wrapper(
             .unwrap()
             .record_new_worst_hit(last_hit.as_ref());
     }
+    drop(locked_incremental_merge_collector);
+
+    if let Some((aggregation_spill, run)) = aggregation_run_to_spill_opt {
+        spill_aggregation_run(aggregation_spill, run, &incremental_merge_collector).await;
+    }
+
+    // We explicitly drop it, to highlight it to the reader. The permit is only released once the
+    // result of the split is recorded, so that the split searches scheduled next can rely on it to
+    // skip splits.
+    std::mem::drop(leaf_split_search_permit);
+}
+
+/// Writes a run of intermediate aggregation results to the spill file on a blocking thread, without
+/// holding the collector. The run is handed back to the collector if it could not be spilled.
+async fn spill_aggregation_run(
+    aggregation_spill: AggregationSpill,
+    run: Vec<u8>,
+    incremental_merge_collector: &Mutex<IncrementalCollector>,
+) {
+    let run = Arc::new(run);
+    let run_clone = run.clone();
+    let spill_res =
+        tokio::task::spawn_blocking(move || aggregation_spill.spill(&run_clone[..])).await;
+    match spill_res {
+        Ok(Ok(true)) => return,
+        // The disk budget is exhausted, the run is kept in memory.
+        Ok(Ok(false)) => {}
+        Ok(Err(io_error)) => {
+            warn!(error=%io_error, "failed to spill intermediate aggregation results");
+        }
+        Err(join_error) => {
+            error!(error=%join_error, "aggregation spill task panicked");
+        }
+    }
+    let run = Arc::try_unwrap(run).unwrap_or_else(|run| run.to_vec());
+    incremental_merge_collector
+        .lock()
+        .unwrap()
+        .restore_unspilled_aggregation_run(run);
+}
+
+/// Records a split whose file is missing from the storage and, if the searcher is configured to do
+/// so, notifies the janitor so that it removes the split from the metastore.
+fn report_missing_split(index_uri: &Uri, split_id: &str) {
+    SEARCH_METRICS.split_not_found_total.inc();
+    warn!(index_uri=%index_uri, split_id, "split file not found in storage");
 }
 
 #[cfg(test)]
 mod tests {
-    use std::ops::Bound;
+    use std::ops::{Bound, RangeBounds};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use bytesize::ByteSize;
+    use quickwit_config::SearcherConfig;
+    use quickwit_query::query_ast::qast_json_helper;
+    use quickwit_storage::MockStorage;
 
     use super::*;
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_get_split_footer_from_cache_or_fetch_dedups_concurrent_fetches() {
+        let mut mock_storage = MockStorage::default();
+        mock_storage
+            .expect_get_slice()
+            .times(1)
+            .returning(|path, range| {
+                assert_eq!(path, Path::new("split-1.split"));
+                assert_eq!(range, 100..200);
+                // Keeps the fetch in flight while the other searches start.
+                std::thread::sleep(Duration::from_millis(200));
+                Ok(OwnedBytes::new(vec![1u8; 100]))
+            });
+        let index_storage: Arc<dyn Storage> = Arc::new(mock_storage);
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        let split_and_footer_offsets = SplitIdAndFooterOffsets {
+            split_id: "split-1".to_string(),
+            split_footer_start: 100,
+            split_footer_end: 200,
+            ..Default::default()
+        };
+        let fetch_tasks = (0..10).map(|_| {
+            let index_storage = index_storage.clone();
+            let searcher_context = searcher_context.clone();
+            let split_and_footer_offsets = split_and_footer_offsets.clone();
+            tokio::spawn(async move {
+                get_split_footer_from_cache_or_fetch(
+                    index_storage,
+                    &split_and_footer_offsets,
+                    &searcher_context.split_footer_cache,
+                    &searcher_context.split_footer_debouncer,
+                )
+                .await
+            })
+        });
+        for footer_data_res in futures::future::join_all(fetch_tasks).await {
+            let (footer_data, _) = footer_data_res.unwrap().unwrap();
+            assert_eq!(footer_data.as_slice(), &[1u8; 100]);
+        }
+        assert!(searcher_context.split_footer_cache.get("split-1").is_some());
+        assert_eq!(searcher_context.split_footer_debouncer.len(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_get_split_footer_from_cache_or_fetch_hit_miss_and_eviction() {
+        let mut mock_storage = MockStorage::default();
+        mock_storage
+            .expect_get_slice()
+            .times(3)
+            .returning(|_, _| Ok(OwnedBytes::new(vec![1u8; 100])));
+        let index_storage: Arc<dyn Storage> = Arc::new(mock_storage);
+        // The cache can only hold one footer.
+        let searcher_config = SearcherConfig {
+            split_footer_cache_capacity: ByteSize::b(150),
+            ..Default::default()
+        };
+        let searcher_context = SearcherContext::new(searcher_config, None);
+        let split_footer = |split_id: &str| SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: 100,
+            split_footer_end: 200,
+            ..Default::default()
+        };
+        let is_cache_hit = |split_and_footer_offsets: SplitIdAndFooterOffsets| {
+            let index_storage = index_storage.clone();
+            let searcher_context = &searcher_context;
+            async move {
+                let (_, is_cache_hit) = get_split_footer_from_cache_or_fetch(
+                    index_storage,
+                    &split_and_footer_offsets,
+                    &searcher_context.split_footer_cache,
+                    &searcher_context.split_footer_debouncer,
+                )
+                .await
+                .unwrap();
+                is_cache_hit
+            }
+        };
+        let evictions_counter = &quickwit_storage::STORAGE_METRICS
+            .split_footer_cache
+            .evictions_num_items;
+        let num_evictions_before = evictions_counter.get();
+
+        assert!(!is_cache_hit(split_footer("split-1")).await);
+        assert!(is_cache_hit(split_footer("split-1")).await);
+
+        // The footer of `split-1` is evicted once it has not been accessed for a while.
+        tokio::time::advance(Duration::from_secs(120)).await;
+        assert!(!is_cache_hit(split_footer("split-2")).await);
+        assert!(evictions_counter.get() > num_evictions_before);
+        assert!(searcher_context.split_footer_cache.get("split-1").is_none());
+        assert!(!is_cache_hit(split_footer("split-1")).await);
+    }
+
     fn bool_filter(ast: impl Into<QueryAst>) -> QueryAst {
         BoolQuery {
             must: vec![QueryAst::MatchAll],
@@ -1619,6 +2185,115 @@ mod tests {
     }
 
     // regression test for #4935
+    #[test]
+    fn test_extract_match_all_timestamp_range() {
+        let split = SplitIdAndFooterOffsets {
+            timestamp_start: Some(1_700_000_000),
+            timestamp_end: Some(1_700_010_000),
+            ..Default::default()
+        };
+        let mut search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(1_700_001_000),
+            end_timestamp: Some(1_700_002_000),
+            ..Default::default()
+        };
+        remove_redundant_timestamp_range(&mut search_request, &split, "timestamp");
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+        assert_eq!(
+            extract_match_all_timestamp_range(&query_ast, "timestamp"),
+            Some((
+                Bound::Included(1_700_001_000_000_000_000),
+                Bound::Excluded(1_700_002_000_000_000_000)
+            ))
+        );
+        assert!(extract_match_all_timestamp_range(&query_ast, "other_timestamp").is_none());
+        assert!(extract_match_all_timestamp_range(&QueryAst::MatchAll, "timestamp").is_none());
+
+        let mut search_request = SearchRequest {
+            query_ast: qast_json_helper("body:error", &[]),
+            start_timestamp: Some(1_700_001_000),
+            ..Default::default()
+        };
+        remove_redundant_timestamp_range(&mut search_request, &split, "timestamp");
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+        assert!(extract_match_all_timestamp_range(&query_ast, "timestamp").is_none());
+    }
+
+    #[test]
+    fn test_count_timestamp_range_with_fast_field() {
+        use tantivy::schema::{DateOptions, DateTimePrecision, Schema};
+        use tantivy::TantivyDocument;
+
+        let mut schema_builder = Schema::builder();
+        let timestamp_field = schema_builder.add_date_field(
+            "timestamp",
+            DateOptions::default()
+                .set_fast()
+                .set_precision(DateTimePrecision::Seconds),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: tantivy::IndexWriter =
+            index.writer_with_num_threads(1, 15_000_000).unwrap();
+
+        // Pseudo-random timestamps, spread over two segments.
+        let timestamps_secs: Vec<i64> = (0..200i64).map(|i| (i * 7_919) % 1_000).collect();
+        for (doc_ord, timestamp_secs) in timestamps_secs.iter().enumerate() {
+            let mut doc = TantivyDocument::new();
+            doc.add_date(
+                timestamp_field,
+                DateTime::from_timestamp_secs(*timestamp_secs),
+            );
+            index_writer.add_document(doc).unwrap();
+            if doc_ord == 100 {
+                index_writer.commit().unwrap();
+            }
+        }
+        // A document without timestamp.
+        index_writer.add_document(TantivyDocument::new()).unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        const S_TO_NS: i64 = 1_000_000_000;
+        let bounds = [
+            (Bound::Unbounded, Bound::Unbounded),
+            (
+                Bound::Included(100 * S_TO_NS),
+                Bound::Excluded(500 * S_TO_NS),
+            ),
+            // Bounds are truncated to the precision of the fast field.
+            (
+                Bound::Excluded(100 * S_TO_NS + 1),
+                Bound::Included(500 * S_TO_NS + 1),
+            ),
+            (Bound::Included(700 * S_TO_NS), Bound::Unbounded),
+            (
+                Bound::Excluded(500 * S_TO_NS),
+                Bound::Excluded(500 * S_TO_NS),
+            ),
+        ];
+        for (lower_bound, upper_bound) in bounds {
+            let expected_count = timestamps_secs
+                .iter()
+                .map(|timestamp_secs| timestamp_secs * S_TO_NS)
+                .filter(|timestamp_nanos| {
+                    let lower_bound_nanos =
+                        map_bound(lower_bound, |bound| bound / S_TO_NS * S_TO_NS);
+                    let upper_bound_nanos =
+                        map_bound(upper_bound, |bound| bound / S_TO_NS * S_TO_NS);
+                    (lower_bound_nanos, upper_bound_nanos).contains(timestamp_nanos)
+                })
+                .count() as u64;
+            let count = count_timestamp_range_with_fast_field(
+                &searcher,
+                "timestamp",
+                (lower_bound, upper_bound),
+            )
+            .unwrap();
+            assert_eq!(count, Some(expected_count));
+        }
+    }
+
     #[test]
     fn test_remove_timestamp_range_keep_should() {
         let time1 = 1700001000;
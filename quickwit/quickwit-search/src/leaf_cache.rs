@@ -27,6 +27,14 @@ use quickwit_proto::types::SplitId;
 use quickwit_storage::{MemorySizedCache, OwnedBytes};
 
 /// A cache to memoize `leaf_search_single_split` results.
+///
+/// Entries are keyed by split and by request, with the request time range clamped to the time
+/// range of the split. A dashboard repeating the same aggregation over a sliding time window
+/// therefore only recomputes the splits straddling the edges of the window, typically the newest
+/// ones. Entries are also keyed by the delete opstamp of the split, as prescribed by the
+/// [`SplitCachePolicy`](crate::SplitCachePolicy), so applying a delete task to a split
+/// invalidates its entries. Stale entries are only evicted when the cache runs out of capacity
+/// (`searcher.partial_request_cache_capacity`).
 pub struct LeafSearchCache {
     content: MemorySizedCache<CacheKey>,
 }
@@ -77,6 +85,10 @@ impl LeafSearchCache {
         let encoded_result = result.encode_to_vec();
         self.content.put(key, OwnedBytes::new(encoded_result));
     }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.content.set_capacity_in_bytes(capacity);
+    }
 }
 
 /// A key inside a [`LeafSearchCache`].
@@ -84,6 +96,8 @@ impl LeafSearchCache {
 struct CacheKey {
     /// The split this entry refers to
     split_id: SplitId,
+    /// The delete opstamp of the split when this entry was computed.
+    delete_opstamp: u64,
     /// The request this matches. The timerange of the request was removed.
     request: SearchRequest,
     /// The effective time range of the request, that is, the intersection of the timerange
@@ -105,9 +119,13 @@ impl CacheKey {
         // it doesn't matter whether or not we count all hits at the scale of a
         // single split: either we did process it and got everything, or we didn't.
         search_request.count_hits = CountHits::CountAll.into();
+        search_request.track_total_hits = None;
+        // Spilling to disk does not change the result of the request.
+        search_request.allow_disk_use = false;
 
         CacheKey {
             split_id: split_info.split_id,
+            delete_opstamp: split_info.delete_opstamp,
             request: search_request,
             merged_time_range,
         }
@@ -208,6 +226,7 @@ mod tests {
             timestamp_start: None,
             timestamp_end: None,
             num_docs: 0,
+            delete_opstamp: 0,
         };
 
         let split_2 = SplitIdAndFooterOffsets {
@@ -217,6 +236,7 @@ mod tests {
             timestamp_start: None,
             timestamp_end: None,
             num_docs: 0,
+            delete_opstamp: 0,
         };
 
         let query_1 = SearchRequest {
@@ -252,6 +272,8 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            histogram_missing_doc_counts: Vec::new(),
+            cpu_time_micros: 0,
         };
 
         assert!(cache.get(split_1.clone(), query_1.clone()).is_none());
@@ -273,6 +295,7 @@ mod tests {
             timestamp_start: Some(100),
             timestamp_end: Some(199),
             num_docs: 0,
+            delete_opstamp: 0,
         };
         let split_2 = SplitIdAndFooterOffsets {
             split_id: "split_2".to_string(),
@@ -281,6 +304,7 @@ mod tests {
             timestamp_start: Some(150),
             timestamp_end: Some(249),
             num_docs: 0,
+            delete_opstamp: 0,
         };
         let split_3 = SplitIdAndFooterOffsets {
             split_id: "split_3".to_string(),
@@ -289,6 +313,7 @@ mod tests {
             timestamp_start: Some(150),
             timestamp_end: Some(249),
             num_docs: 0,
+            delete_opstamp: 0,
         };
 
         let query_1 = SearchRequest {
@@ -342,6 +367,8 @@ mod tests {
                 sort_value2: None,
                 split_id: "split_1".to_string(),
             }],
+            histogram_missing_doc_counts: Vec::new(),
+            cpu_time_micros: 0,
         };
 
         // for split_1, 1 and 1bis cover different timestamp ranges
@@ -369,4 +396,47 @@ mod tests {
         assert!(cache.get(split_3.clone(), query_2).is_none());
         assert!(cache.get(split_3, query_2bis).is_some());
     }
+
+    #[test]
+    fn test_leaf_search_cache_invalidated_by_delete_opstamp() {
+        let cache = LeafSearchCache::new(64_000_000);
+
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split_1".to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            timestamp_start: None,
+            timestamp_end: None,
+            num_docs: 10,
+            delete_opstamp: 3,
+        };
+        let query = SearchRequest {
+            index_id_patterns: vec!["test-idx".to_string()],
+            query_ast: "test".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let result = LeafSearchResponse {
+            num_hits: 10,
+            num_attempted_splits: 1,
+            num_successful_splits: 1,
+            ..Default::default()
+        };
+        cache.put(split.clone(), query.clone(), result.clone());
+        assert_eq!(cache.get(split.clone(), query.clone()).unwrap(), result);
+
+        // A delete task was applied to the split.
+        let deleted_split = SplitIdAndFooterOffsets {
+            delete_opstamp: 4,
+            ..split
+        };
+        assert!(cache.get(deleted_split.clone(), query.clone()).is_none());
+
+        let deleted_result = LeafSearchResponse {
+            num_hits: 7,
+            ..result
+        };
+        cache.put(deleted_split.clone(), query.clone(), deleted_result.clone());
+        assert_eq!(cache.get(deleted_split, query).unwrap(), deleted_result);
+    }
 }
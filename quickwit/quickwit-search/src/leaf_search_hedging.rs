@@ -0,0 +1,346 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use quickwit_config::LeafSearchHedgingConfig;
+use tokio::time::Instant;
+
+use crate::metrics::SEARCH_METRICS;
+
+/// Number of latencies kept per searcher to estimate its latency percentile.
+const LATENCY_WINDOW_SIZE: usize = 100;
+
+/// Below this number of observed latencies, the latency percentile of a searcher is considered
+/// unknown and its requests are never hedged.
+const MIN_NUM_LATENCIES: usize = 10;
+
+/// Cost of a hedged request in budget units. Every request credits the budget with
+/// `max_hedged_requests_percent` units.
+const HEDGE_COST: u64 = 100;
+
+/// Maximum number of hedges that can be saved up while the requests are fast. This bounds the
+/// burst of hedges emitted when a searcher suddenly slows down.
+const MAX_NUM_SAVED_HEDGES: u64 = 10;
+
+/// Hedges the leaf search requests: when a searcher takes longer than its recent latency
+/// percentile to answer, the same request is sent to another searcher and the first response
+/// wins.
+///
+/// The number of hedged requests is capped by a budget: every request adds
+/// `max_hedged_requests_percent` units to the budget and every hedged request consumes
+/// `HEDGE_COST` units.
+pub(crate) struct LeafSearchHedger {
+    latency_percentile: usize,
+    min_delay: Duration,
+    budget_per_request: u64,
+    latencies_per_searcher: Mutex<HashMap<SocketAddr, VecDeque<Duration>>>,
+    budget: Mutex<u64>,
+}
+
+impl LeafSearchHedger {
+    pub fn new(config: &LeafSearchHedgingConfig) -> Self {
+        Self {
+            latency_percentile: config.latency_percentile as usize,
+            min_delay: config.min_delay(),
+            budget_per_request: config.max_hedged_requests_percent as u64,
+            latencies_per_searcher: Mutex::default(),
+            budget: Mutex::new(0),
+        }
+    }
+
+    fn record_latency(&self, grpc_addr: SocketAddr, latency: Duration) {
+        let mut latencies_per_searcher = self.latencies_per_searcher.lock().unwrap();
+        let latencies = latencies_per_searcher.entry(grpc_addr).or_default();
+        if latencies.len() == LATENCY_WINDOW_SIZE {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Returns the delay after which a request sent to the searcher should be hedged, or `None`
+    /// if we have not observed enough of its latencies yet.
+    fn hedging_delay(&self, grpc_addr: SocketAddr) -> Option<Duration> {
+        let latencies_per_searcher = self.latencies_per_searcher.lock().unwrap();
+        let latencies = latencies_per_searcher.get(&grpc_addr)?;
+        if latencies.len() < MIN_NUM_LATENCIES {
+            return None;
+        }
+        let mut sorted_latencies: Vec<Duration> = latencies.iter().copied().collect();
+        sorted_latencies.sort_unstable();
+        let rank = (sorted_latencies.len() * self.latency_percentile).div_ceil(100);
+        let percentile_latency = sorted_latencies[rank.saturating_sub(1)];
+        Some(percentile_latency.max(self.min_delay))
+    }
+
+    fn credit_budget(&self) {
+        let mut budget = self.budget.lock().unwrap();
+        *budget = (*budget + self.budget_per_request).min(MAX_NUM_SAVED_HEDGES * HEDGE_COST);
+    }
+
+    fn try_consume_budget(&self) -> bool {
+        let mut budget = self.budget.lock().unwrap();
+        if *budget < HEDGE_COST {
+            return false;
+        }
+        *budget -= HEDGE_COST;
+        true
+    }
+
+    /// Runs the `primary` request sent to the searcher listening on `primary_addr`.
+    ///
+    /// If the request has not completed after the hedging delay of the searcher and the budget
+    /// allows it, `select_hedge` is called to pick another searcher. The hedged request it returns
+    /// is then raced against the primary request: the first successful response is returned and
+    /// the other request is cancelled.
+    pub async fn run<T, E, PrimaryFut, SelectFut, HedgeFut>(
+        &self,
+        primary_addr: SocketAddr,
+        primary: PrimaryFut,
+        select_hedge: impl FnOnce() -> SelectFut,
+    ) -> Result<T, E>
+    where
+        PrimaryFut: Future<Output = Result<T, E>>,
+        SelectFut: Future<Output = Option<HedgeFut>>,
+        HedgeFut: Future<Output = Result<T, E>>,
+    {
+        self.credit_budget();
+        let start = Instant::now();
+        let Some(hedging_delay) = self.hedging_delay(primary_addr) else {
+            let primary_result = primary.await;
+            self.record_latency(primary_addr, start.elapsed());
+            return primary_result;
+        };
+        tokio::pin!(primary);
+
+        tokio::select! {
+            primary_result = &mut primary => {
+                self.record_latency(primary_addr, start.elapsed());
+                return primary_result;
+            }
+            _ = tokio::time::sleep(hedging_delay) => {}
+        }
+        let hedge_opt = select_hedge().await;
+        let Some(hedge) = hedge_opt.filter(|_| self.try_consume_budget()) else {
+            let primary_result = primary.await;
+            self.record_latency(primary_addr, start.elapsed());
+            return primary_result;
+        };
+        SEARCH_METRICS.leaf_search_hedges_issued_total.inc();
+        tokio::pin!(hedge);
+
+        tokio::select! {
+            primary_result = &mut primary => {
+                self.record_latency(primary_addr, start.elapsed());
+                if primary_result.is_ok() {
+                    return primary_result;
+                }
+                // The primary request failed, the hedged request may still succeed.
+                let hedge_result = hedge.await;
+                if hedge_result.is_ok() {
+                    SEARCH_METRICS.leaf_search_hedges_won_total.inc();
+                    return hedge_result;
+                }
+                primary_result
+            }
+            hedge_result = &mut hedge => {
+                if hedge_result.is_ok() {
+                    // Dropping the primary request cancels it. We still record how long we have
+                    // waited for it so that a slow searcher keeps a high latency percentile.
+                    self.record_latency(primary_addr, start.elapsed());
+                    SEARCH_METRICS.leaf_search_hedges_won_total.inc();
+                    return hedge_result;
+                }
+                let primary_result = primary.await;
+                self.record_latency(primary_addr, start.elapsed());
+                primary_result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::ready;
+
+    use tokio::time::sleep;
+
+    use super::*;
+
+    const FAST_LEAF_ADDR: SocketAddr = SocketAddr::new(
+        std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
+        1001,
+    );
+
+    /// Emulates a leaf answering with its name after `latency`.
+    async fn mock_leaf(name: &'static str, latency: Duration) -> Result<&'static str, ()> {
+        sleep(latency).await;
+        Ok(name)
+    }
+
+    fn hedger_for_test(max_hedged_requests_percent: u8) -> LeafSearchHedger {
+        let config = LeafSearchHedgingConfig {
+            latency_percentile: 90,
+            min_delay_millis: 10,
+            max_hedged_requests_percent,
+        };
+        let hedger = LeafSearchHedger::new(&config);
+        for _ in 0..MIN_NUM_LATENCIES {
+            hedger.record_latency(FAST_LEAF_ADDR, Duration::from_millis(20));
+        }
+        hedger
+    }
+
+    #[test]
+    fn test_leaf_search_hedger_hedging_delay() {
+        let hedger = hedger_for_test(5);
+        let other_addr: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+        assert!(hedger.hedging_delay(other_addr).is_none());
+        assert_eq!(
+            hedger.hedging_delay(FAST_LEAF_ADDR),
+            Some(Duration::from_millis(20))
+        );
+        for latency_millis in 1..=90 {
+            hedger.record_latency(other_addr, Duration::from_millis(latency_millis));
+        }
+        assert_eq!(
+            hedger.hedging_delay(other_addr),
+            Some(Duration::from_millis(81))
+        );
+        hedger.record_latency(FAST_LEAF_ADDR, Duration::ZERO);
+        for _ in 0..LATENCY_WINDOW_SIZE {
+            hedger.record_latency(FAST_LEAF_ADDR, Duration::from_millis(1));
+        }
+        // The delay is never lower than `min_delay`.
+        assert_eq!(
+            hedger.hedging_delay(FAST_LEAF_ADDR),
+            Some(Duration::from_millis(10))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leaf_search_hedger_hedge_wins() {
+        let hedger = hedger_for_test(100);
+        // The first request only fills the budget.
+        hedger
+            .run(
+                FAST_LEAF_ADDR,
+                mock_leaf("primary", Duration::from_millis(5)),
+                || ready(Some(mock_leaf("hedge", Duration::from_millis(5)))),
+            )
+            .await
+            .unwrap();
+
+        let issued_before = SEARCH_METRICS.leaf_search_hedges_issued_total.get();
+        let won_before = SEARCH_METRICS.leaf_search_hedges_won_total.get();
+        let start = Instant::now();
+        let response = hedger
+            .run(
+                FAST_LEAF_ADDR,
+                mock_leaf("primary", Duration::from_secs(10)),
+                || ready(Some(mock_leaf("hedge", Duration::from_millis(30)))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, "hedge");
+        assert_eq!(start.elapsed(), Duration::from_millis(50));
+        assert!(SEARCH_METRICS.leaf_search_hedges_issued_total.get() > issued_before);
+        assert!(SEARCH_METRICS.leaf_search_hedges_won_total.get() > won_before);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leaf_search_hedger_primary_wins() {
+        let hedger = hedger_for_test(100);
+        let response = hedger
+            .run(
+                FAST_LEAF_ADDR,
+                mock_leaf("primary", Duration::from_millis(30)),
+                || ready(Some(mock_leaf("hedge", Duration::from_secs(10)))),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, "primary");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leaf_search_hedger_failed_primary_falls_back_to_hedge() {
+        let hedger = hedger_for_test(100);
+        let failing_primary = async {
+            sleep(Duration::from_millis(30)).await;
+            Err(())
+        };
+        let response = hedger
+            .run(FAST_LEAF_ADDR, failing_primary, || {
+                ready(Some(mock_leaf("hedge", Duration::from_millis(100))))
+            })
+            .await
+            .unwrap();
+        assert_eq!(response, "hedge");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leaf_search_hedger_budget_caps_hedges() {
+        let config = LeafSearchHedgingConfig {
+            latency_percentile: 50,
+            min_delay_millis: 10,
+            max_hedged_requests_percent: 10,
+        };
+        let hedger = LeafSearchHedger::new(&config);
+        for _ in 0..LATENCY_WINDOW_SIZE {
+            hedger.record_latency(FAST_LEAF_ADDR, Duration::from_millis(20));
+        }
+        let mut num_hedged_responses = 0;
+
+        for _ in 0..40 {
+            let response = hedger
+                .run(
+                    FAST_LEAF_ADDR,
+                    mock_leaf("primary", Duration::from_millis(100)),
+                    || ready(Some(mock_leaf("hedge", Duration::from_millis(5)))),
+                )
+                .await
+                .unwrap();
+            if response == "hedge" {
+                num_hedged_responses += 1;
+            }
+        }
+        // Every request is slower than usual, but only 10% of them can be hedged.
+        assert_eq!(num_hedged_responses, 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_leaf_search_hedger_no_alternative_searcher() {
+        let hedger = hedger_for_test(100);
+        let response = hedger
+            .run(
+                FAST_LEAF_ADDR,
+                mock_leaf("primary", Duration::from_millis(100)),
+                || ready(None::<std::future::Ready<Result<&'static str, ()>>>),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, "primary");
+        // The budget was not consumed.
+        assert_eq!(*hedger.budget.lock().unwrap(), 100);
+    }
+}
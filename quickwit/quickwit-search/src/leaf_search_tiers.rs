@@ -0,0 +1,270 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use quickwit_common::metrics::OwnedGaugeGuard;
+use quickwit_common::thread_pool::{Panicked, ThreadPool};
+use quickwit_config::LeafSearchTiersConfig;
+use quickwit_proto::search::SearchRequest;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::metrics::SEARCH_METRICS;
+
+/// Tier of a leaf search request, deciding which search threads may run its split searches.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum LeafSearchTier {
+    /// Requests without aggregations, fetching few hits from few splits.
+    Cheap,
+    Expensive,
+}
+
+impl LeafSearchTier {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LeafSearchTier::Cheap => "cheap",
+            LeafSearchTier::Expensive => "expensive",
+        }
+    }
+}
+
+/// Runs the CPU-intensive part of the split searches on the search thread pool, sharing its
+/// threads between a tier of cheap requests and a tier of expensive ones.
+///
+/// The tier permit of a split search is acquired before its split search permit, so that the
+/// split search permits are only handed out to requests whose tier has room.
+///
+/// The threads are split into two permit budgets:
+/// - the shared permits, `expensive_threads_percent` of the threads, usable by both tiers;
+/// - the permits reserved to the cheap requests.
+///
+/// Expensive requests can therefore never occupy the reserved threads, while cheap requests use
+/// any thread available. When the tiers are disabled, the split searches run on the thread pool
+/// directly.
+pub(crate) struct LeafSearchTiers {
+    config_opt: Option<LeafSearchTiersConfig>,
+    thread_pool: ThreadPool,
+    shared_permits: Arc<Semaphore>,
+    cheap_permits: Arc<Semaphore>,
+}
+
+impl LeafSearchTiers {
+    pub fn new(config_opt: Option<LeafSearchTiersConfig>, thread_pool: ThreadPool) -> Self {
+        let num_threads = thread_pool
+            .get_underlying_rayon_thread_pool()
+            .current_num_threads();
+        let expensive_threads_percent =
+            config_opt.unwrap_or_default().expensive_threads_percent as usize;
+        let num_shared_permits = (num_threads * expensive_threads_percent / 100).max(1);
+        // With a single thread, the reserved permit oversubscribes the thread pool so that cheap
+        // requests can at least queue up next to the expensive ones.
+        let num_cheap_permits = num_threads.saturating_sub(num_shared_permits).max(1);
+        Self {
+            config_opt,
+            thread_pool,
+            shared_permits: Arc::new(Semaphore::new(num_shared_permits)),
+            cheap_permits: Arc::new(Semaphore::new(num_cheap_permits)),
+        }
+    }
+
+    /// Classifies a leaf search request targeting `num_splits` splits.
+    pub fn classify(&self, search_request: &SearchRequest, num_splits: usize) -> LeafSearchTier {
+        let Some(config) = &self.config_opt else {
+            return LeafSearchTier::Expensive;
+        };
+        if search_request.aggregation_request.is_none()
+            && search_request.start_offset + search_request.max_hits <= config.cheap_max_hits
+            && num_splits <= config.cheap_max_splits
+        {
+            LeafSearchTier::Cheap
+        } else {
+            LeafSearchTier::Expensive
+        }
+    }
+
+    /// Waits for a permit of the tier, to be acquired before any other split search permit so
+    /// that a saturated tier queues up its own requests only.
+    pub async fn acquire(&self, tier: LeafSearchTier) -> LeafSearchTierPermit {
+        if self.config_opt.is_none() {
+            return LeafSearchTierPermit {
+                _permit_opt: None,
+                _in_flight_guard_opt: None,
+            };
+        }
+        let queue_wait_start = Instant::now();
+        let permit = self.acquire_permit(tier).await;
+        SEARCH_METRICS
+            .leaf_search_tier_queue_wait_seconds
+            .with_label_values([tier.as_str()])
+            .observe(queue_wait_start.elapsed().as_secs_f64());
+
+        let mut in_flight_guard = OwnedGaugeGuard::from_gauge(
+            SEARCH_METRICS
+                .leaf_search_tier_in_flight_tasks
+                .with_label_values([tier.as_str()]),
+        );
+        in_flight_guard.add(1);
+        LeafSearchTierPermit {
+            _permit_opt: Some(permit),
+            _in_flight_guard_opt: Some(in_flight_guard),
+        }
+    }
+
+    /// Runs `cpu_intensive_fn` on the search thread pool, releasing the tier permit once it
+    /// returns.
+    ///
+    /// Like [`ThreadPool::run_cpu_intensive`], nothing runs if the returned future is dropped.
+    pub async fn run_cpu_intensive<F, R>(
+        &self,
+        _tier_permit: LeafSearchTierPermit,
+        cpu_intensive_fn: F,
+    ) -> Result<R, Panicked>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.thread_pool.run_cpu_intensive(cpu_intensive_fn).await
+    }
+
+    async fn acquire_permit(&self, tier: LeafSearchTier) -> OwnedSemaphorePermit {
+        let permit_res = match tier {
+            LeafSearchTier::Cheap => {
+                tokio::select! {
+                    biased;
+                    permit_res = self.cheap_permits.clone().acquire_owned() => permit_res,
+                    permit_res = self.shared_permits.clone().acquire_owned() => permit_res,
+                }
+            }
+            LeafSearchTier::Expensive => self.shared_permits.clone().acquire_owned().await,
+        };
+        permit_res.expect("the semaphores should never be closed")
+    }
+}
+
+/// Permit of a leaf search tier, held from before the split search permit is acquired until the
+/// CPU-intensive part of the split search is done.
+pub(crate) struct LeafSearchTierPermit {
+    _permit_opt: Option<OwnedSemaphorePermit>,
+    _in_flight_guard_opt: Option<OwnedGaugeGuard>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_leaf_search_tiers_classify() {
+        let thread_pool = ThreadPool::new("test_tiers_classify", Some(1));
+        let leaf_search_tiers = LeafSearchTiers::new(None, thread_pool.clone());
+        let search_request = SearchRequest {
+            max_hits: 10,
+            ..Default::default()
+        };
+        assert_eq!(
+            leaf_search_tiers.classify(&search_request, 1),
+            LeafSearchTier::Expensive
+        );
+        let leaf_search_tiers =
+            LeafSearchTiers::new(Some(LeafSearchTiersConfig::default()), thread_pool);
+        assert_eq!(
+            leaf_search_tiers.classify(&search_request, 1),
+            LeafSearchTier::Cheap
+        );
+        assert_eq!(
+            leaf_search_tiers.classify(&search_request, 11),
+            LeafSearchTier::Expensive
+        );
+        let search_request = SearchRequest {
+            max_hits: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            leaf_search_tiers.classify(&search_request, 1),
+            LeafSearchTier::Expensive
+        );
+        let search_request = SearchRequest {
+            aggregation_request: Some(r#"{"count": {"value_count": {"field": "id"}}}"#.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            leaf_search_tiers.classify(&search_request, 1),
+            LeafSearchTier::Expensive
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leaf_search_tiers_cheap_requests_are_not_starved() {
+        let thread_pool = ThreadPool::new("test_tiers_starvation", Some(4));
+        let config = LeafSearchTiersConfig {
+            expensive_threads_percent: 50,
+            ..Default::default()
+        };
+        let leaf_search_tiers = Arc::new(LeafSearchTiers::new(Some(config), thread_pool));
+        assert_eq!(leaf_search_tiers.shared_permits.available_permits(), 2);
+        assert_eq!(leaf_search_tiers.cheap_permits.available_permits(), 2);
+
+        // The expensive tasks occupy their thread until they are released.
+        let expensive_released = Arc::new(AtomicBool::new(false));
+
+        let expensive_handles: Vec<_> = (0..10)
+            .map(|_| {
+                let leaf_search_tiers = leaf_search_tiers.clone();
+                let expensive_released = expensive_released.clone();
+                tokio::spawn(async move {
+                    let tier_permit = leaf_search_tiers.acquire(LeafSearchTier::Expensive).await;
+                    leaf_search_tiers
+                        .run_cpu_intensive(tier_permit, move || {
+                            while !expensive_released.load(Ordering::Relaxed) {
+                                std::thread::sleep(Duration::from_millis(1));
+                            }
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        while leaf_search_tiers.shared_permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+        // The expensive tier is saturated, yet it did not consume the cheap reservation.
+        assert_eq!(leaf_search_tiers.cheap_permits.available_permits(), 2);
+
+        for _ in 0..10 {
+            let cheap_res = tokio::time::timeout(Duration::from_secs(1), async {
+                let tier_permit = leaf_search_tiers.acquire(LeafSearchTier::Cheap).await;
+                leaf_search_tiers
+                    .run_cpu_intensive(tier_permit, || 1 + 1)
+                    .await
+            })
+            .await
+            .expect("cheap request should not be starved by the expensive ones");
+            assert_eq!(cheap_res, Ok(2));
+        }
+        expensive_released.store(true, Ordering::Relaxed);
+
+        for expensive_handle in expensive_handles {
+            expensive_handle.await.unwrap().unwrap();
+        }
+        assert_eq!(leaf_search_tiers.shared_permits.available_permits(), 2);
+    }
+}
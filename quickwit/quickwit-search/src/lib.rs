@@ -22,25 +22,45 @@
 #![allow(clippy::bool_assert_comparison)]
 #![deny(clippy::disallowed_methods)]
 
+mod aggregation_sampling;
+mod aggregation_spill;
 mod client;
 mod cluster_client;
 mod collector;
 mod error;
+mod es_timestamp_alias;
 mod fetch_docs;
 mod filters;
 mod find_trace_ids_collector;
+mod histogram_missing;
+mod hits_stream_cursor;
+mod index_pseudo_field;
 mod leaf;
 mod leaf_cache;
+mod leaf_search_hedging;
+mod leaf_search_tiers;
 mod list_fields;
 mod list_fields_cache;
+mod list_splits_cache;
 mod list_terms;
+mod load_shedding;
+mod pipeline_aggregation;
+mod point_in_time;
 mod retry;
 mod root;
 mod scroll_context;
+mod search_hits_stream;
+mod search_interrupt;
 mod search_job_placer;
+mod search_memory;
+mod search_permit_provider;
+mod search_quotas;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod split_cache_policy;
+mod split_pruning;
+mod suggest;
 pub(crate) mod top_k_collector;
 
 mod metrics;
@@ -74,9 +94,10 @@ use quickwit_metastore::{
 use quickwit_proto::search::{PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
 use quickwit_proto::types::IndexUid;
 use quickwit_storage::StorageResolver;
-pub use service::SearcherContext;
+pub use service::{SearcherConfigReload, SearcherContext};
 use tantivy::DocAddress;
 
+pub use crate::aggregation_sampling::sample_rate_to_ppm;
 pub use crate::client::{
     create_search_client_from_channel, create_search_client_from_grpc_addr, SearchServiceClient,
 };
@@ -84,13 +105,18 @@ pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{parse_grpc_error, SearchError};
 use crate::fetch_docs::fetch_docs;
 pub use crate::root::{
-    check_all_index_metadata_found, jobs_to_leaf_request, root_search, search_plan,
-    IndexMetasForLeafSearch, SearchJob,
+    check_all_index_metadata_found, jobs_to_leaf_request, root_count_hits_per_split, root_search,
+    search_plan, validate_index_id_patterns, IndexMetasForLeafSearch, SearchJob,
 };
+pub use crate::search_hits_stream::{root_search_hits_stream, HitsStreamPage};
 pub use crate::search_job_placer::{Job, SearchJobPlacer};
-pub use crate::search_response_rest::{SearchPlanResponseRest, SearchResponseRest};
+pub use crate::search_response_rest::{
+    SearchPlanResponseRest, SearchResponseRest, SearchResponseStats, SplitPruningStats,
+};
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+pub use crate::split_cache_policy::SplitCachePolicy;
+pub use crate::suggest::{root_suggest, SuggestRequest, SuggestResponse};
 
 /// A pool of searcher clients identified by their gRPC socket address.
 pub type SearcherPool = Pool<SocketAddr, SearchServiceClient>;
@@ -174,6 +200,7 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
             .as_ref()
             .map(|time_range| *time_range.end()),
         num_docs: split_metadata.num_docs as u64,
+        delete_opstamp: split_metadata.delete_opstamp,
     }
 }
 
@@ -222,6 +249,7 @@ pub async fn resolve_index_patterns(
     index_id_patterns: &[String],
     metastore: &mut MetastoreServiceClient,
 ) -> crate::Result<Vec<IndexMetadata>> {
+    validate_index_id_patterns(index_id_patterns)?;
     let list_indexes_metadata_request = if index_id_patterns.is_empty() {
         ListIndexesMetadataRequest::all()
     } else {
@@ -263,7 +291,10 @@ pub async fn start_searcher_service(
     search_job_placer: SearchJobPlacer,
     searcher_context: Arc<SearcherContext>,
 ) -> anyhow::Result<Arc<dyn SearchService>> {
-    let cluster_client = ClusterClient::new(search_job_placer);
+    let mut cluster_client = ClusterClient::new(search_job_placer);
+    if let Some(hedging_config) = &searcher_context.searcher_config().leaf_search_hedging {
+        cluster_client = cluster_client.with_leaf_search_hedging(hedging_config);
+    }
     let search_service = Arc::new(SearchServiceImpl::new(
         metastore,
         storage_resolver,
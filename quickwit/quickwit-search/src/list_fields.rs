@@ -296,6 +296,28 @@ pub async fn root_list_fields(
     if indexes_metadata.is_empty() {
         return Ok(ListFieldsResponse { fields: Vec::new() });
     }
+    // The dynamic fields of the indexes with a search filter may only exist in documents ruled out
+    // by the filter, so only their declared fields are listed.
+    let index_id_to_declared_fields: HashMap<IndexId, HashSet<String>> = indexes_metadata
+        .iter()
+        .filter(|index_metadata| {
+            index_metadata
+                .index_config
+                .search_settings
+                .search_filter
+                .is_some()
+        })
+        .map(|index_metadata| {
+            let declared_fields: HashSet<String> = index_metadata
+                .index_config
+                .doc_mapping
+                .field_capabilities(None)
+                .into_iter()
+                .map(|field_capability| field_capability.name)
+                .collect();
+            (index_metadata.index_id().to_string(), declared_fields)
+        })
+        .collect();
     let index_uid_to_index_meta: HashMap<IndexUid, IndexMetasForLeafSearch> = indexes_metadata
         .iter()
         .map(|index_metadata| {
@@ -342,12 +364,30 @@ pub async fn root_list_fields(
     let fields = merge_leaf_list_fields(
         leaf_search_responses
             .into_iter()
-            .map(|resp| resp.fields.into_iter())
+            .map(|resp| {
+                resp.fields
+                    .into_iter()
+                    .filter(|entry| is_field_listable(entry, &index_id_to_declared_fields))
+            })
             .collect_vec(),
     )?;
     Ok(ListFieldsResponse { fields })
 }
 
+/// Returns false if the field belongs to an index with a search filter that doesn't declare it.
+fn is_field_listable(
+    entry: &ListFieldsEntryResponse,
+    index_id_to_declared_fields: &HashMap<IndexId, HashSet<String>>,
+) -> bool {
+    entry.index_ids.iter().all(|index_id| {
+        index_id_to_declared_fields
+            .get(index_id)
+            .map_or(true, |declared_fields| {
+                declared_fields.contains(&entry.field_name)
+            })
+    })
+}
+
 /// Builds a list of [`LeafListFieldsRequest`], one per index, from a list of [`SearchJob`].
 pub fn jobs_to_leaf_requests(
     request: &ListFieldsRequest,
@@ -52,6 +52,10 @@ impl ListFieldsCache {
         let encoded_result = serialize_split_fields(list_fields);
         self.content.put(key, OwnedBytes::new(encoded_result));
     }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.content.set_capacity_in_bytes(capacity);
+    }
 }
 
 /// A key inside a [`ListFieldsCache`].
@@ -59,12 +63,15 @@ impl ListFieldsCache {
 struct CacheKey {
     /// The split this entry refers to
     split_id: SplitId,
+    /// The delete opstamp of the split when this entry was computed.
+    delete_opstamp: u64,
 }
 
 impl CacheKey {
     fn from_split_meta(split_info: SplitIdAndFooterOffsets) -> Self {
         CacheKey {
             split_id: split_info.split_id,
+            delete_opstamp: split_info.delete_opstamp,
         }
     }
 }
@@ -88,6 +95,7 @@ mod tests {
             timestamp_start: None,
             timestamp_end: None,
             num_docs: 0,
+            delete_opstamp: 0,
         };
 
         let split_2 = SplitIdAndFooterOffsets {
@@ -97,6 +105,7 @@ mod tests {
             timestamp_start: None,
             timestamp_end: None,
             num_docs: 0,
+            delete_opstamp: 0,
         };
 
         let result = ListFieldsEntryResponse {
@@ -118,5 +127,11 @@ mod tests {
         cache.put(split_1.clone(), list_fields.clone());
         assert_eq!(cache.get(split_1.clone()).unwrap(), list_fields);
         assert!(cache.get(split_2).is_none());
+
+        let deleted_split_1 = SplitIdAndFooterOffsets {
+            delete_opstamp: 1,
+            ..split_1
+        };
+        assert!(cache.get(deleted_split_1).is_none());
     }
 }
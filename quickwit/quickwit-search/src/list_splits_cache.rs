@@ -0,0 +1,270 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::types::IndexUid;
+use tokio::time::Instant;
+
+use crate::SplitCachePolicy;
+
+/// Maximum number of listings retained by the [`ListSplitsCache`].
+const MAX_NUM_ENTRIES: usize = 128;
+
+/// Age beyond which a cached listing is considered too stale to be used.
+pub(crate) const LIST_SPLITS_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// A short-lived cache of the split listings returned by the metastore to the root search.
+///
+/// It is only used as a fallback when the metastore fails to list the splits before the
+/// deadline: the root search then runs over the last known listing and flags its response as
+/// partial.
+///
+/// Listings containing immature splits expire early, following the [`SplitCachePolicy`]: such
+/// splits may have been merged and deleted since.
+pub struct ListSplitsCache {
+    ttl: Duration,
+    split_cache_policy: SplitCachePolicy,
+    entries: Mutex<Vec<ListSplitsCacheEntry>>,
+}
+
+struct ListSplitsCacheEntry {
+    index_uids: Vec<IndexUid>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+    split_metadatas: Arc<[SplitMetadata]>,
+    expires_at: Instant,
+}
+
+impl ListSplitsCacheEntry {
+    /// Returns true if this listing contains all the splits a listing with the given parameters
+    /// would have returned.
+    fn covers(
+        &self,
+        index_uids: &[IndexUid],
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+        tags_filter_opt: &Option<TagFilterAst>,
+    ) -> bool {
+        let start_covered = match (self.start_timestamp, start_timestamp) {
+            (None, _) => true,
+            (Some(cached_start), Some(start)) => cached_start <= start,
+            (Some(_), None) => false,
+        };
+        let end_covered = match (self.end_timestamp, end_timestamp) {
+            (None, _) => true,
+            (Some(cached_end), Some(end)) => end <= cached_end,
+            (Some(_), None) => false,
+        };
+        start_covered
+            && end_covered
+            && self.index_uids == index_uids
+            && &self.tags_filter_opt == tags_filter_opt
+    }
+}
+
+impl ListSplitsCache {
+    pub fn new(ttl: Duration, split_cache_policy: SplitCachePolicy) -> ListSplitsCache {
+        ListSplitsCache {
+            ttl,
+            split_cache_policy,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the splits of a fresh cached listing compatible with the given parameters,
+    /// restricted to the requested time range.
+    pub fn get(
+        &self,
+        index_uids: &[IndexUid],
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+        tags_filter_opt: &Option<TagFilterAst>,
+    ) -> Option<Vec<SplitMetadata>> {
+        let index_uids = sorted_index_uids(index_uids);
+        let now = Instant::now();
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.iter().rev().find(|entry| {
+            now < entry.expires_at
+                && entry.covers(&index_uids, start_timestamp, end_timestamp, tags_filter_opt)
+        })?;
+        let split_metadatas = entry
+            .split_metadatas
+            .iter()
+            .filter(|split_metadata| {
+                let Some(time_range) = &split_metadata.time_range else {
+                    return true;
+                };
+                start_timestamp.map_or(true, |start| *time_range.end() >= start)
+                    && end_timestamp.map_or(true, |end| *time_range.start() < end)
+            })
+            .cloned()
+            .collect();
+        Some(split_metadatas)
+    }
+
+    pub fn put(
+        &self,
+        index_uids: &[IndexUid],
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+        tags_filter_opt: Option<TagFilterAst>,
+        split_metadatas: Arc<[SplitMetadata]>,
+    ) {
+        let index_uids = sorted_index_uids(index_uids);
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| {
+            now < entry.expires_at
+                && !(entry.index_uids == index_uids
+                    && entry.start_timestamp == start_timestamp
+                    && entry.end_timestamp == end_timestamp
+                    && entry.tags_filter_opt == tags_filter_opt)
+        });
+        let ttl = self
+            .split_cache_policy
+            .min_ttl(split_metadatas.iter())
+            .map_or(self.ttl, |split_ttl| split_ttl.min(self.ttl));
+        if entries.len() >= MAX_NUM_ENTRIES {
+            entries.remove(0);
+        }
+        entries.push(ListSplitsCacheEntry {
+            index_uids,
+            start_timestamp,
+            end_timestamp,
+            tags_filter_opt,
+            split_metadatas,
+            expires_at: now + ttl,
+        });
+    }
+}
+
+fn sorted_index_uids(index_uids: &[IndexUid]) -> Vec<IndexUid> {
+    let mut index_uids = index_uids.to_vec();
+    index_uids.sort();
+    index_uids
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::SplitMaturity;
+    use tantivy::time::OffsetDateTime;
+
+    use super::*;
+
+    fn split_metadata(split_id: &str, time_range_opt: Option<(i64, i64)>) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range: time_range_opt.map(|(start, end)| start..=end),
+            maturity: SplitMaturity::Mature,
+            ..Default::default()
+        }
+    }
+
+    fn split_ids(split_metadatas: &[SplitMetadata]) -> Vec<&str> {
+        split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_list_splits_cache() {
+        tokio::time::pause();
+        let cache = ListSplitsCache::new(Duration::from_secs(60), SplitCachePolicy::default());
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+
+        cache.put(
+            &[index_uid.clone()],
+            Some(100),
+            None,
+            None,
+            Arc::from([
+                split_metadata("split-1", Some((0, 149))),
+                split_metadata("split-2", Some((150, 199))),
+                split_metadata("split-3", Some((200, 299))),
+                split_metadata("split-4", None),
+            ]),
+        );
+        let split_metadatas = cache
+            .get(&[index_uid.clone()], Some(150), Some(200), &None)
+            .unwrap();
+        assert_eq!(split_ids(&split_metadatas), ["split-2", "split-4"]);
+
+        // The cached listing does not cover the requested time range.
+        assert!(cache
+            .get(&[index_uid.clone()], Some(50), None, &None)
+            .is_none());
+        assert!(cache.get(&[index_uid.clone()], None, None, &None).is_none());
+        // Different indexes.
+        assert!(cache
+            .get(
+                &[index_uid.clone(), other_index_uid],
+                Some(150),
+                None,
+                &None
+            )
+            .is_none());
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(cache.get(&[index_uid], Some(150), None, &None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_splits_cache_expires_listings_with_immature_splits() {
+        tokio::time::pause();
+        let cache = ListSplitsCache::new(
+            Duration::from_secs(60),
+            SplitCachePolicy::new(Duration::from_secs(10)),
+        );
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+
+        let immature_split = SplitMetadata {
+            split_id: "split-2".to_string(),
+            create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            maturity: SplitMaturity::Immature {
+                maturation_period: Duration::from_secs(3_600),
+            },
+            ..Default::default()
+        };
+        cache.put(
+            &[index_uid.clone()],
+            None,
+            None,
+            None,
+            Arc::from([split_metadata("split-1", None), immature_split]),
+        );
+        cache.put(
+            &[other_index_uid.clone()],
+            None,
+            None,
+            None,
+            Arc::from([split_metadata("split-3", None)]),
+        );
+        tokio::time::advance(Duration::from_secs(11)).await;
+        assert!(cache.get(&[index_uid], None, None, &None).is_none());
+        assert!(cache.get(&[other_index_uid], None, None, &None).is_some());
+    }
+}
@@ -26,19 +26,26 @@ use futures::future::try_join_all;
 use itertools::{Either, Itertools};
 use quickwit_common::pretty::PrettySample;
 use quickwit_config::build_doc_mapper;
-use quickwit_metastore::{ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitMetadata};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_metastore::{
+    IndexMetadata, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitMetadata,
+};
 use quickwit_proto::metastore::{ListSplitsRequest, MetastoreService, MetastoreServiceClient};
 use quickwit_proto::search::{
     LeafListTermsRequest, LeafListTermsResponse, ListTermsRequest, ListTermsResponse,
     SplitIdAndFooterOffsets, SplitSearchError,
 };
 use quickwit_proto::types::IndexUid;
+use quickwit_query::query_ast::QueryAst;
 use quickwit_storage::Storage;
-use tantivy::schema::{Field, FieldType};
-use tantivy::{ReloadPolicy, Term};
+use tantivy::common::BitSet;
+use tantivy::postings::Postings;
+use tantivy::query::EnableScoring;
+use tantivy::schema::{Field, FieldType, IndexRecordOption};
+use tantivy::{DocSet, ReloadPolicy, Searcher, Term, TERMINATED};
 use tracing::{debug, error, info, instrument};
 
-use crate::leaf::open_index_with_caches;
+use crate::leaf::{open_index_with_caches, warmup};
 use crate::search_job_placer::group_jobs_by_index_id;
 use crate::{resolve_index_patterns, ClusterClient, SearchError, SearchJob, SearcherContext};
 
@@ -66,6 +73,9 @@ pub async fn root_list_terms(
         });
     }
 
+    let mut index_uid_to_index_meta: HashMap<IndexUid, IndexMetasForLeafListTerms> =
+        HashMap::with_capacity(indexes_metadata.len());
+
     for index_metadata in indexes_metadata.iter() {
         let index_config = &index_metadata.index_config;
         let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
@@ -85,6 +95,10 @@ pub async fn root_list_terms(
                 "trying to list terms on field which isn't indexed".to_string(),
             ));
         }
+        index_uid_to_index_meta.insert(
+            index_metadata.index_uid.clone(),
+            IndexMetasForLeafListTerms::new(index_metadata, &doc_mapper)?,
+        );
     }
     let index_uids: Vec<IndexUid> = indexes_metadata
         .iter()
@@ -104,15 +118,6 @@ pub async fn root_list_terms(
     if let Some(end_ts) = list_terms_request.end_timestamp {
         query = query.with_time_range_end_lt(end_ts);
     }
-    let index_uid_to_index_uri: HashMap<IndexUid, String> = indexes_metadata
-        .iter()
-        .map(|index_metadata| {
-            (
-                index_metadata.index_uid.clone(),
-                index_metadata.index_uri().to_string(),
-            )
-        })
-        .collect();
     let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
     let split_metadatas: Vec<SplitMetadata> = metastore
         .clone()
@@ -130,7 +135,7 @@ pub async fn root_list_terms(
     // For each node, forward to a node with an affinity for that index id.
     for (client, client_jobs) in assigned_leaf_search_jobs {
         let leaf_requests =
-            jobs_to_leaf_requests(list_terms_request, &index_uid_to_index_uri, client_jobs)?;
+            jobs_to_leaf_requests(list_terms_request, &index_uid_to_index_meta, client_jobs)?;
         for leaf_request in leaf_requests {
             leaf_request_tasks.push(cluster_client.leaf_list_terms(leaf_request, client.clone()));
         }
@@ -181,17 +186,62 @@ pub async fn root_list_terms(
     })
 }
 
+/// Index metadata needed to build the leaf list terms requests of an index.
+pub struct IndexMetasForLeafListTerms {
+    /// Index URI.
+    pub index_uri: String,
+    /// Serialized doc mapper of the index. Only set along with `search_filter_ast_opt`.
+    pub doc_mapper_str_opt: Option<String>,
+    /// Serialized query AST of the search filter of the index, if any.
+    pub search_filter_ast_opt: Option<String>,
+}
+
+impl IndexMetasForLeafListTerms {
+    /// The search filter of the index, if any, is resolved against the doc mapper of the index and
+    /// passed to the leaves, so that the terms of the documents it rules out are not listed.
+    pub(crate) fn new(
+        index_metadata: &IndexMetadata,
+        doc_mapper: &Arc<dyn DocMapper>,
+    ) -> crate::Result<Self> {
+        let index_uri = index_metadata.index_uri().to_string();
+        let Some(search_filter) = &index_metadata.index_config.search_settings.search_filter else {
+            return Ok(Self {
+                index_uri,
+                doc_mapper_str_opt: None,
+                search_filter_ast_opt: None,
+            });
+        };
+        let search_filter_ast = search_filter.resolve(&**doc_mapper).map_err(|err| {
+            SearchError::Internal(format!("failed to resolve search filter. cause: {err}"))
+        })?;
+        Ok(Self {
+            index_uri,
+            doc_mapper_str_opt: Some(serde_json::to_string(doc_mapper)?),
+            search_filter_ast_opt: Some(serde_json::to_string(&search_filter_ast)?),
+        })
+    }
+}
+
+/// Search filter of an index, restricting the listed terms to the terms of the documents matching
+/// it.
+pub struct ListTermsSearchFilter {
+    /// Doc mapper of the index.
+    pub doc_mapper: Arc<dyn DocMapper>,
+    /// Query AST of the search filter.
+    pub query_ast: QueryAst,
+}
+
 /// Builds a list of [`LeafListTermsRequest`], one per index, from a list of [`SearchJob`].
 pub fn jobs_to_leaf_requests(
     request: &ListTermsRequest,
-    index_uid_to_uri: &HashMap<IndexUid, String>,
+    index_uid_to_index_meta: &HashMap<IndexUid, IndexMetasForLeafListTerms>,
     jobs: Vec<SearchJob>,
 ) -> crate::Result<Vec<LeafListTermsRequest>> {
     let search_request_for_leaf = request.clone();
     let mut leaf_search_requests = Vec::new();
     group_jobs_by_index_id(jobs, |job_group| {
         let index_uid = &job_group[0].index_uid;
-        let index_uri = index_uid_to_uri.get(index_uid).ok_or_else(|| {
+        let index_meta = index_uid_to_index_meta.get(index_uid).ok_or_else(|| {
             SearchError::Internal(format!(
                 "received list fields job for an unknown index {index_uid}. it should never happen"
             ))
@@ -199,8 +249,10 @@ pub fn jobs_to_leaf_requests(
 
         let leaf_search_request = LeafListTermsRequest {
             list_terms_request: Some(search_request_for_leaf.clone()),
-            index_uri: index_uri.to_string(),
+            index_uri: index_meta.index_uri.clone(),
             split_offsets: job_group.into_iter().map(|job| job.offsets).collect(),
+            doc_mapper: index_meta.doc_mapper_str_opt.clone(),
+            search_filter_ast: index_meta.search_filter_ast_opt.clone(),
         };
         leaf_search_requests.push(leaf_search_request);
         Ok(())
@@ -213,6 +265,7 @@ pub fn jobs_to_leaf_requests(
 async fn leaf_list_terms_single_split(
     searcher_context: &SearcherContext,
     search_request: &ListTermsRequest,
+    search_filter_opt: Option<&ListTermsSearchFilter>,
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
 ) -> crate::Result<LeafListTermsResponse> {
@@ -243,8 +296,20 @@ async fn leaf_list_terms_single_split(
         .as_ref()
         .map(|data| term_from_data(field, field_type, data));
 
+    let filter_doc_bitsets_opt: Option<Vec<BitSet>> = if let Some(search_filter) = search_filter_opt
+    {
+        Some(search_filter_doc_bitsets(&searcher, search_filter).await?)
+    } else {
+        None
+    };
+    // When the terms are filtered, the number of terms to scan is unknown.
+    let max_scanned_terms_opt = if filter_doc_bitsets_opt.is_some() {
+        None
+    } else {
+        search_request.max_hits
+    };
     let mut segment_results = Vec::new();
-    for segment_reader in searcher.segment_readers() {
+    for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
         let inverted_index = segment_reader.inverted_index(field)?.clone();
         let dict = inverted_index.terms();
         dict.file_slice_for_range(
@@ -260,14 +325,32 @@ async fn leaf_list_terms_single_split(
                     .map(Bound::Excluded)
                     .unwrap_or(Bound::Unbounded),
             ),
-            search_request.max_hits,
+            max_scanned_terms_opt,
         )
         .read_bytes_async()
         .await
         .with_context(|| "failed to load sstable range")?;
 
+        let filter_doc_bitset_opt = filter_doc_bitsets_opt
+            .as_ref()
+            .map(|filter_doc_bitsets| &filter_doc_bitsets[segment_ord]);
+        if filter_doc_bitset_opt.is_some() {
+            inverted_index
+                .warm_postings_range(
+                    (
+                        start_term
+                            .as_ref()
+                            .map_or(Bound::Unbounded, Bound::Included),
+                        end_term.as_ref().map_or(Bound::Unbounded, Bound::Excluded),
+                    ),
+                    None,
+                    false,
+                )
+                .await
+                .with_context(|| "failed to load postings")?;
+        }
         let mut range = dict.range();
-        if let Some(limit) = search_request.max_hits {
+        if let Some(limit) = max_scanned_terms_opt {
             range = range.limit(limit);
         }
         if let Some(start_term) = &start_term {
@@ -282,6 +365,18 @@ async fn leaf_list_terms_single_split(
         let mut segment_result: Vec<Vec<u8>> =
             Vec::with_capacity(search_request.max_hits.unwrap_or(0) as usize);
         while stream.advance() {
+            if let Some(max_hits) = search_request.max_hits {
+                if segment_result.len() as u64 >= max_hits {
+                    break;
+                }
+            }
+            if let Some(filter_doc_bitset) = filter_doc_bitset_opt {
+                let mut postings = inverted_index
+                    .read_postings_from_terminfo(stream.value(), IndexRecordOption::Basic)?;
+                if !has_doc_in(&mut postings, filter_doc_bitset) {
+                    continue;
+                }
+            }
             segment_result.push(term_to_data(field, field_type, stream.key()));
         }
         segment_results.push(segment_result);
@@ -302,6 +397,46 @@ async fn leaf_list_terms_single_split(
     })
 }
 
+/// Collects the documents matching the search filter, for each segment of the split.
+async fn search_filter_doc_bitsets(
+    searcher: &Searcher,
+    search_filter: &ListTermsSearchFilter,
+) -> crate::Result<Vec<BitSet>> {
+    let (query, warmup_info) = search_filter.doc_mapper.query(
+        searcher.schema().clone(),
+        &search_filter.query_ast,
+        false,
+    )?;
+    warmup(searcher, &warmup_info).await?;
+    let weight = query.weight(EnableScoring::disabled_from_searcher(searcher))?;
+
+    let mut doc_bitsets = Vec::with_capacity(searcher.segment_readers().len());
+    for segment_reader in searcher.segment_readers() {
+        let mut doc_bitset = BitSet::with_max_value(segment_reader.max_doc());
+        weight.for_each_no_score(segment_reader, &mut |docs| {
+            for &doc in docs {
+                if !segment_reader.is_deleted(doc) {
+                    doc_bitset.insert(doc);
+                }
+            }
+        })?;
+        doc_bitsets.push(doc_bitset);
+    }
+    Ok(doc_bitsets)
+}
+
+/// Returns true if one of the documents of the posting list is in the bitset.
+fn has_doc_in(postings: &mut impl Postings, doc_bitset: &BitSet) -> bool {
+    let mut doc = postings.doc();
+    while doc != TERMINATED {
+        if doc_bitset.contains(doc) {
+            return true;
+        }
+        doc = postings.advance();
+    }
+    false
+}
+
 fn term_from_data(field: Field, field_type: &FieldType, data: &[u8]) -> Term {
     let mut term = Term::from_field_bool(field, false);
     term.clear_with_type(field_type.value_type());
@@ -321,6 +456,7 @@ fn term_to_data(field: Field, field_type: &FieldType, field_value: &[u8]) -> Vec
 pub async fn leaf_list_terms(
     searcher_context: Arc<SearcherContext>,
     request: &ListTermsRequest,
+    search_filter_opt: Option<&ListTermsSearchFilter>,
     index_storage: Arc<dyn Storage>,
     splits: &[SplitIdAndFooterOffsets],
 ) -> Result<LeafListTermsResponse, SearchError> {
@@ -331,10 +467,10 @@ pub async fn leaf_list_terms(
             let index_storage_clone = index_storage.clone();
             let searcher_context_clone = searcher_context.clone();
             async move {
-                let _leaf_split_search_permit = searcher_context_clone.leaf_search_split_semaphore.clone()
-                    .acquire_owned()
-                    .await
-                    .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
+                let _leaf_split_search_permit = searcher_context_clone
+                    .search_permit_provider
+                    .get_permit(index_storage_clone.uri().as_str())
+                    .await;
                 // TODO dedicated counter and timer?
                 crate::SEARCH_METRICS.leaf_searches_splits_total.inc();
                 let timer = crate::SEARCH_METRICS
@@ -343,6 +479,7 @@ pub async fn leaf_list_terms(
                 let leaf_search_single_split_res = leaf_list_terms_single_split(
                     &searcher_context_clone,
                     request,
+                    search_filter_opt,
                     index_storage_clone,
                     split.clone(),
                 )
@@ -380,6 +517,7 @@ pub async fn leaf_list_terms(
             split_id,
             error: err.to_string(),
             retryable_error: true,
+            ..Default::default()
         })
         .collect();
     let merged_search_response = LeafListTermsResponse {
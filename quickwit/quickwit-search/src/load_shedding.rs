@@ -0,0 +1,292 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use quickwit_config::SearcherConfig;
+use tokio::time::Instant;
+
+use crate::metrics::SEARCH_METRICS;
+use crate::search_memory::SearchMemoryPool;
+use crate::SearchError;
+
+/// Weight of a new permit wait time in the moving average of the permit wait times.
+const PERMIT_WAIT_SMOOTHING_FACTOR: f64 = 0.2;
+
+/// The moving average of the permit wait times is halved every `PERMIT_WAIT_HALF_LIFE` without
+/// new observation, so that a searcher rejecting all the requests eventually recovers.
+const PERMIT_WAIT_HALF_LIFE: Duration = Duration::from_secs(5);
+
+/// Rejects the new leaf search requests while the searcher is overloaded, instead of queueing
+/// them until they time out.
+///
+/// The pressure of the searcher is the maximum of three ratios, each reaching 1 when the
+/// corresponding resource is saturated:
+/// - the number of split searches waiting for a permit over `max_num_concurrent_split_searches`;
+/// - the moving average of the permit wait times over `max_permit_wait`;
+/// - the memory used in the search memory pool over its capacity.
+///
+/// The pressure score is always computed and exported, but requests are only rejected when load
+/// shedding is enabled.
+pub(crate) struct LoadShedder {
+    pressure_threshold_opt: Option<f64>,
+    max_permit_wait: Duration,
+    max_num_concurrent_split_searches: AtomicUsize,
+    num_split_searches: AtomicUsize,
+    permit_wait: Mutex<PermitWaitAverage>,
+    search_memory_pool: SearchMemoryPool,
+}
+
+impl LoadShedder {
+    pub fn new(searcher_config: &SearcherConfig, search_memory_pool: SearchMemoryPool) -> Self {
+        let load_shedding_config = searcher_config.load_shedding.unwrap_or_default();
+        let pressure_threshold_opt = searcher_config.load_shedding.map(|load_shedding_config| {
+            load_shedding_config.pressure_threshold_percent as f64 / 100.0
+        });
+        Self {
+            pressure_threshold_opt,
+            max_permit_wait: load_shedding_config.max_permit_wait(),
+            max_num_concurrent_split_searches: AtomicUsize::new(
+                searcher_config.max_num_concurrent_split_searches,
+            ),
+            num_split_searches: AtomicUsize::new(0),
+            permit_wait: Mutex::new(PermitWaitAverage::new(Instant::now())),
+            search_memory_pool,
+        }
+    }
+
+    /// Admits a leaf request searching `num_splits` splits, or rejects it with a retryable
+    /// `Unavailable` error if the pressure of the searcher is above the threshold.
+    ///
+    /// The splits are accounted for until the returned guard is dropped.
+    pub fn try_admit(self: &Arc<Self>, num_splits: usize) -> crate::Result<SplitSearchesGuard> {
+        let pressure = self.pressure(Instant::now());
+        SEARCH_METRICS.leaf_search_pressure_score.set(pressure);
+
+        if let Some(pressure_threshold) = self.pressure_threshold_opt {
+            if pressure >= pressure_threshold {
+                SEARCH_METRICS.leaf_search_shed_requests_total.inc();
+                return Err(SearchError::Unavailable(format!(
+                    "searcher is overloaded (pressure score: {:.0}%)",
+                    pressure * 100.0
+                )));
+            }
+        }
+        self.num_split_searches
+            .fetch_add(num_splits, Ordering::Relaxed);
+        Ok(SplitSearchesGuard {
+            load_shedder: self.clone(),
+            num_splits,
+        })
+    }
+
+    /// Records the time a split search waited for its permit.
+    pub fn record_permit_wait(&self, permit_wait: Duration) {
+        self.permit_wait
+            .lock()
+            .unwrap()
+            .record(permit_wait, Instant::now());
+    }
+
+    /// Updates the number of split searches that can run concurrently, after the searcher config
+    /// was reloaded.
+    pub fn set_max_num_concurrent_split_searches(&self, max_num_concurrent_split_searches: usize) {
+        self.max_num_concurrent_split_searches
+            .store(max_num_concurrent_split_searches, Ordering::Relaxed);
+    }
+
+    fn pressure(&self, now: Instant) -> f64 {
+        let max_num_concurrent_split_searches = self
+            .max_num_concurrent_split_searches
+            .load(Ordering::Relaxed);
+        let num_split_searches = self.num_split_searches.load(Ordering::Relaxed);
+        let num_queued_split_searches =
+            num_split_searches.saturating_sub(max_num_concurrent_split_searches);
+        let queue_pressure =
+            num_queued_split_searches as f64 / max_num_concurrent_split_searches.max(1) as f64;
+
+        let avg_permit_wait = self.permit_wait.lock().unwrap().get(now);
+        let permit_wait_pressure =
+            avg_permit_wait.as_secs_f64() / self.max_permit_wait.as_secs_f64();
+
+        let memory_pressure = self.search_memory_pool.num_bytes_used() as f64
+            / self.search_memory_pool.capacity().max(1) as f64;
+
+        queue_pressure
+            .max(permit_wait_pressure)
+            .max(memory_pressure)
+    }
+}
+
+/// Accounts for the split searches of an admitted leaf request until dropped.
+pub(crate) struct SplitSearchesGuard {
+    load_shedder: Arc<LoadShedder>,
+    num_splits: usize,
+}
+
+impl Drop for SplitSearchesGuard {
+    fn drop(&mut self) {
+        self.load_shedder
+            .num_split_searches
+            .fetch_sub(self.num_splits, Ordering::Relaxed);
+    }
+}
+
+/// Exponential moving average of the permit wait times, decaying over time.
+struct PermitWaitAverage {
+    avg_secs: f64,
+    updated_at: Instant,
+}
+
+impl PermitWaitAverage {
+    fn new(now: Instant) -> Self {
+        Self {
+            avg_secs: 0.0,
+            updated_at: now,
+        }
+    }
+
+    fn get(&self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let decay = 0.5f64.powf(elapsed.as_secs_f64() / PERMIT_WAIT_HALF_LIFE.as_secs_f64());
+        Duration::from_secs_f64(self.avg_secs * decay)
+    }
+
+    fn record(&mut self, permit_wait: Duration, now: Instant) {
+        let avg_secs = self.get(now).as_secs_f64();
+        self.avg_secs =
+            avg_secs + (permit_wait.as_secs_f64() - avg_secs) * PERMIT_WAIT_SMOOTHING_FACTOR;
+        self.updated_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+    use quickwit_config::LoadSheddingConfig;
+
+    use super::*;
+
+    fn load_shedder(pressure_threshold_percent: u32) -> Arc<LoadShedder> {
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_split_searches: 10,
+            search_memory_pool_size: ByteSize::kb(10),
+            leaf_request_memory_budget: ByteSize::kb(10),
+            load_shedding: Some(LoadSheddingConfig {
+                pressure_threshold_percent,
+                max_permit_wait_millis: 1_000,
+            }),
+            ..Default::default()
+        };
+        let search_memory_pool = SearchMemoryPool::new(searcher_config.search_memory_pool_size);
+        Arc::new(LoadShedder::new(&searcher_config, search_memory_pool))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shedder_rejects_requests_from_threshold_on_queued_split_searches() {
+        let load_shedder = load_shedder(50);
+
+        // A leaf executor that never completes its split searches: 10 of them run and the others
+        // wait for a permit.
+        let mut guards = Vec::new();
+        for _ in 0..3 {
+            guards.push(load_shedder.try_admit(4).unwrap());
+        }
+        // 2 queued split searches out of 10: 20%.
+        assert_eq!(load_shedder.pressure(Instant::now()), 0.2);
+        guards.push(load_shedder.try_admit(3).unwrap());
+
+        // 5 queued split searches out of 10: 50%, the threshold is reached.
+        assert_eq!(load_shedder.pressure(Instant::now()), 0.5);
+        let error = load_shedder.try_admit(1).err().unwrap();
+        assert!(matches!(error, SearchError::Unavailable(_)));
+
+        // Rejected requests are not accounted for.
+        assert_eq!(load_shedder.pressure(Instant::now()), 0.5);
+
+        // The executor completes a request: the pressure drops and requests are admitted again.
+        guards.pop();
+        assert_eq!(load_shedder.pressure(Instant::now()), 0.2);
+        load_shedder.try_admit(1).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shedder_rejects_requests_on_permit_wait() {
+        let load_shedder = load_shedder(50);
+
+        for _ in 0..10 {
+            load_shedder.record_permit_wait(Duration::from_secs(2));
+        }
+        assert!(load_shedder.pressure(Instant::now()) > 0.5);
+        let error = load_shedder.try_admit(1).err().unwrap();
+        assert!(matches!(error, SearchError::Unavailable(_)));
+
+        // Without new permit waits, the average decays until requests are admitted again.
+        let pressure_before = load_shedder.pressure(Instant::now());
+        tokio::time::advance(PERMIT_WAIT_HALF_LIFE).await;
+        let pressure_after = load_shedder.pressure(Instant::now());
+        assert!((pressure_after - pressure_before / 2.0).abs() < 1e-6);
+
+        tokio::time::advance(PERMIT_WAIT_HALF_LIFE * 2).await;
+        load_shedder.try_admit(1).unwrap();
+
+        // Short permit waits bring the average down too.
+        for _ in 0..10 {
+            load_shedder.record_permit_wait(Duration::from_secs(2));
+        }
+        for _ in 0..20 {
+            load_shedder.record_permit_wait(Duration::ZERO);
+        }
+        load_shedder.try_admit(1).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shedder_rejects_requests_on_memory_pool_usage() {
+        let load_shedder = load_shedder(80);
+        let budget = load_shedder
+            .search_memory_pool
+            .new_request_budget(ByteSize::kb(10));
+
+        budget.try_charge(7_000).unwrap();
+        load_shedder.try_admit(1).unwrap();
+
+        budget.try_charge(1_000).unwrap();
+        let error = load_shedder.try_admit(1).err().unwrap();
+        assert!(matches!(error, SearchError::Unavailable(_)));
+
+        budget.release(1_000);
+        load_shedder.try_admit(1).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shedder_disabled_never_rejects_requests() {
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_split_searches: 1,
+            ..Default::default()
+        };
+        let search_memory_pool = SearchMemoryPool::new(searcher_config.search_memory_pool_size);
+        let load_shedder = Arc::new(LoadShedder::new(&searcher_config, search_memory_pool));
+
+        let _guard = load_shedder.try_admit(100).unwrap();
+        assert_eq!(load_shedder.pressure(Instant::now()), 99.0);
+        load_shedder.try_admit(1).unwrap();
+    }
+}
@@ -21,20 +21,46 @@
 
 use once_cell::sync::Lazy;
 use quickwit_common::metrics::{
-    exponential_buckets, linear_buckets, new_counter, new_counter_vec, new_histogram,
-    new_histogram_vec, Histogram, HistogramVec, IntCounter, IntCounterVec,
+    exponential_buckets, linear_buckets, new_counter, new_counter_vec, new_float_counter_vec,
+    new_float_gauge, new_gauge, new_gauge_vec, new_histogram, new_histogram_vec, CounterVec, Gauge,
+    Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 pub struct SearchMetrics {
     pub root_search_requests_total: IntCounterVec<1>,
     pub root_search_request_duration_seconds: HistogramVec<1>,
     pub root_search_targeted_splits: HistogramVec<1>,
+    pub root_search_list_splits_duration_seconds: HistogramVec<1>,
+    pub root_search_list_splits_timeouts_total: IntCounterVec<1>,
     pub leaf_search_requests_total: IntCounterVec<1>,
     pub leaf_search_request_duration_seconds: HistogramVec<1>,
     pub leaf_search_targeted_splits: HistogramVec<1>,
     pub leaf_searches_splits_total: IntCounter,
     pub leaf_search_split_duration_secs: Histogram,
+    pub leaf_search_fast_path_total: IntCounterVec<1>,
+    pub leaf_search_split_num_segments: Histogram,
+    pub split_footer_fetch_dedup_total: IntCounter,
+    pub split_not_found_total: IntCounter,
+    pub split_pruning_slack_saves_total: IntCounter,
+    pub splits_pruned_by_term_presence_total: IntCounter,
+    pub suggest_requests_rejected_total: IntCounter,
     pub job_assigned_total: IntCounterVec<1>,
+    pub search_quota_rejections_total: IntCounterVec<2>,
+    pub search_cpu_seconds_total: CounterVec<1>,
+    pub search_memory_pool_in_use_bytes: IntGauge,
+    pub search_memory_limit_exceeded_total: IntCounterVec<1>,
+    pub leaf_search_hedges_issued_total: IntCounter,
+    pub leaf_search_hedges_won_total: IntCounter,
+    pub leaf_search_pressure_score: Gauge,
+    pub leaf_search_shed_requests_total: IntCounter,
+    pub leaf_search_tier_in_flight_tasks: IntGaugeVec<1>,
+    pub leaf_search_tier_queue_wait_seconds: HistogramVec<1>,
+    pub leaf_search_waiting_splits: IntGaugeVec<1>,
+    pub active_points_in_time: IntGauge,
+    pub points_in_time_opened_total: IntCounter,
+    pub leaf_aggregation_spills_total: IntCounter,
+    pub leaf_aggregation_spilled_bytes_total: IntCounter,
+    pub aggregation_spill_disk_in_use_bytes: IntGauge,
 }
 
 impl Default for SearchMetrics {
@@ -74,6 +100,22 @@ impl Default for SearchMetrics {
                 ["status"],
                 targeted_splits_buckets.clone(),
             ),
+            root_search_list_splits_duration_seconds: new_histogram_vec(
+                "root_search_list_splits_duration_seconds",
+                "Duration of the split listing step of root search requests in seconds.",
+                "search",
+                &[],
+                ["status"],
+                exponential_buckets(0.001, 2.0, 15).unwrap(),
+            ),
+            root_search_list_splits_timeouts_total: new_counter_vec(
+                "root_search_list_splits_timeouts_total",
+                "Number of root searches for which listing the splits from the metastore timed \
+                 out, per fallback: a stale cached listing, or none.",
+                "search",
+                &[],
+                ["fallback"],
+            ),
             leaf_search_requests_total: new_counter_vec(
                 "leaf_search_requests_total",
                 "Total number of leaf search gRPC requests processed.",
@@ -110,6 +152,56 @@ impl Default for SearchMetrics {
                 "search",
                 exponential_buckets(0.001, 2.0, 15).unwrap(),
             ),
+            leaf_search_fast_path_total: new_counter_vec(
+                "leaf_search_fast_path_total",
+                "Number of split searches per shortcut taken: count from the split metadata, \
+                 count from the timestamp fast field, count from the query, aggregations only, or \
+                 hits.",
+                "search",
+                &[],
+                ["fast_path"],
+            ),
+            leaf_search_split_num_segments: new_histogram(
+                "leaf_search_split_num_segments",
+                "Number of segments of the splits opened by leaf searches.",
+                "search",
+                exponential_buckets(1.0, 2.0, 8).unwrap(),
+            ),
+            split_footer_fetch_dedup_total: new_counter(
+                "split_footer_fetch_dedup_total",
+                "Number of split footer fetches served by a fetch already in flight for the same \
+                 split.",
+                "search",
+                &[],
+            ),
+            split_not_found_total: new_counter(
+                "split_not_found_total",
+                "Number of split searches that failed because the split file was missing from the \
+                 storage.",
+                "search",
+                &[],
+            ),
+            split_pruning_slack_saves_total: new_counter(
+                "split_pruning_slack_saves_total",
+                "Number of splits searched only thanks to the split pruning slack, which would \
+                 have been pruned by the time range of the search otherwise.",
+                "search",
+                &[],
+            ),
+            splits_pruned_by_term_presence_total: new_counter(
+                "splits_pruned_by_term_presence_total",
+                "Number of splits skipped by root searches because their term presence filters \
+                 rule out the values searched for.",
+                "search",
+                &[],
+            ),
+            suggest_requests_rejected_total: new_counter(
+                "suggest_requests_rejected_total",
+                "Number of suggestion requests rejected because the searcher exceeded its \
+                 suggestion request rate limit.",
+                "search",
+                &[],
+            ),
             job_assigned_total: new_counter_vec(
                 "job_assigned_total",
                 "Number of job assigned to searchers, per affinity rank.",
@@ -117,6 +209,117 @@ impl Default for SearchMetrics {
                 &[],
                 ["affinity"],
             ),
+            search_quota_rejections_total: new_counter_vec(
+                "search_quota_rejections_total",
+                "Number of root searches rejected because they exceeded a tenant search quota.",
+                "search",
+                &[],
+                ["tenant", "limit"],
+            ),
+            search_cpu_seconds_total: new_float_counter_vec(
+                "search_cpu_seconds_total",
+                "CPU time spent searching splits, in seconds.",
+                "search",
+                &[],
+                ["index"],
+            ),
+            search_memory_pool_in_use_bytes: new_gauge(
+                "search_memory_pool_in_use_bytes",
+                "Number of bytes of the search memory pool currently charged by leaf requests.",
+                "search",
+                &[],
+            ),
+            search_memory_limit_exceeded_total: new_counter_vec(
+                "search_memory_limit_exceeded_total",
+                "Number of leaf requests aborted because they exceeded a search memory limit.",
+                "search",
+                &[],
+                ["limit"],
+            ),
+            leaf_search_hedges_issued_total: new_counter(
+                "leaf_search_hedges_issued_total",
+                "Number of hedged leaf search requests sent to another searcher because the first \
+                 searcher was slower than usual.",
+                "search",
+                &[],
+            ),
+            leaf_search_hedges_won_total: new_counter(
+                "leaf_search_hedges_won_total",
+                "Number of hedged leaf search requests that responded before the original request.",
+                "search",
+                &[],
+            ),
+            leaf_search_pressure_score: new_float_gauge(
+                "leaf_search_pressure_score",
+                "Pressure score of the searcher as of the last leaf search request, in a ratio \
+                 where 1 means one of the queue, permit wait time or memory pool is saturated.",
+                "search",
+                &[],
+            ),
+            leaf_search_shed_requests_total: new_counter(
+                "leaf_search_shed_requests_total",
+                "Number of leaf search requests rejected because the pressure score of the \
+                 searcher was above the load shedding threshold.",
+                "search",
+                &[],
+            ),
+            leaf_search_tier_in_flight_tasks: new_gauge_vec(
+                "leaf_search_tier_in_flight_tasks",
+                "Number of split searches running on the search thread pool per leaf search tier.",
+                "search",
+                &[],
+                ["tier"],
+            ),
+            leaf_search_tier_queue_wait_seconds: new_histogram_vec(
+                "leaf_search_tier_queue_wait_seconds",
+                "Time split searches waited for a search thread of their leaf search tier, in \
+                 seconds.",
+                "search",
+                &[],
+                ["tier"],
+                exponential_buckets(0.001, 2.0, 15).unwrap(),
+            ),
+            leaf_search_waiting_splits: new_gauge_vec(
+                "leaf_search_waiting_splits",
+                "Number of split searches waiting for a split search permit, per index.",
+                "search",
+                &[],
+                ["index"],
+            ),
+            active_points_in_time: new_gauge(
+                "active_points_in_time",
+                "Number of unexpired point in time contexts held by the searcher, as of the last \
+                 time one was accessed.",
+                "search",
+                &[],
+            ),
+            points_in_time_opened_total: new_counter(
+                "points_in_time_opened_total",
+                "Number of point in time contexts opened by root searches.",
+                "search",
+                &[],
+            ),
+            leaf_aggregation_spills_total: new_counter(
+                "leaf_aggregation_spills_total",
+                "Number of times leaf search requests spilled their intermediate aggregation \
+                 results to disk.",
+                "search",
+                &[],
+            ),
+            leaf_aggregation_spilled_bytes_total: new_counter(
+                "leaf_aggregation_spilled_bytes_total",
+                "Number of bytes of intermediate aggregation results spilled to disk by leaf \
+                 search requests.",
+                "search",
+                &[],
+            ),
+            aggregation_spill_disk_in_use_bytes: new_gauge(
+                "aggregation_spill_disk_in_use_bytes",
+                "Number of bytes of the aggregation spill disk budget currently used by leaf \
+                 requests.",
+                "search",
+                &[],
+            ),
         }
     }
 }
@@ -0,0 +1,720 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+
+use quickwit_common::HumanDuration;
+use quickwit_proto::search::SearchRequest;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::SearchError;
+
+const DEFAULT_MOVING_AVG_WINDOW: usize = 5;
+
+/// A pipeline aggregation computed over the buckets of a parent histogram aggregation.
+///
+/// Pipeline aggregations only need the final buckets of their parent, so they are removed from
+/// the request handed to the leaves and tantivy, and the root applies them when finalizing the
+/// aggregation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PipelineAggregation {
+    /// Names of the aggregations leading from the root of the request to the parent histogram.
+    histogram_path: Vec<String>,
+    aggregation_name: String,
+    buckets_path: String,
+    gap_policy: GapPolicy,
+    kind: PipelineAggregationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PipelineAggregationKind {
+    Derivative { unit_millis_opt: Option<f64> },
+    MovingAvg { window: usize },
+}
+
+/// How the buckets lacking a value are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GapPolicy {
+    /// The bucket is ignored: it gets no value and does not feed the following buckets.
+    #[default]
+    Skip,
+    /// The missing value is replaced by zero.
+    InsertZeros,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DerivativeParams {
+    buckets_path: String,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+    #[serde(default)]
+    unit: Option<HumanDuration>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MovingAvgParams {
+    buckets_path: String,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+    #[serde(default)]
+    window: Option<usize>,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParentKind {
+    Histogram,
+    DateHistogram,
+    Other,
+}
+
+fn parent_kind_of(aggregation: &Map<String, Value>) -> ParentKind {
+    if aggregation.contains_key("histogram") {
+        ParentKind::Histogram
+    } else if aggregation.contains_key("date_histogram") {
+        ParentKind::DateHistogram
+    } else {
+        ParentKind::Other
+    }
+}
+
+fn is_pipeline_aggregation(aggregation: &Value) -> bool {
+    aggregation.as_object().map_or(false, |aggregation| {
+        aggregation.contains_key("derivative") || aggregation.contains_key("moving_avg")
+    })
+}
+
+fn invalid_pipeline_aggregation(
+    aggregation_name: &str,
+    error: impl std::fmt::Display,
+) -> SearchError {
+    SearchError::InvalidAggregationRequest(format!(
+        "invalid pipeline aggregation `{aggregation_name}`: {error}"
+    ))
+}
+
+fn parse_pipeline_aggregation(
+    histogram_path: &[String],
+    parent_kind: ParentKind,
+    aggregation_name: &str,
+    aggregation: &Map<String, Value>,
+) -> crate::Result<PipelineAggregation> {
+    if parent_kind == ParentKind::Other {
+        return Err(SearchError::InvalidAggregationRequest(format!(
+            "pipeline aggregation `{aggregation_name}` must be a sub-aggregation of a histogram \
+             or date_histogram aggregation"
+        )));
+    }
+    if aggregation.len() > 1 {
+        return Err(invalid_pipeline_aggregation(
+            aggregation_name,
+            "pipeline aggregations cannot have sub-aggregations",
+        ));
+    }
+    let (buckets_path, gap_policy, kind) = if let Some(params) = aggregation.get("derivative") {
+        let params: DerivativeParams = serde_json::from_value(params.clone())
+            .map_err(|error| invalid_pipeline_aggregation(aggregation_name, error))?;
+        if params.unit.is_some() && parent_kind != ParentKind::DateHistogram {
+            return Err(invalid_pipeline_aggregation(
+                aggregation_name,
+                "the `unit` parameter is only supported under a date_histogram aggregation",
+            ));
+        }
+        let unit_millis_opt = params
+            .unit
+            .map(|unit| unit.as_duration().as_millis() as f64)
+            .filter(|unit_millis| *unit_millis > 0.0);
+        let kind = PipelineAggregationKind::Derivative { unit_millis_opt };
+        (params.buckets_path, params.gap_policy, kind)
+    } else {
+        let params: MovingAvgParams = serde_json::from_value(aggregation["moving_avg"].clone())
+            .map_err(|error| invalid_pipeline_aggregation(aggregation_name, error))?;
+        if params
+            .model
+            .as_deref()
+            .map_or(false, |model| model != "simple")
+        {
+            return Err(invalid_pipeline_aggregation(
+                aggregation_name,
+                "only the `simple` moving average model is supported",
+            ));
+        }
+        let window = params.window.unwrap_or(DEFAULT_MOVING_AVG_WINDOW);
+        if window == 0 {
+            return Err(invalid_pipeline_aggregation(
+                aggregation_name,
+                "the moving average window must be strictly positive",
+            ));
+        }
+        let kind = PipelineAggregationKind::MovingAvg { window };
+        (params.buckets_path, params.gap_policy, kind)
+    };
+    Ok(PipelineAggregation {
+        histogram_path: histogram_path.to_vec(),
+        aggregation_name: aggregation_name.to_string(),
+        buckets_path,
+        gap_policy,
+        kind,
+    })
+}
+
+/// Returns the sibling aggregation a buckets path points to, `_count` standing for the document
+/// count of the bucket.
+fn buckets_path_target(buckets_path: &str) -> &str {
+    buckets_path
+        .split_once('.')
+        .map_or(buckets_path, |(aggregation_name, _)| aggregation_name)
+}
+
+/// Sorts the pipeline aggregations of a histogram so that the ones feeding on other pipeline
+/// aggregations are computed last.
+fn sort_sibling_pipeline_aggregations(
+    mut pipeline_aggregations: Vec<PipelineAggregation>,
+) -> crate::Result<Vec<PipelineAggregation>> {
+    let mut sorted_pipeline_aggregations = Vec::with_capacity(pipeline_aggregations.len());
+
+    while !pipeline_aggregations.is_empty() {
+        let Some(pos) = pipeline_aggregations
+            .iter()
+            .position(|pipeline_aggregation| {
+                let target = buckets_path_target(&pipeline_aggregation.buckets_path);
+                pipeline_aggregations
+                    .iter()
+                    .all(|other| other.aggregation_name != target)
+            })
+        else {
+            return Err(SearchError::InvalidAggregationRequest(format!(
+                "pipeline aggregation `{}` has a cyclic buckets path",
+                pipeline_aggregations[0].aggregation_name
+            )));
+        };
+        sorted_pipeline_aggregations.push(pipeline_aggregations.remove(pos));
+    }
+    Ok(sorted_pipeline_aggregations)
+}
+
+fn take_from_aggregations(
+    aggregations: &mut Map<String, Value>,
+    histogram_path: &mut Vec<String>,
+    parent_kind: ParentKind,
+    pipeline_aggregations: &mut Vec<PipelineAggregation>,
+) -> crate::Result<()> {
+    let pipeline_aggregation_names: Vec<String> = aggregations
+        .iter()
+        .filter(|(_, aggregation)| is_pipeline_aggregation(aggregation))
+        .map(|(aggregation_name, _)| aggregation_name.clone())
+        .collect();
+    let mut sibling_pipeline_aggregations = Vec::with_capacity(pipeline_aggregation_names.len());
+
+    for aggregation_name in pipeline_aggregation_names {
+        let Some(Value::Object(aggregation)) = aggregations.remove(&aggregation_name) else {
+            continue;
+        };
+        let pipeline_aggregation = parse_pipeline_aggregation(
+            histogram_path,
+            parent_kind,
+            &aggregation_name,
+            &aggregation,
+        )?;
+        sibling_pipeline_aggregations.push(pipeline_aggregation);
+    }
+    for pipeline_aggregation in &sibling_pipeline_aggregations {
+        let target = buckets_path_target(&pipeline_aggregation.buckets_path);
+        let is_known_target = target == "_count"
+            || aggregations.contains_key(target)
+            || sibling_pipeline_aggregations
+                .iter()
+                .any(|sibling| sibling.aggregation_name == target);
+        if !is_known_target {
+            return Err(SearchError::InvalidAggregationRequest(format!(
+                "buckets path `{}` of pipeline aggregation `{}` does not match any sibling \
+                 aggregation",
+                pipeline_aggregation.buckets_path, pipeline_aggregation.aggregation_name
+            )));
+        }
+    }
+    pipeline_aggregations.extend(sort_sibling_pipeline_aggregations(
+        sibling_pipeline_aggregations,
+    )?);
+
+    for (aggregation_name, aggregation) in aggregations.iter_mut() {
+        let Some(aggregation) = aggregation.as_object_mut() else {
+            continue;
+        };
+        let parent_kind = parent_kind_of(aggregation);
+        for sub_aggregations_key in ["aggs", "aggregations"] {
+            if let Some(sub_aggregations) = aggregation
+                .get_mut(sub_aggregations_key)
+                .and_then(Value::as_object_mut)
+            {
+                histogram_path.push(aggregation_name.clone());
+                take_from_aggregations(
+                    sub_aggregations,
+                    histogram_path,
+                    parent_kind,
+                    pipeline_aggregations,
+                )?;
+                histogram_path.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the pipeline aggregations (`derivative` and `moving_avg`) from the aggregation request
+/// of a search request and returns them, so that the root can apply them on the final buckets.
+pub(crate) fn take_pipeline_aggregations(
+    search_request: &mut SearchRequest,
+) -> crate::Result<Vec<PipelineAggregation>> {
+    let Some(aggregation_json) = search_request.aggregation_request.as_ref() else {
+        return Ok(Vec::new());
+    };
+    if !aggregation_json.contains("\"derivative\"") && !aggregation_json.contains("\"moving_avg\"")
+    {
+        return Ok(Vec::new());
+    }
+    let Ok(Value::Object(mut aggregations)) = serde_json::from_str::<Value>(aggregation_json)
+    else {
+        return Ok(Vec::new());
+    };
+    let mut pipeline_aggregations = Vec::new();
+    take_from_aggregations(
+        &mut aggregations,
+        &mut Vec::new(),
+        ParentKind::Other,
+        &mut pipeline_aggregations,
+    )?;
+    if !pipeline_aggregations.is_empty() {
+        search_request.aggregation_request = Some(Value::Object(aggregations).to_string());
+    }
+    Ok(pipeline_aggregations)
+}
+
+fn bucket_value(bucket: &Map<String, Value>, buckets_path: &str) -> Option<f64> {
+    if buckets_path == "_count" {
+        return bucket.get("doc_count").and_then(Value::as_f64);
+    }
+    let (aggregation_name, metric_name) = buckets_path
+        .split_once('.')
+        .unwrap_or((buckets_path, "value"));
+    bucket
+        .get(aggregation_name)?
+        .get(metric_name)
+        .and_then(Value::as_f64)
+        .filter(|value| value.is_finite())
+}
+
+fn apply_on_buckets(
+    mut buckets: Vec<&mut Map<String, Value>>,
+    pipeline_aggregation: &PipelineAggregation,
+) {
+    // Keyed histograms return their buckets in a map sorted by the string representation of the
+    // keys.
+    buckets.sort_by(|left, right| {
+        let left_key = left.get("key").and_then(Value::as_f64).unwrap_or(f64::NAN);
+        let right_key = right.get("key").and_then(Value::as_f64).unwrap_or(f64::NAN);
+        left_key.total_cmp(&right_key)
+    });
+    let values: Vec<Option<f64>> = buckets
+        .iter()
+        .map(|bucket| {
+            let value_opt = bucket_value(bucket, &pipeline_aggregation.buckets_path);
+            match pipeline_aggregation.gap_policy {
+                GapPolicy::Skip => value_opt,
+                GapPolicy::InsertZeros => Some(value_opt.unwrap_or(0.0)),
+            }
+        })
+        .collect();
+    let aggregation_name = &pipeline_aggregation.aggregation_name;
+
+    match pipeline_aggregation.kind {
+        PipelineAggregationKind::Derivative { unit_millis_opt } => {
+            let mut previous_opt: Option<(f64, f64)> = None;
+
+            for (bucket, value_opt) in buckets.into_iter().zip(values) {
+                let Some(value) = value_opt else {
+                    continue;
+                };
+                let key = bucket
+                    .get("key")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(f64::NAN);
+
+                if let Some((previous_key, previous_value)) = previous_opt {
+                    let derivative = value - previous_value;
+                    let mut result = json!({ "value": derivative });
+
+                    if let Some(unit_millis) = unit_millis_opt {
+                        let num_units = (key - previous_key) / unit_millis;
+                        result["normalized_value"] = json!(derivative / num_units);
+                    }
+                    bucket.insert(aggregation_name.clone(), result);
+                }
+                previous_opt = Some((key, value));
+            }
+        }
+        PipelineAggregationKind::MovingAvg { window } => {
+            let mut window_values: VecDeque<f64> = VecDeque::with_capacity(window);
+
+            for (bucket, value_opt) in buckets.into_iter().zip(values) {
+                // Like Elasticsearch, the moving average of a bucket is computed over the values
+                // of the buckets preceding it.
+                if !window_values.is_empty() {
+                    let moving_avg = window_values.iter().sum::<f64>() / window_values.len() as f64;
+                    bucket.insert(aggregation_name.clone(), json!({ "value": moving_avg }));
+                }
+                if let Some(value) = value_opt {
+                    if window_values.len() == window {
+                        window_values.pop_front();
+                    }
+                    window_values.push_back(value);
+                }
+            }
+        }
+    }
+}
+
+fn buckets_mut(aggregation_result: &mut Value) -> Vec<&mut Map<String, Value>> {
+    match aggregation_result.get_mut("buckets") {
+        Some(Value::Array(buckets)) => buckets
+            .iter_mut()
+            .filter_map(Value::as_object_mut)
+            .collect(),
+        Some(Value::Object(buckets)) => buckets
+            .values_mut()
+            .filter_map(Value::as_object_mut)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn apply_pipeline_aggregation(
+    aggregation_results: &mut Map<String, Value>,
+    histogram_path: &[String],
+    pipeline_aggregation: &PipelineAggregation,
+) {
+    let Some((aggregation_name, sub_histogram_path)) = histogram_path.split_first() else {
+        return;
+    };
+    let Some(aggregation_result) = aggregation_results.get_mut(aggregation_name) else {
+        return;
+    };
+    let buckets = buckets_mut(aggregation_result);
+
+    if sub_histogram_path.is_empty() {
+        apply_on_buckets(buckets, pipeline_aggregation);
+        return;
+    }
+    for bucket in buckets {
+        apply_pipeline_aggregation(bucket, sub_histogram_path, pipeline_aggregation);
+    }
+}
+
+/// Computes the pipeline aggregations over the final buckets of their parent histograms, and
+/// adds their values to the buckets.
+pub(crate) fn apply_pipeline_aggregations(
+    aggregation_results: &mut Value,
+    pipeline_aggregations: &[PipelineAggregation],
+) {
+    let Some(aggregation_results) = aggregation_results.as_object_mut() else {
+        return;
+    };
+    for pipeline_aggregation in pipeline_aggregations {
+        apply_pipeline_aggregation(
+            aggregation_results,
+            &pipeline_aggregation.histogram_path,
+            pipeline_aggregation,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take_from_json(aggregations: Value) -> crate::Result<(Vec<PipelineAggregation>, Value)> {
+        let mut search_request = SearchRequest {
+            aggregation_request: Some(aggregations.to_string()),
+            ..Default::default()
+        };
+        let pipeline_aggregations = take_pipeline_aggregations(&mut search_request)?;
+        let aggregations =
+            serde_json::from_str(&search_request.aggregation_request.unwrap()).unwrap();
+        Ok((pipeline_aggregations, aggregations))
+    }
+
+    fn histogram_results(buckets: &[(f64, Value)]) -> Value {
+        let buckets: Vec<Value> = buckets
+            .iter()
+            .map(|(key, avg_price)| {
+                json!({
+                    "key": key,
+                    "doc_count": 1,
+                    "avg_price": { "value": avg_price },
+                })
+            })
+            .collect();
+        json!({ "per_second": { "buckets": buckets } })
+    }
+
+    fn pipeline_values(aggregation_results: &Value, aggregation_name: &str) -> Vec<Option<f64>> {
+        aggregation_results["per_second"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket[aggregation_name]["value"].as_f64())
+            .collect()
+    }
+
+    #[test]
+    fn test_take_pipeline_aggregations() {
+        let (pipeline_aggregations, aggregations) = take_from_json(json!({
+            "by_host": {
+                "terms": { "field": "host" },
+                "aggs": {
+                    "per_second": {
+                        "date_histogram": { "field": "timestamp", "fixed_interval": "1s" },
+                        "aggs": {
+                            "avg_price": { "avg": { "field": "price" } },
+                            "avg_price_deriv": {
+                                "derivative": { "buckets_path": "avg_price_mavg", "unit": "1s" }
+                            },
+                            "avg_price_mavg": {
+                                "moving_avg": { "buckets_path": "avg_price", "window": 3 }
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            aggregations,
+            json!({
+                "by_host": {
+                    "terms": { "field": "host" },
+                    "aggs": {
+                        "per_second": {
+                            "date_histogram": { "field": "timestamp", "fixed_interval": "1s" },
+                            "aggs": {
+                                "avg_price": { "avg": { "field": "price" } },
+                            }
+                        }
+                    }
+                }
+            })
+        );
+        // The derivative feeds on the moving average, so it comes last.
+        assert_eq!(
+            pipeline_aggregations,
+            [
+                PipelineAggregation {
+                    histogram_path: vec!["by_host".to_string(), "per_second".to_string()],
+                    aggregation_name: "avg_price_mavg".to_string(),
+                    buckets_path: "avg_price".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                    kind: PipelineAggregationKind::MovingAvg { window: 3 },
+                },
+                PipelineAggregation {
+                    histogram_path: vec!["by_host".to_string(), "per_second".to_string()],
+                    aggregation_name: "avg_price_deriv".to_string(),
+                    buckets_path: "avg_price_mavg".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                    kind: PipelineAggregationKind::Derivative {
+                        unit_millis_opt: Some(1_000.0)
+                    },
+                },
+            ]
+        );
+        let (pipeline_aggregations, _) =
+            take_from_json(json!({ "avg_price": { "avg": { "field": "price" } } })).unwrap();
+        assert!(pipeline_aggregations.is_empty());
+    }
+
+    #[test]
+    fn test_take_pipeline_aggregations_invalid() {
+        let error = take_from_json(json!({
+            "by_host": {
+                "terms": { "field": "host" },
+                "aggs": { "count_deriv": { "derivative": { "buckets_path": "_count" } } }
+            }
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("must be a sub-aggregation of a histogram"));
+
+        let error = take_from_json(json!({
+            "prices": {
+                "histogram": { "field": "price", "interval": 10.0 },
+                "aggs": { "count_deriv": { "derivative": { "buckets_path": "avg" } } }
+            }
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("does not match any sibling"));
+
+        let error = take_from_json(json!({
+            "prices": {
+                "histogram": { "field": "price", "interval": 10.0 },
+                "aggs": {
+                    "count_deriv": { "derivative": { "buckets_path": "_count", "unit": "1s" } }
+                }
+            }
+        }))
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("only supported under a date_histogram"));
+
+        let error = take_from_json(json!({
+            "prices": {
+                "histogram": { "field": "price", "interval": 10.0 },
+                "aggs": {
+                    "count_mavg": {
+                        "moving_avg": { "buckets_path": "_count", "model": "holt" }
+                    }
+                }
+            }
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("`simple` moving average model"));
+
+        let error = take_from_json(json!({
+            "prices": {
+                "histogram": { "field": "price", "interval": 10.0 },
+                "aggs": {
+                    "a": { "derivative": { "buckets_path": "b" } },
+                    "b": { "derivative": { "buckets_path": "a" } }
+                }
+            }
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("cyclic buckets path"));
+    }
+
+    #[test]
+    fn test_derivative_over_uneven_buckets() {
+        let pipeline_aggregation = |unit_millis_opt, gap_policy| PipelineAggregation {
+            histogram_path: vec!["per_second".to_string()],
+            aggregation_name: "avg_price_deriv".to_string(),
+            buckets_path: "avg_price".to_string(),
+            gap_policy,
+            kind: PipelineAggregationKind::Derivative { unit_millis_opt },
+        };
+        // The bucket at 2s has no value, and the bucket at 4s is missing altogether.
+        let buckets = [
+            (0.0, json!(10.0)),
+            (1_000.0, json!(14.0)),
+            (2_000.0, Value::Null),
+            (3_000.0, json!(20.0)),
+            (5_000.0, json!(16.0)),
+        ];
+        let mut aggregation_results = histogram_results(&buckets);
+        apply_pipeline_aggregations(
+            &mut aggregation_results,
+            &[pipeline_aggregation(Some(1_000.0), GapPolicy::Skip)],
+        );
+        assert_eq!(
+            pipeline_values(&aggregation_results, "avg_price_deriv"),
+            [None, Some(4.0), None, Some(6.0), Some(-4.0)]
+        );
+        let normalized_values: Vec<Option<f64>> = aggregation_results["per_second"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| bucket["avg_price_deriv"]["normalized_value"].as_f64())
+            .collect();
+        assert_eq!(
+            normalized_values,
+            [None, Some(4.0), None, Some(3.0), Some(-2.0)]
+        );
+
+        let mut aggregation_results = histogram_results(&buckets);
+        apply_pipeline_aggregations(
+            &mut aggregation_results,
+            &[pipeline_aggregation(None, GapPolicy::InsertZeros)],
+        );
+        assert_eq!(
+            pipeline_values(&aggregation_results, "avg_price_deriv"),
+            [None, Some(4.0), Some(-14.0), Some(20.0), Some(-4.0)]
+        );
+        assert!(
+            aggregation_results["per_second"]["buckets"][1]["avg_price_deriv"]
+                .get("normalized_value")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_moving_avg_window_edges() {
+        let pipeline_aggregation = PipelineAggregation {
+            histogram_path: vec!["per_second".to_string()],
+            aggregation_name: "avg_price_mavg".to_string(),
+            buckets_path: "avg_price".to_string(),
+            gap_policy: GapPolicy::Skip,
+            kind: PipelineAggregationKind::MovingAvg { window: 2 },
+        };
+        let mut aggregation_results = histogram_results(&[
+            (0.0, json!(2.0)),
+            (1_000.0, json!(4.0)),
+            (2_000.0, Value::Null),
+            (3_000.0, json!(12.0)),
+            (4_000.0, json!(0.0)),
+        ]);
+        apply_pipeline_aggregations(&mut aggregation_results, &[pipeline_aggregation]);
+        // The first bucket has no preceding value, the second one only has one, and the empty
+        // bucket does not enter the window.
+        assert_eq!(
+            pipeline_values(&aggregation_results, "avg_price_mavg"),
+            [None, Some(2.0), Some(3.0), Some(3.0), Some(8.0)]
+        );
+    }
+
+    #[test]
+    fn test_apply_pipeline_aggregations_keyed_buckets_and_doc_count() {
+        let pipeline_aggregation = PipelineAggregation {
+            histogram_path: vec!["prices".to_string()],
+            aggregation_name: "count_deriv".to_string(),
+            buckets_path: "_count".to_string(),
+            gap_policy: GapPolicy::Skip,
+            kind: PipelineAggregationKind::Derivative {
+                unit_millis_opt: None,
+            },
+        };
+        let mut aggregation_results = json!({
+            "prices": {
+                "buckets": {
+                    "100": { "key": 100.0, "doc_count": 7 },
+                    "20": { "key": 20.0, "doc_count": 3 },
+                    "60": { "key": 60.0, "doc_count": 1 },
+                }
+            }
+        });
+        apply_pipeline_aggregations(&mut aggregation_results, &[pipeline_aggregation]);
+        let buckets = &aggregation_results["prices"]["buckets"];
+        assert!(buckets["20"].get("count_deriv").is_none());
+        assert_eq!(buckets["60"]["count_deriv"]["value"], -2.0);
+        assert_eq!(buckets["100"]["count_deriv"]["value"], 6.0);
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use quickwit_metastore::SplitMetadata;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::metrics::SEARCH_METRICS;
+use crate::root::max_scroll_ttl;
+use crate::{ClusterClient, SearchError};
+
+/// Maximum number of point in time contexts held by a searcher. When it is reached, the contexts
+/// closest to their expiration are evicted first.
+pub(crate) const MAX_NUM_POINTS_IN_TIME: usize = 1_000;
+
+/// Prefix of the keys under which the point in time contexts are replicated across the
+/// searchers. It sets them apart from the scroll contexts.
+const POINT_IN_TIME_KEY_PREFIX: &[u8] = b"pit:";
+
+/// Point in time recorded by a search request: the set of splits published in the indexes it
+/// targeted when it ran.
+///
+/// All the published splits are recorded, not only the ones matching the query of the request
+/// opening the point in time. The subsequent requests of a paginated search passing the ID of
+/// the point in time prune and search that set of splits instead of the splits currently
+/// published, so that splits published or merged
+/// between two pages do not cause duplicate or missing hits. The metadata of the splits also
+/// records their delete opstamp, hence the delete tasks applied after the point in time was
+/// recorded are not visible either.
+///
+/// Like scroll contexts, point in time contexts are replicated over a few searchers with the
+/// `put_kv` and `get_kv` RPCs, and expire once their keep alive has elapsed.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PointInTime {
+    pit_ulid: Ulid,
+    pub index_id_patterns: Vec<String>,
+    pub split_metadatas: Vec<SplitMetadata>,
+}
+
+impl PointInTime {
+    pub fn new(index_id_patterns: Vec<String>, split_metadatas: Vec<SplitMetadata>) -> Self {
+        Self {
+            pit_ulid: Ulid::new(),
+            index_id_patterns,
+            split_metadatas,
+        }
+    }
+
+    pub fn pit_id(&self) -> String {
+        self.pit_ulid.to_string()
+    }
+
+    /// Loads the point in time with the given ID. Unknown, malformed, and expired IDs are
+    /// reported as not found.
+    pub async fn load(pit_id: &str, cluster_client: &ClusterClient) -> crate::Result<Self> {
+        let not_found_error = || SearchError::PointInTimeNotFound {
+            pit_id: pit_id.to_string(),
+        };
+        let pit_ulid = Ulid::from_string(pit_id).map_err(|_| not_found_error())?;
+        let payload = cluster_client
+            .get_kv(&point_in_time_key(pit_ulid))
+            .await
+            .ok_or_else(not_found_error)?;
+        let point_in_time: PointInTime =
+            serde_json::from_slice(&payload).context("failed to deserialize point in time")?;
+        Ok(point_in_time)
+    }
+
+    /// Replicates the point in time, which expires after `keep_alive` unless it is saved again.
+    pub async fn save(&self, keep_alive: Duration, cluster_client: &ClusterClient) {
+        let payload =
+            serde_json::to_vec(self).expect("serializing a point in time should not fail");
+        cluster_client
+            .put_kv(&point_in_time_key(self.pit_ulid), &payload, keep_alive)
+            .await;
+    }
+
+    /// Checks that the index ID patterns of a request using the point in time are the ones of the
+    /// request that recorded it. Requests that do not specify any index ID patterns target the
+    /// indexes of the point in time.
+    pub fn check_index_id_patterns(
+        &self,
+        index_id_patterns: &mut Vec<String>,
+    ) -> crate::Result<()> {
+        if index_id_patterns.is_empty() {
+            index_id_patterns.clone_from(&self.index_id_patterns);
+            return Ok(());
+        }
+        if *index_id_patterns != self.index_id_patterns {
+            return Err(SearchError::InvalidArgument(format!(
+                "point in time `{}` was opened on indexes `{}`, not `{}`",
+                self.pit_id(),
+                self.index_id_patterns.join(","),
+                index_id_patterns.join(",")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the keep alive requested for a point in time, if any.
+pub(crate) fn get_pit_keep_alive_duration(
+    pit_keep_alive_secs_opt: Option<u32>,
+) -> crate::Result<Option<Duration>> {
+    let Some(pit_keep_alive_secs) = pit_keep_alive_secs_opt else {
+        return Ok(None);
+    };
+    let pit_keep_alive = Duration::from_secs(pit_keep_alive_secs as u64);
+    // The recorded splits must outlive the point in time, so the same limit as for scroll
+    // contexts applies.
+    let max_pit_keep_alive = max_scroll_ttl();
+    if pit_keep_alive.is_zero() || pit_keep_alive > max_pit_keep_alive {
+        return Err(SearchError::InvalidArgument(format!(
+            "point in time keep alive must be between 1 and {} secs",
+            max_pit_keep_alive.as_secs()
+        )));
+    }
+    Ok(Some(pit_keep_alive))
+}
+
+fn point_in_time_key(pit_ulid: Ulid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(POINT_IN_TIME_KEY_PREFIX.len() + 16);
+    key.extend_from_slice(POINT_IN_TIME_KEY_PREFIX);
+    key.extend_from_slice(&u128::from(pit_ulid).to_le_bytes());
+    key
+}
+
+pub(crate) fn is_point_in_time_key(key: &[u8]) -> bool {
+    key.starts_with(POINT_IN_TIME_KEY_PREFIX)
+}
+
+pub(crate) fn record_num_points_in_time(num_points_in_time: usize) {
+    SEARCH_METRICS
+        .active_points_in_time
+        .set(num_points_in_time as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_in_time_key() {
+        let pit_ulid = Ulid::new();
+        let key = point_in_time_key(pit_ulid);
+        assert_eq!(key.len(), 20);
+        assert!(is_point_in_time_key(&key));
+        // Scroll keys are the 16 bytes of a ULID.
+        assert!(!is_point_in_time_key(&u128::from(pit_ulid).to_le_bytes()));
+    }
+
+    #[test]
+    fn test_pit_keep_alive_duration() {
+        assert!(get_pit_keep_alive_duration(None).unwrap().is_none());
+        assert_eq!(
+            get_pit_keep_alive_duration(Some(60)).unwrap(),
+            Some(Duration::from_secs(60))
+        );
+        get_pit_keep_alive_duration(Some(0)).unwrap_err();
+        get_pit_keep_alive_duration(Some(u32::MAX)).unwrap_err();
+    }
+
+    #[test]
+    fn test_point_in_time_check_index_id_patterns() {
+        let point_in_time = PointInTime::new(vec!["index-*".to_string()], Vec::new());
+
+        let mut index_id_patterns = Vec::new();
+        point_in_time
+            .check_index_id_patterns(&mut index_id_patterns)
+            .unwrap();
+        assert_eq!(index_id_patterns, ["index-*"]);
+
+        point_in_time
+            .check_index_id_patterns(&mut index_id_patterns)
+            .unwrap();
+
+        let mut index_id_patterns = vec!["other-index".to_string()];
+        let error = point_in_time
+            .check_index_id_patterns(&mut index_id_patterns)
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+}
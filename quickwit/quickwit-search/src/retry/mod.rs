@@ -134,6 +134,7 @@ mod tests {
             timestamp_start: None,
             timestamp_end: None,
             num_docs: 0,
+            delete_opstamp: 0,
         };
         let client_for_retry = retry_client(
             &search_job_placer,
@@ -98,6 +98,7 @@ mod tests {
                         timestamp_start: None,
                         timestamp_end: None,
                         num_docs: 0,
+                        delete_opstamp: 0,
                     },
                     SplitIdAndFooterOffsets {
                         split_id: "split_2".to_string(),
@@ -106,6 +107,7 @@ mod tests {
                         timestamp_start: None,
                         timestamp_end: None,
                         num_docs: 0,
+                        delete_opstamp: 0,
                     },
                 ],
             }],
@@ -148,6 +150,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            ..Default::default()
         };
         let response_res = Ok(LeafSearchResponse {
             num_hits: 0,
@@ -18,54 +18,86 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
-use std::sync::OnceLock;
+use std::ops::Bound;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use anyhow::Context;
 use futures::future::try_join_all;
+use futures::{stream, StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_common::pretty::PrettySample;
 use quickwit_common::shared_consts;
 use quickwit_common::uri::Uri;
-use quickwit_config::build_doc_mapper;
-use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
-use quickwit_doc_mapper::DYNAMIC_FIELD_NAME;
-use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
+use quickwit_config::{build_doc_mapper, SearchSettings, SearcherConfig};
+use quickwit_doc_mapper::tag_pruning::{extract_tags_from_query, TagFilterAst};
+use quickwit_doc_mapper::term_presence::extract_term_presence_filter_from_query;
+use quickwit_doc_mapper::{DocMapper, DYNAMIC_FIELD_NAME};
+use quickwit_metastore::{
+    IndexIdMatcher, IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata,
+};
 use quickwit_proto::metastore::{
-    ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+    ListIndexesMetadataRequest, MarkSplitsForDeletionRequest, MetastoreService,
+    MetastoreServiceClient,
 };
 use quickwit_proto::search::{
-    FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafRequestRef, LeafSearchRequest,
-    LeafSearchResponse, PartialHit, SearchPlanResponse, SearchRequest, SearchResponse,
-    SnippetRequest, SortDatetimeFormat, SortField, SortValue, SplitIdAndFooterOffsets,
+    CountHits, FetchDocsRequest, FetchDocsResponse, FetchDocsSource, HistogramMissingDocCount, Hit,
+    LeafHit, LeafRequestRef, LeafSearchRequest, LeafSearchResponse, NumHitsRelation, PartialHit,
+    SearchPlanResponse, SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat,
+    SortField, SortValue, SplitIdAndFooterOffsets, SplitSearchError, SplitSearchErrorCode,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_query::query_ast::{
-    BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
+    BoolQuery, QueryAst, QueryAstLimits, QueryAstTransformer, QueryAstVisitor, RangeQuery,
+    TermQuery, TermSetQuery,
 };
 use serde::{Deserialize, Serialize};
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
 use tantivy::schema::{Field, FieldEntry, FieldType, Schema};
-use tantivy::TantivyError;
-use tracing::{debug, info, info_span, instrument};
+use tantivy::time::OffsetDateTime;
+use tantivy::{DateTime, TantivyError};
+use tracing::{debug, info, info_span, instrument, warn};
 
+use crate::aggregation_sampling::{
+    aggregation_sample_rate, sampled_aggregations_warning, scale_sampled_aggregation_results,
+    validate_aggregation_sample_rate,
+};
 use crate::cluster_client::ClusterClient;
 use crate::collector::{make_merge_collector, QuickwitAggregations};
+use crate::es_timestamp_alias::rewrite_es_timestamp_alias;
+use crate::fetch_docs::select_fetch_docs_source;
 use crate::find_trace_ids_collector::Span;
+use crate::histogram_missing::{
+    apply_histogram_missing_doc_counts, extract_histogram_missing, strip_histogram_missing,
+    HistogramMissing,
+};
+use crate::index_pseudo_field::{
+    filter_indexes_on_index_pseudo_field, sort_hits_on_index_pseudo_field,
+    take_index_pseudo_field_sort,
+};
+use crate::leaf::RemoveTimestampRange;
 use crate::metrics::SEARCH_METRICS;
+use crate::pipeline_aggregation::{
+    apply_pipeline_aggregations, take_pipeline_aggregations, PipelineAggregation,
+};
+use crate::point_in_time::{get_pit_keep_alive_duration, PointInTime};
 use crate::scroll_context::{ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_job_placer::{group_by, group_jobs_by_index_id, Job};
-use crate::search_response_rest::StorageRequestCount;
-use crate::service::SearcherContext;
+use crate::search_response_rest::{SplitPruningStats, StorageRequestCount};
+use crate::service::{deserialize_doc_mapper, SearcherContext};
+use crate::split_pruning::{
+    compute_split_pruning_stats, prune_splits_by_term_presence, record_split_pruning_slack_saves,
+    split_matches_time_range_and_tags, widen_time_range_by_slack,
+};
 use crate::{
-    extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
-    SearchPlanResponseRest, SearchServiceClient,
+    extract_split_and_footer_offsets, list_all_splits, list_relevant_splits, SearchError,
+    SearchJobPlacer, SearchPlanResponseRest, SearchServiceClient,
 };
 
 /// Maximum accepted scroll TTL.
-fn max_scroll_ttl() -> Duration {
+pub(crate) fn max_scroll_ttl() -> Duration {
     static MAX_SCROLL_TTL_LOCK: OnceLock<Duration> = OnceLock::new();
     *MAX_SCROLL_TTL_LOCK.get_or_init(|| {
         let split_deletion_grace_period = shared_consts::split_deletion_grace_period();
@@ -81,6 +113,9 @@ fn max_scroll_ttl() -> Duration {
 
 const SORT_DOC_FIELD_NAMES: &[&str] = &["_shard_doc", "_doc"];
 
+/// Maximum number of splits counted concurrently by [`root_count_hits_per_split`].
+const MAX_CONCURRENT_SPLIT_COUNTS: usize = 8;
+
 /// SearchJob to be assigned to search clients by the [`SearchJobPlacer`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct SearchJob {
@@ -166,11 +201,12 @@ pub struct IndexMetasForLeafSearch {
 pub(crate) type IndexesMetasForLeafSearch = HashMap<IndexUid, IndexMetasForLeafSearch>;
 
 #[derive(Debug)]
-struct RequestMetadata {
-    timestamp_field_opt: Option<String>,
-    query_ast_resolved: QueryAst,
-    indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
-    sort_fields_is_datetime: HashMap<String, bool>,
+pub(crate) struct RequestMetadata {
+    pub(crate) timestamp_field_opt: Option<String>,
+    pub(crate) query_ast_resolved: QueryAst,
+    pub(crate) indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
+    pub(crate) sort_fields_is_datetime: HashMap<String, bool>,
+    pub(crate) warnings: Vec<String>,
 }
 
 /// Validates request against each index's doc mapper and ensures that:
@@ -183,7 +219,7 @@ struct RequestMetadata {
 /// needed for leaf search requests.
 /// Note: the requirements on timestamp fields and resolved query ASTs can be lifted
 /// but it adds complexity that does not seem needed right now.
-fn validate_request_and_build_metadata(
+pub(crate) fn validate_request_and_build_metadata(
     indexes_metadata: &[IndexMetadata],
     search_request: &SearchRequest,
 ) -> crate::Result<RequestMetadata> {
@@ -193,11 +229,20 @@ fn validate_request_and_build_metadata(
     )?;
     let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast_limits = QueryAstLimits::default().with_overrides(
+        search_request
+            .max_query_ast_depth
+            .map(|max_depth| max_depth as usize),
+        search_request
+            .max_query_ast_clause_count
+            .map(|max_clause_count| max_clause_count as usize),
+    );
     let mut indexes_meta_for_leaf_search: HashMap<IndexUid, IndexMetasForLeafSearch> =
         HashMap::new();
     let mut query_ast_resolved_opt: Option<QueryAst> = None;
     let mut timestamp_field_opt: Option<String> = None;
     let mut sort_fields_is_datetime: HashMap<String, bool> = HashMap::new();
+    let mut warnings: Vec<String> = Vec::new();
 
     for index_metadata in indexes_metadata {
         let doc_mapper = build_doc_mapper(
@@ -207,11 +252,18 @@ fn validate_request_and_build_metadata(
         .map_err(|err| {
             SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
         })?;
-        let query_ast_resolved_for_index = query_ast
-            .clone()
-            .parse_user_query(doc_mapper.default_search_fields())
+        let query_ast_resolved_for_index = doc_mapper
+            .parse_user_query(query_ast.clone(), &search_request.default_search_fields)
             // We convert the error to return a 400 to the user (and not a 500).
             .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        query_ast_limits
+            .check(&query_ast_resolved_for_index)
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        let query_ast_resolved_for_index = apply_search_filter(
+            query_ast_resolved_for_index,
+            &index_metadata.index_config.search_settings,
+            &*doc_mapper,
+        )?;
 
         // Validate uniqueness of resolved query AST.
         if let Some(query_ast_resolved) = &query_ast_resolved_opt {
@@ -244,13 +296,26 @@ fn validate_request_and_build_metadata(
 
         // Validate request against the current index schema.
         let schema = doc_mapper.schema();
-        validate_request(&schema, &doc_mapper.timestamp_field_name(), search_request)?;
+        validate_request(
+            &schema,
+            &doc_mapper.timestamp_field_name(),
+            index_metadata
+                .index_config
+                .search_settings
+                .max_result_window(),
+            search_request,
+        )?;
 
         validate_sort_field_types(
             &schema,
             &search_request.sort_fields,
             &mut sort_fields_is_datetime,
         )?;
+        warn_on_sort_mode_for_single_valued_fields(
+            &*doc_mapper,
+            &search_request.sort_fields,
+            &mut warnings,
+        );
 
         // Validates the query by effectively building it against the current schema.
         doc_mapper.query(doc_mapper.schema(), &query_ast_resolved_for_index, true)?;
@@ -278,9 +343,56 @@ fn validate_request_and_build_metadata(
         query_ast_resolved,
         indexes_meta_for_leaf_search,
         sort_fields_is_datetime,
+        warnings,
     })
 }
 
+/// Restricts the query to the documents matching the search filter of the index, if any. The
+/// filter is added as a non-scoring clause at the root of the query, so it cannot be bypassed by
+/// the query sent by the client.
+pub(crate) fn apply_search_filter(
+    query_ast: QueryAst,
+    search_settings: &SearchSettings,
+    doc_mapper: &dyn DocMapper,
+) -> crate::Result<QueryAst> {
+    let Some(search_filter) = &search_settings.search_filter else {
+        return Ok(query_ast);
+    };
+    let search_filter_ast = search_filter.resolve(doc_mapper).map_err(|err| {
+        SearchError::Internal(format!("failed to resolve search filter. cause: {err}"))
+    })?;
+    let query_ast = BoolQuery {
+        must: vec![query_ast],
+        filter: vec![search_filter_ast],
+        ..Default::default()
+    }
+    .into();
+    Ok(query_ast)
+}
+
+/// A sort mode has no effect on single-valued fields: instead of rejecting the request, we let
+/// the user know it was ignored.
+fn warn_on_sort_mode_for_single_valued_fields(
+    doc_mapper: &dyn DocMapper,
+    sort_fields: &[SortField],
+    warnings: &mut Vec<String>,
+) {
+    for sort_field in sort_fields {
+        if sort_field.sort_mode.is_none()
+            || !doc_mapper.is_single_valued_field(&sort_field.field_name)
+        {
+            continue;
+        }
+        let warning = format!(
+            "sort mode ignored for single-valued field `{}`",
+            sort_field.field_name
+        );
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+}
+
 /// Validate sort field types.
 fn validate_sort_field_types(
     schema: &Schema,
@@ -370,6 +482,22 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         // request is simplified after initial query, and we cache the hit count, so we don't need
         // to recompute it afterward.
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
+        max_query_ast_depth: req.max_query_ast_depth,
+        max_query_ast_clause_count: req.max_query_ast_clause_count,
+        // The returned fields are the same for all the pages of the scroll.
+        fields: req.fields.clone(),
+        debug_fetch_docs_source: false,
+        allow_no_indices: req.allow_no_indices,
+        es_timestamp_alias: req.es_timestamp_alias,
+        default_search_fields: req.default_search_fields.clone(),
+        // The scroll context records the splits to search, so a point in time is irrelevant.
+        pit_keep_alive_secs: None,
+        pit_id: None,
+        // The scroll responses report the hit count of the initial query with the same relation.
+        track_total_hits: req.track_total_hits,
+        // The aggregations are dropped.
+        allow_disk_use: false,
+        aggregation_sample_rate_ppm: None,
     })
 }
 
@@ -498,6 +626,7 @@ fn check_is_fast_field(
 fn validate_request(
     schema: &Schema,
     timestamp_field_name: &Option<&str>,
+    max_result_window: u64,
     search_request: &SearchRequest,
 ) -> crate::Result<()> {
     if timestamp_field_name.is_none()
@@ -511,10 +640,13 @@ fn validate_request(
     }
 
     validate_requested_snippet_fields(schema, &search_request.snippet_fields)?;
+    validate_aggregation_sample_rate(search_request)?;
 
     if let Some(agg) = search_request.aggregation_request.as_ref() {
-        let aggs: QuickwitAggregations = serde_json::from_str(agg).map_err(|_err| {
-            let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
+        extract_histogram_missing(agg)?;
+        let agg = strip_histogram_missing(agg);
+        let aggs: QuickwitAggregations = serde_json::from_str(&agg).map_err(|_err| {
+            let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(&agg)
                 .unwrap_err();
             SearchError::InvalidAggregationRequest(err.to_string())
         })?;
@@ -527,20 +659,27 @@ fn validate_request(
         }
     };
 
-    if search_request.start_offset > 10_000 {
-        return Err(SearchError::InvalidArgument(format!(
-            "max value for start_offset is 10_000, but got {}",
-            search_request.start_offset
-        )));
-    }
+    validate_result_window(search_request, max_result_window)?;
 
-    if search_request.max_hits > 10_000 {
-        return Err(SearchError::InvalidArgument(format!(
-            "max value for max_hits is 10_000, but got {}",
-            search_request.max_hits
-        )));
-    }
+    Ok(())
+}
 
+/// Bounds the number of hits each leaf has to collect. The leaves collect the hits skipped by
+/// `start_offset` past the `search_after` cursor too, so requests paginated with `search_after`
+/// are bounded by their offset and page size as well.
+fn validate_result_window(
+    search_request: &SearchRequest,
+    max_result_window: u64,
+) -> crate::Result<()> {
+    let result_window = search_request
+        .start_offset
+        .saturating_add(search_request.max_hits);
+    if result_window > max_result_window {
+        return Err(SearchError::ResultWindowTooLarge {
+            result_window,
+            max_result_window,
+        });
+    }
     Ok(())
 }
 
@@ -565,6 +704,7 @@ async fn search_partial_hits_phase_with_scroll(
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     mut search_request: SearchRequest,
     split_metadatas: &[SplitMetadata],
+    timestamp_field_opt: Option<&str>,
     cluster_client: &ClusterClient,
 ) -> crate::Result<(LeafSearchResponse, Option<ScrollKeyAndStartOffset>)> {
     let scroll_ttl_opt = get_scroll_ttl_duration(&search_request)?;
@@ -583,6 +723,7 @@ async fn search_partial_hits_phase_with_scroll(
             indexes_metas_for_leaf_search,
             &search_request,
             split_metadatas,
+            timestamp_field_opt,
             cluster_client,
         )
         .await?;
@@ -601,6 +742,7 @@ async fn search_partial_hits_phase_with_scroll(
             split_metadatas: split_metadatas.to_vec(),
             search_request: scroll_context_search_request,
             total_num_hits: leaf_search_resp.num_hits,
+            count_hits: search_request.count_hits(),
             max_hits_per_page: max_hits,
             cached_partial_hits_start_offset: search_request.start_offset,
             cached_partial_hits,
@@ -628,6 +770,7 @@ async fn search_partial_hits_phase_with_scroll(
             indexes_metas_for_leaf_search,
             &search_request,
             split_metadatas,
+            timestamp_field_opt,
             cluster_client,
         )
         .await?;
@@ -676,46 +819,203 @@ pub fn is_metadata_count_request_with_ast(query_ast: &QueryAst, request: &Search
 pub fn get_count_from_metadata(split_metadatas: &[SplitMetadata]) -> Vec<LeafSearchResponse> {
     split_metadatas
         .iter()
-        .map(|metadata| LeafSearchResponse {
-            num_hits: metadata.num_docs as u64,
-            partial_hits: Vec::new(),
-            failed_splits: Vec::new(),
-            num_attempted_splits: 1,
-            num_successful_splits: 1,
-            intermediate_aggregation_result: None,
-        })
+        .map(|metadata| leaf_search_response_from_count(metadata.num_docs as u64))
         .collect()
 }
 
+fn leaf_search_response_from_count(num_hits: u64) -> LeafSearchResponse {
+    LeafSearchResponse {
+        num_hits,
+        partial_hits: Vec::new(),
+        failed_splits: Vec::new(),
+        num_attempted_splits: 1,
+        num_successful_splits: 1,
+        intermediate_aggregation_result: None,
+        histogram_missing_doc_counts: Vec::new(),
+        cpu_time_micros: 0,
+    }
+}
+
+/// Check if the request is a count request whose query reduces to a match-all query restricted
+/// to a time range, and returns the `(start, end)` bounds of that time range.
+///
+/// The time range combines the `start_timestamp` and `end_timestamp` of the request with the
+/// range queries on the timestamp field found in the query AST.
+fn extract_count_request_time_range(
+    search_request: &SearchRequest,
+    timestamp_field: &str,
+) -> Option<(Bound<DateTime>, Bound<DateTime>)> {
+    if search_request.max_hits != 0
+        || search_request.aggregation_request.is_some()
+        || !search_request.snippet_fields.is_empty()
+    {
+        return None;
+    }
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).ok()?;
+    let mut remove_timestamp_range = RemoveTimestampRange::new(search_request, timestamp_field);
+    let query_ast_without_time_range = remove_timestamp_range
+        .transform(query_ast)
+        .expect("can't fail unwrapping Infallible")
+        .unwrap_or(QueryAst::MatchAll);
+    if !is_match_all(&query_ast_without_time_range) {
+        return None;
+    }
+    Some((
+        remove_timestamp_range.start_timestamp,
+        remove_timestamp_range.end_timestamp,
+    ))
+}
+
+/// Returns true if the query is a match-all query, possibly wrapped in boolean queries made only
+/// of `must` and `filter` match-all clauses.
+fn is_match_all(query_ast: &QueryAst) -> bool {
+    match query_ast {
+        QueryAst::MatchAll => true,
+        QueryAst::Bool(bool_query) => {
+            bool_query.should.is_empty()
+                && bool_query.must_not.is_empty()
+                && (!bool_query.must.is_empty() || !bool_query.filter.is_empty())
+                && bool_query
+                    .must
+                    .iter()
+                    .chain(&bool_query.filter)
+                    .all(is_match_all)
+        }
+        _ => false,
+    }
+}
+
+/// Counts the documents of a split within the time range delimited by `start_timestamp` and
+/// `end_timestamp` from the split metadata alone.
+///
+/// Returns `None` if the split time range partially overlaps with the time range, in which case
+/// the split must be searched to get an exact count.
+fn count_split_docs_in_time_range_from_metadata(
+    split_metadata: &SplitMetadata,
+    start_timestamp: Bound<DateTime>,
+    end_timestamp: Bound<DateTime>,
+) -> Option<u64> {
+    let split_time_range = split_metadata.time_range.as_ref()?;
+    // The split time range is made of timestamps truncated toward zero to the second, so the
+    // timestamps of the documents of the split lie within `[split_start..split_end)`.
+    let split_start_secs = if *split_time_range.start() > 0 {
+        *split_time_range.start()
+    } else {
+        *split_time_range.start() - 1
+    };
+    let split_start = DateTime::from_timestamp_secs(split_start_secs);
+    let split_end = DateTime::from_timestamp_secs(*split_time_range.end() + 1);
+
+    let starts_before_split = match start_timestamp {
+        Bound::Included(start) => start <= split_start,
+        Bound::Excluded(start) => start < split_start,
+        Bound::Unbounded => true,
+    };
+    let ends_after_split = match end_timestamp {
+        Bound::Included(end) | Bound::Excluded(end) => end >= split_end,
+        Bound::Unbounded => true,
+    };
+    if starts_before_split && ends_after_split {
+        return Some(split_metadata.num_docs as u64);
+    }
+    let starts_after_split = match start_timestamp {
+        Bound::Included(start) | Bound::Excluded(start) => start >= split_end,
+        Bound::Unbounded => false,
+    };
+    let ends_before_split = match end_timestamp {
+        Bound::Included(end) => end < split_start,
+        Bound::Excluded(end) => end <= split_start,
+        Bound::Unbounded => false,
+    };
+    if starts_after_split || ends_before_split {
+        return Some(0);
+    }
+    None
+}
+
+/// Sends the leaf search requests for the given jobs and returns the leaf search responses.
+async fn leaf_search_jobs(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+    jobs: Vec<SearchJob>,
+    cluster_client: &ClusterClient,
+) -> crate::Result<Vec<LeafSearchResponse>> {
+    let assigned_leaf_search_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+    let mut leaf_request_tasks = Vec::new();
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        let leaf_request =
+            jobs_to_leaf_request(search_request, indexes_metas_for_leaf_search, client_jobs)?;
+        leaf_request_tasks.push(cluster_client.leaf_search(leaf_request, client.clone()));
+    }
+    let leaf_search_responses = try_join_all(leaf_request_tasks).await?;
+    Ok(leaf_search_responses)
+}
+
 /// If this method fails for some splits, a partial search response is returned, with the list of
 /// faulty splits in the failed_splits field.
+///
+/// Count requests restricted to a time range only search the splits partially overlapping with the
+/// time range: the documents of the other splits are counted from their metadata.
 #[instrument(level = "debug", skip_all)]
 pub(crate) async fn search_partial_hits_phase(
     searcher_context: &SearcherContext,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     search_request: &SearchRequest,
     split_metadatas: &[SplitMetadata],
+    timestamp_field_opt: Option<&str>,
     cluster_client: &ClusterClient,
 ) -> crate::Result<LeafSearchResponse> {
+    let mut num_splits_counted_from_metadata = 0;
+
     let leaf_search_responses: Vec<LeafSearchResponse> =
         if is_metadata_count_request(search_request) {
+            num_splits_counted_from_metadata = split_metadatas.len();
             get_count_from_metadata(split_metadatas)
+        } else if let Some((start_timestamp, end_timestamp)) =
+            timestamp_field_opt.and_then(|timestamp_field| {
+                extract_count_request_time_range(search_request, timestamp_field)
+            })
+        {
+            let mut leaf_search_responses = Vec::new();
+            let mut jobs = Vec::new();
+
+            for split_metadata in split_metadatas {
+                if let Some(num_docs) = count_split_docs_in_time_range_from_metadata(
+                    split_metadata,
+                    start_timestamp,
+                    end_timestamp,
+                ) {
+                    leaf_search_responses.push(leaf_search_response_from_count(num_docs));
+                } else {
+                    jobs.push(SearchJob::from(split_metadata));
+                }
+            }
+            num_splits_counted_from_metadata = leaf_search_responses.len();
+
+            if !jobs.is_empty() {
+                leaf_search_responses.extend(
+                    leaf_search_jobs(
+                        indexes_metas_for_leaf_search,
+                        search_request,
+                        jobs,
+                        cluster_client,
+                    )
+                    .await?,
+                );
+            }
+            leaf_search_responses
         } else {
             let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
-            let assigned_leaf_search_jobs = cluster_client
-                .search_job_placer
-                .assign_jobs(jobs, &HashSet::default())
-                .await?;
-            let mut leaf_request_tasks = Vec::new();
-            for (client, client_jobs) in assigned_leaf_search_jobs {
-                let leaf_request = jobs_to_leaf_request(
-                    search_request,
-                    indexes_metas_for_leaf_search,
-                    client_jobs,
-                )?;
-                leaf_request_tasks.push(cluster_client.leaf_search(leaf_request, client.clone()));
-            }
-            try_join_all(leaf_request_tasks).await?
+            leaf_search_jobs(
+                indexes_metas_for_leaf_search,
+                search_request,
+                jobs,
+                cluster_client,
+            )
+            .await?
         };
 
     // Creates a collector which merges responses into one
@@ -741,6 +1041,7 @@ pub(crate) async fn search_partial_hits_phase(
         num_hits = leaf_search_response.num_hits,
         failed_splits = ?leaf_search_response.failed_splits,
         num_attempted_splits = leaf_search_response.num_attempted_splits,
+        num_splits_counted_from_metadata,
         has_intermediate_aggregation_result = leaf_search_response.intermediate_aggregation_result.is_some(),
         "Merged leaf search response."
     );
@@ -793,6 +1094,7 @@ pub(crate) async fn fetch_docs_phase(
     for (client, client_jobs) in assigned_fetch_docs_jobs {
         let fetch_jobs_requests = jobs_to_fetch_docs_requests(
             snippet_request.clone(),
+            &search_request.fields,
             indexes_metas_for_leaf_search,
             client_jobs,
         )?;
@@ -919,7 +1221,9 @@ async fn root_search_aux(
     searcher_context: &SearcherContext,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     search_request: SearchRequest,
+    pipeline_aggregations: &[PipelineAggregation],
     split_metadatas: Vec<SplitMetadata>,
+    timestamp_field_opt: Option<&str>,
     cluster_client: &ClusterClient,
 ) -> crate::Result<SearchResponse> {
     debug!(split_metadatas = ?PrettySample::new(&split_metadatas, 5));
@@ -931,6 +1235,7 @@ async fn root_search_aux(
         indexes_metas_for_leaf_search,
         search_request.clone(),
         &split_metadatas[..],
+        timestamp_field_opt,
         cluster_client,
     )
     .await?;
@@ -947,16 +1252,39 @@ async fn root_search_aux(
     let mut aggregation_result_json_opt = finalize_aggregation_if_any(
         &search_request,
         first_phase_result.intermediate_aggregation_result,
+        &first_phase_result.histogram_missing_doc_counts,
+        pipeline_aggregations,
         searcher_context,
     )?;
     // In case there is no index, we don't want the response to contain any aggregation structure
     if indexes_metas_for_leaf_search.is_empty() {
         aggregation_result_json_opt = None;
     }
+    let fetch_docs_source_opt = if search_request.debug_fetch_docs_source {
+        let fetch_docs_source =
+            get_fetch_docs_source(indexes_metas_for_leaf_search, &search_request)?;
+        Some(fetch_docs_source as i32)
+    } else {
+        None
+    };
+
+    let (num_hits, num_hits_relation) = num_hits_with_relation(
+        first_phase_result.num_hits,
+        search_request.count_hits(),
+        search_request.track_total_hits,
+    );
+
+    let aggregation_sample_rate_opt = aggregation_result_json_opt
+        .as_ref()
+        .and_then(|_| aggregation_sample_rate(&search_request));
+    let warnings: Vec<String> = aggregation_sample_rate_opt
+        .map(sampled_aggregations_warning)
+        .into_iter()
+        .collect();
 
     Ok(SearchResponse {
         aggregation: aggregation_result_json_opt,
-        num_hits: first_phase_result.num_hits,
+        num_hits,
         hits,
         elapsed_time_micros: 0u64,
         errors: Vec::new(),
@@ -965,12 +1293,70 @@ async fn root_search_aux(
             .map(ToString::to_string),
         failed_splits: first_phase_result.failed_splits,
         num_successful_splits: first_phase_result.num_successful_splits,
+        fetch_docs_source: fetch_docs_source_opt,
+        partial: false,
+        warnings,
+        pit_id: None,
+        cpu_time_micros: first_phase_result.cpu_time_micros,
+        num_hits_relation: num_hits_relation as i32,
+        aggregation_sample_rate: aggregation_sample_rate_opt,
     })
 }
 
+/// Caps the number of hits counted by the leaves to the `track_total_hits` threshold of the
+/// request.
+///
+/// The leaves only stop counting hits once they counted more than the threshold, so the count is
+/// exact as long as it does not exceed it. When the request does not count all the hits, the
+/// leaves may skip entire splits and the count is only a lower bound.
+pub(crate) fn num_hits_with_relation(
+    num_hits: u64,
+    count_hits: CountHits,
+    track_total_hits: Option<u64>,
+) -> (u64, NumHitsRelation) {
+    match track_total_hits {
+        Some(track_total_hits) if num_hits > track_total_hits => {
+            (track_total_hits, NumHitsRelation::GreaterThanOrEqualTo)
+        }
+        _ if count_hits == CountHits::Underestimate => {
+            (num_hits, NumHitsRelation::GreaterThanOrEqualTo)
+        }
+        _ => (num_hits, NumHitsRelation::EqualTo),
+    }
+}
+
+/// Returns where the leaves fetch the hits of the request from. The doc store is reported as
+/// soon as one of the targeted indexes cannot serve the requested fields from its fast fields.
+fn get_fetch_docs_source(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    search_request: &SearchRequest,
+) -> crate::Result<FetchDocsSource> {
+    if indexes_metas_for_leaf_search.is_empty() {
+        return Ok(FetchDocsSource::DocStore);
+    }
+    let has_snippets = !search_request.snippet_fields.is_empty();
+    for index_metas in indexes_metas_for_leaf_search.values() {
+        let doc_mapper = deserialize_doc_mapper(&index_metas.doc_mapper_str)?;
+        let fetch_docs_source =
+            select_fetch_docs_source(&doc_mapper.schema(), &search_request.fields, has_snippets);
+        if fetch_docs_source == FetchDocsSource::DocStore {
+            return Ok(FetchDocsSource::DocStore);
+        }
+    }
+    Ok(FetchDocsSource::FastFields)
+}
+
+/// Sampling parameters of the aggregations: the sample rate and the aggregation request telling
+/// which results to scale back up.
+type AggregationSampling<'a> = (f64, &'a serde_json::Value);
+
 fn finalize_aggregation(
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
     aggregations: QuickwitAggregations,
+    histogram_missings: &[HistogramMissing],
+    histogram_missing_doc_counts: &[HistogramMissingDocCount],
+    pipeline_aggregations: &[PipelineAggregation],
+    aggregation_sampling_opt: Option<AggregationSampling>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let merge_aggregation_result = match aggregations {
@@ -996,9 +1382,49 @@ fn finalize_aggregation(
                     // Default, to return correct structure
                     Default::default()
                 };
-            let final_aggregation_results: AggregationResults = intermediate_aggregation_results
-                .into_final_result(aggregations, searcher_context.get_aggregation_limits())?;
-            serde_json::to_string(&final_aggregation_results)?
+            let mut final_aggregation_results: AggregationResults =
+                intermediate_aggregation_results
+                    .into_final_result(aggregations, searcher_context.get_aggregation_limits())?;
+            // The documents missing the histogram field are counted exactly by the leaves, so
+            // their counts are brought down to the sample before all the counts get scaled up.
+            let sampled_histogram_missing_doc_counts: Vec<HistogramMissingDocCount>;
+            let histogram_missing_doc_counts =
+                if let Some((sample_rate, _)) = aggregation_sampling_opt {
+                    sampled_histogram_missing_doc_counts = histogram_missing_doc_counts
+                        .iter()
+                        .map(|histogram_missing_doc_count| HistogramMissingDocCount {
+                            aggregation_name: histogram_missing_doc_count.aggregation_name.clone(),
+                            num_docs: (histogram_missing_doc_count.num_docs as f64 * sample_rate)
+                                .round() as u64,
+                        })
+                        .collect();
+                    &sampled_histogram_missing_doc_counts[..]
+                } else {
+                    histogram_missing_doc_counts
+                };
+            apply_histogram_missing_doc_counts(
+                &mut final_aggregation_results,
+                histogram_missings,
+                histogram_missing_doc_counts,
+            );
+            if pipeline_aggregations.is_empty() && aggregation_sampling_opt.is_none() {
+                serde_json::to_string(&final_aggregation_results)?
+            } else {
+                let mut final_aggregation_results_json =
+                    serde_json::to_value(&final_aggregation_results)?;
+                if let Some((sample_rate, aggregation_request)) = aggregation_sampling_opt {
+                    scale_sampled_aggregation_results(
+                        &mut final_aggregation_results_json,
+                        aggregation_request,
+                        sample_rate,
+                    );
+                }
+                apply_pipeline_aggregations(
+                    &mut final_aggregation_results_json,
+                    pipeline_aggregations,
+                );
+                final_aggregation_results_json.to_string()
+            }
         }
     };
     Ok(Some(merge_aggregation_result))
@@ -1007,24 +1433,63 @@ fn finalize_aggregation(
 fn finalize_aggregation_if_any(
     search_request: &SearchRequest,
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
+    histogram_missing_doc_counts: &[HistogramMissingDocCount],
+    pipeline_aggregations: &[PipelineAggregation],
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let Some(aggregations_json) = search_request.aggregation_request.as_ref() else {
         return Ok(None);
     };
-    let aggregations: QuickwitAggregations = serde_json::from_str(aggregations_json)?;
+    let histogram_missings = extract_histogram_missing(aggregations_json)?;
+    let stripped_aggregations_json = strip_histogram_missing(aggregations_json);
+    let aggregations: QuickwitAggregations = serde_json::from_str(&stripped_aggregations_json)?;
+    let aggregation_sample_rate_opt = aggregation_sample_rate(search_request);
+    let aggregation_request_opt: Option<serde_json::Value> =
+        if aggregation_sample_rate_opt.is_some() {
+            Some(serde_json::from_str(&stripped_aggregations_json)?)
+        } else {
+            None
+        };
+    let aggregation_sampling_opt =
+        aggregation_sample_rate_opt.zip(aggregation_request_opt.as_ref());
     let aggregation_result_json = finalize_aggregation(
         intermediate_aggregation_result_bytes_opt,
         aggregations,
+        &histogram_missings,
+        histogram_missing_doc_counts,
+        pipeline_aggregations,
+        aggregation_sampling_opt,
         searcher_context,
     )?;
     Ok(aggregation_result_json)
 }
 
+/// Checks that index ID patterns include at least one index.
+///
+/// Patterns prefixed with `-` are exclusions: they are applied after the other patterns and
+/// remove the indexes they match from the resolved indexes. Index IDs must start with a letter,
+/// so a leading `-` always marks an exclusion and never needs to be escaped.
+pub fn validate_index_id_patterns(index_id_patterns: &[String]) -> crate::Result<()> {
+    if index_id_patterns.is_empty() {
+        return Ok(());
+    }
+    let has_inclusion = index_id_patterns
+        .iter()
+        .any(|index_id_pattern| !index_id_pattern.starts_with('-'));
+    if !has_inclusion {
+        return Err(SearchError::InvalidArgument(format!(
+            "index ID patterns `{}` only contain exclusions: at least one pattern not prefixed \
+             with `-` is required",
+            index_id_patterns.join(",")
+        )));
+    }
+    Ok(())
+}
+
 /// Checks that all of the index researched as found.
 ///
 /// An index pattern (= containing a wildcard) not matching is not an error.
-/// A specific index id however must be found.
+/// A specific index id however must be found, unless it is excluded by an exclusion pattern.
 ///
 /// We put this check here and not in the metastore to make sure the logic is independent
 /// of the metastore implementation, and some different use cases could require different
@@ -1033,10 +1498,27 @@ pub fn check_all_index_metadata_found(
     index_metadatas: &[IndexMetadata],
     index_id_patterns: &[String],
 ) -> crate::Result<()> {
+    let exclusion_patterns: Vec<String> = index_id_patterns
+        .iter()
+        .filter_map(|index_ptn| index_ptn.strip_prefix('-'))
+        .map(|exclusion_ptn| exclusion_ptn.to_string())
+        .collect();
+    let exclusion_matcher_opt = if exclusion_patterns.is_empty() {
+        None
+    } else {
+        let exclusion_matcher = IndexIdMatcher::try_from_index_id_patterns(&exclusion_patterns)
+            .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+        Some(exclusion_matcher)
+    };
     let mut index_ids: HashSet<&str> = index_id_patterns
         .iter()
         .map(|index_ptn| index_ptn.as_str())
         .filter(|index_ptn| !index_ptn.contains('*') && !index_ptn.starts_with('-'))
+        .filter(|index_id| {
+            !exclusion_matcher_opt
+                .as_ref()
+                .is_some_and(|exclusion_matcher| exclusion_matcher.is_match(index_id))
+        })
         .collect();
 
     if index_ids.is_empty() {
@@ -1061,25 +1543,39 @@ pub fn check_all_index_metadata_found(
     Ok(())
 }
 
-async fn refine_and_list_matches(
-    metastore: &mut MetastoreServiceClient,
+/// Rejects a search whose index ID patterns match no index at all, unless the request allows it
+/// (the default).
+pub(crate) fn check_allow_no_indices(
+    indexes_metadata: &[IndexMetadata],
+    search_request: &SearchRequest,
+) -> crate::Result<()> {
+    if !indexes_metadata.is_empty() || search_request.allow_no_indices.unwrap_or(true) {
+        return Ok(());
+    }
+    let index_ids = search_request
+        .index_id_patterns
+        .iter()
+        .filter(|index_id_pattern| !index_id_pattern.starts_with('-'))
+        .cloned()
+        .collect();
+    Err(SearchError::IndexesNotFound { index_ids })
+}
+
+/// Refines the search request with the resolved query AST and returns the tag filter to prune the
+/// splits with.
+pub(crate) fn refine_search_request(
     search_request: &mut SearchRequest,
-    indexes_metadata: Vec<IndexMetadata>,
     query_ast_resolved: QueryAst,
-    sort_fields_is_datetime: HashMap<String, bool>,
-    timestamp_field_opt: Option<String>,
-) -> crate::Result<Vec<SplitMetadata>> {
-    let index_uids = indexes_metadata
-        .iter()
-        .map(|index_metadata| index_metadata.index_uid.clone())
-        .collect_vec();
+    sort_fields_is_datetime: &HashMap<String, bool>,
+    timestamp_field_opt: Option<&str>,
+) -> crate::Result<Option<TagFilterAst>> {
     search_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
 
     // convert search_after datetime values from input datetime format to nanos.
-    convert_search_after_datetime_values(search_request, &sort_fields_is_datetime)?;
+    convert_search_after_datetime_values(search_request, sort_fields_is_datetime)?;
 
     // update_search_after_datetime_in_nanos(&mut search_request)?;
-    if let Some(timestamp_field) = &timestamp_field_opt {
+    if let Some(timestamp_field) = timestamp_field_opt {
         refine_start_end_timestamp_from_ast(
             &query_ast_resolved,
             timestamp_field,
@@ -1088,18 +1584,158 @@ async fn refine_and_list_matches(
         );
     }
     let tag_filter_ast = extract_tags_from_query(query_ast_resolved);
+    Ok(tag_filter_ast)
+}
+
+/// Refines the search request and lists the splits it targets.
+///
+/// When a searcher context is provided, the listing is bounded by a deadline (see
+/// [`list_relevant_splits_with_deadline`]) and the returned flag is set if the splits come from
+/// a stale cached listing.
+pub(crate) async fn refine_and_list_matches(
+    metastore: &mut MetastoreServiceClient,
+    search_request: &mut SearchRequest,
+    indexes_metadata: Vec<IndexMetadata>,
+    query_ast_resolved: QueryAst,
+    sort_fields_is_datetime: HashMap<String, bool>,
+    timestamp_field_opt: Option<String>,
+    searcher_context_opt: Option<&SearcherContext>,
+) -> crate::Result<(Vec<SplitMetadata>, bool)> {
+    let index_uids = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_uid.clone())
+        .collect_vec();
+    let term_presence_query_ast = query_ast_resolved.clone();
+    let tag_filter_ast = refine_search_request(
+        search_request,
+        query_ast_resolved,
+        &sort_fields_is_datetime,
+        timestamp_field_opt.as_deref(),
+    )?;
 
     // TODO if search after is set, we sort by timestamp and we don't want to count all results,
     // we can refine more here. Same if we sort by _shard_doc
-    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
-        index_uids,
+    let split_pruning_slack_secs = searcher_context_opt.map_or_else(
+        || SearcherConfig::default().split_pruning_slack_secs,
+        |searcher_context| searcher_context.searcher_config().split_pruning_slack_secs,
+    );
+    let (start_timestamp, end_timestamp) = widen_time_range_by_slack(
         search_request.start_timestamp,
         search_request.end_timestamp,
-        tag_filter_ast,
-        metastore,
+        split_pruning_slack_secs,
+    );
+    let (mut split_metadatas, is_partial_listing) =
+        if let Some(searcher_context) = searcher_context_opt {
+            list_relevant_splits_with_deadline(
+                searcher_context,
+                index_uids,
+                start_timestamp,
+                end_timestamp,
+                tag_filter_ast,
+                metastore,
+            )
+            .await?
+        } else {
+            let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
+                index_uids,
+                start_timestamp,
+                end_timestamp,
+                tag_filter_ast,
+                metastore,
+            )
+            .await?;
+            (split_metadatas, false)
+        };
+    if split_pruning_slack_secs > 0 {
+        record_split_pruning_slack_saves(
+            &split_metadatas,
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+            split_pruning_slack_secs,
+            OffsetDateTime::now_utc().unix_timestamp(),
+        );
+    }
+    prune_splits_by_term_presence(&mut split_metadatas, &term_presence_query_ast);
+    Ok((split_metadatas, is_partial_listing))
+}
+
+/// Lists the splits relevant to a root search within half of the request timeout.
+///
+/// Successful listings are cached for a short while. If the metastore does not respond before
+/// the deadline, a fresh enough cached listing covering the request is used instead and the
+/// returned flag is set, signaling that the search results may be partial. Without such a
+/// listing, the search fails with a timeout error.
+async fn list_relevant_splits_with_deadline(
+    searcher_context: &SearcherContext,
+    index_uids: Vec<IndexUid>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<(Vec<SplitMetadata>, bool)> {
+    let deadline = searcher_context.searcher_config().request_timeout() / 2;
+    let start_instant = tokio::time::Instant::now();
+    let list_splits_result = tokio::time::timeout(
+        deadline,
+        list_relevant_splits(
+            index_uids.clone(),
+            start_timestamp,
+            end_timestamp,
+            tags_filter_opt.clone(),
+            metastore,
+        ),
     )
-    .await?;
-    Ok(split_metadatas)
+    .await;
+    let status = match &list_splits_result {
+        Ok(Ok(_)) => "success",
+        Ok(Err(_)) => "error",
+        Err(_) => "timeout",
+    };
+    SEARCH_METRICS
+        .root_search_list_splits_duration_seconds
+        .with_label_values([status])
+        .observe(start_instant.elapsed().as_secs_f64());
+
+    let list_splits_cache = &searcher_context.list_splits_cache;
+    match list_splits_result {
+        Ok(Ok(split_metadatas)) => {
+            let split_metadatas: Arc<[SplitMetadata]> = split_metadatas.into();
+            list_splits_cache.put(
+                &index_uids,
+                start_timestamp,
+                end_timestamp,
+                tags_filter_opt,
+                split_metadatas.clone(),
+            );
+            Ok((split_metadatas.to_vec(), false))
+        }
+        Ok(Err(error)) => Err(error),
+        Err(_elapsed) => {
+            if let Some(split_metadatas) = list_splits_cache.get(
+                &index_uids,
+                start_timestamp,
+                end_timestamp,
+                &tags_filter_opt,
+            ) {
+                warn!(
+                    deadline=?deadline,
+                    "listing splits from the metastore timed out, falling back to a cached listing"
+                );
+                SEARCH_METRICS
+                    .root_search_list_splits_timeouts_total
+                    .with_label_values(["stale_cache"])
+                    .inc();
+                return Ok((split_metadatas, true));
+            }
+            SEARCH_METRICS
+                .root_search_list_splits_timeouts_total
+                .with_label_values(["none"])
+                .inc();
+            Err(SearchError::Timeout(format!(
+                "listing splits from the metastore timed out after {deadline:?}"
+            )))
+        }
+    }
 }
 
 /// Performs a distributed search.
@@ -1116,6 +1752,26 @@ pub async fn root_search(
 ) -> crate::Result<SearchResponse> {
     info!(searcher_context = ?searcher_context, search_request = ?search_request);
     let start_instant = tokio::time::Instant::now();
+    let pit_keep_alive_opt = get_pit_keep_alive_duration(search_request.pit_keep_alive_secs)?;
+    if (pit_keep_alive_opt.is_some() || search_request.pit_id.is_some())
+        && search_request.scroll_ttl_secs.is_some()
+    {
+        return Err(SearchError::InvalidArgument(
+            "a point in time cannot be used in a scroll context".to_string(),
+        ));
+    }
+    let point_in_time_opt: Option<PointInTime> =
+        if let Some(pit_id) = search_request.pit_id.as_deref() {
+            let point_in_time = PointInTime::load(pit_id, cluster_client).await?;
+            point_in_time.check_index_id_patterns(&mut search_request.index_id_patterns)?;
+            Some(point_in_time)
+        } else {
+            None
+        };
+    validate_index_id_patterns(&search_request.index_id_patterns)?;
+    // Pipeline aggregations are computed by the root over the final buckets, the leaves never see
+    // them.
+    let pipeline_aggregations = take_pipeline_aggregations(&mut search_request)?;
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
@@ -1126,51 +1782,169 @@ pub async fn root_search(
         .await?;
 
     check_all_index_metadata_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
+    check_allow_no_indices(&indexes_metadata, &search_request)?;
+
+    // A new point in time records all the published splits of the indexes, regardless of the
+    // query of the request opening it: the next pages may prune them differently.
+    let point_in_time_opt = if point_in_time_opt.is_none() && pit_keep_alive_opt.is_some() {
+        let index_uids: Vec<IndexUid> = indexes_metadata
+            .iter()
+            .map(|index_metadata| index_metadata.index_uid.clone())
+            .collect();
+        let split_metadatas = list_all_splits(index_uids, &mut metastore).await?;
+        SEARCH_METRICS.points_in_time_opened_total.inc();
+        Some(PointInTime::new(
+            search_request.index_id_patterns.clone(),
+            split_metadatas,
+        ))
+    } else {
+        point_in_time_opt
+    };
+
+    // The `_index` pseudo-field is evaluated here: the leaves never see it.
+    let indexes_metadata =
+        filter_indexes_on_index_pseudo_field(indexes_metadata, &mut search_request)?;
+    let index_sort_order_opt = take_index_pseudo_field_sort(&mut search_request)?;
 
     if indexes_metadata.is_empty() {
         // We go through root_search_aux instead of directly
         // returning an empty response to make sure we generate
         // a (pretty useless) scroll id if requested.
+        let pit_id_opt =
+            keep_point_in_time_alive(point_in_time_opt, pit_keep_alive_opt, cluster_client).await;
         let mut search_response = root_search_aux(
             searcher_context,
             &HashMap::default(),
             search_request,
+            &pipeline_aggregations,
             Vec::new(),
+            None,
             cluster_client,
         )
         .await?;
         search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+        search_response.pit_id = pit_id_opt;
         return Ok(search_response);
     }
 
+    let index_ids: Vec<&str> = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_id())
+        .collect();
+    let search_quota_guard = searcher_context.search_quotas.acquire(&index_ids)?;
+
+    let es_timestamp_alias_warning_opt =
+        rewrite_es_timestamp_alias(&indexes_metadata, &mut search_request)?;
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
-    let split_metadatas = refine_and_list_matches(
-        &mut metastore,
-        &mut search_request,
-        indexes_metadata,
-        request_metadata.query_ast_resolved,
-        request_metadata.sort_fields_is_datetime,
-        request_metadata.timestamp_field_opt,
-    )
-    .await?;
+    let (split_metadatas, is_partial_listing) = if let Some(point_in_time) = &point_in_time_opt {
+        let term_presence_query_ast = request_metadata.query_ast_resolved.clone();
+        let tag_filter_ast = refine_search_request(
+            &mut search_request,
+            request_metadata.query_ast_resolved,
+            &request_metadata.sort_fields_is_datetime,
+            request_metadata.timestamp_field_opt.as_deref(),
+        )?;
+        let (start_timestamp, end_timestamp) = widen_time_range_by_slack(
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+            searcher_context.searcher_config().split_pruning_slack_secs,
+        );
+        // The recorded splits are pruned like the metastore prunes the published splits. The
+        // splits of the indexes deleted since the point in time was recorded are skipped.
+        let mut split_metadatas: Vec<SplitMetadata> = point_in_time
+            .split_metadatas
+            .iter()
+            .filter(|split_metadata| {
+                request_metadata
+                    .indexes_meta_for_leaf_search
+                    .contains_key(&split_metadata.index_uid)
+                    && split_matches_time_range_and_tags(
+                        split_metadata,
+                        start_timestamp,
+                        end_timestamp,
+                        tag_filter_ast.as_ref(),
+                    )
+            })
+            .cloned()
+            .collect();
+        prune_splits_by_term_presence(&mut split_metadatas, &term_presence_query_ast);
+        (split_metadatas, false)
+    } else {
+        refine_and_list_matches(
+            &mut metastore,
+            &mut search_request,
+            indexes_metadata,
+            request_metadata.query_ast_resolved,
+            request_metadata.sort_fields_is_datetime,
+            request_metadata.timestamp_field_opt.clone(),
+            Some(searcher_context),
+        )
+        .await?
+    };
+    let pit_id_opt =
+        keep_point_in_time_alive(point_in_time_opt, pit_keep_alive_opt, cluster_client).await;
 
     let num_docs: usize = split_metadatas.iter().map(|split| split.num_docs).sum();
     let num_splits = split_metadatas.len();
+    search_quota_guard.check_num_splits(num_splits)?;
     let current_span = tracing::Span::current();
     current_span.record("num_docs", num_docs);
     current_span.record("num_splits", num_splits);
 
+    // The index of a split reported missing from the storage is only known here.
+    let split_index_uids_opt: Option<HashMap<SplitId, IndexUid>> =
+        if searcher_context.searcher_config().report_missing_splits {
+            let split_index_uids = split_metadatas
+                .iter()
+                .map(|split_metadata| {
+                    (
+                        split_metadata.split_id.clone(),
+                        split_metadata.index_uid.clone(),
+                    )
+                })
+                .collect();
+            Some(split_index_uids)
+        } else {
+            None
+        };
     let mut search_response_result = root_search_aux(
         searcher_context,
         &request_metadata.indexes_meta_for_leaf_search,
         search_request,
+        &pipeline_aggregations,
         split_metadatas,
+        request_metadata.timestamp_field_opt.as_deref(),
         cluster_client,
     )
     .await;
 
     if let Ok(search_response) = &mut search_response_result {
         search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+        search_response.pit_id = pit_id_opt;
+        if is_partial_listing {
+            search_response.partial = true;
+            search_response.errors.push(
+                "listing splits from the metastore timed out: the search ran over a cached \
+                 listing and may be missing recent splits"
+                    .to_string(),
+            );
+        }
+        search_response
+            .warnings
+            .extend(es_timestamp_alias_warning_opt);
+        search_response.warnings.extend(request_metadata.warnings);
+
+        if let Some(index_sort_order) = index_sort_order_opt {
+            sort_hits_on_index_pseudo_field(&mut search_response.hits, index_sort_order);
+        }
+        if let Some(split_index_uids) = &split_index_uids_opt {
+            mark_missing_splits_for_deletion(
+                &search_response.failed_splits,
+                split_index_uids,
+                &mut metastore,
+            )
+            .await;
+        }
     }
     let label_values = if search_response_result.is_ok() {
         ["success"]
@@ -1185,11 +1959,143 @@ pub async fn root_search(
     search_response_result
 }
 
+/// Counts the documents matching a search request in each of the splits it targets.
+///
+/// The splits are listed and pruned like in [`root_search`], and counted like in the partial hits
+/// phase of a root search: from their metadata when possible, otherwise by leaf searches placed
+/// across the cluster. Unlike a search, a split that fails to be counted fails the request.
+#[instrument(skip_all)]
+pub async fn root_count_hits_per_split(
+    searcher_context: &SearcherContext,
+    mut search_request: SearchRequest,
+    mut metastore: MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<HashMap<SplitId, u64>> {
+    validate_index_id_patterns(&search_request.index_id_patterns)?;
+    search_request.max_hits = 0;
+    search_request.start_offset = 0;
+    search_request.count_hits = CountHits::CountAll as i32;
+    let list_indexes_metadatas_request = ListIndexesMetadataRequest {
+        index_id_patterns: search_request.index_id_patterns.clone(),
+    };
+    let indexes_metadata: Vec<IndexMetadata> = metastore
+        .list_indexes_metadata(list_indexes_metadatas_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    check_all_index_metadata_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
+    if indexes_metadata.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
+    let (split_metadatas, is_partial_listing) = refine_and_list_matches(
+        &mut metastore,
+        &mut search_request,
+        indexes_metadata,
+        request_metadata.query_ast_resolved,
+        request_metadata.sort_fields_is_datetime,
+        request_metadata.timestamp_field_opt.clone(),
+        Some(searcher_context),
+    )
+    .await?;
+    // A stale cached listing may miss recent splits: the counts would be silently wrong.
+    if is_partial_listing {
+        return Err(SearchError::Unavailable(
+            "listing splits from the metastore timed out".to_string(),
+        ));
+    }
+
+    let search_request = &search_request;
+    let indexes_meta_for_leaf_search = &request_metadata.indexes_meta_for_leaf_search;
+    let timestamp_field_opt = request_metadata.timestamp_field_opt.as_deref();
+    let num_hits_per_split: HashMap<SplitId, u64> = stream::iter(split_metadatas)
+        .map(|split_metadata| async move {
+            let leaf_search_response = search_partial_hits_phase(
+                searcher_context,
+                indexes_meta_for_leaf_search,
+                search_request,
+                std::slice::from_ref(&split_metadata),
+                timestamp_field_opt,
+                cluster_client,
+            )
+            .await?;
+            if let Some(split_search_error) = leaf_search_response.failed_splits.first() {
+                return Err(SearchError::Internal(format!(
+                    "failed to count documents in split `{}`: {}",
+                    split_search_error.split_id, split_search_error.error
+                )));
+            }
+            Ok((split_metadata.split_id, leaf_search_response.num_hits))
+        })
+        .buffer_unordered(MAX_CONCURRENT_SPLIT_COUNTS)
+        .try_collect()
+        .await?;
+    Ok(num_hits_per_split)
+}
+
+/// Marks for deletion the splits the leaves failed to open because their file is missing from the
+/// storage, for instance because they were garbage collected after having been listed. Failing to
+/// do so does not fail the search: the splits are reported again by the next searches.
+async fn mark_missing_splits_for_deletion(
+    failed_splits: &[SplitSearchError],
+    split_index_uids: &HashMap<SplitId, IndexUid>,
+    metastore: &mut MetastoreServiceClient,
+) {
+    let mut missing_split_ids_per_index: HashMap<&IndexUid, Vec<SplitId>> = HashMap::new();
+
+    for failed_split in failed_splits {
+        if failed_split.error_code() != SplitSearchErrorCode::SplitNotFound {
+            continue;
+        }
+        if let Some(index_uid) = split_index_uids.get(&failed_split.split_id) {
+            missing_split_ids_per_index
+                .entry(index_uid)
+                .or_default()
+                .push(failed_split.split_id.clone());
+        }
+    }
+    for (index_uid, split_ids) in missing_split_ids_per_index {
+        let num_splits = split_ids.len();
+        let mark_splits_for_deletion_request =
+            MarkSplitsForDeletionRequest::new(index_uid.clone(), split_ids);
+
+        if let Err(error) = metastore
+            .mark_splits_for_deletion(mark_splits_for_deletion_request)
+            .await
+        {
+            warn!(%index_uid, %error, "failed to mark splits missing from the storage for deletion");
+        } else {
+            warn!(%index_uid, num_splits, "marked splits missing from the storage for deletion");
+        }
+    }
+}
+
+/// Saves the point in time of a search request if it asks for one to be kept alive, and returns
+/// the ID of the point in time used or recorded by the request, if any.
+///
+/// A request passing a point in time ID renews the point in time, while a request only passing a
+/// keep alive saves the point in time it just recorded.
+async fn keep_point_in_time_alive(
+    point_in_time_opt: Option<PointInTime>,
+    pit_keep_alive_opt: Option<Duration>,
+    cluster_client: &ClusterClient,
+) -> Option<String> {
+    let point_in_time = point_in_time_opt?;
+    if let Some(pit_keep_alive) = pit_keep_alive_opt {
+        point_in_time.save(pit_keep_alive, cluster_client).await;
+    }
+    Some(point_in_time.pit_id())
+}
+
 /// Returns details on how a query would be executed
 pub async fn search_plan(
+    searcher_context: &SearcherContext,
     mut search_request: SearchRequest,
     mut metastore: MetastoreServiceClient,
 ) -> crate::Result<SearchPlanResponse> {
+    validate_index_id_patterns(&search_request.index_id_patterns)?;
+    // The plan of the leaf search does not depend on the pipeline aggregations.
+    take_pipeline_aggregations(&mut search_request)?;
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
@@ -1200,6 +2106,7 @@ pub async fn search_plan(
         .await?;
 
     check_all_index_metadata_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
+    check_allow_no_indices(&indexes_metadata, &search_request)?;
     if indexes_metadata.is_empty() {
         return Ok(SearchPlanResponse {
             result: serde_json::to_string(&SearchPlanResponseRest {
@@ -1207,6 +2114,7 @@ pub async fn search_plan(
                 tantivy_ast: String::new(),
                 searched_splits: Vec::new(),
                 storage_requests: StorageRequestCount::default(),
+                split_pruning: SplitPruningStats::default(),
             })?,
         });
     }
@@ -1217,16 +2125,49 @@ pub async fn search_plan(
     .map_err(|err| SearchError::Internal(format!("failed to build doc mapper. cause: {err}")))?;
 
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
-    let split_metadatas = refine_and_list_matches(
+    let index_uids: Vec<IndexUid> = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_uid.clone())
+        .collect();
+    let (split_metadatas, _) = refine_and_list_matches(
         &mut metastore,
         &mut search_request,
         indexes_metadata,
         request_metadata.query_ast_resolved.clone(),
         request_metadata.sort_fields_is_datetime,
         request_metadata.timestamp_field_opt,
+        Some(searcher_context),
     )
     .await?;
 
+    // The start and end timestamps of the request have been refined from the query by
+    // `refine_and_list_matches`, which widened them by the split pruning slack to list the
+    // splits.
+    let all_split_metadatas =
+        list_relevant_splits(index_uids, None, None, None, &mut metastore).await?;
+    let tags_filter_opt = extract_tags_from_query(request_metadata.query_ast_resolved.clone());
+    let term_presence_filter_opt =
+        extract_term_presence_filter_from_query(request_metadata.query_ast_resolved.clone());
+    let (start_timestamp, end_timestamp) = widen_time_range_by_slack(
+        search_request.start_timestamp,
+        search_request.end_timestamp,
+        searcher_context.searcher_config().split_pruning_slack_secs,
+    );
+    let split_pruning_stats = compute_split_pruning_stats(
+        &all_split_metadatas,
+        start_timestamp,
+        end_timestamp,
+        tags_filter_opt.as_ref(),
+        term_presence_filter_opt.as_ref(),
+    );
+    debug!(
+        num_splits = split_pruning_stats.num_splits,
+        num_splits_pruned_by_time_range = split_pruning_stats.num_splits_pruned_by_time_range,
+        num_splits_pruned_by_tags = split_pruning_stats.num_splits_pruned_by_tags,
+        num_splits_pruned_by_term_presence = split_pruning_stats.num_splits_pruned_by_term_presence,
+        "split pruning stats"
+    );
+
     let (query, mut warmup_info) = doc_mapper.query(
         doc_mapper.schema(),
         &request_metadata.query_ast_resolved,
@@ -1294,6 +2235,7 @@ pub async fn search_plan(
                 posting: sstable_query_count,
                 position: position_query_count,
             },
+            split_pruning: split_pruning_stats,
         })?,
     })
 }
@@ -1501,8 +2443,6 @@ impl<'a, 'b> QueryAstVisitor<'b> for ExtractTimestampRange<'a> {
     }
 
     fn visit_range(&mut self, range_query: &'b RangeQuery) -> Result<(), Self::Err> {
-        use std::ops::Bound;
-
         if range_query.field == self.timestamp_field {
             match &range_query.lower_bound {
                 Bound::Included(lower_bound) => self.update_start_timestamp(lower_bound, true),
@@ -1670,6 +2610,7 @@ pub fn jobs_to_leaf_request(
 /// Builds a list of [`FetchDocsRequest`], one per index, from a list of [`FetchDocsJob`].
 pub fn jobs_to_fetch_docs_requests(
     snippet_request_opt: Option<SnippetRequest>,
+    fields: &[String],
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     jobs: Vec<FetchDocsJob>,
 ) -> crate::Result<Vec<FetchDocsRequest>> {
@@ -1702,6 +2643,7 @@ pub fn jobs_to_fetch_docs_requests(
                 index_uri: index_meta.index_uri.to_string(),
                 snippet_request: snippet_request_opt.clone(),
                 doc_mapper: index_meta.doc_mapper_str.clone(),
+                fields: fields.to_vec(),
             };
             fetch_docs_requests.push(fetch_docs_req);
 
@@ -1713,21 +2655,24 @@ pub fn jobs_to_fetch_docs_requests(
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
     use std::ops::Range;
     use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, RwLock};
 
     use quickwit_common::shared_consts::SCROLL_BATCH_LEN;
     use quickwit_common::ServiceStream;
-    use quickwit_config::{DocMapping, IndexConfig, IndexingSettings, SearchSettings};
+    use quickwit_config::{
+        DocMapping, IndexConfig, IndexingSettings, SearchFilter, SearchSettings, SearcherConfig,
+        TenantSearchQuotaConfig,
+    };
     use quickwit_indexing::MockSplitBuilder;
     use quickwit_metastore::{IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt};
     use quickwit_proto::metastore::{
-        ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
-    };
-    use quickwit_proto::search::{
-        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
+        EmptyResponse, ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
     };
+    use quickwit_proto::search::{ScrollRequest, SortByValue, SortMode, SortOrder, SortValue};
     use quickwit_query::query_ast::{qast_helper, qast_json_helper, query_ast_from_user_text};
     use tantivy::schema::{FAST, STORED, TEXT};
 
@@ -1744,6 +2689,30 @@ mod tests {
         validate_requested_snippet_fields(&schema, snippet_fields)
     }
 
+    #[test]
+    fn test_num_hits_with_relation() {
+        assert_eq!(
+            num_hits_with_relation(10, CountHits::CountAll, None),
+            (10, NumHitsRelation::EqualTo)
+        );
+        assert_eq!(
+            num_hits_with_relation(10, CountHits::CountAll, Some(10)),
+            (10, NumHitsRelation::EqualTo)
+        );
+        assert_eq!(
+            num_hits_with_relation(11, CountHits::CountAll, Some(10)),
+            (10, NumHitsRelation::GreaterThanOrEqualTo)
+        );
+        assert_eq!(
+            num_hits_with_relation(0, CountHits::CountAll, Some(0)),
+            (0, NumHitsRelation::EqualTo)
+        );
+        assert_eq!(
+            num_hits_with_relation(10, CountHits::Underestimate, None),
+            (10, NumHitsRelation::GreaterThanOrEqualTo)
+        );
+    }
+
     #[test]
     fn test_validate_requested_snippet_fields() {
         check_snippet_fields_validation(&["desc".to_string()]).unwrap();
@@ -1812,6 +2781,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            ..Default::default()
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -1836,11 +2806,13 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                    sort_mode: None,
                 },
                 SortField {
                     field_name: "_doc".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 },
             ],
             ..Default::default()
@@ -1880,6 +2852,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_request_and_build_metadatas_result_window() {
+        let index_metadata = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_offset: 9_990,
+            ..Default::default()
+        };
+        validate_request_and_build_metadata(&[index_metadata.clone()], &search_request).unwrap();
+
+        search_request.start_offset = 9_991;
+        let error = validate_request_and_build_metadata(&[index_metadata.clone()], &search_request)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::ResultWindowTooLarge {
+                result_window: 10_001,
+                max_result_window: 10_000,
+            }
+        ));
+        assert!(error.to_string().contains("use `search_after`"));
+
+        // Requests paginated with `search_after` only collect their page, unless they also skip
+        // hits past the cursor.
+        search_request.start_offset = 0;
+        search_request.sort_fields = vec![SortField {
+            field_name: "timestamp".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+            sort_mode: None,
+        }];
+        search_request.search_after = Some(PartialHit {
+            sort_value: Some(SortByValue {
+                sort_value: Some(SortValue::I64(1)),
+            }),
+            ..Default::default()
+        });
+        validate_request_and_build_metadata(&[index_metadata.clone()], &search_request).unwrap();
+
+        search_request.start_offset = 9_991;
+        let error = validate_request_and_build_metadata(&[index_metadata.clone()], &search_request)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::ResultWindowTooLarge {
+                result_window: 10_001,
+                max_result_window: 10_000,
+            }
+        ));
+
+        search_request.start_offset = 0;
+        search_request.max_hits = 10_001;
+        let error = validate_request_and_build_metadata(&[index_metadata.clone()], &search_request)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::ResultWindowTooLarge {
+                result_window: 10_001,
+                max_result_window: 10_000,
+            }
+        ));
+
+        // The window is configured per index, and the most restrictive one applies.
+        let mut index_metadata_large_window =
+            IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        index_metadata_large_window
+            .index_config
+            .search_settings
+            .max_result_window = Some(50_000);
+
+        search_request.search_after = None;
+        search_request.start_offset = 40_000;
+        search_request.max_hits = 10;
+        validate_request_and_build_metadata(
+            &[index_metadata_large_window.clone()],
+            &search_request,
+        )
+        .unwrap();
+
+        let error = validate_request_and_build_metadata(
+            &[index_metadata_large_window, index_metadata],
+            &search_request,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::ResultWindowTooLarge {
+                result_window: 40_010,
+                max_result_window: 10_000,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_validate_request_and_build_metadatas_warns_on_sort_mode_for_single_valued_field() {
+        let sort_field = |sort_mode: Option<SortMode>| SortField {
+            field_name: "timestamp".to_string(),
+            sort_order: SortOrder::Desc as i32,
+            sort_datetime_format: None,
+            sort_mode: sort_mode.map(|sort_mode| sort_mode as i32),
+        };
+        let indexes_metadata = [
+            IndexMetadata::for_test("test-index-1", "ram:///test-index-1"),
+            IndexMetadata::for_test("test-index-2", "ram:///test-index-2"),
+        ];
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            sort_fields: vec![sort_field(None)],
+            ..Default::default()
+        };
+        let request_metadata =
+            validate_request_and_build_metadata(&indexes_metadata, &search_request).unwrap();
+        assert!(request_metadata.warnings.is_empty());
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            sort_fields: vec![sort_field(Some(SortMode::Min))],
+            ..search_request
+        };
+        let request_metadata =
+            validate_request_and_build_metadata(&indexes_metadata, &search_request).unwrap();
+        assert_eq!(
+            request_metadata.warnings,
+            ["sort mode ignored for single-valued field `timestamp`"]
+        );
+    }
+
     #[test]
     fn test_validate_request_and_build_metadatas_fail_with_different_timestamps() {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -1952,6 +3054,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_validate_request_and_build_metadatas_default_search_fields_override() {
+        let qast = query_ast_from_user_text("test", None);
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: serde_json::to_string(&qast).unwrap(),
+            max_hits: 10,
+            default_search_fields: vec!["body".to_string()],
+            ..Default::default()
+        };
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let mut index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        index_metadata_2
+            .index_config
+            .search_settings
+            .default_search_fields = vec!["owner".to_string()];
+        let request_metadata = validate_request_and_build_metadata(
+            &[index_metadata_1, index_metadata_2],
+            &search_request,
+        )
+        .unwrap();
+        let QueryAst::FullText(full_text_query) = request_metadata.query_ast_resolved else {
+            panic!("expected a full text query");
+        };
+        assert_eq!(full_text_query.field, "body");
+
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .index_config
+            .search_settings
+            .default_search_fields = Vec::new();
+        let error = validate_request_and_build_metadata(
+            &[index_metadata],
+            &quickwit_proto::search::SearchRequest {
+                default_search_fields: Vec::new(),
+                ..search_request
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidQuery(message)
+            if message.contains("no default search field was supplied")));
+    }
+
+    #[test]
+    fn test_validate_request_and_build_metadatas_applies_search_filter() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.search_settings.search_filter =
+            Some(SearchFilter::QueryString("owner:alice".to_string()));
+        // The filter cannot be bypassed by the request, even when it targets the same field.
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("owner:bob OR *", &[]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let request_metadata =
+            validate_request_and_build_metadata(&[index_metadata.clone()], &search_request)
+                .unwrap();
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![qast_helper("owner:bob OR *", &[])],
+            filter: vec![qast_helper("owner:alice", &[])],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(request_metadata.query_ast_resolved, expected_query_ast);
+
+        index_metadata.index_config.search_settings.search_filter =
+            Some(SearchFilter::ElasticQueryDsl(
+                serde_json::json!({"term": {"owner": {"value": "alice"}}}),
+            ));
+        let request_metadata =
+            validate_request_and_build_metadata(&[index_metadata], &search_request).unwrap();
+        let QueryAst::Bool(bool_query) = request_metadata.query_ast_resolved else {
+            panic!("expected a bool query");
+        };
+        assert_eq!(bool_query.must, vec![qast_helper("owner:bob OR *", &[])]);
+        assert_eq!(bool_query.filter.len(), 1);
+        assert!(bool_query.should.is_empty());
+        assert!(bool_query.must_not.is_empty());
+    }
+
     fn index_metadata_for_multi_indexes_test_with_incompatible_sort_type(
         index_id: &str,
         index_uri: &str,
@@ -1984,6 +3167,7 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            ..Default::default()
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -2007,6 +3191,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             }],
             ..Default::default()
         };
@@ -2100,11 +3285,13 @@ mod tests {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
             SortField {
                 field_name: "_shard_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2124,11 +3311,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2148,11 +3337,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2191,11 +3382,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap();
@@ -2208,11 +3401,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2236,11 +3431,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2305,11 +3502,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2338,11 +3537,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2371,11 +3572,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                sort_mode: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2419,16 +3622,19 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
             SortField {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_mode: None,
             },
         ];
         let error = validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap_err();
@@ -2652,15 +3858,11 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
-        let search_request = quickwit_proto::search::SearchRequest {
-            index_id_patterns: vec!["test-index".to_string()],
-            query_ast: qast_json_helper("test", &["body"]),
-            max_hits: 10,
-            ..Default::default()
-        };
+    async fn test_root_search_and_count_apply_search_filter() {
         let mut mock_metastore = MockMetastoreService::new();
-        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.search_settings.search_filter =
+            Some(SearchFilter::QueryString("owner:alice".to_string()));
         let index_uid = index_metadata.index_uid.clone();
         mock_metastore
             .expect_list_indexes_metadata()
@@ -2671,84 +3873,902 @@ mod tests {
             });
         mock_metastore
             .expect_list_splits()
-            .returning(move |_filter| {
-                let splits = vec![
-                    MockSplitBuilder::new("split1")
-                        .with_index_uid(&index_uid)
-                        .build(),
-                    MockSplitBuilder::new("split2")
-                        .with_index_uid(&index_uid)
-                        .build(),
-                ];
+            .returning(move |_list_splits_request| {
+                let splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
                 let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
-        let mut mock_search_service_1 = MockSearchService::new();
-        mock_search_service_1.expect_leaf_search().returning(
-            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
-                Ok(quickwit_proto::search::LeafSearchResponse {
-                    num_hits: 2,
-                    partial_hits: vec![
-                        mock_partial_hit("split1", 3, 1),
-                        mock_partial_hit("split1", 1, 3),
-                    ],
-                    failed_splits: Vec::new(),
-                    num_attempted_splits: 1,
-                    ..Default::default()
-                })
-            },
-        );
-        mock_search_service_1.expect_fetch_docs().returning(
-            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse {
-                    hits: get_doc_for_fetch_req(fetch_docs_req),
-                })
-            },
-        );
-        let mut mock_search_service_2 = MockSearchService::new();
-        mock_search_service_2.expect_leaf_search().returning(
-            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().times(2).returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let search_request = leaf_search_req.search_request.unwrap();
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::Bool(bool_query) = query_ast else {
+                    panic!("expected the search filter to be applied at the root of the query");
+                };
+                assert_eq!(bool_query.must, vec![qast_helper("test", &["body"])]);
+                assert_eq!(bool_query.filter, vec![qast_helper("owner:alice", &[])]);
+
+                let partial_hits = if search_request.max_hits > 0 {
+                    vec![mock_partial_hit("split1", 1, 1)]
+                } else {
+                    Vec::new()
+                };
                 Ok(quickwit_proto::search::LeafSearchResponse {
                     num_hits: 1,
-                    partial_hits: vec![mock_partial_hit("split2", 2, 2)],
-                    failed_splits: Vec::new(),
+                    partial_hits,
                     num_attempted_splits: 1,
                     ..Default::default()
                 })
             },
         );
-        mock_search_service_2.expect_fetch_docs().returning(
+        mock_search_service.expect_fetch_docs().returning(
             |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
                 Ok(quickwit_proto::search::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
                 })
             },
         );
-        let searcher_pool = searcher_pool_for_test([
-            ("127.0.0.1:1001", mock_search_service_1),
-            ("127.0.0.1:1002", mock_search_service_2),
-        ]);
-        let search_job_placer = SearchJobPlacer::new(searcher_pool);
-        let cluster_client = ClusterClient::new(search_job_placer.clone());
-        let search_response = root_search(
-            &SearcherContext::for_test(),
-            search_request,
-            MetastoreServiceClient::from_mock(mock_metastore),
-            &cluster_client,
-        )
-        .await
-        .unwrap();
-        assert_eq!(search_response.num_hits, 3);
-        assert_eq!(search_response.hits.len(), 3);
-        Ok(())
-    }
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = SearcherContext::for_test();
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
 
-    #[tokio::test]
-    async fn test_root_search_multiple_splits_with_failure() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
             index_id_patterns: vec!["test-index".to_string()],
             query_ast: qast_json_helper("test", &["body"]),
-            max_hits: 2,
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+        assert_eq!(search_response.hits.len(), 1);
+
+        let count_request = quickwit_proto::search::SearchRequest {
+            max_hits: 0,
+            ..search_request
+        };
+        let count_response =
+            root_search(&searcher_context, count_request, metastore, &cluster_client)
+                .await
+                .unwrap();
+        assert_eq!(count_response.num_hits, 1);
+        assert!(count_response.hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_root_search_count_time_range_from_split_metadata() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = [
+                    ("split-inside", 1_000..=1_999, 10),
+                    ("split-outside", 3_000..=3_999, 20),
+                    ("split-straddling", 2_000..=2_999, 30),
+                ]
+                .into_iter()
+                .map(|(split_id, time_range, num_docs)| {
+                    let mut split = MockSplitBuilder::new(split_id)
+                        .with_index_uid(&index_uid)
+                        .build();
+                    split.split_metadata.time_range = Some(time_range);
+                    split.split_metadata.num_docs = num_docs;
+                    split
+                })
+                .collect::<Vec<_>>();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().times(1).returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let split_ids: Vec<&str> = leaf_search_req
+                    .leaf_requests
+                    .iter()
+                    .flat_map(|leaf_request| &leaf_request.split_offsets)
+                    .map(|split_offsets| split_offsets.split_id.as_str())
+                    .collect();
+                assert_eq!(split_ids, ["split-straddling"]);
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 7,
+                    num_attempted_splits: 1,
+                    num_successful_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(1_000),
+            end_timestamp: Some(2_500),
+            max_hits: 0,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 10 + 7);
+        assert_eq!(search_response.num_successful_splits, 3);
+        assert!(search_response.failed_splits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_root_search_with_point_in_time() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        // `split2` is published after the first page was returned.
+        let num_list_splits_calls = AtomicUsize::new(0);
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let mut splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
+                if num_list_splits_calls.fetch_add(1, Ordering::Relaxed) > 0 {
+                    splits.push(
+                        MockSplitBuilder::new("split2")
+                            .with_index_uid(&index_uid)
+                            .build(),
+                    );
+                }
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let partial_hits: Vec<PartialHit> = leaf_search_req
+                    .leaf_requests
+                    .iter()
+                    .flat_map(|leaf_request| &leaf_request.split_offsets)
+                    .map(|split_offsets| mock_partial_hit(&split_offsets.split_id, 1, 1))
+                    .collect();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: partial_hits.len() as u64,
+                    num_attempted_splits: partial_hits.len() as u64,
+                    partial_hits,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let searcher_context = SearcherContext::for_test();
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let searched_split_ids = |search_response: &SearchResponse| -> Vec<String> {
+            search_response
+                .hits
+                .iter()
+                .map(|hit| hit.partial_hit.as_ref().unwrap().split_id.clone())
+                .sorted()
+                .collect()
+        };
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            pit_keep_alive_secs: Some(60),
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched_split_ids(&search_response), ["split1"]);
+        let pit_id = search_response.pit_id.unwrap();
+
+        // The next pages search the splits recorded by the point in time only.
+        for pit_keep_alive_secs_opt in [Some(60), None] {
+            let search_request = quickwit_proto::search::SearchRequest {
+                query_ast: qast_json_helper("test", &["body"]),
+                max_hits: 10,
+                pit_keep_alive_secs: pit_keep_alive_secs_opt,
+                pit_id: Some(pit_id.clone()),
+                ..Default::default()
+            };
+            let search_response = root_search(
+                &searcher_context,
+                search_request,
+                metastore.clone(),
+                &cluster_client,
+            )
+            .await
+            .unwrap();
+            assert_eq!(searched_split_ids(&search_response), ["split1"]);
+            assert_eq!(search_response.pit_id.unwrap(), pit_id);
+        }
+
+        // Searches without a point in time see the newly published split.
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched_split_ids(&search_response), ["split1", "split2"]);
+        assert!(search_response.pit_id.is_none());
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["other-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            pit_id: Some(pit_id.clone()),
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+
+        for unknown_pit_id in [ulid::Ulid::new().to_string(), "not-a-pit-id".to_string()] {
+            let search_request = quickwit_proto::search::SearchRequest {
+                query_ast: qast_json_helper("test", &["body"]),
+                max_hits: 10,
+                pit_id: Some(unknown_pit_id.clone()),
+                ..Default::default()
+            };
+            let search_error = root_search(
+                &searcher_context,
+                search_request,
+                metastore.clone(),
+                &cluster_client,
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(
+                search_error,
+                SearchError::PointInTimeNotFound { pit_id } if pit_id == unknown_pit_id
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_root_search_point_in_time_records_all_splits() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        // The point in time is recorded once, the next pages do not list the splits.
+        mock_metastore
+            .expect_list_splits()
+            .times(1)
+            .returning(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert!(list_splits_query.time_range.is_unbounded());
+                let mut split1 = MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build();
+                split1.split_metadata.time_range = Some(0..=10);
+                let mut split2 = MockSplitBuilder::new("split2")
+                    .with_index_uid(&index_uid)
+                    .build();
+                split2.split_metadata.time_range = Some(1_000..=1_010);
+                let splits_response =
+                    ListSplitsResponse::try_from_splits(vec![split1, split2]).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let partial_hits: Vec<PartialHit> = leaf_search_req
+                    .leaf_requests
+                    .iter()
+                    .flat_map(|leaf_request| &leaf_request.split_offsets)
+                    .map(|split_offsets| mock_partial_hit(&split_offsets.split_id, 1, 1))
+                    .collect();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: partial_hits.len() as u64,
+                    num_attempted_splits: partial_hits.len() as u64,
+                    partial_hits,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let searcher_context = SearcherContext::for_test();
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let searched_split_ids = |search_response: &SearchResponse| -> Vec<String> {
+            search_response
+                .hits
+                .iter()
+                .map(|hit| hit.partial_hit.as_ref().unwrap().split_id.clone())
+                .sorted()
+                .collect()
+        };
+        // The request opening the point in time only targets `split1`...
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_timestamp: Some(0),
+            end_timestamp: Some(20),
+            pit_keep_alive_secs: Some(60),
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched_split_ids(&search_response), ["split1"]);
+        let pit_id = search_response.pit_id.unwrap();
+
+        // ... but the point in time records `split2` as well.
+        let search_request = quickwit_proto::search::SearchRequest {
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            pit_id: Some(pit_id.clone()),
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched_split_ids(&search_response), ["split1", "split2"]);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_timestamp: Some(1_000),
+            pit_id: Some(pit_id),
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched_split_ids(&search_response), ["split2"]);
+    }
+
+    #[tokio::test]
+    async fn test_root_search_rejects_search_exceeding_tenant_split_quota() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["tenant-a-logs".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("tenant-a-logs", "ram:///tenant-a-logs");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        // The search is rejected before the leaf fan-out: the leaf search service must not be
+        // called.
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer);
+
+        let mut searcher_config = SearcherConfig::default();
+        searcher_config.tenant_quotas = vec![TenantSearchQuotaConfig {
+            tenant_id: "tenant-a".to_string(),
+            index_id_patterns: vec!["tenant-a-*".to_string()],
+            max_concurrent_searches: None,
+            max_splits_per_search: NonZeroUsize::new(1),
+        }];
+        let searcher_context = SearcherContext::new(searcher_config, None);
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::TooManyRequests));
+    }
+
+    fn index_id_patterns(index_id_patterns: &[&str]) -> Vec<String> {
+        index_id_patterns
+            .iter()
+            .map(|index_id_pattern| index_id_pattern.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_index_id_patterns() {
+        validate_index_id_patterns(&[]).unwrap();
+        validate_index_id_patterns(&index_id_patterns(&["logs-*"])).unwrap();
+        validate_index_id_patterns(&index_id_patterns(&["logs-*", "-logs-debug-*"])).unwrap();
+        // Only the leading `-` marks an exclusion: the other dashes are literal.
+        validate_index_id_patterns(&index_id_patterns(&["logs-debug-*", "-logs-debug-2"])).unwrap();
+
+        let search_error =
+            validate_index_id_patterns(&index_id_patterns(&["-logs-debug-*", "-logs-test"]))
+                .unwrap_err();
+        assert_eq!(
+            search_error.to_string(),
+            "Invalid argument: index ID patterns `-logs-debug-*,-logs-test` only contain \
+             exclusions: at least one pattern not prefixed with `-` is required"
+        );
+    }
+
+    #[test]
+    fn test_check_all_index_metadata_found_with_exclusions() {
+        let indexes_metadata = vec![
+            IndexMetadata::for_test("logs-app", "ram:///logs-app"),
+            IndexMetadata::for_test("logs-web", "ram:///logs-web"),
+        ];
+        check_all_index_metadata_found(
+            &indexes_metadata,
+            &index_id_patterns(&["logs-app", "logs-web", "-logs-debug-*"]),
+        )
+        .unwrap();
+
+        // An index ID that is both included and excluded is excluded, so it does not have to
+        // exist.
+        check_all_index_metadata_found(
+            &indexes_metadata,
+            &index_id_patterns(&["logs-app", "logs-debug-1", "-logs-debug-*"]),
+        )
+        .unwrap();
+        check_all_index_metadata_found(&[], &index_id_patterns(&["logs-app", "-logs-app"]))
+            .unwrap();
+
+        let search_error = check_all_index_metadata_found(
+            &indexes_metadata,
+            &index_id_patterns(&["logs-app", "logs-debug-1", "-logs-web"]),
+        )
+        .unwrap_err();
+        let SearchError::IndexesNotFound { index_ids } = search_error else {
+            panic!("expected `IndexesNotFound` error, got `{search_error}`");
+        };
+        assert_eq!(index_ids, vec!["logs-debug-1".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_root_search_with_index_id_patterns_matching_no_index() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(2)
+            .returning(|list_indexes_metadata_request| {
+                assert_eq!(
+                    list_indexes_metadata_request.index_id_patterns,
+                    vec!["logs-*".to_string(), "-logs-debug-*".to_string()]
+                );
+                Ok(ListIndexesMetadataResponse::for_test(Vec::new()))
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = SearcherContext::for_test();
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: index_id_patterns(&["logs-*", "-logs-debug-*"]),
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        // By default, patterns matching no index yield an empty response.
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 0);
+        assert!(search_response.hits.is_empty());
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            allow_no_indices: Some(false),
+            ..search_request
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        let SearchError::IndexesNotFound { index_ids } = search_error else {
+            panic!("expected `IndexesNotFound` error, got `{search_error}`");
+        };
+        assert_eq!(index_ids, vec!["logs-*".to_string()]);
+
+        // Exclusion-only patterns are rejected before the metastore is called.
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: index_id_patterns(&["-logs-debug-*"]),
+            query_ast: qast_json_helper("test", &["body"]),
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    }
+
+    /// Returns a metastore listing the splits `split1` and `split2` of `test-index`. Every call to
+    /// `list_splits` beyond the first `num_fast_calls` takes a minute to respond.
+    fn slow_metastore_for_test(
+        index_metadata: IndexMetadata,
+        num_fast_calls: usize,
+    ) -> MockMetastoreService {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                let delay = if num_calls.fetch_add(1, Ordering::Relaxed) < num_fast_calls {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs(60)
+                };
+                let splits_stream = futures::stream::once(Box::pin(async move {
+                    tokio::time::sleep(delay).await;
+                    Ok(splits_response)
+                }));
+                Ok(ServiceStream::new(Box::pin(splits_stream)))
+            });
+        mock_metastore
+    }
+
+    fn searcher_context_with_request_timeout_for_test(
+        request_timeout_secs: u64,
+    ) -> SearcherContext {
+        let searcher_config: SearcherConfig = serde_json::from_value(serde_json::json!({
+            "request_timeout_secs": request_timeout_secs,
+        }))
+        .unwrap();
+        SearcherContext::new(searcher_config, None)
+    }
+
+    #[tokio::test]
+    async fn test_root_search_falls_back_to_cached_listing_on_metastore_timeout() {
+        tokio::time::pause();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let metastore =
+            MetastoreServiceClient::from_mock(slow_metastore_for_test(index_metadata, 1));
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let num_splits = leaf_search_req
+                    .leaf_requests
+                    .iter()
+                    .map(|leaf_request| leaf_request.split_offsets.len())
+                    .sum::<usize>();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: num_splits as u64,
+                    num_attempted_splits: num_splits as u64,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service
+            .expect_fetch_docs()
+            .returning(|_fetch_docs_req| Ok(quickwit_proto::search::FetchDocsResponse::default()));
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = searcher_context_with_request_timeout_for_test(10);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 0,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 2);
+        assert!(!search_response.partial);
+        assert!(search_response.errors.is_empty());
+
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 2);
+        assert!(search_response.partial);
+        assert_eq!(search_response.errors.len(), 1);
+        assert!(search_response.errors[0].contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_root_search_fails_fast_on_metastore_timeout_without_cached_listing() {
+        tokio::time::pause();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let metastore =
+            MetastoreServiceClient::from_mock(slow_metastore_for_test(index_metadata, 0));
+
+        // The search fails before the leaf fan-out: the leaf search service must not be called.
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = searcher_context_with_request_timeout_for_test(10);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 0,
+            ..Default::default()
+        };
+        let start_instant = tokio::time::Instant::now();
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        let SearchError::Timeout(error_msg) = search_error else {
+            panic!("expected a timeout error, got `{search_error:?}`");
+        };
+        assert!(error_msg.contains("metastore"));
+        assert_eq!(start_instant.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_filter| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 2,
+                    partial_hits: vec![
+                        mock_partial_hit("split1", 3, 1),
+                        mock_partial_hit("split1", 1, 3),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service_1.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let mut mock_search_service_2 = MockSearchService::new();
+        mock_search_service_2.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 1,
+                    partial_hits: vec![mock_partial_hit("split2", 2, 2)],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service_2.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", mock_search_service_1),
+            ("127.0.0.1:1002", mock_search_service_2),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 3);
+        assert_eq!(search_response.hits.len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_root_search_multiple_splits_with_failure() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 2,
             ..Default::default()
         };
         let mut mock_metastore = MockMetastoreService::new();
@@ -2789,6 +4809,7 @@ mod tests {
                             error: "some error".to_string(),
                             split_id: "split2".to_string(),
                             retryable_error: true,
+                            ..Default::default()
                         }],
                         num_attempted_splits: 2,
                         ..Default::default()
@@ -2838,6 +4859,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Asc.into(),
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampNanos as i32),
+                sort_mode: None,
             }],
             ..Default::default()
         };
@@ -3020,6 +5042,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Desc.into(),
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampNanos as i32),
+                sort_mode: None,
             }],
             ..Default::default()
         };
@@ -3281,6 +5304,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split2".to_string(),
                             retryable_error: true,
+                            ..Default::default()
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -3361,6 +5385,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split2".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3422,6 +5447,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3494,6 +5520,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split1".to_string(),
                             retryable_error: true,
+                            ..Default::default()
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -3505,15 +5532,81 @@ mod tests {
                         failed_splits: Vec::new(),
                         num_attempted_splits: 1,
                         ..Default::default()
-                    })
-                }
+                    })
+                }
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+        assert_eq!(search_response.hits.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_root_search_single_split_retry_single_node_fails() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_filter| {
+                let splits = vec![MockSplitBuilder::new("split1")
+                    .with_index_uid(&index_uid)
+                    .build()];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().times(2).returning(
+            move |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 0,
+                    partial_hits: Vec::new(),
+                    failed_splits: vec![SplitSearchError {
+                        error: "mock_error".to_string(),
+                        split_id: "split1".to_string(),
+                        retryable_error: true,
+                        ..Default::default()
+                    }],
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
             },
         );
         mock_search_service.expect_fetch_docs().returning(
-            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
-                Ok(quickwit_proto::search::FetchDocsResponse {
-                    hits: get_doc_for_fetch_req(fetch_docs_req),
-                })
+            |_fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Err(SearchError::Internal("mockerr docs".to_string()))
             },
         );
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
@@ -3527,13 +5620,11 @@ mod tests {
         )
         .await
         .unwrap();
-        assert_eq!(search_response.num_hits, 1);
-        assert_eq!(search_response.hits.len(), 1);
-        Ok(())
+        assert_eq!(search_response.failed_splits.len(), 1);
     }
 
     #[tokio::test]
-    async fn test_root_search_single_split_retry_single_node_fails() {
+    async fn test_root_search_marks_missing_splits_for_deletion() {
         let search_request = quickwit_proto::search::SearchRequest {
             index_id_patterns: vec!["test-index".to_string()],
             query_ast: qast_json_helper("test", &["body"]),
@@ -3550,49 +5641,76 @@ mod tests {
                     index_metadata.clone()
                 ]))
             });
+        let index_uid_clone = index_uid.clone();
         mock_metastore
             .expect_list_splits()
             .returning(move |_filter| {
-                let splits = vec![MockSplitBuilder::new("split1")
-                    .with_index_uid(&index_uid)
-                    .build()];
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid_clone)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid_clone)
+                        .build(),
+                ];
                 let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
+        mock_metastore
+            .expect_mark_splits_for_deletion()
+            .times(1)
+            .returning(move |mark_splits_for_deletion_request| {
+                assert_eq!(mark_splits_for_deletion_request.index_uid(), &index_uid);
+                assert_eq!(mark_splits_for_deletion_request.split_ids, ["split1"]);
+                Ok(EmptyResponse {})
+            });
 
         let mut mock_search_service = MockSearchService::new();
-        mock_search_service.expect_leaf_search().times(2).returning(
+        mock_search_service.expect_leaf_search().returning(
             move |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
                 Ok(quickwit_proto::search::LeafSearchResponse {
                     num_hits: 0,
                     partial_hits: Vec::new(),
-                    failed_splits: vec![SplitSearchError {
-                        error: "mock_error".to_string(),
-                        split_id: "split1".to_string(),
-                        retryable_error: true,
-                    }],
-                    num_attempted_splits: 1,
+                    failed_splits: vec![
+                        SplitSearchError {
+                            error: "split file not found".to_string(),
+                            split_id: "split1".to_string(),
+                            retryable_error: false,
+                            error_code: SplitSearchErrorCode::SplitNotFound as i32,
+                        },
+                        SplitSearchError {
+                            error: "mock_error".to_string(),
+                            split_id: "split2".to_string(),
+                            retryable_error: false,
+                            ..Default::default()
+                        },
+                    ],
+                    num_attempted_splits: 2,
                     ..Default::default()
                 })
             },
         );
         mock_search_service.expect_fetch_docs().returning(
             |_fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
-                Err(SearchError::Internal("mockerr docs".to_string()))
+                Ok(quickwit_proto::search::FetchDocsResponse { hits: Vec::new() })
             },
         );
         let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
         let search_job_placer = SearchJobPlacer::new(searcher_pool);
         let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let searcher_config = SearcherConfig {
+            report_missing_splits: true,
+            ..Default::default()
+        };
         let search_response = root_search(
-            &SearcherContext::for_test(),
+            &SearcherContext::new(searcher_config, None),
             search_request,
             MetastoreServiceClient::from_mock(mock_metastore),
             &cluster_client,
         )
         .await
         .unwrap();
-        assert_eq!(search_response.failed_splits.len(), 1);
+        assert_eq!(search_response.failed_splits.len(), 2);
     }
 
     #[tokio::test]
@@ -3655,6 +5773,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        ..Default::default()
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -3824,6 +5943,70 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_query_ast_limits() -> anyhow::Result<()> {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone()
+                ]))
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let searcher_context = SearcherContext::for_test();
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        // `body:t0 AND (body:t1 AND (body:t2 AND ...))`
+        let mut deeply_nested_query = "body:leaf".to_string();
+        for i in 0..100 {
+            deeply_nested_query = format!("body:t{i} AND ({deeply_nested_query})");
+        }
+        let search_error = root_search(
+            &searcher_context,
+            quickwit_proto::search::SearchRequest {
+                index_id_patterns: vec!["test-index".to_string()],
+                query_ast: qast_json_helper(&deeply_nested_query, &[]),
+                max_hits: 10,
+                ..Default::default()
+            },
+            metastore.clone(),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            search_error,
+            SearchError::InvalidQuery(message)
+                if message == "query exceeds the `max_query_ast_depth` limit of 64"
+        ));
+
+        // Per-request overrides can lower the limits.
+        let search_error = root_search(
+            &searcher_context,
+            quickwit_proto::search::SearchRequest {
+                index_id_patterns: vec!["test-index".to_string()],
+                query_ast: qast_json_helper("body:a OR body:b OR body:c", &[]),
+                max_hits: 10,
+                max_query_ast_clause_count: Some(2),
+                ..Default::default()
+            },
+            metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            search_error,
+            SearchError::InvalidQuery(message)
+                if message == "query exceeds the `max_query_ast_clause_count` limit of 2"
+        ));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_invalid_aggregation() -> anyhow::Result<()> {
         let agg_req = r#"
@@ -3928,10 +6111,11 @@ mod tests {
             &cluster_client,
         )
         .await;
-        assert!(search_response.is_err());
         assert_eq!(
             search_response.unwrap_err().to_string(),
-            "Invalid argument: max value for start_offset is 10_000, but got 20000",
+            "result window is too large, `start_offset + max_hits` must be less than or equal to \
+             10000 but was 20010. use `search_after` to paginate through large result sets, or \
+             raise the `search_settings.max_result_window` index setting",
         );
 
         let search_request = quickwit_proto::search::SearchRequest {
@@ -3948,11 +6132,13 @@ mod tests {
             &cluster_client,
         )
         .await;
-        assert!(search_response.is_err());
-        assert_eq!(
-            search_response.unwrap_err().to_string(),
-            "Invalid argument: max value for max_hits is 10_000, but got 20000",
-        );
+        assert!(matches!(
+            search_response.unwrap_err(),
+            SearchError::ResultWindowTooLarge {
+                result_window: 20_000,
+                max_result_window: 10_000,
+            }
+        ));
 
         Ok(())
     }
@@ -3993,6 +6179,7 @@ mod tests {
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
         let search_response = search_plan(
+            &SearcherContext::for_test(),
             search_request,
             MetastoreServiceClient::from_mock(mock_metastore),
         )
@@ -4039,15 +6226,140 @@ mod tests {
                     posting: 2,
                     position: 0,
                 },
+                split_pruning: SplitPruningStats {
+                    num_splits: 2,
+                    num_splits_pruned_by_time_range: 0,
+                    num_splits_pruned_by_tags: 0,
+                    num_splits_pruned_by_term_presence: 0,
+                },
             }
         );
         Ok(())
     }
 
     #[test]
-    fn test_extract_timestamp_range_from_ast() {
-        use std::ops::Bound;
+    fn test_extract_count_request_time_range() {
+        use quickwit_query::JsonLiteral;
+
+        let timestamp_field = "timestamp";
+        let start = DateTime::from_timestamp_secs(1_000);
+        let end = DateTime::from_timestamp_secs(2_500);
+
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::MatchAll).unwrap(),
+            start_timestamp: Some(1_000),
+            end_timestamp: Some(2_500),
+            max_hits: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_count_request_time_range(&search_request, timestamp_field),
+            Some((Bound::Included(start), Bound::Excluded(end)))
+        );
+        let search_request = SearchRequest {
+            max_hits: 10,
+            ..search_request
+        };
+        assert!(extract_count_request_time_range(&search_request, timestamp_field).is_none());
+
+        let range_query: QueryAst = RangeQuery {
+            field: timestamp_field.to_string(),
+            lower_bound: Bound::Included(JsonLiteral::String("1970-01-01T00:16:40Z".to_string())),
+            upper_bound: Bound::Excluded(JsonLiteral::String("1970-01-01T00:41:40Z".to_string())),
+        }
+        .into();
+        let bool_query = BoolQuery {
+            must: vec![QueryAst::MatchAll],
+            filter: vec![range_query.clone()],
+            ..Default::default()
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::from(bool_query)).unwrap(),
+            max_hits: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_count_request_time_range(&search_request, timestamp_field),
+            Some((Bound::Included(start), Bound::Excluded(end)))
+        );
+
+        let bool_query = BoolQuery {
+            filter: vec![range_query],
+            should: vec![qast_helper("test", &["body"])],
+            ..Default::default()
+        };
+        let search_request = SearchRequest {
+            query_ast: serde_json::to_string(&QueryAst::from(bool_query)).unwrap(),
+            max_hits: 0,
+            ..Default::default()
+        };
+        assert!(extract_count_request_time_range(&search_request, timestamp_field).is_none());
+
+        let search_request = SearchRequest {
+            query_ast: qast_json_helper("test", &["body"]),
+            start_timestamp: Some(1_000),
+            max_hits: 0,
+            ..Default::default()
+        };
+        assert!(extract_count_request_time_range(&search_request, timestamp_field).is_none());
+    }
+
+    #[test]
+    fn test_count_split_docs_in_time_range_from_metadata() {
+        let split_metadata = SplitMetadata {
+            num_docs: 10,
+            time_range: Some(1_000..=1_999),
+            ..Default::default()
+        };
+        let secs = DateTime::from_timestamp_secs;
+        let count = |start_timestamp, end_timestamp| {
+            count_split_docs_in_time_range_from_metadata(
+                &split_metadata,
+                start_timestamp,
+                end_timestamp,
+            )
+        };
+        // The split is fully inside the time range.
+        assert_eq!(count(Bound::Unbounded, Bound::Unbounded), Some(10));
+        assert_eq!(
+            count(Bound::Included(secs(1_000)), Bound::Excluded(secs(2_000))),
+            Some(10)
+        );
+        // The split is fully outside the time range.
+        assert_eq!(
+            count(Bound::Included(secs(2_000)), Bound::Unbounded),
+            Some(0)
+        );
+        assert_eq!(
+            count(Bound::Unbounded, Bound::Excluded(secs(1_000))),
+            Some(0)
+        );
+        assert_eq!(count(Bound::Unbounded, Bound::Included(secs(999))), Some(0));
+        // The split straddles the bounds of the time range.
+        assert_eq!(count(Bound::Excluded(secs(1_000)), Bound::Unbounded), None);
+        assert_eq!(count(Bound::Unbounded, Bound::Included(secs(1_999))), None);
+        assert_eq!(
+            count(Bound::Included(secs(1_500)), Bound::Excluded(secs(2_500))),
+            None
+        );
 
+        let split_metadata = SplitMetadata {
+            num_docs: 10,
+            time_range: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            count_split_docs_in_time_range_from_metadata(
+                &split_metadata,
+                Bound::Unbounded,
+                Bound::Unbounded
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_timestamp_range_from_ast() {
         use quickwit_query::JsonLiteral;
 
         let timestamp_field = "timestamp";
@@ -4865,6 +7177,127 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_index_pseudo_field() {
+        // Searches the indexes matching the pattern, except `test-index-3`, and sorts the hits tied
+        // on their timestamp on their index in descending order.
+        let query_ast = QueryAst::Bool(BoolQuery {
+            must: vec![qast_helper("test", &["body"])],
+            must_not: vec![TermQuery {
+                field: "_index".to_string(),
+                value: "test-index-3".to_string(),
+            }
+            .into()],
+            ..Default::default()
+        });
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: serde_json::to_string(&query_ast).unwrap(),
+            max_hits: 10,
+            sort_fields: vec![
+                SortField {
+                    field_name: "timestamp".to_string(),
+                    sort_order: SortOrder::Desc as i32,
+                    ..Default::default()
+                },
+                SortField {
+                    field_name: "_index".to_string(),
+                    sort_order: SortOrder::Desc as i32,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata_1 =
+            index_metadata_for_multi_indexes_test("test-index-1", "ram:///test-index-1");
+        let index_uid_1 = index_metadata_1.index_uid.clone();
+        let index_metadata_2 =
+            index_metadata_for_multi_indexes_test("test-index-2", "ram:///test-index-2");
+        let index_uid_2 = index_metadata_2.index_uid.clone();
+        let index_metadata_3 =
+            index_metadata_for_multi_indexes_test("test-index-3", "ram:///test-index-3");
+        mock_metastore.expect_list_indexes_metadata().return_once(
+            move |_list_indexes_metadata_request: ListIndexesMetadataRequest| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_1,
+                    index_metadata_2,
+                    index_metadata_3,
+                ]))
+            },
+        );
+        mock_metastore
+            .expect_list_splits()
+            .return_once(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert_eq!(
+                    list_splits_query.index_uids,
+                    vec![index_uid_1.clone(), index_uid_2.clone()]
+                );
+                let splits = vec![
+                    MockSplitBuilder::new("index-1-split-1")
+                        .with_index_uid(&index_uid_1)
+                        .build(),
+                    MockSplitBuilder::new("index-2-split-1")
+                        .with_index_uid(&index_uid_2)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                // Neither the `_index` clause nor the `_index` sort field reach the leaves.
+                let search_request = leaf_search_req.search_request.as_ref().unwrap();
+                assert!(!search_request.query_ast.contains("_index"));
+                assert_eq!(search_request.sort_fields.len(), 1);
+                assert_eq!(search_request.sort_fields[0].field_name, "timestamp");
+
+                let partial_hits = leaf_search_req
+                    .leaf_requests
+                    .iter()
+                    .flat_map(|leaf_request| &leaf_request.split_offsets)
+                    .map(|split_offset| mock_partial_hit(&split_offset.split_id, 3, 1))
+                    .collect_vec();
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: partial_hits.len() as u64,
+                    num_attempted_splits: partial_hits.len() as u64,
+                    partial_hits,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service
+            .expect_fetch_docs()
+            .returning(|fetch_docs_req| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 2);
+        assert_eq!(
+            search_response
+                .hits
+                .iter()
+                .map(|hit| &hit.index_id)
+                .collect_vec(),
+            vec!["test-index-2", "test-index-1"]
+        );
+    }
+
     #[tokio::test]
     async fn test_root_search_split_failures() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -4921,6 +7354,7 @@ mod tests {
                                 error: "some error".to_string(),
                                 split_id: "index-1-split-1".to_string(),
                                 retryable_error: true,
+                                ..Default::default()
                             }
                         }],
                         num_attempted_splits: 3,
@@ -4947,6 +7381,7 @@ mod tests {
                                 error: "some error".to_string(),
                                 split_id: "index-1-split-1".to_string(),
                                 retryable_error: true,
+                                ..Default::default()
                             }
                         }],
                         num_attempted_splits: 1,
@@ -28,7 +28,9 @@ use anyhow::Context;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use quickwit_metastore::SplitMetadata;
-use quickwit_proto::search::{LeafSearchResponse, PartialHit, SearchRequest, SplitSearchError};
+use quickwit_proto::search::{
+    CountHits, LeafSearchResponse, PartialHit, SearchRequest, SplitSearchError,
+};
 use quickwit_proto::types::IndexUid;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
@@ -54,6 +56,9 @@ pub(crate) struct ScrollContext {
     pub search_request: SearchRequest,
     pub indexes_metas_for_leaf_search: HashMap<IndexUid, IndexMetasForLeafSearch>,
     pub total_num_hits: u64,
+    /// Hit count mode of the initial search request, the one of the scroll search request being
+    /// always [`CountHits::Underestimate`].
+    pub count_hits: CountHits,
     pub max_hits_per_page: u64,
     pub cached_partial_hits_start_offset: u64,
     pub cached_partial_hits: Vec<PartialHit>,
@@ -116,6 +121,7 @@ impl ScrollContext {
             &self.indexes_metas_for_leaf_search,
             &self.search_request,
             &self.split_metadatas[..],
+            None,
             cluster_client,
         )
         .await?;
@@ -132,13 +138,17 @@ pub(crate) struct MiniKV {
 
 impl Default for MiniKV {
     fn default() -> MiniKV {
-        MiniKV {
-            ttl_with_cache: Arc::new(RwLock::new(TtlCache::new(SCROLL_BATCH_LEN))),
-        }
+        MiniKV::with_capacity(SCROLL_BATCH_LEN)
     }
 }
 
 impl MiniKV {
+    pub fn with_capacity(capacity: usize) -> MiniKV {
+        MiniKV {
+            ttl_with_cache: Arc::new(RwLock::new(TtlCache::new(capacity))),
+        }
+    }
+
     pub async fn put(&self, key: Vec<u8>, payload: Vec<u8>, ttl: Duration) {
         let mut cache_lock = self.ttl_with_cache.write().await;
         cache_lock.insert(key, payload, ttl);
@@ -149,6 +159,12 @@ impl MiniKV {
         let search_after_context_bytes = cache_lock.get(key)?;
         Some(search_after_context_bytes.clone())
     }
+
+    /// Returns the number of entries that have not expired yet.
+    pub async fn num_entries(&self) -> usize {
+        let mut cache_lock = self.ttl_with_cache.write().await;
+        cache_lock.iter().count()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
@@ -0,0 +1,804 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::stream;
+use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
+use quickwit_proto::metastore::{
+    ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::search::{CountHits, PartialHit, SearchRequest};
+use tracing::{info, instrument};
+
+use crate::hits_stream_cursor::{request_fingerprint, split_set_fingerprint, HitsStreamCursor};
+use crate::point_in_time::PointInTime;
+use crate::root::{
+    check_all_index_metadata_found, check_allow_no_indices, fetch_docs_phase,
+    refine_and_list_matches, refine_search_request, search_partial_hits_phase,
+    validate_index_id_patterns, validate_request_and_build_metadata, IndexesMetasForLeafSearch,
+};
+use crate::{ClusterClient, SearchError, SearcherContext};
+
+/// Number of documents fetched and emitted by each page of a hits stream.
+///
+/// This bounds the amount of documents a single stream holds at any point in time.
+const HITS_STREAM_PAGE_SIZE: u64 = 1_000;
+
+/// Number of pages of partial hits collected by each leaf search of a split.
+///
+/// Each leaf search evaluates the query over the whole split, so collecting several pages at once
+/// saves as many searches. The resulting number of hits collected by the leaves matches the
+/// default result window of an index.
+const HITS_STREAM_PAGES_PER_SPLIT_SEARCH: u64 = 10;
+
+/// A page of documents of a hits stream.
+#[derive(Clone, Debug)]
+pub struct HitsStreamPage {
+    /// The documents of the page, serialized as newline-delimited JSON.
+    pub ndjson: Bytes,
+    /// Opaque cursor resuming the stream right after the last document of the page.
+    pub cursor: String,
+}
+
+/// Streams all the documents matching a search request as newline-delimited JSON.
+///
+/// Unlike a regular search, no global top-K is computed: splits are scanned one after another,
+/// and the documents of a given split are emitted in the split's natural order before moving on
+/// to the next split. Each split is paged through with `search_after`: a split search collects
+/// the partial hits of several pages past the cursor of the split, and only one page of documents
+/// is ever fetched and held in memory. The stream is lazy: the next page is searched and fetched
+/// only once the previous one has been consumed, and dropping the stream stops the scan.
+///
+/// `max_hits` is interpreted as a hard cap on the total number of streamed documents, `0`
+/// meaning no cap. `fields` restricts the fields returned for each document.
+///
+/// Each page comes with a cursor encoding the position of the stream. The searcher keeps no
+/// state about the stream, so passing the cursor of the last received page along with the same
+/// request to any searcher resumes the stream without duplicate or missing documents. The cursor
+/// is rejected if the splits targeted by the request changed in the meantime, which can be
+/// prevented by streaming from a point in time.
+#[instrument(skip_all)]
+pub async fn root_search_hits_stream(
+    searcher_context: Arc<SearcherContext>,
+    search_request: SearchRequest,
+    cursor_opt: Option<String>,
+    metastore: MetastoreServiceClient,
+    cluster_client: ClusterClient,
+) -> crate::Result<impl futures::Stream<Item = crate::Result<HitsStreamPage>>> {
+    root_search_hits_stream_with_page_size(
+        searcher_context,
+        search_request,
+        cursor_opt,
+        metastore,
+        cluster_client,
+        HITS_STREAM_PAGE_SIZE,
+        HITS_STREAM_PAGE_SIZE * HITS_STREAM_PAGES_PER_SPLIT_SEARCH,
+    )
+    .await
+}
+
+async fn root_search_hits_stream_with_page_size(
+    searcher_context: Arc<SearcherContext>,
+    mut search_request: SearchRequest,
+    cursor_opt: Option<String>,
+    mut metastore: MetastoreServiceClient,
+    cluster_client: ClusterClient,
+    page_size: u64,
+    split_search_size: u64,
+) -> crate::Result<impl futures::Stream<Item = crate::Result<HitsStreamPage>>> {
+    info!(search_request = ?search_request, "root search hits stream");
+    validate_hits_stream_request(&search_request)?;
+    let request_fingerprint = request_fingerprint(&search_request);
+    let cursor_opt: Option<HitsStreamCursor> = cursor_opt
+        .map(|cursor| cursor.parse())
+        .transpose()
+        .map_err(|error: &str| SearchError::InvalidArgument(error.to_string()))?;
+
+    let point_in_time_opt: Option<PointInTime> =
+        if let Some(pit_id) = search_request.pit_id.as_deref() {
+            let point_in_time = PointInTime::load(pit_id, &cluster_client).await?;
+            point_in_time.check_index_id_patterns(&mut search_request.index_id_patterns)?;
+            Some(point_in_time)
+        } else {
+            None
+        };
+    validate_index_id_patterns(&search_request.index_id_patterns)?;
+
+    let list_indexes_metadatas_request = ListIndexesMetadataRequest {
+        index_id_patterns: search_request.index_id_patterns.clone(),
+    };
+    let indexes_metadata: Vec<IndexMetadata> = metastore
+        .list_indexes_metadata(list_indexes_metadatas_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    check_all_index_metadata_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
+    check_allow_no_indices(&indexes_metadata, &search_request)?;
+
+    let max_num_docs = if search_request.max_hits == 0 {
+        u64::MAX
+    } else {
+        search_request.max_hits
+    };
+    // The cap on the total number of documents is enforced by the stream itself.
+    search_request.max_hits = page_size;
+    search_request.count_hits = CountHits::Underestimate as i32;
+
+    let mut split_metadatas = Vec::new();
+    let mut indexes_metas_for_leaf_search = IndexesMetasForLeafSearch::default();
+
+    if !indexes_metadata.is_empty() {
+        let index_ids: Vec<&str> = indexes_metadata
+            .iter()
+            .map(|index_metadata| index_metadata.index_id())
+            .collect();
+        let search_quota_guard = searcher_context.search_quotas.acquire(&index_ids)?;
+        let request_metadata =
+            validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
+
+        if let Some(point_in_time) = point_in_time_opt {
+            refine_search_request(
+                &mut search_request,
+                request_metadata.query_ast_resolved,
+                &request_metadata.sort_fields_is_datetime,
+                request_metadata.timestamp_field_opt.as_deref(),
+            )?;
+            split_metadatas = point_in_time
+                .split_metadatas
+                .into_iter()
+                .filter(|split_metadata| {
+                    request_metadata
+                        .indexes_meta_for_leaf_search
+                        .contains_key(&split_metadata.index_uid)
+                })
+                .collect();
+        } else {
+            (split_metadatas, _) = refine_and_list_matches(
+                &mut metastore,
+                &mut search_request,
+                indexes_metadata,
+                request_metadata.query_ast_resolved,
+                request_metadata.sort_fields_is_datetime,
+                request_metadata.timestamp_field_opt,
+                Some(&searcher_context),
+            )
+            .await?;
+        }
+        search_quota_guard.check_num_splits(split_metadatas.len())?;
+        indexes_metas_for_leaf_search = request_metadata.indexes_meta_for_leaf_search;
+    }
+    // Leaf requests only ever ask for one split search worth of hits.
+    search_request.max_hits = split_search_size;
+
+    // Scanning splits in a deterministic order makes exports reproducible, and resumable.
+    split_metadatas.sort_by(|left, right| right.split_id.cmp(&left.split_id));
+
+    let split_set_fingerprint = split_set_fingerprint(&split_metadatas);
+    let num_splits = split_metadatas.len();
+    let mut split_metadatas: VecDeque<SplitMetadata> = split_metadatas.into();
+    let mut search_after_opt = None;
+    let mut num_streamed_docs = 0;
+
+    if let Some(cursor) = cursor_opt {
+        cursor.validate(request_fingerprint, split_metadatas.make_contiguous())?;
+        split_metadatas.drain(..cursor.split_ord);
+        search_after_opt = cursor.search_after_opt;
+        num_streamed_docs = cursor.num_streamed_docs;
+    }
+    let hits_stream_state = HitsStreamState {
+        searcher_context,
+        cluster_client,
+        indexes_metas_for_leaf_search,
+        search_request,
+        request_fingerprint,
+        split_set_fingerprint,
+        page_size,
+        num_splits,
+        split_metadatas,
+        search_after_opt,
+        buffered_partial_hits: VecDeque::new(),
+        is_split_exhausted: false,
+        num_streamed_docs,
+        num_remaining_docs: max_num_docs.saturating_sub(num_streamed_docs),
+    };
+    Ok(stream::try_unfold(hits_stream_state, |hits_stream_state| {
+        hits_stream_state.next_page()
+    }))
+}
+
+fn validate_hits_stream_request(search_request: &SearchRequest) -> crate::Result<()> {
+    if !search_request.sort_fields.is_empty() {
+        return Err(SearchError::InvalidArgument(
+            "sorting is not supported when streaming hits".to_string(),
+        ));
+    }
+    if search_request.aggregation_request.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "aggregations are not supported when streaming hits".to_string(),
+        ));
+    }
+    if search_request.scroll_ttl_secs.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "scroll is not supported when streaming hits".to_string(),
+        ));
+    }
+    if search_request.pit_keep_alive_secs.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "opening a point in time is not supported when streaming hits".to_string(),
+        ));
+    }
+    if search_request.start_offset != 0 || search_request.search_after.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "pagination is not supported when streaming hits".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+struct HitsStreamState {
+    searcher_context: Arc<SearcherContext>,
+    cluster_client: ClusterClient,
+    indexes_metas_for_leaf_search: IndexesMetasForLeafSearch,
+    search_request: SearchRequest,
+    request_fingerprint: u64,
+    split_set_fingerprint: u64,
+    page_size: u64,
+    num_splits: usize,
+    // Splits left to scan, starting with the one currently being scanned.
+    split_metadatas: VecDeque<SplitMetadata>,
+    // Last hit emitted for the split currently being scanned. The next search of the split
+    // resumes from it once the buffered hits have been emitted.
+    search_after_opt: Option<PartialHit>,
+    // Hits collected by the last search of the split currently being scanned, not emitted yet.
+    buffered_partial_hits: VecDeque<PartialHit>,
+    // Whether the last search of the split currently being scanned collected its last hits.
+    is_split_exhausted: bool,
+    num_streamed_docs: u64,
+    num_remaining_docs: u64,
+}
+
+impl HitsStreamState {
+    /// Fetches the next page of documents and serializes them as NDJSON, searching the next
+    /// hits of the current split first if none are buffered.
+    ///
+    /// Returns `None` once all splits have been scanned or the cap on the number of documents
+    /// has been reached.
+    async fn next_page(mut self) -> crate::Result<Option<(HitsStreamPage, Self)>> {
+        loop {
+            if self.num_remaining_docs == 0 {
+                return Ok(None);
+            }
+            let Some(split_metadata) = self.split_metadatas.front().cloned() else {
+                return Ok(None);
+            };
+            if self.buffered_partial_hits.is_empty() {
+                if self.is_split_exhausted {
+                    self.next_split();
+                } else {
+                    self.search_split(&split_metadata).await?;
+                }
+                continue;
+            }
+            let num_hits = self
+                .page_size
+                .min(self.num_remaining_docs)
+                .min(self.buffered_partial_hits.len() as u64) as usize;
+            let partial_hits: Vec<PartialHit> =
+                self.buffered_partial_hits.drain(..num_hits).collect();
+            self.search_after_opt = partial_hits.last().cloned();
+
+            let hits = fetch_docs_phase(
+                &self.indexes_metas_for_leaf_search,
+                &partial_hits,
+                std::slice::from_ref(&split_metadata),
+                &self.search_request,
+                &self.cluster_client,
+            )
+            .await?;
+            self.num_streamed_docs += hits.len() as u64;
+            self.num_remaining_docs -= hits.len() as u64;
+
+            // The cursor of the last page of a split points to the next split.
+            if self.buffered_partial_hits.is_empty() && self.is_split_exhausted {
+                self.next_split();
+            }
+            let num_bytes: usize = hits.iter().map(|hit| hit.json.len() + 1).sum();
+            let mut buffer = Vec::with_capacity(num_bytes);
+
+            for hit in hits {
+                buffer.extend_from_slice(hit.json.as_bytes());
+                buffer.push(b'\n');
+            }
+            let hits_stream_page = HitsStreamPage {
+                ndjson: Bytes::from(buffer),
+                cursor: self.cursor().to_string(),
+            };
+            return Ok(Some((hits_stream_page, self)));
+        }
+    }
+
+    /// Collects the next hits of the split past its cursor into the buffer.
+    async fn search_split(&mut self, split_metadata: &SplitMetadata) -> crate::Result<()> {
+        let max_hits = self.search_request.max_hits.min(self.num_remaining_docs);
+        let split_search_request = SearchRequest {
+            max_hits,
+            search_after: self.search_after_opt.clone(),
+            ..self.search_request.clone()
+        };
+        let leaf_search_response = search_partial_hits_phase(
+            &self.searcher_context,
+            &self.indexes_metas_for_leaf_search,
+            &split_search_request,
+            std::slice::from_ref(split_metadata),
+            None,
+            &self.cluster_client,
+        )
+        .await?;
+        // Skipping a split would silently produce an incomplete export.
+        if let Some(failed_split) = leaf_search_response.failed_splits.first() {
+            return Err(SearchError::Internal(format!(
+                "failed to search split `{}`: {}",
+                failed_split.split_id, failed_split.error
+            )));
+        }
+        let partial_hits = leaf_search_response.partial_hits;
+        self.is_split_exhausted = (partial_hits.len() as u64) < max_hits;
+        self.buffered_partial_hits = partial_hits.into();
+        Ok(())
+    }
+
+    fn next_split(&mut self) {
+        self.split_metadatas.pop_front();
+        self.search_after_opt = None;
+        self.buffered_partial_hits.clear();
+        self.is_split_exhausted = false;
+    }
+
+    fn cursor(&self) -> HitsStreamCursor {
+        HitsStreamCursor {
+            request_fingerprint: self.request_fingerprint,
+            split_set_fingerprint: self.split_set_fingerprint,
+            split_ord: self.num_splits - self.split_metadatas.len(),
+            search_after_opt: self.search_after_opt.clone(),
+            num_streamed_docs: self.num_streamed_docs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use futures::stream::BoxStream;
+    use futures::{StreamExt, TryStreamExt};
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_query::query_ast::qast_json_helper;
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+    use crate::{SearchJobPlacer, SearchServiceClient, SearchServiceImpl, SearcherPool};
+
+    /// A searcher node, whose state is lost when it is dropped.
+    struct TestSearcher {
+        searcher_context: Arc<SearcherContext>,
+        cluster_client: ClusterClient,
+    }
+
+    impl TestSearcher {
+        fn start(test_sandbox: &TestSandbox) -> Self {
+            let socket_addr = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 7280u16);
+            let searcher_pool = SearcherPool::default();
+            let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool.clone()));
+            let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+            let search_service = Arc::new(SearchServiceImpl::new(
+                test_sandbox.metastore(),
+                test_sandbox.storage_resolver(),
+                cluster_client.clone(),
+                searcher_context.clone(),
+            ));
+            searcher_pool.insert(
+                socket_addr,
+                SearchServiceClient::from_service(search_service, socket_addr),
+            );
+            Self {
+                searcher_context,
+                cluster_client,
+            }
+        }
+
+        async fn hits_stream(
+            &self,
+            test_sandbox: &TestSandbox,
+            search_request: SearchRequest,
+            cursor_opt: Option<String>,
+            page_size: u64,
+        ) -> crate::Result<BoxStream<'static, crate::Result<HitsStreamPage>>> {
+            // Splits are searched two pages at a time, so that the tests page through the
+            // buffered hits and search splits several times.
+            let hits_stream = root_search_hits_stream_with_page_size(
+                self.searcher_context.clone(),
+                search_request,
+                cursor_opt,
+                test_sandbox.metastore(),
+                self.cluster_client.clone(),
+                page_size,
+                page_size * 2,
+            )
+            .await?;
+            Ok(hits_stream.boxed())
+        }
+    }
+
+    fn parse_docs(pages: &[HitsStreamPage]) -> Vec<JsonValue> {
+        pages
+            .iter()
+            .flat_map(|page| page.ndjson.split(|byte| *byte == b'\n'))
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    }
+
+    async fn collect_hits_stream(
+        test_sandbox: &TestSandbox,
+        search_request: SearchRequest,
+        page_size: u64,
+    ) -> crate::Result<Vec<JsonValue>> {
+        let pages: Vec<HitsStreamPage> = TestSearcher::start(test_sandbox)
+            .hits_stream(test_sandbox, search_request, None, page_size)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(parse_docs(&pages))
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_exceeds_max_hits_limit() {
+        let index_id = "hits-stream-exceeds-max-hits-limit";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: split_ord
+                type: u64
+              - name: doc_ord
+                type: u64
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[])
+            .await
+            .unwrap();
+        let num_splits = 2;
+        let num_docs_per_split = 6_000;
+
+        for split_ord in 0..num_splits {
+            let docs = (0..num_docs_per_split)
+                .map(|doc_ord| json!({"split_ord": split_ord, "doc_ord": doc_ord}))
+                .collect();
+            test_sandbox.add_documents(docs).await.unwrap();
+        }
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            ..Default::default()
+        };
+        let docs = collect_hits_stream(&test_sandbox, search_request, HITS_STREAM_PAGE_SIZE)
+            .await
+            .unwrap();
+        // A regular search cannot return more than 10_000 hits.
+        assert_eq!(docs.len(), num_splits * num_docs_per_split);
+
+        // Documents are grouped by split and follow the split's natural order: the most recently
+        // indexed document first.
+        for split_docs in docs.chunks(num_docs_per_split) {
+            let split_ord = &split_docs[0]["split_ord"];
+
+            for (doc, expected_doc_ord) in split_docs.iter().zip((0..num_docs_per_split).rev()) {
+                assert_eq!(&doc["split_ord"], split_ord);
+                assert_eq!(doc["doc_ord"], expected_doc_ord);
+            }
+        }
+        assert_ne!(docs[0]["split_ord"], docs[num_docs_per_split]["split_ord"]);
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_max_hits_and_fields() {
+        let index_id = "hits-stream-max-hits-and-fields";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        for split_ord in 0..3 {
+            let docs = (0..5)
+                .map(|doc_ord| {
+                    json!({"title": format!("title-{split_ord}-{doc_ord}"), "body": "beagle"})
+                })
+                .collect();
+            test_sandbox.add_documents(docs).await.unwrap();
+        }
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("beagle", &["body"]),
+            max_hits: 12,
+            fields: vec!["title".to_string()],
+            ..Default::default()
+        };
+        let docs = collect_hits_stream(&test_sandbox, search_request.clone(), 2)
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 12);
+
+        for doc in &docs {
+            let doc_object = doc.as_object().unwrap();
+            assert_eq!(doc_object.len(), 1);
+            assert!(doc_object.contains_key("title"));
+        }
+        let search_request_no_cap = SearchRequest {
+            max_hits: 0,
+            ..search_request
+        };
+        let docs = collect_hits_stream(&test_sandbox, search_request_no_cap, 2)
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 15);
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_rejects_sort() {
+        let index_id = "hits-stream-rejects-sort";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            sort_fields: vec![quickwit_proto::search::SortField {
+                field_name: "body".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let error = collect_hits_stream(&test_sandbox, search_request, 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+        test_sandbox.assert_quit().await;
+    }
+
+    async fn create_test_sandbox_with_splits(
+        index_id: &str,
+        num_splits: u64,
+        num_docs_per_split: u64,
+    ) -> TestSandbox {
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: split_ord
+                type: u64
+              - name: doc_ord
+                type: u64
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[])
+            .await
+            .unwrap();
+        for split_ord in 0..num_splits {
+            let docs = (0..num_docs_per_split)
+                .map(|doc_ord| json!({"split_ord": split_ord, "doc_ord": doc_ord}))
+                .collect();
+            test_sandbox.add_documents(docs).await.unwrap();
+        }
+        test_sandbox
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_resume_from_cursor() {
+        let index_id = "hits-stream-resume-from-cursor";
+        let test_sandbox = create_test_sandbox_with_splits(index_id, 2, 5).await;
+
+        for max_hits in [0, 7] {
+            let search_request = SearchRequest {
+                index_id_patterns: vec![index_id.to_string()],
+                query_ast: qast_json_helper("*", &[]),
+                max_hits,
+                ..Default::default()
+            };
+            let expected_pages: Vec<HitsStreamPage> = TestSearcher::start(&test_sandbox)
+                .hits_stream(&test_sandbox, search_request.clone(), None, 2)
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+            let expected_docs = parse_docs(&expected_pages);
+
+            // The searcher is killed after each number of pages, including in the middle of a
+            // split and at the boundary between two splits, and another searcher resumes the
+            // stream.
+            for num_pages_before_kill in 1..=expected_pages.len() {
+                let searcher = TestSearcher::start(&test_sandbox);
+                let pages_before_kill: Vec<HitsStreamPage> = searcher
+                    .hits_stream(&test_sandbox, search_request.clone(), None, 2)
+                    .await
+                    .unwrap()
+                    .take(num_pages_before_kill)
+                    .try_collect()
+                    .await
+                    .unwrap();
+                drop(searcher);
+
+                let cursor = pages_before_kill.last().unwrap().cursor.clone();
+                let pages_after_resume: Vec<HitsStreamPage> = TestSearcher::start(&test_sandbox)
+                    .hits_stream(&test_sandbox, search_request.clone(), Some(cursor), 2)
+                    .await
+                    .unwrap()
+                    .try_collect()
+                    .await
+                    .unwrap();
+
+                let mut docs = parse_docs(&pages_before_kill);
+                docs.extend(parse_docs(&pages_after_resume));
+                assert_eq!(
+                    docs, expected_docs,
+                    "stream resumed after {num_pages_before_kill} page(s) with max hits \
+                     {max_hits} has duplicate or missing documents"
+                );
+            }
+        }
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_rejects_mismatching_cursor() {
+        let index_id = "hits-stream-rejects-mismatching-cursor";
+        let test_sandbox = create_test_sandbox_with_splits(index_id, 2, 5).await;
+        let searcher = TestSearcher::start(&test_sandbox);
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            ..Default::default()
+        };
+        let first_page = searcher
+            .hits_stream(&test_sandbox, search_request.clone(), None, 2)
+            .await
+            .unwrap()
+            .try_next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        let other_search_request = SearchRequest {
+            query_ast: qast_json_helper("doc_ord:1", &[]),
+            ..search_request.clone()
+        };
+        let Err(error) = searcher
+            .hits_stream(
+                &test_sandbox,
+                other_search_request,
+                Some(first_page.cursor.clone()),
+                2,
+            )
+            .await
+        else {
+            panic!("cursor issued for another request should be rejected");
+        };
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let Err(error) = searcher
+            .hits_stream(
+                &test_sandbox,
+                search_request.clone(),
+                Some("corrupted-cursor".to_string()),
+                2,
+            )
+            .await
+        else {
+            panic!("corrupted cursor should be rejected");
+        };
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        // A new split changes the set of splits targeted by the request.
+        test_sandbox
+            .add_documents(vec![json!({"split_ord": 2, "doc_ord": 0})])
+            .await
+            .unwrap();
+        let Err(error) = searcher
+            .hits_stream(&test_sandbox, search_request, Some(first_page.cursor), 2)
+            .await
+        else {
+            panic!("cursor issued for another split set should be rejected");
+        };
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_root_search_hits_stream_resume_from_cursor_with_point_in_time() {
+        let index_id = "hits-stream-resume-from-cursor-with-pit";
+        let test_sandbox = create_test_sandbox_with_splits(index_id, 2, 5).await;
+        let searcher = TestSearcher::start(&test_sandbox);
+
+        let open_pit_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            pit_keep_alive_secs: Some(60),
+            ..Default::default()
+        };
+        let pit_id = crate::root_search(
+            &searcher.searcher_context,
+            open_pit_request,
+            test_sandbox.metastore(),
+            &searcher.cluster_client,
+        )
+        .await
+        .unwrap()
+        .pit_id
+        .unwrap();
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            pit_id: Some(pit_id),
+            ..Default::default()
+        };
+        let first_page = searcher
+            .hits_stream(&test_sandbox, search_request.clone(), None, 2)
+            .await
+            .unwrap()
+            .try_next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The splits published after the point in time was opened are not streamed.
+        test_sandbox
+            .add_documents(vec![json!({"split_ord": 2, "doc_ord": 0})])
+            .await
+            .unwrap();
+        let pages: Vec<HitsStreamPage> = searcher
+            .hits_stream(
+                &test_sandbox,
+                search_request,
+                Some(first_page.cursor.clone()),
+                2,
+            )
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let mut docs = parse_docs(&[first_page]);
+        docs.extend(parse_docs(&pages));
+        assert_eq!(docs.len(), 10);
+        assert!(docs.iter().all(|doc| doc["split_ord"] != 2));
+        test_sandbox.assert_quit().await;
+    }
+}
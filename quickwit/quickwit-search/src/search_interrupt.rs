@@ -0,0 +1,545 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use tantivy::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::{DocId, DocSet, Score, SegmentReader, Term, TERMINATED};
+
+use crate::SearchError;
+
+/// Cancellation flag and deadline shared by the split searches of a leaf search request.
+///
+/// The split searches check it at every collection checkpoint (see [`InterruptibleQuery`]), so
+/// that a request abandoned by the root searcher, or running past its deadline, stops consuming
+/// CPU in the middle of a split instead of running to completion.
+#[derive(Clone, Debug)]
+pub(crate) struct SearchInterrupt {
+    inner: Arc<SearchInterruptInner>,
+}
+
+#[derive(Debug)]
+struct SearchInterruptInner {
+    cancelled: AtomicBool,
+    deadline: Instant,
+}
+
+impl SearchInterrupt {
+    pub fn with_deadline(deadline: Instant) -> Self {
+        let inner = SearchInterruptInner {
+            cancelled: AtomicBool::new(false),
+            deadline,
+        };
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns a guard cancelling the request when dropped, typically when the future serving the
+    /// request is dropped.
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed) || Instant::now() >= self.inner.deadline
+    }
+
+    /// Returns an error if the request was cancelled or ran past its deadline.
+    pub fn check(&self) -> crate::Result<()> {
+        if self.inner.cancelled.load(Ordering::Relaxed) {
+            return Err(SearchError::Internal(
+                "leaf search request was cancelled".to_string(),
+            ));
+        }
+        if Instant::now() >= self.inner.deadline {
+            return Err(SearchError::Timeout(
+                "leaf search request exceeded its deadline".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Cancels a [`SearchInterrupt`] on drop.
+pub(crate) struct CancelOnDrop(SearchInterrupt);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// A tantivy query whose scorers stop matching documents once the request is interrupted.
+///
+/// Every `checkpoint_num_docs` documents, the scorers check the [`SearchInterrupt`] of the
+/// request and, if `yield_at_checkpoints` is set, let the current search thread run the pending
+/// split searches. Once interrupted, the scorers behave as if the segment had no more matching
+/// documents, and no more documents are collected: the results collected so far are partial and
+/// must be discarded by the caller.
+///
+/// Counting is delegated to the wrapped query, which often answers without scanning the
+/// documents, so it is only interrupted between segments.
+#[derive(Debug)]
+pub(crate) struct InterruptibleQuery {
+    query: Box<dyn Query>,
+    checkpoints: CollectionCheckpoints,
+}
+
+impl Clone for InterruptibleQuery {
+    fn clone(&self) -> Self {
+        Self {
+            query: self.query.box_clone(),
+            checkpoints: self.checkpoints.clone(),
+        }
+    }
+}
+
+impl InterruptibleQuery {
+    pub fn new(
+        query: Box<dyn Query>,
+        search_interrupt: SearchInterrupt,
+        checkpoint_num_docs: NonZeroU32,
+        yield_at_checkpoints: bool,
+    ) -> Self {
+        let checkpoints = CollectionCheckpoints {
+            search_interrupt,
+            checkpoint_num_docs,
+            yield_at_checkpoints,
+            num_docs_scanned: Arc::new(AtomicU64::new(0)),
+        };
+        Self { query, checkpoints }
+    }
+
+    /// Returns the number of documents scanned by the scorers of the query so far.
+    pub fn num_docs_scanned(&self) -> u64 {
+        self.checkpoints.num_docs_scanned.load(Ordering::Relaxed)
+    }
+}
+
+impl Query for InterruptibleQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let weight = self.query.weight(enable_scoring)?;
+        Ok(Box::new(InterruptibleWeight {
+            weight,
+            checkpoints: self.checkpoints.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CollectionCheckpoints {
+    search_interrupt: SearchInterrupt,
+    checkpoint_num_docs: NonZeroU32,
+    yield_at_checkpoints: bool,
+    num_docs_scanned: Arc<AtomicU64>,
+}
+
+impl CollectionCheckpoints {
+    /// Returns whether the scan must stop.
+    fn checkpoint(&self) -> bool {
+        if self.search_interrupt.is_interrupted() {
+            return true;
+        }
+        if self.yield_at_checkpoints {
+            // Runs a pending job of the thread pool, if any, on the current thread.
+            rayon::yield_now();
+        }
+        false
+    }
+}
+
+struct InterruptibleWeight {
+    weight: Box<dyn Weight>,
+    checkpoints: CollectionCheckpoints,
+}
+
+// The `for_each` methods are forwarded to the wrapped weight, which often implements them more
+// efficiently than by iterating over its scorer. Their callbacks cannot stop the scan: once
+// interrupted, the remaining documents of the segment are no longer collected, and pruning
+// collectors are given a threshold no document can reach.
+impl Weight for InterruptibleWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let scorer = self.weight.scorer(reader, boost)?;
+        Ok(Box::new(InterruptibleScorer {
+            scorer,
+            scan_progress: ScanProgress::new(self.checkpoints.clone()),
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> tantivy::Result<u32> {
+        if self.checkpoints.checkpoint() {
+            return Ok(0);
+        }
+        self.weight.count(reader)
+    }
+
+    fn for_each(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score),
+    ) -> tantivy::Result<()> {
+        let mut scan_progress = ScanProgress::new(self.checkpoints.clone());
+        self.weight.for_each(reader, &mut |doc, score| {
+            if scan_progress.interrupted {
+                return;
+            }
+            callback(doc, score);
+            scan_progress.record_doc();
+        })
+    }
+
+    fn for_each_no_score(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(&[DocId]),
+    ) -> tantivy::Result<()> {
+        let mut scan_progress = ScanProgress::new(self.checkpoints.clone());
+        self.weight.for_each_no_score(reader, &mut |docs| {
+            let mut num_docs = 0;
+
+            while num_docs < docs.len() && !scan_progress.interrupted {
+                num_docs += 1;
+                scan_progress.record_doc();
+            }
+            if num_docs > 0 {
+                callback(&docs[..num_docs]);
+            }
+        })
+    }
+
+    fn for_each_pruning(
+        &self,
+        threshold: Score,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score) -> Score,
+    ) -> tantivy::Result<()> {
+        let mut scan_progress = ScanProgress::new(self.checkpoints.clone());
+        self.weight
+            .for_each_pruning(threshold, reader, &mut |doc, score| {
+                if scan_progress.interrupted {
+                    return Score::MAX;
+                }
+                let threshold = callback(doc, score);
+
+                if scan_progress.record_doc() {
+                    return Score::MAX;
+                }
+                threshold
+            })
+    }
+}
+
+/// Counts the documents scanned in a segment, checking the [`SearchInterrupt`] every
+/// `checkpoint_num_docs` documents.
+struct ScanProgress {
+    checkpoints: CollectionCheckpoints,
+    num_docs_until_checkpoint: u32,
+    num_docs_scanned: u64,
+    interrupted: bool,
+}
+
+impl ScanProgress {
+    fn new(checkpoints: CollectionCheckpoints) -> Self {
+        Self {
+            num_docs_until_checkpoint: checkpoints.checkpoint_num_docs.get(),
+            checkpoints,
+            num_docs_scanned: 0,
+            interrupted: false,
+        }
+    }
+
+    /// Records a scanned document, and returns whether the scan must stop after it.
+    fn record_doc(&mut self) -> bool {
+        self.num_docs_scanned += 1;
+        self.num_docs_until_checkpoint -= 1;
+
+        if self.num_docs_until_checkpoint == 0 {
+            self.num_docs_until_checkpoint = self.checkpoints.checkpoint_num_docs.get();
+            self.flush_num_docs_scanned();
+
+            if self.checkpoints.checkpoint() {
+                self.interrupted = true;
+            }
+        }
+        self.interrupted
+    }
+
+    fn flush_num_docs_scanned(&mut self) {
+        self.checkpoints
+            .num_docs_scanned
+            .fetch_add(self.num_docs_scanned, Ordering::Relaxed);
+        self.num_docs_scanned = 0;
+    }
+}
+
+impl Drop for ScanProgress {
+    fn drop(&mut self) {
+        self.flush_num_docs_scanned();
+    }
+}
+
+struct InterruptibleScorer {
+    scorer: Box<dyn Scorer>,
+    scan_progress: ScanProgress,
+}
+
+impl DocSet for InterruptibleScorer {
+    fn advance(&mut self) -> DocId {
+        if self.scan_progress.interrupted || self.scan_progress.record_doc() {
+            return TERMINATED;
+        }
+        self.scorer.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.scan_progress.interrupted {
+            return TERMINATED;
+        }
+        self.scorer.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        if self.scan_progress.interrupted {
+            return TERMINATED;
+        }
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for InterruptibleScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tantivy::collector::{Collector, SegmentCollector, TopDocs};
+    use tantivy::query::AllQuery;
+    use tantivy::schema::{Schema, INDEXED};
+    use tantivy::{doc, Index, IndexWriter, SegmentOrdinal};
+
+    use super::*;
+
+    const NUM_DOCS: u64 = 100_000;
+
+    fn create_index() -> Index {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for id in 0..NUM_DOCS {
+            index_writer.add_document(doc!(id_field => id)).unwrap();
+        }
+        index_writer.commit().unwrap();
+        index
+    }
+
+    /// Counts the collected documents and cancels the search after `cancel_after_num_docs`.
+    struct CancellingCollector {
+        search_interrupt: SearchInterrupt,
+        cancel_after_num_docs: u64,
+    }
+
+    struct CancellingSegmentCollector {
+        search_interrupt: SearchInterrupt,
+        cancel_after_num_docs: u64,
+        num_docs: u64,
+    }
+
+    impl Collector for CancellingCollector {
+        type Fruit = u64;
+        type Child = CancellingSegmentCollector;
+
+        fn for_segment(
+            &self,
+            _segment_ord: SegmentOrdinal,
+            _segment_reader: &SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            Ok(CancellingSegmentCollector {
+                search_interrupt: self.search_interrupt.clone(),
+                cancel_after_num_docs: self.cancel_after_num_docs,
+                num_docs: 0,
+            })
+        }
+
+        fn requires_scoring(&self) -> bool {
+            false
+        }
+
+        fn merge_fruits(&self, segment_fruits: Vec<u64>) -> tantivy::Result<u64> {
+            Ok(segment_fruits.into_iter().sum())
+        }
+    }
+
+    impl SegmentCollector for CancellingSegmentCollector {
+        type Fruit = u64;
+
+        fn collect(&mut self, _doc: DocId, _score: Score) {
+            self.num_docs += 1;
+
+            if self.num_docs == self.cancel_after_num_docs {
+                self.search_interrupt.cancel();
+            }
+        }
+
+        fn harvest(self) -> u64 {
+            self.num_docs
+        }
+    }
+
+    #[test]
+    fn test_interruptible_query_runs_to_completion() {
+        let index = create_index();
+        let searcher = index.reader().unwrap().searcher();
+        let search_interrupt =
+            SearchInterrupt::with_deadline(Instant::now() + Duration::from_secs(60));
+        let query = InterruptibleQuery::new(
+            Box::new(AllQuery),
+            search_interrupt.clone(),
+            NonZeroU32::new(1_000).unwrap(),
+            true,
+        );
+        let collector = CancellingCollector {
+            search_interrupt: search_interrupt.clone(),
+            cancel_after_num_docs: u64::MAX,
+        };
+        let num_docs = searcher.search(&query, &collector).unwrap();
+        assert_eq!(num_docs, NUM_DOCS);
+        assert_eq!(query.num_docs_scanned(), NUM_DOCS);
+        search_interrupt.check().unwrap();
+
+        let num_docs = query.count(&searcher).unwrap();
+        assert_eq!(num_docs as u64, NUM_DOCS);
+    }
+
+    #[test]
+    fn test_interruptible_query_stops_on_cancellation() {
+        let index = create_index();
+        let searcher = index.reader().unwrap().searcher();
+        let search_interrupt =
+            SearchInterrupt::with_deadline(Instant::now() + Duration::from_secs(60));
+        let query = InterruptibleQuery::new(
+            Box::new(AllQuery),
+            search_interrupt.clone(),
+            NonZeroU32::new(1_000).unwrap(),
+            false,
+        );
+        let collector = CancellingCollector {
+            search_interrupt: search_interrupt.clone(),
+            cancel_after_num_docs: 2_500,
+        };
+        let num_docs = searcher.search(&query, &collector).unwrap();
+        // The scan stops at the first checkpoint following the cancellation.
+        assert_eq!(num_docs, 3_000);
+        assert_eq!(query.num_docs_scanned(), 3_000);
+
+        let error = search_interrupt.check().unwrap_err();
+        assert!(matches!(error, SearchError::Internal(message) if message.contains("cancelled")));
+
+        let num_docs = query.count(&searcher).unwrap();
+        assert_eq!(num_docs, 0);
+    }
+
+    #[test]
+    fn test_interruptible_query_stops_on_deadline() {
+        let index = create_index();
+        let searcher = index.reader().unwrap().searcher();
+        let search_interrupt = SearchInterrupt::with_deadline(Instant::now());
+        let query = InterruptibleQuery::new(
+            Box::new(AllQuery),
+            search_interrupt.clone(),
+            NonZeroU32::new(1_000).unwrap(),
+            false,
+        );
+        let collector = CancellingCollector {
+            search_interrupt: search_interrupt.clone(),
+            cancel_after_num_docs: u64::MAX,
+        };
+        let num_docs = searcher.search(&query, &collector).unwrap();
+        assert_eq!(num_docs, 1_000);
+        assert_eq!(query.num_docs_scanned(), 1_000);
+
+        let error = search_interrupt.check().unwrap_err();
+        assert!(matches!(error, SearchError::Timeout(_)));
+    }
+
+    #[test]
+    fn test_interruptible_query_forwards_pruning() {
+        let index = create_index();
+        let searcher = index.reader().unwrap().searcher();
+        let search_interrupt =
+            SearchInterrupt::with_deadline(Instant::now() + Duration::from_secs(60));
+        let query = InterruptibleQuery::new(
+            Box::new(AllQuery),
+            search_interrupt,
+            NonZeroU32::new(1_000).unwrap(),
+            false,
+        );
+        // Collecting the top docs by score goes through `for_each_pruning`.
+        let top_docs = TopDocs::with_limit(10);
+        let expected_top_docs = searcher.search(&AllQuery, &top_docs).unwrap();
+        let top_docs = searcher.search(&query, &top_docs).unwrap();
+        assert_eq!(top_docs, expected_top_docs);
+
+        // Once interrupted, the collector is given a threshold no document can reach.
+        let search_interrupt = SearchInterrupt::with_deadline(Instant::now());
+        let query = InterruptibleQuery::new(
+            Box::new(AllQuery),
+            search_interrupt,
+            NonZeroU32::new(5).unwrap(),
+            false,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10)).unwrap();
+        assert_eq!(top_docs.len(), 5);
+        assert_eq!(query.num_docs_scanned(), 5);
+    }
+
+    #[test]
+    fn test_cancel_on_drop() {
+        let search_interrupt =
+            SearchInterrupt::with_deadline(Instant::now() + Duration::from_secs(60));
+        let cancel_on_drop = search_interrupt.cancel_on_drop();
+        assert!(!search_interrupt.is_interrupted());
+        drop(cancel_on_drop);
+        assert!(search_interrupt.is_interrupted());
+    }
+}
@@ -22,12 +22,15 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::bail;
 use async_trait::async_trait;
 use quickwit_common::pubsub::EventSubscriber;
 use quickwit_common::rendezvous_hasher::{node_affinity, sort_by_rendez_vous_hash};
 use quickwit_proto::search::{ReportSplit, ReportSplitsRequest};
+use tokio::time::Instant;
 use tracing::warn;
 
 use crate::{SearchJob, SearchServiceClient, SearcherPool, SEARCH_METRICS};
@@ -56,12 +59,18 @@ pub trait Job {
     }
 }
 
+/// Period during which a searcher that rejected a leaf search request because it was overloaded
+/// is only assigned jobs when the other searchers are loaded.
+const SHEDDING_NODE_DEPRIORITIZATION_PERIOD: Duration = Duration::from_secs(10);
+
 /// Search job placer.
 /// It assigns jobs to search clients.
 #[derive(Clone, Default)]
 pub struct SearchJobPlacer {
     /// Search clients pool.
     searcher_pool: SearcherPool,
+    /// Searchers that recently shed load, with the instant of their last rejection.
+    shedding_nodes: Arc<Mutex<HashMap<SocketAddr, Instant>>>,
 }
 
 #[async_trait]
@@ -104,7 +113,28 @@ impl fmt::Debug for SearchJobPlacer {
 impl SearchJobPlacer {
     /// Returns an [`SearchJobPlacer`] from a search service client pool.
     pub fn new(searcher_pool: SearcherPool) -> Self {
-        Self { searcher_pool }
+        Self {
+            searcher_pool,
+            shedding_nodes: Arc::default(),
+        }
+    }
+
+    /// Records that the searcher listening on `grpc_addr` rejected a request because it was
+    /// overloaded. The searcher is deprioritized for `SHEDDING_NODE_DEPRIORITIZATION_PERIOD`.
+    pub(crate) fn record_shedding_node(&self, grpc_addr: SocketAddr) {
+        self.shedding_nodes
+            .lock()
+            .unwrap()
+            .insert(grpc_addr, Instant::now());
+    }
+
+    fn recently_shedding_nodes(&self) -> HashSet<SocketAddr> {
+        let now = Instant::now();
+        let mut shedding_nodes = self.shedding_nodes.lock().unwrap();
+        shedding_nodes.retain(|_, shed_at| {
+            now.saturating_duration_since(*shed_at) < SHEDDING_NODE_DEPRIORITIZATION_PERIOD
+        });
+        shedding_nodes.keys().copied().collect()
     }
 }
 
@@ -145,12 +175,16 @@ impl SearchJobPlacer {
     /// Returns a list of pair (SocketAddr, `Vec<Job>`)
     ///
     /// When exclude_addresses filters all clients it is ignored.
+    ///
+    /// Searchers that recently shed load are only assigned jobs once the other searchers are
+    /// loaded.
     pub async fn assign_jobs<J: Job>(
         &self,
         mut jobs: Vec<J>,
         excluded_addrs: &HashSet<SocketAddr>,
     ) -> anyhow::Result<impl Iterator<Item = (SearchServiceClient, Vec<J>)>> {
         let num_nodes = self.searcher_pool.len();
+        let shedding_addrs = self.recently_shedding_nodes();
 
         let mut candidate_nodes: Vec<CandidateNodes> = self
             .searcher_pool
@@ -165,6 +199,7 @@ impl SearchJobPlacer {
                 grpc_addr,
                 client,
                 load: 0,
+                shedding: shedding_addrs.contains(&grpc_addr),
             })
             .collect();
 
@@ -188,9 +223,18 @@ impl SearchJobPlacer {
         // or modify mock_split_meta() so that not all splits have the same job cost
         // for now i went with the mock_split_meta() changes.
         const ALLOWED_DIFFERENCE: usize = 105;
-        let target_load = (total_load * ALLOWED_DIFFERENCE).div_ceil(num_nodes * 100);
+        // The load is spread over the searchers that are not shedding, unless they all are.
+        let num_shedding_nodes = candidate_nodes.iter().filter(|node| node.shedding).count();
+        let num_target_nodes = if num_shedding_nodes < candidate_nodes.len() {
+            num_nodes - num_shedding_nodes
+        } else {
+            num_nodes
+        };
+        let target_load = (total_load * ALLOWED_DIFFERENCE).div_ceil(num_target_nodes * 100);
         for job in jobs {
             sort_by_rendez_vous_hash(&mut candidate_nodes, job.split_id());
+            // The sort is stable: the searchers keep their affinity order within each group.
+            candidate_nodes.sort_by_key(|node| node.shedding);
 
             let (chosen_node_idx, chosen_node) = if let Some((idx, node)) = candidate_nodes
                 .iter_mut()
@@ -243,6 +287,7 @@ struct CandidateNodes {
     pub grpc_addr: SocketAddr,
     pub client: SearchServiceClient,
     pub load: usize,
+    pub shedding: bool,
 }
 
 impl Hash for CandidateNodes {
@@ -492,4 +537,47 @@ mod tests {
             assert!(job_len <= 1050 / 5);
         }
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_job_placer_deprioritizes_shedding_nodes() {
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", MockSearchService::new()),
+            ("127.0.0.1:1002", MockSearchService::new()),
+            ("127.0.0.1:1003", MockSearchService::new()),
+        ]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let shedding_addr: SocketAddr = ([127, 0, 0, 1], 1002).into();
+        search_job_placer.record_shedding_node(shedding_addr);
+
+        let jobs: Vec<SearchJob> = (0..100)
+            .map(|id| SearchJob::for_test(&format!("split{id}"), 1))
+            .collect();
+        let assigned_addrs: Vec<SocketAddr> = search_job_placer
+            .assign_jobs(jobs.clone(), &HashSet::default())
+            .await
+            .unwrap()
+            .map(|(client, _)| client.grpc_addr())
+            .collect();
+        assert_eq!(assigned_addrs.len(), 2);
+        assert!(!assigned_addrs.contains(&shedding_addr));
+
+        // A single shedding searcher is still assigned the jobs.
+        let excluded_addrs: HashSet<SocketAddr> =
+            HashSet::from_iter([([127, 0, 0, 1], 1001).into(), ([127, 0, 0, 1], 1003).into()]);
+        let client = search_job_placer
+            .assign_job(SearchJob::for_test("split0", 1), &excluded_addrs)
+            .await
+            .unwrap();
+        assert_eq!(client.grpc_addr(), shedding_addr);
+
+        // The searcher is assigned jobs again once the deprioritization period is over.
+        tokio::time::advance(SHEDDING_NODE_DEPRIORITIZATION_PERIOD).await;
+        let assigned_addrs: Vec<SocketAddr> = search_job_placer
+            .assign_jobs(jobs, &HashSet::default())
+            .await
+            .unwrap()
+            .map(|(client, _)| client.grpc_addr())
+            .collect();
+        assert_eq!(assigned_addrs.len(), 3);
+    }
 }
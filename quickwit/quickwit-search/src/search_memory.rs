@@ -0,0 +1,384 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bytesize::ByteSize;
+use tracing::warn;
+
+use crate::metrics::SEARCH_METRICS;
+
+/// Node-wide pool of memory shared by the leaf search requests running on a searcher.
+///
+/// Every leaf request draws its memory from the pool through a [`SearchMemoryBudget`]. The
+/// memory is charged as the collectors allocate, and is returned to the pool when the request
+/// completes.
+#[derive(Clone)]
+pub struct SearchMemoryPool {
+    inner: Arc<SearchMemoryPoolInner>,
+}
+
+struct SearchMemoryPoolInner {
+    capacity: u64,
+    num_bytes_used: AtomicU64,
+}
+
+impl SearchMemoryPool {
+    pub fn new(capacity: ByteSize) -> Self {
+        Self {
+            inner: Arc::new(SearchMemoryPoolInner {
+                capacity: capacity.as_u64(),
+                num_bytes_used: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes currently charged to the pool.
+    pub fn num_bytes_used(&self) -> u64 {
+        self.inner.num_bytes_used.load(Ordering::Acquire)
+    }
+
+    /// Returns the capacity of the pool in bytes.
+    pub fn capacity(&self) -> u64 {
+        self.inner.capacity
+    }
+
+    /// Creates the memory budget of a new leaf request. The budget is not reserved up front: it
+    /// only caps the amount of memory the request may draw from the pool.
+    pub fn new_request_budget(&self, request_budget: ByteSize) -> SearchMemoryBudget {
+        SearchMemoryBudget::new(Some(self.clone()), request_budget.as_u64())
+    }
+
+    fn try_charge(&self, num_bytes: u64) -> Result<(), MemoryLimitExceeded> {
+        let capacity = self.inner.capacity;
+        self.inner
+            .num_bytes_used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num_bytes_used| {
+                num_bytes_used
+                    .checked_add(num_bytes)
+                    .filter(|new_num_bytes_used| *new_num_bytes_used <= capacity)
+            })
+            .map_err(|num_bytes_used| MemoryLimitExceeded {
+                limit: MemoryLimit::SearcherPool,
+                limit_num_bytes: capacity,
+                num_bytes_used,
+                num_bytes_requested: num_bytes,
+            })?;
+        SEARCH_METRICS
+            .search_memory_pool_in_use_bytes
+            .add(num_bytes as i64);
+        Ok(())
+    }
+
+    fn release(&self, num_bytes: u64) {
+        self.inner
+            .num_bytes_used
+            .fetch_sub(num_bytes, Ordering::AcqRel);
+        SEARCH_METRICS
+            .search_memory_pool_in_use_bytes
+            .sub(num_bytes as i64);
+    }
+}
+
+/// The limit a leaf request ran into when it failed to allocate memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryLimit {
+    /// The budget of the leaf request (`searcher.leaf_request_memory_budget`).
+    LeafRequestBudget,
+    /// The node-wide pool (`searcher.search_memory_pool_size`).
+    SearcherPool,
+}
+
+impl MemoryLimit {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MemoryLimit::LeafRequestBudget => "leaf_request_budget",
+            MemoryLimit::SearcherPool => "searcher_pool",
+        }
+    }
+}
+
+/// Error returned when a leaf request fails to charge an allocation to its memory budget.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoryLimitExceeded {
+    pub limit: MemoryLimit,
+    pub limit_num_bytes: u64,
+    pub num_bytes_used: u64,
+    pub num_bytes_requested: u64,
+}
+
+impl fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let limit_name = match self.limit {
+            MemoryLimit::LeafRequestBudget => "leaf request memory budget",
+            MemoryLimit::SearcherPool => "searcher memory pool",
+        };
+        write!(
+            f,
+            "failed to allocate {}: {limit_name} of {} exhausted ({} in use)",
+            ByteSize(self.num_bytes_requested),
+            ByteSize(self.limit_num_bytes),
+            ByteSize(self.num_bytes_used),
+        )
+    }
+}
+
+/// Memory budget of a single leaf request, drawn from the [`SearchMemoryPool`] of the searcher.
+///
+/// Clones share the same budget. The memory charged to the budget is returned to the pool when
+/// the last clone is dropped.
+#[derive(Clone)]
+pub struct SearchMemoryBudget {
+    inner: Arc<SearchMemoryBudgetInner>,
+}
+
+struct SearchMemoryBudgetInner {
+    pool_opt: Option<SearchMemoryPool>,
+    limit_num_bytes: u64,
+    num_bytes_used: AtomicU64,
+}
+
+impl Default for SearchMemoryBudget {
+    /// Returns a budget that is not attached to any pool and never runs out of memory.
+    fn default() -> Self {
+        Self::new(None, u64::MAX)
+    }
+}
+
+impl SearchMemoryBudget {
+    fn new(pool_opt: Option<SearchMemoryPool>, limit_num_bytes: u64) -> Self {
+        Self {
+            inner: Arc::new(SearchMemoryBudgetInner {
+                pool_opt,
+                limit_num_bytes,
+                num_bytes_used: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes currently charged to the budget.
+    pub fn num_bytes_used(&self) -> u64 {
+        self.inner.num_bytes_used.load(Ordering::Acquire)
+    }
+
+    /// Charges an allocation of `num_bytes` to the budget and to the pool it is drawn from. The
+    /// allocation must be charged before it is made.
+    ///
+    /// On failure, nothing is charged.
+    pub fn try_charge(&self, num_bytes: u64) -> Result<(), MemoryLimitExceeded> {
+        self.try_charge_inner(num_bytes)
+            .inspect_err(|limit_exceeded| {
+                warn!(limit=%limit_exceeded, "aborting leaf search: memory limit exceeded");
+                SEARCH_METRICS
+                    .search_memory_limit_exceeded_total
+                    .with_label_values([limit_exceeded.limit.as_str()])
+                    .inc();
+            })
+    }
+
+    fn try_charge_inner(&self, num_bytes: u64) -> Result<(), MemoryLimitExceeded> {
+        let limit_num_bytes = self.inner.limit_num_bytes;
+        self.inner
+            .num_bytes_used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |num_bytes_used| {
+                num_bytes_used
+                    .checked_add(num_bytes)
+                    .filter(|new_num_bytes_used| *new_num_bytes_used <= limit_num_bytes)
+            })
+            .map_err(|num_bytes_used| MemoryLimitExceeded {
+                limit: MemoryLimit::LeafRequestBudget,
+                limit_num_bytes,
+                num_bytes_used,
+                num_bytes_requested: num_bytes,
+            })?;
+        if let Some(pool) = &self.inner.pool_opt {
+            if let Err(limit_exceeded) = pool.try_charge(num_bytes) {
+                self.inner
+                    .num_bytes_used
+                    .fetch_sub(num_bytes, Ordering::AcqRel);
+                return Err(limit_exceeded);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns memory previously charged with [`SearchMemoryBudget::try_charge`].
+    pub fn release(&self, num_bytes: u64) {
+        self.inner
+            .num_bytes_used
+            .fetch_sub(num_bytes, Ordering::AcqRel);
+        if let Some(pool) = &self.inner.pool_opt {
+            pool.release(num_bytes);
+        }
+    }
+
+    /// Returns a handle charging the allocations of a single split search to the budget.
+    pub(crate) fn for_split_search(&self) -> SplitSearchMemoryBudget {
+        SplitSearchMemoryBudget {
+            request_budget: self.clone(),
+            limit_exceeded: Arc::default(),
+        }
+    }
+}
+
+/// Charges the allocations of a split search to the memory budget of its leaf request.
+///
+/// The collectors can only abort the search with an opaque tantivy error when an allocation
+/// fails, so the failure is recorded for the leaf to report its cause. Since the budget of the
+/// request is shared by its split searches, the failure is recorded per split search: the other
+/// splits of the request may fail for unrelated reasons.
+#[derive(Clone, Default)]
+pub(crate) struct SplitSearchMemoryBudget {
+    request_budget: SearchMemoryBudget,
+    limit_exceeded: Arc<Mutex<Option<MemoryLimitExceeded>>>,
+}
+
+impl SplitSearchMemoryBudget {
+    /// Charges an allocation of `num_bytes` to the budget of the request, recording the failure
+    /// of the split search if it does not fit.
+    pub fn try_charge(&self, num_bytes: u64) -> Result<(), MemoryLimitExceeded> {
+        self.request_budget
+            .try_charge(num_bytes)
+            .inspect_err(|limit_exceeded| {
+                self.limit_exceeded
+                    .lock()
+                    .unwrap()
+                    .get_or_insert_with(|| limit_exceeded.clone());
+            })
+    }
+
+    /// Returns memory previously charged with [`SplitSearchMemoryBudget::try_charge`].
+    pub fn release(&self, num_bytes: u64) {
+        self.request_budget.release(num_bytes);
+    }
+
+    /// Returns the first allocation failure of the split search, if any.
+    pub fn limit_exceeded(&self) -> Option<MemoryLimitExceeded> {
+        self.limit_exceeded.lock().unwrap().clone()
+    }
+}
+
+impl Drop for SearchMemoryBudgetInner {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool_opt {
+            pool.release(*self.num_bytes_used.get_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_memory_budget_charges_request_and_pool() {
+        let pool = SearchMemoryPool::new(ByteSize::kb(10));
+        let budget = pool.new_request_budget(ByteSize::kb(4));
+
+        budget.try_charge(3_000).unwrap();
+        assert_eq!(budget.num_bytes_used(), 3_000);
+        assert_eq!(pool.num_bytes_used(), 3_000);
+
+        let limit_exceeded = budget.try_charge(2_000).unwrap_err();
+        assert_eq!(limit_exceeded.limit, MemoryLimit::LeafRequestBudget);
+        assert_eq!(limit_exceeded.num_bytes_used, 3_000);
+        assert_eq!(budget.num_bytes_used(), 3_000);
+        assert_eq!(pool.num_bytes_used(), 3_000);
+
+        budget.release(1_000);
+        assert_eq!(budget.num_bytes_used(), 2_000);
+        assert_eq!(pool.num_bytes_used(), 2_000);
+
+        let budget_clone = budget.clone();
+        drop(budget);
+        assert_eq!(pool.num_bytes_used(), 2_000);
+        drop(budget_clone);
+        assert_eq!(pool.num_bytes_used(), 0);
+    }
+
+    #[test]
+    fn test_search_memory_budget_pool_exhausted() {
+        let pool = SearchMemoryPool::new(ByteSize::kb(10));
+        let budget_1 = pool.new_request_budget(ByteSize::kb(8));
+        let budget_2 = pool.new_request_budget(ByteSize::kb(8));
+
+        budget_1.try_charge(7_000).unwrap();
+        let limit_exceeded = budget_2.try_charge(4_000).unwrap_err();
+        assert_eq!(limit_exceeded.limit, MemoryLimit::SearcherPool);
+        assert_eq!(limit_exceeded.limit_num_bytes, 10_000);
+        assert_eq!(budget_2.num_bytes_used(), 0);
+        assert_eq!(pool.num_bytes_used(), 7_000);
+
+        budget_2.try_charge(3_000).unwrap();
+        assert_eq!(pool.num_bytes_used(), 10_000);
+
+        drop(budget_1);
+        budget_2.try_charge(4_000).unwrap();
+        assert_eq!(pool.num_bytes_used(), 7_000);
+    }
+
+    #[test]
+    fn test_search_memory_budget_default_is_unlimited() {
+        let budget = SearchMemoryBudget::default();
+        budget.try_charge(u64::MAX / 2).unwrap();
+        budget.try_charge(u64::MAX / 2).unwrap();
+    }
+
+    #[test]
+    fn test_split_search_memory_budget_records_its_own_failure() {
+        let pool = SearchMemoryPool::new(ByteSize::kb(10));
+        let budget = pool.new_request_budget(ByteSize::kb(4));
+        let split_budget_1 = budget.for_split_search();
+        let split_budget_2 = budget.for_split_search();
+
+        split_budget_1.try_charge(3_000).unwrap();
+        let limit_exceeded = split_budget_2.try_charge(2_000).unwrap_err();
+        assert_eq!(limit_exceeded.limit, MemoryLimit::LeafRequestBudget);
+        assert!(split_budget_1.limit_exceeded().is_none());
+        assert_eq!(
+            split_budget_2.limit_exceeded(),
+            Some(limit_exceeded.clone())
+        );
+
+        // Only the first failure of the split search is recorded.
+        split_budget_2.try_charge(3_000).unwrap_err();
+        assert_eq!(split_budget_2.limit_exceeded(), Some(limit_exceeded));
+
+        split_budget_1.release(3_000);
+        assert_eq!(budget.num_bytes_used(), 0);
+        assert_eq!(pool.num_bytes_used(), 0);
+    }
+
+    #[test]
+    fn test_memory_limit_exceeded_display() {
+        let limit_exceeded = MemoryLimitExceeded {
+            limit: MemoryLimit::LeafRequestBudget,
+            limit_num_bytes: 2_000_000,
+            num_bytes_used: 1_500_000,
+            num_bytes_requested: 1_000_000,
+        };
+        assert_eq!(
+            limit_exceeded.to_string(),
+            "failed to allocate 1.0 MB: leaf request memory budget of 2.0 MB exhausted (1.5 MB in \
+             use)"
+        );
+    }
+}
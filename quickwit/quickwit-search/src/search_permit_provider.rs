@@ -0,0 +1,390 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use quickwit_common::metrics::{index_label, OwnedGaugeGuard};
+use tokio::sync::oneshot;
+
+use crate::metrics::SEARCH_METRICS;
+
+/// Hands out the permits limiting the number of split searches running concurrently on the
+/// searcher.
+///
+/// Unlike a semaphore, the permits are not granted in FIFO order: the split searches waiting for
+/// a permit are queued per index, and the queues are served round-robin so that an index with
+/// many splits cannot starve the other indexes. Besides, while other indexes are waiting, an
+/// index holding `max_index_permits_percent` of the permits or more is skipped.
+///
+/// The indexes are identified by their URI, which is all the leaves know about them.
+#[derive(Clone)]
+pub struct SearchPermitProvider {
+    state: Arc<Mutex<SearchPermitProviderState>>,
+}
+
+struct SearchPermitProviderState {
+    max_num_permits: usize,
+    max_index_permits_percent: usize,
+    num_permits_in_use: usize,
+    index_permits: HashMap<String, IndexPermits>,
+    /// Indexes with split searches waiting for a permit, in the order they will be served.
+    waiting_indexes: VecDeque<String>,
+}
+
+#[derive(Default)]
+struct IndexPermits {
+    num_permits_in_use: usize,
+    waiters: VecDeque<oneshot::Sender<SearchPermit>>,
+}
+
+impl SearchPermitProvider {
+    pub fn new(max_num_permits: usize, max_index_permits_percent: u8) -> Self {
+        let state = SearchPermitProviderState {
+            max_num_permits,
+            max_index_permits_percent: max_index_permits_percent as usize,
+            num_permits_in_use: 0,
+            index_permits: HashMap::new(),
+            waiting_indexes: VecDeque::new(),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns a future resolving to a permit to search a split of the index located at
+    /// `index_uri`. The split search is queued as soon as this method is called.
+    pub fn get_permit(&self, index_uri: &str) -> SearchPermitFuture {
+        let (permit_tx, permit_rx) = oneshot::channel();
+        let mut state_guard = self.state.lock().unwrap();
+        let state = &mut *state_guard;
+
+        if state.waiting_indexes.is_empty() && state.num_permits_in_use < state.max_num_permits {
+            let permit = state.acquire(index_uri.to_string(), &self.state);
+            // The receiver is alive: the permit cannot be sent back.
+            let _ = permit_tx.send(permit);
+            return SearchPermitFuture {
+                permit_rx,
+                _waiting_splits_guard_opt: None,
+            };
+        }
+        let index_permits = state
+            .index_permits
+            .entry(index_uri.to_string())
+            .or_default();
+        if index_permits.waiters.is_empty() {
+            state.waiting_indexes.push_back(index_uri.to_string());
+        }
+        index_permits.waiters.push_back(permit_tx);
+        drop(state_guard);
+
+        let mut waiting_splits_guard = OwnedGaugeGuard::from_gauge(
+            SEARCH_METRICS
+                .leaf_search_waiting_splits
+                .with_label_values([index_label(index_uri)]),
+        );
+        waiting_splits_guard.add(1);
+        SearchPermitFuture {
+            permit_rx,
+            _waiting_splits_guard_opt: Some(waiting_splits_guard),
+        }
+    }
+
+    /// Updates the limits after the searcher config was reloaded. The permits in use are kept
+    /// until they are released.
+    pub fn set_limits(&self, max_num_permits: usize, max_index_permits_percent: u8) {
+        let mut state = self.state.lock().unwrap();
+        state.max_num_permits = max_num_permits;
+        state.max_index_permits_percent = max_index_permits_percent as usize;
+        state.assign_permits(&self.state);
+    }
+
+    #[cfg(test)]
+    fn num_permits_available(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .max_num_permits
+            .saturating_sub(state.num_permits_in_use)
+    }
+}
+
+impl SearchPermitProviderState {
+    fn acquire(
+        &mut self,
+        index_uri: String,
+        state: &Arc<Mutex<SearchPermitProviderState>>,
+    ) -> SearchPermit {
+        self.num_permits_in_use += 1;
+        self.index_permits
+            .entry(index_uri.clone())
+            .or_default()
+            .num_permits_in_use += 1;
+        SearchPermit {
+            state_opt: Some(state.clone()),
+            index_uri,
+        }
+    }
+
+    fn release(&mut self, index_uri: &str) {
+        self.num_permits_in_use -= 1;
+
+        if let Some(index_permits) = self.index_permits.get_mut(index_uri) {
+            index_permits.num_permits_in_use -= 1;
+
+            if index_permits.num_permits_in_use == 0 && index_permits.waiters.is_empty() {
+                self.index_permits.remove(index_uri);
+            }
+        }
+    }
+
+    /// Returns the position in `waiting_indexes` of the next index to serve: the first one that
+    /// holds less than its share of the permits, or the first one if they all hold their share.
+    fn next_waiting_index_pos(&self) -> Option<usize> {
+        if self.waiting_indexes.len() <= 1 {
+            return (!self.waiting_indexes.is_empty()).then_some(0);
+        }
+        let max_index_permits =
+            (self.max_num_permits * self.max_index_permits_percent / 100).max(1);
+        let next_waiting_index_pos = self
+            .waiting_indexes
+            .iter()
+            .position(|index_uri| {
+                self.index_permits
+                    .get(index_uri)
+                    .map(|index_permits| index_permits.num_permits_in_use)
+                    .unwrap_or_default()
+                    < max_index_permits
+            })
+            .unwrap_or(0);
+        Some(next_waiting_index_pos)
+    }
+
+    fn assign_permits(&mut self, state: &Arc<Mutex<SearchPermitProviderState>>) {
+        while self.num_permits_in_use < self.max_num_permits {
+            let Some(waiting_index_pos) = self.next_waiting_index_pos() else {
+                return;
+            };
+            let index_uri = self
+                .waiting_indexes
+                .remove(waiting_index_pos)
+                .expect("the position should be valid");
+            let index_permits = self
+                .index_permits
+                .get_mut(&index_uri)
+                .expect("the waiting index should have permits");
+            let permit_tx = index_permits
+                .waiters
+                .pop_front()
+                .expect("the waiting index should have waiters");
+            if !index_permits.waiters.is_empty() {
+                self.waiting_indexes.push_back(index_uri.clone());
+            }
+            let permit = self.acquire(index_uri, state);
+
+            if let Err(mut permit) = permit_tx.send(permit) {
+                // The split search was cancelled while waiting. The lock is already held, so the
+                // permit is released here rather than when it is dropped.
+                permit.state_opt = None;
+                self.release(&permit.index_uri);
+            }
+        }
+    }
+}
+
+/// Permit to run a split search, released when dropped.
+pub struct SearchPermit {
+    state_opt: Option<Arc<Mutex<SearchPermitProviderState>>>,
+    index_uri: String,
+}
+
+impl Drop for SearchPermit {
+    fn drop(&mut self) {
+        let Some(state) = self.state_opt.take() else {
+            return;
+        };
+        let mut state_guard = state.lock().unwrap();
+        state_guard.release(&self.index_uri);
+        state_guard.assign_permits(&state);
+    }
+}
+
+/// Future returned by [`SearchPermitProvider::get_permit`]. Dropping it cancels the wait.
+pub struct SearchPermitFuture {
+    permit_rx: oneshot::Receiver<SearchPermit>,
+    _waiting_splits_guard_opt: Option<OwnedGaugeGuard>,
+}
+
+impl Future for SearchPermitFuture {
+    type Output = SearchPermit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.permit_rx).poll(cx).map(|permit_res| {
+            permit_res.expect("the permit provider should never drop a waiting split search")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_search_permit_provider_single_index() {
+        let permit_provider = SearchPermitProvider::new(2, 50);
+        let permit_0 = permit_provider
+            .get_permit("index-a")
+            .now_or_never()
+            .unwrap();
+        let permit_1 = permit_provider
+            .get_permit("index-a")
+            .now_or_never()
+            .unwrap();
+        assert_eq!(permit_provider.num_permits_available(), 0);
+
+        // A single index is never capped to its share of the permits.
+        let mut permit_fut_2 = permit_provider.get_permit("index-a");
+        let mut permit_fut_3 = permit_provider.get_permit("index-a");
+        assert!((&mut permit_fut_2).now_or_never().is_none());
+
+        drop(permit_0);
+        let _permit_2 = (&mut permit_fut_2).now_or_never().unwrap();
+        assert!((&mut permit_fut_3).now_or_never().is_none());
+
+        drop(permit_1);
+        let permit_3 = permit_fut_3.now_or_never().unwrap();
+        drop(permit_3);
+        assert_eq!(permit_provider.num_permits_available(), 1);
+    }
+
+    #[test]
+    fn test_search_permit_provider_round_robin() {
+        let permit_provider = SearchPermitProvider::new(4, 100);
+        let mut permits: VecDeque<SearchPermit> = (0..4)
+            .map(|_| {
+                permit_provider
+                    .get_permit("index-a")
+                    .now_or_never()
+                    .unwrap()
+            })
+            .collect();
+
+        // The majority index queues many split searches before the minority index.
+        let mut permit_futs: Vec<(&str, Option<SearchPermitFuture>)> = (0..100)
+            .map(|_| ("index-a", Some(permit_provider.get_permit("index-a"))))
+            .collect();
+        permit_futs
+            .extend((0..4).map(|_| ("index-b", Some(permit_provider.get_permit("index-b")))));
+
+        let mut granted_indexes = Vec::new();
+
+        while granted_indexes.len() < 8 {
+            drop(permits.pop_front());
+
+            for (index_uri, permit_fut_opt) in &mut permit_futs {
+                let Some(permit_fut) = permit_fut_opt else {
+                    continue;
+                };
+                if let Some(permit) = permit_fut.now_or_never() {
+                    granted_indexes.push(*index_uri);
+                    permits.push_back(permit);
+                    *permit_fut_opt = None;
+                }
+            }
+        }
+        assert_eq!(
+            granted_indexes,
+            [
+                "index-a", "index-b", "index-a", "index-b", "index-a", "index-b", "index-a",
+                "index-b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_permit_provider_max_index_share() {
+        let permit_provider = SearchPermitProvider::new(4, 50);
+        let mut index_a_permits: Vec<SearchPermit> = (0..3)
+            .map(|_| {
+                permit_provider
+                    .get_permit("index-a")
+                    .now_or_never()
+                    .unwrap()
+            })
+            .collect();
+        let index_b_permit = permit_provider
+            .get_permit("index-b")
+            .now_or_never()
+            .unwrap();
+
+        let mut permit_fut_a = permit_provider.get_permit("index-a");
+        let mut permit_fut_b = permit_provider.get_permit("index-b");
+
+        // Index A is served first, but it holds more than its share while index B is waiting.
+        drop(index_a_permits.pop());
+        assert!((&mut permit_fut_a).now_or_never().is_none());
+        let _index_b_permit = (&mut permit_fut_b).now_or_never().unwrap();
+
+        // Index A is served once nobody else is waiting.
+        drop(index_b_permit);
+        let _index_a_permit = permit_fut_a.now_or_never().unwrap();
+    }
+
+    #[test]
+    fn test_search_permit_provider_cancelled_wait() {
+        let permit_provider = SearchPermitProvider::new(1, 100);
+        let permit = permit_provider
+            .get_permit("index-a")
+            .now_or_never()
+            .unwrap();
+        let cancelled_permit_fut = permit_provider.get_permit("index-a");
+        let mut permit_fut = permit_provider.get_permit("index-b");
+        drop(cancelled_permit_fut);
+
+        drop(permit);
+        let permit = (&mut permit_fut).now_or_never().unwrap();
+        drop(permit);
+        assert_eq!(permit_provider.num_permits_available(), 1);
+        assert!(permit_provider
+            .state
+            .lock()
+            .unwrap()
+            .index_permits
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_permit_provider_set_limits() {
+        let permit_provider = SearchPermitProvider::new(1, 100);
+        let _permit = permit_provider
+            .get_permit("index-a")
+            .now_or_never()
+            .unwrap();
+        let mut permit_fut = permit_provider.get_permit("index-a");
+        assert!((&mut permit_fut).now_or_never().is_none());
+
+        permit_provider.set_limits(2, 100);
+        let _permit = permit_fut.now_or_never().unwrap();
+        assert_eq!(permit_provider.num_permits_available(), 0);
+    }
+}
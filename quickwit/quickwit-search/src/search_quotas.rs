@@ -0,0 +1,205 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use quickwit_config::TenantSearchQuotaConfig;
+use quickwit_metastore::IndexIdMatcher;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+
+use crate::metrics::SEARCH_METRICS;
+use crate::SearchError;
+
+/// Per-tenant quotas enforced by the root searcher before fanning out a search to the leaves.
+///
+/// Tenants are identified by the IDs of the indexes a search targets. A search is subject to the
+/// quotas of every tenant owning at least one of those indexes.
+pub struct SearchQuotas {
+    tenant_quotas: Vec<TenantSearchQuota>,
+}
+
+struct TenantSearchQuota {
+    tenant_id: String,
+    index_id_matcher: IndexIdMatcher,
+    concurrent_searches_semaphore_opt: Option<Arc<Semaphore>>,
+    max_splits_per_search_opt: Option<usize>,
+}
+
+impl TenantSearchQuota {
+    fn matches_any(&self, index_ids: &[&str]) -> bool {
+        index_ids
+            .iter()
+            .any(|index_id| self.index_id_matcher.is_match(index_id))
+    }
+}
+
+/// Permits acquired for a search on behalf of the tenants it is attributed to. The concurrency
+/// slots are released when the guard is dropped.
+#[must_use]
+pub struct SearchQuotaGuard<'a> {
+    tenant_quotas: Vec<&'a TenantSearchQuota>,
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+impl SearchQuotaGuard<'_> {
+    /// Rejects the search if it targets more splits than any of its tenants allows.
+    pub fn check_num_splits(&self, num_splits: usize) -> crate::Result<()> {
+        for tenant_quota in &self.tenant_quotas {
+            let Some(max_splits_per_search) = tenant_quota.max_splits_per_search_opt else {
+                continue;
+            };
+            if num_splits > max_splits_per_search {
+                warn!(
+                    tenant_id=%tenant_quota.tenant_id,
+                    num_splits,
+                    max_splits_per_search,
+                    "rejecting search: too many targeted splits"
+                );
+                record_rejection(&tenant_quota.tenant_id, "max_splits_per_search");
+                return Err(SearchError::TooManyRequests);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SearchQuotas {
+    pub fn new(tenant_quota_configs: &[TenantSearchQuotaConfig]) -> Self {
+        let tenant_quotas = tenant_quota_configs
+            .iter()
+            .map(|tenant_quota_config| TenantSearchQuota {
+                tenant_id: tenant_quota_config.tenant_id.clone(),
+                index_id_matcher: IndexIdMatcher::try_from_index_id_patterns(
+                    &tenant_quota_config.index_id_patterns,
+                )
+                .expect("tenant index ID patterns should be valid"),
+                concurrent_searches_semaphore_opt: tenant_quota_config.max_concurrent_searches.map(
+                    |max_concurrent_searches| {
+                        Arc::new(Semaphore::new(max_concurrent_searches.get()))
+                    },
+                ),
+                max_splits_per_search_opt: tenant_quota_config
+                    .max_splits_per_search
+                    .map(|max_splits_per_search| max_splits_per_search.get()),
+            })
+            .collect();
+        Self { tenant_quotas }
+    }
+
+    /// Acquires a concurrency slot from every tenant owning one of the targeted indexes. Fails
+    /// right away, without waiting, if one of those tenants has no slot left.
+    pub fn acquire(&self, index_ids: &[&str]) -> crate::Result<SearchQuotaGuard<'_>> {
+        let mut tenant_quotas = Vec::new();
+        let mut permits = Vec::new();
+
+        for tenant_quota in &self.tenant_quotas {
+            if !tenant_quota.matches_any(index_ids) {
+                continue;
+            }
+            if let Some(semaphore) = &tenant_quota.concurrent_searches_semaphore_opt {
+                let Ok(permit) = semaphore.clone().try_acquire_owned() else {
+                    warn!(
+                        tenant_id=%tenant_quota.tenant_id,
+                        "rejecting search: too many concurrent searches"
+                    );
+                    record_rejection(&tenant_quota.tenant_id, "max_concurrent_searches");
+                    return Err(SearchError::TooManyRequests);
+                };
+                permits.push(permit);
+            }
+            tenant_quotas.push(tenant_quota);
+        }
+        Ok(SearchQuotaGuard {
+            tenant_quotas,
+            _permits: permits,
+        })
+    }
+}
+
+fn record_rejection(tenant_id: &str, limit: &str) {
+    SEARCH_METRICS
+        .search_quota_rejections_total
+        .with_label_values([tenant_id, limit])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    fn tenant_quota_config(
+        tenant_id: &str,
+        index_id_pattern: &str,
+        max_concurrent_searches: usize,
+    ) -> TenantSearchQuotaConfig {
+        TenantSearchQuotaConfig {
+            tenant_id: tenant_id.to_string(),
+            index_id_patterns: vec![index_id_pattern.to_string()],
+            max_concurrent_searches: NonZeroUsize::new(max_concurrent_searches),
+            max_splits_per_search: NonZeroUsize::new(10),
+        }
+    }
+
+    #[test]
+    fn test_search_quotas_max_concurrent_searches() {
+        let search_quotas = SearchQuotas::new(&[
+            tenant_quota_config("tenant-a", "tenant-a-*", 2),
+            tenant_quota_config("tenant-b", "tenant-b-*", 2),
+        ]);
+        let rejections_before = SEARCH_METRICS
+            .search_quota_rejections_total
+            .with_label_values(["tenant-a", "max_concurrent_searches"])
+            .get();
+
+        let first_guard = search_quotas.acquire(&["tenant-a-logs"]).unwrap();
+        let _second_guard = search_quotas.acquire(&["tenant-a-traces"]).unwrap();
+        let error = search_quotas.acquire(&["tenant-a-logs"]).err().unwrap();
+        assert!(matches!(error, SearchError::TooManyRequests));
+        assert_eq!(
+            SEARCH_METRICS
+                .search_quota_rejections_total
+                .with_label_values(["tenant-a", "max_concurrent_searches"])
+                .get(),
+            rejections_before + 1
+        );
+        // Another tenant is unaffected.
+        let _tenant_b_guard = search_quotas.acquire(&["tenant-b-logs"]).unwrap();
+        // Indexes without a tenant are not subject to quotas.
+        let _no_tenant_guard = search_quotas.acquire(&["other-logs"]).unwrap();
+
+        drop(first_guard);
+        let _third_guard = search_quotas.acquire(&["tenant-a-logs"]).unwrap();
+    }
+
+    #[test]
+    fn test_search_quotas_max_splits_per_search() {
+        let search_quotas = SearchQuotas::new(&[tenant_quota_config("tenant-a", "tenant-a-*", 2)]);
+
+        let guard = search_quotas.acquire(&["tenant-a-logs"]).unwrap();
+        guard.check_num_splits(10).unwrap();
+        let error = guard.check_num_splits(11).unwrap_err();
+        assert!(matches!(error, SearchError::TooManyRequests));
+
+        let guard = search_quotas.acquire(&["other-logs"]).unwrap();
+        guard.check_num_splits(11).unwrap();
+    }
+}
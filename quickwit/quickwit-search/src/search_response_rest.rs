@@ -19,8 +19,8 @@
 
 use std::convert::TryFrom;
 
-use quickwit_common::truncate_str;
-use quickwit_proto::search::SearchResponse;
+use quickwit_common::{is_false, truncate_str};
+use quickwit_proto::search::{FetchDocsSource, NumHitsRelation, SearchResponse};
 use quickwit_query::query_ast::QueryAst;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -48,6 +48,49 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Where the hits were fetched from. Only reported if requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetch_docs_source: Option<FetchDocsSource>,
+    /// Whether the hits and aggregations may be missing some results.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub partial: bool,
+    /// Identifier of the point in time to pass to the next requests, if one was requested.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
+    /// Resources spent executing the request.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub stats: SearchResponseStats,
+    /// Whether `num_hits` is exact or a lower bound. Only reported if it is a lower bound,
+    /// which requires `track_total_hits` to be set in the request.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_num_hits_exact")]
+    #[schema(value_type = String)]
+    pub num_hits_relation: NumHitsRelation,
+    /// Whether the aggregations were computed on a sample of the matching documents. The
+    /// document counts, `value_count` and `sum` values, and the `count` and `sum` of stats are
+    /// then scaled up estimates, whereas the other metrics are computed on the sample as is.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub sampled: bool,
+    /// Rate at which the matching documents were sampled to compute the aggregations.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f64>,
+}
+
+fn is_num_hits_exact(num_hits_relation: &NumHitsRelation) -> bool {
+    *num_hits_relation == NumHitsRelation::EqualTo
+}
+
+/// Resources spent executing a search request.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Default, Clone, Copy)]
+pub struct SearchResponseStats {
+    /// CPU time spent by the searchers searching the splits, in milliseconds.
+    pub cpu_time_ms: f64,
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
@@ -91,6 +134,13 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             None
         };
 
+        let num_hits_relation = NumHitsRelation::from_i32(search_response.num_hits_relation)
+            .unwrap_or(NumHitsRelation::EqualTo);
+
+        let fetch_docs_source_opt = search_response
+            .fetch_docs_source
+            .and_then(FetchDocsSource::from_i32);
+
         Ok(SearchResponseRest {
             num_hits: search_response.num_hits,
             hits: documents,
@@ -98,6 +148,15 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            fetch_docs_source: fetch_docs_source_opt,
+            partial: search_response.partial,
+            pit_id: search_response.pit_id,
+            stats: SearchResponseStats {
+                cpu_time_ms: search_response.cpu_time_micros as f64 / 1_000.0,
+            },
+            num_hits_relation,
+            sampled: search_response.aggregation_sample_rate.is_some(),
+            sample_rate: search_response.aggregation_sample_rate,
         })
     }
 }
@@ -117,6 +176,26 @@ pub struct SearchPlanResponseRest {
     /// Requests expected for each split
     #[schema(value_type = Object)]
     pub storage_requests: StorageRequestCount,
+    /// Number of splits pruned by the time range, the tags, and the term presence filters of the
+    /// query.
+    #[schema(value_type = Object)]
+    pub split_pruning: SplitPruningStats,
+}
+
+/// Number of splits of the targeted indexes that are not searched, per pruning reason.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Clone, Copy)]
+pub struct SplitPruningStats {
+    /// Number of published splits of the targeted indexes.
+    pub num_splits: usize,
+    /// Number of splits whose time range does not overlap with the time range of the query.
+    pub num_splits_pruned_by_time_range: usize,
+    /// Number of splits whose tags do not match the query. Splits already pruned by time range
+    /// are not counted.
+    pub num_splits_pruned_by_tags: usize,
+    /// Number of splits whose term presence filters rule out the values searched for by the
+    /// query. Splits already pruned by time range or by tags are not counted.
+    #[serde(default)]
+    pub num_splits_pruned_by_term_presence: usize,
 }
 
 /// Number of expected storage requests, per request kind.
@@ -91,7 +91,7 @@ async fn leaf_search_results_stream(
     doc_mapper: Arc<dyn DocMapper>,
 ) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Sync + Send + 'static {
     let max_num_concurrent_split_streams = searcher_context
-        .searcher_config
+        .searcher_config()
         .max_num_concurrent_split_streams;
     futures::stream::iter(splits)
         .map(move |split| {
@@ -117,8 +117,8 @@ async fn leaf_search_stream_single_split(
     storage: Arc<dyn Storage>,
 ) -> crate::Result<LeafSearchStreamResponse> {
     let _leaf_split_stream_permit = searcher_context
-        .split_stream_semaphore
-        .acquire()
+        .split_stream_semaphore()
+        .acquire_owned()
         .await
         .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
     rewrite_start_end_time_bounds(
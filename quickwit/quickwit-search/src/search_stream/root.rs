@@ -32,7 +32,8 @@ use tokio_stream::StreamMap;
 use tracing::*;
 
 use crate::cluster_client::ClusterClient;
-use crate::root::{refine_start_end_timestamp_from_ast, SearchJob};
+use crate::root::{apply_search_filter, refine_start_end_timestamp_from_ast, SearchJob};
+use crate::split_pruning::prune_splits_by_term_presence;
 use crate::{list_relevant_splits, SearchError};
 
 /// Perform a distributed search stream.
@@ -60,7 +61,14 @@ pub async fn root_search_stream(
 
     let query_ast: QueryAst = serde_json::from_str(&search_stream_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let query_ast_resolved = query_ast.parse_user_query(doc_mapper.default_search_fields())?;
+    let query_ast_resolved = doc_mapper
+        .parse_user_query(query_ast, &[])
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast_resolved = apply_search_filter(
+        query_ast_resolved,
+        &index_config.search_settings,
+        &*doc_mapper,
+    )?;
     let tags_filter_ast = extract_tags_from_query(query_ast_resolved.clone());
 
     if let Some(timestamp_field) = doc_mapper.timestamp_field_name() {
@@ -77,7 +85,7 @@ pub async fn root_search_stream(
     search_stream_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
 
     let search_request = SearchRequest::try_from(search_stream_request.clone())?;
-    let split_metadatas = list_relevant_splits(
+    let mut split_metadatas = list_relevant_splits(
         vec![index_uid],
         search_request.start_timestamp,
         search_request.end_timestamp,
@@ -85,6 +93,7 @@ pub async fn root_search_stream(
         &mut metastore,
     )
     .await?;
+    prune_splits_by_term_presence(&mut split_metadatas, &query_ast_resolved);
 
     let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
         SearchError::Internal(format!("failed to serialize doc mapper: cause {err}"))
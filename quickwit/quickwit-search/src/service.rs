@@ -17,13 +17,17 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use bytes::Bytes;
+use quickwit_common::rate_limiter::RateLimiter;
 use quickwit_common::uri::Uri;
 use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
@@ -36,21 +40,39 @@ use quickwit_proto::search::{
     ScrollRequest, SearchPlanResponse, SearchRequest, SearchResponse, SearchStreamRequest,
     SnippetRequest,
 };
+use quickwit_proto::types::SplitId;
 use quickwit_storage::{
-    MemorySizedCache, QuickwitCache, SplitCache, StorageCache, StorageResolver,
+    AsyncDebouncer, MemorySizedCache, OwnedBytes, QuickwitCache, SplitCache, StorageResolver,
+    StorageResult,
 };
+use serde::Serialize;
+use serde_json::Value as JsonValue;
 use tantivy::aggregation::AggregationLimitsGuard;
 use tokio::sync::Semaphore;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::aggregation_spill::AggregationSpillDisk;
 use crate::leaf::multi_leaf_search;
 use crate::leaf_cache::LeafSearchCache;
+use crate::leaf_search_tiers::LeafSearchTiers;
 use crate::list_fields::{leaf_list_fields, root_list_fields};
 use crate::list_fields_cache::ListFieldsCache;
-use crate::list_terms::{leaf_list_terms, root_list_terms};
-use crate::root::fetch_docs_phase;
+use crate::list_splits_cache::{ListSplitsCache, LIST_SPLITS_CACHE_TTL};
+use crate::list_terms::{leaf_list_terms, root_list_terms, ListTermsSearchFilter};
+use crate::load_shedding::LoadShedder;
+use crate::point_in_time::{
+    is_point_in_time_key, record_num_points_in_time, MAX_NUM_POINTS_IN_TIME,
+};
+use crate::root::{fetch_docs_phase, num_hits_with_relation, root_count_hits_per_split};
 use crate::scroll_context::{MiniKV, ScrollContext, ScrollKeyAndStartOffset};
+use crate::search_hits_stream::{root_search_hits_stream, HitsStreamPage};
+use crate::search_interrupt::SearchInterrupt;
+use crate::search_memory::{SearchMemoryBudget, SearchMemoryPool};
+use crate::search_permit_provider::SearchPermitProvider;
+use crate::search_quotas::SearchQuotas;
 use crate::search_stream::{leaf_search_stream, root_search_stream};
+use crate::split_cache_policy::SplitCachePolicy;
+use crate::suggest::{new_suggest_rate_limiter, root_suggest, SuggestRequest, SuggestResponse};
 use crate::{fetch_docs, root_search, search_plan, ClusterClient, SearchError};
 
 #[derive(Clone)]
@@ -61,6 +83,7 @@ pub struct SearchServiceImpl {
     cluster_client: ClusterClient,
     searcher_context: Arc<SearcherContext>,
     search_after_cache: MiniKV,
+    point_in_time_cache: MiniKV,
 }
 
 /// Trait representing a search service.
@@ -98,6 +121,23 @@ pub trait SearchService: 'static + Send + Sync {
         request: SearchStreamRequest,
     ) -> crate::Result<Pin<Box<dyn futures::Stream<Item = crate::Result<Bytes>> + Send>>>;
 
+    /// Performs a root search streaming all the matching documents as newline-delimited JSON.
+    ///
+    /// Splits are scanned sequentially and no global sort is applied. The stream resumes from
+    /// `cursor_opt`, the cursor of a page previously streamed for the same request, if any.
+    async fn root_search_hits_stream(
+        &self,
+        request: SearchRequest,
+        cursor_opt: Option<String>,
+    ) -> crate::Result<Pin<Box<dyn futures::Stream<Item = crate::Result<HitsStreamPage>> + Send>>>;
+
+    /// Counts the documents matching the request in each of the splits it targets, using the
+    /// same split listing and counting as a root search.
+    async fn root_count_hits_per_split(
+        &self,
+        request: SearchRequest,
+    ) -> crate::Result<HashMap<SplitId, u64>>;
+
     /// Performs a leaf search on a given set of splits and returns a stream.
     async fn leaf_search_stream(
         &self,
@@ -153,6 +193,9 @@ pub trait SearchService: 'static + Send + Sync {
 
     /// Describe how a search would be processed.
     async fn search_plan(&self, request: SearchRequest) -> crate::Result<SearchPlanResponse>;
+
+    /// Suggests the field names, or the values of a field, starting with a prefix.
+    async fn root_suggest(&self, request: SuggestRequest) -> crate::Result<SuggestResponse>;
 }
 
 impl SearchServiceImpl {
@@ -169,6 +212,7 @@ impl SearchServiceImpl {
             cluster_client,
             searcher_context,
             search_after_cache: MiniKV::default(),
+            point_in_time_cache: MiniKV::with_capacity(MAX_NUM_POINTS_IN_TIME),
         }
     }
 }
@@ -201,6 +245,12 @@ impl SearchService for SearchServiceImpl {
         if leaf_search_request.search_request.is_none() {
             return Err(SearchError::Internal("no search request".to_string()));
         }
+        let num_splits: usize = leaf_search_request
+            .leaf_requests
+            .iter()
+            .map(|leaf_request| leaf_request.split_offsets.len())
+            .sum();
+        let _split_searches_guard = self.searcher_context.load_shedder.try_admit(num_splits)?;
 
         let leaf_search_response = multi_leaf_search(
             self.searcher_context.clone(),
@@ -228,6 +278,7 @@ impl SearchService for SearchServiceImpl {
             &fetch_docs_request.split_offsets,
             doc_mapper,
             snippet_request_opt,
+            &fetch_docs_request.fields,
         )
         .await?;
 
@@ -247,6 +298,36 @@ impl SearchService for SearchServiceImpl {
         Ok(Box::pin(data))
     }
 
+    async fn root_search_hits_stream(
+        &self,
+        search_request: SearchRequest,
+        cursor_opt: Option<String>,
+    ) -> crate::Result<Pin<Box<dyn futures::Stream<Item = crate::Result<HitsStreamPage>> + Send>>>
+    {
+        let data = root_search_hits_stream(
+            self.searcher_context.clone(),
+            search_request,
+            cursor_opt,
+            self.metastore.clone(),
+            self.cluster_client.clone(),
+        )
+        .await?;
+        Ok(Box::pin(data))
+    }
+
+    async fn root_count_hits_per_split(
+        &self,
+        search_request: SearchRequest,
+    ) -> crate::Result<HashMap<SplitId, u64>> {
+        root_count_hits_per_split(
+            &self.searcher_context,
+            search_request,
+            self.metastore.clone(),
+            &self.cluster_client,
+        )
+        .await
+    }
+
     async fn leaf_search_stream(
         &self,
         leaf_stream_request: LeafSearchStreamRequest,
@@ -292,10 +373,21 @@ impl SearchService for SearchServiceImpl {
         let index_uri = Uri::from_str(&leaf_search_request.index_uri)?;
         let storage = self.storage_resolver.resolve(&index_uri).await?;
         let split_ids = leaf_search_request.split_offsets;
+        let search_filter_opt = match (
+            leaf_search_request.doc_mapper,
+            leaf_search_request.search_filter_ast,
+        ) {
+            (Some(doc_mapper_str), Some(search_filter_ast)) => Some(ListTermsSearchFilter {
+                doc_mapper: deserialize_doc_mapper(&doc_mapper_str)?,
+                query_ast: serde_json::from_str(&search_filter_ast)?,
+            }),
+            _ => None,
+        };
 
         let leaf_search_response = leaf_list_terms(
             self.searcher_context.clone(),
             &search_request,
+            search_filter_opt.as_ref(),
             storage.clone(),
             &split_ids[..],
         )
@@ -310,12 +402,25 @@ impl SearchService for SearchServiceImpl {
 
     async fn put_kv(&self, put_request: PutKvRequest) {
         let ttl = Duration::from_secs(put_request.ttl_secs as u64);
+
+        if is_point_in_time_key(&put_request.key) {
+            self.point_in_time_cache
+                .put(put_request.key, put_request.payload, ttl)
+                .await;
+            record_num_points_in_time(self.point_in_time_cache.num_entries().await);
+            return;
+        }
         self.search_after_cache
             .put(put_request.key, put_request.payload, ttl)
             .await;
     }
 
     async fn get_kv(&self, get_request: GetKvRequest) -> Option<Vec<u8>> {
+        if is_point_in_time_key(&get_request.key) {
+            let payload_opt = self.point_in_time_cache.get(&get_request.key).await;
+            record_num_points_in_time(self.point_in_time_cache.num_entries().await);
+            return payload_opt;
+        }
         let payload: Vec<u8> = self.search_after_cache.get(&get_request.key).await?;
         Some(payload)
     }
@@ -361,9 +466,27 @@ impl SearchService for SearchServiceImpl {
         &self,
         search_request: SearchRequest,
     ) -> crate::Result<SearchPlanResponse> {
-        let search_plan = search_plan(search_request, self.metastore.clone()).await?;
+        let search_plan = search_plan(
+            &self.searcher_context,
+            search_request,
+            self.metastore.clone(),
+        )
+        .await?;
         Ok(search_plan)
     }
+
+    async fn root_suggest(
+        &self,
+        suggest_request: SuggestRequest,
+    ) -> crate::Result<SuggestResponse> {
+        root_suggest(
+            suggest_request,
+            &self.searcher_context,
+            self.metastore.clone(),
+            &self.cluster_client,
+        )
+        .await
+    }
 }
 
 pub(crate) async fn scroll(
@@ -429,31 +552,49 @@ pub(crate) async fn scroll(
         }
     }
 
+    let (num_hits, num_hits_relation) = num_hits_with_relation(
+        scroll_context.total_num_hits,
+        scroll_context.count_hits,
+        scroll_context.search_request.track_total_hits,
+    );
+
     Ok(SearchResponse {
         hits,
-        num_hits: scroll_context.total_num_hits,
+        num_hits,
         elapsed_time_micros: start.elapsed().as_micros() as u64,
         scroll_id: Some(next_scroll_id.to_string()),
         errors: Vec::new(),
         aggregation: None,
         failed_splits: scroll_context.failed_splits,
         num_successful_splits: scroll_context.num_successful_splits,
+        fetch_docs_source: None,
+        partial: false,
+        warnings: Vec::new(),
+        pit_id: None,
+        cpu_time_micros: 0,
+        num_hits_relation: num_hits_relation as i32,
+        aggregation_sample_rate: None,
     })
 }
 /// [`SearcherContext`] provides a common set of variables
 /// shared by a searcher instance (which instantiates a
 /// [`SearchServiceImpl`]).
 pub struct SearcherContext {
-    /// Searcher config.
-    pub searcher_config: SearcherConfig,
+    /// Searcher config. The limits that can change at runtime are swapped by
+    /// [`SearcherContext::reload_searcher_config`].
+    searcher_config: ArcSwap<SearcherConfig>,
     /// Fast fields cache.
-    pub fast_fields_cache: Arc<dyn StorageCache>,
-    /// Counting semaphore to limit concurrent leaf search split requests.
-    pub leaf_search_split_semaphore: Arc<Semaphore>,
-    /// Split footer cache.
+    pub fast_fields_cache: Arc<QuickwitCache>,
+    /// Limits the concurrent leaf search split requests, sharing the permits fairly between the
+    /// indexes.
+    pub(crate) search_permit_provider: SearchPermitProvider,
+    /// Split footer cache. Footers are keyed by split ID only: unlike search results, they are
+    /// not affected by the delete opstamp of the split since split files are never rewritten.
     pub split_footer_cache: MemorySizedCache<String>,
+    /// Deduplicates concurrent fetches of the footer of a split.
+    pub split_footer_debouncer: AsyncDebouncer<String, StorageResult<OwnedBytes>>,
     /// Counting semaphore to limit concurrent split stream requests.
-    pub split_stream_semaphore: Semaphore,
+    split_stream_semaphore: ArcSwap<Semaphore>,
     /// Recent sub-query cache.
     pub leaf_search_cache: LeafSearchCache,
     /// Search split cache. `None` if no split cache is configured.
@@ -461,22 +602,44 @@ pub struct SearcherContext {
     /// List fields cache. Caches the list fields response for a given split.
     pub list_fields_cache: ListFieldsCache,
     /// The aggregation limits are passed to limit the memory usage.
-    pub aggregation_limit: AggregationLimitsGuard,
+    aggregation_limit: ArcSwap<AggregationLimitsGuard>,
+    /// Per-tenant quotas enforced by the root search.
+    pub search_quotas: SearchQuotas,
+    /// Node-wide pool the leaf requests draw their memory budget from.
+    pub search_memory_pool: SearchMemoryPool,
+    /// Recent split listings, used by the root search when the metastore times out.
+    pub list_splits_cache: ListSplitsCache,
+    /// Rejects the leaf search requests while the searcher is overloaded.
+    pub(crate) load_shedder: Arc<LoadShedder>,
+    /// Shares the search threads between the cheap and the expensive leaf search requests.
+    pub(crate) leaf_search_tiers: LeafSearchTiers,
+    /// Caps the number of suggestion requests served by the searcher.
+    pub(crate) suggest_rate_limiter: Mutex<RateLimiter>,
+    /// Disk the leaf requests spill their intermediate aggregation results to. `None` if no
+    /// spill directory is configured.
+    pub(crate) aggregation_spill_disk_opt: Option<AggregationSpillDisk>,
+    /// Serializes the searcher config reloads.
+    reload_lock: Mutex<()>,
 }
 
 impl std::fmt::Debug for SearcherContext {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("SearcherContext")
-            .field("searcher_config", &self.searcher_config)
-            .field(
-                "leaf_search_split_semaphore",
-                &self.leaf_search_split_semaphore,
-            )
-            .field("split_stream_semaphore", &self.split_stream_semaphore)
+            .field("searcher_config", &self.searcher_config())
+            .field("split_stream_semaphore", &self.split_stream_semaphore())
             .finish()
     }
 }
 
+/// Outcome of a searcher config reload.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct SearcherConfigReload {
+    /// Fields whose new value is now in effect.
+    pub applied: Vec<&'static str>,
+    /// Fields whose new value was ignored because it cannot change without restarting the node.
+    pub ignored: Vec<&'static str>,
+}
+
 impl SearcherContext {
     #[cfg(test)]
     pub fn for_test() -> SearcherContext {
@@ -491,9 +654,10 @@ impl SearcherContext {
             capacity_in_bytes,
             &quickwit_storage::STORAGE_METRICS.split_footer_cache,
         );
-        let leaf_search_split_semaphore = Arc::new(Semaphore::new(
+        let search_permit_provider = SearchPermitProvider::new(
             searcher_config.max_num_concurrent_split_searches,
-        ));
+            searcher_config.max_split_searches_per_index_percent,
+        );
         let split_stream_semaphore =
             Semaphore::new(searcher_config.max_num_concurrent_split_streams);
         let fast_field_cache_capacity = searcher_config.fast_field_cache_capacity.as_u64() as usize;
@@ -502,26 +666,276 @@ impl SearcherContext {
             LeafSearchCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
         let list_fields_cache =
             ListFieldsCache::new(searcher_config.partial_request_cache_capacity.as_u64() as usize);
-        let aggregation_limit = AggregationLimitsGuard::new(
-            Some(searcher_config.aggregation_memory_limit.as_u64()),
-            Some(searcher_config.aggregation_bucket_limit),
+        let aggregation_limit = new_aggregation_limits(&searcher_config);
+        let search_quotas = SearchQuotas::new(&searcher_config.tenant_quotas);
+        let search_memory_pool = SearchMemoryPool::new(searcher_config.search_memory_pool_size);
+        let load_shedder = Arc::new(LoadShedder::new(
+            &searcher_config,
+            search_memory_pool.clone(),
+        ));
+        let leaf_search_tiers = LeafSearchTiers::new(
+            searcher_config.leaf_search_tiers,
+            crate::search_thread_pool().clone(),
         );
+        let split_cache_policy = SplitCachePolicy::default();
+        let list_splits_cache = ListSplitsCache::new(LIST_SPLITS_CACHE_TTL, split_cache_policy);
+        let suggest_rate_limiter = new_suggest_rate_limiter(&searcher_config.suggest);
 
         Self {
-            searcher_config,
+            searcher_config: ArcSwap::from_pointee(searcher_config),
             fast_fields_cache: storage_long_term_cache,
-            leaf_search_split_semaphore,
+            search_permit_provider,
             split_footer_cache: global_split_footer_cache,
-            split_stream_semaphore,
+            split_footer_debouncer: AsyncDebouncer::default(),
+            split_stream_semaphore: ArcSwap::from_pointee(split_stream_semaphore),
             leaf_search_cache,
             list_fields_cache,
             split_cache_opt,
-            aggregation_limit,
+            aggregation_limit: ArcSwap::from_pointee(aggregation_limit),
+            search_quotas,
+            search_memory_pool,
+            list_splits_cache,
+            load_shedder,
+            leaf_search_tiers,
+            suggest_rate_limiter: Mutex::new(suggest_rate_limiter),
+            aggregation_spill_disk_opt: None,
+            reload_lock: Mutex::default(),
         }
     }
 
+    /// Sets the directory the leaf requests spill their intermediate aggregation results to,
+    /// when they allow it.
+    pub fn with_aggregation_spill_dir(mut self, spill_dir: PathBuf) -> Self {
+        let max_disk_usage = self.searcher_config().aggregation_spill.max_disk_usage;
+        self.aggregation_spill_disk_opt =
+            Some(AggregationSpillDisk::new(spill_dir, max_disk_usage));
+        self
+    }
+
+    /// Returns the current searcher config.
+    pub fn searcher_config(&self) -> Arc<SearcherConfig> {
+        self.searcher_config.load_full()
+    }
+
+    /// Returns the semaphore limiting the concurrent split stream requests.
+    ///
+    /// The semaphore is replaced when the limit is reloaded: the permits must be acquired on the
+    /// returned instance with `acquire_owned`.
+    pub fn split_stream_semaphore(&self) -> Arc<Semaphore> {
+        self.split_stream_semaphore.load_full()
+    }
+
     /// Returns the shared instance to track the aggregation memory usage.
     pub fn get_aggregation_limits(&self) -> AggregationLimitsGuard {
-        self.aggregation_limit.clone()
+        self.aggregation_limit.load().as_ref().clone()
+    }
+
+    /// Creates the memory budget of a new leaf request.
+    pub fn new_leaf_request_memory_budget(&self) -> SearchMemoryBudget {
+        let leaf_request_memory_budget = self.searcher_config().leaf_request_memory_budget;
+        self.search_memory_pool
+            .new_request_budget(leaf_request_memory_budget)
+    }
+
+    /// Creates the interrupt of a new leaf request, whose deadline is the request timeout.
+    pub(crate) fn new_leaf_request_interrupt(&self) -> SearchInterrupt {
+        let deadline = Instant::now() + self.searcher_config().request_timeout();
+        SearchInterrupt::with_deadline(deadline)
+    }
+
+    /// Validates `new_searcher_config` and applies the limits that can change at runtime:
+    /// concurrency limits, memory budgets, cache capacities, and request timeout. The other
+    /// fields keep their current value and are reported as ignored.
+    ///
+    /// The requests in flight keep the limits they started with, the new limits apply to the
+    /// following requests. Shrinking a cache evicts its least recently used entries right away.
+    pub fn reload_searcher_config(
+        &self,
+        new_searcher_config: SearcherConfig,
+    ) -> anyhow::Result<SearcherConfigReload> {
+        let _reload_guard = self.reload_lock.lock().unwrap();
+        self.reload_searcher_config_locked(new_searcher_config)
+    }
+
+    /// Applies `searcher_config_patch`, a JSON merge patch (RFC 7386), to the current searcher
+    /// config and reloads the result like [`SearcherContext::reload_searcher_config`]. The fields
+    /// absent from the patch keep their current value, and the fields set to `null` are reset to
+    /// their default value.
+    pub fn patch_searcher_config(
+        &self,
+        searcher_config_patch: JsonValue,
+    ) -> anyhow::Result<SearcherConfigReload> {
+        let _reload_guard = self.reload_lock.lock().unwrap();
+        let mut searcher_config_json = serde_json::to_value(&*self.searcher_config())?;
+        apply_json_merge_patch(&mut searcher_config_json, searcher_config_patch);
+        let new_searcher_config: SearcherConfig = serde_json::from_value(searcher_config_json)?;
+        self.reload_searcher_config_locked(new_searcher_config)
+    }
+
+    fn reload_searcher_config_locked(
+        &self,
+        new_searcher_config: SearcherConfig,
+    ) -> anyhow::Result<SearcherConfigReload> {
+        new_searcher_config.validate()?;
+
+        let current_searcher_config = self.searcher_config();
+        let mut reloaded_searcher_config = new_searcher_config;
+        let mut reload = SearcherConfigReload::default();
+
+        macro_rules! keep_current_value {
+            ($($field:ident),*) => {
+                $(
+                    if reloaded_searcher_config.$field != current_searcher_config.$field {
+                        reloaded_searcher_config.$field = current_searcher_config.$field.clone();
+                        reload.ignored.push(stringify!($field));
+                    }
+                )*
+            };
+        }
+        keep_current_value!(
+            split_cache,
+            leaf_search_response_compression,
+            tenant_quotas,
+            max_query_length,
+            max_request_body_size,
+            search_memory_pool_size,
+            leaf_search_hedging,
+            load_shedding,
+            leaf_search_tiers,
+            aggregation_spill
+        );
+        // The memory budget of the leaf requests must still fit in the current memory pool.
+        reloaded_searcher_config.validate()?;
+
+        let max_num_concurrent_split_searches =
+            reloaded_searcher_config.max_num_concurrent_split_searches;
+        let max_split_searches_per_index_percent =
+            reloaded_searcher_config.max_split_searches_per_index_percent;
+        let max_num_concurrent_split_searches_changed = max_num_concurrent_split_searches
+            != current_searcher_config.max_num_concurrent_split_searches;
+        let max_split_searches_per_index_percent_changed = max_split_searches_per_index_percent
+            != current_searcher_config.max_split_searches_per_index_percent;
+
+        if max_num_concurrent_split_searches_changed || max_split_searches_per_index_percent_changed
+        {
+            self.search_permit_provider.set_limits(
+                max_num_concurrent_split_searches,
+                max_split_searches_per_index_percent,
+            );
+        }
+        if max_num_concurrent_split_searches_changed {
+            self.load_shedder
+                .set_max_num_concurrent_split_searches(max_num_concurrent_split_searches);
+            reload.applied.push("max_num_concurrent_split_searches");
+        }
+        if max_split_searches_per_index_percent_changed {
+            reload.applied.push("max_split_searches_per_index_percent");
+        }
+        let max_num_concurrent_split_streams =
+            reloaded_searcher_config.max_num_concurrent_split_streams;
+        if max_num_concurrent_split_streams
+            != current_searcher_config.max_num_concurrent_split_streams
+        {
+            self.split_stream_semaphore
+                .store(Arc::new(Semaphore::new(max_num_concurrent_split_streams)));
+            reload.applied.push("max_num_concurrent_split_streams");
+        }
+        let aggregation_memory_limit_changed = reloaded_searcher_config.aggregation_memory_limit
+            != current_searcher_config.aggregation_memory_limit;
+        let aggregation_bucket_limit_changed = reloaded_searcher_config.aggregation_bucket_limit
+            != current_searcher_config.aggregation_bucket_limit;
+
+        if aggregation_memory_limit_changed || aggregation_bucket_limit_changed {
+            self.aggregation_limit
+                .store(Arc::new(new_aggregation_limits(&reloaded_searcher_config)));
+        }
+        if aggregation_memory_limit_changed {
+            reload.applied.push("aggregation_memory_limit");
+        }
+        if aggregation_bucket_limit_changed {
+            reload.applied.push("aggregation_bucket_limit");
+        }
+        if reloaded_searcher_config.leaf_request_memory_budget
+            != current_searcher_config.leaf_request_memory_budget
+        {
+            reload.applied.push("leaf_request_memory_budget");
+        }
+        if reloaded_searcher_config.request_timeout() != current_searcher_config.request_timeout() {
+            reload.applied.push("request_timeout_secs");
+        }
+        if reloaded_searcher_config.split_pruning_slack_secs
+            != current_searcher_config.split_pruning_slack_secs
+        {
+            reload.applied.push("split_pruning_slack_secs");
+        }
+        if reloaded_searcher_config.collection_checkpoint_num_docs
+            != current_searcher_config.collection_checkpoint_num_docs
+        {
+            reload.applied.push("collection_checkpoint_num_docs");
+        }
+        if reloaded_searcher_config.yield_at_collection_checkpoints
+            != current_searcher_config.yield_at_collection_checkpoints
+        {
+            reload.applied.push("yield_at_collection_checkpoints");
+        }
+        if reloaded_searcher_config.suggest != current_searcher_config.suggest {
+            *self.suggest_rate_limiter.lock().unwrap() =
+                new_suggest_rate_limiter(&reloaded_searcher_config.suggest);
+            reload.applied.push("suggest");
+        }
+        let fast_field_cache_capacity = reloaded_searcher_config.fast_field_cache_capacity;
+        if fast_field_cache_capacity != current_searcher_config.fast_field_cache_capacity {
+            self.fast_fields_cache
+                .set_fast_field_cache_capacity(fast_field_cache_capacity.as_u64() as usize);
+            reload.applied.push("fast_field_cache_capacity");
+        }
+        let split_footer_cache_capacity = reloaded_searcher_config.split_footer_cache_capacity;
+        if split_footer_cache_capacity != current_searcher_config.split_footer_cache_capacity {
+            self.split_footer_cache
+                .set_capacity_in_bytes(split_footer_cache_capacity.as_u64() as usize);
+            reload.applied.push("split_footer_cache_capacity");
+        }
+        let partial_request_cache_capacity =
+            reloaded_searcher_config.partial_request_cache_capacity;
+        if partial_request_cache_capacity != current_searcher_config.partial_request_cache_capacity
+        {
+            let capacity_in_bytes = partial_request_cache_capacity.as_u64() as usize;
+            self.leaf_search_cache.set_capacity(capacity_in_bytes);
+            self.list_fields_cache.set_capacity(capacity_in_bytes);
+            reload.applied.push("partial_request_cache_capacity");
+        }
+        self.searcher_config
+            .store(Arc::new(reloaded_searcher_config));
+        Ok(reload)
+    }
+}
+
+fn new_aggregation_limits(searcher_config: &SearcherConfig) -> AggregationLimitsGuard {
+    AggregationLimitsGuard::new(
+        Some(searcher_config.aggregation_memory_limit.as_u64()),
+        Some(searcher_config.aggregation_bucket_limit),
+    )
+}
+
+/// Applies a JSON merge patch (RFC 7386): objects are merged recursively, `null` members are
+/// removed, and any other value replaces the target value.
+fn apply_json_merge_patch(target: &mut JsonValue, patch: JsonValue) {
+    let JsonValue::Object(patch_members) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = JsonValue::Object(Default::default());
+    }
+    let JsonValue::Object(target_members) = target else {
+        unreachable!("target should be an object");
+    };
+    for (key, patch_value) in patch_members {
+        if patch_value.is_null() {
+            target_members.remove(&key);
+        } else {
+            let target_value = target_members.entry(key).or_insert(JsonValue::Null);
+            apply_json_merge_patch(target_value, patch_value);
+        }
     }
 }
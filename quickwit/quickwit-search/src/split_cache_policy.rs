@@ -0,0 +1,132 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_metastore::SplitMetadata;
+use tantivy::time::OffsetDateTime;
+
+/// Default time during which values derived from an immature split can be cached.
+pub(crate) const IMMATURE_SPLIT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Decides for how long values derived from a split can be cached.
+///
+/// The files of a split are never rewritten, but a split can be replaced: immature splits are
+/// merged into new splits, and applying a delete task to a split either replaces it or advances
+/// its delete opstamp. Per-split cache entries must therefore be keyed by split ID and delete
+/// opstamp, and values derived from immature splits must not outlive the policy TTL.
+#[derive(Debug, Clone, Copy)]
+pub struct SplitCachePolicy {
+    immature_split_ttl: Duration,
+}
+
+impl Default for SplitCachePolicy {
+    fn default() -> Self {
+        SplitCachePolicy::new(IMMATURE_SPLIT_CACHE_TTL)
+    }
+}
+
+impl SplitCachePolicy {
+    /// Creates a policy caching values derived from immature splits for `immature_split_ttl`.
+    pub fn new(immature_split_ttl: Duration) -> Self {
+        SplitCachePolicy { immature_split_ttl }
+    }
+
+    /// Returns true if the split is mature and therefore no longer merged. From then on, only a
+    /// delete task can replace it, which the delete opstamp of the cache keys accounts for.
+    pub fn is_immutable(&self, split_metadata: &SplitMetadata) -> bool {
+        split_metadata.is_mature(OffsetDateTime::now_utc())
+    }
+
+    /// Returns how long values derived from the split can be cached, `None` meaning they can be
+    /// kept until evicted.
+    pub fn ttl(&self, split_metadata: &SplitMetadata) -> Option<Duration> {
+        if self.is_immutable(split_metadata) {
+            None
+        } else {
+            Some(self.immature_split_ttl)
+        }
+    }
+
+    /// Returns how long a value derived from all the given splits can be cached.
+    pub(crate) fn min_ttl<'a>(
+        &self,
+        split_metadatas: impl IntoIterator<Item = &'a SplitMetadata>,
+    ) -> Option<Duration> {
+        split_metadatas
+            .into_iter()
+            .filter_map(|split_metadata| self.ttl(split_metadata))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::SplitMaturity;
+
+    use super::*;
+
+    #[test]
+    fn test_split_cache_policy() {
+        let split_cache_policy = SplitCachePolicy::new(Duration::from_secs(30));
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+        let mature_split = SplitMetadata {
+            split_id: "mature-split".to_string(),
+            maturity: SplitMaturity::Mature,
+            ..Default::default()
+        };
+        assert!(split_cache_policy.is_immutable(&mature_split));
+        assert_eq!(split_cache_policy.ttl(&mature_split), None);
+
+        let matured_split = SplitMetadata {
+            split_id: "matured-split".to_string(),
+            create_timestamp: now_timestamp - 3_600,
+            maturity: SplitMaturity::Immature {
+                maturation_period: Duration::from_secs(60),
+            },
+            ..Default::default()
+        };
+        assert!(split_cache_policy.is_immutable(&matured_split));
+        assert_eq!(split_cache_policy.ttl(&matured_split), None);
+
+        let immature_split = SplitMetadata {
+            split_id: "immature-split".to_string(),
+            create_timestamp: now_timestamp,
+            maturity: SplitMaturity::Immature {
+                maturation_period: Duration::from_secs(3_600),
+            },
+            ..Default::default()
+        };
+        assert!(!split_cache_policy.is_immutable(&immature_split));
+        assert_eq!(
+            split_cache_policy.ttl(&immature_split),
+            Some(Duration::from_secs(30))
+        );
+
+        assert_eq!(
+            split_cache_policy.min_ttl([&mature_split, &matured_split]),
+            None
+        );
+        assert_eq!(
+            split_cache_policy.min_ttl([&mature_split, &immature_split]),
+            Some(Duration::from_secs(30))
+        );
+    }
+}
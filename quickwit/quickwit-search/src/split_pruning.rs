@@ -0,0 +1,543 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_common::rate_limited_warn;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_doc_mapper::term_presence::{
+    extract_term_presence_filter_from_query, may_match_term_presence_filters,
+};
+use quickwit_metastore::SplitMetadata;
+use quickwit_query::query_ast::QueryAst;
+
+use crate::metrics::SEARCH_METRICS;
+use crate::search_response_rest::SplitPruningStats;
+
+/// Returns true if the time range of the split overlaps with `[start_timestamp, end_timestamp)`.
+/// Splits without a time range are never pruned.
+fn split_overlaps_time_range(
+    split_metadata: &SplitMetadata,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+) -> bool {
+    let Some(time_range) = &split_metadata.time_range else {
+        return true;
+    };
+    start_timestamp.map_or(true, |start_timestamp| *time_range.end() >= start_timestamp)
+        && end_timestamp.map_or(true, |end_timestamp| *time_range.start() < end_timestamp)
+}
+
+/// Returns true if the split may hold documents matching a search over
+/// `[start_timestamp, end_timestamp)` with the given tag filter. This is the predicate applied by
+/// the metastore when it lists the relevant splits of a search.
+pub(crate) fn split_matches_time_range_and_tags(
+    split_metadata: &SplitMetadata,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<&TagFilterAst>,
+) -> bool {
+    split_overlaps_time_range(split_metadata, start_timestamp, end_timestamp)
+        && tags_filter_opt.map_or(true, |tags_filter| {
+            tags_filter.evaluate(&split_metadata.tags)
+        })
+}
+
+/// Counts the splits that a search pruned by time range, by tags, and by term presence filters,
+/// applying the same predicates as the metastore when it lists the relevant splits of the search
+/// and as [`prune_splits_by_term_presence`].
+///
+/// A split pruned for several reasons is counted once, in the first of these categories: time
+/// range, tags, term presence filters.
+pub(crate) fn compute_split_pruning_stats(
+    splits_metadata: &[SplitMetadata],
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<&TagFilterAst>,
+    term_presence_filter_opt: Option<&TagFilterAst>,
+) -> SplitPruningStats {
+    let mut split_pruning_stats = SplitPruningStats {
+        num_splits: splits_metadata.len(),
+        ..Default::default()
+    };
+    for split_metadata in splits_metadata {
+        if !split_overlaps_time_range(split_metadata, start_timestamp, end_timestamp) {
+            split_pruning_stats.num_splits_pruned_by_time_range += 1;
+        } else if tags_filter_opt
+            .is_some_and(|tags_filter| !tags_filter.evaluate(&split_metadata.tags))
+        {
+            split_pruning_stats.num_splits_pruned_by_tags += 1;
+        } else if term_presence_filter_opt.is_some_and(|term_presence_filter| {
+            !may_match_term_presence_filters(
+                term_presence_filter,
+                &split_metadata.term_presence_filters,
+            )
+        }) {
+            split_pruning_stats.num_splits_pruned_by_term_presence += 1;
+        }
+    }
+    split_pruning_stats
+}
+
+/// Removes the splits whose term presence filters guarantee that none of their documents match
+/// the query. Only the queries searching for exact values of the fields are taken into account,
+/// and the false positives of the filters only cause some splits to be searched needlessly.
+///
+/// Returns the number of pruned splits.
+pub(crate) fn prune_splits_by_term_presence(
+    splits_metadata: &mut Vec<SplitMetadata>,
+    query_ast: &QueryAst,
+) -> usize {
+    if splits_metadata
+        .iter()
+        .all(|split_metadata| split_metadata.term_presence_filters.is_empty())
+    {
+        return 0;
+    }
+    let Some(term_presence_filter) = extract_term_presence_filter_from_query(query_ast.clone())
+    else {
+        return 0;
+    };
+    let num_splits_before = splits_metadata.len();
+    splits_metadata.retain(|split_metadata| {
+        may_match_term_presence_filters(
+            &term_presence_filter,
+            &split_metadata.term_presence_filters,
+        )
+    });
+    let num_pruned_splits = num_splits_before - splits_metadata.len();
+    SEARCH_METRICS
+        .splits_pruned_by_term_presence_total
+        .inc_by(num_pruned_splits as u64);
+    num_pruned_splits
+}
+
+/// Widens `[start_timestamp, end_timestamp)` by the split pruning slack on both ends.
+///
+/// The slack only makes the split listing more inclusive: the leaves still apply the time range
+/// of the search to the documents of the splits.
+pub(crate) fn widen_time_range_by_slack(
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    slack_secs: u64,
+) -> (Option<i64>, Option<i64>) {
+    let slack_secs = i64::try_from(slack_secs).unwrap_or(i64::MAX);
+    (
+        start_timestamp.map(|start_timestamp| start_timestamp.saturating_sub(slack_secs)),
+        end_timestamp.map(|end_timestamp| end_timestamp.saturating_add(slack_secs)),
+    )
+}
+
+/// Records the splits listed with the split pruning slack that the time range of the search
+/// alone would have pruned, and warns about the splits time-stamped in the future beyond the
+/// slack, which denotes a clock skew larger than the slack.
+///
+/// Returns the number of splits saved by the slack.
+pub(crate) fn record_split_pruning_slack_saves(
+    splits_metadata: &[SplitMetadata],
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    slack_secs: u64,
+    now_timestamp: i64,
+) -> usize {
+    let max_timestamp = now_timestamp.saturating_add(i64::try_from(slack_secs).unwrap_or(i64::MAX));
+    let mut num_slack_saves = 0;
+
+    for split_metadata in splits_metadata {
+        if !split_overlaps_time_range(split_metadata, start_timestamp, end_timestamp) {
+            num_slack_saves += 1;
+        }
+        let Some(time_range) = &split_metadata.time_range else {
+            continue;
+        };
+        if *time_range.end() > max_timestamp {
+            rate_limited_warn!(
+                limit_per_min = 6,
+                index_uid=%split_metadata.index_uid,
+                split_id=%split_metadata.split_id,
+                split_end_timestamp=*time_range.end(),
+                now_timestamp,
+                "split time range ends in the future by more than the split pruning slack \
+                 ({slack_secs}s), check the clocks of the indexers"
+            );
+        }
+    }
+    SEARCH_METRICS
+        .split_pruning_slack_saves_total
+        .inc_by(num_slack_saves as u64);
+    num_slack_saves
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::ops::RangeInclusive;
+
+    use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
+    use quickwit_doc_mapper::term_presence::{TermPresenceFilter, TermPresenceFilters};
+    use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst, TermSetQuery};
+
+    use super::*;
+
+    fn split_for_test(
+        split_id: &str,
+        time_range: Option<RangeInclusive<i64>>,
+        tags: &[&str],
+    ) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            time_range,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Splits of an index partitioned by `tenant`, with `lang` also being a tag field.
+    fn splits_for_test() -> Vec<SplitMetadata> {
+        vec![
+            split_for_test(
+                "split-a",
+                Some(0..=10),
+                &["tenant!", "tenant:a", "lang!", "lang:fr"],
+            ),
+            split_for_test(
+                "split-b",
+                Some(10..=20),
+                &["tenant!", "tenant:b", "lang!", "lang:en"],
+            ),
+            split_for_test(
+                "split-c",
+                Some(20..=30),
+                &["tenant!", "tenant:c", "lang!", "lang:fr", "lang:en"],
+            ),
+            // Splits with too many values for a tag field do not record its tags.
+            split_for_test("split-d", Some(30..=40), &["tenant!", "tenant:d"]),
+            split_for_test(
+                "split-e",
+                None,
+                &["tenant!", "tenant:a", "lang!", "lang:de"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_widen_time_range_by_slack() {
+        assert_eq!(widen_time_range_by_slack(None, None, 60), (None, None));
+        assert_eq!(
+            widen_time_range_by_slack(Some(1_000), Some(2_000), 60),
+            (Some(940), Some(2_060))
+        );
+        assert_eq!(
+            widen_time_range_by_slack(Some(1_000), Some(2_000), 0),
+            (Some(1_000), Some(2_000))
+        );
+        assert_eq!(
+            widen_time_range_by_slack(Some(i64::MIN), Some(i64::MAX), u64::MAX),
+            (Some(i64::MIN), Some(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_split_pruning_slack_with_future_splits() {
+        // The indexer clock is 2 minutes ahead: the split holding the documents of the last
+        // minute appears to be in the future.
+        let now_timestamp = 10_000;
+        let splits = vec![
+            split_for_test("split-past", Some(9_000..=9_700), &[]),
+            split_for_test("split-skewed", Some(10_060..=10_120), &[]),
+        ];
+        let (start_timestamp, end_timestamp) = (Some(now_timestamp - 300), Some(now_timestamp));
+        let included_split_ids = |slack_secs: u64| -> Vec<&str> {
+            let (start_timestamp, end_timestamp) =
+                widen_time_range_by_slack(start_timestamp, end_timestamp, slack_secs);
+            splits
+                .iter()
+                .filter(|split| split_overlaps_time_range(split, start_timestamp, end_timestamp))
+                .map(|split| split.split_id.as_str())
+                .collect()
+        };
+        assert_eq!(included_split_ids(0), ["split-past"]);
+        assert_eq!(included_split_ids(60), ["split-past"]);
+        assert_eq!(included_split_ids(120), ["split-past", "split-skewed"]);
+
+        assert_eq!(
+            record_split_pruning_slack_saves(
+                &splits,
+                start_timestamp,
+                end_timestamp,
+                120,
+                now_timestamp
+            ),
+            1
+        );
+        assert_eq!(
+            record_split_pruning_slack_saves(
+                &splits[..1],
+                start_timestamp,
+                end_timestamp,
+                120,
+                now_timestamp
+            ),
+            0
+        );
+    }
+
+    fn pruning_stats_for_query(
+        query_ast: QueryAst,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+    ) -> SplitPruningStats {
+        let tags_filter_opt = extract_tags_from_query(query_ast.clone());
+        let term_presence_filter_opt = extract_term_presence_filter_from_query(query_ast);
+        compute_split_pruning_stats(
+            &splits_for_test(),
+            start_timestamp,
+            end_timestamp,
+            tags_filter_opt.as_ref(),
+            term_presence_filter_opt.as_ref(),
+        )
+    }
+
+    fn pruning_stats_for_user_query(
+        user_query: &str,
+        start_timestamp: Option<i64>,
+        end_timestamp: Option<i64>,
+    ) -> SplitPruningStats {
+        let query_ast = query_ast_from_user_text(user_query, None)
+            .parse_user_query(&[])
+            .unwrap();
+        pruning_stats_for_query(query_ast, start_timestamp, end_timestamp)
+    }
+
+    fn stats(num_pruned_by_time_range: usize, num_pruned_by_tags: usize) -> SplitPruningStats {
+        SplitPruningStats {
+            num_splits: 5,
+            num_splits_pruned_by_time_range: num_pruned_by_time_range,
+            num_splits_pruned_by_tags: num_pruned_by_tags,
+            num_splits_pruned_by_term_presence: 0,
+        }
+    }
+
+    #[test]
+    fn test_split_pruning_stats_match_all() {
+        assert_eq!(
+            pruning_stats_for_query(QueryAst::MatchAll, None, None),
+            stats(0, 0)
+        );
+        // `split-a` ends before 15, `split-d` starts after 25.
+        assert_eq!(
+            pruning_stats_for_query(QueryAst::MatchAll, Some(15), Some(25)),
+            stats(2, 0)
+        );
+        // The end timestamp is exclusive.
+        assert_eq!(
+            pruning_stats_for_query(QueryAst::MatchAll, None, Some(10)),
+            stats(3, 0)
+        );
+    }
+
+    #[test]
+    fn test_split_pruning_stats_term() {
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:a", None, None),
+            stats(0, 3)
+        );
+        // `split-d` does not record its `lang` tags.
+        assert_eq!(
+            pruning_stats_for_user_query("lang:fr", None, None),
+            stats(0, 2)
+        );
+        // Pruning by time range takes precedence.
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:a", Some(15), None),
+            stats(1, 3)
+        );
+    }
+
+    #[test]
+    fn test_split_pruning_stats_terms() {
+        let query_ast: QueryAst = TermSetQuery {
+            terms_per_field: [(
+                "tenant".to_string(),
+                BTreeSet::from(["a".to_string(), "b".to_string()]),
+            )]
+            .into_iter()
+            .collect(),
+        }
+        .into();
+        assert_eq!(pruning_stats_for_query(query_ast, None, None), stats(0, 2));
+        assert_eq!(
+            pruning_stats_for_user_query("tenant: IN [c d]", None, None),
+            stats(0, 3)
+        );
+    }
+
+    #[test]
+    fn test_split_pruning_stats_bool() {
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:a AND lang:fr", None, None),
+            stats(0, 4)
+        );
+        assert_eq!(
+            pruning_stats_for_user_query("(tenant:a OR tenant:b) AND lang:en", None, None),
+            stats(0, 4)
+        );
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:a OR lang:en", None, None),
+            stats(0, 0)
+        );
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:a AND (lang:en OR body:hello)", None, None),
+            stats(0, 3)
+        );
+    }
+
+    #[test]
+    fn test_split_pruning_stats_negation_is_noop() {
+        assert_eq!(
+            pruning_stats_for_user_query("NOT tenant:a", None, None),
+            stats(0, 0)
+        );
+        assert_eq!(
+            pruning_stats_for_user_query("-lang:fr -lang:en", None, None),
+            stats(0, 0)
+        );
+        assert_eq!(
+            pruning_stats_for_user_query("NOT tenant: IN [a b]", None, None),
+            stats(0, 0)
+        );
+        // The positive clause still prunes.
+        assert_eq!(
+            pruning_stats_for_user_query("tenant:b AND NOT lang:en", None, None),
+            stats(0, 4)
+        );
+    }
+
+    fn split_with_trace_ids(split_id: &str, trace_ids: &[&str]) -> SplitMetadata {
+        let term_presence_filter = TermPresenceFilter::build(trace_ids.iter().copied(), 0.01);
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            term_presence_filters: TermPresenceFilters::from([(
+                "trace_id".to_string(),
+                term_presence_filter,
+            )]),
+            ..Default::default()
+        }
+    }
+
+    fn pruned_split_ids(splits: &[SplitMetadata], user_query: &str) -> Vec<String> {
+        let query_ast = query_ast_from_user_text(user_query, None)
+            .parse_user_query(&[])
+            .unwrap();
+        let mut remaining_splits = splits.to_vec();
+        let num_pruned_splits = prune_splits_by_term_presence(&mut remaining_splits, &query_ast);
+
+        let pruned_split_ids: Vec<String> = splits
+            .iter()
+            .filter(|split| {
+                !remaining_splits
+                    .iter()
+                    .any(|remaining_split| remaining_split.split_id == split.split_id)
+            })
+            .map(|split| split.split_id.clone())
+            .collect();
+        assert_eq!(pruned_split_ids.len(), num_pruned_splits);
+        pruned_split_ids
+    }
+
+    #[test]
+    fn test_prune_splits_by_term_presence() {
+        let splits = vec![
+            split_with_trace_ids("split-a", &["trace-a1", "trace-a2"]),
+            split_with_trace_ids("split-b", &["trace-b1", "trace-b2"]),
+            // Splits without a filter, for instance because they hold too many distinct values,
+            // are never pruned.
+            SplitMetadata {
+                split_id: "split-c".to_string(),
+                ..Default::default()
+            },
+        ];
+        // Miss.
+        assert_eq!(
+            pruned_split_ids(&splits, "trace_id:trace-unknown"),
+            ["split-a", "split-b"]
+        );
+        // Hit.
+        assert_eq!(pruned_split_ids(&splits, "trace_id:trace-a1"), ["split-b"]);
+        assert_eq!(
+            pruned_split_ids(&splits, "trace_id: IN [trace-a2 trace-b1]"),
+            Vec::<String>::new()
+        );
+        // Queries other than exact terms bypass the filters.
+        assert_eq!(
+            pruned_split_ids(&splits, "trace_id:trace-unk*"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            pruned_split_ids(&splits, "NOT trace_id:trace-a1"),
+            Vec::<String>::new()
+        );
+        assert_eq!(pruned_split_ids(&splits, "*"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_prune_splits_by_term_presence_false_positives() {
+        // A single split holding many values, probed with many absent values: some of them are
+        // false positives, which must keep the split, and the others prune it.
+        let trace_ids: Vec<String> = (0..1_000).map(|i| format!("trace-{i}")).collect();
+        let split = split_with_trace_ids(
+            "split",
+            &trace_ids.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        let splits = [split];
+
+        for trace_id in &trace_ids {
+            assert!(pruned_split_ids(&splits, &format!("trace_id:{trace_id}")).is_empty());
+        }
+        let num_false_positives = (1_000..11_000)
+            .filter(|i| pruned_split_ids(&splits, &format!("trace_id:trace-{i}")).is_empty())
+            .count();
+        assert!(num_false_positives > 0);
+        assert!(num_false_positives < 200);
+    }
+
+    #[test]
+    fn test_split_pruning_stats_term_presence() {
+        let splits = vec![
+            split_with_trace_ids("split-a", &["trace-a"]),
+            split_with_trace_ids("split-b", &["trace-b"]),
+        ];
+        let query_ast = query_ast_from_user_text("trace_id:trace-a", None)
+            .parse_user_query(&[])
+            .unwrap();
+        let term_presence_filter_opt = extract_term_presence_filter_from_query(query_ast);
+        assert_eq!(
+            compute_split_pruning_stats(
+                &splits,
+                None,
+                None,
+                None,
+                term_presence_filter_opt.as_ref()
+            ),
+            SplitPruningStats {
+                num_splits: 2,
+                num_splits_pruned_by_term_presence: 1,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::try_join_all;
+use itertools::Itertools;
+use quickwit_common::rate_limiter::{RateLimiter, RateLimiterSettings};
+use quickwit_common::tower::ConstantRate;
+use quickwit_config::{build_doc_mapper, SuggestConfig};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_metastore::{IndexMetadata, SplitMetadata};
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::search::{
+    LeafListTermsResponse, ListFieldsRequest, ListFieldsResponse, ListTermsRequest,
+};
+use quickwit_proto::types::{IndexId, IndexUid};
+use serde::{Deserialize, Serialize};
+use tantivy::schema::FieldType;
+use tantivy::Term;
+use tracing::{instrument, warn};
+
+use crate::list_fields::IndexMetasForLeafSearch;
+use crate::list_terms::IndexMetasForLeafListTerms;
+use crate::metrics::SEARCH_METRICS;
+use crate::service::SearcherContext;
+use crate::{list_relevant_splits, resolve_index_patterns, ClusterClient, SearchError, SearchJob};
+
+/// Request for the field names, or the values of a field, starting with a prefix.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SuggestRequest {
+    /// ID of the index to introspect.
+    pub index_id: IndexId,
+    /// Prefix the suggested field names or values start with.
+    pub prefix: String,
+    /// Field whose values are suggested. If `None`, field names are suggested instead.
+    pub field_opt: Option<String>,
+    /// Maximum number of suggestions returned.
+    pub max_suggestions: usize,
+}
+
+/// Field names or field values suggested to complete a prefix, in lexicographical order.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct SuggestResponse {
+    /// Suggested field names or values.
+    pub suggestions: Vec<String>,
+}
+
+/// Builds the rate limiter capping the number of suggestion requests served by the searcher.
+pub(crate) fn new_suggest_rate_limiter(suggest_config: &SuggestConfig) -> RateLimiter {
+    let max_requests_per_sec = suggest_config.max_requests_per_sec as u64;
+    let rate_limiter_settings = RateLimiterSettings {
+        burst_limit: max_requests_per_sec,
+        rate_limit: ConstantRate::new(max_requests_per_sec, Duration::from_secs(1)),
+        refill_period: Duration::from_secs(1),
+    };
+    RateLimiter::from_settings(rate_limiter_settings)
+}
+
+/// Suggests the field names or the values of a field starting with a prefix.
+///
+/// Suggestions are meant to be requested as the user types, so they are only gathered from the
+/// few most recent splits of the index rather than from the whole index:
+/// - field names are the fields declared in the doc mapping, plus the dynamic fields found in these
+///   splits;
+/// - field values are read from the term dictionary of the field in these splits.
+///
+/// If the index has a search filter, field values are only suggested from the documents matching
+/// it, and field names are restricted to the fields declared in the doc mapping.
+#[instrument(skip(searcher_context, metastore, cluster_client))]
+pub async fn root_suggest(
+    suggest_request: SuggestRequest,
+    searcher_context: &SearcherContext,
+    mut metastore: MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<SuggestResponse> {
+    if !searcher_context
+        .suggest_rate_limiter
+        .lock()
+        .unwrap()
+        .acquire(1)
+    {
+        SEARCH_METRICS.suggest_requests_rejected_total.inc();
+        return Err(SearchError::TooManyRequests);
+    }
+    if suggest_request.max_suggestions == 0 {
+        return Ok(SuggestResponse::default());
+    }
+    let index_metadata = resolve_index_patterns(
+        std::slice::from_ref(&suggest_request.index_id),
+        &mut metastore,
+    )
+    .await?
+    .into_iter()
+    .next()
+    .ok_or_else(|| SearchError::IndexesNotFound {
+        index_ids: vec![suggest_request.index_id.clone()],
+    })?;
+
+    let index_config = &index_metadata.index_config;
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|err| {
+            SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
+        })?;
+    if let Some(field) = &suggest_request.field_opt {
+        validate_suggested_field(&*doc_mapper, field)?;
+    }
+    let split_metadatas = list_relevant_splits(
+        vec![index_metadata.index_uid.clone()],
+        None,
+        None,
+        None,
+        &mut metastore,
+    )
+    .await?;
+    let max_num_splits = searcher_context.searcher_config().suggest.max_num_splits;
+    let recent_split_metadatas = select_recent_splits(split_metadatas, max_num_splits);
+
+    let suggestions = if let Some(field) = &suggest_request.field_opt {
+        suggest_field_values(
+            &suggest_request,
+            field,
+            &index_metadata,
+            &doc_mapper,
+            &recent_split_metadatas,
+            cluster_client,
+        )
+        .await?
+    } else {
+        suggest_field_names(
+            &suggest_request,
+            &index_metadata,
+            &recent_split_metadatas,
+            cluster_client,
+        )
+        .await?
+    };
+    Ok(SuggestResponse { suggestions })
+}
+
+/// Checks that the values of `field` can be suggested, i.e. that it is an indexed text field.
+fn validate_suggested_field(doc_mapper: &dyn DocMapper, field: &str) -> crate::Result<()> {
+    let schema = doc_mapper.schema();
+    let field_entry = schema
+        .get_field(field)
+        .map(|field| schema.get_field_entry(field))
+        .map_err(|_| {
+            SearchError::InvalidArgument(format!(
+                "failed to suggest values of `{field}`, field doesn't exist"
+            ))
+        })?;
+    if !field_entry.is_indexed() || !matches!(field_entry.field_type(), FieldType::Str(_)) {
+        return Err(SearchError::InvalidArgument(format!(
+            "failed to suggest values of `{field}`, only the values of indexed text fields can be \
+             suggested"
+        )));
+    }
+    Ok(())
+}
+
+/// Keeps the `max_num_splits` splits holding the most recent documents. Splits without a time
+/// range are ordered by creation date.
+fn select_recent_splits(
+    mut split_metadatas: Vec<SplitMetadata>,
+    max_num_splits: usize,
+) -> Vec<SplitMetadata> {
+    split_metadatas.sort_unstable_by_key(|split_metadata| {
+        let time_range_end_opt = split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.end());
+        Reverse((time_range_end_opt, split_metadata.create_timestamp))
+    });
+    split_metadatas.truncate(max_num_splits);
+    split_metadatas
+}
+
+async fn suggest_field_names(
+    suggest_request: &SuggestRequest,
+    index_metadata: &IndexMetadata,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<Vec<String>> {
+    // The dynamic fields found in the splits may only exist in documents ruled out by the search
+    // filter, so they are not suggested.
+    let split_fields = if index_metadata
+        .index_config
+        .search_settings
+        .search_filter
+        .is_some()
+    {
+        ListFieldsResponse::default()
+    } else {
+        list_split_fields(
+            suggest_request,
+            index_metadata,
+            split_metadatas,
+            cluster_client,
+        )
+        .await?
+    };
+    let field_names = index_metadata
+        .index_config
+        .doc_mapping
+        .field_capabilities(Some(split_fields))
+        .into_iter()
+        .map(|field_capability| field_capability.name)
+        .filter(|field_name| field_name.starts_with(&suggest_request.prefix))
+        .sorted()
+        .dedup()
+        .take(suggest_request.max_suggestions)
+        .collect();
+    Ok(field_names)
+}
+
+/// Lists the fields starting with the prefix of the request found in the given splits.
+async fn list_split_fields(
+    suggest_request: &SuggestRequest,
+    index_metadata: &IndexMetadata,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<ListFieldsResponse> {
+    if split_metadatas.is_empty() {
+        return Ok(ListFieldsResponse::default());
+    }
+    let list_fields_request = ListFieldsRequest {
+        index_id_patterns: vec![suggest_request.index_id.clone()],
+        fields: vec![format!("{}*", suggest_request.prefix)],
+        start_timestamp: None,
+        end_timestamp: None,
+    };
+    let index_uid_to_index_meta: HashMap<IndexUid, IndexMetasForLeafSearch> = HashMap::from([(
+        index_metadata.index_uid.clone(),
+        IndexMetasForLeafSearch {
+            index_id: index_metadata.index_id().to_string(),
+            index_uri: index_metadata.index_uri().clone(),
+        },
+    )]);
+    let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let assigned_leaf_search_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+    let mut leaf_request_tasks = Vec::new();
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        let leaf_requests = crate::list_fields::jobs_to_leaf_requests(
+            &list_fields_request,
+            &index_uid_to_index_meta,
+            client_jobs,
+        )?;
+        for leaf_request in leaf_requests {
+            leaf_request_tasks.push(cluster_client.leaf_list_fields(leaf_request, client.clone()));
+        }
+    }
+    let leaf_list_fields_responses: Vec<ListFieldsResponse> =
+        try_join_all(leaf_request_tasks).await?;
+    let split_fields = ListFieldsResponse {
+        fields: leaf_list_fields_responses
+            .into_iter()
+            .flat_map(|leaf_list_fields_response| leaf_list_fields_response.fields)
+            .collect(),
+    };
+    Ok(split_fields)
+}
+
+async fn suggest_field_values(
+    suggest_request: &SuggestRequest,
+    field: &str,
+    index_metadata: &IndexMetadata,
+    doc_mapper: &Arc<dyn DocMapper>,
+    split_metadatas: &[SplitMetadata],
+    cluster_client: &ClusterClient,
+) -> crate::Result<Vec<String>> {
+    if split_metadatas.is_empty() {
+        return Ok(Vec::new());
+    }
+    let prefix = suggest_request.prefix.as_bytes();
+    let list_terms_request = ListTermsRequest {
+        index_id_patterns: vec![suggest_request.index_id.clone()],
+        field: field.to_string(),
+        start_timestamp: None,
+        end_timestamp: None,
+        max_hits: Some(suggest_request.max_suggestions as u64),
+        start_key: Some(prefix.to_vec()),
+        end_key: prefix_upper_bound(prefix),
+    };
+    let index_uid_to_index_meta: HashMap<IndexUid, IndexMetasForLeafListTerms> = HashMap::from([(
+        index_metadata.index_uid.clone(),
+        IndexMetasForLeafListTerms::new(index_metadata, doc_mapper)?,
+    )]);
+    let jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
+    let assigned_leaf_search_jobs = cluster_client
+        .search_job_placer
+        .assign_jobs(jobs, &HashSet::default())
+        .await?;
+    let mut leaf_request_tasks = Vec::new();
+    for (client, client_jobs) in assigned_leaf_search_jobs {
+        let leaf_requests = crate::list_terms::jobs_to_leaf_requests(
+            &list_terms_request,
+            &index_uid_to_index_meta,
+            client_jobs,
+        )?;
+        for leaf_request in leaf_requests {
+            leaf_request_tasks.push(cluster_client.leaf_list_terms(leaf_request, client.clone()));
+        }
+    }
+    let leaf_list_terms_responses: Vec<LeafListTermsResponse> =
+        try_join_all(leaf_request_tasks).await?;
+
+    // Suggestions are best effort: the splits that could not be read are skipped.
+    for leaf_list_terms_response in &leaf_list_terms_responses {
+        for failed_split in &leaf_list_terms_response.failed_splits {
+            warn!(split_id = %failed_split.split_id, error = %failed_split.error, "failed to suggest values from split");
+        }
+    }
+    let field_values = leaf_list_terms_responses
+        .into_iter()
+        .map(|leaf_list_terms_response| leaf_list_terms_response.terms)
+        .kmerge()
+        .dedup()
+        .filter_map(|term_bytes| {
+            Term::wrap(&term_bytes[..])
+                .value()
+                .as_str()
+                .map(|field_value| field_value.to_string())
+        })
+        .take(suggest_request.max_suggestions)
+        .collect();
+    Ok(field_values)
+}
+
+/// Returns the smallest key greater than all the keys starting with `prefix`, or `None` if there
+/// is no such key, i.e. if the prefix is empty or only made of `0xFF` bytes.
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper_bound = prefix.to_vec();
+    while let Some(last_byte) = upper_bound.pop() {
+        if last_byte < u8::MAX {
+            upper_bound.push(last_byte + 1);
+            return Some(upper_bound);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::SplitMetadata;
+
+    use super::*;
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(prefix_upper_bound(b""), None);
+        assert_eq!(prefix_upper_bound(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_upper_bound(b"a\xFF"), Some(b"b".to_vec()));
+        assert_eq!(prefix_upper_bound(b"\xFF\xFF"), None);
+    }
+
+    #[test]
+    fn test_select_recent_splits() {
+        let split = |split_id: &str, time_range_end_opt: Option<i64>, create_timestamp: i64| {
+            SplitMetadata {
+                split_id: split_id.to_string(),
+                time_range: time_range_end_opt.map(|end| 0..=end),
+                create_timestamp,
+                ..Default::default()
+            }
+        };
+        let split_metadatas = vec![
+            split("split-1", Some(10), 3),
+            split("split-2", Some(30), 1),
+            split("split-3", None, 4),
+            split("split-4", Some(20), 2),
+            split("split-5", Some(30), 2),
+        ];
+        let recent_split_ids: Vec<String> = select_recent_splits(split_metadatas, 3)
+            .into_iter()
+            .map(|split_metadata| split_metadata.split_id)
+            .collect();
+        assert_eq!(recent_split_ids, ["split-5", "split-2", "split-4"]);
+    }
+
+    #[test]
+    fn test_suggest_rate_limiter() {
+        let suggest_config = SuggestConfig {
+            max_num_splits: 3,
+            max_requests_per_sec: 2,
+        };
+        let mut rate_limiter = new_suggest_rate_limiter(&suggest_config);
+        assert!(rate_limiter.acquire(1));
+        assert!(rate_limiter.acquire(1));
+        assert!(!rate_limiter.acquire(1));
+    }
+}
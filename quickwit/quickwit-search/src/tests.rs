@@ -17,22 +17,28 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
-use quickwit_config::SearcherConfig;
+use bytesize::ByteSize;
+use futures::FutureExt;
+use quickwit_config::{AggregationSpillConfig, SearchFilter, SearcherConfig, SuggestConfig};
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::TestSandbox;
+use quickwit_metastore::{IndexMetadataResponseExt, UpdateIndexRequestExt};
 use quickwit_opentelemetry::otlp::TraceId;
+use quickwit_proto::metastore::{IndexMetadataRequest, UpdateIndexRequest};
 use quickwit_proto::search::{
-    LeafListTermsResponse, ListTermsRequest, SearchRequest, SortByValue, SortField, SortOrder,
-    SortValue,
+    FetchDocsSource, LeafListTermsResponse, ListTermsRequest, NumHitsRelation, SearchRequest,
+    SortByValue, SortField, SortOrder, SortValue, SplitIdAndFooterOffsets, SplitSearchErrorCode,
 };
 use quickwit_query::query_ast::{
     qast_helper, qast_json_helper, query_ast_from_user_text, QueryAst,
 };
 use serde_json::{json, Value as JsonValue};
+use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::schema::OwnedValue as TantivyValue;
 use tantivy::time::OffsetDateTime;
 use tantivy::Term;
@@ -40,7 +46,7 @@ use tantivy::Term;
 use self::leaf::leaf_search;
 use super::*;
 use crate::find_trace_ids_collector::Span;
-use crate::list_terms::leaf_list_terms;
+use crate::list_terms::{leaf_list_terms, ListTermsSearchFilter};
 use crate::service::SearcherContext;
 use crate::single_node_search;
 
@@ -87,6 +93,117 @@ async fn test_single_node_simple() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_cpu_time() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let docs = || (0..2_000).map(|doc_id| json!({"body": format!("hello world {doc_id}")}));
+    let mut cpu_times_micros = Vec::new();
+    for num_splits in [1, 4] {
+        let index_id = format!("single-node-cpu-time-{num_splits}");
+        let test_sandbox =
+            TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+        for _ in 0..num_splits {
+            test_sandbox.add_documents(docs()).await?;
+        }
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id],
+            query_ast: qast_json_helper("hello", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert_eq!(single_node_result.num_hits, 2_000 * num_splits);
+        assert_eq!(single_node_result.num_successful_splits, num_splits);
+        assert!(single_node_result.cpu_time_micros > 0);
+        cpu_times_micros.push(single_node_result.cpu_time_micros);
+        test_sandbox.assert_quit().await;
+    }
+    // Each split searched adds its own CPU time.
+    assert!(cpu_times_micros[1] > cpu_times_micros[0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_fetch_fields() -> anyhow::Result<()> {
+    let index_id = "single-node-fetch-fields";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: id
+                type: u64
+                fast: true
+              - name: level
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"id": 1, "level": "INFO", "body": "service started"}),
+        json!({"id": 2, "level": "ERROR", "body": "service crashed"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("crashed", &["body"]),
+            max_hits: 10,
+            fields: vec!["id".to_string(), "level".to_string()],
+            debug_fetch_docs_source: true,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert_eq!(
+            single_node_result.fetch_docs_source,
+            Some(FetchDocsSource::FastFields as i32)
+        );
+        assert_eq!(single_node_result.hits.len(), 1);
+        let hit_json: JsonValue = serde_json::from_str(&single_node_result.hits[0].json)?;
+        assert_json_eq!(hit_json, json!({"id": 2, "level": "ERROR"}));
+    }
+    {
+        // `body` is not a fast field: the hits are fetched from the doc store.
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("crashed", &["body"]),
+            max_hits: 10,
+            fields: vec!["id".to_string(), "body".to_string()],
+            debug_fetch_docs_source: true,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert_eq!(
+            single_node_result.fetch_docs_source,
+            Some(FetchDocsSource::DocStore as i32)
+        );
+        assert_eq!(single_node_result.hits.len(), 1);
+        let hit_json: JsonValue = serde_json::from_str(&single_node_result.hits[0].json)?;
+        assert_json_eq!(hit_json, json!({"id": 2, "body": "service crashed"}));
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_termset() -> anyhow::Result<()> {
     let index_id = "single-node-termset-1";
@@ -133,6 +250,44 @@ async fn test_single_node_termset() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_id_pseudo_field() -> anyhow::Result<()> {
+    let index_id = "single-node-id-pseudo-field";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: event_id
+                type: text
+                tokenizer: raw
+              - name: body
+                type: text
+            doc_id_field: event_id
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"event_id": "evt1", "body": "first event"}),
+        json!({"event_id": "evt2", "body": "second event"}),
+        json!({"event_id": "evt3", "body": "third event"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("_id:evt2", &[]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    let hit_json: JsonValue = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_json_include!(actual: hit_json, expected: json!({"event_id": "evt2"}));
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_search_with_snippet() -> anyhow::Result<()> {
     let index_id = "single-node-with-snippet";
@@ -385,6 +540,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            sort_mode: None,
         }],
         ..Default::default()
     };
@@ -409,6 +565,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            sort_mode: None,
         }],
         ..Default::default()
     };
@@ -432,6 +589,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            sort_mode: None,
         }],
         ..Default::default()
     };
@@ -577,6 +735,7 @@ async fn single_node_search_sort_by_field(
             field_name: sort_by_field.to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            sort_mode: None,
         }],
         ..Default::default()
     };
@@ -663,6 +822,7 @@ async fn test_sort_bm25() {
                 field_name: "_score".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             }],
             ..Default::default()
         };
@@ -711,6 +871,140 @@ async fn test_sort_bm25() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_sort_by_score_and_field_with_ties() {
+    let index_id = "sort_by_score_and_field_with_ties".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+                record: freq
+                fieldnorms: true
+              - name: rank
+                type: u64
+                fast: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    // Two splits, each holding a few documents scoring higher than the rest. All the other
+    // documents have the same score, so their order is decided by `rank` alone.
+    for split_ord in 0..2u64 {
+        let docs = (0..15u64)
+            .map(|doc_ord| {
+                let rank = (doc_ord * 7 + split_ord * 3) % 30;
+                let body = if doc_ord % 5 == 0 {
+                    "hello hello"
+                } else {
+                    "hello"
+                };
+                json!({"body": body, "rank": rank + split_ord * 100})
+            })
+            .collect();
+        test_sandbox.add_documents(docs).await.unwrap();
+    }
+    let query_ast_json = serde_json::to_string(&query_ast_from_user_text("hello", None)).unwrap();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: query_ast_json,
+        max_hits: 1_000,
+        sort_fields: vec![
+            SortField {
+                field_name: "_score".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                sort_mode: None,
+            },
+            SortField {
+                field_name: "rank".to_string(),
+                sort_order: SortOrder::Asc as i32,
+                sort_datetime_format: None,
+                sort_mode: None,
+            },
+        ],
+        ..Default::default()
+    };
+    let sort_key = |partial_hit: &PartialHit| {
+        let Some(SortByValue {
+            sort_value: Some(SortValue::F64(score)),
+        }) = partial_hit.sort_value
+        else {
+            panic!("expected a score, got {partial_hit:?}");
+        };
+        let Some(SortByValue {
+            sort_value: Some(SortValue::U64(rank)),
+        }) = partial_hit.sort_value2
+        else {
+            panic!("expected a rank, got {partial_hit:?}");
+        };
+        (score, rank)
+    };
+    let all_hits: Vec<PartialHit> = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap()
+    .hits
+    .into_iter()
+    .map(|hit| hit.partial_hit.unwrap())
+    .collect();
+    assert_eq!(all_hits.len(), 30);
+    let all_keys: Vec<(f64, u64)> = all_hits.iter().map(sort_key).collect();
+    let num_distinct_scores = all_keys
+        .iter()
+        .map(|(score, _)| score.to_bits())
+        .collect::<HashSet<_>>()
+        .len();
+    assert_eq!(num_distinct_scores, 2);
+    for window in all_keys.windows(2) {
+        let (score, rank) = window[0];
+        let (next_score, next_rank) = window[1];
+        assert!(score > next_score || (score == next_score && rank < next_rank));
+    }
+
+    // Paginating through the tie region must return every document exactly once, whether the
+    // cursor carries the address of the last hit or only its sort values.
+    for keep_address in [true, false] {
+        let mut paginated_keys = Vec::new();
+        let mut search_after: Option<PartialHit> = None;
+        loop {
+            let page_request = SearchRequest {
+                max_hits: 4,
+                search_after: search_after.clone(),
+                ..search_request.clone()
+            };
+            let page_hits: Vec<PartialHit> = single_node_search(
+                page_request,
+                test_sandbox.metastore(),
+                test_sandbox.storage_resolver(),
+            )
+            .await
+            .unwrap()
+            .hits
+            .into_iter()
+            .map(|hit| hit.partial_hit.unwrap())
+            .collect();
+            let Some(last_hit) = page_hits.last() else {
+                break;
+            };
+            paginated_keys.extend(page_hits.iter().map(sort_key));
+            search_after = Some(if keep_address {
+                last_hit.clone()
+            } else {
+                PartialHit {
+                    sort_value: last_hit.sort_value.clone(),
+                    sort_value2: last_hit.sort_value2.clone(),
+                    ..Default::default()
+                }
+            });
+        }
+        assert_eq!(paginated_keys, all_keys);
+    }
+    test_sandbox.assert_quit().await;
+}
+
 #[tokio::test]
 async fn test_sort_by_static_and_dynamic_field() {
     let index_id = "sort_by_dynamic_field".to_string();
@@ -756,6 +1050,7 @@ async fn test_sort_by_static_and_dynamic_field() {
                 field_name: sort_field.to_string(),
                 sort_order: order as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             }],
             ..Default::default()
         };
@@ -855,11 +1150,13 @@ async fn test_sort_by_2_field() {
                         field_name: sort_field1.to_string(),
                         sort_order: order1 as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                     SortField {
                         field_name: sort_field2.to_string(),
                         sort_order: order2 as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                 ],
                 ..Default::default()
@@ -939,6 +1236,7 @@ async fn test_single_node_invalid_sorting_with_query() {
             field_name: "description".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            sort_mode: None,
         }],
         ..Default::default()
     };
@@ -1053,6 +1351,8 @@ async fn test_search_util(test_sandbox: &TestSandbox, query: &str) -> Vec<u32> {
         Arc::new(SearcherContext::new(SearcherConfig::default(), None));
 
     let agg_limits = searcher_context.get_aggregation_limits();
+    let memory_budget = searcher_context.new_leaf_request_memory_budget();
+    let search_interrupt = searcher_context.new_leaf_request_interrupt();
 
     let search_response = leaf_search(
         searcher_context,
@@ -1061,6 +1361,8 @@ async fn test_search_util(test_sandbox: &TestSandbox, query: &str) -> Vec<u32> {
         splits_offsets,
         test_sandbox.doc_mapper(),
         agg_limits,
+        memory_budget,
+        search_interrupt,
     )
     .await
     .unwrap();
@@ -1166,6 +1468,64 @@ async fn test_search_dynamic_mode_do_not_expand_dots() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_search_exists_object_field() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            index_field_presence: true
+            field_mappings:
+              - name: user
+                type: object
+                field_mappings:
+                  - name: name
+                    type: text
+                  - name: address
+                    type: object
+                    field_mappings:
+                      - name: city
+                        type: text
+                      - name: zip
+                        type: u64
+                        fast: true
+            mode: dynamic
+        "#;
+    let test_sandbox =
+        TestSandbox::create("search_exists_object_field", doc_mapping_yaml, "{}", &[])
+            .await
+            .unwrap();
+    let docs = vec![
+        json!({"user": {"name": "Fred"}}),
+        json!({"user": {"address": {"city": "Paris"}}}),
+        json!({"user": {"address": {"zip": 75001}}}),
+        json!({"user": {"nickname": "Freddy"}}),
+        json!({"user": {"address": {"country": "France"}}}),
+        json!({"user": {}, "customer": {"address": {"city": "Lyon"}}}),
+        json!({"customer": {"name": "Wilma"}}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    {
+        let mut docs = test_search_util(&test_sandbox, "user:*").await;
+        docs.sort();
+        assert_eq!(&docs[..], &[0u32, 1, 2, 3, 4]);
+    }
+    {
+        let mut docs = test_search_util(&test_sandbox, "user.address:*").await;
+        docs.sort();
+        assert_eq!(&docs[..], &[1u32, 2, 4]);
+    }
+    {
+        // The subfields of dynamic objects are not known from the doc mapping.
+        let mut docs = test_search_util(&test_sandbox, "customer:*").await;
+        docs.sort();
+        assert_eq!(&docs[..], &[5u32, 6]);
+    }
+    {
+        let docs = test_search_util(&test_sandbox, "customer.address:*").await;
+        assert_eq!(&docs[..], &[5u32]);
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 fn json_to_named_field_doc(doc_json: JsonValue) -> NamedFieldDocument {
     assert!(doc_json.is_object());
     let mut doc_map: BTreeMap<String, Vec<TantivyValue>> = BTreeMap::new();
@@ -1407,108 +1767,523 @@ async fn test_single_node_aggregation() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
-async fn test_single_node_aggregation_missing_fast_field() {
-    let index_id = "single-node-agg-2";
+async fn test_single_node_terms_aggregation_doc_count_error() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-doc-count-error";
     let doc_mapping_yaml = r#"
             field_mappings:
               - name: color
                 type: text
-              - name: price
-                type: f64
                 fast: true
         "#;
-    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
-        .await
-        .unwrap();
-    let docs = vec![
-        json!({"color": "blue", "price": 10.0}),
-        json!({"color": "blue", "price": 15.0}),
-        json!({"color": "green", "price": 10.0}),
-        json!({"color": "white", "price": 100.0}),
-        json!({"color": "white", "price": 1.0}),
-    ];
-    let agg_req = r#"
- {
-   "expensive_colors": {
-     "terms": {
-       "field": "color",
-       "order": {
-            "price_stats.max": "desc"
-       }
-     },
-     "aggs": {
-       "price_stats" : {
-          "stats": {
-              "field": "price"
-          }
-       }
-     }
-   }
- }"#;
-
-    test_sandbox.add_documents(docs.clone()).await.unwrap();
-    let search_request = SearchRequest {
-        index_id_patterns: vec![index_id.to_string()],
-        query_ast: qast_json_helper("*", &[]),
-        max_hits: 2,
-        aggregation_request: Some(agg_req.to_string()),
-        ..Default::default()
-    };
-    let single_node_error = single_node_search(
-        search_request,
-        test_sandbox.metastore(),
-        test_sandbox.storage_resolver(),
-    )
-    .await
-    .unwrap_err();
-    let SearchError::InvalidArgument(error_msg) = single_node_error else {
-        panic!();
-    };
-    assert!(error_msg.contains("Field \"color\" is not configured as a fast field"));
-    test_sandbox.assert_quit().await;
-}
-
-#[tokio::test]
-async fn test_single_node_with_ip_field() -> anyhow::Result<()> {
-    let index_id = "single-node-with-ip-field";
-    let doc_mapping_yaml = r#"
-            field_mappings:
-              - name: log
-                type: text
-              - name: host
-                type: ip
-        "#;
-    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["log"]).await?;
-    let docs = vec![
-        json!({"log": "User not found", "host": "192.168.0.1"}),
-        json!({"log": "Request failed", "host": "10.10.12.123"}),
-        json!({"log": "Request successful", "host": "10.10.11.125"}),
-        json!({"log": "Auth service error", "host": "2001:db8::1:0:0:1"}),
-        json!({"log": "Settings saved", "host": "::afff:4567:890a"}),
-        json!({"log": "Request failed", "host": "10.10.12.123"}),
-    ];
-    test_sandbox.add_documents(docs.clone()).await?;
-    {
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    // "blue" is the most frequent color overall but it is never the most frequent color of a
+    // split.
+    for top_color in ["green", "white", "red"] {
+        let docs = vec![
+            json!({"color": top_color}),
+            json!({"color": top_color}),
+            json!({"color": top_color}),
+            json!({"color": "blue"}),
+            json!({"color": "blue"}),
+        ];
+        test_sandbox.add_documents(docs).await?;
+    }
+    let search_colors = |split_size: u32| {
+        let agg_req = json!({
+            "colors": {
+                "terms": {
+                    "field": "color",
+                    "size": 1,
+                    "split_size": split_size,
+                }
+            }
+        });
         let search_request = SearchRequest {
             index_id_patterns: vec![index_id.to_string()],
             query_ast: qast_json_helper("*", &[]),
-            max_hits: 10,
+            max_hits: 0,
+            aggregation_request: Some(agg_req.to_string()),
             ..Default::default()
         };
-        let single_node_result = single_node_search(
+        single_node_search(
             search_request,
             test_sandbox.metastore(),
             test_sandbox.storage_resolver(),
         )
-        .await?;
-        assert_eq!(single_node_result.num_hits, 6);
-        assert_eq!(single_node_result.hits.len(), 6);
+    };
+    {
+        let search_response = search_colors(1).await?;
+        let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+        let colors = &agg_res_json["colors"];
+        assert_ne!(colors["buckets"][0]["key"], "blue");
+        assert_eq!(colors["buckets"][0]["doc_count"], 3);
+        assert!(colors["doc_count_error_upper_bound"].as_u64().unwrap() > 0);
+        assert_eq!(colors["sum_other_doc_count"], 12);
     }
     {
-        let search_request = SearchRequest {
-            index_id_patterns: vec![index_id.to_string()],
-            query_ast: qast_json_helper("10.10.11.125", &["host"]),
-            max_hits: 10,
+        let search_response = search_colors(10).await?;
+        let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+        let colors = &agg_res_json["colors"];
+        assert_eq!(colors["buckets"][0]["key"], "blue");
+        assert_eq!(colors["buckets"][0]["doc_count"], 6);
+        assert_eq!(colors["doc_count_error_upper_bound"], 0);
+        assert_eq!(colors["sum_other_doc_count"], 9);
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_sampled_aggregation() -> anyhow::Result<()> {
+    let index_id = "single-node-sampled-agg";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    let num_docs = 10_000;
+    let docs: Vec<JsonValue> = (0..num_docs)
+        .map(|doc_ord| {
+            let color = ["red", "red", "green", "blue"][doc_ord % 4];
+            json!({"color": color, "price": (doc_ord % 10) as f64})
+        })
+        .collect();
+    test_sandbox.add_documents(docs).await?;
+
+    let agg_req = json!({
+        "colors": {
+            "terms": {"field": "color", "order": {"_key": "asc"}},
+            "aggs": {
+                "num_prices": {"value_count": {"field": "price"}},
+                "avg_price": {"avg": {"field": "price"}}
+            }
+        },
+        "total_price": {"sum": {"field": "price"}}
+    });
+    let search_colors = |aggregation_sample_rate_ppm: Option<u32>| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 3,
+            aggregation_request: Some(agg_req.to_string()),
+            aggregation_sample_rate_ppm,
+            ..Default::default()
+        };
+        single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+    };
+    let exact_response = search_colors(None).await?;
+    assert_eq!(exact_response.aggregation_sample_rate, None);
+
+    let sampled_response = search_colors(Some(200_000)).await?;
+    assert_eq!(sampled_response.aggregation_sample_rate, Some(0.2));
+    assert_eq!(sampled_response.warnings.len(), 1);
+
+    // Hits are not sampled.
+    assert_eq!(sampled_response.num_hits, num_docs as u64);
+    assert_eq!(sampled_response.hits.len(), 3);
+
+    let exact_aggs: JsonValue = serde_json::from_str(&exact_response.aggregation.unwrap())?;
+    let sampled_aggs: JsonValue = serde_json::from_str(&sampled_response.aggregation.unwrap())?;
+
+    let assert_within_tolerance = |exact: &JsonValue, sampled: &JsonValue| {
+        let exact = exact.as_f64().unwrap();
+        let sampled = sampled.as_f64().unwrap();
+        assert!(
+            (sampled - exact).abs() <= exact * 0.15,
+            "sampled value {sampled} is too far from exact value {exact}"
+        );
+    };
+    for bucket_ord in 0..3 {
+        let exact_bucket = &exact_aggs["colors"]["buckets"][bucket_ord];
+        let sampled_bucket = &sampled_aggs["colors"]["buckets"][bucket_ord];
+        assert_eq!(exact_bucket["key"], sampled_bucket["key"]);
+        assert_within_tolerance(&exact_bucket["doc_count"], &sampled_bucket["doc_count"]);
+        assert_within_tolerance(
+            &exact_bucket["num_prices"]["value"],
+            &sampled_bucket["num_prices"]["value"],
+        );
+        // Averages are computed on the sample as is.
+        let sampled_avg_price = sampled_bucket["avg_price"]["value"].as_f64().unwrap();
+        assert!((3.5..=5.5).contains(&sampled_avg_price));
+    }
+    assert_within_tolerance(
+        &exact_aggs["total_price"]["value"],
+        &sampled_aggs["total_price"]["value"],
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_histogram_aggregation_bounds_and_missing() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-histogram";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    // Two splits, so that the buckets of the leaves have to be aligned when merged.
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue", "price": 12.0}),
+            json!({"color": "blue", "price": 18.0}),
+            json!({"color": "green"}),
+            json!({"color": "green"}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "white", "price": 34.0}),
+            json!({"color": "white", "price": 250.0}),
+            json!({"color": "red"}),
+        ])
+        .await?;
+
+    let search_histogram = |histogram: JsonValue| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(json!({ "prices": { "histogram": histogram } }).to_string()),
+            ..Default::default()
+        };
+        single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+    };
+    let buckets = |search_response: SearchResponse| -> Vec<(f64, u64)> {
+        let agg_res_json: JsonValue =
+            serde_json::from_str(&search_response.aggregation.unwrap()).unwrap();
+        agg_res_json["prices"]["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|bucket| {
+                (
+                    bucket["key"].as_f64().unwrap(),
+                    bucket["doc_count"].as_u64().unwrap(),
+                )
+            })
+            .collect()
+    };
+    {
+        // The extended bounds emit empty buckets up to 60, the hard bounds clip the 250 price.
+        let search_response = search_histogram(json!({
+            "field": "price",
+            "interval": 10.0,
+            "extended_bounds": {"min": 0.0, "max": 60.0},
+            "hard_bounds": {"min": 0.0, "max": 100.0},
+        }))
+        .await?;
+        assert_eq!(
+            buckets(search_response),
+            [
+                (0.0, 0),
+                (10.0, 2),
+                (20.0, 0),
+                (30.0, 1),
+                (40.0, 0),
+                (50.0, 0),
+                (60.0, 0)
+            ]
+        );
+    }
+    {
+        // The three documents without price fall into the bucket of the missing value.
+        let search_response = search_histogram(json!({
+            "field": "price",
+            "interval": 10.0,
+            "hard_bounds": {"min": 0.0, "max": 100.0},
+            "missing": 5.0,
+        }))
+        .await?;
+        assert_eq!(
+            buckets(search_response),
+            [(0.0, 3), (10.0, 2), (20.0, 0), (30.0, 1)]
+        );
+    }
+    {
+        // The missing value can create a bucket of its own.
+        let search_response = search_histogram(json!({
+            "field": "price",
+            "interval": 10.0,
+            "hard_bounds": {"min": 0.0, "max": 100.0},
+            "missing": 95.0,
+        }))
+        .await?;
+        assert_eq!(
+            buckets(search_response),
+            [(10.0, 2), (20.0, 0), (30.0, 1), (90.0, 3)]
+        );
+    }
+    {
+        // The missing value is clipped by the hard bounds.
+        let search_response = search_histogram(json!({
+            "field": "price",
+            "interval": 10.0,
+            "hard_bounds": {"min": 0.0, "max": 100.0},
+            "missing": 150.0,
+        }))
+        .await?;
+        assert_eq!(buckets(search_response), [(10.0, 2), (20.0, 0), (30.0, 1)]);
+    }
+    {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(
+                json!({
+                    "colors": {
+                        "terms": {"field": "color"},
+                        "aggs": {
+                            "prices": {
+                                "histogram": {"field": "price", "interval": 10.0, "missing": 0.0}
+                            }
+                        }
+                    }
+                })
+                .to_string(),
+            ),
+            ..Default::default()
+        };
+        let search_error = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            search_error,
+            SearchError::InvalidAggregationRequest(_)
+        ));
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_histogram_pipeline_aggregations() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-pipeline";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"price": 1.0}),
+            json!({"price": 12.0}),
+            json!({"price": 14.0}),
+            json!({"price": 21.0}),
+            json!({"price": 31.0}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![json!({"price": 33.0}), json!({"price": 36.0})])
+        .await?;
+
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(
+            json!({
+                "prices": {
+                    "histogram": {"field": "price", "interval": 10.0},
+                    "aggs": {
+                        "count_deriv": {"derivative": {"buckets_path": "_count"}},
+                        "price_sum": {"sum": {"field": "price"}},
+                        "price_sum_mavg": {
+                            "moving_avg": {"buckets_path": "price_sum", "window": 2}
+                        }
+                    }
+                }
+            })
+            .to_string(),
+        ),
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    let buckets: Vec<(f64, Option<f64>, Option<f64>)> = agg_res_json["prices"]["buckets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|bucket| {
+            (
+                bucket["key"].as_f64().unwrap(),
+                bucket["count_deriv"]["value"].as_f64(),
+                bucket["price_sum_mavg"]["value"].as_f64(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            (0.0, None, None),
+            (10.0, Some(1.0), Some(1.0)),
+            (20.0, Some(-1.0), Some(13.5)),
+            (30.0, Some(2.0), Some(23.5)),
+        ]
+    );
+    {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(
+                json!({
+                    "count_deriv": {"derivative": {"buckets_path": "_count"}}
+                })
+                .to_string(),
+            ),
+            ..Default::default()
+        };
+        let search_error = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            search_error,
+            SearchError::InvalidAggregationRequest(_)
+        ));
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_aggregation_missing_fast_field() {
+    let index_id = "single-node-agg-2";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"color": "blue", "price": 10.0}),
+        json!({"color": "blue", "price": 15.0}),
+        json!({"color": "green", "price": 10.0}),
+        json!({"color": "white", "price": 100.0}),
+        json!({"color": "white", "price": 1.0}),
+    ];
+    let agg_req = r#"
+ {
+   "expensive_colors": {
+     "terms": {
+       "field": "color",
+       "order": {
+            "price_stats.max": "desc"
+       }
+     },
+     "aggs": {
+       "price_stats" : {
+          "stats": {
+              "field": "price"
+          }
+       }
+     }
+   }
+ }"#;
+
+    test_sandbox.add_documents(docs.clone()).await.unwrap();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 2,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    let SearchError::InvalidArgument(error_msg) = single_node_error else {
+        panic!();
+    };
+    assert!(error_msg.contains("Field \"color\" is not configured as a fast field"));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_single_node_with_ip_field() -> anyhow::Result<()> {
+    let index_id = "single-node-with-ip-field";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: log
+                type: text
+              - name: host
+                type: ip
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["log"]).await?;
+    let docs = vec![
+        json!({"log": "User not found", "host": "192.168.0.1"}),
+        json!({"log": "Request failed", "host": "10.10.12.123"}),
+        json!({"log": "Request successful", "host": "10.10.11.125"}),
+        json!({"log": "Auth service error", "host": "2001:db8::1:0:0:1"}),
+        json!({"log": "Settings saved", "host": "::afff:4567:890a"}),
+        json!({"log": "Request failed", "host": "10.10.12.123"}),
+    ];
+    test_sandbox.add_documents(docs.clone()).await?;
+    {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert_eq!(single_node_result.num_hits, 6);
+        assert_eq!(single_node_result.hits.len(), 6);
+    }
+    {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("10.10.11.125", &["host"]),
+            max_hits: 10,
             ..Default::default()
         };
         let single_node_result = single_node_search(
@@ -1702,6 +2477,7 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let search_response = leaf_list_terms(
             searcher_context.clone(),
             &request,
+            None,
             test_sandbox.storage(),
             &splits_offsets,
         )
@@ -1723,6 +2499,7 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let search_response = leaf_list_terms(
             searcher_context.clone(),
             &request,
+            None,
             test_sandbox.storage(),
             &splits_offsets,
         )
@@ -1744,6 +2521,7 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let search_response = leaf_list_terms(
             searcher_context.clone(),
             &request,
+            None,
             test_sandbox.storage(),
             &splits_offsets,
         )
@@ -1765,6 +2543,7 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let search_response = leaf_list_terms(
             searcher_context.clone(),
             &request,
+            None,
             test_sandbox.storage(),
             &splits_offsets,
         )
@@ -1773,10 +2552,36 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
         let terms = collect_str_terms(search_response);
         assert_eq!(terms, &["beagle"]);
     }
-    test_sandbox.assert_quit().await;
-    Ok(())
-}
-
+    {
+        let request = ListTermsRequest {
+            index_id_patterns: vec![test_sandbox.index_uid().index_id.to_string()],
+            field: "title".to_string(),
+            start_key: None,
+            end_key: None,
+            start_timestamp: None,
+            end_timestamp: None,
+            max_hits: Some(1),
+        };
+        let search_filter = ListTermsSearchFilter {
+            doc_mapper: test_sandbox.doc_mapper(),
+            query_ast: qast_helper("url:snoopy", &[]),
+        };
+        let search_response = leaf_list_terms(
+            searcher_context.clone(),
+            &request,
+            Some(&search_filter),
+            test_sandbox.storage(),
+            &splits_offsets,
+        )
+        .await
+        .unwrap();
+        let terms = collect_str_terms(search_response);
+        assert_eq!(terms, &["snoopy"]);
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_find_trace_ids_collector() {
     let index_id = "single-node-find-trace-ids-collector";
@@ -1903,3 +2708,1016 @@ fn test_global_doc_address_ser_deser() {
     let doc_address_deser: GlobalDocAddress = doc_address_string.parse().unwrap();
     assert_eq!(doc_address_deser, doc_address);
 }
+
+#[tokio::test]
+async fn test_leaf_search_cache_only_recomputes_new_splits() {
+    let index_id = "leaf-search-cache-new-splits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
+        .await
+        .unwrap();
+    async fn list_splits_offsets(test_sandbox: &TestSandbox) -> Vec<SplitIdAndFooterOffsets> {
+        test_sandbox
+            .metastore()
+            .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap()
+            .iter()
+            .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+            .collect()
+    }
+    // An aggregation-only request, which the leaf does not rewrite before looking up the cache.
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(r#"{"avg_price": {"avg": {"field": "price"}}}"#.to_string()),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+    let leaf_search_splits = |splits: Vec<SplitIdAndFooterOffsets>| {
+        leaf_search(
+            searcher_context.clone(),
+            Arc::new(search_request.clone()),
+            test_sandbox.storage(),
+            splits,
+            test_sandbox.doc_mapper(),
+            searcher_context.get_aggregation_limits(),
+            searcher_context.new_leaf_request_memory_budget(),
+            searcher_context.new_leaf_request_interrupt(),
+        )
+    };
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue", "price": 10.0}),
+            json!({"color": "green", "price": 20.0}),
+        ])
+        .await
+        .unwrap();
+    test_sandbox
+        .add_documents(vec![json!({"color": "white", "price": 30.0})])
+        .await
+        .unwrap();
+    let old_splits = list_splits_offsets(&test_sandbox).await;
+    assert_eq!(old_splits.len(), 2);
+
+    let leaf_search_response = leaf_search_splits(old_splits.clone()).await.unwrap();
+    assert_eq!(leaf_search_response.num_hits, 3);
+
+    // Tamper with the cached responses of the old splits: if the next search reads them from the
+    // cache instead of recomputing them, the tampered hit counts show up in its response.
+    for split in &old_splits {
+        let mut cached_response = searcher_context
+            .leaf_search_cache
+            .get(split.clone(), search_request.clone())
+            .expect("the leaf search response of the split should be cached");
+        cached_response.num_hits += 1_000;
+        searcher_context.leaf_search_cache.put(
+            split.clone(),
+            search_request.clone(),
+            cached_response,
+        );
+    }
+    test_sandbox
+        .add_documents(vec![json!({"color": "red", "price": 40.0})])
+        .await
+        .unwrap();
+    let splits = list_splits_offsets(&test_sandbox).await;
+    assert_eq!(splits.len(), 3);
+    let new_split = splits
+        .iter()
+        .find(|split| !old_splits.contains(split))
+        .unwrap()
+        .clone();
+    assert!(searcher_context
+        .leaf_search_cache
+        .get(new_split.clone(), search_request.clone())
+        .is_none());
+
+    let leaf_search_response = leaf_search_splits(splits).await.unwrap();
+    // 2 + 1 docs from the cached old splits, 1 doc from the new split.
+    assert_eq!(leaf_search_response.num_hits, 2_000 + 3 + 1);
+    assert!(searcher_context
+        .leaf_search_cache
+        .get(new_split, search_request.clone())
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_search_track_total_hits() {
+    let index_id = "leaf-search-track-total-hits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: description
+                type: text
+              - name: ts
+                type: datetime
+                fast: true
+            timestamp_field: ts
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["description"])
+        .await
+        .unwrap();
+    for start_timestamp in [10, 20, 30] {
+        test_sandbox
+            .add_documents(vec![
+                json!({"description": "city", "ts": start_timestamp}),
+                json!({"description": "city", "ts": start_timestamp + 1}),
+            ])
+            .await
+            .unwrap();
+    }
+    let splits: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap()
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    assert_eq!(splits.len(), 3);
+
+    // Searching one split at a time makes the splits skipped by the leaf deterministic.
+    let searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 1,
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    let leaf_search_num_hits = |track_total_hits: Option<u64>| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("city", &["description"]),
+            max_hits: 1,
+            sort_fields: vec![SortField {
+                field_name: "ts".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                sort_mode: None,
+            }],
+            track_total_hits,
+            ..Default::default()
+        };
+        let leaf_search_fut = leaf_search(
+            searcher_context.clone(),
+            Arc::new(search_request),
+            test_sandbox.storage(),
+            splits.clone(),
+            test_sandbox.doc_mapper(),
+            searcher_context.get_aggregation_limits(),
+            searcher_context.new_leaf_request_memory_budget(),
+            searcher_context.new_leaf_request_interrupt(),
+        );
+        async move { leaf_search_fut.await.unwrap().num_hits }
+    };
+    // The most recent split holds the top hit, the other splits are only searched to count their
+    // hits until more than `track_total_hits` hits were counted.
+    assert_eq!(leaf_search_num_hits(None).await, 6);
+    assert_eq!(leaf_search_num_hits(Some(1)).await, 2);
+    assert_eq!(leaf_search_num_hits(Some(2)).await, 4);
+    assert_eq!(leaf_search_num_hits(Some(6)).await, 6);
+
+    // The root reports the threshold as a lower bound of the number of hits once it is exceeded.
+    for (track_total_hits, expected_num_hits, expected_num_hits_relation) in [
+        (None, 6, NumHitsRelation::EqualTo),
+        (Some(1), 1, NumHitsRelation::GreaterThanOrEqualTo),
+        (Some(6), 6, NumHitsRelation::EqualTo),
+    ] {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("city", &["description"]),
+            max_hits: 1,
+            track_total_hits,
+            ..Default::default()
+        };
+        let search_response = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, expected_num_hits);
+        assert_eq!(
+            search_response.num_hits_relation(),
+            expected_num_hits_relation
+        );
+        assert_eq!(search_response.hits.len(), 1);
+    }
+
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_leaf_search_aggregation_spill() {
+    let index_id = "leaf-search-aggregation-spill";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: size
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
+        .await
+        .unwrap();
+    for split_ord in 0..4u64 {
+        let docs = (0..50u64)
+            .map(|doc_ord| {
+                json!({"color": format!("color-{}", (split_ord * 7 + doc_ord) % 30), "size": doc_ord})
+            })
+            .collect();
+        test_sandbox.add_documents(docs).await.unwrap();
+    }
+    let splits: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap()
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    assert_eq!(splits.len(), 4);
+
+    let spill_dir = tempfile::tempdir().unwrap();
+    // With a 1 byte threshold, the intermediate results are spilled after every split.
+    let searcher_config = SearcherConfig {
+        aggregation_spill: AggregationSpillConfig {
+            in_memory_threshold: ByteSize::b(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(
+        SearcherContext::new(searcher_config, None)
+            .with_aggregation_spill_dir(spill_dir.path().to_path_buf()),
+    );
+    let leaf_search_aggregation = |searcher_context: Arc<SearcherContext>, allow_disk_use: bool| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(
+                json!({
+                    "colors": {
+                        "terms": { "field": "color", "size": 100 },
+                        "aggs": { "size_stats": { "stats": { "field": "size" } } }
+                    }
+                })
+                .to_string(),
+            ),
+            allow_disk_use,
+            ..Default::default()
+        };
+        let leaf_search_fut = leaf_search(
+            searcher_context.clone(),
+            Arc::new(search_request),
+            test_sandbox.storage(),
+            splits.clone(),
+            test_sandbox.doc_mapper(),
+            searcher_context.get_aggregation_limits(),
+            searcher_context.new_leaf_request_memory_budget(),
+            searcher_context.new_leaf_request_interrupt(),
+        );
+        async move {
+            let leaf_search_response = leaf_search_fut.await.unwrap();
+            assert_eq!(leaf_search_response.num_hits, 200);
+            assert!(leaf_search_response.failed_splits.is_empty());
+            let intermediate_aggregation_result = leaf_search_response
+                .intermediate_aggregation_result
+                .unwrap();
+            postcard::from_bytes::<IntermediateAggregationResults>(&intermediate_aggregation_result)
+                .unwrap()
+        }
+    };
+    let num_spills_before = SEARCH_METRICS.leaf_aggregation_spills_total.get();
+    let in_memory_aggregation = leaf_search_aggregation(searcher_context.clone(), false).await;
+    assert_eq!(
+        SEARCH_METRICS.leaf_aggregation_spills_total.get(),
+        num_spills_before
+    );
+    let spilled_aggregation = leaf_search_aggregation(searcher_context.clone(), true).await;
+    assert!(SEARCH_METRICS.leaf_aggregation_spills_total.get() > num_spills_before);
+    assert_eq!(spilled_aggregation, in_memory_aggregation);
+
+    // The spill file is released with the request.
+    let aggregation_spill_disk = searcher_context
+        .aggregation_spill_disk_opt
+        .as_ref()
+        .unwrap();
+    assert_eq!(aggregation_spill_disk.num_bytes_used(), 0);
+
+    // The runs that cannot be spilled are kept in memory, whether the disk budget is exhausted or
+    // the spill file cannot be created.
+    let exhausted_searcher_config = SearcherConfig {
+        aggregation_spill: AggregationSpillConfig {
+            in_memory_threshold: ByteSize::b(1),
+            max_disk_usage: ByteSize::b(1),
+        },
+        ..Default::default()
+    };
+    let exhausted_searcher_context = Arc::new(
+        SearcherContext::new(exhausted_searcher_config, None)
+            .with_aggregation_spill_dir(spill_dir.path().to_path_buf()),
+    );
+    let unspilled_aggregation = leaf_search_aggregation(exhausted_searcher_context, true).await;
+    assert_eq!(unspilled_aggregation, in_memory_aggregation);
+
+    let not_a_dir = spill_dir.path().join("not-a-dir");
+    std::fs::write(&not_a_dir, b"").unwrap();
+    let failing_searcher_context = Arc::new(
+        SearcherContext::new((*searcher_context.searcher_config()).clone(), None)
+            .with_aggregation_spill_dir(not_a_dir.join("spill")),
+    );
+    let unspilled_aggregation = leaf_search_aggregation(failing_searcher_context, true).await;
+    assert_eq!(unspilled_aggregation, in_memory_aggregation);
+
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_leaf_search_memory_limit_exceeded() {
+    let index_id = "leaf-search-memory-limit-exceeded";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: user_id
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["user_id"])
+        .await
+        .unwrap();
+    let docs: Vec<JsonValue> = (0..1_000u64)
+        .map(|user_id| json!({"user_id": user_id}))
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+    let splits: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap()
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+
+    let searcher_config = SearcherConfig {
+        search_memory_pool_size: ByteSize::kb(64),
+        leaf_request_memory_budget: ByteSize::kb(2),
+        ..Default::default()
+    };
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    let leaf_search_with_budget = |search_request: SearchRequest| {
+        leaf_search(
+            searcher_context.clone(),
+            Arc::new(search_request),
+            test_sandbox.storage(),
+            splits.clone(),
+            test_sandbox.doc_mapper(),
+            searcher_context.get_aggregation_limits(),
+            searcher_context.new_leaf_request_memory_budget(),
+            searcher_context.new_leaf_request_interrupt(),
+        )
+    };
+    // One bucket per user: the intermediate aggregation result of the split is far larger than
+    // the budget of the request.
+    let terms_aggregation_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(
+            r#"{"users": {"terms": {"field": "user_id", "size": 1000}}}"#.to_string(),
+        ),
+        ..Default::default()
+    };
+    // The top-K collector of the split needs a buffer of ten times the number of hits.
+    let many_hits_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 1_000,
+        ..Default::default()
+    };
+    let small_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("user_id:42", &[]),
+        max_hits: 1,
+        ..Default::default()
+    };
+    let (terms_aggregation_response, many_hits_response, small_responses) = tokio::join!(
+        leaf_search_with_budget(terms_aggregation_request),
+        leaf_search_with_budget(many_hits_request),
+        futures::future::join_all((0..4).map(|_| leaf_search_with_budget(small_request.clone()))),
+    );
+    for leaf_search_response in [terms_aggregation_response, many_hits_response] {
+        let leaf_search_response = leaf_search_response.unwrap();
+        assert_eq!(leaf_search_response.num_successful_splits, 0);
+        assert_eq!(leaf_search_response.failed_splits.len(), 1);
+
+        let failed_split = &leaf_search_response.failed_splits[0];
+        assert_eq!(
+            failed_split.error_code(),
+            SplitSearchErrorCode::MemoryLimitExceeded
+        );
+        assert!(failed_split
+            .error
+            .contains("leaf request memory budget of 2.0 KB exhausted"));
+
+        let search_error =
+            SearchError::from_split_errors(&leaf_search_response.failed_splits).unwrap();
+        assert!(matches!(search_error, SearchError::MemoryLimitExceeded(_)));
+    }
+    for small_response in small_responses {
+        let small_response = small_response.unwrap();
+        assert!(small_response.failed_splits.is_empty());
+        assert_eq!(small_response.num_hits, 1);
+        assert_eq!(small_response.partial_hits.len(), 1);
+    }
+    // Every leaf request returned its memory to the pool.
+    assert_eq!(searcher_context.search_memory_pool.num_bytes_used(), 0);
+}
+
+#[tokio::test]
+async fn test_leaf_search_cancelled_request() {
+    let index_id = "leaf-search-cancelled-request";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    let docs: Vec<JsonValue> = (0..1_000)
+        .map(|doc_id| json!({"body": format!("doc {doc_id}")}))
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let splits: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap()
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("doc", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let search_interrupt = searcher_context.new_leaf_request_interrupt();
+    // The root searcher gave up on the request: the split searches must not run.
+    search_interrupt.cancel();
+
+    let leaf_search_response = leaf_search(
+        searcher_context.clone(),
+        Arc::new(search_request),
+        test_sandbox.storage(),
+        splits,
+        test_sandbox.doc_mapper(),
+        searcher_context.get_aggregation_limits(),
+        searcher_context.new_leaf_request_memory_budget(),
+        search_interrupt,
+    )
+    .await
+    .unwrap();
+    assert_eq!(leaf_search_response.num_hits, 0);
+    assert!(leaf_search_response.partial_hits.is_empty());
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+    assert!(leaf_search_response.failed_splits[0]
+        .error
+        .contains("leaf search request was cancelled"));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_leaf_search_split_not_found() {
+    let index_id = "leaf-search-split-not-found";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    for split_ord in 0..3 {
+        let docs = vec![json!({"body": format!("split {split_ord}")})];
+        test_sandbox.add_documents(docs).await.unwrap();
+    }
+    let splits: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await
+        .unwrap()
+        .collect_splits()
+        .await
+        .unwrap()
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    assert_eq!(splits.len(), 3);
+
+    // The split is garbage collected after having been listed.
+    let missing_split_id = splits[1].split_id.clone();
+    test_sandbox
+        .storage()
+        .delete(Path::new(&format!("{missing_split_id}.split")))
+        .await
+        .unwrap();
+
+    let searcher_context = Arc::new(SearcherContext::for_test());
+
+    let num_splits_not_found_before = SEARCH_METRICS.split_not_found_total.get();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("split", &["body"]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let leaf_search_response = leaf_search(
+        searcher_context.clone(),
+        Arc::new(search_request),
+        test_sandbox.storage(),
+        splits,
+        test_sandbox.doc_mapper(),
+        searcher_context.get_aggregation_limits(),
+        searcher_context.new_leaf_request_memory_budget(),
+        searcher_context.new_leaf_request_interrupt(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(leaf_search_response.num_hits, 2);
+    assert_eq!(leaf_search_response.partial_hits.len(), 2);
+    assert_eq!(leaf_search_response.num_successful_splits, 2);
+    assert_eq!(leaf_search_response.failed_splits.len(), 1);
+
+    let failed_split = &leaf_search_response.failed_splits[0];
+    assert_eq!(failed_split.split_id, missing_split_id);
+    assert_eq!(
+        failed_split.error_code(),
+        SplitSearchErrorCode::SplitNotFound
+    );
+    assert!(!failed_split.retryable_error);
+    assert!(SEARCH_METRICS.split_not_found_total.get() > num_splits_not_found_before);
+
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_reload_searcher_config() {
+    let searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 1,
+        ..Default::default()
+    };
+    let searcher_context = SearcherContext::new(searcher_config.clone(), None);
+
+    // A split search in flight holds the only permit.
+    let search_permit_provider = &searcher_context.search_permit_provider;
+    let in_flight_permit = search_permit_provider.get_permit("index").await;
+    let mut permit_futs: Vec<_> = (0..3)
+        .map(|_| search_permit_provider.get_permit("index"))
+        .collect();
+    assert!((&mut permit_futs[0]).now_or_never().is_none());
+
+    let new_searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 3,
+        aggregation_bucket_limit: 100,
+        max_query_length: ByteSize::kib(1),
+        ..searcher_config.clone()
+    };
+    let reload = searcher_context
+        .reload_searcher_config(new_searcher_config)
+        .unwrap();
+    assert_eq!(
+        reload.applied,
+        [
+            "max_num_concurrent_split_searches",
+            "aggregation_bucket_limit"
+        ]
+    );
+    assert_eq!(reload.ignored, ["max_query_length"]);
+
+    // The waiting split searches honor the new limit right away, next to the one in flight.
+    let _permits: Vec<_> = permit_futs
+        .drain(..2)
+        .map(|permit_fut| permit_fut.now_or_never().unwrap())
+        .collect();
+    assert!((&mut permit_futs[0]).now_or_never().is_none());
+    drop(in_flight_permit);
+    let _permit = permit_futs.pop().unwrap().now_or_never().unwrap();
+
+    let reloaded_searcher_config = searcher_context.searcher_config();
+    assert_eq!(
+        reloaded_searcher_config.max_num_concurrent_split_searches,
+        3
+    );
+    assert_eq!(reloaded_searcher_config.aggregation_bucket_limit, 100);
+    assert_eq!(
+        reloaded_searcher_config.max_query_length,
+        searcher_config.max_query_length
+    );
+
+    // Reloading the same config is a no-op.
+    let reload = searcher_context
+        .reload_searcher_config((*reloaded_searcher_config).clone())
+        .unwrap();
+    assert!(reload.applied.is_empty());
+    assert!(reload.ignored.is_empty());
+
+    // Invalid configs are rejected as a whole.
+    let invalid_searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 10,
+        leaf_request_memory_budget: ByteSize::gb(3),
+        ..searcher_config
+    };
+    searcher_context
+        .reload_searcher_config(invalid_searcher_config)
+        .unwrap_err();
+    assert_eq!(
+        searcher_context
+            .searcher_config()
+            .max_num_concurrent_split_searches,
+        3
+    );
+}
+
+#[test]
+fn test_patch_searcher_config() {
+    let searcher_config = SearcherConfig {
+        max_num_concurrent_split_searches: 10,
+        aggregation_bucket_limit: 100,
+        ..Default::default()
+    };
+    let searcher_context = SearcherContext::new(searcher_config, None);
+
+    // The fields absent from the patch keep their current value.
+    let reload = searcher_context
+        .patch_searcher_config(serde_json::json!({
+            "max_num_concurrent_split_streams": 7,
+        }))
+        .unwrap();
+    assert_eq!(reload.applied, ["max_num_concurrent_split_streams"]);
+    assert!(reload.ignored.is_empty());
+
+    let patched_searcher_config = searcher_context.searcher_config();
+    assert_eq!(
+        patched_searcher_config.max_num_concurrent_split_searches,
+        10
+    );
+    assert_eq!(patched_searcher_config.aggregation_bucket_limit, 100);
+    assert_eq!(patched_searcher_config.max_num_concurrent_split_streams, 7);
+
+    // `null` resets a field to its default value.
+    let reload = searcher_context
+        .patch_searcher_config(serde_json::json!({
+            "aggregation_bucket_limit": null,
+        }))
+        .unwrap();
+    assert_eq!(reload.applied, ["aggregation_bucket_limit"]);
+    assert_eq!(
+        searcher_context.searcher_config().aggregation_bucket_limit,
+        SearcherConfig::default().aggregation_bucket_limit
+    );
+
+    // Unknown fields are rejected and leave the config untouched.
+    searcher_context
+        .patch_searcher_config(serde_json::json!({
+            "max_num_concurrent_split_searches": 20,
+            "unknown_field": 1,
+        }))
+        .unwrap_err();
+    assert_eq!(
+        searcher_context
+            .searcher_config()
+            .max_num_concurrent_split_searches,
+        10
+    );
+}
+
+async fn single_node_suggest(
+    suggest_request: SuggestRequest,
+    searcher_config: SearcherConfig,
+    test_sandbox: &TestSandbox,
+) -> crate::Result<SuggestResponse> {
+    let socket_addr = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 7280u16);
+    let searcher_pool = SearcherPool::default();
+    let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool.clone()));
+    let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+    let search_service = Arc::new(SearchServiceImpl::new(
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+        cluster_client.clone(),
+        searcher_context.clone(),
+    ));
+    let search_service_client = SearchServiceClient::from_service(search_service, socket_addr);
+    searcher_pool.insert(socket_addr, search_service_client);
+    root_suggest(
+        suggest_request,
+        &searcher_context,
+        test_sandbox.metastore(),
+        &cluster_client,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_suggest_field_names() {
+    let index_id = "suggest-field-names";
+    let doc_mapping_yaml = r#"
+            mode: dynamic
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+              - name: severity_text
+                type: text
+                tokenizer: raw
+              - name: body
+                type: text
+            "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"service": "searcher", "severity_text": "INFO", "server_ip": "10.0.0.1", "body": "ok"}),
+        json!({"service": "indexer", "severity_text": "WARN", "body": "slow"}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let suggest = |prefix: &str, max_suggestions: usize| {
+        let suggest_request = SuggestRequest {
+            index_id: index_id.to_string(),
+            prefix: prefix.to_string(),
+            field_opt: None,
+            max_suggestions,
+        };
+        single_node_suggest(suggest_request, SearcherConfig::default(), &test_sandbox)
+    };
+    let suggest_response = suggest("se", 10).await.unwrap();
+    assert_eq!(
+        suggest_response.suggestions,
+        ["server_ip", "service", "severity_text"]
+    );
+    let suggest_response = suggest("se", 2).await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["server_ip", "service"]);
+
+    let suggest_response = suggest("x", 10).await.unwrap();
+    assert!(suggest_response.suggestions.is_empty());
+
+    let suggest_request = SuggestRequest {
+        index_id: "index-does-not-exist".to_string(),
+        prefix: String::new(),
+        field_opt: None,
+        max_suggestions: 10,
+    };
+    let suggest_error =
+        single_node_suggest(suggest_request, SearcherConfig::default(), &test_sandbox)
+            .await
+            .unwrap_err();
+    assert!(matches!(suggest_error, SearchError::IndexesNotFound { .. }));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_suggest_field_values() {
+    let index_id = "suggest-field-values";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+              - name: status
+                type: u64
+            "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"service": "searcher", "status": 200}),
+        json!({"service": "search", "status": 200}),
+        json!({"service": "server", "status": 500}),
+        json!({"service": "indexer", "status": 200}),
+        json!({"service": "search", "status": 404}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let suggest = |field: &str, prefix: &str, max_suggestions: usize| {
+        let suggest_request = SuggestRequest {
+            index_id: index_id.to_string(),
+            prefix: prefix.to_string(),
+            field_opt: Some(field.to_string()),
+            max_suggestions,
+        };
+        single_node_suggest(suggest_request, SearcherConfig::default(), &test_sandbox)
+    };
+    let suggest_response = suggest("service", "se", 10).await.unwrap();
+    assert_eq!(
+        suggest_response.suggestions,
+        ["search", "searcher", "server"]
+    );
+    let suggest_response = suggest("service", "sea", 1).await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["search"]);
+
+    let suggest_response = suggest("service", "", 10).await.unwrap();
+    assert_eq!(
+        suggest_response.suggestions,
+        ["indexer", "search", "searcher", "server"]
+    );
+    let suggest_error = suggest("status", "2", 10).await.unwrap_err();
+    assert!(matches!(suggest_error, SearchError::InvalidArgument(_)));
+
+    let suggest_error = suggest("unknown", "", 10).await.unwrap_err();
+    assert!(matches!(suggest_error, SearchError::InvalidArgument(_)));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_suggest_apply_search_filter() {
+    let index_id = "suggest-search-filter";
+    let doc_mapping_yaml = r#"
+            mode: dynamic
+            field_mappings:
+              - name: tenant
+                type: text
+                tokenizer: raw
+              - name: service
+                type: text
+                tokenizer: raw
+            "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"tenant": "acme", "service": "searcher", "span_kind": "server"}),
+        json!({"tenant": "globex", "service": "search", "secret": "hunter2"}),
+        json!({"tenant": "acme", "service": "indexer"}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let mut metastore = test_sandbox.metastore();
+    let index_config = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap()
+        .index_config;
+    let mut search_settings = index_config.search_settings.clone();
+    search_settings.search_filter = Some(SearchFilter::QueryString("tenant:acme".to_string()));
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        test_sandbox.index_uid(),
+        &search_settings,
+        &index_config.retention_policy_opt,
+        &index_config.indexing_settings,
+        &index_config.doc_mapping,
+    )
+    .unwrap();
+    metastore.update_index(update_index_request).await.unwrap();
+
+    let suggest = |field_opt: Option<&str>, prefix: &str| {
+        let suggest_request = SuggestRequest {
+            index_id: index_id.to_string(),
+            prefix: prefix.to_string(),
+            field_opt: field_opt.map(|field| field.to_string()),
+            max_suggestions: 10,
+        };
+        single_node_suggest(suggest_request, SearcherConfig::default(), &test_sandbox)
+    };
+    let suggest_response = suggest(Some("service"), "se").await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["searcher"]);
+
+    let suggest_response = suggest(Some("service"), "").await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["indexer", "searcher"]);
+
+    // The dynamic fields may only exist in documents ruled out by the filter.
+    let suggest_response = suggest(None, "s").await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["service"]);
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_suggest_only_reads_recent_splits() {
+    let index_id = "suggest-recent-splits";
+    let doc_mapping_yaml = r#"
+            mode: dynamic
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+              - name: ts
+                type: datetime
+                fast: true
+            timestamp_field: ts
+            "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"])
+        .await
+        .unwrap();
+    let old_docs = vec![json!({"service": "legacy-api", "legacy_field": "a", "ts": 1_000})];
+    test_sandbox.add_documents(old_docs).await.unwrap();
+    let new_docs = vec![json!({"service": "api-gateway", "ts": 2_000})];
+    test_sandbox.add_documents(new_docs).await.unwrap();
+
+    let suggest = |field_opt: Option<&str>, max_num_splits: usize| {
+        let suggest_request = SuggestRequest {
+            index_id: index_id.to_string(),
+            prefix: String::new(),
+            field_opt: field_opt.map(|field| field.to_string()),
+            max_suggestions: 10,
+        };
+        let mut searcher_config = SearcherConfig::default();
+        searcher_config.suggest.max_num_splits = max_num_splits;
+        single_node_suggest(suggest_request, searcher_config, &test_sandbox)
+    };
+    let suggest_response = suggest(Some("service"), 1).await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["api-gateway"]);
+
+    let suggest_response = suggest(Some("service"), 2).await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["api-gateway", "legacy-api"]);
+
+    let suggest_response = suggest(None, 1).await.unwrap();
+    assert_eq!(suggest_response.suggestions, ["service", "ts"]);
+
+    let suggest_response = suggest(None, 2).await.unwrap();
+    assert_eq!(
+        suggest_response.suggestions,
+        ["legacy_field", "service", "ts"]
+    );
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_suggest_rate_limit() {
+    let index_id = "suggest-rate-limit";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+            "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"])
+        .await
+        .unwrap();
+    let searcher_context = SearcherContext::new(
+        SearcherConfig {
+            suggest: SuggestConfig {
+                max_num_splits: 3,
+                max_requests_per_sec: 1,
+            },
+            ..Default::default()
+        },
+        None,
+    );
+    let cluster_client = ClusterClient::new(SearchJobPlacer::default());
+    let suggest_request = SuggestRequest {
+        index_id: index_id.to_string(),
+        prefix: String::new(),
+        field_opt: None,
+        max_suggestions: 10,
+    };
+    root_suggest(
+        suggest_request.clone(),
+        &searcher_context,
+        test_sandbox.metastore(),
+        &cluster_client,
+    )
+    .await
+    .unwrap();
+    let suggest_error = root_suggest(
+        suggest_request,
+        &searcher_context,
+        test_sandbox.metastore(),
+        &cluster_client,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(suggest_error, SearchError::TooManyRequests));
+    test_sandbox.assert_quit().await;
+}
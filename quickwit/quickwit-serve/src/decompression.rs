@@ -17,10 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
 use std::sync::OnceLock;
 
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
+use bytesize::ByteSize;
 use flate2::read::GzDecoder;
 use quickwit_common::metrics::{GaugeGuard, MEMORY_METRICS};
 use quickwit_common::thread_pool::run_cpu_intensive;
@@ -35,6 +36,32 @@ fn get_ingest_load_shield() -> &'static LoadShield {
     LOAD_SHIELD.get_or_init(|| LoadShield::new("ingest"))
 }
 
+/// Returns an error if the content encoding is not supported.
+fn check_encoding(encoding: Option<&str>) -> Result<(), warp::Rejection> {
+    match encoding {
+        None | Some("gzip" | "x-gzip" | "zstd") => Ok(()),
+        Some(encoding) => Err(warp::reject::custom(UnsupportedEncoding(
+            encoding.to_string(),
+        ))),
+    }
+}
+
+/// Returns a reader decompressing the body on the fly. The encoding must have been checked
+/// with [`check_encoding`] beforehand.
+fn decoding_reader(
+    encoding: Option<&str>,
+    body: Bytes,
+) -> std::io::Result<Box<dyn BufRead + Send + 'static>> {
+    let reader: Box<dyn BufRead + Send + 'static> = match encoding {
+        Some("gzip" | "x-gzip") => Box::new(BufReader::new(GzDecoder::new(body.reader()))),
+        Some("zstd") => Box::new(BufReader::new(zstd::stream::read::Decoder::new(
+            body.reader(),
+        )?)),
+        _ => Box::new(body.reader()),
+    };
+    Ok(reader)
+}
+
 /// There are two ways to decompress the body:
 /// - Stream the body through an async decompressor
 /// - Fetch the body and then decompress the bytes
@@ -42,36 +69,39 @@ fn get_ingest_load_shield() -> &'static LoadShield {
 /// The first approach lowers the latency, while the second approach is more CPU efficient.
 /// Ingesting data is usually CPU bound and there is considerable latency until the data is
 /// searchable, so the second approach is more suitable for this use case.
-async fn decompress_body(encoding: Option<String>, body: Bytes) -> Result<Bytes, warp::Rejection> {
-    match encoding.as_deref() {
-        Some("gzip" | "x-gzip") => {
-            let decompressed = run_cpu_intensive(move || {
-                let mut decompressed = Vec::new();
-                let mut decoder = GzDecoder::new(body.as_ref());
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .map_err(|_| warp::reject::custom(CorruptedData))?;
-                Result::<_, warp::Rejection>::Ok(Bytes::from(decompressed))
-            })
-            .await
-            .map_err(|_| warp::reject::custom(CorruptedData))??;
-            Ok(decompressed)
-        }
-        Some("zstd") => {
-            let decompressed = run_cpu_intensive(move || {
-                zstd::decode_all(body.as_ref())
-                    .map(Bytes::from)
-                    .map_err(|_| warp::reject::custom(CorruptedData))
-            })
-            .await
-            .map_err(|_| warp::reject::custom(CorruptedData))??;
-            Ok(decompressed)
+///
+/// The size limit applies to the decompressed body and is enforced while decompressing, so
+/// that a small compressed body cannot inflate into an arbitrarily large one.
+async fn decompress_body(
+    encoding: Option<String>,
+    body: Bytes,
+    max_num_bytes: ByteSize,
+) -> Result<Bytes, warp::Rejection> {
+    check_encoding(encoding.as_deref())?;
+
+    if encoding.is_none() {
+        if body.len() as u64 > max_num_bytes.as_u64() {
+            return Err(warp::reject::custom(BodyTooLarge { max_num_bytes }));
         }
-        Some(encoding) => Err(warp::reject::custom(UnsupportedEncoding(
-            encoding.to_string(),
-        ))),
-        _ => Ok(body),
+        return Ok(body);
     }
+    let decompressed = run_cpu_intensive(move || {
+        let reader = decoding_reader(encoding.as_deref(), body)
+            .map_err(|_| warp::reject::custom(CorruptedData))?;
+        let mut decompressed = Vec::new();
+        reader
+            .take(max_num_bytes.as_u64() + 1)
+            .read_to_end(&mut decompressed)
+            .map_err(|_| warp::reject::custom(CorruptedData))?;
+
+        if decompressed.len() as u64 > max_num_bytes.as_u64() {
+            return Err(warp::reject::custom(BodyTooLarge { max_num_bytes }));
+        }
+        Result::<_, warp::Rejection>::Ok(Bytes::from(decompressed))
+    })
+    .await
+    .map_err(|_| warp::reject::custom(CorruptedData))??;
+    Ok(decompressed)
 }
 
 #[derive(Debug, Error)]
@@ -86,18 +116,75 @@ pub(crate) struct UnsupportedEncoding(String);
 
 impl Reject for UnsupportedEncoding {}
 
-/// Custom filter for optional decompression
-pub(crate) fn get_body_bytes() -> impl Filter<Extract = (Body,), Error = warp::Rejection> + Clone {
+#[derive(Debug, Error)]
+#[error("The decompressed request body exceeds the limit of {max_num_bytes}")]
+pub(crate) struct BodyTooLarge {
+    max_num_bytes: ByteSize,
+}
+
+impl Reject for BodyTooLarge {}
+
+/// Custom filter for optional decompression. The body is rejected if it exceeds
+/// `max_num_bytes` once decompressed.
+pub(crate) fn get_body_bytes(
+    max_num_bytes: ByteSize,
+) -> impl Filter<Extract = (Body,), Error = warp::Rejection> + Clone {
     warp::header::optional("content-encoding")
         .and(warp::body::bytes())
-        .and_then(|encoding: Option<String>, body: Bytes| async move {
+        .and_then(move |encoding: Option<String>, body: Bytes| async move {
             let permit = get_ingest_load_shield().acquire_permit().await?;
-            decompress_body(encoding, body)
+            decompress_body(encoding, body, max_num_bytes)
                 .await
                 .map(|content| Body::new(content, permit))
         })
 }
 
+/// Custom filter for bodies decompressed on the fly by their consumer, see [`EncodedBody`].
+pub(crate) fn get_encoded_body(
+) -> impl Filter<Extract = (EncodedBody,), Error = warp::Rejection> + Clone {
+    warp::header::optional("content-encoding")
+        .and(warp::body::bytes())
+        .and_then(|encoding: Option<String>, body: Bytes| async move {
+            check_encoding(encoding.as_deref())?;
+            let permit = get_ingest_load_shield().acquire_permit().await?;
+            Ok::<_, warp::Rejection>(EncodedBody::new(encoding, body, permit))
+        })
+}
+
+/// A request body kept as received, possibly compressed.
+///
+/// Unlike [`Body`], the body is never decompressed in full: its consumer reads it through
+/// [`EncodedBody::reader`] and enforces its own size limit while doing so.
+pub(crate) struct EncodedBody {
+    encoding: Option<String>,
+    content: Bytes,
+    _gauge_guard: GaugeGuard<'static>,
+    _permit: LoadShieldPermit,
+}
+
+impl EncodedBody {
+    fn new(
+        encoding: Option<String>,
+        content: Bytes,
+        load_shield_permit: LoadShieldPermit,
+    ) -> EncodedBody {
+        let mut gauge_guard = GaugeGuard::from_gauge(&MEMORY_METRICS.in_flight.rest_server);
+        gauge_guard.add(content.len() as i64);
+        EncodedBody {
+            encoding,
+            content,
+            _gauge_guard: gauge_guard,
+            _permit: load_shield_permit,
+        }
+    }
+
+    /// Returns a reader decompressing the body on the fly. Reading from it is CPU intensive
+    /// for compressed bodies.
+    pub fn reader(&self) -> std::io::Result<Box<dyn BufRead + Send + 'static>> {
+        decoding_reader(self.encoding.as_deref(), self.content.clone())
+    }
+}
+
 pub(crate) struct Body {
     pub content: Bytes,
     _gauge_guard: GaugeGuard<'static>,
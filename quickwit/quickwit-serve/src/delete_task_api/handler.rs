@@ -17,9 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use quickwit_config::build_doc_mapper;
 use quickwit_janitor::error::JanitorError;
-use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt};
 use quickwit_proto::metastore::{
     DeleteQuery, DeleteTask, IndexMetadataRequest, ListDeleteTasksRequest, MetastoreResult,
     MetastoreService, MetastoreServiceClient,
@@ -27,7 +29,8 @@ use quickwit_proto::metastore::{
 use quickwit_proto::search::SearchRequest;
 use quickwit_proto::types::{IndexId, IndexUid};
 use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
-use serde::Deserialize;
+use quickwit_search::SearchService;
+use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
 use crate::format::extract_format_from_qs;
@@ -37,8 +40,14 @@ use crate::with_arg;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_delete_tasks, post_delete_request),
-    components(schemas(DeleteQueryRequest, DeleteTask, DeleteQuery))
+    paths(get_delete_tasks, post_delete_request, post_delete_request_dry_run),
+    components(schemas(
+        DeleteQueryRequest,
+        DeleteTask,
+        DeleteQuery,
+        DeleteTaskEstimate,
+        DeleteTaskResponse
+    ))
 )]
 pub struct DeleteTaskApi;
 
@@ -58,12 +67,44 @@ pub struct DeleteQueryRequest {
     pub end_timestamp: Option<i64>,
 }
 
+#[derive(Deserialize, Debug, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PostDeleteTaskQueryParams {
+    /// If set, the response includes an estimate of the documents matching the delete query.
+    #[serde(default)]
+    pub estimate: bool,
+}
+
+/// Estimate of the documents a delete query would delete at the time of the request.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, utoipa::ToSchema)]
+pub struct DeleteTaskEstimate {
+    /// Number of published documents matching the delete query.
+    pub num_matching_docs: u64,
+    /// Number of published splits containing at least one matching document.
+    pub num_affected_splits: usize,
+}
+
+/// Response of the create delete task endpoint.
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct DeleteTaskResponse {
+    #[serde(flatten)]
+    pub delete_task: DeleteTask,
+    /// Estimate of the documents matching the delete query, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<DeleteTaskEstimate>,
+}
+
 /// Delete query API handlers.
 pub fn delete_task_api_handlers(
     metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     get_delete_tasks_handler(metastore.clone())
-        .or(post_delete_tasks_handler(metastore.clone()))
+        .or(post_delete_tasks_handler(
+            metastore.clone(),
+            search_service.clone(),
+        ))
+        .or(post_delete_tasks_dry_run_handler(metastore, search_service))
         .recover(recover_fn)
 }
 
@@ -116,11 +157,14 @@ pub async fn get_delete_tasks(
 
 pub fn post_delete_tasks_handler(
     metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!(String / "delete-tasks")
         .and(warp::body::json())
         .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(with_arg(metastore))
+        .and(with_arg(search_service))
         .then(post_delete_request)
         .and(extract_format_from_qs())
         .map(into_rest_api_response)
@@ -132,10 +176,11 @@ pub fn post_delete_tasks_handler(
     path = "/{index_id}/delete-tasks",
     request_body = DeleteQueryRequest,
     responses(
-        (status = 200, description = "Successfully added a new delete task.", body = DeleteTask)
+        (status = 200, description = "Successfully added a new delete task.", body = DeleteTaskResponse)
     ),
     params(
         ("index_id" = String, Path, description = "The index ID to add the delete task to."),
+        PostDeleteTaskQueryParams,
     )
 )]
 /// Create Delete Task
@@ -145,14 +190,85 @@ pub fn post_delete_tasks_handler(
 pub async fn post_delete_request(
     index_id: IndexId,
     delete_request: DeleteQueryRequest,
+    query_params: PostDeleteTaskQueryParams,
     metastore: MetastoreServiceClient,
-) -> Result<DeleteTask, JanitorError> {
-    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let metadata = metastore
+    search_service: Arc<dyn SearchService>,
+) -> Result<DeleteTaskResponse, JanitorError> {
+    let index_metadata = fetch_index_metadata(index_id, &metastore).await?;
+    let delete_query = build_delete_query(&index_metadata, &delete_request)?;
+
+    let estimate = if query_params.estimate {
+        let estimate = estimate_delete_query(&delete_query, &*search_service).await?;
+        Some(estimate)
+    } else {
+        None
+    };
+    let delete_task = metastore.create_delete_task(delete_query).await?;
+    Ok(DeleteTaskResponse {
+        delete_task,
+        estimate,
+    })
+}
+
+pub fn post_delete_tasks_dry_run_handler(
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "delete-tasks:dry-run")
+        .and(warp::body::json())
+        .and(warp::post())
+        .and(with_arg(metastore))
+        .and(with_arg(search_service))
+        .then(post_delete_request_dry_run)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Delete Tasks",
+    path = "/{index_id}/delete-tasks:dry-run",
+    request_body = DeleteQueryRequest,
+    responses(
+        (status = 200, description = "Successfully estimated the delete query.", body = DeleteTaskEstimate)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to estimate the delete query on."),
+    )
+)]
+/// Dry Run Delete Task
+///
+/// Validates the delete query like the create delete task endpoint, and returns the number of
+/// documents and splits it currently matches without creating a delete task.
+pub async fn post_delete_request_dry_run(
+    index_id: IndexId,
+    delete_request: DeleteQueryRequest,
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> Result<DeleteTaskEstimate, JanitorError> {
+    let index_metadata = fetch_index_metadata(index_id, &metastore).await?;
+    let delete_query = build_delete_query(&index_metadata, &delete_request)?;
+    estimate_delete_query(&delete_query, &*search_service).await
+}
+
+async fn fetch_index_metadata(
+    index_id: IndexId,
+    metastore: &MetastoreServiceClient,
+) -> Result<IndexMetadata, JanitorError> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
         .index_metadata(index_metadata_request)
         .await?
         .deserialize_index_metadata()?;
-    let index_uid: IndexUid = metadata.index_uid.clone();
+    Ok(index_metadata)
+}
+
+/// Parses and validates the delete query against the current doc mapping of the index.
+fn build_delete_query(
+    index_metadata: &IndexMetadata,
+    delete_request: &DeleteQueryRequest,
+) -> Result<DeleteQuery, JanitorError> {
+    let index_uid: IndexUid = index_metadata.index_uid.clone();
     let query_ast = query_ast_from_user_text(&delete_request.query, Some(Vec::new()))
         .parse_user_query(&[])
         .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
@@ -165,7 +281,7 @@ pub async fn post_delete_request(
         end_timestamp: delete_request.end_timestamp,
         query_ast: query_ast_json,
     };
-    let index_config = metadata.into_index_config();
+    let index_config = &index_metadata.index_config;
     // TODO should it be something else than a JanitorError?
     let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
         .map_err(|error| JanitorError::Internal(error.to_string()))?;
@@ -178,16 +294,53 @@ pub async fn post_delete_request(
     doc_mapper
         .query(doc_mapper.schema(), &query_ast, true)
         .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
-    let delete_task = metastore.create_delete_task(delete_query).await?;
-    Ok(delete_task)
+    Ok(delete_query)
+}
+
+/// Counts the published documents matching the delete query, split by split, so that the
+/// number of affected splits can be reported along with the number of documents.
+///
+/// The splits are listed, pruned and counted like in a root search.
+async fn estimate_delete_query(
+    delete_query: &DeleteQuery,
+    search_service: &dyn SearchService,
+) -> Result<DeleteTaskEstimate, JanitorError> {
+    let search_request = SearchRequest::try_from(delete_query.clone())
+        .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
+    let num_hits_per_split = search_service
+        .root_count_hits_per_split(search_request)
+        .await
+        .map_err(|error| JanitorError::Internal(error.to_string()))?;
+
+    let num_matching_docs = num_hits_per_split.values().sum();
+    let num_affected_splits = num_hits_per_split
+        .values()
+        .filter(|num_hits| **num_hits > 0)
+        .count();
+    Ok(DeleteTaskEstimate {
+        num_matching_docs,
+        num_affected_splits,
+    })
 }
 
 #[cfg(test)]
 mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use quickwit_config::SearcherConfig;
     use quickwit_indexing::TestSandbox;
     use quickwit_proto::metastore::DeleteTask;
+    use quickwit_proto::search::SearchRequest;
+    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_search::{
+        single_node_search, start_searcher_service, MockSearchService, SearchJobPlacer,
+        SearchServiceClient, SearcherContext, SearcherPool,
+    };
+    use serde_json::json;
     use warp::Filter;
 
+    use super::{DeleteTaskEstimate, DeleteTaskResponse};
     use crate::rest::recover_fn;
 
     #[tokio::test]
@@ -207,8 +360,9 @@ mod tests {
             .await
             .unwrap();
         let metastore = test_sandbox.metastore();
+        let search_service = Arc::new(MockSearchService::new());
         let delete_query_api_handlers =
-            super::delete_task_api_handlers(metastore).recover(recover_fn);
+            super::delete_task_api_handlers(metastore, search_service).recover(recover_fn);
         let resp = warp::test::request()
             .path("/test-delete-task-rest/delete-tasks")
             .method("POST")
@@ -249,4 +403,119 @@ mod tests {
         assert_eq!(delete_tasks.len(), 1);
         test_sandbox.assert_quit().await;
     }
+    #[tokio::test]
+    async fn test_delete_task_api_dry_run() {
+        quickwit_common::setup_logging_for_tests();
+        let index_id = "test-delete-task-rest-dry-run";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+            mode: lenient
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "myterm"}),
+                json!({"body": "myterm and another term"}),
+                json!({"body": "nothing to see"}),
+            ])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![json!({"body": "myterm"})])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![json!({"body": "nothing to see either"})])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let searcher_pool = SearcherPool::default();
+        let search_service = start_searcher_service(
+            metastore.clone(),
+            test_sandbox.storage_resolver(),
+            SearchJobPlacer::new(searcher_pool.clone()),
+            searcher_context,
+        )
+        .await
+        .unwrap();
+        let socket_addr: SocketAddr = "127.0.0.1:7280".parse().unwrap();
+        searcher_pool.insert(
+            socket_addr,
+            SearchServiceClient::from_service(search_service.clone(), socket_addr),
+        );
+        let delete_query_api_handlers =
+            super::delete_task_api_handlers(metastore.clone(), search_service).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks:dry-run")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"query": "body:myterm"}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let estimate: DeleteTaskEstimate = serde_json::from_slice(resp.body()).unwrap();
+
+        // The dry run count matches the search count for the same query.
+        let query_ast = query_ast_from_user_text("body:myterm", None)
+            .parse_user_query(&[])
+            .unwrap();
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: serde_json::to_string(&query_ast).unwrap(),
+            max_hits: 0,
+            ..Default::default()
+        };
+        let search_response =
+            single_node_search(search_request, metastore, test_sandbox.storage_resolver())
+                .await
+                .unwrap();
+        assert_eq!(search_response.num_hits, 3);
+        assert_eq!(
+            estimate,
+            DeleteTaskEstimate {
+                num_matching_docs: search_response.num_hits,
+                num_affected_splits: 2,
+            }
+        );
+
+        // A dry run does not create any delete task.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks")
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let delete_tasks: Vec<DeleteTask> = serde_json::from_slice(resp.body()).unwrap();
+        assert!(delete_tasks.is_empty());
+
+        // The dry run validates the delete query like the create endpoint.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks:dry-run")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"query": "unknown_field:test"}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert!(String::from_utf8_lossy(resp.body()).contains("invalid delete query"));
+
+        // The create endpoint returns the same estimate on demand.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks?estimate=true")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"query": "body:myterm"}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let delete_task_response: DeleteTaskResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(delete_task_response.delete_task.opstamp, 1);
+        assert_eq!(delete_task_response.estimate, Some(estimate));
+        test_sandbox.assert_quit().await;
+    }
 }
@@ -18,75 +18,132 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
+use std::io::BufRead;
 use std::time::Instant;
 
+use hyper::header::RETRY_AFTER;
+use hyper::http::HeaderValue;
 use hyper::StatusCode;
-use quickwit_config::{disable_ingest_v1, enable_ingest_v2};
+use quickwit_common::thread_pool::run_cpu_intensive;
+use quickwit_config::{disable_ingest_v1, enable_ingest_v2, IngestApiConfig};
 use quickwit_ingest::{
-    CommitType, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+    CommitType, DocBatch, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
 };
 use quickwit_proto::ingest::router::IngestRouterServiceClient;
+use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::types::IndexId;
-use warp::{Filter, Rejection};
+use warp::{Filter, Rejection, Reply};
 
-use super::bulk_v2::{elastic_bulk_ingest_v2, ElasticBulkResponse};
+use super::bulk_parser::{body_read_error, parse_bulk_body, BulkLimits, BulkLine};
+use super::bulk_v2::{
+    elastic_bulk_ingest_v2, record_bulk_docs, record_bulk_response, ElasticBulkAction,
+    ElasticBulkError, ElasticBulkItem, ElasticBulkResponse,
+};
 use crate::elasticsearch_api::filter::{elastic_bulk_filter, elastic_index_bulk_filter};
 use crate::elasticsearch_api::make_elastic_api_response;
-use crate::elasticsearch_api::model::{BulkAction, ElasticBulkOptions, ElasticsearchError};
+use crate::elasticsearch_api::model::{ElasticBulkOptions, ElasticException, ElasticsearchError};
 use crate::format::extract_format_from_qs;
-use crate::ingest_api::lines;
+use crate::ingest_api::check_ingest_not_blocked;
 use crate::rest::recover_fn;
-use crate::{with_arg, Body};
+use crate::{with_arg, EncodedBody};
+
+/// Number of seconds clients are asked to wait before retrying a bulk request rejected because of
+/// ingest backpressure.
+const RETRY_AFTER_SECS: u64 = 1;
 
 /// POST `_elastic/_bulk`
 pub fn es_compat_bulk_handler(
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    metastore: MetastoreServiceClient,
+    ingest_api_config: &IngestApiConfig,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elastic_bulk_filter()
+    let bulk_limits = BulkLimits::from(ingest_api_config);
+    elastic_bulk_filter(bulk_limits.max_num_bytes)
         .and(with_arg(ingest_service))
         .and(with_arg(ingest_router))
-        .then(|body, bulk_options, ingest_service, ingest_router| {
-            elastic_ingest_bulk(None, body, bulk_options, ingest_service, ingest_router)
-        })
+        .and(with_arg(metastore))
+        .then(
+            move |body, bulk_options, ingest_service, ingest_router, metastore| {
+                elastic_ingest_bulk(
+                    None,
+                    body,
+                    bulk_options,
+                    ingest_service,
+                    ingest_router,
+                    metastore,
+                    bulk_limits,
+                )
+            },
+        )
         .and(extract_format_from_qs())
         .map(make_elastic_api_response)
         .recover(recover_fn)
+        .map(with_retry_after_on_too_many_requests)
 }
 
 /// POST `_elastic/<index>/_bulk`
 pub fn es_compat_index_bulk_handler(
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    metastore: MetastoreServiceClient,
+    ingest_api_config: &IngestApiConfig,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elastic_index_bulk_filter()
+    let bulk_limits = BulkLimits::from(ingest_api_config);
+    elastic_index_bulk_filter(bulk_limits.max_num_bytes)
         .and(with_arg(ingest_service))
         .and(with_arg(ingest_router))
+        .and(with_arg(metastore))
         .then(
-            |index_id, body, bulk_options, ingest_service, ingest_router| {
+            move |index_id, body, bulk_options, ingest_service, ingest_router, metastore| {
                 elastic_ingest_bulk(
                     Some(index_id),
                     body,
                     bulk_options,
                     ingest_service,
                     ingest_router,
+                    metastore,
+                    bulk_limits,
                 )
             },
         )
         .and(extract_format_from_qs())
         .map(make_elastic_api_response)
         .recover(recover_fn)
+        .map(with_retry_after_on_too_many_requests)
+}
+
+/// Adds a `Retry-After` header to the responses rejecting a request because of ingest
+/// backpressure, so that clients back off instead of retrying right away.
+fn with_retry_after_on_too_many_requests(reply: impl Reply) -> warp::reply::Response {
+    let mut response = reply.into_response();
+
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+    }
+    response
 }
 
 async fn elastic_ingest_bulk(
     default_index_id: Option<IndexId>,
-    body: Body,
+    body: EncodedBody,
     bulk_options: ElasticBulkOptions,
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    metastore: MetastoreServiceClient,
+    bulk_limits: BulkLimits,
 ) -> Result<ElasticBulkResponse, ElasticsearchError> {
     if enable_ingest_v2() || bulk_options.enable_ingest_v2 {
-        return elastic_bulk_ingest_v2(default_index_id, body, bulk_options, ingest_router).await;
+        return elastic_bulk_ingest_v2(
+            default_index_id,
+            body,
+            bulk_options,
+            ingest_router,
+            bulk_limits,
+        )
+        .await;
     }
     if disable_ingest_v1() {
         return Err(ElasticsearchError::new(
@@ -96,76 +153,157 @@ async fn elastic_ingest_bulk(
         ));
     }
     let now = Instant::now();
-    let mut doc_batch_builders = HashMap::new();
-    let mut lines = lines(&body.content).enumerate();
-
-    while let Some((line_number, line)) = lines.next() {
-        let action = serde_json::from_slice::<BulkAction>(line).map_err(|error| {
-            ElasticsearchError::new(
-                StatusCode::BAD_REQUEST,
-                format!("Malformed action/metadata line [#{line_number}]. Details: `{error}`"),
-                None,
-            )
-        })?;
-        let (_, source) = lines.next().ok_or_else(|| {
+    let body_reader = body.reader().map_err(body_read_error)?;
+    // The body is decompressed while it is parsed, so this is CPU intensive.
+    let ParsedBulkRequest {
+        doc_batch_builders,
+        actions,
+    } = run_cpu_intensive(move || parse_bulk_request(default_index_id, body_reader, bulk_limits))
+        .await
+        .map_err(|_| {
             ElasticsearchError::new(
-                StatusCode::BAD_REQUEST,
-                "expected source for the action".to_string(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to parse bulk request body".to_string(),
                 None,
             )
-        })?;
-        // when ingesting on /my-index/_bulk, if _index: is set to something else than my-index,
-        // ES honors it and create the doc in the requested index. That is, `my-index` is a default
-        // value in case _index: is missing, but not a constraint on each sub-action.
-        let index_id = action
-            .into_index_id()
-            .or_else(|| default_index_id.clone())
-            .ok_or_else(|| {
-                ElasticsearchError::new(
-                    StatusCode::BAD_REQUEST,
-                    format!("missing required field: `_index` in the line [#{line_number}]."),
-                    None,
-                )
-            })?;
-        let doc_batch_builder = doc_batch_builders
-            .entry(index_id.clone())
-            .or_insert(DocBatchBuilder::new(index_id));
+        })??;
+    check_ingest_not_blocked(&metastore, doc_batch_builders.keys().cloned()).await?;
 
-        doc_batch_builder.ingest_doc(source);
-    }
-    let doc_batches = doc_batch_builders
+    let doc_batches: Vec<DocBatch> = doc_batch_builders
         .into_values()
         .map(|builder| builder.build())
         .collect();
+    let num_docs_per_index: Vec<(IndexId, u64)> = doc_batches
+        .iter()
+        .map(|doc_batch| (doc_batch.index_id.clone(), doc_batch.num_docs() as u64))
+        .collect();
     let commit_type: CommitType = bulk_options.refresh.into();
     let ingest_request = IngestRequest {
         doc_batches,
         commit: commit_type.into(),
     };
-    ingest_service.ingest(ingest_request).await?;
-
+    // The request may only hold malformed actions.
+    if !ingest_request.doc_batches.is_empty() {
+        if let Err(error) = ingest_service.ingest(ingest_request).await {
+            for (index_id, num_docs) in &num_docs_per_index {
+                record_bulk_docs(index_id, false, *num_docs);
+            }
+            return Err(error.into());
+        }
+    }
     let took_millis = now.elapsed().as_millis() as u64;
-    let errors = false;
+    let errors = actions.iter().any(|action| action.item().error.is_some());
     let bulk_response = ElasticBulkResponse {
         took_millis,
         errors,
-        actions: Vec::new(),
+        actions,
     };
+    record_bulk_response(&bulk_response);
     Ok(bulk_response)
 }
 
+/// A `_bulk` request parsed for ingest v1.
+struct ParsedBulkRequest {
+    doc_batch_builders: HashMap<IndexId, DocBatchBuilder>,
+    /// One response item per action of the request. Ingest v1 ingests the documents of a request
+    /// all at once, so the items of the well-formed actions are only returned if it succeeds.
+    actions: Vec<ElasticBulkAction>,
+}
+
+/// Parses the body of a `_bulk` request. Malformed action lines are reported in the items of the
+/// response instead of failing the whole request.
+fn parse_bulk_request(
+    default_index_id: Option<IndexId>,
+    body_reader: impl BufRead,
+    bulk_limits: BulkLimits,
+) -> Result<ParsedBulkRequest, ElasticsearchError> {
+    let mut doc_batch_builders = HashMap::new();
+    let mut actions = Vec::new();
+
+    for bulk_line_res in parse_bulk_body(body_reader, bulk_limits) {
+        let (line_number, action, source) = match bulk_line_res? {
+            BulkLine::Action {
+                line_no,
+                action,
+                doc,
+            } => (line_no, action, doc),
+            BulkLine::MalformedAction { line_no, error } => {
+                let error = ElasticBulkError {
+                    index_id: default_index_id.clone(),
+                    exception: ElasticException::IllegalArgument,
+                    reason: format!("Malformed action/metadata line [{}]: {error}", line_no + 1),
+                };
+                let item = ElasticBulkItem {
+                    index_id: default_index_id.clone().unwrap_or_default(),
+                    es_doc_id: None,
+                    status: StatusCode::BAD_REQUEST,
+                    error: Some(error),
+                };
+                actions.push(ElasticBulkAction::Index(item));
+                continue;
+            }
+            BulkLine::MissingSource { .. } => {
+                return Err(ElasticsearchError::new(
+                    StatusCode::BAD_REQUEST,
+                    "expected source for the action".to_string(),
+                    None,
+                ));
+            }
+        };
+        let meta = action.into_meta();
+        // when ingesting on /my-index/_bulk, if _index: is set to something else than my-index,
+        // ES honors it and create the doc in the requested index. That is, `my-index` is a default
+        // value in case _index: is missing, but not a constraint on each sub-action.
+        let index_id = meta
+            .index_id
+            .or_else(|| default_index_id.clone())
+            .ok_or_else(|| {
+                ElasticsearchError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("missing required field: `_index` in the line [#{line_number}]."),
+                    None,
+                )
+            })?;
+        let doc_batch_builder = doc_batch_builders
+            .entry(index_id.clone())
+            .or_insert(DocBatchBuilder::new(index_id.clone()));
+
+        doc_batch_builder.ingest_doc(&source[..]);
+
+        let item = ElasticBulkItem {
+            index_id,
+            es_doc_id: meta.es_doc_id,
+            status: StatusCode::CREATED,
+            error: None,
+        };
+        actions.push(ElasticBulkAction::Index(item));
+    }
+    Ok(ParsedBulkRequest {
+        doc_batch_builders,
+        actions,
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
     use std::sync::Arc;
     use std::time::Duration;
 
+    use bytesize::ByteSize;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
     use hyper::StatusCode;
-    use quickwit_config::{IngestApiConfig, NodeConfig};
+    use quickwit_config::{IndexConfig, IngestApiConfig, NodeConfig};
     use quickwit_index_management::IndexService;
-    use quickwit_ingest::{FetchRequest, IngestServiceClient, SuggestTruncateRequest};
-    use quickwit_metastore::metastore_for_test;
+    use quickwit_ingest::{
+        FetchRequest, IngestServiceClient, IngestServiceError, MockIngestService,
+        SuggestTruncateRequest,
+    };
+    use quickwit_metastore::{metastore_for_test, CreateIndexRequestExt};
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
-    use quickwit_proto::metastore::MetastoreServiceClient;
+    use quickwit_proto::ingest::RateLimitingCause;
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService, ToggleIngestRequest};
     use quickwit_search::MockSearchService;
     use quickwit_storage::StorageResolver;
 
@@ -188,7 +326,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
@@ -220,7 +358,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
@@ -256,7 +394,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = "
@@ -289,7 +427,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
@@ -325,7 +463,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
@@ -412,7 +550,7 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
@@ -485,10 +623,11 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_bulk_ingest_request_returns_400_if_action_is_malformed() {
+    async fn test_bulk_api_reports_malformed_action_items() {
         let config = Arc::new(NodeConfig::for_test());
         let search_service = Arc::new(MockSearchService::new());
-        let ingest_service = IngestServiceClient::mocked();
+        let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
         let ingest_router = IngestRouterServiceClient::mocked();
         let index_service =
             IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
@@ -497,11 +636,224 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
         );
         let payload = r#"
             {"create": {"_index": "my-index", "_id": "1"},}
+            {"id": 1, "message": "my-doc"}
+            {"create": {"_index": "my-index", "_id": "2"}}
+            {"id": 2, "message": "my-doc"}"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert!(bulk_response.errors);
+        assert_eq!(bulk_response.actions.len(), 2);
+
+        let item = bulk_response.actions[0].item();
+        assert_eq!(item.status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            item.error.as_ref().unwrap().reason,
+            "Malformed action/metadata line [1]: expected value at line 1 column 57"
+        );
+        let item = bulk_response.actions[1].item();
+        assert_eq!(item.status, StatusCode::CREATED);
+        assert_eq!(item.es_doc_id.as_deref(), Some("2"));
+
+        // The well-formed action is ingested.
+        assert_eq!(
+            ingest_service_mailbox
+                .ask_for_res(FetchRequest {
+                    index_id: "my-index".to_string(),
+                    start_after: None,
+                    num_bytes_limit: None,
+                })
+                .await
+                .unwrap()
+                .doc_batch
+                .unwrap()
+                .num_docs(),
+            1
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_returns_413_if_decompressed_body_is_too_large() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.ingest_api_config.content_length_limit = ByteSize::kb(1);
+        let search_service = Arc::new(MockSearchService::new());
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service.expect_ingest().never();
+        let ingest_service = IngestServiceClient::from_mock(mock_ingest_service);
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            Arc::new(node_config),
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore_for_test(),
+            index_service,
+        );
+        // The compressed body is well below the limit, but not once decompressed.
+        let payload = format!(
+            r#"
+            {{"create": {{"_index": "my-index", "_id": "1"}}}}
+            {{"id": 1, "message": "{}"}}"#,
+            "a".repeat(100_000)
+        );
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload.as_bytes()).unwrap();
+        let compressed_payload = encoder.finish().unwrap();
+        assert!(compressed_payload.len() < 1_000);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .header("content-encoding", "gzip")
+            .body(compressed_payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 413);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_returns_413_if_request_is_too_large() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.ingest_api_config.content_length_limit = ByteSize::kb(1);
+        node_config.ingest_api_config.bulk_max_num_docs = 1;
+        let search_service = Arc::new(MockSearchService::new());
+        let ingest_service = IngestServiceClient::mocked();
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            Arc::new(node_config),
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore_for_test(),
+            index_service,
+        );
+        let payload = format!(
+            r#"
+            {{"create": {{"_index": "my-index", "_id": "1"}}}}
+            {{"id": 1, "message": "{}"}}"#,
+            "a".repeat(2_000)
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 413);
+
+        let payload = r#"
+            {"create": {"_index": "my-index", "_id": "1"}}
+            {"id": 1, "message": "my-doc"}
+            {"create": {"_index": "my-index", "_id": "2"}}
+            {"id": 2, "message": "my-doc"}"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 413);
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            es_error.error.reason.unwrap(),
+            "bulk request exceeds the limit of 1 documents"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_returns_429_with_retry_after_if_ingest_queue_is_full() {
+        let config = Arc::new(NodeConfig::for_test());
+        let search_service = Arc::new(MockSearchService::new());
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service
+            .expect_ingest()
+            .once()
+            .returning(|_ingest_request| {
+                Err(IngestServiceError::RateLimited(RateLimitingCause::WalFull))
+            });
+        let ingest_service = IngestServiceClient::from_mock(mock_ingest_service);
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            config,
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore_for_test(),
+            index_service,
+        );
+        let payload = r#"
+            {"create": {"_index": "my-index", "_id": "1"}}
+            {"id": 1, "message": "my-doc"}"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get("retry-after").unwrap(), "1");
+
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(es_error.status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_returns_403_if_ingest_is_blocked() {
+        let config = Arc::new(NodeConfig::for_test());
+        let search_service = Arc::new(MockSearchService::new());
+        let mut mock_ingest_service = MockIngestService::new();
+        mock_ingest_service.expect_ingest().never();
+        let ingest_service = IngestServiceClient::from_mock(mock_ingest_service);
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let metastore = metastore_for_test();
+        let index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let toggle_ingest_request = ToggleIngestRequest {
+            index_uid: Some(index_uid),
+            ingest_blocked: true,
+        };
+        metastore
+            .toggle_ingest(toggle_ingest_request)
+            .await
+            .unwrap();
+
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            config,
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore,
+            index_service,
+        );
+        let payload = r#"
+            {"create": {"_index": "my-other-index", "_id": "1"}}
+            {"id": 1, "message": "my-doc"}
+            {"create": {"_index": "my-index", "_id": "1"}}
             {"id": 1, "message": "my-doc"}"#;
         let resp = warp::test::request()
             .path("/_elastic/_bulk")
@@ -509,12 +861,13 @@ mod tests {
             .body(payload)
             .reply(&elastic_api_handlers)
             .await;
-        assert_eq!(resp.status(), 400);
+        assert_eq!(resp.status(), 403);
+
         let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
-        assert_eq!(es_error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(es_error.status, StatusCode::FORBIDDEN);
         assert_eq!(
             es_error.error.reason.unwrap(),
-            "Malformed action/metadata line [#0]. Details: `expected value at line 1 column 57`"
+            "ingest is blocked for index `my-index`"
         );
     }
 }
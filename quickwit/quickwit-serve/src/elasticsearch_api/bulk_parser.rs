@@ -0,0 +1,286 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{self, BufRead, Take};
+
+use bytesize::ByteSize;
+use hyper::StatusCode;
+use quickwit_config::IngestApiConfig;
+
+use crate::elasticsearch_api::model::{BulkAction, ElasticsearchError};
+
+/// Limits enforced on the body of an Elasticsearch `_bulk` request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BulkLimits {
+    /// Maximum size of the body once decompressed.
+    pub max_num_bytes: ByteSize,
+    /// Maximum number of actions, i.e. documents, in the request.
+    pub max_num_docs: usize,
+}
+
+impl From<&IngestApiConfig> for BulkLimits {
+    fn from(ingest_api_config: &IngestApiConfig) -> Self {
+        Self {
+            max_num_bytes: ingest_api_config.content_length_limit,
+            max_num_docs: ingest_api_config.bulk_max_num_docs,
+        }
+    }
+}
+
+impl Default for BulkLimits {
+    fn default() -> Self {
+        Self::from(&IngestApiConfig::default())
+    }
+}
+
+/// An action parsed from the body of a `_bulk` request. Line numbers are zero-based and do not
+/// account for blank lines.
+#[derive(Debug)]
+pub(crate) enum BulkLine {
+    /// A well-formed action/metadata line followed by its source.
+    Action {
+        line_no: usize,
+        action: BulkAction,
+        doc: Vec<u8>,
+    },
+    /// An action/metadata line that could not be parsed. The line following it, if any, is
+    /// consumed as its source.
+    MalformedAction {
+        line_no: usize,
+        error: serde_json::Error,
+    },
+    /// A well-formed action/metadata line ending the body without a source.
+    MissingSource { line_no: usize },
+}
+
+/// Parses the NDJSON body of a `_bulk` request as it is read, one action at a time.
+///
+/// The reader usually decompresses the body on the fly, so the body is never held in full
+/// once decompressed. The iterator yields an error, and should not be polled further, as soon as
+/// the body exceeds one of the `limits` or cannot be read.
+pub(crate) fn parse_bulk_body<R: BufRead>(
+    reader: R,
+    limits: BulkLimits,
+) -> impl Iterator<Item = Result<BulkLine, ElasticsearchError>> {
+    BulkBodyParser {
+        // Reading one byte past the limit is enough to tell that the body exceeds it.
+        reader: reader.take(limits.max_num_bytes.as_u64() + 1),
+        limits,
+        num_bytes_read: 0,
+        num_lines: 0,
+        num_docs: 0,
+        is_done: false,
+    }
+}
+
+struct BulkBodyParser<R> {
+    reader: Take<R>,
+    limits: BulkLimits,
+    num_bytes_read: u64,
+    num_lines: usize,
+    num_docs: usize,
+    is_done: bool,
+}
+
+impl<R: BufRead> BulkBodyParser<R> {
+    /// Returns the next non-blank line of the body, without its line feed.
+    fn next_line(&mut self) -> Result<Option<Vec<u8>>, ElasticsearchError> {
+        loop {
+            let mut line = Vec::new();
+            let num_bytes = self
+                .reader
+                .read_until(b'\n', &mut line)
+                .map_err(body_read_error)?;
+            self.num_bytes_read += num_bytes as u64;
+
+            if self.num_bytes_read > self.limits.max_num_bytes.as_u64() {
+                return Err(ElasticsearchError::new(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!(
+                        "bulk request body size exceeds the limit of {}",
+                        self.limits.max_num_bytes
+                    ),
+                    None,
+                ));
+            }
+            if num_bytes == 0 {
+                return Ok(None);
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            if is_empty_or_blank_line(&line) {
+                continue;
+            }
+            self.num_lines += 1;
+            return Ok(Some(line));
+        }
+    }
+
+    fn next_bulk_line(&mut self) -> Result<Option<BulkLine>, ElasticsearchError> {
+        let line_no = self.num_lines;
+
+        let Some(action_line) = self.next_line()? else {
+            return Ok(None);
+        };
+        self.num_docs += 1;
+
+        if self.num_docs > self.limits.max_num_docs {
+            return Err(ElasticsearchError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "bulk request exceeds the limit of {} documents",
+                    self.limits.max_num_docs
+                ),
+                None,
+            ));
+        }
+        let bulk_line = match serde_json::from_slice::<BulkAction>(&action_line) {
+            Ok(action) => match self.next_line()? {
+                Some(doc) => BulkLine::Action {
+                    line_no,
+                    action,
+                    doc,
+                },
+                None => BulkLine::MissingSource { line_no },
+            },
+            Err(error) => {
+                self.next_line()?;
+                BulkLine::MalformedAction { line_no, error }
+            }
+        };
+        Ok(Some(bulk_line))
+    }
+}
+
+impl<R: BufRead> Iterator for BulkBodyParser<R> {
+    type Item = Result<BulkLine, ElasticsearchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        let bulk_line_res = self.next_bulk_line().transpose();
+
+        if !matches!(bulk_line_res, Some(Ok(_))) {
+            self.is_done = true;
+        }
+        bulk_line_res
+    }
+}
+
+/// Error returned when the body of a `_bulk` request cannot be read, usually because it is not
+/// properly compressed.
+pub(crate) fn body_read_error(error: io::Error) -> ElasticsearchError {
+    ElasticsearchError::new(
+        StatusCode::BAD_REQUEST,
+        format!("failed to read bulk request body: {error}"),
+        None,
+    )
+}
+
+#[inline]
+fn is_empty_or_blank_line(line: &[u8]) -> bool {
+    line.is_empty() || line.iter().all(|ch| ch.is_ascii_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bulk_body() {
+        let body: &[u8] = br#"
+            {"create": {"_index": "my-index", "_id": "1"}}
+            {"ts": 1}
+
+            {"create": {"_index": "my-index"},}
+            {"ts": 2}
+            {"index": {"_id": "3"}}
+            {"ts": 3}
+            {"create": {"_index": "my-index"}}
+            "#;
+        let bulk_lines: Vec<BulkLine> = parse_bulk_body(body, BulkLimits::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(bulk_lines.len(), 4);
+
+        let BulkLine::Action {
+            line_no,
+            action,
+            doc,
+        } = &bulk_lines[0]
+        else {
+            panic!("expected action, got {:?}", bulk_lines[0]);
+        };
+        assert_eq!(*line_no, 0);
+        assert_eq!(action.clone().into_index_id().unwrap(), "my-index");
+        assert_eq!(std::str::from_utf8(doc).unwrap().trim(), r#"{"ts": 1}"#);
+
+        let BulkLine::MalformedAction { line_no, .. } = &bulk_lines[1] else {
+            panic!("expected malformed action, got {:?}", bulk_lines[1]);
+        };
+        assert_eq!(*line_no, 2);
+
+        let BulkLine::Action { line_no, doc, .. } = &bulk_lines[2] else {
+            panic!("expected action, got {:?}", bulk_lines[2]);
+        };
+        assert_eq!(*line_no, 4);
+        assert_eq!(std::str::from_utf8(doc).unwrap().trim(), r#"{"ts": 3}"#);
+
+        let BulkLine::MissingSource { line_no } = &bulk_lines[3] else {
+            panic!("expected missing source, got {:?}", bulk_lines[3]);
+        };
+        assert_eq!(*line_no, 6);
+    }
+
+    #[test]
+    fn test_parse_bulk_body_enforces_limits() {
+        let body: &[u8] = br#"
+            {"create": {"_index": "my-index"}}
+            {"ts": 1}
+            {"create": {"_index": "my-index"}}
+            {"ts": 2}
+            "#;
+        let limits = BulkLimits {
+            max_num_bytes: ByteSize::b(100),
+            max_num_docs: 2,
+        };
+        let mut bulk_lines = parse_bulk_body(body, limits);
+        assert!(bulk_lines.next().unwrap().is_ok());
+
+        let error = bulk_lines.next().unwrap().unwrap_err();
+        assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(bulk_lines.next().is_none());
+
+        let limits = BulkLimits {
+            max_num_bytes: ByteSize::mib(1),
+            max_num_docs: 1,
+        };
+        let mut bulk_lines = parse_bulk_body(body, limits);
+        assert!(bulk_lines.next().unwrap().is_ok());
+
+        let error = bulk_lines.next().unwrap().unwrap_err();
+        assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            error.error.reason.unwrap(),
+            "bulk request exceeds the limit of 1 documents"
+        );
+    }
+}
@@ -18,23 +18,27 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
+use std::io::BufRead;
 use std::time::Instant;
 
 use hyper::StatusCode;
 use quickwit_common::rate_limited_error;
+use quickwit_common::thread_pool::run_cpu_intensive;
 use quickwit_config::INGEST_V2_SOURCE_ID;
 use quickwit_ingest::IngestRequestV2Builder;
 use quickwit_proto::ingest::router::{
-    IngestFailureReason, IngestResponseV2, IngestRouterService, IngestRouterServiceClient,
+    IngestFailure, IngestFailureReason, IngestResponseV2, IngestRouterService,
+    IngestRouterServiceClient,
 };
 use quickwit_proto::ingest::CommitTypeV2;
 use quickwit_proto::types::{DocUid, IndexId};
 use serde::{Deserialize, Serialize};
 
+use super::bulk_parser::{body_read_error, parse_bulk_body, BulkLimits, BulkLine};
 use super::model::ElasticException;
-use crate::elasticsearch_api::model::{BulkAction, ElasticBulkOptions, ElasticsearchError};
-use crate::ingest_api::lines;
-use crate::Body;
+use crate::elasticsearch_api::model::{ElasticBulkOptions, ElasticsearchError};
+use crate::metrics::SERVE_METRICS;
+use crate::EncodedBody;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct ElasticBulkResponse {
@@ -53,6 +57,15 @@ pub(crate) enum ElasticBulkAction {
     Index(ElasticBulkItem),
 }
 
+impl ElasticBulkAction {
+    pub(crate) fn item(&self) -> &ElasticBulkItem {
+        match self {
+            ElasticBulkAction::Create(item) => item,
+            ElasticBulkAction::Index(item) => item,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ElasticBulkItem {
     #[serde(rename = "_index")]
@@ -87,30 +100,122 @@ struct DocHandle {
 
 pub(crate) async fn elastic_bulk_ingest_v2(
     default_index_id: Option<IndexId>,
-    body: Body,
+    body: EncodedBody,
     bulk_options: ElasticBulkOptions,
     ingest_router: IngestRouterServiceClient,
+    bulk_limits: BulkLimits,
 ) -> Result<ElasticBulkResponse, ElasticsearchError> {
     let now = Instant::now();
+    let body_reader = body.reader().map_err(body_read_error)?;
+    // The body is decompressed while it is parsed, so this is CPU intensive.
+    let ParsedBulkRequestV2 {
+        ingest_request_builder,
+        per_subrequest_doc_handles,
+        malformed_actions,
+        action_count,
+    } = run_cpu_intensive(move || {
+        parse_bulk_request_v2(default_index_id, body_reader, bulk_limits)
+    })
+    .await
+    .map_err(|_| {
+        ElasticsearchError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "failed to parse bulk request body".to_string(),
+            None,
+        )
+    })??;
+    let commit_type: CommitTypeV2 = bulk_options.refresh.into();
+
+    let ingest_request_opt = ingest_request_builder.build(INGEST_V2_SOURCE_ID, commit_type);
+
+    let ingest_response = if let Some(ingest_request) = ingest_request_opt {
+        let num_docs_per_index: Vec<(IndexId, u64)> = ingest_request
+            .subrequests
+            .iter()
+            .map(|subrequest| {
+                let num_docs = subrequest
+                    .doc_batch
+                    .as_ref()
+                    .map(|doc_batch| doc_batch.num_docs() as u64)
+                    .unwrap_or(0);
+                (subrequest.index_id.clone(), num_docs)
+            })
+            .collect();
+        match ingest_router.ingest(ingest_request).await {
+            Ok(ingest_response) if is_backpressure_response(&ingest_response) => {
+                record_rejected_docs(&num_docs_per_index);
+                return Err(make_backpressure_error(&ingest_response.failures));
+            }
+            Ok(ingest_response) => ingest_response,
+            Err(error) => {
+                rate_limited_error!(limit_per_min=6, err=?error, "router error");
+                record_rejected_docs(&num_docs_per_index);
+                return Err(error.into());
+            }
+        }
+    } else if malformed_actions.is_empty() {
+        return Ok(ElasticBulkResponse::default());
+    } else {
+        IngestResponseV2::default()
+    };
+    let bulk_response = make_elastic_bulk_response_v2(
+        ingest_response,
+        per_subrequest_doc_handles,
+        malformed_actions,
+        now,
+        action_count,
+    )?;
+    record_bulk_response(&bulk_response);
+    Ok(bulk_response)
+}
+
+/// A `_bulk` request parsed for ingest v2.
+struct ParsedBulkRequestV2 {
+    ingest_request_builder: IngestRequestV2Builder,
+    per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>>,
+    /// Items of the actions that could not be parsed, which are reported without being ingested.
+    malformed_actions: Vec<(usize, ElasticBulkAction)>,
+    action_count: usize,
+}
+
+fn parse_bulk_request_v2(
+    default_index_id: Option<IndexId>,
+    body_reader: impl BufRead,
+    bulk_limits: BulkLimits,
+) -> Result<ParsedBulkRequestV2, ElasticsearchError> {
     let mut ingest_request_builder = IngestRequestV2Builder::default();
-    let mut lines = lines(&body.content).enumerate();
     let mut per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>> = HashMap::new();
+    let mut malformed_actions: Vec<(usize, ElasticBulkAction)> = Vec::new();
     let mut action_count = 0;
-    while let Some((line_no, line)) = lines.next() {
-        let action = serde_json::from_slice::<BulkAction>(line).map_err(|error| {
-            ElasticsearchError::new(
-                StatusCode::BAD_REQUEST,
-                format!("Malformed action/metadata line [{}]: {error}", line_no + 1),
-                Some(ElasticException::IllegalArgument),
-            )
-        })?;
-        let (_, doc) = lines.next().ok_or_else(|| {
-            ElasticsearchError::new(
-                StatusCode::BAD_REQUEST,
-                "Validation Failed: 1: no requests added;".to_string(),
-                Some(ElasticException::ActionRequestValidation),
-            )
-        })?;
+
+    for bulk_line_res in parse_bulk_body(body_reader, bulk_limits) {
+        let (action, doc) = match bulk_line_res? {
+            BulkLine::Action { action, doc, .. } => (action, doc),
+            BulkLine::MalformedAction { line_no, error } => {
+                let index_id = default_index_id.clone().unwrap_or_default();
+                let error = ElasticBulkError {
+                    index_id: default_index_id.clone(),
+                    exception: ElasticException::IllegalArgument,
+                    reason: format!("Malformed action/metadata line [{}]: {error}", line_no + 1),
+                };
+                let item = ElasticBulkItem {
+                    index_id,
+                    es_doc_id: None,
+                    status: StatusCode::BAD_REQUEST,
+                    error: Some(error),
+                };
+                malformed_actions.push((action_count, ElasticBulkAction::Index(item)));
+                action_count += 1;
+                continue;
+            }
+            BulkLine::MissingSource { .. } => {
+                return Err(ElasticsearchError::new(
+                    StatusCode::BAD_REQUEST,
+                    "Validation Failed: 1: no requests added;".to_string(),
+                    Some(ElasticException::ActionRequestValidation),
+                ));
+            }
+        };
         let meta = action.into_meta();
         // When ingesting into `/my-index/_bulk`, if `_index` is set to something other than
         // `my-index`, ES honors it and creates the doc for the requested index. That is,
@@ -126,7 +231,7 @@ pub(crate) async fn elastic_bulk_ingest_v2(
                     Some(ElasticException::ActionRequestValidation),
                 )
             })?;
-        let (subrequest_id, doc_uid) = ingest_request_builder.add_doc(index_id, doc);
+        let (subrequest_id, doc_uid) = ingest_request_builder.add_doc(index_id, &doc);
 
         let doc_handle = DocHandle {
             doc_position: action_count,
@@ -140,33 +245,122 @@ pub(crate) async fn elastic_bulk_ingest_v2(
             .or_default()
             .push(doc_handle);
     }
-    let commit_type: CommitTypeV2 = bulk_options.refresh.into();
-
-    let ingest_request_opt = ingest_request_builder.build(INGEST_V2_SOURCE_ID, commit_type);
-
-    let Some(ingest_request) = ingest_request_opt else {
-        return Ok(ElasticBulkResponse::default());
-    };
-    let ingest_response = ingest_router.ingest(ingest_request).await.map_err(|err| {
-        rate_limited_error!(limit_per_min=6, err=?err, "router error");
-        err
-    })?;
-    make_elastic_bulk_response_v2(
-        ingest_response,
+    Ok(ParsedBulkRequestV2 {
+        ingest_request_builder,
         per_subrequest_doc_handles,
-        now,
+        malformed_actions,
         action_count,
+    })
+}
+
+/// Returns whether the failure reason denotes ingest backpressure, i.e. the ingesters are saturated
+/// and the request should be retried later.
+fn is_backpressure_failure_reason(reason: IngestFailureReason) -> bool {
+    matches!(
+        reason,
+        IngestFailureReason::ShardRateLimited
+            | IngestFailureReason::WalFull
+            | IngestFailureReason::RouterLoadShedding
+            | IngestFailureReason::LoadShedding
+            | IngestFailureReason::CircuitBreaker
     )
 }
 
+/// Returns whether none of the documents of the request were ingested because of ingest
+/// backpressure. The whole request is then rejected with a `429 Too Many Requests` status.
+fn is_backpressure_response(ingest_response: &IngestResponseV2) -> bool {
+    ingest_response.successes.is_empty()
+        && !ingest_response.failures.is_empty()
+        && ingest_response
+            .failures
+            .iter()
+            .all(|failure| is_backpressure_failure_reason(failure.reason()))
+}
+
+fn make_backpressure_error(failures: &[IngestFailure]) -> ElasticsearchError {
+    let mut pretty_reasons: Vec<String> = failures
+        .iter()
+        .map(|failure| pretty_failure_reason(failure.reason()))
+        .collect();
+    pretty_reasons.sort_unstable();
+    pretty_reasons.dedup();
+
+    ElasticsearchError::new(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!(
+            "ingest is temporarily rejecting requests ({}), retry later",
+            pretty_reasons.join(", ")
+        ),
+        Some(ElasticException::RateLimited),
+    )
+}
+
+fn pretty_failure_reason(reason: IngestFailureReason) -> String {
+    reason
+        .as_str_name()
+        .strip_prefix("INGEST_FAILURE_REASON_")
+        .unwrap_or("")
+        .replace('_', " ")
+        .to_ascii_lowercase()
+}
+
+pub(crate) fn record_bulk_docs(index_id: &str, is_accepted: bool, num_docs: u64) {
+    let status = if is_accepted { "accepted" } else { "rejected" };
+    SERVE_METRICS
+        .elastic_bulk_docs_total
+        .with_label_values([index_id, status])
+        .inc_by(num_docs);
+}
+
+fn record_rejected_docs(num_docs_per_index: &[(IndexId, u64)]) {
+    for (index_id, num_docs) in num_docs_per_index {
+        record_bulk_docs(index_id, false, *num_docs);
+    }
+}
+
+pub(crate) fn record_bulk_response(bulk_response: &ElasticBulkResponse) {
+    let mut num_docs_per_index_and_status: HashMap<(&str, bool), u64> = HashMap::new();
+    let mut num_parse_errors_per_index: HashMap<&str, u64> = HashMap::new();
+
+    for action in &bulk_response.actions {
+        let item = action.item();
+        let is_accepted = item.error.is_none();
+        *num_docs_per_index_and_status
+            .entry((item.index_id.as_str(), is_accepted))
+            .or_default() += 1;
+
+        if let Some(error) = &item.error {
+            if matches!(
+                error.exception,
+                ElasticException::IllegalArgument | ElasticException::DocumentParsing
+            ) {
+                *num_parse_errors_per_index
+                    .entry(item.index_id.as_str())
+                    .or_default() += 1;
+            }
+        }
+    }
+    for ((index_id, is_accepted), num_docs) in num_docs_per_index_and_status {
+        record_bulk_docs(index_id, is_accepted, num_docs);
+    }
+    for (index_id, num_parse_errors) in num_parse_errors_per_index {
+        SERVE_METRICS
+            .elastic_bulk_parse_errors_total
+            .with_label_values([index_id])
+            .inc_by(num_parse_errors);
+    }
+}
+
 fn make_elastic_bulk_response_v2(
     ingest_response_v2: IngestResponseV2,
     mut per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>>,
+    malformed_actions: Vec<(usize, ElasticBulkAction)>,
     now: Instant,
     action_count: usize,
 ) -> Result<ElasticBulkResponse, ElasticsearchError> {
     let mut positioned_actions: Vec<(usize, ElasticBulkAction)> = Vec::with_capacity(action_count);
-    let mut errors = false;
+    let mut errors = !malformed_actions.is_empty();
+    positioned_actions.extend(malformed_actions);
 
     // Populate the items for each `IngestSuccess` subresponse. They may be partially successful and
     // contain some parse failures.
@@ -263,6 +457,11 @@ fn make_elastic_bulk_response_v2(
                 format!("no such source [{}]", failure.index_id),
                 StatusCode::NOT_FOUND,
             ),
+            IngestFailureReason::IngestBlocked => (
+                ElasticException::ClusterBlock,
+                format!("index [{}] blocked by: [ingest blocked]", failure.index_id),
+                StatusCode::FORBIDDEN,
+            ),
             IngestFailureReason::Timeout => (
                 ElasticException::Timeout,
                 format!("timeout [{}]", failure.index_id),
@@ -273,19 +472,20 @@ fn make_elastic_bulk_response_v2(
                 format!("shard rate limiting [{}]", failure.index_id),
                 StatusCode::TOO_MANY_REQUESTS,
             ),
-            reason => {
-                let pretty_reason = reason
-                    .as_str_name()
-                    .strip_prefix("INGEST_FAILURE_REASON_")
-                    .unwrap_or("")
-                    .replace('_', " ")
-                    .to_ascii_lowercase();
-                (
-                    ElasticException::Internal,
-                    format!("{} error [{}]", pretty_reason, failure.index_id),
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                )
-            }
+            reason if is_backpressure_failure_reason(reason) => (
+                ElasticException::RateLimited,
+                format!("{} [{}]", pretty_failure_reason(reason), failure.index_id),
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
+            reason => (
+                ElasticException::Internal,
+                format!(
+                    "{} error [{}]",
+                    pretty_failure_reason(reason),
+                    failure.index_id
+                ),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
         };
         for mut doc_handle in doc_handles {
             let error = ElasticBulkError {
@@ -395,10 +595,16 @@ mod tests {
     fn es_compat_bulk_handler_v2(
         ingest_router: IngestRouterServiceClient,
     ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-        elastic_bulk_filter()
+        elastic_bulk_filter(BulkLimits::default().max_num_bytes)
             .and(with_arg(ingest_router))
             .then(|body, bulk_options, ingest_router| {
-                elastic_bulk_ingest_v2(None, body, bulk_options, ingest_router)
+                elastic_bulk_ingest_v2(
+                    None,
+                    body,
+                    bulk_options,
+                    ingest_router,
+                    BulkLimits::default(),
+                )
             })
             .and(extract_format_from_qs())
             .map(make_elastic_api_response)
@@ -586,14 +792,17 @@ mod tests {
             .body(payload)
             .reply(&handler)
             .await;
-        assert_eq!(response.status(), 400);
+        assert_eq!(response.status(), 200);
 
-        let es_error: ElasticsearchError = serde_json::from_slice(response.body()).unwrap();
-        assert_eq!(es_error.status, StatusCode::BAD_REQUEST);
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(bulk_response.errors);
+        assert_eq!(bulk_response.actions.len(), 1);
+        assert_eq!(bulk_response.actions[0].status(), StatusCode::BAD_REQUEST);
 
-        let reason = es_error.error.reason.unwrap();
+        let error = bulk_response.actions[0].error().unwrap();
+        assert_eq!(error.exception, ElasticException::IllegalArgument);
         assert_eq!(
-            reason,
+            error.reason,
             "Malformed action/metadata line [1]: expected value at line 1 column 60"
         );
 
@@ -698,11 +907,117 @@ mod tests {
         assert_eq!(bulk_response.actions.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_bulk_api_reports_malformed_action_items() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .once()
+            .returning(|ingest_request| {
+                assert_eq!(ingest_request.subrequests.len(), 1);
+
+                let subrequest_0 = &ingest_request.subrequests[0];
+                assert_eq!(subrequest_0.index_id, "my-index-1");
+                assert_eq!(subrequest_0.doc_batch.as_ref().unwrap().num_docs(), 2);
+
+                Ok(IngestResponseV2 {
+                    successes: vec![IngestSuccess {
+                        subrequest_id: 0,
+                        index_uid: Some(IndexUid::for_test("my-index-1", 0)),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shard_id: Some(ShardId::from(1)),
+                        replication_position_inclusive: Some(Position::offset(1u64)),
+                        num_ingested_docs: 2,
+                        parse_failures: Vec::new(),
+                    }],
+                    failures: Vec::new(),
+                })
+            });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let handler = es_compat_bulk_handler_v2(ingest_router);
+
+        let payload = r#"
+            {"create": {"_index": "my-index-1", "_id" : "1"}}
+            {"ts": 1, "message": "my-message-1"}
+            {"create": {"_index": "my-index-1", "_id" : "2"
+            {"ts": 2, "message": "my-message-2"}
+            {"create": {"_index": "my-index-1", "_id" : "3"}}
+            {"ts": 3, "message": "my-message-3"}
+        "#;
+        let response = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(bulk_response.errors);
+        assert_eq!(bulk_response.actions.len(), 3);
+
+        assert_eq!(bulk_response.actions[0].es_doc_id(), Some("1"));
+        assert_eq!(bulk_response.actions[0].status(), StatusCode::CREATED);
+
+        assert!(bulk_response.actions[1].es_doc_id().is_none());
+        assert_eq!(bulk_response.actions[1].status(), StatusCode::BAD_REQUEST);
+
+        let error = bulk_response.actions[1].error().unwrap();
+        assert_eq!(error.exception, ElasticException::IllegalArgument);
+        assert!(error
+            .reason
+            .starts_with("Malformed action/metadata line [3]"));
+
+        assert_eq!(bulk_response.actions[2].es_doc_id(), Some("3"));
+        assert_eq!(bulk_response.actions[2].status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_returns_429_on_ingest_backpressure() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .once()
+            .returning(|_ingest_request| {
+                Ok(IngestResponseV2 {
+                    successes: Vec::new(),
+                    failures: vec![IngestFailure {
+                        subrequest_id: 0,
+                        index_id: "my-index-1".to_string(),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        reason: IngestFailureReason::WalFull as i32,
+                    }],
+                })
+            });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let handler = es_compat_bulk_handler_v2(ingest_router);
+
+        let payload = r#"
+            {"create": {"_index": "my-index-1", "_id" : "1"}}
+            {"ts": 1, "message": "my-message-1"}
+        "#;
+        let response = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 429);
+
+        let es_error: ElasticsearchError = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(es_error.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            es_error.error.ty.unwrap(),
+            ElasticException::RateLimited.as_str()
+        );
+    }
+
     #[test]
     fn test_make_elastic_bulk_response_v2() {
         let response = make_elastic_bulk_response_v2(
             IngestResponseV2::default(),
             HashMap::new(),
+            Vec::new(),
             Instant::now(),
             0,
         )
@@ -763,6 +1078,7 @@ mod tests {
         let response = make_elastic_bulk_response_v2(
             ingest_response_v2,
             per_request_doc_handles,
+            Vec::new(),
             Instant::now(),
             3,
         )
@@ -25,17 +25,16 @@ use warp::{Filter, Rejection};
 
 use super::model::{
     CatIndexQueryParams, DeleteQueryParams, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    MultiSearchQueryParams, SearchQueryParamsCount,
+    MultiSearchQueryParams, OpenPointInTimeQueryParams, SearchQueryParamsCount,
 };
-use crate::decompression::get_body_bytes;
+use crate::decompression::get_encoded_body;
 use crate::elasticsearch_api::model::{
     ElasticBulkOptions, ScrollQueryParams, SearchBody, SearchQueryParams,
 };
 use crate::search_api::{extract_index_id_patterns, extract_index_id_patterns_default};
-use crate::Body;
+use crate::EncodedBody;
 
 const BODY_LENGTH_LIMIT: ByteSize = ByteSize::mib(1);
-const CONTENT_LENGTH_LIMIT: ByteSize = ByteSize::mib(10);
 
 // TODO: Make all elastic endpoint models `utoipa` compatible
 // and register them here.
@@ -63,7 +62,7 @@ pub(crate) fn elasticsearch_filter(
     post,
     tag = "Ingest",
     path = "/_bulk",
-    request_body(content = String, description = "Elasticsearch compatible bulk request body limited to 10MB", content_type = "application/json"),
+    request_body(content = String, description = "Elasticsearch compatible bulk request body limited to `ingest_api.content_length_limit` (10MB by default)", content_type = "application/json"),
     responses(
         (status = 200, description = "Successfully ingested documents.", body = IngestResponse)
     ),
@@ -72,13 +71,14 @@ pub(crate) fn elasticsearch_filter(
     )
 )]
 pub(crate) fn elastic_bulk_filter(
-) -> impl Filter<Extract = (Body, ElasticBulkOptions), Error = Rejection> + Clone {
+    content_length_limit: ByteSize,
+) -> impl Filter<Extract = (EncodedBody, ElasticBulkOptions), Error = Rejection> + Clone {
     warp::path!("_elastic" / "_bulk")
         .and(warp::post().or(warp::put()).unify())
         .and(warp::body::content_length_limit(
-            CONTENT_LENGTH_LIMIT.as_u64(),
+            content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_encoded_body())
         .and(serde_qs::warp::query(serde_qs::Config::default()))
 }
 
@@ -86,7 +86,7 @@ pub(crate) fn elastic_bulk_filter(
     post,
     tag = "Ingest",
     path = "/{index}/_bulk",
-    request_body(content = String, description = "Elasticsearch compatible bulk request body limited to 10MB", content_type = "application/json"),
+    request_body(content = String, description = "Elasticsearch compatible bulk request body limited to `ingest_api.content_length_limit` (10MB by default)", content_type = "application/json"),
     responses(
         (status = 200, description = "Successfully ingested documents.", body = IngestResponse)
     ),
@@ -95,13 +95,14 @@ pub(crate) fn elastic_bulk_filter(
     )
 )]
 pub(crate) fn elastic_index_bulk_filter(
-) -> impl Filter<Extract = (String, Body, ElasticBulkOptions), Error = Rejection> + Clone {
+    content_length_limit: ByteSize,
+) -> impl Filter<Extract = (String, EncodedBody, ElasticBulkOptions), Error = Rejection> + Clone {
     warp::path!("_elastic" / String / "_bulk")
         .and(warp::post().or(warp::put()).unify())
         .and(warp::body::content_length_limit(
-            CONTENT_LENGTH_LIMIT.as_u64(),
+            content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_encoded_body())
         .and(serde_qs::warp::query::<ElasticBulkOptions>(
             serde_qs::Config::default(),
         ))
@@ -232,6 +233,15 @@ pub(crate) fn elastic_index_search_filter(
         .and(json_or_empty())
 }
 
+#[utoipa::path(post, tag = "Search", path = "/{index}/_pit")]
+pub(crate) fn elastic_open_point_in_time_filter(
+) -> impl Filter<Extract = (Vec<String>, OpenPointInTimeQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_pit")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
 #[utoipa::path(post, tag = "Search", path = "/_msearch")]
 pub(crate) fn elastic_multi_search_filter(
 ) -> impl Filter<Extract = (Bytes, MultiSearchQueryParams), Error = Rejection> + Clone {
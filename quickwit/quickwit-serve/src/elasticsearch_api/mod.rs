@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod bulk;
+mod bulk_parser;
 mod bulk_v2;
 mod filter;
 mod model;
@@ -38,7 +39,8 @@ pub use rest_handler::{
     es_compat_cat_indices_handler, es_compat_cluster_info_handler, es_compat_delete_index_handler,
     es_compat_index_cat_indices_handler, es_compat_index_count_handler,
     es_compat_index_field_capabilities_handler, es_compat_index_multi_search_handler,
-    es_compat_index_search_handler, es_compat_index_stats_handler, es_compat_resolve_index_handler,
+    es_compat_index_search_handler, es_compat_index_stats_handler,
+    es_compat_open_point_in_time_handler, es_compat_resolve_index_handler,
     es_compat_scroll_handler, es_compat_search_handler, es_compat_stats_handler,
 };
 use serde::{Deserialize, Serialize};
@@ -61,16 +63,25 @@ pub fn elastic_api_handlers(
     metastore: MetastoreServiceClient,
     index_service: IndexService,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    es_compat_cluster_info_handler(node_config, BuildInfo::get())
+    let ingest_api_config = &node_config.ingest_api_config;
+    es_compat_cluster_info_handler(node_config.clone(), BuildInfo::get())
         .or(es_compat_search_handler(search_service.clone()))
         .or(es_compat_bulk_handler(
             ingest_service.clone(),
             ingest_router.clone(),
+            metastore.clone(),
+            ingest_api_config,
+        ))
+        .or(es_compat_index_bulk_handler(
+            ingest_service,
+            ingest_router,
+            metastore.clone(),
+            ingest_api_config,
         ))
-        .or(es_compat_index_bulk_handler(ingest_service, ingest_router))
         .or(es_compat_index_search_handler(search_service.clone()))
         .or(es_compat_index_count_handler(search_service.clone()))
         .or(es_compat_scroll_handler(search_service.clone()))
+        .or(es_compat_open_point_in_time_handler(search_service.clone()))
         .or(es_compat_index_multi_search_handler(search_service.clone()))
         .or(es_compat_index_field_capabilities_handler(
             search_service.clone(),
@@ -92,7 +103,8 @@ pub fn elastic_api_handlers(
 /// match the query accurately.
 ///
 /// When set to `Count` with an integer value `n`, the response accurately tracks the total
-/// hit count that match the query up to `n` documents.
+/// hit count that match the query up to `n` documents. Beyond it, the total is reported as `n`
+/// with the `gte` relation.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum TrackTotalHits {
@@ -137,6 +149,7 @@ mod tests {
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
     use quickwit_proto::metastore::MetastoreServiceClient;
+    use quickwit_proto::search::{CountHits, NumHitsRelation};
     use quickwit_search::MockSearchService;
     use quickwit_storage::StorageResolver;
     use serde_json::Value as JsonValue;
@@ -442,6 +455,250 @@ mod tests {
         assert_eq!(resp.status(), 200);
     }
 
+    #[tokio::test]
+    async fn test_es_compat_count_api() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns
+                        == vec!["index-1".to_string(), "index-2-*".to_string()]
+                        && search_request.max_hits == 0
+                        && search_request.count_hits() == CountHits::CountAll
+                        && search_request.query_ast.contains("PushEvent")
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    num_hits: 42,
+                    num_successful_splits: 3,
+                    ..Default::default()
+                })
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            IngestRouterServiceClient::mocked(),
+            MetastoreServiceClient::mocked(),
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured()),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1,index-2-*/_count?q=type:PushEvent")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "count": 42,
+            "_shards": {
+                "total": 3,
+                "successful": 3,
+                "skipped": 0,
+                "failed": 0,
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1,index-2-*/_count")
+            .method("POST")
+            .json(&serde_json::json!({
+                "query": {"bool": {"filter": [{"term": {"type": "PushEvent"}}]}}
+            }))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_search_track_total_hits() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(3)
+            .returning(|search_request| {
+                // Mimics the root search: the hit count is capped to `track_total_hits`, and only a
+                // lower bound when the hits are not all counted.
+                let search_response =
+                    match (search_request.count_hits(), search_request.track_total_hits) {
+                        (CountHits::Underestimate, None) => {
+                            quickwit_proto::search::SearchResponse {
+                                num_hits: 3,
+                                num_hits_relation: NumHitsRelation::GreaterThanOrEqualTo as i32,
+                                ..Default::default()
+                            }
+                        }
+                        (CountHits::CountAll, None) => quickwit_proto::search::SearchResponse {
+                            num_hits: 42,
+                            ..Default::default()
+                        },
+                        (CountHits::CountAll, Some(track_total_hits)) => {
+                            quickwit_proto::search::SearchResponse {
+                                num_hits: track_total_hits,
+                                num_hits_relation: NumHitsRelation::GreaterThanOrEqualTo as i32,
+                                ..Default::default()
+                            }
+                        }
+                        unexpected => panic!("unexpected hit count parameters: {unexpected:?}"),
+                    };
+                Ok(search_response)
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            IngestRouterServiceClient::mocked(),
+            MetastoreServiceClient::mocked(),
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured()),
+        );
+        for (track_total_hits, expected_total) in [
+            (
+                serde_json::json!(false),
+                serde_json::json!({"value": 3, "relation": "gte"}),
+            ),
+            (
+                serde_json::json!(10),
+                serde_json::json!({"value": 10, "relation": "gte"}),
+            ),
+            (
+                serde_json::json!(true),
+                serde_json::json!({"value": 42, "relation": "eq"}),
+            ),
+        ] {
+            let resp = warp::test::request()
+                .path("/_elastic/index-1/_search")
+                .method("POST")
+                .json(&serde_json::json!({ "track_total_hits": track_total_hits }))
+                .reply(&es_search_api_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(resp_json["hits"]["total"], expected_total);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_search_result_window_too_large() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.start_offset == 9_995 && search_request.max_hits == 10
+                },
+            ))
+            .returning(|_| {
+                Err(quickwit_search::SearchError::ResultWindowTooLarge {
+                    result_window: 10_005,
+                    max_result_window: 10_000,
+                })
+            });
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            IngestRouterServiceClient::mocked(),
+            MetastoreServiceClient::mocked(),
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured()),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search?from=9995&size=10")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "status": 400,
+            "error": {
+                "type": "search_phase_execution_exception",
+                "reason": "all shards failed",
+                "root_cause": [{
+                    "type": "illegal_argument_exception",
+                    "reason": "Result window is too large, from + size must be less than or equal \
+                               to: [10000] but was [10005]. See the search_after parameter for a \
+                               more efficient way to request large data sets. This limit can be \
+                               set by changing the [search_settings.max_result_window] index \
+                               setting."
+                }]
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_point_in_time() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns == vec!["index-1".to_string()]
+                        && search_request.max_hits == 0
+                        && search_request.pit_keep_alive_secs == Some(60)
+                        && search_request.pit_id.is_none()
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    pit_id: Some("01HAV29D4XY3D462FS3D8K5Q2H".to_string()),
+                    ..Default::default()
+                })
+            });
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.pit_id.as_deref() == Some("01HAV29D4XY3D462FS3D8K5Q2H")
+                        && search_request.pit_keep_alive_secs == Some(120)
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            IngestRouterServiceClient::mocked(),
+            MetastoreServiceClient::mocked(),
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured()),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_pit?keep_alive=1m")
+            .method("POST")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            serde_json::json!({"id": "01HAV29D4XY3D462FS3D8K5Q2H"})
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .json(&serde_json::json!({
+                "pit": {"id": "01HAV29D4XY3D462FS3D8K5Q2H", "keep_alive": "2m"}
+            }))
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_pit")
+            .method("POST")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
     #[tokio::test]
     async fn test_es_compat_cluster_info_handler() {
         let build_info = BuildInfo::get();
@@ -63,6 +63,39 @@ impl ElasticsearchError {
 impl From<SearchError> for ElasticsearchError {
     fn from(search_error: SearchError) -> Self {
         let status = search_error.error_code().http_status_code();
+
+        if let SearchError::ResultWindowTooLarge {
+            result_window,
+            max_result_window,
+        } = search_error
+        {
+            // Mimic the error returned by Elasticsearch when `from + size` exceeds
+            // `index.max_result_window`.
+            let root_cause = ErrorCause {
+                reason: Some(format!(
+                    "Result window is too large, from + size must be less than or equal to: \
+                     [{max_result_window}] but was [{result_window}]. See the search_after \
+                     parameter for a more efficient way to request large data sets. This limit \
+                     can be set by changing the [search_settings.max_result_window] index setting."
+                )),
+                caused_by: None,
+                root_cause: Vec::new(),
+                stack_trace: None,
+                suppressed: Vec::new(),
+                ty: Some(ElasticException::IllegalArgument.as_str().to_string()),
+                additional_details: Default::default(),
+            };
+            let error = ErrorCause {
+                reason: Some("all shards failed".to_string()),
+                caused_by: None,
+                root_cause: vec![root_cause],
+                stack_trace: None,
+                suppressed: Vec::new(),
+                ty: Some(ElasticException::SearchPhaseExecution.as_str().to_string()),
+                additional_details: Default::default(),
+            };
+            return ElasticsearchError { status, error };
+        }
         // Fill only reason field to keep it simple.
         let reason = ErrorCause {
             reason: Some(search_error.to_string()),
@@ -144,6 +177,8 @@ impl From<IndexServiceError> for ElasticsearchError {
 pub enum ElasticException {
     #[serde(rename = "action_request_validation_exception")]
     ActionRequestValidation,
+    #[serde(rename = "cluster_block_exception")]
+    ClusterBlock,
     #[serde(rename = "document_parsing_exception")]
     DocumentParsing,
     // This is an exception proper to Quickwit.
@@ -156,6 +191,8 @@ pub enum ElasticException {
     // This is an exception proper to Quickwit.
     #[serde(rename = "rate_limited_exception")]
     RateLimited,
+    #[serde(rename = "search_phase_execution_exception")]
+    SearchPhaseExecution,
     // This is an exception proper to Quickwit.
     #[serde(rename = "source_not_found_exception")]
     SourceNotFound,
@@ -167,11 +204,13 @@ impl ElasticException {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::ActionRequestValidation => "action_request_validation_exception",
+            Self::ClusterBlock => "cluster_block_exception",
             Self::DocumentParsing => "document_parsing_exception",
             Self::Internal => "internal_exception",
             Self::RateLimited => "rate_limited_exception",
             Self::IllegalArgument => "illegal_argument_exception",
             Self::IndexNotFound => "index_not_found_exception",
+            Self::SearchPhaseExecution => "search_phase_execution_exception",
             Self::SourceNotFound => "source_not_found_exception",
             Self::Timeout => "timeout_exception",
         }
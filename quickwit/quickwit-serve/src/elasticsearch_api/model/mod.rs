@@ -23,6 +23,7 @@ mod cat_indices;
 mod error;
 mod field_capability;
 mod multi_search;
+mod point_in_time;
 mod scroll;
 mod search_body;
 mod search_query_params;
@@ -42,9 +43,11 @@ pub use field_capability::{
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
-use quickwit_proto::search::{SortDatetimeFormat, SortOrder};
+pub(crate) use point_in_time::parse_pit_keep_alive_secs;
+pub use point_in_time::{OpenPointInTimeQueryParams, OpenPointInTimeResponse, PointInTimeBody};
+use quickwit_proto::search::{SortDatetimeFormat, SortMode, SortOrder};
 pub use scroll::ScrollQueryParams;
-pub use search_body::SearchBody;
+pub use search_body::{FieldAndFormat, SearchBody};
 pub use search_query_params::{DeleteQueryParams, SearchQueryParams, SearchQueryParamsCount};
 use serde::{Deserialize, Serialize};
 pub use stats::{ElasticsearchStatsResponse, StatsResponseEntry};
@@ -54,6 +57,7 @@ pub struct SortField {
     pub field: String,
     pub order: SortOrder,
     pub date_format: Option<ElasticDateFormat>,
+    pub mode: Option<SortMode>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
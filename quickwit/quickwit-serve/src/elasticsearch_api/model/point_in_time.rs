@@ -0,0 +1,64 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_search::SearchError;
+use serde::{Deserialize, Serialize};
+
+/// Point in time passed in the body of a search request.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PointInTimeBody {
+    pub id: String,
+    /// If set, extends the point in time by this duration (`1m`, `30s`, etc.).
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct OpenPointInTimeQueryParams {
+    pub keep_alive: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct OpenPointInTimeResponse {
+    pub id: String,
+}
+
+/// Parses an Elasticsearch point in time keep alive (`1m`, `30s`, etc.) into a number of seconds.
+pub(crate) fn parse_pit_keep_alive_secs(keep_alive: &str) -> Result<u32, SearchError> {
+    let duration: Duration = humantime::parse_duration(keep_alive).map_err(|_err| {
+        SearchError::InvalidArgument(format!("invalid point in time keep alive: `{keep_alive}`"))
+    })?;
+    Ok(duration.as_secs() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pit_keep_alive_secs() {
+        assert_eq!(parse_pit_keep_alive_secs("1m").unwrap(), 60);
+        assert_eq!(parse_pit_keep_alive_secs("30s").unwrap(), 30);
+        let error = parse_pit_keep_alive_secs("1 fortnight").unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+}
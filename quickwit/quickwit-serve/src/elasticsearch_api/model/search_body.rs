@@ -20,12 +20,12 @@
 use std::collections::BTreeSet;
 use std::fmt;
 
-use quickwit_proto::search::SortOrder;
+use quickwit_proto::search::{SortMode, SortOrder};
 use quickwit_query::{ElasticQueryDsl, OneFieldMap};
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use super::ElasticDateFormat;
+use super::{ElasticDateFormat, PointInTimeBody};
 use crate::elasticsearch_api::model::{default_elasticsearch_sort_order, SortField};
 use crate::elasticsearch_api::TrackTotalHits;
 
@@ -36,6 +36,7 @@ enum FieldSortParamsForDeser {
     Object {
         order: Option<SortOrder>,
         format: Option<ElasticDateFormat>,
+        mode: Option<SortMode>,
     },
     String(SortOrder),
 }
@@ -46,10 +47,16 @@ impl From<FieldSortParamsForDeser> for FieldSortParams {
             FieldSortParamsForDeser::Object {
                 order,
                 format: date_format,
-            } => FieldSortParams { order, date_format },
+                mode,
+            } => FieldSortParams {
+                order,
+                date_format,
+                mode,
+            },
             FieldSortParamsForDeser::String(order) => FieldSortParams {
                 order: Some(order),
                 date_format: None,
+                mode: None,
             },
         }
     }
@@ -64,6 +71,31 @@ struct FieldSortParams {
     #[serde(default)]
     #[serde(rename = "format")]
     pub date_format: Option<ElasticDateFormat>,
+    #[serde(default)]
+    pub mode: Option<SortMode>,
+}
+
+/// A field requested in the `fields` or `docvalue_fields` parameters of a search request, either
+/// as a bare field name or as an object. The `format` of the object is not supported and ignored:
+/// the values are returned as they are in the document.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum FieldAndFormat {
+    FieldName(String),
+    Object {
+        field: String,
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+impl FieldAndFormat {
+    pub fn field_name(&self) -> &str {
+        match self {
+            FieldAndFormat::FieldName(field_name) => field_name,
+            FieldAndFormat::Object { field, .. } => field,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize, PartialEq)]
@@ -86,12 +118,17 @@ pub struct SearchBody {
     pub stored_fields: Option<BTreeSet<String>>,
     #[serde(default)]
     pub search_after: Vec<serde_json::Value>,
-
-    // Ignored values, only here for compatibility with OpenSearch Dashboards.
     #[serde(default)]
-    pub _source: serde::de::IgnoredAny,
+    pub pit: Option<PointInTimeBody>,
+    #[serde(default)]
+    pub fields: Vec<FieldAndFormat>,
     #[serde(default)]
-    pub docvalue_fields: serde::de::IgnoredAny,
+    pub docvalue_fields: Vec<FieldAndFormat>,
+    /// Only `false`, disabling the source of the hits, is taken into account.
+    #[serde(default)]
+    pub _source: Option<serde_json::Value>,
+
+    // Ignored values, only here for compatibility with OpenSearch Dashboards.
     #[serde(default)]
     pub script_fields: serde::de::IgnoredAny,
     #[serde(default)]
@@ -118,6 +155,7 @@ impl From<StringOrMapFieldSort> for SortField {
                     field: field_name,
                     order,
                     date_format: None,
+                    mode: None,
                 }
             }
             StringOrMapFieldSort::Sort(sort) => {
@@ -129,6 +167,7 @@ impl From<StringOrMapFieldSort> for SortField {
                     field: sort.field,
                     order,
                     date_format: sort.value.date_format,
+                    mode: sort.value.mode,
                 }
             }
         }
@@ -164,6 +203,7 @@ impl<'de> Visitor<'de> for FieldSortVecVisitor {
                 field: field_sort_key,
                 order: sort_order,
                 date_format: field_sort_params.date_format,
+                mode: field_sort_params.mode,
             });
         }
         Ok(sort_fields)
@@ -217,6 +257,26 @@ mod tests {
         assert_eq!(sort_fields[4].date_format, None);
     }
 
+    #[test]
+    fn test_sort_field_mode() {
+        let json = r#"
+        {
+            "sort": [
+                { "prices": { "order": "asc", "mode": "max" } },
+                { "sizes": { "order": "desc" } }
+            ]
+        }
+        "#;
+        let search_body: SearchBody = serde_json::from_str(json).unwrap();
+        let sort_fields = search_body.sort.unwrap();
+        assert_eq!(sort_fields.len(), 2);
+        assert_eq!(sort_fields[0].mode, Some(SortMode::Max));
+        assert_eq!(sort_fields[1].mode, None);
+
+        let json = r#"{ "sort": [{ "prices": { "mode": "avg" } }] }"#;
+        serde_json::from_str::<SearchBody>(json).unwrap_err();
+    }
+
     #[test]
     fn test_sort_field_obj() {
         let json = r#"
@@ -261,6 +321,32 @@ mod tests {
         assert_eq!(field_sorts[3].order, SortOrder::Asc);
     }
 
+    #[test]
+    fn test_fields_and_docvalue_fields() {
+        let json = r#"
+        {
+            "fields": ["level"],
+            "docvalue_fields": [
+                "service.name",
+                { "field": "timestamp", "format": "date_time" }
+            ],
+            "_source": false
+        }
+        "#;
+        let search_body: SearchBody = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            search_body.fields,
+            vec![FieldAndFormat::FieldName("level".to_string())]
+        );
+        let docvalue_field_names: Vec<&str> = search_body
+            .docvalue_fields
+            .iter()
+            .map(FieldAndFormat::field_name)
+            .collect();
+        assert_eq!(docvalue_field_names, ["service.name", "timestamp"]);
+        assert_eq!(search_body._source, Some(serde_json::Value::Bool(false)));
+    }
+
     #[test]
     fn test_unknown_field_behaviour() {
         let json = r#"
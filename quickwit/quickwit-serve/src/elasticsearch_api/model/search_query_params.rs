@@ -209,6 +209,8 @@ impl From<SearchQueryParamsCount> for SearchQueryParams {
             request_cache: value.request_cache,
             routing: value.routing,
             size: Some(0),
+            // Unlike `_search`, `_count` always returns the exact number of matching documents.
+            track_total_hits: Some(TrackTotalHits::Track(true)),
             ..Default::default()
         }
     }
@@ -245,6 +247,7 @@ fn parse_sort_field_str(sort_field_str: &str) -> Result<SortField, SearchError>
             field: field.to_string(),
             order,
             date_format: None,
+            mode: None,
         })
     } else {
         let order = default_elasticsearch_sort_order(sort_field_str);
@@ -252,6 +255,7 @@ fn parse_sort_field_str(sort_field_str: &str) -> Result<SortField, SearchError>
             field: sort_field_str.to_string(),
             order,
             date_format: None,
+            mode: None,
         })
     }
 }
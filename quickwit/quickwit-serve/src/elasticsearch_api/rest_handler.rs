@@ -34,8 +34,8 @@ use quickwit_index_management::IndexService;
 use quickwit_metastore::*;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
-    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchResponse, SortByValue,
-    SortDatetimeFormat,
+    CountHits, ListFieldsResponse, NumHitsRelation, PartialHit, ScrollRequest, SearchResponse,
+    SortByValue, SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_query::query_ast::{BoolQuery, QueryAst, UserInputQuery};
@@ -50,22 +50,23 @@ use super::filter::{
     elastic_field_capabilities_filter, elastic_index_cat_indices_filter,
     elastic_index_count_filter, elastic_index_field_capabilities_filter,
     elastic_index_search_filter, elastic_index_stats_filter, elastic_multi_search_filter,
-    elastic_resolve_index_filter, elastic_scroll_filter, elastic_stats_filter,
-    elasticsearch_filter,
+    elastic_open_point_in_time_filter, elastic_resolve_index_filter, elastic_scroll_filter,
+    elastic_stats_filter, elasticsearch_filter,
 };
 use super::model::{
     build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
-    CatIndexQueryParams, DeleteQueryParams, ElasticsearchCatIndexResponse, ElasticsearchError,
-    ElasticsearchResolveIndexEntryResponse, ElasticsearchResolveIndexResponse,
-    ElasticsearchStatsResponse, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
-    MultiSearchSingleResponse, ScrollQueryParams, SearchBody, SearchQueryParams,
-    SearchQueryParamsCount, StatsResponseEntry,
+    parse_pit_keep_alive_secs, CatIndexQueryParams, DeleteQueryParams,
+    ElasticsearchCatIndexResponse, ElasticsearchError, ElasticsearchResolveIndexEntryResponse,
+    ElasticsearchResolveIndexResponse, ElasticsearchStatsResponse, FieldAndFormat,
+    FieldCapabilityQueryParams, FieldCapabilityRequestBody, FieldCapabilityResponse,
+    MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
+    OpenPointInTimeQueryParams, OpenPointInTimeResponse, ScrollQueryParams, SearchBody,
+    SearchQueryParams, SearchQueryParamsCount, StatsResponseEntry,
 };
 use super::{make_elastic_api_response, TrackTotalHits};
 use crate::format::BodyFormat;
 use crate::rest::recover_fn;
-use crate::rest_api_response::{RestApiError, RestApiResponse};
+use crate::rest_api_response::RestApiResponse;
 use crate::{with_arg, BuildInfo};
 
 /// Elastic compatible cluster info handler.
@@ -99,17 +100,14 @@ pub fn es_compat_search_handler(
     elasticsearch_filter()
         .then(|_params: SearchQueryParams| async move {
             // TODO
-            let api_error = RestApiError {
-                status_code: StatusCode::NOT_IMPLEMENTED,
-                message: "_elastic/_search is not supported yet. Please try the index search \
-                          endpoint (_elastic/{index}/search)"
-                    .to_string(),
-            };
-            RestApiResponse::new::<(), _>(
-                &Err(api_error),
+            let es_error = ElasticsearchError::new(
                 StatusCode::NOT_IMPLEMENTED,
-                BodyFormat::default(),
-            )
+                "_elastic/_search is not supported yet. Please try the index search endpoint \
+                 (_elastic/{index}/search)"
+                    .to_string(),
+                None,
+            );
+            make_elastic_api_response::<()>(Err(es_error), BodyFormat::default())
         })
         .recover(recover_fn)
 }
@@ -241,11 +239,33 @@ pub fn es_compat_scroll_handler(
         .recover(recover_fn)
 }
 
+/// POST _elastic/{index}/_pit
+pub fn es_compat_open_point_in_time_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_open_point_in_time_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_open_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+        .recover(recover_fn)
+}
+
+/// How the hits of an Elasticsearch search request are rendered.
+#[derive(Debug, Default)]
+struct EsHitFormat {
+    append_shard_doc: bool,
+    /// Fields requested with the `fields` and `docvalue_fields` parameters, returned in the
+    /// `fields` section of the hits.
+    docvalue_fields: Vec<String>,
+    /// Whether the `_source` of the hits was disabled with `_source: false`.
+    is_source_disabled: bool,
+}
+
 fn build_request_for_es_api(
     index_id_patterns: Vec<String>,
     search_params: SearchQueryParams,
     search_body: SearchBody,
-) -> Result<(quickwit_proto::search::SearchRequest, bool), ElasticsearchError> {
+) -> Result<(quickwit_proto::search::SearchRequest, EsHitFormat), ElasticsearchError> {
     let default_operator = search_params.default_operator.unwrap_or(BooleanOperand::Or);
     // The query string, if present, takes priority over what can be in the request
     // body.
@@ -293,18 +313,28 @@ fn build_request_for_es_api(
         serde_json::to_string(&search_body.aggs).ok()
     };
 
+    // `df` overrides the default search fields of the targeted indexes.
+    let default_search_fields: Vec<String> = search_params.df.clone().into_iter().collect();
+
     let max_hits = search_params.size.or(search_body.size).unwrap_or(10);
     let start_offset = search_params.from.or(search_body.from).unwrap_or(0);
-    let count_hits = match search_params
+    let (count_hits, track_total_hits) = match search_params
         .track_total_hits
         .or(search_body.track_total_hits)
     {
-        None => CountHits::Underestimate,
-        Some(TrackTotalHits::Track(false)) => CountHits::Underestimate,
-        Some(TrackTotalHits::Count(count)) if count <= max_hits as i64 => CountHits::Underestimate,
-        Some(TrackTotalHits::Track(true) | TrackTotalHits::Count(_)) => CountHits::CountAll,
-    }
-    .into();
+        None | Some(TrackTotalHits::Track(false) | TrackTotalHits::Count(-1)) => {
+            (CountHits::Underestimate, None)
+        }
+        Some(TrackTotalHits::Track(true)) => (CountHits::CountAll, None),
+        Some(TrackTotalHits::Count(count)) if count < 0 => {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                format!(
+                    "`track_total_hits` must be a boolean, a positive integer, or -1, got {count}"
+                ),
+            )));
+        }
+        Some(TrackTotalHits::Count(count)) => (CountHits::CountAll, Some(count as u64)),
+    };
 
     let sort_fields: Vec<quickwit_proto::search::SortField> = search_params
         .sort_fields()?
@@ -318,6 +348,7 @@ fn build_request_for_es_api(
                 .date_format
                 .clone()
                 .map(|date_format| SortDatetimeFormat::from(date_format) as i32),
+            sort_mode: sort_field.mode.map(|sort_mode| sort_mode as i32),
         })
         .take_while_inclusive(|sort_field| !is_doc_field(sort_field))
         .collect();
@@ -330,9 +361,64 @@ fn build_request_for_es_api(
     let scroll_duration: Option<Duration> = search_params.parse_scroll_ttl()?;
     let scroll_ttl_secs: Option<u32> = scroll_duration.map(|duration| duration.as_secs() as u32);
 
+    let (pit_id, pit_keep_alive_secs) = if let Some(pit) = search_body.pit {
+        let pit_keep_alive_secs = pit
+            .keep_alive
+            .as_deref()
+            .map(parse_pit_keep_alive_secs)
+            .transpose()?;
+        (Some(pit.id), pit_keep_alive_secs)
+    } else {
+        (None, None)
+    };
+
     let has_doc_id_field = sort_fields.iter().any(is_doc_field);
     let search_after = partial_hit_from_search_after_param(search_body.search_after, &sort_fields)?;
 
+    let mut docvalue_fields: Vec<String> = Vec::new();
+    let requested_field_names = search_body
+        .fields
+        .iter()
+        .chain(&search_body.docvalue_fields)
+        .map(FieldAndFormat::field_name)
+        .chain(
+            search_params
+                .docvalue_fields
+                .iter()
+                .flatten()
+                .map(String::as_str),
+        );
+    for field_name in requested_field_names {
+        if field_name.contains('*') {
+            return Err(ElasticsearchError::from(SearchError::InvalidArgument(
+                format!(
+                    "wildcard patterns are not supported in `fields` and `docvalue_fields`, got \
+                     `{field_name}`"
+                ),
+            )));
+        }
+        if !docvalue_fields
+            .iter()
+            .any(|docvalue_field| docvalue_field == field_name)
+        {
+            docvalue_fields.push(field_name.to_string());
+        }
+    }
+    let is_source_disabled = search_body._source == Some(serde_json::Value::Bool(false))
+        || search_params._source.as_deref() == Some(&["false".to_string()]);
+    // Without the source, the hits only need the requested fields, which lets the searchers read
+    // them from the fast fields instead of the doc store.
+    let fields = if is_source_disabled {
+        docvalue_fields.clone()
+    } else {
+        Vec::new()
+    };
+    let hit_format = EsHitFormat {
+        append_shard_doc: has_doc_id_field,
+        docvalue_fields,
+        is_source_disabled,
+    };
+
     Ok((
         quickwit_proto::search::SearchRequest {
             index_id_patterns,
@@ -346,9 +432,21 @@ fn build_request_for_es_api(
             snippet_fields: Vec::new(),
             scroll_ttl_secs,
             search_after,
-            count_hits,
+            count_hits: count_hits.into(),
+            max_query_ast_depth: None,
+            max_query_ast_clause_count: None,
+            fields,
+            debug_fetch_docs_source: false,
+            allow_no_indices: search_params.allow_no_indices,
+            es_timestamp_alias: true,
+            default_search_fields,
+            pit_keep_alive_secs,
+            pit_id,
+            track_total_hits,
+            allow_disk_use: false,
+            aggregation_sample_rate_ppm: None,
         },
-        has_doc_id_field,
+        hit_format,
     ))
 }
 
@@ -417,6 +515,8 @@ fn partial_hit_from_search_after_param(
 #[derive(Debug, Serialize, Deserialize)]
 struct ElasticsearchCountResponse {
     count: u64,
+    #[serde(rename = "_shards")]
+    shards: ShardStatistics,
 }
 
 async fn es_compat_index_count(
@@ -426,15 +526,34 @@ async fn es_compat_index_count(
     search_service: Arc<dyn SearchService>,
 ) -> Result<ElasticsearchCountResponse, ElasticsearchError> {
     let search_params: SearchQueryParams = search_params.into();
-    let (search_request, _append_shard_doc) =
+    let (search_request, _hit_format) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
     let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    if search_response.num_successful_splits == 0 {
+        if let Some(search_error) = SearchError::from_split_errors(&search_response.failed_splits) {
+            return Err(ElasticsearchError::from(search_error));
+        }
+    }
     let search_response_rest: ElasticsearchCountResponse = ElasticsearchCountResponse {
         count: search_response.num_hits,
+        shards: shard_statistics(&search_response),
     };
     Ok(search_response_rest)
 }
 
+// There is not concept of shards here, but use this to convey split search failures.
+fn shard_statistics(search_response: &SearchResponse) -> ShardStatistics {
+    let num_failed_splits = search_response.failed_splits.len() as u32;
+    let num_successful_splits = search_response.num_successful_splits as u32;
+    ShardStatistics {
+        total: num_successful_splits + num_failed_splits,
+        successful: num_successful_splits,
+        skipped: 0u32,
+        failed: num_failed_splits,
+        failures: Vec::new(),
+    }
+}
+
 async fn es_compat_index_search(
     index_id_patterns: Vec<String>,
     search_params: SearchQueryParams,
@@ -451,13 +570,13 @@ async fn es_compat_index_search(
     let _source_includes = search_params._source_includes.clone();
     let start_instant = Instant::now();
     let allow_partial_search_results = search_params.allow_partial_search_results();
-    let (search_request, append_shard_doc) =
+    let (search_request, hit_format) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
     let search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
     let mut search_response_rest: ElasticsearchResponse = convert_to_es_search_response(
         search_response,
-        append_shard_doc,
+        &hit_format,
         _source_excludes,
         _source_includes,
         allow_partial_search_results,
@@ -697,14 +816,53 @@ fn generate_path_variants_with_suffix(input: &str) -> Vec<(&str, Option<&str>)>
     variants
 }
 
+/// Returns the value at the given dot-separated path of a JSON document, if any.
+fn get_value_at_path<'a>(
+    value: &'a serde_json::Value,
+    path: &str,
+) -> Option<&'a serde_json::Value> {
+    let map = value.as_object()?;
+    for (prefix, suffix_opt) in generate_path_variants_with_suffix(path) {
+        let Some(sub_value) = map.get(prefix) else {
+            continue;
+        };
+        match suffix_opt {
+            Some(suffix) => {
+                if let Some(value) = get_value_at_path(sub_value, suffix) {
+                    return Some(value);
+                }
+            }
+            None => return Some(sub_value),
+        }
+    }
+    None
+}
+
 fn convert_hit(
     hit: quickwit_proto::search::Hit,
-    append_shard_doc: bool,
+    hit_format: &EsHitFormat,
     _source_excludes: &Option<Vec<String>>,
     _source_includes: &Option<Vec<String>>,
 ) -> ElasticHit {
     let mut json: serde_json::Value = serde_json::from_str(&hit.json).unwrap_or(json!({}));
-    filter_source(&mut json, _source_excludes, _source_includes);
+    // Like in Elasticsearch, the values of the requested fields are always returned as arrays.
+    let fields = hit_format
+        .docvalue_fields
+        .iter()
+        .filter_map(|field_name| {
+            let values = match get_value_at_path(&json, field_name)? {
+                serde_json::Value::Array(values) => values.clone(),
+                serde_json::Value::Null => return None,
+                value => vec![value.clone()],
+            };
+            Some((field_name.clone(), serde_json::Value::Array(values)))
+        })
+        .collect();
+    if hit_format.is_source_disabled {
+        json = json!({});
+    } else {
+        filter_source(&mut json, _source_excludes, _source_includes);
+    }
     let source =
         Source::from_string(serde_json::to_string(&json).unwrap_or_else(|_| "{}".to_string()))
             .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap());
@@ -717,7 +875,7 @@ fn convert_hit(
         if let Some(sort_value2) = partial_hit.sort_value2 {
             sort.push(sort_value2.into_json());
         }
-        if append_shard_doc {
+        if hit_format.append_shard_doc {
             sort.push(serde_json::Value::String(
                 quickwit_search::GlobalDocAddress::from_partial_hit(&partial_hit).to_string(),
             ));
@@ -725,7 +883,7 @@ fn convert_hit(
     }
 
     ElasticHit {
-        fields: Default::default(),
+        fields,
         explanation: None,
         index: hit.index_id,
         id: "".to_string(),
@@ -801,10 +959,10 @@ async fn es_compat_index_multi_search(
     }
 
     // TODO: forced to do weird referencing to work around https://github.com/rust-lang/rust/issues/100905
-    // otherwise append_shard_doc is captured by ref, and we get lifetime issues
+    // otherwise hit_format is captured by ref, and we get lifetime issues
     let futures = search_requests
         .into_iter()
-        .map(|(search_request, append_shard_doc)| {
+        .map(|(search_request, hit_format)| {
             let search_service = &search_service;
             let _source_excludes = multi_search_params._source_excludes.clone();
             let _source_includes = multi_search_params._source_includes.clone();
@@ -816,7 +974,7 @@ async fn es_compat_index_multi_search(
                 let mut search_response_rest: ElasticsearchResponse =
                     convert_to_es_search_response(
                         search_response,
-                        append_shard_doc,
+                        &hit_format,
                         _source_excludes,
                         _source_includes,
                         true, //< allow_partial_results. Set to to true to match ES's behavior.
@@ -842,6 +1000,33 @@ async fn es_compat_index_multi_search(
     Ok(multi_search_response)
 }
 
+async fn es_compat_open_point_in_time(
+    index_id_patterns: Vec<String>,
+    open_point_in_time_params: OpenPointInTimeQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<OpenPointInTimeResponse, ElasticsearchError> {
+    let Some(keep_alive) = open_point_in_time_params.keep_alive else {
+        return Err(SearchError::InvalidArgument("missing keep_alive".to_string()).into());
+    };
+    let pit_keep_alive_secs = parse_pit_keep_alive_secs(&keep_alive)?;
+    // Opening a point in time boils down to running an empty search that records the splits of
+    // the targeted indexes.
+    let search_request = quickwit_proto::search::SearchRequest {
+        index_id_patterns,
+        query_ast: serde_json::to_string(&QueryAst::MatchAll)
+            .expect("Failed to serialize QueryAst"),
+        max_hits: 0,
+        count_hits: CountHits::Underestimate.into(),
+        pit_keep_alive_secs: Some(pit_keep_alive_secs),
+        ..Default::default()
+    };
+    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let Some(pit_id) = search_response.pit_id else {
+        return Err(SearchError::Internal("no point in time was opened".to_string()).into());
+    };
+    Ok(OpenPointInTimeResponse { id: pit_id })
+}
+
 async fn es_scroll(
     scroll_query_params: ScrollQueryParams,
     search_service: Arc<dyn SearchService>,
@@ -862,14 +1047,19 @@ async fn es_scroll(
         scroll_ttl_secs,
     };
     let search_response: SearchResponse = search_service.scroll(scroll_request).await?;
-    // TODO append_shard_doc depends on the initial request, but we don't have access to it
+    // TODO the hit format depends on the initial request, but we don't have access to it
 
     // Ideally, we would have wanted to reuse the setting from the initial search request.
     // However, passing that parameter is cumbersome, so we cut some corner and forbid the
     // use of scroll requests in combination with allow_partial_results set to false.
     let allow_failed_splits = true;
-    let mut search_response_rest: ElasticsearchResponse =
-        convert_to_es_search_response(search_response, false, None, None, allow_failed_splits)?;
+    let mut search_response_rest: ElasticsearchResponse = convert_to_es_search_response(
+        search_response,
+        &EsHitFormat::default(),
+        None,
+        None,
+        allow_failed_splits,
+    )?;
     search_response_rest.took = start_instant.elapsed().as_millis() as u32;
     Ok(search_response_rest)
 }
@@ -931,7 +1121,7 @@ fn convert_to_es_stats_response(
 
 fn convert_to_es_search_response(
     resp: SearchResponse,
-    append_shard_doc: bool,
+    hit_format: &EsHitFormat,
     _source_excludes: Option<Vec<String>>,
     _source_includes: Option<Vec<String>>,
     allow_partial_results: bool,
@@ -941,39 +1131,34 @@ fn convert_to_es_search_response(
             return Err(ElasticsearchError::from(search_error));
         }
     }
+    let shards = shard_statistics(&resp);
     let hits: Vec<ElasticHit> = resp
         .hits
         .into_iter()
-        .map(|hit| convert_hit(hit, append_shard_doc, &_source_excludes, &_source_includes))
+        .map(|hit| convert_hit(hit, hit_format, &_source_excludes, &_source_includes))
         .collect();
     let aggregations: Option<serde_json::Value> = if let Some(aggregation_json) = resp.aggregation {
         serde_json::from_str(&aggregation_json).ok()
     } else {
         None
     };
-    let num_failed_splits = resp.failed_splits.len() as u32;
-    let num_successful_splits = resp.num_successful_splits as u32;
-    let num_total_splits = num_successful_splits + num_failed_splits;
+    let num_hits_relation = match resp.num_hits_relation() {
+        NumHitsRelation::EqualTo => TotalHitsRelation::Equal,
+        NumHitsRelation::GreaterThanOrEqualTo => TotalHitsRelation::GreaterThanOrEqualTo,
+    };
     Ok(ElasticsearchResponse {
-        timed_out: false,
+        timed_out: resp.partial,
         hits: HitsMetadata {
             total: Some(TotalHits {
                 value: resp.num_hits,
-                relation: TotalHitsRelation::Equal,
+                relation: num_hits_relation,
             }),
             max_score: None,
             hits,
         },
         aggregations,
         scroll_id: resp.scroll_id,
-        // There is not concept of shards here, but use this to convey split search failures.
-        shards: ShardStatistics {
-            total: num_total_splits,
-            successful: num_successful_splits,
-            skipped: 0u32,
-            failed: num_failed_splits,
-            failures: Vec::new(),
-        },
+        shards,
         ..Default::default()
     })
 }
@@ -1010,6 +1195,7 @@ mod tests {
             field_name: "field1".to_string(),
             sort_order: 1,
             sort_datetime_format: None,
+            sort_mode: None,
         }];
         let error = partial_hit_from_search_after_param(search_after, sort_order).unwrap_err();
         assert_eq!(error.status, StatusCode::BAD_REQUEST);
@@ -1026,6 +1212,7 @@ mod tests {
             field_name: "_doc".to_string(),
             sort_order: 1,
             sort_datetime_format: None,
+            sort_mode: None,
         }];
         let error = partial_hit_from_search_after_param(search_after, sort_order).unwrap_err();
         assert_eq!(error.status, StatusCode::BAD_REQUEST);
@@ -1036,6 +1223,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_partial_hit_from_search_after_param_with_shard_doc_tie_breaker() {
+        let search_after = vec![
+            serde_json::json!(1701962929199u64),
+            serde_json::json!("split_id:00000001:0000002a"),
+        ];
+        let sort_order = &[
+            quickwit_proto::search::SortField {
+                field_name: "timestamp".to_string(),
+                sort_order: 1,
+                sort_datetime_format: None,
+                sort_mode: None,
+            },
+            quickwit_proto::search::SortField {
+                field_name: "_shard_doc".to_string(),
+                sort_order: 1,
+                sort_datetime_format: None,
+                sort_mode: None,
+            },
+        ];
+        let partial_hit = partial_hit_from_search_after_param(search_after, sort_order)
+            .unwrap()
+            .unwrap();
+        assert_eq!(partial_hit.split_id, "split_id");
+        assert_eq!(partial_hit.segment_ord, 1);
+        assert_eq!(partial_hit.doc_id, 42);
+        assert_eq!(
+            partial_hit.sort_value.unwrap().sort_value,
+            Some(quickwit_proto::search::SortValue::U64(1701962929199))
+        );
+        assert!(partial_hit.sort_value2.is_none());
+    }
+
     #[test]
     fn test_single_element() {
         let input = "app";
@@ -1175,6 +1395,7 @@ mod tests {
             error: "some-error".to_string(),
             split_id: "some-split-id".to_string(),
             retryable_error: true,
+            ..Default::default()
         };
         {
             let search_response = SearchResponse {
@@ -1182,7 +1403,14 @@ mod tests {
                 failed_splits: vec![split_error.clone()],
                 ..Default::default()
             };
-            convert_to_es_search_response(search_response, false, None, None, false).unwrap_err();
+            convert_to_es_search_response(
+                search_response,
+                &EsHitFormat::default(),
+                None,
+                None,
+                false,
+            )
+            .unwrap_err();
         }
         {
             let search_response = SearchResponse {
@@ -1192,8 +1420,14 @@ mod tests {
             };
             // if we allow partial search results, this should not fail, but we report the presence
             // of failed splits in the fail shard response.
-            let es_search_resp =
-                convert_to_es_search_response(search_response, false, None, None, true).unwrap();
+            let es_search_resp = convert_to_es_search_response(
+                search_response,
+                &EsHitFormat::default(),
+                None,
+                None,
+                true,
+            )
+            .unwrap();
             assert_eq!(es_search_resp.shards.failed, 1);
         }
         {
@@ -1203,7 +1437,14 @@ mod tests {
             };
             // Event if we allow partial search results, with a fail and no success, we have a
             // failure.
-            convert_to_es_search_response(search_response, false, None, None, true).unwrap_err();
+            convert_to_es_search_response(
+                search_response,
+                &EsHitFormat::default(),
+                None,
+                None,
+                true,
+            )
+            .unwrap_err();
         }
         {
             // Not having any splits (no failure + no success) is not considered a failure.
@@ -1211,7 +1452,7 @@ mod tests {
                 let search_response = SearchResponse::default();
                 let es_search_resp = convert_to_es_search_response(
                     search_response,
-                    false,
+                    &EsHitFormat::default(),
                     None,
                     None,
                     allow_partial,
@@ -1221,4 +1462,92 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_build_request_for_es_api_docvalue_fields() {
+        let search_body: SearchBody = serde_json::from_value(json!({
+            "fields": ["level"],
+            "docvalue_fields": [{ "field": "timestamp", "format": "date_time" }],
+        }))
+        .unwrap();
+        let search_params = SearchQueryParams {
+            docvalue_fields: Some(vec!["level".to_string(), "service.name".to_string()]),
+            ..Default::default()
+        };
+        let (search_request, hit_format) =
+            build_request_for_es_api(vec!["my-index".to_string()], search_params, search_body)
+                .unwrap();
+        assert_eq!(
+            hit_format.docvalue_fields,
+            ["level", "timestamp", "service.name"]
+        );
+        assert!(!hit_format.is_source_disabled);
+        // The source is returned, so the whole documents are fetched.
+        assert!(search_request.fields.is_empty());
+
+        let search_body: SearchBody = serde_json::from_value(json!({
+            "docvalue_fields": ["timestamp"],
+            "_source": false,
+        }))
+        .unwrap();
+        let (search_request, hit_format) = build_request_for_es_api(
+            vec!["my-index".to_string()],
+            SearchQueryParams::default(),
+            search_body,
+        )
+        .unwrap();
+        assert!(hit_format.is_source_disabled);
+        assert_eq!(search_request.fields, ["timestamp"]);
+
+        let search_body: SearchBody = serde_json::from_value(json!({
+            "fields": ["service.*"],
+        }))
+        .unwrap();
+        let error = build_request_for_es_api(
+            vec!["my-index".to_string()],
+            SearchQueryParams::default(),
+            search_body,
+        )
+        .unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_convert_hit_docvalue_fields() {
+        let hit = quickwit_proto::search::Hit {
+            json: json!({
+                "level": "INFO",
+                "service": { "name": "api" },
+                "tags": ["a", "b"],
+                "message": "hello",
+            })
+            .to_string(),
+            ..Default::default()
+        };
+        let hit_format = EsHitFormat {
+            docvalue_fields: vec![
+                "level".to_string(),
+                "service.name".to_string(),
+                "tags".to_string(),
+                "missing".to_string(),
+            ],
+            ..Default::default()
+        };
+        let es_hit = convert_hit(hit.clone(), &hit_format, &None, &None);
+        assert_eq!(es_hit.fields.len(), 3);
+        assert_eq!(es_hit.fields.get("level"), Some(&json!(["INFO"])));
+        assert_eq!(es_hit.fields.get("service.name"), Some(&json!(["api"])));
+        assert_eq!(es_hit.fields.get("tags"), Some(&json!(["a", "b"])));
+        let es_hit_json = serde_json::to_value(&es_hit).unwrap();
+        assert_eq!(es_hit_json["_source"]["message"], json!("hello"));
+
+        let hit_format = EsHitFormat {
+            is_source_disabled: true,
+            ..hit_format
+        };
+        let es_hit = convert_hit(hit, &hit_format, &None, &None);
+        assert_eq!(es_hit.fields.len(), 3);
+        let es_hit_json = serde_json::to_value(&es_hit).unwrap();
+        assert!(es_hit_json["_source"].get("message").is_none());
+    }
 }
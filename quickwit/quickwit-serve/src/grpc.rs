@@ -30,7 +30,6 @@ use quickwit_proto::indexing::IndexingServiceClient;
 use quickwit_proto::jaeger::storage::v1::span_reader_plugin_server::SpanReaderPluginServer;
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::LogsServiceServer;
 use quickwit_proto::opentelemetry::proto::collector::trace::v1::trace_service_server::TraceServiceServer;
-use quickwit_proto::search::search_service_server::SearchServiceServer;
 use quickwit_proto::tonic::codegen::CompressionEncoding;
 use quickwit_proto::tonic::transport::server::TcpIncoming;
 use quickwit_proto::tonic::transport::Server;
@@ -38,7 +37,7 @@ use tokio::net::TcpListener;
 use tracing::*;
 
 use crate::developer_api::DeveloperApiServer;
-use crate::search_api::GrpcSearchAdapter;
+use crate::search_api::build_search_grpc_service;
 use crate::{QuickwitServices, INDEXING_GRPC_SERVER_METRICS_LAYER};
 
 /// Starts and binds gRPC services to `grpc_listen_addr`.
@@ -149,12 +148,14 @@ pub(crate) async fn start_grpc_server(
     {
         enabled_grpc_services.insert("search");
         let search_service = services.search_service.clone();
-        let grpc_search_service = GrpcSearchAdapter::from(search_service);
-        Some(
-            SearchServiceServer::new(grpc_search_service)
-                .max_decoding_message_size(max_message_size.0 as usize)
-                .max_encoding_message_size(max_message_size.0 as usize),
-        )
+        Some(build_search_grpc_service(
+            search_service,
+            max_message_size,
+            services
+                .node_config
+                .searcher_config
+                .leaf_search_response_compression,
+        ))
     } else {
         None
     };
@@ -21,5 +21,5 @@ mod rest_handler;
 
 pub use self::rest_handler::{
     get_index_metadata_handler, index_management_handlers, IndexApi, ListSplitsQueryParams,
-    ListSplitsResponse,
+    ListSplitsResponse, RestoreIndexRequest,
 };
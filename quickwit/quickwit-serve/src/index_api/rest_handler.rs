@@ -17,9 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use bytes::Bytes;
+use quickwit_actors::{Mailbox, Observe};
 use quickwit_common::uri::Uri;
 use quickwit_config::{
     load_index_config_update, load_source_config_from_user_config, validate_index_id_pattern,
@@ -27,7 +28,13 @@ use quickwit_config::{
     INGEST_API_SOURCE_ID,
 };
 use quickwit_doc_mapper::{analyze_text, TokenizerConfig};
-use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_index_management::{
+    ImportPositionsReport, IndexService, IndexServiceError, IndexSnapshotReport, RestoreIndexReport,
+};
+use quickwit_indexing::models::ShardPositionsService;
+use quickwit_janitor::error::JanitorError;
+use quickwit_janitor::{preview_retention_policy, RetentionPolicyPreview};
+use quickwit_metastore::checkpoint::SerializableShardPositions;
 use quickwit_metastore::{
     IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt, ListSplitsQuery,
     ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, Split, SplitInfo, SplitState,
@@ -38,9 +45,9 @@ use quickwit_proto::metastore::{
     DeleteSourceRequest, EntityKind, IndexMetadataRequest, ListIndexesMetadataRequest,
     ListShardsRequest, ListShardsSubrequest, ListSplitsRequest, MarkSplitsForDeletionRequest,
     MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
-    ResetSourceCheckpointRequest, ToggleSourceRequest, UpdateIndexRequest,
+    ResetSourceCheckpointRequest, ToggleIngestRequest, ToggleSourceRequest, UpdateIndexRequest,
 };
-use quickwit_proto::types::{IndexId, IndexUid, SourceId};
+use quickwit_proto::types::{IndexId, IndexUid, SourceId, SourceUid};
 use quickwit_query::query_ast::{query_ast_from_user_text, QueryAst};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -48,6 +55,7 @@ use tracing::{info, warn};
 use warp::{Filter, Rejection};
 
 use crate::format::{extract_config_format, extract_format_from_qs};
+use crate::index_uid_path::{parse_index_id_or_uid, IndexIdOrUid};
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
 use crate::simple_list::{from_simple_list, to_simple_list};
@@ -58,18 +66,25 @@ use crate::with_arg;
     paths(
         create_index,
         update_index,
+        block_ingest,
+        unblock_ingest,
         clear_index,
         delete_index,
         list_indexes_metadata,
         list_splits,
         describe_index,
         mark_splits_for_deletion,
+        get_retention_preview,
         create_source,
         reset_source_checkpoint,
         toggle_source,
         delete_source,
+        export_positions,
+        import_positions,
+        snapshot_index,
+        restore_index,
     ),
-    components(schemas(ToggleSource, SplitsForDeletion, IndexStats))
+    components(schemas(ToggleSource, SplitsForDeletion, IndexStats, RestoreIndexRequest))
 )]
 pub struct IndexApi;
 
@@ -86,6 +101,7 @@ fn log_failure<T, E: std::fmt::Display>(
 
 pub fn index_management_handlers(
     index_service: IndexService,
+    shard_positions_service_mailbox: Arc<OnceLock<Mailbox<ShardPositionsService>>>,
     node_config: Arc<NodeConfig>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     // Indexes handlers.
@@ -93,12 +109,15 @@ pub fn index_management_handlers(
         .or(list_indexes_metadata_handler(index_service.metastore()))
         .or(create_index_handler(index_service.clone(), node_config))
         .or(update_index_handler(index_service.metastore()))
+        .or(block_ingest_handler(index_service.metastore()))
+        .or(unblock_ingest_handler(index_service.metastore()))
         .or(clear_index_handler(index_service.clone()))
         .or(delete_index_handler(index_service.clone()))
         // Splits handlers
         .or(list_splits_handler(index_service.metastore()))
         .or(describe_index_handler(index_service.metastore()))
         .or(mark_splits_for_deletion_handler(index_service.metastore()))
+        .or(retention_preview_handler(index_service.metastore()))
         // Sources handlers.
         .or(reset_source_checkpoint_handler(index_service.metastore()))
         .or(toggle_source_handler(index_service.metastore()))
@@ -106,6 +125,15 @@ pub fn index_management_handlers(
         .or(get_source_handler(index_service.metastore()))
         .or(delete_source_handler(index_service.metastore()))
         .or(get_source_shards_handler(index_service.metastore()))
+        // Positions handlers.
+        .or(export_positions_handler(
+            index_service.clone(),
+            shard_positions_service_mailbox,
+        ))
+        .or(import_positions_handler(index_service.clone()))
+        // Snapshot handlers.
+        .or(snapshot_index_handler(index_service.clone()))
+        .or(restore_index_handler(index_service.clone()))
         // Tokenizer handlers.
         .or(analyze_request_handler())
         // Parse query into query AST handler.
@@ -179,6 +207,7 @@ struct IndexStats {
     pub timestamp_field_name: Option<String>,
     pub min_timestamp: Option<i64>,
     pub max_timestamp: Option<i64>,
+    pub ingest_blocked: bool,
 }
 
 #[utoipa::path(
@@ -235,6 +264,7 @@ async fn describe_index(
         }
     }
 
+    let ingest_blocked = index_metadata.ingest_blocked;
     let index_config = index_metadata.into_index_config();
     let index_stats = IndexStats {
         index_id,
@@ -246,6 +276,7 @@ async fn describe_index(
         timestamp_field_name: index_config.doc_mapping.timestamp_field,
         min_timestamp,
         max_timestamp,
+        ingest_blocked,
     };
 
     Ok(index_stats)
@@ -316,23 +347,18 @@ pub struct ListSplitsResponse {
     ),
     params(
         ListSplitsQueryParams,
-        ("index_id" = String, Path, description = "The index ID to retrieve splits for."),
+        ("index_id" = String, Path, description = "The index ID or UID (`index_id:ulid`) to retrieve splits for."),
     )
 )]
 
 /// Get splits.
 async fn list_splits(
-    index_id: IndexId,
+    index_id_or_uid: IndexIdOrUid,
     list_split_query: ListSplitsQueryParams,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<ListSplitsResponse> {
-    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let index_uid: IndexUid = metastore
-        .index_metadata(index_metadata_request)
-        .await?
-        .deserialize_index_metadata()?
-        .index_uid;
-    info!(index_id = %index_id, list_split_query = ?list_split_query, "get-splits");
+    let index_uid: IndexUid = index_id_or_uid.resolve(&metastore).await?;
+    info!(index_uid = %index_uid, list_split_query = ?list_split_query, "get-splits");
     let mut query = ListSplitsQuery::for_index(index_uid);
     let mut offset = 0;
     if let Some(offset_value) = list_split_query.offset {
@@ -372,6 +398,7 @@ fn list_splits_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String / "splits")
         .and(warp::get())
+        .and_then(parse_index_id_or_uid)
         .and(serde_qs::warp::query(serde_qs::Config::default()))
         .and(with_arg(metastore))
         .then(list_splits)
@@ -394,22 +421,17 @@ struct SplitsForDeletion {
         (status = 200, description = "Successfully marked splits for deletion.")
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID to mark splits for deletion for."),
+        ("index_id" = String, Path, description = "The index ID or UID (`index_id:ulid`) to mark splits for deletion for."),
     )
 )]
 /// Marks splits for deletion.
 async fn mark_splits_for_deletion(
-    index_id: IndexId,
+    index_id_or_uid: IndexIdOrUid,
     splits_for_deletion: SplitsForDeletion,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<()> {
-    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let index_uid: IndexUid = metastore
-        .index_metadata(index_metadata_request)
-        .await?
-        .deserialize_index_metadata()?
-        .index_uid;
-    info!(index_id = %index_id, splits_ids = ?splits_for_deletion.split_ids, "mark-splits-for-deletion");
+    let index_uid: IndexUid = index_id_or_uid.resolve(&metastore).await?;
+    info!(index_uid = %index_uid, splits_ids = ?splits_for_deletion.split_ids, "mark-splits-for-deletion");
     let split_ids: Vec<String> = splits_for_deletion
         .split_ids
         .iter()
@@ -428,6 +450,7 @@ fn mark_splits_for_deletion_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String / "splits" / "mark-for-deletion")
         .and(warp::put())
+        .and_then(parse_index_id_or_uid)
         .and(json_body())
         .and(with_arg(metastore))
         .then(mark_splits_for_deletion)
@@ -435,6 +458,53 @@ fn mark_splits_for_deletion_handler(
         .map(into_rest_api_response)
 }
 
+fn retention_preview_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "retention:preview")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_retention_preview)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Splits",
+    path = "/indexes/{index_id}/retention:preview",
+    responses(
+        (status = 200, description = "Successfully evaluated the retention policy.", body = RetentionPolicyPreview)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to evaluate the retention policy of."),
+    )
+)]
+/// Previews the retention policy.
+///
+/// Returns the splits the retention policy of the index would mark for deletion if it were
+/// evaluated now, and the time of its next scheduled evaluation. No split is marked for deletion.
+async fn get_retention_preview(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> Result<RetentionPolicyPreview, JanitorError> {
+    info!(index_id = %index_id, "retention-preview");
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let Some(retention_policy) = &index_metadata.index_config.retention_policy_opt else {
+        return Err(JanitorError::MissingRetentionPolicy(index_id));
+    };
+    preview_retention_policy(
+        index_metadata.index_uid.clone(),
+        metastore,
+        retention_policy,
+    )
+    .await
+}
+
 #[utoipa::path(
     get,
     tag = "Indexes",
@@ -597,6 +667,91 @@ async fn update_index(
     Ok(update_resp.deserialize_index_metadata()?)
 }
 
+fn block_ingest_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "block-ingest")
+        .and(warp::put())
+        .and(with_arg(metastore))
+        .then(block_ingest)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+fn unblock_ingest_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "unblock-ingest")
+        .and(warp::put())
+        .and(with_arg(metastore))
+        .then(unblock_ingest)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/block-ingest",
+    responses(
+        (status = 200, description = "Successfully blocked ingest for the index.", body = VersionedIndexMetadata)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to block ingest for."),
+    )
+)]
+/// Blocks ingest for an index.
+///
+/// The index switches to read-only mode: ingest requests are rejected, its indexing pipelines are
+/// stopped, and no new splits can be published. The index remains searchable.
+async fn block_ingest(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> Result<IndexMetadata, IndexServiceError> {
+    info!(index_id = %index_id, "block-ingest");
+    set_ingest_blocked(index_id, true, metastore).await
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/unblock-ingest",
+    responses(
+        (status = 200, description = "Successfully unblocked ingest for the index.", body = VersionedIndexMetadata)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to unblock ingest for."),
+    )
+)]
+/// Unblocks ingest for an index.
+async fn unblock_ingest(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> Result<IndexMetadata, IndexServiceError> {
+    info!(index_id = %index_id, "unblock-ingest");
+    set_ingest_blocked(index_id, false, metastore).await
+}
+
+async fn set_ingest_blocked(
+    index_id: IndexId,
+    ingest_blocked: bool,
+    metastore: MetastoreServiceClient,
+) -> Result<IndexMetadata, IndexServiceError> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_uid = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?
+        .index_uid;
+    // Only the flag is sent to the metastore, so concurrent index updates are not overwritten.
+    let toggle_ingest_request = ToggleIngestRequest {
+        index_uid: Some(index_uid),
+        ingest_blocked,
+    };
+    let toggle_ingest_resp = metastore.toggle_ingest(toggle_ingest_request).await?;
+    Ok(toggle_ingest_resp.deserialize_index_metadata()?)
+}
+
 fn clear_index_handler(
     index_service: IndexService,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
@@ -926,6 +1081,13 @@ fn get_source_shards_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String / "sources" / String / "shards")
         .and(warp::get())
+        .and_then(
+            |index_id_or_uid_str: String, source_id: SourceId| async move {
+                let index_id_or_uid = parse_index_id_or_uid(index_id_or_uid_str).await?;
+                Ok::<_, Rejection>((index_id_or_uid, source_id))
+            },
+        )
+        .untuple_one()
         .and(with_arg(metastore))
         .then(get_source_shards)
         .and(extract_format_from_qs())
@@ -933,17 +1095,12 @@ fn get_source_shards_handler(
 }
 
 async fn get_source_shards(
-    index_id: IndexId,
+    index_id_or_uid: IndexIdOrUid,
     source_id: SourceId,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<Vec<Shard>> {
-    info!(index_id = %index_id, source_id = %source_id, "get-source-shards");
-    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let index_uid: IndexUid = metastore
-        .index_metadata(index_metadata_request)
-        .await?
-        .deserialize_index_metadata()?
-        .index_uid;
+    info!(index_id_or_uid = %index_id_or_uid, source_id = %source_id, "get-source-shards");
+    let index_uid: IndexUid = index_id_or_uid.resolve(&metastore).await?;
     let response = metastore
         .list_shards(ListShardsRequest {
             subrequests: vec![ListShardsSubrequest {
@@ -961,6 +1118,175 @@ async fn get_source_shards(
     Ok(shards)
 }
 
+fn export_positions_handler(
+    index_service: IndexService,
+    shard_positions_service_mailbox: Arc<OnceLock<Mailbox<ShardPositionsService>>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("positions")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(index_service))
+        .and(with_arg(shard_positions_service_mailbox))
+        .then(export_positions)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Sources",
+    path = "/positions",
+    responses(
+        (status = 200, description = "Successfully exported the positions of the sources.")
+    ),
+    params(
+        ListIndexesQueryParams,
+    )
+)]
+/// Exports the positions of the sources of the indexes matching the index ID patterns (all the
+/// indexes by default), along with the number of position regressions observed by this node.
+async fn export_positions(
+    list_indexes_params: ListIndexesQueryParams,
+    index_service: IndexService,
+    shard_positions_service_mailbox: Arc<OnceLock<Mailbox<ShardPositionsService>>>,
+) -> Result<SerializableShardPositions, IndexServiceError> {
+    let index_id_patterns = list_indexes_params
+        .index_id_patterns
+        .unwrap_or_else(|| vec!["*".to_string()]);
+    for index_id_pattern in &index_id_patterns {
+        validate_index_id_pattern(index_id_pattern, true)
+            .map_err(|error| IndexServiceError::InvalidIdentifier(error.to_string()))?;
+    }
+    info!(index_id_patterns = ?index_id_patterns, "export-positions");
+    let mut shard_positions = index_service.export_positions(index_id_patterns).await?;
+
+    let Some(mailbox) = shard_positions_service_mailbox.get() else {
+        return Ok(shard_positions);
+    };
+    let mut num_position_regressions_per_source = match mailbox.ask(Observe).await {
+        Ok(state) => state.num_position_regressions_per_source,
+        Err(error) => {
+            warn!(%error, "failed to observe the shard positions service");
+            return Ok(shard_positions);
+        }
+    };
+    for source_positions in &mut shard_positions.sources {
+        let source_uid = SourceUid {
+            index_uid: source_positions.index_uid.clone(),
+            source_id: source_positions.source_id.clone(),
+        };
+        source_positions.num_position_regressions = num_position_regressions_per_source
+            .remove(&source_uid.to_string())
+            .unwrap_or_default();
+    }
+    Ok(shard_positions)
+}
+
+fn import_positions_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("positions")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .then(import_positions)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    put,
+    tag = "Sources",
+    path = "/positions",
+    responses(
+        (status = 200, description = "Successfully imported the positions of the sources.")
+    ),
+)]
+/// Seeds the checkpoints of the sources with previously exported positions. Unknown indexes,
+/// sources, and shards are skipped and reported as warnings.
+async fn import_positions(
+    shard_positions: SerializableShardPositions,
+    mut index_service: IndexService,
+) -> Result<ImportPositionsReport, IndexServiceError> {
+    info!(
+        num_sources = shard_positions.sources.len(),
+        "import-positions"
+    );
+    index_service.import_positions(shard_positions).await
+}
+
+fn snapshot_index_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "snapshot")
+        .and(warp::post())
+        .and(with_arg(index_service))
+        .then(snapshot_index)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/snapshot",
+    responses(
+        (status = 200, description = "Successfully took a snapshot of the index.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to snapshot."),
+    )
+)]
+/// Writes a snapshot of the index metadata, source configs, checkpoints, and published split
+/// manifests to the index storage.
+async fn snapshot_index(
+    index_id: IndexId,
+    index_service: IndexService,
+) -> Result<IndexSnapshotReport, IndexServiceError> {
+    info!(index_id = %index_id, "snapshot-index");
+    index_service.snapshot_index(&index_id).await
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RestoreIndexRequest {
+    /// URI of the snapshot file to restore the index from.
+    #[schema(value_type = String)]
+    pub snapshot_uri: Uri,
+}
+
+fn restore_index_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / "restore")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .then(restore_index)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/restore",
+    request_body = RestoreIndexRequest,
+    responses(
+        (status = 200, description = "Successfully restored the index.")
+    ),
+)]
+/// Recreates an index from a snapshot. The index must not exist. Splits whose file is missing
+/// from the index storage are marked for deletion and reported.
+async fn restore_index(
+    restore_index_request: RestoreIndexRequest,
+    mut index_service: IndexService,
+) -> Result<RestoreIndexReport, IndexServiceError> {
+    info!(snapshot_uri = %restore_index_request.snapshot_uri, "restore-index");
+    index_service
+        .restore_index(&restore_index_request.snapshot_uri)
+        .await
+}
+
 #[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
 struct AnalyzeRequest {
     /// The tokenizer to use.
@@ -1055,16 +1381,22 @@ mod tests {
     use quickwit_common::ServiceStream;
     use quickwit_config::{SourceParams, VecSourceParams};
     use quickwit_indexing::{mock_split, MockSplitBuilder};
-    use quickwit_metastore::{metastore_for_test, IndexMetadata, ListSplitsResponseExt};
+    use quickwit_metastore::checkpoint::PartitionId;
+    use quickwit_metastore::{
+        metastore_for_test, IndexMetadata, ListSplitsResponseExt, StageSplitsRequestExt,
+    };
     use quickwit_proto::metastore::{
         EmptyResponse, IndexMetadataResponse, ListIndexesMetadataResponse, ListSplitsResponse,
-        MetastoreServiceClient, MockMetastoreService, SourceType,
+        MetastoreServiceClient, MockMetastoreService, PublishSplitsRequest, SourceType,
+        StageSplitsRequest,
     };
+    use quickwit_proto::types::Position;
     use quickwit_storage::StorageResolver;
     use serde_json::Value as JsonValue;
 
     use super::*;
     use crate::recover_fn;
+    use crate::rest_api_response::tests::assert_rest_api_error;
 
     #[tokio::test]
     async fn test_get_index() -> anyhow::Result<()> {
@@ -1082,9 +1414,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/test-index")
             .reply(&index_management_handler)
@@ -1106,9 +1441,12 @@ mod tests {
     async fn test_get_non_existing_index() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/test-index")
             .reply(&index_management_handler)
@@ -1155,9 +1493,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         {
             let resp = warp::test::request()
                 .path(
@@ -1193,6 +1534,139 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_splits_by_index_uid() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata =
+            IndexMetadata::for_test("quickwit-demo-index", "ram:///indexes/quickwit-demo-index");
+        let index_uid = index_metadata.index_uid.clone();
+        let expected_index_uid = index_uid.clone();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(move |index_metadata_request| {
+                let is_known_index = match &index_metadata_request.index_uid {
+                    Some(index_uid) => *index_uid == index_metadata.index_uid,
+                    None => {
+                        index_metadata_request.index_id.as_deref() == Some("quickwit-demo-index")
+                    }
+                };
+                if !is_known_index {
+                    return Err(MetastoreError::NotFound(EntityKind::Index {
+                        index_id: "quickwit-demo-index".to_string(),
+                    }));
+                }
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            })
+            .times(3);
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |list_splits_request: ListSplitsRequest| {
+                let list_split_query = list_splits_request.deserialize_list_splits_query().unwrap();
+                assert_eq!(
+                    list_split_query.index_uids,
+                    vec![expected_index_uid.clone()]
+                );
+                let splits = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits)]))
+            })
+            .times(2);
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from_mock(mock_metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
+        {
+            // Well-formed UID of the current incarnation of the index.
+            let resp = warp::test::request()
+                .path(&format!("/indexes/{index_uid}/splits"))
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        {
+            // Bare index ID, resolved to the current incarnation of the index.
+            let resp = warp::test::request()
+                .path("/indexes/quickwit-demo-index/splits")
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+        }
+        {
+            // Well-formed UID of an unknown incarnation of the index.
+            let unknown_index_uid = IndexUid::for_test("quickwit-demo-index", 42);
+            let resp = warp::test::request()
+                .path(&format!("/indexes/{unknown_index_uid}/splits"))
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 404);
+        }
+        {
+            // Malformed UID.
+            let resp = warp::test::request()
+                .path("/indexes/quickwit-demo-index:not-a-ulid/splits")
+                .reply(&index_management_handler)
+                .await;
+            assert_eq!(resp.status(), 400);
+            let message = assert_rest_api_error(resp.body(), "bad_request");
+            assert!(message.contains("`index_id:ulid`"), "{message}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_api_error_schema() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_index_metadata()
+            .returning(|index_metadata_request| {
+                let index_id = index_metadata_request.index_id.unwrap();
+                if index_id == "unknown-index" {
+                    return Err(MetastoreError::NotFound(EntityKind::Index { index_id }));
+                }
+                Err(MetastoreError::Internal {
+                    message: "failed to read index metadata".to_string(),
+                    cause: "connection reset".to_string(),
+                })
+            });
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from_mock(mock_metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits?split_states=Unknown")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert_rest_api_error(resp.body(), "bad_request");
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let message = assert_rest_api_error(resp.body(), "not_found");
+        assert!(message.contains("unknown-index"));
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 500);
+        let message = assert_rest_api_error(resp.body(), "internal");
+        assert!(message.contains("failed to read index metadata"));
+    }
+
     #[tokio::test]
     async fn test_describe_index() -> anyhow::Result<()> {
         let mut mock_metastore = MockMetastoreService::new();
@@ -1231,9 +1705,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/describe")
             .reply(&index_management_handler)
@@ -1251,6 +1728,7 @@ mod tests {
             "timestamp_field_name": "timestamp",
             "min_timestamp": split_1_time_range.start() - 10,
             "max_timestamp": split_1_time_range.end() + 10,
+            "ingest_blocked": false,
         });
 
         assert_eq!(actual_response_json, expected_response_json);
@@ -1288,9 +1766,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/splits")
             .reply(&index_management_handler)
@@ -1335,9 +1816,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/splits/mark-for-deletion")
             .method("PUT")
@@ -1375,9 +1859,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes?index_id_patterns=test-index-*")
             .reply(&index_management_handler)
@@ -1427,9 +1914,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/clear")
             .method("PUT")
@@ -1475,9 +1965,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         {
             // Dry run
             let resp = warp::test::request()
@@ -1513,9 +2006,12 @@ mod tests {
     async fn test_delete_on_non_existing_index() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index")
             .method("DELETE")
@@ -1530,8 +2026,11 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
         {
             let resp = warp::test::request()
                 .path("/indexes?overwrite=true")
@@ -1564,14 +2063,252 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_export_import_positions() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/positions")
+            .method("PUT")
+            .body(r#"{"sources": [
+                {"index_uid": "hdfs-logs:00000000000000000000000000", "source_id": "_ingest-api-source", "positions": {"0": "00000000000000000042"}},
+                {"index_uid": "unknown-index:00000000000000000000000000", "source_id": "_ingest-api-source", "positions": {"0": "00000000000000000042"}}
+            ]}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let report: ImportPositionsReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.num_imported_positions, 1);
+        assert_eq!(report.warnings.len(), 2);
+
+        let resp = warp::test::request()
+            .path("/positions?index_id_patterns=hdfs-*")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let shard_positions: SerializableShardPositions =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(shard_positions.sources.len(), 1);
+
+        let source_positions = &shard_positions.sources[0];
+        assert_eq!(source_positions.index_uid.index_id, "hdfs-logs");
+        assert_eq!(source_positions.source_id, "_ingest-api-source");
+        assert_eq!(
+            source_positions
+                .positions
+                .position_for_partition(&PartitionId::from(0u64)),
+            Some(&Position::offset(42u64))
+        );
+        // This node does not run the shard positions service.
+        assert_eq!(source_positions.num_position_regressions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore_index() {
+        let storage_resolver = StorageResolver::for_test();
+        let index_service = IndexService::new(metastore_for_test(), storage_resolver.clone());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("ram:///indexes");
+        let node_config = Arc::new(node_config);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            node_config.clone(),
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/snapshot")
+            .method("POST")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let snapshot_report: IndexSnapshotReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            snapshot_report.snapshot_uri,
+            "ram:///indexes/hdfs-logs/index-snapshot.json"
+        );
+        assert_eq!(snapshot_report.num_splits, 0);
+
+        let restore_index_request = RestoreIndexRequest {
+            snapshot_uri: snapshot_report.snapshot_uri,
+        };
+        // The index already exists in the original metastore.
+        let resp = warp::test::request()
+            .path("/indexes/restore")
+            .method("POST")
+            .json(&restore_index_request)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let target_metastore = metastore_for_test();
+        let target_index_service = IndexService::new(target_metastore.clone(), storage_resolver);
+        let target_index_management_handler =
+            super::index_management_handlers(target_index_service, Default::default(), node_config);
+
+        let resp = warp::test::request()
+            .path("/indexes/restore")
+            .method("POST")
+            .json(&restore_index_request)
+            .reply(&target_index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let restore_report: RestoreIndexReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(restore_report.index_uid.index_id, "hdfs-logs");
+        assert_eq!(restore_report.num_restored_splits, 0);
+
+        let index_metadata = target_metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(index_metadata.sources.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retention_preview() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::for_test());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("ram:///indexes");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "doc_mapping": {"timestamp_field": "timestamp", "field_mappings":[{"name": "timestamp", "type": "datetime", "fast": true}]}, "retention": {"period": "1 day", "schedule": "daily"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let index_uid = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap()
+            .index_uid;
+        let now_timestamp = time::OffsetDateTime::now_utc().unix_timestamp();
+        let split_metadata = |split_id: &str, time_range: Option<RangeInclusive<i64>>| {
+            quickwit_metastore::SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                num_docs: 10,
+                time_range,
+                ..Default::default()
+            }
+        };
+        let old_time_range = now_timestamp - 10 * 86_400..=now_timestamp - 9 * 86_400;
+        let splits_metadata = vec![
+            split_metadata("old-split", Some(old_time_range.clone())),
+            split_metadata("new-split", Some(now_timestamp - 3_600..=now_timestamp)),
+            split_metadata("no-time-range-split", None),
+        ];
+        let stage_splits_request =
+            StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), &splits_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid.clone()),
+            staged_split_ids: splits_metadata
+                .iter()
+                .map(|split_metadata| split_metadata.split_id.clone())
+                .collect(),
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/retention:preview")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let retention_preview: RetentionPolicyPreview =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(retention_preview.expired_splits.len(), 1);
+        let expired_split = &retention_preview.expired_splits[0];
+        assert_eq!(expired_split.split_id, "old-split");
+        assert_eq!(expired_split.time_range_start, *old_time_range.start());
+        assert_eq!(expired_split.time_range_end, *old_time_range.end());
+        assert_eq!(expired_split.num_docs, 10);
+        assert_eq!(retention_preview.num_ignored_splits, 1);
+        assert!(
+            retention_preview.next_evaluation_timestamp > retention_preview.evaluation_timestamp
+        );
+
+        // Nothing was marked for deletion.
+        let query = ListSplitsQuery::for_index(index_uid).with_split_state(SplitState::Published);
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+        let published_splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        assert_eq!(published_splits.len(), 3);
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "no-retention"}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp = warp::test::request()
+            .path("/indexes/no-retention/retention:preview")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
     #[tokio::test]
     async fn test_create_delete_index_and_source() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1693,9 +2430,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1731,9 +2471,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1767,9 +2510,12 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1788,9 +2534,12 @@ mod tests {
             MetastoreServiceClient::mocked(),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes")
             .method("POST")
@@ -1814,8 +2563,11 @@ mod tests {
         let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
         let mut node_config = NodeConfig::for_test();
         node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(node_config));
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
         {
             let resp = warp::test::request()
                 .path("/indexes")
@@ -1870,13 +2622,91 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_block_and_unblock_ingest() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let mut node_config = NodeConfig::for_test();
+        node_config.default_index_root_uri = Uri::for_test("file:///default-index-root-uri");
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(node_config),
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"version": "0.7", "index_id": "hdfs-logs", "retention": {"period": "1 day", "schedule": "daily"}}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/block-ingest")
+            .method("PUT")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_metadata: IndexMetadata = serde_json::from_slice(resp.body()).unwrap();
+        assert!(index_metadata.ingest_blocked);
+        // Blocking ingest leaves the index config untouched.
+        assert!(index_metadata.index_config.retention_policy_opt.is_some());
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/describe")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let describe_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(describe_json["ingest_blocked"], true);
+
+        let split_metadata = quickwit_metastore::SplitMetadata {
+            split_id: "test-split".to_string(),
+            index_uid: index_metadata.index_uid.clone(),
+            ..Default::default()
+        };
+        let stage_splits_request = StageSplitsRequest::try_from_split_metadata(
+            index_metadata.index_uid.clone(),
+            &split_metadata,
+        )
+        .unwrap();
+        let error = metastore
+            .stage_splits(stage_splits_request.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::FailedPrecondition { .. }));
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/unblock-ingest")
+            .method("PUT")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_metadata: IndexMetadata = serde_json::from_slice(resp.body()).unwrap();
+        assert!(!index_metadata.ingest_blocked);
+
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+
+        let resp = warp::test::request()
+            .path("/indexes/does-not-exist/block-ingest")
+            .method("PUT")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
     #[tokio::test]
     async fn test_create_source_with_bad_config() {
         let metastore = metastore_for_test();
         let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         {
             // Source config with bad version.
             let resp = warp::test::request()
@@ -1971,9 +2801,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/foo-source")
             .method("DELETE")
@@ -2018,9 +2851,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/source-to-reset/reset-checkpoint")
             .method("PUT")
@@ -2072,9 +2908,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         // Check server returns 405 if sources root path is used.
         let resp = warp::test::request()
             .path("/indexes/quickwit-demo-index/sources/source-to-toggle")
@@ -2133,9 +2972,12 @@ mod tests {
             MetastoreServiceClient::from_mock(mock_metastore),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/analyze")
             .method("POST")
@@ -2169,9 +3011,12 @@ mod tests {
             MetastoreServiceClient::mocked(),
             StorageResolver::unconfigured(),
         );
-        let index_management_handler =
-            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
-                .recover(recover_fn);
+        let index_management_handler = super::index_management_handlers(
+            index_service,
+            Default::default(),
+            Arc::new(NodeConfig::for_test()),
+        )
+        .recover(recover_fn);
         let resp = warp::test::request()
             .path("/parse-query")
             .method("POST")
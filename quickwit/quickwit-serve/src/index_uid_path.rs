@@ -0,0 +1,133 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::str::FromStr;
+
+use quickwit_config::validate_identifier;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::{IndexId, IndexUid};
+use warp::Rejection;
+
+use crate::rest::InvalidArgument;
+
+/// Index referenced in the path of a REST endpoint, either by UID (`index_id:ulid`) or by ID. In
+/// the latter case, the current incarnation of the index is looked up in the metastore.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum IndexIdOrUid {
+    Id(IndexId),
+    Uid(IndexUid),
+}
+
+impl IndexIdOrUid {
+    pub fn index_id(&self) -> &str {
+        match self {
+            IndexIdOrUid::Id(index_id) => index_id,
+            IndexIdOrUid::Uid(index_uid) => &index_uid.index_id,
+        }
+    }
+
+    /// Returns the UID of the index, checking that the index exists. The metastore returns a
+    /// not found error if the UID does not match the current incarnation of the index.
+    pub async fn resolve(self, metastore: &MetastoreServiceClient) -> MetastoreResult<IndexUid> {
+        let index_metadata_request = match self {
+            IndexIdOrUid::Id(index_id) => IndexMetadataRequest::for_index_id(index_id),
+            IndexIdOrUid::Uid(index_uid) => IndexMetadataRequest::for_index_uid(index_uid),
+        };
+        let index_uid = metastore
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?
+            .index_uid;
+        Ok(index_uid)
+    }
+}
+
+impl fmt::Display for IndexIdOrUid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexIdOrUid::Id(index_id) => write!(f, "{index_id}"),
+            IndexIdOrUid::Uid(index_uid) => write!(f, "{index_uid}"),
+        }
+    }
+}
+
+impl FromStr for IndexIdOrUid {
+    type Err = InvalidArgument;
+
+    fn from_str(index_id_or_uid_str: &str) -> Result<Self, Self::Err> {
+        let invalid_argument = || {
+            InvalidArgument(format!(
+                "invalid index ID or UID `{index_id_or_uid_str}`: expected an index ID or an \
+                 index UID formatted as `index_id:ulid` (e.g. \
+                 `my-index:01HJ6JQ2S7ARDS4M4J5K2V3C6W`)"
+            ))
+        };
+        let index_id_or_uid = if index_id_or_uid_str.contains(':') {
+            let index_uid =
+                IndexUid::from_str(index_id_or_uid_str).map_err(|_| invalid_argument())?;
+            IndexIdOrUid::Uid(index_uid)
+        } else {
+            IndexIdOrUid::Id(index_id_or_uid_str.to_string())
+        };
+        validate_identifier("index", index_id_or_uid.index_id()).map_err(|_| invalid_argument())?;
+        Ok(index_id_or_uid)
+    }
+}
+
+/// Parses an index ID or UID extracted from the path of a request. Malformed values are rejected
+/// with a `400 Bad Request` error.
+pub(crate) async fn parse_index_id_or_uid(
+    index_id_or_uid_str: String,
+) -> Result<IndexIdOrUid, Rejection> {
+    IndexIdOrUid::from_str(&index_id_or_uid_str).map_err(warp::reject::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_id_or_uid_from_str() {
+        assert_eq!(
+            IndexIdOrUid::from_str("my-index").unwrap(),
+            IndexIdOrUid::Id("my-index".to_string())
+        );
+        assert_eq!(
+            IndexIdOrUid::from_str("my-index:00000000000000000000000001").unwrap(),
+            IndexIdOrUid::Uid(IndexUid::for_test("my-index", 1))
+        );
+        for malformed in [
+            "my-index:",
+            "my-index:not-a-ulid",
+            ":00000000000000000000000001",
+            "my index",
+            "my-index:00000000000000000000000001:00000000000000000000000001",
+        ] {
+            let error = IndexIdOrUid::from_str(malformed).unwrap_err();
+            assert!(
+                error.0.contains("`index_id:ulid`"),
+                "unexpected error message for `{malformed}`: {}",
+                error.0
+            );
+        }
+    }
+}
@@ -19,4 +19,8 @@
 
 mod rest_handler;
 
-pub use rest_handler::{indexing_get_handler, IndexingApi};
+pub use rest_handler::{
+    doc_processing_errors_handler, drain_handler, drain_status_handler, indexing_get_handler,
+    indexing_plan_dry_run_handler, indexing_plan_watch_handler, pause_pipeline_handler,
+    resume_pipeline_handler, unscheduled_sources_handler, DrainStatus, IndexingApi,
+};
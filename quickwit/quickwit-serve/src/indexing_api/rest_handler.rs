@@ -19,17 +19,42 @@
 
 use std::convert::Infallible;
 
+use futures::StreamExt;
 use quickwit_actors::{AskError, Mailbox, Observe};
-use quickwit_indexing::actors::{IndexingService, IndexingServiceCounters};
-use warp::{Filter, Rejection};
+use quickwit_cluster::Cluster;
+use quickwit_control_plane::control_plane::{
+    ControlPlane, DryRunIndexingPlan, GetUnscheduledSources, WatchIndexingPlan,
+};
+use quickwit_control_plane::indexing_scheduler::{
+    IndexingPlanDryRun, IndexingPlanEvent, IndexingPlanOverrides, IndexingPlanWatcher,
+    UnscheduledSource,
+};
+use quickwit_indexing::actors::{
+    DocProcessingErrorSample, IndexingService, IndexingServiceCounters,
+};
+use quickwit_indexing::models::{GetDocProcessingErrorSamples, PausePipeline, ResumePipeline};
+use quickwit_proto::indexing::IndexingError;
+use quickwit_proto::types::PipelineUid;
+use serde::Serialize;
+use warp::{Filter, Rejection, Reply};
 
-use crate::format::extract_format_from_qs;
-use crate::require;
+use crate::format::{extract_format_from_qs, BodyFormat};
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
+use crate::{require, with_arg};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(indexing_endpoint))]
+#[openapi(paths(
+    indexing_endpoint,
+    pause_pipeline_endpoint,
+    resume_pipeline_endpoint,
+    doc_processing_errors_endpoint,
+    indexing_plan_dry_run_endpoint,
+    unscheduled_sources_endpoint,
+    indexing_plan_watch_endpoint,
+    drain_status_endpoint,
+    drain_endpoint
+))]
 pub struct IndexingApi;
 
 #[utoipa::path(
@@ -63,3 +88,397 @@ pub fn indexing_get_handler(
         .map(into_rest_api_response)
         .recover(recover_fn)
 }
+
+#[utoipa::path(
+    put,
+    tag = "Indexing",
+    path = "/indexing/pipelines/{pipeline_uid}/pause",
+    responses(
+        (status = 200, description = "Successfully paused indexing pipeline.")
+    ),
+    params(
+        ("pipeline_uid" = String, Path, description = "The UID of the pipeline to pause."),
+    )
+)]
+/// Pause Indexing Pipeline
+///
+/// Stops the source of the pipeline from emitting new batches. In-flight batches are still
+/// indexed and published.
+async fn pause_pipeline_endpoint(
+    pipeline_uid: PipelineUid,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<(), AskError<IndexingError>> {
+    indexing_service_mailbox
+        .ask_for_res(PausePipeline { pipeline_uid })
+        .await
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexing",
+    path = "/indexing/pipelines/{pipeline_uid}/resume",
+    responses(
+        (status = 200, description = "Successfully resumed indexing pipeline.")
+    ),
+    params(
+        ("pipeline_uid" = String, Path, description = "The UID of the pipeline to resume."),
+    )
+)]
+/// Resume Indexing Pipeline
+async fn resume_pipeline_endpoint(
+    pipeline_uid: PipelineUid,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<(), AskError<IndexingError>> {
+    indexing_service_mailbox
+        .ask_for_res(ResumePipeline { pipeline_uid })
+        .await
+}
+
+fn pause_pipeline_filter() -> impl Filter<Extract = (PipelineUid,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "pipelines" / PipelineUid / "pause").and(warp::put())
+}
+
+fn resume_pipeline_filter() -> impl Filter<Extract = (PipelineUid,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "pipelines" / PipelineUid / "resume").and(warp::put())
+}
+
+pub fn pause_pipeline_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    pause_pipeline_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .then(pause_pipeline_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+pub fn resume_pipeline_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    resume_pipeline_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .then(resume_pipeline_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexing",
+    path = "/indexing/pipelines/{pipeline_uid}/errors",
+    responses(
+        (status = 200, description = "Successfully fetched the doc processing error samples.", body = [DocProcessingErrorSample])
+    ),
+    params(
+        ("pipeline_uid" = String, Path, description = "The UID of the pipeline to inspect."),
+    )
+)]
+/// Get Doc Processing Error Samples
+///
+/// Returns the most recent documents rejected by the pipeline, from the oldest to the most
+/// recent. The samples are cleared when the pipeline restarts.
+async fn doc_processing_errors_endpoint(
+    pipeline_uid: PipelineUid,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<Vec<DocProcessingErrorSample>, AskError<IndexingError>> {
+    indexing_service_mailbox
+        .ask_for_res(GetDocProcessingErrorSamples { pipeline_uid })
+        .await
+}
+
+fn doc_processing_errors_filter() -> impl Filter<Extract = (PipelineUid,), Error = Rejection> + Clone
+{
+    warp::path!("indexing" / "pipelines" / PipelineUid / "errors").and(warp::get())
+}
+
+pub fn doc_processing_errors_handler(
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    doc_processing_errors_filter()
+        .and(require(indexing_service_mailbox_opt))
+        .then(doc_processing_errors_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexing",
+    path = "/indexing/plan/dry-run",
+    request_body(content = Object, description = "Hypothetical changes to the cluster.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successfully computed the hypothetical indexing plan.")
+    ),
+)]
+/// Dry Run Indexing Plan
+///
+/// Computes the physical indexing plan the control plane would build if the overrides were
+/// applied to the cluster and compares it with the current plan. Nothing is applied.
+async fn indexing_plan_dry_run_endpoint(
+    overrides: IndexingPlanOverrides,
+    control_plane_mailbox: Mailbox<ControlPlane>,
+) -> Result<IndexingPlanDryRun, AskError<Infallible>> {
+    control_plane_mailbox
+        .ask(DryRunIndexingPlan(overrides))
+        .await
+}
+
+fn indexing_plan_dry_run_filter(
+) -> impl Filter<Extract = (IndexingPlanOverrides,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "plan" / "dry-run")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+}
+
+/// Only served by the node running the control plane.
+pub fn indexing_plan_dry_run_handler(
+    control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    indexing_plan_dry_run_filter()
+        .and(require(control_plane_mailbox_opt))
+        .then(indexing_plan_dry_run_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexing",
+    path = "/indexing/plan/unscheduled",
+    responses(
+        (status = 200, description = "Successfully listed the unscheduled sources.")
+    ),
+)]
+/// List Unscheduled Sources
+///
+/// Lists the sources left out of the indexing plan because no indexer matches their placement
+/// selector.
+async fn unscheduled_sources_endpoint(
+    control_plane_mailbox: Mailbox<ControlPlane>,
+) -> Result<Vec<UnscheduledSource>, AskError<Infallible>> {
+    control_plane_mailbox.ask(GetUnscheduledSources).await
+}
+
+fn unscheduled_sources_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path!("indexing" / "plan" / "unscheduled").and(warp::get())
+}
+
+/// Only served by the node running the control plane.
+pub fn unscheduled_sources_handler(
+    control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    unscheduled_sources_filter()
+        .and(require(control_plane_mailbox_opt))
+        .then(unscheduled_sources_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexing",
+    path = "/indexing/plan/watch",
+    responses(
+        (status = 200, description = "Successfully subscribed to the indexing plan, streamed as server-sent events.")
+    ),
+)]
+/// Watch Indexing Plan
+///
+/// Streams the physical indexing plan as server-sent events: a `snapshot` event with the current
+/// plan first, then a `diff` event listing the tasks added, removed, or moved between indexers
+/// every time the plan changes. A client falling too far behind receives a new `snapshot` event
+/// instead of the diffs it missed.
+async fn indexing_plan_watch_endpoint(
+    control_plane_mailbox: Mailbox<ControlPlane>,
+) -> Result<IndexingPlanWatcher, AskError<Infallible>> {
+    control_plane_mailbox.ask(WatchIndexingPlan).await
+}
+
+fn make_indexing_plan_watch_reply(
+    watcher_result: Result<IndexingPlanWatcher, AskError<Infallible>>,
+) -> warp::reply::Response {
+    match watcher_result {
+        Ok(watcher) => {
+            let sse_event_stream = watcher.into_stream().map(|event| {
+                let event_type = match &event {
+                    IndexingPlanEvent::Snapshot(_) => "snapshot",
+                    IndexingPlanEvent::Diff { .. } => "diff",
+                };
+                warp::sse::Event::default()
+                    .event(event_type)
+                    .id(event.plan_version().to_string())
+                    .json_data(&event)
+            });
+            warp::sse::reply(warp::sse::keep_alive().stream(sse_event_stream)).into_response()
+        }
+        Err(error) => {
+            into_rest_api_response::<(), _>(Err(error), BodyFormat::default()).into_response()
+        }
+    }
+}
+
+fn indexing_plan_watch_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path!("indexing" / "plan" / "watch").and(warp::get())
+}
+
+/// Only served by the node running the control plane.
+pub fn indexing_plan_watch_handler(
+    control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    indexing_plan_watch_filter()
+        .and(require(control_plane_mailbox_opt))
+        .then(indexing_plan_watch_endpoint)
+        .map(make_indexing_plan_watch_reply)
+        .recover(recover_fn)
+}
+
+/// Drain status of an indexer.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct DrainStatus {
+    /// Whether the indexer is being drained.
+    pub is_draining: bool,
+    /// Number of indexing pipelines still running on the indexer.
+    pub num_running_pipelines: usize,
+    /// Whether the indexer is draining and no longer runs any indexing pipeline, i.e. it can be
+    /// shut down.
+    pub is_drained: bool,
+}
+
+async fn get_drain_status(
+    cluster: &Cluster,
+    indexing_service_mailbox: &Mailbox<IndexingService>,
+) -> Result<DrainStatus, AskError<Infallible>> {
+    let is_draining = cluster.is_self_node_draining().await;
+    let counters = indexing_service_mailbox.ask(Observe).await?;
+    let num_running_pipelines = counters.num_running_pipelines;
+    Ok(DrainStatus {
+        is_draining,
+        num_running_pipelines,
+        is_drained: is_draining && num_running_pipelines == 0,
+    })
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexing",
+    path = "/indexing/drain",
+    responses(
+        (status = 200, description = "Successfully fetched the drain status of the indexer.", body = DrainStatus)
+    ),
+)]
+/// Get Indexer Drain Status
+async fn drain_status_endpoint(
+    cluster: Cluster,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<DrainStatus, AskError<Infallible>> {
+    get_drain_status(&cluster, &indexing_service_mailbox).await
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexing",
+    path = "/indexing/drain",
+    responses(
+        (status = 200, description = "Successfully marked the indexer as draining.", body = DrainStatus)
+    ),
+)]
+/// Drain Indexer
+///
+/// Marks the indexer as draining. The control plane stops assigning new indexing tasks to the
+/// indexer and moves its running tasks to other indexers. Once the returned status reports the
+/// indexer as drained, it can be shut down.
+async fn drain_endpoint(
+    cluster: Cluster,
+    indexing_service_mailbox: Mailbox<IndexingService>,
+) -> Result<DrainStatus, AskError<Infallible>> {
+    cluster.set_self_node_draining(true).await;
+    get_drain_status(&cluster, &indexing_service_mailbox).await
+}
+
+pub fn drain_status_handler(
+    cluster: Cluster,
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "drain")
+        .and(warp::get())
+        .and(with_arg(cluster))
+        .and(require(indexing_service_mailbox_opt))
+        .then(drain_status_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+pub fn drain_handler(
+    cluster: Cluster,
+    indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "drain")
+        .and(warp::put())
+        .and(with_arg(cluster))
+        .and(require(indexing_service_mailbox_opt))
+        .then(drain_endpoint)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+
+    use super::*;
+    use crate::rest::recover_fn_final;
+    use crate::rest_api_response::tests::assert_rest_api_error;
+
+    #[tokio::test]
+    async fn test_indexing_api_error_schema() {
+        let universe = Universe::new();
+        let (indexing_service_mailbox, indexing_service_inbox) =
+            universe.create_test_mailbox::<IndexingService>();
+        let (control_plane_mailbox, _control_plane_inbox) =
+            universe.create_test_mailbox::<ControlPlane>();
+        let indexing_api_handler = pause_pipeline_handler(Some(indexing_service_mailbox))
+            .or(indexing_plan_dry_run_handler(Some(control_plane_mailbox)))
+            .recover(recover_fn_final);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/indexing/plan/dry-run")
+            .body("not JSON")
+            .reply(&indexing_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert_rest_api_error(resp.body(), "bad_request");
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/indexing/pipelines/not-a-pipeline-uid/pause")
+            .reply(&indexing_api_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        assert_rest_api_error(resp.body(), "route_not_found");
+
+        // The indexing service drops the message without replying.
+        tokio::spawn(async move {
+            indexing_service_inbox
+                .recv_typed_message::<PausePipeline>()
+                .await
+                .unwrap();
+        });
+        let pipeline_uid = PipelineUid::for_test(0u128);
+        let resp = warp::test::request()
+            .method("PUT")
+            .path(&format!("/indexing/pipelines/{pipeline_uid}/pause"))
+            .reply(&indexing_api_handler)
+            .await;
+        assert_eq!(resp.status(), 500);
+        assert_rest_api_error(resp.body(), "internal");
+    }
+}
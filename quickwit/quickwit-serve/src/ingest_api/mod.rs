@@ -21,5 +21,5 @@ mod rest_handler;
 
 #[cfg(test)]
 pub(crate) use rest_handler::tests::setup_ingest_service;
-pub(crate) use rest_handler::{ingest_api_handlers, lines};
+pub(crate) use rest_handler::{check_ingest_not_blocked, ingest_api_handlers, lines};
 pub use rest_handler::{IngestApi, IngestApiSchemas};
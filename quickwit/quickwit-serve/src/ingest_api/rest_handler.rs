@@ -23,11 +23,15 @@ use quickwit_ingest::{
     CommitType, DocBatchBuilder, DocBatchV2Builder, FetchResponse, IngestRequest, IngestResponse,
     IngestService, IngestServiceClient, IngestServiceError, TailRequest,
 };
+use quickwit_metastore::IndexesMetadataResponseExt;
 use quickwit_proto::ingest::router::{
     IngestRequestV2, IngestResponseV2, IngestRouterService, IngestRouterServiceClient,
     IngestSubrequest,
 };
 use quickwit_proto::ingest::CommitTypeV2;
+use quickwit_proto::metastore::{
+    IndexMetadataSubrequest, IndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+};
 use quickwit_proto::types::{DocUidGenerator, IndexId};
 use serde::Deserialize;
 use warp::{Filter, Rejection};
@@ -67,9 +71,10 @@ struct IngestV2Options {
 pub(crate) fn ingest_api_handlers(
     ingest_router: IngestRouterServiceClient,
     ingest_service: IngestServiceClient,
+    metastore: MetastoreServiceClient,
     config: IngestApiConfig,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    ingest_handler(ingest_service.clone(), config.clone())
+    ingest_handler(ingest_service.clone(), metastore, config.clone())
         .or(tail_handler(ingest_service))
         .or(ingest_v2_handler(ingest_router, config))
 }
@@ -82,7 +87,7 @@ fn ingest_filter(
         .and(warp::body::content_length_limit(
             config.content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(config.content_length_limit))
         .and(serde_qs::warp::query::<IngestOptions>(
             serde_qs::Config::default(),
         ))
@@ -90,10 +95,12 @@ fn ingest_filter(
 
 fn ingest_handler(
     ingest_service: IngestServiceClient,
+    metastore: MetastoreServiceClient,
     config: IngestApiConfig,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     ingest_filter(config)
         .and(with_arg(ingest_service))
+        .and(with_arg(metastore))
         .then(ingest)
         .map(|result| into_rest_api_response(result, BodyFormat::default()))
 }
@@ -106,7 +113,7 @@ fn ingest_v2_filter(
         .and(warp::body::content_length_limit(
             config.content_length_limit.as_u64(),
         ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(config.content_length_limit))
         .and(serde_qs::warp::query::<IngestV2Options>(
             serde_qs::Config::default(),
         ))
@@ -196,11 +203,14 @@ async fn ingest(
     body: Body,
     ingest_options: IngestOptions,
     ingest_service: IngestServiceClient,
+    metastore: MetastoreServiceClient,
 ) -> Result<IngestResponse, IngestServiceError> {
     if disable_ingest_v1() {
         let message = "ingest v1 is disabled: environment variable `QW_DISABLE_INGEST_V1` is set";
         return Err(IngestServiceError::Internal(message.to_string()));
     }
+    check_ingest_not_blocked(&metastore, [index_id.clone()]).await?;
+
     // The size of the body should be an upper bound of the size of the batch. The removal of the
     // end of line character for each doc compensates the addition of the `DocCommand` header.
     let mut doc_batch_builder = DocBatchBuilder::with_capacity(index_id, body.content.remaining());
@@ -215,6 +225,45 @@ async fn ingest(
     Ok(ingest_response)
 }
 
+/// Returns an error if ingest is blocked for one of the indexes. Ingest v2 enforces the block in
+/// the control plane, but ingest v1 writes to the local queues directly, so its handlers must check
+/// it. Unknown indexes are ignored and reported by the ingest service.
+pub(crate) async fn check_ingest_not_blocked(
+    metastore: &MetastoreServiceClient,
+    index_ids: impl IntoIterator<Item = IndexId>,
+) -> Result<(), IngestServiceError> {
+    let subrequests: Vec<IndexMetadataSubrequest> = index_ids
+        .into_iter()
+        .map(|index_id| IndexMetadataSubrequest {
+            index_id: Some(index_id),
+            index_uid: None,
+        })
+        .collect();
+    if subrequests.is_empty() {
+        return Ok(());
+    }
+    let indexes_metadata_request = IndexesMetadataRequest { subrequests };
+    let indexes_metadata = metastore
+        .indexes_metadata(indexes_metadata_request)
+        .await
+        .map_err(|error| {
+            IngestServiceError::Unavailable(format!("failed to fetch indexes metadata: {error}"))
+        })?
+        .deserialize_indexes_metadata()
+        .await
+        .map_err(|error| IngestServiceError::Internal(error.to_string()))?;
+
+    if let Some(index_metadata) = indexes_metadata
+        .iter()
+        .find(|index_metadata| index_metadata.ingest_blocked)
+    {
+        return Err(IngestServiceError::IngestBlocked {
+            index_id: index_metadata.index_id().to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub fn tail_handler(
     ingest_service: IngestServiceClient,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
@@ -266,16 +315,24 @@ pub(crate) mod tests {
 
     use bytes::Bytes;
     use quickwit_actors::{Mailbox, Universe};
-    use quickwit_config::IngestApiConfig;
+    use quickwit_config::{IndexConfig, IngestApiConfig};
     use quickwit_ingest::{
         init_ingest_api, CreateQueueIfNotExistsRequest, FetchRequest, FetchResponse,
         IngestApiService, IngestResponse, IngestServiceClient, SuggestTruncateRequest,
         QUEUES_DIR_NAME,
     };
-    use quickwit_proto::ingest::router::IngestRouterServiceClient;
+    use quickwit_metastore::{metastore_for_test, CreateIndexRequestExt};
+    use quickwit_proto::ingest::router::{
+        IngestFailure, IngestFailureReason, IngestResponseV2, IngestRouterServiceClient,
+        MockIngestRouterService,
+    };
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService, ToggleIngestRequest};
+    use warp::Filter;
 
     use super::ingest_api_handlers;
     use crate::ingest_api::lines;
+    use crate::rest::recover_fn;
+    use crate::rest_api_response::tests::assert_rest_api_error;
 
     #[test]
     fn test_process_lines() {
@@ -328,8 +385,12 @@ pub(crate) mod tests {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
         let ingest_router = IngestRouterServiceClient::mocked();
-        let ingest_api_handlers =
-            ingest_api_handlers(ingest_router, ingest_service, IngestApiConfig::default());
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            IngestApiConfig::default(),
+        );
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -364,8 +425,12 @@ pub(crate) mod tests {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
         let ingest_router = IngestRouterServiceClient::mocked();
-        let ingest_api_handlers =
-            ingest_api_handlers(ingest_router, ingest_service, IngestApiConfig::default());
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            IngestApiConfig::default(),
+        );
         let payload = r#"
             {"id": 1, "message": "push"}
             {"id": 2, "message": "push"}
@@ -390,8 +455,12 @@ pub(crate) mod tests {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &config).await;
         let ingest_router = IngestRouterServiceClient::mocked();
-        let ingest_api_handlers =
-            ingest_api_handlers(ingest_router, ingest_service, IngestApiConfig::default());
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            IngestApiConfig::default(),
+        );
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -410,8 +479,12 @@ pub(crate) mod tests {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
         let ingest_router = IngestRouterServiceClient::mocked();
-        let ingest_api_handlers =
-            ingest_api_handlers(ingest_router, ingest_service, config.clone());
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            config.clone(),
+        );
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -423,6 +496,140 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_error_schema() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .returning(|_| Ok(IngestResponseV2::default()));
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            IngestApiConfig::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest?commit=eventually")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert_rest_api_error(resp.body(), "bad_request");
+
+        let resp = warp::test::request()
+            .path("/unknown-index/ingest")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let message = assert_rest_api_error(resp.body(), "not_found");
+        assert!(message.contains("unknown-index"));
+
+        // The router returns neither a success nor a failure for the subrequest.
+        let resp = warp::test::request()
+            .path("/my-index/ingest-v2")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 500);
+        assert_rest_api_error(resp.body(), "internal");
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_returns_403_when_ingest_blocked() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let metastore = metastore_for_test();
+        let index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let toggle_ingest_request = ToggleIngestRequest {
+            index_uid: Some(index_uid),
+            ingest_blocked: true,
+        };
+        metastore
+            .toggle_ingest(toggle_ingest_request)
+            .await
+            .unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore,
+            IngestApiConfig::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let message = assert_rest_api_error(resp.body(), "forbidden");
+        assert_eq!(message, "ingest is blocked for index `my-index`");
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_v2_api_returns_403_when_ingest_blocked() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&[], &IngestApiConfig::default()).await;
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().returning(|request| {
+            let subrequest = &request.subrequests[0];
+            let failure = IngestFailure {
+                subrequest_id: subrequest.subrequest_id,
+                index_id: subrequest.index_id.clone(),
+                source_id: subrequest.source_id.clone(),
+                reason: IngestFailureReason::IngestBlocked as i32,
+            };
+            Ok(IngestResponseV2 {
+                successes: Vec::new(),
+                failures: vec![failure],
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            IngestApiConfig::default(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest-v2")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let message = assert_rest_api_error(resp.body(), "forbidden");
+        assert_eq!(message, "ingest is blocked for index `my-index`");
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_blocks_when_wait_is_specified() {
         let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
@@ -431,6 +638,7 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service_client,
+            metastore_for_test(),
             IngestApiConfig::default(),
         );
         let handle = tokio::spawn(async move {
@@ -480,6 +688,7 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service_client,
+            metastore_for_test(),
             IngestApiConfig::default(),
         );
         let handle = tokio::spawn(async move {
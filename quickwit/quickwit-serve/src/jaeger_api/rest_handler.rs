@@ -339,7 +339,6 @@ fn make_jaeger_api_response<T: serde::Serialize>(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
     use std::sync::Arc;
 
     use quickwit_config::JaegerConfig;
@@ -349,6 +348,7 @@ mod tests {
 
     use super::*;
     use crate::recover_fn;
+    use crate::rest_api_response::tests::assert_rest_api_error;
 
     #[tokio::test]
     async fn test_when_jaeger_not_found() {
@@ -358,9 +358,8 @@ mod tests {
             .reply(&jaeger_api_handler)
             .await;
         assert_eq!(resp.status(), 404);
-        let error_body = serde_json::from_slice::<HashMap<String, String>>(resp.body()).unwrap();
-        assert!(error_body.contains_key("message"));
-        assert_eq!(error_body.get("message").unwrap(), "Route not found");
+        let message = assert_rest_api_error(resp.body(), "route_not_found");
+        assert_eq!(message, "Route not found");
     }
 
     #[tokio::test]
@@ -465,6 +464,13 @@ mod tests {
                     scroll_id: None,
                     failed_splits: Vec::new(),
                     num_successful_splits: 1,
+                    fetch_docs_source: None,
+                    partial: false,
+                    warnings: Vec::new(),
+                    pit_id: None,
+                    cpu_time_micros: 0,
+                    num_hits_relation: 0,
+                    aggregation_sample_rate: None,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -498,6 +504,13 @@ mod tests {
                     scroll_id: None,
                     failed_splits: Vec::new(),
                     num_successful_splits: 1,
+                    fetch_docs_source: None,
+                    partial: false,
+                    warnings: Vec::new(),
+                    pit_id: None,
+                    cpu_time_micros: 0,
+                    num_hits_relation: 0,
+                    aggregation_sample_rate: None,
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
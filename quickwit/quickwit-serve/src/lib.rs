@@ -29,6 +29,7 @@ mod format;
 mod grpc;
 mod health_check_api;
 mod index_api;
+mod index_uid_path;
 mod indexing_api;
 mod ingest_api;
 mod jaeger_api;
@@ -53,12 +54,12 @@ use std::fs;
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use anyhow::{bail, Context};
 use bytesize::ByteSize;
-pub(crate) use decompression::Body;
+pub(crate) use decompression::{Body, EncodedBody};
 pub use format::BodyFormat;
 use futures::StreamExt;
 use itertools::Itertools;
@@ -74,17 +75,20 @@ use quickwit_common::runtimes::RuntimesConfig;
 use quickwit_common::tower::{
     BalanceChannel, BoxFutureInfaillible, BufferLayer, Change, CircuitBreakerEvaluator,
     ConstantRate, EstimateRateLayer, EventListenerLayer, GrpcMetricsLayer, LoadShedLayer,
-    RateLimitLayer, RetryLayer, RetryPolicy, SmaRateEstimator, TimeoutLayer,
+    RateLimitLayer, RetryLayer, RetryPolicy, RpcPolicyLayer, RpcPolicyMetrics, SmaRateEstimator,
+    TimeoutLayer,
 };
 use quickwit_common::uri::Uri;
 use quickwit_common::{get_bool_from_env, spawn_named_task};
 use quickwit_config::service::QuickwitService;
-use quickwit_config::{ClusterConfig, IngestApiConfig, NodeConfig};
+use quickwit_config::{ClusterConfig, IndexerConfig, IngestApiConfig, NodeConfig, SearcherConfig};
 use quickwit_control_plane::control_plane::{ControlPlane, ControlPlaneEventSubscriber};
 use quickwit_control_plane::{IndexerNodeInfo, IndexerPool};
 use quickwit_index_management::{IndexService as IndexManager, IndexServiceError};
 use quickwit_indexing::actors::IndexingService;
-use quickwit_indexing::models::ShardPositionsService;
+use quickwit_indexing::models::{
+    IngestLatencyRecorder, ShardPositionsFlushSettings, ShardPositionsService,
+};
 use quickwit_indexing::start_indexing_service;
 use quickwit_ingest::{
     get_idle_shard_timeout, setup_local_shards_update_listener, start_ingest_api_service,
@@ -110,7 +114,7 @@ use quickwit_proto::metastore::{
     MetastoreServiceClient,
 };
 use quickwit_proto::search::ReportSplitsRequest;
-use quickwit_proto::types::NodeId;
+use quickwit_proto::types::{NodeId, PipelineUid};
 use quickwit_search::{
     create_search_client_from_channel, start_searcher_service, SearchJobPlacer, SearchService,
     SearchServiceClient, SearcherContext, SearcherPool,
@@ -124,7 +128,7 @@ use tracing::{debug, error, info, warn};
 use warp::{Filter, Rejection};
 
 pub use crate::build_info::{BuildInfo, RuntimeInfo};
-pub use crate::index_api::{ListSplitsQueryParams, ListSplitsResponse};
+pub use crate::index_api::{ListSplitsQueryParams, ListSplitsResponse, RestoreIndexRequest};
 pub use crate::metrics::SERVE_METRICS;
 use crate::rate_modulator::RateModulator;
 #[cfg(test)]
@@ -174,6 +178,9 @@ static METASTORE_GRPC_CLIENT_METRICS_LAYER: Lazy<GrpcMetricsLayer> =
 static METASTORE_GRPC_SERVER_METRICS_LAYER: Lazy<GrpcMetricsLayer> =
     Lazy::new(|| GrpcMetricsLayer::new("metastore", "server"));
 
+static SEARCHER_METASTORE_RPC_POLICY_METRICS: Lazy<RpcPolicyMetrics> =
+    Lazy::new(|| RpcPolicyMetrics::new("metastore", "searcher"));
+
 static GRPC_TIMEOUT_LAYER: Lazy<TimeoutLayer> =
     Lazy::new(|| TimeoutLayer::new(Duration::from_secs(30)));
 
@@ -186,6 +193,9 @@ struct QuickwitServices {
     pub control_plane_client: ControlPlaneServiceClient,
     pub index_manager: IndexManager,
     pub indexing_service_opt: Option<Mailbox<IndexingService>>,
+    /// Set once the shard positions service is spawned, which only happens on indexer and
+    /// control plane nodes.
+    pub shard_positions_service_mailbox: Arc<OnceLock<Mailbox<ShardPositionsService>>>,
     // Ingest v1
     pub ingest_service: IngestServiceClient,
     // Ingest v2
@@ -201,6 +211,8 @@ struct QuickwitServices {
     /// It is only used to serve the rest API calls and will only execute
     /// the root requests.
     pub search_service: Arc<dyn SearchService>,
+    /// Shared by the searcher service of the node. Its limits can be reloaded at runtime.
+    pub searcher_context: Arc<SearcherContext>,
 
     pub env_filter_reload_fn: EnvFilterReloadFn,
 
@@ -329,6 +341,7 @@ async fn start_control_plane_if_needed(
             metastore_client.clone(),
             node_config.default_index_root_uri.clone(),
             &node_config.ingest_api_config,
+            &node_config.indexer_config,
         )
         .await?;
 
@@ -369,8 +382,12 @@ async fn start_control_plane_if_needed(
 fn start_shard_positions_service(
     ingester_opt: Option<Ingester>,
     cluster: Cluster,
+    metastore: MetastoreServiceClient,
+    eof_retention: Duration,
+    flush_settings: ShardPositionsFlushSettings,
     event_broker: EventBroker,
     spawn_ctx: SpawnContext,
+    shard_positions_service_mailbox: Arc<OnceLock<Mailbox<ShardPositionsService>>>,
 ) {
     // We spawn a task here, because we need the ingester to be ready before spawning the
     // the `ShardPositionsService`. If we don't, all the events we emit too early will be dismissed.
@@ -383,7 +400,15 @@ fn start_shard_positions_service(
                 warn!("ingester failed to reach ready status");
             }
         }
-        ShardPositionsService::spawn(&spawn_ctx, event_broker, cluster);
+        let mailbox = ShardPositionsService::spawn(
+            &spawn_ctx,
+            event_broker,
+            cluster,
+            metastore,
+            eof_retention,
+            flush_settings,
+        );
+        let _ = shard_positions_service_mailbox.set(mailbox);
     });
 }
 
@@ -399,6 +424,7 @@ pub async fn serve_quickwit(
     let cluster = start_cluster_service(&node_config)
         .await
         .context("failed to start cluster service")?;
+    PipelineUid::set_node_hint(cluster.self_node_id());
 
     let event_broker = EventBroker::default();
     let indexer_pool = IndexerPool::default();
@@ -538,14 +564,26 @@ pub async fn serve_quickwit(
     .await
     .context("failed to start ingest v2 service")?;
 
+    let shard_positions_service_mailbox = Arc::new(OnceLock::new());
+
     if node_config.is_service_enabled(QuickwitService::Indexer)
         || node_config.is_service_enabled(QuickwitService::ControlPlane)
     {
         start_shard_positions_service(
             ingester_opt.clone(),
             cluster.clone(),
+            metastore_client.clone(),
+            node_config.indexer_config.shard_positions_eof_retention(),
+            ShardPositionsFlushSettings {
+                interval: node_config.indexer_config.shard_positions_flush_interval(),
+                batch_size: node_config
+                    .indexer_config
+                    .shard_positions_flush_batch_size
+                    .get(),
+            },
             event_broker.clone(),
             universe.spawn_ctx().clone(),
+            shard_positions_service_mailbox.clone(),
         );
     }
 
@@ -596,17 +634,19 @@ pub async fn serve_quickwit(
             None
         };
 
-    let searcher_context = Arc::new(SearcherContext::new(
-        node_config.searcher_config.clone(),
-        split_cache_opt,
-    ));
+    let searcher_context = Arc::new(
+        SearcherContext::new(node_config.searcher_config.clone(), split_cache_opt)
+            .with_aggregation_spill_dir(
+                node_config.data_dir_path.join("searcher-aggregation-spill"),
+            ),
+    );
 
     let (search_job_placer, search_service) = setup_searcher(
         &node_config,
         cluster.change_stream(),
         metastore_through_control_plane.clone(),
         storage_resolver.clone(),
-        searcher_context,
+        searcher_context.clone(),
     )
     .await
     .context("failed to start searcher service")?;
@@ -632,6 +672,12 @@ pub async fn serve_quickwit(
             None
         };
 
+    // Searchers record the ingest-to-publish latency of the splits published by the indexing
+    // pipelines.
+    if node_config.is_service_enabled(QuickwitService::Searcher) {
+        event_broker.subscribe(IngestLatencyRecorder).forever();
+    }
+
     let janitor_service_opt = if node_config.is_service_enabled(QuickwitService::Janitor) {
         let janitor_service = start_janitor_service(
             &universe,
@@ -693,6 +739,7 @@ pub async fn serve_quickwit(
         _report_splits_subscription_handle_opt: report_splits_subscription_handle_opt,
         index_manager,
         indexing_service_opt,
+        shard_positions_service_mailbox,
         ingest_router_opt: Some(ingest_router),
         ingest_router_service,
         ingest_service,
@@ -702,6 +749,7 @@ pub async fn serve_quickwit(
         otlp_logs_service_opt,
         otlp_traces_service_opt,
         search_service,
+        searcher_context,
         env_filter_reload_fn,
     });
     // Setup and start gRPC server.
@@ -976,6 +1024,25 @@ async fn setup_ingest_v2(
     Ok((ingest_router, ingest_router_service, ingester_opt))
 }
 
+/// Applies the per-RPC timeout and retry policies configured for the searcher to the metastore
+/// client. The timeout of `list_splits` bounds each attempt until the whole split stream is
+/// consumed.
+fn searcher_metastore_client(
+    searcher_config: &SearcherConfig,
+    metastore: MetastoreServiceClient,
+) -> MetastoreServiceClient {
+    let rpc_policy_layer = RpcPolicyLayer::new(
+        searcher_config.metastore_rpc_policy_table(),
+        SEARCHER_METASTORE_RPC_POLICY_METRICS.clone(),
+    );
+    let stream_timeout_layer = rpc_policy_layer.stream_timeout_layer();
+
+    MetastoreServiceClient::tower()
+        .stack_layer(rpc_policy_layer)
+        .stack_list_splits_layer(stream_timeout_layer)
+        .build(metastore)
+}
+
 async fn setup_searcher(
     node_config: &NodeConfig,
     cluster_change_stream: ClusterChangeStream,
@@ -985,6 +1052,7 @@ async fn setup_searcher(
 ) -> anyhow::Result<(SearchJobPlacer, Arc<dyn SearchService>)> {
     let searcher_pool = SearcherPool::default();
     let search_job_placer = SearchJobPlacer::new(searcher_pool.clone());
+    let metastore = searcher_metastore_client(&node_config.searcher_config, metastore);
     let search_service = start_searcher_service(
         metastore,
         storage_resolver,
@@ -995,6 +1063,8 @@ async fn setup_searcher(
     let search_service_clone = search_service.clone();
     let max_message_size = node_config.grpc_config.max_message_size;
     let request_timeout = node_config.searcher_config.request_timeout();
+    let leaf_search_response_compression =
+        node_config.searcher_config.leaf_search_response_compression;
     let searcher_change_stream = cluster_change_stream.filter_map(move |cluster_change| {
         let search_service_clone = search_service_clone.clone();
         Box::pin(async move {
@@ -1019,7 +1089,8 @@ async fn setup_searcher(
                             grpc_addr,
                             timeout_channel,
                             max_message_size,
-                        );
+                        )
+                        .with_leaf_search_response_compression(leaf_search_response_compression);
                         Some(Change::Insert(grpc_addr, search_client))
                     }
                 }
@@ -1052,6 +1123,7 @@ async fn setup_control_plane(
     metastore: MetastoreServiceClient,
     default_index_root_uri: Uri,
     ingest_api_config: &IngestApiConfig,
+    indexer_config: &IndexerConfig,
 ) -> anyhow::Result<Mailbox<ControlPlane>> {
     let cluster_id = cluster.cluster_id().to_string();
     let replication_factor = ingest_api_config
@@ -1064,6 +1136,7 @@ async fn setup_control_plane(
         default_index_root_uri,
         replication_factor,
         shard_throughput_limit: ingest_api_config.shard_throughput_limit,
+        max_shards_per_pipeline: indexer_config.max_shards_per_pipeline,
     };
     let (control_plane_mailbox, _control_plane_handle, mut readiness_rx) = ControlPlane::spawn(
         universe,
@@ -1121,6 +1194,10 @@ fn setup_indexer_pool(
                     let node_id = node.node_id().to_owned();
                     let indexing_tasks = node.indexing_tasks().to_vec();
                     let indexing_capacity = node.indexing_capacity();
+                    let is_draining = node.is_draining();
+                    let supports_plan_deltas = node.supports_indexing_plan_deltas();
+                    let num_unhealthy_pipelines = node.num_unhealthy_pipelines();
+                    let indexing_affinity_labels = node.indexing_affinity_labels().clone();
 
                     if node.is_self_node() {
                         // Here, since the service is available locally, we bypass the network stack
@@ -1144,6 +1221,10 @@ fn setup_indexer_pool(
                                 client,
                                 indexing_tasks,
                                 indexing_capacity,
+                                is_draining,
+                                supports_plan_deltas,
+                                num_unhealthy_pipelines,
+                                indexing_affinity_labels,
                             },
                         );
                         Some(change)
@@ -1164,6 +1245,10 @@ fn setup_indexer_pool(
                                 client,
                                 indexing_tasks,
                                 indexing_capacity,
+                                is_draining,
+                                supports_plan_deltas,
+                                num_unhealthy_pipelines,
+                                indexing_affinity_labels,
                             },
                         );
                         Some(change)
@@ -1307,7 +1392,7 @@ pub mod lambda_search_api {
     };
     pub use crate::index_api::get_index_metadata_handler;
     pub use crate::rest::recover_fn;
-    pub use crate::search_api::{search_get_handler, search_post_handler};
+    pub use crate::search_api::{search_get_handler, search_post_handler, SearchRequestLimits};
 }
 
 #[cfg(test)]
@@ -1315,11 +1400,16 @@ mod tests {
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport, ClusterNode};
     use quickwit_common::uri::Uri;
     use quickwit_common::ServiceStream;
-    use quickwit_config::SearcherConfig;
-    use quickwit_metastore::{metastore_for_test, IndexMetadata};
-    use quickwit_proto::indexing::IndexingTask;
+    use quickwit_config::MetastoreRpcPolicyConfig;
+    use quickwit_metastore::{
+        metastore_for_test, IndexMetadata, IndexMetadataResponseExt, ListSplitsResponseExt,
+    };
+    use quickwit_proto::indexing::{IndexingTask, IndexingTaskPriority};
     use quickwit_proto::ingest::ingester::{MockIngesterService, ObservationMessage};
-    use quickwit_proto::metastore::{ListIndexesMetadataResponse, MockMetastoreService};
+    use quickwit_proto::metastore::{
+        IndexMetadataRequest, IndexMetadataResponse, ListIndexesMetadataResponse,
+        ListSplitsRequest, ListSplitsResponse, MockMetastoreService,
+    };
     use quickwit_proto::types::{IndexUid, PipelineUid};
     use quickwit_search::Job;
     use tokio::sync::watch;
@@ -1444,6 +1534,8 @@ mod tests {
             source_id: "test-source".to_string(),
             shard_ids: Vec::new(),
             params_fingerprint: 0,
+            standby: false,
+            priority: IndexingTaskPriority::Normal as i32,
         };
         let updated_indexer_node = ClusterNode::for_test(
             "test-indexer-node",
@@ -1473,6 +1565,89 @@ mod tests {
         assert!(indexer_pool.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_searcher_metastore_client() {
+        let mut searcher_config = SearcherConfig::default();
+        searcher_config.metastore_rpc_policies.insert(
+            "list_indexes_metadata".to_string(),
+            MetastoreRpcPolicyConfig {
+                max_retries: 2,
+                base_backoff_millis: 1,
+                max_backoff_millis: 1,
+                ..Default::default()
+            },
+        );
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(3)
+            .returning(|_| Err(MetastoreError::Unavailable("unavailable".to_string())));
+        mock_metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(|_| Err(MetastoreError::Unavailable("unavailable".to_string())));
+        let metastore = searcher_metastore_client(
+            &searcher_config,
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        metastore
+            .list_indexes_metadata(ListIndexesMetadataRequest::all())
+            .await
+            .unwrap_err();
+        metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_searcher_metastore_client_list_splits_timeout() {
+        let mut searcher_config = SearcherConfig::default();
+        searcher_config.metastore_rpc_policies.insert(
+            "list_splits".to_string(),
+            MetastoreRpcPolicyConfig {
+                timeout_millis: Some(50),
+                ..Default::default()
+            },
+        );
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_list_splits().times(1).returning(|_| {
+            // The stream is opened right away, but its splits come after the timeout.
+            let (splits_tx, splits_stream) = ServiceStream::new_unbounded();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                let _ = splits_tx.send(Ok(ListSplitsResponse::empty()));
+            });
+            Ok(splits_stream)
+        });
+        mock_metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(move |_| {
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+        let metastore = searcher_metastore_client(
+            &searcher_config,
+            MetastoreServiceClient::from_mock(mock_metastore),
+        );
+        let mut splits_stream = metastore
+            .list_splits(ListSplitsRequest::default())
+            .await
+            .unwrap();
+        let error = splits_stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, MetastoreError::Timeout(_)));
+        assert!(splits_stream.next().await.is_none());
+
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("test-index".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(index_metadata.index_id(), "test-index");
+    }
+
     #[tokio::test]
     async fn test_setup_searcher() {
         let node_config = NodeConfig::for_test();
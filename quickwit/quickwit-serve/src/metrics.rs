@@ -29,6 +29,10 @@ pub struct ServeMetrics {
     pub ongoing_requests: IntGaugeVec<1>,
     pub pending_requests: IntGaugeVec<1>,
     pub circuit_break_total: IntCounter,
+    pub leaf_search_response_uncompressed_bytes_total: IntCounter,
+    pub leaf_search_response_wire_bytes_total: IntCounterVec<1>,
+    pub elastic_bulk_docs_total: IntCounterVec<2>,
+    pub elastic_bulk_parse_errors_total: IntCounterVec<1>,
 }
 
 impl Default for ServeMetrics {
@@ -70,6 +74,35 @@ impl Default for ServeMetrics {
                 ["endpoint_group"],
             ),
             circuit_break_total,
+            leaf_search_response_uncompressed_bytes_total: new_counter(
+                "leaf_search_response_uncompressed_bytes_total",
+                "Number of bytes of the leaf search responses before compression.",
+                "grpc",
+                &[],
+            ),
+            leaf_search_response_wire_bytes_total: new_counter_vec(
+                "leaf_search_response_wire_bytes_total",
+                "Number of bytes of the leaf search responses sent over the wire, per encoding.",
+                "grpc",
+                &[],
+                ["encoding"],
+            ),
+            elastic_bulk_docs_total: new_counter_vec(
+                "elastic_bulk_docs_total",
+                "Number of documents received by the Elasticsearch bulk API, per index and status \
+                 (accepted or rejected).",
+                "",
+                &[],
+                ["index", "status"],
+            ),
+            elastic_bulk_parse_errors_total: new_counter_vec(
+                "elastic_bulk_parse_errors_total",
+                "Number of malformed action lines and documents received by the Elasticsearch \
+                 bulk API, per index.",
+                "",
+                &[],
+                ["index"],
+            ),
         }
     }
 }
@@ -20,23 +20,40 @@
 use std::sync::Arc;
 
 use quickwit_config::NodeConfig;
-use serde_json::json;
+use quickwit_search::{SearchError, SearcherConfigReload, SearcherContext};
+use serde_json::{json, Value as JsonValue};
+use tracing::info;
 use warp::{Filter, Rejection};
 
+use crate::format::extract_format_from_qs;
 use crate::rest::recover_fn;
+use crate::rest_api_response::{
+    error_code_descriptions, into_rest_api_response, RestApiErrorCode, RestApiErrorCodeDescription,
+};
 use crate::{with_arg, BuildInfo, RuntimeInfo};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(node_version_handler, node_config_handler,))]
+#[openapi(
+    paths(
+        node_version_handler,
+        node_config_handler,
+        searcher_config_put_handler,
+        error_codes_handler,
+    ),
+    components(schemas(RestApiErrorCode, RestApiErrorCodeDescription))
+)]
 pub struct NodeInfoApi;
 
 pub fn node_info_handler(
     build_info: &'static BuildInfo,
     runtime_info: &'static RuntimeInfo,
     config: Arc<NodeConfig>,
+    searcher_context: Arc<SearcherContext>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     node_version_handler(build_info, runtime_info)
         .or(node_config_handler(config))
+        .or(searcher_config_put_handler(searcher_context))
+        .or(error_codes_handler())
         .recover(recover_fn)
 }
 
@@ -79,11 +96,68 @@ async fn get_config(config: Arc<NodeConfig>) -> impl warp::Reply {
     warp::reply::json(&config)
 }
 
+#[utoipa::path(
+    put,
+    tag = "Node Info",
+    path = "/config/searcher",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Successfully reloaded the searcher config.")
+    ),
+)]
+/// Reload Searcher Config
+///
+/// Applies the searcher limits that can change without restarting the node: concurrency limits,
+/// memory budgets, cache capacities, and request timeout. The body is a JSON merge patch of the
+/// current searcher config: the omitted fields keep their current value. The other fields are
+/// reported as ignored.
+fn searcher_config_put_handler(
+    searcher_context: Arc<SearcherContext>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("config" / "searcher")
+        .and(warp::put())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(with_arg(searcher_context))
+        .then(put_searcher_config)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+async fn put_searcher_config(
+    searcher_config_patch: JsonValue,
+    searcher_context: Arc<SearcherContext>,
+) -> Result<SearcherConfigReload, SearchError> {
+    let reload = searcher_context
+        .patch_searcher_config(searcher_config_patch)
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+    info!(applied=?reload.applied, ignored=?reload.ignored, "reloaded searcher config");
+    Ok(reload)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Node Info",
+    path = "/error-codes",
+    responses(
+        (status = 200, description = "Successfully listed the error codes.", body = [RestApiErrorCodeDescription])
+    ),
+)]
+/// List Error Codes
+///
+/// Lists the codes of the errors returned by the REST API, along with their HTTP status code.
+fn error_codes_handler() -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path("error-codes")
+        .and(warp::path::end())
+        .and(warp::get())
+        .map(|| warp::reply::json(&error_code_descriptions()))
+}
+
 #[cfg(test)]
 mod tests {
     use assert_json_diff::assert_json_include;
     use quickwit_common::uri::Uri;
-    use serde_json::Value as JsonValue;
+    use quickwit_config::SearcherConfig;
 
     use super::*;
     use crate::recover_fn;
@@ -94,8 +168,14 @@ mod tests {
         let runtime_info = RuntimeInfo::get();
         let mut config = NodeConfig::for_test();
         config.metastore_uri = Uri::for_test("postgresql://username:password@db");
-        let handler = node_info_handler(build_info, runtime_info, Arc::new(config.clone()))
-            .recover(recover_fn);
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let handler = node_info_handler(
+            build_info,
+            runtime_info,
+            Arc::new(config.clone()),
+            searcher_context,
+        )
+        .recover(recover_fn);
         let resp = warp::test::request().path("/version").reply(&handler).await;
         assert_eq!(resp.status(), 200);
         let info_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
@@ -123,5 +203,85 @@ mod tests {
             "metastore_uri": "postgresql://username:***redacted***@db",
         });
         assert_json_include!(actual: resp_json, expected: expected_response_json);
+
+        let resp = warp::test::request()
+            .path("/error-codes")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let error_codes: Vec<&str> = resp_json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error_code_json| error_code_json["code"].as_str().unwrap())
+            .collect();
+        assert!(error_codes.contains(&"not_found"));
+        assert!(error_codes.contains(&"route_not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_put_searcher_config() {
+        let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default(), None));
+        let handler = node_info_handler(
+            BuildInfo::get(),
+            RuntimeInfo::get(),
+            Arc::new(NodeConfig::for_test()),
+            searcher_context.clone(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/config/searcher")
+            .json(&serde_json::json!({
+                "max_num_concurrent_split_searches": 4,
+                "max_query_length": "1KiB",
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "applied": ["max_num_concurrent_split_searches"],
+            "ignored": ["max_query_length"],
+        });
+        assert_eq!(resp_json, expected_response_json);
+        assert_eq!(
+            searcher_context
+                .searcher_config()
+                .max_num_concurrent_split_searches,
+            4
+        );
+
+        // The omitted fields keep their current value.
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/config/searcher")
+            .json(&serde_json::json!({
+                "max_num_concurrent_split_streams": 3,
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "applied": ["max_num_concurrent_split_streams"],
+            "ignored": [],
+        });
+        assert_eq!(resp_json, expected_response_json);
+        let searcher_config = searcher_context.searcher_config();
+        assert_eq!(searcher_config.max_num_concurrent_split_searches, 4);
+        assert_eq!(searcher_config.max_num_concurrent_split_streams, 3);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/config/searcher")
+            .json(&serde_json::json!({
+                "leaf_request_memory_budget": "0B",
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
     }
 }
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use bytesize::ByteSize;
 use quickwit_common::rate_limited_error;
 use quickwit_opentelemetry::otlp::{
     OtlpGrpcLogsService, OtlpGrpcTracesService, OTEL_LOGS_INDEX_ID, OTEL_TRACES_INDEX_ID,
@@ -45,14 +46,20 @@ use crate::{require, with_arg, Body, BodyFormat};
 pub struct OtlpApi;
 
 /// Setup OpenTelemetry API handlers.
+///
+/// The request bodies are rejected if they exceed `max_num_bytes` once decompressed.
 pub(crate) fn otlp_ingest_api_handlers(
     otlp_logs_service: Option<OtlpGrpcLogsService>,
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    max_num_bytes: ByteSize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    otlp_default_logs_handler(otlp_logs_service.clone())
-        .or(otlp_default_traces_handler(otlp_traces_service.clone()).recover(recover_fn))
-        .or(otlp_logs_handler(otlp_logs_service).recover(recover_fn))
-        .or(otlp_ingest_traces_handler(otlp_traces_service).recover(recover_fn))
+    otlp_default_logs_handler(otlp_logs_service.clone(), max_num_bytes)
+        .or(
+            otlp_default_traces_handler(otlp_traces_service.clone(), max_num_bytes)
+                .recover(recover_fn),
+        )
+        .or(otlp_logs_handler(otlp_logs_service, max_num_bytes).recover(recover_fn))
+        .or(otlp_ingest_traces_handler(otlp_traces_service, max_num_bytes).recover(recover_fn))
 }
 
 /// Open Telemetry REST/Protobuf logs ingest endpoint.
@@ -67,6 +74,7 @@ pub(crate) fn otlp_ingest_api_handlers(
 )]
 pub(crate) fn otlp_default_logs_handler(
     otlp_logs_service: Option<OtlpGrpcLogsService>,
+    max_num_bytes: ByteSize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_logs_service)
         .and(warp::path!("otlp" / "v1" / "logs"))
@@ -75,7 +83,7 @@ pub(crate) fn otlp_default_logs_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(get_body_bytes())
+        .and(get_body_bytes(max_num_bytes))
         .then(|otlp_logs_service, body| async move {
             otlp_ingest_logs(otlp_logs_service, OTEL_LOGS_INDEX_ID.to_string(), body).await
         })
@@ -85,6 +93,7 @@ pub(crate) fn otlp_default_logs_handler(
 
 pub(crate) fn otlp_logs_handler(
     otlp_log_service: Option<OtlpGrpcLogsService>,
+    max_num_bytes: ByteSize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_log_service)
         .and(warp::path!(String / "otlp" / "v1" / "logs"))
@@ -93,7 +102,7 @@ pub(crate) fn otlp_logs_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(get_body_bytes())
+        .and(get_body_bytes(max_num_bytes))
         .then(otlp_ingest_logs)
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -111,6 +120,7 @@ pub(crate) fn otlp_logs_handler(
 )]
 pub(crate) fn otlp_default_traces_handler(
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    max_num_bytes: ByteSize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
         .and(warp::path!("otlp" / "v1" / "traces"))
@@ -119,7 +129,7 @@ pub(crate) fn otlp_default_traces_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(get_body_bytes())
+        .and(get_body_bytes(max_num_bytes))
         .then(|otlp_traces_service, body| async move {
             otlp_ingest_traces(otlp_traces_service, OTEL_TRACES_INDEX_ID.to_string(), body).await
         })
@@ -129,6 +139,7 @@ pub(crate) fn otlp_default_traces_handler(
 
 pub(crate) fn otlp_ingest_traces_handler(
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    max_num_bytes: ByteSize,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
         .and(warp::path!(String / "otlp" / "v1" / "traces"))
@@ -137,7 +148,7 @@ pub(crate) fn otlp_ingest_traces_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(get_body_bytes())
+        .and(get_body_bytes(max_num_bytes))
         .then(otlp_ingest_traces)
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -201,6 +212,7 @@ mod tests {
     use flate2::write::GzEncoder;
     use flate2::Compression;
     use prost::Message;
+    use quickwit_config::IngestApiConfig;
     use quickwit_ingest::CommitType;
     use quickwit_opentelemetry::otlp::{
         make_resource_spans_for_test, OtlpGrpcLogsService, OtlpGrpcTracesService,
@@ -276,8 +288,12 @@ mod tests {
             }],
         };
         let body = export_logs_request.encode_to_vec();
-        let otlp_traces_api_handler =
-            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service)).recover(recover_fn);
+        let otlp_traces_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            IngestApiConfig::default().content_length_limit,
+        )
+        .recover(recover_fn);
         {
             // Test default otlp endpoint
             let resp = warp::test::request()
@@ -371,8 +387,12 @@ mod tests {
             resource_spans: make_resource_spans_for_test(),
         };
         let body = export_trace_request.encode_to_vec();
-        let otlp_traces_api_handler =
-            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service)).recover(recover_fn);
+        let otlp_traces_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            IngestApiConfig::default().content_length_limit,
+        )
+        .recover(recover_fn);
         {
             // Test default otlp endpoint
             let resp = warp::test::request()
@@ -21,7 +21,7 @@ use std::fmt::Formatter;
 use std::sync::Arc;
 
 use hyper::http::HeaderValue;
-use hyper::{http, Method, StatusCode};
+use hyper::{http, Method};
 use quickwit_common::tower::BoxFutureInfaillible;
 use quickwit_search::SearchService;
 use tokio::net::TcpListener;
@@ -35,22 +35,27 @@ use warp::filters::log::Info;
 use warp::{redirect, Filter, Rejection, Reply};
 
 use crate::cluster_api::cluster_handler;
-use crate::decompression::{CorruptedData, UnsupportedEncoding};
+use crate::decompression::{BodyTooLarge, CorruptedData, UnsupportedEncoding};
 use crate::delete_task_api::delete_task_api_handlers;
 use crate::developer_api::developer_api_routes;
 use crate::elasticsearch_api::elastic_api_handlers;
 use crate::health_check_api::health_check_handlers;
 use crate::index_api::index_management_handlers;
-use crate::indexing_api::indexing_get_handler;
+use crate::indexing_api::{
+    doc_processing_errors_handler, drain_handler, drain_status_handler, indexing_get_handler,
+    indexing_plan_dry_run_handler, indexing_plan_watch_handler, pause_pipeline_handler,
+    resume_pipeline_handler, unscheduled_sources_handler,
+};
 use crate::ingest_api::ingest_api_handlers;
 use crate::jaeger_api::jaeger_api_handlers;
 use crate::metrics_api::metrics_handler;
 use crate::node_info_handler::node_info_handler;
 use crate::otlp_api::otlp_ingest_api_handlers;
-use crate::rest_api_response::{RestApiError, RestApiResponse};
+use crate::rest_api_response::{RestApiError, RestApiErrorCode, RestApiResponse};
 use crate::search_api::{
-    search_get_handler, search_plan_get_handler, search_plan_post_handler, search_post_handler,
-    search_stream_handler,
+    field_caps_handler, search_get_handler, search_hits_stream_handler, search_plan_get_handler,
+    search_plan_post_handler, search_post_handler, search_stream_handler, suggest_handler,
+    SearchRequestLimits,
 };
 use crate::template_api::index_template_api_handlers;
 use crate::ui_handler::ui_handler;
@@ -236,11 +241,25 @@ pub(crate) async fn start_rest_server(
 
 fn search_routes(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_get_handler(search_service.clone())
-        .or(search_post_handler(search_service.clone()))
-        .or(search_plan_get_handler(search_service.clone()))
-        .or(search_plan_post_handler(search_service.clone()))
+    search_get_handler(search_service.clone(), search_request_limits)
+        .or(search_post_handler(
+            search_service.clone(),
+            search_request_limits,
+        ))
+        .or(search_plan_get_handler(
+            search_service.clone(),
+            search_request_limits,
+        ))
+        .or(search_plan_post_handler(
+            search_service.clone(),
+            search_request_limits,
+        ))
+        .or(search_hits_stream_handler(
+            search_service.clone(),
+            search_request_limits,
+        ))
         .or(search_stream_handler(search_service))
         .recover(recover_fn)
 }
@@ -263,26 +282,68 @@ fn api_v1_routes(
             BuildInfo::get(),
             RuntimeInfo::get(),
             quickwit_services.node_config.clone(),
+            quickwit_services.searcher_context.clone(),
         ))
         .or(indexing_get_handler(
             quickwit_services.indexing_service_opt.clone(),
         ))
-        .or(search_routes(quickwit_services.search_service.clone()))
+        .or(pause_pipeline_handler(
+            quickwit_services.indexing_service_opt.clone(),
+        ))
+        .or(resume_pipeline_handler(
+            quickwit_services.indexing_service_opt.clone(),
+        ))
+        .or(doc_processing_errors_handler(
+            quickwit_services.indexing_service_opt.clone(),
+        ))
+        .or(indexing_plan_dry_run_handler(
+            quickwit_services.control_plane_server_opt.clone(),
+        ))
+        .or(unscheduled_sources_handler(
+            quickwit_services.control_plane_server_opt.clone(),
+        ))
+        .or(indexing_plan_watch_handler(
+            quickwit_services.control_plane_server_opt.clone(),
+        ))
+        .or(drain_status_handler(
+            quickwit_services.cluster.clone(),
+            quickwit_services.indexing_service_opt.clone(),
+        ))
+        .or(drain_handler(
+            quickwit_services.cluster.clone(),
+            quickwit_services.indexing_service_opt.clone(),
+        ))
+        .or(search_routes(
+            quickwit_services.search_service.clone(),
+            SearchRequestLimits::from(&quickwit_services.node_config.searcher_config),
+        ))
+        .or(field_caps_handler(
+            quickwit_services.metastore_client.clone(),
+            quickwit_services.search_service.clone(),
+        ))
+        .or(suggest_handler(quickwit_services.search_service.clone()))
         .or(ingest_api_handlers(
             quickwit_services.ingest_router_service.clone(),
             quickwit_services.ingest_service.clone(),
+            quickwit_services.metastore_client.clone(),
             quickwit_services.node_config.ingest_api_config.clone(),
         ))
         .or(otlp_ingest_api_handlers(
             quickwit_services.otlp_logs_service_opt.clone(),
             quickwit_services.otlp_traces_service_opt.clone(),
+            quickwit_services
+                .node_config
+                .ingest_api_config
+                .content_length_limit,
         ))
         .or(index_management_handlers(
             quickwit_services.index_manager.clone(),
+            quickwit_services.shard_positions_service_mailbox.clone(),
             quickwit_services.node_config.clone(),
         ))
         .or(delete_task_api_handlers(
             quickwit_services.metastore_client.clone(),
+            quickwit_services.search_service.clone(),
         ))
         .or(jaeger_api_handlers(
             quickwit_services.jaeger_service_opt.clone(),
@@ -306,7 +367,7 @@ fn api_v1_routes(
 // We may use this work on the PR is merged: https://github.com/seanmonstar/warp/pull/909.
 pub async fn recover_fn(rejection: Rejection) -> Result<impl Reply, Rejection> {
     let error = get_status_with_error(rejection)?;
-    let status_code = error.status_code;
+    let status_code = error.status_code();
     Ok(RestApiResponse::new::<(), _>(
         &Err(error),
         status_code,
@@ -317,19 +378,13 @@ pub async fn recover_fn(rejection: Rejection) -> Result<impl Reply, Rejection> {
 pub async fn recover_fn_final(rejection: Rejection) -> Result<impl Reply, Rejection> {
     let error = get_status_with_error(rejection).unwrap_or_else(|rejection: Rejection| {
         if rejection.is_not_found() {
-            RestApiError {
-                status_code: StatusCode::NOT_FOUND,
-                message: "Route not found".to_string(),
-            }
+            RestApiError::new(RestApiErrorCode::RouteNotFound, "Route not found")
         } else {
             error!("REST server error: {:?}", rejection);
-            RestApiError {
-                status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                message: "internal server error".to_string(),
-            }
+            RestApiError::new(RestApiErrorCode::Internal, "internal server error")
         }
     });
-    let status_code = error.status_code;
+    let status_code = error.status_code();
     Ok(RestApiResponse::new::<(), _>(
         &Err(error),
         status_code,
@@ -338,87 +393,49 @@ pub async fn recover_fn_final(rejection: Rejection) -> Result<impl Reply, Reject
 }
 
 fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection> {
-    if let Some(error) = rejection.find::<crate::format::UnsupportedMediaType>() {
-        Ok(RestApiError {
-            status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            message: error.to_string(),
-        })
+    let error = if let Some(error) = rejection.find::<crate::format::UnsupportedMediaType>() {
+        RestApiError::new(RestApiErrorCode::UnsupportedMediaType, error.to_string())
     } else if let Some(error) = rejection.find::<serde_qs::Error>() {
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
     } else if let Some(error) = rejection.find::<InvalidJsonRequest>() {
         // Happens when the request body could not be deserialized correctly.
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.0.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.0.to_string())
+            .with_detail("line", error.0.line())
+            .with_detail("column", error.0.column())
     } else if let Some(error) = rejection.find::<warp::filters::body::BodyDeserializeError>() {
         // Happens when the request body could not be deserialized correctly.
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
     } else if let Some(error) = rejection.find::<warp::reject::UnsupportedMediaType>() {
-        Ok(RestApiError {
-            status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::UnsupportedMediaType, error.to_string())
     } else if let Some(error) = rejection.find::<UnsupportedEncoding>() {
-        Ok(RestApiError {
-            status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::UnsupportedMediaType, error.to_string())
     } else if let Some(error) = rejection.find::<CorruptedData>() {
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
     } else if let Some(error) = rejection.find::<warp::reject::InvalidQuery>() {
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
     } else if let Some(error) = rejection.find::<warp::reject::LengthRequired>() {
-        Ok(RestApiError {
-            status_code: StatusCode::LENGTH_REQUIRED,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::LengthRequired, error.to_string())
     } else if let Some(error) = rejection.find::<warp::reject::MissingHeader>() {
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
+            .with_detail("header", error.name())
     } else if let Some(error) = rejection.find::<warp::reject::InvalidHeader>() {
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.to_string())
+            .with_detail("header", error.name())
     } else if let Some(error) = rejection.find::<warp::reject::PayloadTooLarge>() {
-        Ok(RestApiError {
-            status_code: StatusCode::PAYLOAD_TOO_LARGE,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::PayloadTooLarge, error.to_string())
+    } else if let Some(error) = rejection.find::<BodyTooLarge>() {
+        RestApiError::new(RestApiErrorCode::PayloadTooLarge, error.to_string())
     } else if let Some(err) = rejection.find::<TooManyRequests>() {
-        Ok(RestApiError {
-            status_code: StatusCode::TOO_MANY_REQUESTS,
-            message: err.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::TooManyRequests, err.to_string())
     } else if let Some(error) = rejection.find::<InvalidArgument>() {
         // Happens when the url path or request body contains invalid argument(s).
-        Ok(RestApiError {
-            status_code: StatusCode::BAD_REQUEST,
-            message: error.0.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::BadRequest, error.0.to_string())
     } else if let Some(error) = rejection.find::<warp::reject::MethodNotAllowed>() {
-        Ok(RestApiError {
-            status_code: StatusCode::METHOD_NOT_ALLOWED,
-            message: error.to_string(),
-        })
+        RestApiError::new(RestApiErrorCode::MethodNotAllowed, error.to_string())
     } else {
-        Err(rejection)
-    }
+        return Err(rejection);
+    };
+    Ok(error)
 }
 
 fn build_cors(cors_origins: &[String]) -> CorsLayer {
@@ -457,13 +474,13 @@ mod tests {
     use http::HeaderName;
     use hyper::{Request, Response, StatusCode};
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
-    use quickwit_config::NodeConfig;
+    use quickwit_config::{NodeConfig, SearcherConfig};
     use quickwit_index_management::IndexService;
     use quickwit_ingest::{IngestApiService, IngestServiceClient};
     use quickwit_proto::control_plane::ControlPlaneServiceClient;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
     use quickwit_proto::metastore::MetastoreServiceClient;
-    use quickwit_search::MockSearchService;
+    use quickwit_search::{MockSearchService, SearcherContext};
     use quickwit_storage::StorageResolver;
     use tower::Service;
 
@@ -714,6 +731,7 @@ mod tests {
             control_plane_server_opt: None,
             control_plane_client,
             indexing_service_opt: None,
+            shard_positions_service_mailbox: Default::default(),
             index_manager: index_service,
             ingest_service: ingest_service_client(),
             ingest_router_opt: None,
@@ -726,6 +744,7 @@ mod tests {
             metastore_server_opt: None,
             node_config: Arc::new(node_config.clone()),
             search_service: Arc::new(MockSearchService::new()),
+            searcher_context: Arc::new(SearcherContext::new(SearcherConfig::default(), None)),
             jaeger_service_opt: None,
             env_filter_reload_fn: crate::do_nothing_env_filter_reload_fn(),
         };
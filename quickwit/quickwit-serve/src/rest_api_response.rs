@@ -20,21 +20,188 @@
 use hyper::header::CONTENT_TYPE;
 use hyper::http::HeaderValue;
 use hyper::{Body, Response, StatusCode};
-use quickwit_proto::ServiceError;
-use serde::{self, Serialize};
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use serde::ser::SerializeStruct;
+use serde::{self, Serialize, Serializer};
+use serde_json::{Map as JsonMap, Value as JsonValue};
 use warp::Reply;
 
 use crate::format::BodyFormat;
 
 const JSON_SERIALIZATION_ERROR: &str = "JSON serialization failed.";
 
-#[derive(Serialize)]
+/// Stable, machine-readable codes of the errors returned by the REST API. The Elasticsearch
+/// compatible endpoints are exempt: they return errors shaped like the Elasticsearch ones.
+///
+/// Codes can be added but never renamed or removed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestApiErrorCode {
+    AlreadyExists,
+    BadRequest,
+    Forbidden,
+    Internal,
+    LengthRequired,
+    MethodNotAllowed,
+    NotFound,
+    PayloadTooLarge,
+    RouteNotFound,
+    Timeout,
+    TooManyRequests,
+    Unauthenticated,
+    Unavailable,
+    UnsupportedMediaType,
+}
+
+impl RestApiErrorCode {
+    pub const ALL: [RestApiErrorCode; 14] = [
+        Self::AlreadyExists,
+        Self::BadRequest,
+        Self::Forbidden,
+        Self::Internal,
+        Self::LengthRequired,
+        Self::MethodNotAllowed,
+        Self::NotFound,
+        Self::PayloadTooLarge,
+        Self::RouteNotFound,
+        Self::Timeout,
+        Self::TooManyRequests,
+        Self::Unauthenticated,
+        Self::Unavailable,
+        Self::UnsupportedMediaType,
+    ];
+
+    pub fn http_status_code(&self) -> StatusCode {
+        match self {
+            Self::AlreadyExists | Self::BadRequest => StatusCode::BAD_REQUEST,
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::LengthRequired => StatusCode::LENGTH_REQUIRED,
+            Self::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            Self::NotFound | Self::RouteNotFound => StatusCode::NOT_FOUND,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Timeout => StatusCode::REQUEST_TIMEOUT,
+            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::AlreadyExists => "The resource to create already exists.",
+            Self::BadRequest => "The request is malformed or contains invalid arguments.",
+            Self::Forbidden => "The caller is not allowed to perform the request.",
+            Self::Internal => "The request failed because of an unexpected server-side error.",
+            Self::LengthRequired => "The request body must declare its length.",
+            Self::MethodNotAllowed => "The route does not support the HTTP method of the request.",
+            Self::NotFound => {
+                "The index, source, split, or other resource targeted does not exist."
+            }
+            Self::PayloadTooLarge => "The request body exceeds the maximum size accepted.",
+            Self::RouteNotFound => "No route matches the path of the request.",
+            Self::Timeout => "The request did not complete in time.",
+            Self::TooManyRequests => "The request was rate limited and can be retried later.",
+            Self::Unauthenticated => "The caller could not be identified.",
+            Self::Unavailable => {
+                "The service handling the request is unavailable and the request can be retried \
+                 later."
+            }
+            Self::UnsupportedMediaType => {
+                "The content type or encoding of the request body is not supported."
+            }
+        }
+    }
+}
+
+impl From<ServiceErrorCode> for RestApiErrorCode {
+    fn from(service_error_code: ServiceErrorCode) -> Self {
+        match service_error_code {
+            ServiceErrorCode::AlreadyExists => Self::AlreadyExists,
+            ServiceErrorCode::BadRequest => Self::BadRequest,
+            ServiceErrorCode::Forbidden => Self::Forbidden,
+            ServiceErrorCode::Internal => Self::Internal,
+            ServiceErrorCode::NotFound => Self::NotFound,
+            ServiceErrorCode::Timeout => Self::Timeout,
+            ServiceErrorCode::TooManyRequests => Self::TooManyRequests,
+            ServiceErrorCode::Unauthenticated => Self::Unauthenticated,
+            ServiceErrorCode::Unavailable => Self::Unavailable,
+        }
+    }
+}
+
+/// Entry of the list of error codes returned by the `error-codes` endpoint.
+#[derive(Serialize, utoipa::ToSchema)]
+pub(crate) struct RestApiErrorCodeDescription {
+    code: RestApiErrorCode,
+    http_status_code: u16,
+    description: &'static str,
+}
+
+pub(crate) fn error_code_descriptions() -> Vec<RestApiErrorCodeDescription> {
+    RestApiErrorCode::ALL
+        .into_iter()
+        .map(|code| RestApiErrorCodeDescription {
+            code,
+            http_status_code: code.http_status_code().as_u16(),
+            description: code.description(),
+        })
+        .collect()
+}
+
+/// Error returned by the REST API, serialized as
+/// `{"error": {"code": "...", "message": "...", "details": {...}}}`.
+#[derive(Debug)]
 pub(crate) struct RestApiError {
-    // For now, we want to keep [`RestApiError`] as simple as possible
-    // and return just a message.
-    #[serde(skip_serializing)]
-    pub status_code: StatusCode,
+    pub code: RestApiErrorCode,
     pub message: String,
+    /// Additional context specific to the error, empty for most errors.
+    pub details: JsonMap<String, JsonValue>,
+}
+
+impl RestApiError {
+    pub fn new(code: RestApiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: JsonMap::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, key: &str, value: impl Into<JsonValue>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.code.http_status_code()
+    }
+
+    pub fn from_service_error<E: ServiceError>(error: &E) -> Self {
+        Self::new(error.error_code().into(), error.to_string())
+    }
+}
+
+impl Serialize for RestApiError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct ErrorBody<'a> {
+            code: RestApiErrorCode,
+            message: &'a str,
+            details: &'a JsonMap<String, JsonValue>,
+        }
+        let mut state = serializer.serialize_struct("RestApiError", 1)?;
+        state.serialize_field(
+            "error",
+            &ErrorBody {
+                code: self.code,
+                message: &self.message,
+                details: &self.details,
+            },
+        )?;
+        state.end()
+    }
 }
 
 /// Makes a JSON API response from a result.
@@ -44,13 +211,10 @@ pub(crate) fn into_rest_api_response<T: serde::Serialize, E: ServiceError>(
     result: Result<T, E>,
     body_format: BodyFormat,
 ) -> RestApiResponse {
-    let rest_api_result = result.map_err(|error| RestApiError {
-        status_code: error.error_code().http_status_code(),
-        message: error.to_string(),
-    });
+    let rest_api_result = result.map_err(|error| RestApiError::from_service_error(&error));
     let status_code = match &rest_api_result {
         Ok(_) => StatusCode::OK,
-        Err(error) => error.status_code,
+        Err(error) => error.status_code(),
     };
     RestApiResponse::new(&rest_api_result, status_code, body_format)
 }
@@ -89,12 +253,79 @@ impl Reply for RestApiResponse {
                     limit_per_min = 10,
                     "REST body json serialization error."
                 );
-                warp::reply::json(&RestApiError {
-                    status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                    message: JSON_SERIALIZATION_ERROR.to_string(),
-                })
-                .into_response()
+                let error = RestApiError::new(RestApiErrorCode::Internal, JSON_SERIALIZATION_ERROR);
+                warp::reply::with_status(warp::reply::json(&error), error.status_code())
+                    .into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Asserts that `body` is a REST API error with the code `expected_code` and returns its
+    /// message.
+    pub(crate) fn assert_rest_api_error(body: &[u8], expected_code: &str) -> String {
+        let body_json: JsonValue = serde_json::from_slice(body).unwrap();
+        let JsonValue::Object(body_object) = &body_json else {
+            panic!("expected a JSON object, got `{body_json}`");
+        };
+        assert_eq!(body_object.len(), 1, "{body_json}");
+
+        let error_json = &body_json["error"];
+        assert_eq!(error_json["code"], expected_code, "{body_json}");
+        assert!(error_json["details"].is_object(), "{body_json}");
+        error_json["message"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_rest_api_error_serialization() {
+        let error = RestApiError::new(RestApiErrorCode::BadRequest, "invalid JSON")
+            .with_detail("line", 1)
+            .with_detail("column", 2);
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+
+        let error_json = serde_json::to_value(&error).unwrap();
+        let expected_error_json = json!({
+            "error": {
+                "code": "bad_request",
+                "message": "invalid JSON",
+                "details": {
+                    "line": 1,
+                    "column": 2,
+                },
             }
+        });
+        assert_eq!(error_json, expected_error_json);
+    }
+
+    #[test]
+    fn test_rest_api_error_code_status_codes() {
+        for service_error_code in [
+            ServiceErrorCode::AlreadyExists,
+            ServiceErrorCode::BadRequest,
+            ServiceErrorCode::Forbidden,
+            ServiceErrorCode::Internal,
+            ServiceErrorCode::NotFound,
+            ServiceErrorCode::Timeout,
+            ServiceErrorCode::TooManyRequests,
+            ServiceErrorCode::Unauthenticated,
+            ServiceErrorCode::Unavailable,
+        ] {
+            let rest_api_error_code = RestApiErrorCode::from(service_error_code);
+            assert_eq!(
+                rest_api_error_code.http_status_code(),
+                service_error_code.http_status_code()
+            );
+            assert!(RestApiErrorCode::ALL.contains(&rest_api_error_code));
         }
+        let descriptions_json = serde_json::to_value(error_code_descriptions()).unwrap();
+        assert_eq!(descriptions_json.as_array().unwrap().len(), 14);
+        assert_eq!(descriptions_json[13]["code"], "unsupported_media_type");
+        assert_eq!(descriptions_json[13]["http_status_code"], 415);
     }
 }
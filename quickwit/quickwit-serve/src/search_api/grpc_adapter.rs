@@ -21,6 +21,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures::TryStreamExt;
+use prost::Message;
 use quickwit_proto::error::convert_to_grpc_result;
 use quickwit_proto::search::{
     search_service_server as grpc, GetKvRequest, GetKvResponse, LeafListFieldsRequest,
@@ -31,6 +32,8 @@ use quickwit_proto::{set_parent_span_from_request_metadata, tonic, GrpcServiceEr
 use quickwit_search::SearchService;
 use tracing::instrument;
 
+use crate::SERVE_METRICS;
+
 #[derive(Clone)]
 pub struct GrpcSearchAdapter(Arc<dyn SearchService>);
 
@@ -61,6 +64,12 @@ impl grpc::SearchService for GrpcSearchAdapter {
         set_parent_span_from_request_metadata(request.metadata());
         let leaf_search_request = request.into_inner();
         let leaf_search_result = self.0.leaf_search(leaf_search_request).await;
+
+        if let Ok(leaf_search_response) = &leaf_search_result {
+            SERVE_METRICS
+                .leaf_search_response_uncompressed_bytes_total
+                .inc_by(leaf_search_response.encoded_len() as u64);
+        }
         convert_to_grpc_result(leaf_search_result)
     }
 
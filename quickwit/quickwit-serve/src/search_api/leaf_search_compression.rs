@@ -0,0 +1,151 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytesize::ByteSize;
+use futures::future::BoxFuture;
+use hyper::body::{Bytes, HttpBody, SizeHint};
+use hyper::{HeaderMap, Request, Response};
+use quickwit_config::LeafSearchResponseCompression;
+use quickwit_proto::search::search_service_server::SearchServiceServer;
+use quickwit_proto::tonic;
+use quickwit_proto::tonic::body::BoxBody;
+use quickwit_proto::tonic::codegen::CompressionEncoding;
+use quickwit_proto::tonic::server::NamedService;
+use quickwit_search::SearchService;
+use tower::Service;
+
+use super::GrpcSearchAdapter;
+use crate::SERVE_METRICS;
+
+const LEAF_SEARCH_PATH: &str = "/quickwit.search.SearchService/LeafSearch";
+
+/// Builds the gRPC search service. Leaf search responses are compressed with gzip if the node
+/// settings allow it and the root asked for it.
+pub(crate) fn build_search_grpc_service(
+    search_service: Arc<dyn SearchService>,
+    max_message_size: ByteSize,
+    leaf_search_response_compression: LeafSearchResponseCompression,
+) -> LeafSearchWireBytesService<SearchServiceServer<GrpcSearchAdapter>> {
+    let grpc_search_adapter = GrpcSearchAdapter::from(search_service);
+    let mut search_service_server = SearchServiceServer::new(grpc_search_adapter)
+        .max_decoding_message_size(max_message_size.0 as usize)
+        .max_encoding_message_size(max_message_size.0 as usize);
+
+    if leaf_search_response_compression != LeafSearchResponseCompression::Off {
+        search_service_server = search_service_server.send_compressed(CompressionEncoding::Gzip);
+    }
+    LeafSearchWireBytesService {
+        inner: search_service_server,
+    }
+}
+
+/// Wraps the gRPC search service to record the number of bytes of the leaf search responses
+/// sent over the wire, i.e. after compression, if any.
+#[derive(Clone)]
+pub(crate) struct LeafSearchWireBytesService<S> {
+    inner: S,
+}
+
+impl<S: NamedService> NamedService for LeafSearchWireBytesService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<Request<B>> for LeafSearchWireBytesService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>, Error = Infallible>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let is_leaf_search = request.uri().path() == LEAF_SEARCH_PATH;
+        let response_fut = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response_fut.await?;
+
+            if !is_leaf_search {
+                return Ok(response);
+            }
+            let encoding = match response.headers().get("grpc-encoding") {
+                Some(header_value) if header_value == "gzip" => "gzip",
+                _ => "identity",
+            };
+            let response = response.map(|body| {
+                WireBytesCountingBody {
+                    inner: body,
+                    encoding,
+                }
+                .boxed_unsync()
+            });
+            Ok(response)
+        })
+    }
+}
+
+struct WireBytesCountingBody {
+    inner: BoxBody,
+    encoding: &'static str,
+}
+
+impl HttpBody for WireBytesCountingBody {
+    type Data = Bytes;
+    type Error = tonic::Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_data(cx);
+
+        if let Poll::Ready(Some(Ok(data))) = &poll {
+            SERVE_METRICS
+                .leaf_search_response_wire_bytes_total
+                .with_label_values([self.encoding])
+                .inc_by(data.len() as u64);
+        }
+        poll
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
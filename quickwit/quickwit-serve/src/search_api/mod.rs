@@ -18,14 +18,17 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod grpc_adapter;
+mod leaf_search_compression;
 mod rest_handler;
 
 pub use self::grpc_adapter::GrpcSearchAdapter;
+pub(crate) use self::leaf_search_compression::build_search_grpc_service;
 pub(crate) use self::rest_handler::{extract_index_id_patterns, extract_index_id_patterns_default};
 pub use self::rest_handler::{
-    search_get_handler, search_plan_get_handler, search_plan_post_handler, search_post_handler,
-    search_request_from_api_request, search_stream_handler, SearchApi, SearchRequestQueryString,
-    SortBy,
+    field_caps_handler, search_get_handler, search_hits_stream_handler, search_plan_get_handler,
+    search_plan_post_handler, search_post_handler, search_request_from_api_request,
+    search_stream_handler, suggest_handler, SearchApi, SearchRequestLimits,
+    SearchRequestQueryString, SortBy,
 };
 
 #[cfg(test)]
@@ -35,24 +38,29 @@ mod tests {
 
     use bytesize::ByteSize;
     use futures::TryStreamExt;
+    use prost::Message;
     use quickwit_common::ServiceStream;
+    use quickwit_config::LeafSearchResponseCompression;
     use quickwit_indexing::MockSplitBuilder;
     use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt, ListSplitsResponseExt};
     use quickwit_proto::metastore::{
         IndexMetadataResponse, ListSplitsResponse, MetastoreServiceClient, MockMetastoreService,
     };
     use quickwit_proto::search::search_service_server::SearchServiceServer;
-    use quickwit_proto::search::OutputFormat;
+    use quickwit_proto::search::{LeafSearchRequest, LeafSearchResponse, OutputFormat, PartialHit};
     use quickwit_proto::tonic;
     use quickwit_query::query_ast::qast_json_helper;
     use quickwit_search::{
         create_search_client_from_grpc_addr, root_search_stream, ClusterClient, MockSearchService,
         SearchError, SearchJobPlacer, SearchService, SearcherPool,
     };
+    use tokio::net::TcpListener;
     use tokio_stream::wrappers::UnboundedReceiverStream;
+    use tonic::transport::server::TcpIncoming;
     use tonic::transport::Server;
 
-    use crate::search_api::GrpcSearchAdapter;
+    use crate::search_api::{build_search_grpc_service, GrpcSearchAdapter};
+    use crate::SERVE_METRICS;
 
     async fn start_test_server(
         address: SocketAddr,
@@ -150,4 +158,74 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_leaf_search_response_compression() {
+        let leaf_search_response = LeafSearchResponse {
+            num_hits: 1_000,
+            partial_hits: (0..1_000)
+                .map(|doc_id| PartialHit {
+                    split_id: "split-with-a-rather-long-identifier".to_string(),
+                    segment_ord: 0,
+                    doc_id,
+                    ..Default::default()
+                })
+                .collect(),
+            num_attempted_splits: 1,
+            num_successful_splits: 1,
+            intermediate_aggregation_result: Some(vec![42; 100_000]),
+            ..Default::default()
+        };
+        let mut mock_search_service = MockSearchService::new();
+        let leaf_search_response_clone = leaf_search_response.clone();
+        mock_search_service
+            .expect_leaf_search()
+            .returning(move |_| Ok(leaf_search_response_clone.clone()));
+
+        let search_grpc_service = build_search_grpc_service(
+            Arc::new(mock_search_service),
+            ByteSize::mib(10),
+            LeafSearchResponseCompression::Forced,
+        );
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let grpc_addr = tcp_listener.local_addr().unwrap();
+        let tcp_incoming = TcpIncoming::from_listener(tcp_listener, true, None).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(search_grpc_service)
+                .serve_with_incoming(tcp_incoming),
+        );
+        let mut search_client = create_search_client_from_grpc_addr(grpc_addr, ByteSize::mib(10))
+            .with_leaf_search_response_compression(LeafSearchResponseCompression::Forced);
+
+        let uncompressed_bytes_before = SERVE_METRICS
+            .leaf_search_response_uncompressed_bytes_total
+            .get();
+        let wire_bytes_before = SERVE_METRICS
+            .leaf_search_response_wire_bytes_total
+            .with_label_values(["gzip"])
+            .get();
+
+        let response = search_client
+            .leaf_search(LeafSearchRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(response, leaf_search_response);
+
+        let uncompressed_bytes = SERVE_METRICS
+            .leaf_search_response_uncompressed_bytes_total
+            .get()
+            - uncompressed_bytes_before;
+        let wire_bytes = SERVE_METRICS
+            .leaf_search_response_wire_bytes_total
+            .with_label_values(["gzip"])
+            .get()
+            - wire_bytes_before;
+        assert_eq!(
+            uncompressed_bytes,
+            leaf_search_response.encoded_len() as u64
+        );
+        assert!(wire_bytes > 0);
+        assert!(wire_bytes * 10 < uncompressed_bytes);
+    }
 }
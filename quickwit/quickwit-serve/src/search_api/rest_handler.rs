@@ -18,25 +18,33 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::TryFrom;
+use std::pin::Pin;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use futures::stream::StreamExt;
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use percent_encoding::percent_decode_str;
-use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder};
+use quickwit_config::{validate_index_id_pattern, SearcherConfig};
+use quickwit_doc_mapper::FieldCapability;
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreServiceClient};
+use quickwit_proto::search::{CountHits, ListFieldsRequest, OutputFormat, SortField, SortOrder};
 use quickwit_proto::types::IndexId;
 use quickwit_proto::ServiceError;
 use quickwit_query::query_ast::query_ast_from_user_text;
-use quickwit_search::{SearchError, SearchPlanResponseRest, SearchResponseRest, SearchService};
+use quickwit_search::{
+    sample_rate_to_ppm, validate_index_id_patterns, SearchError, SearchPlanResponseRest,
+    SearchResponseRest, SearchService, SuggestRequest, SuggestResponse,
+};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use tracing::info;
 use warp::hyper::header::CONTENT_TYPE;
-use warp::hyper::StatusCode;
-use warp::{reply, Filter, Rejection, Reply};
+use warp::{Filter, Rejection, Reply};
 
+use crate::format::extract_format_from_qs;
 use crate::rest_api_response::into_rest_api_response;
 use crate::simple_list::{from_simple_list, to_simple_list};
 use crate::{with_arg, BodyFormat};
@@ -47,18 +55,25 @@ use crate::{with_arg, BodyFormat};
         search_get_handler,
         search_post_handler,
         search_stream_handler,
+        search_hits_stream_handler,
         search_plan_get_handler,
         search_plan_post_handler,
+        field_caps_handler,
+        suggest_handler,
     ),
     components(schemas(
         BodyFormat,
+        FieldCapsQueryParams,
         OutputFormat,
+        SearchHitsStreamRequestBody,
         SearchRequestQueryString,
         SearchResponseRest,
         SearchPlanResponseRest,
         SortBy,
         SortField,
         SortOrder,
+        SuggestQueryParams,
+        SuggestResponse,
     ),)
 )]
 pub struct SearchApi;
@@ -89,6 +104,8 @@ pub(crate) async fn extract_index_id_patterns(
         index_id_patterns.push(index_id_pattern.to_string());
     }
     assert!(!index_id_patterns.is_empty());
+    validate_index_id_patterns(&index_id_patterns)
+        .map_err(|error| crate::rest::InvalidArgument(error.to_string()))?;
     Ok(index_id_patterns)
 }
 
@@ -125,6 +142,7 @@ impl From<String> for SortBy {
                 field_name,
                 sort_order: sort_order as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             };
             sort_fields.push(sort_field);
         }
@@ -179,7 +197,7 @@ where D: Deserializer<'de> {
 /// This struct represents the QueryString passed to
 /// the rest API.
 #[derive(
-    Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema,
+    Debug, Default, PartialEq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema,
 )]
 #[into_params(parameter_in = Query)]
 #[serde(deny_unknown_fields)]
@@ -206,6 +224,12 @@ pub struct SearchRequestQueryString {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(serialize_with = "to_simple_list")]
     pub snippet_fields: Option<Vec<String>>,
+    /// Fields to return in the hits. Returns the whole document if not set.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub fields: Option<Vec<String>>,
     /// If set, restrict search to documents with a `timestamp >= start_timestamp`.
     /// This timestamp is expressed in seconds.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -243,6 +267,47 @@ pub struct SearchRequestQueryString {
     #[schema(value_type = bool)]
     #[serde(default)]
     pub allow_failed_splits: bool,
+    /// Lowers the maximum depth of the query AST (64 by default) for this request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_query_ast_depth: Option<u32>,
+    /// Lowers the maximum number of leaf clauses of the query AST (1024 by default) for this
+    /// request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_query_ast_clause_count: Option<u32>,
+    /// If set, the response reports whether the hits were fetched from the doc store or from
+    /// the fast fields.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub debug_fetch_docs_source: bool,
+    /// If set, opens a point in time, or extends the one given by `pit_id`, for this number of
+    /// seconds. The `pit_id` returned in the response pins the splits searched by the
+    /// subsequent requests.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_keep_alive_secs: Option<u32>,
+    /// Searches the splits recorded by this point in time instead of the splits currently
+    /// published.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
+    /// If set, hits are only counted accurately up to this value. Beyond it, `num_hits` is
+    /// reported as this value and `num_hits_relation` as `greater_than_or_equal_to`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_total_hits: Option<u64>,
+    /// If set, the leaves may spill the intermediate aggregation results to disk instead of
+    /// buffering them all in memory.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub allow_disk_use: bool,
+    /// If set, within `]0, 1]`, the aggregations are computed on a deterministic sample of this
+    /// fraction of the matching documents, and their counts and sums are scaled back up. Hits
+    /// are not affected.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f32>,
 }
 
 mod count_hits_from_bool {
@@ -282,6 +347,10 @@ pub fn search_request_from_api_request(
     // the user of the docmapper default fields (which we do not have at this point).
     let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
     let query_ast_json = serde_json::to_string(&query_ast)?;
+    let aggregation_sample_rate_ppm = search_request
+        .sample_rate
+        .map(sample_rate_to_ppm)
+        .transpose()?;
     let search_request = quickwit_proto::search::SearchRequest {
         index_id_patterns,
         query_ast: query_ast_json,
@@ -297,6 +366,18 @@ pub fn search_request_from_api_request(
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
+        max_query_ast_depth: search_request.max_query_ast_depth,
+        max_query_ast_clause_count: search_request.max_query_ast_clause_count,
+        fields: search_request.fields.unwrap_or_default(),
+        debug_fetch_docs_source: search_request.debug_fetch_docs_source,
+        allow_no_indices: None,
+        es_timestamp_alias: false,
+        default_search_fields: Vec::new(),
+        pit_keep_alive_secs: search_request.pit_keep_alive_secs,
+        pit_id: search_request.pit_id,
+        track_total_hits: search_request.track_total_hits,
+        allow_disk_use: search_request.allow_disk_use,
+        aggregation_sample_rate_ppm,
     };
     Ok(search_request)
 }
@@ -326,38 +407,203 @@ async fn search_endpoint(
     Ok(search_response_rest)
 }
 
+/// Limits enforced on the requests of the search REST API before their query is handed over to
+/// the query parser.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchRequestLimits {
+    /// Maximum length of the query, in bytes.
+    pub max_query_length: usize,
+    /// Maximum size of the request body, in bytes.
+    pub max_request_body_size: u64,
+}
+
+impl From<&SearcherConfig> for SearchRequestLimits {
+    fn from(searcher_config: &SearcherConfig) -> Self {
+        Self {
+            max_query_length: searcher_config.max_query_length.as_u64() as usize,
+            max_request_body_size: searcher_config.max_request_body_size.as_u64(),
+        }
+    }
+}
+
+impl Default for SearchRequestLimits {
+    fn default() -> Self {
+        Self::from(&SearcherConfig::default())
+    }
+}
+
+const UTF8_BOM: char = '\u{feff}';
+
+fn invalid_utf8_rejection(context: &str, error: std::str::Utf8Error) -> Rejection {
+    warp::reject::custom(crate::rest::InvalidArgument(format!(
+        "{context} is not valid UTF-8: invalid byte sequence at offset {}",
+        error.valid_up_to()
+    )))
+}
+
+fn check_request_body_size(
+    body_size: u64,
+    search_request_limits: SearchRequestLimits,
+) -> Result<(), Rejection> {
+    if body_size > search_request_limits.max_request_body_size {
+        return Err(warp::reject::custom(crate::rest::InvalidArgument(format!(
+            "request body is {body_size} bytes long, which exceeds the limit of {} bytes \
+             (`searcher.max_request_body_size`)",
+            search_request_limits.max_request_body_size
+        ))));
+    }
+    Ok(())
+}
+
+/// Strips the leading byte order mark of the query and checks its length.
+fn sanitize_query(
+    query: &mut String,
+    search_request_limits: SearchRequestLimits,
+) -> Result<(), Rejection> {
+    if let Some(stripped_query) = query.strip_prefix(UTF8_BOM) {
+        *query = stripped_query.to_string();
+    }
+    if query.len() > search_request_limits.max_query_length {
+        return Err(warp::reject::custom(crate::rest::InvalidArgument(format!(
+            "query is {} bytes long, which exceeds the limit of {} bytes \
+             (`searcher.max_query_length`)",
+            query.len(),
+            search_request_limits.max_query_length
+        ))));
+    }
+    Ok(())
+}
+
+fn sanitize_search_request(
+    mut search_request: SearchRequestQueryString,
+    search_request_limits: SearchRequestLimits,
+) -> Result<SearchRequestQueryString, Rejection> {
+    sanitize_query(&mut search_request.query, search_request_limits)?;
+    Ok(search_request)
+}
+
+fn parse_search_request_query_string(
+    raw_query_string: &str,
+    search_request_limits: SearchRequestLimits,
+) -> Result<SearchRequestQueryString, Rejection> {
+    // The query string deserializer silently replaces invalid UTF-8 sequences, so we check them
+    // beforehand. The reported offset is that of the percent-decoded query string.
+    let decoded_query_string: Vec<u8> = percent_decode_str(raw_query_string).collect();
+    if let Err(error) = std::str::from_utf8(&decoded_query_string) {
+        return Err(invalid_utf8_rejection("query string", error));
+    }
+    let search_request: SearchRequestQueryString = serde_qs::Config::default()
+        .deserialize_str(raw_query_string)
+        .map_err(warp::reject::custom)?;
+    sanitize_search_request(search_request, search_request_limits)
+}
+
+fn deserialize_request_body<T: de::DeserializeOwned>(
+    body: &[u8],
+    search_request_limits: SearchRequestLimits,
+) -> Result<T, Rejection> {
+    // The `Content-Length` header is checked before reading the body, but chunked bodies are not
+    // bound by it.
+    check_request_body_size(body.len() as u64, search_request_limits)?;
+    let body_str =
+        std::str::from_utf8(body).map_err(|error| invalid_utf8_rejection("request body", error))?;
+    let body_str = body_str.strip_prefix(UTF8_BOM).unwrap_or(body_str);
+    serde_json::from_str(body_str).map_err(|error| {
+        warp::reject::custom(crate::rest::InvalidArgument(format!(
+            "Request body deserialize error: {error}"
+        )))
+    })
+}
+
+fn parse_search_request_body(
+    body: &[u8],
+    search_request_limits: SearchRequestLimits,
+) -> Result<SearchRequestQueryString, Rejection> {
+    let search_request: SearchRequestQueryString =
+        deserialize_request_body(body, search_request_limits)?;
+    sanitize_search_request(search_request, search_request_limits)
+}
+
+fn parse_search_hits_stream_request_body(
+    body: &[u8],
+    search_request_limits: SearchRequestLimits,
+) -> Result<SearchHitsStreamRequestBody, Rejection> {
+    let mut search_request: SearchHitsStreamRequestBody =
+        deserialize_request_body(body, search_request_limits)?;
+    sanitize_query(&mut search_request.query, search_request_limits)?;
+    Ok(search_request)
+}
+
+fn search_request_query_string_filter(
+    search_request_limits: SearchRequestLimits,
+) -> impl Filter<Extract = (SearchRequestQueryString,), Error = Rejection> + Clone {
+    warp::query::raw()
+        .or(warp::any().map(String::new))
+        .unify()
+        .and_then(move |raw_query_string: String| async move {
+            parse_search_request_query_string(&raw_query_string, search_request_limits)
+        })
+}
+
+fn search_request_body_filter(
+    search_request_limits: SearchRequestLimits,
+) -> impl Filter<Extract = (SearchRequestQueryString,), Error = Rejection> + Clone {
+    request_body_bytes_filter(search_request_limits).and_then(move |body: Bytes| async move {
+        parse_search_request_body(&body, search_request_limits)
+    })
+}
+
+fn request_body_bytes_filter(
+    search_request_limits: SearchRequestLimits,
+) -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and_then(move |content_length_opt: Option<u64>| async move {
+            if let Some(content_length) = content_length_opt {
+                check_request_body_size(content_length, search_request_limits)?;
+            }
+            Ok::<_, Rejection>(())
+        })
+        .untuple_one()
+        .and(warp::body::content_length_limit(
+            search_request_limits.max_request_body_size,
+        ))
+        .and(warp::body::bytes())
+}
+
 fn search_get_filter(
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (Vec<String>, SearchRequestQueryString), Error = Rejection> + Clone {
     warp::path!(String / "search")
         .and_then(extract_index_id_patterns)
         .and(warp::get())
-        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(search_request_query_string_filter(search_request_limits))
 }
 
 fn search_post_filter(
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (Vec<String>, SearchRequestQueryString), Error = Rejection> + Clone {
     warp::path!(String / "search")
         .and_then(extract_index_id_patterns)
         .and(warp::post())
-        .and(warp::body::content_length_limit(1024 * 1024))
-        .and(warp::body::json())
+        .and(search_request_body_filter(search_request_limits))
 }
 
 fn search_plan_get_filter(
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (Vec<String>, SearchRequestQueryString), Error = Rejection> + Clone {
     warp::path!(String / "search-plan")
         .and_then(extract_index_id_patterns)
         .and(warp::get())
-        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(search_request_query_string_filter(search_request_limits))
 }
 
 fn search_plan_post_filter(
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (Vec<String>, SearchRequestQueryString), Error = Rejection> + Clone {
     warp::path!(String / "search-plan")
         .and_then(extract_index_id_patterns)
         .and(warp::post())
-        .and(warp::body::content_length_limit(1024 * 1024))
-        .and(warp::body::json())
+        .and(search_request_body_filter(search_request_limits))
 }
 
 async fn search(
@@ -404,8 +650,9 @@ async fn search_plan(
 /// Parses the search request from the request query string.
 pub fn search_get_handler(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_get_filter()
+    search_get_filter(search_request_limits)
         .and(with_arg(search_service))
         .then(search)
 }
@@ -429,8 +676,9 @@ pub fn search_get_handler(
 /// Parses the search request from the request body.
 pub fn search_post_handler(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_post_filter()
+    search_post_filter(search_request_limits)
         .and(with_arg(search_service))
         .then(search)
 }
@@ -456,6 +704,32 @@ pub fn search_stream_handler(
         .then(search_stream)
 }
 
+#[utoipa::path(
+    post,
+    tag = "Search",
+    path = "/{index_id}/search/stream/hits",
+    request_body = SearchHitsStreamRequestBody,
+    responses(
+        (status = 200, description = "Successfully streamed the matching documents as newline-delimited JSON.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to search."),
+    )
+)]
+/// Stream Search Hits
+///
+/// Streams all the documents matching the query as newline-delimited JSON. Splits are scanned
+/// one after another and no global sort is applied. An interrupted stream can be resumed from
+/// any node with the last cursor received.
+pub fn search_hits_stream_handler(
+    search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    search_hits_stream_filter(search_request_limits)
+        .and(with_arg(search_service))
+        .then(search_hits_stream)
+}
+
 #[utoipa::path(
     get,
     tag = "Search",
@@ -473,8 +747,9 @@ pub fn search_stream_handler(
 /// Parses the search request from the request query string.
 pub fn search_plan_get_handler(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_plan_get_filter()
+    search_plan_get_filter(search_request_limits)
         .and(with_arg(search_service))
         .then(search_plan)
 }
@@ -496,12 +771,148 @@ pub fn search_plan_get_handler(
 /// Parses the search request from the request body.
 pub fn search_plan_post_handler(
     search_service: Arc<dyn SearchService>,
+    search_request_limits: SearchRequestLimits,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_plan_post_filter()
+    search_plan_post_filter(search_request_limits)
         .and(with_arg(search_service))
         .then(search_plan)
 }
 
+/// This struct represents the query string passed to the field capabilities REST API.
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct FieldCapsQueryParams {
+    /// If set, the fields that are not declared in the doc mapping but are present in the
+    /// splits of the index are also returned.
+    #[serde(default = "default_include_dynamic_fields")]
+    pub include_dynamic_fields: bool,
+}
+
+fn default_include_dynamic_fields() -> bool {
+    true
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/field-caps",
+    responses(
+        (status = 200, description = "Successfully fetched the field capabilities of the index.", body = [FieldCapability])
+    ),
+    params(
+        FieldCapsQueryParams,
+        ("index_id" = String, Path, description = "The index ID to introspect."),
+    )
+)]
+/// Get Field Capabilities
+///
+/// Returns the type, and the searchable, aggregatable, and stored capabilities of the fields of
+/// an index.
+pub fn field_caps_handler(
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "field-caps")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(metastore))
+        .and(with_arg(search_service))
+        .then(field_caps)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+async fn field_caps(
+    index_id: IndexId,
+    query_params: FieldCapsQueryParams,
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
+) -> Result<Vec<FieldCapability>, SearchError> {
+    info!(index_id = %index_id, "field-caps");
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let split_fields_opt = if query_params.include_dynamic_fields {
+        let list_fields_request = ListFieldsRequest {
+            index_id_patterns: vec![index_id],
+            ..Default::default()
+        };
+        Some(search_service.root_list_fields(list_fields_request).await?)
+    } else {
+        None
+    };
+    let field_capabilities = index_metadata
+        .index_config
+        .doc_mapping
+        .field_capabilities(split_fields_opt);
+    Ok(field_capabilities)
+}
+
+/// This struct represents the query string passed to the suggestion REST API.
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub struct SuggestQueryParams {
+    /// Prefix the suggested field names or values start with.
+    #[serde(default)]
+    pub prefix: String,
+    /// If set, the values of this field are suggested instead of field names.
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Maximum number of suggestions returned.
+    #[serde(default = "default_max_suggestions")]
+    pub max_suggestions: usize,
+}
+
+fn default_max_suggestions() -> usize {
+    10
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/suggest",
+    responses(
+        (status = 200, description = "Successfully fetched the suggestions.", body = SuggestResponse)
+    ),
+    params(
+        SuggestQueryParams,
+        ("index_id" = String, Path, description = "The index ID to introspect."),
+    )
+)]
+/// Suggest Field Names and Values
+///
+/// Returns the field names starting with a prefix or, if a field is specified, the values of that
+/// field starting with the prefix. Only the most recent splits of the index are considered.
+pub fn suggest_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "suggest")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(with_arg(search_service))
+        .then(suggest)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+async fn suggest(
+    index_id: IndexId,
+    query_params: SuggestQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> Result<SuggestResponse, SearchError> {
+    info!(index_id = %index_id, field = ?query_params.field, "suggest");
+    let suggest_request = SuggestRequest {
+        index_id,
+        prefix: query_params.prefix,
+        field_opt: query_params.field,
+        max_suggestions: query_params.max_suggestions,
+    };
+    search_service.root_suggest(suggest_request).await
+}
+
 /// This struct represents the search stream query passed to
 /// the REST API.
 #[derive(Deserialize, Debug, Eq, PartialEq, utoipa::IntoParams)]
@@ -552,7 +963,17 @@ async fn search_stream_endpoint(
         output_format: search_request.output_format as i32,
         partition_by_field: search_request.partition_by_field,
     };
-    let mut data = search_service.root_search_stream(request).await?;
+    let data = search_service.root_search_stream(request).await?;
+    Ok(stream_into_body(data))
+}
+
+/// Forwards a stream of search results into a response body.
+///
+/// Chunks are only pulled from the stream once the previous one has been handed over to the
+/// client, and the stream is dropped as soon as the client disconnects.
+fn stream_into_body(
+    mut data: Pin<Box<dyn futures::Stream<Item = Result<Bytes, SearchError>> + Send>>,
+) -> hyper::Body {
     let (mut sender, body) = hyper::Body::channel();
     tokio::spawn(async move {
         while let Some(result) = data.next().await {
@@ -584,22 +1005,25 @@ async fn search_stream_endpoint(
             };
         }
     });
-    Ok(body)
+    body
 }
 
-fn make_streaming_reply(result: Result<hyper::Body, SearchError>) -> impl Reply {
-    let status_code: StatusCode;
-    let body = match result {
+fn make_streaming_reply(
+    result: Result<hyper::Body, SearchError>,
+    content_type: &'static str,
+) -> warp::reply::Response {
+    match result {
         Ok(body) => {
-            status_code = StatusCode::OK;
-            warp::reply::Response::new(body)
+            let mut response = warp::reply::Response::new(body);
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
         }
         Err(error) => {
-            status_code = error.error_code().http_status_code();
-            warp::reply::Response::new(hyper::Body::from(error.to_string()))
+            into_rest_api_response::<(), _>(Err(error), BodyFormat::default()).into_response()
         }
-    };
-    reply::with_status(body, status_code)
+    }
 }
 
 async fn search_stream(
@@ -612,9 +1036,10 @@ async fn search_stream(
         OutputFormat::ClickHouseRowBinary => "application/octet-stream",
         OutputFormat::Csv => "text/csv",
     };
-    let reply =
-        make_streaming_reply(search_stream_endpoint(index_id, request, &*search_service).await);
-    reply::with_header(reply, CONTENT_TYPE, content_type)
+    make_streaming_reply(
+        search_stream_endpoint(index_id, request, &*search_service).await,
+        content_type,
+    )
 }
 
 fn search_stream_filter(
@@ -624,26 +1049,144 @@ fn search_stream_filter(
         .and(serde_qs::warp::query(serde_qs::Config::default()))
 }
 
+/// This struct represents the body of the search hits stream REST API.
+#[derive(Debug, Default, Eq, PartialEq, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+struct SearchHitsStreamRequestBody {
+    /// Query text. The query language is that of tantivy.
+    pub query: String,
+    // Fields to search on.
+    #[schema(rename = "search_field")]
+    #[serde(default)]
+    #[serde(rename = "search_field")]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub search_fields: Option<Vec<String>>,
+    /// Fields to return for each document. Returns the whole document if not set.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub fields: Option<Vec<String>>,
+    /// If set, restricts search to documents with a `timestamp >= start_timestamp`.
+    pub start_timestamp: Option<i64>,
+    /// If set, restricts search to documents with a `timestamp < end_timestamp`.
+    pub end_timestamp: Option<i64>,
+    /// Maximum number of documents to stream. Streams all the matching documents if not set.
+    pub max_hits: Option<u64>,
+    /// Point in time fixing the set of splits streamed.
+    pub pit_id: Option<String>,
+    /// Cursor from which to resume a stream previously interrupted. The other parameters must be
+    /// those of the interrupted stream.
+    pub cursor: Option<String>,
+    /// If set, a `{"_cursor": "..."}` line follows each page of documents. Passing the last
+    /// received cursor resumes the stream right after the last received document.
+    #[serde(default)]
+    pub with_cursors: bool,
+}
+
+async fn search_hits_stream_endpoint(
+    index_id_patterns: Vec<String>,
+    search_request: SearchHitsStreamRequestBody,
+    search_service: &dyn SearchService,
+) -> Result<hyper::Body, SearchError> {
+    let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
+    let query_ast_json = serde_json::to_string(&query_ast)?;
+    let request = quickwit_proto::search::SearchRequest {
+        index_id_patterns,
+        query_ast: query_ast_json,
+        start_timestamp: search_request.start_timestamp,
+        end_timestamp: search_request.end_timestamp,
+        max_hits: search_request.max_hits.unwrap_or_default(),
+        fields: search_request.fields.unwrap_or_default(),
+        pit_id: search_request.pit_id,
+        ..Default::default()
+    };
+    let with_cursors = search_request.with_cursors;
+    let data = search_service
+        .root_search_hits_stream(request, search_request.cursor)
+        .await?
+        .map(move |page_res| {
+            page_res.map(|page| {
+                if !with_cursors {
+                    return page.ndjson;
+                }
+                let cursor_line = serde_json::to_vec(&serde_json::json!({"_cursor": page.cursor}))
+                    .expect("serializing a hits stream cursor should never fail");
+                let mut buffer = Vec::with_capacity(page.ndjson.len() + cursor_line.len() + 1);
+                buffer.extend_from_slice(&page.ndjson);
+                buffer.extend_from_slice(&cursor_line);
+                buffer.push(b'\n');
+                Bytes::from(buffer)
+            })
+        });
+    Ok(stream_into_body(Box::pin(data)))
+}
+
+async fn search_hits_stream(
+    index_id_patterns: Vec<String>,
+    search_request: SearchHitsStreamRequestBody,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    info!(index_id_patterns=?index_id_patterns, request=?search_request, "search_hits_stream");
+    make_streaming_reply(
+        search_hits_stream_endpoint(index_id_patterns, search_request, &*search_service).await,
+        "application/x-ndjson",
+    )
+}
+
+fn search_hits_stream_filter(
+    search_request_limits: SearchRequestLimits,
+) -> impl Filter<Extract = (Vec<String>, SearchHitsStreamRequestBody), Error = Rejection> + Clone {
+    warp::path!(String / "search" / "stream" / "hits")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(request_body_bytes_filter(search_request_limits).and_then(
+            move |body: Bytes| async move {
+                parse_search_hits_stream_request_body(&body, search_request_limits)
+            },
+        ))
+}
+
 #[cfg(test)]
 mod tests {
     use assert_json_diff::{assert_json_eq, assert_json_include};
     use bytes::Bytes;
     use mockall::predicate;
-    use quickwit_search::{MockSearchService, SearchError};
+    use quickwit_proto::search::NumHitsRelation;
+    use quickwit_search::{HitsStreamPage, MockSearchService, SearchError, SearchResponseStats};
     use serde_json::{json, Value as JsonValue};
 
     use super::*;
     use crate::recover_fn;
+    use crate::rest_api_response::tests::assert_rest_api_error;
 
     fn search_handler(
         mock_search_service: MockSearchService,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+        search_handler_with_limits(mock_search_service, SearchRequestLimits::default())
+    }
+
+    fn search_handler_with_limits(
+        mock_search_service: MockSearchService,
+        search_request_limits: SearchRequestLimits,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
         let mock_search_service_in_arc = Arc::new(mock_search_service);
-        search_get_handler(mock_search_service_in_arc.clone())
-            .or(search_post_handler(mock_search_service_in_arc.clone()))
+        search_get_handler(mock_search_service_in_arc.clone(), search_request_limits)
+            .or(search_post_handler(
+                mock_search_service_in_arc.clone(),
+                search_request_limits,
+            ))
             .or(search_stream_handler(mock_search_service_in_arc.clone()))
-            .or(search_plan_get_handler(mock_search_service_in_arc.clone()))
-            .or(search_plan_post_handler(mock_search_service_in_arc.clone()))
+            .or(search_hits_stream_handler(
+                mock_search_service_in_arc.clone(),
+                search_request_limits,
+            ))
+            .or(search_plan_get_handler(
+                mock_search_service_in_arc.clone(),
+                search_request_limits,
+            ))
+            .or(search_plan_post_handler(
+                mock_search_service_in_arc.clone(),
+                search_request_limits,
+            ))
             .recover(recover_fn)
     }
 
@@ -670,6 +1213,41 @@ mod tests {
             .unwrap_err();
     }
 
+    #[tokio::test]
+    async fn test_extract_index_id_patterns_with_exclusions() {
+        assert_eq!(
+            extract_index_id_patterns("logs-*,-logs-debug-*".to_string())
+                .await
+                .unwrap(),
+            vec!["logs-*".to_string(), "-logs-debug-*".to_string()]
+        );
+        assert_eq!(
+            extract_index_id_patterns("-logs-debug-*,logs-*".to_string())
+                .await
+                .unwrap(),
+            vec!["-logs-debug-*".to_string(), "logs-*".to_string()]
+        );
+        // Index IDs start with a letter, so a leading dash can only mark an exclusion: a
+        // literal leading dash cannot be expressed, escaped or not.
+        extract_index_id_patterns("logs-*,--logs-debug".to_string())
+            .await
+            .unwrap_err();
+        extract_index_id_patterns("logs-*,%5C-logs-debug".to_string())
+            .await
+            .unwrap_err();
+        extract_index_id_patterns("logs-*,-".to_string())
+            .await
+            .unwrap_err();
+
+        let rejection = extract_index_id_patterns("-logs-debug-*,-logs-test".to_string())
+            .await
+            .unwrap_err();
+        let invalid_argument = rejection.find::<crate::rest::InvalidArgument>().unwrap();
+        assert!(invalid_argument
+            .0
+            .contains("only contain exclusions: at least one pattern not prefixed with `-`"));
+    }
+
     #[test]
     fn test_serialize_search_response() -> anyhow::Result<()> {
         let search_response = SearchResponseRest {
@@ -679,6 +1257,13 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            fetch_docs_source: None,
+            partial: false,
+            pit_id: None,
+            stats: SearchResponseStats::default(),
+            num_hits_relation: NumHitsRelation::EqualTo,
+            sampled: false,
+            sample_rate: None,
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -844,6 +1429,107 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_fields() {
+        let rest_search_api_filter = search_get_filter();
+        let (indexes, req) = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&fields=id,level&debug_fetch_docs_source=true",
+            )
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(indexes, vec!["quickwit-demo-index".to_string()]);
+        assert_eq!(
+            &req,
+            &super::SearchRequestQueryString {
+                query: "*".to_string(),
+                fields: Some(vec!["id".to_string(), "level".to_string()]),
+                max_hits: 20,
+                count_all: CountHits::CountAll,
+                debug_fetch_docs_source: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_point_in_time() {
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&pit_keep_alive_secs=60&\
+                 pit_id=01HAV29D4XY3D462FS3D8K5Q2H",
+            )
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.pit_keep_alive_secs, Some(60));
+        assert_eq!(req.pit_id.as_deref(), Some("01HAV29D4XY3D462FS3D8K5Q2H"));
+
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.pit_keep_alive_secs, Some(60));
+        assert_eq!(
+            search_request.pit_id.as_deref(),
+            Some("01HAV29D4XY3D462FS3D8K5Q2H")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_track_total_hits() {
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.count_hits(), CountHits::CountAll);
+        assert_eq!(search_request.track_total_hits, None);
+
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&track_total_hits=100")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.track_total_hits, Some(100));
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.track_total_hits, Some(100));
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_sample_rate() {
+        let rest_search_api_filter = search_get_filter();
+        let (_indexes, req) = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&sample_rate=0.1")
+            .filter(&rest_search_api_filter)
+            .await
+            .unwrap();
+        assert_eq!(req.sample_rate, Some(0.1));
+        let search_request =
+            search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req).unwrap();
+        assert_eq!(search_request.aggregation_sample_rate_ppm, Some(100_000));
+
+        for invalid_sample_rate in ["0", "-0.5", "1.5"] {
+            let rest_search_api_filter = search_get_filter();
+            let (_indexes, req) = warp::test::request()
+                .path(&format!(
+                    "/quickwit-demo-index/search?query=*&sample_rate={invalid_sample_rate}"
+                ))
+                .filter(&rest_search_api_filter)
+                .await
+                .unwrap();
+            let error =
+                search_request_from_api_request(vec!["quickwit-demo-index".to_string()], req)
+                    .unwrap_err();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_simple_default_num_hits_default_offset() {
         let rest_search_api_filter = search_get_filter();
@@ -908,6 +1594,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -916,6 +1603,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -924,6 +1612,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -932,6 +1621,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -940,6 +1630,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -948,6 +1639,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    sort_mode: None,
                 }],
             ),
             (
@@ -957,11 +1649,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                 ],
             ),
@@ -972,11 +1666,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Asc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                 ],
             ),
@@ -987,11 +1683,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Asc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        sort_mode: None,
                     },
                 ],
             ),
@@ -1027,6 +1725,7 @@ mod tests {
                 field_name: "fiel1".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                sort_mode: None,
             }],
         );
     }
@@ -1038,13 +1737,8 @@ mod tests {
             .reply(&search_handler(MockSearchService::new()))
             .await;
         assert_eq!(resp.status(), 400);
-        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
-        assert!(resp_json
-            .get("message")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .contains("unknown field `end_unix_timestamp`"));
+        let message = assert_rest_api_error(resp.body(), "bad_request");
+        assert!(message.contains("unknown field `end_unix_timestamp`"));
     }
 
     #[tokio::test]
@@ -1062,6 +1756,199 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_error_schema() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                if search_request.index_id_patterns == ["unknown-index"] {
+                    Err(SearchError::IndexesNotFound {
+                        index_ids: search_request.index_id_patterns,
+                    })
+                } else {
+                    Err(SearchError::Internal("something went wrong".to_string()))
+                }
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&max_hits=many")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert_rest_api_error(resp.body(), "bad_request");
+
+        let resp = warp::test::request()
+            .path("/unknown-index/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let message = assert_rest_api_error(resp.body(), "not_found");
+        assert!(message.contains("unknown-index"));
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 500);
+        let message = assert_rest_api_error(resp.body(), "internal");
+        assert!(message.contains("something went wrong"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_max_query_length() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|_| Ok(Default::default()));
+        let search_request_limits = SearchRequestLimits {
+            max_query_length: 16,
+            max_request_body_size: 1024,
+        };
+        let rest_search_api_handler =
+            search_handler_with_limits(mock_search_service, search_request_limits);
+
+        let query_at_limit = "a".repeat(16);
+        let resp = warp::test::request()
+            .path(&format!(
+                "/quickwit-demo-index/search?query={query_at_limit}"
+            ))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .json(&json!({ "query": query_at_limit }))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let query_over_limit = "a".repeat(17);
+        let resp = warp::test::request()
+            .path(&format!(
+                "/quickwit-demo-index/search?query={query_over_limit}"
+            ))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(content.contains("query is 17 bytes long, which exceeds the limit of 16 bytes"));
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search-plan")
+            .json(&json!({ "query": query_over_limit }))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(content.contains("query is 17 bytes long, which exceeds the limit of 16 bytes"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_max_request_body_size() {
+        let body = r#"{"query": "*"}"#;
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(1)
+            .returning(|_| Ok(Default::default()));
+        let search_request_limits = SearchRequestLimits {
+            max_query_length: 16,
+            max_request_body_size: body.len() as u64,
+        };
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .body(body)
+            .reply(&search_handler_with_limits(
+                mock_search_service,
+                search_request_limits,
+            ))
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let search_request_limits = SearchRequestLimits {
+            max_query_length: 1,
+            max_request_body_size: body.len() as u64 - 1,
+        };
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .body(body)
+            .reply(&search_handler_with_limits(
+                MockSearchService::new(),
+                search_request_limits,
+            ))
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(content.contains(
+            "request body is 14 bytes long, which exceeds the limit of 13 bytes \
+             (`searcher.max_request_body_size`)"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_invalid_utf8() {
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .body(&b"{\"query\": \"abc\xFF\"}"[..])
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(
+            content.contains("request body is not valid UTF-8: invalid byte sequence at offset 14")
+        );
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=abc%FF")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(
+            content.contains("query string is not valid UTF-8: invalid byte sequence at offset 9")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_strips_byte_order_mark() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    let query_ast: JsonValue =
+                        serde_json::from_str(&search_request.query_ast).unwrap();
+                    query_ast["user_text"] == "foo"
+                },
+            ))
+            .times(2)
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=%EF%BB%BFfoo")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .body("\u{feff}{\"query\": \"foo\"}")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_serialize_with_results() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -1133,6 +2020,45 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_suggest_api() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_suggest()
+            .with(predicate::function(|suggest_request: &SuggestRequest| {
+                suggest_request.index_id == "quickwit-demo-index"
+                    && suggest_request.prefix == "ser"
+                    && suggest_request.field_opt.as_deref() == Some("service")
+                    && suggest_request.max_suggestions == 10
+            }))
+            .returning(|_| {
+                Ok(SuggestResponse {
+                    suggestions: vec!["search".to_string(), "server".to_string()],
+                })
+            });
+        mock_search_service
+            .expect_root_suggest()
+            .with(predicate::function(|suggest_request: &SuggestRequest| {
+                suggest_request.field_opt.is_none()
+            }))
+            .returning(|_| Err(SearchError::TooManyRequests));
+        let suggest_handler = suggest_handler(Arc::new(mock_search_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/suggest?prefix=ser&field=service")
+            .reply(&suggest_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"suggestions": ["search", "server"]}));
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/suggest?prefix=ser")
+            .reply(&suggest_handler)
+            .await;
+        assert_eq!(resp.status(), 429);
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_wrong_fieldname() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -1258,6 +2184,148 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_hits_stream_api() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search_hits_stream()
+            .with(
+                predicate::function(|search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns == vec!["my-index".to_string()]
+                        && search_request.max_hits == 20_000
+                        && search_request.fields == vec!["title".to_string(), "url".to_string()]
+                        && search_request.sort_fields.is_empty()
+                }),
+                predicate::eq(None),
+            )
+            .return_once(|_, _| {
+                Ok(Box::pin(futures::stream::iter(vec![
+                    Ok(HitsStreamPage {
+                        ndjson: Bytes::from("{\"title\":\"first\"}\n"),
+                        cursor: "cursor-1".to_string(),
+                    }),
+                    Ok(HitsStreamPage {
+                        ndjson: Bytes::from("{\"title\":\"second\"}\n"),
+                        cursor: "cursor-2".to_string(),
+                    }),
+                ])))
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/my-index/search/stream/hits")
+            .json(&json!({"query": "*", "fields": "title,url", "max_hits": 20_000}))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let body = String::from_utf8_lossy(response.body());
+        assert_eq!(body, "{\"title\":\"first\"}\n{\"title\":\"second\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_hits_stream_api_with_cursors() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search_hits_stream()
+            .with(
+                predicate::function(|search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.pit_id.as_deref() == Some("my-pit")
+                }),
+                predicate::eq(Some("cursor-1".to_string())),
+            )
+            .return_once(|_, _| {
+                Ok(Box::pin(futures::stream::iter(vec![Ok(HitsStreamPage {
+                    ndjson: Bytes::from("{\"title\":\"second\"}\n"),
+                    cursor: "cursor-2".to_string(),
+                })])))
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/my-index/search/stream/hits")
+            .json(&json!({
+                "query": "*",
+                "pit_id": "my-pit",
+                "cursor": "cursor-1",
+                "with_cursors": true,
+            }))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body = String::from_utf8_lossy(response.body());
+        assert_eq!(body, "{\"title\":\"second\"}\n{\"_cursor\":\"cursor-2\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_hits_stream_api_error() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search_hits_stream()
+            .with(
+                predicate::function(|search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.max_hits == 0
+                }),
+                predicate::always(),
+            )
+            .return_once(|_, _| {
+                Err(SearchError::InvalidArgument(
+                    "sorting is not supported when streaming hits".to_string(),
+                ))
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/my-index/search/stream/hits")
+            .json(&json!({"query": "*"}))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        let message = assert_rest_api_error(response.body(), "bad_request");
+        assert!(message.contains("sorting is not supported when streaming hits"));
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/my-index/search/stream/hits")
+            .json(&json!({"query": "*", "sort_by": "timestamp"}))
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        let body = String::from_utf8_lossy(response.body());
+        assert!(body.contains("unknown field `sort_by`"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_into_body_drops_stream_on_disconnect() {
+        struct DropGuard(Option<tokio::sync::oneshot::Sender<()>>);
+
+        impl Drop for DropGuard {
+            fn drop(&mut self) {
+                if let Some(drop_tx) = self.0.take() {
+                    let _ = drop_tx.send(());
+                }
+            }
+        }
+        let (drop_tx, drop_rx) = tokio::sync::oneshot::channel();
+        let drop_guard = DropGuard(Some(drop_tx));
+        let data = futures::stream::repeat_with(move || {
+            let _drop_guard = &drop_guard;
+            Ok(Bytes::from("{}\n"))
+        });
+        let mut body = stream_into_body(Box::pin(data));
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(chunk, "{}\n");
+        // Dropping the body is what hyper does when the client disconnects.
+        drop(body);
+        tokio::time::timeout(std::time::Duration::from_secs(5), drop_rx)
+            .await
+            .expect("the stream should be dropped once the client disconnects")
+            .unwrap();
+    }
+
     #[tokio::test]
     async fn test_rest_search_stream_api_error_empty_fastfield() {
         let rejection = warp::test::request()
@@ -1306,6 +2374,7 @@ mod tests {
             "snippets": [{"title": [], "body": ["foo <em>bar</em> baz"]}],
             "elapsed_time_micros": 16,
             "errors": [],
+            "stats": {"cpu_time_ms": 0.0},
         });
         assert_json_eq!(resp_json, expected_response_json);
         Ok(())
@@ -164,6 +164,7 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
             }
             if let Some((_, bytes)) = self.lru_cache.pop_lru() {
                 self.drop_item(bytes.len() as u64);
+                self.cache_counters.evictions_num_items.inc();
             } else {
                 error!(
                     "Logical error. Even after removing all of the items in the cache the \
@@ -176,6 +177,20 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         self.record_item(bytes.len() as u64);
         self.lru_cache.put(key, StoredItem::new(bytes, now));
     }
+
+    /// Changes the capacity of the cache, evicting the least recently used items until the cache
+    /// fits in the new capacity. Unlike `put`, recently accessed items are evicted too.
+    fn set_capacity(&mut self, capacity: Capacity) {
+        self.capacity = capacity;
+
+        while self.capacity.exceeds_capacity(self.num_bytes as usize) {
+            let Some((_, bytes)) = self.lru_cache.pop_lru() else {
+                break;
+            };
+            self.drop_item(bytes.len() as u64);
+            self.cache_counters.evictions_num_items.inc();
+        }
+    }
 }
 
 /// A simple in-resident memory slice cache.
@@ -222,6 +237,15 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
     pub fn put(&self, val: K, bytes: OwnedBytes) {
         self.inner.lock().unwrap().put(val, bytes);
     }
+
+    /// Changes the capacity of the cache. Shrinking the cache evicts the least recently used
+    /// items right away.
+    pub fn set_capacity_in_bytes(&self, capacity_in_bytes: usize) {
+        self.inner
+            .lock()
+            .unwrap()
+            .set_capacity(Capacity::InBytes(capacity_in_bytes));
+    }
 }
 
 impl MemorySizedCache<SliceAddress> {
@@ -243,6 +267,8 @@ impl MemorySizedCache<SliceAddress> {
 #[cfg(test)]
 mod tests {
 
+    use once_cell::sync::Lazy;
+
     use super::*;
     use crate::metrics::CACHE_METRICS_FOR_TESTS;
 
@@ -288,6 +314,66 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_cache_evictions_counter() {
+        static CACHE_METRICS: Lazy<CacheMetrics> =
+            Lazy::new(|| CacheMetrics::for_component("fortest_evictions"));
+        tokio::time::pause();
+        let cache = MemorySizedCache::<String>::with_capacity_in_bytes(5, &CACHE_METRICS);
+        cache.put("3".to_string(), OwnedBytes::new(&b"abc"[..]));
+        // Replacing an item is not an eviction.
+        cache.put("3".to_string(), OwnedBytes::new(&b"abc"[..]));
+        cache.put("2".to_string(), OwnedBytes::new(&b"de"[..]));
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 0);
+
+        // The items in cache are too young to be evicted.
+        cache.put("5".to_string(), OwnedBytes::new(&b"fghij"[..]));
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 0);
+
+        tokio::time::advance(super::MIN_TIME_SINCE_LAST_ACCESS.mul_f32(1.1f32)).await;
+        cache.put("5".to_string(), OwnedBytes::new(&b"fghij"[..]));
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 2);
+
+        // Items larger than the cache are not stored and evict nothing.
+        tokio::time::advance(super::MIN_TIME_SINCE_LAST_ACCESS.mul_f32(1.1f32)).await;
+        cache.put("6".to_string(), OwnedBytes::new(&b"klmnop"[..]));
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_set_capacity_in_bytes() {
+        static CACHE_METRICS: Lazy<CacheMetrics> =
+            Lazy::new(|| CacheMetrics::for_component("fortest_set_capacity"));
+        tokio::time::pause();
+        let cache = MemorySizedCache::<String>::with_capacity_in_bytes(10, &CACHE_METRICS);
+        cache.put("3".to_string(), OwnedBytes::new(&b"abc"[..]));
+        cache.put("2".to_string(), OwnedBytes::new(&b"de"[..]));
+        cache.put("4".to_string(), OwnedBytes::new(&b"fghi"[..]));
+        // Touch the oldest item so that it becomes the most recently used one.
+        assert!(cache.get(&"3".to_string()).is_some());
+
+        cache.set_capacity_in_bytes(20);
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 0);
+        assert_eq!(CACHE_METRICS.in_cache_num_bytes.get(), 9);
+
+        // Shrinking the cache evicts the least recently used items, however young they are.
+        cache.set_capacity_in_bytes(7);
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 1);
+        assert_eq!(CACHE_METRICS.in_cache_num_bytes.get(), 7);
+        assert!(cache.get(&"2".to_string()).is_none());
+        assert!(cache.get(&"3".to_string()).is_some());
+        assert!(cache.get(&"4".to_string()).is_some());
+
+        // The new capacity applies to the items put afterwards.
+        tokio::time::advance(super::MIN_TIME_SINCE_LAST_ACCESS.mul_f32(1.1f32)).await;
+        cache.put("8".to_string(), OwnedBytes::new(&b"jklmnopq"[..]));
+        assert!(cache.get(&"8".to_string()).is_none());
+
+        cache.set_capacity_in_bytes(0);
+        assert_eq!(CACHE_METRICS.evictions_num_items.get(), 3);
+        assert_eq!(CACHE_METRICS.in_cache_num_bytes.get(), 0);
+    }
+
     #[test]
     fn test_cache_edge_unlimited_capacity() {
         let cache = MemorySizedCache::with_infinite_capacity(&CACHE_METRICS_FOR_TESTS);
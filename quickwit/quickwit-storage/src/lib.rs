@@ -40,7 +40,7 @@ pub(crate) use debouncer::DebouncedStorage;
 
 pub use self::metrics::STORAGE_METRICS;
 pub use self::payload::PutPayload;
-pub use self::storage::Storage;
+pub use self::storage::{FileEntry, Storage};
 
 mod bundle_storage;
 mod error;
@@ -59,7 +59,7 @@ mod storage_resolver;
 mod versioned_component;
 
 use quickwit_common::uri::Uri;
-pub use split_cache::SplitCache;
+pub use split_cache::{SplitCache, SplitPin};
 pub use tantivy::directory::OwnedBytes;
 pub use versioned_component::VersionedComponent;
 
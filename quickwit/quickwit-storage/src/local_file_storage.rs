@@ -35,7 +35,7 @@ use tracing::warn;
 
 use crate::storage::SendableAsync;
 use crate::{
-    BulkDeleteError, DebouncedStorage, DeleteFailure, OwnedBytes, Storage, StorageError,
+    BulkDeleteError, DebouncedStorage, DeleteFailure, FileEntry, OwnedBytes, Storage, StorageError,
     StorageErrorKind, StorageFactory, StorageResolverError, StorageResult,
 };
 
@@ -347,6 +347,60 @@ impl Storage for LocalFileStorage {
             }
         }
     }
+
+    async fn list_files_recursive(
+        &self,
+        dir_path: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let start_after_opt: Option<&[u8]> =
+            start_after_opt.map(|start_after| start_after.as_os_str().as_encoded_bytes());
+        let mut file_entries = Vec::new();
+        let mut full_dir_paths = vec![self.full_path(dir_path)?];
+
+        while let Some(full_dir_path) = full_dir_paths.pop() {
+            let mut read_dir = match tokio::fs::read_dir(&full_dir_path).await {
+                Ok(read_dir) => read_dir,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+            while let Some(dir_entry) = read_dir.next_entry().await? {
+                // Hidden files, such as the temporary files written by `put`, are skipped.
+                if dir_entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let metadata = dir_entry.metadata().await?;
+                let full_path = dir_entry.path();
+                let path = full_path
+                    .strip_prefix(&self.root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|_| full_path.clone());
+                let path_bytes = path.as_os_str().as_encoded_bytes();
+
+                if metadata.is_dir() {
+                    let dir_prefix = [path_bytes, b"/"].concat();
+                    // All the files of a directory sort before `start_after` if the directory
+                    // prefix itself does and is not a prefix of `start_after`.
+                    let is_dir_before_start = start_after_opt.is_some_and(|start_after| {
+                        dir_prefix.as_slice() < start_after && !start_after.starts_with(&dir_prefix)
+                    });
+                    if !is_dir_before_start {
+                        full_dir_paths.push(full_path);
+                    }
+                } else if metadata.is_file() {
+                    if start_after_opt.is_some_and(|start_after| path_bytes <= start_after) {
+                        continue;
+                    }
+                    file_entries.push(FileEntry {
+                        path,
+                        num_bytes: metadata.len(),
+                        last_modified: metadata.modified()?,
+                    });
+                }
+            }
+        }
+        Ok(file_entries)
+    }
 }
 
 /// A File storage resolver
@@ -453,6 +507,73 @@ mod tests {
         assert!(!temp_dir.path().join("foo-dir").try_exists().unwrap());
     }
 
+    #[tokio::test]
+    async fn test_local_file_storage_list_files_recursive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let uri = Uri::from_str(&format!("{}", temp_dir.path().display())).unwrap();
+        let local_file_storage = LocalFileStorage::from_uri(&uri).unwrap();
+
+        let file_entries = local_file_storage
+            .list_files_recursive(Path::new("logs"), None)
+            .await
+            .unwrap();
+        assert!(file_entries.is_empty());
+
+        for (path, payload) in [
+            ("logs/a.json", &b"a"[..]),
+            ("logs/2024/b.json", &b"bb"[..]),
+            ("other/c.json", &b"ccc"[..]),
+        ] {
+            local_file_storage
+                .put(Path::new(path), Box::new(payload.to_vec()))
+                .await
+                .unwrap();
+        }
+        tokio::fs::write(temp_dir.path().join("logs/.hidden"), b"hidden")
+            .await
+            .unwrap();
+
+        let mut file_entries = local_file_storage
+            .list_files_recursive(Path::new("logs"), None)
+            .await
+            .unwrap();
+        file_entries.sort_by(|left, right| left.path.cmp(&right.path));
+        let paths_and_sizes: Vec<(&Path, u64)> = file_entries
+            .iter()
+            .map(|file_entry| (file_entry.path.as_path(), file_entry.num_bytes))
+            .collect();
+        assert_eq!(
+            paths_and_sizes,
+            [
+                (Path::new("logs/2024/b.json"), 2),
+                (Path::new("logs/a.json"), 1)
+            ]
+        );
+        for (path, payload) in [("logs/2025/d.json", &b"d"[..]), ("logs/b.json", &b"b"[..])] {
+            local_file_storage
+                .put(Path::new(path), Box::new(payload.to_vec()))
+                .await
+                .unwrap();
+        }
+        let mut file_entries = local_file_storage
+            .list_files_recursive(Path::new("logs"), Some(Path::new("logs/2024/b.json")))
+            .await
+            .unwrap();
+        file_entries.sort_by(|left, right| left.path.cmp(&right.path));
+        let paths: Vec<&Path> = file_entries
+            .iter()
+            .map(|file_entry| file_entry.path.as_path())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("logs/2025/d.json"),
+                Path::new("logs/a.json"),
+                Path::new("logs/b.json")
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_try_delete_dir_all() -> anyhow::Result<()> {
         let path_root = tempfile::tempdir()?.into_path();
@@ -32,6 +32,8 @@ pub struct StorageMetrics {
     pub fast_field_cache: CacheMetrics,
     pub split_footer_cache: CacheMetrics,
     pub searcher_split_cache: CacheMetrics,
+    pub searcher_split_cache_pinned_num_bytes: IntGauge,
+    pub searcher_split_cache_deferred_evictions_total: IntCounter,
     pub object_storage_get_total: IntCounter,
     pub object_storage_put_total: IntCounter,
     pub object_storage_put_parts: IntCounter,
@@ -48,6 +50,19 @@ impl Default for StorageMetrics {
             searcher_split_cache: CacheMetrics::for_component("searcher_split"),
             shortlived_cache: CacheMetrics::for_component("shortlived"),
             split_footer_cache: CacheMetrics::for_component("splitfooter"),
+            searcher_split_cache_pinned_num_bytes: new_gauge(
+                "searcher_split_cache_pinned_num_bytes",
+                "Number of bytes of the searcher split cache pinned by running searches.",
+                "cache",
+                &[],
+            ),
+            searcher_split_cache_deferred_evictions_total: new_counter(
+                "searcher_split_cache_deferred_evictions_total",
+                "Number of splits whose eviction from the searcher split cache was deferred \
+                 because they were pinned by running searches.",
+                "cache",
+                &[],
+            ),
 
             object_storage_get_total: new_counter(
                 "object_storage_gets_total",
@@ -93,6 +108,7 @@ pub struct CacheMetrics {
     pub hits_num_items: IntCounter,
     pub hits_num_bytes: IntCounter,
     pub misses_num_items: IntCounter,
+    pub evictions_num_items: IntCounter,
 }
 
 impl CacheMetrics {
@@ -130,6 +146,12 @@ impl CacheMetrics {
                 CACHE_METRICS_NAMESPACE,
                 &[("component_name", component_name)],
             ),
+            evictions_num_items: new_counter_with_labels(
+                "searcher_cache_evictions_total",
+                "Number of items evicted from the cache to stay within its capacity by component",
+                CACHE_METRICS_NAMESPACE,
+                &[("component_name", component_name)],
+            ),
         }
     }
 }
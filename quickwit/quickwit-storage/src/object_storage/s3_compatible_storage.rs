@@ -22,6 +22,7 @@ use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fmt, io};
 
 use anyhow::{anyhow, Context as AnyhhowContext};
@@ -52,7 +53,7 @@ use tracing::{info, instrument, warn};
 use crate::object_storage::MultiPartPolicy;
 use crate::storage::SendableAsync;
 use crate::{
-    BulkDeleteError, DeleteFailure, OwnedBytes, Storage, StorageError, StorageErrorKind,
+    BulkDeleteError, DeleteFailure, FileEntry, OwnedBytes, Storage, StorageError, StorageErrorKind,
     StorageResolverError, StorageResult, STORAGE_METRICS,
 };
 
@@ -862,6 +863,58 @@ impl Storage for S3CompatibleObjectStorage {
         Ok(head_object_output.content_length().unwrap_or(0) as u64)
     }
 
+    async fn list_files_recursive(
+        &self,
+        dir_path: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let mut key_prefix = self.key(dir_path);
+        if !key_prefix.is_empty() && !key_prefix.ends_with('/') {
+            key_prefix.push('/');
+        }
+        let start_after_key_opt = start_after_opt.map(|start_after| self.key(start_after));
+        let mut file_entries = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let _permit = REQUEST_SEMAPHORE.acquire().await;
+            let list_objects_output = aws_retry(&self.retry_params, || async {
+                self.s3_client
+                    .list_objects_v2()
+                    .bucket(&self.bucket)
+                    .prefix(&key_prefix)
+                    .set_start_after(start_after_key_opt.clone())
+                    .set_continuation_token(continuation_token.clone())
+                    .send()
+                    .await
+            })
+            .await?;
+
+            for object in list_objects_output.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+                let last_modified = object
+                    .last_modified()
+                    .and_then(|date_time| SystemTime::try_from(*date_time).ok())
+                    .unwrap_or(UNIX_EPOCH);
+                file_entries.push(FileEntry {
+                    path: self.relative_path(key),
+                    num_bytes: object.size().unwrap_or(0) as u64,
+                    last_modified,
+                });
+            }
+            continuation_token = list_objects_output
+                .next_continuation_token()
+                .map(ToString::to_string);
+
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        Ok(file_entries)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
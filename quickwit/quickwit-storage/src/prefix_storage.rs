@@ -27,7 +27,7 @@ use quickwit_common::uri::Uri;
 use tokio::io::AsyncRead;
 
 use crate::storage::SendableAsync;
-use crate::{BulkDeleteError, OwnedBytes, Storage};
+use crate::{BulkDeleteError, FileEntry, OwnedBytes, Storage};
 
 /// This storage acts as a proxy to another storage that simply modifies each API call
 /// by preceding each path with a given a prefix.
@@ -119,6 +119,24 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list_files_recursive(
+        &self,
+        dir_path: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> crate::StorageResult<Vec<FileEntry>> {
+        let start_after_opt = start_after_opt.map(|start_after| self.prefix.join(start_after));
+        let mut file_entries = self
+            .storage
+            .list_files_recursive(&self.prefix.join(dir_path), start_after_opt.as_deref())
+            .await?;
+        for file_entry in &mut file_entries {
+            if let Ok(path) = file_entry.path.strip_prefix(&self.prefix) {
+                file_entry.path = path.to_path_buf();
+            }
+        }
+        Ok(file_entries)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
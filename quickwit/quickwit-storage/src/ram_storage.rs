@@ -23,6 +23,7 @@ use std::io::Cursor;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
 use quickwit_common::uri::{Protocol, Uri};
@@ -33,8 +34,8 @@ use tokio::sync::RwLock;
 use crate::prefix_storage::add_prefix_to_storage;
 use crate::storage::SendableAsync;
 use crate::{
-    BulkDeleteError, OwnedBytes, Storage, StorageErrorKind, StorageFactory, StorageResolverError,
-    StorageResult,
+    BulkDeleteError, FileEntry, OwnedBytes, Storage, StorageErrorKind, StorageFactory,
+    StorageResolverError, StorageResult,
 };
 
 /// In Ram implementation of quickwit's storage.
@@ -43,7 +44,22 @@ use crate::{
 #[derive(Clone)]
 pub struct RamStorage {
     uri: Uri,
-    files: Arc<RwLock<HashMap<PathBuf, OwnedBytes>>>,
+    files: Arc<RwLock<HashMap<PathBuf, RamFile>>>,
+}
+
+#[derive(Clone)]
+struct RamFile {
+    payload: OwnedBytes,
+    last_modified: SystemTime,
+}
+
+impl RamFile {
+    fn new(payload: OwnedBytes) -> Self {
+        Self {
+            payload,
+            last_modified: SystemTime::now(),
+        }
+    }
 }
 
 impl fmt::Debug for RamStorage {
@@ -71,11 +87,18 @@ impl RamStorage {
     }
 
     async fn put_data(&self, path: &Path, payload: OwnedBytes) {
-        self.files.write().await.insert(path.to_path_buf(), payload);
+        self.files
+            .write()
+            .await
+            .insert(path.to_path_buf(), RamFile::new(payload));
     }
 
     async fn get_data(&self, path: &Path) -> Option<OwnedBytes> {
-        self.files.read().await.get(path).cloned()
+        self.files
+            .read()
+            .await
+            .get(path)
+            .map(|file| file.payload.clone())
     }
 
     /// Returns the list of files that are present in the RamStorage.
@@ -153,26 +176,54 @@ impl Storage for RamStorage {
     }
 
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
-        if let Some(file_bytes) = self.files.read().await.get(path) {
-            Ok(file_bytes.len() as u64)
+        if let Some(file) = self.files.read().await.get(path) {
+            Ok(file.payload.len() as u64)
         } else {
             let err = anyhow::anyhow!("missing file `{}`", path.display());
             Err(StorageErrorKind::NotFound.with_error(err))
         }
     }
+
+    async fn list_files_recursive(
+        &self,
+        dir_path: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let file_entries = self
+            .files
+            .read()
+            .await
+            .iter()
+            .filter(|(path, _)| {
+                path.starts_with(dir_path)
+                    && start_after_opt.map_or(true, |start_after| {
+                        path.as_os_str().as_encoded_bytes()
+                            > start_after.as_os_str().as_encoded_bytes()
+                    })
+            })
+            .map(|(path, file)| FileEntry {
+                path: path.clone(),
+                num_bytes: file.payload.len() as u64,
+                last_modified: file.last_modified,
+            })
+            .collect();
+        Ok(file_entries)
+    }
 }
 
 /// Builder to create a prepopulated [`RamStorage`]. This is mostly useful for tests.
 #[derive(Default)]
 pub struct RamStorageBuilder {
-    files: HashMap<PathBuf, OwnedBytes>,
+    files: HashMap<PathBuf, RamFile>,
 }
 
 impl RamStorageBuilder {
     /// Adds a new file into the [`RamStorageBuilder`].
     pub fn put(mut self, path: &str, payload: &[u8]) -> Self {
-        self.files
-            .insert(PathBuf::from(path), OwnedBytes::new(payload.to_vec()));
+        self.files.insert(
+            PathBuf::from(path),
+            RamFile::new(OwnedBytes::new(payload.to_vec())),
+        );
         self
     }
 
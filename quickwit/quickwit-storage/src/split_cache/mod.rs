@@ -155,6 +155,19 @@ impl SplitCache {
         }
     }
 
+    /// Pins a split in the cache. As long as the returned pin is alive, the split won't be evicted
+    /// from the cache, and its file won't be deleted.
+    ///
+    /// Returns `None` if the split id is not a valid ulid.
+    pub fn pin_split(self_arc: Arc<Self>, split_id: &str) -> Option<SplitPin> {
+        let split_ulid = Ulid::from_str(split_id).ok()?;
+        self_arc.split_table.lock().unwrap().pin(split_ulid);
+        Some(SplitPin {
+            split_cache: self_arc,
+            split_ulid,
+        })
+    }
+
     // Returns a split guard object. As long as it is not dropped, the
     // split won't be evinced from the cache.
     async fn get_split_file(&self, split_id: Ulid, storage_uri: &Uri) -> Option<SplitFile> {
@@ -174,6 +187,26 @@ impl SplitCache {
     }
 }
 
+/// Pin on a split of the [`SplitCache`], released on drop.
+pub struct SplitPin {
+    split_cache: Arc<SplitCache>,
+    split_ulid: Ulid,
+}
+
+impl Drop for SplitPin {
+    fn drop(&mut self) {
+        let split_to_evict_opt = self
+            .split_cache
+            .split_table
+            .lock()
+            .unwrap()
+            .unpin(self.split_ulid);
+        if let Some(split_to_evict) = split_to_evict_opt {
+            self.split_cache.evict(&[split_to_evict]);
+        }
+    }
+}
+
 /// Removes the evicted split files from the file system.
 /// This function just logs errors, and swallows them.
 ///
@@ -249,3 +282,120 @@ impl StorageCache for SplitCacheBackingStorage {
     async fn put(&self, _path: PathBuf, _byte_range: Range<usize>, _bytes: OwnedBytes) {}
     async fn put_all(&self, _path: PathBuf, _bytes: OwnedBytes) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    use bytesize::ByteSize;
+
+    use super::*;
+    use crate::StorageResolver;
+
+    const SPLIT_NUM_BYTES: usize = 1_000;
+
+    fn write_split_file(root_path: &Path, split_ulid: Ulid) {
+        let split_file_path = root_path.join(split_file(split_ulid));
+        std::fs::write(split_file_path, vec![split_ulid.0 as u8; SPLIT_NUM_BYTES]).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_split_cache_pinned_splits_are_never_deleted_while_read() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root_path = temp_dir.path().to_path_buf();
+        let split_ulids: Vec<Ulid> = (1u128..=8).map(Ulid).collect();
+        for &split_ulid in &split_ulids {
+            write_split_file(&root_path, split_ulid);
+        }
+        let limits = SplitCacheLimits {
+            max_num_bytes: ByteSize::b(4 * SPLIT_NUM_BYTES as u64),
+            max_num_splits: NonZeroU32::new(100).unwrap(),
+            num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+            max_file_descriptors: NonZeroU32::new(100).unwrap(),
+        };
+        let split_cache =
+            SplitCache::with_root_path(root_path.clone(), StorageResolver::for_test(), limits)
+                .unwrap();
+        let storage_uri = Uri::for_test("ram:///indexes");
+
+        // Aggressively re-downloads splits, evicting the least recently used ones every time.
+        let eviction_split_cache = split_cache.clone();
+        let eviction_split_ulids = split_ulids.clone();
+        let eviction_task = tokio::spawn(async move {
+            for i in 0..2_000 {
+                let split_ulid = eviction_split_ulids[i % eviction_split_ulids.len()];
+                let is_on_disk = eviction_split_cache
+                    .split_table
+                    .lock()
+                    .unwrap()
+                    .on_disk_num_bytes(split_ulid)
+                    .is_some();
+                if !is_on_disk
+                    && !eviction_split_cache
+                        .root_path
+                        .join(split_file(split_ulid))
+                        .exists()
+                {
+                    write_split_file(&eviction_split_cache.root_path, split_ulid);
+                    let splits_to_evict = {
+                        let mut split_table = eviction_split_cache.split_table.lock().unwrap();
+                        split_table.register_as_downloaded(split_ulid, SPLIT_NUM_BYTES as u64);
+                        split_table
+                            .make_room_for_split_if_necessary(u64::MAX)
+                            .unwrap_or_default()
+                    };
+                    eviction_split_cache.evict(&splits_to_evict);
+                }
+                tokio::task::yield_now().await;
+            }
+        });
+        let mut search_tasks = Vec::new();
+        for task_id in 0..8 {
+            let split_cache = split_cache.clone();
+            let split_ulids = split_ulids.clone();
+            let storage_uri = storage_uri.clone();
+            search_tasks.push(tokio::spawn(async move {
+                let mut num_cache_hits = 0;
+                for i in 0..200 {
+                    let split_ulid = split_ulids[(task_id + i) % split_ulids.len()];
+                    let _split_pin =
+                        SplitCache::pin_split(split_cache.clone(), &split_ulid.to_string())
+                            .unwrap();
+                    if split_cache
+                        .get_split_file(split_ulid, &storage_uri)
+                        .await
+                        .is_none()
+                    {
+                        continue;
+                    }
+                    num_cache_hits += 1;
+                    // Once a pinned split has been read from the cache, all the subsequent reads
+                    // have to succeed.
+                    for _ in 0..5 {
+                        let split_file = split_cache
+                            .get_split_file(split_ulid, &storage_uri)
+                            .await
+                            .expect("pinned split should stay in cache");
+                        let split_bytes = split_file.get_all().await.unwrap();
+                        assert_eq!(split_bytes.len(), SPLIT_NUM_BYTES);
+                        assert!(split_bytes.iter().all(|&byte| byte == split_ulid.0 as u8));
+                        tokio::time::sleep(Duration::from_micros(100)).await;
+                    }
+                }
+                num_cache_hits
+            }));
+        }
+        let mut num_cache_hits = 0;
+        for search_task in search_tasks {
+            num_cache_hits += search_task.await.unwrap();
+        }
+        eviction_task.await.unwrap();
+        assert!(num_cache_hits > 0);
+
+        assert_eq!(
+            split_cache.split_table.lock().unwrap().pinned_num_bytes(),
+            0
+        );
+    }
+}
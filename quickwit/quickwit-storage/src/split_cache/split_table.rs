@@ -18,7 +18,8 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
@@ -101,7 +102,10 @@ pub struct SplitInfo {
 /// - candidate_splits.
 ///
 /// It is possible for the split table size in bytes to exceed its limits, by at
-/// most one split.
+/// most one split, plus the splits pinned by the searches running on them.
+///
+/// Pinned splits are never evicted. If they would have been, they are evicted
+/// as soon as their last pin is released instead.
 pub struct SplitTable {
     on_disk_splits: BTreeSet<SplitKey>,
     downloading_splits: BTreeSet<SplitKey>,
@@ -110,6 +114,13 @@ pub struct SplitTable {
     origin_time: Instant,
     limits: SplitCacheLimits,
     on_disk_bytes: u64,
+    // Number of pins held on each split, regardless of its status.
+    split_to_num_pins: HashMap<Ulid, usize>,
+    // Pinned splits that were selected for eviction.
+    evict_on_unpin: HashSet<Ulid>,
+    // Number of bytes of the pinned splits that are on disk. These bytes are also accounted in
+    // `on_disk_bytes`.
+    pinned_on_disk_bytes: u64,
 }
 
 impl SplitTable {
@@ -126,6 +137,9 @@ impl SplitTable {
             origin_time,
             limits,
             on_disk_bytes: 0u64,
+            split_to_num_pins: HashMap::default(),
+            evict_on_unpin: HashSet::default(),
+            pinned_on_disk_bytes: 0u64,
         };
         split_table.acknowledge_on_disk_splits(existing_filepaths);
         split_table
@@ -165,6 +179,12 @@ impl SplitTable {
                     .searcher_split_cache
                     .in_cache_num_bytes
                     .sub(num_bytes as i64);
+                if self.split_to_num_pins.contains_key(&split_ulid) {
+                    self.pinned_on_disk_bytes -= num_bytes;
+                    crate::metrics::STORAGE_METRICS
+                        .searcher_split_cache_pinned_num_bytes
+                        .sub(num_bytes as i64);
+                }
                 &mut self.on_disk_splits
             }
         };
@@ -222,6 +242,15 @@ impl SplitTable {
                     .searcher_split_cache
                     .in_cache_num_bytes
                     .add(num_bytes as i64);
+                if self
+                    .split_to_num_pins
+                    .contains_key(&split_info.split_key.split_ulid)
+                {
+                    self.pinned_on_disk_bytes += num_bytes;
+                    crate::metrics::STORAGE_METRICS
+                        .searcher_split_cache_pinned_num_bytes
+                        .add(num_bytes as i64);
+                }
                 self.on_disk_splits.insert(split_info.split_key)
             }
         };
@@ -333,6 +362,55 @@ impl SplitTable {
         }
     }
 
+    pub(crate) fn on_disk_num_bytes(&self, split_ulid: Ulid) -> Option<u64> {
+        match self.split_to_status.get(&split_ulid)?.status {
+            Status::OnDisk { num_bytes } => Some(num_bytes),
+            _ => None,
+        }
+    }
+
+    /// Pins a split, preventing it from being evicted until it is unpinned.
+    ///
+    /// A split can be pinned before it is downloaded. It then stays pinned once it lands on disk.
+    pub(crate) fn pin(&mut self, split_ulid: Ulid) {
+        let num_pins = self.split_to_num_pins.entry(split_ulid).or_default();
+        *num_pins += 1;
+        if *num_pins > 1 {
+            return;
+        }
+        if let Some(num_bytes) = self.on_disk_num_bytes(split_ulid) {
+            self.pinned_on_disk_bytes += num_bytes;
+            crate::metrics::STORAGE_METRICS
+                .searcher_split_cache_pinned_num_bytes
+                .add(num_bytes as i64);
+        }
+    }
+
+    /// Releases a pin on a split.
+    ///
+    /// If this was the last pin and the split was selected for eviction while pinned, the split is
+    /// removed from the table and returned. It is then up to the caller to delete its file.
+    pub(crate) fn unpin(&mut self, split_ulid: Ulid) -> Option<Ulid> {
+        let num_pins = self.split_to_num_pins.get_mut(&split_ulid)?;
+        *num_pins -= 1;
+        if *num_pins > 0 {
+            return None;
+        }
+        self.split_to_num_pins.remove(&split_ulid);
+        let on_disk_num_bytes_opt = self.on_disk_num_bytes(split_ulid);
+        if let Some(num_bytes) = on_disk_num_bytes_opt {
+            self.pinned_on_disk_bytes -= num_bytes;
+            crate::metrics::STORAGE_METRICS
+                .searcher_split_cache_pinned_num_bytes
+                .sub(num_bytes as i64);
+        }
+        if !self.evict_on_unpin.remove(&split_ulid) || on_disk_num_bytes_opt.is_none() {
+            return None;
+        }
+        self.remove(split_ulid);
+        Some(split_ulid)
+    }
+
     pub(crate) fn register_as_downloaded(&mut self, split_ulid: Ulid, num_bytes: u64) {
         self.change_split_status(split_ulid, Status::OnDisk { num_bytes });
     }
@@ -361,15 +439,21 @@ impl SplitTable {
     }
 
     fn is_out_of_limits(&self) -> bool {
-        if self.on_disk_splits.is_empty() {
+        self.is_out_of_limits_after_evicting(0, 0)
+    }
+
+    /// Returns true if the table would still be out of limits after evicting `num_splits` on-disk
+    /// splits totaling `num_bytes`.
+    fn is_out_of_limits_after_evicting(&self, num_splits: usize, num_bytes: u64) -> bool {
+        if self.on_disk_splits.len() <= num_splits {
             return false;
         }
-        if self.on_disk_splits.len() + self.downloading_splits.len()
+        if self.on_disk_splits.len() - num_splits + self.downloading_splits.len()
             >= self.limits.max_num_splits.get() as usize
         {
             return true;
         }
-        if self.on_disk_bytes > self.limits.max_num_bytes.as_u64() {
+        if self.on_disk_bytes.saturating_sub(num_bytes) > self.limits.max_num_bytes.as_u64() {
             return true;
         }
         false
@@ -382,21 +466,49 @@ impl SplitTable {
     ///
     /// Returns `None` if this would mean evicting splits that
     /// have been accessed more recently than the candidate split.
+    ///
+    /// Pinned splits are skipped. If the eviction goes through, the pinned splits skipped while
+    /// room was still needed are evicted once unpinned.
     pub(crate) fn make_room_for_split_if_necessary(
         &mut self,
         last_access_date: LastAccessDate,
     ) -> Result<Vec<Ulid>, NoRoomAvailable> {
         let mut split_infos = Vec::new();
+        let mut skipped_pinned_splits = Vec::new();
+        let mut skipped_pinned_num_bytes = 0u64;
+        // Last pinned split skipped. Splits before it are all pinned.
+        let mut cursor_opt: Option<SplitKey> = None;
         while self.is_out_of_limits() {
-            if let Some(first_split) = self.on_disk_splits.first() {
-                if first_split.last_accessed > last_access_date {
-                    // This is not worth doing the eviction.
-                    break;
-                }
-                split_infos.extend(self.remove(first_split.split_ulid));
+            let next_split_opt = if let Some(cursor) = cursor_opt {
+                self.on_disk_splits
+                    .range((Bound::Excluded(cursor), Bound::Unbounded))
+                    .next()
             } else {
+                self.on_disk_splits.first()
+            };
+            let Some(&next_split) = next_split_opt else {
+                break;
+            };
+            if next_split.last_accessed > last_access_date {
+                // This is not worth doing the eviction.
                 break;
             }
+            if self.split_to_num_pins.contains_key(&next_split.split_ulid) {
+                // The pinned splits already skipped will free some room once unpinned. There is
+                // no need to evict this one as well if that is enough.
+                if self.is_out_of_limits_after_evicting(
+                    skipped_pinned_splits.len(),
+                    skipped_pinned_num_bytes,
+                ) {
+                    skipped_pinned_splits.push(next_split.split_ulid);
+                    skipped_pinned_num_bytes += self
+                        .on_disk_num_bytes(next_split.split_ulid)
+                        .unwrap_or_default();
+                }
+                cursor_opt = Some(next_split);
+                continue;
+            }
+            split_infos.extend(self.remove(next_split.split_ulid));
         }
         if self.is_out_of_limits() {
             // We are still out of limits.
@@ -406,6 +518,13 @@ impl SplitTable {
             }
             Err(NoRoomAvailable)
         } else {
+            for split_ulid in skipped_pinned_splits {
+                if self.evict_on_unpin.insert(split_ulid) {
+                    crate::metrics::STORAGE_METRICS
+                        .searcher_split_cache_deferred_evictions_total
+                        .inc();
+                }
+            }
             Ok(split_infos
                 .into_iter()
                 .map(|split_info| split_info.split_key.split_ulid)
@@ -430,6 +549,11 @@ impl SplitTable {
     pub fn num_bytes(&self) -> u64 {
         self.on_disk_bytes
     }
+
+    #[cfg(test)]
+    pub fn pinned_num_bytes(&self) -> u64 {
+        self.pinned_on_disk_bytes
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -671,6 +795,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pinned_split_eviction_is_deferred() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(1),
+                max_num_splits: NonZeroU32::new(30).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let split_ulids = sorted_split_ulids(3);
+        let splits = [
+            (split_ulids[0], 400_000),
+            (split_ulids[1], 400_000),
+            (split_ulids[2], 400_000),
+        ];
+        for (split_ulid, num_bytes) in splits {
+            split_table.report(split_ulid, Uri::for_test(TEST_STORAGE_URI));
+            split_table.register_as_downloaded(split_ulid, num_bytes);
+        }
+        split_table.pin(split_ulids[0]);
+        split_table.pin(split_ulids[0]);
+        assert_eq!(split_table.pinned_num_bytes(), 400_000);
+
+        let new_ulid = Ulid::new();
+        split_table.report(new_ulid, Uri::for_test(TEST_STORAGE_URI));
+        let DownloadOpportunity {
+            splits_to_delete,
+            split_to_download,
+        } = split_table.find_download_opportunity().unwrap();
+        // The oldest split is pinned, so the next one is evicted in its stead.
+        assert_eq!(&splits_to_delete[..], &[split_ulids[1]]);
+        assert_eq!(split_to_download.split_ulid, new_ulid);
+        assert_eq!(split_table.num_bytes(), 800_000);
+
+        assert!(split_table.unpin(split_ulids[0]).is_none());
+        assert_eq!(split_table.pinned_num_bytes(), 400_000);
+        // Releasing the last pin evicts the split.
+        assert_eq!(split_table.unpin(split_ulids[0]), Some(split_ulids[0]));
+        assert_eq!(split_table.pinned_num_bytes(), 0);
+        assert_eq!(split_table.num_bytes(), 400_000);
+        assert!(split_table.unpin(split_ulids[0]).is_none());
+    }
+
+    #[test]
+    fn test_pinned_splits_not_evicted_once_enough_room_is_made() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(1),
+                max_num_splits: NonZeroU32::new(30).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let split_ulids = sorted_split_ulids(3);
+        for split_ulid in &split_ulids {
+            split_table.report(*split_ulid, Uri::for_test(TEST_STORAGE_URI));
+            split_table.register_as_downloaded(*split_ulid, 400_000);
+        }
+        split_table.pin(split_ulids[0]);
+        split_table.pin(split_ulids[1]);
+
+        let new_ulid = Ulid::new();
+        split_table.report(new_ulid, Uri::for_test(TEST_STORAGE_URI));
+        let DownloadOpportunity {
+            splits_to_delete, ..
+        } = split_table.find_download_opportunity().unwrap();
+        assert_eq!(&splits_to_delete[..], &[split_ulids[2]]);
+
+        // Evicting the first pinned split is enough to get back within the limits, so the second
+        // one is kept once unpinned.
+        assert!(split_table.unpin(split_ulids[1]).is_none());
+        assert_eq!(split_table.num_bytes(), 800_000);
+        assert_eq!(split_table.unpin(split_ulids[0]), Some(split_ulids[0]));
+        assert_eq!(split_table.num_bytes(), 400_000);
+    }
+
+    #[test]
+    fn test_split_pinned_before_download() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(1),
+                max_num_splits: NonZeroU32::new(30).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let split_ulid = Ulid::new();
+        split_table.report(split_ulid, Uri::for_test(TEST_STORAGE_URI));
+        split_table.pin(split_ulid);
+        assert_eq!(split_table.pinned_num_bytes(), 0);
+        split_table.start_download(split_ulid).unwrap();
+        split_table.register_as_downloaded(split_ulid, 1_000);
+        assert_eq!(split_table.pinned_num_bytes(), 1_000);
+        assert!(split_table.unpin(split_ulid).is_none());
+        assert_eq!(split_table.pinned_num_bytes(), 0);
+        assert_eq!(split_table.num_bytes(), 1_000);
+    }
+
     // Unit test for #5334
     #[test]
     fn test_split_inserted_is_the_worst_candidate_5334() {
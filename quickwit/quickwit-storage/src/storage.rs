@@ -21,6 +21,7 @@ use std::fmt;
 use std::io::{self, ErrorKind};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
@@ -31,6 +32,17 @@ use tracing::error;
 
 use crate::{BulkDeleteError, OwnedBytes, PutPayload, StorageErrorKind, StorageResult};
 
+/// A file listed by [`Storage::list_files_recursive`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileEntry {
+    /// Path of the file, relative to the storage.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub num_bytes: u64,
+    /// Last time the file was modified.
+    pub last_modified: SystemTime,
+}
+
 /// This trait is only used to make it build trait object with `AsyncWrite + Send + Unpin`.
 pub trait SendableAsync: AsyncWrite + Send + Unpin {}
 impl<W: AsyncWrite + Send + Unpin> SendableAsync for W {}
@@ -140,6 +152,27 @@ pub trait Storage: fmt::Debug + Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists the files located under `dir_path`, at any depth, in no particular order. As for
+    /// object storages, `dir_path` is not required to exist.
+    ///
+    /// If `start_after_opt` is set, only the files whose path sorts strictly after it, byte-wise,
+    /// are listed. Object storages hand it over to the listing request, so that the keys located
+    /// before it are not listed at all.
+    ///
+    /// Storages that cannot list their files return an error.
+    async fn list_files_recursive(
+        &self,
+        dir_path: &Path,
+        _start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let error = anyhow::anyhow!(
+            "listing files of `{}` under `{}` is not supported",
+            self.uri(),
+            dir_path.display()
+        );
+        Err(StorageErrorKind::Internal.with_error(error))
+    }
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> &Uri;
 }